@@ -0,0 +1,74 @@
+//! Compares clone-heavy vs shared-reference entity read paths.
+//!
+//! Run with: `cargo bench --bench entity_snapshot`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use flux::state::StateEngine;
+use serde_json::json;
+
+const ENTITY_COUNT: usize = 100_000;
+
+/// Synthetic world: 100k entities with a handful of properties including a JSON blob
+fn build_world() -> StateEngine {
+    let engine = StateEngine::new();
+    for i in 0..ENTITY_COUNT {
+        let id = format!("bench/entity_{}", i);
+        engine.update_properties(
+            &id,
+            vec![
+                ("status".to_string(), json!("active")),
+                ("value".to_string(), json!(i)),
+                (
+                    "blob".to_string(),
+                    json!({"tags": ["a", "b", "c"], "readings": [1.0, 2.0, 3.0, 4.0]}),
+                ),
+            ],
+        );
+    }
+    engine
+}
+
+fn bench_entity_reads(c: &mut Criterion) {
+    let engine = build_world();
+    let mut group = c.benchmark_group("entity_reads_100k");
+    group.sample_size(10);
+
+    group.bench_function("get_all_entities (clone)", |b| {
+        b.iter(|| black_box(engine.get_all_entities().len()))
+    });
+
+    group.bench_function("entities_snapshot_refs (arc)", |b| {
+        b.iter(|| black_box(engine.entities_snapshot_refs().len()))
+    });
+
+    group.bench_function("for_each_entity (visit)", |b| {
+        b.iter(|| {
+            let mut count = 0usize;
+            engine.for_each_entity(|_| count += 1);
+            black_box(count)
+        })
+    });
+
+    group.bench_function("serialize via clone", |b| {
+        b.iter(|| {
+            black_box(
+                serde_json::to_vec(&engine.get_all_entities())
+                    .unwrap()
+                    .len(),
+            )
+        })
+    });
+
+    group.bench_function("serialize via refs", |b| {
+        b.iter(|| {
+            let refs = engine.entities_snapshot_refs();
+            let borrowed: Vec<&flux::state::Entity> = refs.iter().map(|e| e.as_ref()).collect();
+            black_box(serde_json::to_vec(&borrowed).unwrap().len())
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_entity_reads);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+//! Compares the parsed and raw-payload single-event ingestion paths: parse,
+//! validate, check limits and timestamp, serialize for NATS.
+//!
+//! Run with: `cargo bench --bench ingestion`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use flux::event::{EventLimits, FluxEvent, RawFluxEvent, SecondsTimestampPolicy, TimestampRules};
+use serde_json::json;
+
+const TIMESTAMP: i64 = 1_707_668_400_000;
+
+/// Generous enough that no benchmark event is rejected
+const LIMITS: EventLimits = EventLimits {
+    max_payload_bytes: 1 << 20,
+    max_properties_per_event: 100_000,
+    max_property_name_length: 256,
+    max_string_value_length: 1 << 20,
+};
+
+const RULES: TimestampRules = TimestampRules {
+    max_skew_seconds: 300,
+    seconds_policy: SecondsTimestampPolicy::Reject,
+};
+
+/// Request body whose payload is about `size` bytes of mixed properties
+fn event_body(size: usize) -> Vec<u8> {
+    let mut properties = serde_json::Map::new();
+    let mut len = 0;
+    while len < size {
+        let name = format!("reading_{}", properties.len());
+        let value = json!({
+            "value": properties.len() as f64 * 0.5,
+            "unit": "celsius",
+            "tags": ["zone-1", "rack-7"]
+        });
+        // Quotes, colon and comma around the entry
+        len += name.len() + value.to_string().len() + 4;
+        properties.insert(name, value);
+    }
+    serde_json::to_vec(&json!({
+        "stream": "sensors",
+        "source": "bench",
+        "timestamp": TIMESTAMP,
+        "payload": {"entity_id": "bench/sensor-01", "properties": properties}
+    }))
+    .unwrap()
+}
+
+fn parsed(body: &[u8]) -> usize {
+    let mut event: FluxEvent = serde_json::from_slice(body).unwrap();
+    event.validate_and_prepare().unwrap();
+    event.check_limits(&LIMITS).unwrap();
+    event.check_timestamp(&RULES, TIMESTAMP).unwrap();
+    serde_json::to_vec(&event).unwrap().len()
+}
+
+fn raw(body: &[u8]) -> usize {
+    let mut event = RawFluxEvent::from_slice(body).unwrap();
+    event.validate_and_prepare().unwrap();
+    event.check_limits(&LIMITS).unwrap();
+    event.check_timestamp(&RULES, TIMESTAMP).unwrap();
+    serde_json::to_vec(&event).unwrap().len()
+}
+
+fn bench_ingestion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_event_ingestion");
+
+    for (label, size) in [("1KB", 1 << 10), ("64KB", 64 << 10), ("512KB", 512 << 10)] {
+        let body = event_body(size);
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parsed", label), &body, |b, body| {
+            b.iter(|| black_box(parsed(body)))
+        });
+        group.bench_with_input(BenchmarkId::new("raw", label), &body, |b, body| {
+            b.iter(|| black_box(raw(body)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_ingestion);
+criterion_main!(benches);
@@ -1,29 +1,85 @@
 //! Connector Manager HTTP API — generic connector endpoints.
 //!
-//! Exposes four routes:
-//! - `POST /api/connectors/generic` — create a new generic (Bento) source
+//! Exposes these routes:
+//! - `POST /api/connectors/generic` — create a new generic (native or Bento) source
 //! - `DELETE /api/connectors/generic/:source_id` — remove a generic source
-//! - `GET /api/connectors` — list all connectors (builtin + generic + named)
+//! - `GET /api/connectors/generic/:source_id/rendered-config` — a Bento source's config, secrets redacted
+//! - `POST /api/connectors/named` — create a new named (Singer) source
+//! - `DELETE /api/connectors/named/:source_id` — remove a named source
+//! - `POST /api/connectors/named/:source_id/sync` — trigger an immediate sync
+//! - `POST /api/connectors/files` — create a new file-drop (CSV / JSONL) source
+//! - `GET /api/connectors/files` — list file-drop sources with ingest status
+//! - `GET /api/connectors/files/:source_id` — get one file-drop source
+//! - `PUT /api/connectors/files/:source_id` — replace a file-drop source's config
+//! - `DELETE /api/connectors/files/:source_id` — remove a file-drop source
+//! - `POST /api/connectors/postgres` — create a new Postgres change-polling source
+//! - `DELETE /api/connectors/postgres/:source_id` — remove a Postgres source
+//! - `POST /api/connectors/weather` — create a new Open-Meteo weather source
+//! - `GET /api/connectors/weather` — list weather sources with poll status
+//! - `DELETE /api/connectors/weather/:source_id` — remove a weather source
+//! - `GET/PUT /api/connectors/{generic,named,files,postgres,weather}/:source_id/transforms`
+//!   — a source's transform rules
+//! - `GET/PUT /api/connectors/builtin/:user_id/:connector/transforms` — a
+//!   builtin or external connector's transform rules for one user
+//! - `GET/PUT /api/connectors/builtin/:user_id/:connector/config` — a builtin
+//!   connector's configuration for one user (e.g. Notion databases to sync)
+//! - `GET /api/connectors` — list all connectors (builtin + external + generic + named + file + postgres + weather)
+//!
+//! With Flux auth enabled every route needs a bearer token. Namespace tokens
+//! only reach the generic and named sources their namespace owns (and their
+//! own builtin transform rules and configuration); the admin token reaches
+//! everything.
+//! - `POST /api/connectors/registry/reload` — rescan external connector manifests
 //! - `GET /api/connectors/taps` — return the Meltano Hub tap catalog
+//! - `GET /api/connectors/rate-limit` — global publish rate limiter utilization
+//! - `GET /api/leader` — this instance's leader election state
+//! - `GET /metrics` — runner and publisher metrics in Prometheus text format
+//! - `GET /api/openapi.json` — OpenAPI spec for the above (Swagger UI at
+//!   `/api/docs` when enabled)
 
-use crate::generic_config::{AuthType, GenericConfigStore, GenericSourceConfig};
+use crate::auth::{ApiAuth, Caller};
+use crate::file_config::{FileFormat, FileSourceConfig};
+use crate::generic_config::{
+    secret_key, validate_proxy_url, validate_request_params, AuthType, BentoOverrides,
+    GenericConfigStore, GenericSourceConfig, ParamKind, ParamValue, RequestParams, SourceEngine,
+    SourceProxy,
+};
+use crate::metrics::{self, ConnectorMetrics};
 use crate::named_config::NamedSourceConfig;
-use crate::registry::get_all_connectors;
-use crate::runners::generic::GenericRunner;
-use crate::runners::named::{NamedRunner, TapCatalogEntry, TapCatalogStore};
+use crate::postgres_config::PostgresSourceConfig;
+use crate::registry::{ConnectorRegistry, ManifestError, ReloadReport};
+use crate::runners::bento;
+use crate::runners::builtin::ConnectorStatus;
+use crate::runners::file::{FileRunner, FileStatus};
+use crate::runners::generic::{render_bento_config, GenericRunner};
+use crate::runners::named::{NamedRunner, NamedStreamStatus, TapCatalogEntry, TapCatalogStore};
+use crate::runners::postgres::PostgresRunner;
+use crate::runners::rate_limit::{LimiterStatus, PublishLimiter, DEFAULT_MAX_EVENTS_PER_RUN};
+use crate::runners::retry::RetryPolicy;
+use crate::runners::weather::{WeatherRunner, WeatherStatus};
+use crate::transform::{self, RedactMode, TransformRule};
+use crate::transform_config::TransformConfigStore;
+use crate::user_config::UserConfigStore;
+use crate::weather_config::{validate_weather_source, WeatherLocation, WeatherSourceConfig};
 use anyhow::Result;
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
     routing::{delete, get, post},
     Router,
 };
 use chrono::Utc;
 use flux::credentials::{CredentialStore, Credentials};
+use flux::http_client::ProxyConfig;
+use flux::idempotency::{self, Claim, IdempotencyStore};
+use flux::leader::{LeaderStatus, Leadership};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{info, warn};
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 
 /// Shared state for the connector API handlers.
 #[derive(Clone)]
@@ -33,6 +89,29 @@ pub struct ApiState {
     pub credential_store: Arc<CredentialStore>,
     pub tap_catalog: Arc<TapCatalogStore>,
     pub named_runner: Arc<NamedRunner>,
+    pub file_runner: Arc<FileRunner>,
+    pub postgres_runner: Arc<PostgresRunner>,
+    pub weather_runner: Arc<WeatherRunner>,
+    /// Builtin and external connectors' transform rules
+    pub transform_store: Arc<TransformConfigStore>,
+    /// Builtin connectors' per-user configuration
+    pub user_config_store: Arc<UserConfigStore>,
+    /// Builtin and external connectors
+    pub registry: Arc<ConnectorRegistry>,
+    /// Builtin and external scheduler status keyed by `user_id:connector`
+    pub builtin_status:
+        Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>>,
+    /// Leader election state; followers persist configs without starting sources
+    pub leadership: Leadership,
+    /// Publish rate limit shared by all runners
+    pub limiter: Arc<PublishLimiter>,
+    /// Runner and publisher metrics served at `/metrics`
+    pub metrics: Arc<ConnectorMetrics>,
+    /// Bearer token resolution; `None` with Flux auth disabled, when every
+    /// caller is an admin
+    pub auth: Option<Arc<ApiAuth>>,
+    /// `Idempotency-Key`s of generic and named source creation
+    pub idempotency: Arc<IdempotencyStore>,
 }
 
 /// Auth type as received in the API request body.
@@ -40,7 +119,7 @@ pub struct ApiState {
 /// Matches the format described in ADR-007:
 /// - `"none"` or `"bearer"` as a plain string
 /// - `{ "api_key_header": "<header-name>" }` as an object
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum AuthTypeInput {
     /// Plain string: `"none"` or `"bearer"`
@@ -61,8 +140,43 @@ impl From<AuthTypeInput> for AuthType {
     }
 }
 
+/// Custom header or query parameter of a generic source.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RequestParamInput {
+    pub name: String,
+    pub value: String,
+    /// Keep the value in CredentialStore rather than the config DB.
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// Proxy a generic source is polled through instead of the default one.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ProxyInput {
+    /// `http` or `https` URL, without credentials
+    pub url: String,
+    pub username: Option<String>,
+    /// Stored in CredentialStore, never logged.
+    pub password: Option<String>,
+    /// Hosts polled directly, in `NO_PROXY` syntax.
+    pub no_proxy: Option<String>,
+}
+
 /// Request body for `POST /api/connectors/generic`.
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "Weather API",
+    "url": "https://api.example.com/weather",
+    "poll_interval_secs": 300,
+    "entity_key": "station_id",
+    "namespace": "personal",
+    "auth_type": {"api_key_header": "X-API-Key"},
+    "token": "secret",
+    "engine": "native",
+    "headers": [{"name": "X-Api-Version", "value": "2"}],
+    "query_params": [{"name": "appid", "value": "secret", "secret": true}],
+    "proxy": {"url": "http://proxy.internal:3128", "username": "flux", "password": "secret"}
+}))]
 pub struct CreateGenericSourceRequest {
     pub name: String,
     pub url: String,
@@ -74,16 +188,148 @@ pub struct CreateGenericSourceRequest {
     pub token: Option<String>,
     /// Optional Flux namespace token for auth-enabled Flux instances.
     pub flux_namespace_token: Option<String>,
+    /// Polling engine; defaults to `native`.
+    #[serde(default)]
+    pub engine: SourceEngine,
+    /// Extra headers sent with every poll.
+    #[serde(default)]
+    pub headers: Vec<RequestParamInput>,
+    /// Query parameters appended to `url` on every poll.
+    #[serde(default)]
+    pub query_params: Vec<RequestParamInput>,
+    /// Proxy for this source's polls, replacing the connector manager's.
+    #[serde(default)]
+    pub proxy: Option<ProxyInput>,
+    /// Additions to the rendered Bento config; `bento` engine only.
+    #[serde(default)]
+    pub bento_overrides: Option<BentoOverrides>,
+    /// Namespace that owns the source; defaults to `namespace`. A namespace
+    /// token may only name its own namespace.
+    #[serde(default)]
+    pub owner_namespace: Option<String>,
+    /// Backoff and circuit breaker settings; defaults apply when omitted.
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+impl CreateGenericSourceRequest {
+    /// Checks the custom headers and query parameters against `auth_type`,
+    /// the proxy URL, the shape of the Bento overrides and the retry policy.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref policy) = self.retry_policy {
+            policy.validate()?;
+        }
+        if let Some(ref proxy) = self.proxy {
+            validate_proxy_url(&proxy.url)?;
+        }
+        if let Some(ref overrides) = self.bento_overrides {
+            if self.engine != SourceEngine::Bento {
+                anyhow::bail!("bento_overrides need the bento engine");
+            }
+            overrides.validate()?;
+        }
+        fn pairs(params: &[RequestParamInput]) -> Vec<(&str, &str)> {
+            params
+                .iter()
+                .map(|p| (p.name.as_str(), p.value.as_str()))
+                .collect()
+        }
+        validate_request_params(
+            &self.auth_type.clone().into(),
+            &pairs(&self.headers),
+            &pairs(&self.query_params),
+        )
+    }
+
+    /// The config this request creates, secret values replaced by
+    /// `{"secret_ref": true}` markers.
+    pub fn to_config(&self, source_id: &str) -> GenericSourceConfig {
+        let values = |params: &[RequestParamInput]| {
+            params
+                .iter()
+                .map(|param| {
+                    let value = if param.secret {
+                        ParamValue::SecretRef { secret_ref: true }
+                    } else {
+                        ParamValue::Plain(param.value.clone())
+                    };
+                    (param.name.clone(), value)
+                })
+                .collect()
+        };
+        GenericSourceConfig {
+            id: source_id.to_string(),
+            name: self.name.clone(),
+            url: self.url.clone(),
+            poll_interval_secs: self.poll_interval_secs,
+            entity_key: self.entity_key.clone(),
+            namespace: self.namespace.clone(),
+            auth_type: self.auth_type.clone().into(),
+            created_at: Utc::now(),
+            flux_namespace_token: self.flux_namespace_token.clone(),
+            engine: self.engine,
+            headers: values(&self.headers),
+            query_params: values(&self.query_params),
+            proxy: self.proxy.as_ref().map(|proxy| SourceProxy {
+                url: proxy.url.clone(),
+                username: proxy.username.clone(),
+                password: proxy
+                    .password
+                    .as_ref()
+                    .map(|_| ParamValue::SecretRef { secret_ref: true }),
+                no_proxy: proxy.no_proxy.clone(),
+            }),
+            bento_overrides: self.bento_overrides.clone(),
+            transforms: Vec::new(),
+            owner_namespace: self
+                .owner_namespace
+                .clone()
+                .unwrap_or_else(|| self.namespace.clone()),
+            retry_policy: self.retry_policy.unwrap_or_default(),
+        }
+    }
+
+    /// Secret header, query parameter and proxy password values, with the
+    /// kind and name they are stored under.
+    fn secrets(&self) -> impl Iterator<Item = (ParamKind, &str, &str)> {
+        fn secrets(
+            kind: ParamKind,
+            params: &[RequestParamInput],
+        ) -> impl Iterator<Item = (ParamKind, &str, &str)> {
+            params
+                .iter()
+                .filter(|param| param.secret)
+                .map(move |param| (kind, param.name.as_str(), param.value.as_str()))
+        }
+        let proxy_password = self
+            .proxy
+            .iter()
+            .filter_map(|proxy| proxy.password.as_deref())
+            .map(|password| (ParamKind::Proxy, "password", password));
+        secrets(ParamKind::Header, &self.headers)
+            .chain(secrets(ParamKind::Query, &self.query_params))
+            .chain(proxy_password)
+    }
 }
 
 /// Response for `POST /api/connectors/generic`.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"source_id": "9b2f6c1e-4a57-4f0e-8d8e-3c1b2a7d5e10"}))]
 pub struct CreateGenericSourceResponse {
     pub source_id: String,
 }
 
 /// Request body for `POST /api/connectors/named`.
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "tap_name": "tap-github",
+    "namespace": "personal",
+    "entity_key_field": "id",
+    "config_json": "{\"repository\": \"owner/repo\"}",
+    "poll_interval_secs": 3600,
+    "coerce_types": true,
+    "max_events_per_run": 10000
+}))]
 pub struct CreateNamedSourceRequest {
     pub tap_name: String,
     pub namespace: String,
@@ -93,16 +339,339 @@ pub struct CreateNamedSourceRequest {
     pub poll_interval_secs: u64,
     /// Optional Flux namespace token for auth-enabled Flux instances.
     pub flux_namespace_token: Option<String>,
+    /// Convert record values to the types in the tap's SCHEMA messages.
+    #[serde(default)]
+    pub coerce_types: bool,
+    /// Records one run may publish; the excess is dropped. Defaults to 50,000.
+    #[serde(default = "default_max_events_per_run")]
+    pub max_events_per_run: u64,
+    /// Namespace that owns the source; defaults to `namespace`. A namespace
+    /// token may only name its own namespace.
+    #[serde(default)]
+    pub owner_namespace: Option<String>,
+    /// Backoff and circuit breaker settings; defaults apply when omitted.
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+    /// Run each stream in its own tap process, up to this many at once.
+    /// 0 (the default) runs all streams in one process.
+    #[serde(default)]
+    pub parallel_streams: u32,
+}
+
+fn default_max_events_per_run() -> u64 {
+    DEFAULT_MAX_EVENTS_PER_RUN
 }
 
 /// Response for `POST /api/connectors/named`.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"source_id": "4e1d2c3b-5a69-4788-9f00-112233445566"}))]
 pub struct CreateNamedSourceResponse {
     pub source_id: String,
 }
 
+/// Request body for `POST /api/connectors/postgres`.
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "Shop orders",
+    "connection_string": "host=db user=flux dbname=shop",
+    "password": "secret",
+    "table": "public.orders",
+    "key_column": "id",
+    "updated_at_column": "updated_at",
+    "namespace": "shop",
+    "batch_size": 500,
+    "poll_interval_secs": 60
+}))]
+pub struct CreatePostgresSourceRequest {
+    pub name: String,
+    /// libpq-style connection string or URL, without the password.
+    pub connection_string: String,
+    /// Database password — stored in CredentialStore, never logged.
+    pub password: Option<String>,
+    /// Table to mirror, optionally schema-qualified.
+    pub table: String,
+    /// Column whose value becomes the entity key.
+    pub key_column: String,
+    /// Timestamp, date or integer column that increases whenever a row changes.
+    pub updated_at_column: String,
+    pub namespace: String,
+    /// Maximum rows per query; defaults to 500.
+    #[serde(default = "default_postgres_batch_size")]
+    pub batch_size: u32,
+    /// Poll interval in seconds; defaults to 60.
+    #[serde(default = "default_postgres_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+}
+
+fn default_postgres_batch_size() -> u32 {
+    500
+}
+
+fn default_postgres_poll_interval() -> u64 {
+    60
+}
+
+/// Response for `POST /api/connectors/postgres`.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"source_id": "0c6a8f0e-2b1d-4c3e-9a7f-5d4e3c2b1a09"}))]
+pub struct CreatePostgresSourceResponse {
+    pub source_id: String,
+}
+
+/// Request body for `POST /api/connectors/weather`.
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "Home and cabin",
+    "locations": [
+        {"name": "home", "latitude": 52.52, "longitude": 13.41},
+        {"name": "cabin", "latitude": 61.5, "longitude": 8.2}
+    ],
+    "namespace": "matt",
+    "poll_interval_secs": 900
+}))]
+pub struct WeatherSourceRequest {
+    pub name: String,
+    /// Named points; each becomes `{namespace}/weather/{name}`.
+    pub locations: Vec<WeatherLocation>,
+    pub namespace: String,
+    /// Poll interval in seconds (minimum 60); defaults to 900.
+    #[serde(default = "default_weather_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+}
+
+fn default_weather_poll_interval() -> u64 {
+    900
+}
+
+/// Response for `POST /api/connectors/weather`.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"source_id": "5f0e3a2b-7c1d-4e9f-8a6b-2d4c6e8f0a1b"}))]
+pub struct CreateWeatherSourceResponse {
+    pub source_id: String,
+}
+
+/// A weather source with its poll status (namespace token omitted).
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "source_id": "5f0e3a2b-7c1d-4e9f-8a6b-2d4c6e8f0a1b",
+    "name": "Home and cabin",
+    "locations": [{"name": "home", "latitude": 52.52, "longitude": 13.41}],
+    "namespace": "matt",
+    "poll_interval_secs": 900,
+    "created_at": "2026-01-01T00:00:00+00:00",
+    "last_poll": "2026-01-02T03:00:00+00:00",
+    "events_published": 96,
+    "alerting_locations": ["home"]
+}))]
+pub struct WeatherSourceInfo {
+    pub source_id: String,
+    pub name: String,
+    pub locations: Vec<WeatherLocation>,
+    pub namespace: String,
+    pub poll_interval_secs: u64,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_poll: Option<String>,
+    /// Locations that failed on the last poll
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub events_published: u64,
+    /// Locations with an active `{namespace}/weather/{location}/alerts` entity
+    pub alerting_locations: Vec<String>,
+}
+
+impl WeatherSourceInfo {
+    fn new(config: WeatherSourceConfig, status: Option<&WeatherStatus>) -> Self {
+        Self {
+            source_id: config.id,
+            name: config.name,
+            locations: config.locations,
+            namespace: config.namespace,
+            poll_interval_secs: config.poll_interval_secs,
+            created_at: config.created_at.to_rfc3339(),
+            last_poll: status.and_then(|s| s.last_poll).map(|dt| dt.to_rfc3339()),
+            last_error: status.and_then(|s| s.last_error.clone()),
+            events_published: status.map_or(0, |s| s.events_published),
+            alerting_locations: status.map_or_else(Vec::new, |s| s.alerting_locations.clone()),
+        }
+    }
+}
+
+/// Request body for `POST /api/connectors/files` and
+/// `PUT /api/connectors/files/:source_id`.
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "Nightly inventory",
+    "directory": "/data/drop/inventory",
+    "pattern": "*.csv",
+    "format": "csv",
+    "entity_key_field": "sku",
+    "namespace": "warehouse",
+    "column_mapping": {"Qty": "quantity"},
+    "poll_interval_secs": 30
+}))]
+pub struct FileSourceRequest {
+    pub name: String,
+    /// Directory to watch; processed files are moved to `processed/` inside it.
+    pub directory: String,
+    /// File name pattern (`*` and `?` wildcards); defaults to `*`.
+    #[serde(default = "default_file_pattern")]
+    pub pattern: String,
+    /// File format; defaults to `csv`.
+    #[serde(default)]
+    pub format: FileFormat,
+    /// Column (CSV) or field (JSONL) used as the entity key.
+    pub entity_key_field: String,
+    pub namespace: String,
+    /// Optional renames from source column to property name.
+    #[serde(default)]
+    pub column_mapping: HashMap<String, String>,
+    /// Directory scan interval; defaults to 60 seconds.
+    #[serde(default = "default_file_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+}
+
+fn default_file_pattern() -> String {
+    "*".to_string()
+}
+
+fn default_file_poll_interval() -> u64 {
+    60
+}
+
+/// Response for `POST /api/connectors/files`.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"source_id": "0c6a1f3e-2b4d-4e8f-9a7b-5d3c2e1f0a9b"}))]
+pub struct CreateFileSourceResponse {
+    pub source_id: String,
+}
+
+/// A file-drop source with its ingest status (namespace token omitted).
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "source_id": "0c6a1f3e-2b4d-4e8f-9a7b-5d3c2e1f0a9b",
+    "name": "Nightly inventory",
+    "directory": "/data/drop/inventory",
+    "pattern": "*.csv",
+    "format": "csv",
+    "entity_key_field": "sku",
+    "namespace": "warehouse",
+    "column_mapping": {"Qty": "quantity"},
+    "poll_interval_secs": 30,
+    "created_at": "2026-01-01T00:00:00+00:00",
+    "last_scan": "2026-01-02T03:00:00+00:00",
+    "last_error": "inventory.csv: 1 row failed: line 4: missing entity key 'sku'",
+    "files_processed": 12,
+    "rows_published": 5120,
+    "rows_failed": 1
+}))]
+pub struct FileSourceInfo {
+    pub source_id: String,
+    pub name: String,
+    pub directory: String,
+    pub pattern: String,
+    pub format: FileFormat,
+    pub entity_key_field: String,
+    pub namespace: String,
+    pub column_mapping: HashMap<String, String>,
+    pub poll_interval_secs: u64,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_scan: Option<String>,
+    /// Failed rows (with line numbers) from the last processed file, or the last scan error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub files_processed: u64,
+    pub rows_published: u64,
+    pub rows_failed: u64,
+}
+
+impl FileSourceInfo {
+    fn new(config: FileSourceConfig, status: Option<&FileStatus>) -> Self {
+        Self {
+            source_id: config.id,
+            name: config.name,
+            directory: config.directory,
+            pattern: config.pattern,
+            format: config.format,
+            entity_key_field: config.entity_key_field,
+            namespace: config.namespace,
+            column_mapping: config.column_mapping,
+            poll_interval_secs: config.poll_interval_secs,
+            created_at: config.created_at.to_rfc3339(),
+            last_scan: status.and_then(|s| s.last_scan).map(|dt| dt.to_rfc3339()),
+            last_error: status.and_then(|s| s.last_error.clone()),
+            files_processed: status.map_or(0, |s| s.files_processed),
+            rows_published: status.map_or(0, |s| s.rows_published),
+            rows_failed: status.map_or(0, |s| s.rows_failed),
+        }
+    }
+}
+
+/// Body of the `.../transforms` endpoints: rules applied in order to each
+/// event's `payload.properties` before it is published.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "rules": [
+        {"type": "redact", "path": "author.email", "mode": "hash"},
+        {"type": "rename", "from": "repo", "to": "repository"},
+        {"type": "add_static", "path": "site", "value": "eu-1"}
+    ]
+}))]
+pub struct TransformRules {
+    pub rules: Vec<TransformRule>,
+}
+
+/// Body of `/api/connectors/builtin/:user_id/:connector/config`: what the
+/// connector needs besides a token, `null` if it takes nothing.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "config": {"database_ids": ["d9824bdc84454327be8b5b47500af6ce"]}
+}))]
+pub struct ConnectorUserConfig {
+    #[schema(value_type = Object)]
+    pub config: serde_json::Value,
+}
+
+/// Kinds of sources that keep their transform rules on their config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransformSource {
+    Generic,
+    Named,
+    File,
+    Postgres,
+    Weather,
+}
+
+impl TransformSource {
+    fn label(self) -> &'static str {
+        match self {
+            TransformSource::Generic => "Generic source",
+            TransformSource::Named => "Named source",
+            TransformSource::File => "File source",
+            TransformSource::Postgres => "Postgres source",
+            TransformSource::Weather => "Weather source",
+        }
+    }
+}
+
 /// A single entry in the `GET /api/connectors` response.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "Weather API",
+    "type": "generic",
+    "enabled": true,
+    "status": "running",
+    "source_id": "9b2f6c1e-4a57-4f0e-8d8e-3c1b2a7d5e10",
+    "last_started": "2026-01-01T00:00:00+00:00",
+    "owner_namespace": "personal"
+}))]
 pub struct ConnectorInfo {
     pub name: String,
     #[serde(rename = "type")]
@@ -115,9 +684,38 @@ pub struct ConnectorInfo {
     pub last_started: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_error: Option<String>,
+    /// When an errored builtin connector will next be restarted (`status: "backoff"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry_at: Option<String>,
+    /// HTTP status of the last poll (native generic sources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_http_status: Option<u16>,
+    /// Latency of the last poll in milliseconds (native generic sources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_latency_ms: Option<u64>,
+    /// Events the last run dropped at its per-run cap (`status: "partial"`;
+    /// builtin, external and named connectors)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dropped_events: Option<u64>,
+    /// Namespace that owns the source (generic and named sources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_namespace: Option<String>,
+    /// Failed runs since the last successful one (generic and named sources)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consecutive_failures: Option<u32>,
+    /// When an open circuit allows the next attempt (`status: "circuit_open"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit_open_until: Option<String>,
+    /// Events Flux accepted from the source since it was last started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events_emitted: Option<u64>,
+    /// Per-stream results of the last run (named sources with
+    /// `parallel_streams`; `status: "partial"` if any failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streams: Option<Vec<NamedStreamStatus>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
@@ -129,26 +727,41 @@ struct ErrorResponse {
 /// Creates and starts a new generic source.
 ///
 /// Generates a UUIDv4 source ID, persists the config in `GenericConfigStore`,
-/// stores the token in `CredentialStore` under `user_id="generic"`, and
-/// starts the Bento subprocess via `GenericRunner`.
+/// stores the token and secret header/query values in `CredentialStore` under
+/// `user_id="generic"`, and starts polling via `GenericRunner` with the
+/// requested engine. Call [`CreateGenericSourceRequest::validate`] first.
 pub async fn handle_create_generic_source(
     state: &ApiState,
     req: CreateGenericSourceRequest,
 ) -> Result<String> {
     let source_id = uuid::Uuid::new_v4().to_string();
-    let auth_type = req.auth_type.into();
-    let token = req.token;
+    let config = req.to_config(&source_id);
 
-    let config = GenericSourceConfig {
-        id: source_id.clone(),
-        name: req.name,
-        url: req.url,
-        poll_interval_secs: req.poll_interval_secs,
-        entity_key: req.entity_key,
-        namespace: req.namespace,
-        auth_type,
-        created_at: Utc::now(),
-        flux_namespace_token: req.flux_namespace_token,
+    // Secrets go in before the config, so the reconcile loop never starts
+    // the source without them
+    for (kind, name, value) in req.secrets() {
+        let creds = Credentials {
+            access_token: value.to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        state
+            .credential_store
+            .store("generic", &secret_key(&source_id, kind, name), &creds)?;
+    }
+    let token = req.token;
+    let params = |inputs: Vec<RequestParamInput>| -> Vec<(String, String)> {
+        inputs.into_iter().map(|p| (p.name, p.value)).collect()
+    };
+    let params = RequestParams {
+        headers: params(req.headers),
+        query_params: params(req.query_params),
+        proxy: req.proxy.map(|proxy| ProxyConfig {
+            url: proxy.url,
+            username: proxy.username,
+            password: proxy.password,
+            no_proxy: proxy.no_proxy,
+        }),
     };
 
     state.config_store.insert(&config)?;
@@ -164,7 +777,10 @@ pub async fn handle_create_generic_source(
             .store("generic", &source_id, &creds)?;
     }
 
-    state.runner.start_source(&config, token).await?;
+    // Followers only persist; the leader's reconcile loop starts the source
+    if state.leadership.is_leader() {
+        state.runner.start_source(&config, token, params).await?;
+    }
 
     info!(source_id = %source_id, name = %config.name, "Generic source created");
     Ok(source_id)
@@ -179,6 +795,7 @@ pub async fn handle_create_named_source(
     req: CreateNamedSourceRequest,
 ) -> Result<String> {
     let source_id = uuid::Uuid::new_v4().to_string();
+    let owner_namespace = req.owner_namespace.unwrap_or_else(|| req.namespace.clone());
     let config = NamedSourceConfig {
         id: source_id.clone(),
         tap_name: req.tap_name,
@@ -188,9 +805,18 @@ pub async fn handle_create_named_source(
         poll_interval_secs: req.poll_interval_secs,
         created_at: Utc::now(),
         flux_namespace_token: req.flux_namespace_token,
+        coerce_types: req.coerce_types,
+        max_events_per_run: req.max_events_per_run,
+        transforms: Vec::new(),
+        owner_namespace,
+        retry_policy: req.retry_policy.unwrap_or_default(),
+        paused: false,
+        parallel_streams: req.parallel_streams,
     };
     state.named_runner.store.insert(&config)?;
-    state.named_runner.start_source(&config).await?;
+    if state.leadership.is_leader() {
+        state.named_runner.start_source(&config).await?;
+    }
     info!(source_id = %source_id, tap = %config.tap_name, "Named source created");
     Ok(source_id)
 }
@@ -214,117 +840,1555 @@ pub async fn handle_delete_named_source(state: &ApiState, source_id: &str) -> Re
     Ok(())
 }
 
-/// Stops and removes a generic source.
+/// Pauses or resumes a named Singer tap source.
 ///
-/// Kills the Bento subprocess, deletes the config from SQLite, and removes
-/// credentials from `CredentialStore` (best-effort — no error if not found).
-pub async fn handle_delete_generic_source(state: &ApiState, source_id: &str) -> Result<()> {
-    state.runner.stop_source(source_id).await?;
-    state.config_store.delete(source_id)?;
-    // Best-effort credential cleanup (may not exist if auth_type was None)
-    let _ = state.credential_store.delete("generic", source_id);
-    info!(source_id = %source_id, "Generic source deleted");
-    Ok(())
+/// The flag is persisted; the leader stops or starts the source right away,
+/// followers leave that to the leader's reconcile loop. Returns `Ok(false)`
+/// if the source does not exist.
+pub async fn handle_set_named_source_paused(
+    state: &ApiState,
+    source_id: &str,
+    paused: bool,
+) -> Result<bool> {
+    if !state.named_runner.store.set_paused(source_id, paused)? {
+        return Ok(false);
+    }
+    if state.leadership.is_leader() {
+        let running = state
+            .named_runner
+            .running_ids()
+            .iter()
+            .any(|id| id == source_id);
+        if paused && running {
+            state.named_runner.stop_source(source_id).await?;
+        } else if !paused && !running {
+            if let Some(config) = state.named_runner.store.get(source_id)? {
+                state.named_runner.start_source(&config).await?;
+            }
+        }
+    }
+    info!(source_id = %source_id, paused, "Named source paused flag changed");
+    Ok(true)
 }
 
-// ---------------------------------------------------------------------------
-// HTTP handlers
-// ---------------------------------------------------------------------------
-
-async fn post_named_source(
-    State(state): State<Arc<ApiState>>,
-    Json(req): Json<CreateNamedSourceRequest>,
-) -> Result<(StatusCode, Json<CreateNamedSourceResponse>), AppError> {
-    let source_id = handle_create_named_source(&state, req)
-        .await
-        .map_err(AppError::from)?;
-    Ok((
-        StatusCode::CREATED,
-        Json(CreateNamedSourceResponse { source_id }),
-    ))
+/// Builds a file source config from a request, keeping `id` and `created_at`.
+fn file_source_config(
+    id: String,
+    created_at: chrono::DateTime<Utc>,
+    req: FileSourceRequest,
+) -> FileSourceConfig {
+    FileSourceConfig {
+        id,
+        name: req.name,
+        directory: req.directory,
+        pattern: req.pattern,
+        format: req.format,
+        entity_key_field: req.entity_key_field,
+        namespace: req.namespace,
+        column_mapping: req.column_mapping,
+        poll_interval_secs: req.poll_interval_secs,
+        created_at,
+        flux_namespace_token: req.flux_namespace_token,
+        transforms: Vec::new(),
+    }
 }
 
-async fn delete_named_source(
-    State(state): State<Arc<ApiState>>,
-    Path(source_id): Path<String>,
-) -> Result<StatusCode, AppError> {
-    handle_delete_named_source(&state, &source_id)
-        .await
-        .map_err(AppError::from)?;
-    Ok(StatusCode::NO_CONTENT)
+/// Creates and starts a new file-drop source.
+///
+/// Generates a UUIDv4 source ID, persists the config in `FileConfigStore`,
+/// and starts scanning the directory via `FileRunner`.
+pub async fn handle_create_file_source(state: &ApiState, req: FileSourceRequest) -> Result<String> {
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let config = file_source_config(source_id.clone(), Utc::now(), req);
+    state.file_runner.store.insert(&config)?;
+    if state.leadership.is_leader() {
+        state.file_runner.start_source(&config).await?;
+    }
+    info!(source_id = %source_id, directory = %config.directory, "File source created");
+    Ok(source_id)
 }
 
-async fn post_sync_named_source(
-    State(state): State<Arc<ApiState>>,
-    Path(source_id): Path<String>,
-) -> Result<StatusCode, AppError> {
-    handle_sync_named_source(&state, &source_id)
-        .await
-        .map_err(AppError::from)?;
-    Ok(StatusCode::ACCEPTED)
+/// Replaces a file-drop source's config and restarts its scan loop.
+///
+/// Returns `Ok(false)` if the source does not exist.
+pub async fn handle_update_file_source(
+    state: &ApiState,
+    source_id: &str,
+    req: FileSourceRequest,
+) -> Result<bool> {
+    let Some(existing) = state.file_runner.store.get(source_id)? else {
+        return Ok(false);
+    };
+    let config = FileSourceConfig {
+        transforms: existing.transforms,
+        ..file_source_config(existing.id, existing.created_at, req)
+    };
+    state.file_runner.store.update(&config)?;
+    if state.leadership.is_leader() {
+        state.file_runner.start_source(&config).await?;
+    }
+    info!(source_id = %source_id, "File source updated");
+    Ok(true)
 }
 
-async fn post_generic_source(
-    State(state): State<Arc<ApiState>>,
-    Json(req): Json<CreateGenericSourceRequest>,
-) -> Result<(StatusCode, Json<CreateGenericSourceResponse>), AppError> {
-    let source_id = handle_create_generic_source(&state, req)
-        .await
-        .map_err(AppError::from)?;
-    Ok((
-        StatusCode::CREATED,
-        Json(CreateGenericSourceResponse { source_id }),
-    ))
+/// Lists file-drop sources with their runner status.
+pub fn handle_list_file_sources(state: &ApiState) -> Result<Vec<FileSourceInfo>> {
+    let statuses = state.file_runner.status();
+    Ok(state
+        .file_runner
+        .store
+        .list()?
+        .into_iter()
+        .map(|config| {
+            let status = statuses.iter().find(|s| s.source_id == config.id);
+            FileSourceInfo::new(config, status)
+        })
+        .collect())
 }
 
-async fn delete_generic_source(
-    State(state): State<Arc<ApiState>>,
-    Path(source_id): Path<String>,
-) -> Result<StatusCode, AppError> {
-    handle_delete_generic_source(&state, &source_id)
-        .await
-        .map_err(AppError::from)?;
-    Ok(StatusCode::NO_CONTENT)
+/// Stops and removes a file-drop source. Files in the directory are left alone.
+pub async fn handle_delete_file_source(state: &ApiState, source_id: &str) -> Result<()> {
+    state.file_runner.stop_source(source_id).await?;
+    state.file_runner.store.delete(source_id)?;
+    info!(source_id = %source_id, "File source deleted");
+    Ok(())
 }
 
-async fn list_connectors(State(state): State<Arc<ApiState>>) -> Json<Vec<ConnectorInfo>> {
-    let mut connectors: Vec<ConnectorInfo> = Vec::new();
-
-    // Built-in connectors from registry
-    for c in get_all_connectors() {
-        connectors.push(ConnectorInfo {
-            name: c.name().to_string(),
-            connector_type: "builtin".to_string(),
-            enabled: true,
-            status: "running".to_string(),
-            source_id: None,
-            last_started: None,
-            last_error: None,
-        });
+/// Checks a Postgres source request before anything is stored.
+///
+/// The password must come in `password` so it lands in the CredentialStore
+/// rather than in the plain config table.
+fn validate_postgres_request(req: &CreatePostgresSourceRequest) -> Result<(), String> {
+    let pg_config: tokio_postgres::Config = req
+        .connection_string
+        .parse()
+        .map_err(|e| format!("invalid connection_string: {}", e))?;
+    if pg_config.get_password().is_some() {
+        return Err(
+            "connection_string must not contain a password; pass it in `password`".to_string(),
+        );
     }
+    for (field, value) in [
+        ("table", &req.table),
+        ("key_column", &req.key_column),
+        ("updated_at_column", &req.updated_at_column),
+        ("namespace", &req.namespace),
+    ] {
+        if value.trim().is_empty() {
+            return Err(format!("{} must not be empty", field));
+        }
+    }
+    if req.batch_size == 0 {
+        return Err("batch_size must be at least 1".to_string());
+    }
+    Ok(())
+}
 
-    // Generic connectors from config store + runner status
-    let generic_configs = state.config_store.list().unwrap_or_else(|e| {
-        warn!(error = %e, "Failed to list generic source configs");
-        vec![]
-    });
-    let statuses = state.runner.status();
+/// Creates and starts a new Postgres change-polling source.
+///
+/// Generates a UUIDv4 source ID, persists the config in `PostgresConfigStore`,
+/// stores the password in `CredentialStore` under `user_id="postgres"`, and
+/// starts polling via `PostgresRunner`. Call [`validate_postgres_request`] first.
+pub async fn handle_create_postgres_source(
+    state: &ApiState,
+    req: CreatePostgresSourceRequest,
+) -> Result<String> {
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let password = req.password;
+    let config = PostgresSourceConfig {
+        id: source_id.clone(),
+        name: req.name,
+        connection_string: req.connection_string,
+        table: req.table,
+        key_column: req.key_column,
+        updated_at_column: req.updated_at_column,
+        namespace: req.namespace,
+        batch_size: req.batch_size,
+        poll_interval_secs: req.poll_interval_secs,
+        created_at: Utc::now(),
+        flux_namespace_token: req.flux_namespace_token,
+        transforms: Vec::new(),
+    };
 
-    for config in generic_configs {
-        let status_entry = statuses.iter().find(|s| s.source_id == config.id);
-        let (status, last_started, last_error) = match status_entry {
-            Some(s) => {
-                let st = if s.last_error.is_some() { "error" } else { "running" };
-                (
-                    st.to_string(),
-                    s.last_started.map(|dt| dt.to_rfc3339()),
-                    s.last_error.clone(),
-                )
-            }
-            None => ("stopped".to_string(), None, None),
+    state.postgres_runner.store.insert(&config)?;
+
+    if let Some(ref p) = password {
+        let creds = Credentials {
+            access_token: p.clone(),
+            refresh_token: None,
+            expires_at: None,
         };
+        state
+            .credential_store
+            .store("postgres", &source_id, &creds)?;
+    }
 
-        connectors.push(ConnectorInfo {
+    if state.leadership.is_leader() {
+        state
+            .postgres_runner
+            .start_source(&config, password)
+            .await?;
+    }
+
+    info!(source_id = %source_id, table = %config.table, "Postgres source created");
+    Ok(source_id)
+}
+
+/// Stops and removes a Postgres source, its high-water mark and its password.
+pub async fn handle_delete_postgres_source(state: &ApiState, source_id: &str) -> Result<()> {
+    state.postgres_runner.stop_source(source_id).await?;
+    state.postgres_runner.store.delete(source_id)?;
+    // Best-effort credential cleanup (none stored for password-less sources)
+    let _ = state.credential_store.delete("postgres", source_id);
+    info!(source_id = %source_id, "Postgres source deleted");
+    Ok(())
+}
+
+/// Creates and starts a new weather source.
+///
+/// Generates a UUIDv4 source ID, persists the config in `WeatherConfigStore`,
+/// and starts polling via `WeatherRunner`. Call [`validate_weather_source`] first.
+pub async fn handle_create_weather_source(
+    state: &ApiState,
+    req: WeatherSourceRequest,
+) -> Result<String> {
+    let source_id = uuid::Uuid::new_v4().to_string();
+    let config = WeatherSourceConfig {
+        id: source_id.clone(),
+        name: req.name,
+        locations: req.locations,
+        namespace: req.namespace,
+        poll_interval_secs: req.poll_interval_secs,
+        created_at: Utc::now(),
+        flux_namespace_token: req.flux_namespace_token,
+        transforms: Vec::new(),
+    };
+    state.weather_runner.store.insert(&config)?;
+    if state.leadership.is_leader() {
+        state.weather_runner.start_source(&config).await?;
+    }
+    info!(source_id = %source_id, locations = config.locations.len(), "Weather source created");
+    Ok(source_id)
+}
+
+/// Lists weather sources with their runner status.
+pub fn handle_list_weather_sources(state: &ApiState) -> Result<Vec<WeatherSourceInfo>> {
+    let statuses = state.weather_runner.status();
+    Ok(state
+        .weather_runner
+        .store
+        .list()?
+        .into_iter()
+        .map(|config| {
+            let status = statuses.iter().find(|s| s.source_id == config.id);
+            WeatherSourceInfo::new(config, status)
+        })
+        .collect())
+}
+
+/// Stops and removes a weather source. Published entities are left in Flux.
+pub async fn handle_delete_weather_source(state: &ApiState, source_id: &str) -> Result<()> {
+    state.weather_runner.stop_source(source_id).await?;
+    state.weather_runner.store.delete(source_id)?;
+    info!(source_id = %source_id, "Weather source deleted");
+    Ok(())
+}
+
+/// Checks the Bento config a request with `bento_overrides` would run:
+/// `bento lint` on the rendered config, or the allowlist without Bento.
+pub async fn check_bento_overrides(
+    state: &ApiState,
+    req: &CreateGenericSourceRequest,
+) -> Result<()> {
+    let Some(ref overrides) = req.bento_overrides else {
+        return Ok(());
+    };
+    let config = req.to_config("lint");
+    let rendered = render_bento_config(
+        &config,
+        &state.runner.flux_api_url,
+        config.flux_namespace_token.as_deref(),
+    );
+    bento::check_config(overrides, &rendered).await
+}
+
+/// Bento config `config` runs with, credentials in its URL and
+/// secret-looking override fields redacted.
+pub fn rendered_generic_config(state: &ApiState, config: &GenericSourceConfig) -> String {
+    render_bento_config(
+        &bento::redacted(config),
+        &state.runner.flux_api_url,
+        config.flux_namespace_token.as_deref(),
+    )
+}
+
+/// Stops and removes a generic source.
+///
+/// Stops the poller (or kills the Bento subprocess), deletes the config from SQLite, and removes
+/// credentials from `CredentialStore` (best-effort — no error if not found).
+pub async fn handle_delete_generic_source(state: &ApiState, source_id: &str) -> Result<()> {
+    state.runner.stop_source(source_id).await?;
+    let config = state.config_store.get(source_id)?;
+    state.config_store.delete(source_id)?;
+    // Best-effort credential cleanup (may not exist if auth_type was None)
+    let _ = state.credential_store.delete("generic", source_id);
+    for (kind, name) in config.iter().flat_map(|c| c.secret_params()) {
+        let _ = state
+            .credential_store
+            .delete("generic", &secret_key(source_id, kind, name));
+    }
+    info!(source_id = %source_id, "Generic source deleted");
+    Ok(())
+}
+
+/// Returns a source's transform rules, or `None` if it does not exist.
+pub fn handle_get_source_transforms(
+    state: &ApiState,
+    kind: TransformSource,
+    source_id: &str,
+) -> Result<Option<Vec<TransformRule>>> {
+    Ok(match kind {
+        TransformSource::Generic => state.config_store.get(source_id)?.map(|c| c.transforms),
+        TransformSource::Named => state
+            .named_runner
+            .store
+            .get(source_id)?
+            .map(|c| c.transforms),
+        TransformSource::File => state
+            .file_runner
+            .store
+            .get(source_id)?
+            .map(|c| c.transforms),
+        TransformSource::Postgres => state
+            .postgres_runner
+            .store
+            .get(source_id)?
+            .map(|c| c.transforms),
+        TransformSource::Weather => state
+            .weather_runner
+            .store
+            .get(source_id)?
+            .map(|c| c.transforms),
+    })
+}
+
+/// Replaces a source's transform rules. Call [`transform::validate_rules`] first.
+///
+/// The changed config restarts a running source on the leader's next
+/// reconcile pass. Returns `Ok(false)` if the source does not exist.
+pub fn handle_set_source_transforms(
+    state: &ApiState,
+    kind: TransformSource,
+    source_id: &str,
+    rules: &[TransformRule],
+) -> Result<bool> {
+    let updated = match kind {
+        TransformSource::Generic => state.config_store.set_transforms(source_id, rules)?,
+        TransformSource::Named => state.named_runner.store.set_transforms(source_id, rules)?,
+        TransformSource::File => state.file_runner.store.set_transforms(source_id, rules)?,
+        TransformSource::Postgres => state
+            .postgres_runner
+            .store
+            .set_transforms(source_id, rules)?,
+        TransformSource::Weather => state
+            .weather_runner
+            .store
+            .set_transforms(source_id, rules)?,
+    };
+    if updated {
+        info!(source_id = %source_id, rules = rules.len(), "Source transform rules replaced");
+    }
+    Ok(updated)
+}
+
+// ---------------------------------------------------------------------------
+// HTTP handlers
+// ---------------------------------------------------------------------------
+
+/// The caller presenting `headers`; everyone is an admin with auth disabled.
+fn resolve_caller(state: &ApiState, headers: &HeaderMap) -> Result<Caller, AppError> {
+    let Some(ref auth) = state.auth else {
+        return Ok(Caller::Admin);
+    };
+    auth.caller(headers)?
+        .ok_or_else(|| AppError::Unauthorized("Missing or unknown bearer token".to_string()))
+}
+
+/// Sets the owner of a source a namespace caller creates to that namespace.
+/// Admins may name any owner.
+fn claim_owner(caller: &Caller, owner_namespace: &mut Option<String>) -> Result<(), AppError> {
+    let Caller::Namespace(ns) = caller else {
+        return Ok(());
+    };
+    if owner_namespace.as_ref().is_some_and(|owner| owner != ns) {
+        return Err(AppError::Forbidden(format!(
+            "Namespace {} may only create sources it owns",
+            ns
+        )));
+    }
+    *owner_namespace = Some(ns.clone());
+    Ok(())
+}
+
+/// Hash of a create request as parsed, so formatting and spelled-out
+/// defaults don't make a retry look like a different request.
+fn request_hash<T: Serialize>(req: &T) -> String {
+    idempotency::body_hash(&serde_json::to_vec(req).expect("requests serialize"))
+}
+
+/// How a create request with (or without) an `Idempotency-Key` proceeds.
+enum IdempotentCreate<'a> {
+    /// No key: create as usual
+    Untracked,
+    /// The key is this request's; settle it with [`settle_idempotency_key`]
+    Claimed { scope: String, key: &'a str },
+    /// The key already created this source from the same body
+    Replay(String),
+}
+
+/// Claims the request's `Idempotency-Key` for creating a `kind` source.
+///
+/// Keys are scoped to the endpoint and the caller. Fails with a conflict if
+/// the key was used with another body or its first request is still running.
+fn claim_idempotency_key<'a>(
+    state: &ApiState,
+    headers: &'a HeaderMap,
+    kind: &str,
+    caller: &Caller,
+    body_hash: &str,
+) -> Result<IdempotentCreate<'a>, AppError> {
+    let Some(key) =
+        idempotency::key_from_headers(headers).map_err(|e| AppError::BadRequest(e.to_string()))?
+    else {
+        return Ok(IdempotentCreate::Untracked);
+    };
+    let scope = match caller {
+        Caller::Admin => format!("{}:admin", kind),
+        Caller::Namespace(ns) => format!("{}:namespace:{}", kind, ns),
+    };
+    match state.idempotency.claim(&scope, key, body_hash)? {
+        Claim::New => Ok(IdempotentCreate::Claimed { scope, key }),
+        Claim::Replay(source_id) => Ok(IdempotentCreate::Replay(source_id)),
+        Claim::Mismatch => Err(AppError::Conflict(
+            "Idempotency-Key was already used with a different request body".to_string(),
+        )),
+        Claim::InProgress => Err(AppError::Conflict(
+            "A request with this Idempotency-Key is still in progress".to_string(),
+        )),
+    }
+}
+
+/// Records the source a claimed key created, or frees the key if creating
+/// failed so a retry can try again.
+fn settle_idempotency_key(state: &ApiState, create: &IdempotentCreate, result: &Result<String>) {
+    let IdempotentCreate::Claimed { scope, key } = create else {
+        return;
+    };
+    let settled = match result {
+        Ok(source_id) => state.idempotency.complete(scope, key, source_id),
+        Err(_) => state.idempotency.release(scope, key),
+    };
+    if let Err(e) = settled {
+        warn!(error = %e, "Failed to settle Idempotency-Key");
+    }
+}
+
+/// Fails unless `caller` owns the generic or named source. Sources of other
+/// namespaces are reported as not found, so their IDs are not confirmed.
+fn check_owner(
+    state: &ApiState,
+    caller: &Caller,
+    kind: TransformSource,
+    source_id: &str,
+) -> Result<(), AppError> {
+    if *caller == Caller::Admin {
+        return Ok(());
+    }
+    let owner = match kind {
+        TransformSource::Generic => state
+            .config_store
+            .get(source_id)?
+            .map(|c| c.owner_namespace),
+        TransformSource::Named => state
+            .named_runner
+            .store
+            .get(source_id)?
+            .map(|c| c.owner_namespace),
+        // File, Postgres and weather sources have no owner
+        _ => None,
+    };
+    match owner {
+        Some(owner) if caller.owns(&owner) => Ok(()),
+        _ => Err(AppError::NotFound(format!(
+            "{} {} not found",
+            kind.label(),
+            source_id
+        ))),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/named",
+    tag = "named",
+    request_body = CreateNamedSourceRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key and body return the source created first, for 24 hours"),
+    ),
+    responses(
+        (status = 201, description = "Source created and started", body = CreateNamedSourceResponse),
+        (status = 200, description = "Created earlier by a request with the same Idempotency-Key and body", body = CreateNamedSourceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`owner_namespace` is not the caller's namespace", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key used with a different body, or its first request is still running", body = ErrorResponse),
+        (status = 500, description = "Failed to persist or start", body = ErrorResponse),
+    )
+)]
+async fn post_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Json(mut req): Json<CreateNamedSourceRequest>,
+) -> Result<(StatusCode, Json<CreateNamedSourceResponse>), AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    let body_hash = request_hash(&req);
+    claim_owner(&caller, &mut req.owner_namespace)?;
+    if req.max_events_per_run == 0 {
+        return Err(AppError::BadRequest(
+            "max_events_per_run must be at least 1".to_string(),
+        ));
+    }
+    if let Some(ref policy) = req.retry_policy {
+        policy
+            .validate()
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    }
+    let create = claim_idempotency_key(&state, &headers, "named", &caller, &body_hash)?;
+    if let IdempotentCreate::Replay(source_id) = create {
+        return Ok((
+            StatusCode::OK,
+            Json(CreateNamedSourceResponse { source_id }),
+        ));
+    }
+    let result = handle_create_named_source(&state, req).await;
+    settle_idempotency_key(&state, &create, &result);
+    let source_id = result.map_err(AppError::from)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateNamedSourceResponse { source_id }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/named/{source_id}",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 204, description = "Source stopped and removed"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source owned by another namespace", body = ErrorResponse),
+        (status = 500, description = "Failed to remove", body = ErrorResponse),
+    )
+)]
+async fn delete_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Named, &source_id)?;
+    handle_delete_named_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/named/{source_id}/sync",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 202, description = "Sync started in the background"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source owned by another namespace", body = ErrorResponse),
+        (status = 500, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn post_sync_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Named, &source_id)?;
+    handle_sync_named_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/named/{source_id}/retry",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 202, description = "Trial attempt started"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not running or owned by another namespace", body = ErrorResponse),
+        (status = 409, description = "Circuit is not open", body = ErrorResponse),
+    )
+)]
+async fn post_retry_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Named, &source_id)?;
+    let retried = state
+        .named_runner
+        .retry_source(&source_id)
+        .map_err(|e| AppError::NotFound(e.to_string()))?;
+    if !retried {
+        return Err(AppError::Conflict(format!(
+            "Named source {} circuit is not open",
+            source_id
+        )));
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/named/{source_id}/pause",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 204, description = "Source paused; it is kept but no longer run"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn post_pause_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    set_named_source_paused(&state, &headers, &source_id, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/named/{source_id}/resume",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 204, description = "Source resumed"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn post_resume_named_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    set_named_source_paused(&state, &headers, &source_id, false).await
+}
+
+async fn set_named_source_paused(
+    state: &ApiState,
+    headers: &HeaderMap,
+    source_id: &str,
+    paused: bool,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(state, headers)?;
+    check_owner(state, &caller, TransformSource::Named, source_id)?;
+    if !handle_set_named_source_paused(state, source_id, paused).await? {
+        return Err(AppError::NotFound(format!(
+            "Named source {} not found",
+            source_id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/generic",
+    tag = "generic",
+    request_body = CreateGenericSourceRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key and body return the source created first, for 24 hours"),
+    ),
+    responses(
+        (status = 201, description = "Source created and started", body = CreateGenericSourceResponse),
+        (status = 200, description = "Created earlier by a request with the same Idempotency-Key and body", body = CreateGenericSourceResponse),
+        (status = 400, description = "Invalid headers, query parameters or Bento overrides (with the lint output)", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`owner_namespace` is not the caller's namespace", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key used with a different body, or its first request is still running", body = ErrorResponse),
+        (status = 500, description = "Failed to persist or start", body = ErrorResponse),
+    )
+)]
+async fn post_generic_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Json(mut req): Json<CreateGenericSourceRequest>,
+) -> Result<(StatusCode, Json<CreateGenericSourceResponse>), AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    let body_hash = request_hash(&req);
+    claim_owner(&caller, &mut req.owner_namespace)?;
+    req.validate()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    check_bento_overrides(&state, &req)
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let create = claim_idempotency_key(&state, &headers, "generic", &caller, &body_hash)?;
+    if let IdempotentCreate::Replay(source_id) = create {
+        return Ok((
+            StatusCode::OK,
+            Json(CreateGenericSourceResponse { source_id }),
+        ));
+    }
+    let result = handle_create_generic_source(&state, req).await;
+    settle_idempotency_key(&state, &create, &result);
+    let source_id = result.map_err(AppError::from)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateGenericSourceResponse { source_id }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/generic/{source_id}",
+    tag = "generic",
+    params(("source_id" = String, Path, description = "Generic source ID")),
+    responses(
+        (status = 204, description = "Source stopped and removed"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source owned by another namespace", body = ErrorResponse),
+        (status = 500, description = "Failed to remove", body = ErrorResponse),
+    )
+)]
+async fn delete_generic_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Generic, &source_id)?;
+    handle_delete_generic_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/generic/{source_id}/retry",
+    tag = "generic",
+    params(("source_id" = String, Path, description = "Generic source ID")),
+    responses(
+        (status = 202, description = "Trial attempt started"),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not running or owned by another namespace", body = ErrorResponse),
+        (status = 409, description = "Circuit is not open", body = ErrorResponse),
+    )
+)]
+async fn post_retry_generic_source(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Generic, &source_id)?;
+    let retried = state
+        .runner
+        .retry_source(&source_id)
+        .map_err(|e| AppError::NotFound(e.to_string()))?;
+    if !retried {
+        return Err(AppError::Conflict(format!(
+            "Generic source {} circuit is not open",
+            source_id
+        )));
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/generic/{source_id}/rendered-config",
+    tag = "generic",
+    params(("source_id" = String, Path, description = "Generic source ID")),
+    responses(
+        (status = 200, description = "Bento YAML config, secrets redacted", body = String, content_type = "application/yaml"),
+        (status = 400, description = "Source uses the native engine", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn get_generic_rendered_config(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<Response, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Generic, &source_id)?;
+    let config = state
+        .config_store
+        .get(&source_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Generic source {} not found", source_id)))?;
+    if config.engine != SourceEngine::Bento {
+        return Err(AppError::BadRequest(format!(
+            "Generic source {} uses the native engine, not Bento",
+            source_id
+        )));
+    }
+    let rendered = rendered_generic_config(&state, &config);
+    Ok(([(header::CONTENT_TYPE, "application/yaml")], rendered).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/files",
+    tag = "files",
+    request_body = FileSourceRequest,
+    responses(
+        (status = 201, description = "Source created and started", body = CreateFileSourceResponse),
+        (status = 500, description = "Failed to persist or start", body = ErrorResponse),
+    )
+)]
+async fn post_file_source(
+    State(state): State<Arc<ApiState>>,
+    Json(req): Json<FileSourceRequest>,
+) -> Result<(StatusCode, Json<CreateFileSourceResponse>), AppError> {
+    let source_id = handle_create_file_source(&state, req)
+        .await
+        .map_err(AppError::from)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateFileSourceResponse { source_id }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/files",
+    tag = "files",
+    responses(
+        (status = 200, description = "File-drop sources with ingest status", body = [FileSourceInfo]),
+        (status = 500, description = "Failed to list", body = ErrorResponse),
+    )
+)]
+async fn get_file_sources(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<Vec<FileSourceInfo>>, AppError> {
+    Ok(Json(handle_list_file_sources(&state)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/files/{source_id}",
+    tag = "files",
+    params(("source_id" = String, Path, description = "File source ID")),
+    responses(
+        (status = 200, description = "File-drop source with ingest status", body = FileSourceInfo),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn get_file_source(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<Json<FileSourceInfo>, AppError> {
+    let config = state
+        .file_runner
+        .store
+        .get(&source_id)?
+        .ok_or_else(|| AppError::NotFound(format!("File source {} not found", source_id)))?;
+    let statuses = state.file_runner.status();
+    let status = statuses.iter().find(|s| s.source_id == source_id);
+    Ok(Json(FileSourceInfo::new(config, status)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/files/{source_id}",
+    tag = "files",
+    params(("source_id" = String, Path, description = "File source ID")),
+    request_body = FileSourceRequest,
+    responses(
+        (status = 204, description = "Config replaced and source restarted"),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+        (status = 500, description = "Failed to persist or restart", body = ErrorResponse),
+    )
+)]
+async fn put_file_source(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+    Json(req): Json<FileSourceRequest>,
+) -> Result<StatusCode, AppError> {
+    if !handle_update_file_source(&state, &source_id, req).await? {
+        return Err(AppError::NotFound(format!(
+            "File source {} not found",
+            source_id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/files/{source_id}",
+    tag = "files",
+    params(("source_id" = String, Path, description = "File source ID")),
+    responses(
+        (status = 204, description = "Source stopped and removed"),
+        (status = 500, description = "Failed to remove", body = ErrorResponse),
+    )
+)]
+async fn delete_file_source(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    handle_delete_file_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/postgres",
+    tag = "postgres",
+    request_body = CreatePostgresSourceRequest,
+    responses(
+        (status = 201, description = "Source created and started", body = CreatePostgresSourceResponse),
+        (status = 400, description = "Invalid connection string or settings", body = ErrorResponse),
+        (status = 500, description = "Failed to persist or start", body = ErrorResponse),
+    )
+)]
+async fn post_postgres_source(
+    State(state): State<Arc<ApiState>>,
+    Json(req): Json<CreatePostgresSourceRequest>,
+) -> Result<(StatusCode, Json<CreatePostgresSourceResponse>), AppError> {
+    validate_postgres_request(&req).map_err(AppError::BadRequest)?;
+    let source_id = handle_create_postgres_source(&state, req)
+        .await
+        .map_err(AppError::from)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatePostgresSourceResponse { source_id }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/postgres/{source_id}",
+    tag = "postgres",
+    params(("source_id" = String, Path, description = "Postgres source ID")),
+    responses(
+        (status = 204, description = "Source stopped and removed"),
+        (status = 500, description = "Failed to remove", body = ErrorResponse),
+    )
+)]
+async fn delete_postgres_source(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    handle_delete_postgres_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/connectors/weather",
+    tag = "weather",
+    request_body = WeatherSourceRequest,
+    responses(
+        (status = 201, description = "Source created and started", body = CreateWeatherSourceResponse),
+        (status = 400, description = "Invalid namespace, locations or poll interval", body = ErrorResponse),
+        (status = 500, description = "Failed to persist or start", body = ErrorResponse),
+    )
+)]
+async fn post_weather_source(
+    State(state): State<Arc<ApiState>>,
+    Json(req): Json<WeatherSourceRequest>,
+) -> Result<(StatusCode, Json<CreateWeatherSourceResponse>), AppError> {
+    validate_weather_source(&req.namespace, &req.locations, req.poll_interval_secs)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let source_id = handle_create_weather_source(&state, req)
+        .await
+        .map_err(AppError::from)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateWeatherSourceResponse { source_id }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/weather",
+    tag = "weather",
+    responses(
+        (status = 200, description = "Weather sources with poll status", body = [WeatherSourceInfo]),
+        (status = 500, description = "Failed to read configs", body = ErrorResponse),
+    )
+)]
+async fn get_weather_sources(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<Vec<WeatherSourceInfo>>, AppError> {
+    Ok(Json(handle_list_weather_sources(&state)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/weather/{source_id}",
+    tag = "weather",
+    params(("source_id" = String, Path, description = "Weather source ID")),
+    responses(
+        (status = 204, description = "Source stopped and removed"),
+        (status = 500, description = "Failed to remove", body = ErrorResponse),
+    )
+)]
+async fn delete_weather_source(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    handle_delete_weather_source(&state, &source_id)
+        .await
+        .map_err(AppError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Shared body of the `GET .../:source_id/transforms` handlers.
+fn source_transforms(
+    state: &ApiState,
+    kind: TransformSource,
+    source_id: &str,
+) -> Result<Json<TransformRules>, AppError> {
+    let rules = handle_get_source_transforms(state, kind, source_id)?
+        .ok_or_else(|| AppError::NotFound(format!("{} {} not found", kind.label(), source_id)))?;
+    Ok(Json(TransformRules { rules }))
+}
+
+/// Shared body of the `PUT .../:source_id/transforms` handlers.
+fn replace_source_transforms(
+    state: &ApiState,
+    kind: TransformSource,
+    source_id: &str,
+    body: TransformRules,
+) -> Result<StatusCode, AppError> {
+    transform::validate_rules(&body.rules).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    // Bento posts to Flux itself, past the publishing path the rules run in
+    if kind == TransformSource::Generic {
+        let engine = state.config_store.get(source_id)?.map(|c| c.engine);
+        if engine == Some(SourceEngine::Bento) {
+            return Err(AppError::BadRequest(format!(
+                "Generic source {} uses the Bento engine, which does not support transforms",
+                source_id
+            )));
+        }
+    }
+    if !handle_set_source_transforms(state, kind, source_id, &body.rules)? {
+        return Err(AppError::NotFound(format!(
+            "{} {} not found",
+            kind.label(),
+            source_id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/generic/{source_id}/transforms",
+    tag = "generic",
+    params(("source_id" = String, Path, description = "Generic source ID")),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn get_generic_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<Json<TransformRules>, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Generic, &source_id)?;
+    source_transforms(&state, TransformSource::Generic, &source_id)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/generic/{source_id}/transforms",
+    tag = "generic",
+    params(("source_id" = String, Path, description = "Generic source ID")),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; the source restarts with them"),
+        (status = 400, description = "Invalid rules, or the source uses the Bento engine", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn put_generic_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Generic, &source_id)?;
+    replace_source_transforms(&state, TransformSource::Generic, &source_id, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/named/{source_id}/transforms",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn get_named_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+) -> Result<Json<TransformRules>, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Named, &source_id)?;
+    source_transforms(&state, TransformSource::Named, &source_id)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/named/{source_id}/transforms",
+    tag = "named",
+    params(("source_id" = String, Path, description = "Named source ID")),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; the source restarts with them"),
+        (status = 400, description = "Invalid rules", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 404, description = "Source not found or owned by another namespace", body = ErrorResponse),
+    )
+)]
+async fn put_named_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path(source_id): Path<String>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    check_owner(&state, &caller, TransformSource::Named, &source_id)?;
+    replace_source_transforms(&state, TransformSource::Named, &source_id, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/files/{source_id}/transforms",
+    tag = "files",
+    params(("source_id" = String, Path, description = "File source ID")),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn get_file_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<Json<TransformRules>, AppError> {
+    source_transforms(&state, TransformSource::File, &source_id)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/files/{source_id}/transforms",
+    tag = "files",
+    params(("source_id" = String, Path, description = "File source ID")),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; the source restarts with them"),
+        (status = 400, description = "Invalid rules", body = ErrorResponse),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn put_file_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    replace_source_transforms(&state, TransformSource::File, &source_id, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/postgres/{source_id}/transforms",
+    tag = "postgres",
+    params(("source_id" = String, Path, description = "Postgres source ID")),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn get_postgres_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<Json<TransformRules>, AppError> {
+    source_transforms(&state, TransformSource::Postgres, &source_id)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/postgres/{source_id}/transforms",
+    tag = "postgres",
+    params(("source_id" = String, Path, description = "Postgres source ID")),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; the source restarts with them"),
+        (status = 400, description = "Invalid rules", body = ErrorResponse),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn put_postgres_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    replace_source_transforms(&state, TransformSource::Postgres, &source_id, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/weather/{source_id}/transforms",
+    tag = "weather",
+    params(("source_id" = String, Path, description = "Weather source ID")),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn get_weather_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+) -> Result<Json<TransformRules>, AppError> {
+    source_transforms(&state, TransformSource::Weather, &source_id)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/weather/{source_id}/transforms",
+    tag = "weather",
+    params(("source_id" = String, Path, description = "Weather source ID")),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; the source restarts with them"),
+        (status = 400, description = "Invalid rules", body = ErrorResponse),
+        (status = 404, description = "Source not found", body = ErrorResponse),
+    )
+)]
+async fn put_weather_transforms(
+    State(state): State<Arc<ApiState>>,
+    Path(source_id): Path<String>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    replace_source_transforms(&state, TransformSource::Weather, &source_id, body)
+}
+
+/// With auth enabled a builtin connector's `user_id` is the namespace whose
+/// token added its credentials, so namespaces only reach their own rules
+/// and configuration.
+fn check_builtin_user(caller: &Caller, user_id: &str) -> Result<(), AppError> {
+    if caller.owns(user_id) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden(format!(
+            "Connectors of user {} belong to another namespace",
+            user_id
+        )))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/builtin/{user_id}/{connector}/transforms",
+    tag = "builtin",
+    params(
+        ("user_id" = String, Path, description = "User whose credentials the connector polls with"),
+        ("connector" = String, Path, description = "Builtin or external connector name"),
+    ),
+    responses(
+        (status = 200, description = "Transform rules in the order they are applied", body = TransformRules),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`user_id` is not the caller's namespace", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    )
+)]
+async fn get_builtin_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path((user_id, connector)): Path<(String, String)>,
+) -> Result<Json<TransformRules>, AppError> {
+    check_builtin_user(&resolve_caller(&state, &headers)?, &user_id)?;
+    if state.registry.get(&connector).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Unknown connector {}",
+            connector
+        )));
+    }
+    let rules = state.transform_store.get(&user_id, &connector)?;
+    Ok(Json(TransformRules { rules }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/builtin/{user_id}/{connector}/transforms",
+    tag = "builtin",
+    params(
+        ("user_id" = String, Path, description = "User whose credentials the connector polls with"),
+        ("connector" = String, Path, description = "Builtin or external connector name"),
+    ),
+    request_body = TransformRules,
+    responses(
+        (status = 204, description = "Rules replaced; applied from the next poll"),
+        (status = 400, description = "Invalid rules", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`user_id` is not the caller's namespace", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    )
+)]
+async fn put_builtin_transforms(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path((user_id, connector)): Path<(String, String)>,
+    Json(body): Json<TransformRules>,
+) -> Result<StatusCode, AppError> {
+    check_builtin_user(&resolve_caller(&state, &headers)?, &user_id)?;
+    transform::validate_rules(&body.rules).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    if state.registry.get(&connector).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Unknown connector {}",
+            connector
+        )));
+    }
+    state
+        .transform_store
+        .set(&user_id, &connector, &body.rules)?;
+    info!(user_id = %user_id, connector = %connector, rules = body.rules.len(), "Connector transform rules replaced");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/builtin/{user_id}/{connector}/config",
+    tag = "builtin",
+    params(
+        ("user_id" = String, Path, description = "User whose credentials the connector polls with"),
+        ("connector" = String, Path, description = "Builtin or external connector name"),
+    ),
+    responses(
+        (status = 200, description = "The user's configuration, `null` if none was set", body = ConnectorUserConfig),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`user_id` is not the caller's namespace", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    )
+)]
+async fn get_builtin_config(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path((user_id, connector)): Path<(String, String)>,
+) -> Result<Json<ConnectorUserConfig>, AppError> {
+    check_builtin_user(&resolve_caller(&state, &headers)?, &user_id)?;
+    if state.registry.get(&connector).is_none() {
+        return Err(AppError::NotFound(format!(
+            "Unknown connector {}",
+            connector
+        )));
+    }
+    let config = state.user_config_store.get(&user_id, &connector)?;
+    Ok(Json(ConnectorUserConfig { config }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/connectors/builtin/{user_id}/{connector}/config",
+    tag = "builtin",
+    params(
+        ("user_id" = String, Path, description = "User whose credentials the connector polls with"),
+        ("connector" = String, Path, description = "Builtin or external connector name"),
+    ),
+    request_body = ConnectorUserConfig,
+    responses(
+        (status = 204, description = "Configuration replaced; used from the next poll"),
+        (status = 400, description = "The connector rejected the configuration", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+        (status = 403, description = "`user_id` is not the caller's namespace", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    )
+)]
+async fn put_builtin_config(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Path((user_id, connector)): Path<(String, String)>,
+    Json(body): Json<ConnectorUserConfig>,
+) -> Result<StatusCode, AppError> {
+    check_builtin_user(&resolve_caller(&state, &headers)?, &user_id)?;
+    let Some(found) = state.registry.get(&connector) else {
+        return Err(AppError::NotFound(format!(
+            "Unknown connector {}",
+            connector
+        )));
+    };
+    found
+        .validate_user_config(&body.config)
+        .map_err(AppError::BadRequest)?;
+    state
+        .user_config_store
+        .set(&user_id, &connector, &body.config)?;
+    info!(user_id = %user_id, connector = %connector, "Connector user config replaced");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors",
+    tag = "connectors",
+    responses(
+        (status = 200, description = "Builtin, external, generic, named, file, postgres and weather connectors; a namespace token sees only its own schedulers and sources", body = [ConnectorInfo]),
+        (status = 401, description = "Missing or unknown bearer token", body = ErrorResponse),
+    )
+)]
+async fn list_connectors(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ConnectorInfo>>, AppError> {
+    let caller = resolve_caller(&state, &headers)?;
+    let mut connectors: Vec<ConnectorInfo> = Vec::new();
+
+    // Builtin and external connectors from registry, one entry per active scheduler
+    let builtin_status: Vec<(String, Arc<tokio::sync::Mutex<ConnectorStatus>>)> = {
+        let map = state.builtin_status.lock().await;
+        let mut entries: Vec<_> = map
+            .iter()
+            .filter(|(k, _)| {
+                k.split_once(':')
+                    .is_some_and(|(user_id, _)| caller.owns(user_id))
+            })
+            .map(|(k, v)| (k.clone(), Arc::clone(v)))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    };
+
+    for c in state.registry.connectors() {
+        let suffix = format!(":{}", c.name());
+        let mut has_scheduler = false;
+        let connector_type = if state.registry.is_external(c.name()) {
+            "external"
+        } else {
+            "builtin"
+        };
+
+        for (key, status) in builtin_status.iter().filter(|(k, _)| k.ends_with(&suffix)) {
+            has_scheduler = true;
+            let status = status.lock().await;
+            let st = if status.stopped {
+                "stopped"
+            } else if status.last_error.is_some() && status.next_retry_at.is_some() {
+                "backoff"
+            } else if status.last_error.is_some() {
+                "error"
+            } else if status.last_run_dropped_events > 0 {
+                "partial"
+            } else {
+                "running"
+            };
+            connectors.push(ConnectorInfo {
+                name: c.name().to_string(),
+                connector_type: connector_type.to_string(),
+                enabled: !status.stopped,
+                status: st.to_string(),
+                source_id: Some(key.clone()),
+                last_started: status.last_poll.map(|dt| dt.to_rfc3339()),
+                last_error: status.last_error.clone(),
+                next_retry_at: status.next_retry_at.map(|dt| dt.to_rfc3339()),
+                last_http_status: None,
+                last_latency_ms: None,
+                dropped_events: Some(status.last_run_dropped_events),
+                owner_namespace: None,
+                consecutive_failures: None,
+                circuit_open_until: None,
+                events_emitted: state.metrics.events_emitted("builtin", key),
+                streams: None,
+            });
+        }
+
+        if !has_scheduler {
+            connectors.push(ConnectorInfo {
+                name: c.name().to_string(),
+                connector_type: connector_type.to_string(),
+                enabled: true,
+                status: "running".to_string(),
+                source_id: None,
+                last_started: None,
+                last_error: None,
+                next_retry_at: None,
+                last_http_status: None,
+                last_latency_ms: None,
+                dropped_events: None,
+                owner_namespace: None,
+                consecutive_failures: None,
+                circuit_open_until: None,
+                events_emitted: None,
+                streams: None,
+            });
+        }
+    }
+
+    // Generic connectors from config store + runner status
+    let generic_configs = state.config_store.list().unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to list generic source configs");
+        vec![]
+    });
+    let statuses = state.runner.status();
+
+    for config in generic_configs
+        .into_iter()
+        .filter(|c| caller.owns(&c.owner_namespace))
+    {
+        let status_entry = statuses.iter().find(|s| s.source_id == config.id);
+        let (status, last_started, last_error) = match status_entry {
+            Some(s) => {
+                let st = if s.circuit_open_until.is_some() {
+                    "circuit_open"
+                } else if s.last_error.is_some() {
+                    "error"
+                } else {
+                    "running"
+                };
+                (
+                    st.to_string(),
+                    s.last_started.map(|dt| dt.to_rfc3339()),
+                    s.last_error.clone(),
+                )
+            }
+            None => ("stopped".to_string(), None, None),
+        };
+        let events_emitted = state.metrics.events_emitted("generic", &config.id);
+
+        connectors.push(ConnectorInfo {
             name: config.name,
             connector_type: "generic".to_string(),
             enabled: true,
@@ -332,186 +2396,1167 @@ async fn list_connectors(State(state): State<Arc<ApiState>>) -> Json<Vec<Connect
             source_id: Some(config.id),
             last_started,
             last_error,
+            next_retry_at: None,
+            last_http_status: status_entry.and_then(|s| s.last_http_status),
+            last_latency_ms: status_entry.and_then(|s| s.last_latency_ms),
+            dropped_events: None,
+            owner_namespace: Some(config.owner_namespace),
+            consecutive_failures: status_entry.map(|s| s.consecutive_failures),
+            circuit_open_until: status_entry
+                .and_then(|s| s.circuit_open_until)
+                .map(|dt| dt.to_rfc3339()),
+            events_emitted,
+            streams: None,
+        });
+    }
+
+    // Named connectors from config store + runner status
+    let named_configs = state.named_runner.store.list().unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to list named source configs");
+        vec![]
+    });
+    let named_statuses = state.named_runner.status();
+
+    for config in named_configs
+        .into_iter()
+        .filter(|c| caller.owns(&c.owner_namespace))
+    {
+        let status_entry = named_statuses.iter().find(|s| s.source_id == config.id);
+        let (status, last_started, last_error) = match status_entry {
+            Some(s) => {
+                let st = if config.paused {
+                    "paused"
+                } else if s.circuit_open_until.is_some() {
+                    "circuit_open"
+                } else if s.last_error.is_some() {
+                    "error"
+                } else if s.last_run_dropped_events > 0 || s.last_run_partial {
+                    "partial"
+                } else {
+                    "running"
+                };
+                (
+                    st.to_string(),
+                    s.last_run.map(|dt| dt.to_rfc3339()),
+                    s.last_error.clone(),
+                )
+            }
+            None if config.paused => ("paused".to_string(), None, None),
+            None => ("stopped".to_string(), None, None),
+        };
+        let events_emitted = state.metrics.events_emitted("named", &config.id);
+
+        connectors.push(ConnectorInfo {
+            name: config.tap_name,
+            connector_type: "named".to_string(),
+            enabled: !config.paused,
+            status,
+            source_id: Some(config.id),
+            last_started,
+            last_error,
+            next_retry_at: None,
+            last_http_status: None,
+            last_latency_ms: None,
+            dropped_events: status_entry.map(|s| s.last_run_dropped_events),
+            owner_namespace: Some(config.owner_namespace),
+            consecutive_failures: status_entry.map(|s| s.consecutive_failures),
+            circuit_open_until: status_entry
+                .and_then(|s| s.circuit_open_until)
+                .map(|dt| dt.to_rfc3339()),
+            events_emitted,
+            streams: status_entry
+                .filter(|s| !s.streams.is_empty())
+                .map(|s| s.streams.clone()),
+        });
+    }
+
+    // File, Postgres and weather sources have no owner; only admins see them
+    if caller != Caller::Admin {
+        return Ok(Json(connectors));
+    }
+
+    // File-drop connectors from config store + runner status
+    let file_sources = handle_list_file_sources(&state).unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to list file source configs");
+        vec![]
+    });
+
+    for source in file_sources {
+        let status = if source.last_error.is_some() {
+            "error"
+        } else if source.last_scan.is_some() {
+            "running"
+        } else {
+            "stopped"
+        };
+        connectors.push(ConnectorInfo {
+            name: source.name,
+            connector_type: "file".to_string(),
+            enabled: true,
+            status: status.to_string(),
+            source_id: Some(source.source_id),
+            last_started: source.last_scan,
+            last_error: source.last_error,
+            next_retry_at: None,
+            last_http_status: None,
+            last_latency_ms: None,
+            dropped_events: None,
+            owner_namespace: None,
+            consecutive_failures: None,
+            circuit_open_until: None,
+            events_emitted: None,
+            streams: None,
+        });
+    }
+
+    // Postgres connectors from config store + runner status
+    let postgres_configs = state.postgres_runner.store.list().unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to list postgres source configs");
+        vec![]
+    });
+    let postgres_statuses = state.postgres_runner.status();
+
+    for config in postgres_configs {
+        let status_entry = postgres_statuses.iter().find(|s| s.source_id == config.id);
+        let (status, last_started, last_error) = match status_entry {
+            Some(s) => {
+                let st = if s.last_error.is_some() {
+                    "error"
+                } else {
+                    "running"
+                };
+                (
+                    st.to_string(),
+                    s.last_poll.map(|dt| dt.to_rfc3339()),
+                    s.last_error.clone(),
+                )
+            }
+            None => ("stopped".to_string(), None, None),
+        };
+
+        connectors.push(ConnectorInfo {
+            name: config.name,
+            connector_type: "postgres".to_string(),
+            enabled: true,
+            status,
+            source_id: Some(config.id),
+            last_started,
+            last_error,
+            next_retry_at: None,
+            last_http_status: None,
+            last_latency_ms: None,
+            dropped_events: None,
+            owner_namespace: None,
+            consecutive_failures: None,
+            circuit_open_until: None,
+            events_emitted: None,
+            streams: None,
         });
     }
 
-    // Named connectors from config store + runner status
-    let named_configs = state.named_runner.store.list().unwrap_or_else(|e| {
-        warn!(error = %e, "Failed to list named source configs");
-        vec![]
-    });
-    let named_statuses = state.named_runner.status();
+    // Weather connectors from config store + runner status
+    let weather_sources = handle_list_weather_sources(&state).unwrap_or_else(|e| {
+        warn!(error = %e, "Failed to list weather source configs");
+        vec![]
+    });
+
+    for source in weather_sources {
+        let status = if source.last_error.is_some() {
+            "error"
+        } else if source.last_poll.is_some() {
+            "running"
+        } else {
+            "stopped"
+        };
+        connectors.push(ConnectorInfo {
+            name: source.name,
+            connector_type: "weather".to_string(),
+            enabled: true,
+            status: status.to_string(),
+            source_id: Some(source.source_id),
+            last_started: source.last_poll,
+            last_error: source.last_error,
+            next_retry_at: None,
+            last_http_status: None,
+            last_latency_ms: None,
+            dropped_events: None,
+            owner_namespace: None,
+            consecutive_failures: None,
+            circuit_open_until: None,
+            events_emitted: None,
+            streams: None,
+        });
+    }
+
+    Ok(Json(connectors))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/taps",
+    tag = "connectors",
+    responses((status = 200, description = "Meltano Hub tap catalog", body = [TapCatalogEntry]))
+)]
+async fn get_tap_catalog(State(state): State<Arc<ApiState>>) -> Json<Vec<TapCatalogEntry>> {
+    Json(state.tap_catalog.list())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/leader",
+    tag = "connectors",
+    responses((status = 200, description = "Leader election state of this instance", body = LeaderStatus))
+)]
+async fn get_leader(State(state): State<Arc<ApiState>>) -> Json<LeaderStatus> {
+    Json(state.leadership.status())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/connectors/rate-limit",
+    tag = "connectors",
+    responses((status = 200, description = "Global publish rate limiter state", body = LimiterStatus))
+)]
+async fn get_rate_limit(State(state): State<Arc<ApiState>>) -> Json<LimiterStatus> {
+    Json(state.limiter.status())
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "connectors",
+    responses((status = 200, description = "Prometheus text exposition", content_type = "text/plain", body = String))
+)]
+async fn get_metrics(State(state): State<Arc<ApiState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        state.metrics.render(),
+    )
+}
+
+/// Rescan the external connector manifest directory.
+///
+/// Schedulers of connectors that are no longer registered stop at the next
+/// discovery cycle; new ones start once they have credentials.
+#[utoipa::path(
+    post,
+    path = "/api/connectors/registry/reload",
+    tag = "connectors",
+    responses(
+        (status = 200, description = "External connectors now registered, and skipped manifests", body = ReloadReport),
+        (status = 400, description = "EXTERNAL_CONNECTORS_DIR is not set", body = ErrorResponse),
+        (status = 500, description = "Manifest directory could not be read", body = ErrorResponse)
+    )
+)]
+async fn post_registry_reload(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<ReloadReport>, AppError> {
+    if !state.registry.external_enabled() {
+        return Err(AppError::BadRequest(
+            "EXTERNAL_CONNECTORS_DIR is not set".to_string(),
+        ));
+    }
+    Ok(Json(state.registry.reload()?))
+}
+
+// ---------------------------------------------------------------------------
+// Error handling
+// ---------------------------------------------------------------------------
+
+enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Internal(String),
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, msg) = match self {
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+        (status, Json(ErrorResponse { error: msg })).into_response()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// OpenAPI
+// ---------------------------------------------------------------------------
 
-    for config in named_configs {
-        let status_entry = named_statuses.iter().find(|s| s.source_id == config.id);
-        let (status, last_started, last_error) = match status_entry {
-            Some(s) => {
-                let st = if s.last_error.is_some() { "error" } else { "running" };
-                (
-                    st.to_string(),
-                    s.last_run.map(|dt| dt.to_rfc3339()),
-                    s.last_error.clone(),
-                )
-            }
-            None => ("stopped".to_string(), None, None),
-        };
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Flux Connector Manager API",
+        description = "Manage generic (native or Bento), named (Singer), file-drop, Postgres and weather connector sources"
+    ),
+    paths(
+        post_named_source,
+        delete_named_source,
+        post_sync_named_source,
+        post_retry_named_source,
+        post_pause_named_source,
+        post_resume_named_source,
+        post_generic_source,
+        delete_generic_source,
+        post_retry_generic_source,
+        get_generic_rendered_config,
+        post_file_source,
+        get_file_sources,
+        get_file_source,
+        put_file_source,
+        delete_file_source,
+        post_postgres_source,
+        delete_postgres_source,
+        post_weather_source,
+        get_weather_sources,
+        delete_weather_source,
+        get_generic_transforms,
+        put_generic_transforms,
+        get_named_transforms,
+        put_named_transforms,
+        get_file_transforms,
+        put_file_transforms,
+        get_postgres_transforms,
+        put_postgres_transforms,
+        get_weather_transforms,
+        put_weather_transforms,
+        get_builtin_transforms,
+        put_builtin_transforms,
+        get_builtin_config,
+        put_builtin_config,
+        list_connectors,
+        get_tap_catalog,
+        get_leader,
+        get_rate_limit,
+        get_metrics,
+        post_registry_reload
+    ),
+    components(schemas(
+        AuthTypeInput,
+        SourceEngine,
+        RequestParamInput,
+        ProxyInput,
+        BentoOverrides,
+        RetryPolicy,
+        CreateGenericSourceRequest,
+        CreateGenericSourceResponse,
+        CreateNamedSourceRequest,
+        CreateNamedSourceResponse,
+        FileFormat,
+        FileSourceRequest,
+        CreateFileSourceResponse,
+        FileSourceInfo,
+        CreatePostgresSourceRequest,
+        CreatePostgresSourceResponse,
+        WeatherLocation,
+        WeatherSourceRequest,
+        CreateWeatherSourceResponse,
+        WeatherSourceInfo,
+        TransformRule,
+        RedactMode,
+        TransformRules,
+        ConnectorUserConfig,
+        ConnectorInfo,
+        NamedStreamStatus,
+        TapCatalogEntry,
+        LeaderStatus,
+        LimiterStatus,
+        ReloadReport,
+        ManifestError,
+        ErrorResponse
+    ))
+)]
+pub struct ApiDoc;
 
-        connectors.push(ConnectorInfo {
-            name: config.tap_name,
-            connector_type: "named".to_string(),
-            enabled: true,
-            status,
-            source_id: Some(config.id),
-            last_started,
-            last_error,
-        });
+/// Router serving the OpenAPI spec at `/api/openapi.json`, plus Swagger UI
+/// at `/api/docs` if `docs_enabled`.
+pub fn create_openapi_router(docs_enabled: bool) -> Router {
+    let spec = ApiDoc::openapi();
+    if docs_enabled {
+        Router::new().merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", spec))
+    } else {
+        Router::new().route(
+            "/api/openapi.json",
+            get(move || {
+                let spec = spec.clone();
+                async move { Json(spec) }
+            }),
+        )
     }
+}
+
+// ---------------------------------------------------------------------------
+// Router
+// ---------------------------------------------------------------------------
 
-    Json(connectors)
+pub fn create_router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/connectors/named", post(post_named_source))
+        .route(
+            "/api/connectors/named/:source_id",
+            delete(delete_named_source),
+        )
+        .route(
+            "/api/connectors/named/:source_id/sync",
+            post(post_sync_named_source),
+        )
+        .route(
+            "/api/connectors/named/:source_id/retry",
+            post(post_retry_named_source),
+        )
+        .route(
+            "/api/connectors/named/:source_id/pause",
+            post(post_pause_named_source),
+        )
+        .route(
+            "/api/connectors/named/:source_id/resume",
+            post(post_resume_named_source),
+        )
+        .route("/api/connectors/generic", post(post_generic_source))
+        .route(
+            "/api/connectors/generic/:source_id",
+            delete(delete_generic_source),
+        )
+        .route(
+            "/api/connectors/generic/:source_id/retry",
+            post(post_retry_generic_source),
+        )
+        .route(
+            "/api/connectors/generic/:source_id/rendered-config",
+            get(get_generic_rendered_config),
+        )
+        .route(
+            "/api/connectors/files",
+            post(post_file_source).get(get_file_sources),
+        )
+        .route(
+            "/api/connectors/files/:source_id",
+            get(get_file_source)
+                .put(put_file_source)
+                .delete(delete_file_source),
+        )
+        .route("/api/connectors/postgres", post(post_postgres_source))
+        .route(
+            "/api/connectors/postgres/:source_id",
+            delete(delete_postgres_source),
+        )
+        .route(
+            "/api/connectors/weather",
+            post(post_weather_source).get(get_weather_sources),
+        )
+        .route(
+            "/api/connectors/weather/:source_id",
+            delete(delete_weather_source),
+        )
+        .route(
+            "/api/connectors/generic/:source_id/transforms",
+            get(get_generic_transforms).put(put_generic_transforms),
+        )
+        .route(
+            "/api/connectors/named/:source_id/transforms",
+            get(get_named_transforms).put(put_named_transforms),
+        )
+        .route(
+            "/api/connectors/files/:source_id/transforms",
+            get(get_file_transforms).put(put_file_transforms),
+        )
+        .route(
+            "/api/connectors/postgres/:source_id/transforms",
+            get(get_postgres_transforms).put(put_postgres_transforms),
+        )
+        .route(
+            "/api/connectors/weather/:source_id/transforms",
+            get(get_weather_transforms).put(put_weather_transforms),
+        )
+        .route(
+            "/api/connectors/builtin/:user_id/:connector/transforms",
+            get(get_builtin_transforms).put(put_builtin_transforms),
+        )
+        .route(
+            "/api/connectors/builtin/:user_id/:connector/config",
+            get(get_builtin_config).put(put_builtin_config),
+        )
+        .route("/api/connectors", get(list_connectors))
+        .route("/api/connectors/taps", get(get_tap_catalog))
+        .route(
+            "/api/connectors/registry/reload",
+            post(post_registry_reload),
+        )
+        .route("/api/connectors/rate-limit", get(get_rate_limit))
+        .route("/api/leader", get(get_leader))
+        .route("/metrics", get(get_metrics))
+        .with_state(Arc::new(state))
 }
 
-async fn get_tap_catalog(State(state): State<Arc<ApiState>>) -> Json<Vec<TapCatalogEntry>> {
-    Json(state.tap_catalog.list())
-}
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::notion::NotionConnector;
+    use crate::file_config::FileConfigStore;
+    use crate::named_config::NamedConfigStore;
+    use crate::postgres_config::PostgresConfigStore;
+    use crate::weather_config::WeatherConfigStore;
+    use crate::Connector;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use flux::namespace::{Namespace, NamespaceStore};
+    use flux::state::NamespaceQuota;
+
+    fn make_state() -> ApiState {
+        let config_store = Arc::new(GenericConfigStore::new(":memory:").unwrap());
+        let named_store = Arc::new(NamedConfigStore::new(":memory:").unwrap());
+        let credential_store =
+            Arc::new(CredentialStore::new(":memory:", &BASE64.encode([0u8; 32])).unwrap());
+        let runner = Arc::new(GenericRunner::new(
+            Arc::clone(&config_store),
+            "http://localhost:3000".to_string(),
+        ));
+        let named_runner = Arc::new(NamedRunner::new(
+            Arc::clone(&named_store),
+            "http://localhost:3000".to_string(),
+        ));
+        let file_runner = Arc::new(FileRunner::new(
+            Arc::new(FileConfigStore::new(":memory:").unwrap()),
+            "http://localhost:3000".to_string(),
+        ));
+        let postgres_runner = Arc::new(PostgresRunner::new(
+            Arc::new(PostgresConfigStore::new(":memory:").unwrap()),
+            "http://localhost:3000".to_string(),
+        ));
+        let weather_runner = Arc::new(WeatherRunner::new(
+            Arc::new(WeatherConfigStore::new(":memory:").unwrap()),
+            "http://localhost:3000".to_string(),
+        ));
+        let tap_catalog = Arc::new(TapCatalogStore::new("/nonexistent/test-catalog.json"));
+        ApiState {
+            config_store,
+            runner,
+            credential_store,
+            tap_catalog,
+            named_runner,
+            file_runner,
+            postgres_runner,
+            weather_runner,
+            transform_store: Arc::new(TransformConfigStore::new(":memory:").unwrap()),
+            user_config_store: Arc::new(UserConfigStore::new(":memory:").unwrap()),
+            registry: Arc::new(ConnectorRegistry::default()),
+            builtin_status: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            leadership: Leadership::standalone("test"),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+            metrics: Arc::new(ConnectorMetrics::new()),
+            auth: None,
+            idempotency: Arc::new(IdempotencyStore::new(":memory:").unwrap()),
+        }
+    }
+
+    fn make_request(name: &str) -> CreateGenericSourceRequest {
+        CreateGenericSourceRequest {
+            name: name.to_string(),
+            url: "https://api.coingecko.com/api/v3/simple/price".to_string(),
+            poll_interval_secs: 300,
+            entity_key: "bitcoin".to_string(),
+            namespace: "personal".to_string(),
+            auth_type: AuthTypeInput::Plain("none".to_string()),
+            token: None,
+            flux_namespace_token: None,
+            engine: SourceEngine::Bento,
+            headers: Vec::new(),
+            query_params: Vec::new(),
+            proxy: None,
+            bento_overrides: None,
+            owner_namespace: None,
+            retry_policy: None,
+        }
+    }
+
+    fn make_named_request(tap: &str) -> CreateNamedSourceRequest {
+        CreateNamedSourceRequest {
+            tap_name: tap.to_string(),
+            namespace: "personal".to_string(),
+            entity_key_field: "id".to_string(),
+            config_json: r#"{"access_token": "ghp_test"}"#.to_string(),
+            poll_interval_secs: 3600,
+            flux_namespace_token: None,
+            coerce_types: false,
+            max_events_per_run: DEFAULT_MAX_EVENTS_PER_RUN,
+            owner_namespace: None,
+            retry_policy: None,
+            parallel_streams: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_named_source_stores_config() {
+        let state = make_state();
+        let source_id = handle_create_named_source(&state, make_named_request("tap-github"))
+            .await
+            .unwrap();
+
+        let stored = state.named_runner.store.get(&source_id).unwrap();
+        assert!(stored.is_some(), "config should be stored after POST");
+        let config = stored.unwrap();
+        assert_eq!(config.tap_name, "tap-github");
+        assert_eq!(config.namespace, "personal");
+        assert_eq!(config.entity_key_field, "id");
+        assert_eq!(config.poll_interval_secs, 3600);
+    }
+
+    #[tokio::test]
+    async fn test_delete_named_source_removes_config() {
+        let state = make_state();
+        let source_id = handle_create_named_source(&state, make_named_request("tap-github"))
+            .await
+            .unwrap();
+        assert!(
+            state.named_runner.store.get(&source_id).unwrap().is_some(),
+            "config should exist before delete"
+        );
+
+        handle_delete_named_source(&state, &source_id)
+            .await
+            .unwrap();
+
+        let stored = state.named_runner.store.get(&source_id).unwrap();
+        assert!(stored.is_none(), "config should be removed after DELETE");
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_named_source() {
+        let state = Arc::new(make_state());
+        let source_id = handle_create_named_source(&state, make_named_request("tap-github"))
+            .await
+            .unwrap();
+        let named = |connectors: Vec<ConnectorInfo>| {
+            connectors
+                .into_iter()
+                .find(|c| c.connector_type == "named")
+                .unwrap()
+        };
+
+        let status = post_pause_named_source(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            Path(source_id.clone()),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(
+            state
+                .named_runner
+                .store
+                .get(&source_id)
+                .unwrap()
+                .unwrap()
+                .paused
+        );
+        assert!(state.named_runner.running_ids().is_empty());
+        let listed = list_connectors(State(Arc::clone(&state)), HeaderMap::new())
+            .await
+            .ok()
+            .unwrap();
+        let info = named(listed.0);
+        assert_eq!(info.status, "paused");
+        assert!(!info.enabled);
+
+        post_resume_named_source(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            Path(source_id.clone()),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(state.named_runner.running_ids(), vec![source_id.clone()]);
+        state
+            .metrics
+            .source("named", &source_id, "tap-github")
+            .record_events(4);
+        let listed = list_connectors(State(Arc::clone(&state)), HeaderMap::new())
+            .await
+            .ok()
+            .unwrap();
+        let info = named(listed.0);
+        assert!(info.enabled);
+        assert_eq!(info.events_emitted, Some(4));
+
+        let result = post_pause_named_source(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            Path("ghost".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    fn make_file_request(directory: &str) -> FileSourceRequest {
+        serde_json::from_value(serde_json::json!({
+            "name": "Nightly inventory",
+            "directory": directory,
+            "entity_key_field": "sku",
+            "namespace": "warehouse"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_file_source_crud() {
+        let state = make_state();
+        let source_id = handle_create_file_source(&state, make_file_request("/data/drop"))
+            .await
+            .unwrap();
+
+        let config = state.file_runner.store.get(&source_id).unwrap().unwrap();
+        assert_eq!(config.directory, "/data/drop");
+        assert_eq!(config.pattern, "*");
+        assert_eq!(config.format, FileFormat::Csv);
+        assert_eq!(config.poll_interval_secs, 60);
+
+        let mut req = make_file_request("/data/other");
+        req.format = FileFormat::Jsonl;
+        req.column_mapping
+            .insert("Qty".to_string(), "quantity".to_string());
+        assert!(handle_update_file_source(&state, &source_id, req)
+            .await
+            .unwrap());
+        let updated = state.file_runner.store.get(&source_id).unwrap().unwrap();
+        assert_eq!(updated.directory, "/data/other");
+        assert_eq!(updated.format, FileFormat::Jsonl);
+        assert_eq!(updated.created_at, config.created_at);
+
+        let listed = handle_list_file_sources(&state).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].source_id, source_id);
+        assert_eq!(listed[0].column_mapping["Qty"], "quantity");
+
+        assert!(
+            !handle_update_file_source(&state, "ghost", make_file_request("/x"))
+                .await
+                .unwrap()
+        );
+
+        handle_delete_file_source(&state, &source_id).await.unwrap();
+        assert!(state.file_runner.store.get(&source_id).unwrap().is_none());
+        assert!(handle_list_file_sources(&state).unwrap().is_empty());
+    }
+
+    fn transform_rules(rules: serde_json::Value) -> TransformRules {
+        serde_json::from_value(serde_json::json!({ "rules": rules })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_source_transforms_replace_and_survive_update() {
+        let state = make_state();
+        let source_id = handle_create_file_source(&state, make_file_request("/data/drop"))
+            .await
+            .unwrap();
+        let rules = transform_rules(serde_json::json!([
+            {"type": "redact", "path": "customer.email", "mode": "hash"},
+            {"type": "add_static", "path": "site", "value": "eu-1"}
+        ]));
+
+        let status = replace_source_transforms(&state, TransformSource::File, &source_id, rules)
+            .ok()
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let stored = source_transforms(&state, TransformSource::File, &source_id)
+            .ok()
+            .unwrap();
+        assert_eq!(stored.rules.len(), 2);
+
+        // Replacing the rest of the config keeps the rules
+        assert!(
+            handle_update_file_source(&state, &source_id, make_file_request("/data/other"))
+                .await
+                .unwrap()
+        );
+        let config = state.file_runner.store.get(&source_id).unwrap().unwrap();
+        assert_eq!(config.transforms, stored.rules);
+
+        let invalid = transform_rules(serde_json::json!([
+            {"type": "rename", "from": "a", "to": "a"}
+        ]));
+        let result = replace_source_transforms(&state, TransformSource::File, &source_id, invalid);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        let result = replace_source_transforms(
+            &state,
+            TransformSource::Weather,
+            "ghost",
+            transform_rules(serde_json::json!([])),
+        );
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bento_source_rejects_transforms() {
+        let state = make_state();
+        let source_id = handle_create_generic_source(&state, make_request("Bitcoin Price"))
+            .await
+            .unwrap();
+        let rules = transform_rules(serde_json::json!([
+            {"type": "redact", "path": "usd"}
+        ]));
+        let result = replace_source_transforms(&state, TransformSource::Generic, &source_id, rules);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        let stored = state.config_store.get(&source_id).unwrap().unwrap();
+        assert!(stored.transforms.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_builtin_transforms_by_user() {
+        let state = Arc::new(make_state());
+        let path = Path(("alice".to_string(), "github".to_string()));
+        let rules = transform_rules(serde_json::json!([
+            {"type": "redact", "path": "author_email"}
+        ]));
+        let status = put_builtin_transforms(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            path,
+            Json(rules),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let store = &state.transform_store;
+        assert_eq!(store.get("alice", "github").unwrap().len(), 1);
+        assert!(store.get("bob", "github").unwrap().is_empty());
+
+        let result = get_builtin_transforms(
+            State(state),
+            HeaderMap::new(),
+            Path(("alice".to_string(), "nope".to_string())),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_builtin_config_by_user() {
+        let state = Arc::new(make_state());
+        let path = |connector: &str| Path(("alice".to_string(), connector.to_string()));
+        let body = |config: serde_json::Value| Json(ConnectorUserConfig { config });
 
-// ---------------------------------------------------------------------------
-// Error handling
-// ---------------------------------------------------------------------------
+        let config = serde_json::json!({"database_ids": ["d9824bdc84454327be8b5b47500af6ce"]});
+        let status = put_builtin_config(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            path("notion"),
+            body(config.clone()),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let Json(stored) =
+            get_builtin_config(State(Arc::clone(&state)), HeaderMap::new(), path("notion"))
+                .await
+                .ok()
+                .unwrap();
+        assert_eq!(stored.config, config);
 
-enum AppError {
-    Internal(String),
-}
+        // Rejected by the connector
+        for (connector, config) in [
+            ("notion", serde_json::json!({"database_ids": "not-a-list"})),
+            ("github", serde_json::json!({"repos": ["alice/x"]})),
+        ] {
+            let result = put_builtin_config(
+                State(Arc::clone(&state)),
+                HeaderMap::new(),
+                path(connector),
+                body(config),
+            )
+            .await;
+            assert!(
+                matches!(result, Err(AppError::BadRequest(_))),
+                "{}",
+                connector
+            );
+        }
+        let result = put_builtin_config(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            path("nope"),
+            body(serde_json::Value::Null),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(
+            state.user_config_store.get("alice", "notion").unwrap(),
+            config
+        );
+    }
 
-impl From<anyhow::Error> for AppError {
-    fn from(e: anyhow::Error) -> Self {
-        AppError::Internal(e.to_string())
+    /// A state with auth enabled: token `admin`, and namespaces `alpha` and
+    /// `beta` with tokens `tok-alpha` and `tok-beta`.
+    fn make_auth_state() -> ApiState {
+        let namespaces = NamespaceStore::new(":memory:").unwrap();
+        for name in ["alpha", "beta"] {
+            namespaces
+                .insert(&Namespace {
+                    id: format!("ns_{}", name),
+                    name: name.to_string(),
+                    token: format!("tok-{}", name),
+                    created_at: Utc::now(),
+                    entity_count: 0,
+                    quota: NamespaceQuota::default(),
+                })
+                .unwrap();
+        }
+        ApiState {
+            auth: Some(Arc::new(ApiAuth::new(
+                Some("admin".to_string()),
+                namespaces,
+            ))),
+            ..make_state()
+        }
     }
-}
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let AppError::Internal(msg) = self;
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: msg }),
-        )
-            .into_response()
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
     }
-}
 
-// ---------------------------------------------------------------------------
-// Router
-// ---------------------------------------------------------------------------
+    #[tokio::test]
+    async fn test_sources_scoped_to_owner_namespace() {
+        let state = Arc::new(make_auth_state());
+        let (_, Json(created)) = post_named_source(
+            State(Arc::clone(&state)),
+            bearer("tok-alpha"),
+            Json(make_named_request("tap-github")),
+        )
+        .await
+        .ok()
+        .unwrap();
+        let named_id = created.source_id;
+        let stored = state.named_runner.store.get(&named_id).unwrap().unwrap();
+        assert_eq!(stored.owner_namespace, "alpha");
+        let (_, Json(created)) = post_generic_source(
+            State(Arc::clone(&state)),
+            bearer("tok-alpha"),
+            Json(make_request("Bitcoin Price")),
+        )
+        .await
+        .ok()
+        .unwrap();
+        let generic_id = created.source_id;
 
-pub fn create_router(state: ApiState) -> Router {
-    Router::new()
-        .route("/api/connectors/named", post(post_named_source))
-        .route(
-            "/api/connectors/named/:source_id",
-            delete(delete_named_source),
+        // beta can't create sources for alpha, nor see or touch alpha's
+        let mut req = make_named_request("tap-github");
+        req.owner_namespace = Some("alpha".to_string());
+        let result =
+            post_named_source(State(Arc::clone(&state)), bearer("tok-beta"), Json(req)).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        let listed = list_connectors(State(Arc::clone(&state)), bearer("tok-beta"))
+            .await
+            .ok()
+            .unwrap();
+        assert!(listed.iter().all(|c| c.owner_namespace.is_none()));
+        let result = post_sync_named_source(
+            State(Arc::clone(&state)),
+            bearer("tok-beta"),
+            Path(named_id.clone()),
         )
-        .route(
-            "/api/connectors/named/:source_id/sync",
-            post(post_sync_named_source),
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = delete_named_source(
+            State(Arc::clone(&state)),
+            bearer("tok-beta"),
+            Path(named_id.clone()),
         )
-        .route("/api/connectors/generic", post(post_generic_source))
-        .route(
-            "/api/connectors/generic/:source_id",
-            delete(delete_generic_source),
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = delete_generic_source(
+            State(Arc::clone(&state)),
+            bearer("tok-beta"),
+            Path(generic_id.clone()),
         )
-        .route("/api/connectors", get(list_connectors))
-        .route("/api/connectors/taps", get(get_tap_catalog))
-        .with_state(Arc::new(state))
-}
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert!(state.named_runner.store.get(&named_id).unwrap().is_some());
+        assert!(state.config_store.get(&generic_id).unwrap().is_some());
+        let result = get_builtin_transforms(
+            State(Arc::clone(&state)),
+            bearer("tok-beta"),
+            Path(("alpha".to_string(), "github".to_string())),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+        // The admin sees both, with their owner
+        let listed = list_connectors(State(Arc::clone(&state)), bearer("admin"))
+            .await
+            .ok()
+            .unwrap();
+        let owners: Vec<_> = listed
+            .iter()
+            .filter_map(|c| c.owner_namespace.as_deref())
+            .collect();
+        assert_eq!(owners, vec!["alpha", "alpha"]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::named_config::NamedConfigStore;
+        let result = list_connectors(State(Arc::clone(&state)), HeaderMap::new()).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
 
-    fn make_state() -> ApiState {
-        let config_store = Arc::new(GenericConfigStore::new(":memory:").unwrap());
-        let named_store = Arc::new(NamedConfigStore::new(":memory:").unwrap());
-        let credential_store = Arc::new(
-            CredentialStore::new(":memory:", &base64::encode([0u8; 32])).unwrap(),
-        );
-        let runner = Arc::new(GenericRunner::new(
-            Arc::clone(&config_store),
-            "http://localhost:3000".to_string(),
-        ));
-        let named_runner = Arc::new(NamedRunner::new(
-            Arc::clone(&named_store),
-            "http://localhost:3000".to_string(),
-        ));
-        let tap_catalog = Arc::new(TapCatalogStore::new("/nonexistent/test-catalog.json"));
-        ApiState {
-            config_store,
-            runner,
-            credential_store,
-            tap_catalog,
-            named_runner,
-        }
+        // alpha still owns them
+        let status = delete_named_source(State(state), bearer("tok-alpha"), Path(named_id))
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
     }
 
-    fn make_request(name: &str) -> CreateGenericSourceRequest {
-        CreateGenericSourceRequest {
-            name: name.to_string(),
-            url: "https://api.coingecko.com/api/v3/simple/price".to_string(),
-            poll_interval_secs: 300,
-            entity_key: "bitcoin".to_string(),
-            namespace: "personal".to_string(),
-            auth_type: AuthTypeInput::Plain("none".to_string()),
-            token: None,
-            flux_namespace_token: None,
-        }
+    fn make_postgres_request(connection_string: &str) -> CreatePostgresSourceRequest {
+        serde_json::from_value(serde_json::json!({
+            "name": "Shop orders",
+            "connection_string": connection_string,
+            "password": "secret",
+            "table": "public.orders",
+            "key_column": "id",
+            "updated_at_column": "updated_at",
+            "namespace": "shop"
+        }))
+        .unwrap()
     }
 
-    fn make_named_request(tap: &str) -> CreateNamedSourceRequest {
-        CreateNamedSourceRequest {
-            tap_name: tap.to_string(),
-            namespace: "personal".to_string(),
-            entity_key_field: "id".to_string(),
-            config_json: r#"{"access_token": "ghp_test"}"#.to_string(),
-            poll_interval_secs: 3600,
-            flux_namespace_token: None,
-        }
+    #[test]
+    fn test_validate_postgres_request() {
+        assert!(validate_postgres_request(&make_postgres_request("host=db user=flux")).is_ok());
+        assert!(
+            validate_postgres_request(&make_postgres_request("postgresql://flux@db/shop")).is_ok()
+        );
+
+        let err =
+            validate_postgres_request(&make_postgres_request("postgresql://flux:hunter2@db/shop"))
+                .unwrap_err();
+        assert!(err.contains("must not contain a password"));
+        assert!(!err.contains("hunter2"));
+
+        let err =
+            validate_postgres_request(&make_postgres_request("host=db port=nope")).unwrap_err();
+        assert!(err.starts_with("invalid connection_string"));
+
+        let mut req = make_postgres_request("host=db");
+        req.updated_at_column = " ".to_string();
+        assert_eq!(
+            validate_postgres_request(&req).unwrap_err(),
+            "updated_at_column must not be empty"
+        );
     }
 
     #[tokio::test]
-    async fn test_post_named_source_stores_config() {
+    async fn test_postgres_source_create_and_delete() {
         let state = make_state();
-        let source_id = handle_create_named_source(&state, make_named_request("tap-github"))
-            .await
+        let source_id =
+            handle_create_postgres_source(&state, make_postgres_request("host=127.0.0.1 port=1"))
+                .await
+                .unwrap();
+
+        let config = state
+            .postgres_runner
+            .store
+            .get(&source_id)
+            .unwrap()
             .unwrap();
+        assert_eq!(config.table, "public.orders");
+        assert_eq!(config.batch_size, 500);
+        assert_eq!(config.poll_interval_secs, 60);
+        let creds = state
+            .credential_store
+            .get("postgres", &source_id)
+            .unwrap()
+            .expect("password should be stored");
+        assert_eq!(creds.access_token, "secret");
 
-        let stored = state.named_runner.store.get(&source_id).unwrap();
-        assert!(stored.is_some(), "config should be stored after POST");
-        let config = stored.unwrap();
-        assert_eq!(config.tap_name, "tap-github");
-        assert_eq!(config.namespace, "personal");
-        assert_eq!(config.entity_key_field, "id");
-        assert_eq!(config.poll_interval_secs, 3600);
+        handle_delete_postgres_source(&state, &source_id)
+            .await
+            .unwrap();
+        assert!(state
+            .postgres_runner
+            .store
+            .get(&source_id)
+            .unwrap()
+            .is_none());
+        assert!(state
+            .credential_store
+            .get("postgres", &source_id)
+            .unwrap()
+            .is_none());
     }
 
     #[tokio::test]
-    async fn test_delete_named_source_removes_config() {
+    async fn test_weather_source_create_list_delete() {
         let state = make_state();
-        let source_id = handle_create_named_source(&state, make_named_request("tap-github"))
+        let req: WeatherSourceRequest = serde_json::from_value(serde_json::json!({
+            "name": "Demo",
+            "locations": [{"name": "home", "latitude": 52.52, "longitude": 13.41}],
+            "namespace": "matt"
+        }))
+        .unwrap();
+        assert_eq!(req.poll_interval_secs, 900);
+        let source_id = handle_create_weather_source(&state, req).await.unwrap();
+
+        let listed = handle_list_weather_sources(&state).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].source_id, source_id);
+        assert_eq!(listed[0].locations[0].name, "home");
+        assert_eq!(listed[0].namespace, "matt");
+        assert!(listed[0].alerting_locations.is_empty());
+
+        handle_delete_weather_source(&state, &source_id)
             .await
             .unwrap();
-        assert!(
-            state.named_runner.store.get(&source_id).unwrap().is_some(),
-            "config should exist before delete"
-        );
+        assert!(handle_list_weather_sources(&state).unwrap().is_empty());
+    }
 
-        handle_delete_named_source(&state, &source_id).await.unwrap();
+    #[tokio::test]
+    async fn test_follower_persists_without_starting() {
+        use crate::leader::SqliteLeaseStore;
+        use flux::leader::LeaderElector;
 
-        let stored = state.named_runner.store.get(&source_id).unwrap();
-        assert!(stored.is_none(), "config should be removed after DELETE");
+        let store = Arc::new(SqliteLeaseStore::new(":memory:", "connector-manager").unwrap());
+        let ttl = std::time::Duration::from_secs(15);
+        let (mut other, _) = LeaderElector::new(Arc::clone(&store), "other".to_string(), ttl);
+        let (mut this, leadership) = LeaderElector::new(store, "test".to_string(), ttl);
+        assert!(other.tick(Utc::now()).await);
+        assert!(!this.tick(Utc::now()).await);
+
+        let state = ApiState {
+            leadership,
+            ..make_state()
+        };
+        let req: WeatherSourceRequest = serde_json::from_value(serde_json::json!({
+            "name": "Demo",
+            "locations": [{"name": "home", "latitude": 52.52, "longitude": 13.41}],
+            "namespace": "matt"
+        }))
+        .unwrap();
+        let source_id = handle_create_weather_source(&state, req).await.unwrap();
+
+        let stored = state.weather_runner.store.get(&source_id).unwrap();
+        assert!(stored.is_some(), "follower should still persist the config");
+        assert!(state.weather_runner.running_ids().is_empty());
     }
 
     #[tokio::test]
@@ -552,4 +3597,481 @@ mod tests {
         let stored = state.config_store.get(&source_id).unwrap();
         assert!(stored.is_none(), "config should be removed after DELETE");
     }
+
+    #[test]
+    fn test_create_generic_request_defaults_to_native() {
+        let req: CreateGenericSourceRequest = serde_json::from_value(serde_json::json!({
+            "name": "Weather API",
+            "url": "https://api.example.com/weather",
+            "poll_interval_secs": 300,
+            "entity_key": "station",
+            "namespace": "personal",
+            "auth_type": "none"
+        }))
+        .unwrap();
+        assert_eq!(req.engine, SourceEngine::Native);
+    }
+
+    #[tokio::test]
+    async fn test_post_generic_source_stores_engine() {
+        let state = make_state();
+        let source_id = handle_create_generic_source(&state, make_request("Legacy"))
+            .await
+            .unwrap();
+
+        let config = state.config_store.get(&source_id).unwrap().unwrap();
+        assert_eq!(config.engine, SourceEngine::Bento);
+    }
+
+    #[tokio::test]
+    async fn test_generic_source_secret_params_kept_out_of_config() {
+        let state = make_state();
+        let param = |name: &str, value: &str, secret| RequestParamInput {
+            name: name.to_string(),
+            value: value.to_string(),
+            secret,
+        };
+        let mut req = make_request("Custom");
+        req.headers = vec![param("X-Api-Version", "2", false)];
+        req.query_params = vec![param("appid", "hunter2", true)];
+        req.proxy = Some(ProxyInput {
+            url: "http://proxy.internal:3128".to_string(),
+            username: Some("flux".to_string()),
+            password: Some("swordfish".to_string()),
+            no_proxy: None,
+        });
+        req.validate().unwrap();
+        let source_id = handle_create_generic_source(&state, req).await.unwrap();
+
+        let config = state.config_store.get(&source_id).unwrap().unwrap();
+        assert_eq!(
+            config.headers,
+            vec![("X-Api-Version".to_string(), ParamValue::Plain("2".into()))]
+        );
+        assert_eq!(
+            config.query_params,
+            vec![(
+                "appid".to_string(),
+                ParamValue::SecretRef { secret_ref: true }
+            )]
+        );
+        assert_eq!(
+            config.proxy.as_ref().unwrap().password,
+            Some(ParamValue::SecretRef { secret_ref: true })
+        );
+        let key = secret_key(&source_id, ParamKind::Query, "appid");
+        let stored = state
+            .credential_store
+            .get("generic", &key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.access_token, "hunter2");
+        let proxy_key = secret_key(&source_id, ParamKind::Proxy, "password");
+        let stored = state.credential_store.get("generic", &proxy_key).unwrap();
+        assert_eq!(stored.unwrap().access_token, "swordfish");
+
+        handle_delete_generic_source(&state, &source_id)
+            .await
+            .unwrap();
+        assert!(state
+            .credential_store
+            .get("generic", &key)
+            .unwrap()
+            .is_none());
+        let stored = state.credential_store.get("generic", &proxy_key).unwrap();
+        assert!(stored.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generic_source_bento_overrides_rendered_redacted() {
+        let state = make_state();
+        let mut req = make_request("Paged");
+        req.bento_overrides = Some(
+            serde_json::from_value(serde_json::json!({
+                "http_client": {
+                    "basic_auth": {"enabled": true, "username": "flux", "password": "swordfish"},
+                    "retries": 3
+                },
+                "processors": [{"unarchive": {"format": "json_array"}}]
+            }))
+            .unwrap(),
+        );
+        req.validate().unwrap();
+        let source_id = handle_create_generic_source(&state, req).await.unwrap();
+
+        let config = state.config_store.get(&source_id).unwrap().unwrap();
+        let rendered = rendered_generic_config(&state, &config);
+        assert!(rendered.contains("    retries: 3\n"));
+        assert!(rendered.contains("    - unarchive:\n        format: \"json_array\"\n"));
+        assert!(rendered.contains("      password: \"[REDACTED]\"\n"));
+        assert!(!rendered.contains("swordfish"));
+        // Only the shown config is redacted
+        let overrides = config.bento_overrides.unwrap();
+        assert_eq!(overrides.http_client["basic_auth"]["password"], "swordfish");
+    }
+
+    #[test]
+    fn test_create_generic_request_checks_bento_overrides() {
+        let request = |engine: &str, overrides: serde_json::Value| {
+            serde_json::from_value::<CreateGenericSourceRequest>(serde_json::json!({
+                "name": "Paged API",
+                "url": "https://api.example.com/items",
+                "poll_interval_secs": 60,
+                "entity_key": "items",
+                "namespace": "personal",
+                "auth_type": "none",
+                "engine": engine,
+                "bento_overrides": overrides
+            }))
+            .unwrap()
+        };
+        let split = serde_json::json!({"processors": [{"unarchive": {"format": "json_array"}}]});
+        request("bento", split.clone()).validate().unwrap();
+        let err = request("native", split).validate().unwrap_err();
+        assert!(err.to_string().contains("bento engine"));
+        let reserved = serde_json::json!({"http_client": {"verb": "POST"}});
+        assert!(request("bento", reserved).validate().is_err());
+    }
+
+    #[test]
+    fn test_create_generic_request_rejects_auth_override() {
+        let req: CreateGenericSourceRequest = serde_json::from_value(serde_json::json!({
+            "name": "Weather API",
+            "url": "https://api.example.com/weather",
+            "poll_interval_secs": 300,
+            "entity_key": "station",
+            "namespace": "personal",
+            "auth_type": "bearer",
+            "token": "secret",
+            "headers": [{"name": "Authorization", "value": "Basic abc"}]
+        }))
+        .unwrap();
+        assert!(req.validate().is_err());
+    }
+
+    // --- OpenAPI ---
+
+    fn schema_example<T: serde::de::DeserializeOwned>(spec: &serde_json::Value, name: &str) -> T {
+        let schema = &spec["components"]["schemas"][name];
+        let example = schema["example"].clone();
+        let props = schema["properties"].as_object().expect("object schema");
+        for key in example.as_object().expect("object example").keys() {
+            assert!(props.contains_key(key), "{}: `{}` not in schema", name, key);
+        }
+        serde_json::from_value(example)
+            .unwrap_or_else(|e| panic!("{} example does not deserialize: {}", name, e))
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_registry_reload_lists_external_connectors() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exec = dir.path().join("echo.sh");
+        std::fs::write(&exec, include_str!("../tests/fixtures/external/echo.sh")).unwrap();
+        std::fs::set_permissions(&exec, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let registry =
+            ConnectorRegistry::with_external(dir.path().to_path_buf(), dir.path().to_path_buf());
+        let state = Arc::new(ApiState {
+            registry: Arc::new(registry),
+            ..make_state()
+        });
+
+        // Manifest added while running
+        let manifest = serde_json::json!({
+            "name": "echo",
+            "exec": "echo.sh",
+            "poll_interval": 60,
+            "oauth": {"auth_url": "https://a", "token_url": "https://t", "scopes": []}
+        });
+        std::fs::write(dir.path().join("echo.json"), manifest.to_string()).unwrap();
+        let report = post_registry_reload(State(Arc::clone(&state)))
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(report.loaded, vec!["echo"]);
+
+        let connectors = list_connectors(State(state), HeaderMap::new())
+            .await
+            .ok()
+            .unwrap();
+        let types: Vec<(&str, &str)> = connectors
+            .iter()
+            .map(|c| (c.name.as_str(), c.connector_type.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("github", "builtin"),
+                ("notion", "builtin"),
+                ("jira", "builtin"),
+                ("echo", "external")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_named_source_rejects_zero_event_cap() {
+        let mut req = make_named_request("tap-github");
+        req.max_events_per_run = 0;
+        let result =
+            post_named_source(State(Arc::new(make_state())), HeaderMap::new(), Json(req)).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sources_reject_invalid_retry_policy() {
+        let state = Arc::new(make_state());
+        let policy = RetryPolicy {
+            backoff_base_secs: 0,
+            ..RetryPolicy::default()
+        };
+        let mut req = make_request("Bitcoin Price");
+        req.retry_policy = Some(policy);
+        let result =
+            post_generic_source(State(Arc::clone(&state)), HeaderMap::new(), Json(req)).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let mut req = make_named_request("tap-github");
+        req.retry_policy = Some(policy);
+        let result =
+            post_named_source(State(Arc::clone(&state)), HeaderMap::new(), Json(req)).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_source_idempotency_key() {
+        let state = Arc::new(make_state());
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", "create-github-1".parse().unwrap());
+
+        let (status, Json(first)) = post_named_source(
+            State(Arc::clone(&state)),
+            headers.clone(),
+            Json(make_named_request("tap-github")),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, Json(retry)) = post_named_source(
+            State(Arc::clone(&state)),
+            headers.clone(),
+            Json(make_named_request("tap-github")),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(retry.source_id, first.source_id);
+
+        // Same key, different body
+        let mut req = make_named_request("tap-github");
+        req.poll_interval_secs = 60;
+        let result = post_named_source(State(Arc::clone(&state)), headers.clone(), Json(req)).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+        assert_eq!(state.named_runner.store.list().unwrap().len(), 1);
+
+        // Keys are per endpoint
+        let (status, _) = post_generic_source(
+            State(Arc::clone(&state)),
+            headers,
+            Json(make_request("Bitcoin Price")),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_retry_unknown_source_not_found() {
+        let state = Arc::new(make_state());
+        let result = post_retry_generic_source(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            Path("missing".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = post_retry_named_source(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            Path("missing".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_runs_and_limiter_are_reported() {
+        let mut state = make_state();
+        state.limiter = Arc::new(PublishLimiter::new(200));
+        let status = ConnectorStatus {
+            last_run_dropped_events: 3,
+            ..Default::default()
+        };
+        state.builtin_status.lock().await.insert(
+            "alice:github".to_string(),
+            Arc::new(tokio::sync::Mutex::new(status)),
+        );
+        let state = Arc::new(state);
+
+        let connectors = list_connectors(State(Arc::clone(&state)), HeaderMap::new())
+            .await
+            .ok()
+            .unwrap();
+        let github = connectors.iter().find(|c| c.name == "github").unwrap();
+        assert_eq!(github.status, "partial");
+        assert_eq!(github.dropped_events, Some(3));
+
+        let limiter = get_rate_limit(State(state)).await;
+        assert_eq!(limiter.events_per_second, Some(200));
+        assert_eq!(limiter.utilization, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_renders_registry() {
+        let state = make_state();
+        state
+            .metrics
+            .source("generic", "src-1", "native")
+            .record_events(2);
+
+        let response = get_metrics(State(Arc::new(state))).await.into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            metrics::CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains(
+            r#"flux_connector_events_emitted_total{runner="generic",source_id="src-1",connector="native"} 2"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_registry_reload_requires_directory() {
+        let result = post_registry_reload(State(Arc::new(make_state()))).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_openapi_examples_match_serde() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let req: CreateGenericSourceRequest = schema_example(&spec, "CreateGenericSourceRequest");
+        assert!(matches!(
+            AuthType::from(req.auth_type),
+            AuthType::ApiKeyHeader { ref header_name } if header_name == "X-API-Key"
+        ));
+        let _: CreateGenericSourceResponse = schema_example(&spec, "CreateGenericSourceResponse");
+        let req: CreateNamedSourceRequest = schema_example(&spec, "CreateNamedSourceRequest");
+        assert_eq!(req.tap_name, "tap-github");
+        assert!(req.coerce_types);
+        assert_eq!(req.max_events_per_run, 10_000);
+        let _: CreateNamedSourceResponse = schema_example(&spec, "CreateNamedSourceResponse");
+        let info: ConnectorInfo = schema_example(&spec, "ConnectorInfo");
+        assert_eq!(info.connector_type, "generic");
+        let _: TapCatalogEntry = schema_example(&spec, "TapCatalogEntry");
+        let req: FileSourceRequest = schema_example(&spec, "FileSourceRequest");
+        assert_eq!(req.format, FileFormat::Csv);
+        let _: CreateFileSourceResponse = schema_example(&spec, "CreateFileSourceResponse");
+        let info: FileSourceInfo = schema_example(&spec, "FileSourceInfo");
+        assert_eq!(info.rows_failed, 1);
+        let req: CreatePostgresSourceRequest = schema_example(&spec, "CreatePostgresSourceRequest");
+        assert!(validate_postgres_request(&req).is_ok());
+        let _: CreatePostgresSourceResponse = schema_example(&spec, "CreatePostgresSourceResponse");
+        let req: WeatherSourceRequest = schema_example(&spec, "WeatherSourceRequest");
+        validate_weather_source(&req.namespace, &req.locations, req.poll_interval_secs).unwrap();
+        let _: CreateWeatherSourceResponse = schema_example(&spec, "CreateWeatherSourceResponse");
+        let info: WeatherSourceInfo = schema_example(&spec, "WeatherSourceInfo");
+        assert_eq!(info.alerting_locations, vec!["home"]);
+        let status: LeaderStatus = schema_example(&spec, "LeaderStatus");
+        assert!(status.is_leader);
+        let report: ReloadReport = schema_example(&spec, "ReloadReport");
+        assert_eq!(report.errors.len(), 1);
+        let limiter: LimiterStatus = schema_example(&spec, "LimiterStatus");
+        assert_eq!(limiter.events_per_second, Some(500));
+        let body: TransformRules = schema_example(&spec, "TransformRules");
+        transform::validate_rules(&body.rules).unwrap();
+        let body: ConnectorUserConfig = schema_example(&spec, "ConnectorUserConfig");
+        NotionConnector::new()
+            .validate_user_config(&body.config)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_openapi_auth_type_is_untagged() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let variants = spec["components"]["schemas"]["AuthTypeInput"]["oneOf"]
+            .as_array()
+            .expect("untagged enum renders as oneOf");
+
+        // Plain string form ("none" / "bearer") and object form
+        assert!(variants.iter().any(|v| v["type"] == "string"));
+        assert!(variants
+            .iter()
+            .any(|v| v["properties"].get("api_key_header").is_some()));
+        // No externally-tagged wrapper keys
+        assert!(variants
+            .iter()
+            .all(|v| v["properties"].get("Plain").is_none()
+                && v["properties"].get("ApiKey").is_none()));
+    }
+
+    #[test]
+    fn test_openapi_covers_all_routes() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for (path, method) in [
+            ("/api/connectors/named", "post"),
+            ("/api/connectors/named/{source_id}", "delete"),
+            ("/api/connectors/named/{source_id}/sync", "post"),
+            ("/api/connectors/generic", "post"),
+            ("/api/connectors/generic/{source_id}", "delete"),
+            ("/api/connectors/files", "post"),
+            ("/api/connectors/files", "get"),
+            ("/api/connectors/files/{source_id}", "get"),
+            ("/api/connectors/files/{source_id}", "put"),
+            ("/api/connectors/files/{source_id}", "delete"),
+            ("/api/connectors/postgres", "post"),
+            ("/api/connectors/postgres/{source_id}", "delete"),
+            ("/api/connectors/weather", "post"),
+            ("/api/connectors/weather", "get"),
+            ("/api/connectors/weather/{source_id}", "delete"),
+            ("/api/connectors/generic/{source_id}/transforms", "put"),
+            ("/api/connectors/named/{source_id}/transforms", "put"),
+            ("/api/connectors/files/{source_id}/transforms", "get"),
+            ("/api/connectors/files/{source_id}/transforms", "put"),
+            ("/api/connectors/postgres/{source_id}/transforms", "put"),
+            ("/api/connectors/weather/{source_id}/transforms", "put"),
+            (
+                "/api/connectors/builtin/{user_id}/{connector}/transforms",
+                "put",
+            ),
+            (
+                "/api/connectors/builtin/{user_id}/{connector}/config",
+                "get",
+            ),
+            (
+                "/api/connectors/builtin/{user_id}/{connector}/config",
+                "put",
+            ),
+            ("/api/connectors", "get"),
+            ("/api/connectors/taps", "get"),
+            ("/api/leader", "get"),
+            ("/api/connectors/rate-limit", "get"),
+            ("/api/connectors/registry/reload", "post"),
+        ] {
+            assert!(
+                spec["paths"][path].get(method).is_some(),
+                "missing {} {}",
+                method,
+                path
+            );
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//! Who is calling the connector API when Flux auth is enabled.
+//!
+//! Callers authenticate with the same bearer tokens Flux accepts: the admin
+//! token, or a namespace token looked up in Flux's namespace database. A
+//! namespace caller only sees and changes the generic and named sources
+//! that namespace owns.
+
+use anyhow::Result;
+use axum::http::HeaderMap;
+use flux::auth::extract_bearer_token;
+use flux::namespace::NamespaceStore;
+
+/// An authenticated connector API caller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Caller {
+    /// Sees and changes every source. All callers are admins with auth off.
+    Admin,
+    /// Sees and changes only the sources this namespace owns.
+    Namespace(String),
+}
+
+impl Caller {
+    /// Whether the caller may see or change a source owned by `owner`.
+    pub fn owns(&self, owner: &str) -> bool {
+        match self {
+            Caller::Admin => true,
+            Caller::Namespace(ns) => ns == owner,
+        }
+    }
+}
+
+/// Resolves bearer tokens to callers.
+pub struct ApiAuth {
+    admin_token: Option<String>,
+    namespaces: NamespaceStore,
+}
+
+impl ApiAuth {
+    /// `namespaces` is Flux's namespace store (`FLUX_NAMESPACE_DB`); without
+    /// an admin token no caller is an admin.
+    pub fn new(admin_token: Option<String>, namespaces: NamespaceStore) -> Self {
+        Self {
+            admin_token,
+            namespaces,
+        }
+    }
+
+    /// The caller presenting `headers`' bearer token, or `None` if it is
+    /// missing or not a known token.
+    pub fn caller(&self, headers: &HeaderMap) -> Result<Option<Caller>> {
+        let Ok(token) = extract_bearer_token(headers) else {
+            return Ok(None);
+        };
+        if self.admin_token.as_deref() == Some(token.as_str()) {
+            return Ok(Some(Caller::Admin));
+        }
+        Ok(self
+            .namespaces
+            .name_for_token(&token)?
+            .map(Caller::Namespace))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use flux::namespace::Namespace;
+    use flux::state::NamespaceQuota;
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_caller_from_token() {
+        let store = NamespaceStore::new(":memory:").unwrap();
+        store
+            .insert(&Namespace {
+                id: "ns_aaaaaaaa".to_string(),
+                name: "alpha".to_string(),
+                token: "tok-alpha".to_string(),
+                created_at: Utc::now(),
+                entity_count: 0,
+                quota: NamespaceQuota::default(),
+            })
+            .unwrap();
+        let auth = ApiAuth::new(Some("admin".to_string()), store);
+
+        assert_eq!(auth.caller(&headers("admin")).unwrap(), Some(Caller::Admin));
+        assert_eq!(
+            auth.caller(&headers("tok-alpha")).unwrap(),
+            Some(Caller::Namespace("alpha".to_string()))
+        );
+        assert_eq!(auth.caller(&headers("tok-beta")).unwrap(), None);
+        assert_eq!(auth.caller(&HeaderMap::new()).unwrap(), None);
+
+        let alpha = Caller::Namespace("alpha".to_string());
+        assert!(alpha.owns("alpha"));
+        assert!(!alpha.owns("beta"));
+        assert!(Caller::Admin.owns("beta"));
+    }
+}
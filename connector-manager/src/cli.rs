@@ -0,0 +1,494 @@
+//! `connector-manager` subcommands for administering a running instance.
+//!
+//! Each subcommand is one or two requests to the connector API, printed as a
+//! table (or as JSON with `--json`). Requests carry the caller's bearer
+//! token, so with Flux auth enabled a namespace token only sees its own
+//! sources.
+
+use crate::api::{ConnectorInfo, CreateGenericSourceResponse};
+use crate::runners::named::TapCatalogEntry;
+use crate::runners::rate_limit::LimiterStatus;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Subcommand;
+use flux::leader::LeaderStatus;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List, create, delete and sync sources
+    #[command(subcommand)]
+    Sources(SourcesCommand),
+    /// Browse the Meltano Hub tap catalog
+    #[command(subcommand)]
+    Taps(TapsCommand),
+    /// Leader election, publish limiter and source counts by status
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum SourcesCommand {
+    /// Every source visible to the token, with its status
+    List,
+    /// Create a generic source from a JSON request body
+    CreateGeneric {
+        /// `POST /api/connectors/generic` body
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Delete a generic, named, file, postgres or weather source
+    Delete { source_id: String },
+    /// Run a named source now
+    Sync { source_id: String },
+}
+
+#[derive(Subcommand)]
+pub enum TapsCommand {
+    /// Taps whose name, label or description contains the query
+    Search { query: String },
+}
+
+/// Leader, limiter and source counts reported by `status`
+#[derive(Serialize)]
+pub struct Status {
+    pub leader: LeaderStatus,
+    pub rate_limit: LimiterStatus,
+    /// Sources per status (`running`, `error`, ...)
+    pub sources: BTreeMap<String, usize>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Connector API client used by the subcommands
+pub struct ApiClient {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client: crate::http::client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sends `request`, turning error responses into their `error` message
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach connector API at {}", self.base_url))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        match response.json::<ErrorResponse>().await {
+            Ok(body) => bail!("{}: {}", status, body.error),
+            Err(_) => bail!("{}", status),
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.send(self.request(Method::GET, path)).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn list_sources(&self) -> Result<Vec<ConnectorInfo>> {
+        self.get("/api/connectors").await
+    }
+
+    pub async fn create_generic(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<CreateGenericSourceResponse> {
+        let request = self
+            .request(Method::POST, "/api/connectors/generic")
+            .json(body);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    /// Deletes the source through its type's route; returns the type
+    pub async fn delete_source(&self, source_id: &str) -> Result<String> {
+        let source = self.find_source(source_id).await?;
+        let route = match source.connector_type.as_str() {
+            "generic" => "generic",
+            "named" => "named",
+            "file" => "files",
+            "postgres" => "postgres",
+            "weather" => "weather",
+            other => bail!("{} connectors can't be deleted through the API", other),
+        };
+        let path = format!("/api/connectors/{}/{}", route, source_id);
+        self.send(self.request(Method::DELETE, &path)).await?;
+        Ok(source.connector_type)
+    }
+
+    pub async fn sync_source(&self, source_id: &str) -> Result<()> {
+        let source = self.find_source(source_id).await?;
+        if source.connector_type != "named" {
+            bail!(
+                "only named sources can be synced ({} is {})",
+                source_id,
+                source.connector_type
+            );
+        }
+        let path = format!("/api/connectors/named/{}/sync", source_id);
+        self.send(self.request(Method::POST, &path)).await?;
+        Ok(())
+    }
+
+    pub async fn search_taps(&self, query: &str) -> Result<Vec<TapCatalogEntry>> {
+        let taps: Vec<TapCatalogEntry> = self.get("/api/connectors/taps").await?;
+        let query = query.to_lowercase();
+        Ok(taps
+            .into_iter()
+            .filter(|tap| {
+                [&tap.name, &tap.label, &tap.description]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&query))
+            })
+            .collect())
+    }
+
+    pub async fn status(&self) -> Result<Status> {
+        let mut sources = BTreeMap::new();
+        for source in self.list_sources().await? {
+            *sources.entry(source.status).or_insert(0) += 1;
+        }
+        Ok(Status {
+            leader: self.get("/api/leader").await?,
+            rate_limit: self.get("/api/connectors/rate-limit").await?,
+            sources,
+        })
+    }
+
+    async fn find_source(&self, source_id: &str) -> Result<ConnectorInfo> {
+        self.list_sources()
+            .await?
+            .into_iter()
+            .find(|c| c.source_id.as_deref() == Some(source_id))
+            .ok_or_else(|| anyhow!("no source {}", source_id))
+    }
+}
+
+/// Runs `command` against `client`, returning what to print
+pub async fn run(client: &ApiClient, command: Command, json: bool) -> Result<String> {
+    match command {
+        Command::Sources(SourcesCommand::List) => {
+            render(json, &client.list_sources().await?[..], sources_table)
+        }
+        Command::Sources(SourcesCommand::CreateGeneric { file }) => {
+            let body = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let body: serde_json::Value = serde_json::from_str(&body)
+                .with_context(|| format!("{} is not valid JSON", file.display()))?;
+            let created = client.create_generic(&body).await?;
+            render(json, &created, |c| format!("Created {}", c.source_id))
+        }
+        Command::Sources(SourcesCommand::Delete { source_id }) => {
+            let connector_type = client.delete_source(&source_id).await?;
+            let deleted = serde_json::json!({"source_id": source_id, "type": connector_type});
+            render(json, &deleted, |_| {
+                format!("Deleted {} source {}", connector_type, source_id)
+            })
+        }
+        Command::Sources(SourcesCommand::Sync { source_id }) => {
+            client.sync_source(&source_id).await?;
+            let started = serde_json::json!({"source_id": source_id, "sync": "started"});
+            render(json, &started, |_| format!("Sync of {} started", source_id))
+        }
+        Command::Taps(TapsCommand::Search { query }) => {
+            render(json, &client.search_taps(&query).await?[..], taps_table)
+        }
+        Command::Status => render(json, &client.status().await?, status_text),
+    }
+}
+
+fn render<T: Serialize + ?Sized>(
+    json: bool,
+    value: &T,
+    text: impl FnOnce(&T) -> String,
+) -> Result<String> {
+    if json {
+        Ok(serde_json::to_string_pretty(value)?)
+    } else {
+        Ok(text(value))
+    }
+}
+
+/// Columns padded to their widest cell
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let headers = headers.iter().map(|h| h.to_string()).collect();
+    std::iter::once(headers)
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn sources_table(sources: &[ConnectorInfo]) -> String {
+    let rows = sources
+        .iter()
+        .map(|c| {
+            vec![
+                c.source_id.clone().unwrap_or_else(|| "-".to_string()),
+                c.connector_type.clone(),
+                c.name.clone(),
+                c.status.clone(),
+                c.owner_namespace.clone().unwrap_or_else(|| "-".to_string()),
+                c.last_error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    table(
+        &["ID", "TYPE", "NAME", "STATUS", "OWNER", "LAST ERROR"],
+        rows,
+    )
+}
+
+pub fn taps_table(taps: &[TapCatalogEntry]) -> String {
+    let rows = taps
+        .iter()
+        .map(|t| vec![t.name.clone(), t.label.clone(), t.description.clone()])
+        .collect();
+    table(&["NAME", "LABEL", "DESCRIPTION"], rows)
+}
+
+pub fn status_text(status: &Status) -> String {
+    let leader = match (&status.leader.leader, status.leader.is_leader) {
+        (_, true) => "this instance".to_string(),
+        (Some(leader), false) => leader.clone(),
+        (None, false) => "unknown".to_string(),
+    };
+    let limit = match status.rate_limit.events_per_second {
+        Some(limit) => format!(
+            "{} events/s, {:.0}% used, {} queued",
+            limit,
+            status.rate_limit.utilization * 100.0,
+            status.rate_limit.queued_events
+        ),
+        None => "unlimited".to_string(),
+    };
+    let mut lines = vec![
+        format!("Instance:     {}", status.leader.instance_id),
+        format!("Leader:       {}", leader),
+        format!("Publish rate: {}", limit),
+        "Sources:".to_string(),
+    ];
+    if status.sources.is_empty() {
+        lines.push("  none".to_string());
+    }
+    for (state, count) in &status.sources {
+        lines.push(format!("  {:<10} {}", state, count));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    fn sources() -> String {
+        json!([
+            {"name": "github", "type": "builtin", "enabled": true, "status": "running"},
+            {"name": "Weather API", "type": "generic", "enabled": true, "status": "running",
+             "source_id": "gen-1", "owner_namespace": "personal"},
+            {"name": "Drops", "type": "file", "enabled": true, "status": "error",
+             "source_id": "file-1", "last_error": "bad header"},
+            {"name": "tap-github", "type": "named", "enabled": true, "status": "running",
+             "source_id": "named-1"}
+        ])
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_list_sources_table() {
+        let mut server = mockito::Server::new_async().await;
+        let list = server
+            .mock("GET", "/api/connectors")
+            .match_header("authorization", "Bearer tok")
+            .with_body(sources())
+            .create_async()
+            .await;
+        let client = ApiClient::new(&server.url(), Some("tok".to_string())).unwrap();
+
+        let out = run(&client, Command::Sources(SourcesCommand::List), false)
+            .await
+            .unwrap();
+        list.assert_async().await;
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "ID       TYPE     NAME         STATUS   OWNER     LAST ERROR"
+        );
+        assert_eq!(lines[1], "-        builtin  github       running  -");
+        assert_eq!(
+            lines[3],
+            "file-1   file     Drops        error    -         bad header"
+        );
+
+        let out = run(&client, Command::Sources(SourcesCommand::List), true)
+            .await
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed[1]["source_id"], "gen-1");
+    }
+
+    #[tokio::test]
+    async fn test_create_generic_posts_file() {
+        let mut server = mockito::Server::new_async().await;
+        let body = json!({"name": "Weather API", "url": "https://api.example.com"});
+        let create = server
+            .mock("POST", "/api/connectors/generic")
+            .match_body(Matcher::Json(body.clone()))
+            .with_status(201)
+            .with_body(r#"{"source_id":"gen-2"}"#)
+            .create_async()
+            .await;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), body.to_string()).unwrap();
+        let client = ApiClient::new(&server.url(), None).unwrap();
+
+        let command = Command::Sources(SourcesCommand::CreateGeneric {
+            file: file.path().to_path_buf(),
+        });
+        let out = run(&client, command, false).await.unwrap();
+        create.assert_async().await;
+        assert_eq!(out, "Created gen-2");
+    }
+
+    #[tokio::test]
+    async fn test_delete_uses_the_source_type_route() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/connectors")
+            .with_body(sources())
+            .create_async()
+            .await;
+        let delete = server
+            .mock("DELETE", "/api/connectors/files/file-1")
+            .with_status(204)
+            .create_async()
+            .await;
+        let client = ApiClient::new(&server.url(), None).unwrap();
+
+        assert_eq!(client.delete_source("file-1").await.unwrap(), "file");
+        delete.assert_async().await;
+
+        let err = client.delete_source("missing").await.unwrap_err();
+        assert_eq!(err.to_string(), "no source missing");
+        let err = client.sync_source("gen-1").await.unwrap_err();
+        assert!(err.to_string().contains("only named sources"));
+    }
+
+    #[tokio::test]
+    async fn test_api_error_message_is_surfaced() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/connectors")
+            .with_body(sources())
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/api/connectors/named/named-1/sync")
+            .with_status(404)
+            .with_body(r#"{"error":"Source not found"}"#)
+            .create_async()
+            .await;
+        let client = ApiClient::new(&server.url(), None).unwrap();
+
+        let err = client.sync_source("named-1").await.unwrap_err();
+        assert_eq!(err.to_string(), "404 Not Found: Source not found");
+    }
+
+    #[tokio::test]
+    async fn test_taps_search_matches_any_field() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/connectors/taps")
+            .with_body(
+                json!([
+                    {"name": "tap-github", "label": "GitHub", "description": "", "pip_url": "tap-github"},
+                    {"name": "tap-gitlab", "label": "GitLab", "description": "", "pip_url": "tap-gitlab"},
+                    {"name": "tap-jira", "label": "Jira", "description": "Issues from GitHub-style trackers", "pip_url": "tap-jira"}
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ApiClient::new(&server.url(), None).unwrap();
+
+        let taps = client.search_taps("GitHub").await.unwrap();
+        let names: Vec<&str> = taps.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["tap-github", "tap-jira"]);
+        assert!(taps_table(&taps).starts_with("NAME        LABEL   DESCRIPTION\n"));
+    }
+
+    #[tokio::test]
+    async fn test_status_counts_sources() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/connectors")
+            .with_body(sources())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/api/leader")
+            .with_body(r#"{"instance_id":"cm-1","is_leader":false,"leader":"cm-0"}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/api/connectors/rate-limit")
+            .with_body(
+                r#"{"events_per_second":500,"utilization":0.42,"queued_events":3,"paced_events":10}"#,
+            )
+            .create_async()
+            .await;
+        let client = ApiClient::new(&server.url(), None).unwrap();
+
+        let out = run(&client, Command::Status, false).await.unwrap();
+        assert_eq!(
+            out,
+            "Instance:     cm-1\n\
+             Leader:       cm-0\n\
+             Publish rate: 500 events/s, 42% used, 3 queued\n\
+             Sources:\n  \
+             error      1\n  \
+             running    3"
+        );
+    }
+}
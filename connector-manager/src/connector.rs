@@ -1,8 +1,9 @@
+use crate::error::ConnectorError;
 use crate::types::OAuthConfig;
-use crate::Credentials;
-use anyhow::Result;
+use crate::{ClientAuth, Credentials, ETagCache};
 use async_trait::async_trait;
 use flux::FluxEvent;
+use serde_json::Value;
 
 /// Connector interface for external API integrations.
 ///
@@ -19,9 +20,8 @@ use flux::FluxEvent;
 ///
 /// # Example
 /// ```no_run
-/// use connector_manager::{Connector, OAuthConfig, Credentials};
+/// use connector_manager::{Connector, ConnectorError, OAuthConfig, Credentials};
 /// use async_trait::async_trait;
-/// use anyhow::Result;
 /// use flux::FluxEvent;
 ///
 /// struct GitHubConnector;
@@ -40,7 +40,7 @@ use flux::FluxEvent;
 ///         }
 ///     }
 ///
-///     async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>> {
+///     async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
 ///         // Fetch data from GitHub API using credentials
 ///         // Transform to Flux events
 ///         // Return events
@@ -79,13 +79,84 @@ pub trait Connector: Send + Sync {
     ///
     /// # Returns
     /// * `Ok(Vec<FluxEvent>)` - Events to publish to Flux
-    /// * `Err(...)` - Authentication, network, or API errors
+    /// * `Err(ConnectorError)` - Classified failure (see below)
     ///
     /// # Error Handling
-    /// - Auth errors (expired token) → manager will trigger re-auth
-    /// - Rate limit errors → manager will back off
-    /// - Network errors → manager will retry with exponential backoff
-    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>>;
+    /// - `AuthExpired` → manager refreshes the token immediately and retries
+    /// - `RateLimited` → manager waits `retry_after` before retrying
+    /// - `Transient` → manager retries with exponential backoff
+    /// - `Permanent` → manager stops polling until credentials change
+    ///
+    /// `anyhow::Error` converts to `Transient`, so `?` on untyped errors works.
+    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError>;
+
+    /// Like [`fetch`](Self::fetch), for a Flux namespace and with the ETags
+    /// of the last published poll.
+    ///
+    /// With a `namespace`, entity IDs and keys must start with
+    /// `{namespace}/` (see [`namespaced`]), as an auth-enabled Flux only
+    /// accepts writes to the namespace the publishing token owns.
+    ///
+    /// Connectors whose API supports conditional requests override this:
+    /// they send the cached ETags as `If-None-Match`, emit no events for
+    /// resources that come back `304 Not Modified`, and record new ETags in
+    /// `etags`. The manager keeps the updated cache only once the events are
+    /// published, so a failed publish is fetched again in full.
+    ///
+    /// Defaults to a plain `fetch` with the namespace prefixed to its events.
+    async fn fetch_conditional(
+        &self,
+        credentials: &Credentials,
+        namespace: Option<&str>,
+        _etags: &mut ETagCache,
+    ) -> Result<Vec<FluxEvent>, ConnectorError> {
+        let mut events = self.fetch(credentials).await?;
+        if let Some(namespace) = namespace {
+            for event in &mut events {
+                event.key = event
+                    .key
+                    .as_deref()
+                    .map(|key| namespaced(Some(namespace), key));
+                let entity_id = event.payload.get("entity_id").and_then(|v| v.as_str());
+                if let Some(entity_id) = entity_id.map(|id| namespaced(Some(namespace), id)) {
+                    event.payload["entity_id"] = entity_id.into();
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Like [`fetch_conditional`](Self::fetch_conditional), with the user's
+    /// configuration of this connector (`null` if none was stored, see
+    /// [`crate::user_config`]).
+    ///
+    /// Connectors that need more than a token to poll, such as the Notion
+    /// databases to sync, override this and
+    /// [`validate_user_config`](Self::validate_user_config).
+    async fn fetch_configured(
+        &self,
+        credentials: &Credentials,
+        _config: &Value,
+        namespace: Option<&str>,
+        etags: &mut ETagCache,
+    ) -> Result<Vec<FluxEvent>, ConnectorError> {
+        self.fetch_conditional(credentials, namespace, etags).await
+    }
+
+    /// Checks a user's configuration before it is stored. Connectors that
+    /// take none only accept `null`.
+    fn validate_user_config(&self, config: &Value) -> Result<(), String> {
+        if config.is_null() {
+            Ok(())
+        } else {
+            Err(format!("Connector {} takes no configuration", self.name()))
+        }
+    }
+
+    /// How the token endpoint takes the client ID and secret when refreshing.
+    fn client_auth(&self) -> ClientAuth {
+        ClientAuth::Body
+    }
 
     /// Returns the poll interval in seconds.
     ///
@@ -94,4 +165,17 @@ pub trait Connector: Send + Sync {
     ///
     /// Consider API rate limits when setting this value.
     fn poll_interval(&self) -> u64;
+
+    /// Most events one poll may publish; the rest of a larger fetch is dropped.
+    fn max_events_per_run(&self) -> u64 {
+        crate::runners::rate_limit::DEFAULT_MAX_EVENTS_PER_RUN
+    }
+}
+
+/// `id` under `namespace`: `{namespace}/{id}`, or `id` as is without one.
+pub fn namespaced(namespace: Option<&str>, id: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("{}/{}", namespace, id),
+        None => id.to_string(),
+    }
 }
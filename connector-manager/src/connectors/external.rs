@@ -0,0 +1,470 @@
+//! External connectors — executables described by manifest files.
+//!
+//! A manifest is a JSON file in the manifest directory:
+//!
+//! ```json
+//! {
+//!   "name": "jira",
+//!   "exec": "jira-connector",
+//!   "poll_interval": 300,
+//!   "oauth": {
+//!     "auth_url": "https://auth.atlassian.com/authorize",
+//!     "token_url": "https://auth.atlassian.com/oauth/token",
+//!     "scopes": ["read:jira-work"]
+//!   }
+//! }
+//! ```
+//!
+//! On each poll the executable is spawned with the user's [`Credentials`] as
+//! one line of JSON on stdin, and writes the poll's [`FluxEvent`]s to stdout
+//! as NDJSON. It must exit within `timeout_secs` and write at most
+//! `max_output_bytes`. A non-zero exit is a transient error. Events beyond
+//! `max_events_per_run` (default 50,000) are dropped.
+
+use crate::runners::rate_limit::DEFAULT_MAX_EVENTS_PER_RUN;
+use crate::types::OAuthConfig;
+use crate::{Connector, ConnectorError, Credentials};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use flux::FluxEvent;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Default limit on one run of an external connector
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+/// Default cap on the NDJSON an external connector may write per run
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+/// Trailing bytes of stderr kept for error messages
+const MAX_STDERR_BYTES: usize = 4096;
+
+/// Contents of an external connector manifest file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectorManifest {
+    /// Connector name: lowercase letters, digits and `_`
+    pub name: String,
+    /// Executable, relative to the exec directory (or absolute inside it)
+    pub exec: String,
+    /// Seconds between polls
+    pub poll_interval: u64,
+    pub oauth: OAuthConfig,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    #[serde(default = "default_max_events_per_run")]
+    pub max_events_per_run: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+fn default_max_output_bytes() -> usize {
+    DEFAULT_MAX_OUTPUT_BYTES
+}
+
+fn default_max_events_per_run() -> u64 {
+    DEFAULT_MAX_EVENTS_PER_RUN
+}
+
+impl ConnectorManifest {
+    /// Checks everything but the executable (see [`resolve_exec`]).
+    pub fn validate(&self) -> Result<()> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            anyhow::bail!(
+                "name '{}' must be lowercase letters, digits and '_'",
+                self.name
+            );
+        }
+        if self.poll_interval == 0 {
+            anyhow::bail!("poll_interval must be at least 1 second");
+        }
+        if self.timeout_secs == 0 {
+            anyhow::bail!("timeout_secs must be at least 1 second");
+        }
+        if self.max_output_bytes == 0 {
+            anyhow::bail!("max_output_bytes must be positive");
+        }
+        if self.max_events_per_run == 0 {
+            anyhow::bail!("max_events_per_run must be positive");
+        }
+        for (field, url) in [
+            ("auth_url", &self.oauth.auth_url),
+            ("token_url", &self.oauth.token_url),
+        ] {
+            if !url.starts_with("https://") {
+                anyhow::bail!("oauth.{} must be an https URL", field);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path of `exec`, which must be an executable file inside `exec_dir`.
+///
+/// Symlinks are resolved first, so a link pointing out of the directory is
+/// refused.
+pub fn resolve_exec(exec: &str, exec_dir: &Path) -> Result<PathBuf> {
+    let dir = exec_dir
+        .canonicalize()
+        .with_context(|| format!("exec directory {} not found", exec_dir.display()))?;
+    let path = dir
+        .join(exec)
+        .canonicalize()
+        .with_context(|| format!("exec '{}' not found", exec))?;
+    if !path.starts_with(&dir) {
+        anyhow::bail!("exec '{}' is outside {}", exec, dir.display());
+    }
+
+    let metadata = std::fs::metadata(&path)?;
+    if !metadata.is_file() {
+        anyhow::bail!("exec '{}' is not a file", exec);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            anyhow::bail!("exec '{}' is not executable", exec);
+        }
+    }
+    Ok(path)
+}
+
+/// Connector that runs an executable per poll.
+#[derive(Debug)]
+pub struct ExternalConnector {
+    manifest: ConnectorManifest,
+    exec: PathBuf,
+}
+
+impl ExternalConnector {
+    /// Loads and validates the manifest at `path`.
+    pub fn from_manifest_file(path: &Path, exec_dir: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).context("Failed to read manifest")?;
+        let manifest: ConnectorManifest =
+            serde_json::from_str(&json).context("Invalid manifest")?;
+        Self::new(manifest, exec_dir)
+    }
+
+    pub fn new(manifest: ConnectorManifest, exec_dir: &Path) -> Result<Self> {
+        manifest.validate()?;
+        let exec = resolve_exec(&manifest.exec, exec_dir)?;
+        Ok(Self { manifest, exec })
+    }
+
+    pub fn manifest(&self) -> &ConnectorManifest {
+        &self.manifest
+    }
+
+    /// Spawns the executable and collects its stdout and stderr.
+    async fn run(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+        let name = &self.manifest.name;
+        let mut cmd = tokio::process::Command::new(&self.exec);
+        if let Some(dir) = self.exec.parent() {
+            cmd.current_dir(dir);
+        }
+        // Credentials arrive on stdin only; keep the manager's secrets out
+        cmd.env_clear();
+        if let Ok(path) = std::env::var("PATH") {
+            cmd.env("PATH", path);
+        }
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn().map_err(|e| {
+            ConnectorError::Permanent(format!("failed to start connector '{}': {}", name, e))
+        })?;
+
+        let mut input = serde_json::to_vec(credentials)
+            .map_err(|e| ConnectorError::Permanent(e.to_string()))?;
+        input.push(b'\n');
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let write = async move {
+            // A connector that needs no credentials may exit without reading
+            match stdin.write_all(&input).await {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e),
+                _ => Ok(()),
+            }
+        };
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let (_, stdout, stderr) = tokio::try_join!(
+            async { write.await.map_err(|e| transient(name, e)) },
+            read_capped(stdout, self.manifest.max_output_bytes, name),
+            async { Ok::<_, ConnectorError>(read_tail(stderr, MAX_STDERR_BYTES).await) },
+        )?;
+        let status = child.wait().await.map_err(|e| transient(name, e))?;
+
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            let reason = stderr.lines().last().unwrap_or("").trim();
+            return Err(ConnectorError::Transient(format!(
+                "connector '{}' exited with {}: {}",
+                name, status, reason
+            )));
+        }
+        parse_events(&stdout).map_err(|e| transient(name, format!("{:#}", e)))
+    }
+}
+
+fn transient(name: &str, e: impl std::fmt::Display) -> ConnectorError {
+    ConnectorError::Transient(format!("connector '{}': {}", name, e))
+}
+
+/// Reads all of `reader`, failing once more than `max` bytes arrive.
+async fn read_capped(
+    reader: impl AsyncRead + Unpin,
+    max: usize,
+    name: &str,
+) -> Result<Vec<u8>, ConnectorError> {
+    let mut buf = Vec::new();
+    reader
+        .take(max as u64 + 1)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(|e| transient(name, e))?;
+    if buf.len() > max {
+        return Err(transient(
+            name,
+            format!("output exceeds max_output_bytes ({})", max),
+        ));
+    }
+    Ok(buf)
+}
+
+/// Reads all of `reader`, keeping the last `max` bytes.
+async fn read_tail(mut reader: impl AsyncRead + Unpin, max: usize) -> Vec<u8> {
+    let mut tail = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        tail.extend_from_slice(&chunk[..n]);
+        if tail.len() > max {
+            tail.drain(..tail.len() - max);
+        }
+    }
+    tail
+}
+
+/// Parses NDJSON output into events, skipping blank lines.
+pub fn parse_events(output: &[u8]) -> Result<Vec<FluxEvent>> {
+    let output = std::str::from_utf8(output).context("output is not UTF-8")?;
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("line {}: invalid event", i + 1))
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Connector for ExternalConnector {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn oauth_config(&self) -> OAuthConfig {
+        self.manifest.oauth.clone()
+    }
+
+    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+        let timeout = Duration::from_secs(self.manifest.timeout_secs);
+        match tokio::time::timeout(timeout, self.run(credentials)).await {
+            Ok(result) => result,
+            // Dropping the run kills the child
+            Err(_) => Err(ConnectorError::Transient(format!(
+                "connector '{}' timed out after {}s",
+                self.manifest.name, self.manifest.timeout_secs
+            ))),
+        }
+    }
+
+    fn poll_interval(&self) -> u64 {
+        self.manifest.poll_interval
+    }
+
+    fn max_events_per_run(&self) -> u64 {
+        self.manifest.max_events_per_run
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    const ECHO_CONNECTOR: &str = include_str!("../../tests/fixtures/external/echo.sh");
+
+    /// Writes `script` as an executable named `file` into `dir`
+    fn write_script(dir: &Path, file: &str, script: &str) {
+        let path = dir.join(file);
+        std::fs::write(&path, script).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    fn manifest(exec: &str) -> ConnectorManifest {
+        serde_json::from_value(json!({
+            "name": "echo",
+            "exec": exec,
+            "poll_interval": 60,
+            "oauth": {
+                "auth_url": "https://example.com/authorize",
+                "token_url": "https://example.com/token",
+                "scopes": ["read"]
+            },
+            "timeout_secs": 5
+        }))
+        .unwrap()
+    }
+
+    fn credentials() -> Credentials {
+        Credentials {
+            access_token: "secret-token".to_string(),
+            refresh_token: None,
+            expires_at: Some(Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_manifest_validation() {
+        assert!(manifest("echo.sh").validate().is_ok());
+
+        let mut bad = manifest("echo.sh");
+        bad.name = "Echo-Connector".to_string();
+        assert!(bad.validate().unwrap_err().to_string().contains("name"));
+
+        let mut bad = manifest("echo.sh");
+        bad.poll_interval = 0;
+        assert!(bad.validate().is_err());
+
+        assert_eq!(manifest("echo.sh").max_events_per_run, 50_000);
+        let mut bad = manifest("echo.sh");
+        bad.max_events_per_run = 0;
+        assert!(bad.validate().is_err());
+
+        let mut bad = manifest("echo.sh");
+        bad.oauth.token_url = "file:///etc/passwd".to_string();
+        assert!(bad
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("token_url"));
+
+        let unknown_field = serde_json::from_value::<ConnectorManifest>(json!({
+            "name": "echo", "exec": "echo.sh", "poll_interval": 60, "shell": true,
+            "oauth": {"auth_url": "https://a", "token_url": "https://t", "scopes": []}
+        }));
+        assert!(unknown_field.is_err());
+    }
+
+    #[test]
+    fn test_exec_sandboxed_to_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let exec_dir = root.path().join("bin");
+        std::fs::create_dir(&exec_dir).unwrap();
+        write_script(&exec_dir, "echo.sh", ECHO_CONNECTOR);
+        write_script(root.path(), "outside.sh", ECHO_CONNECTOR);
+        std::fs::write(exec_dir.join("data.txt"), "not a program").unwrap();
+
+        assert!(resolve_exec("echo.sh", &exec_dir).is_ok());
+        assert!(resolve_exec(exec_dir.join("echo.sh").to_str().unwrap(), &exec_dir).is_ok());
+        for exec in [
+            "../outside.sh",
+            root.path().join("outside.sh").to_str().unwrap(),
+        ] {
+            let err = resolve_exec(exec, &exec_dir).unwrap_err().to_string();
+            assert!(err.contains("outside"), "{}", err);
+        }
+        assert!(resolve_exec("missing.sh", &exec_dir).is_err());
+        #[cfg(unix)]
+        {
+            assert!(resolve_exec("data.txt", &exec_dir).is_err());
+            std::os::unix::fs::symlink(root.path().join("outside.sh"), exec_dir.join("link.sh"))
+                .unwrap();
+            assert!(resolve_exec("link.sh", &exec_dir).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let output = b"{\"stream\":\"s\",\"source\":\"x\",\"timestamp\":1,\"payload\":{\"entity_id\":\"a\"}}\n\n";
+        let events = parse_events(output).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].payload["entity_id"], "a");
+
+        let err = parse_events(b"{\"stream\":\"s\"}\nnot json\n").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fetch_runs_executable_with_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        write_script(dir.path(), "echo.sh", ECHO_CONNECTOR);
+        let connector = ExternalConnector::new(manifest("echo.sh"), dir.path()).unwrap();
+
+        let events = connector.fetch(&credentials()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].stream, "external.echo");
+        assert_eq!(
+            events[0].payload["properties"]["credentials"]["access_token"],
+            "secret-token"
+        );
+        assert_eq!(connector.poll_interval(), 60);
+        assert_eq!(connector.oauth_config().scopes, vec!["read"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fetch_failures_are_transient() {
+        let dir = tempfile::tempdir().unwrap();
+        write_script(
+            dir.path(),
+            "fail.sh",
+            "#!/bin/sh\necho 'bad credentials' >&2\nexit 3\n",
+        );
+        write_script(dir.path(), "slow.sh", "#!/bin/sh\nsleep 10\n");
+        write_script(dir.path(), "chatty.sh", "#!/bin/sh\nyes '{}'\n");
+
+        let run = |exec: &str, max_output_bytes: usize| {
+            let mut manifest = manifest(exec);
+            manifest.timeout_secs = 1;
+            manifest.max_output_bytes = max_output_bytes;
+            let connector = ExternalConnector::new(manifest, dir.path()).unwrap();
+            async move { connector.fetch(&credentials()).await.unwrap_err() }
+        };
+
+        for (exec, expected) in [
+            ("fail.sh", "bad credentials"),
+            ("slow.sh", "timed out"),
+            ("chatty.sh", "max_output_bytes"),
+        ] {
+            match run(exec, 1024).await {
+                ConnectorError::Transient(msg) => assert!(msg.contains(expected), "{}", msg),
+                other => panic!("{}: expected transient error, got {:?}", exec, other),
+            }
+        }
+    }
+}
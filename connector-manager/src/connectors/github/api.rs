@@ -1,8 +1,15 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::Context;
+use flux::credentials::CachedETag;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
 use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::time::Duration;
 
 use super::config::BASE_URL;
+use crate::{ConnectorError, ETagCache};
+
+type Result<T> = std::result::Result<T, ConnectorError>;
 
 /// GitHub repository.
 #[derive(Debug, Deserialize)]
@@ -56,9 +63,19 @@ pub struct GitHubIssue {
     pub updated_at: String,
 }
 
+/// Outcome of a conditional GET.
+#[derive(Debug, PartialEq)]
+pub enum Fetched<T> {
+    /// The resource changed (or had no cached ETag)
+    Modified(T),
+    /// `304 Not Modified`: the resource is as it was at the cached ETag
+    NotModified,
+}
+
 /// HTTP client for the GitHub REST API.
 ///
-/// Authenticates with a Bearer token and sets a User-Agent header.
+/// Authenticates with a Bearer token and sets a User-Agent header. Requests
+/// are conditional on the ETags in the [`ETagCache`] passed in.
 pub struct GitHubClient {
     access_token: String,
     http_client: Client,
@@ -73,10 +90,7 @@ impl GitHubClient {
 
     /// Create a client with a custom base URL (for testing with a mock server).
     pub fn with_base_url(access_token: String, base_url: String) -> Self {
-        let http_client = Client::builder()
-            .user_agent("flux-connector/1.0")
-            .build()
-            .expect("Failed to build HTTP client");
+        let http_client = crate::http::client().expect("Failed to build HTTP client");
         Self {
             access_token,
             http_client,
@@ -85,92 +99,175 @@ impl GitHubClient {
     }
 
     /// Fetch the authenticated user's repositories (sorted by last updated).
-    pub async fn fetch_repos(&self) -> Result<Vec<GitHubRepo>> {
-        let url = format!("{}/user/repos?sort=updated&per_page=30", self.base_url);
-        let response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await
-            .context("Failed to send fetch_repos request")?;
+    ///
+    /// Their full names are kept with the ETag; see [`known_repos`](Self::known_repos).
+    pub async fn fetch_repos(&self, etags: &mut ETagCache) -> Result<Fetched<Vec<GitHubRepo>>> {
+        let url = self.repos_url();
+        self.get_conditional(&url, etags, "fetch_repos", |repos: &Vec<GitHubRepo>| {
+            repos.iter().map(|r| r.full_name.clone()).collect()
+        })
+        .await
+    }
 
-        check_response_status(&response)?;
-        response
-            .json::<Vec<GitHubRepo>>()
-            .await
-            .context("Failed to parse repos response")
+    /// Full names of the repositories in the last `fetch_repos` response,
+    /// for polling their issues while the listing is unchanged.
+    pub fn known_repos(&self, etags: &ETagCache) -> Vec<String> {
+        etags
+            .get(&self.repos_url())
+            .map(|e| e.items.clone())
+            .unwrap_or_default()
     }
 
     /// Fetch the authenticated user's notifications.
-    pub async fn fetch_notifications(&self) -> Result<Vec<GitHubNotification>> {
+    pub async fn fetch_notifications(
+        &self,
+        etags: &mut ETagCache,
+    ) -> Result<Fetched<Vec<GitHubNotification>>> {
         let url = format!("{}/notifications?per_page=30", self.base_url);
-        let response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await
-            .context("Failed to send fetch_notifications request")?;
-
-        check_response_status(&response)?;
-        response
-            .json::<Vec<GitHubNotification>>()
+        self.get_conditional(&url, etags, "fetch_notifications", |_| Vec::new())
             .await
-            .context("Failed to parse notifications response")
     }
 
     /// Fetch open issues for a repository.
-    pub async fn fetch_issues(&self, owner: &str, repo: &str) -> Result<Vec<GitHubIssue>> {
+    pub async fn fetch_issues(
+        &self,
+        owner: &str,
+        repo: &str,
+        etags: &mut ETagCache,
+    ) -> Result<Fetched<Vec<GitHubIssue>>> {
         let url = format!(
             "{}/repos/{}/{}/issues?state=open&per_page=10",
             self.base_url, owner, repo
         );
-        let response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&self.access_token)
+        self.get_conditional(&url, etags, "fetch_issues", |_| Vec::new())
+            .await
+    }
+
+    fn repos_url(&self) -> String {
+        format!("{}/user/repos?sort=updated&per_page=30", self.base_url)
+    }
+
+    /// GET `url` with `If-None-Match` from `etags`, recording the new ETag.
+    ///
+    /// 304 responses don't count against GitHub's rate limit.
+    async fn get_conditional<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        etags: &mut ETagCache,
+        operation: &str,
+        items: impl FnOnce(&T) -> Vec<String>,
+    ) -> Result<Fetched<T>> {
+        let mut request = self.http_client.get(url).bearer_auth(&self.access_token);
+        if let Some(etag) = etags.etag(url) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request
             .send()
             .await
-            .context("Failed to send fetch_issues request")?;
+            .with_context(|| format!("Failed to send {} request", operation))?;
 
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
         check_response_status(&response)?;
-        response
-            .json::<Vec<GitHubIssue>>()
+
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response
+            .json::<T>()
             .await
-            .context("Failed to parse issues response")
+            .with_context(|| format!("Failed to parse {} response", operation))?;
+
+        match etag {
+            Some(etag) => etags.insert(
+                url,
+                CachedETag {
+                    etag,
+                    items: items(&body),
+                },
+            ),
+            None => {
+                etags.remove(url);
+            }
+        }
+        Ok(Fetched::Modified(body))
     }
 }
 
-/// Check the response status and map known error codes to descriptive errors.
+/// Check the response status and classify failures for the scheduler.
 ///
-/// - 401 → auth error (token expired or invalid)
-/// - 403 → rate limit (logs X-RateLimit-Remaining)
-/// - Other non-2xx → generic API error
+/// - 401 → `AuthExpired`
+/// - 403 with `X-RateLimit-Remaining: 0` or `Retry-After`, and 429 → `RateLimited`
+/// - Other 403 → `Permanent` (token lacks the required scopes)
+/// - 5xx → `Transient`
+/// - Other non-2xx → `Permanent`
 fn check_response_status(response: &reqwest::Response) -> Result<()> {
-    match response.status() {
-        StatusCode::UNAUTHORIZED => Err(anyhow!("GitHub auth error: token expired or invalid")),
+    let status = response.status();
+    let headers = response.headers();
+    match status {
+        s if s.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED => Err(ConnectorError::AuthExpired),
+        StatusCode::TOO_MANY_REQUESTS => Err(ConnectorError::RateLimited {
+            retry_after: retry_after(headers),
+        }),
         StatusCode::FORBIDDEN => {
-            let remaining = response
-                .headers()
+            let exhausted = headers
                 .get("X-RateLimit-Remaining")
                 .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or(0);
-            Err(anyhow!(
-                "GitHub rate limit exceeded (X-RateLimit-Remaining: {})",
-                remaining
-            ))
+                .is_some_and(|v| v.trim() == "0");
+            if exhausted || headers.contains_key(reqwest::header::RETRY_AFTER) {
+                Err(ConnectorError::RateLimited {
+                    retry_after: retry_after(headers),
+                })
+            } else {
+                Err(ConnectorError::Permanent(
+                    "GitHub API forbidden: token lacks required scopes".to_string(),
+                ))
+            }
         }
-        s if !s.is_success() => Err(anyhow!("GitHub API error: {}", s)),
-        _ => Ok(()),
+        s if s.is_server_error() => Err(ConnectorError::Transient(format!(
+            "GitHub API error: {}",
+            s
+        ))),
+        s => Err(ConnectorError::Permanent(format!(
+            "GitHub API error: {}",
+            s
+        ))),
     }
 }
 
+/// Wait hint from `Retry-After` (seconds), else `X-RateLimit-Reset` (epoch seconds).
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<i64>().ok())
+    };
+
+    if let Some(secs) = header("Retry-After") {
+        return Some(Duration::from_secs(secs.max(0) as u64));
+    }
+    header("X-RateLimit-Reset").map(|reset| {
+        let wait = reset - chrono::Utc::now().timestamp();
+        Duration::from_secs(wait.max(0) as u64)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockito::Server;
+    use mockito::{Matcher, Server};
+
+    fn modified<T>(fetched: Fetched<T>) -> T {
+        match fetched {
+            Fetched::Modified(value) => value,
+            Fetched::NotModified => panic!("expected a modified response"),
+        }
+    }
 
     #[tokio::test]
     async fn test_fetch_repos() {
@@ -199,7 +296,7 @@ mod tests {
             .await;
 
         let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
-        let repos = client.fetch_repos().await.unwrap();
+        let repos = modified(client.fetch_repos(&mut ETagCache::default()).await.unwrap());
 
         assert_eq!(repos.len(), 1);
         assert_eq!(repos[0].name, "test-repo");
@@ -235,7 +332,12 @@ mod tests {
             .await;
 
         let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
-        let notifications = client.fetch_notifications().await.unwrap();
+        let notifications = modified(
+            client
+                .fetch_notifications(&mut ETagCache::default())
+                .await
+                .unwrap(),
+        );
 
         assert_eq!(notifications.len(), 1);
         assert_eq!(notifications[0].id, "1");
@@ -249,7 +351,10 @@ mod tests {
     async fn test_fetch_issues() {
         let mut server = Server::new_async().await;
         let _mock = server
-            .mock("GET", "/repos/testuser/test-repo/issues?state=open&per_page=10")
+            .mock(
+                "GET",
+                "/repos/testuser/test-repo/issues?state=open&per_page=10",
+            )
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(
@@ -269,7 +374,12 @@ mod tests {
             .await;
 
         let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
-        let issues = client.fetch_issues("testuser", "test-repo").await.unwrap();
+        let issues = modified(
+            client
+                .fetch_issues("testuser", "test-repo", &mut ETagCache::default())
+                .await
+                .unwrap(),
+        );
 
         assert_eq!(issues.len(), 1);
         assert_eq!(issues[0].number, 42);
@@ -277,6 +387,110 @@ mod tests {
         assert_eq!(issues[0].user.login, "testuser");
     }
 
+    fn repo_json(name: &str, stars: u64) -> String {
+        format!(
+            r#"[{{
+                "id": 1,
+                "name": "{name}",
+                "full_name": "alice/{name}",
+                "description": null,
+                "language": null,
+                "stargazers_count": {stars},
+                "forks_count": 0,
+                "open_issues_count": 0,
+                "updated_at": "2026-02-17T12:00:00Z",
+                "private": false
+            }}]"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_conditional_fetch_200_then_304_then_changed() {
+        let mut server = Server::new_async().await;
+        let path = "/user/repos?sort=updated&per_page=30";
+        let first = server
+            .mock("GET", path)
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"v1\"")
+            .with_body(repo_json("repo", 1))
+            .expect(1)
+            .create_async()
+            .await;
+        let unchanged = server
+            .mock("GET", path)
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let mut etags = ETagCache::default();
+
+        let repos = modified(client.fetch_repos(&mut etags).await.unwrap());
+        assert_eq!(repos[0].stargazers_count, 1);
+        assert_eq!(etags.len(), 1);
+        assert_eq!(client.known_repos(&etags), vec!["alice/repo".to_string()]);
+
+        assert!(matches!(
+            client.fetch_repos(&mut etags).await.unwrap(),
+            Fetched::NotModified
+        ));
+        // A 304 keeps the cached ETag and repo list
+        assert_eq!(client.known_repos(&etags), vec!["alice/repo".to_string()]);
+        first.assert_async().await;
+        unchanged.assert_async().await;
+
+        // The resource changes after the 304
+        unchanged.remove_async().await;
+        let changed = server
+            .mock("GET", path)
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(200)
+            .with_header("ETag", "\"v2\"")
+            .with_body(repo_json("renamed", 2))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let repos = modified(client.fetch_repos(&mut etags).await.unwrap());
+        assert_eq!(repos[0].stargazers_count, 2);
+        assert_eq!(
+            client.known_repos(&etags),
+            vec!["alice/renamed".to_string()]
+        );
+        let url = format!("{}{}", server.url(), path);
+        assert_eq!(etags.etag(&url), Some("\"v2\""));
+        changed.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_response_without_etag_clears_cache_entry() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/notifications?per_page=30")
+            .with_status(200)
+            .with_body("[]")
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let url = format!("{}/notifications?per_page=30", server.url());
+        let mut etags = ETagCache::default();
+        etags.insert(
+            url.clone(),
+            CachedETag {
+                etag: "\"stale\"".to_string(),
+                items: vec![],
+            },
+        );
+
+        let notifications = modified(client.fetch_notifications(&mut etags).await.unwrap());
+        assert!(notifications.is_empty());
+        assert_eq!(etags.etag(&url), None);
+    }
+
     #[tokio::test]
     async fn test_401_auth_error() {
         let mut server = Server::new_async().await;
@@ -289,25 +503,114 @@ mod tests {
             .await;
 
         let client = GitHubClient::with_base_url("expired_token".to_string(), server.url());
-        let err = client.fetch_repos().await.unwrap_err();
+        let err = client
+            .fetch_repos(&mut ETagCache::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err, ConnectorError::AuthExpired);
         assert!(err.to_string().contains("token expired or invalid"));
     }
 
     #[tokio::test]
     async fn test_403_rate_limit() {
         let mut server = Server::new_async().await;
+        let reset = chrono::Utc::now().timestamp() + 120;
         let _mock = server
             .mock("GET", "/user/repos?sort=updated&per_page=30")
             .with_status(403)
             .with_header("X-RateLimit-Remaining", "0")
+            .with_header("X-RateLimit-Reset", &reset.to_string())
             .with_header("content-type", "application/json")
             .with_body(r#"{"message": "API rate limit exceeded"}"#)
             .create_async()
             .await;
 
         let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
-        let err = client.fetch_repos().await.unwrap_err();
-        assert!(err.to_string().contains("rate limit exceeded"));
-        assert!(err.to_string().contains("X-RateLimit-Remaining: 0"));
+        let err = client
+            .fetch_repos(&mut ETagCache::default())
+            .await
+            .unwrap_err();
+        match err {
+            ConnectorError::RateLimited {
+                retry_after: Some(after),
+            } => assert!((110..=120).contains(&after.as_secs()), "got {:?}", after),
+            other => panic!("expected RateLimited with hint, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_403_without_rate_limit_is_permanent() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/user/repos?sort=updated&per_page=30")
+            .with_status(403)
+            .with_header("X-RateLimit-Remaining", "4999")
+            .with_body(r#"{"message": "Resource not accessible by integration"}"#)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let err = client
+            .fetch_repos(&mut ETagCache::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::Permanent(_)), "got {:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_429_uses_retry_after() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/notifications?per_page=30")
+            .with_status(429)
+            .with_header("Retry-After", "45")
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let err = client
+            .fetch_notifications(&mut ETagCache::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConnectorError::RateLimited {
+                retry_after: Some(Duration::from_secs(45))
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_5xx_is_transient() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/repos/o/r/issues?state=open&per_page=10")
+            .with_status(502)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let err = client
+            .fetch_issues("o", "r", &mut ETagCache::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::Transient(_)), "got {:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_404_is_permanent() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/user/repos?sort=updated&per_page=30")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_url("test_token".to_string(), server.url());
+        let err = client
+            .fetch_repos(&mut ETagCache::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::Permanent(_)), "got {:?}", err);
     }
 }
@@ -2,12 +2,11 @@ pub mod api;
 pub mod config;
 pub mod transformer;
 
-use crate::{Connector, Credentials, OAuthConfig};
-use anyhow::Result;
+use crate::{Connector, ConnectorError, Credentials, ETagCache, OAuthConfig};
 use async_trait::async_trait;
 use flux::FluxEvent;
 
-use self::api::GitHubClient;
+use self::api::{Fetched, GitHubClient};
 use self::config::{AUTH_URL, BASE_URL, SCOPES, TOKEN_URL};
 use self::transformer::{issue_to_event, notification_to_event, repo_to_event};
 
@@ -31,6 +30,12 @@ impl GitHubConnector {
     }
 }
 
+impl Default for GitHubConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Connector for GitHubConnector {
     fn name(&self) -> &str {
@@ -45,34 +50,60 @@ impl Connector for GitHubConnector {
         }
     }
 
-    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>> {
+    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+        self.fetch_conditional(credentials, None, &mut ETagCache::default())
+            .await
+    }
+
+    /// Repos, issues and notifications that come back `304 Not Modified`
+    /// produce no events. Issues are still polled for every known repo when
+    /// the repo listing itself is unchanged.
+    async fn fetch_conditional(
+        &self,
+        credentials: &Credentials,
+        namespace: Option<&str>,
+        etags: &mut ETagCache,
+    ) -> Result<Vec<FluxEvent>, ConnectorError> {
         let client =
             GitHubClient::with_base_url(credentials.access_token.clone(), self.base_url.clone());
         let mut events = Vec::new();
 
         // Fetch repos; for each repo also fetch its open issues.
-        let repos = client.fetch_repos().await?;
-        for repo in &repos {
-            events.push(repo_to_event(repo));
-            if let Some((owner, name)) = repo.full_name.split_once('/') {
-                match client.fetch_issues(owner, name).await {
-                    Ok(issues) => {
+        let repos = match client.fetch_repos(etags).await? {
+            Fetched::Modified(repos) => {
+                for repo in &repos {
+                    events.push(repo_to_event(namespace, repo)?);
+                }
+                repos.into_iter().map(|r| r.full_name).collect()
+            }
+            Fetched::NotModified => client.known_repos(etags),
+        };
+        for full_name in &repos {
+            if let Some((owner, name)) = full_name.split_once('/') {
+                match client.fetch_issues(owner, name, etags).await {
+                    Ok(Fetched::Modified(issues)) => {
                         for issue in &issues {
-                            events.push(issue_to_event(owner, name, issue));
+                            events.push(issue_to_event(namespace, owner, name, issue)?);
                         }
                     }
+                    Ok(Fetched::NotModified) => {}
+                    // Every later request would fail the same way
+                    Err(e @ (ConnectorError::AuthExpired | ConnectorError::RateLimited { .. })) => {
+                        return Err(e);
+                    }
                     Err(e) => {
                         // Non-fatal: log and continue with remaining repos.
-                        tracing::warn!("Failed to fetch issues for {}: {}", repo.full_name, e);
+                        tracing::warn!("Failed to fetch issues for {}: {}", full_name, e);
                     }
                 }
             }
         }
 
         // Fetch notifications.
-        let notifications = client.fetch_notifications().await?;
-        for notification in &notifications {
-            events.push(notification_to_event(notification));
+        if let Fetched::Modified(notifications) = client.fetch_notifications(etags).await? {
+            for notification in &notifications {
+                events.push(notification_to_event(namespace, notification)?);
+            }
         }
 
         Ok(events)
@@ -194,4 +225,174 @@ mod tests {
             .unwrap();
         assert_eq!(notif_event.schema.as_deref(), Some("github.notification"));
     }
+
+    #[tokio::test]
+    async fn test_fetch_conditional_skips_unchanged_resources() {
+        let mut server = Server::new_async().await;
+
+        let _repos_mock = server
+            .mock("GET", "/user/repos?sort=updated&per_page=30")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"repos-1\"")
+            .with_body(
+                r#"[{
+                    "id": 1,
+                    "name": "my-repo",
+                    "full_name": "alice/my-repo",
+                    "description": null,
+                    "language": null,
+                    "stargazers_count": 0,
+                    "forks_count": 0,
+                    "open_issues_count": 1,
+                    "updated_at": "2026-02-18T00:00:00Z",
+                    "private": false
+                }]"#,
+            )
+            .create_async()
+            .await;
+        let _repos_unchanged = server
+            .mock("GET", "/user/repos?sort=updated&per_page=30")
+            .match_header("if-none-match", "\"repos-1\"")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let issue = |title: &str| {
+            format!(
+                r#"[{{
+                    "id": 99,
+                    "number": 5,
+                    "title": "{title}",
+                    "state": "open",
+                    "user": {{"login": "alice"}},
+                    "created_at": "2026-02-17T00:00:00Z",
+                    "updated_at": "2026-02-18T00:00:00Z"
+                }}]"#
+            )
+        };
+        let issues_path = "/repos/alice/my-repo/issues?state=open&per_page=10";
+        let _issues_mock = server
+            .mock("GET", issues_path)
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"issues-1\"")
+            .with_body(issue("A bug"))
+            .create_async()
+            .await;
+        let issues_unchanged = server
+            .mock("GET", issues_path)
+            .match_header("if-none-match", "\"issues-1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let _notifs_mock = server
+            .mock("GET", "/notifications?per_page=30")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"notifs-1\"")
+            .with_body("[]")
+            .create_async()
+            .await;
+        let _notifs_unchanged = server
+            .mock("GET", "/notifications?per_page=30")
+            .match_header("if-none-match", "\"notifs-1\"")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let connector = GitHubConnector::with_base_url(server.url());
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        let mut etags = ETagCache::default();
+
+        let events = connector
+            .fetch_conditional(&credentials, None, &mut etags)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(etags.len(), 3);
+
+        // Nothing changed: no events, but the known repo's issues are still checked
+        let events = connector
+            .fetch_conditional(&credentials, None, &mut etags)
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+        issues_unchanged.assert_async().await;
+
+        // The issue changes after the 304
+        issues_unchanged.remove_async().await;
+        let _issues_changed = server
+            .mock("GET", issues_path)
+            .match_header("if-none-match", "\"issues-1\"")
+            .with_status(200)
+            .with_header("ETag", "\"issues-2\"")
+            .with_body(issue("A renamed bug"))
+            .create_async()
+            .await;
+
+        let events = connector
+            .fetch_conditional(&credentials, None, &mut etags)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].key.as_deref(),
+            Some("github/issue/alice/my-repo/5")
+        );
+        assert_eq!(events[0].payload["properties"]["title"], "A renamed bug");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_propagates_rate_limit_from_issues() {
+        let mut server = Server::new_async().await;
+
+        let _repos_mock = server
+            .mock("GET", "/user/repos?sort=updated&per_page=30")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{
+                    "id": 1,
+                    "name": "my-repo",
+                    "full_name": "alice/my-repo",
+                    "description": null,
+                    "language": null,
+                    "stargazers_count": 0,
+                    "forks_count": 0,
+                    "open_issues_count": 0,
+                    "updated_at": "2026-02-18T00:00:00Z",
+                    "private": false
+                }]"#,
+            )
+            .create_async()
+            .await;
+
+        let _issues_mock = server
+            .mock("GET", "/repos/alice/my-repo/issues?state=open&per_page=10")
+            .with_status(429)
+            .with_header("Retry-After", "10")
+            .create_async()
+            .await;
+
+        let connector = GitHubConnector::with_base_url(server.url());
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+
+        let err = connector.fetch(&credentials).await.unwrap_err();
+        assert!(
+            matches!(err, ConnectorError::RateLimited { .. }),
+            "got {:?}",
+            err
+        );
+    }
 }
@@ -1,86 +1,82 @@
-use chrono::Utc;
+use flux::event::{BuildError, FluxEventBuilder};
 use flux::FluxEvent;
-use uuid::Uuid;
 
 use super::api::{GitHubIssue, GitHubNotification, GitHubRepo};
+use crate::namespaced;
+
+const STREAM: &str = "connectors";
+const SOURCE: &str = "connector-manager";
 
 /// Transform a GitHub repository into a Flux event.
 ///
-/// Entity key: `github/repo/{full_name}`
-pub fn repo_to_event(repo: &GitHubRepo) -> FluxEvent {
-    FluxEvent {
-        event_id: Some(Uuid::now_v7().to_string()),
-        stream: "connectors".to_string(),
-        source: "connector-manager".to_string(),
-        timestamp: Utc::now().timestamp_millis(),
-        key: Some(format!("github/repo/{}", repo.full_name)),
-        schema: Some("github.repository".to_string()),
-        payload: serde_json::json!({
-            "entity_id": format!("github/repo/{}", repo.full_name),
-            "properties": {
-                "name": repo.name,
-                "full_name": repo.full_name,
-                "description": repo.description,
-                "language": repo.language,
-                "stars": repo.stargazers_count,
-                "forks": repo.forks_count,
-                "open_issues": repo.open_issues_count,
-                "private": repo.private,
-                "updated_at": repo.updated_at,
-            }
-        }),
-    }
+/// Entity key: `[{namespace}/]github/repo/{full_name}`
+pub fn repo_to_event(namespace: Option<&str>, repo: &GitHubRepo) -> Result<FluxEvent, BuildError> {
+    FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(namespaced(
+            namespace,
+            &format!("github/repo/{}", repo.full_name),
+        ))
+        .key_from_entity()
+        .schema("github.repository")
+        .property("name", &repo.name)
+        .property("full_name", &repo.full_name)
+        .property("description", &repo.description)
+        .property("language", &repo.language)
+        .property("stars", repo.stargazers_count)
+        .property("forks", repo.forks_count)
+        .property("open_issues", repo.open_issues_count)
+        .property("private", repo.private)
+        .property("updated_at", &repo.updated_at)
+        .build()
 }
 
 /// Transform a GitHub notification into a Flux event.
 ///
-/// Entity key: `github/notification/{id}`
-pub fn notification_to_event(notification: &GitHubNotification) -> FluxEvent {
-    FluxEvent {
-        event_id: Some(Uuid::now_v7().to_string()),
-        stream: "connectors".to_string(),
-        source: "connector-manager".to_string(),
-        timestamp: Utc::now().timestamp_millis(),
-        key: Some(format!("github/notification/{}", notification.id)),
-        schema: Some("github.notification".to_string()),
-        payload: serde_json::json!({
-            "entity_id": format!("github/notification/{}", notification.id),
-            "properties": {
-                "id": notification.id,
-                "reason": notification.reason,
-                "unread": notification.unread,
-                "updated_at": notification.updated_at,
-                "subject_title": notification.subject.title,
-                "subject_type": notification.subject.subject_type,
-                "subject_url": notification.subject.url,
-            }
-        }),
-    }
+/// Entity key: `[{namespace}/]github/notification/{id}`
+pub fn notification_to_event(
+    namespace: Option<&str>,
+    notification: &GitHubNotification,
+) -> Result<FluxEvent, BuildError> {
+    FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(namespaced(
+            namespace,
+            &format!("github/notification/{}", notification.id),
+        ))
+        .key_from_entity()
+        .schema("github.notification")
+        .property("id", &notification.id)
+        .property("reason", &notification.reason)
+        .property("unread", notification.unread)
+        .property("updated_at", &notification.updated_at)
+        .property("subject_title", &notification.subject.title)
+        .property("subject_type", &notification.subject.subject_type)
+        .property("subject_url", &notification.subject.url)
+        .build()
 }
 
 /// Transform a GitHub issue into a Flux event.
 ///
-/// Entity key: `github/issue/{owner}/{repo}/{number}`
-pub fn issue_to_event(owner: &str, repo: &str, issue: &GitHubIssue) -> FluxEvent {
-    FluxEvent {
-        event_id: Some(Uuid::now_v7().to_string()),
-        stream: "connectors".to_string(),
-        source: "connector-manager".to_string(),
-        timestamp: Utc::now().timestamp_millis(),
-        key: Some(format!("github/issue/{}/{}/{}", owner, repo, issue.number)),
-        schema: Some("github.issue".to_string()),
-        payload: serde_json::json!({
-            "entity_id": format!("github/issue/{}/{}/{}", owner, repo, issue.number),
-            "properties": {
-                "number": issue.number,
-                "title": issue.title,
-                "state": issue.state,
-                "author": issue.user.login,
-                "created_at": issue.created_at,
-                "updated_at": issue.updated_at,
-            }
-        }),
-    }
+/// Entity key: `[{namespace}/]github/issue/{owner}/{repo}/{number}`
+pub fn issue_to_event(
+    namespace: Option<&str>,
+    owner: &str,
+    repo: &str,
+    issue: &GitHubIssue,
+) -> Result<FluxEvent, BuildError> {
+    FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(namespaced(
+            namespace,
+            &format!("github/issue/{}/{}/{}", owner, repo, issue.number),
+        ))
+        .key_from_entity()
+        .schema("github.issue")
+        .property("number", issue.number)
+        .property("title", &issue.title)
+        .property("state", &issue.state)
+        .property("author", &issue.user.login)
+        .property("created_at", &issue.created_at)
+        .property("updated_at", &issue.updated_at)
+        .build()
 }
 
 #[cfg(test)]
@@ -89,6 +85,8 @@ mod tests {
     use crate::connectors::github::api::{
         GitHubIssue, GitHubNotification, GitHubRepo, IssueUser, NotificationSubject,
     };
+    use crate::connectors::golden;
+    use serde_json::Value;
 
     fn make_repo() -> GitHubRepo {
         GitHubRepo {
@@ -114,9 +112,7 @@ mod tests {
             subject: NotificationSubject {
                 title: "Fix the bug".to_string(),
                 subject_type: "Issue".to_string(),
-                url: Some(
-                    "https://api.github.com/repos/testuser/test-repo/issues/1".to_string(),
-                ),
+                url: Some("https://api.github.com/repos/testuser/test-repo/issues/1".to_string()),
             },
         }
     }
@@ -138,14 +134,17 @@ mod tests {
     #[test]
     fn test_repo_to_event() {
         let repo = make_repo();
-        let event = repo_to_event(&repo);
+        let event = repo_to_event(None, &repo).unwrap();
 
         assert_eq!(event.stream, "connectors");
         assert_eq!(event.source, "connector-manager");
         assert_eq!(event.key.unwrap(), "github/repo/testuser/test-repo");
         assert_eq!(event.schema.unwrap(), "github.repository");
         assert!(event.event_id.is_some());
-        assert_eq!(event.payload["properties"]["full_name"], "testuser/test-repo");
+        assert_eq!(
+            event.payload["properties"]["full_name"],
+            "testuser/test-repo"
+        );
         assert_eq!(event.payload["properties"]["stars"], 42);
         assert_eq!(event.payload["properties"]["language"], "Rust");
         assert_eq!(event.payload["properties"]["open_issues"], 5);
@@ -155,7 +154,7 @@ mod tests {
     #[test]
     fn test_notification_to_event() {
         let notif = make_notification();
-        let event = notification_to_event(&notif);
+        let event = notification_to_event(None, &notif).unwrap();
 
         assert_eq!(event.key.unwrap(), "github/notification/notif-1");
         assert_eq!(event.schema.unwrap(), "github.notification");
@@ -168,7 +167,7 @@ mod tests {
     #[test]
     fn test_issue_to_event() {
         let issue = make_issue();
-        let event = issue_to_event("testuser", "test-repo", &issue);
+        let event = issue_to_event(None, "testuser", "test-repo", &issue).unwrap();
 
         assert_eq!(event.key.unwrap(), "github/issue/testuser/test-repo/7");
         assert_eq!(event.schema.unwrap(), "github.issue");
@@ -177,4 +176,61 @@ mod tests {
         assert_eq!(event.payload["properties"]["author"], "testuser");
         assert_eq!(event.payload["properties"]["state"], "open");
     }
+
+    #[test]
+    fn test_events_under_namespace() {
+        let alice = Some("alice");
+
+        let event = repo_to_event(alice, &make_repo()).unwrap();
+        assert_eq!(
+            event.key.as_deref(),
+            Some("alice/github/repo/testuser/test-repo")
+        );
+        assert_eq!(
+            event.payload["entity_id"],
+            "alice/github/repo/testuser/test-repo"
+        );
+
+        let event = notification_to_event(alice, &make_notification()).unwrap();
+        assert_eq!(
+            event.payload["entity_id"],
+            "alice/github/notification/notif-1"
+        );
+
+        let event = issue_to_event(alice, "testuser", "test-repo", &make_issue()).unwrap();
+        assert_eq!(
+            event.payload["entity_id"],
+            "alice/github/issue/testuser/test-repo/7"
+        );
+        assert_eq!(
+            event.key.as_deref(),
+            Some("alice/github/issue/testuser/test-repo/7")
+        );
+    }
+
+    /// Events for a captured response of each endpoint the connector polls
+    fn transform_fixture(case: &str, fixture: Value) -> anyhow::Result<Vec<FluxEvent>> {
+        let events: Result<Vec<_>, _> = match case {
+            "repos" => serde_json::from_value::<Vec<GitHubRepo>>(fixture)?
+                .iter()
+                .map(|repo| repo_to_event(None, repo))
+                .collect(),
+            "notifications" => serde_json::from_value::<Vec<GitHubNotification>>(fixture)?
+                .iter()
+                .map(|notification| notification_to_event(None, notification))
+                .collect(),
+            // Open issues and pull requests of octo-org/widgets
+            "issues" => serde_json::from_value::<Vec<GitHubIssue>>(fixture)?
+                .iter()
+                .map(|issue| issue_to_event(None, "octo-org", "widgets", issue))
+                .collect(),
+            _ => anyhow::bail!("No transformer for GitHub fixture '{}'", case),
+        };
+        Ok(events?)
+    }
+
+    #[test]
+    fn test_golden_fixtures() {
+        golden::check_fixtures("github", transform_fixture);
+    }
 }
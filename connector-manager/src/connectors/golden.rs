@@ -0,0 +1,240 @@
+//! Golden-file tests for connector transformers.
+//!
+//! Every connector keeps captured API responses in
+//! `tests/fixtures/{connector}/{case}.json`, sanitized of tokens, emails and
+//! private names, and the events its transformer emits for each in
+//! `{case}.golden.json` next to it. A connector's tests call
+//! [`check_fixtures`] with a function turning one fixture into events; a new
+//! connector needs a fixture for every kind of response it transforms.
+//!
+//! Event IDs and timestamps differ on every run and are replaced by
+//! placeholders before comparing, as is the deletion time of tombstones. To
+//! accept a change in the output, run the tests with
+//! `FLUX_REGENERATE_GOLDENS=1` and review the rewritten goldens.
+
+use anyhow::{bail, Context, Result};
+use flux::FluxEvent;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Set to rewrite the golden files from the current output instead of comparing
+pub const REGENERATE_ENV: &str = "FLUX_REGENERATE_GOLDENS";
+
+const GOLDEN_SUFFIX: &str = ".golden.json";
+
+/// Fields replaced by placeholders before comparing
+const VOLATILE_FIELDS: [&str; 3] = ["eventId", "timestamp", "received_at"];
+
+/// Tombstone property set to the time the tombstone was built
+const DELETED_AT: &str = "__deleted_at__";
+
+/// `tests/fixtures/{connector}` of this crate
+pub fn fixtures_dir(connector: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(connector)
+}
+
+/// Run `transform` over every fixture of `connector`, check each emitted event
+/// with [`check_event`] and compare them with the goldens.
+///
+/// `transform` gets the case name (the fixture's file stem) and its JSON.
+/// Panics listing every case that failed.
+pub fn check_fixtures<F>(connector: &str, transform: F)
+where
+    F: Fn(&str, Value) -> Result<Vec<FluxEvent>>,
+{
+    let dir = fixtures_dir(connector);
+    let regenerate = std::env::var_os(REGENERATE_ENV).is_some_and(|v| !v.is_empty());
+    let (cases, goldens) = list(&dir).unwrap();
+    assert!(!cases.is_empty(), "No fixtures in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for case in &cases {
+        if let Err(e) = check_case(&dir, case, &transform, regenerate) {
+            failures.push(format!("{}: {:#}", case, e));
+        }
+    }
+    for golden in goldens.iter().filter(|golden| !cases.contains(golden)) {
+        failures.push(format!("{}: golden file without a fixture", golden));
+    }
+    assert!(
+        failures.is_empty(),
+        "{} golden test failure(s) in {}:\n\n{}\n\nIf the new output is intended, rerun with {}=1 and review the diff",
+        failures.len(),
+        dir.display(),
+        failures.join("\n\n"),
+        REGENERATE_ENV
+    );
+}
+
+/// Checks every event a connector emits must pass: it is accepted by
+/// ingestion and its payload names an entity and its properties
+pub fn check_event(event: &FluxEvent) -> Result<()> {
+    event
+        .clone()
+        .validate_and_prepare()
+        .context("Fails ingestion validation")?;
+    match event.payload.get("entity_id") {
+        Some(Value::String(entity_id)) if !entity_id.is_empty() => {}
+        _ => bail!("Payload has no entity_id"),
+    }
+    let properties = event.payload.get("properties");
+    if !properties.is_some_and(Value::is_object) {
+        bail!("Payload has no properties object");
+    }
+    Ok(())
+}
+
+/// Case names of the fixtures and of the goldens in `dir`, sorted
+fn list(dir: &Path) -> Result<(Vec<String>, Vec<String>)> {
+    let mut cases = Vec::new();
+    let mut goldens = Vec::new();
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))?;
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if let Some(case) = name.strip_suffix(GOLDEN_SUFFIX) {
+            goldens.push(case.to_string());
+        } else if let Some(case) = name.strip_suffix(".json") {
+            cases.push(case.to_string());
+        }
+    }
+    cases.sort();
+    goldens.sort();
+    Ok((cases, goldens))
+}
+
+fn check_case<F>(dir: &Path, case: &str, transform: &F, regenerate: bool) -> Result<()>
+where
+    F: Fn(&str, Value) -> Result<Vec<FluxEvent>>,
+{
+    let fixture_path = dir.join(format!("{}.json", case));
+    let fixture: Value = serde_json::from_slice(
+        &std::fs::read(&fixture_path)
+            .with_context(|| format!("Failed to read {}", fixture_path.display()))?,
+    )
+    .context("Invalid fixture JSON")?;
+
+    let events = transform(case, fixture).context("Transform failed")?;
+    let mut emitted = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        check_event(event).with_context(|| format!("Event {}", i))?;
+        emitted.push(normalize(event)?);
+    }
+    let emitted = Value::Array(emitted);
+
+    let golden_path = dir.join(format!("{}{}", case, GOLDEN_SUFFIX));
+    if regenerate {
+        let json = serde_json::to_string_pretty(&emitted)? + "\n";
+        return std::fs::write(&golden_path, json)
+            .with_context(|| format!("Failed to write {}", golden_path.display()));
+    }
+    let golden: Value = match std::fs::read(&golden_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .with_context(|| format!("Invalid golden file {}", golden_path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            bail!("Missing golden file {}", golden_path.display())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if golden != emitted {
+        bail!(
+            "Events differ from {}\n--- expected\n{}\n--- actual\n{}",
+            golden_path.display(),
+            serde_json::to_string_pretty(&golden)?,
+            serde_json::to_string_pretty(&emitted)?
+        );
+    }
+    Ok(())
+}
+
+/// `event` as JSON with its volatile fields replaced by `"<field>"`
+fn normalize(event: &FluxEvent) -> Result<Value> {
+    let mut value = serde_json::to_value(event)?;
+    if let Some(fields) = value.as_object_mut() {
+        for field in VOLATILE_FIELDS {
+            if let Some(value) = fields.get_mut(field) {
+                *value = Value::String(format!("<{}>", field));
+            }
+        }
+    }
+    if let Some(deleted_at) = value
+        .pointer_mut("/payload/properties")
+        .and_then(|properties| properties.get_mut(DELETED_AT))
+    {
+        *deleted_at = Value::String(format!("<{}>", DELETED_AT));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flux::event::FluxEventBuilder;
+    use serde_json::json;
+
+    fn transform(_case: &str, fixture: Value) -> Result<Vec<FluxEvent>> {
+        let event = FluxEventBuilder::new("connectors", "test")
+            .entity(format!("test/{}", fixture["id"]))
+            .property("name", &fixture["name"])
+            .build()?;
+        Ok(vec![event])
+    }
+
+    #[test]
+    fn test_normalize_tombstone() {
+        let tombstone = FluxEventBuilder::tombstone("test/1", "test").unwrap();
+        let value = normalize(&tombstone).unwrap();
+        assert_eq!(value["timestamp"], "<timestamp>");
+        assert_eq!(value["payload"]["properties"]["__deleted__"], true);
+        assert_eq!(
+            value["payload"]["properties"]["__deleted_at__"],
+            "<__deleted_at__>"
+        );
+    }
+
+    #[test]
+    fn test_regenerate_then_compare() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = dir.path().join("item.json");
+        std::fs::write(&fixture, r#"{"id": 1, "name": "first"}"#).unwrap();
+
+        let missing = check_case(dir.path(), "item", &transform, false).unwrap_err();
+        assert!(format!("{:#}", missing).contains("Missing golden file"));
+
+        check_case(dir.path(), "item", &transform, true).unwrap();
+        let golden: Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("item.golden.json")).unwrap())
+                .unwrap();
+        assert_eq!(golden[0]["eventId"], "<eventId>");
+        assert_eq!(golden[0]["timestamp"], "<timestamp>");
+        assert_eq!(golden[0]["payload"]["entity_id"], "test/1");
+        // Same output on a later run despite new IDs and timestamps
+        check_case(dir.path(), "item", &transform, false).unwrap();
+
+        std::fs::write(&fixture, r#"{"id": 1, "name": "renamed"}"#).unwrap();
+        let changed = check_case(dir.path(), "item", &transform, false).unwrap_err();
+        assert!(format!("{:#}", changed).contains("Events differ"));
+
+        let (cases, goldens) = list(dir.path()).unwrap();
+        assert_eq!(cases, ["item"]);
+        assert_eq!(goldens, ["item"]);
+    }
+
+    #[test]
+    fn test_check_event_requires_entity_payload() {
+        let mut event = transform("item", json!({"id": 1, "name": "first"})).unwrap()[0].clone();
+        check_event(&event).unwrap();
+
+        event.payload = json!({"properties": {"name": "first"}});
+        assert!(check_event(&event).is_err());
+
+        event.payload = json!({"entity_id": "test/1", "properties": "first"});
+        assert!(check_event(&event).is_err());
+
+        event.payload = json!({"entity_id": "test/1", "properties": {}});
+        event.stream = "Not A Stream".to_string();
+        assert!(check_event(&event).is_err());
+    }
+}
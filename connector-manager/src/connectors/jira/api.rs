@@ -0,0 +1,392 @@
+use anyhow::Context;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+use super::config::BASE_URL;
+use crate::ConnectorError;
+
+type Result<T> = std::result::Result<T, ConnectorError>;
+
+/// Issues per search request (Jira's maximum when fields are listed)
+const SEARCH_PAGE_SIZE: u32 = 100;
+
+/// Sprints per board request (the agile API's maximum)
+const SPRINT_PAGE_SIZE: u32 = 50;
+
+/// Requests per search or sprint listing and poll. Issues come oldest update
+/// first, so those beyond this are fetched by the next poll.
+pub const MAX_REQUESTS_PER_LISTING: usize = 20;
+
+/// A Jira site the authorization grants access to.
+#[derive(Debug, Deserialize)]
+pub struct AccessibleResource {
+    /// Cloud ID, the site's part of every API URL
+    pub id: String,
+    /// Site URL, e.g. `https://acme.atlassian.net`
+    pub url: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Jira issue with the requested fields.
+#[derive(Debug, Deserialize)]
+pub struct JiraIssue {
+    pub id: String,
+    pub key: String,
+    /// Field ID to value, e.g. `"summary"` or `"customfield_10016"`
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+/// One response of an enhanced JQL search.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    #[serde(default)]
+    pub issues: Vec<JiraIssue>,
+    #[serde(default)]
+    pub next_page_token: Option<String>,
+}
+
+/// Issues found by [`JiraClient::search`].
+#[derive(Debug)]
+pub struct SearchResult {
+    pub issues: Vec<JiraIssue>,
+    /// False if more issues matched than [`MAX_REQUESTS_PER_LISTING`] pages
+    pub complete: bool,
+}
+
+/// Sprint of a Jira Software board.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sprint {
+    pub id: u64,
+    pub name: String,
+    /// `future`, `active` or `closed`
+    pub state: String,
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub complete_date: Option<String>,
+    #[serde(default)]
+    pub origin_board_id: Option<u64>,
+    #[serde(default)]
+    pub goal: Option<String>,
+}
+
+/// One response of a board's sprint listing.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SprintPage {
+    #[serde(default)]
+    pub values: Vec<Sprint>,
+    #[serde(default = "default_true")]
+    pub is_last: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// HTTP client for the Jira Cloud REST and agile APIs.
+///
+/// Authenticates with a Bearer token. Every call but
+/// [`accessible_resources`](Self::accessible_resources) addresses one site
+/// by its cloud ID.
+pub struct JiraClient {
+    access_token: String,
+    http_client: Client,
+    base_url: String,
+}
+
+impl JiraClient {
+    /// Create a client using the default Atlassian API base URL.
+    pub fn new(access_token: String) -> Self {
+        Self::with_base_url(access_token, BASE_URL.to_string())
+    }
+
+    /// Create a client with a custom base URL (for testing with a mock server).
+    pub fn with_base_url(access_token: String, base_url: String) -> Self {
+        let http_client = crate::http::client().expect("Failed to build HTTP client");
+        Self {
+            access_token,
+            http_client,
+            base_url,
+        }
+    }
+
+    /// Endpoint listing the sites the token grants access to
+    pub fn accessible_resources_url(&self) -> String {
+        format!("{}/oauth/token/accessible-resources", self.base_url)
+    }
+
+    /// JQL search endpoint of the site `cloud_id`
+    pub fn search_url(&self, cloud_id: &str) -> String {
+        format!(
+            "{}/ex/jira/{}/rest/api/3/search/jql",
+            self.base_url, cloud_id
+        )
+    }
+
+    /// Sprint listing endpoint of `board_id` on the site `cloud_id`
+    pub fn board_sprints_url(&self, cloud_id: &str, board_id: u64) -> String {
+        format!(
+            "{}/ex/jira/{}/rest/agile/1.0/board/{}/sprint",
+            self.base_url, cloud_id, board_id
+        )
+    }
+
+    /// Sites the token grants access to.
+    pub async fn accessible_resources(&self) -> Result<Vec<AccessibleResource>> {
+        self.get(&self.accessible_resources_url(), &[]).await
+    }
+
+    /// Issues matching `jql` with `fields`, following `nextPageToken` for at
+    /// most [`MAX_REQUESTS_PER_LISTING`] requests.
+    pub async fn search(&self, cloud_id: &str, jql: &str, fields: &[&str]) -> Result<SearchResult> {
+        let url = self.search_url(cloud_id);
+        let fields = fields.join(",");
+        let page_size = SEARCH_PAGE_SIZE.to_string();
+        let mut issues = Vec::new();
+        let mut token: Option<String> = None;
+
+        for _ in 0..MAX_REQUESTS_PER_LISTING {
+            let page: SearchResponse = {
+                let mut query = vec![
+                    ("jql", jql),
+                    ("fields", fields.as_str()),
+                    ("maxResults", page_size.as_str()),
+                ];
+                if let Some(token) = &token {
+                    query.push(("nextPageToken", token.as_str()));
+                }
+                self.get(&url, &query).await?
+            };
+            issues.extend(page.issues);
+            match page.next_page_token {
+                Some(next) => token = Some(next),
+                None => {
+                    return Ok(SearchResult {
+                        issues,
+                        complete: true,
+                    })
+                }
+            }
+        }
+        Ok(SearchResult {
+            issues,
+            complete: false,
+        })
+    }
+
+    /// Active and future sprints of `board_id`.
+    pub async fn board_sprints(&self, cloud_id: &str, board_id: u64) -> Result<Vec<Sprint>> {
+        let url = self.board_sprints_url(cloud_id, board_id);
+        let page_size = SPRINT_PAGE_SIZE.to_string();
+        let mut sprints = Vec::new();
+
+        for _ in 0..MAX_REQUESTS_PER_LISTING {
+            let start_at = sprints.len().to_string();
+            let query = [
+                ("state", "active,future"),
+                ("startAt", start_at.as_str()),
+                ("maxResults", page_size.as_str()),
+            ];
+            let page: SprintPage = self.get(&url, &query).await?;
+            let done = page.is_last || page.values.is_empty();
+            sprints.extend(page.values);
+            if done {
+                break;
+            }
+        }
+        Ok(sprints)
+    }
+
+    /// One sprint, whatever its state.
+    pub async fn sprint(&self, cloud_id: &str, sprint_id: u64) -> Result<Sprint> {
+        let url = format!(
+            "{}/ex/jira/{}/rest/agile/1.0/sprint/{}",
+            self.base_url, cloud_id, sprint_id
+        );
+        self.get(&url, &[]).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        let response = self
+            .http_client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .header("Accept", "application/json")
+            .query(query)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", url))?;
+        check_response_status(&response)?;
+        let body = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", url))?;
+        Ok(body)
+    }
+}
+
+/// Check the response status and classify failures for the scheduler.
+///
+/// - 401 → `AuthExpired`
+/// - 429 → `RateLimited`, waiting `Retry-After` seconds
+/// - 400 → `Permanent` (usually invalid JQL)
+/// - 403/404 → `Permanent` (missing scope, or no access to the site or board)
+/// - 5xx → `Transient`
+/// - Other non-2xx → `Permanent`
+fn check_response_status(response: &reqwest::Response) -> Result<()> {
+    match response.status() {
+        s if s.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED => Err(ConnectorError::AuthExpired),
+        StatusCode::TOO_MANY_REQUESTS => Err(ConnectorError::RateLimited {
+            retry_after: response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs),
+        }),
+        StatusCode::BAD_REQUEST => Err(ConnectorError::Permanent(
+            "Jira rejected the request; check the configured JQL".to_string(),
+        )),
+        s @ (StatusCode::FORBIDDEN | StatusCode::NOT_FOUND) => Err(ConnectorError::Permanent(
+            format!("Jira API error: {} (missing scope or no access)", s),
+        )),
+        s if s.is_server_error() => {
+            Err(ConnectorError::Transient(format!("Jira API error: {}", s)))
+        }
+        s => Err(ConnectorError::Permanent(format!("Jira API error: {}", s))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    const CLOUD_ID: &str = "11223344-a1b2-3b33-c444-def123456789";
+
+    fn issue(key: &str) -> Value {
+        json!({
+            "id": "10001",
+            "key": key,
+            "fields": {"summary": format!("Issue {}", key)}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_search_follows_page_token() {
+        let mut server = Server::new_async().await;
+        let path = format!("/ex/jira/{}/rest/api/3/search/jql", CLOUD_ID);
+        let first = server
+            .mock("GET", path.as_str())
+            .match_header("authorization", "Bearer secret_token")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("jql".into(), "project in (OPS)".into()),
+                Matcher::UrlEncoded("fields".into(), "summary,status".into()),
+                Matcher::UrlEncoded("maxResults".into(), "100".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "issues": [issue("OPS-1"), issue("OPS-2")],
+                    "nextPageToken": "page-2"
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", path.as_str())
+            .match_query(Matcher::UrlEncoded("nextPageToken".into(), "page-2".into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"issues": [issue("OPS-3")], "isLast": true}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = JiraClient::with_base_url("secret_token".to_string(), server.url());
+        let result = client
+            .search(CLOUD_ID, "project in (OPS)", &["summary", "status"])
+            .await
+            .unwrap();
+        first.assert_async().await;
+        second.assert_async().await;
+        let keys: Vec<&str> = result.issues.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, vec!["OPS-1", "OPS-2", "OPS-3"]);
+        assert!(result.complete);
+    }
+
+    #[tokio::test]
+    async fn test_board_sprints_pages_by_offset() {
+        let mut server = Server::new_async().await;
+        let path = "/ex/jira/11223344-a1b2-3b33-c444-def123456789/rest/agile/1.0/board/12/sprint";
+        let sprint =
+            |id: u64| json!({"id": id, "name": format!("Sprint {}", id), "state": "future"});
+        let _first = server
+            .mock("GET", path)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("state".into(), "active,future".into()),
+                Matcher::UrlEncoded("startAt".into(), "0".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"isLast": false, "values": [sprint(7), sprint(8)]}).to_string())
+            .create_async()
+            .await;
+        let _second = server
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("startAt".into(), "2".into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"isLast": true, "values": [sprint(9)]}).to_string())
+            .create_async()
+            .await;
+
+        let client = JiraClient::with_base_url("secret_token".to_string(), server.url());
+        let sprints = client.board_sprints(CLOUD_ID, 12).await.unwrap();
+        let ids: Vec<u64> = sprints.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![7, 8, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_classified() {
+        let mut server = Server::new_async().await;
+        let client = JiraClient::with_base_url("secret_token".to_string(), server.url());
+
+        // Status, Retry-After header, expected error
+        type Case = (usize, Option<&'static str>, fn(&ConnectorError) -> bool);
+        let cases: [Case; 5] = [
+            (401, None, |e| matches!(e, ConnectorError::AuthExpired)),
+            (
+                429,
+                Some("30"),
+                |e| matches!(e, ConnectorError::RateLimited { retry_after } if *retry_after == Some(Duration::from_secs(30))),
+            ),
+            (400, None, |e| matches!(e, ConnectorError::Permanent(_))),
+            (403, None, |e| matches!(e, ConnectorError::Permanent(_))),
+            (503, None, |e| matches!(e, ConnectorError::Transient(_))),
+        ];
+        for (status, retry_after, expected) in cases {
+            let mut mock = server
+                .mock("GET", "/oauth/token/accessible-resources")
+                .with_status(status);
+            if let Some(secs) = retry_after {
+                mock = mock.with_header("Retry-After", secs);
+            }
+            let mock = mock.create_async().await;
+            let err = client.accessible_resources().await.unwrap_err();
+            assert!(expected(&err), "{}: got {:?}", status, err);
+            mock.remove_async().await;
+        }
+    }
+}
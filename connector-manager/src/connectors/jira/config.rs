@@ -0,0 +1,188 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Jira Cloud REST APIs, reached through `/ex/jira/{cloud_id}`
+pub const BASE_URL: &str = "https://api.atlassian.com";
+/// `audience` and `prompt` are required by Atlassian's 3LO flow
+pub const AUTH_URL: &str =
+    "https://auth.atlassian.com/authorize?audience=api.atlassian.com&prompt=consent";
+pub const TOKEN_URL: &str = "https://auth.atlassian.com/oauth/token";
+/// `offline_access` grants the refresh token; the agile API needs the
+/// granular `jira-software` scopes
+pub const SCOPES: &[&str] = &[
+    "read:jira-work",
+    "read:jira-user",
+    "read:board-scope:jira-software",
+    "read:sprint:jira-software",
+    "offline_access",
+];
+
+/// Field of the "Story point estimate" of team-managed projects
+pub const DEFAULT_STORY_POINTS_FIELD: &str = "customfield_10016";
+
+/// Project keys one user may sync
+pub const MAX_PROJECTS: usize = 50;
+
+/// A user's Jira configuration, set through
+/// `PUT /api/connectors/builtin/:user_id/jira/config`.
+///
+/// ```json
+/// {"project_keys": ["OPS", "WEB"], "board_id": 12}
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JiraUserConfig {
+    /// Issues to sync as a JQL query, without `ORDER BY`
+    #[serde(default)]
+    pub jql: Option<String>,
+    /// Issues to sync as project keys, instead of `jql`
+    #[serde(default)]
+    pub project_keys: Vec<String>,
+    /// Board whose active and future sprints are synced
+    #[serde(default)]
+    pub board_id: Option<u64>,
+    /// Site to sync (`acme` or `acme.atlassian.net`) when the authorization
+    /// grants several; the first one otherwise
+    #[serde(default)]
+    pub site: Option<String>,
+    /// Custom field holding story points
+    #[serde(default)]
+    pub story_points_field: Option<String>,
+}
+
+impl JiraUserConfig {
+    /// Parses and checks a stored configuration; `null` syncs nothing.
+    pub fn from_value(config: &Value) -> Result<Self, String> {
+        if config.is_null() {
+            return Ok(Self::default());
+        }
+        // serde would also read a struct from an array
+        if !config.is_object() {
+            return Err("Invalid Jira config: expected an object".to_string());
+        }
+        let parsed: Self = serde_json::from_value(config.clone())
+            .map_err(|e| format!("Invalid Jira config: {}", e))?;
+        if let Some(jql) = &parsed.jql {
+            if !parsed.project_keys.is_empty() {
+                return Err("Set either jql or project_keys, not both".to_string());
+            }
+            if jql.trim().is_empty() {
+                return Err("jql is empty".to_string());
+            }
+            // It is combined with the incremental bound and ordering
+            if jql.to_ascii_lowercase().contains("order by") {
+                return Err("jql must not contain ORDER BY".to_string());
+            }
+        }
+        if parsed.project_keys.len() > MAX_PROJECTS {
+            return Err(format!(
+                "At most {} Jira projects can be synced, got {}",
+                MAX_PROJECTS,
+                parsed.project_keys.len()
+            ));
+        }
+        if let Some(key) = parsed.project_keys.iter().find(|key| !is_project_key(key)) {
+            return Err(format!(
+                "Invalid Jira project key '{}': expected e.g. OPS or DATA_ENG",
+                key
+            ));
+        }
+        if let Some(field) = &parsed.story_points_field {
+            if !is_custom_field(field) {
+                return Err(format!(
+                    "Invalid story_points_field '{}': expected customfield_<number>",
+                    field
+                ));
+            }
+        }
+        if parsed
+            .site
+            .as_deref()
+            .is_some_and(|site| site.trim().is_empty())
+        {
+            return Err("site is empty".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// JQL selecting the issues to sync, if any are configured
+    pub fn issue_jql(&self) -> Option<String> {
+        match &self.jql {
+            Some(jql) => Some(jql.trim().to_string()),
+            None if self.project_keys.is_empty() => None,
+            None => Some(format!("project in ({})", self.project_keys.join(", "))),
+        }
+    }
+
+    /// Custom field holding story points
+    pub fn story_points_field(&self) -> &str {
+        self.story_points_field
+            .as_deref()
+            .unwrap_or(DEFAULT_STORY_POINTS_FIELD)
+    }
+}
+
+/// `OPS`, `WEB2`, `DATA_ENG`
+fn is_project_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn is_custom_field(field: &str) -> bool {
+    field
+        .strip_prefix("customfield_")
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_user_config() {
+        let config = JiraUserConfig::from_value(&Value::Null).unwrap();
+        assert_eq!(config, JiraUserConfig::default());
+        assert_eq!(config.issue_jql(), None);
+        assert_eq!(config.story_points_field(), DEFAULT_STORY_POINTS_FIELD);
+
+        let config = JiraUserConfig::from_value(&json!({
+            "project_keys": ["OPS", "DATA_ENG"],
+            "board_id": 12,
+            "story_points_field": "customfield_10028"
+        }))
+        .unwrap();
+        assert_eq!(
+            config.issue_jql().as_deref(),
+            Some("project in (OPS, DATA_ENG)")
+        );
+        assert_eq!(config.board_id, Some(12));
+        assert_eq!(config.story_points_field(), "customfield_10028");
+
+        let config =
+            JiraUserConfig::from_value(&json!({"jql": " assignee = currentUser() "})).unwrap();
+        assert_eq!(
+            config.issue_jql().as_deref(),
+            Some("assignee = currentUser()")
+        );
+
+        for invalid in [
+            json!({"jql": "project = OPS", "project_keys": ["OPS"]}),
+            json!({"jql": "project = OPS ORDER BY created"}),
+            json!({"jql": "  "}),
+            json!({"project_keys": ["ops"]}),
+            json!({"project_keys": ["OPS) OR (project = X"]}),
+            json!({"story_points_field": "summary"}),
+            json!({"site": ""}),
+            json!({"board": 12}),
+            json!([]),
+        ] {
+            assert!(
+                JiraUserConfig::from_value(&invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+}
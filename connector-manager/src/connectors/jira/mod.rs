@@ -0,0 +1,507 @@
+pub mod api;
+pub mod config;
+pub mod transformer;
+
+use crate::{Connector, ConnectorError, Credentials, ETagCache, OAuthConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flux::credentials::CachedETag;
+use flux::FluxEvent;
+use serde_json::Value;
+
+use self::api::{JiraClient, JiraIssue};
+use self::config::{JiraUserConfig, AUTH_URL, BASE_URL, SCOPES, TOKEN_URL};
+use self::transformer::{issue_to_event, sprint_to_event, ISSUE_FIELDS};
+
+/// Jira connector — syncs the issues each user configured (see
+/// [`JiraUserConfig`]) as `jira/issue/{key}` entities, and the sprints of
+/// their board as `jira/sprint/{id}`.
+///
+/// The ETag cache keeps what has to survive between polls:
+/// - the site's cloud ID, resolved once from the accessible resources
+/// - when the issue search last completed, keyed by its URL and JQL, so a
+///   changed JQL starts over with a full sync
+/// - the sprints the board listed, so one that closes is fetched once more
+pub struct JiraConnector {
+    base_url: String,
+}
+
+impl JiraConnector {
+    /// Create a connector using the real Atlassian API base URL.
+    pub fn new() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    /// Create a connector with a custom API base URL (for testing).
+    pub fn with_base_url(base_url: String) -> Self {
+        Self { base_url }
+    }
+}
+
+impl Default for JiraConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Connector for JiraConnector {
+    fn name(&self) -> &str {
+        "jira"
+    }
+
+    fn oauth_config(&self) -> OAuthConfig {
+        OAuthConfig {
+            auth_url: AUTH_URL.to_string(),
+            token_url: TOKEN_URL.to_string(),
+            scopes: SCOPES.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Syncs nothing: which issues to sync is per-user configuration.
+    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+        self.fetch_configured(credentials, &Value::Null, None, &mut ETagCache::default())
+            .await
+    }
+
+    async fn fetch_configured(
+        &self,
+        credentials: &Credentials,
+        config: &Value,
+        namespace: Option<&str>,
+        etags: &mut ETagCache,
+    ) -> Result<Vec<FluxEvent>, ConnectorError> {
+        let config = JiraUserConfig::from_value(config).map_err(ConnectorError::Permanent)?;
+        let jql = config.issue_jql();
+        if jql.is_none() && config.board_id.is_none() {
+            return Ok(Vec::new());
+        }
+        let client =
+            JiraClient::with_base_url(credentials.access_token.clone(), self.base_url.clone());
+        let cloud_id = resolve_cloud_id(&client, config.site.as_deref(), etags).await?;
+        let mut events = Vec::new();
+
+        if let Some(jql) = jql {
+            let synced_key = format!("{}?jql={}", client.search_url(&cloud_id), jql);
+            let since = etags
+                .etag(&synced_key)
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .map(|time| time.with_timezone(&Utc));
+            let polled_at = Utc::now();
+            let story_points_field = config.story_points_field();
+            let mut fields = ISSUE_FIELDS.to_vec();
+            fields.push(story_points_field);
+
+            let result = client
+                .search(&cloud_id, &incremental_jql(&jql, since, polled_at), &fields)
+                .await?;
+            for issue in &result.issues {
+                events.push(issue_to_event(namespace, issue, story_points_field)?);
+            }
+            // A search cut short resumes from the last issue it got
+            let synced_until = if result.complete {
+                Some(polled_at)
+            } else {
+                result.issues.last().and_then(updated_at)
+            };
+            if let Some(synced_until) = synced_until {
+                etags.insert(
+                    synced_key,
+                    CachedETag {
+                        etag: synced_until.to_rfc3339(),
+                        items: Vec::new(),
+                    },
+                );
+            }
+        }
+
+        if let Some(board_id) = config.board_id {
+            match fetch_sprints(&client, &cloud_id, board_id, namespace, etags).await {
+                Ok(sprint_events) => events.extend(sprint_events),
+                Err(e) if is_fatal(&e) => return Err(e),
+                // Non-fatal: the issues are still published
+                Err(e) => tracing::warn!("Failed to fetch sprints of board {}: {}", board_id, e),
+            }
+        }
+
+        Ok(events)
+    }
+
+    fn validate_user_config(&self, config: &Value) -> Result<(), String> {
+        JiraUserConfig::from_value(config).map(|_| ())
+    }
+
+    fn poll_interval(&self) -> u64 {
+        120 // 2 minutes, so status changes show up promptly
+    }
+}
+
+/// `jql` ordered oldest update first and, after a completed sync, bounded to
+/// the issues updated since.
+///
+/// The bound is relative (`updated >= -7m`) as JQL dates are read in the
+/// user's time zone. It is rounded up to whole minutes plus one, so issues
+/// updated while the last poll ran are fetched again rather than missed.
+pub fn incremental_jql(jql: &str, since: Option<DateTime<Utc>>, now: DateTime<Utc>) -> String {
+    match since {
+        Some(since) => {
+            let seconds = (now - since).num_seconds().max(0);
+            let minutes = (seconds + 59) / 60 + 1;
+            format!(
+                "({}) AND updated >= -{}m ORDER BY updated ASC",
+                jql, minutes
+            )
+        }
+        None => format!("({}) ORDER BY updated ASC", jql),
+    }
+}
+
+/// Cloud ID of the configured site, resolved from the accessible resources
+/// unless cached with its site URL in `etags`
+async fn resolve_cloud_id(
+    client: &JiraClient,
+    site: Option<&str>,
+    etags: &mut ETagCache,
+) -> Result<String, ConnectorError> {
+    let cache_key = client.accessible_resources_url();
+    if let Some(cached) = etags.get(&cache_key) {
+        let wanted = |url: &String| site.is_none_or(|site| site_matches(url, site));
+        if cached.items.iter().any(wanted) {
+            return Ok(cached.etag.clone());
+        }
+    }
+
+    let resources = client.accessible_resources().await?;
+    let resource = match site {
+        Some(site) => resources.iter().find(|r| site_matches(&r.url, site)),
+        None => resources.first(),
+    };
+    let Some(resource) = resource else {
+        return Err(ConnectorError::Permanent(match site {
+            Some(site) => format!("Jira site '{}' is not authorized", site),
+            None => "The authorization grants access to no Jira site".to_string(),
+        }));
+    };
+    etags.insert(
+        cache_key,
+        CachedETag {
+            etag: resource.id.clone(),
+            items: vec![resource.url.clone()],
+        },
+    );
+    Ok(resource.id.clone())
+}
+
+/// Whether the site at `url` is `site`, given as `acme`, `acme.atlassian.net`
+/// or its URL
+fn site_matches(url: &str, site: &str) -> bool {
+    let host = |s: &str| {
+        s.trim()
+            .trim_start_matches("https://")
+            .trim_end_matches('/')
+            .to_ascii_lowercase()
+    };
+    let (url, site) = (host(url), host(site));
+    url == site || url == format!("{}.atlassian.net", site)
+}
+
+/// Events for the active and future sprints of `board_id`, and for those
+/// listed last poll but no longer (closed since)
+async fn fetch_sprints(
+    client: &JiraClient,
+    cloud_id: &str,
+    board_id: u64,
+    namespace: Option<&str>,
+    etags: &mut ETagCache,
+) -> Result<Vec<FluxEvent>, ConnectorError> {
+    let listed_key = client.board_sprints_url(cloud_id, board_id);
+    let mut sprints = client.board_sprints(cloud_id, board_id).await?;
+    let listed: Vec<String> = sprints.iter().map(|s| s.id.to_string()).collect();
+
+    let previously_listed = etags
+        .get(&listed_key)
+        .map(|cached| cached.items.clone())
+        .unwrap_or_default();
+    for id in previously_listed.iter().filter(|id| !listed.contains(id)) {
+        let Ok(id) = id.parse() else { continue };
+        match client.sprint(cloud_id, id).await {
+            Ok(sprint) => sprints.push(sprint),
+            Err(e) if is_fatal(&e) => return Err(e),
+            // Deleted, most likely
+            Err(e) => tracing::warn!("Failed to fetch Jira sprint {}: {}", id, e),
+        }
+    }
+
+    let events = sprints
+        .iter()
+        .map(|sprint| sprint_to_event(namespace, sprint))
+        .collect::<Result<Vec<_>, _>>()?;
+    etags.insert(
+        listed_key,
+        CachedETag {
+            etag: String::new(),
+            items: listed,
+        },
+    );
+    Ok(events)
+}
+
+/// Errors every later request of the poll would hit too
+fn is_fatal(error: &ConnectorError) -> bool {
+    matches!(
+        error,
+        ConnectorError::AuthExpired | ConnectorError::RateLimited { .. }
+    )
+}
+
+/// When `issue` was last updated (Jira formats it `2026-02-18T10:21:00.000+0000`)
+fn updated_at(issue: &JiraIssue) -> Option<DateTime<Utc>> {
+    let updated = issue.fields.get("updated")?.as_str()?;
+    DateTime::parse_from_str(updated, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    const CLOUD_ID: &str = "11223344-a1b2-3b33-c444-def123456789";
+    const SITE_URL: &str = "https://acme.atlassian.net";
+
+    fn credentials() -> Credentials {
+        Credentials {
+            access_token: "secret_token".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: None,
+        }
+    }
+
+    fn issue(key: &str, status_category: &str) -> Value {
+        json!({
+            "id": "10001",
+            "key": key,
+            "fields": {
+                "summary": format!("Issue {}", key),
+                "status": {"name": "Status", "statusCategory": {"key": status_category}},
+                "updated": "2026-02-18T10:21:00.000+0000"
+            }
+        })
+    }
+
+    /// ETags with the cloud ID already resolved
+    fn resolved_etags(client: &JiraClient) -> ETagCache {
+        let mut etags = ETagCache::default();
+        etags.insert(
+            client.accessible_resources_url(),
+            CachedETag {
+                etag: CLOUD_ID.to_string(),
+                items: vec![SITE_URL.to_string()],
+            },
+        );
+        etags
+    }
+
+    #[test]
+    fn test_connector_metadata() {
+        let connector = JiraConnector::new();
+        assert_eq!(connector.name(), "jira");
+        assert_eq!(connector.poll_interval(), 120);
+
+        let oauth = connector.oauth_config();
+        assert!(oauth.auth_url.contains("audience=api.atlassian.com"));
+        assert!(oauth.scopes.contains(&"offline_access".to_string()));
+
+        assert!(connector.validate_user_config(&Value::Null).is_ok());
+        assert!(connector
+            .validate_user_config(&json!({"project_keys": ["ops"]}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_incremental_jql() {
+        let now = Utc::now();
+        assert_eq!(
+            incremental_jql("project in (OPS)", None, now),
+            "(project in (OPS)) ORDER BY updated ASC"
+        );
+        for (elapsed, minutes) in [(0, 1), (60, 2), (61, 3), (120, 3)] {
+            assert_eq!(
+                incremental_jql("project = OPS", Some(now - Duration::seconds(elapsed)), now),
+                format!(
+                    "(project = OPS) AND updated >= -{}m ORDER BY updated ASC",
+                    minutes
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_updated_at() {
+        let issue: JiraIssue = serde_json::from_value(issue("OPS-1", "new")).unwrap();
+        assert_eq!(
+            updated_at(&issue).unwrap().to_rfc3339(),
+            "2026-02-18T10:21:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_site_matches() {
+        assert!(site_matches(SITE_URL, "acme"));
+        assert!(site_matches(SITE_URL, "ACME.atlassian.net"));
+        assert!(site_matches(SITE_URL, "https://acme.atlassian.net/"));
+        assert!(!site_matches(SITE_URL, "acme-staging"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_resolves_site_and_syncs_incrementally() {
+        let mut server = Server::new_async().await;
+        let resources = server
+            .mock("GET", "/oauth/token/accessible-resources")
+            .match_header("authorization", "Bearer secret_token")
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!([
+                    {
+                        "id": "99999999-0000-0000-0000-000000000000",
+                        "url": "https://other.atlassian.net",
+                        "name": "other"
+                    },
+                    {"id": CLOUD_ID, "url": SITE_URL, "name": "acme"}
+                ])
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let path = format!("/ex/jira/{}/rest/api/3/search/jql", CLOUD_ID);
+        let full = server
+            .mock("GET", path.as_str())
+            .match_query(Matcher::UrlEncoded(
+                "jql".into(),
+                "(project in (OPS)) ORDER BY updated ASC".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"issues": [issue("OPS-1", "new")], "isLast": true}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let connector = JiraConnector::with_base_url(server.url());
+        let config = json!({"project_keys": ["OPS"], "site": "acme"});
+        let mut etags = ETagCache::default();
+        let events = connector
+            .fetch_configured(&credentials(), &config, Some("alice"), &mut etags)
+            .await
+            .unwrap();
+        full.assert_async().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].key.as_deref(), Some("alice/jira/issue/OPS-1"));
+        assert_eq!(events[0].payload["properties"]["status_category"], "new");
+        let synced_key = format!("{}{}?jql=project in (OPS)", server.url(), path);
+        assert!(etags.etag(&synced_key).is_some());
+
+        // The next poll reuses the cloud ID and asks only for recent updates
+        let since = (Utc::now() - Duration::seconds(570)).to_rfc3339();
+        etags.insert(
+            synced_key,
+            CachedETag {
+                etag: since,
+                items: Vec::new(),
+            },
+        );
+        let recent = server
+            .mock("GET", path.as_str())
+            .match_query(Matcher::UrlEncoded(
+                "jql".into(),
+                "(project in (OPS)) AND updated >= -11m ORDER BY updated ASC".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"issues": [issue("OPS-1", "done")]}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let events = connector
+            .fetch_configured(&credentials(), &config, Some("alice"), &mut etags)
+            .await
+            .unwrap();
+        recent.assert_async().await;
+        resources.assert_async().await;
+        assert_eq!(events[0].payload["properties"]["status_category"], "done");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sprints_publishes_closed_ones_once() {
+        let mut server = Server::new_async().await;
+        let connector = JiraConnector::with_base_url(server.url());
+        let client = JiraClient::with_base_url("secret_token".to_string(), server.url());
+        let mut etags = resolved_etags(&client);
+        etags.insert(
+            client.board_sprints_url(CLOUD_ID, 12),
+            CachedETag {
+                etag: "2026-02-18T10:00:00+00:00".to_string(),
+                items: vec!["7".to_string(), "8".to_string()],
+            },
+        );
+        let _listing = server
+            .mock(
+                "GET",
+                format!("/ex/jira/{}/rest/agile/1.0/board/12/sprint", CLOUD_ID).as_str(),
+            )
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"isLast": true, "values": [
+                    {"id": 8, "name": "Sprint 8", "state": "active", "originBoardId": 12},
+                    {"id": 9, "name": "Sprint 9", "state": "future", "originBoardId": 12}
+                ]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let closed = server
+            .mock(
+                "GET",
+                format!("/ex/jira/{}/rest/agile/1.0/sprint/7", CLOUD_ID).as_str(),
+            )
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"id": 7, "name": "Sprint 7", "state": "closed", "originBoardId": 12})
+                    .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = json!({"board_id": 12});
+        let events = connector
+            .fetch_configured(&credentials(), &config, None, &mut etags)
+            .await
+            .unwrap();
+        closed.assert_async().await;
+        let keys: Vec<&str> = events.iter().filter_map(|e| e.key.as_deref()).collect();
+        assert_eq!(keys, ["jira/sprint/8", "jira/sprint/9", "jira/sprint/7"]);
+        assert_eq!(events[2].payload["properties"]["state"], "closed");
+        let listed = etags.get(&client.board_sprints_url(CLOUD_ID, 12)).unwrap();
+        assert_eq!(listed.items, ["8", "9"]);
+
+        // Sprint 7 isn't fetched again
+        connector
+            .fetch_configured(&credentials(), &config, None, &mut etags)
+            .await
+            .unwrap();
+        closed.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_without_configuration_makes_no_requests() {
+        let connector = JiraConnector::with_base_url("http://127.0.0.1:9".to_string());
+        let events = connector.fetch(&credentials()).await.unwrap();
+        assert!(events.is_empty());
+    }
+}
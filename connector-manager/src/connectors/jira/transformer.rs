@@ -0,0 +1,139 @@
+use flux::event::{BuildError, FluxEventBuilder};
+use flux::FluxEvent;
+use serde_json::Value;
+
+use super::api::{JiraIssue, Sprint};
+use crate::namespaced;
+
+const STREAM: &str = "connectors";
+const SOURCE: &str = "connector-manager";
+
+/// Issue fields [`issue_to_event`] reads, besides the story points field
+pub const ISSUE_FIELDS: &[&str] = &[
+    "summary",
+    "status",
+    "assignee",
+    "priority",
+    "labels",
+    "issuetype",
+    "project",
+    "updated",
+];
+
+/// Transform a Jira issue into a Flux event.
+///
+/// Entity key: `[{namespace}/]jira/issue/{key}`
+///
+/// `status_category` is Jira's `new`, `indeterminate` or `done`, whatever
+/// the project calls its statuses. Missing assignee, priority and story
+/// points are `null`.
+pub fn issue_to_event(
+    namespace: Option<&str>,
+    issue: &JiraIssue,
+    story_points_field: &str,
+) -> Result<FluxEvent, BuildError> {
+    let fields = &issue.fields;
+    let field = |name: &str| fields.get(name).unwrap_or(&Value::Null);
+    let labels = field("labels").as_array().cloned().unwrap_or_default();
+
+    FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(namespaced(namespace, &format!("jira/issue/{}", issue.key)))
+        .key_from_entity()
+        .schema("jira.issue")
+        .property("key", &issue.key)
+        .property("summary", field("summary"))
+        .property("status", &field("status")["name"])
+        .property("status_category", &field("status")["statusCategory"]["key"])
+        .property("assignee", &field("assignee")["displayName"])
+        .property("priority", &field("priority")["name"])
+        .property("labels", labels)
+        .property("story_points", field(story_points_field))
+        .property("issue_type", &field("issuetype")["name"])
+        .property("project", &field("project")["key"])
+        .property("updated", field("updated"))
+        .build()
+}
+
+/// Transform a board's sprint into a Flux event.
+///
+/// Entity key: `[{namespace}/]jira/sprint/{id}`
+pub fn sprint_to_event(namespace: Option<&str>, sprint: &Sprint) -> Result<FluxEvent, BuildError> {
+    FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(namespaced(namespace, &format!("jira/sprint/{}", sprint.id)))
+        .key_from_entity()
+        .schema("jira.sprint")
+        .property("name", &sprint.name)
+        .property("state", &sprint.state)
+        .property("goal", &sprint.goal)
+        .property("start_date", &sprint.start_date)
+        .property("end_date", &sprint.end_date)
+        .property("complete_date", &sprint.complete_date)
+        .property("board_id", sprint.origin_board_id)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::golden;
+    use crate::connectors::jira::api::{SearchResponse, SprintPage};
+    use crate::connectors::jira::config::DEFAULT_STORY_POINTS_FIELD;
+    use serde_json::json;
+
+    #[test]
+    fn test_issue_to_event() {
+        let issue: JiraIssue = serde_json::from_value(json!({
+            "id": "10042",
+            "key": "OPS-42",
+            "fields": {
+                "summary": "Rotate staging certificates",
+                "status": {"name": "Done", "statusCategory": {"key": "done", "name": "Done"}},
+                "assignee": null,
+                "labels": ["infra"],
+                "customfield_10028": 3.0,
+                "updated": "2026-02-18T10:21:00.000+0000"
+            }
+        }))
+        .unwrap();
+        let event = issue_to_event(Some("alice"), &issue, "customfield_10028").unwrap();
+
+        assert_eq!(event.stream, "connectors");
+        assert_eq!(event.key.as_deref(), Some("alice/jira/issue/OPS-42"));
+        assert_eq!(event.schema.as_deref(), Some("jira.issue"));
+        let properties = &event.payload["properties"];
+        assert_eq!(properties["status"], "Done");
+        assert_eq!(properties["status_category"], "done");
+        assert_eq!(properties["assignee"], Value::Null);
+        assert_eq!(properties["priority"], Value::Null);
+        assert_eq!(properties["labels"], json!(["infra"]));
+        assert_eq!(properties["story_points"], 3.0);
+    }
+
+    /// Events for captured search and sprint listing responses
+    fn transform_fixture(case: &str, fixture: Value) -> anyhow::Result<Vec<FluxEvent>> {
+        let events: Result<Vec<_>, _> = match case {
+            "search" => {
+                let response: SearchResponse = serde_json::from_value(fixture)?;
+                response
+                    .issues
+                    .iter()
+                    .map(|issue| issue_to_event(None, issue, DEFAULT_STORY_POINTS_FIELD))
+                    .collect()
+            }
+            "sprints" => {
+                let page: SprintPage = serde_json::from_value(fixture)?;
+                page.values
+                    .iter()
+                    .map(|sprint| sprint_to_event(None, sprint))
+                    .collect()
+            }
+            _ => anyhow::bail!("No transformer for Jira fixture '{}'", case),
+        };
+        Ok(events?)
+    }
+
+    #[test]
+    fn test_golden_fixtures() {
+        golden::check_fixtures("jira", transform_fixture);
+    }
+}
@@ -1 +1,7 @@
+pub mod external;
 pub mod github;
+#[cfg(test)]
+pub(crate) mod golden;
+pub mod jira;
+pub mod notion;
+pub mod weather;
@@ -0,0 +1,278 @@
+use anyhow::Context;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+use super::config::{BASE_URL, NOTION_VERSION};
+use crate::ConnectorError;
+
+type Result<T> = std::result::Result<T, ConnectorError>;
+
+/// Results per query request (Notion's maximum)
+const PAGE_SIZE: u32 = 100;
+
+/// Query requests per database and poll. Results come oldest edit first, so
+/// pages beyond this are fetched by the next poll.
+pub const MAX_REQUESTS_PER_QUERY: usize = 20;
+
+/// Where a Notion page lives.
+#[derive(Debug, Deserialize)]
+pub struct PageParent {
+    #[serde(default)]
+    pub database_id: Option<String>,
+}
+
+/// Notion page (a database row).
+#[derive(Debug, Deserialize)]
+pub struct NotionPage {
+    pub id: String,
+    pub created_time: String,
+    pub last_edited_time: String,
+    #[serde(default)]
+    pub archived: bool,
+    /// Newer API versions' name for `archived`
+    #[serde(default)]
+    pub in_trash: bool,
+    #[serde(default)]
+    pub url: Option<String>,
+    pub parent: PageParent,
+    /// Property name to typed value, e.g. `{"type": "checkbox", "checkbox": true}`
+    #[serde(default)]
+    pub properties: Map<String, Value>,
+}
+
+/// One response of a database query.
+#[derive(Debug, Deserialize)]
+pub struct QueryResponse {
+    pub results: Vec<NotionPage>,
+    #[serde(default)]
+    pub has_more: bool,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// HTTP client for the Notion API.
+///
+/// Authenticates with a Bearer token and pins the API version with the
+/// `Notion-Version` header.
+pub struct NotionClient {
+    access_token: String,
+    http_client: Client,
+    base_url: String,
+}
+
+impl NotionClient {
+    /// Create a client using the default Notion API base URL.
+    pub fn new(access_token: String) -> Self {
+        Self::with_base_url(access_token, BASE_URL.to_string())
+    }
+
+    /// Create a client with a custom base URL (for testing with a mock server).
+    pub fn with_base_url(access_token: String, base_url: String) -> Self {
+        let http_client = crate::http::client().expect("Failed to build HTTP client");
+        Self {
+            access_token,
+            http_client,
+            base_url,
+        }
+    }
+
+    /// Query endpoint of `database_id`
+    pub fn query_url(&self, database_id: &str) -> String {
+        format!("{}/v1/databases/{}/query", self.base_url, database_id)
+    }
+
+    /// Pages of `database_id` last edited at or after `since` (RFC 3339),
+    /// oldest edit first.
+    ///
+    /// Follows `next_cursor` while `has_more` is set, for at most
+    /// [`MAX_REQUESTS_PER_QUERY`] requests.
+    pub async fn query_database(
+        &self,
+        database_id: &str,
+        since: Option<&str>,
+    ) -> Result<Vec<NotionPage>> {
+        let url = self.query_url(database_id);
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_REQUESTS_PER_QUERY {
+            let mut body = json!({
+                "page_size": PAGE_SIZE,
+                "sorts": [{"timestamp": "last_edited_time", "direction": "ascending"}],
+            });
+            if let Some(since) = since {
+                body["filter"] = json!({
+                    "timestamp": "last_edited_time",
+                    "last_edited_time": {"on_or_after": since},
+                });
+            }
+            if let Some(cursor) = &cursor {
+                body["start_cursor"] = json!(cursor);
+            }
+
+            let response = self
+                .http_client
+                .post(&url)
+                .bearer_auth(&self.access_token)
+                .header("Notion-Version", NOTION_VERSION)
+                .json(&body)
+                .send()
+                .await
+                .context("Failed to send query_database request")?;
+            check_response_status(&response)?;
+            let page: QueryResponse = response
+                .json()
+                .await
+                .context("Failed to parse query_database response")?;
+
+            pages.extend(page.results);
+            match (page.has_more, page.next_cursor) {
+                (true, Some(next)) => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(pages)
+    }
+}
+
+/// Check the response status and classify failures for the scheduler.
+///
+/// - 401 → `AuthExpired`
+/// - 429 → `RateLimited`, waiting `Retry-After` seconds
+/// - 404 → `Permanent` (database missing or not shared with the integration)
+/// - 5xx → `Transient`
+/// - Other non-2xx → `Permanent`
+fn check_response_status(response: &reqwest::Response) -> Result<()> {
+    match response.status() {
+        s if s.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED => Err(ConnectorError::AuthExpired),
+        StatusCode::TOO_MANY_REQUESTS => Err(ConnectorError::RateLimited {
+            retry_after: response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs),
+        }),
+        StatusCode::NOT_FOUND => Err(ConnectorError::Permanent(
+            "Notion database not found or not shared with the integration".to_string(),
+        )),
+        s if s.is_server_error() => Err(ConnectorError::Transient(format!(
+            "Notion API error: {}",
+            s
+        ))),
+        s => Err(ConnectorError::Permanent(format!(
+            "Notion API error: {}",
+            s
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    const DATABASE_ID: &str = "d9824bdc84454327be8b5b47500af6ce";
+
+    fn page(id: &str, last_edited_time: &str) -> Value {
+        json!({
+            "object": "page",
+            "id": id,
+            "created_time": "2026-02-01T09:00:00.000Z",
+            "last_edited_time": last_edited_time,
+            "archived": false,
+            "parent": {"type": "database_id", "database_id": DATABASE_ID},
+            "properties": {}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_query_database_follows_cursor() {
+        let mut server = Server::new_async().await;
+        let path = format!("/v1/databases/{}/query", DATABASE_ID);
+        let first = server
+            .mock("POST", path.as_str())
+            .match_header("authorization", "Bearer secret_token")
+            .match_header("notion-version", NOTION_VERSION)
+            .match_body(Matcher::PartialJson(json!({
+                "page_size": 100,
+                "filter": {
+                    "timestamp": "last_edited_time",
+                    "last_edited_time": {"on_or_after": "2026-02-17T00:00:00.000Z"}
+                }
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "object": "list",
+                    "results": [page("page-1", "2026-02-17T08:00:00.000Z")],
+                    "has_more": true,
+                    "next_cursor": "cursor-2"
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let second = server
+            .mock("POST", path.as_str())
+            .match_body(Matcher::PartialJson(json!({"start_cursor": "cursor-2"})))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "object": "list",
+                    "results": [page("page-2", "2026-02-18T08:00:00.000Z")],
+                    "has_more": false,
+                    "next_cursor": null
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = NotionClient::with_base_url("secret_token".to_string(), server.url());
+        let pages = client
+            .query_database(DATABASE_ID, Some("2026-02-17T00:00:00.000Z"))
+            .await
+            .unwrap();
+        first.assert_async().await;
+        second.assert_async().await;
+        let ids: Vec<&str> = pages.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["page-1", "page-2"]);
+        assert_eq!(pages[0].parent.database_id.as_deref(), Some(DATABASE_ID));
+    }
+
+    #[tokio::test]
+    async fn test_query_database_classifies_errors() {
+        let mut server = Server::new_async().await;
+        let client = NotionClient::with_base_url("secret_token".to_string(), server.url());
+        let path = format!("/v1/databases/{}/query", DATABASE_ID);
+
+        // Status, Retry-After header, expected error
+        type Case = (usize, Option<&'static str>, fn(&ConnectorError) -> bool);
+        let cases: [Case; 4] = [
+            (401, None, |e| matches!(e, ConnectorError::AuthExpired)),
+            (
+                429,
+                Some("7"),
+                |e| matches!(e, ConnectorError::RateLimited { retry_after } if *retry_after == Some(Duration::from_secs(7))),
+            ),
+            (404, None, |e| matches!(e, ConnectorError::Permanent(_))),
+            (502, None, |e| matches!(e, ConnectorError::Transient(_))),
+        ];
+        for (status, retry_after, expected) in cases {
+            let mut mock = server.mock("POST", path.as_str()).with_status(status);
+            if let Some(secs) = retry_after {
+                mock = mock.with_header("Retry-After", secs);
+            }
+            let mock = mock.create_async().await;
+            let err = client.query_database(DATABASE_ID, None).await.unwrap_err();
+            assert!(expected(&err), "{}: got {:?}", status, err);
+            mock.remove_async().await;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+pub const BASE_URL: &str = "https://api.notion.com";
+/// `owner=user`: pages are shared by the authorizing user, not a workspace admin
+pub const AUTH_URL: &str = "https://api.notion.com/v1/oauth/authorize?owner=user";
+pub const TOKEN_URL: &str = "https://api.notion.com/v1/oauth/token";
+/// Notion has no scopes: the user picks the pages to share when authorizing
+pub const SCOPES: &[&str] = &[];
+/// `Notion-Version` header sent with every request
+pub const NOTION_VERSION: &str = "2022-06-28";
+
+/// Databases one user may sync
+pub const MAX_DATABASES: usize = 50;
+
+/// A user's Notion configuration, set through
+/// `PUT /api/connectors/builtin/:user_id/notion/config`.
+///
+/// ```json
+/// {"database_ids": ["d9824bdc84454327be8b5b47500af6ce"]}
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotionUserConfig {
+    /// Databases whose pages are synced, as shown in their URL (with or
+    /// without dashes)
+    pub database_ids: Vec<String>,
+}
+
+impl NotionUserConfig {
+    /// Parses and checks a stored configuration; `null` syncs nothing.
+    pub fn from_value(config: &Value) -> Result<Self, String> {
+        if config.is_null() {
+            return Ok(Self::default());
+        }
+        let parsed: Self = serde_json::from_value(config.clone())
+            .map_err(|e| format!("Invalid Notion config: {}", e))?;
+        if parsed.database_ids.len() > MAX_DATABASES {
+            return Err(format!(
+                "At most {} Notion databases can be synced, got {}",
+                MAX_DATABASES,
+                parsed.database_ids.len()
+            ));
+        }
+        if let Some(id) = parsed.database_ids.iter().find(|id| !is_notion_id(id)) {
+            return Err(format!(
+                "Invalid Notion database ID '{}': expected 32 hex digits",
+                id
+            ));
+        }
+        Ok(parsed)
+    }
+}
+
+/// 32 hex digits, optionally grouped with dashes like a UUID
+fn is_notion_id(id: &str) -> bool {
+    let digits: Vec<char> = id.chars().filter(|&c| c != '-').collect();
+    digits.len() == 32 && digits.iter().all(char::is_ascii_hexdigit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_user_config() {
+        assert_eq!(
+            NotionUserConfig::from_value(&Value::Null).unwrap(),
+            NotionUserConfig::default()
+        );
+        let config = NotionUserConfig::from_value(&json!({
+            "database_ids": [
+                "d9824bdc84454327be8b5b47500af6ce",
+                "d9824bdc-8445-4327-be8b-5b47500af6ce"
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.database_ids.len(), 2);
+
+        for invalid in [
+            json!({"database_ids": ["../users"]}),
+            json!({"database_ids": ["d9824bdc84454327be8b5b47500af6c"]}),
+            json!({"database_ids": "d9824bdc84454327be8b5b47500af6ce"}),
+            json!({"databases": []}),
+            json!([]),
+        ] {
+            assert!(
+                NotionUserConfig::from_value(&invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+        let too_many: Vec<String> = (0..=MAX_DATABASES).map(|i| format!("{:032x}", i)).collect();
+        let err = NotionUserConfig::from_value(&json!({"database_ids": too_many})).unwrap_err();
+        assert!(err.contains("At most"));
+    }
+}
@@ -0,0 +1,271 @@
+pub mod api;
+pub mod config;
+pub mod transformer;
+
+use crate::{ClientAuth, Connector, ConnectorError, Credentials, ETagCache, OAuthConfig};
+use async_trait::async_trait;
+use flux::credentials::CachedETag;
+use flux::FluxEvent;
+use serde_json::Value;
+
+use self::api::NotionClient;
+use self::config::{NotionUserConfig, AUTH_URL, BASE_URL, SCOPES, TOKEN_URL};
+use self::transformer::page_to_event;
+
+/// Notion connector — syncs the pages of the databases each user
+/// configured (see [`NotionUserConfig`]) as `notion/page/{id}` entities.
+///
+/// Each database is queried for pages edited since the last published poll.
+/// That `last_edited_time` is kept in the ETag cache under the database's
+/// query URL, so it only advances once the poll's events are published.
+pub struct NotionConnector {
+    base_url: String,
+}
+
+impl NotionConnector {
+    /// Create a connector using the real Notion API base URL.
+    pub fn new() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    /// Create a connector with a custom API base URL (for testing).
+    pub fn with_base_url(base_url: String) -> Self {
+        Self { base_url }
+    }
+}
+
+impl Default for NotionConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Connector for NotionConnector {
+    fn name(&self) -> &str {
+        "notion"
+    }
+
+    fn oauth_config(&self) -> OAuthConfig {
+        OAuthConfig {
+            auth_url: AUTH_URL.to_string(),
+            token_url: TOKEN_URL.to_string(),
+            scopes: SCOPES.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Syncs nothing: which databases to sync is per-user configuration.
+    async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+        self.fetch_configured(credentials, &Value::Null, None, &mut ETagCache::default())
+            .await
+    }
+
+    async fn fetch_configured(
+        &self,
+        credentials: &Credentials,
+        config: &Value,
+        namespace: Option<&str>,
+        etags: &mut ETagCache,
+    ) -> Result<Vec<FluxEvent>, ConnectorError> {
+        let config = NotionUserConfig::from_value(config).map_err(ConnectorError::Permanent)?;
+        let client =
+            NotionClient::with_base_url(credentials.access_token.clone(), self.base_url.clone());
+        let mut events = Vec::new();
+
+        for database_id in &config.database_ids {
+            let synced_key = client.query_url(database_id);
+            let since = etags.etag(&synced_key).map(str::to_string);
+            let pages = match client.query_database(database_id, since.as_deref()).await {
+                Ok(pages) => pages,
+                // Every later request would fail the same way
+                Err(e @ (ConnectorError::AuthExpired | ConnectorError::RateLimited { .. })) => {
+                    return Err(e);
+                }
+                Err(e) => {
+                    // Non-fatal: log and continue with remaining databases.
+                    tracing::warn!("Failed to query Notion database {}: {}", database_id, e);
+                    continue;
+                }
+            };
+            for page in &pages {
+                events.push(page_to_event(namespace, page)?);
+            }
+            // Same format throughout, so the latest sorts last
+            if let Some(latest) = pages.iter().map(|p| &p.last_edited_time).max() {
+                etags.insert(
+                    synced_key,
+                    CachedETag {
+                        etag: latest.clone(),
+                        items: Vec::new(),
+                    },
+                );
+            }
+        }
+
+        Ok(events)
+    }
+
+    fn validate_user_config(&self, config: &Value) -> Result<(), String> {
+        NotionUserConfig::from_value(config).map(|_| ())
+    }
+
+    fn client_auth(&self) -> ClientAuth {
+        ClientAuth::Basic
+    }
+
+    fn poll_interval(&self) -> u64 {
+        300 // 5 minutes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    const DATABASE_ID: &str = "d9824bdc84454327be8b5b47500af6ce";
+
+    fn credentials() -> Credentials {
+        Credentials {
+            access_token: "secret_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        }
+    }
+
+    fn query_response(pages: Value) -> String {
+        json!({"object": "list", "results": pages, "has_more": false, "next_cursor": null})
+            .to_string()
+    }
+
+    fn page(id: &str, last_edited_time: &str, archived: bool) -> Value {
+        json!({
+            "object": "page",
+            "id": id,
+            "created_time": "2026-02-01T09:00:00.000Z",
+            "last_edited_time": last_edited_time,
+            "archived": archived,
+            "parent": {"type": "database_id", "database_id": DATABASE_ID},
+            "properties": {
+                "Name": {"id": "title", "type": "title", "title": [{"plain_text": id}]}
+            }
+        })
+    }
+
+    #[test]
+    fn test_connector_metadata() {
+        let connector = NotionConnector::new();
+        assert_eq!(connector.name(), "notion");
+        assert_eq!(connector.poll_interval(), 300);
+        assert_eq!(connector.client_auth(), ClientAuth::Basic);
+
+        let oauth = connector.oauth_config();
+        assert!(oauth.auth_url.starts_with("https://api.notion.com/"));
+        assert!(oauth.token_url.starts_with("https://api.notion.com/"));
+        assert!(oauth.scopes.is_empty());
+
+        assert!(connector.validate_user_config(&Value::Null).is_ok());
+        assert!(connector
+            .validate_user_config(&json!({"database_ids": ["nope"]}))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_syncs_incrementally() {
+        let mut server = Server::new_async().await;
+        let path = format!("/v1/databases/{}/query", DATABASE_ID);
+        let full = server
+            .mock("POST", path.as_str())
+            .with_header("content-type", "application/json")
+            .with_body(query_response(json!([
+                page("page-1", "2026-02-18T10:21:00.000Z", false),
+                page("page-2", "2026-02-17T08:00:00.000Z", false)
+            ])))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let connector = NotionConnector::with_base_url(server.url());
+        let config = json!({"database_ids": [DATABASE_ID]});
+        let mut etags = ETagCache::default();
+        let events = connector
+            .fetch_configured(&credentials(), &config, Some("alice"), &mut etags)
+            .await
+            .unwrap();
+        full.assert_async().await;
+        full.remove_async().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].key.as_deref(), Some("alice/notion/page/page-1"));
+        assert_eq!(events[0].schema.as_deref(), Some("notion.page"));
+        let synced_key = format!("{}{}", server.url(), path);
+        assert_eq!(etags.etag(&synced_key), Some("2026-02-18T10:21:00.000Z"));
+
+        // The next poll asks only for pages edited since; an archived one is deleted
+        let since = server
+            .mock("POST", path.as_str())
+            .match_body(Matcher::PartialJson(json!({
+                "filter": {"last_edited_time": {"on_or_after": "2026-02-18T10:21:00.000Z"}}
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(query_response(json!([page(
+                "page-2",
+                "2026-02-18T11:00:00.000Z",
+                true
+            )])))
+            .expect(1)
+            .create_async()
+            .await;
+        let events = connector
+            .fetch_configured(&credentials(), &config, Some("alice"), &mut etags)
+            .await
+            .unwrap();
+        since.assert_async().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].stream, "flux.events.deletions");
+        assert_eq!(events[0].payload["entity_id"], "alice/notion/page/page-2");
+        assert_eq!(etags.etag(&synced_key), Some("2026-02-18T11:00:00.000Z"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_without_databases_makes_no_requests() {
+        let connector = NotionConnector::with_base_url("http://127.0.0.1:9".to_string());
+        let events = connector.fetch(&credentials()).await.unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_skips_unshared_database() {
+        let mut server = Server::new_async().await;
+        let missing = "00000000000000000000000000000000";
+        let _missing_mock = server
+            .mock("POST", format!("/v1/databases/{}/query", missing).as_str())
+            .with_status(404)
+            .create_async()
+            .await;
+        let _mock = server
+            .mock(
+                "POST",
+                format!("/v1/databases/{}/query", DATABASE_ID).as_str(),
+            )
+            .with_header("content-type", "application/json")
+            .with_body(query_response(json!([page(
+                "page-1",
+                "2026-02-18T10:21:00.000Z",
+                false
+            )])))
+            .create_async()
+            .await;
+
+        let connector = NotionConnector::with_base_url(server.url());
+        let config = json!({"database_ids": [missing, DATABASE_ID]});
+        let events = connector
+            .fetch_configured(&credentials(), &config, None, &mut ETagCache::default())
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].key.as_deref(), Some("notion/page/page-1"));
+    }
+}
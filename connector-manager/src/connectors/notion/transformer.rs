@@ -0,0 +1,208 @@
+use flux::event::{BuildError, FluxEventBuilder};
+use flux::FluxEvent;
+use serde_json::Value;
+
+use super::api::NotionPage;
+use crate::namespaced;
+
+const STREAM: &str = "connectors";
+const SOURCE: &str = "connector-manager";
+
+/// Transform a Notion database page into a Flux event, or into a tombstone
+/// once the page is archived.
+///
+/// Entity key: `[{namespace}/]notion/page/{id}`
+///
+/// Title, select, multi-select, number, date, checkbox and people properties
+/// become flat properties named after the Notion property, lowercased with
+/// other characters replaced by `_` (`Due Date` → `due_date`). A date range
+/// adds `{name}_end`. Other property types are skipped.
+pub fn page_to_event(namespace: Option<&str>, page: &NotionPage) -> Result<FluxEvent, BuildError> {
+    let entity_id = namespaced(namespace, &format!("notion/page/{}", page.id));
+    if page.archived || page.in_trash {
+        return FluxEventBuilder::tombstone(&entity_id, SOURCE);
+    }
+
+    let mut builder = FluxEventBuilder::new(STREAM, SOURCE)
+        .entity(entity_id)
+        .key_from_entity()
+        .schema("notion.page");
+    for (name, value) in &page.properties {
+        let name = property_name(name);
+        if name.is_empty() {
+            continue;
+        }
+        if let Some(flat) = flat_value(value) {
+            builder = builder.property(name.clone(), flat);
+        }
+        if let Some(end) = date_end(value) {
+            builder = builder.property(format!("{}_end", name), end);
+        }
+    }
+    // Page metadata wins over a Notion property of the same name
+    builder
+        .property("database_id", &page.parent.database_id)
+        .property("url", &page.url)
+        .property("created_time", &page.created_time)
+        .property("last_edited_time", &page.last_edited_time)
+        .build()
+}
+
+/// `name` lowercased, with runs of anything but letters and digits as `_`
+fn property_name(name: &str) -> String {
+    let mut flat = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            flat.push(c);
+        } else if !flat.is_empty() && !flat.ends_with('_') {
+            flat.push('_');
+        }
+    }
+    flat.trim_end_matches('_').to_string()
+}
+
+/// Plain value of a typed Notion property, `None` for unsupported types
+fn flat_value(property: &Value) -> Option<Value> {
+    let kind = property.get("type")?.as_str()?;
+    let value = property.get(kind).unwrap_or(&Value::Null);
+    let flat = match kind {
+        "title" => Value::String(plain_text(value)),
+        "select" => value.get("name").cloned().unwrap_or(Value::Null),
+        "multi_select" => Value::Array(
+            items(value)
+                .filter_map(|option| option.get("name").cloned())
+                .collect(),
+        ),
+        "number" | "checkbox" => value.clone(),
+        "date" => value.get("start").cloned().unwrap_or(Value::Null),
+        // Names, or IDs of users the integration can't see
+        "people" => Value::Array(
+            items(value)
+                .filter_map(|person| person.get("name").or_else(|| person.get("id")).cloned())
+                .collect(),
+        ),
+        _ => return None,
+    };
+    Some(flat)
+}
+
+/// End of a date range property, if it has one
+fn date_end(property: &Value) -> Option<Value> {
+    if property.get("type")?.as_str()? != "date" {
+        return None;
+    }
+    property["date"]
+        .get("end")
+        .filter(|end| !end.is_null())
+        .cloned()
+}
+
+/// Concatenated `plain_text` of a rich text array
+fn plain_text(rich_text: &Value) -> String {
+    items(rich_text)
+        .filter_map(|span| span.get("plain_text").and_then(Value::as_str))
+        .collect()
+}
+
+fn items(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::golden;
+    use crate::connectors::notion::api::QueryResponse;
+    use serde_json::json;
+
+    fn make_page(properties: Value) -> NotionPage {
+        serde_json::from_value(json!({
+            "id": "59833787-2cf9-4fdf-8782-e53db20768a5",
+            "created_time": "2026-02-01T09:00:00.000Z",
+            "last_edited_time": "2026-02-18T10:21:00.000Z",
+            "archived": false,
+            "url": "https://www.notion.so/Launch-plan-598337872cf94fdf8782e53db20768a5",
+            "parent": {"type": "database_id", "database_id": "d9824bdc-8445-4327-be8b-5b47500af6ce"},
+            "properties": properties
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_page_to_event() {
+        let page = make_page(json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": "Launch "},
+                {"type": "text", "plain_text": "plan"}
+            ]},
+            "Status": {"id": "a%3Ab", "type": "select", "select": {"id": "1", "name": "In progress", "color": "blue"}},
+            "Due Date": {"id": "c", "type": "date", "date": {"start": "2026-03-01", "end": "2026-03-05", "time_zone": null}},
+            "Notes": {"id": "d", "type": "rich_text", "rich_text": []}
+        }));
+        let event = page_to_event(None, &page).unwrap();
+
+        assert_eq!(event.stream, "connectors");
+        assert_eq!(
+            event.key.as_deref(),
+            Some("notion/page/59833787-2cf9-4fdf-8782-e53db20768a5")
+        );
+        assert_eq!(event.schema.as_deref(), Some("notion.page"));
+        let properties = &event.payload["properties"];
+        assert_eq!(properties["name"], "Launch plan");
+        assert_eq!(properties["status"], "In progress");
+        assert_eq!(properties["due_date"], "2026-03-01");
+        assert_eq!(properties["due_date_end"], "2026-03-05");
+        assert!(properties.get("notes").is_none());
+        assert_eq!(
+            properties["database_id"],
+            "d9824bdc-8445-4327-be8b-5b47500af6ce"
+        );
+    }
+
+    #[test]
+    fn test_archived_page_is_tombstone() {
+        let mut page = make_page(json!({}));
+        page.archived = true;
+        let event = page_to_event(Some("alice"), &page).unwrap();
+        assert_eq!(event.stream, "flux.events.deletions");
+        assert_eq!(
+            event.payload["entity_id"],
+            "alice/notion/page/59833787-2cf9-4fdf-8782-e53db20768a5"
+        );
+        assert_eq!(event.payload["properties"]["__deleted__"], true);
+
+        let mut page = make_page(json!({}));
+        page.in_trash = true;
+        let event = page_to_event(None, &page).unwrap();
+        assert_eq!(event.stream, "flux.events.deletions");
+    }
+
+    #[test]
+    fn test_property_name() {
+        assert_eq!(property_name("Due Date"), "due_date");
+        assert_eq!(property_name("  Story points (SP) "), "story_points_sp");
+        assert_eq!(property_name("Größe"), "größe");
+        assert_eq!(property_name("🚀"), "");
+    }
+
+    /// Events for a captured database query response
+    fn transform_fixture(case: &str, fixture: Value) -> anyhow::Result<Vec<FluxEvent>> {
+        match case {
+            "query" => {
+                let response: QueryResponse = serde_json::from_value(fixture)?;
+                let events: Result<Vec<_>, _> = response
+                    .results
+                    .iter()
+                    .map(|page| page_to_event(None, page))
+                    .collect();
+                Ok(events?)
+            }
+            _ => anyhow::bail!("No transformer for Notion fixture '{}'", case),
+        }
+    }
+
+    #[test]
+    fn test_golden_fixtures() {
+        golden::check_fixtures("notion", transform_fixture);
+    }
+}
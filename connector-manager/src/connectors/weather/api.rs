@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::config::{BASE_URL, CURRENT_VARIABLES, FORECAST_HOURS, HOURLY_VARIABLES};
+
+/// Conditions at the time of the request.
+///
+/// Fields are optional because Open-Meteo returns `null` where a model has
+/// no value for the location.
+#[derive(Debug, Deserialize)]
+pub struct CurrentWeather {
+    /// UTC time of the observation, e.g. `"2026-03-01T12:15"`.
+    pub time: String,
+    pub temperature_2m: Option<f64>,
+    pub relative_humidity_2m: Option<f64>,
+    pub precipitation: Option<f64>,
+    pub weather_code: Option<u8>,
+    pub wind_speed_10m: Option<f64>,
+    pub wind_direction_10m: Option<f64>,
+    pub wind_gusts_10m: Option<f64>,
+}
+
+/// Hourly forecast, one array entry per hour starting with the current hour.
+#[derive(Debug, Default, Deserialize)]
+pub struct HourlyForecast {
+    #[serde(default)]
+    pub time: Vec<String>,
+    #[serde(default)]
+    pub temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    pub precipitation_probability: Vec<Option<f64>>,
+    #[serde(default)]
+    pub weather_code: Vec<Option<u8>>,
+    #[serde(default)]
+    pub wind_gusts_10m: Vec<Option<f64>>,
+}
+
+/// Response of the Open-Meteo `/v1/forecast` endpoint.
+#[derive(Debug, Deserialize)]
+pub struct Forecast {
+    pub current: CurrentWeather,
+    #[serde(default)]
+    pub hourly: HourlyForecast,
+}
+
+/// Error body Open-Meteo returns with 4xx responses.
+#[derive(Deserialize)]
+struct ApiError {
+    reason: String,
+}
+
+/// HTTP client for the Open-Meteo forecast API (no API key required).
+///
+/// Requests metric units (°C, km/h, mm) with times in UTC.
+pub struct OpenMeteoClient {
+    http_client: Client,
+    base_url: String,
+}
+
+impl OpenMeteoClient {
+    /// Create a client using the public Open-Meteo API.
+    pub fn new() -> Self {
+        Self::with_base_url(BASE_URL.to_string())
+    }
+
+    /// Create a client with a custom base URL (for testing with a mock server).
+    pub fn with_base_url(base_url: String) -> Self {
+        let http_client = crate::http::client().expect("Failed to build HTTP client");
+        Self {
+            http_client,
+            base_url,
+        }
+    }
+
+    /// Fetch current conditions and the next [`FORECAST_HOURS`] hours for a point.
+    pub async fn fetch_forecast(&self, latitude: f64, longitude: f64) -> Result<Forecast> {
+        let url = format!("{}/v1/forecast", self.base_url);
+        let response = self
+            .http_client
+            .get(&url)
+            .query(&[
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                ("current", CURRENT_VARIABLES.to_string()),
+                ("hourly", HOURLY_VARIABLES.to_string()),
+                ("forecast_hours", FORECAST_HOURS.to_string()),
+                ("timezone", "UTC".to_string()),
+            ])
+            .send()
+            .await
+            .context("Failed to send forecast request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let reason = serde_json::from_str::<ApiError>(&body)
+                .map(|e| e.reason)
+                .unwrap_or(body);
+            anyhow::bail!("Open-Meteo returned {}: {}", status, reason);
+        }
+        response
+            .json::<Forecast>()
+            .await
+            .context("Failed to parse forecast response")
+    }
+}
+
+impl Default for OpenMeteoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_fetch_forecast_sends_coordinates_and_variables() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v1/forecast")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("latitude".into(), "52.52".into()),
+                Matcher::UrlEncoded("longitude".into(), "-13.41".into()),
+                Matcher::UrlEncoded("current".into(), CURRENT_VARIABLES.into()),
+                Matcher::UrlEncoded("hourly".into(), HOURLY_VARIABLES.into()),
+                Matcher::UrlEncoded("forecast_hours".into(), "6".into()),
+                Matcher::UrlEncoded("timezone".into(), "UTC".into()),
+            ]))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{
+                    "latitude": 52.52,
+                    "longitude": -13.41,
+                    "current": {"time": "2026-03-01T12:15", "interval": 900, "temperature_2m": 4.2, "weather_code": 3, "wind_gusts_10m": null},
+                    "hourly": {"time": ["2026-03-01T12:00"], "temperature_2m": [4.0], "precipitation_probability": [null], "weather_code": [3], "wind_gusts_10m": [20.5]}
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let client = OpenMeteoClient::with_base_url(server.url());
+        let forecast = client.fetch_forecast(52.52, -13.41).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(forecast.current.time, "2026-03-01T12:15");
+        assert_eq!(forecast.current.temperature_2m, Some(4.2));
+        assert_eq!(forecast.current.wind_gusts_10m, None);
+        assert_eq!(forecast.current.precipitation, None);
+        assert_eq!(forecast.hourly.precipitation_probability, vec![None]);
+        assert_eq!(forecast.hourly.wind_gusts_10m, vec![Some(20.5)]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_forecast_reports_api_reason() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/v1/forecast")
+            .match_query(Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"error": true, "reason": "Latitude must be in range of -90 to 90°."}"#)
+            .create_async()
+            .await;
+
+        let client = OpenMeteoClient::with_base_url(server.url());
+        let err = client.fetch_forecast(95.0, 0.0).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Open-Meteo returned 400 Bad Request: Latitude must be in range of -90 to 90°."
+        );
+    }
+}
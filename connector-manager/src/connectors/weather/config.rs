@@ -0,0 +1,14 @@
+pub const BASE_URL: &str = "https://api.open-meteo.com";
+
+/// Hours of hourly forecast summarized on each location entity.
+pub const FORECAST_HOURS: usize = 6;
+
+/// Wind gusts at or above this speed (km/h) raise an alert.
+pub const SEVERE_GUST_KMH: f64 = 75.0;
+
+/// Current conditions requested from the forecast endpoint.
+pub const CURRENT_VARIABLES: &str = "temperature_2m,relative_humidity_2m,precipitation,weather_code,wind_speed_10m,wind_direction_10m,wind_gusts_10m";
+
+/// Hourly forecast variables requested from the forecast endpoint.
+pub const HOURLY_VARIABLES: &str =
+    "temperature_2m,precipitation_probability,weather_code,wind_gusts_10m";
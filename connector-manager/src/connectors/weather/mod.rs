@@ -0,0 +1,11 @@
+//! Open-Meteo weather connector.
+//!
+//! Unlike GitHub this needs no OAuth: each weather source (see
+//! [`crate::weather_config`]) lists named locations, and the
+//! [`WeatherRunner`](crate::runners::weather::WeatherRunner) polls the
+//! forecast for each one, emitting `{namespace}/weather/{location}` entities
+//! plus a `{namespace}/weather/{location}/alerts` entity while severe
+//! conditions are forecast.
+pub mod api;
+pub mod config;
+pub mod transformer;
@@ -0,0 +1,440 @@
+use chrono::{NaiveDateTime, SecondsFormat};
+use flux::event::{BuildError, FluxEventBuilder};
+use flux::FluxEvent;
+use serde_json::{json, Value};
+
+use super::api::{Forecast, HourlyForecast};
+use super::config::{FORECAST_HOURS, SEVERE_GUST_KMH};
+use crate::weather_config::WeatherLocation;
+
+/// Text for a WMO weather interpretation code, as returned by Open-Meteo.
+pub fn describe_weather_code(code: u8) -> &'static str {
+    match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 => "Fog",
+        48 => "Depositing rime fog",
+        51 => "Light drizzle",
+        53 => "Moderate drizzle",
+        55 => "Dense drizzle",
+        56 => "Light freezing drizzle",
+        57 => "Dense freezing drizzle",
+        61 => "Slight rain",
+        63 => "Moderate rain",
+        65 => "Heavy rain",
+        66 => "Light freezing rain",
+        67 => "Heavy freezing rain",
+        71 => "Slight snowfall",
+        73 => "Moderate snowfall",
+        75 => "Heavy snowfall",
+        77 => "Snow grains",
+        80 => "Slight rain showers",
+        81 => "Moderate rain showers",
+        82 => "Violent rain showers",
+        85 => "Slight snow showers",
+        86 => "Heavy snow showers",
+        95 => "Thunderstorm",
+        96 => "Thunderstorm with slight hail",
+        99 => "Thunderstorm with heavy hail",
+        _ => "Unknown",
+    }
+}
+
+/// Heavy rain/snow, freezing rain, violent showers and thunderstorms.
+fn is_severe_code(code: u8) -> bool {
+    matches!(code, 65 | 67 | 75 | 82 | 86 | 95 | 96 | 99)
+}
+
+/// Open-Meteo UTC time (`2026-03-01T12:15`) as RFC 3339; unparseable values pass through.
+fn to_rfc3339(time: &str) -> String {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")
+        .map(|t| t.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|_| time.to_string())
+}
+
+/// The hourly forecast reduced to ranges and the most significant condition.
+#[derive(Debug, PartialEq)]
+pub struct ForecastSummary {
+    pub temp_min_c: Option<f64>,
+    pub temp_max_c: Option<f64>,
+    pub precipitation_probability_max: Option<f64>,
+    pub gusts_max_kmh: Option<f64>,
+    /// Highest WMO code in the window (higher codes are more significant).
+    pub weather_code: Option<u8>,
+    /// e.g. `"Moderate rain, 4–7°C, up to 80% chance of precipitation"`
+    pub text: String,
+}
+
+/// Summarizes the first [`FORECAST_HOURS`] hours of `hourly`.
+pub fn summarize_forecast(hourly: &HourlyForecast) -> ForecastSummary {
+    fn values(series: &[Option<f64>]) -> impl Iterator<Item = f64> + '_ {
+        series.iter().take(FORECAST_HOURS).flatten().copied()
+    }
+    let temp_min_c = values(&hourly.temperature_2m).reduce(f64::min);
+    let temp_max_c = values(&hourly.temperature_2m).reduce(f64::max);
+    let precipitation_probability_max = values(&hourly.precipitation_probability).reduce(f64::max);
+    let gusts_max_kmh = values(&hourly.wind_gusts_10m).reduce(f64::max);
+    let weather_code = hourly
+        .weather_code
+        .iter()
+        .take(FORECAST_HOURS)
+        .flatten()
+        .copied()
+        .max();
+
+    let mut parts = Vec::new();
+    if let Some(code) = weather_code {
+        parts.push(describe_weather_code(code).to_string());
+    }
+    match (temp_min_c, temp_max_c) {
+        (Some(min), Some(max)) if min.round() == max.round() => parts.push(format!("{:.0}°C", min)),
+        (Some(min), Some(max)) => parts.push(format!("{:.0}–{:.0}°C", min, max)),
+        _ => {}
+    }
+    if let Some(p) = precipitation_probability_max {
+        parts.push(format!("up to {:.0}% chance of precipitation", p));
+    }
+    let text = if parts.is_empty() {
+        "No forecast available".to_string()
+    } else {
+        parts.join(", ")
+    };
+
+    ForecastSummary {
+        temp_min_c,
+        temp_max_c,
+        precipitation_probability_max,
+        gusts_max_kmh,
+        weather_code,
+        text,
+    }
+}
+
+/// Severe conditions now or in the forecast window, oldest first.
+///
+/// Each alert is `{"time", "type", "description", ...}` where `type` is
+/// `severe_weather` (with `weather_code`) or `high_wind` (with `wind_gusts_kmh`).
+pub fn severe_alerts(forecast: &Forecast) -> Vec<Value> {
+    let current = &forecast.current;
+    let hourly = &forecast.hourly;
+    let slots = std::iter::once((
+        current.time.as_str(),
+        current.weather_code,
+        current.wind_gusts_10m,
+    ))
+    .chain(
+        hourly
+            .time
+            .iter()
+            .take(FORECAST_HOURS)
+            .enumerate()
+            .map(|(i, time)| {
+                (
+                    time.as_str(),
+                    hourly.weather_code.get(i).copied().flatten(),
+                    hourly.wind_gusts_10m.get(i).copied().flatten(),
+                )
+            }),
+    );
+
+    let mut alerts = Vec::new();
+    for (time, code, gusts) in slots {
+        let time = to_rfc3339(time);
+        if let Some(code) = code.filter(|c| is_severe_code(*c)) {
+            alerts.push(json!({
+                "time": time,
+                "type": "severe_weather",
+                "weather_code": code,
+                "description": describe_weather_code(code),
+            }));
+        }
+        if let Some(gusts) = gusts.filter(|g| *g >= SEVERE_GUST_KMH) {
+            alerts.push(json!({
+                "time": time,
+                "type": "high_wind",
+                "wind_gusts_kmh": gusts,
+                "description": format!("Wind gusts of {:.0} km/h", gusts),
+            }));
+        }
+    }
+    alerts
+}
+
+fn weather_event(
+    source: &str,
+    entity_id: String,
+    schema: &str,
+    properties: Value,
+) -> Result<FluxEvent, BuildError> {
+    FluxEventBuilder::new("connectors", source)
+        .entity(entity_id)
+        .key_from_entity()
+        .schema(schema)
+        .properties(properties)
+        .build()
+}
+
+/// Transform a location's forecast into a Flux event.
+///
+/// Entity key: `{namespace}/weather/{location}`
+pub fn forecast_to_event(
+    namespace: &str,
+    source: &str,
+    location: &WeatherLocation,
+    forecast: &Forecast,
+) -> Result<FluxEvent, BuildError> {
+    let current = &forecast.current;
+    let next = summarize_forecast(&forecast.hourly);
+    weather_event(
+        source,
+        format!("{}/weather/{}", namespace, location.name),
+        "weather.current",
+        json!({
+            "location": location.name,
+            "latitude": location.latitude,
+            "longitude": location.longitude,
+            "observed_at": to_rfc3339(&current.time),
+            "temperature_c": current.temperature_2m,
+            "relative_humidity_pct": current.relative_humidity_2m,
+            "precipitation_mm": current.precipitation,
+            "precipitation_probability_pct": forecast
+                .hourly
+                .precipitation_probability
+                .first()
+                .copied()
+                .flatten(),
+            "wind_speed_kmh": current.wind_speed_10m,
+            "wind_direction_deg": current.wind_direction_10m,
+            "wind_gusts_kmh": current.wind_gusts_10m,
+            "weather_code": current.weather_code,
+            "conditions": current.weather_code.map(describe_weather_code),
+            "next_6h_summary": next.text,
+            "next_6h_temp_min_c": next.temp_min_c,
+            "next_6h_temp_max_c": next.temp_max_c,
+            "next_6h_precipitation_probability_max_pct": next.precipitation_probability_max,
+            "next_6h_wind_gusts_max_kmh": next.gusts_max_kmh,
+            "next_6h_weather_code": next.weather_code,
+        }),
+    )
+}
+
+/// Transform severe conditions into an alerts event, or `None` if there are none.
+///
+/// Entity key: `{namespace}/weather/{location}/alerts`
+pub fn alerts_to_event(
+    namespace: &str,
+    source: &str,
+    location: &WeatherLocation,
+    forecast: &Forecast,
+) -> Result<Option<FluxEvent>, BuildError> {
+    let alerts = severe_alerts(forecast);
+    let Some(first) = alerts.first() else {
+        return Ok(None);
+    };
+    let headline = format!(
+        "{} at {}",
+        first["description"].as_str().unwrap_or_default(),
+        first["time"].as_str().unwrap_or_default()
+    );
+    weather_event(
+        source,
+        format!("{}/weather/{}/alerts", namespace, location.name),
+        "weather.alerts",
+        json!({
+            "active": true,
+            "count": alerts.len(),
+            "headline": headline,
+            "alerts": alerts,
+        }),
+    )
+    .map(Some)
+}
+
+/// Marks a location's alerts entity inactive once its severe conditions pass.
+///
+/// Entity key: `{namespace}/weather/{location}/alerts`
+pub fn alerts_cleared_event(
+    namespace: &str,
+    source: &str,
+    location: &WeatherLocation,
+) -> Result<FluxEvent, BuildError> {
+    weather_event(
+        source,
+        format!("{}/weather/{}/alerts", namespace, location.name),
+        "weather.alerts",
+        json!({
+            "active": false,
+            "count": 0,
+            "headline": null,
+            "alerts": [],
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::golden;
+    use crate::connectors::weather::api::CurrentWeather;
+
+    fn make_location() -> WeatherLocation {
+        WeatherLocation {
+            name: "home".to_string(),
+            latitude: 52.52,
+            longitude: 13.41,
+        }
+    }
+
+    fn make_forecast(codes: [u8; 6], gusts: [f64; 6]) -> Forecast {
+        Forecast {
+            current: CurrentWeather {
+                time: "2026-03-01T12:15".to_string(),
+                temperature_2m: Some(4.2),
+                relative_humidity_2m: Some(81.0),
+                precipitation: Some(0.3),
+                weather_code: Some(codes[0]),
+                wind_speed_10m: Some(14.8),
+                wind_direction_10m: Some(250.0),
+                wind_gusts_10m: Some(gusts[0]),
+            },
+            hourly: HourlyForecast {
+                time: (12..18).map(|h| format!("2026-03-01T{}:00", h)).collect(),
+                temperature_2m: vec![Some(4.0), Some(4.6), Some(5.9), None, Some(7.3), Some(6.1)],
+                precipitation_probability: vec![
+                    Some(35.0),
+                    Some(60.0),
+                    Some(80.0),
+                    Some(45.0),
+                    None,
+                    Some(10.0),
+                ],
+                weather_code: codes.iter().map(|c| Some(*c)).collect(),
+                wind_gusts_10m: gusts.iter().map(|g| Some(*g)).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_forecast_to_event() {
+        let forecast = make_forecast([3, 61, 63, 61, 3, 2], [30.0; 6]);
+        let event = forecast_to_event("matt", "weather.wx-1", &make_location(), &forecast).unwrap();
+
+        assert_eq!(event.stream, "connectors");
+        assert_eq!(event.source, "weather.wx-1");
+        assert_eq!(event.key.as_deref(), Some("matt/weather/home"));
+        assert_eq!(event.schema.as_deref(), Some("weather.current"));
+        assert!(event.event_id.is_some());
+
+        let props = &event.payload["properties"];
+        assert_eq!(event.payload["entity_id"], "matt/weather/home");
+        assert_eq!(props["observed_at"], "2026-03-01T12:15:00Z");
+        assert_eq!(props["temperature_c"], 4.2);
+        assert_eq!(props["wind_speed_kmh"], 14.8);
+        assert_eq!(props["wind_direction_deg"], 250.0);
+        assert_eq!(props["precipitation_probability_pct"], 35.0);
+        assert_eq!(props["conditions"], "Overcast");
+        assert_eq!(
+            props["next_6h_summary"],
+            "Moderate rain, 4–7°C, up to 80% chance of precipitation"
+        );
+        assert_eq!(props["next_6h_temp_min_c"], 4.0);
+        assert_eq!(props["next_6h_temp_max_c"], 7.3);
+        assert_eq!(props["next_6h_precipitation_probability_max_pct"], 80.0);
+        assert_eq!(props["next_6h_weather_code"], 63);
+    }
+
+    #[test]
+    fn test_summarize_forecast_handles_missing_data() {
+        let summary = summarize_forecast(&HourlyForecast::default());
+        assert_eq!(summary.temp_min_c, None);
+        assert_eq!(summary.weather_code, None);
+        assert_eq!(summary.text, "No forecast available");
+
+        let hourly = HourlyForecast {
+            time: vec!["2026-03-01T12:00".to_string()],
+            temperature_2m: vec![Some(5.2)],
+            ..HourlyForecast::default()
+        };
+        assert_eq!(summarize_forecast(&hourly).text, "5°C");
+    }
+
+    #[test]
+    fn test_no_alerts_in_calm_weather() {
+        let forecast = make_forecast([0, 1, 2, 3, 61, 80], [40.0; 6]);
+        assert!(severe_alerts(&forecast).is_empty());
+        let event = alerts_to_event("matt", "weather.wx-1", &make_location(), &forecast).unwrap();
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn test_alerts_for_storms_and_wind() {
+        let forecast = make_forecast([3, 3, 95, 3, 3, 3], [30.0, 30.0, 30.0, 82.0, 30.0, 30.0]);
+        let alerts = severe_alerts(&forecast);
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0]["type"], "severe_weather");
+        assert_eq!(alerts[0]["time"], "2026-03-01T14:00:00Z");
+        assert_eq!(alerts[0]["description"], "Thunderstorm");
+        assert_eq!(alerts[1]["type"], "high_wind");
+        assert_eq!(alerts[1]["wind_gusts_kmh"], 82.0);
+
+        let event = alerts_to_event("matt", "weather.wx-1", &make_location(), &forecast)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.key.as_deref(), Some("matt/weather/home/alerts"));
+        assert_eq!(event.schema.as_deref(), Some("weather.alerts"));
+        let props = &event.payload["properties"];
+        assert_eq!(props["active"], true);
+        assert_eq!(props["count"], 2);
+        assert_eq!(props["headline"], "Thunderstorm at 2026-03-01T14:00:00Z");
+
+        let cleared = alerts_cleared_event("matt", "weather.wx-1", &make_location()).unwrap();
+        assert_eq!(cleared.key.as_deref(), Some("matt/weather/home/alerts"));
+        assert_eq!(cleared.payload["properties"]["active"], false);
+        assert_eq!(cleared.payload["properties"]["count"], 0);
+    }
+
+    #[test]
+    fn test_current_conditions_can_alert() {
+        // Severe now (the current slot), calm in the forecast
+        let forecast = make_forecast([99, 3, 3, 3, 3, 3], [30.0; 6]);
+        let alerts = severe_alerts(&forecast);
+        assert_eq!(alerts.len(), 2, "current slot and first hourly slot");
+        assert_eq!(alerts[0]["time"], "2026-03-01T12:15:00Z");
+        assert_eq!(alerts[0]["description"], "Thunderstorm with heavy hail");
+    }
+
+    /// Events for captured forecast responses: calm weather at `home`, a
+    /// thunderstorm with gale gusts at `cabin`
+    fn transform_fixture(case: &str, fixture: Value) -> anyhow::Result<Vec<FluxEvent>> {
+        let location = match case {
+            "forecast" => make_location(),
+            "storm" => WeatherLocation {
+                name: "cabin".to_string(),
+                latitude: 61.5,
+                longitude: 8.2,
+            },
+            _ => anyhow::bail!("No transformer for weather fixture '{}'", case),
+        };
+        let forecast: Forecast = serde_json::from_value(fixture)?;
+        let mut events = vec![forecast_to_event(
+            "matt",
+            "weather.wx-1",
+            &location,
+            &forecast,
+        )?];
+        events.extend(alerts_to_event(
+            "matt",
+            "weather.wx-1",
+            &location,
+            &forecast,
+        )?);
+        Ok(events)
+    }
+
+    #[test]
+    fn test_golden_fixtures() {
+        golden::check_fixtures("weather", transform_fixture);
+    }
+}
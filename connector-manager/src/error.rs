@@ -0,0 +1,90 @@
+use flux::event::BuildError;
+use std::fmt;
+use std::time::Duration;
+
+/// Error returned by [`Connector::fetch`](crate::Connector::fetch).
+///
+/// The variant tells the scheduler how to react:
+/// - `AuthExpired` → refresh the token immediately and retry
+/// - `RateLimited` → wait `retry_after` (or the normal backoff) and retry
+/// - `Transient` → retry with exponential backoff
+/// - `Permanent` → stop polling until credentials change
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectorError {
+    /// Access token expired or was revoked
+    AuthExpired,
+    /// API rate limit hit; `retry_after` is the provider's hint, if any
+    RateLimited { retry_after: Option<Duration> },
+    /// Network failure, 5xx, unparseable response — worth retrying
+    Transient(String),
+    /// Misconfiguration or missing permissions — retrying won't help
+    Permanent(String),
+}
+
+impl fmt::Display for ConnectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectorError::AuthExpired => write!(f, "auth error: token expired or invalid"),
+            ConnectorError::RateLimited {
+                retry_after: Some(after),
+            } => write!(f, "rate limit exceeded (retry after {}s)", after.as_secs()),
+            ConnectorError::RateLimited { retry_after: None } => {
+                write!(f, "rate limit exceeded")
+            }
+            ConnectorError::Transient(msg) => write!(f, "{}", msg),
+            ConnectorError::Permanent(msg) => write!(f, "permanent error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConnectorError {}
+
+/// Untyped errors are assumed to be retryable.
+impl From<anyhow::Error> for ConnectorError {
+    fn from(e: anyhow::Error) -> Self {
+        ConnectorError::Transient(format!("{:#}", e))
+    }
+}
+
+/// An event the connector assembled is malformed; polling again would
+/// produce the same one.
+impl From<BuildError> for ConnectorError {
+    fn from(e: BuildError) -> Self {
+        ConnectorError::Permanent(format!("invalid event: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_anyhow_maps_to_transient_with_context_chain() {
+        let err: anyhow::Result<()> = Err(anyhow::anyhow!("connection refused"));
+        let err: ConnectorError = err.context("Failed to send request").unwrap_err().into();
+        assert_eq!(
+            err,
+            ConnectorError::Transient("Failed to send request: connection refused".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_error_is_permanent() {
+        let err: ConnectorError = BuildError::MissingEntity.into();
+        assert!(
+            matches!(&err, ConnectorError::Permanent(msg) if msg.starts_with("invalid event: entity ID"))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let err = ConnectorError::RateLimited {
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(err.to_string(), "rate limit exceeded (retry after 30s)");
+        assert!(ConnectorError::Permanent("bad scope".to_string())
+            .to_string()
+            .contains("bad scope"));
+    }
+}
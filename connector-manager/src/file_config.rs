@@ -0,0 +1,371 @@
+//! File-drop connector config storage.
+//!
+//! Stores directory-watching sources in SQLite. Each source defines a watch
+//! directory, a file name pattern, the file format (CSV or JSON Lines), the
+//! column used as the entity key, a namespace, and an optional column mapping.
+
+use crate::transform::TransformRule;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flux::migrations::{self, Migration};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Format of the files dropped into a watch directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl FileFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileFormat::Csv => "csv",
+            FileFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+impl std::str::FromStr for FileFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(FileFormat::Csv),
+            "jsonl" => Ok(FileFormat::Jsonl),
+            other => anyhow::bail!("unknown file format '{}'", other),
+        }
+    }
+}
+
+/// Config for a single file-drop source.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileSourceConfig {
+    /// Unique source ID (UUIDv4).
+    pub id: String,
+    /// Human-readable label shown in the UI.
+    pub name: String,
+    /// Directory to watch for new files.
+    pub directory: String,
+    /// File name pattern (`*` and `?` wildcards), e.g. `"*.csv"`.
+    pub pattern: String,
+    /// How files are parsed.
+    pub format: FileFormat,
+    /// Column (CSV) or field (JSONL) used as the Flux entity key.
+    pub entity_key_field: String,
+    /// Flux namespace to publish entities under.
+    pub namespace: String,
+    /// Renames columns to property names (`source column -> property`).
+    /// Unmapped columns keep their name.
+    pub column_mapping: HashMap<String, String>,
+    /// How often to scan the directory (seconds).
+    pub poll_interval_secs: u64,
+    /// When this source was created.
+    pub created_at: DateTime<Utc>,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+    /// Rules applied to each row before it is published.
+    #[serde(default)]
+    pub transforms: Vec<TransformRule>,
+}
+
+/// Schema history of the file config store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS file_sources (
+            id                   TEXT PRIMARY KEY,
+            name                 TEXT NOT NULL,
+            directory            TEXT NOT NULL,
+            pattern              TEXT NOT NULL,
+            format               TEXT NOT NULL,
+            entity_key_field     TEXT NOT NULL,
+            namespace            TEXT NOT NULL,
+            column_mapping_json  TEXT NOT NULL,
+            poll_interval_secs   INTEGER NOT NULL,
+            created_at           TEXT NOT NULL,
+            flux_namespace_token TEXT
+        );",
+    ),
+    Migration::AddColumn {
+        table: "file_sources",
+        column: "transforms_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+];
+
+/// Persists file-drop source configs in SQLite.
+pub struct FileConfigStore {
+    conn: Mutex<Connection>,
+}
+
+impl FileConfigStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open file config DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "file_sources", MIGRATIONS)
+            .context("Failed to migrate file config DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Inserts a new file source config. Fails if `id` already exists.
+    pub fn insert(&self, config: &FileSourceConfig) -> Result<()> {
+        let mapping_json = serde_json::to_string(&config.column_mapping)
+            .context("Failed to serialize column_mapping")?;
+        let transforms_json =
+            serde_json::to_string(&config.transforms).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO file_sources
+                (id, name, directory, pattern, format, entity_key_field, namespace, column_mapping_json, poll_interval_secs, created_at, flux_namespace_token, transforms_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                config.id,
+                config.name,
+                config.directory,
+                config.pattern,
+                config.format.as_str(),
+                config.entity_key_field,
+                config.namespace,
+                mapping_json,
+                config.poll_interval_secs as i64,
+                config.created_at.to_rfc3339(),
+                config.flux_namespace_token,
+                transforms_json,
+            ],
+        )
+        .context("Failed to insert file source config")?;
+        Ok(())
+    }
+
+    /// Replaces an existing config (matched by `id`), keeping its transform
+    /// rules. Returns false if not found.
+    pub fn update(&self, config: &FileSourceConfig) -> Result<bool> {
+        let mapping_json = serde_json::to_string(&config.column_mapping)
+            .context("Failed to serialize column_mapping")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE file_sources SET
+                    name = ?2, directory = ?3, pattern = ?4, format = ?5, entity_key_field = ?6,
+                    namespace = ?7, column_mapping_json = ?8, poll_interval_secs = ?9,
+                    flux_namespace_token = ?10
+                 WHERE id = ?1",
+                params![
+                    config.id,
+                    config.name,
+                    config.directory,
+                    config.pattern,
+                    config.format.as_str(),
+                    config.entity_key_field,
+                    config.namespace,
+                    mapping_json,
+                    config.poll_interval_secs as i64,
+                    config.flux_namespace_token,
+                ],
+            )
+            .context("Failed to update file source config")?;
+        Ok(updated > 0)
+    }
+
+    /// Returns a single source by ID, or `None` if not found.
+    pub fn get(&self, id: &str) -> Result<Option<FileSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, directory, pattern, format, entity_key_field, namespace, column_mapping_json, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM file_sources WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(row_to_config(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns all source configs ordered by creation time.
+    pub fn list(&self) -> Result<Vec<FileSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, directory, pattern, format, entity_key_field, namespace, column_mapping_json, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM file_sources ORDER BY created_at ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(row_to_config(row).expect("row_to_config failed"))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to list file source configs")
+    }
+
+    /// Replaces a source's transform rules. Returns false if not found.
+    pub fn set_transforms(&self, id: &str, rules: &[TransformRule]) -> Result<bool> {
+        let transforms_json =
+            serde_json::to_string(rules).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE file_sources SET transforms_json = ?2 WHERE id = ?1",
+                params![id, transforms_json],
+            )
+            .context("Failed to update file source transforms")?;
+        Ok(updated > 0)
+    }
+
+    /// Deletes a source by ID. No-op if the ID does not exist.
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM file_sources WHERE id = ?1", params![id])
+            .context("Failed to delete file source config")?;
+        Ok(())
+    }
+}
+
+fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileSourceConfig> {
+    let id: String = row.get(0)?;
+    let name: String = row.get(1)?;
+    let directory: String = row.get(2)?;
+    let pattern: String = row.get(3)?;
+    let format: String = row.get(4)?;
+    let entity_key_field: String = row.get(5)?;
+    let namespace: String = row.get(6)?;
+    let column_mapping_json: String = row.get(7)?;
+    let poll_interval_secs: i64 = row.get(8)?;
+    let created_at_str: String = row.get(9)?;
+    let flux_namespace_token: Option<String> = row.get(10)?;
+    let transforms_json: String = row.get(11)?;
+
+    let format: FileFormat = format.parse().expect("Failed to parse format");
+    let column_mapping: HashMap<String, String> =
+        serde_json::from_str(&column_mapping_json).expect("Failed to deserialize column_mapping");
+    let created_at: DateTime<Utc> = created_at_str.parse().expect("Failed to parse created_at");
+    let transforms =
+        serde_json::from_str(&transforms_json).expect("Failed to deserialize transforms");
+
+    Ok(FileSourceConfig {
+        id,
+        name,
+        directory,
+        pattern,
+        format,
+        entity_key_field,
+        namespace,
+        column_mapping,
+        poll_interval_secs: poll_interval_secs as u64,
+        created_at,
+        flux_namespace_token,
+        transforms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory_store() -> FileConfigStore {
+        FileConfigStore::new(":memory:").expect("in-memory store failed")
+    }
+
+    fn sample_config(id: &str) -> FileSourceConfig {
+        FileSourceConfig {
+            id: id.to_string(),
+            name: "Nightly inventory".to_string(),
+            directory: "/data/drop".to_string(),
+            pattern: "*.csv".to_string(),
+            format: FileFormat::Csv,
+            entity_key_field: "sku".to_string(),
+            namespace: "warehouse".to_string(),
+            column_mapping: HashMap::from([("Qty".to_string(), "quantity".to_string())]),
+            poll_interval_secs: 30,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let store = in_memory_store();
+        store
+            .insert(&sample_config("file-1"))
+            .expect("insert failed");
+
+        let fetched = store.get("file-1").unwrap().expect("config should exist");
+        assert_eq!(fetched.name, "Nightly inventory");
+        assert_eq!(fetched.directory, "/data/drop");
+        assert_eq!(fetched.pattern, "*.csv");
+        assert_eq!(fetched.format, FileFormat::Csv);
+        assert_eq!(fetched.entity_key_field, "sku");
+        assert_eq!(fetched.namespace, "warehouse");
+        assert_eq!(fetched.column_mapping["Qty"], "quantity");
+        assert_eq!(fetched.poll_interval_secs, 30);
+    }
+
+    #[test]
+    fn test_update() {
+        let store = in_memory_store();
+        let mut config = sample_config("file-1");
+        store.insert(&config).unwrap();
+
+        config.format = FileFormat::Jsonl;
+        config.pattern = "*.jsonl".to_string();
+        config.column_mapping.clear();
+        assert!(store.update(&config).unwrap());
+
+        let fetched = store.get("file-1").unwrap().unwrap();
+        assert_eq!(fetched.format, FileFormat::Jsonl);
+        assert_eq!(fetched.pattern, "*.jsonl");
+        assert!(fetched.column_mapping.is_empty());
+
+        assert!(!store.update(&sample_config("ghost")).unwrap());
+    }
+
+    #[test]
+    fn test_set_transforms_survives_update() {
+        let store = in_memory_store();
+        let config = sample_config("file-1");
+        store.insert(&config).unwrap();
+
+        let rules = vec![TransformRule::Rename {
+            from: "Qty".to_string(),
+            to: "quantity".to_string(),
+        }];
+        assert!(store.set_transforms("file-1", &rules).unwrap());
+        assert!(store.update(&config).unwrap());
+        assert_eq!(store.get("file-1").unwrap().unwrap().transforms, rules);
+
+        assert!(!store.set_transforms("ghost", &rules).unwrap());
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let store = in_memory_store();
+        store.insert(&sample_config("id-1")).unwrap();
+        store.insert(&sample_config("id-2")).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        store.delete("id-1").unwrap();
+        let ids: Vec<String> = store.list().unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["id-2"]);
+
+        // Deleting a missing ID is a no-op
+        store.delete("ghost").unwrap();
+        assert!(store.get("ghost").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        for format in [FileFormat::Csv, FileFormat::Jsonl] {
+            assert_eq!(format.as_str().parse::<FileFormat>().unwrap(), format);
+        }
+        assert!("xml".parse::<FileFormat>().is_err());
+    }
+}
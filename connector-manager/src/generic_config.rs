@@ -1,15 +1,22 @@
 //! Generic connector config storage.
 //!
 //! Stores user-defined HTTP polling sources in SQLite. Each source defines a URL,
-//! poll interval, entity key/namespace, and optional auth.
+//! poll interval, entity key/namespace, optional auth, and the engine that polls it.
 //!
 //! # Credential storage
 //! Generic tokens are NOT stored in this table. They are stored in the existing
 //! CredentialStore under `user_id="generic"`, `connector_name=<source-id>`.
 //! This reuses all encryption/access-control infrastructure without new plumbing.
+//! Secret header and query parameter values live there too, under
+//! [`secret_key`], with a `{"secret_ref": true}` marker in their place here.
+//! So does the password of a source's own proxy.
 
+use crate::runners::retry::RetryPolicy;
+use crate::transform::TransformRule;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use flux::http_client::ProxyConfig;
+use flux::migrations::{self, Migration};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
@@ -26,6 +33,160 @@ pub enum AuthType {
     ApiKeyHeader { header_name: String },
 }
 
+/// How a generic source is polled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceEngine {
+    /// In-process HTTP poller (default for new sources).
+    #[default]
+    Native,
+    /// `bento` subprocess driven by a rendered YAML config.
+    Bento,
+}
+
+impl SourceEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceEngine::Native => "native",
+            SourceEngine::Bento => "bento",
+        }
+    }
+}
+
+impl std::str::FromStr for SourceEngine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "native" => Ok(SourceEngine::Native),
+            "bento" => Ok(SourceEngine::Bento),
+            other => anyhow::bail!("unknown source engine '{}'", other),
+        }
+    }
+}
+
+/// Value of a custom header or query parameter.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValue {
+    /// Stored as-is in the config DB.
+    Plain(String),
+    /// Stored in CredentialStore under [`secret_key`]; always `{"secret_ref": true}`.
+    SecretRef { secret_ref: bool },
+}
+
+/// Where a custom request parameter goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+    Header,
+    Query,
+    /// The source's proxy (only `password` is secret)
+    Proxy,
+}
+
+impl ParamKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamKind::Header => "header",
+            ParamKind::Query => "query",
+            ParamKind::Proxy => "proxy",
+        }
+    }
+}
+
+/// CredentialStore `connector_name` of a secret header or query parameter value.
+pub fn secret_key(source_id: &str, kind: ParamKind, name: &str) -> String {
+    format!("{}:{}:{}", source_id, kind.as_str(), name)
+}
+
+/// Custom headers and query parameters with secret values filled in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestParams {
+    pub headers: Vec<(String, String)>,
+    pub query_params: Vec<(String, String)>,
+    /// The source's own proxy, password included
+    pub proxy: Option<ProxyConfig>,
+}
+
+/// Proxy one source is polled through instead of the connector manager's.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SourceProxy {
+    /// Proxy URL, without credentials
+    pub url: String,
+    pub username: Option<String>,
+    /// Stored in CredentialStore under [`secret_key`]; always `{"secret_ref": true}`.
+    pub password: Option<ParamValue>,
+    /// Hosts reached directly, in `NO_PROXY` syntax
+    pub no_proxy: Option<String>,
+}
+
+/// `input.http_client` fields Flux renders itself.
+pub const RESERVED_HTTP_CLIENT_FIELDS: &[&str] = &["url", "verb", "headers", "rate_limit"];
+
+/// Label of the rate limit resource that paces polls.
+pub const POLL_RATE_LABEL: &str = "poll_rate";
+
+/// Additions to the Bento config rendered for a `bento` source, each merged
+/// at one extension point of the generated config.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BentoOverrides {
+    /// Extra `input.http_client` fields, e.g. `retries` or `timeout`.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub http_client: serde_json::Map<String, serde_json::Value>,
+    /// Processors run on each response before it becomes a Flux event,
+    /// e.g. `{"unarchive": {"format": "json_array"}}` to split a list.
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub processors: Vec<serde_json::Value>,
+    /// Extra `rate_limit_resources`, e.g. for a `rate_limit` processor.
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub rate_limit_resources: Vec<serde_json::Value>,
+}
+
+impl BentoOverrides {
+    /// Checks the shape of each fragment: no field Flux renders itself,
+    /// one component per processor, and uniquely labelled rate limits of
+    /// one type each, none named [`POLL_RATE_LABEL`].
+    pub fn validate(&self) -> Result<()> {
+        for field in self.http_client.keys() {
+            if RESERVED_HTTP_CLIENT_FIELDS.contains(&field.as_str()) {
+                anyhow::bail!("bento_overrides.http_client cannot set '{}'", field);
+            }
+        }
+        for (i, processor) in self.processors.iter().enumerate() {
+            if processor.as_object().is_none_or(|p| p.len() != 1) {
+                anyhow::bail!(
+                    "bento_overrides.processors[{}] must be an object with one processor",
+                    i
+                );
+            }
+        }
+        let mut labels = Vec::new();
+        for (i, resource) in self.rate_limit_resources.iter().enumerate() {
+            let label = resource
+                .as_object()
+                .filter(|r| r.len() == 2)
+                .and_then(|r| r.get("label"))
+                .and_then(|label| label.as_str())
+                .filter(|label| !label.is_empty());
+            let Some(label) = label else {
+                anyhow::bail!(
+                    "bento_overrides.rate_limit_resources[{}] must have a label and one rate limit",
+                    i
+                );
+            };
+            if label == POLL_RATE_LABEL || labels.contains(&label) {
+                anyhow::bail!("rate limit label '{}' is already used", label);
+            }
+            labels.push(label);
+        }
+        Ok(())
+    }
+}
+
 /// Config for a single generic HTTP polling source.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GenericSourceConfig {
@@ -47,69 +208,266 @@ pub struct GenericSourceConfig {
     pub created_at: DateTime<Utc>,
     /// Optional Flux namespace token for auth-enabled Flux instances.
     pub flux_namespace_token: Option<String>,
+    /// Polling engine. Rows created before engines existed are `Bento`.
+    pub engine: SourceEngine,
+    /// Extra headers sent with every poll, in order.
+    pub headers: Vec<(String, ParamValue)>,
+    /// Query parameters appended to `url` on every poll, in order.
+    pub query_params: Vec<(String, ParamValue)>,
+    /// Proxy for this source's polls; `None` uses the connector manager's.
+    pub proxy: Option<SourceProxy>,
+    /// Additions to the rendered Bento config (`bento` sources only).
+    pub bento_overrides: Option<BentoOverrides>,
+    /// Rules applied to each event before it is published (`native` sources only).
+    #[serde(default)]
+    pub transforms: Vec<TransformRule>,
+    /// Namespace that created the source; only it (and admins) may see or
+    /// change it.
+    #[serde(default)]
+    pub owner_namespace: String,
+    /// Backoff and circuit breaker applied when polls or Bento runs fail.
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+}
+
+impl GenericSourceConfig {
+    /// Custom headers, query parameters and proxy, with secrets looked up by [`secret_key`].
+    ///
+    /// Fails if a secret is missing, so a source never polls without it.
+    pub fn resolve_params(&self, secret: impl Fn(&str) -> Option<String>) -> Result<RequestParams> {
+        let resolve = |kind: ParamKind, params: &[(String, ParamValue)]| {
+            params
+                .iter()
+                .map(|(name, value)| match value {
+                    ParamValue::Plain(value) => Ok((name.clone(), value.clone())),
+                    ParamValue::SecretRef { .. } => secret(&secret_key(&self.id, kind, name))
+                        .map(|value| (name.clone(), value))
+                        .with_context(|| {
+                            format!(
+                                "secret {} '{}' is not in the credential store",
+                                kind.as_str(),
+                                name
+                            )
+                        }),
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let proxy = self
+            .proxy
+            .as_ref()
+            .map(|proxy| -> Result<ProxyConfig> {
+                let password = match proxy.password {
+                    Some(_) => Some(
+                        secret(&secret_key(&self.id, ParamKind::Proxy, "password"))
+                            .context("proxy password is not in the credential store")?,
+                    ),
+                    None => None,
+                };
+                Ok(ProxyConfig {
+                    url: proxy.url.clone(),
+                    username: proxy.username.clone(),
+                    password,
+                    no_proxy: proxy.no_proxy.clone(),
+                })
+            })
+            .transpose()?;
+        Ok(RequestParams {
+            headers: resolve(ParamKind::Header, &self.headers)?,
+            query_params: resolve(ParamKind::Query, &self.query_params)?,
+            proxy,
+        })
+    }
+
+    /// Names of the secret headers, query parameters and proxy password.
+    pub fn secret_params(&self) -> impl Iterator<Item = (ParamKind, &str)> {
+        fn secrets(
+            kind: ParamKind,
+            params: &[(String, ParamValue)],
+        ) -> impl Iterator<Item = (ParamKind, &str)> {
+            params
+                .iter()
+                .filter(|(_, value)| matches!(value, ParamValue::SecretRef { .. }))
+                .map(move |(name, _)| (kind, name.as_str()))
+        }
+        let proxy_password = self
+            .proxy
+            .iter()
+            .filter(|proxy| proxy.password.is_some())
+            .map(|_| (ParamKind::Proxy, "password"));
+        secrets(ParamKind::Header, &self.headers)
+            .chain(secrets(ParamKind::Query, &self.query_params))
+            .chain(proxy_password)
+    }
+}
+
+/// True if a header named `name` would override the auth `auth_type` manages.
+fn overrides_auth(auth_type: &AuthType, name: &str) -> bool {
+    match auth_type {
+        AuthType::None => false,
+        AuthType::BearerToken => name.eq_ignore_ascii_case("authorization"),
+        AuthType::ApiKeyHeader { header_name } => {
+            name.eq_ignore_ascii_case("authorization") || name.eq_ignore_ascii_case(header_name)
+        }
+    }
+}
+
+/// Checks custom headers and query parameters as `(name, value)` pairs.
+///
+/// Header names must be valid HTTP tokens and values valid header values.
+/// `Host` is never accepted, and neither are `Authorization` and the API key
+/// header when `auth_type` manages auth. Query parameter names must be
+/// non-empty.
+pub fn validate_request_params(
+    auth_type: &AuthType,
+    headers: &[(&str, &str)],
+    query_params: &[(&str, &str)],
+) -> Result<()> {
+    for (name, value) in headers {
+        let header = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow::anyhow!("'{}' is not a valid header name", name))?;
+        if header == reqwest::header::HOST {
+            anyhow::bail!("the Host header cannot be overridden");
+        }
+        if overrides_auth(auth_type, name) {
+            anyhow::bail!("header '{}' would override auth_type", name);
+        }
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| anyhow::anyhow!("header '{}' has an invalid value", name))?;
+    }
+    if query_params.iter().any(|(name, _)| name.is_empty()) {
+        anyhow::bail!("query parameter names must not be empty");
+    }
+    Ok(())
 }
 
+/// Checks the URL of a source's proxy: `http` or `https`, without
+/// credentials (those go in `username` and `password`, which is kept secret).
+pub fn validate_proxy_url(url: &str) -> Result<()> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|_| anyhow::anyhow!("proxy url is not a valid URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        anyhow::bail!("proxy url must use http or https");
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        anyhow::bail!("proxy url must not contain credentials; set username and password");
+    }
+    Ok(())
+}
+
+/// Schema history of the generic config store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS generic_sources (
+            id                TEXT PRIMARY KEY,
+            name              TEXT NOT NULL,
+            url               TEXT NOT NULL,
+            poll_interval_secs INTEGER NOT NULL,
+            entity_key        TEXT NOT NULL,
+            namespace         TEXT NOT NULL,
+            auth_type_json    TEXT NOT NULL,
+            created_at        TEXT NOT NULL
+        );",
+    ),
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "flux_namespace_token",
+        definition: "TEXT",
+    },
+    // Sources created before engines existed keep running under Bento
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "engine",
+        definition: "TEXT NOT NULL DEFAULT 'bento'",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "headers_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "query_params_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "proxy_json",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "bento_overrides_json",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "transforms_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "owner_namespace",
+        definition: "TEXT NOT NULL DEFAULT ''",
+    },
+    // Sources created before owners were recorded belong to the namespace
+    // they publish under
+    Migration::Sql(
+        "UPDATE generic_sources SET owner_namespace = namespace WHERE owner_namespace = '';",
+    ),
+    // NULL = the default policy
+    Migration::AddColumn {
+        table: "generic_sources",
+        column: "retry_policy_json",
+        definition: "TEXT",
+    },
+];
+
 /// Persists generic source configs in SQLite.
 pub struct GenericConfigStore {
     conn: Mutex<Connection>,
 }
 
 impl GenericConfigStore {
-    /// Opens (or creates) the SQLite database and ensures the table exists.
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
     pub fn new(db_path: &str) -> Result<Self> {
-        let conn = Connection::open(db_path)
+        let mut conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open generic config DB at {}", db_path))?;
-        let store = Self {
+        migrations::migrate(&mut conn, "generic_sources", MIGRATIONS)
+            .context("Failed to migrate generic config DB")?;
+        Ok(Self {
             conn: Mutex::new(conn),
-        };
-        store.create_table()?;
-        store.migrate()?;
-        Ok(store)
-    }
-
-    /// Creates the `generic_sources` table if it does not already exist.
-    pub fn create_table(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS generic_sources (
-                id                TEXT PRIMARY KEY,
-                name              TEXT NOT NULL,
-                url               TEXT NOT NULL,
-                poll_interval_secs INTEGER NOT NULL,
-                entity_key        TEXT NOT NULL,
-                namespace         TEXT NOT NULL,
-                auth_type_json    TEXT NOT NULL,
-                created_at        TEXT NOT NULL,
-                flux_namespace_token TEXT
-            );",
-        )
-        .context("Failed to create generic_sources table")?;
-        Ok(())
-    }
-
-    /// Adds `flux_namespace_token` column to existing databases.
-    fn migrate(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.execute_batch(
-            "ALTER TABLE generic_sources ADD COLUMN flux_namespace_token TEXT;",
-        );
-        if let Err(e) = result {
-            if !e.to_string().contains("duplicate column") {
-                return Err(e.into());
-            }
-        }
-        Ok(())
+        })
     }
 
     /// Inserts a new generic source config. Fails if `id` already exists.
     pub fn insert(&self, config: &GenericSourceConfig) -> Result<()> {
         let auth_json =
             serde_json::to_string(&config.auth_type).context("Failed to serialize auth_type")?;
+        let headers_json =
+            serde_json::to_string(&config.headers).context("Failed to serialize headers")?;
+        let query_params_json = serde_json::to_string(&config.query_params)
+            .context("Failed to serialize query_params")?;
+        let proxy_json = config
+            .proxy
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("Failed to serialize proxy")?;
+        let bento_overrides_json = config
+            .bento_overrides
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("Failed to serialize bento_overrides")?;
+        let transforms_json =
+            serde_json::to_string(&config.transforms).context("Failed to serialize transforms")?;
+        let retry_policy_json = serde_json::to_string(&config.retry_policy)
+            .context("Failed to serialize retry_policy")?;
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "INSERT INTO generic_sources
-                (id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                (id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token, engine, headers_json, query_params_json, proxy_json, bento_overrides_json, transforms_json, owner_namespace, retry_policy_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
             params![
                 config.id,
                 config.name,
@@ -120,6 +478,14 @@ impl GenericConfigStore {
                 auth_json,
                 config.created_at.to_rfc3339(),
                 config.flux_namespace_token,
+                config.engine.as_str(),
+                headers_json,
+                query_params_json,
+                proxy_json,
+                bento_overrides_json,
+                transforms_json,
+                config.owner_namespace,
+                retry_policy_json,
             ],
         )
         .context("Failed to insert generic source config")?;
@@ -130,7 +496,7 @@ impl GenericConfigStore {
     pub fn get(&self, id: &str) -> Result<Option<GenericSourceConfig>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token
+            "SELECT id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token, engine, headers_json, query_params_json, proxy_json, bento_overrides_json, transforms_json, owner_namespace, retry_policy_json
              FROM generic_sources WHERE id = ?1",
         )?;
         let mut rows = stmt.query(params![id])?;
@@ -145,7 +511,7 @@ impl GenericConfigStore {
     pub fn list(&self) -> Result<Vec<GenericSourceConfig>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token
+            "SELECT id, name, url, poll_interval_secs, entity_key, namespace, auth_type_json, created_at, flux_namespace_token, engine, headers_json, query_params_json, proxy_json, bento_overrides_json, transforms_json, owner_namespace, retry_policy_json
              FROM generic_sources ORDER BY created_at ASC",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -155,6 +521,20 @@ impl GenericConfigStore {
             .context("Failed to list generic source configs")
     }
 
+    /// Replaces a source's transform rules. Returns false if not found.
+    pub fn set_transforms(&self, id: &str, rules: &[TransformRule]) -> Result<bool> {
+        let transforms_json =
+            serde_json::to_string(rules).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE generic_sources SET transforms_json = ?2 WHERE id = ?1",
+                params![id, transforms_json],
+            )
+            .context("Failed to update generic source transforms")?;
+        Ok(updated > 0)
+    }
+
     /// Deletes a source by ID. No-op if the ID does not exist.
     pub fn delete(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -174,11 +554,31 @@ fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<GenericSourceConfi
     let auth_type_json: String = row.get(6)?;
     let created_at_str: String = row.get(7)?;
     let flux_namespace_token: Option<String> = row.get(8)?;
+    let engine: String = row.get(9)?;
+    let headers_json: String = row.get(10)?;
+    let query_params_json: String = row.get(11)?;
+    let proxy_json: Option<String> = row.get(12)?;
+    let bento_overrides_json: Option<String> = row.get(13)?;
+    let transforms_json: String = row.get(14)?;
+    let owner_namespace: String = row.get(15)?;
+    let retry_policy_json: Option<String> = row.get(16)?;
 
     let auth_type: AuthType =
         serde_json::from_str(&auth_type_json).expect("Failed to deserialize auth_type");
-    let created_at: DateTime<Utc> =
-        created_at_str.parse().expect("Failed to parse created_at");
+    let created_at: DateTime<Utc> = created_at_str.parse().expect("Failed to parse created_at");
+    let engine: SourceEngine = engine.parse().expect("Failed to parse engine");
+    let headers = serde_json::from_str(&headers_json).expect("Failed to deserialize headers");
+    let query_params =
+        serde_json::from_str(&query_params_json).expect("Failed to deserialize query_params");
+    let proxy =
+        proxy_json.map(|json| serde_json::from_str(&json).expect("Failed to deserialize proxy"));
+    let bento_overrides = bento_overrides_json
+        .map(|json| serde_json::from_str(&json).expect("Failed to deserialize bento_overrides"));
+    let transforms =
+        serde_json::from_str(&transforms_json).expect("Failed to deserialize transforms");
+    let retry_policy = retry_policy_json
+        .map(|json| serde_json::from_str(&json).expect("Failed to deserialize retry_policy"))
+        .unwrap_or_default();
 
     Ok(GenericSourceConfig {
         id,
@@ -190,6 +590,14 @@ fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<GenericSourceConfi
         auth_type,
         created_at,
         flux_namespace_token,
+        engine,
+        headers,
+        query_params,
+        proxy,
+        bento_overrides,
+        transforms,
+        owner_namespace,
+        retry_policy,
     })
 }
 
@@ -213,6 +621,14 @@ mod tests {
             auth_type: AuthType::None,
             created_at: Utc::now(),
             flux_namespace_token: None,
+            engine: SourceEngine::Native,
+            headers: Vec::new(),
+            query_params: Vec::new(),
+            proxy: None,
+            bento_overrides: None,
+            transforms: Vec::new(),
+            owner_namespace: "personal".to_string(),
+            retry_policy: RetryPolicy::default(),
         }
     }
 
@@ -233,6 +649,130 @@ mod tests {
         assert_eq!(fetched.entity_key, "test-entity");
         assert_eq!(fetched.namespace, "personal");
         assert_eq!(fetched.auth_type, AuthType::None);
+        assert_eq!(fetched.engine, SourceEngine::Native);
+    }
+
+    #[test]
+    fn test_insert_and_get_bento_engine() {
+        let store = in_memory_store();
+        let mut config = sample_config("bento-src");
+        config.engine = SourceEngine::Bento;
+
+        store.insert(&config).expect("insert failed");
+
+        let fetched = store.get("bento-src").unwrap().unwrap();
+        assert_eq!(fetched.engine, SourceEngine::Bento);
+    }
+
+    #[test]
+    fn test_insert_and_get_bento_overrides() {
+        let store = in_memory_store();
+        let mut config = sample_config("overrides");
+        config.engine = SourceEngine::Bento;
+        config.bento_overrides = Some(
+            serde_json::from_value(serde_json::json!({
+                "http_client": {"retries": 5},
+                "processors": [{"unarchive": {"format": "json_array"}}]
+            }))
+            .unwrap(),
+        );
+        store.insert(&config).unwrap();
+
+        let fetched = store.get("overrides").unwrap().unwrap();
+        assert_eq!(fetched.bento_overrides, config.bento_overrides);
+        store.insert(&sample_config("plain")).unwrap();
+        assert_eq!(store.get("plain").unwrap().unwrap().bento_overrides, None);
+    }
+
+    #[test]
+    fn test_insert_and_get_retry_policy() {
+        let store = in_memory_store();
+        let mut config = sample_config("retry");
+        config.retry_policy = RetryPolicy {
+            max_consecutive_failures: 3,
+            backoff_base_secs: 30,
+            ..RetryPolicy::default()
+        };
+        store.insert(&config).unwrap();
+
+        let fetched = store.get("retry").unwrap().unwrap();
+        assert_eq!(fetched.retry_policy, config.retry_policy);
+    }
+
+    #[test]
+    fn test_validate_bento_overrides() {
+        let overrides =
+            |value: serde_json::Value| -> BentoOverrides { serde_json::from_value(value).unwrap() };
+        overrides(serde_json::json!({
+            "http_client": {"retries": 5},
+            "processors": [{"mapping": "root = this.items"}],
+            "rate_limit_resources": [{"label": "pages", "local": {"count": 10, "interval": "1s"}}]
+        }))
+        .validate()
+        .unwrap();
+
+        for invalid in [
+            serde_json::json!({"http_client": {"url": "https://other.example.com"}}),
+            serde_json::json!({"processors": [{"mapping": "root = this", "log": {}}]}),
+            serde_json::json!({"processors": ["mapping"]}),
+            serde_json::json!({"rate_limit_resources": [{"local": {}}]}),
+            serde_json::json!({"rate_limit_resources": [{"label": "poll_rate", "local": {}}]}),
+            serde_json::json!({"rate_limit_resources": [
+                {"label": "a", "local": {}},
+                {"label": "a", "local": {}}
+            ]}),
+        ] {
+            assert!(
+                overrides(invalid.clone()).validate().is_err(),
+                "{}",
+                invalid
+            );
+        }
+        assert!(
+            serde_json::from_value::<BentoOverrides>(serde_json::json!({"output": {}})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_migrate_existing_rows_to_bento() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("generic.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Table as created before the engine column existed
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE generic_sources (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, url TEXT NOT NULL,
+                poll_interval_secs INTEGER NOT NULL, entity_key TEXT NOT NULL,
+                namespace TEXT NOT NULL, auth_type_json TEXT NOT NULL,
+                created_at TEXT NOT NULL, flux_namespace_token TEXT
+            );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO generic_sources VALUES ('old', 'Old', 'https://example.com', 60, 'k', 'ns', ?1, ?2, NULL)",
+            params![r#"{"type":"None"}"#, Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = GenericConfigStore::new(db_path).expect("migrate failed");
+        let old = store.get("old").unwrap().unwrap();
+        assert_eq!(old.engine, SourceEngine::Bento);
+        // Owned by the namespace it publishes under
+        assert_eq!(old.owner_namespace, "ns");
+        assert_eq!(old.retry_policy, RetryPolicy::default());
+        assert_eq!(
+            migrations::schema_version(&store.conn.lock().unwrap(), "generic_sources").unwrap(),
+            MIGRATIONS.len() as u32
+        );
+
+        store.insert(&sample_config("new")).unwrap();
+        assert_eq!(
+            store.get("new").unwrap().unwrap().engine,
+            SourceEngine::Native
+        );
     }
 
     #[test]
@@ -266,6 +806,157 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_and_get_request_params() {
+        let store = in_memory_store();
+        let mut config = sample_config("params-src");
+        config.headers = vec![
+            (
+                "X-Api-Version".to_string(),
+                ParamValue::Plain("2".to_string()),
+            ),
+            (
+                "X-Secret".to_string(),
+                ParamValue::SecretRef { secret_ref: true },
+            ),
+        ];
+        config.query_params = vec![(
+            "apikey".to_string(),
+            ParamValue::SecretRef { secret_ref: true },
+        )];
+
+        store.insert(&config).expect("insert failed");
+
+        let fetched = store.get("params-src").unwrap().unwrap();
+        assert_eq!(fetched.headers, config.headers);
+        assert_eq!(fetched.query_params, config.query_params);
+
+        // Secrets never reach the config DB
+        let conn = store.conn.lock().unwrap();
+        let stored: String = conn
+            .query_row(
+                "SELECT headers_json FROM generic_sources WHERE id = 'params-src'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            stored,
+            r#"[["X-Api-Version","2"],["X-Secret",{"secret_ref":true}]]"#
+        );
+    }
+
+    #[test]
+    fn test_resolve_params_reads_secrets() {
+        let mut config = sample_config("src");
+        config.headers = vec![
+            (
+                "X-Api-Version".to_string(),
+                ParamValue::Plain("2".to_string()),
+            ),
+            (
+                "X-Secret".to_string(),
+                ParamValue::SecretRef { secret_ref: true },
+            ),
+        ];
+        config.query_params = vec![(
+            "apikey".to_string(),
+            ParamValue::SecretRef { secret_ref: true },
+        )];
+
+        let secrets: std::collections::HashMap<String, String> = [
+            (
+                secret_key("src", ParamKind::Header, "X-Secret"),
+                "h".to_string(),
+            ),
+            (
+                secret_key("src", ParamKind::Query, "apikey"),
+                "q".to_string(),
+            ),
+        ]
+        .into();
+        let params = config
+            .resolve_params(|key| secrets.get(key).cloned())
+            .unwrap();
+        assert_eq!(
+            params.headers,
+            vec![
+                ("X-Api-Version".to_string(), "2".to_string()),
+                ("X-Secret".to_string(), "h".to_string()),
+            ]
+        );
+        assert_eq!(
+            params.query_params,
+            vec![("apikey".to_string(), "q".to_string())]
+        );
+        assert_eq!(config.secret_params().count(), 2);
+
+        // A missing secret fails instead of polling without it
+        assert!(config.resolve_params(|_| None).is_err());
+    }
+
+    #[test]
+    fn test_validate_request_params() {
+        let ok = validate_request_params(
+            &AuthType::None,
+            &[("X-Api-Version", "2")],
+            &[("apikey", "k")],
+        );
+        assert!(ok.is_ok());
+        // Without auth_type the source may send its own Authorization header
+        assert!(
+            validate_request_params(&AuthType::None, &[("Authorization", "Basic x")], &[]).is_ok()
+        );
+
+        let bearer = AuthType::BearerToken;
+        assert!(validate_request_params(&bearer, &[("authorization", "x")], &[]).is_err());
+        let api_key = AuthType::ApiKeyHeader {
+            header_name: "X-API-Key".to_string(),
+        };
+        assert!(validate_request_params(&api_key, &[("x-api-key", "x")], &[]).is_err());
+        assert!(validate_request_params(&api_key, &[("Authorization", "x")], &[]).is_err());
+        assert!(validate_request_params(&api_key, &[("X-Api-Version", "2")], &[]).is_ok());
+
+        assert!(validate_request_params(&AuthType::None, &[("Host", "evil")], &[]).is_err());
+        assert!(validate_request_params(&AuthType::None, &[("Bad Header", "x")], &[]).is_err());
+        assert!(validate_request_params(&AuthType::None, &[("X-Ok", "line\nbreak")], &[]).is_err());
+        assert!(validate_request_params(&AuthType::None, &[], &[("", "x")]).is_err());
+    }
+
+    #[test]
+    fn test_source_proxy_password_is_secret() {
+        let store = in_memory_store();
+        let mut config = sample_config("proxied");
+        config.proxy = Some(SourceProxy {
+            url: "http://proxy.internal:3128".to_string(),
+            username: Some("poller".to_string()),
+            password: Some(ParamValue::SecretRef { secret_ref: true }),
+            no_proxy: Some(".internal".to_string()),
+        });
+        store.insert(&config).unwrap();
+        let fetched = store.get("proxied").unwrap().unwrap();
+        assert_eq!(fetched.proxy, config.proxy);
+        assert_eq!(
+            fetched.secret_params().collect::<Vec<_>>(),
+            vec![(ParamKind::Proxy, "password")]
+        );
+
+        let key = secret_key("proxied", ParamKind::Proxy, "password");
+        let params = fetched
+            .resolve_params(|k| (k == key).then(|| "pw".to_string()))
+            .unwrap();
+        let proxy = params.proxy.unwrap();
+        assert_eq!(proxy.url, "http://proxy.internal:3128");
+        assert_eq!(proxy.username.as_deref(), Some("poller"));
+        assert_eq!(proxy.password.as_deref(), Some("pw"));
+        assert!(fetched.resolve_params(|_| None).is_err());
+
+        assert!(validate_proxy_url("http://proxy.internal:3128").is_ok());
+        assert!(validate_proxy_url("http://u:p@proxy.internal:3128").is_err());
+        assert!(validate_proxy_url("ftp://proxy.internal").is_err());
+        assert!(validate_proxy_url("proxy.internal:3128").is_err());
+    }
+
     #[test]
     fn test_list_configs() {
         let store = in_memory_store();
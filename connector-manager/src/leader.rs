@@ -0,0 +1,413 @@
+//! Leader election between connector-manager replicas.
+//!
+//! Replicas share a lease row in SQLite (`LEADER_LEASE_DB`, on the same shared
+//! volume as the config and credential databases). Only the leader runs the
+//! builtin connector schedulers and the source pollers. Every replica serves
+//! the HTTP API; a source created or changed on a follower is only persisted,
+//! and the leader's reconcile loop starts, restarts or stops it.
+
+use crate::manager::ConnectorManager;
+use crate::runners::file::FileRunner;
+use crate::runners::generic::GenericRunner;
+use crate::runners::named::NamedRunner;
+use crate::runners::postgres::PostgresRunner;
+use crate::runners::weather::WeatherRunner;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flux::credentials::CredentialStore;
+use flux::leader::{Leadership, Lease, LeaseStore};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Lease kept in a single SQLite row, taken with an atomic upsert.
+pub struct SqliteLeaseStore {
+    conn: Mutex<Connection>,
+    name: String,
+}
+
+impl SqliteLeaseStore {
+    /// Opens (or creates) the lease database. `name` identifies the lease row.
+    pub fn new(db_path: &str, name: &str) -> Result<Self> {
+        let conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open leader lease DB at {}", db_path))?;
+        // Replicas write the same row; wait out each other's locks
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS leader_lease (
+                name          TEXT PRIMARY KEY,
+                holder        TEXT NOT NULL,
+                expires_at_ms INTEGER NOT NULL
+            );",
+        )
+        .context("Failed to create leader_lease table")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            name: name.to_string(),
+        })
+    }
+}
+
+impl LeaseStore for SqliteLeaseStore {
+    async fn acquire(
+        &self,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Lease> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO leader_lease (name, holder, expires_at_ms) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE
+                SET holder = excluded.holder, expires_at_ms = excluded.expires_at_ms
+                WHERE leader_lease.holder = excluded.holder
+                   OR leader_lease.expires_at_ms <= ?4",
+            params![
+                self.name,
+                holder,
+                expires_at.timestamp_millis(),
+                now.timestamp_millis()
+            ],
+        )
+        .context("Failed to write leader lease")?;
+        let (holder, expires_at_ms): (String, i64) = conn
+            .query_row(
+                "SELECT holder, expires_at_ms FROM leader_lease WHERE name = ?1",
+                params![self.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .context("Failed to read leader lease")?;
+        Ok(Lease {
+            holder,
+            expires_at: DateTime::from_timestamp_millis(expires_at_ms)
+                .context("Leader lease expiry out of range")?,
+        })
+    }
+}
+
+/// Changes that bring running sources in line with the stored configs.
+///
+/// A source in both lists is restarted with its new config.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReconcilePlan {
+    pub stop: Vec<String>,
+    pub start: Vec<String>,
+}
+
+/// Plans one reconcile pass for a kind of source.
+///
+/// `configs` pairs each stored source ID with a fingerprint of its config;
+/// `seen` holds the fingerprints from earlier passes. A running source seen
+/// for the first time is adopted as-is (it was started by this replica's API).
+pub fn plan_reconcile(
+    configs: &[(String, String)],
+    running: &[String],
+    seen: &mut HashMap<String, String>,
+) -> ReconcilePlan {
+    let mut plan = ReconcilePlan::default();
+    for (id, fingerprint) in configs {
+        let previous = seen.insert(id.clone(), fingerprint.clone());
+        if !running.contains(id) {
+            plan.start.push(id.clone());
+        } else if previous.is_some_and(|p| p != *fingerprint) {
+            plan.stop.push(id.clone());
+            plan.start.push(id.clone());
+        }
+    }
+    for id in running {
+        if !configs.iter().any(|(config_id, _)| config_id == id) {
+            seen.remove(id);
+            plan.stop.push(id.clone());
+        }
+    }
+    plan
+}
+
+fn fingerprints<C: Serialize>(configs: &[C], id: impl Fn(&C) -> &str) -> Vec<(String, String)> {
+    configs
+        .iter()
+        .map(|c| {
+            (
+                id(c).to_string(),
+                serde_json::to_string(c).unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// Source runners the leader drives.
+pub struct LeaderSources {
+    pub credential_store: Arc<CredentialStore>,
+    pub generic: Arc<GenericRunner>,
+    pub named: Arc<NamedRunner>,
+    pub file: Arc<FileRunner>,
+    pub postgres: Arc<PostgresRunner>,
+    pub weather: Arc<WeatherRunner>,
+}
+
+impl LeaderSources {
+    /// Starts stored sources that are not running, restarts changed ones and
+    /// stops those whose config was deleted or that were paused.
+    pub async fn reconcile(&self, seen: &mut HashMap<String, String>) -> Result<()> {
+        let configs = self.generic.store.list()?;
+        let plan = plan_reconcile(
+            &fingerprints(&configs, |c| c.id.as_str()),
+            &self.generic.running_ids(),
+            seen,
+        );
+        for id in &plan.stop {
+            self.generic.stop_source(id).await?;
+        }
+        for config in configs.iter().filter(|c| plan.start.contains(&c.id)) {
+            let token = self.stored_secret("generic", &config.id);
+            let params = match config.resolve_params(|key| self.stored_secret("generic", key)) {
+                Ok(params) => params,
+                Err(e) => {
+                    warn!(
+                        source_id = %config.id,
+                        error = %e,
+                        "Failed to resolve generic source params"
+                    );
+                    continue;
+                }
+            };
+            if let Err(e) = self.generic.start_source(config, token, params).await {
+                warn!(source_id = %config.id, error = %e, "Failed to start generic source");
+            }
+        }
+
+        // Paused sources are left out, so a running one is stopped
+        let mut configs = self.named.store.list()?;
+        configs.retain(|c| !c.paused);
+        let plan = plan_reconcile(
+            &fingerprints(&configs, |c| c.id.as_str()),
+            &self.named.running_ids(),
+            seen,
+        );
+        for id in &plan.stop {
+            self.named.stop_source(id).await?;
+        }
+        for config in configs.iter().filter(|c| plan.start.contains(&c.id)) {
+            if let Err(e) = self.named.start_source(config).await {
+                warn!(source_id = %config.id, tap = %config.tap_name, error = %e, "Failed to start named source");
+            }
+        }
+
+        let configs = self.file.store.list()?;
+        let plan = plan_reconcile(
+            &fingerprints(&configs, |c| c.id.as_str()),
+            &self.file.running_ids(),
+            seen,
+        );
+        for id in &plan.stop {
+            self.file.stop_source(id).await?;
+        }
+        for config in configs.iter().filter(|c| plan.start.contains(&c.id)) {
+            if let Err(e) = self.file.start_source(config).await {
+                warn!(source_id = %config.id, error = %e, "Failed to start file source");
+            }
+        }
+
+        let configs = self.postgres.store.list()?;
+        let plan = plan_reconcile(
+            &fingerprints(&configs, |c| c.id.as_str()),
+            &self.postgres.running_ids(),
+            seen,
+        );
+        for id in &plan.stop {
+            self.postgres.stop_source(id).await?;
+        }
+        for config in configs.iter().filter(|c| plan.start.contains(&c.id)) {
+            let password = self.stored_secret("postgres", &config.id);
+            if let Err(e) = self.postgres.start_source(config, password).await {
+                warn!(source_id = %config.id, error = %e, "Failed to start postgres source");
+            }
+        }
+
+        let configs = self.weather.store.list()?;
+        let plan = plan_reconcile(
+            &fingerprints(&configs, |c| c.id.as_str()),
+            &self.weather.running_ids(),
+            seen,
+        );
+        for id in &plan.stop {
+            self.weather.stop_source(id).await?;
+        }
+        for config in configs.iter().filter(|c| plan.start.contains(&c.id)) {
+            if let Err(e) = self.weather.start_source(config).await {
+                warn!(source_id = %config.id, error = %e, "Failed to start weather source");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops every running source (leadership lost).
+    pub async fn stop_all(&self) {
+        for id in self.generic.running_ids() {
+            let _ = self.generic.stop_source(&id).await;
+        }
+        for id in self.named.running_ids() {
+            let _ = self.named.stop_source(&id).await;
+        }
+        for id in self.file.running_ids() {
+            let _ = self.file.stop_source(&id).await;
+        }
+        for id in self.postgres.running_ids() {
+            let _ = self.postgres.stop_source(&id).await;
+        }
+        for id in self.weather.running_ids() {
+            let _ = self.weather.stop_source(&id).await;
+        }
+    }
+
+    fn stored_secret(&self, kind: &str, source_id: &str) -> Option<String> {
+        self.credential_store
+            .get(kind, source_id)
+            .ok()
+            .flatten()
+            .map(|c| c.access_token)
+    }
+}
+
+/// Runs the builtin schedulers and source pollers while this replica leads.
+///
+/// On gaining leadership the connector manager starts and sources are
+/// reconciled every `reconcile_every`; on losing it everything is stopped.
+pub async fn run_leader_duties(
+    mut leadership: Leadership,
+    mut manager: ConnectorManager,
+    sources: Arc<LeaderSources>,
+    reconcile_every: Duration,
+) {
+    loop {
+        if !leadership.wait_for(true).await {
+            return;
+        }
+        match manager.start().await {
+            Ok(started) => info!(schedulers_started = started, "Connector manager started"),
+            Err(e) => warn!(error = %e, "Failed to start connector manager"),
+        }
+
+        {
+            let lost = async {
+                if !leadership.wait_for(false).await {
+                    // Standalone: leadership never ends
+                    std::future::pending::<()>().await;
+                }
+            };
+            tokio::pin!(lost);
+            let mut seen = HashMap::new();
+            let mut interval = tokio::time::interval(reconcile_every);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        if let Err(e) = sources.reconcile(&mut seen).await {
+                            warn!(error = %e, "Source reconcile failed");
+                        }
+                    }
+                    _ = &mut lost => break,
+                }
+            }
+        }
+
+        info!("Stopping connectors after losing leadership");
+        manager.shutdown().await;
+        sources.stop_all().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flux::leader::LeaderElector;
+    use tempfile::NamedTempFile;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_lease_is_exclusive_until_expiry() {
+        let db = NamedTempFile::new().unwrap();
+        let path = db.path().to_str().unwrap();
+        let a = SqliteLeaseStore::new(path, "connector-manager").unwrap();
+        let b = SqliteLeaseStore::new(path, "connector-manager").unwrap();
+
+        let lease = a.acquire("a", at(0), at(15)).await.unwrap();
+        assert_eq!(lease.holder, "a");
+        let lease = b.acquire("b", at(5), at(20)).await.unwrap();
+        assert_eq!(lease.holder, "a");
+        assert_eq!(lease.expires_at, at(15));
+
+        // Renewal by the holder extends it
+        assert_eq!(
+            a.acquire("a", at(10), at(25)).await.unwrap().expires_at,
+            at(25)
+        );
+        assert_eq!(b.acquire("b", at(20), at(35)).await.unwrap().holder, "a");
+
+        let lease = b.acquire("b", at(25), at(40)).await.unwrap();
+        assert_eq!(lease.holder, "b");
+        assert_eq!(a.acquire("a", at(26), at(41)).await.unwrap().holder, "b");
+    }
+
+    #[tokio::test]
+    async fn test_electors_share_sqlite_lease() {
+        let db = NamedTempFile::new().unwrap();
+        let path = db.path().to_str().unwrap();
+        let ttl = Duration::from_secs(15);
+        let store = || Arc::new(SqliteLeaseStore::new(path, "connector-manager").unwrap());
+        let (mut a, lead_a) = LeaderElector::new(store(), "a".to_string(), ttl);
+        let (mut b, lead_b) = LeaderElector::new(store(), "b".to_string(), ttl);
+
+        assert!(a.tick(at(0)).await);
+        assert!(!b.tick(at(1)).await);
+        assert_eq!(lead_b.status().leader.as_deref(), Some("a"));
+        assert!(b.tick(at(16)).await);
+        assert!(!a.tick(at(17)).await);
+        assert!(!lead_a.is_leader());
+    }
+
+    fn configs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(id, fp)| (id.to_string(), fp.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_reconcile() {
+        let mut seen = HashMap::new();
+        let running = vec!["local".to_string()];
+
+        // First pass: start what is not running, adopt what is
+        let plan = plan_reconcile(
+            &configs(&[("new", "1"), ("local", "1")]),
+            &running,
+            &mut seen,
+        );
+        assert_eq!(plan.start, vec!["new"]);
+        assert!(plan.stop.is_empty());
+
+        // Config changed on a follower: restart; deleted: stop
+        let running = vec!["new".to_string(), "local".to_string()];
+        let plan = plan_reconcile(&configs(&[("local", "2")]), &running, &mut seen);
+        assert_eq!(
+            plan,
+            ReconcilePlan {
+                stop: vec!["local".to_string(), "new".to_string()],
+                start: vec!["local".to_string()],
+            }
+        );
+        assert!(!seen.contains_key("new"));
+
+        // Nothing changed: nothing to do
+        let running = vec!["local".to_string()];
+        let plan = plan_reconcile(&configs(&[("local", "2")]), &running, &mut seen);
+        assert_eq!(plan, ReconcilePlan::default());
+    }
+}
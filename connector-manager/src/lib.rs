@@ -30,16 +30,21 @@
 //! # Core Types
 //!
 //! - [`Connector`] - Trait that all connectors must implement
+//! - [`ConnectorError`] - Classified fetch failure (auth, rate limit, transient, permanent)
 //! - [`OAuthConfig`] - OAuth configuration (auth URL, token URL, scopes)
+//! - [`registry::ConnectorRegistry`] - Connectors the manager schedules; register
+//!   custom ones there and pass it to [`ConnectorManager::with_registry`]
 //! - [`Credentials`] - OAuth credentials (access token, refresh token)
-//! - [`FluxEvent`] - Re-exported from flux crate (event format)
+//! - [`ETagCache`] - ETags carried between polls for conditional requests
+//! - [`FluxEvent`] - Re-exported from flux crate (event format); build state
+//!   events with [`flux::event::FluxEventBuilder`]
 //!
 //! # Creating a Connector
 //!
 //! ```no_run
-//! use connector_manager::{Connector, OAuthConfig, Credentials};
+//! use connector_manager::{Connector, ConnectorError, OAuthConfig, Credentials};
 //! use async_trait::async_trait;
-//! use anyhow::Result;
+//! use flux::event::FluxEventBuilder;
 //! use flux::FluxEvent;
 //!
 //! struct MyConnector;
@@ -58,12 +63,15 @@
 //!         }
 //!     }
 //!
-//!     async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>> {
-//!         // 1. Use credentials.access_token to authenticate
-//!         // 2. Fetch data from external API
-//!         // 3. Transform to Flux events
-//!         // 4. Return events
-//!         Ok(vec![])
+//!     async fn fetch(&self, credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+//!         // Use credentials.access_token to fetch data from the external API,
+//!         // then transform each item into a Flux event
+//!         let event = FluxEventBuilder::new("connectors", "connector-manager")
+//!             .entity("myservice/item/42")
+//!             .key_from_entity()
+//!             .property("title", "Example")
+//!             .build()?;
+//!         Ok(vec![event])
 //!     }
 //!
 //!     fn poll_interval(&self) -> u64 {
@@ -72,22 +80,36 @@
 //! }
 //! ```
 
-mod connector;
-mod types;
 pub mod api;
+pub mod auth;
+pub mod cli;
+mod connector;
 pub mod connectors;
+mod error;
+pub mod file_config;
 pub mod generic_config;
+pub mod http;
+pub mod leader;
 pub mod manager;
+pub mod metrics;
 pub mod named_config;
+pub mod postgres_config;
 pub mod registry;
 pub mod runners;
+pub mod transform;
+pub mod transform_config;
+mod types;
+pub mod user_config;
+pub mod weather_config;
 
 // Re-export public types
-pub use connector::Connector;
+pub use connector::{namespaced, Connector};
+pub use error::ConnectorError;
 pub use manager::ConnectorManager;
 pub use runners::builtin::{ConnectorScheduler, ConnectorStatus};
-pub use types::OAuthConfig;
+pub use types::{ConnectorType, OAuthConfig};
 
 // Re-export FluxEvent and Credentials from flux crate for convenience
-pub use flux::credentials::Credentials;
+pub use flux::api::oauth::ClientAuth;
+pub use flux::credentials::{Credentials, ETagCache};
 pub use flux::FluxEvent;
@@ -1,56 +1,180 @@
 use anyhow::{Context, Result};
-use connector_manager::api::{create_router, ApiState};
+use clap::Parser;
+use connector_manager::api::{create_openapi_router, create_router, ApiState};
+use connector_manager::auth::ApiAuth;
+use connector_manager::cli::{self, ApiClient};
+use connector_manager::file_config::FileConfigStore;
 use connector_manager::generic_config::GenericConfigStore;
+use connector_manager::http;
+use connector_manager::leader::{run_leader_duties, LeaderSources, SqliteLeaseStore};
 use connector_manager::manager::ConnectorManager;
+use connector_manager::metrics::ConnectorMetrics;
 use connector_manager::named_config::NamedConfigStore;
+use connector_manager::postgres_config::PostgresConfigStore;
+use connector_manager::registry::ConnectorRegistry;
+use connector_manager::runners::file::FileRunner;
 use connector_manager::runners::generic::GenericRunner;
 use connector_manager::runners::named::{NamedRunner, TapCatalogStore};
+use connector_manager::runners::postgres::PostgresRunner;
+use connector_manager::runners::rate_limit::PublishLimiter;
+use connector_manager::runners::weather::WeatherRunner;
+use connector_manager::transform_config::TransformConfigStore;
+use connector_manager::user_config::UserConfigStore;
+use connector_manager::weather_config::WeatherConfigStore;
+use flux::api::with_request_tracing;
 use flux::credentials::CredentialStore;
+use flux::idempotency::{run_idempotency_sweeper, IdempotencyStore, DEFAULT_IDEMPOTENCY_TTL};
+use flux::leader::{LeaderElector, Leadership};
+use flux::namespace::NamespaceStore;
 use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn};
 
+#[derive(Parser)]
+#[command(name = "connector-manager", version, about = "Flux connector manager")]
+struct Cli {
+    /// Runs the server when omitted
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+    /// Connector API base URL (subcommands)
+    #[arg(
+        long,
+        global = true,
+        env = "CONNECTOR_MANAGER_URL",
+        default_value = "http://localhost:3001"
+    )]
+    url: String,
+    /// Bearer token when Flux auth is enabled (subcommands)
+    #[arg(long, global = true, env = "FLUX_TOKEN")]
+    token: Option<String>,
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize tracing subscriber
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "connector_manager=info".into()),
+                .unwrap_or_else(|_| "connector_manager=info,tower_http=info".into()),
         )
         .init();
 
+    match cli.command {
+        None => serve().await,
+        Some(command) => {
+            let client = ApiClient::new(&cli.url, cli.token)?;
+            println!("{}", cli::run(&client, command, cli.json).await?);
+            Ok(())
+        }
+    }
+}
+
+async fn serve() -> Result<()> {
     info!("Connector Manager starting...");
 
     // Read configuration from environment
-    let flux_api_url = std::env::var("FLUX_API_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let flux_api_url =
+        std::env::var("FLUX_API_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
 
     let encryption_key = std::env::var("FLUX_ENCRYPTION_KEY")
         .context("FLUX_ENCRYPTION_KEY is required (base64-encoded 32-byte key)")?;
 
-    let credentials_db = std::env::var("FLUX_CREDENTIALS_DB")
-        .unwrap_or_else(|_| "credentials.db".to_string());
+    let credentials_db =
+        std::env::var("FLUX_CREDENTIALS_DB").unwrap_or_else(|_| "credentials.db".to_string());
+
+    let generic_config_db =
+        std::env::var("GENERIC_CONFIG_DB").unwrap_or_else(|_| "generic_config.db".to_string());
+
+    let named_config_db =
+        std::env::var("NAMED_CONFIG_DB").unwrap_or_else(|_| "named_config.db".to_string());
+
+    let file_config_db =
+        std::env::var("FILE_CONFIG_DB").unwrap_or_else(|_| "file_config.db".to_string());
+
+    let postgres_config_db =
+        std::env::var("POSTGRES_CONFIG_DB").unwrap_or_else(|_| "postgres_config.db".to_string());
+
+    let weather_config_db =
+        std::env::var("WEATHER_CONFIG_DB").unwrap_or_else(|_| "weather_config.db".to_string());
+
+    let transform_config_db =
+        std::env::var("TRANSFORM_CONFIG_DB").unwrap_or_else(|_| "transform_config.db".to_string());
+
+    let user_config_db =
+        std::env::var("USER_CONFIG_DB").unwrap_or_else(|_| "user_config.db".to_string());
+
+    let idempotency_db =
+        std::env::var("IDEMPOTENCY_DB").unwrap_or_else(|_| "idempotency.db".to_string());
+
+    // External connector manifests; executables must be in the exec directory
+    let external_connectors_dir = std::env::var("EXTERNAL_CONNECTORS_DIR").ok();
+    let external_connectors_exec_dir = std::env::var("EXTERNAL_CONNECTORS_EXEC_DIR")
+        .ok()
+        .or_else(|| external_connectors_dir.clone());
 
-    let generic_config_db = std::env::var("GENERIC_CONFIG_DB")
-        .unwrap_or_else(|_| "generic_config.db".to_string());
+    // Shared lease DB enables leader election between replicas
+    let leader_lease_db = std::env::var("LEADER_LEASE_DB").ok();
 
-    let named_config_db = std::env::var("NAMED_CONFIG_DB")
-        .unwrap_or_else(|_| "named_config.db".to_string());
+    let instance_id = std::env::var("CONNECTOR_INSTANCE_ID")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| uuid::Uuid::new_v4().to_string());
+
+    let leader_ttl_secs: u64 = std::env::var("LEADER_TTL_SECS")
+        .unwrap_or_else(|_| "15".to_string())
+        .parse()
+        .context("LEADER_TTL_SECS must be a number of seconds")?;
 
     let api_port: u16 = std::env::var("CONNECTOR_API_PORT")
         .unwrap_or_else(|_| "3001".to_string())
         .parse()
         .context("CONNECTOR_API_PORT must be a valid port number")?;
 
+    // Events per second published by all sources together (0 = unlimited)
+    let publish_max_events_per_sec: u32 = std::env::var("PUBLISH_MAX_EVENTS_PER_SEC")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse()
+        .context("PUBLISH_MAX_EVENTS_PER_SEC must be a number of events")?;
+
+    // API auth follows Flux's: the same admin token, and namespace tokens
+    // from Flux's namespace DB
+    let auth_enabled = std::env::var("FLUX_AUTH_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    // Swagger UI at /api/docs (the OpenAPI JSON is always served)
+    let api_docs_enabled = std::env::var("CONNECTOR_API_DOCS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
     info!(
         flux_api_url = %flux_api_url,
         credentials_db = %credentials_db,
         generic_config_db = %generic_config_db,
         named_config_db = %named_config_db,
+        file_config_db = %file_config_db,
+        postgres_config_db = %postgres_config_db,
+        weather_config_db = %weather_config_db,
+        external_connectors_dir = ?external_connectors_dir,
+        leader_lease_db = ?leader_lease_db,
+        instance_id = %instance_id,
         api_port = api_port,
+        publish_max_events_per_sec = publish_max_events_per_sec,
         "Configuration loaded"
     );
 
+    // Outbound HTTP settings for every runner and connector; fail on a bad proxy now
+    let http_config = http::config_from_env(&flux_api_url);
+    if let Some(ref proxy) = http_config.proxy {
+        info!(proxy = %proxy.redacted_url(), "Outbound HTTP goes through a proxy");
+    }
+    http::init(http_config);
+    http::client().context("Invalid outbound HTTP settings")?;
+
     // Initialize credential store (shared by manager and generic runner)
     let credential_store = Arc::new(
         CredentialStore::new(&credentials_db, &encryption_key)
@@ -58,6 +182,9 @@ async fn main() -> Result<()> {
     );
     info!("Credential store initialized");
 
+    let limiter = Arc::new(PublishLimiter::new(publish_max_events_per_sec));
+    let metrics = Arc::new(ConnectorMetrics::new());
+
     // Initialize generic config store
     let generic_config_store = Arc::new(
         GenericConfigStore::new(&generic_config_db)
@@ -66,28 +193,11 @@ async fn main() -> Result<()> {
     info!("Generic config store initialized");
 
     // Initialize generic runner
-    let generic_runner = Arc::new(GenericRunner::new(
-        Arc::clone(&generic_config_store),
-        flux_api_url.clone(),
-    ));
-
-    // Restart any persisted generic sources from a previous session
-    let persisted = generic_config_store
-        .list()
-        .context("Failed to list persisted generic sources")?;
-    if !persisted.is_empty() {
-        info!(count = persisted.len(), "Restarting persisted generic sources");
-        for config in &persisted {
-            let token = credential_store
-                .get("generic", &config.id)
-                .ok()
-                .flatten()
-                .map(|c| c.access_token);
-            if let Err(e) = generic_runner.start_source(config, token).await {
-                warn!(source_id = %config.id, error = %e, "Failed to restart generic source");
-            }
-        }
-    }
+    let generic_runner = Arc::new(
+        GenericRunner::new(Arc::clone(&generic_config_store), flux_api_url.clone())
+            .with_limiter(Arc::clone(&limiter))
+            .with_metrics(Arc::clone(&metrics)),
+    );
 
     // Initialize named config store
     let named_config_store = Arc::new(
@@ -97,23 +207,72 @@ async fn main() -> Result<()> {
     info!("Named config store initialized");
 
     // Initialize named runner
-    let named_runner = Arc::new(NamedRunner::new(
-        Arc::clone(&named_config_store),
-        flux_api_url.clone(),
-    ));
+    let named_runner = Arc::new(
+        NamedRunner::new(Arc::clone(&named_config_store), flux_api_url.clone())
+            .with_limiter(Arc::clone(&limiter))
+            .with_metrics(Arc::clone(&metrics)),
+    );
+
+    // Initialize file-drop config store and runner
+    let file_config_store = Arc::new(
+        FileConfigStore::new(&file_config_db).context("Failed to initialize file config store")?,
+    );
+    info!("File config store initialized");
+
+    let file_runner = Arc::new(
+        FileRunner::new(Arc::clone(&file_config_store), flux_api_url.clone())
+            .with_limiter(Arc::clone(&limiter)),
+    );
+
+    // Initialize Postgres config store and runner
+    let postgres_config_store = Arc::new(
+        PostgresConfigStore::new(&postgres_config_db)
+            .context("Failed to initialize postgres config store")?,
+    );
+    info!("Postgres config store initialized");
+
+    let postgres_runner = Arc::new(
+        PostgresRunner::new(Arc::clone(&postgres_config_store), flux_api_url.clone())
+            .with_limiter(Arc::clone(&limiter)),
+    );
+
+    // Initialize weather config store and runner
+    let weather_config_store = Arc::new(
+        WeatherConfigStore::new(&weather_config_db)
+            .context("Failed to initialize weather config store")?,
+    );
+    info!("Weather config store initialized");
+
+    let weather_runner = Arc::new(
+        WeatherRunner::new(Arc::clone(&weather_config_store), flux_api_url.clone())
+            .with_limiter(Arc::clone(&limiter)),
+    );
+
+    // Builtin connectors' transform rules; sources keep theirs on their config
+    let transform_config_store = Arc::new(
+        TransformConfigStore::new(&transform_config_db)
+            .context("Failed to initialize transform config store")?,
+    );
+    info!("Transform config store initialized");
 
-    // Restart any persisted named sources from a previous session
-    let persisted_named = named_config_store
-        .list()
-        .context("Failed to list persisted named sources")?;
-    if !persisted_named.is_empty() {
-        info!(count = persisted_named.len(), "Restarting persisted named sources");
-        for config in &persisted_named {
-            if let Err(e) = named_runner.start_source(config).await {
-                warn!(source_id = %config.id, tap = %config.tap_name, error = %e, "Failed to restart named source");
+    // Builtin connectors' per-user configuration, e.g. Notion databases
+    let user_config_store = Arc::new(
+        UserConfigStore::new(&user_config_db).context("Failed to initialize user config store")?,
+    );
+    info!("User config store initialized");
+
+    // Load external connectors next to the builtin ones
+    let registry = match (external_connectors_dir, external_connectors_exec_dir) {
+        (Some(dir), Some(exec_dir)) => {
+            let registry = ConnectorRegistry::with_external(dir.into(), exec_dir.into());
+            if let Err(e) = registry.reload() {
+                warn!(error = %e, "Failed to load external connectors");
             }
+            registry
         }
-    }
+        _ => ConnectorRegistry::default(),
+    };
+    let registry = Arc::new(registry);
 
     // Initialize tap catalog store (load from disk if cached, else empty)
     let tap_catalog_path = std::env::var("TAP_CATALOG_CACHE")
@@ -132,10 +291,69 @@ async fn main() -> Result<()> {
         }
     });
 
-    // Initialize connector manager (builtin connectors)
-    let mut manager = ConnectorManager::new(Arc::clone(&credential_store), flux_api_url);
-    let started = manager.start().await?;
-    info!(schedulers_started = started, "Connector manager started");
+    // Leader election: without a shared lease DB this instance always leads
+    let leadership = match &leader_lease_db {
+        Some(path) => {
+            let lease_store = SqliteLeaseStore::new(path, "connector-manager")
+                .context("Failed to initialize leader lease store")?;
+            let (elector, leadership) = LeaderElector::new(
+                Arc::new(lease_store),
+                instance_id.clone(),
+                std::time::Duration::from_secs(leader_ttl_secs.max(3)),
+            );
+            tokio::spawn(elector.run());
+            info!(instance_id = %instance_id, "Leader election started");
+            leadership
+        }
+        None => Leadership::standalone(instance_id),
+    };
+
+    // Builtin connectors and sources run only on the leader; persisted sources
+    // are started by its first reconcile pass
+    let manager = ConnectorManager::new(Arc::clone(&credential_store), flux_api_url)
+        .with_registry(Arc::clone(&registry))
+        .with_limiter(Arc::clone(&limiter))
+        .with_metrics(Arc::clone(&metrics))
+        .with_transforms(Arc::clone(&transform_config_store))
+        .with_user_config(Arc::clone(&user_config_store));
+    let builtin_status = manager.status_map();
+    let leader_sources = Arc::new(LeaderSources {
+        credential_store: Arc::clone(&credential_store),
+        generic: Arc::clone(&generic_runner),
+        named: Arc::clone(&named_runner),
+        file: Arc::clone(&file_runner),
+        postgres: Arc::clone(&postgres_runner),
+        weather: Arc::clone(&weather_runner),
+    });
+    let duties_handle = tokio::spawn(run_leader_duties(
+        leadership.clone(),
+        manager,
+        leader_sources,
+        std::time::Duration::from_secs(30),
+    ));
+
+    let api_auth = if auth_enabled {
+        let namespace_db =
+            std::env::var("FLUX_NAMESPACE_DB").unwrap_or_else(|_| "namespaces.db".to_string());
+        let namespaces =
+            NamespaceStore::new(&namespace_db).context("Failed to open Flux namespace DB")?;
+        let admin_token = std::env::var("FLUX_ADMIN_TOKEN").ok();
+        if admin_token.is_none() {
+            warn!("FLUX_ADMIN_TOKEN not set - no connector API caller is an admin");
+        }
+        info!(namespace_db = %namespace_db, "Connector API auth enabled");
+        Some(Arc::new(ApiAuth::new(admin_token, namespaces)))
+    } else {
+        None
+    };
+
+    // Idempotency-Keys of source creation, forgotten after a day
+    let idempotency =
+        Arc::new(IdempotencyStore::new(&idempotency_db).context("Failed to open idempotency DB")?);
+    tokio::spawn(run_idempotency_sweeper(
+        Arc::clone(&idempotency),
+        DEFAULT_IDEMPOTENCY_TTL / 24,
+    ));
 
     // Start HTTP API server
     let api_state = ApiState {
@@ -144,8 +362,38 @@ async fn main() -> Result<()> {
         credential_store: Arc::clone(&credential_store),
         tap_catalog: Arc::clone(&tap_catalog),
         named_runner: Arc::clone(&named_runner),
+        file_runner: Arc::clone(&file_runner),
+        postgres_runner: Arc::clone(&postgres_runner),
+        weather_runner: Arc::clone(&weather_runner),
+        transform_store: transform_config_store,
+        user_config_store,
+        registry,
+        builtin_status,
+        leadership,
+        limiter,
+        metrics,
+        auth: api_auth,
+        idempotency,
     };
-    let router = create_router(api_state);
+    // CORS — the Flux monitor UI calls this API from the Flux origin
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([
+            axum::http::Method::GET,
+            axum::http::Method::POST,
+            axum::http::Method::PUT,
+            axum::http::Method::DELETE,
+            axum::http::Method::OPTIONS,
+        ])
+        .allow_headers([
+            axum::http::header::AUTHORIZATION,
+            axum::http::header::CONTENT_TYPE,
+            axum::http::HeaderName::from_static("idempotency-key"),
+        ]);
+    let router = with_request_tracing(
+        create_router(api_state).merge(create_openapi_router(api_docs_enabled)),
+    )
+    .layer(cors);
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", api_port))
         .await
         .context("Failed to bind connector API port")?;
@@ -165,7 +413,7 @@ async fn main() -> Result<()> {
 
     // Graceful shutdown
     server_handle.abort();
-    manager.shutdown().await;
+    duties_handle.abort();
     info!("Connector manager stopped");
 
     Ok(())
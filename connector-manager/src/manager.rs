@@ -3,9 +3,15 @@
 //! Loads available connectors, retrieves credentials from storage,
 //! and starts polling schedulers for each user-connector pair.
 
-use crate::registry::get_all_connectors;
-use crate::runners::builtin::{ConnectorScheduler, ConnectorStatus};
+use crate::metrics::ConnectorMetrics;
+use crate::registry::ConnectorRegistry;
+use crate::runners::builtin::{credentials_fingerprint, ConnectorScheduler, ConnectorStatus};
+use crate::runners::rate_limit::PublishLimiter;
+use crate::transform_config::TransformConfigStore;
+use crate::user_config::UserConfigStore;
+use crate::{Connector, Credentials};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use flux::credentials::CredentialStore;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -13,6 +19,14 @@ use tokio::task::JoinHandle;
 use tokio::time;
 use tracing::{info, warn};
 
+/// Delay before the second consecutive restart of an errored scheduler
+const RESTART_BACKOFF_BASE_SECS: i64 = 60;
+/// Upper bound on the restart delay
+const RESTART_BACKOFF_MAX_SECS: i64 = 3600;
+
+/// Status of every running scheduler, keyed by `{user_id}:{connector}`
+type StatusMap = Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>>;
+
 /// Connector manager - Orchestrates all connector polling.
 ///
 /// # Responsibilities
@@ -26,10 +40,20 @@ pub struct ConnectorManager {
     credential_store: Arc<CredentialStore>,
     /// Flux API base URL
     flux_api_url: String,
+    /// Builtin and external connectors
+    registry: Arc<ConnectorRegistry>,
+    /// Publish rate limit shared with the other runners
+    limiter: Arc<PublishLimiter>,
+    /// Poll and publish metrics shared with the other runners
+    metrics: Arc<ConnectorMetrics>,
+    /// Per-user transform rules, if configured
+    transforms: Option<Arc<TransformConfigStore>>,
+    /// Per-user connector configuration, if configured
+    user_config: Option<Arc<UserConfigStore>>,
     /// Discovery loop task handle
     scheduler_handles: Vec<JoinHandle<()>>,
     /// Status tracking per (user_id, connector) pair
-    status_map: Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>>,
+    status_map: StatusMap,
     /// Per-key scheduler handles — enables per-key abort/restart
     connector_handles: Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>>,
 }
@@ -44,16 +68,59 @@ impl ConnectorManager {
         Self {
             credential_store,
             flux_api_url,
+            registry: Arc::new(ConnectorRegistry::default()),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+            metrics: Arc::new(ConnectorMetrics::new()),
+            transforms: None,
+            user_config: None,
             scheduler_handles: Vec::new(),
             status_map: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             connector_handles: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
         }
     }
 
+    /// Uses `registry` instead of the builtin connectors alone.
+    pub fn with_registry(mut self, registry: Arc<ConnectorRegistry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Paces every scheduler's publishing with `limiter`.
+    pub fn with_limiter(mut self, limiter: Arc<PublishLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Records every scheduler's polls and publishes in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<ConnectorMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Applies the transform rules in `store` to every scheduler's events.
+    pub fn with_transforms(mut self, store: Arc<TransformConfigStore>) -> Self {
+        self.transforms = Some(store);
+        self
+    }
+
+    /// Passes each user's configuration in `store` to their schedulers' fetches.
+    pub fn with_user_config(mut self, store: Arc<UserConfigStore>) -> Self {
+        self.user_config = Some(store);
+        self
+    }
+
+    fn scheduler_settings(&self) -> SchedulerSettings {
+        SchedulerSettings {
+            flux_api_url: self.flux_api_url.clone(),
+            limiter: Arc::clone(&self.limiter),
+            metrics: Arc::clone(&self.metrics),
+            transforms: self.transforms.clone(),
+            user_config: self.user_config.clone(),
+        }
+    }
+
     /// Returns a clone of the status map for external monitoring.
-    pub fn status_map(
-        &self,
-    ) -> Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>> {
+    pub fn status_map(&self) -> StatusMap {
         Arc::clone(&self.status_map)
     }
 
@@ -71,7 +138,7 @@ impl ConnectorManager {
         info!("Starting connector manager");
 
         // Load all available connectors
-        let connectors = get_all_connectors();
+        let connectors = self.registry.connectors();
         info!(connector_count = connectors.len(), "Loaded connectors");
 
         if connectors.is_empty() {
@@ -87,7 +154,10 @@ impl ConnectorManager {
 
         let mut started_count = 0;
         for (user_id, connector_name) in &all_credentials {
-            if !connectors.iter().any(|c| c.name() == connector_name.as_str()) {
+            if !connectors
+                .iter()
+                .any(|c| c.name() == connector_name.as_str())
+            {
                 warn!(connector = %connector_name, "Skipping unknown connector in credential store");
                 continue;
             }
@@ -111,9 +181,10 @@ impl ConnectorManager {
         //  - restarts schedulers that have entered an error state
         //  - removes schedulers whose credentials were deleted
         let cred_store = Arc::clone(&self.credential_store);
+        let registry = Arc::clone(&self.registry);
         let status_map = Arc::clone(&self.status_map);
         let conn_handles = Arc::clone(&self.connector_handles);
-        let flux_url = self.flux_api_url.clone();
+        let settings = self.scheduler_settings();
 
         let discovery_handle = tokio::spawn(async move {
             let mut interval = time::interval(time::Duration::from_secs(60));
@@ -121,7 +192,15 @@ impl ConnectorManager {
 
             loop {
                 interval.tick().await;
-                run_discovery_cycle(&cred_store, &status_map, &conn_handles, &flux_url).await;
+                run_discovery_cycle(
+                    &cred_store,
+                    &registry,
+                    &status_map,
+                    &conn_handles,
+                    &settings,
+                    Utc::now(),
+                )
+                .await;
             }
         });
 
@@ -149,10 +228,9 @@ impl ConnectorManager {
         );
 
         // Find the connector
-        let connectors = get_all_connectors();
-        let connector = connectors
-            .iter()
-            .find(|c| c.name() == connector_name)
+        let connector = self
+            .registry
+            .get(connector_name)
             .context(format!("Connector '{}' not found", connector_name))?;
 
         // Get credentials
@@ -171,12 +249,11 @@ impl ConnectorManager {
         );
 
         // Create scheduler
-        let scheduler = ConnectorScheduler::new(
-            user_id.to_string(),
-            Arc::clone(connector),
+        let scheduler = self.scheduler_settings().scheduler(
+            user_id,
+            connector,
             credentials,
-            self.flux_api_url.clone(),
-            Arc::clone(&self.credential_store),
+            &self.credential_store,
         );
 
         let status_handle = scheduler.status();
@@ -194,7 +271,10 @@ impl ConnectorManager {
             handles.insert(status_key.clone(), handle);
         }
 
-        self.status_map.lock().await.insert(status_key, status_handle);
+        self.status_map
+            .lock()
+            .await
+            .insert(status_key, status_handle);
 
         info!(
             user_id = %user_id,
@@ -220,11 +300,16 @@ impl ConnectorManager {
         let mut handles = self.connector_handles.lock().await;
         let count = handles.len();
         if count > 0 {
-            info!(scheduler_count = count, "Aborting connector scheduler tasks");
+            info!(
+                scheduler_count = count,
+                "Aborting connector scheduler tasks"
+            );
             for (_, handle) in handles.drain() {
                 handle.abort();
             }
         }
+        drop(handles);
+        self.status_map.lock().await.clear();
 
         info!("All scheduler tasks aborted");
     }
@@ -245,17 +330,62 @@ impl Drop for ConnectorManager {
     }
 }
 
+/// What every scheduler the manager starts shares.
+struct SchedulerSettings {
+    flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    metrics: Arc<ConnectorMetrics>,
+    transforms: Option<Arc<TransformConfigStore>>,
+    user_config: Option<Arc<UserConfigStore>>,
+}
+
+impl SchedulerSettings {
+    fn scheduler(
+        &self,
+        user_id: &str,
+        connector: Arc<dyn Connector>,
+        credentials: Credentials,
+        cred_store: &Arc<CredentialStore>,
+    ) -> ConnectorScheduler {
+        let scheduler = ConnectorScheduler::new(
+            user_id.to_string(),
+            connector,
+            credentials,
+            self.flux_api_url.clone(),
+            Arc::clone(cred_store),
+        )
+        .with_limiter(Arc::clone(&self.limiter))
+        .with_metrics(&self.metrics);
+        let scheduler = match &self.transforms {
+            Some(store) => scheduler.with_transforms(Arc::clone(store)),
+            None => scheduler,
+        };
+        match &self.user_config {
+            Some(store) => scheduler.with_user_config(Arc::clone(store)),
+            None => scheduler,
+        }
+    }
+}
+
 /// Runs one iteration of the credential discovery cycle.
 ///
 /// Three responsibilities:
-/// 1. Remove schedulers for credentials that have been deleted
-/// 2. Restart schedulers that have entered an error state (fresh credentials)
+/// 1. Remove schedulers for credentials that have been deleted, or for
+///    connectors no longer in the registry
+/// 2. Restart schedulers that have entered an error state (fresh credentials).
+///    The first restart is immediate; consecutive ones back off per key
+///    (see [`restart_backoff`]) until a poll succeeds. Schedulers stopped on a
+///    permanent error are only restarted once their stored credentials change.
 /// 3. Start schedulers for newly added credentials
+///
+/// `now` is the cycle's clock reading, injected so tests can advance time.
 async fn run_discovery_cycle(
     cred_store: &Arc<CredentialStore>,
-    status_map: &Arc<tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>>,
+    registry: &ConnectorRegistry,
+    status_map: &StatusMap,
     connector_handles: &Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>>,
-    flux_url: &str,
+    settings: &SchedulerSettings,
+    now: DateTime<Utc>,
 ) {
     let all_creds = match cred_store.list_all() {
         Ok(c) => c,
@@ -265,7 +395,7 @@ async fn run_discovery_cycle(
         }
     };
 
-    let connectors = get_all_connectors();
+    let connectors = registry.connectors();
 
     // Build set of currently-credentialed keys for O(1) lookup
     let cred_keys: std::collections::HashSet<String> = all_creds
@@ -276,24 +406,57 @@ async fn run_discovery_cycle(
     // Snapshot existing entries without holding the map lock during status reads
     let existing: Vec<(String, Arc<tokio::sync::Mutex<ConnectorStatus>>)> = {
         let map = status_map.lock().await;
-        map.iter().map(|(k, v)| (k.clone(), Arc::clone(v))).collect()
+        map.iter()
+            .map(|(k, v)| (k.clone(), Arc::clone(v)))
+            .collect()
     };
 
     let mut to_remove: Vec<String> = Vec::new();
-    let mut to_restart: Vec<String> = Vec::new();
+    // Keys to restart, with the restart count carried over to the new status
+    let mut to_restart: Vec<(String, u32)> = Vec::new();
+
+    // An external connector can disappear on a registry reload
+    let registered = |key: &str| {
+        key.split_once(':')
+            .is_some_and(|(_, name)| connectors.iter().any(|c| c.name() == name))
+    };
 
     for (key, status_arc) in &existing {
-        if !cred_keys.contains(key) {
+        if !cred_keys.contains(key) || !registered(key) {
             to_remove.push(key.clone());
         } else {
-            let status = status_arc.lock().await;
-            if status.last_error.is_some() {
-                to_restart.push(key.clone());
+            let mut status = status_arc.lock().await;
+            if status.stopped {
+                if credentials_changed(cred_store, key, status.stopped_credentials) {
+                    to_restart.push((key.clone(), 0));
+                }
+            } else if status.last_error.is_some() {
+                let due = match status.next_retry_at {
+                    Some(at) => now >= at,
+                    None => {
+                        let delay = restart_backoff(status.restart_attempts);
+                        if delay.is_zero() {
+                            true
+                        } else {
+                            status.next_retry_at = Some(now + delay);
+                            info!(
+                                key = %key,
+                                attempts = status.restart_attempts,
+                                delay_secs = delay.num_seconds(),
+                                "Discovery: backing off before restarting errored scheduler"
+                            );
+                            false
+                        }
+                    }
+                };
+                if due {
+                    to_restart.push((key.clone(), status.restart_attempts + 1));
+                }
             }
         }
     }
 
-    // 1. Abort schedulers for deleted credentials
+    // 1. Abort schedulers for deleted credentials or connectors
     for key in &to_remove {
         {
             let mut handles = connector_handles.lock().await;
@@ -302,11 +465,15 @@ async fn run_discovery_cycle(
             }
         }
         status_map.lock().await.remove(key);
-        info!(key = %key, "Discovery: removed scheduler (credentials deleted)");
+        settings.metrics.remove_source("builtin", key);
+        info!(
+            key = %key,
+            "Discovery: removed scheduler (credentials or connector removed)"
+        );
     }
 
     // 2. Restart schedulers in error state
-    for key in &to_restart {
+    for (key, restart_attempts) in &to_restart {
         let parts: Vec<&str> = key.splitn(2, ':').collect();
         if parts.len() != 2 {
             warn!(key = %key, "Discovery: skipping invalid key format");
@@ -344,18 +511,16 @@ async fn run_discovery_cycle(
             }
         };
 
-        let scheduler = ConnectorScheduler::new(
-            user_id.to_string(),
-            connector,
-            credentials,
-            flux_url.to_string(),
-            Arc::clone(cred_store),
-        );
+        let scheduler = settings.scheduler(user_id, connector, credentials, cred_store);
 
         let new_status = scheduler.status();
+        new_status.lock().await.restart_attempts = *restart_attempts;
         let new_handle = scheduler.start();
 
-        connector_handles.lock().await.insert(key.clone(), new_handle);
+        connector_handles
+            .lock()
+            .await
+            .insert(key.clone(), new_handle);
         status_map.lock().await.insert(key.clone(), new_status);
 
         info!(key = %key, "Discovery: restarted errored scheduler");
@@ -402,18 +567,15 @@ async fn run_discovery_cycle(
             }
         };
 
-        let connector = match connectors.iter().find(|c| c.name() == connector_name.as_str()) {
+        let connector = match connectors
+            .iter()
+            .find(|c| c.name() == connector_name.as_str())
+        {
             Some(c) => Arc::clone(c),
             None => continue,
         };
 
-        let scheduler = ConnectorScheduler::new(
-            user_id.clone(),
-            connector,
-            credentials,
-            flux_url.to_string(),
-            Arc::clone(cred_store),
-        );
+        let scheduler = settings.scheduler(user_id, connector, credentials, cred_store);
 
         let status_handle = scheduler.status();
         let handle = scheduler.start();
@@ -430,9 +592,34 @@ async fn run_discovery_cycle(
     }
 }
 
+/// Delay before restarting a scheduler that has already been restarted
+/// `attempts` times in a row: none, then 1m, 2m, 4m, … capped at 1h.
+fn restart_backoff(attempts: u32) -> chrono::Duration {
+    if attempts == 0 {
+        return chrono::Duration::zero();
+    }
+    let secs = RESTART_BACKOFF_BASE_SECS
+        .saturating_mul(1 << (attempts - 1).min(16))
+        .min(RESTART_BACKOFF_MAX_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+/// Whether the stored credentials for `key` differ from those a stopped
+/// scheduler was running with.
+fn credentials_changed(cred_store: &CredentialStore, key: &str, stopped_with: Option<u64>) -> bool {
+    let Some((user_id, connector_name)) = key.split_once(':') else {
+        return false;
+    };
+    match cred_store.get(user_id, connector_name) {
+        Ok(Some(credentials)) => Some(credentials_fingerprint(&credentials)) != stopped_with,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
     use chrono::Utc;
     use flux::credentials::Credentials;
 
@@ -440,7 +627,7 @@ mod tests {
     async fn test_manager_creation() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
         let store = Arc::new(store);
@@ -453,7 +640,7 @@ mod tests {
     async fn test_start_connector_for_user() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
 
@@ -469,7 +656,9 @@ mod tests {
         let mut manager = ConnectorManager::new(store, "http://localhost:3000".to_string());
 
         // Start connector for user
-        let result = manager.start_connector_for_user("test_user", "github").await;
+        let result = manager
+            .start_connector_for_user("test_user", "github")
+            .await;
         assert!(result.is_ok());
 
         // Handle stored in connector_handles, not scheduler_handles
@@ -492,7 +681,7 @@ mod tests {
     async fn test_start_connector_missing_credentials() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
         let store = Arc::new(store);
@@ -500,7 +689,9 @@ mod tests {
         let mut manager = ConnectorManager::new(store, "http://localhost:3000".to_string());
 
         // Try to start connector without credentials
-        let result = manager.start_connector_for_user("test_user", "github").await;
+        let result = manager
+            .start_connector_for_user("test_user", "github")
+            .await;
         assert!(result.is_err());
         assert_eq!(manager.scheduler_handles.len(), 0);
     }
@@ -509,7 +700,7 @@ mod tests {
     async fn test_shutdown() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
 
@@ -543,11 +734,21 @@ mod tests {
 
     /// Verifies that a scheduler whose status shows an error is aborted and
     /// restarted with fresh credentials on the next discovery cycle.
+    fn test_settings() -> SchedulerSettings {
+        SchedulerSettings {
+            flux_api_url: "http://localhost:3000".to_string(),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+            metrics: Arc::new(ConnectorMetrics::new()),
+            transforms: None,
+            user_config: None,
+        }
+    }
+
     #[tokio::test]
     async fn test_discovery_restarts_errored_scheduler() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
         let credentials = Credentials {
@@ -559,9 +760,7 @@ mod tests {
         let store = Arc::new(store);
 
         let status_map: Arc<
-            tokio::sync::Mutex<
-                HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>,
-            >,
+            tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>,
         > = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
         let connector_handles: Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>> =
             Arc::new(tokio::sync::Mutex::new(HashMap::new()));
@@ -572,6 +771,7 @@ mod tests {
             last_poll: None,
             poll_count: 0,
             error_count: 1,
+            ..Default::default()
         }));
         let dummy_handle: JoinHandle<()> = tokio::spawn(async {
             tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
@@ -587,8 +787,15 @@ mod tests {
             .insert("test_user:github".to_string(), dummy_handle);
 
         // Run one discovery cycle
-        run_discovery_cycle(&store, &status_map, &connector_handles, "http://localhost:3000")
-            .await;
+        run_discovery_cycle(
+            &store,
+            &ConnectorRegistry::default(),
+            &status_map,
+            &connector_handles,
+            &test_settings(),
+            Utc::now(),
+        )
+        .await;
 
         // Verify: entry still exists but the status Arc was replaced
         let map = status_map.lock().await;
@@ -607,6 +814,7 @@ mod tests {
             new_status.last_error.is_none(),
             "restarted scheduler should start with no error"
         );
+        assert_eq!(new_status.restart_attempts, 1);
     }
 
     /// Verifies that a scheduler is aborted and removed from status_map when
@@ -615,16 +823,14 @@ mod tests {
     async fn test_discovery_removes_deleted_credentials() {
         let temp_dir = tempfile::tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let encryption_key = base64::encode(&[0u8; 32]);
+        let encryption_key = BASE64.encode([0u8; 32]);
 
         let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
         // No credentials stored — simulates credential deletion
         let store = Arc::new(store);
 
         let status_map: Arc<
-            tokio::sync::Mutex<
-                HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>,
-            >,
+            tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>,
         > = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
         let connector_handles: Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>> =
             Arc::new(tokio::sync::Mutex::new(HashMap::new()));
@@ -645,8 +851,15 @@ mod tests {
             .insert("test_user:github".to_string(), dummy_handle);
 
         // Run one discovery cycle
-        run_discovery_cycle(&store, &status_map, &connector_handles, "http://localhost:3000")
-            .await;
+        run_discovery_cycle(
+            &store,
+            &ConnectorRegistry::default(),
+            &status_map,
+            &connector_handles,
+            &test_settings(),
+            Utc::now(),
+        )
+        .await;
 
         // Verify: entry removed from both maps
         let map = status_map.lock().await;
@@ -661,4 +874,313 @@ mod tests {
             "deleted credentials should remove the handle from connector_handles"
         );
     }
+
+    struct TicketsConnector;
+
+    #[async_trait::async_trait]
+    impl Connector for TicketsConnector {
+        fn name(&self) -> &str {
+            "tickets"
+        }
+
+        fn oauth_config(&self) -> crate::OAuthConfig {
+            crate::OAuthConfig {
+                auth_url: "https://tickets.example.com/authorize".to_string(),
+                token_url: "https://tickets.example.com/token".to_string(),
+                scopes: Vec::new(),
+            }
+        }
+
+        async fn fetch(
+            &self,
+            _credentials: &Credentials,
+        ) -> Result<Vec<crate::FluxEvent>, crate::ConnectorError> {
+            Ok(Vec::new())
+        }
+
+        fn poll_interval(&self) -> u64 {
+            3600
+        }
+    }
+
+    /// Connectors registered by an embedding crate are scheduled once
+    /// credentials for them are stored.
+    #[tokio::test]
+    async fn test_discovery_picks_up_registered_connector() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let store =
+            CredentialStore::new(db_path.to_str().unwrap(), &BASE64.encode([0u8; 32])).unwrap();
+        let store = Arc::new(store);
+
+        let mut registry = ConnectorRegistry::default();
+        registry.register(Arc::new(TicketsConnector)).unwrap();
+
+        let status_map = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let connector_handles = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let settings = test_settings();
+        let cycle = || {
+            run_discovery_cycle(
+                &store,
+                &registry,
+                &status_map,
+                &connector_handles,
+                &settings,
+                Utc::now(),
+            )
+        };
+
+        cycle().await;
+        assert!(status_map.lock().await.is_empty());
+
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        store.store("test_user", "tickets", &credentials).unwrap();
+        cycle().await;
+        assert!(status_map.lock().await.contains_key("test_user:tickets"));
+        assert!(connector_handles
+            .lock()
+            .await
+            .contains_key("test_user:tickets"));
+    }
+
+    /// External connectors get schedulers like builtin ones, which are removed
+    /// once a registry reload drops the connector.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_discovery_follows_external_connectors() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let exec = temp_dir.path().join("echo.sh");
+        std::fs::write(&exec, include_str!("../tests/fixtures/external/echo.sh")).unwrap();
+        std::fs::set_permissions(&exec, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let manifest = temp_dir.path().join("echo.json");
+        let json = serde_json::json!({
+            "name": "echo",
+            "exec": "echo.sh",
+            "poll_interval": 3600,
+            "oauth": {"auth_url": "https://a", "token_url": "https://t", "scopes": []}
+        });
+        std::fs::write(&manifest, json.to_string()).unwrap();
+
+        let registry = ConnectorRegistry::with_external(
+            temp_dir.path().to_path_buf(),
+            temp_dir.path().to_path_buf(),
+        );
+        registry.reload().unwrap();
+
+        let db_path = temp_dir.path().join("test.db");
+        let store =
+            CredentialStore::new(db_path.to_str().unwrap(), &BASE64.encode([0u8; 32])).unwrap();
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        store.store("test_user", "echo", &credentials).unwrap();
+        let store = Arc::new(store);
+
+        let status_map = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let connector_handles = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let settings = test_settings();
+        let cycle = || {
+            run_discovery_cycle(
+                &store,
+                &registry,
+                &status_map,
+                &connector_handles,
+                &settings,
+                Utc::now(),
+            )
+        };
+
+        cycle().await;
+        assert!(status_map.lock().await.contains_key("test_user:echo"));
+
+        std::fs::remove_file(&manifest).unwrap();
+        registry.reload().unwrap();
+        cycle().await;
+        assert!(status_map.lock().await.is_empty());
+        assert!(connector_handles.lock().await.is_empty());
+    }
+
+    /// A scheduler stopped on a permanent error stays stopped while the stored
+    /// credentials are unchanged, and restarts once they are replaced.
+    #[tokio::test]
+    async fn test_discovery_restarts_stopped_scheduler_only_on_new_credentials() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let encryption_key = BASE64.encode([0u8; 32]);
+
+        let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        store.store("test_user", "github", &credentials).unwrap();
+        let store = Arc::new(store);
+
+        let status_map: Arc<
+            tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>,
+        > = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let connector_handles: Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>> =
+            Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+
+        let stopped_status = Arc::new(tokio::sync::Mutex::new(ConnectorStatus {
+            last_error: Some("permanent error: missing scope".to_string()),
+            error_count: 1,
+            stopped: true,
+            stopped_credentials: Some(credentials_fingerprint(&credentials)),
+            ..Default::default()
+        }));
+        status_map
+            .lock()
+            .await
+            .insert("test_user:github".to_string(), Arc::clone(&stopped_status));
+
+        // Same credentials: left alone
+        run_discovery_cycle(
+            &store,
+            &ConnectorRegistry::default(),
+            &status_map,
+            &connector_handles,
+            &test_settings(),
+            Utc::now(),
+        )
+        .await;
+        {
+            let map = status_map.lock().await;
+            assert!(Arc::ptr_eq(
+                map.get("test_user:github").unwrap(),
+                &stopped_status
+            ));
+            assert!(connector_handles.lock().await.is_empty());
+        }
+
+        // User reconnects with a new token: restarted
+        store
+            .store(
+                "test_user",
+                "github",
+                &Credentials {
+                    access_token: "new_token".to_string(),
+                    ..credentials
+                },
+            )
+            .unwrap();
+        run_discovery_cycle(
+            &store,
+            &ConnectorRegistry::default(),
+            &status_map,
+            &connector_handles,
+            &test_settings(),
+            Utc::now(),
+        )
+        .await;
+
+        let map = status_map.lock().await;
+        let new_status = map.get("test_user:github").unwrap();
+        assert!(!Arc::ptr_eq(new_status, &stopped_status));
+        assert!(!new_status.lock().await.stopped);
+        assert!(connector_handles
+            .lock()
+            .await
+            .contains_key("test_user:github"));
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_and_caps() {
+        let secs: Vec<i64> = (0..10).map(|n| restart_backoff(n).num_seconds()).collect();
+        assert_eq!(
+            secs,
+            vec![0, 60, 120, 240, 480, 960, 1920, 3600, 3600, 3600]
+        );
+        assert_eq!(restart_backoff(u32::MAX).num_seconds(), 3600);
+    }
+
+    /// A scheduler that keeps failing after restarts is left alone until its
+    /// backoff expires, and the restart count carries over.
+    #[tokio::test]
+    async fn test_discovery_backs_off_repeated_restarts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let encryption_key = BASE64.encode([0u8; 32]);
+
+        let store = CredentialStore::new(db_path.to_str().unwrap(), &encryption_key).unwrap();
+        let credentials = Credentials {
+            access_token: "test_token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        store.store("test_user", "github", &credentials).unwrap();
+        let store = Arc::new(store);
+
+        let status_map: Arc<
+            tokio::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<ConnectorStatus>>>>,
+        > = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+        let connector_handles: Arc<tokio::sync::Mutex<HashMap<String, JoinHandle<()>>>> =
+            Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+
+        // Already restarted twice without a successful poll
+        let errored_status = Arc::new(tokio::sync::Mutex::new(ConnectorStatus {
+            last_error: Some("auth error: token expired or invalid".to_string()),
+            error_count: 3,
+            restart_attempts: 2,
+            ..Default::default()
+        }));
+        status_map
+            .lock()
+            .await
+            .insert("test_user:github".to_string(), Arc::clone(&errored_status));
+
+        let t0 = Utc::now();
+        let registry = ConnectorRegistry::default();
+        let settings = test_settings();
+        let cycle = |now| {
+            run_discovery_cycle(
+                &store,
+                &registry,
+                &status_map,
+                &connector_handles,
+                &settings,
+                now,
+            )
+        };
+
+        // First sighting schedules the retry 2 minutes out
+        cycle(t0).await;
+        assert_eq!(
+            errored_status.lock().await.next_retry_at,
+            Some(t0 + chrono::Duration::seconds(120))
+        );
+
+        // Still backing off
+        cycle(t0 + chrono::Duration::seconds(60)).await;
+        {
+            let map = status_map.lock().await;
+            assert!(Arc::ptr_eq(
+                map.get("test_user:github").unwrap(),
+                &errored_status
+            ));
+        }
+        assert!(connector_handles.lock().await.is_empty());
+
+        // Backoff expired: restarted, with the attempt count carried over
+        cycle(t0 + chrono::Duration::seconds(120)).await;
+        let map = status_map.lock().await;
+        let new_status = map.get("test_user:github").unwrap();
+        assert!(!Arc::ptr_eq(new_status, &errored_status));
+        let new_status = new_status.lock().await;
+        assert_eq!(new_status.restart_attempts, 3);
+        assert!(new_status.next_retry_at.is_none());
+        assert!(connector_handles
+            .lock()
+            .await
+            .contains_key("test_user:github"));
+    }
 }
@@ -0,0 +1,385 @@
+//! Prometheus metrics for runner and publisher health.
+//!
+//! [`ConnectorMetrics`] is shared by the connector schedulers and the generic
+//! and named runners. Each source records into a [`SourceMetrics`] handle
+//! labelled by runner, source ID and connector name (never by entity), so
+//! the number of series stays bounded by the number of configured sources.
+//! [`ConnectorMetrics::render`] produces the text exposition format served
+//! at `GET /metrics`.
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Content type of [`ConnectorMetrics::render`] output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// External program a runner invokes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// `pip install` of a missing Singer tap
+    Pip,
+    /// A Singer tap (`--discover` or a sync run)
+    Tap,
+    /// A Bento pipeline
+    Bento,
+}
+
+impl Tool {
+    fn label(self) -> &'static str {
+        match self {
+            Tool::Pip => "pip",
+            Tool::Tap => "tap",
+            Tool::Bento => "bento",
+        }
+    }
+}
+
+const TOOLS: [Tool; 3] = [Tool::Pip, Tool::Tap, Tool::Bento];
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct SourceLabels {
+    runner: &'static str,
+    source_id: String,
+    connector: String,
+}
+
+/// Responses from the Flux API by status code (`"error"` when no response).
+#[derive(Default)]
+struct FluxRequests(Mutex<BTreeMap<String, u64>>);
+
+impl FluxRequests {
+    fn record(&self, status: Option<u16>) {
+        let label = status.map_or_else(|| "error".to_string(), |s| s.to_string());
+        *self.0.lock().unwrap().entry(label).or_default() += 1;
+    }
+}
+
+/// Metrics of one source. Cheap to update from hot paths (atomics only,
+/// except for the shared Flux request counts).
+pub struct SourceMetrics {
+    last_run_ms: AtomicU64,
+    last_run_duration_ms: AtomicU64,
+    runs: AtomicU64,
+    run_failures: AtomicU64,
+    events_emitted: AtomicU64,
+    publish_failures: AtomicU64,
+    subprocess_restarts: AtomicU64,
+    invocation_failures: [AtomicU64; TOOLS.len()],
+    flux_requests: Arc<FluxRequests>,
+}
+
+impl SourceMetrics {
+    fn new(flux_requests: Arc<FluxRequests>) -> Self {
+        Self {
+            last_run_ms: AtomicU64::new(0),
+            last_run_duration_ms: AtomicU64::new(0),
+            runs: AtomicU64::new(0),
+            run_failures: AtomicU64::new(0),
+            events_emitted: AtomicU64::new(0),
+            publish_failures: AtomicU64::new(0),
+            subprocess_restarts: AtomicU64::new(0),
+            invocation_failures: Default::default(),
+            flux_requests,
+        }
+    }
+
+    /// Handle that belongs to no registry, for runners built without one.
+    pub fn detached() -> Arc<Self> {
+        Arc::new(Self::new(Arc::default()))
+    }
+
+    /// A run that started at `started` finished after `duration`.
+    pub fn record_run(&self, started: DateTime<Utc>, duration: Duration, succeeded: bool) {
+        self.last_run_ms
+            .store(started.timestamp_millis().max(0) as u64, Ordering::Relaxed);
+        self.last_run_duration_ms
+            .store(duration.as_millis() as u64, Ordering::Relaxed);
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.run_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// `count` events were accepted by Flux.
+    pub fn record_events(&self, count: u64) {
+        self.events_emitted.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Publishing to Flux failed (unreachable, or the request was rejected).
+    pub fn record_publish_failure(&self) {
+        self.publish_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A long-running subprocess exited and is being started again.
+    pub fn record_restart(&self) {
+        self.subprocess_restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `tool` could not be started or exited unsuccessfully.
+    pub fn record_invocation_failure(&self, tool: Tool) {
+        let index = TOOLS
+            .iter()
+            .position(|&t| t == tool)
+            .expect("tool is listed");
+        self.invocation_failures[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An HTTP request to the Flux API got `status`, or no response (`None`).
+    pub fn record_flux_response(&self, status: Option<u16>) {
+        self.flux_requests.record(status);
+    }
+}
+
+/// Registry of every source's metrics plus the global Flux request counts.
+#[derive(Default)]
+pub struct ConnectorMetrics {
+    sources: Mutex<BTreeMap<SourceLabels, Arc<SourceMetrics>>>,
+    flux_requests: Arc<FluxRequests>,
+}
+
+impl ConnectorMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metrics of the source `source_id` run by `runner` (e.g. `"generic"`)
+    /// with connector `connector`, created on first use.
+    pub fn source(
+        &self,
+        runner: &'static str,
+        source_id: &str,
+        connector: &str,
+    ) -> Arc<SourceMetrics> {
+        let labels = SourceLabels {
+            runner,
+            source_id: source_id.to_string(),
+            connector: connector.to_string(),
+        };
+        let mut sources = self.sources.lock().unwrap();
+        let metrics = sources
+            .entry(labels)
+            .or_insert_with(|| Arc::new(SourceMetrics::new(Arc::clone(&self.flux_requests))));
+        Arc::clone(metrics)
+    }
+
+    /// Drops the series of a removed source.
+    pub fn remove_source(&self, runner: &'static str, source_id: &str) {
+        self.sources
+            .lock()
+            .unwrap()
+            .retain(|labels, _| !(labels.runner == runner && labels.source_id == source_id));
+    }
+
+    /// Events Flux accepted from the source `source_id` run by `runner`
+    /// since it was started, or `None` if it has no metrics.
+    pub fn events_emitted(&self, runner: &str, source_id: &str) -> Option<u64> {
+        self.sources
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(labels, _)| labels.runner == runner && labels.source_id == source_id)
+            .map(|(_, metrics)| metrics.events_emitted.load(Ordering::Relaxed))
+    }
+
+    /// All metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let sources: Vec<(SourceLabels, Arc<SourceMetrics>)> = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(labels, metrics)| (labels.clone(), Arc::clone(metrics)))
+            .collect();
+        let mut out = String::new();
+
+        let load = |atomic: &AtomicU64| atomic.load(Ordering::Relaxed);
+        let mut per_source =
+            |name: &str, kind: &str, help: &str, value: &dyn Fn(&SourceMetrics) -> f64| {
+                header(&mut out, name, kind, help);
+                for (labels, metrics) in &sources {
+                    let _ = writeln!(
+                        out,
+                        "{}{{{}}} {}",
+                        name,
+                        source_labels(labels),
+                        value(metrics)
+                    );
+                }
+            };
+        per_source(
+            "flux_connector_last_run_timestamp_seconds",
+            "gauge",
+            "Start time of the source's last completed run (Unix seconds)",
+            &|m| load(&m.last_run_ms) as f64 / 1000.0,
+        );
+        per_source(
+            "flux_connector_last_run_duration_seconds",
+            "gauge",
+            "Duration of the source's last completed run",
+            &|m| load(&m.last_run_duration_ms) as f64 / 1000.0,
+        );
+        per_source(
+            "flux_connector_runs_total",
+            "counter",
+            "Completed runs (polls, tap runs, Bento process lifetimes)",
+            &|m| load(&m.runs) as f64,
+        );
+        per_source(
+            "flux_connector_run_failures_total",
+            "counter",
+            "Runs that ended in an error",
+            &|m| load(&m.run_failures) as f64,
+        );
+        per_source(
+            "flux_connector_events_emitted_total",
+            "counter",
+            "Events accepted by the Flux API",
+            &|m| load(&m.events_emitted) as f64,
+        );
+        per_source(
+            "flux_connector_publish_failures_total",
+            "counter",
+            "Failed attempts to publish to the Flux API",
+            &|m| load(&m.publish_failures) as f64,
+        );
+        per_source(
+            "flux_connector_subprocess_restarts_total",
+            "counter",
+            "Times a long-running subprocess exited and was restarted",
+            &|m| load(&m.subprocess_restarts) as f64,
+        );
+
+        let name = "flux_connector_invocation_failures_total";
+        header(
+            &mut out,
+            name,
+            "counter",
+            "External program (pip, tap, bento) failed to start or exited unsuccessfully",
+        );
+        for (labels, metrics) in &sources {
+            for (tool, count) in TOOLS.iter().zip(&metrics.invocation_failures) {
+                let count = load(count);
+                if count > 0 {
+                    let _ = writeln!(
+                        out,
+                        "{}{{{},tool=\"{}\"}} {}",
+                        name,
+                        source_labels(labels),
+                        tool.label(),
+                        count
+                    );
+                }
+            }
+        }
+
+        let name = "flux_connector_flux_api_requests_total";
+        header(
+            &mut out,
+            name,
+            "counter",
+            "HTTP requests to the Flux API by response status (\"error\" if none)",
+        );
+        for (status, count) in self.flux_requests.0.lock().unwrap().iter() {
+            let _ = writeln!(out, "{}{{status=\"{}\"}} {}", name, status, count);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn source_labels(labels: &SourceLabels) -> String {
+    format!(
+        "runner=\"{}\",source_id=\"{}\",connector=\"{}\"",
+        labels.runner,
+        escape_label(&labels.source_id),
+        escape_label(&labels.connector)
+    )
+}
+
+/// Escapes a label value per the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_source_and_request_series() {
+        let metrics = ConnectorMetrics::new();
+        let source = metrics.source("named", "src-1", "tap-github");
+        let started = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        source.record_run(started, Duration::from_millis(2500), true);
+        source.record_events(3);
+        source.record_flux_response(Some(200));
+        source.record_flux_response(None);
+        source.record_invocation_failure(Tool::Pip);
+
+        let text = metrics.render();
+        let labels = r#"runner="named",source_id="src-1",connector="tap-github""#;
+        for line in [
+            format!(
+                "flux_connector_last_run_timestamp_seconds{{{}}} 1700000000",
+                labels
+            ),
+            format!("flux_connector_last_run_duration_seconds{{{}}} 2.5", labels),
+            format!("flux_connector_runs_total{{{}}} 1", labels),
+            format!("flux_connector_run_failures_total{{{}}} 0", labels),
+            format!("flux_connector_events_emitted_total{{{}}} 3", labels),
+            format!(
+                "flux_connector_invocation_failures_total{{{},tool=\"pip\"}} 1",
+                labels
+            ),
+            "flux_connector_flux_api_requests_total{status=\"200\"} 1".to_string(),
+            "flux_connector_flux_api_requests_total{status=\"error\"} 1".to_string(),
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {:?} in\n{}",
+                line,
+                text
+            );
+        }
+        assert!(
+            !text.contains("tool=\"bento\""),
+            "zero tool counts are omitted"
+        );
+        assert!(text.contains("# TYPE flux_connector_runs_total counter"));
+    }
+
+    #[test]
+    fn test_sources_share_request_counts_and_can_be_removed() {
+        let metrics = ConnectorMetrics::new();
+        metrics
+            .source("generic", "a", "native")
+            .record_flux_response(Some(202));
+        metrics
+            .source("generic", "b", "native")
+            .record_flux_response(Some(202));
+        assert!(metrics
+            .render()
+            .contains("flux_connector_flux_api_requests_total{status=\"202\"} 2"));
+
+        metrics.remove_source("generic", "a");
+        let text = metrics.render();
+        assert!(!text.contains("source_id=\"a\""));
+        assert!(text.contains("source_id=\"b\""));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
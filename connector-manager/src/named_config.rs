@@ -8,8 +8,11 @@
 //! string. It is written to a temp file at runtime with 0600 permissions
 //! and removed after the tap exits.
 
+use crate::runners::retry::RetryPolicy;
+use crate::transform::TransformRule;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use flux::migrations::{self, Migration};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
@@ -34,66 +37,118 @@ pub struct NamedSourceConfig {
     pub created_at: DateTime<Utc>,
     /// Optional Flux namespace token for auth-enabled Flux instances.
     pub flux_namespace_token: Option<String>,
+    /// Convert RECORD values to the types declared by the stream's SCHEMA.
+    pub coerce_types: bool,
+    /// Records one run may publish; the rest of the run is dropped.
+    pub max_events_per_run: u64,
+    /// Rules applied to each record before it is published.
+    #[serde(default)]
+    pub transforms: Vec<TransformRule>,
+    /// Namespace that created the source; only it (and admins) may see or
+    /// change it.
+    #[serde(default)]
+    pub owner_namespace: String,
+    /// Backoff and circuit breaker applied when tap runs fail.
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// Paused sources are kept but not run.
+    #[serde(default)]
+    pub paused: bool,
+    /// Run each selected stream in its own tap process, at most this many at
+    /// once; 0 runs all streams in one process.
+    #[serde(default)]
+    pub parallel_streams: u32,
 }
 
+/// Schema history of the named config store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS named_sources (
+            id                  TEXT PRIMARY KEY,
+            tap_name            TEXT NOT NULL,
+            namespace           TEXT NOT NULL,
+            entity_key_field    TEXT NOT NULL,
+            config_json         TEXT NOT NULL,
+            poll_interval_secs  INTEGER NOT NULL,
+            created_at          TEXT NOT NULL
+        );",
+    ),
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "flux_namespace_token",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "coerce_types",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "max_events_per_run",
+        definition: "INTEGER NOT NULL DEFAULT 50000",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "transforms_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "owner_namespace",
+        definition: "TEXT NOT NULL DEFAULT ''",
+    },
+    // Sources created before owners were recorded belong to the namespace
+    // they publish under
+    Migration::Sql(
+        "UPDATE named_sources SET owner_namespace = namespace WHERE owner_namespace = '';",
+    ),
+    // NULL = the default policy
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "retry_policy_json",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "paused",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+    },
+    Migration::AddColumn {
+        table: "named_sources",
+        column: "parallel_streams",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+    },
+];
+
 /// Persists named source configs in SQLite.
 pub struct NamedConfigStore {
     conn: Mutex<Connection>,
 }
 
 impl NamedConfigStore {
-    /// Opens (or creates) the SQLite database and ensures the table exists.
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
     pub fn new(db_path: &str) -> Result<Self> {
-        let conn = Connection::open(db_path)
+        let mut conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open named config DB at {}", db_path))?;
-        let store = Self {
+        migrations::migrate(&mut conn, "named_sources", MIGRATIONS)
+            .context("Failed to migrate named config DB")?;
+        Ok(Self {
             conn: Mutex::new(conn),
-        };
-        store.create_table()?;
-        store.migrate()?;
-        Ok(store)
-    }
-
-    /// Creates the `named_sources` table if it does not already exist.
-    pub fn create_table(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS named_sources (
-                id                  TEXT PRIMARY KEY,
-                tap_name            TEXT NOT NULL,
-                namespace           TEXT NOT NULL,
-                entity_key_field    TEXT NOT NULL,
-                config_json         TEXT NOT NULL,
-                poll_interval_secs  INTEGER NOT NULL,
-                created_at          TEXT NOT NULL,
-                flux_namespace_token TEXT
-            );",
-        )
-        .context("Failed to create named_sources table")?;
-        Ok(())
-    }
-
-    /// Adds `flux_namespace_token` column to existing databases.
-    fn migrate(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.execute_batch(
-            "ALTER TABLE named_sources ADD COLUMN flux_namespace_token TEXT;",
-        );
-        if let Err(e) = result {
-            if !e.to_string().contains("duplicate column") {
-                return Err(e.into());
-            }
-        }
-        Ok(())
+        })
     }
 
     /// Inserts a new named source config. Fails if `id` already exists.
     pub fn insert(&self, config: &NamedSourceConfig) -> Result<()> {
+        let transforms_json =
+            serde_json::to_string(&config.transforms).context("Failed to serialize transforms")?;
+        let retry_policy_json = serde_json::to_string(&config.retry_policy)
+            .context("Failed to serialize retry_policy")?;
         let conn = self.conn.lock().unwrap();
         conn.execute(
             "INSERT INTO named_sources
-                (id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                (id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token, coerce_types, max_events_per_run, transforms_json, owner_namespace, retry_policy_json, paused, parallel_streams)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             params![
                 config.id,
                 config.tap_name,
@@ -103,6 +158,13 @@ impl NamedConfigStore {
                 config.poll_interval_secs as i64,
                 config.created_at.to_rfc3339(),
                 config.flux_namespace_token,
+                config.coerce_types,
+                config.max_events_per_run as i64,
+                transforms_json,
+                config.owner_namespace,
+                retry_policy_json,
+                config.paused,
+                config.parallel_streams,
             ],
         )
         .context("Failed to insert named source config")?;
@@ -113,7 +175,7 @@ impl NamedConfigStore {
     pub fn get(&self, id: &str) -> Result<Option<NamedSourceConfig>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token
+            "SELECT id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token, coerce_types, max_events_per_run, transforms_json, owner_namespace, retry_policy_json, paused, parallel_streams
              FROM named_sources WHERE id = ?1",
         )?;
         let mut rows = stmt.query(params![id])?;
@@ -128,7 +190,7 @@ impl NamedConfigStore {
     pub fn list(&self) -> Result<Vec<NamedSourceConfig>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token
+            "SELECT id, tap_name, namespace, entity_key_field, config_json, poll_interval_secs, created_at, flux_namespace_token, coerce_types, max_events_per_run, transforms_json, owner_namespace, retry_policy_json, paused, parallel_streams
              FROM named_sources ORDER BY created_at ASC",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -138,6 +200,32 @@ impl NamedConfigStore {
             .context("Failed to list named source configs")
     }
 
+    /// Replaces a source's transform rules. Returns false if not found.
+    pub fn set_transforms(&self, id: &str, rules: &[TransformRule]) -> Result<bool> {
+        let transforms_json =
+            serde_json::to_string(rules).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE named_sources SET transforms_json = ?2 WHERE id = ?1",
+                params![id, transforms_json],
+            )
+            .context("Failed to update named source transforms")?;
+        Ok(updated > 0)
+    }
+
+    /// Pauses or resumes a source. Returns false if not found.
+    pub fn set_paused(&self, id: &str, paused: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE named_sources SET paused = ?2 WHERE id = ?1",
+                params![id, paused],
+            )
+            .context("Failed to update named source paused flag")?;
+        Ok(updated > 0)
+    }
+
     /// Deletes a source by ID. No-op if the ID does not exist.
     pub fn delete(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -156,7 +244,19 @@ fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<NamedSourceConfig>
     let poll_interval_secs: i64 = row.get(5)?;
     let created_at_str: String = row.get(6)?;
     let flux_namespace_token: Option<String> = row.get(7)?;
+    let coerce_types: bool = row.get(8)?;
+    let max_events_per_run: i64 = row.get(9)?;
+    let transforms_json: String = row.get(10)?;
+    let owner_namespace: String = row.get(11)?;
+    let retry_policy_json: Option<String> = row.get(12)?;
+    let paused: bool = row.get(13)?;
+    let parallel_streams: u32 = row.get(14)?;
     let created_at: DateTime<Utc> = created_at_str.parse().expect("Failed to parse created_at");
+    let transforms =
+        serde_json::from_str(&transforms_json).expect("Failed to deserialize transforms");
+    let retry_policy = retry_policy_json
+        .map(|json| serde_json::from_str(&json).expect("Failed to deserialize retry_policy"))
+        .unwrap_or_default();
     Ok(NamedSourceConfig {
         id,
         tap_name,
@@ -166,6 +266,13 @@ fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<NamedSourceConfig>
         poll_interval_secs: poll_interval_secs as u64,
         created_at,
         flux_namespace_token,
+        coerce_types,
+        max_events_per_run: max_events_per_run as u64,
+        transforms,
+        owner_namespace,
+        retry_policy,
+        paused,
+        parallel_streams,
     })
 }
 
@@ -192,13 +299,21 @@ mod tests {
             poll_interval_secs: 3600,
             created_at: Utc::now(),
             flux_namespace_token: None,
+            coerce_types: false,
+            max_events_per_run: 50_000,
+            transforms: Vec::new(),
+            owner_namespace: "personal".to_string(),
+            retry_policy: RetryPolicy::default(),
+            paused: false,
+            parallel_streams: 0,
         }
     }
 
     #[test]
     fn test_insert_and_get() {
         let store = in_memory_store();
-        let config = sample_config("src-001");
+        let mut config = sample_config("src-001");
+        config.coerce_types = true;
         store.insert(&config).unwrap();
 
         let result = store.get("src-001").unwrap();
@@ -210,6 +325,7 @@ mod tests {
         assert_eq!(fetched.entity_key_field, "id");
         assert_eq!(fetched.poll_interval_secs, 3600);
         assert_eq!(fetched.config_json, r#"{"access_token": "ghp_test"}"#);
+        assert!(fetched.coerce_types);
     }
 
     #[test]
@@ -225,6 +341,19 @@ mod tests {
         assert!(ids.contains(&"id-2"));
     }
 
+    #[test]
+    fn test_set_paused() {
+        let store = in_memory_store();
+        store.insert(&sample_config("src-001")).unwrap();
+        assert!(!store.get("src-001").unwrap().unwrap().paused);
+
+        assert!(store.set_paused("src-001", true).unwrap());
+        assert!(store.get("src-001").unwrap().unwrap().paused);
+        assert!(store.set_paused("src-001", false).unwrap());
+        assert!(!store.list().unwrap()[0].paused);
+        assert!(!store.set_paused("ghost", true).unwrap());
+    }
+
     #[test]
     fn test_delete_config() {
         let store = in_memory_store();
@@ -247,4 +376,43 @@ mod tests {
         let store = in_memory_store();
         store.delete("ghost").unwrap();
     }
+
+    #[test]
+    fn test_migrates_unversioned_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("named.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Table as created before flux_namespace_token and versioning
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE named_sources (
+                id TEXT PRIMARY KEY, tap_name TEXT NOT NULL, namespace TEXT NOT NULL,
+                entity_key_field TEXT NOT NULL, config_json TEXT NOT NULL,
+                poll_interval_secs INTEGER NOT NULL, created_at TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO named_sources VALUES ('old', 'tap-github', 'personal', 'id', '{}', 60, ?1)",
+            params![Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = NamedConfigStore::new(db_path).expect("migrate failed");
+        let old = store.get("old").unwrap().unwrap();
+        assert_eq!(old.tap_name, "tap-github");
+        assert_eq!(old.flux_namespace_token, None);
+        assert!(!old.coerce_types);
+        assert_eq!(old.max_events_per_run, 50_000);
+        assert!(old.transforms.is_empty());
+        assert_eq!(old.owner_namespace, "personal");
+        assert!(!old.paused);
+        assert_eq!(old.parallel_streams, 0);
+        assert_eq!(
+            migrations::schema_version(&store.conn.lock().unwrap(), "named_sources").unwrap(),
+            MIGRATIONS.len() as u32
+        );
+    }
 }
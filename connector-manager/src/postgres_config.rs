@@ -0,0 +1,318 @@
+//! PostgreSQL change-polling connector config storage.
+//!
+//! Stores Postgres table sources in SQLite. Each source defines a connection
+//! string, the table to mirror, its key column, and the `updated_at` column
+//! used for incremental polling. The high-water mark (the last `updated_at`
+//! value published) is kept in the same row so restarts resume where the
+//! previous run stopped.
+//!
+//! # Credential storage
+//! Passwords are NOT stored in this table. They are stored in the
+//! CredentialStore under `user_id="postgres"`, `connector_name=<source-id>`,
+//! the same way generic source tokens are.
+
+use crate::transform::TransformRule;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flux::migrations::{self, Migration};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Config for a single Postgres table source.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostgresSourceConfig {
+    /// Unique source ID (UUIDv4).
+    pub id: String,
+    /// Human-readable label shown in the UI.
+    pub name: String,
+    /// libpq-style connection string without the password, e.g.
+    /// `"host=db user=flux dbname=app"` or `"postgresql://flux@db/app"`.
+    pub connection_string: String,
+    /// Table to mirror, optionally schema-qualified (`"public.orders"`).
+    pub table: String,
+    /// Column whose value becomes the entity key.
+    pub key_column: String,
+    /// Timestamp (or integer) column that increases whenever a row changes.
+    pub updated_at_column: String,
+    /// Flux namespace to publish entities under.
+    pub namespace: String,
+    /// Maximum rows fetched per query.
+    pub batch_size: u32,
+    /// How often to poll for changed rows (seconds).
+    pub poll_interval_secs: u64,
+    /// When this source was created.
+    pub created_at: DateTime<Utc>,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+    /// Rules applied to each row before it is published.
+    #[serde(default)]
+    pub transforms: Vec<TransformRule>,
+}
+
+/// Schema history of the postgres config store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS postgres_sources (
+            id                   TEXT PRIMARY KEY,
+            name                 TEXT NOT NULL,
+            connection_string    TEXT NOT NULL,
+            table_name           TEXT NOT NULL,
+            key_column           TEXT NOT NULL,
+            updated_at_column    TEXT NOT NULL,
+            namespace            TEXT NOT NULL,
+            batch_size           INTEGER NOT NULL,
+            poll_interval_secs   INTEGER NOT NULL,
+            created_at           TEXT NOT NULL,
+            flux_namespace_token TEXT,
+            watermark_json       TEXT
+        );",
+    ),
+    Migration::AddColumn {
+        table: "postgres_sources",
+        column: "transforms_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+];
+
+/// Persists Postgres source configs and high-water marks in SQLite.
+pub struct PostgresConfigStore {
+    conn: Mutex<Connection>,
+}
+
+impl PostgresConfigStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open postgres config DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "postgres_sources", MIGRATIONS)
+            .context("Failed to migrate postgres config DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Inserts a new Postgres source config. Fails if `id` already exists.
+    pub fn insert(&self, config: &PostgresSourceConfig) -> Result<()> {
+        let transforms_json =
+            serde_json::to_string(&config.transforms).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO postgres_sources
+                (id, name, connection_string, table_name, key_column, updated_at_column, namespace, batch_size, poll_interval_secs, created_at, flux_namespace_token, transforms_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                config.id,
+                config.name,
+                config.connection_string,
+                config.table,
+                config.key_column,
+                config.updated_at_column,
+                config.namespace,
+                config.batch_size,
+                config.poll_interval_secs as i64,
+                config.created_at.to_rfc3339(),
+                config.flux_namespace_token,
+                transforms_json,
+            ],
+        )
+        .context("Failed to insert postgres source config")?;
+        Ok(())
+    }
+
+    /// Returns a single source by ID, or `None` if not found.
+    pub fn get(&self, id: &str) -> Result<Option<PostgresSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, connection_string, table_name, key_column, updated_at_column, namespace, batch_size, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM postgres_sources WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(row_to_config(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns all source configs ordered by creation time.
+    pub fn list(&self) -> Result<Vec<PostgresSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, connection_string, table_name, key_column, updated_at_column, namespace, batch_size, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM postgres_sources ORDER BY created_at ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(row_to_config(row).expect("row_to_config failed"))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to list postgres source configs")
+    }
+
+    /// Replaces a source's transform rules. Returns false if not found.
+    pub fn set_transforms(&self, id: &str, rules: &[TransformRule]) -> Result<bool> {
+        let transforms_json =
+            serde_json::to_string(rules).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE postgres_sources SET transforms_json = ?2 WHERE id = ?1",
+                params![id, transforms_json],
+            )
+            .context("Failed to update postgres source transforms")?;
+        Ok(updated > 0)
+    }
+
+    /// Deletes a source (and its high-water mark). No-op if the ID does not exist.
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM postgres_sources WHERE id = ?1", params![id])
+            .context("Failed to delete postgres source config")?;
+        Ok(())
+    }
+
+    /// Returns the last published `updated_at` value, or `None` before the
+    /// first poll.
+    pub fn watermark(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn.lock().unwrap();
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT watermark_json FROM postgres_sources WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })
+            .context("Failed to read postgres watermark")?;
+        json.map(|j| serde_json::from_str(&j).context("Invalid postgres watermark"))
+            .transpose()
+    }
+
+    /// Records the last published `updated_at` value.
+    pub fn set_watermark(&self, id: &str, watermark: &serde_json::Value) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE postgres_sources SET watermark_json = ?2 WHERE id = ?1",
+            params![id, watermark.to_string()],
+        )
+        .context("Failed to save postgres watermark")?;
+        Ok(())
+    }
+}
+
+fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<PostgresSourceConfig> {
+    let id: String = row.get(0)?;
+    let name: String = row.get(1)?;
+    let connection_string: String = row.get(2)?;
+    let table: String = row.get(3)?;
+    let key_column: String = row.get(4)?;
+    let updated_at_column: String = row.get(5)?;
+    let namespace: String = row.get(6)?;
+    let batch_size: u32 = row.get(7)?;
+    let poll_interval_secs: i64 = row.get(8)?;
+    let created_at_str: String = row.get(9)?;
+    let flux_namespace_token: Option<String> = row.get(10)?;
+    let transforms_json: String = row.get(11)?;
+
+    let created_at: DateTime<Utc> = created_at_str.parse().expect("Failed to parse created_at");
+    let transforms =
+        serde_json::from_str(&transforms_json).expect("Failed to deserialize transforms");
+
+    Ok(PostgresSourceConfig {
+        id,
+        name,
+        connection_string,
+        table,
+        key_column,
+        updated_at_column,
+        namespace,
+        batch_size,
+        poll_interval_secs: poll_interval_secs as u64,
+        created_at,
+        flux_namespace_token,
+        transforms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory_store() -> PostgresConfigStore {
+        PostgresConfigStore::new(":memory:").expect("in-memory store failed")
+    }
+
+    fn sample_config(id: &str) -> PostgresSourceConfig {
+        PostgresSourceConfig {
+            id: id.to_string(),
+            name: "Orders".to_string(),
+            connection_string: "host=db user=flux dbname=shop".to_string(),
+            table: "public.orders".to_string(),
+            key_column: "id".to_string(),
+            updated_at_column: "updated_at".to_string(),
+            namespace: "shop".to_string(),
+            batch_size: 500,
+            poll_interval_secs: 30,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let store = in_memory_store();
+        store.insert(&sample_config("pg-1")).expect("insert failed");
+
+        let fetched = store.get("pg-1").unwrap().expect("config should exist");
+        assert_eq!(fetched.connection_string, "host=db user=flux dbname=shop");
+        assert_eq!(fetched.table, "public.orders");
+        assert_eq!(fetched.key_column, "id");
+        assert_eq!(fetched.updated_at_column, "updated_at");
+        assert_eq!(fetched.namespace, "shop");
+        assert_eq!(fetched.batch_size, 500);
+        assert_eq!(fetched.poll_interval_secs, 30);
+        assert!(store.get("ghost").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let store = in_memory_store();
+        store.insert(&sample_config("id-1")).unwrap();
+        store.insert(&sample_config("id-2")).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        store.delete("id-1").unwrap();
+        let ids: Vec<String> = store.list().unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["id-2"]);
+        store.delete("ghost").unwrap();
+    }
+
+    #[test]
+    fn test_watermark_persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("postgres.db");
+        let db_path = db_path.to_str().unwrap();
+
+        let store = PostgresConfigStore::new(db_path).unwrap();
+        store.insert(&sample_config("pg-1")).unwrap();
+        assert_eq!(store.watermark("pg-1").unwrap(), None);
+
+        let mark = serde_json::json!("2026-03-01T12:00:00.123456Z");
+        store.set_watermark("pg-1", &mark).unwrap();
+        drop(store);
+
+        let store = PostgresConfigStore::new(db_path).unwrap();
+        assert_eq!(store.watermark("pg-1").unwrap(), Some(mark));
+        assert_eq!(store.watermark("ghost").unwrap(), None);
+
+        // Integer cursors round-trip as numbers
+        store.set_watermark("pg-1", &serde_json::json!(42)).unwrap();
+        assert_eq!(
+            store.watermark("pg-1").unwrap(),
+            Some(serde_json::json!(42))
+        );
+    }
+}
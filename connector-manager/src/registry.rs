@@ -1,20 +1,237 @@
 //! Connector registry - Manages available connectors.
 //!
-//! Phase 1: Hardcoded mock connectors for testing.
-//! Phase 2+: Dynamic connector loading (plugins, WASM).
+//! Builtin connectors are compiled in, and crates embedding the connector
+//! manager add their own with [`ConnectorRegistry::register`]. External
+//! connectors (see [`crate::connectors::external`]) are loaded from a
+//! manifest directory at startup and on `POST /api/connectors/registry/reload`.
+//!
+//! # Embedding a custom connector
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use connector_manager::registry::ConnectorRegistry;
+//! use connector_manager::{
+//!     Connector, ConnectorError, ConnectorManager, Credentials, FluxEvent, OAuthConfig,
+//! };
+//! use flux::credentials::CredentialStore;
+//! use std::sync::Arc;
+//!
+//! struct TicketsConnector;
+//!
+//! #[async_trait]
+//! impl Connector for TicketsConnector {
+//!     fn name(&self) -> &str {
+//!         "tickets"
+//!     }
+//!
+//!     fn oauth_config(&self) -> OAuthConfig {
+//!         OAuthConfig {
+//!             auth_url: "https://tickets.example.com/oauth/authorize".to_string(),
+//!             token_url: "https://tickets.example.com/oauth/token".to_string(),
+//!             scopes: vec!["read".to_string()],
+//!         }
+//!     }
+//!
+//!     async fn fetch(&self, _credentials: &Credentials) -> Result<Vec<FluxEvent>, ConnectorError> {
+//!         Ok(Vec::new())
+//!     }
+//!
+//!     fn poll_interval(&self) -> u64 {
+//!         60
+//!     }
+//! }
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! // Builtins plus the custom connector
+//! let mut registry = ConnectorRegistry::default();
+//! registry.register(Arc::new(TicketsConnector))?;
+//!
+//! let store = Arc::new(CredentialStore::new("credentials.db", "<base64 key>")?);
+//! store.store("alice", "tickets", &Credentials {
+//!     access_token: "token".to_string(),
+//!     refresh_token: None,
+//!     expires_at: None,
+//! })?;
+//!
+//! // Polls "tickets" for alice, and picks up credentials stored later
+//! let mut manager = ConnectorManager::new(store, "http://localhost:3000".to_string())
+//!     .with_registry(Arc::new(registry));
+//! manager.start().await?;
+//! # Ok(())
+//! # }
+//! ```
 
+use crate::connectors::external::ExternalConnector;
 use crate::connectors::github::GitHubConnector;
+use crate::connectors::jira::JiraConnector;
+use crate::connectors::notion::NotionConnector;
 use crate::Connector;
-use std::sync::Arc;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+use utoipa::ToSchema;
 
-/// Returns all available connectors.
+/// Returns the builtin connectors.
 pub fn get_all_connectors() -> Vec<Arc<dyn Connector>> {
-    vec![Arc::new(GitHubConnector::new())]
+    vec![
+        Arc::new(GitHubConnector::new()),
+        Arc::new(NotionConnector::new()),
+        Arc::new(JiraConnector::new()),
+    ]
+}
+
+/// Builtin and registered connectors plus the external connectors loaded
+/// from manifests.
+pub struct ConnectorRegistry {
+    /// Builtins, then connectors added with [`register`](Self::register)
+    builtin: Vec<Arc<dyn Connector>>,
+    /// Manifest directory and the directory executables must live in
+    external_dirs: Option<(PathBuf, PathBuf)>,
+    external: RwLock<Vec<Arc<dyn Connector>>>,
+}
+
+/// Outcome of scanning the manifest directory.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "loaded": ["jira"],
+    "errors": [{"manifest": "broken.json", "error": "poll_interval must be at least 1 second"}]
+}))]
+pub struct ReloadReport {
+    /// Names of the external connectors now registered
+    pub loaded: Vec<String>,
+    /// Manifests that were skipped
+    pub errors: Vec<ManifestError>,
+}
+
+/// Why a manifest was skipped.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ManifestError {
+    /// Manifest file name
+    pub manifest: String,
+    pub error: String,
+}
+
+impl Default for ConnectorRegistry {
+    /// Builtin connectors only
+    fn default() -> Self {
+        Self {
+            builtin: get_all_connectors(),
+            external_dirs: None,
+            external: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl ConnectorRegistry {
+    /// Registry that loads `*.json` manifests from `manifest_dir`, whose
+    /// executables must be inside `exec_dir`. Nothing is loaded until
+    /// [`reload`](Self::reload).
+    pub fn with_external(manifest_dir: PathBuf, exec_dir: PathBuf) -> Self {
+        Self {
+            external_dirs: Some((manifest_dir, exec_dir)),
+            ..Self::default()
+        }
+    }
+
+    /// Adds a custom connector, scheduled like the builtins.
+    ///
+    /// Fails if a connector with the same name is already registered. Later
+    /// manifests with the name are skipped on reload.
+    pub fn register(&mut self, connector: Arc<dyn Connector>) -> Result<()> {
+        if self.get(connector.name()).is_some() {
+            anyhow::bail!("connector '{}' is already registered", connector.name());
+        }
+        info!(connector = %connector.name(), "Custom connector registered");
+        self.builtin.push(connector);
+        Ok(())
+    }
+
+    /// Whether a manifest directory is configured.
+    pub fn external_enabled(&self) -> bool {
+        self.external_dirs.is_some()
+    }
+
+    /// All registered connectors, builtin first.
+    pub fn connectors(&self) -> Vec<Arc<dyn Connector>> {
+        let external = self.external.read().unwrap();
+        self.builtin
+            .iter()
+            .chain(external.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Connector>> {
+        self.connectors().into_iter().find(|c| c.name() == name)
+    }
+
+    pub fn is_external(&self, name: &str) -> bool {
+        self.external
+            .read()
+            .unwrap()
+            .iter()
+            .any(|c| c.name() == name)
+    }
+
+    /// Rescans the manifest directory and replaces the external connectors.
+    ///
+    /// Invalid manifests, and names taken by a builtin or an earlier manifest
+    /// (in file name order), are skipped and reported. If the directory
+    /// cannot be read the current connectors are kept.
+    pub fn reload(&self) -> Result<ReloadReport> {
+        let Some((manifest_dir, exec_dir)) = &self.external_dirs else {
+            anyhow::bail!("no external connector manifest directory is configured");
+        };
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(manifest_dir)
+            .with_context(|| format!("Failed to read {}", manifest_dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut external: Vec<Arc<dyn Connector>> = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let manifest = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let loaded = ExternalConnector::from_manifest_file(&path, exec_dir).and_then(|c| {
+                let mut taken = self.builtin.iter().chain(external.iter());
+                if taken.any(|other| other.name() == c.name()) {
+                    anyhow::bail!("connector '{}' is already registered", c.name());
+                }
+                Ok(c)
+            });
+            match loaded {
+                Ok(connector) => external.push(Arc::new(connector)),
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    warn!(
+                        manifest = %manifest,
+                        error = %error,
+                        "Skipping external connector manifest"
+                    );
+                    errors.push(ManifestError { manifest, error });
+                }
+            }
+        }
+
+        let loaded: Vec<String> = external.iter().map(|c| c.name().to_string()).collect();
+        info!(connectors = ?loaded, skipped = errors.len(), "External connectors loaded");
+        *self.external.write().unwrap() = external;
+        Ok(ReloadReport { loaded, errors })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+    use std::path::Path;
 
     #[test]
     fn test_github_connector() {
@@ -30,7 +247,136 @@ mod tests {
     #[test]
     fn test_get_all_connectors() {
         let connectors = get_all_connectors();
-        assert_eq!(connectors.len(), 1);
-        assert_eq!(connectors[0].name(), "github");
+        let names: Vec<&str> = connectors.iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["github", "notion", "jira"]);
+    }
+
+    fn write_manifest(dir: &Path, file: &str, name: &str, exec: &str) {
+        let manifest = json!({
+            "name": name,
+            "exec": exec,
+            "poll_interval": 60,
+            "oauth": {
+                "auth_url": "https://example.com/authorize",
+                "token_url": "https://example.com/token",
+                "scopes": []
+            }
+        });
+        std::fs::write(dir.join(file), manifest.to_string()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reload_loads_valid_manifests() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exec = dir.path().join("echo.sh");
+        std::fs::write(&exec, include_str!("../tests/fixtures/external/echo.sh")).unwrap();
+        std::fs::set_permissions(&exec, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        write_manifest(dir.path(), "a.json", "echo", "echo.sh");
+        write_manifest(dir.path(), "b.json", "echo", "echo.sh");
+        write_manifest(dir.path(), "c.json", "github", "echo.sh");
+        write_manifest(dir.path(), "d.json", "escape", "/bin/sh");
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let registry =
+            ConnectorRegistry::with_external(dir.path().to_path_buf(), dir.path().to_path_buf());
+        assert!(registry.connectors().iter().all(|c| c.name() != "echo"));
+
+        let report = registry.reload().unwrap();
+        assert_eq!(report.loaded, vec!["echo"]);
+        let skipped: Vec<&str> = report.errors.iter().map(|e| e.manifest.as_str()).collect();
+        assert_eq!(skipped, vec!["b.json", "c.json", "d.json"]);
+        assert!(report.errors[2].error.contains("outside"));
+
+        assert!(registry.is_external("echo"));
+        assert!(!registry.is_external("github"));
+        assert_eq!(registry.get("echo").unwrap().poll_interval(), 60);
+
+        // Removing the manifests unregisters the connector
+        std::fs::remove_file(dir.path().join("a.json")).unwrap();
+        std::fs::remove_file(dir.path().join("b.json")).unwrap();
+        registry.reload().unwrap();
+        assert!(registry.get("echo").is_none());
+    }
+
+    #[test]
+    fn test_reload_without_directory() {
+        let registry = ConnectorRegistry::default();
+        assert!(!registry.external_enabled());
+        assert!(registry.reload().is_err());
+        assert_eq!(registry.connectors().len(), 3);
+    }
+
+    struct CustomConnector(&'static str);
+
+    #[async_trait::async_trait]
+    impl Connector for CustomConnector {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn oauth_config(&self) -> crate::OAuthConfig {
+            crate::OAuthConfig {
+                auth_url: "https://example.com/authorize".to_string(),
+                token_url: "https://example.com/token".to_string(),
+                scopes: Vec::new(),
+            }
+        }
+
+        async fn fetch(
+            &self,
+            _credentials: &crate::Credentials,
+        ) -> Result<Vec<crate::FluxEvent>, crate::ConnectorError> {
+            Ok(Vec::new())
+        }
+
+        fn poll_interval(&self) -> u64 {
+            60
+        }
+    }
+
+    #[test]
+    fn test_register_rejects_duplicate_names() {
+        let mut registry = ConnectorRegistry::default();
+        registry
+            .register(Arc::new(CustomConnector("tickets")))
+            .unwrap();
+        assert_eq!(registry.connectors().len(), 4);
+        assert!(!registry.is_external("tickets"));
+
+        for name in ["tickets", "github"] {
+            let err = registry
+                .register(Arc::new(CustomConnector(name)))
+                .unwrap_err();
+            assert!(err.to_string().contains("already registered"), "{}", err);
+        }
+        assert_eq!(registry.connectors().len(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_manifests_cannot_shadow_registered_connectors() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exec = dir.path().join("echo.sh");
+        std::fs::write(&exec, include_str!("../tests/fixtures/external/echo.sh")).unwrap();
+        std::fs::set_permissions(&exec, std::fs::Permissions::from_mode(0o755)).unwrap();
+        write_manifest(dir.path(), "tickets.json", "tickets", "echo.sh");
+
+        let mut registry =
+            ConnectorRegistry::with_external(dir.path().to_path_buf(), dir.path().to_path_buf());
+        registry
+            .register(Arc::new(CustomConnector("tickets")))
+            .unwrap();
+
+        let report = registry.reload().unwrap();
+        assert!(report.loaded.is_empty());
+        assert!(report.errors[0].error.contains("already registered"));
+        assert!(!registry.is_external("tickets"));
+        assert_eq!(registry.get("tickets").unwrap().poll_interval(), 60);
     }
 }
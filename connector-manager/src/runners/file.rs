@@ -0,0 +1,997 @@
+//! File-drop connector runner.
+//!
+//! Each source scans its watch directory every `poll_interval_secs` for files
+//! matching its pattern. A file is picked up once its size and modification
+//! time are unchanged between two scans (so half-written files are left
+//! alone), parsed into one Flux event per row, published through
+//! `/api/events/batch`, and then moved to `processed/` in the watch directory.
+//!
+//! Rows that fail to parse or are rejected by Flux do not hold up the rest of
+//! the file; their line numbers are reported in the source's `last_error`.
+//! If Flux cannot be reached the file stays where it is and is retried on the
+//! next scan.
+use crate::file_config::{FileConfigStore, FileFormat, FileSourceConfig};
+use crate::runners::named::value_to_string;
+use crate::runners::publish::publish_batch;
+use crate::runners::rate_limit::PublishLimiter;
+use crate::transform;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::{debug, info, warn};
+
+type StatusMap = Arc<Mutex<HashMap<String, FileStatus>>>;
+
+/// Subfolder of the watch directory that processed files are moved into.
+pub const PROCESSED_DIR: &str = "processed";
+
+/// Events per `/api/events/batch` request.
+const BATCH_SIZE: usize = 500;
+
+/// Failed rows listed individually in `last_error` before summarising.
+const MAX_REPORTED_ROWS: usize = 10;
+
+/// Runtime status for a single file-drop source.
+#[derive(Clone, Debug)]
+pub struct FileStatus {
+    pub source_id: String,
+    /// Time the most recent directory scan started.
+    pub last_scan: Option<DateTime<Utc>>,
+    /// Failures from the most recently processed file (or the scan itself).
+    pub last_error: Option<String>,
+    /// Files published and moved to `processed/`.
+    pub files_processed: u64,
+    /// Rows accepted by Flux.
+    pub rows_published: u64,
+    /// Rows that failed to parse or were rejected by Flux.
+    pub rows_failed: u64,
+}
+
+/// File-drop connector runner — ingests CSV / JSON Lines files from directories.
+pub struct FileRunner {
+    pub store: Arc<FileConfigStore>,
+    pub flux_api_url: String,
+    task_handles: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    status_map: StatusMap,
+    limiter: Arc<PublishLimiter>,
+}
+
+impl FileRunner {
+    pub fn new(store: Arc<FileConfigStore>, flux_api_url: String) -> Self {
+        Self {
+            store,
+            flux_api_url,
+            task_handles: Mutex::new(HashMap::new()),
+            status_map: Arc::new(Mutex::new(HashMap::new())),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+        }
+    }
+
+    /// Paces publishing with the shared `limiter` instead of an unlimited one.
+    pub fn with_limiter(mut self, limiter: Arc<PublishLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Starts the scan loop for the given source, replacing any running one.
+    pub async fn start_source(&self, config: &FileSourceConfig) -> Result<()> {
+        {
+            let mut map = self.status_map.lock().unwrap();
+            map.entry(config.id.clone()).or_insert_with(|| FileStatus {
+                source_id: config.id.clone(),
+                last_scan: None,
+                last_error: None,
+                files_processed: 0,
+                rows_published: 0,
+                rows_failed: 0,
+            });
+        }
+
+        let scanner = FileScanner::new(
+            config.clone(),
+            self.flux_api_url.clone(),
+            Arc::clone(&self.limiter),
+            Arc::clone(&self.status_map),
+        )?;
+        let handle = tokio::spawn(run_scan_loop(scanner));
+
+        let previous = {
+            let mut handles = self.task_handles.lock().unwrap();
+            handles.insert(config.id.clone(), handle)
+        };
+        if let Some(h) = previous {
+            h.abort();
+        }
+        info!(source_id = %config.id, directory = %config.directory, "File source started");
+        Ok(())
+    }
+
+    /// Aborts the scan loop. No-op if the source is not running.
+    pub async fn stop_source(&self, source_id: &str) -> Result<()> {
+        let handle = {
+            let mut handles = self.task_handles.lock().unwrap();
+            handles.remove(source_id)
+        };
+        if let Some(h) = handle {
+            h.abort();
+        }
+        info!(source_id = %source_id, "File source stopped");
+        Ok(())
+    }
+
+    /// IDs of file sources with a running task.
+    pub fn running_ids(&self) -> Vec<String> {
+        self.task_handles.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Returns current status for all file sources.
+    pub fn status(&self) -> Vec<FileStatus> {
+        let map = self.status_map.lock().unwrap();
+        map.values().cloned().collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// One parsed row, ready to publish.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    /// 1-based line number in the file.
+    pub line: u64,
+    /// Flux entity key (value of the entity key column).
+    pub key: String,
+    /// Row properties after column mapping.
+    pub properties: Map<String, Value>,
+}
+
+/// A row that could not be ingested.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowError {
+    /// 1-based line number in the file.
+    pub line: u64,
+    pub message: String,
+}
+
+/// Result of parsing a whole file.
+#[derive(Debug, Default)]
+pub struct ParsedFile {
+    pub rows: Vec<Row>,
+    pub errors: Vec<RowError>,
+}
+
+/// Parses a file in the source's format.
+///
+/// Returns `Err` only when the file as a whole is unusable (e.g. the CSV
+/// header has no entity key column); bad rows are returned in `errors`.
+pub fn parse_file(data: &[u8], config: &FileSourceConfig) -> Result<ParsedFile> {
+    match config.format {
+        FileFormat::Csv => parse_csv(data, config),
+        FileFormat::Jsonl => Ok(parse_jsonl(data, config)),
+    }
+}
+
+/// Parses CSV with a header row. Cell types are inferred with [`infer_value`];
+/// the entity key is always taken verbatim.
+pub fn parse_csv(data: &[u8], config: &FileSourceConfig) -> Result<ParsedFile> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(data);
+    let headers = reader
+        .headers()
+        .context("Failed to read CSV header")?
+        .clone();
+    let key_index = headers
+        .iter()
+        .position(|h| h == config.entity_key_field)
+        .with_context(|| {
+            format!(
+                "CSV header has no entity key column '{}'",
+                config.entity_key_field
+            )
+        })?;
+    let names: Vec<&str> = headers.iter().map(|h| mapped_name(config, h)).collect();
+
+    let mut parsed = ParsedFile::default();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                parsed.errors.push(RowError {
+                    line,
+                    message: csv_error_message(&e),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        let key = record.get(key_index).unwrap_or_default().trim();
+        if key.is_empty() {
+            parsed.errors.push(missing_key(config, line));
+            continue;
+        }
+
+        let properties = names
+            .iter()
+            .zip(record.iter())
+            .map(|(name, cell)| (name.to_string(), infer_value(cell)))
+            .collect();
+        parsed.rows.push(Row {
+            line,
+            key: key.to_string(),
+            properties,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Parses JSON Lines: one object per line, blank lines ignored. Values keep
+/// their JSON types.
+pub fn parse_jsonl(data: &[u8], config: &FileSourceConfig) -> ParsedFile {
+    let mut parsed = ParsedFile::default();
+    for (index, raw) in data.split(|b| *b == b'\n').enumerate() {
+        let line = index as u64 + 1;
+        let raw = raw.trim_ascii();
+        if raw.is_empty() {
+            continue;
+        }
+
+        let object = match serde_json::from_slice::<Value>(raw) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => {
+                parsed.errors.push(RowError {
+                    line,
+                    message: "line is not a JSON object".to_string(),
+                });
+                continue;
+            }
+            Err(e) => {
+                parsed.errors.push(RowError {
+                    line,
+                    message: format!("invalid JSON: {}", e),
+                });
+                continue;
+            }
+        };
+
+        let key = match object.get(&config.entity_key_field) {
+            None | Some(Value::Null) => {
+                parsed.errors.push(missing_key(config, line));
+                continue;
+            }
+            Some(value) => value_to_string(value),
+        };
+        if key.is_empty() {
+            parsed.errors.push(missing_key(config, line));
+            continue;
+        }
+
+        let properties = object
+            .into_iter()
+            .map(|(name, value)| (mapped_name(config, &name).to_string(), value))
+            .collect();
+        parsed.rows.push(Row {
+            line,
+            key,
+            properties,
+        });
+    }
+    parsed
+}
+
+/// Infers the JSON type of a CSV cell.
+///
+/// `true`/`false` (any case) become booleans and plain decimal numbers become
+/// numbers. Numbers with leading zeros (`"007"`, zip codes) stay strings.
+/// Empty cells become `null`; everything else is kept as the original string.
+pub fn infer_value(cell: &str) -> Value {
+    let trimmed = cell.trim();
+    if trimmed.is_empty() {
+        return Value::Null;
+    }
+    if trimmed.eq_ignore_ascii_case("true") {
+        return Value::Bool(true);
+    }
+    if trimmed.eq_ignore_ascii_case("false") {
+        return Value::Bool(false);
+    }
+    if looks_numeric(trimmed) {
+        if let Ok(i) = trimmed.parse::<i64>() {
+            return Value::from(i);
+        }
+        if let Some(n) = trimmed
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            return Value::Number(n);
+        }
+    }
+    Value::String(cell.to_string())
+}
+
+/// Digits with an optional sign, decimal point and exponent, and no leading
+/// zero before another digit.
+fn looks_numeric(s: &str) -> bool {
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let bytes = unsigned.as_bytes();
+    match bytes {
+        [first, ..] if !first.is_ascii_digit() => return false,
+        [b'0', second, ..] if second.is_ascii_digit() => return false,
+        [] => return false,
+        _ => {}
+    }
+    bytes
+        .iter()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+'))
+}
+
+fn mapped_name<'a>(config: &'a FileSourceConfig, column: &'a str) -> &'a str {
+    config
+        .column_mapping
+        .get(column)
+        .map(String::as_str)
+        .unwrap_or(column)
+}
+
+fn missing_key(config: &FileSourceConfig, line: u64) -> RowError {
+    RowError {
+        line,
+        message: format!("missing entity key '{}'", config.entity_key_field),
+    }
+}
+
+fn csv_error_message(e: &csv::Error) -> String {
+    match e.kind() {
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!("expected {} fields, found {}", expected_len, len),
+        _ => e.to_string(),
+    }
+}
+
+/// Shell-style file name match: `*` matches any run of characters, `?` any
+/// single character.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it is currently matching up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Summarises failed rows for `last_error`, e.g.
+/// `"orders.csv: 2 rows failed: line 3: missing entity key 'id'; line 7: ..."`.
+pub fn describe_failures(file_name: &str, errors: &[RowError]) -> String {
+    let mut errors = errors.to_vec();
+    errors.sort_by_key(|e| e.line);
+    let listed: Vec<String> = errors
+        .iter()
+        .take(MAX_REPORTED_ROWS)
+        .map(|e| format!("line {}: {}", e.line, e.message))
+        .collect();
+    let mut message = format!(
+        "{}: {} row{} failed: {}",
+        file_name,
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" },
+        listed.join("; ")
+    );
+    if errors.len() > MAX_REPORTED_ROWS {
+        message.push_str(&format!("; and {} more", errors.len() - MAX_REPORTED_ROWS));
+    }
+    message
+}
+
+// ---------------------------------------------------------------------------
+// Scanning and publishing
+// ---------------------------------------------------------------------------
+
+/// Size and modification time, used to tell when a file has stopped changing.
+type FileStamp = (u64, SystemTime);
+
+/// Outcome of processing one file.
+struct FileOutcome {
+    published: u64,
+    errors: Vec<RowError>,
+}
+
+/// Scans one source's directory and ingests finished files.
+pub struct FileScanner {
+    config: FileSourceConfig,
+    flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    http_client: reqwest::Client,
+    status_map: StatusMap,
+    /// Stamps seen on the previous scan, for files not yet processed
+    pending: HashMap<PathBuf, FileStamp>,
+    /// Files that could not be parsed at all; skipped until they change
+    rejected: HashMap<PathBuf, FileStamp>,
+}
+
+impl FileScanner {
+    fn new(
+        config: FileSourceConfig,
+        flux_api_url: String,
+        limiter: Arc<PublishLimiter>,
+        status_map: StatusMap,
+    ) -> Result<Self> {
+        let http_client = crate::http::client()?;
+        Ok(Self {
+            config,
+            flux_api_url,
+            limiter,
+            http_client,
+            status_map,
+            pending: HashMap::new(),
+            rejected: HashMap::new(),
+        })
+    }
+
+    /// Runs one scan, processing every file that has stopped changing.
+    pub async fn scan(&mut self) -> Result<()> {
+        self.update_status(|s| s.last_scan = Some(Utc::now()));
+        let result = self.scan_directory().await;
+        if let Err(ref e) = result {
+            let message = format!("{:#}", e);
+            self.update_status(|s| s.last_error = Some(message));
+        }
+        result
+    }
+
+    async fn scan_directory(&mut self) -> Result<()> {
+        let directory = PathBuf::from(&self.config.directory);
+        let mut current = HashMap::new();
+        let entries = std::fs::read_dir(&directory)
+            .with_context(|| format!("Failed to read directory {}", directory.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else { continue };
+            if name.starts_with('.') || !glob_match(&self.config.pattern, name) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            current.insert(entry.path(), (metadata.len(), metadata.modified()?));
+        }
+
+        let mut ready: Vec<PathBuf> = current
+            .iter()
+            .filter(|&(path, stamp)| {
+                self.pending.get(path) == Some(stamp) && self.rejected.get(path) != Some(stamp)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        ready.sort();
+        self.rejected.retain(|path, _| current.contains_key(path));
+        self.pending = current;
+
+        for path in ready {
+            let name = file_name(&path);
+            match self.process_file(&path).await {
+                Ok(outcome) => {
+                    info!(
+                        source_id = %self.config.id,
+                        file = %name,
+                        published = outcome.published,
+                        failed = outcome.errors.len(),
+                        "File ingested"
+                    );
+                    let last_error = (!outcome.errors.is_empty())
+                        .then(|| describe_failures(&name, &outcome.errors));
+                    self.update_status(|s| {
+                        s.files_processed += 1;
+                        s.rows_published += outcome.published;
+                        s.rows_failed += outcome.errors.len() as u64;
+                        s.last_error = last_error;
+                    });
+                    self.pending.remove(&path);
+                }
+                Err(FileError::Rejected(e)) => {
+                    warn!(source_id = %self.config.id, file = %name, error = %format!("{:#}", e), "File rejected");
+                    if let Some(stamp) = self.pending.get(&path) {
+                        self.rejected.insert(path.clone(), *stamp);
+                    }
+                    let message = format!("{}: {:#}", name, e);
+                    self.update_status(|s| s.last_error = Some(message));
+                }
+                // Flux unreachable or the move failed: leave the file for the next scan
+                Err(FileError::Retry(e)) => return Err(e.context(name)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses, publishes and moves one file.
+    async fn process_file(&self, path: &Path) -> Result<FileOutcome, FileError> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .map_err(FileError::Retry)?;
+        let parsed = parse_file(&data, &self.config).map_err(FileError::Rejected)?;
+
+        let mut errors = parsed.errors;
+        let mut published = 0;
+        for chunk in parsed.rows.chunks(BATCH_SIZE) {
+            let events: Vec<Value> = chunk.iter().map(|row| self.build_event(row)).collect();
+            let results = publish_batch(
+                &self.http_client,
+                &self.limiter,
+                &self.flux_api_url,
+                self.config.flux_namespace_token.as_deref(),
+                &events,
+            )
+            .await
+            .map_err(FileError::Retry)?;
+            for (row, result) in chunk.iter().zip(results) {
+                match result {
+                    None => published += 1,
+                    Some(message) => errors.push(RowError {
+                        line: row.line,
+                        message,
+                    }),
+                }
+            }
+        }
+
+        move_to_processed(path).map_err(FileError::Retry)?;
+        errors.sort_by_key(|e| e.line);
+        Ok(FileOutcome { published, errors })
+    }
+
+    fn build_event(&self, row: &Row) -> Value {
+        let mut event = serde_json::json!({
+            "stream": "files",
+            "source": format!("file.{}", self.config.id),
+            "timestamp": Utc::now().timestamp_millis(),
+            "key": row.key,
+            "payload": {
+                "entity_id": format!("{}/{}", self.config.namespace, row.key),
+                "properties": row.properties,
+            }
+        });
+        transform::apply_to_event(&self.config.transforms, &mut event);
+        event
+    }
+
+    fn update_status(&self, f: impl FnOnce(&mut FileStatus)) {
+        let mut map = self.status_map.lock().unwrap();
+        if let Some(s) = map.get_mut(&self.config.id) {
+            f(s);
+        }
+    }
+}
+
+/// Why a file was not processed.
+enum FileError {
+    /// The file itself is unusable; skip it until it changes.
+    Rejected(anyhow::Error),
+    /// A transient failure; try again on the next scan.
+    Retry(anyhow::Error),
+}
+
+/// Moves a file into `processed/`, prefixed with the time so repeated
+/// exports with the same name do not overwrite each other.
+fn move_to_processed(path: &Path) -> Result<PathBuf> {
+    let parent = path.parent().context("File has no parent directory")?;
+    let processed = parent.join(PROCESSED_DIR);
+    std::fs::create_dir_all(&processed)
+        .with_context(|| format!("Failed to create {}", processed.display()))?;
+    let target = processed.join(format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        file_name(path)
+    ));
+    std::fs::rename(path, &target)
+        .with_context(|| format!("Failed to move {} to {}", path.display(), target.display()))?;
+    Ok(target)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Long-running loop: scan the directory every `poll_interval_secs`.
+async fn run_scan_loop(mut scanner: FileScanner) {
+    let period = std::time::Duration::from_secs(scanner.config.poll_interval_secs.max(1));
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match scanner.scan().await {
+            Ok(()) => debug!(source_id = %scanner.config.id, "File source scanned"),
+            Err(e) => {
+                warn!(source_id = %scanner.config.id, error = %format!("{:#}", e), "File source scan failed")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVENTORY_CSV: &[u8] = include_bytes!("../../tests/fixtures/files/inventory.csv");
+    const EVENTS_JSONL: &[u8] = include_bytes!("../../tests/fixtures/files/events.jsonl");
+
+    fn make_config(format: FileFormat, key: &str, directory: &str) -> FileSourceConfig {
+        FileSourceConfig {
+            id: "file-001".to_string(),
+            name: "Drop".to_string(),
+            directory: directory.to_string(),
+            pattern: "*".to_string(),
+            format,
+            entity_key_field: key.to_string(),
+            namespace: "warehouse".to_string(),
+            column_mapping: HashMap::new(),
+            poll_interval_secs: 1,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_fixture() {
+        let mut config = make_config(FileFormat::Csv, "sku", "/tmp");
+        config
+            .column_mapping
+            .insert("Qty".to_string(), "quantity".to_string());
+
+        let parsed = parse_file(INVENTORY_CSV, &config).unwrap();
+        let keys: Vec<&str> = parsed.rows.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(keys, vec!["A-100", "A-101", "A-104"]);
+        assert_eq!(
+            parsed.rows.iter().map(|r| r.line).collect::<Vec<_>>(),
+            vec![2, 3, 6]
+        );
+
+        let widget = &parsed.rows[0].properties;
+        assert_eq!(widget["sku"], "A-100");
+        assert_eq!(widget["Name"], "Widget");
+        assert_eq!(widget["quantity"], 12);
+        assert!(widget.get("Qty").is_none());
+        assert_eq!(widget["price"], 4.5);
+        assert_eq!(widget["active"], true);
+        assert_eq!(widget["zip"], "02134");
+        assert_eq!(widget["notes"], "small, blue");
+
+        let gadget = &parsed.rows[1].properties;
+        assert_eq!(gadget["quantity"], 0);
+        assert_eq!(gadget["active"], false);
+        assert_eq!(gadget["zip"], 90210);
+        assert_eq!(gadget["notes"], Value::Null);
+
+        let sprocket = &parsed.rows[2].properties;
+        assert_eq!(sprocket["quantity"], -2);
+        assert_eq!(sprocket["price"], 1000.0);
+        assert_eq!(sprocket["active"], "yes");
+        assert_eq!(sprocket["zip"], "007");
+        assert_eq!(sprocket["notes"], "  padded  ");
+
+        assert_eq!(
+            parsed.errors,
+            vec![
+                RowError {
+                    line: 4,
+                    message: "missing entity key 'sku'".to_string()
+                },
+                RowError {
+                    line: 5,
+                    message: "expected 7 fields, found 3".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_without_key_column_is_rejected() {
+        let config = make_config(FileFormat::Csv, "id", "/tmp");
+        let err = parse_file(INVENTORY_CSV, &config).unwrap_err();
+        assert!(err.to_string().contains("no entity key column 'id'"));
+
+        // Empty file has no header at all
+        assert!(parse_file(b"", &config).is_err());
+    }
+
+    #[test]
+    fn test_parse_jsonl_fixture() {
+        let mut config = make_config(FileFormat::Jsonl, "device", "/tmp");
+        config
+            .column_mapping
+            .insert("rpm".to_string(), "speed".to_string());
+
+        let parsed = parse_file(EVENTS_JSONL, &config).unwrap();
+        let rows: Vec<(u64, &str)> = parsed
+            .rows
+            .iter()
+            .map(|r| (r.line, r.key.as_str()))
+            .collect();
+        assert_eq!(rows, vec![(1, "pump-1"), (3, "pump-2"), (7, "42")]);
+
+        let pump = &parsed.rows[0].properties;
+        assert_eq!(pump["speed"], 1200);
+        assert_eq!(pump["running"], true);
+        assert_eq!(pump["meta"]["site"], "north");
+        assert!(pump.get("rpm").is_none());
+
+        let lines: Vec<u64> = parsed.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![4, 5, 6]);
+        assert!(parsed.errors[0].message.starts_with("invalid JSON"));
+        assert_eq!(parsed.errors[1].message, "line is not a JSON object");
+        assert_eq!(parsed.errors[2].message, "missing entity key 'device'");
+    }
+
+    #[test]
+    fn test_infer_value() {
+        assert_eq!(infer_value("42"), Value::from(42));
+        assert_eq!(infer_value("-7"), Value::from(-7));
+        assert_eq!(infer_value("0"), Value::from(0));
+        assert_eq!(infer_value("0.25"), Value::from(0.25));
+        assert_eq!(infer_value("2.5e2"), Value::from(250.0));
+        assert_eq!(infer_value("TRUE"), Value::Bool(true));
+        assert_eq!(infer_value(" false "), Value::Bool(false));
+        assert_eq!(infer_value(""), Value::Null);
+        assert_eq!(infer_value("   "), Value::Null);
+        assert_eq!(infer_value("00123"), Value::from("00123"));
+        assert_eq!(infer_value("1-2-3"), Value::from("1-2-3"));
+        assert_eq!(infer_value("NaN"), Value::from("NaN"));
+        assert_eq!(infer_value("inf"), Value::from("inf"));
+        assert_eq!(infer_value("12 apples"), Value::from("12 apples"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "anything.csv"));
+        assert!(glob_match("*.csv", "orders.csv"));
+        assert!(!glob_match("*.csv", "orders.csv.tmp"));
+        assert!(glob_match("orders-????.jsonl", "orders-2026.jsonl"));
+        assert!(!glob_match("orders-????.jsonl", "orders-26.jsonl"));
+        assert!(glob_match("*-export-*.csv", "crm-export-2026-01-01.csv"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(glob_match("exact.csv", "exact.csv"));
+        assert!(!glob_match("exact.csv", "inexact.csv"));
+    }
+
+    #[test]
+    fn test_describe_failures() {
+        let errors: Vec<RowError> = (1..=12)
+            .rev()
+            .map(|line| RowError {
+                line,
+                message: "bad".to_string(),
+            })
+            .collect();
+        let message = describe_failures("orders.csv", &errors);
+        assert!(message.starts_with("orders.csv: 12 rows failed: line 1: bad; line 2: bad"));
+        assert!(message.ends_with("line 10: bad; and 2 more"));
+
+        let single = describe_failures("a.csv", &errors[..1]);
+        assert_eq!(single, "a.csv: 1 row failed: line 12: bad");
+    }
+
+    fn scanner(flux_url: String, config: FileSourceConfig) -> FileScanner {
+        let status_map: StatusMap = Arc::new(Mutex::new(HashMap::new()));
+        status_map.lock().unwrap().insert(
+            config.id.clone(),
+            FileStatus {
+                source_id: config.id.clone(),
+                last_scan: None,
+                last_error: None,
+                files_processed: 0,
+                rows_published: 0,
+                rows_failed: 0,
+            },
+        );
+        FileScanner::new(
+            config,
+            flux_url,
+            Arc::new(PublishLimiter::unlimited()),
+            status_map,
+        )
+        .unwrap()
+    }
+
+    fn scanner_status(scanner: &FileScanner) -> FileStatus {
+        scanner.status_map.lock().unwrap()["file-001"].clone()
+    }
+
+    fn processed_files(dir: &Path) -> Vec<String> {
+        match std::fs::read_dir(dir.join(PROCESSED_DIR)) {
+            Ok(entries) => entries
+                .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_publishes_stable_files_and_reports_failed_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("inventory.csv"), INVENTORY_CSV).unwrap();
+        std::fs::write(dir.path().join("ignored.txt"), "sku\nX").unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "events": [{
+                    "stream": "files",
+                    "source": "file.file-001",
+                    "key": "A-100",
+                    "payload": {"entity_id": "warehouse/A-100"}
+                }]
+            })))
+            .with_status(200)
+            .with_body(
+                r#"{"successful": 2, "failed": 1, "results": [
+                    {"eventId": "e1", "stream": "files", "error": null},
+                    {"eventId": null, "stream": "files", "error": "validation failed: too many properties"},
+                    {"eventId": "e3", "stream": "files", "error": null}
+                ]}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = make_config(FileFormat::Csv, "sku", dir.path().to_str().unwrap());
+        config.pattern = "*.csv".to_string();
+        let mut scanner = scanner(server.url(), config);
+
+        // First sighting: the file may still be being written
+        scanner.scan().await.unwrap();
+        assert!(processed_files(dir.path()).is_empty());
+
+        // Unchanged since the last scan: ingest it
+        scanner.scan().await.unwrap();
+        flux.assert_async().await;
+
+        assert!(!dir.path().join("inventory.csv").exists());
+        assert!(dir.path().join("ignored.txt").exists());
+        let processed = processed_files(dir.path());
+        assert_eq!(processed.len(), 1);
+        assert!(processed[0].ends_with("-inventory.csv"));
+
+        let status = scanner_status(&scanner);
+        assert_eq!(status.files_processed, 1);
+        assert_eq!(status.rows_published, 2);
+        assert_eq!(status.rows_failed, 3);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some(
+                "inventory.csv: 3 rows failed: line 3: validation failed: too many properties; \
+                 line 4: missing entity key 'sku'; line 5: expected 7 fields, found 3"
+            )
+        );
+
+        // Nothing left to do
+        scanner.scan().await.unwrap();
+        flux.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_scan_waits_for_file_to_stop_changing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(&path, "{\"device\": \"pump-1\"}\n").unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .with_status(200)
+            .with_body(
+                r#"{"successful": 2, "failed": 0, "results": [{"error": null}, {"error": null}]}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = make_config(FileFormat::Jsonl, "device", dir.path().to_str().unwrap());
+        let mut scanner = scanner(server.url(), config);
+        scanner.scan().await.unwrap();
+
+        // Still growing at the next scan
+        std::fs::write(
+            &path,
+            "{\"device\": \"pump-1\"}\n{\"device\": \"pump-2\"}\n",
+        )
+        .unwrap();
+        scanner.scan().await.unwrap();
+        assert!(path.exists());
+
+        scanner.scan().await.unwrap();
+        flux.assert_async().await;
+        assert!(!path.exists());
+        let status = scanner_status(&scanner);
+        assert_eq!(status.rows_published, 2);
+        assert!(status.last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scan_keeps_file_when_flux_is_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inventory.csv");
+        std::fs::write(&path, INVENTORY_CSV).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let config = make_config(FileFormat::Csv, "sku", dir.path().to_str().unwrap());
+        let mut scanner = scanner(server.url(), config);
+        scanner.scan().await.unwrap();
+        let err = scanner.scan().await.unwrap_err();
+        flux.assert_async().await;
+
+        assert!(format!("{:#}", err).contains("503"));
+        assert!(path.exists());
+        assert!(processed_files(dir.path()).is_empty());
+        assert!(scanner_status(&scanner).last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scan_skips_rejected_file_until_it_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wrong.csv");
+        std::fs::write(&path, "id,name\n1,a\n").unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let config = make_config(FileFormat::Csv, "sku", dir.path().to_str().unwrap());
+        let mut scanner = scanner(server.url(), config);
+        scanner.scan().await.unwrap();
+        scanner.scan().await.unwrap();
+        scanner.scan().await.unwrap();
+        flux.assert_async().await;
+
+        assert!(path.exists());
+        let status = scanner_status(&scanner);
+        assert_eq!(status.files_processed, 0);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("wrong.csv: CSV header has no entity key column 'sku'")
+        );
+        assert!(scanner.rejected.contains_key(&path));
+    }
+}
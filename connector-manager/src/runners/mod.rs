@@ -1,3 +1,11 @@
+pub mod bento;
 pub mod builtin;
+pub mod file;
 pub mod generic;
 pub mod named;
+pub mod postgres;
+mod publish;
+pub mod rate_limit;
+pub mod retry;
+mod singer_schema;
+pub mod weather;
@@ -7,8 +7,32 @@
 //! `NamedRunner` spawns Singer tap subprocesses, parses their stdout, and
 //! publishes Flux events. State files persist incremental sync bookmarks
 //! between runs.
-
+//!
+//! # Stream schemas
+//! Each Singer `SCHEMA` message is reduced to a property type map and
+//! published as entity `{namespace}/_schema/{tap}/{stream}` (event schema
+//! `flux.tap_schema`) when its hash differs from the last one published.
+//! Sources with `coerce_types` set also convert RECORD values to those types.
+//!
+//! # Limits
+//! A run publishes at most `max_events_per_run` records. Further records are
+//! dropped and STATE is no longer saved, so the next run resumes from the
+//! last bookmark covering published records. All events are paced by the
+//! shared [`PublishLimiter`].
+//!
+//! # Parallel streams
+//! With `parallel_streams` set, the discovered catalog is split into one
+//! catalog per stream and up to that many tap processes run at once, each
+//! with its own state file. Their bookmarks are merged into the source's
+//! state file afterwards. A failed stream doesn't stop the others; the run
+//! is then partial, and fails only if every stream failed.
+
+use super::rate_limit::{PublishLimiter, RunCap};
+use super::retry::{jitter_sample, wait_or_retry, CircuitBreaker};
+use super::singer_schema::{self, PropertyTypes};
+use crate::metrics::{ConnectorMetrics, SourceMetrics, Tool};
 use crate::named_config::{NamedConfigStore, NamedSourceConfig};
+use crate::transform;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -16,11 +40,13 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::process::Stdio;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::JoinSet;
 use tracing::{info, warn};
 
-const MELTANO_INDEX_URL: &str =
-    "https://hub.meltano.com/meltano/api/v1/plugins/extractors/index";
+const MELTANO_INDEX_URL: &str = "https://hub.meltano.com/meltano/api/v1/plugins/extractors/index";
 
 /// 24 hours in seconds.
 const CACHE_TTL_SECS: i64 = 86_400;
@@ -30,7 +56,13 @@ const CACHE_TTL_SECS: i64 = 86_400;
 // ---------------------------------------------------------------------------
 
 /// A single entry in the Meltano Hub tap catalog.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[schema(example = json!({
+    "name": "tap-github",
+    "label": "Github",
+    "description": "",
+    "pip_url": "tap-github"
+}))]
 pub struct TapCatalogEntry {
     /// Tap name and pip install package (e.g. `"tap-github"`).
     pub name: String,
@@ -60,6 +92,59 @@ pub struct NamedStatus {
     pub last_error: Option<String>,
     /// Total number of completed runs (success or failure).
     pub restart_count: u32,
+    /// Records the last completed run dropped at its `max_events_per_run` cap.
+    pub last_run_dropped_events: u64,
+    /// Failed scheduled runs since the last successful one.
+    pub consecutive_failures: u32,
+    /// Set while the circuit is open: the tap is not run again before then.
+    pub circuit_open_until: Option<DateTime<Utc>>,
+    /// Some streams of the last completed run failed (`parallel_streams`).
+    pub last_run_partial: bool,
+    /// Per-stream results of the last completed run; empty unless the source
+    /// runs its streams in parallel.
+    pub streams: Vec<NamedStreamStatus>,
+}
+
+impl NamedStatus {
+    /// Records a completed run's results.
+    fn record_run(&mut self, run: TapRun) {
+        self.last_run_partial = run.is_partial();
+        self.last_run_dropped_events = run.dropped;
+        self.streams = run.streams;
+    }
+}
+
+/// Result of one stream's tap process in a parallel run.
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+#[schema(example = json!({
+    "stream": "issues",
+    "records": 120,
+    "duration_ms": 5400
+}))]
+pub struct NamedStreamStatus {
+    /// Singer stream (`tap_stream_id`).
+    pub stream: String,
+    /// Records published from the stream.
+    pub records: u64,
+    /// How long the stream's tap process ran.
+    pub duration_ms: u64,
+    /// Why the stream failed, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Outcome of a successful (possibly partial) tap run.
+struct TapRun {
+    /// Records dropped at the run's `max_events_per_run` cap.
+    dropped: u64,
+    /// Per-stream results; empty for a single-process run.
+    streams: Vec<NamedStreamStatus>,
+}
+
+impl TapRun {
+    fn is_partial(&self) -> bool {
+        self.streams.iter().any(|s| s.last_error.is_some())
+    }
 }
 
 /// Named connector runner — manages Singer tap subprocesses.
@@ -69,12 +154,23 @@ pub struct NamedStatus {
 /// 2. Optionally passes a state file (`/tmp/flux-tap-{id}-state.json`) for incremental sync
 /// 3. Spawns the tap subprocess and reads its stdout line by line
 /// 4. Parses Singer `RECORD` messages → Flux events, `STATE` messages → state file
-/// 5. After the tap exits, waits `poll_interval_secs`, then repeats
+/// 5. After the tap exits, waits `poll_interval_secs` (or the retry backoff
+///    after a failed run, if longer), then repeats
+///
+/// Sources with `parallel_streams` run one tap process per stream instead
+/// (see the module docs).
+///
+/// Once a source's circuit opens, it waits for the cool-down or
+/// [`retry_source`](Self::retry_source).
 pub struct NamedRunner {
     pub store: Arc<NamedConfigStore>,
     pub flux_api_url: String,
     task_handles: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    /// Wakes a source's loop for [`retry_source`](Self::retry_source)
+    retry_signals: Mutex<HashMap<String, Arc<Notify>>>,
     status_map: Arc<Mutex<HashMap<String, NamedStatus>>>,
+    limiter: Arc<PublishLimiter>,
+    metrics: Arc<ConnectorMetrics>,
 }
 
 impl NamedRunner {
@@ -83,10 +179,25 @@ impl NamedRunner {
             store,
             flux_api_url,
             task_handles: Mutex::new(HashMap::new()),
+            retry_signals: Mutex::new(HashMap::new()),
             status_map: Arc::new(Mutex::new(HashMap::new())),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+            metrics: Arc::new(ConnectorMetrics::new()),
         }
     }
 
+    /// Paces publishing with the shared `limiter` instead of an unlimited one.
+    pub fn with_limiter(mut self, limiter: Arc<PublishLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Records tap runs, publishes and pip/tap failures in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<ConnectorMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Starts a polling loop for the given Singer tap source.
     ///
     /// Spawns a background task that runs the tap immediately, then reschedules
@@ -100,14 +211,33 @@ impl NamedRunner {
                 last_run: None,
                 last_error: None,
                 restart_count: 0,
+                last_run_dropped_events: 0,
+                consecutive_failures: 0,
+                circuit_open_until: None,
+                last_run_partial: false,
+                streams: Vec::new(),
             });
         }
 
+        let retry = Arc::new(Notify::new());
         let config_owned = config.clone();
         let flux_url = self.flux_api_url.clone();
+        let limiter = Arc::clone(&self.limiter);
+        let metrics = self.metrics.source("named", &config.id, &config.tap_name);
         let status_map = Arc::clone(&self.status_map);
-        let handle = tokio::spawn(run_tap_loop(config_owned, flux_url, status_map));
+        let handle = tokio::spawn(run_tap_loop(
+            config_owned,
+            flux_url,
+            limiter,
+            metrics,
+            status_map,
+            Arc::clone(&retry),
+        ));
 
+        self.retry_signals
+            .lock()
+            .unwrap()
+            .insert(config.id.clone(), retry);
         let mut handles = self.task_handles.lock().unwrap();
         handles.insert(config.id.clone(), handle);
         info!(source_id = %config.id, tap = %config.tap_name, "Named source started");
@@ -123,10 +253,13 @@ impl NamedRunner {
         if let Some(h) = handle {
             h.abort();
         }
+        self.retry_signals.lock().unwrap().remove(source_id);
+        self.metrics.remove_source("named", source_id);
         // Best-effort cleanup of temp files
         for path in [
             format!("/tmp/flux-tap-{}-config.json", source_id),
             format!("/tmp/flux-tap-{}-state.json", source_id),
+            format!("/tmp/flux-tap-{}-schema-hashes.json", source_id),
         ] {
             if let Err(e) = tokio::fs::remove_file(&path).await {
                 if e.kind() != std::io::ErrorKind::NotFound {
@@ -134,16 +267,56 @@ impl NamedRunner {
                 }
             }
         }
+        // Per-stream state files (`parallel_streams`)
+        let prefix = format!("flux-tap-{}-state-", source_id);
+        if let Ok(mut entries) = tokio::fs::read_dir("/tmp").await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    let _ = tokio::fs::remove_file(entry.path()).await;
+                }
+            }
+        }
         info!(source_id = %source_id, "Named source stopped");
         Ok(())
     }
 
+    /// IDs of named sources with a running task.
+    pub fn running_ids(&self) -> Vec<String> {
+        self.task_handles.lock().unwrap().keys().cloned().collect()
+    }
+
     /// Returns current status for all named sources.
     pub fn status(&self) -> Vec<NamedStatus> {
         let map = self.status_map.lock().unwrap();
         map.values().cloned().collect()
     }
 
+    /// Resumes a source whose circuit is open now rather than after the
+    /// cool-down.
+    ///
+    /// Returns false if the circuit is not open; fails if the source is not
+    /// running.
+    pub fn retry_source(&self, source_id: &str) -> Result<bool> {
+        let retry = self
+            .retry_signals
+            .lock()
+            .unwrap()
+            .get(source_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Named source {} is not running", source_id))?;
+        let open = self
+            .status_map
+            .lock()
+            .unwrap()
+            .get(source_id)
+            .is_some_and(|s| s.circuit_open_until.is_some());
+        if open {
+            retry.notify_one();
+            info!(source_id = %source_id, "Named source retry requested");
+        }
+        Ok(open)
+    }
+
     /// Triggers an immediate one-shot tap run (fire and forget).
     ///
     /// Returns `Err` if the source is not found in the config store.
@@ -154,6 +327,8 @@ impl NamedRunner {
             .get(source_id)?
             .ok_or_else(|| anyhow::anyhow!("Named source {} not found", source_id))?;
         let flux_url = self.flux_api_url.clone();
+        let limiter = Arc::clone(&self.limiter);
+        let metrics = self.metrics.source("named", &config.id, &config.tap_name);
         let status_map = Arc::clone(&self.status_map);
         tokio::spawn(async move {
             let id = config.id.clone();
@@ -165,13 +340,14 @@ impl NamedRunner {
                     s.last_run = Some(Utc::now());
                 }
             }
-            match run_tap_once(&config, &flux_url).await {
-                Ok(()) => {
+            match run_tap_recorded(&config, &flux_url, &limiter, &metrics).await {
+                Ok(run) => {
                     info!(source_id = %id, tap = %tap, "Manual sync complete");
                     let mut map = status_map.lock().unwrap();
                     if let Some(s) = map.get_mut(&id) {
                         s.last_error = None;
                         s.restart_count += 1;
+                        s.record_run(run);
                     }
                 }
                 Err(e) => {
@@ -192,12 +368,24 @@ impl NamedRunner {
 // Singer subprocess execution
 // ---------------------------------------------------------------------------
 
-/// Long-running loop: run tap immediately, then reschedule after poll_interval_secs.
+/// Long-running loop: run tap immediately, then reschedule after
+/// poll_interval_secs, the retry backoff, or the circuit's cool-down.
 async fn run_tap_loop(
     config: NamedSourceConfig,
     flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    metrics: Arc<SourceMetrics>,
     status_map: Arc<Mutex<HashMap<String, NamedStatus>>>,
+    retry: Arc<Notify>,
 ) {
+    let period = chrono::Duration::seconds(config.poll_interval_secs as i64);
+    let mut breaker = CircuitBreaker::new(config.retry_policy);
+    let record_breaker = |breaker: &CircuitBreaker| {
+        if let Some(s) = status_map.lock().unwrap().get_mut(&config.id) {
+            s.consecutive_failures = breaker.consecutive_failures();
+            s.circuit_open_until = breaker.open_until();
+        }
+    };
     loop {
         // Record run start time
         {
@@ -208,14 +396,17 @@ async fn run_tap_loop(
         }
         info!(source_id = %config.id, tap = %config.tap_name, "Singer tap run starting");
 
-        match run_tap_once(&config, &flux_api_url).await {
-            Ok(()) => {
+        let delay = match run_tap_recorded(&config, &flux_api_url, &limiter, &metrics).await {
+            Ok(run) => {
                 info!(source_id = %config.id, tap = %config.tap_name, "Singer tap run complete");
                 let mut map = status_map.lock().unwrap();
                 if let Some(s) = map.get_mut(&config.id) {
                     s.last_error = None;
                     s.restart_count += 1;
+                    s.record_run(run);
                 }
+                breaker.record_success();
+                period
             }
             Err(e) => {
                 warn!(
@@ -229,13 +420,66 @@ async fn run_tap_loop(
                     s.last_error = Some(e.to_string());
                     s.restart_count += 1;
                 }
+                breaker
+                    .record_failure(Utc::now(), jitter_sample())
+                    .max(period)
             }
-        }
+        };
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(config.poll_interval_secs)).await;
+        record_breaker(&breaker);
+        if let Some(until) = breaker.open_until() {
+            warn!(
+                source_id = %config.id,
+                tap = %config.tap_name,
+                failures = breaker.consecutive_failures(),
+                until = %until.to_rfc3339(),
+                "Singer tap circuit open"
+            );
+        }
+        let retried = wait_or_retry(delay, &retry).await;
+        if breaker.open_until().is_some() {
+            info!(source_id = %config.id, retried, "Singer tap circuit half-open, trying again");
+            breaker.trial();
+            record_breaker(&breaker);
+        }
     }
 }
 
+/// [`run_tap_once`], recording the run in `metrics`.
+async fn run_tap_recorded(
+    config: &NamedSourceConfig,
+    flux_api_url: &str,
+    limiter: &Arc<PublishLimiter>,
+    metrics: &Arc<SourceMetrics>,
+) -> Result<TapRun> {
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let result = run_tap_once(config, flux_api_url, limiter, metrics).await;
+    metrics.record_run(started_at, started.elapsed(), result.is_ok());
+    result
+}
+
+/// What the tap processes of one run share.
+struct TapContext {
+    config: NamedSourceConfig,
+    flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    metrics: Arc<SourceMetrics>,
+    http_client: reqwest::Client,
+    config_path: String,
+    schema_hashes_path: String,
+    /// Singer stream → last published schema hash
+    schema_hashes: tokio::sync::Mutex<HashMap<String, String>>,
+    cap: Mutex<RunCap>,
+}
+
+/// A tap process that ran to exit.
+struct TapProcess {
+    /// Records admitted under the cap and published.
+    records: u64,
+    exit_status: std::process::ExitStatus,
+}
+
 /// Runs one complete tap invocation: discover → spawn → read stdout → wait for exit.
 ///
 /// - Writes config JSON to `/tmp/flux-tap-{id}-config.json` (mode 0600).
@@ -243,13 +487,27 @@ async fn run_tap_loop(
 ///   Auto-installs the tap via pip if not found on PATH (during discover step).
 /// - Writes the selected catalog to `/tmp/flux-tap-{id}-catalog.json`.
 /// - If `/tmp/flux-tap-{id}-state.json` exists, passes it via `--state`.
-/// - Parses Singer RECORD messages → Flux events → POSTs to flux_api_url.
-/// - Persists Singer STATE messages to the state file for incremental sync.
-/// - Removes the config and catalog files after the tap exits (state file is kept).
-async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<()> {
+/// - Publishes changed Singer SCHEMA messages as `flux.tap_schema` events; the
+///   last published hash per stream is kept in `/tmp/flux-tap-{id}-schema-hashes.json`.
+/// - Parses Singer RECORD messages → Flux events → POSTs to flux_api_url,
+///   up to `max_events_per_run`.
+/// - Persists Singer STATE messages to the state file for incremental sync,
+///   until a record has been dropped.
+/// - Removes the config and catalog files after the tap exits (state and
+///   schema hash files are kept).
+///
+/// With `parallel_streams` set, the catalog and state steps happen per
+/// stream instead; see [`run_streams`].
+async fn run_tap_once(
+    config: &NamedSourceConfig,
+    flux_api_url: &str,
+    limiter: &Arc<PublishLimiter>,
+    metrics: &Arc<SourceMetrics>,
+) -> Result<TapRun> {
     let config_path = format!("/tmp/flux-tap-{}-config.json", config.id);
     let state_path = format!("/tmp/flux-tap-{}-state.json", config.id);
     let catalog_path = format!("/tmp/flux-tap-{}-catalog.json", config.id);
+    let schema_hashes_path = format!("/tmp/flux-tap-{}-schema-hashes.json", config.id);
 
     // Write tap config with restricted permissions
     tokio::fs::write(&config_path, &config.config_json)
@@ -263,35 +521,265 @@ async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<
     }
 
     // Run --discover to get a selected catalog; auto-installs tap if missing
-    let catalog_json = match run_discover(config, &config_path).await {
-        Ok(j) => j,
+    let catalog = match run_discover(config, &config_path, metrics).await {
+        Ok(c) => c,
         Err(e) => {
             let _ = tokio::fs::remove_file(&config_path).await;
             return Err(e);
         }
     };
-    tokio::fs::write(&catalog_path, &catalog_json)
+
+    let schema_hashes = tokio::fs::read_to_string(&schema_hashes_path)
+        .await
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let ctx = Arc::new(TapContext {
+        config: config.clone(),
+        flux_api_url: flux_api_url.to_string(),
+        limiter: Arc::clone(limiter),
+        metrics: Arc::clone(metrics),
+        http_client: crate::http::client()?,
+        config_path,
+        schema_hashes_path,
+        schema_hashes: tokio::sync::Mutex::new(schema_hashes),
+        cap: Mutex::new(RunCap::new(config.max_events_per_run)),
+    });
+
+    let streams = if config.parallel_streams > 0 {
+        split_catalog(&catalog)
+    } else {
+        Vec::new()
+    };
+    let result = if streams.is_empty() {
+        run_single(&ctx, &catalog, &catalog_path, &state_path).await
+    } else {
+        run_streams(&ctx, streams, &state_path).await
+    };
+
+    // Remove config and catalog files; state file is kept for incremental sync
+    for path in [&ctx.config_path, &catalog_path] {
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!(path = %path, error = %e, "Failed to remove tap temp file");
+            }
+        }
+    }
+
+    let run = result?;
+    if run.dropped > 0 {
+        warn!(
+            tap = %config.tap_name,
+            dropped = run.dropped,
+            "Singer tap run was truncated at its event cap"
+        );
+    }
+    Ok(run)
+}
+
+/// Runs all streams of `catalog` in one tap process.
+async fn run_single(
+    ctx: &TapContext,
+    catalog: &serde_json::Value,
+    catalog_path: &str,
+    state_path: &str,
+) -> Result<TapRun> {
+    tokio::fs::write(catalog_path, serde_json::to_string(catalog)?)
         .await
         .context("Failed to write catalog file")?;
 
+    // Attach state file if it exists (incremental sync bookmark)
+    let state_in = tokio::fs::metadata(state_path)
+        .await
+        .is_ok()
+        .then_some(state_path);
+    run_tap_process(ctx, catalog_path, state_in, state_path).await?;
+    Ok(TapRun {
+        dropped: ctx.cap.lock().unwrap().dropped(),
+        streams: Vec::new(),
+    })
+}
+
+/// Runs each of `streams` in its own tap process, at most
+/// `parallel_streams` at once, then merges their bookmarks into
+/// `state_path`.
+///
+/// Fails only if every stream failed.
+async fn run_streams(
+    ctx: &Arc<TapContext>,
+    streams: Vec<(String, serde_json::Value)>,
+    state_path: &str,
+) -> Result<TapRun> {
+    let permits = Arc::new(Semaphore::new(ctx.config.parallel_streams as usize));
+    let mut tasks = JoinSet::new();
+    for (stream, catalog) in streams {
+        let ctx = Arc::clone(ctx);
+        let permits = Arc::clone(&permits);
+        let state_path = state_path.to_string();
+        tasks.spawn(async move {
+            let _permit = permits
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let started = Instant::now();
+            let (records, last_error) =
+                match run_stream(&ctx, &stream, &catalog, &state_path).await {
+                    Ok(p) if p.exit_status.success() => (p.records, None),
+                    Ok(p) => (
+                        p.records,
+                        Some(format!(
+                            "tap exited with non-zero status (exit code {})",
+                            p.exit_status.code().unwrap_or(-1)
+                        )),
+                    ),
+                    Err(e) => (0, Some(e.to_string())),
+                };
+            if let Some(e) = &last_error {
+                warn!(tap = %ctx.config.tap_name, stream = %stream, error = %e, "Singer stream failed");
+            }
+            NamedStreamStatus {
+                stream,
+                records,
+                duration_ms: started.elapsed().as_millis() as u64,
+                last_error,
+            }
+        });
+    }
+
+    let mut statuses = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        statuses.push(joined.context("Singer stream task panicked")?);
+    }
+    statuses.sort_by(|a, b| a.stream.cmp(&b.stream));
+
+    // Bookmarks from failed streams are kept too: they only cover published records
+    let mut stream_states = Vec::new();
+    for status in &statuses {
+        let path = stream_file(&ctx.config.id, "state", &status.stream);
+        if let Some(state) = read_state(&path).await {
+            stream_states.push((status.stream.clone(), state));
+        }
+    }
+    if !stream_states.is_empty() {
+        let merged = merge_states(read_state(state_path).await.as_ref(), &stream_states);
+        if let Err(e) = tokio::fs::write(state_path, merged.to_string()).await {
+            warn!(tap = %ctx.config.tap_name, error = %e, "Failed to write Singer state file");
+        }
+    }
+
+    let failed: Vec<String> = statuses
+        .iter()
+        .filter_map(|s| {
+            s.last_error
+                .as_ref()
+                .map(|e| format!("{}: {}", s.stream, e))
+        })
+        .collect();
+    if failed.len() == statuses.len() {
+        return Err(anyhow::anyhow!(
+            "All {} streams failed ({})",
+            failed.len(),
+            failed.join("; ")
+        ));
+    }
+    if !failed.is_empty() {
+        warn!(
+            tap = %ctx.config.tap_name,
+            failed = failed.len(),
+            streams = statuses.len(),
+            "Singer tap run was partial"
+        );
+    }
+    Ok(TapRun {
+        dropped: ctx.cap.lock().unwrap().dropped(),
+        streams: statuses,
+    })
+}
+
+/// Runs one stream with its own catalog and state files.
+///
+/// The stream starts from its own last state, or from the source's state
+/// the first time (e.g. after switching from a single process).
+async fn run_stream(
+    ctx: &TapContext,
+    stream: &str,
+    catalog: &serde_json::Value,
+    state_path: &str,
+) -> Result<TapProcess> {
+    let catalog_path = stream_file(&ctx.config.id, "catalog", stream);
+    let stream_state_path = stream_file(&ctx.config.id, "state", stream);
+    tokio::fs::write(&catalog_path, serde_json::to_string(catalog)?)
+        .await
+        .context("Failed to write catalog file")?;
+
+    let state_in = if tokio::fs::metadata(&stream_state_path).await.is_ok() {
+        Some(stream_state_path.as_str())
+    } else if tokio::fs::metadata(state_path).await.is_ok() {
+        Some(state_path)
+    } else {
+        None
+    };
+    let result = run_tap_process(ctx, &catalog_path, state_in, &stream_state_path).await;
+
+    if let Err(e) = tokio::fs::remove_file(&catalog_path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!(path = %catalog_path, error = %e, "Failed to remove tap temp file");
+        }
+    }
+    result
+}
+
+/// Per-stream temp file: `/tmp/flux-tap-{id}-{kind}-{stream}.json`, with
+/// characters unsafe in file names replaced by `_`.
+fn stream_file(source_id: &str, kind: &str, stream: &str) -> String {
+    let stream: String = stream
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("/tmp/flux-tap-{}-{}-{}.json", source_id, kind, stream)
+}
+
+/// Singer state saved at `path`, if any.
+async fn read_state(path: &str) -> Option<serde_json::Value> {
+    let json = tokio::fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Spawns the tap with `catalog_path` and publishes its output until it exits.
+///
+/// Saves STATE messages to `state_out` until one of this process's records
+/// is dropped at the run's cap. A non-zero exit is returned, not an error.
+async fn run_tap_process(
+    ctx: &TapContext,
+    catalog_path: &str,
+    state_in: Option<&str>,
+    state_out: &str,
+) -> Result<TapProcess> {
+    let config = &ctx.config;
+    let limiter = ctx.limiter.as_ref();
+    let metrics = ctx.metrics.as_ref();
+    let flux_api_url = ctx.flux_api_url.as_str();
+
     // Build command (tap guaranteed installed after successful discover)
     let mut cmd = tokio::process::Command::new(&config.tap_name);
-    cmd.arg("--config").arg(&config_path);
-    cmd.arg("--properties").arg(&catalog_path);
+    cmd.arg("--config").arg(&ctx.config_path);
+    cmd.arg("--properties").arg(catalog_path);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::null());
-
-    // Attach state file if it exists (incremental sync bookmark)
-    if tokio::fs::metadata(&state_path).await.is_ok() {
-        cmd.arg("--state").arg(&state_path);
+    if let Some(state_in) = state_in {
+        cmd.arg("--state").arg(state_in);
     }
 
     let mut child = match cmd.spawn() {
         Ok(c) => c,
         Err(e) => {
-            for path in [&config_path, &catalog_path] {
-                let _ = tokio::fs::remove_file(path).await;
-            }
+            metrics.record_invocation_failure(Tool::Tap);
             return Err(e.into());
         }
     };
@@ -299,9 +787,12 @@ async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<
     let stdout = child.stdout.take().expect("stdout is piped");
     let mut lines = BufReader::new(stdout).lines();
 
-    let http_client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+    let http_client = &ctx.http_client;
+    let token = config.flux_namespace_token.as_deref();
+
+    let mut stream_types: HashMap<String, PropertyTypes> = HashMap::new();
+    let mut records = 0;
+    let mut dropped = 0u64;
 
     while let Some(line) = lines.next_line().await? {
         let line = line.trim().to_string();
@@ -321,39 +812,86 @@ async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<
 
         match msg_type {
             "SCHEMA" => {
-                // Schema messages are informational — no action needed
+                let Some(singer_stream) = msg.get("stream").and_then(|v| v.as_str()) else {
+                    warn!(tap = %config.tap_name, "SCHEMA missing stream field");
+                    continue;
+                };
+                let types = singer_schema::property_types(
+                    msg.get("schema").unwrap_or(&serde_json::Value::Null),
+                );
+                let hash = singer_schema::schema_hash(&types);
+                let mut schema_hashes = ctx.schema_hashes.lock().await;
+                if schema_hashes.get(singer_stream) != Some(&hash) {
+                    let event = schema_event(config, singer_stream, &msg, &types, &hash);
+                    match post_event(http_client, limiter, metrics, flux_api_url, token, &event)
+                        .await
+                    {
+                        Ok(()) => {
+                            schema_hashes.insert(singer_stream.to_string(), hash);
+                            let hashes_json = serde_json::to_string(&*schema_hashes)?;
+                            if let Err(e) =
+                                tokio::fs::write(&ctx.schema_hashes_path, hashes_json).await
+                            {
+                                warn!(tap = %config.tap_name, error = %e, "Failed to write Singer schema hash file");
+                            }
+                        }
+                        Err(e) => {
+                            warn!(tap = %config.tap_name, error = %e, "Failed to post Singer schema to Flux");
+                        }
+                    }
+                }
+                drop(schema_hashes);
+                stream_types.insert(singer_stream.to_string(), types);
             }
             "RECORD" => {
                 let singer_stream = msg
                     .get("stream")
                     .and_then(|v| v.as_str())
                     .unwrap_or("unknown");
-                let record = match msg.get("record").and_then(|v| v.as_object()) {
+                let mut record = match msg.get("record").and_then(|v| v.as_object()) {
                     Some(r) => r.clone(),
                     None => {
                         warn!(tap = %config.tap_name, "RECORD missing record field");
                         continue;
                     }
                 };
+                {
+                    let mut cap = ctx.cap.lock().unwrap();
+                    if !cap.admit() {
+                        if cap.dropped() == 1 {
+                            warn!(
+                                tap = %config.tap_name,
+                                max_events = config.max_events_per_run,
+                                "Run reached its event cap, dropping further records"
+                            );
+                        }
+                        dropped += 1;
+                        continue;
+                    }
+                }
+                records += 1;
+                if config.coerce_types {
+                    if let Some(types) = stream_types.get(singer_stream) {
+                        singer_schema::coerce_record(&mut record, types);
+                    }
+                }
 
                 // Entity key: configured field, or fallback to first field value
                 let key = record
                     .get(&config.entity_key_field)
-                    .map(|v| value_to_string(v))
+                    .map(value_to_string)
                     .unwrap_or_else(|| {
                         record
                             .values()
                             .next()
-                            .map(|v| value_to_string(v))
+                            .map(value_to_string)
                             .unwrap_or_else(|| "unknown".to_string())
                     });
 
                 let entity_id = format!("{}/{}", config.namespace, key);
 
-                let safe_tap = config.tap_name.replace('-', ".");
-                let safe_stream = singer_stream.replace('-', ".");
-                let event = serde_json::json!({
-                    "stream": format!("taps.{}.{}", safe_tap, safe_stream),
+                let mut event = serde_json::json!({
+                    "stream": flux_stream(&config.tap_name, singer_stream),
                     "source": format!("tap.{}", config.tap_name),
                     "timestamp": Utc::now().timestamp_millis(),
                     "key": key,
@@ -362,27 +900,24 @@ async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<
                         "properties": record,
                     }
                 });
+                transform::apply_to_event(&config.transforms, &mut event);
 
-                let mut req = http_client
-                    .post(format!("{}/api/events", flux_api_url))
-                    .json(&event);
-                if let Some(ref token) = config.flux_namespace_token {
-                    req = req.header(
-                        "Authorization",
-                        format!("Bearer {}", token),
-                    );
-                }
-                if let Err(e) = req.send().await {
+                if let Err(e) =
+                    post_event(http_client, limiter, metrics, flux_api_url, token, &event).await
+                {
                     warn!(tap = %config.tap_name, error = %e, "Failed to post Singer event to Flux");
                 }
             }
             "STATE" => {
+                // Past the cap the bookmark would skip dropped records
+                if dropped > 0 {
+                    continue;
+                }
                 // Persist state bookmark for incremental sync on next run
-                let state_value =
-                    msg.get("value").cloned().unwrap_or(serde_json::Value::Null);
+                let state_value = msg.get("value").cloned().unwrap_or(serde_json::Value::Null);
                 match serde_json::to_string(&state_value) {
                     Ok(state_json) => {
-                        if let Err(e) = tokio::fs::write(&state_path, &state_json).await {
+                        if let Err(e) = tokio::fs::write(state_out, &state_json).await {
                             warn!(tap = %config.tap_name, error = %e, "Failed to write Singer state file");
                         }
                     }
@@ -400,27 +935,91 @@ async fn run_tap_once(config: &NamedSourceConfig, flux_api_url: &str) -> Result<
     // Wait for tap to fully exit
     let exit_status = child.wait().await?;
     if !exit_status.success() {
+        metrics.record_invocation_failure(Tool::Tap);
         warn!(
             tap = %config.tap_name,
             code = ?exit_status.code(),
             "Tap exited with non-zero status"
         );
     }
+    Ok(TapProcess {
+        records,
+        exit_status,
+    })
+}
 
-    // Remove config and catalog files; state file is kept for incremental sync
-    for path in [&config_path, &catalog_path] {
-        if let Err(e) = tokio::fs::remove_file(path).await {
-            if e.kind() != std::io::ErrorKind::NotFound {
-                warn!(path = %path, error = %e, "Failed to remove tap temp file");
+/// Flux stream for a Singer stream: `taps.{tap}.{stream}` with `-` → `.`.
+fn flux_stream(tap_name: &str, singer_stream: &str) -> String {
+    format!(
+        "taps.{}.{}",
+        tap_name.replace('-', "."),
+        singer_stream.replace('-', ".")
+    )
+}
+
+/// `flux.tap_schema` event describing a Singer stream's property types.
+fn schema_event(
+    config: &NamedSourceConfig,
+    singer_stream: &str,
+    schema_msg: &serde_json::Value,
+    types: &PropertyTypes,
+    hash: &str,
+) -> serde_json::Value {
+    let key = format!("_schema/{}/{}", config.tap_name, singer_stream);
+    serde_json::json!({
+        "stream": flux_stream(&config.tap_name, singer_stream),
+        "source": format!("tap.{}", config.tap_name),
+        "timestamp": Utc::now().timestamp_millis(),
+        "key": key,
+        "schema": "flux.tap_schema",
+        "payload": {
+            "entity_id": format!("{}/{}", config.namespace, key),
+            "properties": {
+                "tap": config.tap_name,
+                "stream": singer_stream,
+                "key_properties": schema_msg
+                    .get("key_properties")
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!([])),
+                "schema_hash": hash,
+                "fields": types,
             }
         }
-    }
+    })
+}
 
-    Ok(())
+/// POSTs a single event to `{flux_api_url}/api/events` once `limiter` allows.
+async fn post_event(
+    http_client: &reqwest::Client,
+    limiter: &PublishLimiter,
+    metrics: &SourceMetrics,
+    flux_api_url: &str,
+    token: Option<&str>,
+    event: &serde_json::Value,
+) -> Result<()> {
+    limiter.acquire(1).await;
+    let mut req = http_client
+        .post(format!("{}/api/events", flux_api_url))
+        .json(event);
+    if let Some(token) = token {
+        req = req.header("Authorization", format!("Bearer {}", token));
+    }
+    let sent = req.send().await;
+    metrics.record_flux_response(sent.as_ref().ok().map(|r| r.status().as_u16()));
+    match sent.and_then(|r| r.error_for_status()) {
+        Ok(_) => {
+            metrics.record_events(1);
+            Ok(())
+        }
+        Err(e) => {
+            metrics.record_publish_failure();
+            Err(e.into())
+        }
+    }
 }
 
 /// Converts a JSON value to a string for use as a Flux entity key.
-fn value_to_string(v: &serde_json::Value) -> String {
+pub(crate) fn value_to_string(v: &serde_json::Value) -> String {
     match v {
         serde_json::Value::String(s) => s.clone(),
         serde_json::Value::Number(n) => n.to_string(),
@@ -497,9 +1096,7 @@ impl TapCatalogStore {
     /// Fetch the extractor index from Meltano Hub, update the in-memory store,
     /// and write the result to the on-disk cache.
     pub async fn refresh(&self) -> Result<usize> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+        let client = crate::http::client()?;
 
         let index: HashMap<String, MeltanoIndexEntry> = client
             .get(MELTANO_INDEX_URL)
@@ -541,7 +1138,11 @@ impl TapCatalogStore {
             serde_json::from_str(&data).context("Failed to parse cached catalog JSON")?;
         let count = cached.entries.len();
         *self.entries.write().unwrap() = cached.entries;
-        info!(count, cache_path = &self.cache_path, "Tap catalog loaded from cache");
+        info!(
+            count,
+            cache_path = &self.cache_path,
+            "Tap catalog loaded from cache"
+        );
         Ok(())
     }
 
@@ -567,7 +1168,10 @@ impl TapCatalogStore {
 /// - `"tap-google-analytics"` → `"Google Analytics"`
 fn derive_label(tap_name: &str) -> String {
     let base = tap_name.strip_prefix("tap-").unwrap_or(tap_name);
-    base.split('-').map(capitalize).collect::<Vec<_>>().join(" ")
+    base.split('-')
+        .map(capitalize)
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 fn capitalize(s: &str) -> String {
@@ -582,10 +1186,14 @@ fn capitalize(s: &str) -> String {
 // Singer discover helpers
 // ---------------------------------------------------------------------------
 
-/// Runs `tap --discover`, marks all streams selected, returns catalog JSON.
+/// Runs `tap --discover`, marks all streams selected, returns the catalog.
 ///
 /// Auto-installs the tap via pip if the binary is not found on PATH.
-async fn run_discover(config: &NamedSourceConfig, config_path: &str) -> Result<String> {
+async fn run_discover(
+    config: &NamedSourceConfig,
+    config_path: &str,
+    metrics: &SourceMetrics,
+) -> Result<serde_json::Value> {
     let result = tokio::process::Command::new(&config.tap_name)
         .arg("--config")
         .arg(config_path)
@@ -615,34 +1223,41 @@ async fn run_discover(config: &NamedSourceConfig, config_path: &str) -> Result<S
                         .context("Failed to spawn tap after pip install")?
                 }
                 Ok(s) => {
+                    metrics.record_invocation_failure(Tool::Pip);
                     return Err(anyhow::anyhow!(
                         "pip install {} failed (exit code {})",
                         config.tap_name,
                         s.code().unwrap_or(-1)
-                    ))
+                    ));
                 }
                 Err(pe) => {
+                    metrics.record_invocation_failure(Tool::Pip);
                     return Err(anyhow::anyhow!(
                         "pip not available ({}); install {} manually",
-                        pe, config.tap_name
-                    ))
+                        pe,
+                        config.tap_name
+                    ));
                 }
             }
         }
-        Err(e) => return Err(e.into()),
+        Err(e) => {
+            metrics.record_invocation_failure(Tool::Tap);
+            return Err(e.into());
+        }
     };
 
     if !output.status.success() {
+        metrics.record_invocation_failure(Tool::Tap);
         return Err(anyhow::anyhow!(
             "tap --discover failed (exit code {})",
             output.status.code().unwrap_or(-1)
         ));
     }
 
-    let mut catalog: serde_json::Value =
-        serde_json::from_slice(&output.stdout).context("Failed to parse catalog from --discover")?;
+    let mut catalog: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .context("Failed to parse catalog from --discover")?;
     select_all_streams(&mut catalog);
-    serde_json::to_string(&catalog).context("Failed to serialize catalog")
+    Ok(catalog)
 }
 
 /// Marks every stream in a Singer catalog as selected.
@@ -669,9 +1284,7 @@ fn select_all_streams(catalog: &mut serde_json::Value) {
                     .map(|b| b.is_empty())
                     .unwrap_or(false);
                 if is_root {
-                    if let Some(meta) =
-                        entry.get_mut("metadata").and_then(|m| m.as_object_mut())
-                    {
+                    if let Some(meta) = entry.get_mut("metadata").and_then(|m| m.as_object_mut()) {
                         meta.insert("selected".to_string(), serde_json::Value::Bool(true));
                     }
                     found_root = true;
@@ -687,6 +1300,62 @@ fn select_all_streams(catalog: &mut serde_json::Value) {
     }
 }
 
+/// Splits a catalog into one catalog per stream, named by the stream's
+/// `tap_stream_id` (or `stream`). Streams with neither are left out.
+fn split_catalog(catalog: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+    let Some(fields) = catalog.as_object() else {
+        return Vec::new();
+    };
+    let Some(streams) = fields.get("streams").and_then(|s| s.as_array()) else {
+        return Vec::new();
+    };
+    streams
+        .iter()
+        .filter_map(|stream| {
+            let name = stream
+                .get("tap_stream_id")
+                .or_else(|| stream.get("stream"))
+                .and_then(|v| v.as_str())?;
+            let mut single: serde_json::Map<String, serde_json::Value> = fields
+                .iter()
+                .filter(|(key, _)| *key != "streams")
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            single.insert("streams".to_string(), serde_json::json!([stream]));
+            Some((name.to_string(), serde_json::Value::Object(single)))
+        })
+        .collect()
+}
+
+/// Merges the states written by per-stream tap processes into `base`.
+///
+/// Only each stream's own entry under `bookmarks` is taken: a process also
+/// echoes the bookmarks it started with for other streams, which may be
+/// older than what those streams just wrote. `currently_syncing` is dropped.
+fn merge_states(
+    base: Option<&serde_json::Value>,
+    streams: &[(String, serde_json::Value)],
+) -> serde_json::Value {
+    let mut merged = base
+        .and_then(|b| b.as_object())
+        .cloned()
+        .unwrap_or_default();
+    merged.remove("currently_syncing");
+    let bookmarks = merged
+        .entry("bookmarks")
+        .or_insert_with(|| serde_json::json!({}));
+    if !bookmarks.is_object() {
+        *bookmarks = serde_json::json!({});
+    }
+    let bookmarks = bookmarks.as_object_mut().expect("bookmarks is an object");
+    for (stream, state) in streams {
+        if let Some(bookmark) = state.get("bookmarks").and_then(|b| b.get(stream)) {
+            bookmarks.insert(stream.clone(), bookmark.clone());
+        }
+    }
+    serde_json::Value::Object(merged)
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -749,13 +1418,61 @@ mod tests {
 
     #[test]
     fn test_value_to_string() {
-        assert_eq!(value_to_string(&serde_json::Value::String("abc".to_string())), "abc");
+        assert_eq!(
+            value_to_string(&serde_json::Value::String("abc".to_string())),
+            "abc"
+        );
         assert_eq!(value_to_string(&serde_json::json!(42)), "42");
-        assert_eq!(value_to_string(&serde_json::json!(3.14)), "3.14");
+        assert_eq!(value_to_string(&serde_json::json!(2.5)), "2.5");
         assert_eq!(value_to_string(&serde_json::Value::Bool(true)), "true");
         assert_eq!(value_to_string(&serde_json::Value::Null), "null");
     }
 
+    #[test]
+    fn test_schema_event() {
+        let config = NamedSourceConfig {
+            id: "src-1".to_string(),
+            tap_name: "tap-github".to_string(),
+            namespace: "personal".to_string(),
+            entity_key_field: "id".to_string(),
+            config_json: "{}".to_string(),
+            poll_interval_secs: 3600,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            coerce_types: false,
+            max_events_per_run: 50_000,
+            transforms: Vec::new(),
+            owner_namespace: "personal".to_string(),
+            retry_policy: Default::default(),
+            paused: false,
+            parallel_streams: 0,
+        };
+        let msg: serde_json::Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/singer/tap-github-issues.json"
+        ))
+        .unwrap();
+        let types = singer_schema::property_types(&msg["schema"]);
+
+        let event = schema_event(&config, "pull-requests", &msg, &types, "abc");
+        assert_eq!(event["stream"], "taps.tap.github.pull.requests");
+        assert_eq!(event["schema"], "flux.tap_schema");
+        assert_eq!(
+            event["payload"]["entity_id"],
+            "personal/_schema/tap-github/pull-requests"
+        );
+        let properties = &event["payload"]["properties"];
+        assert_eq!(properties["key_properties"], serde_json::json!(["id"]));
+        assert_eq!(properties["schema_hash"], "abc");
+        assert_eq!(
+            properties["fields"]["closed_at"],
+            serde_json::json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(
+            properties["fields"]["number"],
+            serde_json::json!({"type": "integer"})
+        );
+    }
+
     #[test]
     fn test_named_runner_status_empty() {
         use crate::named_config::NamedConfigStore;
@@ -763,4 +1480,81 @@ mod tests {
         let runner = NamedRunner::new(store, "http://localhost:3000".to_string());
         assert!(runner.status().is_empty());
     }
+
+    #[test]
+    fn test_split_catalog() {
+        let mut catalog = serde_json::json!({
+            "version": 1,
+            "streams": [
+                {"tap_stream_id": "issues", "stream": "issues", "schema": {}},
+                {"stream": "pull_requests", "schema": {}},
+                {"schema": {}}
+            ]
+        });
+        select_all_streams(&mut catalog);
+
+        let split = split_catalog(&catalog);
+        let names: Vec<&str> = split.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["issues", "pull_requests"]);
+        let (_, issues) = &split[0];
+        assert_eq!(issues["version"], 1);
+        assert_eq!(issues["streams"].as_array().unwrap().len(), 1);
+        assert_eq!(issues["streams"][0]["tap_stream_id"], "issues");
+        assert_eq!(issues["streams"][0]["selected"], true);
+
+        assert!(split_catalog(&serde_json::json!({})).is_empty());
+        assert_eq!(
+            stream_file("src-1", "state", "public/orders.v2"),
+            "/tmp/flux-tap-src-1-state-public_orders_v2.json"
+        );
+    }
+
+    #[test]
+    fn test_merge_states() {
+        let base = serde_json::json!({
+            "bookmarks": {
+                "issues": {"since": "2026-01-01"},
+                "labels": {"since": "2026-01-01"}
+            },
+            "currently_syncing": "issues",
+            "version": 2
+        });
+        // Each process echoes the bookmarks it started with for other streams
+        let streams = [
+            (
+                "issues".to_string(),
+                serde_json::json!({"bookmarks": {
+                    "issues": {"since": "2026-02-01"},
+                    "pulls": {"since": "2026-01-01"}
+                }}),
+            ),
+            (
+                "pulls".to_string(),
+                serde_json::json!({"bookmarks": {
+                    "issues": {"since": "2026-01-01"},
+                    "pulls": {"since": "2026-03-01"}
+                }}),
+            ),
+            ("labels".to_string(), serde_json::json!({})),
+        ];
+
+        let merged = merge_states(Some(&base), &streams);
+        assert_eq!(
+            merged,
+            serde_json::json!({
+                "bookmarks": {
+                    "issues": {"since": "2026-02-01"},
+                    "labels": {"since": "2026-01-01"},
+                    "pulls": {"since": "2026-03-01"}
+                },
+                "version": 2
+            })
+        );
+
+        let fresh = merge_states(None, &streams[1..2]);
+        assert_eq!(
+            fresh,
+            serde_json::json!({"bookmarks": {"pulls": {"since": "2026-03-01"}}})
+        );
+    }
 }
@@ -0,0 +1,1062 @@
+//! PostgreSQL change-polling runner (query-based CDC).
+//!
+//! Each source runs `SELECT * FROM <table> WHERE <updated_at> > $1 ORDER BY
+//! <updated_at> LIMIT <batch_size>` on its poll interval, converts the rows to
+//! Flux events keyed `{namespace}/{table}/{key}`, publishes them through
+//! `/api/events/batch`, and saves the last `updated_at` value as the source's
+//! high-water mark so a restart resumes where it stopped.
+//!
+//! Rows are picked up when their `updated_at` moves forward: deletes are not
+//! mirrored and rows with a NULL `updated_at` are ignored. A full batch that
+//! ends partway through rows sharing one `updated_at` value leaves those rows
+//! for the next query, so none are skipped.
+//!
+//! Connection and query failures are recorded in the source's status (with the
+//! SQLSTATE when Postgres reported one); the loop reconnects on the next poll.
+use crate::postgres_config::{PostgresConfigStore, PostgresSourceConfig};
+use crate::runners::named::value_to_string;
+use crate::runners::publish::publish_batch;
+use crate::runners::rate_limit::PublishLimiter;
+use crate::transform;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio_postgres::types::{FromSql, Kind, ToSql, Type};
+use tokio_postgres::{Client, NoTls, Row};
+use tracing::{debug, info, warn};
+
+type StatusMap = Arc<Mutex<HashMap<String, PostgresStatus>>>;
+
+type DecodeResult<T> = std::result::Result<T, Box<dyn std::error::Error + Sync + Send>>;
+
+/// Events per `/api/events/batch` request.
+const PUBLISH_CHUNK: usize = 500;
+
+/// Runtime status for a single Postgres source.
+#[derive(Clone, Debug)]
+pub struct PostgresStatus {
+    pub source_id: String,
+    /// Time the most recent poll started.
+    pub last_poll: Option<DateTime<Utc>>,
+    /// Error from the most recent poll, or rows that failed to publish.
+    pub last_error: Option<String>,
+    /// SQLSTATE of the most recent error, if Postgres reported one.
+    pub last_sqlstate: Option<String>,
+    /// Rows accepted by Flux.
+    pub rows_published: u64,
+    /// Rows skipped (NULL key) or rejected by Flux.
+    pub rows_failed: u64,
+    /// Last published `updated_at` value.
+    pub watermark: Option<Value>,
+}
+
+/// Postgres connector runner — mirrors tables by polling an `updated_at` column.
+pub struct PostgresRunner {
+    pub store: Arc<PostgresConfigStore>,
+    pub flux_api_url: String,
+    task_handles: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    status_map: StatusMap,
+    limiter: Arc<PublishLimiter>,
+}
+
+impl PostgresRunner {
+    pub fn new(store: Arc<PostgresConfigStore>, flux_api_url: String) -> Self {
+        Self {
+            store,
+            flux_api_url,
+            task_handles: Mutex::new(HashMap::new()),
+            status_map: Arc::new(Mutex::new(HashMap::new())),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+        }
+    }
+
+    /// Paces publishing with the shared `limiter` instead of an unlimited one.
+    pub fn with_limiter(mut self, limiter: Arc<PublishLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Starts the poll loop for the given source, resuming from its stored
+    /// high-water mark. `password` comes from the CredentialStore.
+    pub async fn start_source(
+        &self,
+        config: &PostgresSourceConfig,
+        password: Option<String>,
+    ) -> Result<()> {
+        let watermark = self.store.watermark(&config.id)?;
+        {
+            let mut map = self.status_map.lock().unwrap();
+            map.entry(config.id.clone())
+                .or_insert_with(|| PostgresStatus {
+                    source_id: config.id.clone(),
+                    last_poll: None,
+                    last_error: None,
+                    last_sqlstate: None,
+                    rows_published: 0,
+                    rows_failed: 0,
+                    watermark: watermark.clone(),
+                });
+        }
+
+        let poller = PostgresPoller::new(
+            config.clone(),
+            password,
+            self.flux_api_url.clone(),
+            Arc::clone(&self.limiter),
+            Arc::clone(&self.store),
+            Arc::clone(&self.status_map),
+            watermark,
+        )?;
+        let handle = tokio::spawn(run_poll_loop(poller));
+
+        let mut handles = self.task_handles.lock().unwrap();
+        handles.insert(config.id.clone(), handle);
+        info!(source_id = %config.id, table = %config.table, "Postgres source started");
+        Ok(())
+    }
+
+    /// Aborts the poll loop (closing its connection). No-op if not running.
+    pub async fn stop_source(&self, source_id: &str) -> Result<()> {
+        let handle = {
+            let mut handles = self.task_handles.lock().unwrap();
+            handles.remove(source_id)
+        };
+        if let Some(h) = handle {
+            h.abort();
+        }
+        info!(source_id = %source_id, "Postgres source stopped");
+        Ok(())
+    }
+
+    /// IDs of Postgres sources with a running task.
+    pub fn running_ids(&self) -> Vec<String> {
+        self.task_handles.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Returns current status for all Postgres sources.
+    pub fn status(&self) -> Vec<PostgresStatus> {
+        let map = self.status_map.lock().unwrap();
+        map.values().cloned().collect()
+    }
+}
+
+/// Polls one Postgres table and publishes changed rows.
+pub struct PostgresPoller {
+    config: PostgresSourceConfig,
+    password: Option<String>,
+    flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    http_client: reqwest::Client,
+    store: Arc<PostgresConfigStore>,
+    status_map: StatusMap,
+    client: Option<Client>,
+    watermark: Option<Value>,
+}
+
+/// Result of one successful poll.
+#[derive(Debug, Default)]
+struct PollOutcome {
+    /// The query returned a full batch, so more rows may be waiting.
+    more: bool,
+    published: u64,
+    /// `"<key>: <reason>"` for each row that was not published
+    failed: Vec<String>,
+}
+
+impl PostgresPoller {
+    fn new(
+        config: PostgresSourceConfig,
+        password: Option<String>,
+        flux_api_url: String,
+        limiter: Arc<PublishLimiter>,
+        store: Arc<PostgresConfigStore>,
+        status_map: StatusMap,
+        watermark: Option<Value>,
+    ) -> Result<Self> {
+        let http_client = crate::http::client()?;
+        Ok(Self {
+            config,
+            password,
+            flux_api_url,
+            limiter,
+            http_client,
+            store,
+            status_map,
+            client: None,
+            watermark,
+        })
+    }
+
+    /// Runs one poll and records the result in the source's status.
+    ///
+    /// Returns true if more changed rows may be waiting. On error the
+    /// connection is dropped and re-established by the next poll.
+    pub async fn poll(&mut self) -> Result<bool> {
+        self.update_status(|s| s.last_poll = Some(Utc::now()));
+        match self.poll_changes().await {
+            Ok(outcome) => {
+                let last_error = (!outcome.failed.is_empty()).then(|| {
+                    format!(
+                        "{} row(s) not published: {}",
+                        outcome.failed.len(),
+                        outcome.failed.join("; ")
+                    )
+                });
+                let watermark = self.watermark.clone();
+                self.update_status(|s| {
+                    s.rows_published += outcome.published;
+                    s.rows_failed += outcome.failed.len() as u64;
+                    s.last_error = last_error;
+                    s.last_sqlstate = None;
+                    s.watermark = watermark;
+                });
+                Ok(outcome.more)
+            }
+            Err(e) => {
+                self.client = None;
+                let code = sqlstate(&e);
+                let message = match code {
+                    Some(ref code) => format!("[{}] {:#}", code, e),
+                    None => format!("{:#}", e),
+                };
+                self.update_status(|s| {
+                    s.last_error = Some(message);
+                    s.last_sqlstate = code;
+                });
+                Err(e)
+            }
+        }
+    }
+
+    async fn poll_changes(&mut self) -> Result<PollOutcome> {
+        if self.client.as_ref().is_none_or(|c| c.is_closed()) {
+            self.client = Some(self.connect().await?);
+        }
+        let client = self.client.as_ref().expect("connected above");
+        let (rows, more) = fetch_changes(client, &self.config, self.watermark.as_ref()).await?;
+        let Some(last_cursor) = rows.last().map(|r| r.cursor.clone()) else {
+            return Ok(PollOutcome::default());
+        };
+
+        let mut outcome = PollOutcome {
+            more,
+            ..PollOutcome::default()
+        };
+        let mut keyed = Vec::with_capacity(rows.len());
+        for row in rows {
+            match row.key {
+                Some(key) => keyed.push((key, row.properties)),
+                None => outcome.failed.push(format!(
+                    "row with {} = {}: key column '{}' is NULL",
+                    self.config.updated_at_column, row.cursor, self.config.key_column
+                )),
+            }
+        }
+
+        for chunk in keyed.chunks(PUBLISH_CHUNK) {
+            let events: Vec<Value> = chunk
+                .iter()
+                .map(|(key, properties)| self.build_event(key, properties))
+                .collect();
+            let results = publish_batch(
+                &self.http_client,
+                &self.limiter,
+                &self.flux_api_url,
+                self.config.flux_namespace_token.as_deref(),
+                &events,
+            )
+            .await?;
+            for ((key, _), result) in chunk.iter().zip(results) {
+                match result {
+                    None => outcome.published += 1,
+                    Some(message) => outcome.failed.push(format!("{}: {}", key, message)),
+                }
+            }
+        }
+
+        // Only advance once the rows are in Flux, so a failed publish is retried
+        self.store.set_watermark(&self.config.id, &last_cursor)?;
+        self.watermark = Some(last_cursor);
+        Ok(outcome)
+    }
+
+    async fn connect(&self) -> Result<Client> {
+        let mut pg_config: tokio_postgres::Config = self
+            .config
+            .connection_string
+            .parse()
+            .context("Invalid Postgres connection string")?;
+        if let Some(ref password) = self.password {
+            pg_config.password(password);
+        }
+        if pg_config.get_connect_timeout().is_none() {
+            pg_config.connect_timeout(std::time::Duration::from_secs(10));
+        }
+
+        let (client, connection) = pg_config
+            .connect(NoTls)
+            .await
+            .context("Failed to connect to Postgres")?;
+        let source_id = self.config.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!(source_id = %source_id, error = %e, "Postgres connection closed");
+            }
+        });
+        info!(source_id = %self.config.id, "Connected to Postgres");
+        Ok(client)
+    }
+
+    fn build_event(&self, key: &str, properties: &Map<String, Value>) -> Value {
+        let mut event = serde_json::json!({
+            "stream": "postgres",
+            "source": format!("postgres.{}", self.config.id),
+            "timestamp": Utc::now().timestamp_millis(),
+            "key": format!("{}/{}", self.config.table, key),
+            "payload": {
+                "entity_id": format!("{}/{}/{}", self.config.namespace, self.config.table, key),
+                "properties": properties,
+            }
+        });
+        transform::apply_to_event(&self.config.transforms, &mut event);
+        event
+    }
+
+    fn update_status(&self, f: impl FnOnce(&mut PostgresStatus)) {
+        let mut map = self.status_map.lock().unwrap();
+        if let Some(s) = map.get_mut(&self.config.id) {
+            f(s);
+        }
+    }
+}
+
+/// Long-running loop: poll every `poll_interval_secs`, draining backlogs
+/// without waiting while queries keep returning full batches.
+async fn run_poll_loop(mut poller: PostgresPoller) {
+    let period = std::time::Duration::from_secs(poller.config.poll_interval_secs.max(1));
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        loop {
+            match poller.poll().await {
+                Ok(true) => continue,
+                Ok(false) => {
+                    debug!(source_id = %poller.config.id, "Postgres source polled");
+                    break;
+                }
+                Err(e) => {
+                    warn!(source_id = %poller.config.id, error = %format!("{:#}", e), "Postgres source poll failed");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Queries
+// ---------------------------------------------------------------------------
+
+/// Fetches the next batch of changed rows after `watermark`.
+///
+/// Returns the rows and whether the query hit the batch limit.
+async fn fetch_changes(
+    client: &Client,
+    config: &PostgresSourceConfig,
+    watermark: Option<&Value>,
+) -> Result<(Vec<ChangeRow>, bool)> {
+    let table = quote_table(&config.table);
+    let column = quote_ident(&config.updated_at_column);
+    let limit = config.batch_size.max(1) as usize;
+
+    let rows = match watermark {
+        Some(cursor) => {
+            let sql = format!(
+                "SELECT * FROM {} WHERE {} > $1 ORDER BY {} LIMIT {}",
+                table, column, column, limit
+            );
+            query_after(client, &sql, cursor).await?
+        }
+        None => {
+            let sql = format!(
+                "SELECT * FROM {} WHERE {} IS NOT NULL ORDER BY {} LIMIT {}",
+                table, column, column, limit
+            );
+            client
+                .query(sql.as_str(), &[])
+                .await
+                .context("Failed to query changed rows")?
+        }
+    };
+    let rows = rows
+        .iter()
+        .map(|row| convert_row(row, config))
+        .collect::<Result<Vec<_>>>()?;
+
+    match settle_batch(rows, limit) {
+        BatchEnd::Complete(rows, full) => Ok((rows, full)),
+        BatchEnd::AllTied(cursor) => {
+            // Every row shares one updated_at: take all of them so the
+            // watermark can move past it
+            let sql = format!("SELECT * FROM {} WHERE {} = $1", table, column);
+            let rows = query_after(client, &sql, &cursor).await?;
+            let rows = rows
+                .iter()
+                .map(|row| convert_row(row, config))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((rows, true))
+        }
+    }
+}
+
+/// Runs `sql` with the watermark bound as `$1` in the column's own type.
+async fn query_after(client: &Client, sql: &str, cursor: &Value) -> Result<Vec<Row>> {
+    let statement = client
+        .prepare(sql)
+        .await
+        .context("Failed to prepare change query")?;
+    let param = bind_cursor(&statement.params()[0], cursor)?;
+    client
+        .query(&statement, &[&*param])
+        .await
+        .context("Failed to query changed rows")
+}
+
+/// How a fetched batch ends.
+#[derive(Debug, PartialEq)]
+enum BatchEnd {
+    /// Rows safe to publish, and whether the query returned a full batch.
+    Complete(Vec<ChangeRow>, bool),
+    /// A full batch where every row has this `updated_at` value.
+    AllTied(Value),
+}
+
+/// Drops trailing rows that share the last `updated_at` value of a full
+/// batch: more rows with that value may be beyond the limit, and the next
+/// query (`> watermark`) would skip them.
+fn settle_batch(mut rows: Vec<ChangeRow>, limit: usize) -> BatchEnd {
+    if rows.len() < limit {
+        return BatchEnd::Complete(rows, false);
+    }
+    let Some(last) = rows.last().map(|r| r.cursor.clone()) else {
+        return BatchEnd::Complete(rows, false);
+    };
+    let tied = rows.iter().rev().take_while(|r| r.cursor == last).count();
+    if tied == rows.len() {
+        return BatchEnd::AllTied(last);
+    }
+    rows.truncate(rows.len() - tied);
+    BatchEnd::Complete(rows, true)
+}
+
+/// Converts a watermark back into a query parameter of type `ty`.
+fn bind_cursor(ty: &Type, cursor: &Value) -> Result<Box<dyn ToSql + Sync + Send>> {
+    let text = || {
+        cursor
+            .as_str()
+            .with_context(|| format!("watermark {} is not a string", cursor))
+    };
+    let int = || {
+        cursor
+            .as_i64()
+            .with_context(|| format!("watermark {} is not an integer", cursor))
+    };
+    Ok(match *ty {
+        Type::TIMESTAMPTZ => Box::new(DateTime::parse_from_rfc3339(text()?)?.with_timezone(&Utc)),
+        Type::TIMESTAMP => Box::new(DateTime::parse_from_rfc3339(text()?)?.naive_utc()),
+        Type::DATE => Box::new(NaiveDate::parse_from_str(text()?, "%Y-%m-%d")?),
+        Type::INT2 => Box::new(i16::try_from(int()?)?),
+        Type::INT4 => Box::new(i32::try_from(int()?)?),
+        Type::INT8 => Box::new(int()?),
+        _ => anyhow::bail!(
+            "unsupported updated_at column type '{}' (use a timestamp, date or integer column)",
+            ty
+        ),
+    })
+}
+
+/// Quotes an identifier (`we"ird` → `"we""ird"`).
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a table name, keeping an optional schema prefix
+/// (`public.orders` → `"public"."orders"`).
+fn quote_table(table: &str) -> String {
+    table
+        .split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// SQLSTATE of the first Postgres error in `e`'s chain, e.g. `"28P01"`.
+fn sqlstate(e: &anyhow::Error) -> Option<String> {
+    e.chain()
+        .find_map(|cause| cause.downcast_ref::<tokio_postgres::Error>())
+        .and_then(|pg| pg.code())
+        .map(|code| code.code().to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Row conversion
+// ---------------------------------------------------------------------------
+
+/// A changed row, converted to JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeRow {
+    /// The row's `updated_at` value.
+    pub cursor: Value,
+    /// Entity key, or `None` if the key column is NULL.
+    pub key: Option<String>,
+    /// Mirrored columns by name.
+    pub properties: Map<String, Value>,
+}
+
+/// Raw binary column value, so any column type can be read and converted by hand.
+struct RawValue<'a>(Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> DecodeResult<Self> {
+        Ok(RawValue(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> DecodeResult<Self> {
+        Ok(RawValue(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+fn convert_row(row: &Row, config: &PostgresSourceConfig) -> Result<ChangeRow> {
+    let columns: Vec<(&str, &Type)> = row
+        .columns()
+        .iter()
+        .map(|c| (c.name(), c.type_()))
+        .collect();
+    let values = (0..row.len())
+        .map(|i| row.try_get::<_, RawValue>(i).map(|v| v.0))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to read row")?;
+    change_row(&columns, &values, config)
+}
+
+/// Builds a [`ChangeRow`] from column names/types and binary values.
+///
+/// Fails if the `updated_at` column is missing, NULL or of a type that is
+/// not mirrored.
+pub fn change_row(
+    columns: &[(&str, &Type)],
+    values: &[Option<&[u8]>],
+    config: &PostgresSourceConfig,
+) -> Result<ChangeRow> {
+    let mut properties = Map::new();
+    let mut key = None;
+    let mut cursor = None;
+    for ((name, ty), raw) in columns.iter().zip(values) {
+        let Some(value) = column_to_json(ty, *raw) else {
+            continue;
+        };
+        if *name == config.key_column && !value.is_null() {
+            key = Some(value_to_string(&value));
+        }
+        if *name == config.updated_at_column && !value.is_null() {
+            cursor = Some(value.clone());
+        }
+        properties.insert(name.to_string(), value);
+    }
+    let cursor = cursor.with_context(|| {
+        format!(
+            "updated_at column '{}' is missing, NULL or of an unsupported type",
+            config.updated_at_column
+        )
+    })?;
+    Ok(ChangeRow {
+        cursor,
+        key,
+        properties,
+    })
+}
+
+/// Converts a binary column value to JSON.
+///
+/// NULL becomes `null`; timestamps become RFC 3339 strings (`timestamp`
+/// without time zone is taken as UTC); numerics become numbers. Returns `None`
+/// for columns that are not mirrored: `bytea`, types with no sensible JSON
+/// form, and values that fail to decode.
+pub fn column_to_json(ty: &Type, raw: Option<&[u8]>) -> Option<Value> {
+    let Some(raw) = raw else {
+        return Some(Value::Null);
+    };
+    match decode_column(ty, raw) {
+        Ok(value) => value,
+        Err(e) => {
+            debug!(column_type = %ty, error = %e, "Skipping undecodable Postgres value");
+            None
+        }
+    }
+}
+
+fn decode_column(ty: &Type, raw: &[u8]) -> DecodeResult<Option<Value>> {
+    let value = match *ty {
+        Type::BOOL => Value::Bool(bool::from_sql(ty, raw)?),
+        Type::INT2 => Value::from(i16::from_sql(ty, raw)?),
+        Type::INT4 => Value::from(i32::from_sql(ty, raw)?),
+        Type::INT8 => Value::from(i64::from_sql(ty, raw)?),
+        Type::OID => Value::from(u32::from_sql(ty, raw)?),
+        // Via the shortest decimal form, so 0.1::real stays 0.1
+        Type::FLOAT4 => float_value(f32::from_sql(ty, raw)?.to_string().parse()?),
+        Type::FLOAT8 => float_value(f64::from_sql(ty, raw)?),
+        Type::NUMERIC => numeric_value(Decimal::from_sql(ty, raw)?),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => {
+            Value::String(String::from_sql(ty, raw)?)
+        }
+        Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+        Type::TIMESTAMPTZ => match infinity(raw) {
+            Some(value) => value,
+            None => Value::from(
+                DateTime::<Utc>::from_sql(ty, raw)?.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ),
+        },
+        Type::TIMESTAMP => match infinity(raw) {
+            Some(value) => value,
+            None => Value::from(
+                NaiveDateTime::from_sql(ty, raw)?
+                    .and_utc()
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ),
+        },
+        Type::DATE => match infinity(raw) {
+            Some(value) => value,
+            None => Value::from(NaiveDate::from_sql(ty, raw)?.to_string()),
+        },
+        Type::TIME => Value::from(NaiveTime::from_sql(ty, raw)?.to_string()),
+        Type::UUID => Value::from(uuid::Uuid::from_sql(ty, raw)?.to_string()),
+        Type::BYTEA => return Ok(None),
+        _ => match ty.kind() {
+            Kind::Enum(_) => Value::String(String::from_sql(ty, raw)?),
+            Kind::Domain(base) => return decode_column(base, raw),
+            Kind::Array(member) => {
+                let elements = Vec::<RawValue>::from_sql(ty, raw)?;
+                let values: Option<Vec<Value>> = elements
+                    .into_iter()
+                    .map(|element| column_to_json(member, element.0))
+                    .collect();
+                match values {
+                    Some(values) => Value::Array(values),
+                    None => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        },
+    };
+    Ok(Some(value))
+}
+
+/// `"infinity"` / `"-infinity"` for the special timestamp and date values,
+/// which chrono cannot represent.
+fn infinity(raw: &[u8]) -> Option<Value> {
+    let (is_max, is_min) = match raw.len() {
+        4 => {
+            let v = i32::from_be_bytes(raw.try_into().ok()?);
+            (v == i32::MAX, v == i32::MIN)
+        }
+        8 => {
+            let v = i64::from_be_bytes(raw.try_into().ok()?);
+            (v == i64::MAX, v == i64::MIN)
+        }
+        _ => return None,
+    };
+    match (is_max, is_min) {
+        (true, _) => Some(Value::from("infinity")),
+        (_, true) => Some(Value::from("-infinity")),
+        _ => None,
+    }
+}
+
+/// NaN and infinities have no JSON number form and become `null`.
+fn float_value(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+/// Whole numerics stay exact integers; others become JSON numbers, or
+/// strings if they do not fit in an f64.
+fn numeric_value(d: Decimal) -> Value {
+    if d.fract().is_zero() {
+        if let Some(i) = d.to_i64() {
+            return Value::from(i);
+        }
+    }
+    d.to_f64()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(d.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use chrono::TimeZone;
+    use std::str::FromStr;
+
+    fn make_config() -> PostgresSourceConfig {
+        PostgresSourceConfig {
+            id: "pg-001".to_string(),
+            name: "Orders".to_string(),
+            connection_string: "host=127.0.0.1 port=1 user=flux connect_timeout=2".to_string(),
+            table: "public.orders".to_string(),
+            key_column: "id".to_string(),
+            updated_at_column: "updated_at".to_string(),
+            namespace: "shop".to_string(),
+            batch_size: 3,
+            poll_interval_secs: 30,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Binary encoding of `value` as Postgres type `ty`.
+    fn encode<T: ToSql>(value: T, ty: &Type) -> Vec<u8> {
+        let mut out = BytesMut::new();
+        value.to_sql(ty, &mut out).unwrap();
+        out.to_vec()
+    }
+
+    fn convert<T: ToSql>(value: T, ty: &Type) -> Option<Value> {
+        column_to_json(ty, Some(&encode(value, ty)))
+    }
+
+    #[test]
+    fn test_scalar_types_to_json() {
+        assert_eq!(convert(true, &Type::BOOL), Some(Value::Bool(true)));
+        assert_eq!(convert(7i16, &Type::INT2), Some(Value::from(7)));
+        assert_eq!(convert(-70_000i32, &Type::INT4), Some(Value::from(-70_000)));
+        assert_eq!(
+            convert(9_007_199_254_740_993i64, &Type::INT8),
+            Some(Value::from(9_007_199_254_740_993i64))
+        );
+        assert_eq!(convert(0.1f32, &Type::FLOAT4), Some(Value::from(0.1)));
+        assert_eq!(convert(2.5f64, &Type::FLOAT8), Some(Value::from(2.5)));
+        assert_eq!(convert(f64::NAN, &Type::FLOAT8), Some(Value::Null));
+        assert_eq!(convert("hello", &Type::TEXT), Some(Value::from("hello")));
+        assert_eq!(convert("v", &Type::VARCHAR), Some(Value::from("v")));
+        assert_eq!(
+            convert(serde_json::json!({"a": [1, 2]}), &Type::JSONB),
+            Some(serde_json::json!({"a": [1, 2]}))
+        );
+        let id = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        assert_eq!(
+            convert(id, &Type::UUID),
+            Some(Value::from("67e55044-10b1-426f-9247-bb680e5fe0c8"))
+        );
+    }
+
+    #[test]
+    fn test_numeric_to_json() {
+        let numeric = |s: &str| convert(Decimal::from_str(s).unwrap(), &Type::NUMERIC);
+        assert_eq!(numeric("42"), Some(Value::from(42)));
+        assert_eq!(numeric("-5.00"), Some(Value::from(-5)));
+        assert_eq!(numeric("12.50"), Some(Value::from(12.5)));
+        assert_eq!(numeric("0.000001"), Some(Value::from(0.000001)));
+    }
+
+    #[test]
+    fn test_time_types_to_rfc3339() {
+        let instant = Utc
+            .with_ymd_and_hms(2026, 3, 1, 12, 0, 0)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::microseconds(123_456))
+            .unwrap();
+        assert_eq!(
+            convert(instant, &Type::TIMESTAMPTZ),
+            Some(Value::from("2026-03-01T12:00:00.123456Z"))
+        );
+        assert_eq!(
+            convert(instant.naive_utc(), &Type::TIMESTAMP),
+            Some(Value::from("2026-03-01T12:00:00.123456Z"))
+        );
+        assert_eq!(
+            convert(instant.date_naive(), &Type::DATE),
+            Some(Value::from("2026-03-01"))
+        );
+        assert_eq!(
+            convert(instant.time(), &Type::TIME),
+            Some(Value::from("12:00:00.123456"))
+        );
+
+        // Special values chrono cannot represent
+        assert_eq!(
+            column_to_json(&Type::TIMESTAMPTZ, Some(&i64::MAX.to_be_bytes())),
+            Some(Value::from("infinity"))
+        );
+        assert_eq!(
+            column_to_json(&Type::DATE, Some(&i32::MIN.to_be_bytes())),
+            Some(Value::from("-infinity"))
+        );
+    }
+
+    #[test]
+    fn test_nulls_arrays_and_skipped_types() {
+        assert_eq!(column_to_json(&Type::INT4, None), Some(Value::Null));
+        assert_eq!(column_to_json(&Type::BYTEA, None), Some(Value::Null));
+        assert_eq!(convert(vec![1u8, 2, 3], &Type::BYTEA), None);
+        assert_eq!(
+            convert(vec!["a", "b"], &Type::TEXT_ARRAY),
+            Some(serde_json::json!(["a", "b"]))
+        );
+        assert_eq!(
+            convert(vec![Some(1i32), None], &Type::INT4_ARRAY),
+            Some(serde_json::json!([1, null]))
+        );
+        assert_eq!(convert(vec![vec![1u8]], &Type::BYTEA_ARRAY), None);
+        // No JSON form
+        assert_eq!(column_to_json(&Type::INTERVAL, Some(&[0; 16])), None);
+        // Corrupt value
+        assert_eq!(column_to_json(&Type::INT8, Some(&[1, 2])), None);
+
+        let status = Type::new(
+            "order_status".to_string(),
+            90_001,
+            Kind::Enum(vec!["open".to_string(), "closed".to_string()]),
+            "public".to_string(),
+        );
+        assert_eq!(
+            column_to_json(&status, Some(b"open")),
+            Some(Value::from("open"))
+        );
+    }
+
+    #[test]
+    fn test_change_row() {
+        let config = make_config();
+        let updated = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let id = encode(42i64, &Type::INT8);
+        let status = encode("shipped", &Type::TEXT);
+        let blob = encode(vec![0u8; 4], &Type::BYTEA);
+        let stamp = encode(updated, &Type::TIMESTAMPTZ);
+        let columns = [
+            ("id", &Type::INT8),
+            ("status", &Type::TEXT),
+            ("attachment", &Type::BYTEA),
+            ("note", &Type::TEXT),
+            ("updated_at", &Type::TIMESTAMPTZ),
+        ];
+
+        let row = change_row(
+            &columns,
+            &[
+                Some(&id[..]),
+                Some(&status[..]),
+                Some(&blob[..]),
+                None,
+                Some(&stamp[..]),
+            ],
+            &config,
+        )
+        .unwrap();
+        assert_eq!(row.key.as_deref(), Some("42"));
+        assert_eq!(row.cursor, Value::from("2026-03-01T12:00:00Z"));
+        assert_eq!(
+            Value::Object(row.properties),
+            serde_json::json!({
+                "id": 42,
+                "status": "shipped",
+                "note": null,
+                "updated_at": "2026-03-01T12:00:00Z"
+            })
+        );
+
+        // NULL key: kept so the watermark can move past it, but not publishable
+        let row = change_row(
+            &columns,
+            &[None, Some(&status[..]), None, None, Some(&stamp[..])],
+            &config,
+        )
+        .unwrap();
+        assert_eq!(row.key, None);
+
+        // The updated_at column is required
+        let err =
+            change_row(&columns, &[Some(&id[..]), None, None, None, None], &config).unwrap_err();
+        assert!(err.to_string().contains("updated_at column 'updated_at'"));
+    }
+
+    fn cursor_row(key: &str, cursor: i64) -> ChangeRow {
+        ChangeRow {
+            cursor: Value::from(cursor),
+            key: Some(key.to_string()),
+            properties: Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_settle_batch() {
+        // Short batch: nothing more to fetch
+        let rows = vec![cursor_row("a", 1), cursor_row("b", 1)];
+        assert_eq!(
+            settle_batch(rows.clone(), 3),
+            BatchEnd::Complete(rows, false)
+        );
+
+        // Full batch ending in ties: the tied rows wait for the next query
+        let rows = vec![cursor_row("a", 1), cursor_row("b", 2), cursor_row("c", 2)];
+        assert_eq!(
+            settle_batch(rows, 3),
+            BatchEnd::Complete(vec![cursor_row("a", 1)], true)
+        );
+
+        // Full batch without a visible tie: the last row may still have one
+        // beyond the limit
+        let rows = vec![cursor_row("a", 1), cursor_row("b", 2), cursor_row("c", 3)];
+        assert_eq!(
+            settle_batch(rows, 3),
+            BatchEnd::Complete(vec![cursor_row("a", 1), cursor_row("b", 2)], true)
+        );
+
+        // Every row tied: fetch them all
+        let rows = vec![cursor_row("a", 5), cursor_row("b", 5), cursor_row("c", 5)];
+        assert_eq!(settle_batch(rows, 3), BatchEnd::AllTied(Value::from(5)));
+    }
+
+    #[test]
+    fn test_bind_cursor_round_trips_watermark() {
+        let instant = Utc
+            .with_ymd_and_hms(2026, 3, 1, 12, 0, 0)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::microseconds(7))
+            .unwrap();
+
+        let cases: Vec<(Type, Vec<u8>)> = vec![
+            (Type::TIMESTAMPTZ, encode(instant, &Type::TIMESTAMPTZ)),
+            (
+                Type::TIMESTAMP,
+                encode(instant.naive_utc(), &Type::TIMESTAMP),
+            ),
+            (Type::DATE, encode(instant.date_naive(), &Type::DATE)),
+            (Type::INT4, encode(1_700_000_000i32, &Type::INT4)),
+            (Type::INT8, encode(i64::MAX - 1, &Type::INT8)),
+        ];
+        for (ty, raw) in cases {
+            let watermark = column_to_json(&ty, Some(&raw)).unwrap();
+            let param = bind_cursor(&ty, &watermark).unwrap();
+            let mut out = BytesMut::new();
+            param.to_sql_checked(&ty, &mut out).unwrap();
+            assert_eq!(out.to_vec(), raw, "{} watermark {}", ty, watermark);
+        }
+
+        assert!(bind_cursor(&Type::TEXT, &Value::from("x")).is_err());
+        assert!(bind_cursor(&Type::INT2, &Value::from(1_000_000)).is_err());
+        assert!(bind_cursor(&Type::TIMESTAMPTZ, &Value::from(5)).is_err());
+    }
+
+    #[test]
+    fn test_quote_table() {
+        assert_eq!(quote_table("orders"), "\"orders\"");
+        assert_eq!(quote_table("public.orders"), "\"public\".\"orders\"");
+        assert_eq!(quote_ident("we\"ird"), "\"we\"\"ird\"");
+    }
+
+    fn poller(config: PostgresSourceConfig) -> PostgresPoller {
+        let store = Arc::new(PostgresConfigStore::new(":memory:").unwrap());
+        store.insert(&config).unwrap();
+        let status_map: StatusMap = Arc::new(Mutex::new(HashMap::new()));
+        status_map.lock().unwrap().insert(
+            config.id.clone(),
+            PostgresStatus {
+                source_id: config.id.clone(),
+                last_poll: None,
+                last_error: None,
+                last_sqlstate: None,
+                rows_published: 0,
+                rows_failed: 0,
+                watermark: None,
+            },
+        );
+        PostgresPoller::new(
+            config,
+            Some("secret".to_string()),
+            "http://localhost:3000".to_string(),
+            Arc::new(PublishLimiter::unlimited()),
+            store,
+            status_map,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_build_event() {
+        let poller = poller(make_config());
+        let mut properties = Map::new();
+        properties.insert("total".to_string(), Value::from(99.5));
+
+        let event = poller.build_event("42", &properties);
+        assert_eq!(event["stream"], "postgres");
+        assert_eq!(event["source"], "postgres.pg-001");
+        assert_eq!(event["key"], "public.orders/42");
+        assert_eq!(event["payload"]["entity_id"], "shop/public.orders/42");
+        assert_eq!(event["payload"]["properties"]["total"], 99.5);
+    }
+
+    #[test]
+    fn test_build_event_applies_transforms() {
+        let mut config = make_config();
+        config.transforms = serde_json::from_value(serde_json::json!([
+            {"type": "redact", "path": "email"},
+            {"type": "add_static", "path": "site", "value": "eu-1"}
+        ]))
+        .unwrap();
+        let poller = poller(config);
+        let mut properties = Map::new();
+        properties.insert("email".to_string(), Value::from("a@example.com"));
+        properties.insert("total".to_string(), Value::from(99.5));
+
+        let event = poller.build_event("42", &properties);
+        let published = event["payload"]["properties"].as_object().unwrap();
+        assert!(!published.contains_key("email"));
+        assert_eq!(published["site"], "eu-1");
+        assert_eq!(published["total"], 99.5);
+    }
+
+    #[tokio::test]
+    async fn test_connection_failure_marks_status_and_keeps_polling() {
+        let mut poller = poller(make_config());
+
+        for _ in 0..2 {
+            let err = poller.poll().await.unwrap_err();
+            assert!(format!("{:#}", err).contains("Failed to connect to Postgres"));
+            assert!(poller.client.is_none());
+        }
+
+        let status = poller.status_map.lock().unwrap()["pg-001"].clone();
+        assert!(status.last_poll.is_some());
+        assert!(status
+            .last_error
+            .unwrap()
+            .contains("Failed to connect to Postgres"));
+        // Refused connections never reach Postgres, so there is no SQLSTATE
+        assert_eq!(status.last_sqlstate, None);
+        assert_eq!(poller.store.watermark("pg-001").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_connection_string_is_reported() {
+        let mut config = make_config();
+        config.connection_string = "host=db port=notaport".to_string();
+        let mut poller = poller(config);
+        let err = poller.poll().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid Postgres connection string"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Batch publishing shared by runners that emit many events per poll.
+use crate::runners::rate_limit::PublishLimiter;
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use serde_json::Value;
+use std::io::Write;
+
+/// Batch bodies larger than this are sent gzip-compressed
+const GZIP_THRESHOLD_BYTES: usize = 8 * 1024;
+
+/// Publishes `events` through `POST /api/events/batch`, once `limiter` allows
+/// that many events.
+///
+/// Bodies over 8 KB are gzipped. Returns each event's error (`None` if
+/// accepted), in the same order as `events`. Fails if Flux cannot be reached
+/// or rejects the batch as a whole.
+pub(crate) async fn publish_batch(
+    http_client: &reqwest::Client,
+    limiter: &PublishLimiter,
+    flux_api_url: &str,
+    token: Option<&str>,
+    events: &[Value],
+) -> Result<Vec<Option<String>>> {
+    limiter.acquire(events.len() as u64).await;
+    let body = serde_json::to_vec(&serde_json::json!({ "events": events }))
+        .context("Failed to serialize batch")?;
+    let mut request = http_client
+        .post(format!("{}/api/events/batch", flux_api_url))
+        .header(CONTENT_TYPE, "application/json");
+    request = if body.len() > GZIP_THRESHOLD_BYTES {
+        request.header(CONTENT_ENCODING, "gzip").body(gzip(&body)?)
+    } else {
+        request.body(body)
+    };
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .context("Failed to send HTTP request to Flux API")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "<failed to read body>".to_string());
+        anyhow::bail!("Flux API returned error status {}: {}", status, body);
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .context("Invalid batch response from Flux API")?;
+    let results = body["results"]
+        .as_array()
+        .context("Batch response has no results")?;
+    if results.len() != events.len() {
+        anyhow::bail!(
+            "Batch response has {} results for {} events",
+            results.len(),
+            events.len()
+        );
+    }
+    Ok(results
+        .iter()
+        .map(|r| r["error"].as_str().map(String::from))
+        .collect())
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .context("Failed to compress batch")?;
+    encoder.finish().context("Failed to compress batch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    fn events(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|i| {
+                serde_json::json!({
+                    "stream": "files",
+                    "source": "file.file-001",
+                    "payload": {"entity_id": format!("warehouse/item-{}", i)}
+                })
+            })
+            .collect()
+    }
+
+    fn accepted(count: usize) -> String {
+        let results: Vec<Value> = (0..count)
+            .map(|_| serde_json::json!({"eventId": "e", "stream": "files", "error": null}))
+            .collect();
+        serde_json::json!({ "results": results }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_large_batches_are_gzipped() {
+        let mut server = mockito::Server::new_async().await;
+        let small = server
+            .mock("POST", "/api/events/batch")
+            .match_header("content-encoding", Matcher::Missing)
+            .with_body(accepted(2))
+            .expect(1)
+            .create_async()
+            .await;
+        let large = server
+            .mock("POST", "/api/events/batch")
+            .match_header("content-encoding", "gzip")
+            .with_body(accepted(500))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = crate::http::client().unwrap();
+        let limiter = PublishLimiter::unlimited();
+        let results = publish_batch(&client, &limiter, &server.url(), None, &events(2))
+            .await
+            .unwrap();
+        assert_eq!(results, vec![None, None]);
+        let results = publish_batch(&client, &limiter, &server.url(), None, &events(500))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 500);
+
+        small.assert_async().await;
+        large.assert_async().await;
+    }
+}
@@ -0,0 +1,303 @@
+//! Outbound event rate limits.
+//!
+//! [`PublishLimiter`] is a token bucket shared by every runner. It paces
+//! publishing rather than dropping events: a caller that finds the bucket
+//! empty reserves its tokens anyway and sleeps until they are refilled.
+//! [`RunCap`] bounds the events a single run of one source may publish; the
+//! excess is dropped.
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Events one run of a source may publish unless the source sets its own cap.
+pub const DEFAULT_MAX_EVENTS_PER_RUN: u64 = 50_000;
+
+/// Time source of a [`PublishLimiter`], replaceable in tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+}
+
+/// Wall clock backed by `tokio::time::sleep`.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Current state of the global publish limiter.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "events_per_second": 500,
+    "utilization": 0.42,
+    "queued_events": 0,
+    "paced_events": 120000
+}))]
+pub struct LimiterStatus {
+    /// Configured limit; null when publishing is unlimited
+    pub events_per_second: Option<u32>,
+    /// Share of the one-second burst currently used, 0–1
+    pub utilization: f64,
+    /// Events reserved beyond the available tokens, waiting to be sent
+    pub queued_events: u64,
+    /// Events that had to wait for tokens since startup
+    pub paced_events: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket limiting the events per second published by all runners.
+///
+/// The bucket holds one second's worth of events, so bursts up to the limit
+/// go out immediately.
+pub struct PublishLimiter {
+    events_per_second: Option<u32>,
+    clock: Arc<dyn Clock>,
+    bucket: Mutex<Bucket>,
+    paced_events: AtomicU64,
+}
+
+impl PublishLimiter {
+    /// Limiter that never waits.
+    pub fn unlimited() -> Self {
+        Self::build(None, Arc::new(SystemClock))
+    }
+
+    /// Limiter allowing `events_per_second` events per second (0 = unlimited).
+    pub fn new(events_per_second: u32) -> Self {
+        Self::with_clock(events_per_second, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(events_per_second: u32, clock: Arc<dyn Clock>) -> Self {
+        Self::build(Some(events_per_second).filter(|&n| n > 0), clock)
+    }
+
+    fn build(events_per_second: Option<u32>, clock: Arc<dyn Clock>) -> Self {
+        let bucket = Bucket {
+            tokens: events_per_second.unwrap_or(0) as f64,
+            updated: clock.now(),
+        };
+        Self {
+            events_per_second,
+            clock,
+            bucket: Mutex::new(bucket),
+            paced_events: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits until `events` more events may be published.
+    pub async fn acquire(&self, events: u64) {
+        let Some(rate) = self.events_per_second.map(f64::from) else {
+            return;
+        };
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            self.refill(&mut bucket, rate);
+            bucket.tokens -= events as f64;
+            if bucket.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-bucket.tokens / rate)
+            }
+        };
+        if !wait.is_zero() {
+            self.paced_events.fetch_add(events, Ordering::Relaxed);
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    pub fn status(&self) -> LimiterStatus {
+        let paced_events = self.paced_events.load(Ordering::Relaxed);
+        let Some(rate) = self.events_per_second else {
+            return LimiterStatus {
+                events_per_second: None,
+                utilization: 0.0,
+                queued_events: 0,
+                paced_events,
+            };
+        };
+        let mut bucket = self.bucket.lock().unwrap();
+        self.refill(&mut bucket, rate as f64);
+        let burst = rate as f64;
+        LimiterStatus {
+            events_per_second: Some(rate),
+            utilization: ((burst - bucket.tokens) / burst).clamp(0.0, 1.0),
+            queued_events: (-bucket.tokens).max(0.0).ceil() as u64,
+            paced_events,
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket, rate: f64) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.updated = now;
+    }
+}
+
+/// Counts the events of one source run against its cap.
+#[derive(Debug)]
+pub struct RunCap {
+    max_events: u64,
+    admitted: u64,
+    dropped: u64,
+}
+
+impl RunCap {
+    pub fn new(max_events: u64) -> Self {
+        Self {
+            max_events,
+            admitted: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Whether the next event may be published; counts it as dropped if not.
+    pub fn admit(&mut self) -> bool {
+        if self.admitted < self.max_events {
+            self.admitted += 1;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+
+    /// Admits the first events of `events`, dropping the rest.
+    pub fn truncate<T>(&mut self, events: &mut Vec<T>) {
+        let room = self.max_events.saturating_sub(self.admitted);
+        let keep = events
+            .len()
+            .min(usize::try_from(room).unwrap_or(usize::MAX));
+        self.admitted += keep as u64;
+        self.dropped += (events.len() - keep) as u64;
+        events.truncate(keep);
+    }
+
+    /// Events dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock that only moves when slept on or advanced.
+    struct ManualClock {
+        now: Mutex<Instant>,
+    }
+
+    impl ManualClock {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                now: Mutex::new(Instant::now()),
+            })
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+            self.advance(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limiter_paces_beyond_burst() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let limiter = PublishLimiter::with_clock(10, clock.clone());
+
+        // A full second's worth goes out at once
+        limiter.acquire(10).await;
+        assert_eq!(clock.now(), start);
+        assert_eq!(limiter.status().utilization, 1.0);
+
+        // Then each event waits for its token
+        limiter.acquire(5).await;
+        assert_eq!(clock.now() - start, Duration::from_millis(500));
+        limiter.acquire(1).await;
+        assert_eq!(clock.now() - start, Duration::from_millis(600));
+
+        let status = limiter.status();
+        assert_eq!(status.events_per_second, Some(10));
+        assert_eq!(status.paced_events, 6);
+        assert_eq!(status.queued_events, 0);
+
+        // Idle time refills the bucket, up to one second's worth
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(limiter.status().utilization, 0.0);
+        limiter.acquire(10).await;
+        assert_eq!(clock.now() - start, Duration::from_millis(5600));
+    }
+
+    #[tokio::test]
+    async fn test_limiter_reports_queued_reservations() {
+        let clock = ManualClock::new();
+        let limiter = PublishLimiter::with_clock(4, clock.clone());
+
+        // Reserve without sleeping, as a caller still waiting would have
+        {
+            let mut bucket = limiter.bucket.lock().unwrap();
+            bucket.tokens -= 10.0;
+        }
+        let status = limiter.status();
+        assert_eq!(status.queued_events, 6);
+        assert_eq!(status.utilization, 1.0);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(limiter.status().queued_events, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_limiter_never_waits() {
+        for limiter in [PublishLimiter::unlimited(), PublishLimiter::new(0)] {
+            limiter.acquire(1_000_000).await;
+            let status = limiter.status();
+            assert_eq!(status.events_per_second, None);
+            assert_eq!(status.paced_events, 0);
+        }
+    }
+
+    #[test]
+    fn test_run_cap() {
+        let mut cap = RunCap::new(2);
+        assert!(cap.admit());
+        assert!(cap.admit());
+        assert!(!cap.admit());
+        assert_eq!(cap.dropped(), 1);
+
+        let mut cap = RunCap::new(3);
+        let mut events = vec![1, 2, 3, 4, 5];
+        cap.truncate(&mut events);
+        assert_eq!(events, [1, 2, 3]);
+        assert_eq!(cap.dropped(), 2);
+
+        let mut events = vec![1, 2];
+        RunCap::new(5).truncate(&mut events);
+        assert_eq!(events, [1, 2]);
+    }
+}
@@ -0,0 +1,354 @@
+//! Retry policy and circuit breaker for generic and named source loops.
+//!
+//! A failing source waits an exponentially growing, jittered delay before its
+//! next attempt. After `max_consecutive_failures` failures in a row the
+//! circuit opens: the source is not retried until `circuit_cooldown_secs`
+//! have passed or a retry is requested through the API. The attempt after
+//! that is a trial; if it fails too, the circuit opens again at once.
+//!
+//! [`CircuitBreaker`] is handed the time and the jitter sample by its caller,
+//! so tests drive it without a clock or randomness.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+/// Longest `backoff_max_secs` a policy may set (one day).
+const MAX_BACKOFF_SECS: u64 = 24 * 60 * 60;
+
+/// Longest `circuit_cooldown_secs` a policy may set (30 days).
+const MAX_CIRCUIT_COOLDOWN_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// How a source is retried after failures, and when its circuit opens.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+#[schema(example = json!({
+    "max_consecutive_failures": 10,
+    "backoff_base_secs": 5,
+    "backoff_max_secs": 900,
+    "jitter": 0.2,
+    "circuit_cooldown_secs": 3600
+}))]
+pub struct RetryPolicy {
+    /// Failures in a row that open the circuit (0 = never open).
+    pub max_consecutive_failures: u32,
+    /// Delay after the first failure; doubles with each further one.
+    pub backoff_base_secs: u64,
+    /// Longest delay between two attempts.
+    pub backoff_max_secs: u64,
+    /// Fraction of each delay randomly added or taken off (0.0–1.0).
+    pub jitter: f64,
+    /// How long an open circuit waits before a trial attempt.
+    pub circuit_cooldown_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_consecutive_failures: 10,
+            backoff_base_secs: 5,
+            backoff_max_secs: 15 * 60,
+            jitter: 0.2,
+            circuit_cooldown_secs: 60 * 60,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.backoff_base_secs == 0 {
+            anyhow::bail!("retry_policy.backoff_base_secs must be at least 1");
+        }
+        if self.backoff_max_secs < self.backoff_base_secs {
+            anyhow::bail!("retry_policy.backoff_max_secs must be at least backoff_base_secs");
+        }
+        if self.backoff_max_secs > MAX_BACKOFF_SECS {
+            anyhow::bail!(
+                "retry_policy.backoff_max_secs must be at most {}",
+                MAX_BACKOFF_SECS
+            );
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            anyhow::bail!("retry_policy.jitter must be between 0 and 1");
+        }
+        if self.circuit_cooldown_secs == 0 {
+            anyhow::bail!("retry_policy.circuit_cooldown_secs must be at least 1");
+        }
+        if self.circuit_cooldown_secs > MAX_CIRCUIT_COOLDOWN_SECS {
+            anyhow::bail!(
+                "retry_policy.circuit_cooldown_secs must be at most {}",
+                MAX_CIRCUIT_COOLDOWN_SECS
+            );
+        }
+        Ok(())
+    }
+
+    /// Delay after `failures` failures in a row, before jitter: the base,
+    /// then doubling, capped at `backoff_max_secs`.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(32);
+        let secs = self
+            .backoff_base_secs
+            .saturating_mul(1 << doublings)
+            .min(self.backoff_max_secs);
+        seconds(secs)
+    }
+
+    /// `delay` moved by `sample` (-1.0..=1.0) times the jitter fraction.
+    fn jittered(&self, delay: Duration, sample: f64) -> Duration {
+        let millis = delay.num_milliseconds() as f64;
+        let offset = millis * self.jitter * sample.clamp(-1.0, 1.0);
+        Duration::milliseconds((millis + offset).round() as i64)
+    }
+}
+
+/// Failure count and circuit state of one source.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    policy: RetryPolicy,
+    consecutive_failures: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// When the open circuit allows a trial attempt; `None` while closed.
+    pub fn open_until(&self) -> Option<DateTime<Utc>> {
+        self.open_until
+    }
+
+    /// Resets the failure count and closes the circuit.
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    /// Counts a failed attempt at `now` and returns how long to wait before
+    /// the next one. `jitter` is a sample in -1.0..=1.0 (see [`jitter_sample`]).
+    ///
+    /// Opens the circuit, for `circuit_cooldown_secs`, once the failures
+    /// reach `max_consecutive_failures`.
+    pub fn record_failure(&mut self, now: DateTime<Utc>, jitter: f64) -> Duration {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let max = self.policy.max_consecutive_failures;
+        if max > 0 && self.consecutive_failures >= max {
+            let cooldown = seconds(self.policy.circuit_cooldown_secs);
+            self.open_until = Some(
+                now.checked_add_signed(cooldown)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            );
+            return cooldown;
+        }
+        self.policy
+            .jittered(self.policy.backoff(self.consecutive_failures), jitter)
+    }
+
+    /// Lets the next attempt through as a trial, after the cool-down or a
+    /// requested retry. The failure count is kept, so a failed trial opens
+    /// the circuit again.
+    pub fn trial(&mut self) {
+        self.open_until = None;
+    }
+}
+
+/// `secs` as a duration, saturating at the longest one chrono can hold.
+fn seconds(secs: u64) -> Duration {
+    i64::try_from(secs)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .unwrap_or(Duration::MAX)
+}
+
+/// Random jitter sample for [`CircuitBreaker::record_failure`].
+pub fn jitter_sample() -> f64 {
+    rand::thread_rng().gen_range(-1.0..=1.0)
+}
+
+/// Sleeps for `delay`, or until a retry is requested on `retry`.
+///
+/// Returns true if the wait was cut short by a retry request.
+pub async fn wait_or_retry(delay: Duration, retry: &Notify) -> bool {
+    let delay = delay.to_std().unwrap_or_default();
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => false,
+        _ = retry.notified() => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn policy(max_consecutive_failures: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_consecutive_failures,
+            backoff_base_secs: 5,
+            backoff_max_secs: 60,
+            jitter: 0.5,
+            circuit_cooldown_secs: 600,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let secs: Vec<i64> = (1..=6)
+            .map(|n| policy(0).backoff(n).num_seconds())
+            .collect();
+        assert_eq!(secs, [5, 10, 20, 40, 60, 60]);
+        assert_eq!(policy(0).backoff(u32::MAX).num_seconds(), 60);
+
+        let default = RetryPolicy::default();
+        assert_eq!(default.backoff(u32::MAX).num_seconds(), 900);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_fraction() {
+        let mut breaker = CircuitBreaker::new(policy(0));
+        assert_eq!(breaker.record_failure(at(0), 0.0).num_seconds(), 5);
+        // Second failure: 10s ± 50%
+        let mut low = breaker.clone();
+        assert_eq!(low.record_failure(at(0), -1.0).num_seconds(), 5);
+        assert_eq!(breaker.record_failure(at(0), 1.0).num_seconds(), 15);
+        // Out-of-range samples are clamped
+        assert_eq!(breaker.record_failure(at(0), 7.0).num_seconds(), 30);
+    }
+
+    #[test]
+    fn test_circuit_opens_after_max_failures() {
+        let mut breaker = CircuitBreaker::new(policy(3));
+        breaker.record_failure(at(0), 0.0);
+        breaker.record_failure(at(5), 0.0);
+        assert!(breaker.open_until().is_none());
+
+        let wait = breaker.record_failure(at(15), 0.0);
+        assert_eq!(wait.num_seconds(), 600);
+        assert_eq!(breaker.open_until(), Some(at(615)));
+        assert_eq!(breaker.consecutive_failures(), 3);
+    }
+
+    #[test]
+    fn test_failed_trial_reopens_and_success_closes() {
+        let mut breaker = CircuitBreaker::new(policy(2));
+        breaker.record_failure(at(0), 0.0);
+        breaker.record_failure(at(5), 0.0);
+        assert_eq!(breaker.open_until(), Some(at(605)));
+
+        // Cool-down over (or retry requested): one trial, which fails
+        breaker.trial();
+        assert!(breaker.open_until().is_none());
+        let wait = breaker.record_failure(at(605), 0.0);
+        assert_eq!(wait.num_seconds(), 600);
+        assert_eq!(breaker.open_until(), Some(at(1205)));
+
+        // A successful trial closes the circuit and starts over
+        breaker.trial();
+        breaker.record_success();
+        assert_eq!(breaker.consecutive_failures(), 0);
+        assert!(breaker.open_until().is_none());
+        assert_eq!(breaker.record_failure(at(1300), 0.0).num_seconds(), 5);
+        assert!(breaker.open_until().is_none());
+    }
+
+    #[test]
+    fn test_zero_max_never_opens() {
+        let mut breaker = CircuitBreaker::new(policy(0));
+        for n in 0..100 {
+            breaker.record_failure(at(n), 0.0);
+        }
+        assert!(breaker.open_until().is_none());
+        assert_eq!(breaker.consecutive_failures(), 100);
+    }
+
+    #[test]
+    fn test_validate() {
+        RetryPolicy::default().validate().unwrap();
+        let invalid = [
+            RetryPolicy {
+                backoff_base_secs: 0,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                backoff_max_secs: 1,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                jitter: 1.5,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                circuit_cooldown_secs: 0,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                backoff_max_secs: MAX_BACKOFF_SECS + 1,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                backoff_base_secs: u64::MAX,
+                backoff_max_secs: u64::MAX,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                circuit_cooldown_secs: MAX_CIRCUIT_COOLDOWN_SECS + 1,
+                ..RetryPolicy::default()
+            },
+            RetryPolicy {
+                circuit_cooldown_secs: u64::MAX,
+                ..RetryPolicy::default()
+            },
+        ];
+        for policy in invalid {
+            assert!(policy.validate().is_err(), "{:?}", policy);
+        }
+
+        let longest = RetryPolicy {
+            backoff_max_secs: MAX_BACKOFF_SECS,
+            circuit_cooldown_secs: MAX_CIRCUIT_COOLDOWN_SECS,
+            ..RetryPolicy::default()
+        };
+        longest.validate().unwrap();
+    }
+
+    #[test]
+    fn test_unvalidated_huge_policy_does_not_panic() {
+        let huge = RetryPolicy {
+            max_consecutive_failures: 2,
+            backoff_base_secs: u64::MAX,
+            backoff_max_secs: u64::MAX,
+            jitter: 1.0,
+            circuit_cooldown_secs: u64::MAX,
+        };
+        let mut breaker = CircuitBreaker::new(huge);
+        assert!(breaker.record_failure(at(0), 1.0) > Duration::days(365));
+        assert_eq!(breaker.record_failure(at(0), 0.0), Duration::MAX);
+        assert_eq!(breaker.open_until(), Some(DateTime::<Utc>::MAX_UTC));
+    }
+
+    #[tokio::test]
+    async fn test_wait_or_retry() {
+        let retry = Notify::new();
+        assert!(!wait_or_retry(Duration::milliseconds(10), &retry).await);
+
+        retry.notify_one();
+        assert!(wait_or_retry(Duration::hours(1), &retry).await);
+    }
+}
@@ -0,0 +1,211 @@
+//! Singer SCHEMA messages: property type maps and RECORD coercion.
+//!
+//! A stream's JSON Schema is reduced to a flat map of top-level property
+//! name → JSON type (ignoring `"null"`) and optional `format`. Properties
+//! whose type cannot be determined (e.g. `{}`) are left out.
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Type of one top-level property of a Singer stream.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct PropertyType {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// Property name → type, sorted by name.
+pub(crate) type PropertyTypes = BTreeMap<String, PropertyType>;
+
+/// Derives the property type map from a stream's JSON Schema (the `schema`
+/// field of a SCHEMA message).
+pub(crate) fn property_types(schema: &Value) -> PropertyTypes {
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return PropertyTypes::new();
+    };
+    properties
+        .iter()
+        .filter_map(|(name, property)| Some((name.clone(), property_type(property)?)))
+        .collect()
+}
+
+/// First non-null type of `schema`, looking into `anyOf`/`oneOf` branches.
+fn property_type(schema: &Value) -> Option<PropertyType> {
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) if kind != "null" => Some(kind.clone()),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(|k| k.as_str())
+            .find(|k| *k != "null")
+            .map(str::to_string),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        return Some(PropertyType {
+            kind,
+            format: schema
+                .get("format")
+                .and_then(|f| f.as_str())
+                .map(str::to_string),
+        });
+    }
+    ["anyOf", "oneOf"]
+        .iter()
+        .filter_map(|key| schema.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .find_map(property_type)
+}
+
+/// Hex SHA-256 of the type map, used to skip republishing an unchanged schema.
+pub(crate) fn schema_hash(types: &PropertyTypes) -> String {
+    let canonical = serde_json::to_string(types).expect("property types serialize");
+    format!("{:x}", Sha256::digest(canonical.as_bytes()))
+}
+
+/// Converts record values to the type the schema declares where that is
+/// lossless: numeric and boolean strings, integral floats for integers, and
+/// scalars for strings. Values that don't convert, and fields missing from
+/// `types`, are left as they are.
+pub(crate) fn coerce_record(record: &mut Map<String, Value>, types: &PropertyTypes) {
+    for (name, value) in record.iter_mut() {
+        if let Some(coerced) = types.get(name).and_then(|t| coerce_value(value, &t.kind)) {
+            *value = coerced;
+        }
+    }
+}
+
+fn coerce_value(value: &Value, kind: &str) -> Option<Value> {
+    match (kind, value) {
+        ("integer", Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+        ("integer", Value::Number(n)) if !n.is_i64() && !n.is_u64() => {
+            let f = n.as_f64()?;
+            (f.fract() == 0.0 && f.abs() < i64::MAX as f64).then(|| Value::from(f as i64))
+        }
+        ("number", Value::String(s)) => {
+            let s = s.trim();
+            if let Ok(i) = s.parse::<i64>() {
+                return Some(Value::from(i));
+            }
+            let f = s.parse::<f64>().ok().filter(|f| f.is_finite())?;
+            serde_json::Number::from_f64(f).map(Value::Number)
+        }
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema_of(message: &str) -> Value {
+        let message: Value = serde_json::from_str(message).unwrap();
+        message["schema"].clone()
+    }
+
+    fn kind(types: &PropertyTypes, name: &str) -> (String, Option<String>) {
+        let t = &types[name];
+        (t.kind.clone(), t.format.clone())
+    }
+
+    #[test]
+    fn test_property_types_from_nullable_type_arrays() {
+        let types = property_types(&schema_of(include_str!(
+            "../../tests/fixtures/singer/tap-github-issues.json"
+        )));
+
+        assert_eq!(types.len(), 14);
+        assert_eq!(kind(&types, "id"), ("integer".into(), None));
+        assert_eq!(kind(&types, "title"), ("string".into(), None));
+        assert_eq!(kind(&types, "locked"), ("boolean".into(), None));
+        assert_eq!(
+            kind(&types, "created_at"),
+            ("string".into(), Some("date-time".into()))
+        );
+        assert_eq!(kind(&types, "labels"), ("array".into(), None));
+        assert_eq!(kind(&types, "user"), ("object".into(), None));
+    }
+
+    #[test]
+    fn test_property_types_from_any_of() {
+        let types = property_types(&schema_of(include_str!(
+            "../../tests/fixtures/singer/tap-salesforce-account.json"
+        )));
+
+        assert_eq!(kind(&types, "Id"), ("string".into(), None));
+        assert_eq!(kind(&types, "AnnualRevenue"), ("number".into(), None));
+        assert_eq!(kind(&types, "NumberOfEmployees"), ("integer".into(), None));
+        assert_eq!(
+            kind(&types, "SystemModstamp"),
+            ("string".into(), Some("date-time".into()))
+        );
+        // Untyped properties are left out
+        assert!(!types.contains_key("Jigsaw"));
+        assert!(property_types(&json!({"type": "object"})).is_empty());
+    }
+
+    #[test]
+    fn test_schema_hash_tracks_types() {
+        let github = property_types(&schema_of(include_str!(
+            "../../tests/fixtures/singer/tap-github-issues.json"
+        )));
+        let mut changed = github.clone();
+        changed.get_mut("number").unwrap().kind = "string".into();
+
+        assert_eq!(schema_hash(&github), schema_hash(&github.clone()));
+        assert_ne!(schema_hash(&github), schema_hash(&changed));
+        assert_eq!(schema_hash(&github).len(), 64);
+    }
+
+    #[test]
+    fn test_coerce_record() {
+        let lines: Vec<Value> = include_str!("../../tests/fixtures/singer/tap-csv-orders.jsonl")
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let types = property_types(&lines[0]["schema"]);
+
+        let mut record = lines[1]["record"].as_object().unwrap().clone();
+        coerce_record(&mut record, &types);
+        assert_eq!(
+            Value::Object(record),
+            json!({
+                "order_id": 1042,
+                "total": 19.9,
+                "paid": true,
+                "sku": "1234567",
+                "placed_at": "2024-03-01T10:15:00Z",
+                "note": "gift"
+            })
+        );
+
+        // Values that don't parse are passed through unchanged
+        let mut record = lines[2]["record"].as_object().unwrap().clone();
+        let original = record.clone();
+        coerce_record(&mut record, &types);
+        assert_eq!(record, original);
+    }
+
+    #[test]
+    fn test_coerce_value_edge_cases() {
+        assert_eq!(coerce_value(&json!("42"), "number"), Some(json!(42)));
+        assert_eq!(coerce_value(&json!(" 7 "), "integer"), Some(json!(7)));
+        assert_eq!(coerce_value(&json!(3.0), "integer"), Some(json!(3)));
+        assert_eq!(coerce_value(&json!(3.5), "integer"), None);
+        assert_eq!(coerce_value(&json!(3), "integer"), None);
+        assert_eq!(coerce_value(&json!("NaN"), "number"), None);
+        assert_eq!(coerce_value(&json!(false), "string"), Some(json!("false")));
+        assert_eq!(coerce_value(&json!(null), "integer"), None);
+        assert_eq!(coerce_value(&json!({"a": 1}), "string"), None);
+    }
+}
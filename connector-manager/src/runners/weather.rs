@@ -0,0 +1,596 @@
+//! Weather runner — polls Open-Meteo for each location of a weather source.
+//!
+//! Every poll fetches each location's forecast, publishes
+//! `{namespace}/weather/{location}` and, while severe conditions are forecast,
+//! `{namespace}/weather/{location}/alerts`.
+//! When a location's alerts pass, one final event marks them inactive.
+//! A location that fails to fetch is reported in the source's `last_error`
+//! without holding back the others.
+use crate::connectors::weather::api::{Forecast, OpenMeteoClient};
+use crate::connectors::weather::transformer::{
+    alerts_cleared_event, alerts_to_event, forecast_to_event,
+};
+use crate::runners::publish::publish_batch;
+use crate::runners::rate_limit::PublishLimiter;
+use crate::transform;
+use crate::weather_config::{WeatherConfigStore, WeatherLocation, WeatherSourceConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flux::event::BuildError;
+use flux::FluxEvent;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+type StatusMap = Arc<Mutex<HashMap<String, WeatherStatus>>>;
+
+/// Runtime status for a single weather source.
+#[derive(Clone, Debug)]
+pub struct WeatherStatus {
+    pub source_id: String,
+    /// Time the most recent poll started.
+    pub last_poll: Option<DateTime<Utc>>,
+    /// Locations that failed on the last poll, or the publish error.
+    pub last_error: Option<String>,
+    /// Events accepted by Flux.
+    pub events_published: u64,
+    /// Locations with active alerts after the last poll.
+    pub alerting_locations: Vec<String>,
+}
+
+/// Weather connector runner — one poll loop per weather source.
+pub struct WeatherRunner {
+    pub store: Arc<WeatherConfigStore>,
+    pub flux_api_url: String,
+    task_handles: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    status_map: StatusMap,
+    limiter: Arc<PublishLimiter>,
+}
+
+impl WeatherRunner {
+    pub fn new(store: Arc<WeatherConfigStore>, flux_api_url: String) -> Self {
+        Self {
+            store,
+            flux_api_url,
+            task_handles: Mutex::new(HashMap::new()),
+            status_map: Arc::new(Mutex::new(HashMap::new())),
+            limiter: Arc::new(PublishLimiter::unlimited()),
+        }
+    }
+
+    /// Paces publishing with the shared `limiter` instead of an unlimited one.
+    pub fn with_limiter(mut self, limiter: Arc<PublishLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Starts the poll loop for the given source against the public Open-Meteo API.
+    pub async fn start_source(&self, config: &WeatherSourceConfig) -> Result<()> {
+        {
+            let mut map = self.status_map.lock().unwrap();
+            map.entry(config.id.clone())
+                .or_insert_with(|| WeatherStatus {
+                    source_id: config.id.clone(),
+                    last_poll: None,
+                    last_error: None,
+                    events_published: 0,
+                    alerting_locations: Vec::new(),
+                });
+        }
+
+        let poller = WeatherPoller::new(
+            config.clone(),
+            OpenMeteoClient::new(),
+            self.flux_api_url.clone(),
+            Arc::clone(&self.limiter),
+            Arc::clone(&self.status_map),
+        )?;
+        let handle = tokio::spawn(run_poll_loop(poller));
+
+        let mut handles = self.task_handles.lock().unwrap();
+        if let Some(previous) = handles.insert(config.id.clone(), handle) {
+            previous.abort();
+        }
+        info!(source_id = %config.id, locations = config.locations.len(), "Weather source started");
+        Ok(())
+    }
+
+    /// Aborts the poll loop. No-op if not running.
+    pub async fn stop_source(&self, source_id: &str) -> Result<()> {
+        let handle = {
+            let mut handles = self.task_handles.lock().unwrap();
+            handles.remove(source_id)
+        };
+        if let Some(h) = handle {
+            h.abort();
+        }
+        info!(source_id = %source_id, "Weather source stopped");
+        Ok(())
+    }
+
+    /// IDs of weather sources with a running task.
+    pub fn running_ids(&self) -> Vec<String> {
+        self.task_handles.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Returns current status for all weather sources.
+    pub fn status(&self) -> Vec<WeatherStatus> {
+        let map = self.status_map.lock().unwrap();
+        map.values().cloned().collect()
+    }
+}
+
+/// Fetches forecasts for one weather source and publishes them.
+pub struct WeatherPoller {
+    config: WeatherSourceConfig,
+    client: OpenMeteoClient,
+    flux_api_url: String,
+    limiter: Arc<PublishLimiter>,
+    http_client: reqwest::Client,
+    status_map: StatusMap,
+    /// Locations whose alerts entity is currently active.
+    alerting: HashSet<String>,
+}
+
+impl WeatherPoller {
+    fn new(
+        config: WeatherSourceConfig,
+        client: OpenMeteoClient,
+        flux_api_url: String,
+        limiter: Arc<PublishLimiter>,
+        status_map: StatusMap,
+    ) -> Result<Self> {
+        let http_client = crate::http::client()?;
+        Ok(Self {
+            config,
+            client,
+            flux_api_url,
+            limiter,
+            http_client,
+            status_map,
+            alerting: HashSet::new(),
+        })
+    }
+
+    /// Runs one poll and records the result in the source's status.
+    pub async fn poll(&mut self) -> Result<()> {
+        self.update_status(|s| s.last_poll = Some(Utc::now()));
+        let result = self.fetch_and_publish().await;
+        let last_error = match &result {
+            Ok(failures) if failures.is_empty() => None,
+            Ok(failures) => Some(failures.join("; ")),
+            Err(e) => Some(format!("{:#}", e)),
+        };
+        let mut alerting: Vec<String> = self.alerting.iter().cloned().collect();
+        alerting.sort();
+        self.update_status(|s| {
+            s.last_error = last_error;
+            s.alerting_locations = alerting;
+        });
+        result.map(|_| ())
+    }
+
+    /// Returns one message per location that could not be fetched or published.
+    async fn fetch_and_publish(&mut self) -> Result<Vec<String>> {
+        let source = format!("weather.{}", self.config.id);
+        let mut failures = Vec::new();
+        let mut events = Vec::new();
+        // (location, alerting after this poll) for each event-producing location
+        let mut alert_changes = Vec::new();
+
+        for location in &self.config.locations {
+            let forecast = match self
+                .client
+                .fetch_forecast(location.latitude, location.longitude)
+                .await
+            {
+                Ok(forecast) => forecast,
+                Err(e) => {
+                    warn!(source_id = %self.config.id, location = %location.name, error = %format!("{:#}", e), "Weather fetch failed");
+                    failures.push(format!("{}: {:#}", location.name, e));
+                    continue;
+                }
+            };
+
+            let was_alerting = self.alerting.contains(&location.name);
+            match location_events(
+                &self.config.namespace,
+                &source,
+                location,
+                &forecast,
+                was_alerting,
+            ) {
+                Ok((location_events, alerting)) => {
+                    events.extend(
+                        location_events
+                            .into_iter()
+                            .map(|event| (location.name.as_str(), event)),
+                    );
+                    if let Some(active) = alerting {
+                        alert_changes.push((location.name.clone(), active));
+                    }
+                }
+                Err(e) => {
+                    warn!(source_id = %self.config.id, location = %location.name, error = %e, "Invalid weather event");
+                    failures.push(format!("{}: {}", location.name, e));
+                }
+            }
+        }
+
+        if events.is_empty() {
+            return Ok(failures);
+        }
+
+        let mut payloads = events
+            .iter()
+            .map(|(_, event)| serde_json::to_value(event))
+            .collect::<serde_json::Result<Vec<_>>>()
+            .context("Failed to serialize weather events")?;
+        for payload in &mut payloads {
+            transform::apply_to_event(&self.config.transforms, payload);
+        }
+        let results = publish_batch(
+            &self.http_client,
+            &self.limiter,
+            &self.flux_api_url,
+            self.config.flux_namespace_token.as_deref(),
+            &payloads,
+        )
+        .await?;
+
+        let mut published = 0;
+        let mut rejected = HashSet::new();
+        for ((location, _), result) in events.iter().zip(results) {
+            match result {
+                None => published += 1,
+                Some(message) => {
+                    failures.push(format!("{}: {}", location, message));
+                    rejected.insert(location.to_string());
+                }
+            }
+        }
+        // A rejected location keeps its previous alert state so it is retried
+        for (location, active) in alert_changes {
+            if rejected.contains(&location) {
+                continue;
+            }
+            if active {
+                self.alerting.insert(location);
+            } else {
+                self.alerting.remove(&location);
+            }
+        }
+        self.update_status(|s| s.events_published += published);
+        Ok(failures)
+    }
+
+    fn update_status(&self, f: impl FnOnce(&mut WeatherStatus)) {
+        let mut map = self.status_map.lock().unwrap();
+        if let Some(s) = map.get_mut(&self.config.id) {
+            f(s);
+        }
+    }
+}
+
+/// Events for one location's forecast, and its alert state after them if it
+/// changes (`Some(true)` raised, `Some(false)` cleared).
+fn location_events(
+    namespace: &str,
+    source: &str,
+    location: &WeatherLocation,
+    forecast: &Forecast,
+    was_alerting: bool,
+) -> Result<(Vec<FluxEvent>, Option<bool>), BuildError> {
+    let mut events = vec![forecast_to_event(namespace, source, location, forecast)?];
+    let alerting = match alerts_to_event(namespace, source, location, forecast)? {
+        Some(event) => {
+            events.push(event);
+            Some(true)
+        }
+        None if was_alerting => {
+            events.push(alerts_cleared_event(namespace, source, location)?);
+            Some(false)
+        }
+        None => None,
+    };
+    Ok((events, alerting))
+}
+
+/// Long-running loop: poll every `poll_interval_secs`.
+async fn run_poll_loop(mut poller: WeatherPoller) {
+    let period = std::time::Duration::from_secs(poller.config.poll_interval_secs.max(1));
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match poller.poll().await {
+            Ok(()) => debug!(source_id = %poller.config.id, "Weather source polled"),
+            Err(e) => {
+                warn!(source_id = %poller.config.id, error = %format!("{:#}", e), "Weather source poll failed")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flux::api::{create_router, AppState};
+    use flux::namespace::NamespaceRegistry;
+    use flux::nats::{AckFuture, EventPublisher, PublishSink};
+    use mockito::{Matcher, Server};
+    use std::future::Future;
+    use std::pin::Pin;
+
+    fn make_config() -> WeatherSourceConfig {
+        WeatherSourceConfig {
+            id: "wx-001".to_string(),
+            name: "Demo".to_string(),
+            locations: vec![
+                WeatherLocation {
+                    name: "home".to_string(),
+                    latitude: 52.52,
+                    longitude: 13.41,
+                },
+                WeatherLocation {
+                    name: "cabin".to_string(),
+                    latitude: 61.5,
+                    longitude: 8.2,
+                },
+            ],
+            namespace: "matt".to_string(),
+            poll_interval_secs: 900,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    fn weather_poller(server: &Server) -> WeatherPoller {
+        weather_poller_for(server, make_config(), server.url())
+    }
+
+    fn weather_poller_for(
+        server: &Server,
+        config: WeatherSourceConfig,
+        flux_api_url: String,
+    ) -> WeatherPoller {
+        let status_map: StatusMap = Arc::new(Mutex::new(HashMap::new()));
+        status_map.lock().unwrap().insert(
+            config.id.clone(),
+            WeatherStatus {
+                source_id: config.id.clone(),
+                last_poll: None,
+                last_error: None,
+                events_published: 0,
+                alerting_locations: Vec::new(),
+            },
+        );
+        WeatherPoller::new(
+            config,
+            OpenMeteoClient::with_base_url(server.url()),
+            flux_api_url,
+            Arc::new(PublishLimiter::unlimited()),
+            status_map,
+        )
+        .unwrap()
+    }
+
+    fn poller_status(poller: &WeatherPoller) -> WeatherStatus {
+        poller.status_map.lock().unwrap()["wx-001"].clone()
+    }
+
+    fn forecast_body(code: u8) -> String {
+        serde_json::json!({
+            "current": {"time": "2026-03-01T12:00", "temperature_2m": 3.5, "weather_code": code, "wind_gusts_10m": 20.0},
+            "hourly": {
+                "time": ["2026-03-01T12:00", "2026-03-01T13:00"],
+                "temperature_2m": [3.5, 4.0],
+                "precipitation_probability": [10, 20],
+                "weather_code": [code, 3],
+                "wind_gusts_10m": [20.0, 25.0]
+            }
+        })
+        .to_string()
+    }
+
+    fn batch_ok(count: usize) -> String {
+        let results: Vec<_> = (0..count)
+            .map(|_| serde_json::json!({"eventId": "e", "stream": "connectors", "error": null}))
+            .collect();
+        serde_json::json!({"successful": count, "failed": 0, "results": results}).to_string()
+    }
+
+    async fn mock_location(server: &mut Server, latitude: &str, body: String) -> mockito::Mock {
+        server
+            .mock("GET", "/v1/forecast")
+            .match_query(Matcher::UrlEncoded("latitude".into(), latitude.into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_poll_publishes_locations_and_alerts() {
+        let mut server = Server::new_async().await;
+        mock_location(&mut server, "52.52", forecast_body(95)).await;
+        mock_location(&mut server, "61.5", forecast_body(1)).await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "events": [
+                    {"key": "matt/weather/home", "source": "weather.wx-001"},
+                    {"key": "matt/weather/home/alerts", "payload": {"properties": {"active": true}}},
+                    {"key": "matt/weather/cabin", "payload": {"properties": {"conditions": "Mainly clear"}}}
+                ]
+            })))
+            .with_status(200)
+            .with_body(batch_ok(3))
+            .create_async()
+            .await;
+
+        let mut poller = weather_poller(&server);
+        poller.poll().await.unwrap();
+
+        flux.assert_async().await;
+        let status = poller_status(&poller);
+        assert_eq!(status.events_published, 3);
+        assert!(status.last_error.is_none());
+        assert_eq!(status.alerting_locations, vec!["home"]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_clears_alerts_once() {
+        let mut server = Server::new_async().await;
+        mock_location(&mut server, "52.52", forecast_body(1)).await;
+        mock_location(&mut server, "61.5", forecast_body(1)).await;
+        let cleared = server
+            .mock("POST", "/api/events/batch")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "events": [
+                    {"key": "matt/weather/home"},
+                    {"key": "matt/weather/home/alerts", "payload": {"properties": {"active": false}}},
+                    {"key": "matt/weather/cabin"}
+                ]
+            })))
+            .with_status(200)
+            .with_body(batch_ok(3))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut poller = weather_poller(&server);
+        poller.alerting.insert("home".to_string());
+        poller.poll().await.unwrap();
+        cleared.assert_async().await;
+        assert!(poller_status(&poller).alerting_locations.is_empty());
+
+        // Nothing left to clear on the next poll
+        let quiet = server
+            .mock("POST", "/api/events/batch")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "events": [{"key": "matt/weather/home"}, {"key": "matt/weather/cabin"}]
+            })))
+            .with_status(200)
+            .with_body(batch_ok(2))
+            .create_async()
+            .await;
+        poller.poll().await.unwrap();
+        quiet.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_failed_location_does_not_block_others() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/v1/forecast")
+            .match_query(Matcher::UrlEncoded("latitude".into(), "52.52".into()))
+            .with_status(502)
+            .create_async()
+            .await;
+        mock_location(&mut server, "61.5", forecast_body(3)).await;
+        let flux = server
+            .mock("POST", "/api/events/batch")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "events": [{"key": "matt/weather/cabin"}]
+            })))
+            .with_status(200)
+            .with_body(batch_ok(1))
+            .create_async()
+            .await;
+
+        let mut poller = weather_poller(&server);
+        poller.poll().await.unwrap();
+
+        flux.assert_async().await;
+        let status = poller_status(&poller);
+        assert_eq!(status.events_published, 1);
+        let error = status.last_error.unwrap();
+        assert!(
+            error.starts_with("home: Open-Meteo returned 502"),
+            "{}",
+            error
+        );
+    }
+
+    /// Records the entity ID of every event Flux publishes
+    #[derive(Default)]
+    struct CapturingSink {
+        entity_ids: Mutex<Vec<String>>,
+    }
+
+    impl PublishSink for CapturingSink {
+        fn send(
+            &self,
+            _subject: String,
+            _request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = Result<AckFuture>> + Send + '_>> {
+            Box::pin(async move {
+                let event: FluxEvent = serde_json::from_slice(&payload)?;
+                let entity_id = event.payload["entity_id"].as_str().unwrap_or_default();
+                self.entity_ids.lock().unwrap().push(entity_id.to_string());
+                let ack: AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    /// Serves Flux's ingestion API with auth enabled; returns its URL.
+    async fn spawn_flux(registry: Arc<NamespaceRegistry>, sink: Arc<CapturingSink>) -> String {
+        let state = AppState {
+            event_publisher: EventPublisher::with_sink(sink as Arc<dyn PublishSink>),
+            namespace_registry: registry,
+            auth_enabled: true,
+            admin_token: None,
+            runtime_config: flux::config::new_runtime_config(),
+            rate_limiter: Arc::new(flux::rate_limit::RateLimiter::new()),
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, create_router(state)).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_poll_publishes_into_auth_enabled_namespace() {
+        let mut server = Server::new_async().await;
+        mock_location(&mut server, "52.52", forecast_body(95)).await;
+        mock_location(&mut server, "61.5", forecast_body(1)).await;
+        let registry = Arc::new(NamespaceRegistry::new());
+        let matt = registry.register("matt").unwrap();
+        let other = registry.register("other").unwrap();
+        let sink = Arc::new(CapturingSink::default());
+        let flux_url = spawn_flux(registry, Arc::clone(&sink)).await;
+
+        let mut config = make_config();
+        config.flux_namespace_token = Some(matt.token.clone());
+        let mut poller = weather_poller_for(&server, config, flux_url.clone());
+        poller.poll().await.unwrap();
+
+        let status = poller_status(&poller);
+        assert!(status.last_error.is_none(), "{:?}", status.last_error);
+        assert_eq!(status.events_published, 3);
+        assert_eq!(
+            *sink.entity_ids.lock().unwrap(),
+            vec![
+                "matt/weather/home",
+                "matt/weather/home/alerts",
+                "matt/weather/cabin"
+            ]
+        );
+
+        // Another namespace's token can't publish into `matt`
+        let mut config = make_config();
+        config.flux_namespace_token = Some(other.token.clone());
+        let mut poller = weather_poller_for(&server, config, flux_url);
+        poller.poll().await.unwrap();
+        let status = poller_status(&poller);
+        assert_eq!(status.events_published, 0);
+        assert!(status.last_error.is_some());
+        assert_eq!(sink.entity_ids.lock().unwrap().len(), 3);
+    }
+}
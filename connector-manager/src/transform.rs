@@ -0,0 +1,457 @@
+//! Per-source event transforms.
+//!
+//! A source's rules run in order on each event's `payload.properties` right
+//! before it is published, so a redacted field never reaches Flux. Sources
+//! keep their rules on their config (`transforms`); builtin connectors keep
+//! them per user in
+//! [`TransformConfigStore`](crate::transform_config::TransformConfigStore).
+//!
+//! Paths are dot-separated keys into nested objects (`user.email`). A rule
+//! whose path is missing, or runs into something that isn't an object, leaves
+//! the event as it is; applying rules never fails.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// Rules one source may have
+pub const MAX_RULES: usize = 100;
+
+/// Prefix of a hashed value
+pub const HASH_PREFIX: &str = "sha256:";
+
+/// One step of a source's transform pipeline.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformRule {
+    /// Drops the property at `path`, or replaces it with its hash.
+    Redact {
+        path: String,
+        #[serde(default)]
+        mode: RedactMode,
+    },
+    /// Moves the property at `from` to `to`, replacing what was there.
+    Rename { from: String, to: String },
+    /// Sets the property at `path` to `value`, creating parent objects.
+    AddStatic {
+        path: String,
+        #[schema(value_type = Object)]
+        value: Value,
+    },
+}
+
+/// What a redact rule leaves behind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactMode {
+    /// Nothing: the property is removed.
+    #[default]
+    Drop,
+    /// `sha256:{hex}` of the value, so equal values stay correlatable.
+    /// Values are hashed as compact JSON, so `42` and `"42"` differ.
+    /// Unsalted: low-entropy values such as emails can be guessed back.
+    Hash,
+}
+
+/// Checks rules before they are stored.
+pub fn validate_rules(rules: &[TransformRule]) -> Result<()> {
+    if rules.len() > MAX_RULES {
+        anyhow::bail!("at most {} transform rules are allowed", MAX_RULES);
+    }
+    for (i, rule) in rules.iter().enumerate() {
+        let paths = match rule {
+            TransformRule::Redact { path, .. } | TransformRule::AddStatic { path, .. } => {
+                vec![("path", path)]
+            }
+            TransformRule::Rename { from, to } => {
+                if from == to {
+                    anyhow::bail!("transforms[{}]: rename 'from' and 'to' are the same", i);
+                }
+                vec![("from", from), ("to", to)]
+            }
+        };
+        for (field, path) in paths {
+            if path.split('.').any(str::is_empty) {
+                anyhow::bail!(
+                    "transforms[{}]: {} '{}' must be dot-separated non-empty keys",
+                    i,
+                    field,
+                    path
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies `rules` in order to `properties`.
+pub fn apply(rules: &[TransformRule], properties: &mut Map<String, Value>) {
+    for rule in rules {
+        match rule {
+            TransformRule::Redact { path, mode } => match mode {
+                RedactMode::Drop => {
+                    take(properties, path);
+                }
+                RedactMode::Hash => {
+                    if let Some(value) = get_mut(properties, path) {
+                        *value = Value::String(hash(value));
+                    }
+                }
+            },
+            TransformRule::Rename { from, to } => {
+                if let Some(value) = take(properties, from) {
+                    if let Err(value) = put(properties, to, value) {
+                        // `to` is blocked: leave the property where it was
+                        let _ = put(properties, from, value);
+                    }
+                }
+            }
+            TransformRule::AddStatic { path, value } => {
+                let _ = put(properties, path, value.clone());
+            }
+        }
+    }
+}
+
+/// Applies `rules` to the `properties` object of an event payload. Payloads
+/// without one are left alone.
+pub fn apply_to_payload(rules: &[TransformRule], payload: &mut Value) {
+    if rules.is_empty() {
+        return;
+    }
+    if let Some(Value::Object(properties)) = payload.get_mut("properties") {
+        apply(rules, properties);
+    }
+}
+
+/// Applies `rules` to a serialized Flux event (`payload.properties`).
+pub fn apply_to_event(rules: &[TransformRule], event: &mut Value) {
+    if let Some(payload) = event.get_mut("payload") {
+        apply_to_payload(rules, payload);
+    }
+}
+
+/// `sha256:{hex}` of a value's compact JSON, so its type is part of the hash
+pub fn hash(value: &Value) -> String {
+    let digest = Sha256::digest(value.to_string().as_bytes());
+    format!("{}{:x}", HASH_PREFIX, digest)
+}
+
+/// Splits `path` into its parent keys and last key
+fn split(path: &str) -> (Vec<&str>, &str) {
+    let mut keys: Vec<&str> = path.split('.').collect();
+    let last = keys.pop().unwrap_or_default();
+    (keys, last)
+}
+
+/// The object at `keys`, if each step is an existing object
+fn object_at<'a>(
+    mut map: &'a mut Map<String, Value>,
+    keys: &[&str],
+) -> Option<&'a mut Map<String, Value>> {
+    for key in keys {
+        map = map.get_mut(*key)?.as_object_mut()?;
+    }
+    Some(map)
+}
+
+fn get_mut<'a>(properties: &'a mut Map<String, Value>, path: &str) -> Option<&'a mut Value> {
+    let (parents, last) = split(path);
+    object_at(properties, &parents)?.get_mut(last)
+}
+
+fn take(properties: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    let (parents, last) = split(path);
+    object_at(properties, &parents)?.remove(last)
+}
+
+/// Sets `path` to `value`, creating missing parents. Hands `value` back if a
+/// parent exists but isn't an object.
+fn put(properties: &mut Map<String, Value>, path: &str, value: Value) -> Result<(), Value> {
+    let (parents, last) = split(path);
+    let mut map = properties;
+    for key in parents {
+        let next = map.entry(key).or_insert_with(|| Value::Object(Map::new()));
+        match next.as_object_mut() {
+            Some(object) => map = object,
+            None => return Err(value),
+        }
+    }
+    map.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rules(value: Value) -> Vec<TransformRule> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn applied(rules: &[TransformRule], properties: Value) -> Value {
+        let mut event =
+            json!({"stream": "s", "payload": {"entity_id": "ns/e", "properties": properties}});
+        apply_to_event(rules, &mut event);
+        event["payload"]["properties"].take()
+    }
+
+    #[test]
+    fn test_rules_deserialize() {
+        let parsed = rules(json!([
+            {"type": "redact", "path": "email"},
+            {"type": "redact", "path": "user.name", "mode": "hash"},
+            {"type": "rename", "from": "repo", "to": "repository"},
+            {"type": "add_static", "path": "site", "value": "eu-1"},
+        ]));
+        assert_eq!(
+            parsed,
+            vec![
+                TransformRule::Redact {
+                    path: "email".to_string(),
+                    mode: RedactMode::Drop,
+                },
+                TransformRule::Redact {
+                    path: "user.name".to_string(),
+                    mode: RedactMode::Hash,
+                },
+                TransformRule::Rename {
+                    from: "repo".to_string(),
+                    to: "repository".to_string(),
+                },
+                TransformRule::AddStatic {
+                    path: "site".to_string(),
+                    value: json!("eu-1"),
+                },
+            ]
+        );
+        assert!(serde_json::from_value::<Vec<TransformRule>>(
+            json!([{"type": "uppercase", "path": "a"}])
+        )
+        .is_err());
+        assert!(serde_json::from_value::<Vec<TransformRule>>(
+            json!([{"type": "redact", "path": "a", "extra": 1}])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_redact_drop() {
+        let rules = rules(json!([
+            {"type": "redact", "path": "email"},
+            {"type": "redact", "path": "user.email"},
+        ]));
+        assert_eq!(
+            applied(
+                &rules,
+                json!({"email": "a@example.com", "title": "t", "user": {"email": "b@example.com", "id": 7}})
+            ),
+            json!({"title": "t", "user": {"id": 7}})
+        );
+    }
+
+    #[test]
+    fn test_redact_hash_is_deterministic() {
+        let rules = rules(json!([{"type": "redact", "path": "customer", "mode": "hash"}]));
+        let first = applied(&rules, json!({"customer": "Acme Corp"}));
+        let second = applied(&rules, json!({"customer": "Acme Corp", "other": 1}));
+        assert_eq!(first["customer"], second["customer"]);
+        assert_eq!(first["customer"], json!(hash(&json!("Acme Corp"))));
+        assert!(first["customer"].as_str().unwrap().starts_with(HASH_PREFIX));
+        assert_eq!(
+            first["customer"].as_str().unwrap().len(),
+            HASH_PREFIX.len() + 64
+        );
+        assert_ne!(
+            applied(&rules, json!({"customer": "Other"}))["customer"],
+            first["customer"]
+        );
+
+        // Known value: sha256 of the JSON text "abc", quotes included
+        assert_eq!(
+            hash(&json!("abc")),
+            "sha256:6cc43f858fbb763301637b5af970e2a46b46f461f27e5a0f41e009c59b827b25"
+        );
+        // The type is part of the hash; object key order doesn't matter
+        assert_eq!(hash(&json!(42)), hash(&json!(42)));
+        assert_ne!(hash(&json!(42)), hash(&json!("42")));
+        assert_ne!(hash(&json!(42)), hash(&json!(42.5)));
+        let a: Value = serde_json::from_str(r#"{"x": 1, "y": 2}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"y": 2, "x": 1}"#).unwrap();
+        assert_eq!(hash(&a), hash(&b));
+    }
+
+    #[test]
+    fn test_rename() {
+        let rules = rules(json!([
+            {"type": "rename", "from": "repo", "to": "repository"},
+            {"type": "rename", "from": "owner.login", "to": "owner_login"},
+            {"type": "rename", "from": "state", "to": "meta.state"},
+        ]));
+        assert_eq!(
+            applied(
+                &rules,
+                json!({"repo": "flux", "owner": {"login": "x", "id": 1}, "state": "open"})
+            ),
+            json!({"repository": "flux", "owner": {"id": 1}, "owner_login": "x", "meta": {"state": "open"}})
+        );
+
+        // Replaces an existing target
+        let overwrite = TransformRule::Rename {
+            from: "a".to_string(),
+            to: "b".to_string(),
+        };
+        assert_eq!(
+            applied(&[overwrite], json!({"a": 1, "b": 2})),
+            json!({"b": 1})
+        );
+    }
+
+    #[test]
+    fn test_add_static() {
+        let rules = rules(json!([
+            {"type": "add_static", "path": "site", "value": "eu-1"},
+            {"type": "add_static", "path": "labels.team", "value": {"name": "ops"}},
+        ]));
+        assert_eq!(
+            applied(&rules, json!({"site": "us-1", "labels": {"env": "prod"}})),
+            json!({"site": "eu-1", "labels": {"env": "prod", "team": {"name": "ops"}}})
+        );
+        assert_eq!(
+            applied(&rules, json!({})),
+            json!({"site": "eu-1", "labels": {"team": {"name": "ops"}}})
+        );
+    }
+
+    #[test]
+    fn test_missing_fields_are_left_alone() {
+        let rules = rules(json!([
+            {"type": "redact", "path": "email"},
+            {"type": "redact", "path": "user.email", "mode": "hash"},
+            {"type": "rename", "from": "missing", "to": "other"},
+            {"type": "rename", "from": "a.b.c", "to": "d"},
+        ]));
+        let properties = json!({"title": "t", "user": "not an object", "a": {"b": 5}});
+        assert_eq!(applied(&rules, properties.clone()), properties);
+    }
+
+    #[test]
+    fn test_blocked_paths_keep_data() {
+        let rules = rules(json!([
+            {"type": "rename", "from": "email", "to": "user.email"},
+            {"type": "add_static", "path": "user.site.region", "value": "eu"},
+            {"type": "add_static", "path": "tags.site", "value": "eu"},
+        ]));
+        // `user` and `tags` aren't objects: the rename stays put and nothing is added
+        let properties = json!({"email": "a@example.com", "user": "alice", "tags": ["x"]});
+        assert_eq!(applied(&rules, properties.clone()), properties);
+    }
+
+    #[test]
+    fn test_unexpected_payload_shapes() {
+        let rules = rules(json!([
+            {"type": "redact", "path": "email"},
+            {"type": "add_static", "path": "site", "value": "eu-1"},
+        ]));
+        for mut event in [
+            json!({"stream": "s"}),
+            json!({"payload": null}),
+            json!({"payload": [1, 2]}),
+            json!({"payload": {"properties": "text"}}),
+            json!({"payload": {"properties": [{"email": "a"}]}}),
+            json!("not an event"),
+            Value::Null,
+        ] {
+            let before = event.clone();
+            apply_to_event(&rules, &mut event);
+            assert_eq!(event, before);
+        }
+
+        // Payload without properties stays as it is; with them, rules apply
+        let mut payload = json!({"entity_id": "ns/e"});
+        apply_to_payload(&rules, &mut payload);
+        assert_eq!(payload, json!({"entity_id": "ns/e"}));
+        let mut payload = json!({"properties": {"email": "a", "x": 1}});
+        apply_to_payload(&rules, &mut payload);
+        assert_eq!(payload, json!({"properties": {"x": 1, "site": "eu-1"}}));
+    }
+
+    #[test]
+    fn test_unvalidated_paths_do_not_panic() {
+        let rules = vec![
+            TransformRule::Redact {
+                path: String::new(),
+                mode: RedactMode::Hash,
+            },
+            TransformRule::Rename {
+                from: "..".to_string(),
+                to: ".x.".to_string(),
+            },
+            TransformRule::AddStatic {
+                path: "a..b".to_string(),
+                value: json!(1),
+            },
+        ];
+        let mut properties = Map::new();
+        properties.insert(String::new(), json!("empty key"));
+        properties.insert("a".to_string(), json!({"": {"b": 0}}));
+        apply(&rules, &mut properties);
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rules = rules(json!([
+            {"type": "rename", "from": "email", "to": "contact"},
+            {"type": "redact", "path": "contact", "mode": "hash"},
+            {"type": "redact", "path": "email"},
+        ]));
+        let out = applied(&rules, json!({"email": "a@example.com"}));
+        assert_eq!(out, json!({"contact": hash(&json!("a@example.com"))}));
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        assert!(validate_rules(&[]).is_ok());
+        assert!(validate_rules(&rules(json!([
+            {"type": "redact", "path": "user.email"},
+            {"type": "rename", "from": "a", "to": "b.c"},
+            {"type": "add_static", "path": "site", "value": "eu-1"},
+        ])))
+        .is_ok());
+
+        for (bad, message) in [
+            (
+                json!([{"type": "redact", "path": ""}]),
+                "transforms[0]: path",
+            ),
+            (json!([{"type": "redact", "path": "user."}]), "path 'user.'"),
+            (
+                json!([{"type": "redact", "path": "a"}, {"type": "add_static", "path": ".a", "value": 1}]),
+                "transforms[1]: path",
+            ),
+            (
+                json!([{"type": "rename", "from": "a", "to": "a"}]),
+                "are the same",
+            ),
+            (
+                json!([{"type": "rename", "from": "a", "to": "b..c"}]),
+                "to 'b..c'",
+            ),
+        ] {
+            let err = validate_rules(&rules(bad)).unwrap_err().to_string();
+            assert!(err.contains(message), "{}", err);
+        }
+
+        let too_many = vec![
+            TransformRule::Redact {
+                path: "a".to_string(),
+                mode: RedactMode::Drop,
+            };
+            MAX_RULES + 1
+        ];
+        assert!(validate_rules(&too_many).is_err());
+    }
+}
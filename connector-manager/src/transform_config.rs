@@ -0,0 +1,111 @@
+//! Transform rules of builtin connectors.
+//!
+//! Sources keep their rules on their own config; builtin and external
+//! connectors have none, so their rules are stored here per
+//! `user_id:connector` and read by the scheduler on every poll.
+
+use crate::transform::TransformRule;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flux::migrations::{self, Migration};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+
+/// Schema history of the transform config store. Append only.
+const MIGRATIONS: &[Migration] = &[Migration::Sql(
+    "CREATE TABLE IF NOT EXISTS connector_transforms (
+        user_id     TEXT NOT NULL,
+        connector   TEXT NOT NULL,
+        rules_json  TEXT NOT NULL,
+        updated_at  TEXT NOT NULL,
+        PRIMARY KEY (user_id, connector)
+    );",
+)];
+
+/// Persists builtin connectors' transform rules in SQLite.
+pub struct TransformConfigStore {
+    conn: Mutex<Connection>,
+}
+
+impl TransformConfigStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open transform config DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "connector_transforms", MIGRATIONS)
+            .context("Failed to migrate transform config DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Rules of `user_id`'s `connector` (empty if none were set).
+    pub fn get(&self, user_id: &str, connector: &str) -> Result<Vec<TransformRule>> {
+        let conn = self.conn.lock().unwrap();
+        let rules_json: Option<String> = conn
+            .query_row(
+                "SELECT rules_json FROM connector_transforms WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read connector transforms")?;
+        match rules_json {
+            Some(json) => serde_json::from_str(&json).context("Invalid stored transform rules"),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Replaces the rules of `user_id`'s `connector`; empty rules remove them.
+    pub fn set(&self, user_id: &str, connector: &str, rules: &[TransformRule]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if rules.is_empty() {
+            conn.execute(
+                "DELETE FROM connector_transforms WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+            )
+            .context("Failed to delete connector transforms")?;
+            return Ok(());
+        }
+        let rules_json = serde_json::to_string(rules).context("Failed to serialize rules")?;
+        conn.execute(
+            "INSERT INTO connector_transforms (user_id, connector, rules_json, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(user_id, connector) DO UPDATE
+                SET rules_json = excluded.rules_json, updated_at = excluded.updated_at",
+            params![user_id, connector, rules_json, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to store connector transforms")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::RedactMode;
+
+    #[test]
+    fn test_set_get_and_clear() {
+        let store = TransformConfigStore::new(":memory:").unwrap();
+        assert!(store.get("alice", "github").unwrap().is_empty());
+
+        let rules = vec![TransformRule::Redact {
+            path: "author_email".to_string(),
+            mode: RedactMode::Hash,
+        }];
+        store.set("alice", "github", &rules).unwrap();
+        assert_eq!(store.get("alice", "github").unwrap(), rules);
+        assert!(store.get("bob", "github").unwrap().is_empty());
+
+        let replaced = vec![TransformRule::AddStatic {
+            path: "site".to_string(),
+            value: serde_json::json!("eu-1"),
+        }];
+        store.set("alice", "github", &replaced).unwrap();
+        assert_eq!(store.get("alice", "github").unwrap(), replaced);
+
+        store.set("alice", "github", &[]).unwrap();
+        assert!(store.get("alice", "github").unwrap().is_empty());
+    }
+}
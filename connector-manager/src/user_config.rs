@@ -0,0 +1,112 @@
+//! Per-user configuration of builtin connectors.
+//!
+//! Some builtin connectors need more than a token to poll, such as the Notion
+//! databases to sync. Each user's configuration is a JSON object stored per
+//! `user_id:connector`, checked by
+//! [`Connector::validate_user_config`](crate::Connector::validate_user_config)
+//! and read by the scheduler on every poll.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flux::migrations::{self, Migration};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Schema history of the user config store. Append only.
+const MIGRATIONS: &[Migration] = &[Migration::Sql(
+    "CREATE TABLE IF NOT EXISTS connector_user_config (
+        user_id     TEXT NOT NULL,
+        connector   TEXT NOT NULL,
+        config_json TEXT NOT NULL,
+        updated_at  TEXT NOT NULL,
+        PRIMARY KEY (user_id, connector)
+    );",
+)];
+
+/// Persists builtin connectors' per-user configuration in SQLite.
+pub struct UserConfigStore {
+    conn: Mutex<Connection>,
+}
+
+impl UserConfigStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open user config DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "connector_user_config", MIGRATIONS)
+            .context("Failed to migrate user config DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Configuration of `user_id`'s `connector` (`null` if none was set).
+    pub fn get(&self, user_id: &str, connector: &str) -> Result<Value> {
+        let conn = self.conn.lock().unwrap();
+        let config_json: Option<String> = conn
+            .query_row(
+                "SELECT config_json FROM connector_user_config
+                 WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read connector user config")?;
+        match config_json {
+            Some(json) => serde_json::from_str(&json).context("Invalid stored user config"),
+            None => Ok(Value::Null),
+        }
+    }
+
+    /// Replaces the configuration of `user_id`'s `connector`; `null` removes it.
+    pub fn set(&self, user_id: &str, connector: &str, config: &Value) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if config.is_null() {
+            conn.execute(
+                "DELETE FROM connector_user_config WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+            )
+            .context("Failed to delete connector user config")?;
+            return Ok(());
+        }
+        conn.execute(
+            "INSERT INTO connector_user_config (user_id, connector, config_json, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(user_id, connector) DO UPDATE
+                SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+            params![
+                user_id,
+                connector,
+                config.to_string(),
+                Utc::now().to_rfc3339()
+            ],
+        )
+        .context("Failed to store connector user config")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_set_get_and_clear() {
+        let store = UserConfigStore::new(":memory:").unwrap();
+        assert!(store.get("alice", "notion").unwrap().is_null());
+
+        let config = json!({"database_ids": ["d9824bdc84454327be8b5b47500af6ce"]});
+        store.set("alice", "notion", &config).unwrap();
+        assert_eq!(store.get("alice", "notion").unwrap(), config);
+        assert!(store.get("bob", "notion").unwrap().is_null());
+
+        let replaced = json!({"database_ids": []});
+        store.set("alice", "notion", &replaced).unwrap();
+        assert_eq!(store.get("alice", "notion").unwrap(), replaced);
+
+        store.set("alice", "notion", &Value::Null).unwrap();
+        assert!(store.get("alice", "notion").unwrap().is_null());
+    }
+}
@@ -0,0 +1,358 @@
+//! Weather connector config storage.
+//!
+//! Stores Open-Meteo weather sources in SQLite. Each source holds a list of
+//! named locations (latitude/longitude), the namespace to publish them under
+//! and a poll interval. Open-Meteo needs no API key, so nothing is kept in
+//! the CredentialStore.
+
+use crate::transform::TransformRule;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flux::migrations::{self, Migration};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Shortest accepted poll interval; Open-Meteo refreshes its models every 15 minutes.
+pub const MIN_POLL_INTERVAL_SECS: u64 = 60;
+
+/// A named point to fetch weather for.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WeatherLocation {
+    /// Becomes the entity ID `{namespace}/weather/{name}`.
+    pub name: String,
+    /// Degrees north, -90 to 90.
+    pub latitude: f64,
+    /// Degrees east, -180 to 180.
+    pub longitude: f64,
+}
+
+/// Config for a single weather source.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WeatherSourceConfig {
+    /// Unique source ID (UUIDv4).
+    pub id: String,
+    /// Human-readable label shown in the UI.
+    pub name: String,
+    /// Locations polled on every run.
+    pub locations: Vec<WeatherLocation>,
+    /// Flux namespace to publish entities under.
+    pub namespace: String,
+    /// How often to poll (seconds).
+    pub poll_interval_secs: u64,
+    /// When this source was created.
+    pub created_at: DateTime<Utc>,
+    /// Optional Flux namespace token for auth-enabled Flux instances.
+    pub flux_namespace_token: Option<String>,
+    /// Rules applied to each location's reading before it is published.
+    #[serde(default)]
+    pub transforms: Vec<TransformRule>,
+}
+
+/// Checks a source's namespace, locations and poll interval.
+///
+/// The namespace and location names must be non-empty and free of `/`
+/// (they become entity IDs), and location names unique; coordinates must be
+/// finite and in range.
+pub fn validate_weather_source(
+    namespace: &str,
+    locations: &[WeatherLocation],
+    poll_interval_secs: u64,
+) -> Result<()> {
+    if namespace.trim().is_empty() {
+        anyhow::bail!("namespace must not be empty");
+    }
+    if namespace.contains('/') {
+        anyhow::bail!("namespace '{}' must not contain '/'", namespace);
+    }
+    if locations.is_empty() {
+        anyhow::bail!("at least one location is required");
+    }
+    if poll_interval_secs < MIN_POLL_INTERVAL_SECS {
+        anyhow::bail!(
+            "poll_interval_secs must be at least {}",
+            MIN_POLL_INTERVAL_SECS
+        );
+    }
+
+    let mut names = HashSet::new();
+    for location in locations {
+        let name = location.name.trim();
+        if name.is_empty() {
+            anyhow::bail!("location name must not be empty");
+        }
+        if name.contains('/') {
+            anyhow::bail!("location name '{}' must not contain '/'", name);
+        }
+        if !names.insert(name) {
+            anyhow::bail!("duplicate location name '{}'", name);
+        }
+        if !(-90.0..=90.0).contains(&location.latitude) {
+            anyhow::bail!(
+                "location '{}': latitude {} is outside -90..90",
+                name,
+                location.latitude
+            );
+        }
+        if !(-180.0..=180.0).contains(&location.longitude) {
+            anyhow::bail!(
+                "location '{}': longitude {} is outside -180..180",
+                name,
+                location.longitude
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Schema history of the weather config store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS weather_sources (
+            id                   TEXT PRIMARY KEY,
+            name                 TEXT NOT NULL,
+            locations_json       TEXT NOT NULL,
+            poll_interval_secs   INTEGER NOT NULL,
+            created_at           TEXT NOT NULL,
+            flux_namespace_token TEXT
+        );",
+    ),
+    Migration::AddColumn {
+        table: "weather_sources",
+        column: "transforms_json",
+        definition: "TEXT NOT NULL DEFAULT '[]'",
+    },
+    // Earlier sources published `weather/{location}`, i.e. into the
+    // `weather` namespace, and stay there
+    Migration::AddColumn {
+        table: "weather_sources",
+        column: "namespace",
+        definition: "TEXT NOT NULL DEFAULT 'weather'",
+    },
+];
+
+/// Persists weather source configs in SQLite.
+pub struct WeatherConfigStore {
+    conn: Mutex<Connection>,
+}
+
+impl WeatherConfigStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open weather config DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "weather_sources", MIGRATIONS)
+            .context("Failed to migrate weather config DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Inserts a new weather source config. Fails if `id` already exists.
+    pub fn insert(&self, config: &WeatherSourceConfig) -> Result<()> {
+        let transforms_json =
+            serde_json::to_string(&config.transforms).context("Failed to serialize transforms")?;
+        let locations_json =
+            serde_json::to_string(&config.locations).context("Failed to serialize locations")?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO weather_sources
+                (id, name, locations_json, namespace, poll_interval_secs, created_at, flux_namespace_token, transforms_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                config.id,
+                config.name,
+                locations_json,
+                config.namespace,
+                config.poll_interval_secs as i64,
+                config.created_at.to_rfc3339(),
+                config.flux_namespace_token,
+                transforms_json,
+            ],
+        )
+        .context("Failed to insert weather source config")?;
+        Ok(())
+    }
+
+    /// Returns a single source by ID, or `None` if not found.
+    pub fn get(&self, id: &str) -> Result<Option<WeatherSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, locations_json, namespace, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM weather_sources WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(row_to_config(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns all source configs ordered by creation time.
+    pub fn list(&self) -> Result<Vec<WeatherSourceConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, locations_json, namespace, poll_interval_secs, created_at, flux_namespace_token, transforms_json
+             FROM weather_sources ORDER BY created_at ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(row_to_config(row).expect("row_to_config failed"))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to list weather source configs")
+    }
+
+    /// Replaces a source's transform rules. Returns false if not found.
+    pub fn set_transforms(&self, id: &str, rules: &[TransformRule]) -> Result<bool> {
+        let transforms_json =
+            serde_json::to_string(rules).context("Failed to serialize transforms")?;
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE weather_sources SET transforms_json = ?2 WHERE id = ?1",
+                params![id, transforms_json],
+            )
+            .context("Failed to update weather source transforms")?;
+        Ok(updated > 0)
+    }
+
+    /// Deletes a source by ID. No-op if the ID does not exist.
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM weather_sources WHERE id = ?1", params![id])
+            .context("Failed to delete weather source config")?;
+        Ok(())
+    }
+}
+
+fn row_to_config(row: &rusqlite::Row<'_>) -> rusqlite::Result<WeatherSourceConfig> {
+    let id: String = row.get(0)?;
+    let name: String = row.get(1)?;
+    let locations_json: String = row.get(2)?;
+    let namespace: String = row.get(3)?;
+    let poll_interval_secs: i64 = row.get(4)?;
+    let created_at_str: String = row.get(5)?;
+    let flux_namespace_token: Option<String> = row.get(6)?;
+    let transforms_json: String = row.get(7)?;
+
+    let locations: Vec<WeatherLocation> =
+        serde_json::from_str(&locations_json).expect("Failed to deserialize locations");
+    let created_at: DateTime<Utc> = created_at_str.parse().expect("Failed to parse created_at");
+    let transforms =
+        serde_json::from_str(&transforms_json).expect("Failed to deserialize transforms");
+
+    Ok(WeatherSourceConfig {
+        id,
+        name,
+        locations,
+        namespace,
+        poll_interval_secs: poll_interval_secs as u64,
+        created_at,
+        flux_namespace_token,
+        transforms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory_store() -> WeatherConfigStore {
+        WeatherConfigStore::new(":memory:").expect("in-memory store failed")
+    }
+
+    fn location(name: &str, latitude: f64, longitude: f64) -> WeatherLocation {
+        WeatherLocation {
+            name: name.to_string(),
+            latitude,
+            longitude,
+        }
+    }
+
+    fn sample_config(id: &str) -> WeatherSourceConfig {
+        WeatherSourceConfig {
+            id: id.to_string(),
+            name: "Home and office".to_string(),
+            locations: vec![
+                location("home", 52.52, 13.41),
+                location("office", 48.14, 11.58),
+            ],
+            namespace: "matt".to_string(),
+            poll_interval_secs: 900,
+            created_at: Utc::now(),
+            flux_namespace_token: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let store = in_memory_store();
+        store.insert(&sample_config("wx-1")).expect("insert failed");
+
+        let fetched = store.get("wx-1").unwrap().expect("config should exist");
+        assert_eq!(fetched.name, "Home and office");
+        assert_eq!(fetched.locations, sample_config("wx-1").locations);
+        assert_eq!(fetched.namespace, "matt");
+        assert_eq!(fetched.poll_interval_secs, 900);
+        assert!(store.get("ghost").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let store = in_memory_store();
+        store.insert(&sample_config("id-1")).unwrap();
+        store.insert(&sample_config("id-2")).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        store.delete("id-1").unwrap();
+        let ids: Vec<String> = store.list().unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["id-2"]);
+        store.delete("ghost").unwrap();
+    }
+
+    #[test]
+    fn test_validate_accepts_edges() {
+        let locations = vec![
+            location("north-pole", 90.0, 0.0),
+            location("date-line", -12.5, -180.0),
+            location("Home Office", 0.0, 180.0),
+        ];
+        validate_weather_source("matt", &locations, MIN_POLL_INTERVAL_SECS).unwrap();
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_locations() {
+        let err = |locations: Vec<WeatherLocation>| {
+            validate_weather_source("matt", &locations, 900)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(err(vec![]), "at least one location is required");
+        assert!(err(vec![location("home", 90.5, 0.0)]).contains("latitude 90.5"));
+        assert!(err(vec![location("home", 0.0, -180.01)]).contains("longitude -180.01"));
+        assert!(err(vec![location("home", f64::NAN, 0.0)]).contains("latitude NaN"));
+        assert_eq!(
+            err(vec![location("  ", 0.0, 0.0)]),
+            "location name must not be empty"
+        );
+        assert!(err(vec![location("a/b", 0.0, 0.0)]).contains("must not contain '/'"));
+        assert_eq!(
+            err(vec![location("home", 1.0, 1.0), location("home", 2.0, 2.0)]),
+            "duplicate location name 'home'"
+        );
+
+        let too_fast =
+            validate_weather_source("matt", &[location("home", 0.0, 0.0)], 10).unwrap_err();
+        assert!(too_fast.to_string().contains("poll_interval_secs"));
+
+        let home = [location("home", 0.0, 0.0)];
+        let no_namespace = validate_weather_source(" ", &home, 900).unwrap_err();
+        assert_eq!(no_namespace.to_string(), "namespace must not be empty");
+        let nested = validate_weather_source("matt/home", &home, 900).unwrap_err();
+        assert!(nested.to_string().contains("must not contain '/'"));
+    }
+}
@@ -0,0 +1,5 @@
+#!/bin/sh
+# External connector fixture: reads the credentials JSON from stdin and
+# emits one event carrying it.
+read -r credentials
+printf '{"stream":"external.echo","source":"echo","timestamp":1700000000000,"payload":{"entity_id":"echo/1","properties":{"credentials":%s}}}\n' "$credentials"
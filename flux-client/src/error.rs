@@ -0,0 +1,95 @@
+use flux::event::ValidationError;
+use std::fmt;
+
+/// Errors returned by [`FluxClient`](crate::FluxClient)
+#[derive(Debug)]
+pub enum FluxClientError {
+    /// Event failed local validation before being sent
+    Validation(ValidationError),
+    /// Server rejected the request body (400)
+    BadRequest(String),
+    /// Missing or invalid token (401)
+    Unauthorized(String),
+    /// Token not allowed to write the target entity (403)
+    Forbidden(String),
+    /// Entity does not exist (404)
+    NotFound(String),
+    /// Request body exceeds the server's size limit (413)
+    PayloadTooLarge,
+    /// Namespace rate limit exceeded (429); `retry_after` in seconds if sent
+    RateLimited { retry_after: Option<u64> },
+    /// Any other non-success status
+    Server { status: u16, message: String },
+    /// Transport or decoding failure
+    Http(reqwest::Error),
+}
+
+impl fmt::Display for FluxClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FluxClientError::Validation(e) => write!(f, "invalid event: {}", e),
+            FluxClientError::BadRequest(msg) => write!(f, "bad request: {}", msg),
+            FluxClientError::Unauthorized(msg) => write!(f, "unauthorized: {}", msg),
+            FluxClientError::Forbidden(msg) => write!(f, "forbidden: {}", msg),
+            FluxClientError::NotFound(msg) => write!(f, "not found: {}", msg),
+            FluxClientError::PayloadTooLarge => write!(f, "payload too large"),
+            FluxClientError::RateLimited { retry_after } => match retry_after {
+                Some(secs) => write!(f, "rate limited, retry after {}s", secs),
+                None => write!(f, "rate limited"),
+            },
+            FluxClientError::Server { status, message } => {
+                write!(f, "server error {}: {}", status, message)
+            }
+            FluxClientError::Http(e) => write!(f, "http error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FluxClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FluxClientError::Validation(e) => Some(e),
+            FluxClientError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ValidationError> for FluxClientError {
+    fn from(e: ValidationError) -> Self {
+        FluxClientError::Validation(e)
+    }
+}
+
+impl From<reqwest::Error> for FluxClientError {
+    fn from(e: reqwest::Error) -> Self {
+        FluxClientError::Http(e)
+    }
+}
+
+impl FluxClientError {
+    /// Map a non-success response to an error, using the `{"error": ...}` body if present
+    pub(crate) async fn from_response(resp: reqwest::Response) -> Self {
+        let status = resp.status().as_u16();
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        let body = resp.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(String::from))
+            .unwrap_or(body);
+
+        match status {
+            400 => FluxClientError::BadRequest(message),
+            401 => FluxClientError::Unauthorized(message),
+            403 => FluxClientError::Forbidden(message),
+            404 => FluxClientError::NotFound(message),
+            413 => FluxClientError::PayloadTooLarge,
+            429 => FluxClientError::RateLimited { retry_after },
+            _ => FluxClientError::Server { status, message },
+        }
+    }
+}
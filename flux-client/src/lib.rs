@@ -0,0 +1,248 @@
+//! Rust client for the Flux HTTP and WebSocket APIs.
+//!
+//! Wraps event publishing (`/api/events`), state queries and deletion
+//! (`/api/state/entities`), and live subscriptions (`/api/ws`). Event and
+//! state types are re-exported from the `flux` crate so they always match
+//! the server.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use flux_client::{FluxClient, FluxEvent, SubscriptionFilter};
+//! use futures::StreamExt;
+//! use serde_json::json;
+//!
+//! # async fn run() -> Result<(), flux_client::FluxClientError> {
+//! let client = FluxClient::new("http://localhost:3000", None);
+//!
+//! // Publish a sensor reading
+//! let event = FluxEvent {
+//!     event_id: None,
+//!     stream: "sensors".to_string(),
+//!     source: "sensor-01".to_string(),
+//!     timestamp: chrono::Utc::now().timestamp_millis(),
+//!     received_at: None,
+//!     key: None,
+//!     schema: None,
+//!     payload: json!({
+//!         "entity_id": "temp-sensor-01",
+//!         "properties": {"temperature": 22.5, "unit": "celsius"}
+//!     }),
+//! };
+//! client.publish(event).await?;
+//!
+//! // Read it back
+//! if let Some(entity) = client.get_entity("temp-sensor-01").await? {
+//!     println!("temperature = {}", entity.properties["temperature"]);
+//! }
+//!
+//! // Follow live updates (reconnects automatically)
+//! let mut updates = client.subscribe(SubscriptionFilter::Entities(vec![
+//!     "temp-sensor-01".to_string(),
+//! ]));
+//! while let Some(update) = updates.next().await {
+//!     println!("{}.{} = {}", update.entity_id, update.property, update.new_value);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod subscribe;
+
+pub use error::FluxClientError;
+pub use subscribe::SubscriptionFilter;
+
+// Shared types from the server crate
+pub use flux::event::ValidationError;
+pub use flux::state::{Entity, StateUpdate};
+pub use flux::FluxEvent;
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, FluxClientError>;
+
+/// Response for a published event
+#[derive(Clone, Debug, Deserialize)]
+pub struct PublishResponse {
+    #[serde(rename = "eventId")]
+    pub event_id: String,
+    pub stream: String,
+}
+
+/// Response for a published batch
+#[derive(Clone, Debug, Deserialize)]
+pub struct BatchResponse {
+    pub successful: usize,
+    pub failed: usize,
+    pub results: Vec<BatchResult>,
+}
+
+/// Per-event outcome within a batch
+#[derive(Clone, Debug, Deserialize)]
+pub struct BatchResult {
+    #[serde(rename = "eventId")]
+    pub event_id: Option<String>,
+    pub stream: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Response for an entity deletion
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeleteResponse {
+    pub entity_id: String,
+    #[serde(rename = "eventId")]
+    pub event_id: String,
+}
+
+/// Filters for listing entities (combined with AND)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EntityFilter {
+    /// Exact namespace match (`namespace/...` IDs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Entity ID prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    events: &'a [FluxEvent],
+}
+
+/// Client for a single Flux server
+#[derive(Clone, Debug)]
+pub struct FluxClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl FluxClient {
+    /// Create a client for `base_url` (e.g. `http://localhost:3000`).
+    ///
+    /// `token` is sent as a bearer token on writes and in WebSocket subscribe
+    /// messages.
+    pub fn new(base_url: impl Into<String>, token: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorized(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn send_json<T: serde::de::DeserializeOwned>(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let resp = self.authorized(builder).send().await?;
+        if !resp.status().is_success() {
+            return Err(FluxClientError::from_response(resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Publish a single event.
+    ///
+    /// The event is validated locally first, so malformed events fail with
+    /// [`FluxClientError::Validation`] without a round trip.
+    pub async fn publish(&self, mut event: FluxEvent) -> Result<PublishResponse> {
+        event.validate_and_prepare()?;
+        self.send_json(self.http.post(self.url("/api/events")).json(&event))
+            .await
+    }
+
+    /// Publish several events in one request.
+    ///
+    /// All events are validated locally before anything is sent; per-event
+    /// server-side failures are reported in [`BatchResponse::results`].
+    pub async fn publish_batch(&self, mut events: Vec<FluxEvent>) -> Result<BatchResponse> {
+        for event in &mut events {
+            event.validate_and_prepare()?;
+        }
+        self.send_json(
+            self.http
+                .post(self.url("/api/events/batch"))
+                .json(&BatchRequest { events: &events }),
+        )
+        .await
+    }
+
+    /// Fetch one entity, or `None` if it does not exist
+    pub async fn get_entity(&self, entity_id: &str) -> Result<Option<Entity>> {
+        let url = self.url(&format!(
+            "/api/state/entities/{}",
+            urlencoding::encode(entity_id)
+        ));
+        match self.send_json(self.http.get(url)).await {
+            Ok(entity) => Ok(Some(entity)),
+            Err(FluxClientError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List entities matching `filter`
+    pub async fn list_entities(&self, filter: &EntityFilter) -> Result<Vec<Entity>> {
+        self.send_json(self.http.get(self.url("/api/state/entities")).query(filter))
+            .await
+    }
+
+    /// Delete an entity (publishes a tombstone server-side)
+    pub async fn delete_entity(&self, entity_id: &str) -> Result<DeleteResponse> {
+        let url = self.url(&format!(
+            "/api/state/entities/{}",
+            urlencoding::encode(entity_id)
+        ));
+        self.send_json(self.http.delete(url)).await
+    }
+
+    /// WebSocket URL derived from the base URL (`http` → `ws`, `https` → `wss`)
+    ///
+    /// The token is not part of the URL; it travels in each subscribe message.
+    fn ws_url(&self) -> String {
+        let base = if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            self.base_url.clone()
+        };
+        format!("{}/api/ws", base)
+    }
+
+    /// Subscribe to live state updates.
+    ///
+    /// Batch updates are flattened into one [`StateUpdate`] per property. The
+    /// connection is re-established (and the subscription re-sent) whenever it
+    /// drops; dropping the stream stops it. Must be called within a Tokio runtime.
+    pub fn subscribe(&self, filter: SubscriptionFilter) -> impl Stream<Item = StateUpdate> {
+        subscribe::spawn_subscription(self.ws_url(), self.token.clone(), filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_url_from_base_url() {
+        let client = FluxClient::new("http://localhost:3000/", None);
+        assert_eq!(client.ws_url(), "ws://localhost:3000/api/ws");
+
+        let client = FluxClient::new("https://flux.example.com", Some("a b".to_string()));
+        assert_eq!(client.ws_url(), "wss://flux.example.com/api/ws");
+    }
+}
@@ -0,0 +1,324 @@
+//! WebSocket subscriptions with automatic reconnect.
+
+use chrono::{DateTime, Utc};
+use flux::state::StateUpdate;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, warn};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Which entities a subscription receives updates for
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubscriptionFilter {
+    /// Every entity (`"*"`)
+    All,
+    /// Only the listed entity IDs
+    Entities(Vec<String>),
+}
+
+impl SubscriptionFilter {
+    fn entity_ids(&self) -> Vec<String> {
+        match self {
+            SubscriptionFilter::All => vec!["*".to_string()],
+            SubscriptionFilter::Entities(ids) => ids.clone(),
+        }
+    }
+}
+
+/// Server → client messages the subscription cares about
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum ServerMessage {
+    #[serde(rename = "state_update")]
+    StateUpdate {
+        entity_id: String,
+        property: String,
+        value: Value,
+        #[serde(default)]
+        removed: bool,
+        timestamp: DateTime<Utc>,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        entity_last_updated: Option<DateTime<Utc>>,
+        #[serde(default)]
+        request_id: Option<String>,
+    },
+    #[serde(rename = "state_update_batch")]
+    StateUpdateBatch {
+        entity_id: String,
+        changes: Vec<BatchChange>,
+        timestamp: DateTime<Utc>,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        entity_last_updated: Option<DateTime<Utc>>,
+        #[serde(default)]
+        request_id: Option<String>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct BatchChange {
+    property: String,
+    #[serde(default)]
+    old_value: Option<Value>,
+    value: Value,
+    #[serde(default)]
+    removed: bool,
+}
+
+/// Convert one WS text frame into zero or more state updates
+fn parse_updates(text: &str) -> Vec<StateUpdate> {
+    let message = match serde_json::from_str::<ServerMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            debug!(error = %e, "Ignoring unrecognized WebSocket message");
+            return Vec::new();
+        }
+    };
+
+    match message {
+        ServerMessage::StateUpdate {
+            entity_id,
+            property,
+            value,
+            removed,
+            timestamp,
+            source,
+            entity_last_updated,
+            request_id,
+        } => vec![StateUpdate {
+            entity_id,
+            property,
+            old_value: None,
+            new_value: value,
+            removed,
+            timestamp,
+            source,
+            entity_last_updated,
+            request_id,
+        }],
+        ServerMessage::StateUpdateBatch {
+            entity_id,
+            changes,
+            timestamp,
+            source,
+            entity_last_updated,
+            request_id,
+        } => changes
+            .into_iter()
+            .map(|change| StateUpdate {
+                entity_id: entity_id.clone(),
+                property: change.property,
+                old_value: change.old_value,
+                new_value: change.value,
+                removed: change.removed,
+                timestamp,
+                source: source.clone(),
+                entity_last_updated,
+                request_id: request_id.clone(),
+            })
+            .collect(),
+        ServerMessage::Other => Vec::new(),
+    }
+}
+
+/// Subscribe message for `entity_id`, carrying `token` for auth-enabled servers
+fn subscribe_message(entity_id: &str, token: Option<&str>) -> Value {
+    let mut msg = serde_json::json!({"type": "subscribe", "entity_id": entity_id});
+    if let Some(token) = token {
+        msg["token"] = Value::from(token);
+    }
+    msg
+}
+
+/// Spawn the connection task and return the receiving end as a stream.
+///
+/// The task reconnects with exponential backoff and re-sends the subscribe
+/// messages after every reconnect. It exits once the stream is dropped.
+pub(crate) fn spawn_subscription(
+    ws_url: String,
+    token: Option<String>,
+    filter: SubscriptionFilter,
+) -> ReceiverStream<StateUpdate> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            match connect_async(ws_url.as_str()).await {
+                Ok((mut ws, _)) => {
+                    debug!(url = %ws_url, "WebSocket connected");
+                    backoff = INITIAL_BACKOFF;
+
+                    let mut subscribed = true;
+                    for entity_id in filter.entity_ids() {
+                        let msg = subscribe_message(&entity_id, token.as_deref());
+                        if let Err(e) = ws.send(Message::Text(msg.to_string())).await {
+                            warn!(error = %e, "Failed to send subscribe message");
+                            subscribed = false;
+                            break;
+                        }
+                    }
+
+                    if subscribed {
+                        loop {
+                            tokio::select! {
+                                _ = tx.closed() => return,
+                                msg = ws.next() => match msg {
+                                    Some(Ok(Message::Text(text))) => {
+                                        for update in parse_updates(&text) {
+                                            if tx.send(update).await.is_err() {
+                                                return;
+                                            }
+                                        }
+                                    }
+                                    Some(Ok(Message::Close(_))) | None => break,
+                                    Some(Ok(_)) => {}
+                                    Some(Err(e)) => {
+                                        warn!(error = %e, "WebSocket read failed");
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(url = %ws_url, error = %e, "WebSocket connect failed");
+                }
+            }
+
+            tokio::select! {
+                _ = tx.closed() => return,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_single_update() {
+        let text = json!({
+            "type": "state_update",
+            "entity_id": "sensor-01",
+            "property": "temperature",
+            "value": 22.5,
+            "timestamp": "2026-01-01T00:00:00Z"
+        })
+        .to_string();
+
+        let updates = parse_updates(&text);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].entity_id, "sensor-01");
+        assert_eq!(updates[0].new_value, json!(22.5));
+        assert_eq!(updates[0].old_value, None);
+        assert_eq!(updates[0].source, None);
+    }
+
+    #[test]
+    fn test_parse_batch_update_expands_changes() {
+        let text = json!({
+            "type": "state_update_batch",
+            "entity_id": "sensor-01",
+            "changes": [
+                {"property": "temperature", "old_value": 21.0, "value": 22.5},
+                {"property": "unit", "old_value": null, "value": "celsius"}
+            ],
+            "timestamp": "2026-01-01T00:00:00Z"
+        })
+        .to_string();
+
+        let updates = parse_updates(&text);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].old_value, Some(json!(21.0)));
+        assert_eq!(updates[1].property, "unit");
+    }
+
+    #[test]
+    fn test_parse_source_and_entity_last_updated() {
+        let text = json!({
+            "type": "state_update_batch",
+            "entity_id": "sensor-01",
+            "changes": [
+                {"property": "temperature", "value": 22.5},
+                {"property": "unit", "value": "celsius"}
+            ],
+            "timestamp": "2026-01-01T00:00:00Z",
+            "source": "modbus-gw",
+            "entity_last_updated": "2026-01-01T00:00:00Z",
+            "request_id": "req-1"
+        })
+        .to_string();
+
+        let updates = parse_updates(&text);
+        assert!(updates
+            .iter()
+            .all(|u| u.source.as_deref() == Some("modbus-gw")));
+        assert_eq!(updates[1].entity_last_updated, Some(updates[1].timestamp));
+        assert_eq!(updates[1].request_id.as_deref(), Some("req-1"));
+    }
+
+    #[test]
+    fn test_parse_removed_property() {
+        let text = json!({
+            "type": "state_update_batch",
+            "entity_id": "sensor-01",
+            "changes": [
+                {"property": "error_message", "old_value": "timeout", "value": null, "removed": true},
+                {"property": "status", "old_value": "error", "value": "ok"}
+            ],
+            "timestamp": "2026-01-01T00:00:00Z"
+        })
+        .to_string();
+
+        let updates = parse_updates(&text);
+        assert!(updates[0].removed);
+        assert!(updates[0].new_value.is_null());
+        assert!(!updates[1].removed);
+    }
+
+    #[test]
+    fn test_parse_ignores_other_messages() {
+        let text = json!({"type": "metrics_update", "entities": {"total": 1}}).to_string();
+        assert!(parse_updates(&text).is_empty());
+        assert!(parse_updates("not json").is_empty());
+    }
+
+    #[test]
+    fn test_filter_all_subscribes_wildcard() {
+        assert_eq!(SubscriptionFilter::All.entity_ids(), vec!["*"]);
+    }
+
+    #[test]
+    fn test_subscribe_message_carries_token() {
+        assert_eq!(
+            subscribe_message("*", None),
+            json!({"type": "subscribe", "entity_id": "*"})
+        );
+        assert_eq!(
+            subscribe_message("ns/a", Some("tok")),
+            json!({"type": "subscribe", "entity_id": "ns/a", "token": "tok"})
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// HTTP surface tests for FluxClient against a mock server
+
+use flux_client::{EntityFilter, FluxClient, FluxClientError, FluxEvent, ValidationError};
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn sensor_event() -> FluxEvent {
+    FluxEvent {
+        event_id: None,
+        stream: "sensors".to_string(),
+        source: "sensor-01".to_string(),
+        timestamp: 1_700_000_000_000,
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({
+            "entity_id": "temp-sensor-01",
+            "properties": {"temperature": 22.5}
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_publish_sends_bearer_token_and_parses_response() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/events"))
+        .and(header("authorization", "Bearer secret"))
+        .and(body_partial_json(json!({"stream": "sensors"})))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"eventId": "evt-1", "stream": "sensors"})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = FluxClient::new(server.uri(), Some("secret".to_string()));
+    let resp = client.publish(sensor_event()).await.unwrap();
+
+    assert_eq!(resp.event_id, "evt-1");
+    assert_eq!(resp.stream, "sensors");
+}
+
+#[tokio::test]
+async fn test_publish_invalid_event_fails_locally() {
+    let server = MockServer::start().await;
+    // No mocks mounted: any request would get a 404
+    let client = FluxClient::new(server.uri(), None);
+
+    let mut event = sensor_event();
+    event.stream = "Not Valid".to_string();
+
+    match client.publish(event).await {
+        Err(FluxClientError::Validation(ValidationError::InvalidStreamFormat(s))) => {
+            assert_eq!(s, "Not Valid")
+        }
+        other => panic!("expected validation error, got {:?}", other),
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_publish_batch_reports_per_event_results() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/events/batch"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "successful": 1,
+            "failed": 1,
+            "results": [
+                {"eventId": "evt-1", "stream": "sensors", "error": null},
+                {"eventId": "evt-2", "stream": "sensors", "error": "rate limit exceeded"}
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let client = FluxClient::new(server.uri(), None);
+    let resp = client
+        .publish_batch(vec![sensor_event(), sensor_event()])
+        .await
+        .unwrap();
+
+    assert_eq!(resp.successful, 1);
+    assert_eq!(resp.failed, 1);
+    assert_eq!(
+        resp.results[1].error.as_deref(),
+        Some("rate limit exceeded")
+    );
+}
+
+#[tokio::test]
+async fn test_error_statuses_map_to_typed_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/events"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "60")
+                .set_body_json(json!({"error": "rate limit exceeded"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/state/entities/matt%2Fsensor-01"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_json(json!({"error": "Token does not own namespace"})),
+        )
+        .mount(&server)
+        .await;
+
+    let client = FluxClient::new(server.uri(), None);
+
+    match client.publish(sensor_event()).await {
+        Err(FluxClientError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(60)),
+        other => panic!("expected rate limit, got {:?}", other),
+    }
+
+    // Slash in the ID is percent-encoded so it stays one path segment
+    match client.delete_entity("matt/sensor-01").await {
+        Err(FluxClientError::Forbidden(msg)) => assert!(msg.contains("namespace")),
+        other => panic!("expected forbidden, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_get_entity_found_and_missing() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/state/entities/temp-sensor-01"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "temp-sensor-01",
+            "properties": {"temperature": 22.5},
+            "lastUpdated": "2026-01-01T00:00:00+00:00"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/state/entities/missing"))
+        .respond_with(
+            ResponseTemplate::new(404).set_body_json(json!({"error": "Entity not found"})),
+        )
+        .mount(&server)
+        .await;
+
+    let client = FluxClient::new(server.uri(), None);
+
+    let entity = client.get_entity("temp-sensor-01").await.unwrap().unwrap();
+    assert_eq!(entity.id, "temp-sensor-01");
+    assert_eq!(entity.properties["temperature"], json!(22.5));
+
+    assert!(client.get_entity("missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_list_entities_sends_filters() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/state/entities"))
+        .and(query_param("namespace", "matt"))
+        .and(query_param("prefix", "matt/sensor"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"id": "matt/sensor-01", "properties": {}, "lastUpdated": "2026-01-01T00:00:00Z"}
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = FluxClient::new(server.uri(), None);
+    let entities = client
+        .list_entities(&EntityFilter {
+            namespace: Some("matt".to_string()),
+            prefix: Some("matt/sensor".to_string()),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].id, "matt/sensor-01");
+}
@@ -0,0 +1,44 @@
+#!/usr/bin/env bash
+# Checks formatting, then builds and tests the flux crate under each
+# supported feature combination.
+#
+# The core build (state-engine alone) must compile, pass the engine and event
+# tests, and pull in neither SQLite nor an HTTP client. Run from the
+# repository root; CI can run it as is.
+set -euo pipefail
+
+run() {
+    echo "+ $*"
+    "$@"
+}
+
+# Formatting first: it is the cheapest check to fail
+run cargo fmt --all --check
+
+core=(--no-default-features --features state-engine)
+
+run cargo test -p flux "${core[@]}" --lib
+run cargo test -p flux "${core[@]}" --test body_size_test --test rate_limit_test
+run cargo bench -p flux "${core[@]}" --no-run
+
+forbidden=$(cargo tree -p flux "${core[@]}" -e normal --prefix none \
+    | grep -E '^(rusqlite|reqwest|aes-gcm|serde_urlencoded|tower-http) ' || true)
+if [ -n "$forbidden" ]; then
+    echo "core build depends on:" >&2
+    echo "$forbidden" >&2
+    exit 1
+fi
+
+# flux-client builds flux with state-engine only
+run cargo check -p flux-client --all-targets
+
+for features in webhook snapshot credentials "snapshot,credentials" http-api; do
+    run cargo check -p flux --no-default-features --features "$features" --all-targets
+done
+
+run cargo test -p flux --no-default-features --features snapshot --lib
+run cargo test -p flux --no-default-features --features http-api
+run cargo clippy --workspace --all-targets -- -D warnings
+# Compiles tests/integration; running it needs NATS (see the feature)
+run cargo clippy -p flux --all-targets --features integration-tests -- -D warnings
+run cargo test --workspace
@@ -1,13 +1,21 @@
-use crate::config::SharedRuntimeConfig;
+use crate::config::{ConfigSource, RuntimeConfig, SharedRuntimeConfig};
+use crate::entity::IdNormalization;
+use crate::event::SecondsTimestampPolicy;
+use crate::state::DeadbandRule;
+use crate::supervisor::{TaskInfo, TaskStatus, TaskSupervisor};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use utoipa::{OpenApi, ToSchema};
+
+pub use crate::config::RuntimeConfigUpdate;
 
 /// State for the admin API.
 #[derive(Clone)]
@@ -15,44 +23,113 @@ pub struct AdminAppState {
     pub runtime_config: SharedRuntimeConfig,
     /// Required bearer token for PUT /api/admin/config. None = PUT disabled.
     pub admin_token: Option<String>,
+    /// Background tasks listed and restarted by /api/admin/tasks
+    pub tasks: Arc<TaskSupervisor>,
 }
 
-/// Partial update body — only fields present in the request are changed.
-#[derive(Deserialize)]
-pub struct RuntimeConfigUpdate {
-    pub rate_limit_enabled: Option<bool>,
-    pub rate_limit_per_namespace_per_minute: Option<u64>,
-    pub body_size_limit_single_bytes: Option<usize>,
-    pub body_size_limit_batch_bytes: Option<usize>,
+/// Effective config plus where each field's value came from.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "rate_limit_enabled": true,
+    "rate_limit_per_namespace_per_minute": 10000,
+    "body_size_limit_single_bytes": 1048576,
+    "body_size_limit_batch_bytes": 10485760,
+    "metrics_broadcast_interval_seconds": 2,
+    "active_publisher_window_seconds": 10,
+    "snapshot_interval_minutes": 5,
+    "entity_ttl_seconds": 0,
+    "max_payload_bytes": 262144,
+    "max_properties_per_event": 256,
+    "max_property_name_length": 256,
+    "max_string_value_length": 65536,
+    "max_timestamp_skew_seconds": 300,
+    "seconds_timestamp_policy": "convert",
+    "entity_id_normalization": {},
+    "deadband_by_namespace": {"sensors": {"min_change": 0.1, "keepalive_seconds": 300}},
+    "deadband_by_stream": {},
+    "indexed_properties": ["status"],
+    "sources": {"rate_limit_enabled": "default", "entity_ttl_seconds": "admin-api"}
+}))]
+pub(crate) struct ConfigResponse {
+    #[serde(flatten)]
+    pub(crate) config: RuntimeConfig,
+    sources: BTreeMap<String, ConfigSource>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[schema(as = AdminErrorResponse)]
 struct ErrorResponse {
     error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
 }
 
+/// OpenAPI description of the admin endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_config, put_config, list_tasks, restart_task),
+    components(schemas(
+        ConfigResponse,
+        TaskInfo,
+        TaskStatus,
+        RuntimeConfig,
+        ConfigSource,
+        SecondsTimestampPolicy,
+        IdNormalization,
+        DeadbandRule,
+        RuntimeConfigUpdate,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct AdminApi;
+
 pub fn create_admin_router(state: AdminAppState) -> Router {
     Router::new()
         .route(
             "/api/admin/config",
-            get(get_config).put(put_config),
+            get(get_config).put(put_config).patch(put_config),
         )
+        .route("/api/admin/tasks", get(list_tasks))
+        .route("/api/admin/tasks/:name/restart", post(restart_task))
         .with_state(Arc::new(state))
 }
 
-/// GET /api/admin/config — returns current RuntimeConfig.
-async fn get_config(
-    State(state): State<Arc<AdminAppState>>,
-) -> Response {
+/// GET /api/admin/config — returns current RuntimeConfig with a source per field.
+#[utoipa::path(
+    get,
+    path = "/api/admin/config",
+    tag = "admin",
+    responses((status = 200, description = "Effective runtime config", body = ConfigResponse))
+)]
+async fn get_config(State(state): State<Arc<AdminAppState>>) -> Response {
     let cfg = state
         .runtime_config
         .read()
         .expect("RuntimeConfig lock poisoned")
         .clone();
-    Json(cfg).into_response()
+    Json(ConfigResponse {
+        config: cfg,
+        sources: state.runtime_config.sources(),
+    })
+    .into_response()
 }
 
-/// PUT /api/admin/config — partial update. Requires FLUX_ADMIN_TOKEN bearer.
+/// PUT/PATCH /api/admin/config — partial update. Requires FLUX_ADMIN_TOKEN bearer.
+///
+/// Fields are range-checked; an invalid value returns 422 naming the field and
+/// leaves the config unchanged. Successful updates notify long-running tasks.
+#[utoipa::path(
+    put,
+    path = "/api/admin/config",
+    tag = "admin",
+    request_body = RuntimeConfigUpdate,
+    responses(
+        (status = 200, description = "Updated runtime config", body = ConfigResponse),
+        (status = 401, description = "Admin token required", body = AdminErrorResponse),
+        (status = 422, description = "Value out of range; `field` names it", body = AdminErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
 async fn put_config(
     State(state): State<Arc<AdminAppState>>,
     headers: HeaderMap,
@@ -64,36 +141,76 @@ async fn put_config(
             StatusCode::UNAUTHORIZED,
             Json(ErrorResponse {
                 error: "Unauthorized".to_string(),
+                field: None,
             }),
         )
             .into_response();
     }
 
-    // Apply partial update
-    let mut cfg = state
-        .runtime_config
-        .write()
-        .expect("RuntimeConfig lock poisoned");
-
-    if let Some(v) = update.rate_limit_enabled {
-        cfg.rate_limit_enabled = v;
-    }
-    if let Some(v) = update.rate_limit_per_namespace_per_minute {
-        cfg.rate_limit_per_namespace_per_minute = v;
-    }
-    if let Some(v) = update.body_size_limit_single_bytes {
-        cfg.body_size_limit_single_bytes = v;
-    }
-    if let Some(v) = update.body_size_limit_batch_bytes {
-        cfg.body_size_limit_batch_bytes = v;
+    // Validate and apply partial update
+    match state.runtime_config.apply_update(&update) {
+        Ok(cfg) => Json(ConfigResponse {
+            config: cfg,
+            sources: state.runtime_config.sources(),
+        })
+        .into_response(),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse {
+                error: e.message,
+                field: Some(e.field.to_string()),
+            }),
+        )
+            .into_response(),
     }
+}
+
+/// GET /api/admin/tasks — supervised background tasks, their restarts and last panic.
+#[utoipa::path(
+    get,
+    path = "/api/admin/tasks",
+    tag = "admin",
+    responses((status = 200, description = "Supervised background tasks", body = [TaskInfo]))
+)]
+async fn list_tasks(State(state): State<Arc<AdminAppState>>) -> Response {
+    Json(state.tasks.tasks()).into_response()
+}
 
-    Json(cfg.clone()).into_response()
+/// POST /api/admin/tasks/:name/restart — stop a task and start a fresh run.
+/// Requires FLUX_ADMIN_TOKEN bearer.
+///
+/// A task waiting out its restart backoff starts at once. The new run starts
+/// in the background; `GET /api/admin/tasks` shows it.
+#[utoipa::path(
+    post,
+    path = "/api/admin/tasks/{name}/restart",
+    tag = "admin",
+    params(("name" = String, Path, description = "Task name, e.g. `metrics_broadcaster`")),
+    responses(
+        (status = 202, description = "Restart requested"),
+        (status = 401, description = "Admin token required", body = AdminErrorResponse),
+        (status = 404, description = "No task with that name", body = AdminErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn restart_task(
+    State(state): State<Arc<AdminAppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Response {
+    let (status, error) = if !validate_admin_token(&headers, &state.admin_token) {
+        (StatusCode::UNAUTHORIZED, "Unauthorized".to_string())
+    } else if !state.tasks.restart(&name) {
+        (StatusCode::NOT_FOUND, format!("No task named '{}'", name))
+    } else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+    (status, Json(ErrorResponse { error, field: None })).into_response()
 }
 
 /// Returns true if the bearer token in `Authorization` matches the expected admin token.
 /// Returns true (no restriction) when `expected` is None.
-fn validate_admin_token(headers: &HeaderMap, expected: &Option<String>) -> bool {
+pub(crate) fn validate_admin_token(headers: &HeaderMap, expected: &Option<String>) -> bool {
     let Some(expected_token) = expected else {
         // No admin token configured → PUT is unrestricted (dev mode)
         return true;
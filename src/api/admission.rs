@@ -0,0 +1,218 @@
+use crate::api::auth_middleware::{authorize_event_entity, AuthError};
+use crate::config::SharedRuntimeConfig;
+use crate::entity::parse_entity_id;
+use crate::event::{FluxEvent, RawFluxEvent, ValidationError};
+use crate::namespace::NamespaceRegistry;
+use crate::rate_limit::RateLimiter;
+use crate::state::QUOTAS_STREAM;
+use axum::http::{HeaderMap, StatusCode};
+use chrono::Utc;
+use std::fmt;
+use std::sync::Arc;
+
+/// Checks an event must pass before it is published
+///
+/// Shared by `POST /api/events`, the batch endpoint and the NATS ingester,
+/// so an event is accepted or turned away the same way whichever path it
+/// took.
+#[derive(Clone)]
+pub struct EventAdmission {
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    pub auth_enabled: bool,
+    pub runtime_config: SharedRuntimeConfig,
+    pub rate_limiter: Arc<RateLimiter>,
+}
+
+/// Why an event was not admitted
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// Body exceeds `body_size_limit_single_bytes`
+    BodyTooLarge,
+    /// Body is not a JSON event
+    Malformed(String),
+    /// Body uses a `Content-Encoding` other than gzip or deflate
+    UnsupportedEncoding(String),
+    /// Event failed validation, a limit or the timestamp rules
+    Invalid(ValidationError),
+    /// Token missing or not allowed to write the event's entity
+    Unauthorized(AuthError),
+    /// Namespace is over its rate limit
+    RateLimited,
+}
+
+impl Rejection {
+    /// Status the HTTP API answers with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Rejection::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Rejection::Malformed(_) => StatusCode::BAD_REQUEST,
+            Rejection::UnsupportedEncoding(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Rejection::Invalid(ValidationError::PayloadTooLarge { .. }) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Rejection::Invalid(e) if e.is_limit_exceeded() || e.is_timestamp_rejected() => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Rejection::Invalid(_) => StatusCode::BAD_REQUEST,
+            Rejection::Unauthorized(AuthError::InvalidToken(_))
+            | Rejection::Unauthorized(AuthError::NamespaceNotFound(_)) => StatusCode::UNAUTHORIZED,
+            // Token is valid but the target entity isn't provably in its namespace
+            Rejection::Unauthorized(AuthError::InvalidEntityId(_))
+            | Rejection::Unauthorized(AuthError::Forbidden(_)) => StatusCode::FORBIDDEN,
+            Rejection::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+/// The message the HTTP API puts in its `error` body
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::BodyTooLarge => write!(f, "payload too large"),
+            Rejection::Malformed(msg) => write!(f, "{}", msg),
+            Rejection::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported Content-Encoding '{}'", encoding)
+            }
+            Rejection::Invalid(e) => write!(f, "{}", e),
+            Rejection::Unauthorized(
+                AuthError::InvalidToken(msg)
+                | AuthError::InvalidEntityId(msg)
+                | AuthError::NamespaceNotFound(msg)
+                | AuthError::Forbidden(msg),
+            ) => write!(f, "{}", msg),
+            Rejection::RateLimited => write!(f, "rate limit exceeded"),
+        }
+    }
+}
+
+impl EventAdmission {
+    /// Parse a single-event body and admit the event
+    pub fn admit_body(&self, body: &[u8], headers: &HeaderMap) -> Result<FluxEvent, Rejection> {
+        let limit = self
+            .runtime_config
+            .read()
+            .unwrap()
+            .body_size_limit_single_bytes;
+        if body.len() > limit {
+            return Err(Rejection::BodyTooLarge);
+        }
+
+        let mut event: FluxEvent =
+            serde_json::from_slice(body).map_err(|e| Rejection::Malformed(e.to_string()))?;
+        self.admit(&mut event, headers, Utc::now().timestamp_millis())?;
+        Ok(event)
+    }
+
+    /// [`admit_body`](Self::admit_body) without parsing the payload, which
+    /// stays borrowed from `body`
+    pub fn admit_raw_body<'a>(
+        &self,
+        body: &'a [u8],
+        headers: &HeaderMap,
+    ) -> Result<RawFluxEvent<'a>, Rejection> {
+        let (size_limit, limits, timestamp_rules) = {
+            let config = self.runtime_config.read().unwrap();
+            (
+                config.body_size_limit_single_bytes,
+                config.event_limits(),
+                config.timestamp_rules(),
+            )
+        };
+        if body.len() > size_limit {
+            return Err(Rejection::BodyTooLarge);
+        }
+
+        let mut event =
+            RawFluxEvent::from_slice(body).map_err(|e| Rejection::Malformed(e.to_string()))?;
+        let received_at = Utc::now().timestamp_millis();
+        event
+            .validate_and_prepare()
+            .and_then(|_| event.check_limits(&limits))
+            .and_then(|_| event.check_timestamp(&timestamp_rules, received_at))
+            .map_err(Rejection::Invalid)?;
+
+        let entity_id = event.entity_id();
+        self.authorize(&event.stream, entity_id.as_deref(), headers)?;
+        event.received_at = Some(received_at);
+        Ok(event)
+    }
+
+    /// Validate and prepare `event`, then authorize and rate limit it
+    ///
+    /// Assigns a UUIDv7 if the event has no ID, checks the runtime-configured
+    /// limits and timestamp rules against `received_at`, and records
+    /// `received_at` on success.
+    pub fn admit(
+        &self,
+        event: &mut FluxEvent,
+        headers: &HeaderMap,
+        received_at: i64,
+    ) -> Result<(), Rejection> {
+        let (limits, timestamp_rules) = {
+            let config = self.runtime_config.read().unwrap();
+            (config.event_limits(), config.timestamp_rules())
+        };
+        event
+            .validate_and_prepare()
+            .and_then(|_| event.check_limits(&limits))
+            .and_then(|_| event.check_timestamp(&timestamp_rules, received_at))
+            .map_err(Rejection::Invalid)?;
+
+        let entity_id = event.payload.get("entity_id").and_then(|v| v.as_str());
+        self.authorize(&event.stream, entity_id, headers)?;
+
+        event.received_at = Some(received_at);
+        Ok(())
+    }
+
+    /// Reserved streams, authorization and rate limit, for a validated event
+    /// on `stream` writing `entity_id`
+    fn authorize(
+        &self,
+        stream: &str,
+        entity_id: Option<&str>,
+        headers: &HeaderMap,
+    ) -> Result<(), Rejection> {
+        // Quotas are set through the admin API only
+        if stream == QUOTAS_STREAM {
+            return Err(Rejection::Unauthorized(AuthError::Forbidden(format!(
+                "stream '{}' is reserved for namespace quotas",
+                QUOTAS_STREAM
+            ))));
+        }
+
+        authorize_event_entity(
+            headers,
+            entity_id,
+            &self.namespace_registry,
+            self.auth_enabled,
+        )
+        .map_err(Rejection::Unauthorized)?;
+
+        // Rate limit check (auth-gated: only active when auth is enabled)
+        if self.auth_enabled {
+            let namespace = rate_limit_namespace(stream, entity_id);
+            let limit = self
+                .runtime_config
+                .read()
+                .unwrap()
+                .rate_limit_per_namespace_per_minute;
+            if !self.rate_limiter.check_and_consume(&namespace, limit) {
+                return Err(Rejection::RateLimited);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Namespace of the event's entity_id, falling back to stream name.
+///
+/// Used for rate-limit bucket keying. If entity_id is missing or has no namespace
+/// prefix, we fall back to the stream field so rate limiting still applies.
+fn rate_limit_namespace(stream: &str, entity_id: Option<&str>) -> String {
+    entity_id
+        .and_then(|eid| parse_entity_id(eid).ok())
+        .and_then(|parsed| parsed.namespace)
+        .unwrap_or_else(|| stream.to_string())
+}
@@ -1,15 +1,23 @@
 use crate::auth::extract_bearer_token;
 use crate::entity::parse_entity_id;
 use crate::event::FluxEvent;
-use crate::namespace::{AuthError as NamespaceAuthError, NamespaceRegistry};
-use axum::http::HeaderMap;
+use crate::namespace::{
+    grants::prefix_covers, AuthError as NamespaceAuthError, GrantScope, NamespaceRegistry,
+};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
 use std::sync::Arc;
 
 #[cfg(test)]
 mod tests;
 
 /// Authorization errors
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AuthError {
     /// Missing or invalid Authorization header
     InvalidToken(String),
@@ -56,50 +64,235 @@ pub fn authorize_event(
     event: &FluxEvent,
     registry: &Arc<NamespaceRegistry>,
     auth_enabled: bool,
+) -> Result<(), AuthError> {
+    let entity_id = event.payload.get("entity_id").and_then(|v| v.as_str());
+    authorize_event_entity(headers, entity_id, registry, auth_enabled)
+}
+
+/// [`authorize_event`] given the payload's `entity_id` (`None` if missing
+/// or not a string)
+pub fn authorize_event_entity(
+    headers: &HeaderMap,
+    entity_id: Option<&str>,
+    registry: &Arc<NamespaceRegistry>,
+    auth_enabled: bool,
 ) -> Result<(), AuthError> {
     // If auth disabled, allow all
     if !auth_enabled {
         return Ok(());
     }
 
+    // The entity_id is the key the state engine writes to, so it (not
+    // stream/key/source) is what must be authorized.
+    let entity_id = entity_id.ok_or_else(|| {
+        AuthError::InvalidEntityId("Missing 'entity_id' field in payload".to_string())
+    })?;
+
+    authorize_entity_write(headers, entity_id, registry)?;
+
+    Ok(())
+}
+
+/// Authorize a write (publish or delete) to a single entity ID
+///
+/// Extracts the bearer token, parses the namespace prefix of `entity_id`, and
+/// checks the token owns it, or is a write grant covering `entity_id`.
+/// Returns the namespace on success.
+///
+/// Policy: entity IDs without a namespace prefix are rejected rather than being
+/// mapped into the caller's namespace. Silently rewriting IDs would make the
+/// stored key differ from what the publisher sent, and unprefixed IDs are
+/// reserved for internal (auth-disabled) deployments.
+///
+/// # Errors
+/// - InvalidToken: Missing or malformed Authorization header
+/// - InvalidEntityId: Invalid format or missing namespace prefix
+/// - NamespaceNotFound: Namespace doesn't exist in registry
+/// - Forbidden: Token doesn't own the namespace
+pub fn authorize_entity_write(
+    headers: &HeaderMap,
+    entity_id: &str,
+    registry: &Arc<NamespaceRegistry>,
+) -> Result<String, AuthError> {
     // Extract bearer token from Authorization header
-    let token = extract_bearer_token(headers)
-        .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
-
-    // Extract entity_id from event payload
-    let entity_id = event
-        .payload
-        .get("entity_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| {
-            AuthError::InvalidEntityId("Missing 'entity_id' field in payload".to_string())
+    let token =
+        extract_bearer_token(headers).map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+
+    let namespace = entity_namespace(entity_id)?;
+
+    if let Some(grant) = registry.lookup_grant(&token) {
+        if !grant.allows_write(entity_id) {
+            return Err(AuthError::Forbidden(format!(
+                "Token does not have permission to write to '{}'",
+                entity_id
+            )));
+        }
+        return Ok(grant.namespace);
+    }
+
+    // Validate token owns namespace
+    registry
+        .validate_token(&token, &namespace)
+        .map_err(|e| match e {
+            NamespaceAuthError::NamespaceNotFound => AuthError::NamespaceNotFound(format!(
+                "Namespace '{}' not found. Get a namespace at flux-universe.com",
+                namespace
+            )),
+            NamespaceAuthError::Unauthorized => AuthError::Forbidden(format!(
+                "Token does not have permission to write to namespace '{}'",
+                namespace
+            )),
         })?;
 
-    // Parse namespace from entity_id
+    Ok(namespace)
+}
+
+/// Namespace prefix of `entity_id`, the part before its first '/'
+///
+/// Owner tokens and grants are checked against the same namespace, so an ID
+/// like `alice/github/repo/x` is in `alice` for both.
+///
+/// # Errors
+/// - InvalidEntityId: Invalid format or missing namespace prefix
+fn entity_namespace(entity_id: &str) -> Result<String, AuthError> {
     let parsed = parse_entity_id(entity_id).map_err(|e| {
-        AuthError::InvalidEntityId(format!("Failed to parse entity_id '{}': {:?}", entity_id, e))
+        AuthError::InvalidEntityId(format!(
+            "Failed to parse entity_id '{}': {:?}",
+            entity_id, e
+        ))
     })?;
 
     // If no namespace prefix, reject in auth mode (must use namespace/entity format)
-    let namespace = parsed.namespace.ok_or_else(|| {
+    parsed.namespace.ok_or_else(|| {
         AuthError::InvalidEntityId(format!(
             "Entity ID '{}' missing namespace prefix (expected 'namespace/entity' format)",
             entity_id
         ))
-    })?;
+    })
+}
 
-    // Validate token owns namespace
-    registry.validate_token(&token, &namespace).map_err(|e| {
-        match e {
-            NamespaceAuthError::NamespaceNotFound => {
-                AuthError::NamespaceNotFound(format!("Namespace '{}' not found. Get a namespace at flux-universe.com", namespace))
+/// What a read-side caller (WebSocket, query) is allowed to see
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthScope {
+    /// Every entity — auth disabled, or the admin token
+    All,
+    /// Only entities prefixed with this namespace
+    Namespace(String),
+    /// Only entities under an access grant's prefix
+    Grant {
+        namespace: String,
+        /// Entity ID prefix, e.g. `alice/public/`
+        prefix: String,
+        scope: GrantScope,
+    },
+}
+
+impl AuthScope {
+    /// True if `entity_id` is visible in this scope.
+    ///
+    /// Unprefixed entity IDs belong to no namespace and are only visible to `All`.
+    pub fn allows(&self, entity_id: &str) -> bool {
+        match self {
+            AuthScope::All => true,
+            AuthScope::Namespace(ns) => {
+                entity_namespace(entity_id).is_ok_and(|namespace| &namespace == ns)
             }
-            NamespaceAuthError::Unauthorized => AuthError::Forbidden(format!(
-                "Token does not have permission to write to namespace '{}'",
-                namespace
-            )),
+            AuthScope::Grant { prefix, .. } => prefix_covers(prefix, entity_id),
         }
-    })?;
+    }
 
-    Ok(())
+    /// True if `entity_id` may be changed in this scope; read grants change nothing
+    pub fn allows_write(&self, entity_id: &str) -> bool {
+        match self {
+            AuthScope::Grant {
+                scope: GrantScope::Read,
+                ..
+            } => false,
+            _ => self.allows(entity_id),
+        }
+    }
+
+    /// Namespace the scope is confined to, None for `All`
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            AuthScope::All => None,
+            AuthScope::Namespace(namespace) | AuthScope::Grant { namespace, .. } => Some(namespace),
+        }
+    }
+}
+
+/// Resolve a token to the scope it may read
+///
+/// The admin token (if configured) sees everything; a namespace token sees
+/// its own namespace and a grant token what is under the grant's prefix.
+/// Shared by HTTP handlers and WebSocket subscriptions.
+///
+/// # Errors
+/// - InvalidToken: Token matches neither the admin token, a namespace nor an
+///   unexpired grant
+pub fn resolve_scope(
+    token: &str,
+    registry: &NamespaceRegistry,
+    admin_token: Option<&str>,
+) -> Result<AuthScope, AuthError> {
+    if admin_token == Some(token) {
+        return Ok(AuthScope::All);
+    }
+
+    if let Some(ns) = registry.lookup_by_token(token) {
+        return Ok(AuthScope::Namespace(ns.name));
+    }
+    registry
+        .lookup_grant(token)
+        .map(|grant| AuthScope::Grant {
+            prefix: grant.entity_prefix(),
+            namespace: grant.namespace,
+            scope: grant.scope,
+        })
+        .ok_or_else(|| AuthError::InvalidToken("Unknown token".to_string()))
+}
+
+/// Router state that can resolve a request's [`AuthScope`]
+pub trait ReadAuthState {
+    fn auth_enabled(&self) -> bool;
+    fn namespace_registry(&self) -> &NamespaceRegistry;
+    fn admin_token(&self) -> Option<&str>;
+}
+
+/// Extracts the caller's read scope from the `Authorization` header.
+///
+/// With auth disabled every request gets [`AuthScope::All`]. Otherwise a
+/// bearer token is required and resolved with [`resolve_scope`]; handlers
+/// apply the scope while filtering rather than after.
+#[async_trait]
+impl<S> FromRequestParts<Arc<S>> for AuthScope
+where
+    S: ReadAuthState + Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<S>,
+    ) -> Result<Self, Self::Rejection> {
+        if !state.auth_enabled() {
+            return Ok(AuthScope::All);
+        }
+
+        let token = extract_bearer_token(&parts.headers)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+        resolve_scope(&token, state.namespace_registry(), state.admin_token())
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidEntityId(_) => StatusCode::BAD_REQUEST,
+            AuthError::NamespaceNotFound(_) => StatusCode::NOT_FOUND,
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+        };
+        (status, Json(json!({"error": self.to_string()}))).into_response()
+    }
 }
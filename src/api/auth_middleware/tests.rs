@@ -10,6 +10,7 @@ fn create_test_event(entity_id: &str) -> FluxEvent {
         stream: "test".to_string(),
         source: "test".to_string(),
         timestamp: 1234567890,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({
@@ -165,10 +166,185 @@ fn test_auth_enabled_invalid_namespace_format() {
 #[test]
 fn test_auth_enabled_multiple_slashes() {
     let registry = Arc::new(NamespaceRegistry::new());
-    let event = create_test_event("matt/sensors/temperature"); // Too many slashes
-    let headers = create_auth_headers("test-token");
+    let matt = registry.register("matt").unwrap();
+    let event = create_test_event("matt/sensors/temperature"); // Namespace is "matt"
 
-    // Should fail due to invalid entity_id format
-    let result = authorize_event(&headers, &event, &registry, true);
+    let result = authorize_event(&create_auth_headers(&matt.token), &event, &registry, true);
+    assert!(result.is_ok());
+
+    let result = authorize_event(&create_auth_headers("test-token"), &event, &registry, true);
+    assert!(matches!(result, Err(AuthError::Forbidden(_))));
+}
+
+#[test]
+fn test_cross_namespace_payload_entity_id_rejected() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    let alice = registry.register("alice").unwrap();
+    registry.register("bob").unwrap();
+
+    // Stream, key, and source all claim alice, but the state engine keys off
+    // payload.entity_id — which targets bob
+    let mut event = create_test_event("bob/secret-device");
+    event.stream = "alice".to_string();
+    event.key = Some("alice/secret-device".to_string());
+    event.source = "alice".to_string();
+
+    let result = authorize_event(&create_auth_headers(&alice.token), &event, &registry, true);
+    assert!(matches!(result, Err(AuthError::Forbidden(_))));
+}
+
+#[test]
+fn test_authorize_entity_write_returns_namespace() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    let alice = registry.register("alice").unwrap();
+
+    let namespace = authorize_entity_write(
+        &create_auth_headers(&alice.token),
+        "alice/device",
+        &registry,
+    )
+    .unwrap();
+    assert_eq!(namespace, "alice");
+}
+
+#[test]
+fn test_authorize_entity_write_rejects_unprefixed() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    let alice = registry.register("alice").unwrap();
+
+    // Unprefixed IDs are rejected, not mapped into the caller's namespace
+    let result = authorize_entity_write(&create_auth_headers(&alice.token), "device", &registry);
     assert!(matches!(result, Err(AuthError::InvalidEntityId(_))));
 }
+
+#[test]
+fn test_resolve_scope() {
+    let registry = NamespaceRegistry::new();
+    let alice = registry.register("alice").unwrap();
+
+    assert_eq!(
+        resolve_scope(&alice.token, &registry, Some("admin-secret")).unwrap(),
+        AuthScope::Namespace("alice".to_string())
+    );
+    assert_eq!(
+        resolve_scope("admin-secret", &registry, Some("admin-secret")).unwrap(),
+        AuthScope::All
+    );
+    assert!(matches!(
+        resolve_scope("bogus", &registry, Some("admin-secret")),
+        Err(AuthError::InvalidToken(_))
+    ));
+    // Without a configured admin token nothing resolves to All
+    assert!(resolve_scope("admin-secret", &registry, None).is_err());
+}
+
+#[test]
+fn test_auth_scope_allows() {
+    let scope = AuthScope::Namespace("alice".to_string());
+    assert!(scope.allows("alice/sensor-01"));
+    assert!(!scope.allows("bob/sensor-01"));
+    assert!(!scope.allows("sensor-01"));
+    assert!(!scope.allows("alicex/sensor-01"));
+
+    assert!(AuthScope::All.allows("bob/sensor-01"));
+    assert!(AuthScope::All.allows("sensor-01"));
+}
+
+#[test]
+fn test_write_grant_is_confined_to_prefix() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    registry.register("alice").unwrap();
+    registry.register("bob").unwrap();
+    let grant = registry
+        .create_grant("alice", "public/", GrantScope::Write, None)
+        .unwrap();
+    let headers = create_auth_headers(&grant.token);
+    let write = |entity_id: &str| authorize_entity_write(&headers, entity_id, &registry);
+
+    assert_eq!(write("alice/public/sensor-01"), Ok("alice".to_string()));
+    assert_eq!(write("alice/public/rooms/kitchen"), Ok("alice".to_string()));
+    assert_eq!(write("alice/public/./sensor-01"), Ok("alice".to_string()));
+
+    for outside in [
+        "alice/secret",
+        "alice/publicity",
+        "alice/public",
+        "alice/public/../secret",
+        "alice/public/rooms/../../secret",
+        "bob/public/sensor-01",
+        "public/sensor-01",
+    ] {
+        assert!(
+            matches!(write(outside), Err(AuthError::Forbidden(_))),
+            "{} should be forbidden",
+            outside
+        );
+    }
+}
+
+#[test]
+fn test_owner_writes_paths_a_grant_covers() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    let alice = registry.register("alice").unwrap();
+    registry
+        .create_grant("alice", "public/", GrantScope::Write, None)
+        .unwrap();
+    let headers = create_auth_headers(&alice.token);
+
+    for entity_id in ["alice/public/rooms/kitchen", "alice/secret/sensor-01"] {
+        assert_eq!(
+            authorize_entity_write(&headers, entity_id, &registry),
+            Ok("alice".to_string())
+        );
+    }
+    let scope = resolve_scope(&alice.token, &registry, None).unwrap();
+    assert!(scope.allows_write("alice/public/rooms/kitchen"));
+    assert!(!scope.allows("bob/public/rooms/kitchen"));
+}
+
+#[test]
+fn test_read_grant_cannot_write() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    registry.register("alice").unwrap();
+    let grant = registry
+        .create_grant("alice", "public/", GrantScope::Read, None)
+        .unwrap();
+    let headers = create_auth_headers(&grant.token);
+
+    let result = authorize_entity_write(&headers, "alice/public/sensor-01", &registry);
+    assert!(matches!(result, Err(AuthError::Forbidden(_))));
+
+    let scope = resolve_scope(&grant.token, &registry, None).unwrap();
+    assert!(scope.allows("alice/public/sensor-01"));
+    assert!(!scope.allows_write("alice/public/sensor-01"));
+    assert!(!scope.allows("alice/public/../secret"));
+    assert!(!scope.allows("alice/secret"));
+    assert_eq!(scope.namespace(), Some("alice"));
+}
+
+#[test]
+fn test_expired_and_revoked_grants_are_unknown_tokens() {
+    let registry = Arc::new(NamespaceRegistry::new());
+    registry.register("alice").unwrap();
+    let expired = registry
+        .create_grant(
+            "alice",
+            "public/",
+            GrantScope::Write,
+            Some(chrono::Duration::seconds(-1)),
+        )
+        .unwrap();
+    let revoked = registry
+        .create_grant("alice", "public/", GrantScope::Write, None)
+        .unwrap();
+    assert!(registry.revoke_grant("alice", &revoked.id));
+
+    for token in [&expired.token, &revoked.token] {
+        assert!(matches!(
+            resolve_scope(token, &registry, None),
+            Err(AuthError::InvalidToken(_))
+        ));
+        let result = authorize_entity_write(&create_auth_headers(token), "alice/x", &registry);
+        assert!(matches!(result, Err(AuthError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::api::admin::validate_admin_token;
+use crate::api::openapi::ErrorResponse;
+use crate::compaction::{CompactOptions, CompactionError, CompactionReport, Compactor};
+use crate::leader::Leadership;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use std::sync::Arc;
+use tracing::{error, info};
+use utoipa::OpenApi;
+
+/// Shared state for the compaction endpoint
+pub struct CompactionAppState {
+    pub compactor: Arc<Compactor>,
+    /// Only the leader compacts
+    pub leadership: Leadership,
+    /// Required bearer token. None = unrestricted (dev mode)
+    pub admin_token: Option<String>,
+}
+
+/// OpenAPI description of the compaction endpoint
+#[derive(OpenApi)]
+#[openapi(
+    paths(compact),
+    components(schemas(CompactOptions, CompactionReport, ErrorResponse))
+)]
+pub(crate) struct CompactionApi;
+
+/// Create compaction router
+pub fn create_compaction_router(state: Arc<CompactionAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/compact", post(compact))
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/compact - Compact the event stream
+///
+/// Replaces every event up to the end of the stream with one event per
+/// entity, then purges the originals. The body is optional; without it the
+/// run is neither a dry run nor forced. Runs to completion before answering.
+#[utoipa::path(
+    post,
+    path = "/api/admin/compact",
+    tag = "admin",
+    request_body(content = Option<CompactOptions>, description = "Dry run and force flags"),
+    responses(
+        (status = 200, description = "Compaction report", body = CompactionReport),
+        (status = 400, description = "Invalid body", body = ErrorResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 409, description = "Not the leader, replaying, already running, or the result would change some entity's state", body = ErrorResponse),
+        (status = 500, description = "Event stream unavailable", body = ErrorResponse),
+        (status = 503, description = "State engine did not catch up; nothing was purged", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn compact(
+    State(state): State<Arc<CompactionAppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+    let options: CompactOptions = if body.iter().all(u8::is_ascii_whitespace) {
+        CompactOptions::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(options) => options,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, &format!("Invalid body: {}", e))
+            }
+        }
+    };
+    if !state.leadership.is_leader() {
+        return error_response(
+            StatusCode::CONFLICT,
+            "Only the leader compacts; send the request to the leader",
+        );
+    }
+
+    match state.compactor.run(options).await {
+        Ok(report) => {
+            info!(
+                dry_run = report.dry_run,
+                events_read = report.events_read,
+                events_after = report.events_after,
+                purged_before = ?report.purged_before,
+                "Compaction requested through API complete"
+            );
+            Json(report).into_response()
+        }
+        Err(CompactionError::Failed(e)) => {
+            error!(error = %e, "Compaction failed");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Compaction failed")
+        }
+        Err(e @ CompactionError::EngineBehind { .. }) => {
+            error_response(StatusCode::SERVICE_UNAVAILABLE, &e.to_string())
+        }
+        Err(e) => error_response(StatusCode::CONFLICT, &e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::archiver::{ArchiveSource, StreamBounds};
+    use crate::archive::StoredEvent;
+    use crate::compaction::job::CompactionStream;
+    use crate::compaction::CompactionConfig;
+    use crate::state::StateEngine;
+    use anyhow::Result;
+    use axum::body::Body;
+    use axum::http::Request;
+    use futures::future::BoxFuture;
+    use tower::ServiceExt;
+
+    /// A stream with nothing in it
+    struct EmptyStream;
+
+    impl ArchiveSource for EmptyStream {
+        fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+            Box::pin(async {
+                Ok(StreamBounds {
+                    first_sequence: 1,
+                    last_sequence: 0,
+                })
+            })
+        }
+
+        fn read(&self, _start: u64, _max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn purge_before(&self, _sequence: u64) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    impl CompactionStream for EmptyStream {
+        fn publish_after(
+            &self,
+            _subject: String,
+            _through: u64,
+            _payload: Vec<u8>,
+            _expected_last: u64,
+        ) -> BoxFuture<'_, Result<Option<u64>>> {
+            Box::pin(async { Ok(None) })
+        }
+    }
+
+    fn app(engine: Arc<StateEngine>, leadership: Leadership) -> Router {
+        let compactor = Compactor::new(Arc::new(EmptyStream), engine, CompactionConfig::default());
+        create_compaction_router(Arc::new(CompactionAppState {
+            compactor: Arc::new(compactor),
+            leadership,
+            admin_token: Some("admin".to_string()),
+        }))
+    }
+
+    fn request(token: Option<&str>, body: &str) -> Request<Body> {
+        let mut request = Request::post("/api/admin/compact");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requires_admin_token_and_leadership() {
+        let engine = Arc::new(StateEngine::new());
+        engine.set_live();
+
+        let follower = app(
+            Arc::clone(&engine),
+            Leadership::standalone_after("flux-1", std::future::pending()),
+        );
+        let response = follower.clone().oneshot(request(None, "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = follower.oneshot(request(Some("admin"), "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let leader = app(engine, Leadership::standalone("flux-0"));
+        let response = leader
+            .clone()
+            .oneshot(request(Some("admin"), "{\"dry_run\": 1}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = leader
+            .oneshot(request(Some("admin"), "{\"dry_run\": true}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: CompactionReport = serde_json::from_slice(&body).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.events_read, 0);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_while_replaying() {
+        let app = app(
+            Arc::new(StateEngine::new()),
+            Leadership::standalone("flux-0"),
+        );
+        let response = app.oneshot(request(Some("admin"), "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
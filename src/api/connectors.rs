@@ -17,6 +17,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
+use utoipa::{OpenApi, ToSchema};
 
 #[cfg(test)]
 mod tests;
@@ -30,7 +31,8 @@ pub struct ConnectorAppState {
 }
 
 /// Connector status summary (for list endpoint)
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[schema(example = json!({"name": "github", "enabled": true, "status": "configured"}))]
 pub struct ConnectorSummary {
     pub name: String,
     pub enabled: bool,
@@ -38,7 +40,15 @@ pub struct ConnectorSummary {
 }
 
 /// Detailed connector status (for single connector endpoint)
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[schema(example = json!({
+    "name": "github",
+    "enabled": true,
+    "status": "configured",
+    "poll_interval_seconds": 300,
+    "granted_scopes": ["repo", "read:user"],
+    "missing_scopes": ["notifications"]
+}))]
 pub struct ConnectorDetail {
     pub name: String,
     pub enabled: bool,
@@ -48,40 +58,65 @@ pub struct ConnectorDetail {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_error: Option<String>,
     pub poll_interval_seconds: u64,
+    /// Scopes the OAuth provider granted (omitted for PATs, or if it didn't say)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted_scopes: Option<Vec<String>>,
+    /// Requested scopes that were not granted; re-authorize to get them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_scopes: Vec<String>,
 }
 
 /// List connectors response
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"connectors": [{"name": "github", "enabled": false, "status": "not_configured"}]}))]
 pub struct ListConnectorsResponse {
     pub connectors: Vec<ConnectorSummary>,
 }
 
 /// Error response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
 /// Request body for POST /api/connectors/:name/token
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({"token": "ghp_xxxxxxxxxxxxxxxxxxxx"}))]
 pub struct TokenRequest {
     pub token: String,
 }
 
 /// Response for POST /api/connectors/:name/token
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct StoreTokenResponse {
     pub success: bool,
 }
 
 /// Response for DELETE /api/connectors/:name/token
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct DeleteTokenResponse {
     pub success: bool,
 }
 
 /// Available connectors (Phase 1: hardcoded from ADR-005)
-const AVAILABLE_CONNECTORS: &[&str] = &["github", "gmail", "linkedin", "calendar"];
+const AVAILABLE_CONNECTORS: &[&str] =
+    &["github", "gmail", "linkedin", "calendar", "notion", "jira"];
+
+/// OpenAPI description of the connector status endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(list_connectors, get_connector, store_token, delete_token),
+    components(schemas(
+        ConnectorSummary,
+        ConnectorDetail,
+        ListConnectorsResponse,
+        TokenRequest,
+        StoreTokenResponse,
+        DeleteTokenResponse,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct ConnectorApi;
 
 /// Create connector API router
 pub fn create_connector_router(state: ConnectorAppState) -> Router {
@@ -93,29 +128,62 @@ pub fn create_connector_router(state: ConnectorAppState) -> Router {
         .with_state(Arc::new(state))
 }
 
+/// Namespace a connector request acts for
+pub(crate) struct ConnectorOwner {
+    /// Credential-store user ID: the namespace name, or "default" without auth
+    pub namespace: String,
+    /// Namespace token the connector-manager publishes with (auth mode only)
+    pub flux_token: Option<String>,
+}
+
+/// Resolves the caller's namespace from its bearer token.
+///
+/// Without auth every request acts for the "default" namespace. Errors are
+/// messages for a 401.
+pub(crate) fn connector_owner(
+    headers: &HeaderMap,
+    auth_enabled: bool,
+    registry: &NamespaceRegistry,
+) -> Result<ConnectorOwner, String> {
+    if !auth_enabled {
+        return Ok(ConnectorOwner {
+            namespace: "default".to_string(),
+            flux_token: None,
+        });
+    }
+    let token = extract_bearer_token(headers).map_err(|e| format!("Invalid token: {}", e))?;
+    let namespace = registry
+        .lookup_by_token(&token)
+        .ok_or_else(|| "Invalid token: no namespace owns it".to_string())?;
+    Ok(ConnectorOwner {
+        namespace: namespace.name,
+        flux_token: Some(token),
+    })
+}
+
 /// GET /api/connectors - List all available connectors
 ///
 /// Returns status for all connectors. If auth is enabled, only shows
 /// connectors for the authenticated user's namespace.
+#[utoipa::path(
+    get,
+    path = "/api/connectors",
+    tag = "connectors",
+    responses(
+        (status = 200, description = "All connectors", body = ListConnectorsResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn list_connectors(
     State(state): State<Arc<ConnectorAppState>>,
     headers: HeaderMap,
 ) -> Result<Json<ListConnectorsResponse>, AppError> {
-    // Extract user namespace (if auth enabled)
-    let user_namespace = if state.auth_enabled {
-        let token = extract_bearer_token(&headers)
-            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
-
-        // Validate token and get namespace
-        // For now, we extract the namespace from the token itself (it's the namespace ID)
-        // In production, you might want to look this up in the registry
-        Some(token)
-    } else {
-        None
-    };
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
 
     debug!(
-        namespace = ?user_namespace,
+        namespace = %owner.namespace,
         "Listing connectors"
     );
 
@@ -135,55 +203,28 @@ async fn list_connectors(
     };
 
     // Check which connectors have credentials
-    let connectors = if let Some(namespace) = user_namespace {
-        // Get user's configured connectors
-        let configured = credential_store
-            .list_by_user(&namespace)
-            .unwrap_or_else(|e| {
-                warn!(error = %e, "Failed to list user connectors");
-                vec![]
-            });
-
-        AVAILABLE_CONNECTORS
-            .iter()
-            .map(|name| {
-                let has_credentials = configured.contains(&name.to_string());
-                ConnectorSummary {
-                    name: name.to_string(),
-                    enabled: has_credentials,
-                    status: if has_credentials {
-                        "configured".to_string()
-                    } else {
-                        "not_configured".to_string()
-                    },
-                }
-            })
-            .collect()
-    } else {
-        // Auth disabled: check credentials under "default" namespace
-        let configured = credential_store
-            .list_by_user("default")
-            .unwrap_or_else(|e| {
-                warn!(error = %e, "Failed to list default connectors");
-                vec![]
-            });
-
-        AVAILABLE_CONNECTORS
-            .iter()
-            .map(|name| {
-                let has_credentials = configured.contains(&name.to_string());
-                ConnectorSummary {
-                    name: name.to_string(),
-                    enabled: has_credentials,
-                    status: if has_credentials {
-                        "configured".to_string()
-                    } else {
-                        "not_configured".to_string()
-                    },
-                }
-            })
-            .collect()
-    };
+    let configured = credential_store
+        .list_by_user(&owner.namespace)
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to list user connectors");
+            vec![]
+        });
+
+    let connectors = AVAILABLE_CONNECTORS
+        .iter()
+        .map(|name| {
+            let has_credentials = configured.contains(&name.to_string());
+            ConnectorSummary {
+                name: name.to_string(),
+                enabled: has_credentials,
+                status: if has_credentials {
+                    "configured".to_string()
+                } else {
+                    "not_configured".to_string()
+                },
+            }
+        })
+        .collect();
 
     Ok(Json(ListConnectorsResponse { connectors }))
 }
@@ -191,6 +232,18 @@ async fn list_connectors(
 /// GET /api/connectors/:name - Get detailed status for specific connector
 ///
 /// Returns detailed status including poll interval and any error information.
+#[utoipa::path(
+    get,
+    path = "/api/connectors/{name}",
+    tag = "connectors",
+    params(("name" = String, Path, description = "Connector name")),
+    responses(
+        (status = 200, description = "Connector detail", body = ConnectorDetail),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn get_connector(
     State(state): State<Arc<ConnectorAppState>>,
     headers: HeaderMap,
@@ -204,51 +257,54 @@ async fn get_connector(
         )));
     }
 
-    // Extract user namespace (if auth enabled)
-    let user_namespace = if state.auth_enabled {
-        let token = extract_bearer_token(&headers)
-            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
-        Some(token)
-    } else {
-        None
-    };
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
 
     debug!(
         connector = %name,
-        namespace = ?user_namespace,
+        namespace = %owner.namespace,
         "Getting connector status"
     );
 
     // Default poll intervals (Phase 1: hardcoded, will come from connector config later)
     let poll_interval = match name.as_str() {
-        "github" => 300,      // 5 minutes
-        "gmail" => 60,        // 1 minute
-        "linkedin" => 600,    // 10 minutes
-        "calendar" => 300,    // 5 minutes
+        "github" => 300,   // 5 minutes
+        "gmail" => 60,     // 1 minute
+        "linkedin" => 600, // 10 minutes
+        "calendar" => 300, // 5 minutes
+        "notion" => 300,   // 5 minutes
+        "jira" => 120,     // 2 minutes
         _ => 300,
     };
 
+    let scope_grant = state
+        .credential_store
+        .as_ref()
+        .and_then(|store| {
+            store
+                .get_scope_grant(&owner.namespace, &name)
+                .inspect_err(|e| warn!(error = %e, "Failed to fetch granted scopes"))
+                .ok()
+        })
+        .flatten()
+        .unwrap_or_default();
+
     // Check if credentials exist
     let (enabled, status) = if let Some(credential_store) = &state.credential_store {
-        if let Some(namespace) = user_namespace {
-            match credential_store.get(&namespace, &name) {
-                Ok(Some(_credentials)) => {
-                    // Credentials exist - connector is configured
-                    // Phase 1: We don't have manager integration yet, so just report "configured"
-                    (true, "configured".to_string())
-                }
-                Ok(None) => {
-                    // No credentials
-                    (false, "not_configured".to_string())
-                }
-                Err(e) => {
-                    warn!(error = %e, "Failed to fetch credentials");
-                    (false, "error".to_string())
-                }
+        match credential_store.get(&owner.namespace, &name) {
+            Ok(Some(_credentials)) => {
+                // Credentials exist - connector is configured
+                // Phase 1: We don't have manager integration yet, so just report "configured"
+                (true, "configured".to_string())
+            }
+            Ok(None) => {
+                // No credentials
+                (false, "not_configured".to_string())
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch credentials");
+                (false, "error".to_string())
             }
-        } else {
-            // Auth disabled: no user context
-            (false, "not_configured".to_string())
         }
     } else {
         // No credential store available
@@ -259,16 +315,32 @@ async fn get_connector(
         name,
         enabled,
         status,
-        last_poll: None,      // Phase 1: No manager integration yet
-        last_error: None,     // Phase 1: No manager integration yet
+        last_poll: None,  // Phase 1: No manager integration yet
+        last_error: None, // Phase 1: No manager integration yet
         poll_interval_seconds: poll_interval,
+        missing_scopes: scope_grant.missing(),
+        granted_scopes: scope_grant.granted,
     }))
 }
 
 /// POST /api/connectors/:name/token - Store a PAT for a connector
 ///
 /// Stores a personal access token as credentials. Uses "default" namespace
-/// when auth is disabled, bearer token namespace when auth is enabled.
+/// when auth is disabled, bearer token namespace when auth is enabled; the
+/// bearer token is recorded so the connector-manager can publish with it.
+#[utoipa::path(
+    post,
+    path = "/api/connectors/{name}/token",
+    tag = "connectors",
+    params(("name" = String, Path, description = "Connector name")),
+    request_body = TokenRequest,
+    responses(
+        (status = 200, description = "Token stored", body = StoreTokenResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Unknown connector", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn store_token(
     State(state): State<Arc<ConnectorAppState>>,
     headers: HeaderMap,
@@ -290,13 +362,9 @@ async fn store_token(
         )
     })?;
 
-    // Determine namespace
-    let namespace = if state.auth_enabled {
-        extract_bearer_token(&headers)
-            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?
-    } else {
-        "default".to_string()
-    };
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
+    let namespace = owner.namespace;
 
     debug!(
         connector = %name,
@@ -316,6 +384,18 @@ async fn store_token(
             warn!(error = %e, "Failed to store credentials");
             AppError::InternalServerError("Failed to store credentials".to_string())
         })?;
+    if let Some(flux_token) = &owner.flux_token {
+        credential_store
+            .set_flux_token(&namespace, &name, flux_token)
+            .map_err(|e| {
+                warn!(error = %e, "Failed to store flux token");
+                AppError::InternalServerError("Failed to store credentials".to_string())
+            })?;
+    }
+    // Scopes of an earlier OAuth grant don't describe the PAT
+    if let Err(e) = credential_store.set_scope_grant(&namespace, &name, None) {
+        warn!(error = %e, "Failed to clear granted scopes");
+    }
 
     info!(
         connector = %name,
@@ -330,6 +410,18 @@ async fn store_token(
 ///
 /// Deletes the credential from the store. Returns 404 if no credential exists.
 /// Uses "default" namespace when auth is disabled, bearer token namespace when enabled.
+#[utoipa::path(
+    delete,
+    path = "/api/connectors/{name}/token",
+    tag = "connectors",
+    params(("name" = String, Path, description = "Connector name")),
+    responses(
+        (status = 200, description = "Credentials removed", body = DeleteTokenResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No stored credentials", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn delete_token(
     State(state): State<Arc<ConnectorAppState>>,
     headers: HeaderMap,
@@ -350,13 +442,9 @@ async fn delete_token(
         )
     })?;
 
-    // Determine namespace
-    let namespace = if state.auth_enabled {
-        extract_bearer_token(&headers)
-            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?
-    } else {
-        "default".to_string()
-    };
+    let namespace = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?
+        .namespace;
 
     debug!(
         connector = %name,
@@ -364,12 +452,10 @@ async fn delete_token(
         "Deleting token for connector"
     );
 
-    let deleted = credential_store
-        .delete(&namespace, &name)
-        .map_err(|e| {
-            warn!(error = %e, "Failed to delete credentials");
-            AppError::InternalServerError("Failed to delete credentials".to_string())
-        })?;
+    let deleted = credential_store.delete(&namespace, &name).map_err(|e| {
+        warn!(error = %e, "Failed to delete credentials");
+        AppError::InternalServerError("Failed to delete credentials".to_string())
+    })?;
 
     if !deleted {
         return Err(AppError::NotFound(format!(
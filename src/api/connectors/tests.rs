@@ -25,6 +25,8 @@ fn test_connector_detail_serialization() {
         last_poll: Some("2026-02-17T10:30:00Z".to_string()),
         last_error: None,
         poll_interval_seconds: 300,
+        granted_scopes: Some(vec!["repo".to_string()]),
+        missing_scopes: vec!["notifications".to_string()],
     };
 
     let json = serde_json::to_string(&detail).unwrap();
@@ -34,6 +36,8 @@ fn test_connector_detail_serialization() {
     assert!(json.contains("\"last_poll\":\"2026-02-17T10:30:00Z\""));
     assert!(!json.contains("\"last_error\"")); // Should be omitted when None
     assert!(json.contains("\"poll_interval_seconds\":300"));
+    assert!(json.contains("\"granted_scopes\":[\"repo\"]"));
+    assert!(json.contains("\"missing_scopes\":[\"notifications\"]"));
 }
 
 #[test]
@@ -45,12 +49,16 @@ fn test_connector_detail_optional_fields() {
         last_poll: None,
         last_error: None,
         poll_interval_seconds: 60,
+        granted_scopes: None,
+        missing_scopes: vec![],
     };
 
     let json = serde_json::to_string(&detail).unwrap();
     // Optional fields should not appear when None
     assert!(!json.contains("\"last_poll\""));
     assert!(!json.contains("\"last_error\""));
+    assert!(!json.contains("\"granted_scopes\""));
+    assert!(!json.contains("\"missing_scopes\""));
 }
 
 #[test]
@@ -79,11 +87,13 @@ fn test_list_connectors_response_serialization() {
 #[test]
 fn test_available_connectors_list() {
     // Verify expected connectors from ADR-005
-    assert_eq!(AVAILABLE_CONNECTORS.len(), 4);
+    assert_eq!(AVAILABLE_CONNECTORS.len(), 6);
     assert!(AVAILABLE_CONNECTORS.contains(&"github"));
     assert!(AVAILABLE_CONNECTORS.contains(&"gmail"));
     assert!(AVAILABLE_CONNECTORS.contains(&"linkedin"));
     assert!(AVAILABLE_CONNECTORS.contains(&"calendar"));
+    assert!(AVAILABLE_CONNECTORS.contains(&"notion"));
+    assert!(AVAILABLE_CONNECTORS.contains(&"jira"));
 }
 
 #[test]
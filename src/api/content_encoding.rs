@@ -0,0 +1,108 @@
+//! `Content-Encoding` support for request bodies.
+//!
+//! Ingestion accepts gzip and deflate bodies. The size limits apply to the
+//! decoded body, and decoding stops as soon as it passes the limit, so a
+//! small compressed body can't expand into an unbounded allocation.
+
+use crate::api::admission::Rejection;
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::io::Read;
+
+/// `body` decoded per its `Content-Encoding`, if at most `limit` bytes
+pub(crate) fn decode_body(
+    headers: &HeaderMap,
+    body: Bytes,
+    limit: usize,
+) -> Result<Bytes, Rejection> {
+    let encoding = headers.get(header::CONTENT_ENCODING).map(|v| {
+        String::from_utf8_lossy(v.as_bytes())
+            .trim()
+            .to_ascii_lowercase()
+    });
+    match encoding.as_deref() {
+        None | Some("") | Some("identity") => Ok(body),
+        Some("gzip") | Some("x-gzip") => read_limited(GzDecoder::new(&body[..]), limit),
+        Some("deflate") => read_limited(ZlibDecoder::new(&body[..]), limit),
+        Some(other) => Err(Rejection::UnsupportedEncoding(other.to_string())),
+    }
+}
+
+fn read_limited(decoder: impl Read, limit: usize) -> Result<Bytes, Rejection> {
+    let mut decoded = Vec::new();
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| Rejection::Malformed(format!("invalid compressed body: {}", e)))?;
+    if decoded.len() > limit {
+        return Err(Rejection::BodyTooLarge);
+    }
+    Ok(decoded.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn headers(encoding: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_ENCODING, encoding.parse().unwrap());
+        headers
+    }
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap().into()
+    }
+
+    #[test]
+    fn test_decodes_gzip_and_deflate() {
+        let body = br#"{"events": []}"#;
+        assert_eq!(
+            decode_body(&headers("gzip"), gzip(body), 1024).unwrap(),
+            &body[..]
+        );
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        let deflated = encoder.finish().unwrap().into();
+        assert_eq!(
+            decode_body(&headers("deflate"), deflated, 1024).unwrap(),
+            &body[..]
+        );
+
+        let plain = Bytes::from_static(body);
+        assert_eq!(
+            decode_body(&HeaderMap::new(), plain, 1024).unwrap(),
+            &body[..]
+        );
+    }
+
+    #[test]
+    fn test_zip_bomb_is_cut_off_at_limit() {
+        // 64 MB of zeros compresses to about 64 KB
+        let bomb = gzip(&vec![0u8; 64 << 20]);
+        assert!(bomb.len() < 1 << 20);
+        assert_eq!(
+            decode_body(&headers("gzip"), bomb, 1 << 20),
+            Err(Rejection::BodyTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_encoding_and_corrupt_body() {
+        assert_eq!(
+            decode_body(&headers("br"), Bytes::from_static(b"x"), 1024),
+            Err(Rejection::UnsupportedEncoding("br".to_string()))
+        );
+        assert!(matches!(
+            decode_body(&headers("gzip"), Bytes::from_static(b"not gzip"), 1024),
+            Err(Rejection::Malformed(_))
+        ));
+    }
+}
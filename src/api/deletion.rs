@@ -1,8 +1,9 @@
-use crate::entity::parse_entity_id;
+use crate::api::auth_middleware::{authorize_entity_write, resolve_scope, AuthError, AuthScope};
+use crate::api::openapi::ErrorResponse;
 use crate::event::FluxEvent;
-use crate::namespace::NamespaceRegistry;
+use crate::namespace::{GrantScope, NamespaceRegistry};
 use crate::nats::EventPublisher;
-use crate::state::StateEngine;
+use crate::state::{Entity, StateEngine};
 use axum::{
     extract::{Path, State},
     http::{HeaderMap, StatusCode},
@@ -10,9 +11,13 @@ use axum::{
     routing::delete,
     Router,
 };
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
 
 /// Shared state for deletion API
 #[derive(Clone)]
@@ -21,19 +26,34 @@ pub struct DeletionAppState {
     pub namespace_registry: Arc<NamespaceRegistry>,
     pub state_engine: Arc<StateEngine>,
     pub auth_enabled: bool,
+    /// Lets filter deletes span every namespace
+    pub admin_token: Option<String>,
     pub max_batch_delete: usize,
 }
 
 /// Response for single entity deletion
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"entity_id": "matt/sensor-01", "eventId": "01936f8e-7c2a-7000-8000-000000000000"}))]
 pub struct DeleteResponse {
     pub entity_id: String,
     #[serde(rename = "eventId")]
     pub event_id: String,
 }
 
+/// Response for undeleting an entity
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"entity_id": "matt/sensor-01", "eventId": "01936f8e-7c2a-7000-8000-000000000000", "properties": 4}))]
+pub struct UndeleteResponse {
+    pub entity_id: String,
+    #[serde(rename = "eventId")]
+    pub event_id: String,
+    /// Number of properties restored
+    pub properties: usize,
+}
+
 /// Response for batch deletion
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"deleted": 2, "failed": 0, "errors": []}))]
 pub struct BatchDeleteResponse {
     pub deleted: usize,
     pub failed: usize,
@@ -41,14 +61,15 @@ pub struct BatchDeleteResponse {
 }
 
 /// Batch delete request
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({"namespace": "matt"}))]
 pub struct BatchDeleteRequest {
     #[serde(flatten)]
     pub filter: DeleteFilter,
 }
 
 /// Filter for batch deletion
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum DeleteFilter {
     Namespace { namespace: String },
@@ -56,12 +77,91 @@ pub enum DeleteFilter {
     EntityIds { entity_ids: Vec<String> },
 }
 
+/// Filter-based delete request
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "prefix": "tmp/",
+    "properties": {"status": "offline"},
+    "older_than_seconds": 3600,
+    "dry_run": true
+}))]
+pub struct FilterDeleteRequest {
+    /// Entity ID prefix to match (required; use namespace prefix like "tmp/")
+    pub prefix: String,
+    /// Property equality conditions (all must match)
+    #[serde(default)]
+    #[schema(value_type = HashMap<String, Object>)]
+    pub properties: HashMap<String, Value>,
+    /// Only match entities not updated within this many seconds
+    pub older_than_seconds: Option<u64>,
+    /// Count matches without deleting
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl FilterDeleteRequest {
+    /// Check whether an entity matches prefix, property, and staleness conditions
+    pub fn matches(&self, entity: &Entity, now: DateTime<Utc>) -> bool {
+        if !entity.id.starts_with(&self.prefix) {
+            return false;
+        }
+
+        if !self
+            .properties
+            .iter()
+            .all(|(key, expected)| entity.properties.get(key) == Some(expected))
+        {
+            return false;
+        }
+
+        if let Some(secs) = self.older_than_seconds {
+            // No entity is older than a cutoff before the earliest representable time
+            let age = Duration::seconds(secs.min(i64::MAX as u64 / 1_000) as i64);
+            match now.checked_sub_signed(age) {
+                Some(cutoff) if entity.last_updated <= cutoff => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Response for filter-based deletion
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"matched": 12, "deleted": 0, "failed": 0, "dry_run": true, "errors": []}))]
+pub struct FilterDeleteResponse {
+    pub matched: usize,
+    pub deleted: usize,
+    pub failed: usize,
+    pub dry_run: bool,
+    pub errors: Vec<String>,
+}
+
 /// DELETE /api/state/entities/:id - Delete single entity
+#[utoipa::path(
+    delete,
+    path = "/api/state/entities/{id}",
+    tag = "deletion",
+    params(("id" = String, Path, description = "Entity ID (percent-encode `/`)")),
+    responses(
+        (status = 200, description = "Tombstone published", body = DeleteResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Token does not own namespace", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn delete_entity(
     State(state): State<Arc<DeletionAppState>>,
     headers: HeaderMap,
     Path(entity_id): Path<String>,
 ) -> Result<Json<DeleteResponse>, DeletionError> {
+    // Target the ID the entity is stored under, as the query API does
+    let entity_id = state
+        .state_engine
+        .normalize_entity_id(&entity_id)
+        .into_owned();
+
     // Authorize if auth is enabled
     if state.auth_enabled {
         authorize_deletion(&headers, &entity_id, &state.namespace_registry)?;
@@ -76,7 +176,78 @@ async fn delete_entity(
     }))
 }
 
+/// POST /api/state/entities/:id/undelete - Restore a deleted entity
+///
+/// Republishes the entity's last properties from the trash, so replay sees the
+/// restore after the tombstone. Entities leave the trash once their retention
+/// (`trash_retention_seconds`) runs out or they are written again.
+#[utoipa::path(
+    post,
+    path = "/api/state/entities/{id}/undelete",
+    tag = "deletion",
+    params(("id" = String, Path, description = "Entity ID (percent-encode `/`)")),
+    responses(
+        (status = 200, description = "Restore event published", body = UndeleteResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Token does not own namespace", body = ErrorResponse),
+        (status = 404, description = "Entity not in the trash", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn undelete_entity(
+    State(state): State<Arc<DeletionAppState>>,
+    headers: HeaderMap,
+    Path(entity_id): Path<String>,
+) -> Result<Json<UndeleteResponse>, DeletionError> {
+    let entity_id = state
+        .state_engine
+        .normalize_entity_id(&entity_id)
+        .into_owned();
+
+    // Authorize first, so the trash of another namespace isn't revealed
+    if state.auth_enabled {
+        authorize_deletion(&headers, &entity_id, &state.namespace_registry)?;
+    }
+
+    let deleted = state
+        .state_engine
+        .get_deleted_entity(&entity_id)
+        .ok_or_else(|| {
+            DeletionError::NotFound(format!("Entity '{}' is not in the trash", entity_id))
+        })?;
+
+    let mut event = FluxEvent::undelete(&entity_id, &deleted.entity.properties, "api");
+    event
+        .validate_and_prepare()
+        .map_err(|e| DeletionError::PublishError(e.to_string()))?;
+    state
+        .event_publisher
+        .publish(&event)
+        .await
+        .map_err(|e| DeletionError::PublishError(e.to_string()))?;
+
+    info!(entity_id = %entity_id, "Entity undeleted");
+    Ok(Json(UndeleteResponse {
+        entity_id,
+        event_id: event.event_id.unwrap(),
+        properties: deleted.entity.properties.len(),
+    }))
+}
+
 /// POST /api/state/entities/delete - Batch delete entities
+#[utoipa::path(
+    post,
+    path = "/api/state/entities/delete",
+    tag = "deletion",
+    request_body = BatchDeleteRequest,
+    responses(
+        (status = 200, description = "Deletion summary", body = BatchDeleteResponse),
+        (status = 400, description = "Batch exceeds max_batch_delete", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Token does not own namespace", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn delete_batch(
     State(state): State<Arc<DeletionAppState>>,
     headers: HeaderMap,
@@ -85,22 +256,28 @@ async fn delete_batch(
     // Get entities matching filter
     let entities_to_delete = match &request.filter {
         DeleteFilter::Namespace { namespace } => {
-            let all_entities = state.state_engine.get_all_entities();
-            all_entities
-                .into_iter()
-                .filter(|e| e.id.starts_with(&format!("{}/", namespace)))
-                .map(|e| e.id)
-                .collect::<Vec<_>>()
+            let ns_prefix = format!("{}/", namespace);
+            let mut ids = Vec::new();
+            state.state_engine.for_each_entity(|e| {
+                if e.id.starts_with(&ns_prefix) {
+                    ids.push(e.id.clone());
+                }
+            });
+            ids
         }
         DeleteFilter::Prefix { prefix } => {
-            let all_entities = state.state_engine.get_all_entities();
-            all_entities
-                .into_iter()
-                .filter(|e| e.id.starts_with(prefix))
-                .map(|e| e.id)
-                .collect::<Vec<_>>()
+            let mut ids = Vec::new();
+            state.state_engine.for_each_entity(|e| {
+                if e.id.starts_with(prefix.as_str()) {
+                    ids.push(e.id.clone());
+                }
+            });
+            ids
         }
-        DeleteFilter::EntityIds { entity_ids } => entity_ids.clone(),
+        DeleteFilter::EntityIds { entity_ids } => entity_ids
+            .iter()
+            .map(|id| state.state_engine.normalize_entity_id(id).into_owned())
+            .collect(),
     };
 
     // Validate batch size
@@ -140,68 +317,171 @@ async fn delete_batch(
     }))
 }
 
-/// Authorize deletion (check namespace ownership)
-fn authorize_deletion(
-    headers: &HeaderMap,
-    entity_id: &str,
-    registry: &Arc<NamespaceRegistry>,
-) -> Result<(), DeletionError> {
-    // Extract bearer token
-    let token = extract_bearer_token(headers)?;
+/// POST /api/state/entities/delete-by-filter - Delete entities matching a filter
+///
+/// Matches by prefix, optional property equality, and optional staleness.
+/// Tombstones are published as batches of up to max_batch_delete, one batch
+/// at a time; `dry_run` only counts.
+#[utoipa::path(
+    post,
+    path = "/api/state/entities/delete-by-filter",
+    tag = "deletion",
+    request_body = FilterDeleteRequest,
+    responses(
+        (status = 200, description = "Match and deletion counts", body = FilterDeleteResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Prefix outside the token's namespace or grant, or a read grant", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn delete_by_filter(
+    State(state): State<Arc<DeletionAppState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<FilterDeleteRequest>,
+) -> Result<Json<FilterDeleteResponse>, DeletionError> {
+    // Restrict the filter to what the caller may delete if auth is enabled
+    let scope = if state.auth_enabled {
+        let token = crate::auth::extract_bearer_token(&headers)
+            .map_err(|e| DeletionError::Unauthorized(e.to_string()))?;
+        let scope = resolve_scope(
+            &token,
+            &state.namespace_registry,
+            state.admin_token.as_deref(),
+        )?;
+        request.prefix = scope_prefix_to_caller(&scope, &request.prefix)?;
+        scope
+    } else {
+        AuthScope::All
+    };
 
-    // Parse entity ID to get namespace
-    let parsed = parse_entity_id(entity_id)
-        .map_err(|e| DeletionError::InvalidEntityId(format!("{:?}", e)))?;
+    let now = Utc::now();
+    let mut matched_ids = Vec::new();
+    state.state_engine.for_each_entity(|entity| {
+        if request.matches(entity, now) && scope.allows_write(&entity.id) {
+            matched_ids.push(entity.id.clone());
+        }
+    });
+
+    let matched = matched_ids.len();
+    if request.dry_run {
+        return Ok(Json(FilterDeleteResponse {
+            matched,
+            deleted: 0,
+            failed: 0,
+            dry_run: true,
+            errors: Vec::new(),
+        }));
+    }
 
-    let namespace = parsed
-        .namespace
-        .ok_or_else(|| DeletionError::Unauthorized("Entity has no namespace".to_string()))?;
+    let mut deleted = 0;
+    let mut failed = 0;
+    let mut errors = Vec::new();
 
-    // Validate token owns namespace
-    registry
-        .validate_token(&token, &namespace)
-        .map_err(|_| DeletionError::Forbidden("Token does not own namespace".to_string()))?;
+    // Each chunk is one batch publish, so at most max_batch_delete acks are
+    // awaited at a time
+    for chunk in matched_ids.chunks(state.max_batch_delete.max(1)) {
+        let mut ids = Vec::with_capacity(chunk.len());
+        let mut tombstones = Vec::with_capacity(chunk.len());
+        for entity_id in chunk {
+            let mut event = FluxEvent::tombstone(entity_id, "api");
+            match event.validate_and_prepare() {
+                Ok(()) => {
+                    ids.push(entity_id);
+                    tombstones.push(event);
+                }
+                Err(e) => {
+                    failed += 1;
+                    errors.push(format!(
+                        "{}: {:?}",
+                        entity_id,
+                        DeletionError::PublishError(e.to_string())
+                    ));
+                }
+            }
+        }
+        let results = state.event_publisher.publish_batch(&tombstones).await;
+        for (entity_id, result) in ids.into_iter().zip(results) {
+            match result {
+                Ok(()) => deleted += 1,
+                Err(e) => {
+                    failed += 1;
+                    errors.push(format!(
+                        "{}: {:?}",
+                        entity_id,
+                        DeletionError::PublishError(e.to_string())
+                    ));
+                }
+            }
+        }
+        info!(
+            chunk = chunk.len(),
+            deleted = deleted,
+            matched = matched,
+            "Filter delete chunk published"
+        );
+    }
 
-    Ok(())
+    Ok(Json(FilterDeleteResponse {
+        matched,
+        deleted,
+        failed,
+        dry_run: false,
+        errors,
+    }))
 }
 
-/// Extract bearer token from Authorization header
-fn extract_bearer_token(headers: &HeaderMap) -> Result<String, DeletionError> {
-    let auth_header = headers
-        .get("authorization")
-        .ok_or_else(|| DeletionError::Unauthorized("Missing Authorization header".to_string()))?
-        .to_str()
-        .map_err(|_| DeletionError::Unauthorized("Invalid Authorization header".to_string()))?;
+/// Narrow a filter prefix to what `scope` (see [`resolve_scope`]) may delete
+///
+/// The admin token keeps any prefix. For a namespace token (or a write grant),
+/// a prefix inside the namespace ("ns/tmp-") or grant is kept; a broader one
+/// ("" or "n") is narrowed to "ns/" (or the grant's prefix); a prefix pointing
+/// elsewhere is rejected, as are read grants.
+fn scope_prefix_to_caller(scope: &AuthScope, prefix: &str) -> Result<String, DeletionError> {
+    let allowed = match scope {
+        AuthScope::All => return Ok(prefix.to_string()),
+        AuthScope::Namespace(namespace) => format!("{}/", namespace),
+        AuthScope::Grant {
+            scope: GrantScope::Read,
+            ..
+        } => {
+            return Err(DeletionError::Forbidden(
+                "Read grants cannot delete entities".to_string(),
+            ))
+        }
+        AuthScope::Grant { prefix, .. } => prefix.clone(),
+    };
 
-    if let Some(token) = auth_header.strip_prefix("Bearer ") {
-        Ok(token.to_string())
+    if prefix.starts_with(&allowed) {
+        Ok(prefix.to_string())
+    } else if allowed.starts_with(prefix) {
+        Ok(allowed)
     } else {
-        Err(DeletionError::Unauthorized(
-            "Invalid Authorization format".to_string(),
-        ))
+        Err(DeletionError::Forbidden(format!(
+            "Prefix '{}' is outside '{}'",
+            prefix, allowed
+        )))
     }
 }
 
+/// Authorize deletion (check namespace ownership)
+///
+/// Shares the ingestion write check: the token must own the entity's namespace
+/// prefix, and unprefixed entity IDs are rejected.
+fn authorize_deletion(
+    headers: &HeaderMap,
+    entity_id: &str,
+    registry: &Arc<NamespaceRegistry>,
+) -> Result<(), DeletionError> {
+    authorize_entity_write(headers, entity_id, registry)?;
+    Ok(())
+}
+
 /// Publish tombstone event to NATS
 async fn publish_tombstone(
     publisher: &EventPublisher,
     entity_id: &str,
 ) -> Result<String, DeletionError> {
-    let mut event = FluxEvent {
-        event_id: None,
-        stream: "flux.events.deletions".to_string(),
-        source: "api".to_string(),
-        timestamp: Utc::now().timestamp_millis(),
-        key: Some(entity_id.to_string()),
-        schema: None,
-        payload: serde_json::json!({
-            "entity_id": entity_id,
-            "properties": {
-                "__deleted__": true,
-                "__deleted_at__": Utc::now().timestamp_millis()
-            }
-        }),
-    };
+    let mut event = FluxEvent::tombstone(entity_id, "api");
 
     // Validate and generate event ID
     event
@@ -217,11 +497,39 @@ async fn publish_tombstone(
     Ok(event.event_id.unwrap())
 }
 
+/// OpenAPI description of the deletion endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(delete_entity, undelete_entity, delete_batch, delete_by_filter),
+    components(schemas(
+        DeleteResponse,
+        UndeleteResponse,
+        BatchDeleteRequest,
+        DeleteFilter,
+        BatchDeleteResponse,
+        FilterDeleteRequest,
+        FilterDeleteResponse,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct DeletionApi;
+
 /// Create deletion API router
 pub fn create_deletion_router(state: DeletionAppState) -> Router {
     Router::new()
         .route("/api/state/entities/:id", delete(delete_entity))
-        .route("/api/state/entities/delete", axum::routing::post(delete_batch))
+        .route(
+            "/api/state/entities/:id/undelete",
+            axum::routing::post(undelete_entity),
+        )
+        .route(
+            "/api/state/entities/delete",
+            axum::routing::post(delete_batch),
+        )
+        .route(
+            "/api/state/entities/delete-by-filter",
+            axum::routing::post(delete_by_filter),
+        )
         .with_state(Arc::new(state))
 }
 
@@ -231,19 +539,35 @@ pub enum DeletionError {
     Unauthorized(String),
     Forbidden(String),
     InvalidEntityId(String),
+    NotFound(String),
     BatchTooLarge { requested: usize, max: usize },
     PublishError(String),
 }
 
+impl From<AuthError> for DeletionError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::InvalidToken(msg) => DeletionError::Unauthorized(msg),
+            AuthError::InvalidEntityId(msg) => DeletionError::Forbidden(msg),
+            AuthError::NamespaceNotFound(msg) => DeletionError::Forbidden(msg),
+            AuthError::Forbidden(msg) => DeletionError::Forbidden(msg),
+        }
+    }
+}
+
 impl IntoResponse for DeletionError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             DeletionError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             DeletionError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             DeletionError::InvalidEntityId(msg) => (StatusCode::BAD_REQUEST, msg),
+            DeletionError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             DeletionError::BatchTooLarge { requested, max } => (
                 StatusCode::BAD_REQUEST,
-                format!("Batch too large: {} entities requested, max is {}", requested, max),
+                format!(
+                    "Batch too large: {} entities requested, max is {}",
+                    requested, max
+                ),
             ),
             DeletionError::PublishError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
@@ -288,4 +612,255 @@ mod tests {
             _ => panic!("Expected entity_ids filter"),
         }
     }
+
+    fn auth_headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_authorize_deletion_rejects_cross_namespace() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let alice = registry.register("alice").unwrap();
+        registry.register("bob").unwrap();
+
+        let result =
+            authorize_deletion(&auth_headers(&alice.token), "bob/secret-device", &registry);
+        assert!(matches!(result, Err(DeletionError::Forbidden(_))));
+
+        // Own namespace is allowed
+        assert!(authorize_deletion(&auth_headers(&alice.token), "alice/device", &registry).is_ok());
+    }
+
+    #[test]
+    fn test_authorize_deletion_rejects_unprefixed_entity() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let alice = registry.register("alice").unwrap();
+
+        let result = authorize_deletion(&auth_headers(&alice.token), "secret-device", &registry);
+        assert!(matches!(result, Err(DeletionError::Forbidden(_))));
+    }
+
+    fn make_entity(id: &str, props: serde_json::Value, last_updated: DateTime<Utc>) -> Entity {
+        Entity {
+            id: id.to_string(),
+            properties: serde_json::from_value(props).unwrap(),
+            last_updated,
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter_request_deserialization() {
+        let json = r#"{"prefix": "tmp/", "older_than_seconds": 604800, "dry_run": true}"#;
+        let request: FilterDeleteRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.prefix, "tmp/");
+        assert_eq!(request.older_than_seconds, Some(604800));
+        assert!(request.dry_run);
+        assert!(request.properties.is_empty());
+    }
+
+    #[test]
+    fn test_filter_matches_prefix() {
+        let now = Utc::now();
+        let request: FilterDeleteRequest = serde_json::from_str(r#"{"prefix": "tmp/"}"#).unwrap();
+
+        assert!(request.matches(&make_entity("tmp/a", serde_json::json!({}), now), now));
+        assert!(!request.matches(&make_entity("prod/a", serde_json::json!({}), now), now));
+    }
+
+    #[test]
+    fn test_filter_matches_properties() {
+        let now = Utc::now();
+        let request: FilterDeleteRequest = serde_json::from_str(
+            r#"{"prefix": "tmp/", "properties": {"status": "done", "retries": 3}}"#,
+        )
+        .unwrap();
+
+        let done = make_entity(
+            "tmp/a",
+            serde_json::json!({"status": "done", "retries": 3}),
+            now,
+        );
+        let running = make_entity(
+            "tmp/b",
+            serde_json::json!({"status": "running", "retries": 3}),
+            now,
+        );
+        let missing = make_entity("tmp/c", serde_json::json!({"status": "done"}), now);
+
+        assert!(request.matches(&done, now));
+        assert!(!request.matches(&running, now));
+        assert!(!request.matches(&missing, now));
+    }
+
+    #[test]
+    fn test_filter_matches_staleness() {
+        let now = Utc::now();
+        let request: FilterDeleteRequest =
+            serde_json::from_str(r#"{"prefix": "tmp/", "older_than_seconds": 604800}"#).unwrap();
+
+        let stale = make_entity("tmp/old", serde_json::json!({}), now - Duration::days(8));
+        let fresh = make_entity("tmp/new", serde_json::json!({}), now - Duration::days(6));
+
+        assert!(request.matches(&stale, now));
+        assert!(!request.matches(&fresh, now));
+    }
+
+    #[test]
+    fn test_filter_staleness_beyond_time_range_matches_nothing() {
+        let now = Utc::now();
+        let oldest = make_entity("tmp/old", serde_json::json!({}), DateTime::<Utc>::MIN_UTC);
+
+        for secs in [
+            i64::MAX as u64 / 1_000,
+            i64::MAX as u64 / 1_000 + 1,
+            u64::MAX,
+        ] {
+            let request: FilterDeleteRequest = serde_json::from_str(&format!(
+                r#"{{"prefix": "tmp/", "older_than_seconds": {}}}"#,
+                secs
+            ))
+            .unwrap();
+            assert!(!request.matches(&oldest, now));
+        }
+    }
+
+    #[test]
+    fn test_scope_prefix_to_caller() {
+        let registry = NamespaceRegistry::new();
+        let alice = registry.register("alice").unwrap();
+        let scope = |token: &str| resolve_scope(token, &registry, Some("admin-secret"));
+        let namespace = scope(&alice.token).unwrap();
+
+        // Inside namespace: kept
+        assert_eq!(
+            scope_prefix_to_caller(&namespace, "alice/tmp-").unwrap(),
+            "alice/tmp-"
+        );
+        // Broader than namespace: narrowed
+        assert_eq!(scope_prefix_to_caller(&namespace, "").unwrap(), "alice/");
+        // Another namespace: rejected
+        assert!(matches!(
+            scope_prefix_to_caller(&namespace, "bob/"),
+            Err(DeletionError::Forbidden(_))
+        ));
+        // Unknown token: rejected
+        assert!(matches!(scope("nope"), Err(AuthError::InvalidToken(_))));
+
+        // Admin token: any prefix
+        let admin = scope("admin-secret").unwrap();
+        assert_eq!(scope_prefix_to_caller(&admin, "").unwrap(), "");
+        assert_eq!(scope_prefix_to_caller(&admin, "bob/").unwrap(), "bob/");
+
+        // Write grant: narrowed to its prefix; read grant: rejected
+        let write = registry
+            .create_grant("alice", "tmp/", GrantScope::Write, None)
+            .unwrap();
+        let write = scope(&write.token).unwrap();
+        assert_eq!(
+            scope_prefix_to_caller(&write, "alice/").unwrap(),
+            "alice/tmp/"
+        );
+        assert_eq!(
+            scope_prefix_to_caller(&write, "alice/tmp/a-").unwrap(),
+            "alice/tmp/a-"
+        );
+        assert!(matches!(
+            scope_prefix_to_caller(&write, "alice/secret/"),
+            Err(DeletionError::Forbidden(_))
+        ));
+        assert!(!write.allows_write("alice/tmp/../secret"));
+        let read = registry
+            .create_grant("alice", "tmp/", GrantScope::Read, None)
+            .unwrap();
+        let read = scope(&read.token).unwrap();
+        assert!(matches!(
+            scope_prefix_to_caller(&read, "alice/tmp/"),
+            Err(DeletionError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_authorize_deletion_missing_token() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        registry.register("alice").unwrap();
+
+        let result = authorize_deletion(&HeaderMap::new(), "alice/device", &registry);
+        assert!(matches!(result, Err(DeletionError::Unauthorized(_))));
+    }
+
+    /// Keeps the entity ID of every published tombstone
+    #[derive(Default)]
+    struct CapturingSink {
+        entity_ids: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl crate::nats::PublishSink for CapturingSink {
+        fn send(
+            &self,
+            _subject: String,
+            _request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> futures::future::BoxFuture<'_, anyhow::Result<crate::nats::AckFuture>> {
+            Box::pin(async move {
+                let event: FluxEvent = serde_json::from_slice(&payload).unwrap();
+                let entity_id = event.payload["entity_id"].as_str().unwrap().to_string();
+                self.entity_ids.lock().unwrap().push(entity_id);
+                let ack: crate::nats::AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_filter_publishes_every_chunk() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let engine = Arc::new(StateEngine::new());
+        for i in 0..5 {
+            let event = crate::event::FluxEventBuilder::new("sensors", "test")
+                .entity(format!("tmp/{}", i))
+                .property("v", i)
+                .build()
+                .unwrap();
+            engine.process_event(&event, None);
+        }
+        let sink = Arc::new(CapturingSink::default());
+        let app = create_deletion_router(DeletionAppState {
+            event_publisher: EventPublisher::with_sink(Arc::clone(&sink) as _),
+            namespace_registry: Arc::new(NamespaceRegistry::new()),
+            state_engine: engine,
+            auth_enabled: false,
+            admin_token: None,
+            max_batch_delete: 2,
+        });
+
+        let request = Request::post("/api/state/entities/delete-by-filter")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"prefix": "tmp/"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: FilterDeleteResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            (response.matched, response.deleted, response.failed),
+            (5, 5, 0)
+        );
+
+        let mut published = sink.entity_ids.lock().unwrap().clone();
+        published.sort();
+        assert_eq!(published, ["tmp/0", "tmp/1", "tmp/2", "tmp/3", "tmp/4"]);
+    }
 }
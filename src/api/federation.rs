@@ -0,0 +1,146 @@
+use crate::api::admin::validate_admin_token;
+use crate::api::openapi::ErrorResponse;
+use crate::federation::{Forwarder, RuleStatus};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the federation admin endpoint
+pub struct FederationAppState {
+    /// None when no federation rules are configured
+    pub forwarder: Option<Arc<Forwarder>>,
+    pub admin_token: Option<String>,
+}
+
+/// Federation rules and how far each is behind the stream
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "site": "us-east",
+    "rules": [{
+        "name": "eu",
+        "namespace": "acme",
+        "remote_url": "https://flux-eu.example.com",
+        "forwarded_sequence": 18230,
+        "lag": 12,
+        "last_forwarded_at": "2026-02-12T14:00:05Z"
+    }]
+}))]
+pub struct FederationStatusResponse {
+    /// Name recorded on forwarded events; absent without rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    pub rules: Vec<RuleStatus>,
+}
+
+/// OpenAPI description of the federation endpoint
+#[derive(OpenApi)]
+#[openapi(
+    paths(federation_status),
+    components(schemas(FederationStatusResponse, RuleStatus))
+)]
+pub(crate) struct FederationApi;
+
+/// Create federation admin router
+pub fn create_federation_router(state: Arc<FederationAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/federation", get(federation_status))
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// GET /api/admin/federation - Federation rules and their lag
+///
+/// Progress is that of the forwarder on this instance; only the leader
+/// forwards.
+#[utoipa::path(
+    get,
+    path = "/api/admin/federation",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Rules with forwarding progress", body = FederationStatusResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 500, description = "Event stream unavailable", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn federation_status(
+    State(state): State<Arc<FederationAppState>>,
+    headers: HeaderMap,
+) -> Response {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+
+    let Some(forwarder) = &state.forwarder else {
+        return Json(FederationStatusResponse {
+            site: None,
+            rules: Vec::new(),
+        })
+        .into_response();
+    };
+    match forwarder.status().await {
+        Ok(rules) => Json(FederationStatusResponse {
+            site: Some(forwarder.site().to_string()),
+            rules,
+        })
+        .into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to read federation status");
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read event stream bounds",
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_requires_admin_token() {
+        let app = create_federation_router(Arc::new(FederationAppState {
+            forwarder: None,
+            admin_token: Some("admin".to_string()),
+        }));
+        let request = |token: Option<&str>| {
+            let mut request = Request::get("/api/admin/federation");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(request(Some("admin"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: FederationStatusResponse = serde_json::from_slice(&body).unwrap();
+        assert!(status.rules.is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::leader::{LeaderStatus, Leadership};
+use crate::nats::{ConnectionState, ConnectionStatus, NatsConnectionStatus, NatsStatusHandle};
+use crate::standby::{InstanceMode, ModeHandle};
+use crate::state::{StartupReplayProgress, StateEngine};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the readiness endpoint
+pub struct HealthAppState {
+    pub nats: NatsStatusHandle,
+    pub leadership: Leadership,
+    pub mode: ModeHandle,
+    pub state_engine: Arc<StateEngine>,
+}
+
+/// Readiness plus the negotiated NATS connection, leader election state,
+/// instance mode and startup replay progress
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "ready": true,
+    "mode": "primary",
+    "nats": {
+        "connected": true,
+        "tls": true,
+        "server_version": "2.10.22",
+        "server_name": "nats-1",
+        "state": "connected",
+        "last_disconnect_at": "2026-01-01T00:00:00Z",
+        "reconnects": 1,
+        "messages_since_reconnect": 5200
+    },
+    "leader": {
+        "instance_id": "flux-0",
+        "is_leader": true,
+        "leader": "flux-0",
+        "changed_at": "2026-01-01T00:00:00Z"
+    },
+    "replay": {
+        "replaying": false,
+        "processed_sequence": 4800000,
+        "target_sequence": 4800000,
+        "percent_complete": 100.0,
+        "events_per_second": 0.0
+    }
+}))]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// A standby is ready too; it serves reads but no writes
+    pub mode: InstanceMode,
+    pub nats: NatsConnectionStatus,
+    /// Followers are ready too; only the leader writes snapshots and archives
+    pub leader: LeaderStatus,
+    /// Ready does not wait for the replay; reads may see partial state until it ends
+    pub replay: StartupReplayProgress,
+}
+
+/// Body of a read refused while the startup replay runs
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "error": "State is still being rebuilt from the event stream",
+    "replay": {
+        "replaying": true,
+        "processed_sequence": 1200000,
+        "target_sequence": 4800000,
+        "percent_complete": 25.0,
+        "events_per_second": 85000.0
+    }
+}))]
+pub struct ReplayingResponse {
+    pub error: String,
+    pub replay: StartupReplayProgress,
+}
+
+/// OpenAPI description of the readiness endpoint
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_ready),
+    components(schemas(
+        ReadinessResponse,
+        NatsConnectionStatus,
+        ConnectionStatus,
+        ConnectionState,
+        LeaderStatus,
+        InstanceMode,
+        StartupReplayProgress,
+        ReplayingResponse
+    ))
+)]
+pub(crate) struct HealthApi;
+
+/// Create readiness router
+pub fn create_health_router(state: Arc<HealthAppState>) -> Router {
+    Router::new()
+        .route("/api/ready", get(get_ready))
+        .with_state(state)
+}
+
+/// Answer 503 with the replay progress on every route of `router` until
+/// `state_engine` has caught up with the stream
+pub fn block_during_replay(router: Router, state_engine: Arc<StateEngine>) -> Router {
+    router.layer(middleware::from_fn_with_state(state_engine, require_live))
+}
+
+async fn require_live(
+    State(state_engine): State<Arc<StateEngine>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state_engine.is_live() {
+        let body = ReplayingResponse {
+            error: "State is still being rebuilt from the event stream".to_string(),
+            replay: state_engine.replay_progress(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response();
+    }
+    next.run(request).await
+}
+
+/// GET /api/ready
+///
+/// 200 while connected to NATS, 503 while disconnected or reconnecting.
+#[utoipa::path(
+    get,
+    path = "/api/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Connected to NATS", body = ReadinessResponse),
+        (status = 503, description = "NATS connection down", body = ReadinessResponse),
+    )
+)]
+async fn get_ready(State(state): State<Arc<HealthAppState>>) -> Response {
+    let (status, body) = readiness(
+        state.nats.status(),
+        state.leadership.status(),
+        state.mode.mode(),
+        state.state_engine.replay_progress(),
+    );
+    (status, Json(body)).into_response()
+}
+
+fn readiness(
+    nats: NatsConnectionStatus,
+    leader: LeaderStatus,
+    mode: InstanceMode,
+    replay: StartupReplayProgress,
+) -> (StatusCode, ReadinessResponse) {
+    let ready = nats.connected;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        ReadinessResponse {
+            ready,
+            mode,
+            nats,
+            leader,
+            replay,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nats::ConnectionMonitor;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn nats_status(connected: bool) -> NatsConnectionStatus {
+        NatsConnectionStatus {
+            connected,
+            tls: true,
+            server_version: "2.10.22".to_string(),
+            server_name: "nats-1".to_string(),
+            connection: ConnectionMonitor::new().status(),
+        }
+    }
+
+    #[test]
+    fn test_readiness_follows_nats_connection() {
+        let leader = Leadership::standalone("flux-0").status();
+        let primary = InstanceMode::Primary;
+        let replay = StateEngine::new().replay_progress();
+        let (status, body) = readiness(nats_status(true), leader.clone(), primary, replay.clone());
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.ready);
+        assert!(body.leader.is_leader);
+        assert!(body.replay.replaying);
+
+        let (status, body) = readiness(nats_status(false), leader, primary, replay);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!body.ready);
+        assert!(body.nats.tls);
+        assert_eq!(body.nats.server_version, "2.10.22");
+    }
+
+    #[test]
+    fn test_standby_is_ready() {
+        let leader = Leadership::standalone("flux-1").status();
+        let replay = StateEngine::new().replay_progress();
+        let (status, body) = readiness(nats_status(true), leader, InstanceMode::Standby, replay);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.mode, InstanceMode::Standby);
+    }
+
+    #[tokio::test]
+    async fn test_reads_blocked_until_live() {
+        let engine = Arc::new(StateEngine::new());
+        engine.begin_replay(0, 200);
+        let app = block_during_replay(
+            Router::new().route("/api/state/entities", get(|| async { "[]" })),
+            Arc::clone(&engine),
+        );
+        let get_entities = || {
+            Request::builder()
+                .uri("/api/state/entities")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(get_entities()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: ReplayingResponse = serde_json::from_slice(&body).unwrap();
+        assert!(body.replay.replaying);
+        assert_eq!(body.replay.target_sequence, 200);
+        assert_eq!(body.replay.percent_complete, Some(0.0));
+
+        engine.set_live();
+        let response = app.oneshot(get_entities()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
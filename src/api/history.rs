@@ -1,25 +1,67 @@
+use crate::api::auth_middleware::{AuthScope, ReadAuthState};
+use crate::archive::{self, ArchiveStore};
+use crate::entity::parse_entity_id;
 use crate::event::FluxEvent;
+use crate::namespace::NamespaceRegistry;
+use crate::nats::is_ingest_subject;
 use async_nats::jetstream;
+use async_nats::jetstream::consumer::DeliverPolicy;
 use axum::{
+    body::{Body, Bytes},
     extract::{Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     response::{IntoResponse, Json, Response},
     routing::get,
     Router,
 };
 use chrono::{DateTime, Duration, Utc};
 use futures::StreamExt;
+use http_body::Frame;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tower_http::compression::CompressionLayer;
 use tracing::warn;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+/// Most events one history or export response returns
+const MAX_LIMIT: usize = 500;
+
+/// A scan of the stream ends once no event arrives for this long
+const IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Trailer of a capped export: the `from_seq` to resume it with
+const NEXT_FROM_SEQ_TRAILER: &str = "flux-next-from-seq";
 
 /// Shared state for history API
 pub struct HistoryAppState {
     pub jetstream: jetstream::Context,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    /// When true, requests need a bearer token and only see its namespace
+    pub auth_enabled: bool,
+    /// Token that sees every namespace
+    pub admin_token: Option<String>,
+    /// Archived events (pruned from the stream), when archiving is enabled
+    pub archive: Option<Arc<dyn ArchiveStore>>,
+}
+
+impl ReadAuthState for HistoryAppState {
+    fn auth_enabled(&self) -> bool {
+        self.auth_enabled
+    }
+    fn namespace_registry(&self) -> &NamespaceRegistry {
+        &self.namespace_registry
+    }
+    fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
 }
 
 /// Query parameters for event history
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct HistoryParams {
     /// Entity ID to fetch history for (required)
     pub entity: Option<String>,
@@ -29,23 +71,109 @@ pub struct HistoryParams {
     pub limit: Option<usize>,
 }
 
-#[derive(Serialize)]
+/// Query parameters for an event export
+#[derive(Deserialize, IntoParams)]
+pub struct ExportParams {
+    /// Entity to export events for (this or `namespace` is required)
+    pub entity_id: Option<String>,
+    /// Namespace to export the events of all its entities for
+    pub namespace: Option<String>,
+    /// ISO 8601 start timestamp (default: oldest stored event)
+    pub since: Option<String>,
+    /// ISO 8601 end timestamp, inclusive (default: no end)
+    pub until: Option<String>,
+    /// Stream sequence to resume a capped export from
+    pub from_seq: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
+/// Events a history request is for
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventFilter {
+    /// Events whose payload `entity_id` is this entity
+    Entity(String),
+    /// Events for any entity prefixed with this namespace
+    Namespace(String),
+}
+
+impl EventFilter {
+    /// True if `event` is for this filter's entity or namespace
+    pub fn matches(&self, event: &FluxEvent) -> bool {
+        let Some(entity_id) = event.payload.get("entity_id").and_then(|v| v.as_str()) else {
+            return false;
+        };
+        match self {
+            EventFilter::Entity(entity) => entity_id == entity,
+            EventFilter::Namespace(ns) => parse_entity_id(entity_id)
+                .map(|parsed| parsed.namespace.as_deref() == Some(ns.as_str()))
+                .unwrap_or(false),
+        }
+    }
+
+    /// True if `scope` may read every event this filter matches
+    fn allowed(&self, scope: &AuthScope) -> bool {
+        match (self, scope) {
+            (EventFilter::Entity(entity), _) => scope.allows(entity),
+            (EventFilter::Namespace(_), AuthScope::All) => true,
+            (EventFilter::Namespace(ns), AuthScope::Namespace(own)) => ns == own,
+            // Part of a namespace only; ask for its entities one at a time
+            (EventFilter::Namespace(_), AuthScope::Grant { .. }) => false,
+        }
+    }
+}
+
+impl fmt::Display for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventFilter::Entity(entity) => write!(f, "'{}'", entity),
+            EventFilter::Namespace(ns) => write!(f, "namespace '{}'", ns),
+        }
+    }
+}
+
+/// OpenAPI description of the history endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_events, export_events),
+    components(schemas(FluxEvent, ErrorResponse))
+)]
+pub(crate) struct HistoryApi;
+
 /// Create history API router
 pub fn create_history_router(state: Arc<HistoryAppState>) -> Router {
     Router::new()
         .route("/api/events", get(get_events))
+        .route("/api/history/export", get(export_events))
+        .layer(CompressionLayer::new())
         .with_state(state)
 }
 
 /// GET /api/events?entity=X&since=T&limit=N
 ///
 /// Returns raw stored events for an entity from NATS JetStream, newest first.
+/// Events already archived and pruned from the stream are read from the
+/// archive. With auth enabled, the entity must be in the token's namespace.
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "history",
+    params(HistoryParams),
+    responses(
+        (status = 200, description = "Stored events, newest first", body = [FluxEvent]),
+        (status = 400, description = "Missing entity or invalid since", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Entity outside the token's namespace", body = ErrorResponse),
+        (status = 500, description = "Event stream unavailable", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn get_events(
     State(state): State<Arc<HistoryAppState>>,
+    scope: AuthScope,
     Query(params): Query<HistoryParams>,
 ) -> Response {
     // entity is required
@@ -62,6 +190,11 @@ async fn get_events(
         }
     };
 
+    let filter = EventFilter::Entity(entity);
+    if let Some(response) = check_scope(&scope, &filter) {
+        return response;
+    }
+
     // Parse `since` or default to 24h ago
     let since: DateTime<Utc> = if let Some(s) = params.since {
         match DateTime::parse_from_rfc3339(&s) {
@@ -81,7 +214,7 @@ async fn get_events(
     };
 
     // Clamp limit to 1..=500
-    let limit = params.limit.unwrap_or(100).min(500).max(1);
+    let limit = params.limit.unwrap_or(100).clamp(1, MAX_LIMIT);
 
     // Convert chrono timestamp to time::OffsetDateTime for NATS DeliverPolicy
     let start_time = match time::OffsetDateTime::from_unix_timestamp(since.timestamp()) {
@@ -97,6 +230,31 @@ async fn get_events(
         }
     };
 
+    let mut collected: Vec<FluxEvent> = Vec::new();
+
+    // Oldest events first: whatever was archived out of the stream
+    let mut archived_through = 0;
+    if let Some(store) = &state.archive {
+        let scanned = archive::scan_since(store.as_ref(), since, |stored| {
+            if let Some(event) = filtered_event(&stored.payload, &filter) {
+                collected.push(event);
+            }
+            collected.len() < limit
+        })
+        .await;
+        match scanned {
+            Ok(high_water) => archived_through = high_water,
+            Err(e) => {
+                warn!(error = %e, "Failed to read event archive for history");
+                collected.clear();
+            }
+        }
+    }
+    if collected.len() >= limit {
+        collected.reverse();
+        return Json(collected).into_response();
+    }
+
     // Get FLUX_EVENTS stream
     let stream = match state.jetstream.get_stream("FLUX_EVENTS").await {
         Ok(s) => s,
@@ -149,33 +307,24 @@ async fn get_events(
         }
     };
 
-    let mut collected: Vec<FluxEvent> = Vec::new();
-
-    // Read until 200ms idle timeout or limit reached
-    loop {
-        match tokio::time::timeout(
-            std::time::Duration::from_millis(200),
-            messages.next(),
-        )
-        .await
+    // Read until the stream ends, a message fails, 200ms pass idle or the
+    // limit is reached
+    while let Ok(Some(Ok(msg))) = tokio::time::timeout(IDLE_TIMEOUT, messages.next()).await {
+        // Archived but not yet purged: already read from the archive
+        if msg
+            .info()
+            .is_ok_and(|info| info.stream_sequence <= archived_through)
         {
-            Ok(Some(Ok(msg))) => {
-                if let Ok(event) = serde_json::from_slice::<FluxEvent>(&msg.payload) {
-                    if event
-                        .payload
-                        .get("entity_id")
-                        .and_then(|v| v.as_str())
-                        == Some(entity.as_str())
-                    {
-                        collected.push(event);
-                        if collected.len() >= limit {
-                            break;
-                        }
-                    }
-                }
+            continue;
+        }
+        if is_ingest_subject(msg.subject.as_str()) {
+            continue;
+        }
+        if let Some(event) = filtered_event(&msg.payload, &filter) {
+            collected.push(event);
+            if collected.len() >= limit {
+                break;
             }
-            // Stream ended, message error, or 200ms idle — stop
-            Ok(Some(Err(_))) | Ok(None) | Err(_) => break,
         }
     }
 
@@ -185,16 +334,416 @@ async fn get_events(
     Json(collected).into_response()
 }
 
+/// Parse a stored event, keeping it only if `filter` matches it
+fn filtered_event(payload: &[u8], filter: &EventFilter) -> Option<FluxEvent> {
+    let event = serde_json::from_slice::<FluxEvent>(payload).ok()?;
+    filter.matches(&event).then_some(event)
+}
+
+/// 403 response if `filter` reaches outside `scope`
+fn check_scope(scope: &AuthScope, filter: &EventFilter) -> Option<Response> {
+    if filter.allowed(scope) {
+        return None;
+    }
+    Some(error_response(
+        StatusCode::FORBIDDEN,
+        format!("Not authorized to read history for {}", filter),
+    ))
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.into(),
+        }),
+    )
+        .into_response()
+}
+
+/// GET /api/history/export?entity_id=X or ?namespace=N&since=T&until=T
+///
+/// Streams the stored events of an entity or namespace as NDJSON, oldest
+/// first, for download. Stops after 500 events with a `Flux-Next-From-Seq`
+/// trailer; pass it back as `from_seq` to continue. With auth enabled, the
+/// entity or namespace must be the token's.
+#[utoipa::path(
+    get,
+    path = "/api/history/export",
+    tag = "history",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "Stored events as NDJSON, oldest first", body = FluxEvent, content_type = "application/x-ndjson",
+            headers(("Content-Disposition" = String, description = "Attachment filename"))),
+        (status = 400, description = "Not exactly one of entity_id and namespace, or invalid since/until", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Entity or namespace outside the token's namespace", body = ErrorResponse),
+        (status = 500, description = "Event stream unavailable", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn export_events(
+    State(state): State<Arc<HistoryAppState>>,
+    scope: AuthScope,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    let filter = match (params.entity_id, params.namespace) {
+        (Some(entity), None) => EventFilter::Entity(entity),
+        (None, Some(ns)) => EventFilter::Namespace(ns),
+        _ => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "exactly one of `entity_id` and `namespace` is required",
+            )
+        }
+    };
+    if let Some(response) = check_scope(&scope, &filter) {
+        return response;
+    }
+
+    let parse = |value: Option<String>, name: &str| {
+        value
+            .map(|s| DateTime::parse_from_rfc3339(&s).map(|dt| dt.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|_| format!("invalid `{}` timestamp (expected ISO 8601)", name))
+    };
+    let since = match parse(params.since, "since") {
+        Ok(since) => since,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, message),
+    };
+    let until = match parse(params.until, "until") {
+        Ok(until) => until,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, message),
+    };
+
+    let stream = match state.jetstream.get_stream("FLUX_EVENTS").await {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(error = %e, "Failed to get FLUX_EVENTS stream for export");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to access event stream",
+            );
+        }
+    };
+
+    let filename = export_filename(&filter);
+    let scan = ExportScan::new(filter, since, until, params.from_seq.unwrap_or(0));
+    let (tx, rx) = mpsc::channel(64);
+    let archive = state.archive.clone();
+    tokio::spawn(async move {
+        match run_export(stream, archive, scan, &tx).await {
+            Ok(Some(next)) => {
+                let mut trailers = HeaderMap::new();
+                trailers.insert(
+                    HeaderName::from_static(NEXT_FROM_SEQ_TRAILER),
+                    HeaderValue::from(next),
+                );
+                let _ = tx.send(Ok(Frame::trailers(trailers))).await;
+            }
+            Ok(None) => {}
+            // Aborts the response, so a partial export can't pass for a whole one
+            Err(e) => {
+                warn!(error = %e, "Event export failed");
+                let _ = tx.send(Err(std::io::Error::other(e))).await;
+            }
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+            (header::TRAILER, NEXT_FROM_SEQ_TRAILER.to_string()),
+        ],
+        Body::new(ExportBody(rx)),
+    )
+        .into_response()
+}
+
+/// Download filename for an export, e.g. `flux-events-alice_sensor-01.ndjson`
+fn export_filename(filter: &EventFilter) -> String {
+    let name = match filter {
+        EventFilter::Entity(entity) => entity,
+        EventFilter::Namespace(ns) => ns,
+    };
+    let safe: String = name
+        .chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("flux-events-{}.ndjson", safe)
+}
+
+type ExportFrame = Result<Frame<Bytes>, std::io::Error>;
+
+/// Response body fed by the export task
+struct ExportBody(mpsc::Receiver<ExportFrame>);
+
+impl http_body::Body for ExportBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ExportFrame>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// Which stored events an export writes, and where it stopped
+struct ExportScan {
+    filter: EventFilter,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    /// Events before this stream sequence are skipped
+    from_seq: u64,
+    exported: usize,
+    /// Set once no later event can be exported
+    done: bool,
+    /// Sequence to resume from, when the scan stopped at the cap
+    resume_at: Option<u64>,
+}
+
+impl ExportScan {
+    fn new(
+        filter: EventFilter,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        from_seq: u64,
+    ) -> Self {
+        Self {
+            filter,
+            since,
+            until,
+            from_seq,
+            exported: 0,
+            done: false,
+            resume_at: None,
+        }
+    }
+
+    /// NDJSON line for a stored event, if it is exported
+    fn visit(
+        &mut self,
+        sequence: u64,
+        published: DateTime<Utc>,
+        payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        if self.done || sequence < self.from_seq || self.since.is_some_and(|s| published < s) {
+            return None;
+        }
+        if self.until.is_some_and(|u| published > u) {
+            self.done = true;
+            return None;
+        }
+        let event = filtered_event(payload, &self.filter)?;
+        let mut line = serde_json::to_vec(&event).ok()?;
+        line.push(b'\n');
+        self.exported += 1;
+        if self.exported >= MAX_LIMIT {
+            self.done = true;
+            self.resume_at = Some(sequence + 1);
+        }
+        Some(line)
+    }
+}
+
+/// Sends the lines of `scan` to `tx`, archive first, then the stream.
+/// Returns the sequence to resume from if the scan was capped.
+async fn run_export(
+    stream: jetstream::stream::Stream,
+    archive: Option<Arc<dyn ArchiveStore>>,
+    mut scan: ExportScan,
+    tx: &mpsc::Sender<ExportFrame>,
+) -> Result<Option<u64>, String> {
+    let send = |line: Vec<u8>| tx.send(Ok(Frame::data(Bytes::from(line))));
+
+    if let Some(store) = &archive {
+        let mut lines = Vec::new();
+        let since = scan.since.unwrap_or(DateTime::UNIX_EPOCH);
+        let archived_through = archive::scan_since(store.as_ref(), since, |stored| {
+            lines.extend(scan.visit(stored.sequence, stored.published, &stored.payload));
+            !scan.done
+        })
+        .await
+        .map_err(|e| format!("failed to read event archive: {}", e))?;
+        for line in lines {
+            if send(line).await.is_err() {
+                return Ok(None);
+            }
+        }
+        if scan.done {
+            return Ok(scan.resume_at);
+        }
+        // Archived but not yet purged: already read from the archive
+        scan.from_seq = scan.from_seq.max(archived_through + 1);
+    }
+
+    let deliver_policy = match scan.since {
+        Some(since) if scan.from_seq <= 1 => DeliverPolicy::ByStartTime {
+            start_time: time::OffsetDateTime::from_unix_timestamp(since.timestamp())
+                .map_err(|e| format!("failed to convert start time: {}", e))?,
+        },
+        _ => DeliverPolicy::ByStartSequence {
+            start_sequence: scan.from_seq.max(1),
+        },
+    };
+    let consumer = stream
+        .create_consumer(jetstream::consumer::pull::OrderedConfig {
+            deliver_policy,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("failed to create event consumer: {}", e))?;
+    let mut messages = consumer
+        .messages()
+        .await
+        .map_err(|e| format!("failed to read events: {}", e))?;
+
+    while !scan.done {
+        let msg = match tokio::time::timeout(IDLE_TIMEOUT, messages.next()).await {
+            Ok(Some(Ok(msg))) => msg,
+            Ok(Some(Err(e))) => return Err(format!("failed to read events: {}", e)),
+            // Stream ended or idle
+            Ok(None) | Err(_) => break,
+        };
+        if is_ingest_subject(msg.subject.as_str()) {
+            continue;
+        }
+        let info = msg
+            .info()
+            .map_err(|e| format!("invalid message metadata: {}", e))?;
+        let published =
+            DateTime::from_timestamp(info.published.unix_timestamp(), info.published.nanosecond())
+                .ok_or("invalid publish time")?;
+        if let Some(line) = scan.visit(info.stream_sequence, published, &msg.payload) {
+            if send(line).await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+    Ok(scan.resume_at)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn entity(id: &str) -> EventFilter {
+        EventFilter::Entity(id.to_string())
+    }
+
+    fn namespace(ns: &str) -> EventFilter {
+        EventFilter::Namespace(ns.to_string())
+    }
+
+    fn stored(entity_id: &str) -> Vec<u8> {
+        format!(
+            r#"{{"stream":"s","source":"src","timestamp":1,"payload":{{"entity_id":"{}","properties":{{}}}}}}"#,
+            entity_id
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_history_scoped_to_namespace() {
+        let alice = AuthScope::Namespace("alice".to_string());
+        assert!(check_scope(&alice, &entity("alice/sensor-01")).is_none());
+
+        let denied = check_scope(&alice, &entity("bob/sensor-01")).unwrap();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        assert!(check_scope(&alice, &entity("sensor-01")).is_some());
+
+        assert!(check_scope(&AuthScope::All, &entity("bob/sensor-01")).is_none());
+    }
+
+    #[test]
+    fn test_export_scoped_to_namespace() {
+        let alice = AuthScope::Namespace("alice".to_string());
+        assert!(check_scope(&alice, &namespace("alice")).is_none());
+        let denied = check_scope(&alice, &namespace("bob")).unwrap();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        assert!(check_scope(&AuthScope::All, &namespace("bob")).is_none());
+    }
+
+    #[test]
+    fn test_entity_event_filter() {
+        let stored = br#"{"stream":"s","source":"src","timestamp":1,"payload":{"entity_id":"sensor-01","properties":{}}}"#;
+        assert!(filtered_event(stored, &entity("sensor-01")).is_some());
+        assert!(filtered_event(stored, &entity("sensor-02")).is_none());
+        assert!(filtered_event(b"not json", &entity("sensor-01")).is_none());
+    }
+
+    #[test]
+    fn test_namespace_event_filter() {
+        let alice = namespace("alice");
+        assert!(filtered_event(&stored("alice/sensor-01"), &alice).is_some());
+        assert!(filtered_event(&stored("alice/sensors/a"), &alice).is_some());
+        assert!(filtered_event(&stored("alicex/sensor-01"), &alice).is_none());
+        assert!(filtered_event(&stored("bob/sensor-01"), &alice).is_none());
+        assert!(filtered_event(&stored("alice"), &alice).is_none());
+        let unscoped =
+            br#"{"stream":"s","source":"src","timestamp":1,"payload":{"properties":{}}}"#;
+        assert!(filtered_event(unscoped, &alice).is_none());
+    }
+
+    #[test]
+    fn test_export_scan_caps_and_resumes() {
+        let at = |seconds| DateTime::from_timestamp(seconds, 0).unwrap();
+        let mut scan = ExportScan::new(namespace("alice"), None, None, 0);
+        assert!(scan.visit(1, at(1), &stored("bob/x")).is_none());
+        for seq in 2..(MAX_LIMIT as u64 + 1) {
+            assert!(scan.visit(seq, at(1), &stored("alice/x")).is_some());
+        }
+        assert!(!scan.done);
+        let last = scan.visit(501, at(1), &stored("alice/x")).unwrap();
+        assert!(last.ends_with(b"\n"));
+        assert!(scan.done);
+        assert_eq!(scan.resume_at, Some(502));
+        assert!(scan.visit(502, at(1), &stored("alice/x")).is_none());
+
+        let mut resumed = ExportScan::new(namespace("alice"), None, None, 502);
+        assert!(resumed.visit(501, at(1), &stored("alice/x")).is_none());
+        assert!(resumed.visit(502, at(1), &stored("alice/x")).is_some());
+    }
+
+    #[test]
+    fn test_export_scan_time_window() {
+        let at = |seconds| DateTime::from_timestamp(seconds, 0).unwrap();
+        let mut scan = ExportScan::new(entity("alice/x"), Some(at(10)), Some(at(20)), 0);
+        assert!(scan.visit(1, at(9), &stored("alice/x")).is_none());
+        assert!(scan.visit(2, at(10), &stored("alice/x")).is_some());
+        assert!(scan.visit(3, at(20), &stored("alice/x")).is_some());
+        assert!(!scan.done);
+        assert!(scan.visit(4, at(21), &stored("alice/x")).is_none());
+        assert!(scan.done);
+        assert_eq!(scan.resume_at, None);
+    }
+
+    #[test]
+    fn test_export_filename() {
+        assert_eq!(
+            export_filename(&entity("alice/sensor-01")),
+            "flux-events-alice_sensor-01.ndjson"
+        );
+        assert_eq!(
+            export_filename(&namespace("a\"b")),
+            "flux-events-a_b.ndjson"
+        );
+    }
+
     #[test]
+    #[allow(clippy::unnecessary_literal_unwrap)]
     fn test_default_limit() {
         assert_eq!(None::<usize>.unwrap_or(100).min(500), 100);
     }
 
     #[test]
+    #[allow(clippy::unnecessary_literal_unwrap)]
     fn test_limit_clamped_to_max() {
         assert_eq!(Some(1000usize).unwrap_or(100).min(500), 500);
     }
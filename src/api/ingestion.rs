@@ -1,9 +1,13 @@
-use crate::api::auth_middleware::{authorize_event, AuthError};
+use crate::api::admission::{EventAdmission, Rejection};
+use crate::api::content_encoding::decode_body;
+use crate::api::request_id::request_id;
 use crate::config::SharedRuntimeConfig;
-use crate::entity::parse_entity_id;
-use crate::event::FluxEvent;
+use crate::event::{
+    CloudEvent, CloudEventError, FluxEvent, CLOUDEVENTS_BATCH_CONTENT_TYPE,
+    CLOUDEVENTS_CONTENT_TYPE, SPECVERSION_HEADER,
+};
 use crate::namespace::NamespaceRegistry;
-use crate::nats::EventPublisher;
+use crate::nats::{is_unavailable, EventPublisher};
 use crate::rate_limit::RateLimiter;
 use axum::{
     body::Bytes,
@@ -13,9 +17,11 @@ use axum::{
     routing::post,
     Router,
 };
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{error, info};
+use utoipa::{OpenApi, ToSchema};
 
 /// Shared application state
 #[derive(Clone)]
@@ -28,42 +34,84 @@ pub struct AppState {
     pub rate_limiter: Arc<RateLimiter>,
 }
 
+impl AppState {
+    /// Admission checks for events published through this state
+    pub fn admission(&self) -> EventAdmission {
+        EventAdmission {
+            namespace_registry: Arc::clone(&self.namespace_registry),
+            auth_enabled: self.auth_enabled,
+            runtime_config: Arc::clone(&self.runtime_config),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+        }
+    }
+}
+
 /// Success response for event ingestion
-#[derive(Serialize)]
-struct EventResponse {
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"eventId": "01936f8e-7c2a-7000-8000-000000000000", "stream": "sensors"}))]
+pub(crate) struct EventResponse {
     #[serde(rename = "eventId")]
     event_id: String,
     stream: String,
 }
 
 /// Error response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
 /// Batch request
-#[derive(Deserialize)]
-struct BatchRequest {
-    events: Vec<FluxEvent>,
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({"events": [{
+    "stream": "sensors",
+    "source": "sensor-01",
+    "timestamp": 1700000000000i64,
+    "payload": {"entity_id": "temp-sensor-01", "properties": {"temperature": 22.5}}
+}]}))]
+pub(crate) struct BatchRequest {
+    pub(crate) events: Vec<FluxEvent>,
 }
 
 /// Batch response
-#[derive(Serialize)]
-struct BatchResponse {
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "successful": 1,
+    "failed": 1,
+    "results": [
+        {"eventId": "01936f8e-7c2a-7000-8000-000000000000", "stream": "sensors", "error": null},
+        {"eventId": null, "stream": "Bad Stream", "error": "validation failed: invalid stream format"}
+    ]
+}))]
+pub(crate) struct BatchResponse {
     successful: usize,
     failed: usize,
-    results: Vec<BatchResult>,
+    pub(crate) results: Vec<BatchResult>,
 }
 
-#[derive(Serialize)]
-struct BatchResult {
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct BatchResult {
     #[serde(rename = "eventId")]
     event_id: Option<String>,
     stream: Option<String>,
     error: Option<String>,
 }
 
+/// OpenAPI description of the ingestion endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(publish_event, publish_batch),
+    components(schemas(
+        FluxEvent,
+        EventResponse,
+        ErrorResponse,
+        BatchRequest,
+        BatchResponse,
+        BatchResult
+    ))
+)]
+pub(crate) struct IngestionApi;
+
 /// Create API router with ingestion endpoints
 pub fn create_router(state: AppState) -> Router {
     Router::new()
@@ -73,63 +121,65 @@ pub fn create_router(state: AppState) -> Router {
 }
 
 /// POST /api/events - Publish single event
+#[utoipa::path(
+    post,
+    path = "/api/events",
+    tag = "ingestion",
+    request_body = FluxEvent,
+    responses(
+        (status = 200, description = "Event published", body = EventResponse),
+        (status = 400, description = "Invalid event", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Token cannot write this entity", body = ErrorResponse),
+        (status = 413, description = "Body or payload exceeds size limit", body = ErrorResponse),
+        (status = 415, description = "Unsupported Content-Encoding", body = ErrorResponse),
+        (status = 422, description = "Event exceeds a property limit, its timestamp is implausible or CloudEvent data is not an object", body = ErrorResponse),
+        (status = 429, description = "Namespace rate limit exceeded", body = ErrorResponse),
+        (status = 503, description = "NATS connection down; retry after Retry-After seconds", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn publish_event(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Json<EventResponse>, AppError> {
-    // Check body size against runtime-configurable limit
-    let limit = state.runtime_config.read().unwrap().body_size_limit_single_bytes;
-    if body.len() > limit {
-        return Err(AppError::PayloadTooLarge);
-    }
-
-    // Deserialize from checked bytes
-    let mut event: FluxEvent = serde_json::from_slice(&body)
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-
-    // Validate and prepare event (generates UUIDv7 if needed)
-    event
-        .validate_and_prepare()
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
-
-    // Authorize event (if auth enabled)
-    authorize_event(
-        &headers,
-        &event,
-        &state.namespace_registry,
-        state.auth_enabled,
-    )?;
-
-    // Rate limit check (auth-gated: only active when auth is enabled)
-    if state.auth_enabled {
-        let namespace = extract_namespace_from_event(&event);
-        let limit = state
-            .runtime_config
-            .read()
-            .unwrap()
-            .rate_limit_per_namespace_per_minute;
-        if !state.rate_limiter.check_and_consume(&namespace, limit) {
-            return Err(AppError::RateLimited);
+    // Decoded size is what counts against the limit
+    let limit = state
+        .runtime_config
+        .read()
+        .unwrap()
+        .body_size_limit_single_bytes;
+    let body = decode_body(&headers, body, limit)?;
+    // Binary-mode CloudEvents are marked by their ce-* headers
+    let cloud_event = content_type_is(&headers, CLOUDEVENTS_CONTENT_TYPE)
+        || headers.contains_key(SPECVERSION_HEADER);
+    if cloud_event {
+        if body.len() > limit {
+            return Err(Rejection::BodyTooLarge.into());
         }
+        return publish_cloudevent(&state, &headers, &body).await;
     }
 
+    // Size limit, validation, authorization and rate limit, as for NATS
+    // ingestion; the payload is checked in place and never parsed into a Value
+    let event = state.admission().admit_raw_body(&body, &headers)?;
+    let request_id = request_id(&headers);
+
     info!(
         event_id = %event.event_id.as_ref().unwrap(),
         stream = %event.stream,
         source = %event.source,
+        request_id = %request_id,
         "Ingesting event"
     );
 
-    // Publish to NATS
+    // Publish to NATS, with the request ID as a header
     state
         .event_publisher
-        .publish(&event)
+        .publish_raw_with_request_id(&event, Some(&request_id))
         .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to publish event to NATS");
-            AppError::PublishError(e.to_string())
-        })?;
+        .map_err(publish_failed)?;
 
     Ok(Json(EventResponse {
         event_id: event.event_id.clone().unwrap(),
@@ -137,102 +187,211 @@ async fn publish_event(
     }))
 }
 
+/// Map a CloudEvent (structured or binary mode) onto a Flux event, admit and
+/// publish it
+async fn publish_cloudevent(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Json<EventResponse>, AppError> {
+    let cloud_event = if headers.contains_key(SPECVERSION_HEADER) {
+        CloudEvent::from_binary(headers, body)?
+    } else {
+        let value =
+            serde_json::from_slice(body).map_err(|e| Rejection::Malformed(e.to_string()))?;
+        CloudEvent::from_structured(value)?
+    };
+    let stream_attribute = state
+        .runtime_config
+        .read()
+        .unwrap()
+        .cloudevents_stream_attribute
+        .clone();
+    let received_at = Utc::now().timestamp_millis();
+    let mut event = cloud_event.into_flux_event(&stream_attribute, received_at)?;
+    state.admission().admit(&mut event, headers, received_at)?;
+    let request_id = request_id(headers);
+
+    info!(
+        event_id = %event.event_id.as_ref().unwrap(),
+        stream = %event.stream,
+        source = %event.source,
+        request_id = %request_id,
+        "Ingesting CloudEvent"
+    );
+
+    state
+        .event_publisher
+        .publish_with_request_id(&event, Some(&request_id))
+        .await
+        .map_err(publish_failed)?;
+
+    Ok(Json(EventResponse {
+        event_id: event.event_id.unwrap(),
+        stream: event.stream,
+    }))
+}
+
+/// True if the request's media type, ignoring parameters, is `media_type`
+fn content_type_is(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case(media_type))
+}
+
+fn publish_failed(e: anyhow::Error) -> AppError {
+    error!(error = %e, "Failed to publish event to NATS");
+    if is_unavailable(&e) {
+        AppError::Unavailable(e.to_string())
+    } else {
+        AppError::PublishError(e.to_string())
+    }
+}
+
 /// POST /api/events/batch - Publish multiple events
+#[utoipa::path(
+    post,
+    path = "/api/events/batch",
+    tag = "ingestion",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Per-event results", body = BatchResponse),
+        (status = 400, description = "Malformed or empty batch", body = ErrorResponse),
+        (status = 413, description = "Body exceeds size limit", body = ErrorResponse),
+        (status = 415, description = "Unsupported Content-Encoding", body = ErrorResponse),
+        (status = 503, description = "NATS connection down, nothing published; retry after Retry-After seconds", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn publish_batch(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Json<BatchResponse>, AppError> {
-    // Check body size against runtime-configurable limit
-    let limit = state.runtime_config.read().unwrap().body_size_limit_batch_bytes;
+    // Check body size against runtime-configurable limit, after decompression
+    let limit = state
+        .runtime_config
+        .read()
+        .unwrap()
+        .body_size_limit_batch_bytes;
+    let body = decode_body(&headers, body, limit)?;
     if body.len() > limit {
-        return Err(AppError::PayloadTooLarge);
+        return Err(Rejection::BodyTooLarge.into());
     }
 
-    // Deserialize from checked bytes
-    let mut request: BatchRequest = serde_json::from_slice(&body)
-        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    // Deserialize from checked bytes; a CloudEvent that can't be mapped fails
+    // on its own, like an event failing validation
+    let received_at = Utc::now().timestamp_millis();
+    let events: Vec<Result<FluxEvent, CloudEventError>> =
+        if content_type_is(&headers, CLOUDEVENTS_BATCH_CONTENT_TYPE) {
+            let cloud_events: Vec<serde_json::Value> =
+                serde_json::from_slice(&body).map_err(|e| Rejection::Malformed(e.to_string()))?;
+            let stream_attribute = state
+                .runtime_config
+                .read()
+                .unwrap()
+                .cloudevents_stream_attribute
+                .clone();
+            cloud_events
+                .into_iter()
+                .map(|value| {
+                    CloudEvent::from_structured(value)?
+                        .into_flux_event(&stream_attribute, received_at)
+                })
+                .collect()
+        } else {
+            let request: BatchRequest =
+                serde_json::from_slice(&body).map_err(|e| Rejection::Malformed(e.to_string()))?;
+            request.events.into_iter().map(Ok).collect()
+        };
 
-    if request.events.is_empty() {
-        return Err(AppError::ValidationError(
+    if events.is_empty() {
+        return Err(Rejection::Malformed(
             "Batch request must contain at least one event".to_string(),
-        ));
+        )
+        .into());
     }
 
-    info!(count = request.events.len(), "Ingesting event batch");
+    let request_id = request_id(&headers);
+    info!(count = events.len(), request_id = %request_id, "Ingesting event batch");
 
-    let mut results = Vec::new();
-    let mut successful = 0;
-    let mut failed = 0;
+    let admission = state.admission();
+    let total = events.len();
+    let mut results: Vec<Option<BatchResult>> = Vec::with_capacity(total);
+    // Events that passed validation, auth and rate limiting, with their index
+    let mut accepted = Vec::new();
+    let mut accepted_index = Vec::new();
 
-    for event in &mut request.events {
-        // Validate and prepare
-        if let Err(e) = event.validate_and_prepare() {
-            failed += 1;
-            results.push(BatchResult {
-                event_id: None,
-                stream: Some(event.stream.clone()),
-                error: Some(format!("validation failed: {}", e)),
-            });
-            continue;
-        }
-
-        // Authorize event (if auth enabled)
-        if let Err(e) = authorize_event(
-            &headers,
-            event,
-            &state.namespace_registry,
-            state.auth_enabled,
-        ) {
-            failed += 1;
-            results.push(BatchResult {
-                event_id: event.event_id.clone(),
+    for event in events {
+        let mut event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                results.push(Some(BatchResult {
+                    event_id: None,
+                    stream: None,
+                    error: Some(format!("invalid CloudEvent: {}", e)),
+                }));
+                continue;
+            }
+        };
+        if let Err(rejection) = admission.admit(&mut event, &headers, received_at) {
+            let (event_id, error) = match rejection {
+                Rejection::Invalid(e) => (None, format!("validation failed: {}", e)),
+                Rejection::Unauthorized(e) => (
+                    event.event_id.clone(),
+                    format!("authorization failed: {}", e),
+                ),
+                other => (event.event_id.clone(), other.to_string()),
+            };
+            results.push(Some(BatchResult {
+                event_id,
                 stream: Some(event.stream.clone()),
-                error: Some(format!("authorization failed: {}", e)),
-            });
+                error: Some(error),
+            }));
             continue;
         }
 
-        // Rate limit check (auth-gated)
-        if state.auth_enabled {
-            let namespace = extract_namespace_from_event(event);
-            let limit = state
-                .runtime_config
-                .read()
-                .unwrap()
-                .rate_limit_per_namespace_per_minute;
-            if !state.rate_limiter.check_and_consume(&namespace, limit) {
-                failed += 1;
-                results.push(BatchResult {
-                    event_id: event.event_id.clone(),
-                    stream: Some(event.stream.clone()),
-                    error: Some("rate limit exceeded".to_string()),
-                });
-                continue;
-            }
-        }
+        accepted_index.push(results.len());
+        results.push(None);
+        accepted.push(event);
+    }
 
-        // Publish to NATS
-        match state.event_publisher.publish(event).await {
-            Ok(_) => {
-                successful += 1;
-                results.push(BatchResult {
-                    event_id: event.event_id.clone(),
-                    stream: Some(event.stream.clone()),
-                    error: None,
-                });
-            }
-            Err(e) => {
-                error!(error = %e, event_id = %event.event_id.as_ref().unwrap(), "Failed to publish event");
-                failed += 1;
-                results.push(BatchResult {
-                    event_id: event.event_id.clone(),
-                    stream: Some(event.stream.clone()),
-                    error: Some(format!("publish failed: {}", e)),
-                });
-            }
-        }
+    // Publish to NATS; acks are awaited concurrently, order is kept
+    let published = state
+        .event_publisher
+        .publish_batch_with_request_id(&accepted, Some(&request_id))
+        .await;
+    // Nothing got through: tell the client to retry the whole batch
+    if !published.is_empty()
+        && published
+            .iter()
+            .all(|result| result.as_ref().is_err_and(is_unavailable))
+    {
+        error!(
+            count = published.len(),
+            "NATS connection down, batch not published"
+        );
+        return Err(AppError::Unavailable(
+            "NATS connection is down, no events were published".to_string(),
+        ));
     }
+    for ((index, event), result) in accepted_index.into_iter().zip(&accepted).zip(published) {
+        let error = result.err().map(|e| {
+            error!(error = %e, event_id = %event.event_id.as_ref().unwrap(), "Failed to publish event");
+            format!("publish failed: {}", e)
+        });
+        results[index] = Some(BatchResult {
+            event_id: event.event_id.clone(),
+            stream: Some(event.stream.clone()),
+            error,
+        });
+    }
+
+    let results: Vec<BatchResult> = results.into_iter().flatten().collect();
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let successful = total - failed;
 
     Ok(Json(BatchResponse {
         successful,
@@ -243,69 +402,404 @@ async fn publish_batch(
 
 /// Application error types
 enum AppError {
-    ValidationError(String),
+    /// Event turned away by admission; same status and message as over NATS
+    Rejected(Rejection),
     PublishError(String),
-    Unauthorized(String),
-    Forbidden(String),
-    PayloadTooLarge,
-    RateLimited,
+    /// NATS connection down; the client should retry
+    Unavailable(String),
+    /// CloudEvent that can't be mapped onto a Flux event
+    CloudEvent(CloudEventError),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        match self {
-            AppError::RateLimited => {
-                let body = Json(ErrorResponse {
-                    error: "rate limit exceeded".to_string(),
-                });
-                let mut resp = (StatusCode::TOO_MANY_REQUESTS, body).into_response();
-                resp.headers_mut().insert(
-                    axum::http::header::RETRY_AFTER,
-                    axum::http::HeaderValue::from_static("60"),
-                );
-                resp
-            }
-            other => {
-                let (status, error_message) = match other {
-                    AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
-                    AppError::PublishError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-                    AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-                    AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
-                    AppError::PayloadTooLarge => {
-                        (StatusCode::PAYLOAD_TOO_LARGE, "payload too large".to_string())
-                    }
-                    AppError::RateLimited => unreachable!(),
-                };
-                let body = Json(ErrorResponse {
-                    error: error_message,
-                });
-                (status, body).into_response()
+        let (status, error) = match self {
+            AppError::Rejected(rejection) => (rejection.status(), rejection.to_string()),
+            AppError::PublishError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::Unavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
+            AppError::CloudEvent(e) if e.is_unprocessable() => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
             }
+            AppError::CloudEvent(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        let retry_after = match status {
+            StatusCode::TOO_MANY_REQUESTS => Some("60"),
+            StatusCode::SERVICE_UNAVAILABLE => Some("5"),
+            _ => None,
+        };
+        let mut resp = (status, Json(ErrorResponse { error })).into_response();
+        if let Some(seconds) = retry_after {
+            resp.headers_mut().insert(
+                axum::http::header::RETRY_AFTER,
+                axum::http::HeaderValue::from_static(seconds),
+            );
         }
+        resp
     }
 }
 
-impl From<AuthError> for AppError {
-    fn from(e: AuthError) -> Self {
-        match e {
-            AuthError::InvalidToken(msg) => AppError::Unauthorized(msg),
-            AuthError::InvalidEntityId(msg) => AppError::Unauthorized(msg),
-            AuthError::NamespaceNotFound(msg) => AppError::Unauthorized(msg),
-            AuthError::Forbidden(msg) => AppError::Forbidden(msg),
-        }
+impl From<Rejection> for AppError {
+    fn from(rejection: Rejection) -> Self {
+        AppError::Rejected(rejection)
     }
 }
 
-/// Extract namespace from event payload's entity_id, falling back to stream name.
-///
-/// Used for rate-limit bucket keying. If entity_id is missing or has no namespace
-/// prefix, we fall back to the stream field so rate limiting still applies.
-fn extract_namespace_from_event(event: &FluxEvent) -> String {
-    event
-        .payload
-        .get("entity_id")
-        .and_then(|v| v.as_str())
-        .and_then(|eid| parse_entity_id(eid).ok())
-        .and_then(|parsed| parsed.namespace)
-        .unwrap_or_else(|| event.stream.clone())
+impl From<CloudEventError> for AppError {
+    fn from(e: CloudEventError) -> Self {
+        AppError::CloudEvent(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::new_runtime_config;
+    use crate::nats::{AckFuture, ConnectionMonitor, PublishSink};
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use futures::future::BoxFuture;
+    use serde_json::json;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    /// Records the subject and payload of every published event
+    #[derive(Default)]
+    struct CapturingSink {
+        subjects: Mutex<Vec<String>>,
+        payloads: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl PublishSink for CapturingSink {
+        fn send(
+            &self,
+            subject: String,
+            _request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> BoxFuture<'_, anyhow::Result<AckFuture>> {
+            Box::pin(async move {
+                self.subjects.lock().unwrap().push(subject);
+                self.payloads.lock().unwrap().push(payload);
+                let ack: AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    fn app() -> (Router, Arc<CapturingSink>) {
+        app_with_connection(ConnectionMonitor::new())
+    }
+
+    fn app_with_connection(connection: ConnectionMonitor) -> (Router, Arc<CapturingSink>) {
+        let sink = Arc::new(CapturingSink::default());
+        let state = AppState {
+            event_publisher: EventPublisher::with_sink(Arc::clone(&sink) as Arc<dyn PublishSink>)
+                .with_connection(connection),
+            namespace_registry: Arc::new(NamespaceRegistry::new()),
+            auth_enabled: false,
+            admin_token: None,
+            runtime_config: new_runtime_config(),
+            rate_limiter: Arc::new(RateLimiter::new()),
+        };
+        (create_router(state), sink)
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn post_gzipped(uri: &str, body: Vec<u8>) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gzipped_batch_is_published() {
+        let (app, sink) = app();
+        let event = |entity_id: &str| {
+            json!({
+                "stream": "sensors",
+                "source": "edge-01",
+                "timestamp": Utc::now().timestamp_millis(),
+                "payload": {"entity_id": entity_id, "properties": {"temperature": 21.5}}
+            })
+        };
+        let batch = json!({"events": [event("sensor-01"), event("sensor-02")]});
+
+        let response = app
+            .oneshot(post_gzipped(
+                "/api/events/batch",
+                gzip(batch.to_string().as_bytes()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: BatchResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((body.successful, body.failed), (2, 0));
+        assert_eq!(sink.subjects.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_event_payload_is_published_verbatim() {
+        let (app, sink) = app();
+        let payload =
+            r#"{"entity_id":"sensor-01","properties":{"temperature":21.50,"tags":["a"]}}"#;
+        let body = format!(
+            r#"{{"stream":"sensors","source":"edge-01","timestamp":{},"payload":{}}}"#,
+            Utc::now().timestamp_millis(),
+            payload
+        );
+
+        let request = Request::post("/api/events")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: EventResponse = serde_json::from_slice(&response).unwrap();
+
+        let published = sink.payloads.lock().unwrap().remove(0);
+        let published = String::from_utf8(published).unwrap();
+        // `21.50` would be `21.5` had the payload been re-encoded
+        assert!(published.contains(payload));
+        let event: FluxEvent = serde_json::from_str(&published).unwrap();
+        assert_eq!(event.event_id, Some(response.event_id));
+        assert!(event.received_at.is_some());
+        assert_eq!(
+            sink.subjects.lock().unwrap()[0],
+            "flux.events._default.sensors"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_single_event_validation_errors() {
+        let (app, sink) = app();
+        let now = Utc::now().timestamp_millis();
+        fn event(stream: &str, timestamp: i64, payload: serde_json::Value) -> serde_json::Value {
+            json!({"stream": stream, "source": "s", "timestamp": timestamp, "payload": payload})
+        }
+        let cases = [
+            (event("Bad", now, json!({})), StatusCode::BAD_REQUEST),
+            (event("s", now, json!([1])), StatusCode::BAD_REQUEST),
+            (event("s", now, json!(null)), StatusCode::BAD_REQUEST),
+            (
+                json!({"stream": "s", "source": "s", "timestamp": now}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                event("s", now + 3_600_000, json!({})),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                event("s", now, json!({"blob": "x".repeat(300_000)})),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ];
+        for (body, status) in cases {
+            let request = Request::post("/api/events")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), status, "{}", body);
+        }
+        assert!(sink.subjects.lock().unwrap().is_empty());
+    }
+
+    fn cloud_event(data: serde_json::Value) -> serde_json::Value {
+        json!({
+            "specversion": "1.0",
+            "id": "ce-0001",
+            "source": "/plant/line-4",
+            "type": "plant.telemetry",
+            "subject": "pump-7",
+            "data": data
+        })
+    }
+
+    #[tokio::test]
+    async fn test_structured_cloudevent_is_published() {
+        let (app, sink) = app();
+        let event = cloud_event(json!({"entity_id": "pump-7", "properties": {"rpm": 1450}}));
+        let request = Request::post("/api/events")
+            .header(
+                header::CONTENT_TYPE,
+                "application/cloudevents+json; charset=utf-8",
+            )
+            .body(Body::from(event.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let published = sink.payloads.lock().unwrap().remove(0);
+        let event: FluxEvent = serde_json::from_slice(&published).unwrap();
+        assert_eq!(event.event_id.as_deref(), Some("ce-0001"));
+        assert_eq!(event.schema.as_deref(), Some("plant.telemetry"));
+        assert_eq!(event.key.as_deref(), Some("pump-7"));
+        assert!(event.received_at.is_some());
+        assert_eq!(
+            sink.subjects.lock().unwrap()[0],
+            "flux.events._default.plant.telemetry"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_binary_cloudevent_is_published() {
+        let (app, sink) = app();
+        let request = Request::post("/api/events")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("ce-specversion", "1.0")
+            .header("ce-id", "ce-0002")
+            .header("ce-source", "/plant/line-4")
+            .header("ce-type", "plant.telemetry")
+            .body(Body::from(
+                r#"{"entity_id": "pump-7", "properties": {"rpm": 1450}}"#,
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: EventResponse = serde_json::from_slice(&response).unwrap();
+        assert_eq!(response.event_id, "ce-0002");
+        assert_eq!(response.stream, "plant.telemetry");
+        assert_eq!(sink.subjects.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cloudevent_errors() {
+        let (app, sink) = app();
+        let mut no_id = cloud_event(json!({}));
+        no_id.as_object_mut().unwrap().remove("id");
+        let cases = [
+            (cloud_event(json!([1, 2])), StatusCode::UNPROCESSABLE_ENTITY),
+            (cloud_event(json!(null)), StatusCode::UNPROCESSABLE_ENTITY),
+            (no_id, StatusCode::BAD_REQUEST),
+            (json!([cloud_event(json!({}))]), StatusCode::BAD_REQUEST),
+        ];
+        for (body, status) in cases {
+            let request = Request::post("/api/events")
+                .header(header::CONTENT_TYPE, "application/cloudevents+json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), status, "{}", body);
+        }
+        assert!(sink.subjects.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cloudevents_batch_reports_unmappable_events() {
+        let (app, sink) = app();
+        let batch = json!([
+            cloud_event(json!({"entity_id": "pump-7", "properties": {"rpm": 1450}})),
+            cloud_event(json!("not an object")),
+        ]);
+        let request = Request::post("/api/events/batch")
+            .header(header::CONTENT_TYPE, "application/cloudevents-batch+json")
+            .body(Body::from(batch.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: BatchResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((body.successful, body.failed), (1, 1));
+        assert_eq!(body.results[0].event_id.as_deref(), Some("ce-0001"));
+        assert!(body.results[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("invalid CloudEvent"));
+        assert_eq!(sink.subjects.lock().unwrap().len(), 1);
+
+        let request = Request::post("/api/events/batch")
+            .header(header::CONTENT_TYPE, "application/cloudevents-batch+json")
+            .body(Body::from("[]"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_zip_bomb_is_rejected_with_413() {
+        let (app, sink) = app();
+        // Whitespace is valid JSON padding and compresses about 1000:1
+        let limit = new_runtime_config()
+            .read()
+            .unwrap()
+            .body_size_limit_single_bytes;
+        let mut padded = vec![b' '; limit * 4];
+        padded.extend_from_slice(b"{}");
+        let bomb = gzip(&padded);
+        assert!(bomb.len() < limit / 10);
+
+        let response = app
+            .oneshot(post_gzipped("/api/events", bomb))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(sink.subjects.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_encoding_is_rejected_with_415() {
+        let (app, _) = app();
+        let request = Request::post("/api/events")
+            .header(header::CONTENT_ENCODING, "br")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_nats_outage_answers_503_with_retry_after() {
+        let connection = ConnectionMonitor::new();
+        let (app, sink) = app_with_connection(connection.clone());
+        connection.record_disconnected();
+        let event = json!({
+            "stream": "sensors",
+            "source": "edge-01",
+            "timestamp": Utc::now().timestamp_millis(),
+            "payload": {"entity_id": "sensor-01", "properties": {"temperature": 21.5}}
+        });
+
+        for (uri, body) in [
+            ("/api/events", event.clone()),
+            (
+                "/api/events/batch",
+                json!({"events": [event.clone(), event]}),
+            ),
+        ] {
+            let request = Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "{}",
+                uri
+            );
+            assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        }
+        assert!(sink.subjects.lock().unwrap().is_empty());
+    }
 }
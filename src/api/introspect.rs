@@ -0,0 +1,350 @@
+//! Token introspection: lets other services check what a Flux token may do
+//! without a copy of the namespace registry.
+//!
+//! Every request looks the token up afresh, so a revoked or expired token is
+//! inactive at once. Requests are rate limited per client IP to slow down
+//! token guessing, and tokens are never logged.
+
+use crate::api::openapi::ErrorResponse;
+use crate::auth::extract_bearer_token;
+use crate::namespace::NamespaceRegistry;
+use crate::rate_limit::RateLimiter;
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{debug, warn};
+use utoipa::{OpenApi, ToSchema};
+
+/// Default introspection requests allowed per client IP per minute
+pub const DEFAULT_INTROSPECT_PER_IP_PER_MINUTE: u64 = 60;
+
+/// Shared state for the introspection endpoint
+pub struct IntrospectAppState {
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    pub admin_token: Option<String>,
+    /// Requests allowed per client IP per minute (0 = no limit)
+    pub per_ip_per_minute: u64,
+    /// Buckets by client IP
+    pub limiter: RateLimiter,
+}
+
+/// Token to introspect
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({"token": "550e8400-e29b-41d4-a716-446655440000"}))]
+pub struct IntrospectRequest {
+    pub token: String,
+}
+
+/// What a token may do; only `active` is set for an unknown token
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "active": true,
+    "scope": "read",
+    "namespace": "matt",
+    "prefix": "matt/public/",
+    "expires_at": "2026-01-02T00:00:00+00:00"
+}))]
+pub struct IntrospectResponse {
+    pub active: bool,
+    /// `admin`, `namespace` (a namespace token), or a grant's `read` / `write`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Namespace the token is confined to (none for the admin token)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Entity ID prefix a grant token is confined to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// When a grant token stops working; omitted if it doesn't expire
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+impl IntrospectResponse {
+    fn inactive() -> Self {
+        Self {
+            active: false,
+            scope: None,
+            namespace: None,
+            prefix: None,
+            expires_at: None,
+        }
+    }
+}
+
+/// Look `token` up as the admin, a namespace or a grant token
+pub fn introspect_token(
+    token: &str,
+    registry: &NamespaceRegistry,
+    admin_token: Option<&str>,
+) -> IntrospectResponse {
+    if admin_token == Some(token) {
+        return IntrospectResponse {
+            active: true,
+            scope: Some("admin".to_string()),
+            ..IntrospectResponse::inactive()
+        };
+    }
+    if let Some(namespace) = registry.lookup_by_token(token) {
+        return IntrospectResponse {
+            active: true,
+            scope: Some("namespace".to_string()),
+            namespace: Some(namespace.name),
+            ..IntrospectResponse::inactive()
+        };
+    }
+    match registry.lookup_grant(token) {
+        Some(grant) => IntrospectResponse {
+            active: true,
+            scope: Some(grant.scope.as_str().to_string()),
+            prefix: Some(grant.entity_prefix()),
+            namespace: Some(grant.namespace),
+            expires_at: grant.expires_at.map(|t| t.to_rfc3339()),
+        },
+        None => IntrospectResponse::inactive(),
+    }
+}
+
+/// OpenAPI description of the introspection endpoint
+#[derive(OpenApi)]
+#[openapi(
+    paths(introspect),
+    components(schemas(IntrospectRequest, IntrospectResponse, ErrorResponse))
+)]
+pub(crate) struct IntrospectApi;
+
+/// Create token introspection router
+pub fn create_introspect_router(state: Arc<IntrospectAppState>) -> Router {
+    Router::new()
+        .route("/api/auth/introspect", post(introspect))
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// POST /api/auth/introspect - Check a token
+///
+/// The token is taken from the body or, without one, from the
+/// `Authorization` header. An unknown, revoked or expired token answers 200
+/// with `{"active": false}`. Responses are never cached.
+#[utoipa::path(
+    post,
+    path = "/api/auth/introspect",
+    tag = "auth",
+    request_body(content = Option<IntrospectRequest>, description = "Token to check; omit to check the bearer token"),
+    responses(
+        (status = 200, description = "Whether the token is active, and what it may do", body = IntrospectResponse),
+        (status = 400, description = "No token given, or invalid body", body = ErrorResponse),
+        (status = 429, description = "Too many requests from this client IP", body = ErrorResponse),
+    )
+)]
+async fn introspect(
+    State(state): State<Arc<IntrospectAppState>>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    // Without connect info every client shares one bucket
+    let client = peer.map_or_else(
+        || "unknown".to_string(),
+        |ConnectInfo(addr)| addr.ip().to_string(),
+    );
+    if state.per_ip_per_minute > 0
+        && !state
+            .limiter
+            .check_and_consume(&client, state.per_ip_per_minute)
+    {
+        warn!(client = %client, "Token introspection rate limited");
+        let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from_static("60"));
+        return response;
+    }
+
+    let token = if body.iter().all(u8::is_ascii_whitespace) {
+        match extract_bearer_token(&headers) {
+            Ok(token) => token,
+            Err(_) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Pass the token in the body or the Authorization header",
+                )
+            }
+        }
+    } else {
+        match serde_json::from_slice::<IntrospectRequest>(&body) {
+            Ok(request) if !request.token.is_empty() => request.token,
+            Ok(_) => return error_response(StatusCode::BAD_REQUEST, "token must not be empty"),
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, &format!("Invalid body: {}", e))
+            }
+        }
+    };
+
+    let response = introspect_token(
+        &token,
+        &state.namespace_registry,
+        state.admin_token.as_deref(),
+    );
+    debug!(client = %client, active = response.active, scope = ?response.scope, "Token introspected");
+    (
+        [(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))],
+        Json(response),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::namespace::GrantScope;
+    use axum::body::Body;
+    use axum::http::Request;
+    use chrono::Duration;
+    use tower::ServiceExt;
+
+    fn app(registry: &Arc<NamespaceRegistry>, per_ip_per_minute: u64) -> Router {
+        create_introspect_router(Arc::new(IntrospectAppState {
+            namespace_registry: Arc::clone(registry),
+            admin_token: Some("admin-secret".to_string()),
+            per_ip_per_minute,
+            limiter: RateLimiter::new(),
+        }))
+    }
+
+    fn request(ip: [u8; 4], body: String) -> Request<Body> {
+        let mut request = Request::post("/api/auth/introspect")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+        request
+    }
+
+    async fn introspect(app: &Router, token: &str) -> IntrospectResponse {
+        let body = serde_json::json!({ "token": token }).to_string();
+        let response = app
+            .clone()
+            .oneshot(request([10, 0, 0, 1], body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_each_token_class() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let matt = registry.register("matt").unwrap();
+        let grant = registry
+            .create_grant(
+                "matt",
+                "public/",
+                GrantScope::Read,
+                Some(Duration::hours(1)),
+            )
+            .unwrap();
+        let app = app(&registry, 0);
+
+        let admin = introspect(&app, "admin-secret").await;
+        assert!(admin.active);
+        assert_eq!(admin.scope.as_deref(), Some("admin"));
+        assert_eq!(admin.namespace, None);
+
+        let namespace = introspect(&app, &matt.token).await;
+        assert_eq!(namespace.scope.as_deref(), Some("namespace"));
+        assert_eq!(namespace.namespace.as_deref(), Some("matt"));
+        assert_eq!(namespace.expires_at, None);
+
+        let read = introspect(&app, &grant.token).await;
+        assert!(read.active);
+        assert_eq!(read.scope.as_deref(), Some("read"));
+        assert_eq!(read.prefix.as_deref(), Some("matt/public/"));
+        assert_eq!(read.expires_at, grant.expires_at.map(|t| t.to_rfc3339()));
+
+        assert_eq!(
+            introspect(&app, "bogus").await,
+            IntrospectResponse::inactive()
+        );
+
+        // Revocation takes effect on the next request
+        assert!(registry.revoke_grant("matt", &grant.id));
+        assert!(!introspect(&app, &grant.token).await.active);
+        assert!(registry.delete("matt"));
+        assert!(!introspect(&app, &matt.token).await.active);
+    }
+
+    #[tokio::test]
+    async fn test_token_from_header_and_bad_requests() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let matt = registry.register("matt").unwrap();
+        let app = app(&registry, 0);
+
+        let mut from_header = request([10, 0, 0, 1], String::new());
+        from_header.headers_mut().insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", matt.token).parse().unwrap(),
+        );
+        let response = app.clone().oneshot(from_header).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for body in ["", "{\"token\": \"\"}", "not json"] {
+            let response = app
+                .clone()
+                .oneshot(request([10, 0, 0, 1], body.to_string()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{:?}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_per_client_ip() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let app = app(&registry, 2);
+        let body = || "{\"token\": \"guess\"}".to_string();
+
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(request([10, 0, 0, 1], body()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app
+            .clone()
+            .oneshot(request([10, 0, 0, 1], body()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+
+        // Other clients have their own budget
+        let response = app.oneshot(request([10, 0, 0, 2], body())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
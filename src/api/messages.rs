@@ -0,0 +1,375 @@
+use crate::api::admission::{EventAdmission, Rejection};
+use crate::api::auth_middleware::{AuthScope, ReadAuthState};
+use crate::api::openapi::ErrorResponse;
+use crate::event::FluxEvent;
+use crate::namespace::NamespaceRegistry;
+use crate::nats::EventPublisher;
+use crate::state::{AgentMessage, StateEngine};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::error;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+/// Shared state for the agent messaging API
+pub struct MessagesAppState {
+    pub event_publisher: EventPublisher,
+    /// Messages pass the same checks as events sent to `POST /api/events`
+    pub admission: EventAdmission,
+    pub state_engine: Arc<StateEngine>,
+    /// Token that may read every recipient's messages
+    pub admin_token: Option<String>,
+}
+
+impl ReadAuthState for MessagesAppState {
+    fn auth_enabled(&self) -> bool {
+        self.admission.auth_enabled
+    }
+    fn namespace_registry(&self) -> &NamespaceRegistry {
+        &self.admission.namespace_registry
+    }
+    fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+}
+
+/// Message to send
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "from": "agent-a",
+    "to": "agent-b",
+    "body": "Found the anomaly in zone 3"
+}))]
+pub struct SendMessageRequest {
+    /// Sending entity; with auth enabled it must be in the token's namespace
+    pub from: String,
+    pub to: String,
+    #[schema(value_type = Object)]
+    pub body: Value,
+}
+
+/// Event the message was published as
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"eventId": "01936f8e-7c2a-7000-8000-000000000000"}))]
+pub struct SendMessageResponse {
+    #[serde(rename = "eventId")]
+    pub event_id: String,
+}
+
+/// Query parameters for reading messages
+#[derive(Deserialize, IntoParams)]
+pub struct MessagesQueryParams {
+    /// Recipient entity ID
+    pub to: String,
+    /// Only messages sent after this time (RFC 3339)
+    pub since: Option<DateTime<Utc>>,
+    /// Return at most this many of the newest messages
+    pub limit: Option<usize>,
+}
+
+/// OpenAPI description of the messaging endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(send_message, list_messages),
+    components(schemas(SendMessageRequest, SendMessageResponse, AgentMessage, ErrorResponse))
+)]
+pub(crate) struct MessagesApi;
+
+/// Create agent messaging router
+pub fn create_messages_router(state: Arc<MessagesAppState>) -> Router {
+    Router::new()
+        .route("/api/messages", post(send_message).get(list_messages))
+        .with_state(state)
+}
+
+/// POST /api/messages - Send a message from one entity to another
+///
+/// Published on the `flux.messages` stream; subscribers get it as an
+/// `agent_message` WebSocket message once the state engine has read it back.
+#[utoipa::path(
+    post,
+    path = "/api/messages",
+    tag = "messages",
+    request_body = SendMessageRequest,
+    responses(
+        (status = 200, description = "Message published", body = SendMessageResponse),
+        (status = 400, description = "Missing sender, recipient or body", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Sender outside the token's namespace", body = ErrorResponse),
+        (status = 429, description = "Namespace rate limit exceeded", body = ErrorResponse),
+        (status = 500, description = "Publishing failed", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn send_message(
+    State(state): State<Arc<MessagesAppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SendMessageRequest>,
+) -> Result<Json<SendMessageResponse>, MessagesError> {
+    if request.from.is_empty() || request.to.is_empty() {
+        return Err(MessagesError::BadRequest(
+            "`from` and `to` are required".to_string(),
+        ));
+    }
+    if request.body.is_null() {
+        return Err(MessagesError::BadRequest("`body` is required".to_string()));
+    }
+
+    let mut event = FluxEvent::agent_message(&request.from, &request.to, request.body, "api");
+    state
+        .admission
+        .admit(&mut event, &headers, Utc::now().timestamp_millis())?;
+    if let Err(e) = state.event_publisher.publish(&event).await {
+        error!(from = %request.from, to = %request.to, error = %e, "Failed to publish message");
+        return Err(MessagesError::PublishError(e.to_string()));
+    }
+
+    Ok(Json(SendMessageResponse {
+        event_id: event.event_id.unwrap_or_default(),
+    }))
+}
+
+/// GET /api/messages - Recent messages to an entity
+///
+/// Oldest first. Only the last `[state] max_messages_per_recipient`
+/// messages per recipient are kept.
+#[utoipa::path(
+    get,
+    path = "/api/messages",
+    tag = "messages",
+    params(MessagesQueryParams),
+    responses(
+        (status = 200, description = "Messages to the recipient", body = [AgentMessage]),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Recipient outside the token's namespace", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn list_messages(
+    State(state): State<Arc<MessagesAppState>>,
+    scope: AuthScope,
+    Query(params): Query<MessagesQueryParams>,
+) -> Result<Json<Vec<AgentMessage>>, MessagesError> {
+    if !scope.allows(&params.to) {
+        return Err(MessagesError::Forbidden(format!(
+            "Entity '{}' is outside the token's namespace",
+            params.to
+        )));
+    }
+    Ok(Json(state.state_engine.messages_to(
+        &params.to,
+        params.since,
+        params.limit.unwrap_or(usize::MAX),
+    )))
+}
+
+/// Messaging API errors
+#[derive(Debug)]
+pub enum MessagesError {
+    BadRequest(String),
+    Forbidden(String),
+    /// Turned away by admission, answered like `POST /api/events`
+    Rejected(Rejection),
+    PublishError(String),
+}
+
+impl From<Rejection> for MessagesError {
+    fn from(rejection: Rejection) -> Self {
+        MessagesError::Rejected(rejection)
+    }
+}
+
+impl IntoResponse for MessagesError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            MessagesError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            MessagesError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            MessagesError::Rejected(rejection) => (rejection.status(), rejection.to_string()),
+            MessagesError::PublishError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+        (status, Json(ErrorResponse { error: message })).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::new_runtime_config;
+    use crate::nats::{AckFuture, PublishSink};
+    use crate::rate_limit::RateLimiter;
+    use axum::body::Body;
+    use axum::http::Request;
+    use futures::future::BoxFuture;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    /// Keeps every published event so tests can feed it to a state engine
+    #[derive(Default)]
+    struct CapturingSink {
+        events: Mutex<Vec<FluxEvent>>,
+    }
+
+    impl PublishSink for CapturingSink {
+        fn send(
+            &self,
+            _subject: String,
+            _request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> BoxFuture<'_, anyhow::Result<AckFuture>> {
+            Box::pin(async move {
+                let event = serde_json::from_slice(&payload).unwrap();
+                self.events.lock().unwrap().push(event);
+                let ack: AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    struct Harness {
+        app: Router,
+        sink: Arc<CapturingSink>,
+        engine: Arc<StateEngine>,
+        token: String,
+    }
+
+    fn harness(auth_enabled: bool) -> Harness {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let token = registry.register("alice").unwrap().token;
+        registry.register("bob").unwrap();
+        let sink = Arc::new(CapturingSink::default());
+        let engine = Arc::new(StateEngine::new());
+        engine.set_live();
+        let state = MessagesAppState {
+            event_publisher: EventPublisher::with_sink(Arc::clone(&sink) as Arc<dyn PublishSink>),
+            admission: EventAdmission {
+                namespace_registry: registry,
+                auth_enabled,
+                runtime_config: new_runtime_config(),
+                rate_limiter: Arc::new(RateLimiter::new()),
+            },
+            state_engine: Arc::clone(&engine),
+            admin_token: None,
+        };
+        Harness {
+            app: create_messages_router(Arc::new(state)),
+            sink,
+            engine,
+            token,
+        }
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn post_message(body: Value, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/api/messages").header("Content-Type", "application/json");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    fn get_messages(query: &str, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::get(format!("/api/messages?{}", query));
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sent_message_is_logged_and_broadcast() {
+        let h = harness(false);
+        let mut messages = h.engine.subscribe_messages();
+
+        let (status, body) = send(
+            &h.app,
+            post_message(
+                json!({"from": "agent-a", "to": "agent-b", "body": {"text": "hello"}}),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Read back from the stream
+        let published = h.sink.events.lock().unwrap().clone();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].stream, "flux.messages");
+        h.engine.process_event(&published[0], Some(1));
+        assert!(h.engine.get_entity("agent-a").is_none());
+
+        let broadcast = messages.try_recv().unwrap();
+        assert_eq!(broadcast.id, body["eventId"].as_str().unwrap());
+        assert_eq!(broadcast.body, json!({"text": "hello"}));
+
+        let (status, listed) = send(&h.app, get_messages("to=agent-b", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed: Vec<AgentMessage> = serde_json::from_value(listed).unwrap();
+        assert_eq!(listed, vec![broadcast]);
+
+        let (_, listed) = send(&h.app, get_messages("to=agent-a", None)).await;
+        assert_eq!(listed, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_validation() {
+        let h = harness(false);
+        for body in [
+            json!({"from": "", "to": "agent-b", "body": "hi"}),
+            json!({"from": "agent-a", "to": "agent-b", "body": null}),
+        ] {
+            let (status, _) = send(&h.app, post_message(body, None)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        assert!(h.sink.events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_messages_scoped_to_namespace() {
+        let h = harness(true);
+        let token = Some(h.token.as_str());
+
+        let (status, _) = send(
+            &h.app,
+            post_message(
+                json!({"from": "bob/agent", "to": "alice/agent", "body": "hi"}),
+                token,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send(
+            &h.app,
+            post_message(
+                json!({"from": "alice/agent", "to": "bob/agent", "body": "hi"}),
+                token,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send(&h.app, get_messages("to=bob/agent", token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(&h.app, get_messages("to=alice/agent", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&h.app, get_messages("to=alice/agent", token)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -1,22 +1,56 @@
 // HTTP and WebSocket APIs (Tasks 4-6)
 
-mod ingestion;
 pub mod admin;
+pub mod admission;
 pub mod auth_middleware;
+pub mod compaction;
 pub mod connectors;
+mod content_encoding;
 pub mod deletion;
+pub mod federation;
+pub mod health;
 pub mod history;
+mod ingestion;
+pub mod introspect;
+pub mod messages;
 pub mod namespace;
+#[cfg(feature = "oauth")]
 pub mod oauth;
+mod openapi;
 pub mod query;
+mod query_cache;
+pub mod rename;
+pub mod replay;
+mod request_id;
+pub mod standby;
+pub mod stream_mappings;
+pub mod watches;
 pub mod websocket;
 
 pub use admin::{create_admin_router, AdminAppState};
+pub use admission::{EventAdmission, Rejection};
+pub use compaction::{create_compaction_router, CompactionAppState};
 pub use connectors::{create_connector_router, ConnectorAppState};
 pub use deletion::{create_deletion_router, DeletionAppState};
+pub use federation::{create_federation_router, FederationAppState};
+pub use health::{block_during_replay, create_health_router, HealthAppState};
 pub use history::{create_history_router, HistoryAppState};
 pub use ingestion::{create_router, AppState};
-pub use namespace::create_namespace_router;
-pub use oauth::{create_oauth_router, run_state_cleanup, OAuthAppState, StateManager};
+pub use introspect::{create_introspect_router, IntrospectAppState};
+pub use messages::{create_messages_router, MessagesAppState};
+pub use namespace::{create_namespace_router, NamespaceAppState};
+#[cfg(feature = "oauth")]
+pub use oauth::{
+    create_oauth_router, parse_allowed_origins, run_state_cleanup, OAuthAppState, ProviderRegistry,
+    StateManager,
+};
+pub use openapi::{create_openapi_router, openapi_spec};
 pub use query::{create_query_router, QueryAppState};
+pub use query_cache::QueryCache;
+pub use rename::{create_rename_router, RenameAppState};
+pub use replay::{create_replay_router, ReplayAppState};
+pub use request_id::{with_request_tracing, REQUEST_ID_HEADER};
+pub use standby::{create_standby_router, primary_only, StandbyAppState};
+pub use stream_mappings::{create_stream_mapping_router, StreamMappingAppState};
+pub use watches::{create_watch_router, WatchAppState};
 pub use websocket::{create_ws_router, ws_handler, WsAppState};
@@ -1,33 +1,80 @@
-use crate::api::AppState;
-use crate::namespace::{RegistrationError, ValidationError};
+use crate::event::FluxEvent;
+use crate::idempotency::{self, Claim, IdempotencyStore, IDEMPOTENT_REPLAYED_HEADER};
+use crate::namespace::{
+    AccessGrant, AuthError, GrantError, GrantScope, Namespace, NamespaceRegistry,
+    RegistrationError, ValidationError,
+};
+use crate::nats::EventPublisher;
+use crate::state::{NamespaceQuota, NamespaceUsage, StateEngine};
 use axum::{
     extract::{Path, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::info;
+use tracing::{error, info};
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the namespace API
+pub struct NamespaceAppState {
+    /// Publishes quota changes, which the state engine applies in stream order
+    pub event_publisher: EventPublisher,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    /// Source of each namespace's usage
+    pub state_engine: Arc<StateEngine>,
+    pub auth_enabled: bool,
+    pub admin_token: Option<String>,
+    /// Remembers `Idempotency-Key`s of registrations; without it the header
+    /// is ignored
+    pub idempotency: Option<Arc<IdempotencyStore>>,
+}
+
+/// Idempotency key scope of namespace registrations: the admin's when an
+/// admin token is configured (it was checked), otherwise shared by the
+/// anonymous callers allowed to register. Replays carry no token, so a shared
+/// scope reveals nothing a namespace lookup wouldn't.
+fn register_scope(state: &NamespaceAppState) -> &'static str {
+    if state.admin_token.is_some() {
+        "namespaces:admin"
+    } else {
+        "namespaces:anonymous"
+    }
+}
 
 /// Request to register a new namespace
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"name": "matt"}))]
 pub struct RegisterRequest {
     pub name: String,
 }
 
 /// Response for successful namespace registration
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"namespaceId": "ns_7x9f2a", "name": "matt", "token": "550e8400-e29b-41d4-a716-446655440000"}))]
 pub struct RegisterResponse {
     #[serde(rename = "namespaceId")]
     pub namespace_id: String,
     pub name: String,
-    pub token: String,
+    /// Absent when an `Idempotency-Key` replay answers a retry; the token is
+    /// only in the first response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
 }
 
 /// Response for namespace lookup (NO token)
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "namespaceId": "ns_7x9f2a",
+    "name": "matt",
+    "createdAt": "2026-01-01T00:00:00+00:00",
+    "entityCount": 42,
+    "quota": {"max_entities": 10000, "max_bytes": null},
+    "usage": {"entities": 42, "bytes": 18230, "quota_rejections": 0}
+}))]
 pub struct NamespaceInfo {
     #[serde(rename = "namespaceId")]
     pub namespace_id: String,
@@ -36,31 +83,163 @@ pub struct NamespaceInfo {
     pub created_at: String,
     #[serde(rename = "entityCount")]
     pub entity_count: u64,
+    /// Limits on the namespace (null = unlimited)
+    pub quota: NamespaceQuota,
+    /// What the namespace holds, counted against its quota
+    pub usage: NamespaceUsage,
+}
+
+/// Longest grant lifetime accepted (ten years)
+const MAX_GRANT_EXPIRES_IN: u64 = 10 * 365 * 24 * 60 * 60;
+
+/// Request to create an access grant
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({"prefix": "public/", "scope": "read", "expires_in": 86400}))]
+pub struct CreateGrantRequest {
+    /// Path inside the namespace; `public`, `public/` and `public/*` are the same
+    pub prefix: String,
+    pub scope: GrantScope,
+    /// Lifetime in seconds (omit for a grant that lasts until revoked)
+    pub expires_in: Option<u64>,
+}
+
+/// An access grant (token only in the create response)
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "grantId": "grant_k3m9x2pq",
+    "namespace": "matt",
+    "prefix": "public/",
+    "scope": "read",
+    "token": "6f1c2a9e-4b7d-4e8a-9c3f-2d5b8a7e1f04",
+    "createdAt": "2026-01-01T00:00:00+00:00",
+    "expiresAt": "2026-01-02T00:00:00+00:00"
+}))]
+pub struct GrantInfo {
+    #[serde(rename = "grantId")]
+    pub grant_id: String,
+    pub namespace: String,
+    /// Path inside the namespace the grant covers, ending in `/`
+    pub prefix: String,
+    pub scope: GrantScope,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// Null = never expires
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<String>,
+}
+
+impl GrantInfo {
+    fn new(grant: AccessGrant, with_token: bool) -> Self {
+        GrantInfo {
+            grant_id: grant.id,
+            namespace: grant.namespace,
+            prefix: grant.prefix,
+            scope: grant.scope,
+            token: with_token.then_some(grant.token),
+            created_at: grant.created_at.to_rfc3339(),
+            expires_at: grant.expires_at.map(|t| t.to_rfc3339()),
+        }
+    }
 }
 
 /// Error response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
+/// OpenAPI description of the namespace endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        register_namespace,
+        lookup_namespace,
+        delete_namespace,
+        set_namespace_quota,
+        create_grant,
+        list_grants,
+        revoke_grant
+    ),
+    components(schemas(
+        RegisterRequest,
+        RegisterResponse,
+        NamespaceInfo,
+        NamespaceQuota,
+        NamespaceUsage,
+        CreateGrantRequest,
+        GrantInfo,
+        GrantScope,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct NamespaceApi;
+
 /// Create namespace API router
-pub fn create_namespace_router(state: AppState) -> Router {
+pub fn create_namespace_router(state: NamespaceAppState) -> Router {
     Router::new()
         .route("/api/namespaces", post(register_namespace))
         .route(
             "/api/namespaces/:name",
             get(lookup_namespace).delete(delete_namespace),
         )
+        .route(
+            "/api/admin/namespaces/:name/quota",
+            put(set_namespace_quota),
+        )
+        .route(
+            "/api/namespaces/:name/grants",
+            post(create_grant).get(list_grants),
+        )
+        .route(
+            "/api/namespaces/:name/grants/:grant_id",
+            delete(revoke_grant),
+        )
         .with_state(Arc::new(state))
 }
 
+/// Lookup response for `namespace`, with usage from the state engine
+fn namespace_info(state: &NamespaceAppState, namespace: Namespace) -> NamespaceInfo {
+    let usage = state.state_engine.namespace_usage(&namespace.name);
+    NamespaceInfo {
+        namespace_id: namespace.id,
+        name: namespace.name,
+        created_at: namespace.created_at.to_rfc3339(),
+        entity_count: usage.entities,
+        quota: namespace.quota,
+        usage,
+    }
+}
+
 /// POST /api/namespaces - Register new namespace
+///
+/// With an `Idempotency-Key` header, repeating the request with the same
+/// body returns the first registration instead of a conflict, without the
+/// token: only the namespace ID and name are kept, so the client must hold on
+/// to the token from the first response.
+#[utoipa::path(
+    post,
+    path = "/api/namespaces",
+    tag = "namespaces",
+    request_body = RegisterRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Repeats with the same key and body return the first response for 24 hours"),
+    ),
+    responses(
+        (status = 200, description = "Namespace registered; token is only returned here. A retry with the same Idempotency-Key gets the earlier registration without its token, with `Idempotent-Replayed: true`", body = RegisterResponse),
+        (status = 400, description = "Invalid namespace name or Idempotency-Key", body = ErrorResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Auth disabled", body = ErrorResponse),
+        (status = 409, description = "Name already exists, or the Idempotency-Key was used with another body or is still in progress", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
 async fn register_namespace(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<NamespaceAppState>>,
     headers: HeaderMap,
     Json(request): Json<RegisterRequest>,
-) -> Result<Json<RegisterResponse>, NamespaceError> {
+) -> Result<Response, NamespaceError> {
     // Check if auth is enabled
     if !state.auth_enabled {
         return Err(NamespaceError::AuthDisabled);
@@ -77,13 +256,55 @@ async fn register_namespace(
         }
     }
 
+    let body_hash =
+        idempotency::body_hash(&serde_json::to_vec(&request).expect("RegisterRequest serializes"));
+    let key = idempotency::key_from_headers(&headers)
+        .map_err(|e| NamespaceError::InvalidRequest(e.to_string()))?;
+    let scope = register_scope(&state);
+    let claimed = match (&state.idempotency, key) {
+        (Some(store), Some(key)) => {
+            match store
+                .claim(scope, key, &body_hash)
+                .map_err(NamespaceError::Idempotency)?
+            {
+                Claim::New => Some((store, key)),
+                Claim::Replay(stored) => {
+                    let response: RegisterResponse = serde_json::from_str(&stored)
+                        .map_err(|e| NamespaceError::Idempotency(e.into()))?;
+                    info!(name = %response.name, "Replaying idempotent namespace registration");
+                    return Ok(
+                        ([(IDEMPOTENT_REPLAYED_HEADER, "true")], Json(response)).into_response()
+                    );
+                }
+                Claim::Mismatch => {
+                    return Err(NamespaceError::IdempotencyConflict(
+                        "Idempotency-Key was already used with a different request body",
+                    ))
+                }
+                Claim::InProgress => {
+                    return Err(NamespaceError::IdempotencyConflict(
+                        "A request with this Idempotency-Key is still in progress",
+                    ))
+                }
+            }
+        }
+        _ => None,
+    };
+
     info!(name = %request.name, "Registering namespace");
 
     // Register namespace
-    let namespace = state
-        .namespace_registry
-        .register(&request.name)
-        .map_err(NamespaceError::Registration)?;
+    let namespace = match state.namespace_registry.register(&request.name) {
+        Ok(namespace) => namespace,
+        Err(e) => {
+            if let Some((store, key)) = claimed {
+                if let Err(e) = store.release(scope, key) {
+                    error!(error = %e, "Failed to release idempotency key");
+                }
+            }
+            return Err(NamespaceError::Registration(e));
+        }
+    };
 
     info!(
         namespace_id = %namespace.id,
@@ -91,16 +312,35 @@ async fn register_namespace(
         "Namespace registered successfully"
     );
 
-    Ok(Json(RegisterResponse {
+    let mut response = RegisterResponse {
         namespace_id: namespace.id,
         name: namespace.name,
-        token: namespace.token,
-    }))
+        token: None,
+    };
+    if let Some((store, key)) = claimed {
+        // Stored without the token, which must not be kept at rest
+        let stored = serde_json::to_string(&response).expect("RegisterResponse serializes");
+        if let Err(e) = store.complete(scope, key, &stored) {
+            error!(error = %e, "Failed to store idempotent registration");
+        }
+    }
+    response.token = Some(namespace.token);
+    Ok(Json(response).into_response())
 }
 
 /// GET /api/namespaces/:name - Lookup namespace (NO token in response)
+#[utoipa::path(
+    get,
+    path = "/api/namespaces/{name}",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    responses(
+        (status = 200, description = "Namespace info", body = NamespaceInfo),
+        (status = 404, description = "Namespace not found or auth disabled", body = ErrorResponse),
+    )
+)]
 async fn lookup_namespace(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<NamespaceAppState>>,
     Path(name): Path<String>,
 ) -> Result<Json<NamespaceInfo>, NamespaceError> {
     // Check if auth is enabled
@@ -114,17 +354,24 @@ async fn lookup_namespace(
         .lookup_by_name(&name)
         .ok_or(NamespaceError::NotFound)?;
 
-    Ok(Json(NamespaceInfo {
-        namespace_id: namespace.id,
-        name: namespace.name,
-        created_at: namespace.created_at.to_rfc3339(),
-        entity_count: namespace.entity_count,
-    }))
+    Ok(Json(namespace_info(&state, namespace)))
 }
 
 /// DELETE /api/namespaces/:name - Delete namespace (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/namespaces/{name}",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    responses(
+        (status = 204, description = "Namespace deleted"),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Namespace not found", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
 async fn delete_namespace(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<NamespaceAppState>>,
     headers: HeaderMap,
     Path(name): Path<String>,
 ) -> Result<StatusCode, NamespaceError> {
@@ -149,12 +396,236 @@ async fn delete_namespace(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// PUT /api/admin/namespaces/:name/quota - Set namespace quota (admin only)
+///
+/// Published as an event, so the quota applies from its position in the
+/// stream on and replays identically. Null limits are unlimited.
+#[utoipa::path(
+    put,
+    path = "/api/admin/namespaces/{name}/quota",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    request_body = NamespaceQuota,
+    responses(
+        (status = 200, description = "Quota published; usage may not reflect it yet", body = NamespaceInfo),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Namespace not found or auth disabled", body = ErrorResponse),
+        (status = 500, description = "Failed to publish the quota event", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn set_namespace_quota(
+    State(state): State<Arc<NamespaceAppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(quota): Json<NamespaceQuota>,
+) -> Result<Json<NamespaceInfo>, NamespaceError> {
+    if !state.auth_enabled {
+        return Err(NamespaceError::AuthDisabled);
+    }
+
+    // Require admin token if configured
+    if let Some(ref expected) = state.admin_token {
+        let provided = headers
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if provided != Some(expected.as_str()) {
+            return Err(NamespaceError::Unauthorized);
+        }
+    }
+
+    if state.namespace_registry.lookup_by_name(&name).is_none() {
+        return Err(NamespaceError::NotFound);
+    }
+
+    let mut event = FluxEvent::namespace_quota(&name, &quota, "flux-admin");
+    event
+        .validate_and_prepare()
+        .map_err(|e| NamespaceError::PublishError(e.to_string()))?;
+    if let Err(e) = state.event_publisher.publish(&event).await {
+        error!(name = %name, error = %e, "Failed to publish namespace quota");
+        return Err(NamespaceError::PublishError(e.to_string()));
+    }
+
+    let namespace = state
+        .namespace_registry
+        .set_quota(&name, quota)
+        .ok_or(NamespaceError::NotFound)?;
+
+    info!(
+        name = %name,
+        max_entities = ?quota.max_entities,
+        max_bytes = ?quota.max_bytes,
+        "Namespace quota set"
+    );
+    Ok(Json(namespace_info(&state, namespace)))
+}
+
+/// Require the token of namespace `name` or the admin token
+fn authorize_owner(
+    state: &NamespaceAppState,
+    headers: &HeaderMap,
+    name: &str,
+) -> Result<(), NamespaceError> {
+    if !state.auth_enabled {
+        return Err(NamespaceError::AuthDisabled);
+    }
+
+    let provided = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(NamespaceError::NotOwner)?;
+    if state.admin_token.as_deref() == Some(provided) {
+        return match state.namespace_registry.lookup_by_name(name) {
+            Some(_) => Ok(()),
+            None => Err(NamespaceError::NotFound),
+        };
+    }
+    state
+        .namespace_registry
+        .validate_token(provided, name)
+        .map_err(|e| match e {
+            AuthError::NamespaceNotFound => NamespaceError::NotFound,
+            AuthError::Unauthorized => NamespaceError::NotOwner,
+        })
+}
+
+/// POST /api/namespaces/:name/grants - Create an access grant (owner only)
+///
+/// The grant's token reads (and with `write` scope publishes to and deletes)
+/// only the entities under `prefix`. It is returned once, here.
+#[utoipa::path(
+    post,
+    path = "/api/namespaces/{name}/grants",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    request_body = CreateGrantRequest,
+    responses(
+        (status = 200, description = "Grant created; token is only returned here", body = GrantInfo),
+        (status = 400, description = "Invalid prefix or expires_in", body = ErrorResponse),
+        (status = 403, description = "Namespace or admin token required", body = ErrorResponse),
+        (status = 404, description = "Namespace not found or auth disabled", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []), ("admin_token" = []))
+)]
+async fn create_grant(
+    State(state): State<Arc<NamespaceAppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(request): Json<CreateGrantRequest>,
+) -> Result<Json<GrantInfo>, NamespaceError> {
+    authorize_owner(&state, &headers, &name)?;
+
+    let expires_in = match request.expires_in {
+        None => None,
+        Some(secs) if (1..=MAX_GRANT_EXPIRES_IN).contains(&secs) => {
+            Some(Duration::seconds(secs as i64))
+        }
+        Some(_) => {
+            return Err(NamespaceError::InvalidGrant(format!(
+                "expires_in must be between 1 and {} seconds",
+                MAX_GRANT_EXPIRES_IN
+            )))
+        }
+    };
+
+    let grant = state
+        .namespace_registry
+        .create_grant(&name, &request.prefix, request.scope, expires_in)
+        .map_err(NamespaceError::Grant)?;
+
+    info!(
+        name = %name,
+        grant_id = %grant.id,
+        prefix = %grant.prefix,
+        scope = grant.scope.as_str(),
+        "Access grant created"
+    );
+    Ok(Json(GrantInfo::new(grant, true)))
+}
+
+/// GET /api/namespaces/:name/grants - List access grants (owner only, NO tokens)
+#[utoipa::path(
+    get,
+    path = "/api/namespaces/{name}/grants",
+    tag = "namespaces",
+    params(("name" = String, Path, description = "Namespace name")),
+    responses(
+        (status = 200, description = "Grants of the namespace, expired ones included, oldest first", body = [GrantInfo]),
+        (status = 403, description = "Namespace or admin token required", body = ErrorResponse),
+        (status = 404, description = "Namespace not found or auth disabled", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []), ("admin_token" = []))
+)]
+async fn list_grants(
+    State(state): State<Arc<NamespaceAppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<GrantInfo>>, NamespaceError> {
+    authorize_owner(&state, &headers, &name)?;
+
+    let grants = state
+        .namespace_registry
+        .list_grants(&name)
+        .into_iter()
+        .map(|grant| GrantInfo::new(grant, false))
+        .collect();
+    Ok(Json(grants))
+}
+
+/// DELETE /api/namespaces/:name/grants/:grant_id - Revoke an access grant (owner only)
+///
+/// Takes effect immediately for new requests and WebSocket subscriptions.
+#[utoipa::path(
+    delete,
+    path = "/api/namespaces/{name}/grants/{grant_id}",
+    tag = "namespaces",
+    params(
+        ("name" = String, Path, description = "Namespace name"),
+        ("grant_id" = String, Path, description = "Grant ID"),
+    ),
+    responses(
+        (status = 204, description = "Grant revoked"),
+        (status = 403, description = "Namespace or admin token required", body = ErrorResponse),
+        (status = 404, description = "Namespace or grant not found, or auth disabled", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []), ("admin_token" = []))
+)]
+async fn revoke_grant(
+    State(state): State<Arc<NamespaceAppState>>,
+    headers: HeaderMap,
+    Path((name, grant_id)): Path<(String, String)>,
+) -> Result<StatusCode, NamespaceError> {
+    authorize_owner(&state, &headers, &name)?;
+
+    if !state.namespace_registry.revoke_grant(&name, &grant_id) {
+        return Err(NamespaceError::GrantNotFound);
+    }
+
+    info!(name = %name, grant_id = %grant_id, "Access grant revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Namespace API error types
 enum NamespaceError {
     AuthDisabled,
     Unauthorized,
+    /// Neither the namespace's token nor the admin token
+    NotOwner,
     NotFound,
+    GrantNotFound,
+    InvalidGrant(String),
     Registration(RegistrationError),
+    Grant(GrantError),
+    PublishError(String),
+    /// Malformed Idempotency-Key header
+    InvalidRequest(String),
+    /// Idempotency-Key reused with another body, or still in progress
+    IdempotencyConflict(&'static str),
+    /// The idempotency store failed
+    Idempotency(anyhow::Error),
 }
 
 impl IntoResponse for NamespaceError {
@@ -164,14 +635,34 @@ impl IntoResponse for NamespaceError {
                 StatusCode::NOT_FOUND,
                 "Namespace registration not available (auth disabled)".to_string(),
             ),
-            NamespaceError::Unauthorized => (
-                StatusCode::UNAUTHORIZED,
-                "Admin token required".to_string(),
-            ),
-            NamespaceError::NotFound => (
-                StatusCode::NOT_FOUND,
-                "Namespace not found".to_string(),
+            NamespaceError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "Admin token required".to_string())
+            }
+            NamespaceError::NotOwner => (
+                StatusCode::FORBIDDEN,
+                "Namespace or admin token required".to_string(),
             ),
+            NamespaceError::NotFound => (StatusCode::NOT_FOUND, "Namespace not found".to_string()),
+            NamespaceError::GrantNotFound => (StatusCode::NOT_FOUND, "Grant not found".to_string()),
+            NamespaceError::InvalidGrant(msg) => (StatusCode::BAD_REQUEST, msg),
+            NamespaceError::Grant(e) => {
+                let status = match e {
+                    GrantError::NamespaceNotFound => StatusCode::NOT_FOUND,
+                    GrantError::InvalidPrefix(_) => StatusCode::BAD_REQUEST,
+                    GrantError::StoreFailed => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, e.to_string())
+            }
+            NamespaceError::PublishError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            NamespaceError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            NamespaceError::IdempotencyConflict(msg) => (StatusCode::CONFLICT, msg.to_string()),
+            NamespaceError::Idempotency(e) => {
+                error!(error = %e, "Idempotency store failed");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to check Idempotency-Key".to_string(),
+                )
+            }
             NamespaceError::Registration(e) => match e {
                 RegistrationError::InvalidName(validation_error) => {
                     let msg = match validation_error {
@@ -207,20 +698,16 @@ impl IntoResponse for NamespaceError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::new_runtime_config;
-    use crate::namespace::NamespaceRegistry;
-    use crate::nats::EventPublisher;
-    use crate::rate_limit::RateLimiter;
+    use crate::nats::{AckFuture, PublishSink};
     use axum::body::Body;
     use axum::http::{Request, StatusCode};
+    use futures::future::BoxFuture;
     use serde_json::json;
     use std::sync::Arc;
     use tower::util::ServiceExt;
 
     async fn create_test_publisher() -> EventPublisher {
-        // Create a test NATS client - won't actually connect in tests
-        let client = async_nats::connect("nats://localhost:4223").await.unwrap();
-        EventPublisher::new(async_nats::jetstream::new(client))
+        EventPublisher::with_sink(Arc::new(CapturingSink::default()))
     }
 
     async fn create_test_app(auth_enabled: bool) -> Router {
@@ -231,13 +718,13 @@ mod tests {
         let namespace_registry = Arc::new(NamespaceRegistry::new());
         let event_publisher = create_test_publisher().await;
 
-        let state = AppState {
+        let state = NamespaceAppState {
             event_publisher,
             namespace_registry,
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled,
             admin_token,
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
 
         create_namespace_router(state)
@@ -264,7 +751,7 @@ mod tests {
 
         assert_eq!(response.name, "matt");
         assert!(response.namespace_id.starts_with("ns_"));
-        assert!(!response.token.is_empty());
+        assert!(!response.token.unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -316,24 +803,24 @@ mod tests {
 
         // Create two apps with the same registry
         let event_publisher1 = create_test_publisher().await;
-        let state1 = AppState {
+        let state1 = NamespaceAppState {
             event_publisher: event_publisher1,
             namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled: true,
             admin_token: None,
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
         let app1 = create_namespace_router(state1);
 
         let event_publisher2 = create_test_publisher().await;
-        let state2 = AppState {
+        let state2 = NamespaceAppState {
             event_publisher: event_publisher2,
             namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled: true,
             admin_token: None,
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
         let app2 = create_namespace_router(state2);
 
@@ -367,13 +854,13 @@ mod tests {
 
         let event_publisher = create_test_publisher().await;
 
-        let state = AppState {
+        let state = NamespaceAppState {
             event_publisher,
             namespace_registry,
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled: true,
             admin_token: None,
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
 
         let app = create_namespace_router(state);
@@ -432,13 +919,13 @@ mod tests {
 
         let event_publisher = create_test_publisher().await;
 
-        let state = AppState {
+        let state = NamespaceAppState {
             event_publisher,
             namespace_registry,
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled: true,
             admin_token: None,
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
 
         let app = create_namespace_router(state);
@@ -541,13 +1028,13 @@ mod tests {
         namespace_registry.register("matt").unwrap();
 
         let event_publisher = create_test_publisher().await;
-        let state = AppState {
+        let state = NamespaceAppState {
             event_publisher,
             namespace_registry,
+            state_engine: Arc::new(StateEngine::new()),
             auth_enabled: true,
             admin_token: Some("secret".to_string()),
-            runtime_config: new_runtime_config(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            idempotency: None,
         };
         let app = create_namespace_router(state);
 
@@ -561,4 +1048,356 @@ mod tests {
         let response = app.oneshot(request).await.unwrap();
         assert_eq!(response.status(), StatusCode::NO_CONTENT);
     }
+
+    /// Keeps every published event so tests can feed it to a state engine
+    #[derive(Default)]
+    struct CapturingSink {
+        events: std::sync::Mutex<Vec<FluxEvent>>,
+    }
+
+    impl PublishSink for CapturingSink {
+        fn send(
+            &self,
+            _subject: String,
+            _request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> BoxFuture<'_, anyhow::Result<AckFuture>> {
+            Box::pin(async move {
+                let event = serde_json::from_slice(&payload).unwrap();
+                self.events.lock().unwrap().push(event);
+                let ack: AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    fn quota_app() -> (Router, Arc<CapturingSink>, Arc<StateEngine>) {
+        let namespace_registry = Arc::new(NamespaceRegistry::new());
+        namespace_registry.register("matt").unwrap();
+        let sink = Arc::new(CapturingSink::default());
+        let state_engine = Arc::new(StateEngine::new());
+        let state = NamespaceAppState {
+            event_publisher: EventPublisher::with_sink(Arc::clone(&sink) as Arc<dyn PublishSink>),
+            namespace_registry,
+            state_engine: Arc::clone(&state_engine),
+            auth_enabled: true,
+            admin_token: Some("secret".to_string()),
+            idempotency: None,
+        };
+        (create_namespace_router(state), sink, state_engine)
+    }
+
+    fn put_quota(name: &str, quota: serde_json::Value, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::put(format!("/api/admin/namespaces/{}/quota", name))
+            .header("content-type", "application/json");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::from(quota.to_string())).unwrap()
+    }
+
+    async fn lookup(app: &Router) -> NamespaceInfo {
+        let request = Request::get("/api/namespaces/matt")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_set_quota_publishes_event_and_reports_usage() {
+        let (app, sink, engine) = quota_app();
+
+        let response = app
+            .clone()
+            .oneshot(put_quota(
+                "matt",
+                json!({"max_entities": 1, "max_bytes": null}),
+                Some("secret"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let published = sink.events.lock().unwrap().clone();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].stream, crate::state::QUOTAS_STREAM);
+        assert!(published[0].event_id.is_some());
+
+        // The quota takes effect once the engine processes the event
+        engine.process_event(&published[0], Some(1));
+        for (seq, id) in [(2, "matt/sensor-1"), (3, "matt/sensor-2")] {
+            let mut event = FluxEvent {
+                event_id: None,
+                stream: "sensors".to_string(),
+                source: "test".to_string(),
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                received_at: None,
+                key: None,
+                schema: None,
+                payload: json!({"entity_id": id, "properties": {"temp": 20}}),
+            };
+            event.validate_and_prepare().unwrap();
+            engine.process_event(&event, Some(seq));
+        }
+
+        let info = lookup(&app).await;
+        assert_eq!(info.quota.max_entities, Some(1));
+        assert_eq!(info.quota.max_bytes, None);
+        assert_eq!(info.entity_count, 1);
+        assert_eq!(info.usage.entities, 1);
+        assert_eq!(info.usage.bytes, 6);
+        assert_eq!(info.usage.quota_rejections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_set_quota_requires_admin_and_known_namespace() {
+        let (app, sink, _) = quota_app();
+        let quota = json!({"max_entities": 10});
+
+        for token in [None, Some("wrong")] {
+            let response = app
+                .clone()
+                .oneshot(put_quota("matt", quota.clone(), token))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(put_quota("nonexistent", quota, Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(sink.events.lock().unwrap().is_empty());
+    }
+
+    fn grant_app() -> (Router, Arc<NamespaceRegistry>, String, String) {
+        let namespace_registry = Arc::new(NamespaceRegistry::new());
+        let matt = namespace_registry.register("matt").unwrap();
+        let bob = namespace_registry.register("bob").unwrap();
+        let state = NamespaceAppState {
+            event_publisher: EventPublisher::with_sink(Arc::new(CapturingSink::default())),
+            namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::new(StateEngine::new()),
+            auth_enabled: true,
+            admin_token: Some("secret".to_string()),
+            idempotency: None,
+        };
+        (
+            create_namespace_router(state),
+            namespace_registry,
+            matt.token,
+            bob.token,
+        )
+    }
+
+    fn grants_request(
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+        token: &str,
+    ) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_list_and_revoke_grant() {
+        let (app, registry, matt_token, _) = grant_app();
+
+        let request = grants_request(
+            "POST",
+            "/api/namespaces/matt/grants",
+            Some(json!({"prefix": "public", "scope": "read", "expires_in": 86400})),
+            &matt_token,
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: GrantInfo = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created.prefix, "public/");
+        assert_eq!(created.scope, GrantScope::Read);
+        assert!(created.expires_at.is_some());
+        let token = created.token.unwrap();
+        assert_eq!(registry.lookup_grant(&token).unwrap().namespace, "matt");
+
+        // Listing works with the admin token too and never shows tokens
+        let request = grants_request("GET", "/api/namespaces/matt/grants", None, "secret");
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains(&token));
+        let listed: Vec<GrantInfo> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].grant_id, created.grant_id);
+
+        let uri = format!("/api/namespaces/matt/grants/{}", created.grant_id);
+        let response = app
+            .clone()
+            .oneshot(grants_request("DELETE", &uri, None, &matt_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(registry.lookup_grant(&token).is_none());
+
+        let response = app
+            .oneshot(grants_request("DELETE", &uri, None, &matt_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_grants_require_owner() {
+        let (app, registry, _, bob_token) = grant_app();
+        let grant = registry
+            .create_grant("matt", "public/", GrantScope::Write, None)
+            .unwrap();
+        let body = json!({"prefix": "public/", "scope": "write"});
+
+        // Another namespace's token, a grant token and no valid token at all
+        for token in [bob_token.as_str(), grant.token.as_str(), "wrong"] {
+            for (method, body) in [("POST", Some(body.clone())), ("GET", None)] {
+                let request = grants_request(method, "/api/namespaces/matt/grants", body, token);
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            }
+        }
+        let uri = format!("/api/namespaces/matt/grants/{}", grant.id);
+        let response = app
+            .clone()
+            .oneshot(grants_request("DELETE", &uri, None, &bob_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(registry.lookup_grant(&grant.token).is_some());
+
+        // bob can't revoke matt's grant through his own namespace either
+        let uri = format!("/api/namespaces/bob/grants/{}", grant.id);
+        let response = app
+            .oneshot(grants_request("DELETE", &uri, None, &bob_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(registry.lookup_grant(&grant.token).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_grant_validation() {
+        let (app, registry, matt_token, _) = grant_app();
+
+        for body in [
+            json!({"prefix": "../bob", "scope": "read"}),
+            json!({"prefix": "*", "scope": "read"}),
+            json!({"prefix": "public/", "scope": "read", "expires_in": 0}),
+        ] {
+            let request = grants_request(
+                "POST",
+                "/api/namespaces/matt/grants",
+                Some(body),
+                &matt_token,
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        assert!(registry.list_grants("matt").is_empty());
+
+        let request = grants_request(
+            "POST",
+            "/api/namespaces/nonexistent/grants",
+            Some(json!({"prefix": "public/", "scope": "read"})),
+            "secret",
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_register_namespace_idempotency_key() {
+        let namespace_registry = Arc::new(NamespaceRegistry::new());
+        let store = Arc::new(IdempotencyStore::new(":memory:").unwrap());
+        let app = create_namespace_router(NamespaceAppState {
+            event_publisher: EventPublisher::with_sink(Arc::new(CapturingSink::default())),
+            namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::new(StateEngine::new()),
+            auth_enabled: true,
+            admin_token: None,
+            idempotency: Some(Arc::clone(&store)),
+        });
+        let register = |name: &str, key: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/namespaces")
+                .header("content-type", "application/json")
+                .header("Idempotency-Key", key)
+                .body(Body::from(json!({ "name": name }).to_string()))
+                .unwrap()
+        };
+
+        let first = app.clone().oneshot(register("matt", "k1")).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+        let first: RegisterResponse = serde_json::from_slice(
+            &axum::body::to_bytes(first.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        // A retry gets the same registration back instead of a conflict
+        let retry = app.clone().oneshot(register("matt", "k1")).await.unwrap();
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(retry.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+        let retry: RegisterResponse = serde_json::from_slice(
+            &axum::body::to_bytes(retry.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(retry.namespace_id, first.namespace_id);
+        assert!(first.token.is_some());
+        assert!(retry.token.is_none(), "tokens are not kept for replays");
+        let body_hash = idempotency::body_hash(br#"{"name":"matt"}"#);
+        match store
+            .claim("namespaces:anonymous", "k1", &body_hash)
+            .unwrap()
+        {
+            Claim::Replay(stored) => assert!(!stored.contains("token"), "{}", stored),
+            _ => panic!("registration should be stored under the caller's scope"),
+        }
+        assert!(matches!(
+            store.claim("namespaces:admin", "k1", &body_hash).unwrap(),
+            Claim::New
+        ));
+
+        // Same key, different body
+        let response = app.clone().oneshot(register("bob", "k1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(namespace_registry.lookup_by_name("bob").is_none());
+
+        // Without a key (or with a new one) the name is still taken; the
+        // failed claim is released so the key stays usable
+        let response = app.clone().oneshot(register("matt", "k2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app.clone().oneshot(register("bob", "k2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(register("carol", "not a key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -7,29 +7,54 @@
 //! 4. Provider redirects to /api/connectors/:name/oauth/callback
 //! 5. Exchange code for token, store encrypted credentials
 //! 6. Connector is now "connected" and can poll
+//!
+//! If `/oauth/start` is given a `return_to` URL whose origin is on the
+//! configured allowlist, the callback finishes with a 302 back to it
+//! (`?connector=<name>&status=success` or `status=error&reason=<code>`)
+//! instead of a JSON body.
+//!
+//! Scopes the provider didn't grant are reported as `missing_scopes` in the
+//! callback's JSON body or redirect, and kept with the credentials.
+//!
+//! Hosts without a browser can use the device authorization grant instead:
+//! `POST /api/connectors/:name/oauth/device/start` returns a code for the
+//! user to enter at the provider, and the client calls
+//! `POST /api/connectors/:name/oauth/device/poll` every `interval` seconds
+//! until the credentials are stored. Only providers with a
+//! `device_auth_url` support it.
+//!
+//! Providers come from a [`ProviderRegistry`]: built-ins, plus any loaded
+//! from `FLUX_OAUTH_PROVIDERS_FILE` or added with
+//! `PUT /api/admin/oauth-providers/:name`.
 
 mod exchange;
 mod provider;
 mod state_manager;
 
+pub use exchange::ClientAuth;
+pub use provider::{ProviderDefinition, ProviderError, ProviderRegistry};
 pub use state_manager::{run_state_cleanup, StateManager};
 
-use crate::auth::extract_bearer_token;
-use crate::credentials::CredentialStore;
+use state_manager::StateEntry;
+
+use crate::api::admin::validate_admin_token;
+use crate::api::connectors::connector_owner;
+use crate::credentials::{CredentialStore, ScopeGrant};
 use crate::namespace::NamespaceRegistry;
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json, Redirect, Response},
-    routing::get,
+    routing::{get, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 /// Error response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
@@ -38,8 +63,11 @@ struct ErrorResponse {
 enum AppError {
     BadRequest(String),
     Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
+    Gone(String),
     ServerError(String),
+    NotImplemented(String),
     BadGateway(String),
 }
 
@@ -48,8 +76,11 @@ impl IntoResponse for AppError {
         let (status, error_message) = match self {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::Gone(msg) => (StatusCode::GONE, msg),
             AppError::ServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::NotImplemented(msg) => (StatusCode::NOT_IMPLEMENTED, msg),
             AppError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
         };
 
@@ -69,10 +100,109 @@ pub struct OAuthAppState {
     pub state_manager: StateManager,
     pub auth_enabled: bool,
     pub callback_base_url: String,
+    /// Origins (`scheme://host[:port]`) that `return_to` may point at.
+    /// Empty means post-callback redirects are disabled.
+    pub allowed_return_origins: Vec<String>,
+    /// Providers the flow can be started for
+    pub providers: Arc<ProviderRegistry>,
+    /// Required bearer token for changing providers. None = unrestricted (dev mode)
+    pub admin_token: Option<String>,
+    /// Client for token exchanges (see [`crate::http_client`])
+    pub http_client: reqwest::Client,
+}
+
+/// Parse a comma-separated origin allowlist (`FLUX_OAUTH_ALLOWED_RETURN_ORIGINS`).
+///
+/// Entries are normalized to their origin; invalid entries are skipped with a warning.
+pub fn parse_allowed_origins(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|entry| match reqwest::Url::parse(entry) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                Some(url.origin().ascii_serialization())
+            }
+            _ => {
+                warn!(entry = %entry, "Ignoring invalid OAuth return origin");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Validate a `return_to` URL against the origin allowlist.
+///
+/// Only absolute http(s) URLs whose exact origin is allowlisted are accepted,
+/// which rules out relative, protocol-relative and look-alike host redirects.
+fn validate_return_to(return_to: &str, allowed_origins: &[String]) -> Result<String, AppError> {
+    let url = reqwest::Url::parse(return_to)
+        .map_err(|_| AppError::BadRequest("Invalid 'return_to' URL".to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::BadRequest(
+            "'return_to' must be an http(s) URL".to_string(),
+        ));
+    }
+
+    let origin = url.origin().ascii_serialization();
+    if !allowed_origins.contains(&origin) {
+        return Err(AppError::BadRequest(format!(
+            "'return_to' origin '{}' is not allowed",
+            origin
+        )));
+    }
+
+    Ok(url.to_string())
+}
+
+/// Build the 302 back to the caller's `return_to` with the flow outcome.
+///
+/// On success the outcome is the requested scopes that were not granted.
+fn redirect_to_return(
+    return_to: &str,
+    connector: &str,
+    outcome: Result<&[String], &str>,
+) -> Response {
+    let location = match reqwest::Url::parse(return_to) {
+        Ok(mut url) => {
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("connector", connector);
+                match outcome {
+                    Ok(missing_scopes) => {
+                        query.append_pair("status", "success");
+                        if !missing_scopes.is_empty() {
+                            query.append_pair("missing_scopes", &missing_scopes.join(" "));
+                        }
+                    }
+                    Err(reason) => {
+                        query.append_pair("status", "error");
+                        query.append_pair("reason", reason);
+                    }
+                }
+            }
+            url.to_string()
+        }
+        // Validated on start, so this should not happen
+        Err(_) => {
+            return AppError::ServerError("Invalid stored return_to".to_string()).into_response()
+        }
+    };
+
+    (StatusCode::FOUND, [(header::LOCATION, location)]).into_response()
+}
+
+/// OAuth start query parameters
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OAuthStart {
+    /// Absolute URL to redirect to after the callback (origin must be allowlisted)
+    return_to: Option<String>,
 }
 
 /// OAuth callback query parameters
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct OAuthCallback {
     code: Option<String>,
     state: Option<String>,
@@ -81,18 +211,132 @@ pub struct OAuthCallback {
 }
 
 /// OAuth success response
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "success": true,
+    "message": "github connected successfully",
+    "connector": "github",
+    "missing_scopes": ["notifications"]
+}))]
 pub struct OAuthSuccessResponse {
     success: bool,
     message: String,
     connector: String,
+    /// Requested scopes the provider did not grant (omitted if none)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    missing_scopes: Vec<String>,
 }
 
+/// Device flow started: the code to show the user and how to poll
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "flow_id": "5b0c6a2e-8f5e-4d1c-9a7e-2f4b1c3d5e6f",
+    "user_code": "WDJB-MJHT",
+    "verification_uri": "https://github.com/login/device",
+    "expires_in": 900,
+    "interval": 5
+}))]
+pub struct DeviceStartResponse {
+    /// Identifies the flow to `/oauth/device/poll`
+    flow_id: String,
+    /// Code the user enters at `verification_uri`
+    user_code: String,
+    verification_uri: String,
+    /// `verification_uri` with the code filled in, if the provider offers one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verification_uri_complete: Option<String>,
+    /// Seconds until the code expires
+    expires_in: i64,
+    /// Seconds to wait between polls
+    interval: i64,
+}
+
+/// Device flow poll request
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DevicePollRequest {
+    /// From `/oauth/device/start`
+    flow_id: String,
+}
+
+/// Where a device flow stands
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DevicePollStatus {
+    /// Not approved yet: poll again after `interval`
+    Pending,
+    /// Polled too fast: poll again after the longer `interval`
+    SlowDown,
+    /// Approved and credentials stored
+    Complete,
+}
+
+/// Device flow poll result
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"status": "pending", "interval": 5}))]
+pub struct DevicePollResponse {
+    status: DevicePollStatus,
+    /// Seconds to wait before the next poll (omitted once complete)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interval: Option<i64>,
+    /// Requested scopes the provider did not grant (omitted if none)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    missing_scopes: Vec<String>,
+}
+
+/// Registered OAuth provider
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "github",
+    "auth_url": "https://github.com/login/oauth/authorize",
+    "token_url": "https://github.com/login/oauth/access_token",
+    "scopes": ["repo", "read:user"]
+}))]
+pub struct ProviderEntry {
+    pub name: String,
+    #[serde(flatten)]
+    pub definition: ProviderDefinition,
+}
+
+/// OpenAPI description of the OAuth flow endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        oauth_start,
+        oauth_callback,
+        oauth_device_start,
+        oauth_device_poll,
+        list_providers,
+        put_provider
+    ),
+    components(schemas(
+        OAuthSuccessResponse,
+        DeviceStartResponse,
+        DevicePollRequest,
+        DevicePollStatus,
+        DevicePollResponse,
+        ProviderDefinition,
+        ClientAuth,
+        ProviderEntry,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct OAuthApi;
+
 /// Create OAuth API router
 pub fn create_oauth_router(state: OAuthAppState) -> Router {
     Router::new()
         .route("/api/connectors/:name/oauth/start", get(oauth_start))
         .route("/api/connectors/:name/oauth/callback", get(oauth_callback))
+        .route(
+            "/api/connectors/:name/oauth/device/start",
+            post(oauth_device_start),
+        )
+        .route(
+            "/api/connectors/:name/oauth/device/poll",
+            post(oauth_device_poll),
+        )
+        .route("/api/admin/oauth-providers", get(list_providers))
+        .route("/api/admin/oauth-providers/:name", put(put_provider))
         .with_state(Arc::new(state))
 }
 
@@ -101,18 +345,33 @@ pub fn create_oauth_router(state: OAuthAppState) -> Router {
 /// Initiates OAuth flow by redirecting user to provider's authorization page.
 ///
 /// # Security
-/// - Requires bearer token (namespace extracted from token)
+/// - Requires bearer token (namespace looked up from token)
 /// - Generates CSRF state parameter
 /// - State stored in-memory with 10-minute expiry
+/// - Optional `return_to` must match an allowlisted origin
+#[utoipa::path(
+    get,
+    path = "/api/connectors/{name}/oauth/start",
+    tag = "oauth",
+    params(("name" = String, Path, description = "Connector name"), OAuthStart),
+    responses(
+        (status = 302, description = "Redirect to the provider's authorization page"),
+        (status = 400, description = "return_to not allowlisted", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Connector has no OAuth provider configured", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []))
+)]
 async fn oauth_start(
     State(state): State<Arc<OAuthAppState>>,
     Path(connector_name): Path<String>,
+    Query(params): Query<OAuthStart>,
     headers: HeaderMap,
 ) -> Result<Redirect, AppError> {
     debug!(connector = %connector_name, "OAuth start requested");
 
     // Validate connector name
-    if !provider::is_valid_connector(&connector_name) {
+    if !state.providers.is_valid_connector(&connector_name) {
         warn!(connector = %connector_name, "Invalid connector name");
         return Err(AppError::NotFound(format!(
             "Connector '{}' not found",
@@ -120,30 +379,35 @@ async fn oauth_start(
         )));
     }
 
-    // Extract namespace from bearer token
-    let namespace = if state.auth_enabled {
-        extract_bearer_token(&headers)
-            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?
-    } else {
-        // No auth mode: use a default namespace
-        "default".to_string()
-    };
+    // Resolve the namespace from the bearer token ("default" without auth)
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
+    let namespace = owner.namespace;
 
     debug!(connector = %connector_name, namespace = %namespace, "User authenticated");
 
+    let return_to = match params.return_to {
+        Some(ref raw) => Some(
+            validate_return_to(raw, &state.allowed_return_origins).inspect_err(|_| {
+                warn!(connector = %connector_name, return_to = %raw, "Rejected OAuth return_to");
+            })?,
+        ),
+        None => None,
+    };
+
     // Get OAuth provider config
-    let provider_config = provider::get_provider_config(&connector_name).ok_or_else(|| {
-        error!(connector = %connector_name, "OAuth provider config not found (missing env vars?)");
-        AppError::ServerError(format!(
-            "OAuth not configured for connector '{}'. Set FLUX_OAUTH_{}_CLIENT_ID and FLUX_OAUTH_{}_CLIENT_SECRET environment variables.",
-            connector_name,
-            connector_name.to_uppercase(),
-            connector_name.to_uppercase()
-        ))
-    })?;
+    let provider_config = state
+        .providers
+        .get_provider_config(&connector_name)
+        .ok_or_else(|| not_configured(&state, &connector_name))?;
 
     // Generate CSRF state parameter
-    let csrf_state = state.state_manager.create_state(&connector_name, &namespace);
+    let csrf_state = state.state_manager.create_state_with_return(
+        &connector_name,
+        &namespace,
+        owner.flux_token,
+        return_to,
+    );
 
     // Build callback URL
     let redirect_uri = format!(
@@ -163,15 +427,43 @@ async fn oauth_start(
     Ok(Redirect::temporary(&auth_url))
 }
 
+/// Error for a provider whose client ID or secret env var is unset
+fn not_configured(state: &OAuthAppState, connector_name: &str) -> AppError {
+    error!(connector = %connector_name, "OAuth provider config not found (missing env vars?)");
+    let (id_var, secret_var) = state
+        .providers
+        .get(connector_name)
+        .map(|definition| definition.credential_vars(connector_name))
+        .unwrap_or_default();
+    AppError::ServerError(format!(
+        "OAuth not configured for connector '{}'. Set {} and {} environment variables.",
+        connector_name, id_var, secret_var
+    ))
+}
+
 /// GET /api/connectors/:name/oauth/callback
 ///
 /// OAuth callback endpoint. Exchanges authorization code for access token
 /// and stores encrypted credentials.
 ///
+/// Responds with JSON, or with a 302 to the `return_to` stashed on start.
+///
 /// # Security
 /// - Validates CSRF state parameter
 /// - Single-use state (consumed on validation)
 /// - Namespace isolation (user can only connect their own accounts)
+#[utoipa::path(
+    get,
+    path = "/api/connectors/{name}/oauth/callback",
+    tag = "oauth",
+    params(("name" = String, Path, description = "Connector name"), OAuthCallback),
+    responses(
+        (status = 200, description = "Credentials stored (no return_to)", body = OAuthSuccessResponse),
+        (status = 302, description = "Redirect to return_to with status and connector"),
+        (status = 400, description = "Missing code or invalid state", body = ErrorResponse),
+        (status = 502, description = "Token exchange failed", body = ErrorResponse),
+    )
+)]
 async fn oauth_callback(
     State(state): State<Arc<OAuthAppState>>,
     Path(connector_name): Path<String>,
@@ -179,6 +471,60 @@ async fn oauth_callback(
 ) -> Result<Response, AppError> {
     debug!(connector = %connector_name, "OAuth callback received");
 
+    // Consume state up front so failures can also be redirected
+    let state_entry = callback
+        .state
+        .as_deref()
+        .and_then(|csrf_state| state.state_manager.validate_and_consume(csrf_state));
+
+    let return_to = state_entry.as_ref().and_then(|e| e.return_to.clone());
+
+    let result = complete_callback(&state, &connector_name, callback, state_entry).await;
+
+    match (return_to, result) {
+        (Some(return_to), Ok(missing_scopes)) => Ok(redirect_to_return(
+            &return_to,
+            &connector_name,
+            Ok(&missing_scopes),
+        )),
+        (Some(return_to), Err(failure)) => Ok(redirect_to_return(
+            &return_to,
+            &connector_name,
+            Err(&failure.reason),
+        )),
+        (None, Ok(missing_scopes)) => Ok(Json(OAuthSuccessResponse {
+            success: true,
+            message: format!("Successfully connected {}", connector_name),
+            connector: connector_name,
+            missing_scopes,
+        })
+        .into_response()),
+        (None, Err(failure)) => Err(failure.error),
+    }
+}
+
+/// Callback failure with a short machine-readable reason for redirects
+struct CallbackFailure {
+    reason: String,
+    error: AppError,
+}
+
+impl CallbackFailure {
+    fn new(reason: impl Into<String>, error: AppError) -> Self {
+        Self {
+            reason: reason.into(),
+            error,
+        }
+    }
+}
+
+/// Finishes the flow, returning the requested scopes that were not granted
+async fn complete_callback(
+    state: &OAuthAppState,
+    connector_name: &str,
+    callback: OAuthCallback,
+    state_entry: Option<StateEntry>,
+) -> Result<Vec<String>, CallbackFailure> {
     // Check for OAuth errors
     if let Some(error) = callback.error {
         let description = callback
@@ -190,30 +536,36 @@ async fn oauth_callback(
             description = %description,
             "OAuth authorization failed"
         );
-        return Err(AppError::BadRequest(format!(
-            "OAuth authorization failed: {} - {}",
-            error, description
-        )));
+        let message = format!("OAuth authorization failed: {} - {}", error, description);
+        return Err(CallbackFailure::new(error, AppError::BadRequest(message)));
     }
 
     // Extract code and state
-    let code = callback
-        .code
-        .ok_or_else(|| AppError::BadRequest("Missing 'code' parameter".to_string()))?;
-    let csrf_state = callback
-        .state
-        .ok_or_else(|| AppError::BadRequest("Missing 'state' parameter".to_string()))?;
+    let code = callback.code.ok_or_else(|| {
+        CallbackFailure::new(
+            "missing_code",
+            AppError::BadRequest("Missing 'code' parameter".to_string()),
+        )
+    })?;
+    let csrf_state = callback.state.ok_or_else(|| {
+        CallbackFailure::new(
+            "missing_state",
+            AppError::BadRequest("Missing 'state' parameter".to_string()),
+        )
+    })?;
 
     debug!(connector = %connector_name, state = %csrf_state, "Validating CSRF state");
 
-    // Validate and consume CSRF state
-    let state_entry = state
-        .state_manager
-        .validate_and_consume(&csrf_state)
-        .ok_or_else(|| {
-            warn!(state = %csrf_state, "Invalid or expired OAuth state");
-            AppError::Unauthorized("Invalid or expired OAuth state (possible CSRF attack)".to_string())
-        })?;
+    // State was validated and consumed by the caller
+    let state_entry = state_entry.ok_or_else(|| {
+        warn!(state = %csrf_state, "Invalid or expired OAuth state");
+        CallbackFailure::new(
+            "invalid_state",
+            AppError::Unauthorized(
+                "Invalid or expired OAuth state (possible CSRF attack)".to_string(),
+            ),
+        )
+    })?;
 
     // Verify connector name matches state
     if state_entry.connector != connector_name {
@@ -222,12 +574,14 @@ async fn oauth_callback(
             actual = %connector_name,
             "Connector name mismatch"
         );
-        return Err(AppError::BadRequest(
-            "Connector name mismatch".to_string(),
+        return Err(CallbackFailure::new(
+            "connector_mismatch",
+            AppError::BadRequest("Connector name mismatch".to_string()),
         ));
     }
 
     let namespace = state_entry.namespace;
+    let flux_token = state_entry.flux_token;
 
     debug!(
         connector = %connector_name,
@@ -236,13 +590,19 @@ async fn oauth_callback(
     );
 
     // Get OAuth provider config
-    let provider_config = provider::get_provider_config(&connector_name).ok_or_else(|| {
-        error!(connector = %connector_name, "OAuth provider config not found");
-        AppError::ServerError(format!(
-            "OAuth not configured for connector '{}'",
-            connector_name
-        ))
-    })?;
+    let provider_config = state
+        .providers
+        .get_provider_config(connector_name)
+        .ok_or_else(|| {
+            error!(connector = %connector_name, "OAuth provider config not found");
+            CallbackFailure::new(
+                "not_configured",
+                AppError::ServerError(format!(
+                    "OAuth not configured for connector '{}'",
+                    connector_name
+                )),
+            )
+        })?;
 
     // Build redirect URI (must match the one used in start)
     let redirect_uri = format!(
@@ -252,12 +612,14 @@ async fn oauth_callback(
 
     // Exchange authorization code for access token
     debug!(connector = %connector_name, "Exchanging authorization code for token");
-    let credentials = exchange::exchange_code_for_token(
+    let grant = exchange::exchange_code_for_token(
+        &state.http_client,
         &provider_config.token_url,
         &code,
         &redirect_uri,
         &provider_config.client_id,
         &provider_config.client_secret,
+        provider_config.client_auth,
     )
     .await
     .map_err(|e| {
@@ -266,9 +628,39 @@ async fn oauth_callback(
             error = %e,
             "Token exchange failed"
         );
-        AppError::BadGateway(format!("Failed to exchange authorization code: {}", e))
+        CallbackFailure::new(
+            "token_exchange_failed",
+            AppError::BadGateway(format!("Failed to exchange authorization code: {}", e)),
+        )
     })?;
 
+    store_grant(
+        state,
+        connector_name,
+        &namespace,
+        flux_token.as_deref(),
+        &provider_config.scopes,
+        grant,
+    )
+}
+
+/// Stores the credentials of a completed flow, returning the requested
+/// scopes that were not granted
+fn store_grant(
+    state: &OAuthAppState,
+    connector_name: &str,
+    namespace: &str,
+    flux_token: Option<&str>,
+    requested_scopes: &[String],
+    grant: exchange::TokenGrant,
+) -> Result<Vec<String>, CallbackFailure> {
+    let credentials = grant.credentials;
+    let scope_grant = ScopeGrant {
+        requested: requested_scopes.to_vec(),
+        granted: grant.granted_scopes,
+    };
+    let missing_scopes = scope_grant.missing();
+
     // Store encrypted credentials
     debug!(
         connector = %connector_name,
@@ -277,7 +669,7 @@ async fn oauth_callback(
     );
     state
         .credential_store
-        .store(&namespace, &connector_name, &credentials)
+        .store(namespace, connector_name, &credentials)
         .map_err(|e| {
             error!(
                 connector = %connector_name,
@@ -285,8 +677,49 @@ async fn oauth_callback(
                 error = %e,
                 "Failed to store credentials"
             );
-            AppError::ServerError(format!("Failed to store credentials: {}", e))
+            CallbackFailure::new(
+                "storage_failed",
+                AppError::ServerError(format!("Failed to store credentials: {}", e)),
+            )
         })?;
+    if let Some(flux_token) = flux_token {
+        state
+            .credential_store
+            .set_flux_token(namespace, connector_name, flux_token)
+            .map_err(|e| {
+                error!(
+                    connector = %connector_name,
+                    namespace = %namespace,
+                    error = %e,
+                    "Failed to store flux token"
+                );
+                CallbackFailure::new(
+                    "storage_failed",
+                    AppError::ServerError(format!("Failed to store credentials: {}", e)),
+                )
+            })?;
+    }
+    // Not fatal: the token itself is stored and usable
+    if let Err(e) =
+        state
+            .credential_store
+            .set_scope_grant(namespace, connector_name, Some(&scope_grant))
+    {
+        warn!(
+            connector = %connector_name,
+            namespace = %namespace,
+            error = %e,
+            "Failed to store granted scopes"
+        );
+    }
+    if !missing_scopes.is_empty() {
+        warn!(
+            connector = %connector_name,
+            namespace = %namespace,
+            missing_scopes = %missing_scopes.join(" "),
+            "Provider did not grant all requested scopes"
+        );
+    }
 
     info!(
         connector = %connector_name,
@@ -295,18 +728,275 @@ async fn oauth_callback(
         "OAuth flow completed successfully"
     );
 
-    // Return JSON success response
-    Ok(Json(OAuthSuccessResponse {
-        success: true,
-        message: format!("Successfully connected {}", connector_name),
-        connector: connector_name,
-    })
-    .into_response())
+    Ok(missing_scopes)
+}
+
+/// POST /api/connectors/:name/oauth/device/start
+///
+/// Starts a device authorization for hosts that can't take the callback:
+/// the user enters `user_code` at `verification_uri` on any device, while
+/// the client polls `/oauth/device/poll` with `flow_id`.
+///
+/// # Security
+/// - Requires bearer token (namespace looked up from token)
+/// - The device code stays server-side; only the caller's namespace can poll the flow
+#[utoipa::path(
+    post,
+    path = "/api/connectors/{name}/oauth/device/start",
+    tag = "oauth",
+    params(("name" = String, Path, description = "Connector name")),
+    responses(
+        (status = 200, description = "Code to show the user", body = DeviceStartResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Connector has no OAuth provider configured", body = ErrorResponse),
+        (status = 501, description = "Provider has no device authorization endpoint", body = ErrorResponse),
+        (status = 502, description = "Device authorization request failed", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []))
+)]
+async fn oauth_device_start(
+    State(state): State<Arc<OAuthAppState>>,
+    Path(connector_name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<DeviceStartResponse>, AppError> {
+    debug!(connector = %connector_name, "OAuth device flow start requested");
+
+    let definition = state.providers.get(&connector_name).ok_or_else(|| {
+        warn!(connector = %connector_name, "Invalid connector name");
+        AppError::NotFound(format!("Connector '{}' not found", connector_name))
+    })?;
+
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
+
+    let Some(device_auth_url) = definition.device_auth_url else {
+        return Err(AppError::NotImplemented(format!(
+            "Connector '{}' does not support the device flow",
+            connector_name
+        )));
+    };
+
+    let provider_config = state
+        .providers
+        .get_provider_config(&connector_name)
+        .ok_or_else(|| not_configured(&state, &connector_name))?;
+
+    let authorization = exchange::request_device_code(
+        &state.http_client,
+        &device_auth_url,
+        &provider_config.client_id,
+        &provider_config.scopes,
+    )
+    .await
+    .map_err(|e| {
+        error!(connector = %connector_name, error = %e, "Device authorization failed");
+        AppError::BadGateway(format!("Failed to start device authorization: {}", e))
+    })?;
+
+    let flow_id = state.state_manager.create_device_flow(
+        &connector_name,
+        &owner.namespace,
+        owner.flux_token,
+        authorization.device_code,
+        authorization.interval,
+        authorization.expires_in,
+    );
+
+    info!(
+        connector = %connector_name,
+        namespace = %owner.namespace,
+        expires_in = authorization.expires_in,
+        "OAuth device flow started"
+    );
+
+    Ok(Json(DeviceStartResponse {
+        flow_id,
+        user_code: authorization.user_code,
+        verification_uri: authorization.verification_uri,
+        verification_uri_complete: authorization.verification_uri_complete,
+        expires_in: authorization.expires_in,
+        interval: authorization.interval,
+    }))
+}
+
+/// POST /api/connectors/:name/oauth/device/poll
+///
+/// Asks the provider once whether the user approved the device flow, and
+/// stores the credentials like the callback does when they have. Call it
+/// no more often than the returned `interval`.
+#[utoipa::path(
+    post,
+    path = "/api/connectors/{name}/oauth/device/poll",
+    tag = "oauth",
+    params(("name" = String, Path, description = "Connector name")),
+    request_body = DevicePollRequest,
+    responses(
+        (status = 200, description = "Pending, slow_down or complete", body = DevicePollResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "The user denied the authorization", body = ErrorResponse),
+        (status = 404, description = "Unknown or expired flow", body = ErrorResponse),
+        (status = 410, description = "Device code expired before approval", body = ErrorResponse),
+        (status = 502, description = "Token request failed", body = ErrorResponse),
+    ),
+    security(("bearer_token" = []))
+)]
+async fn oauth_device_poll(
+    State(state): State<Arc<OAuthAppState>>,
+    Path(connector_name): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<DevicePollRequest>,
+) -> Result<Json<DevicePollResponse>, AppError> {
+    let owner = connector_owner(&headers, state.auth_enabled, &state.namespace_registry)
+        .map_err(AppError::Unauthorized)?;
+
+    // Other namespaces' flows look the same as unknown ones
+    let flow_id = request.flow_id;
+    let entry = state
+        .state_manager
+        .device_flow(&flow_id)
+        .filter(|entry| entry.connector == connector_name && entry.namespace == owner.namespace)
+        .ok_or_else(|| AppError::NotFound("Device flow not found or expired".to_string()))?;
+
+    let provider_config = state
+        .providers
+        .get_provider_config(&connector_name)
+        .ok_or_else(|| not_configured(&state, &connector_name))?;
+
+    let poll = exchange::poll_device_token(
+        &state.http_client,
+        &provider_config.token_url,
+        &entry.device_code,
+        &provider_config.client_id,
+        &provider_config.client_secret,
+    )
+    .await
+    .map_err(|e| {
+        error!(connector = %connector_name, error = %e, "Device token request failed");
+        AppError::BadGateway(format!("Failed to poll for device token: {}", e))
+    })?;
+
+    let pending = |status, interval| {
+        Json(DevicePollResponse {
+            status,
+            interval: Some(interval),
+            missing_scopes: Vec::new(),
+        })
+    };
+    match poll {
+        exchange::DevicePoll::Pending => Ok(pending(DevicePollStatus::Pending, entry.interval)),
+        exchange::DevicePoll::SlowDown => {
+            let interval = state
+                .state_manager
+                .slow_down_device_flow(&flow_id)
+                .unwrap_or(entry.interval);
+            debug!(connector = %connector_name, interval, "Device flow told to slow down");
+            Ok(pending(DevicePollStatus::SlowDown, interval))
+        }
+        exchange::DevicePoll::Expired => {
+            state.state_manager.remove_device_flow(&flow_id);
+            warn!(connector = %connector_name, namespace = %entry.namespace, "Device code expired");
+            Err(AppError::Gone(
+                "Device code expired before it was approved".to_string(),
+            ))
+        }
+        exchange::DevicePoll::Denied => {
+            state.state_manager.remove_device_flow(&flow_id);
+            warn!(connector = %connector_name, namespace = %entry.namespace, "Device flow denied");
+            Err(AppError::Forbidden(
+                "Device authorization denied by user".to_string(),
+            ))
+        }
+        exchange::DevicePoll::Granted(grant) => {
+            state.state_manager.remove_device_flow(&flow_id);
+            let missing_scopes = store_grant(
+                &state,
+                &connector_name,
+                &entry.namespace,
+                entry.flux_token.as_deref(),
+                &provider_config.scopes,
+                grant,
+            )
+            .map_err(|failure| failure.error)?;
+            Ok(Json(DevicePollResponse {
+                status: DevicePollStatus::Complete,
+                interval: None,
+                missing_scopes,
+            }))
+        }
+    }
+}
+
+/// GET /api/admin/oauth-providers
+///
+/// Lists every provider the OAuth flow accepts. Secrets are never returned,
+/// only the names of the env vars they are read from.
+#[utoipa::path(
+    get,
+    path = "/api/admin/oauth-providers",
+    tag = "oauth",
+    responses(
+        (status = 200, description = "Providers, ordered by name", body = Vec<ProviderEntry>),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn list_providers(
+    State(state): State<Arc<OAuthAppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ProviderEntry>>, AppError> {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return Err(AppError::Unauthorized("Admin token required".to_string()));
+    }
+    Ok(Json(
+        state
+            .providers
+            .list()
+            .into_iter()
+            .map(|(name, definition)| ProviderEntry { name, definition })
+            .collect(),
+    ))
+}
+
+/// PUT /api/admin/oauth-providers/:name
+///
+/// Adds a provider, or replaces one (built-ins included), until restart.
+#[utoipa::path(
+    put,
+    path = "/api/admin/oauth-providers/{name}",
+    tag = "oauth",
+    params(("name" = String, Path, description = "Connector name")),
+    request_body = ProviderDefinition,
+    responses(
+        (status = 200, description = "Provider saved", body = ProviderDefinition),
+        (status = 400, description = "Invalid name or non-https URL", body = ErrorResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn put_provider(
+    State(state): State<Arc<OAuthAppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(definition): Json<ProviderDefinition>,
+) -> Result<Json<ProviderDefinition>, AppError> {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return Err(AppError::Unauthorized("Admin token required".to_string()));
+    }
+    state
+        .providers
+        .set(&name, definition.clone())
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    info!(connector = %name, auth_url = %definition.auth_url, "OAuth provider saved");
+    Ok(Json(definition))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::credentials::Credentials;
+    use crate::http_client::HttpClientConfig;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 
     #[test]
     fn test_oauth_callback_deserialization() {
@@ -321,7 +1011,10 @@ mod tests {
         let query = "error=access_denied&error_description=User+cancelled";
         let callback: OAuthCallback = serde_urlencoded::from_str(query).unwrap();
         assert_eq!(callback.error, Some("access_denied".to_string()));
-        assert_eq!(callback.error_description, Some("User cancelled".to_string()));
+        assert_eq!(
+            callback.error_description,
+            Some("User cancelled".to_string())
+        );
         assert_eq!(callback.code, None);
     }
 
@@ -331,10 +1024,281 @@ mod tests {
             success: true,
             message: "Connected to GitHub".to_string(),
             connector: "github".to_string(),
+            missing_scopes: vec![],
         };
 
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("\"success\":true"));
         assert!(json.contains("\"connector\":\"github\""));
+        assert!(!json.contains("missing_scopes"));
+    }
+
+    fn allowlist() -> Vec<String> {
+        parse_allowed_origins("https://app.example.com, http://localhost:8080/")
+    }
+
+    #[test]
+    fn test_parse_allowed_origins_normalizes() {
+        assert_eq!(
+            allowlist(),
+            vec![
+                "https://app.example.com".to_string(),
+                "http://localhost:8080".to_string()
+            ]
+        );
+        assert!(parse_allowed_origins("not a url, ftp://files.example.com").is_empty());
+    }
+
+    #[test]
+    fn test_return_to_allowed_origin_accepted() {
+        let allowed = allowlist();
+        assert!(validate_return_to("https://app.example.com/settings?tab=1", &allowed).is_ok());
+        assert!(validate_return_to("http://localhost:8080/", &allowed).is_ok());
+    }
+
+    #[test]
+    fn test_return_to_open_redirects_rejected() {
+        let allowed = allowlist();
+        for candidate in [
+            "https://evil.com/",
+            "https://app.example.com.evil.com/",
+            "https://app.example.com@evil.com/",
+            "http://app.example.com/",
+            "https://app.example.com:8443/",
+            "http://localhost:9090/",
+            "//evil.com/",
+            "/relative/path",
+            "javascript:alert(1)",
+        ] {
+            assert!(
+                validate_return_to(candidate, &allowed).is_err(),
+                "{} should be rejected",
+                candidate
+            );
+        }
+    }
+
+    #[test]
+    fn test_return_to_rejected_when_allowlist_empty() {
+        assert!(validate_return_to("https://app.example.com/", &[]).is_err());
+    }
+
+    #[test]
+    fn test_redirect_to_return_appends_outcome() {
+        let response = redirect_to_return("https://app.example.com/done?x=1", "github", Ok(&[]));
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "https://app.example.com/done?x=1&connector=github&status=success"
+        );
+
+        let response = redirect_to_return(
+            "https://app.example.com/done",
+            "github",
+            Err("access denied"),
+        );
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "https://app.example.com/done?connector=github&status=error&reason=access+denied"
+        );
+    }
+
+    #[test]
+    fn test_redirect_to_return_reports_missing_scopes() {
+        let missing = vec!["notifications".to_string(), "read:user".to_string()];
+        let response = redirect_to_return("https://app.example.com/done", "github", Ok(&missing));
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "https://app.example.com/done?connector=github&status=success&missing_scopes=notifications+read%3Auser"
+        );
+    }
+
+    fn device_state(provider_url: &str) -> Arc<OAuthAppState> {
+        let providers = ProviderRegistry::builtin();
+        providers.insert_unchecked(
+            "mock",
+            ProviderDefinition {
+                auth_url: format!("{}/authorize", provider_url),
+                token_url: format!("{}/token", provider_url),
+                scopes: vec!["repo".to_string(), "gist".to_string()],
+                client_id_env: Some("FLUX_TEST_DEVICE_CLIENT_ID".to_string()),
+                client_secret_env: Some("FLUX_TEST_DEVICE_CLIENT_SECRET".to_string()),
+                device_auth_url: Some(format!("{}/device/code", provider_url)),
+                client_auth: ClientAuth::Body,
+            },
+        );
+        std::env::set_var("FLUX_TEST_DEVICE_CLIENT_ID", "client");
+        std::env::set_var("FLUX_TEST_DEVICE_CLIENT_SECRET", "secret");
+
+        let key = BASE64.encode([0u8; 32]);
+        Arc::new(OAuthAppState {
+            credential_store: Arc::new(CredentialStore::new(":memory:", &key).unwrap()),
+            namespace_registry: Arc::new(NamespaceRegistry::new()),
+            state_manager: StateManager::new(600),
+            auth_enabled: false,
+            callback_base_url: "http://localhost:3000".to_string(),
+            allowed_return_origins: Vec::new(),
+            providers: Arc::new(providers),
+            admin_token: None,
+            http_client: HttpClientConfig::default().build().unwrap(),
+        })
+    }
+
+    fn expect_ok<T>(result: Result<T, AppError>) -> T {
+        match result {
+            Ok(value) => value,
+            Err(e) => panic!("request failed with {}", e.into_response().status()),
+        }
+    }
+
+    fn error_status<T>(result: Result<T, AppError>) -> StatusCode {
+        match result {
+            Ok(_) => panic!("request should have failed"),
+            Err(e) => e.into_response().status(),
+        }
+    }
+
+    async fn start_device_flow(
+        server: &mut mockito::Server,
+        state: &Arc<OAuthAppState>,
+    ) -> DeviceStartResponse {
+        let mock = server
+            .mock("POST", "/device/code")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"device_code": "dev-code", "user_code": "WDJB-MJHT",
+                    "verification_uri": "https://example.com/device",
+                    "expires_in": 900, "interval": 5}"#,
+            )
+            .create_async()
+            .await;
+        let Json(started) = expect_ok(
+            oauth_device_start(
+                State(Arc::clone(state)),
+                Path("mock".to_string()),
+                HeaderMap::new(),
+            )
+            .await,
+        );
+        mock.assert_async().await;
+        started
+    }
+
+    /// Polls once, the provider answering `body` with a 200 as GitHub does
+    async fn poll_device_flow(
+        server: &mut mockito::Server,
+        state: &Arc<OAuthAppState>,
+        flow_id: &str,
+        body: &str,
+    ) -> Result<DevicePollResponse, AppError> {
+        let mock = server
+            .mock("POST", "/token")
+            .match_body(mockito::Matcher::UrlEncoded(
+                "device_code".into(),
+                "dev-code".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create_async()
+            .await;
+        let result = oauth_device_poll(
+            State(Arc::clone(state)),
+            Path("mock".to_string()),
+            HeaderMap::new(),
+            Json(DevicePollRequest {
+                flow_id: flow_id.to_string(),
+            }),
+        )
+        .await;
+        mock.assert_async().await;
+        mock.remove_async().await;
+        result.map(|Json(response)| response)
+    }
+
+    fn stored_credentials(state: &OAuthAppState) -> Option<Credentials> {
+        state.credential_store.get("default", "mock").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_device_flow_stores_credentials_once_approved() {
+        let mut server = mockito::Server::new_async().await;
+        let state = device_state(&server.url());
+
+        let started = start_device_flow(&mut server, &state).await;
+        assert_eq!(started.user_code, "WDJB-MJHT");
+        assert_eq!(started.interval, 5);
+        let flow = started.flow_id;
+
+        let pending = r#"{"error": "authorization_pending"}"#;
+        let poll = expect_ok(poll_device_flow(&mut server, &state, &flow, pending).await);
+        assert_eq!(poll.status, DevicePollStatus::Pending);
+        assert_eq!(poll.interval, Some(5));
+        assert!(stored_credentials(&state).is_none());
+
+        let slow_down = r#"{"error": "slow_down"}"#;
+        let poll = expect_ok(poll_device_flow(&mut server, &state, &flow, slow_down).await);
+        assert_eq!(poll.status, DevicePollStatus::SlowDown);
+        assert_eq!(poll.interval, Some(10));
+
+        let granted = r#"{"access_token": "gho_device", "scope": "repo"}"#;
+        let poll = expect_ok(poll_device_flow(&mut server, &state, &flow, granted).await);
+        assert_eq!(poll.status, DevicePollStatus::Complete);
+        assert_eq!(poll.interval, None);
+        assert_eq!(poll.missing_scopes, vec!["gist".to_string()]);
+
+        // Stored like the callback stores them
+        let credentials = stored_credentials(&state).unwrap();
+        assert_eq!(credentials.access_token, "gho_device");
+        let scope_grant = state
+            .credential_store
+            .get_scope_grant("default", "mock")
+            .unwrap()
+            .unwrap();
+        assert_eq!(scope_grant.granted, Some(vec!["repo".to_string()]));
+
+        // The flow is finished
+        let result = oauth_device_poll(
+            State(Arc::clone(&state)),
+            Path("mock".to_string()),
+            HeaderMap::new(),
+            Json(DevicePollRequest { flow_id: flow }),
+        )
+        .await;
+        assert_eq!(error_status(result), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_device_flow_expired_code() {
+        let mut server = mockito::Server::new_async().await;
+        let state = device_state(&server.url());
+        let started = start_device_flow(&mut server, &state).await;
+
+        let expired = r#"{"error": "expired_token"}"#;
+        let result = poll_device_flow(&mut server, &state, &started.flow_id, expired).await;
+        assert_eq!(error_status(result), StatusCode::GONE);
+        assert_eq!(state.state_manager.device_flow_count(), 0);
+        assert!(stored_credentials(&state).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_device_flow_unsupported_provider() {
+        let server = mockito::Server::new_async().await;
+        let state = device_state(&server.url());
+
+        let result = oauth_device_start(
+            State(Arc::clone(&state)),
+            Path("linkedin".to_string()),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(error_status(result), StatusCode::NOT_IMPLEMENTED);
+
+        let result = oauth_device_start(
+            State(Arc::clone(&state)),
+            Path("unknown".to_string()),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(error_status(result), StatusCode::NOT_FOUND);
     }
 }
@@ -1,8 +1,17 @@
 //! OAuth provider configurations.
 //!
 //! Defines OAuth 2.0 configuration for each supported external service.
+//! Built-in providers can be overridden, and new ones added, from a TOML or
+//! JSON file (`FLUX_OAUTH_PROVIDERS_FILE`) or through the admin API. Client
+//! IDs and secrets are always read from environment variables.
 
+use super::exchange::ClientAuth;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::RwLock;
+use utoipa::ToSchema;
 
 /// OAuth provider configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -21,15 +30,29 @@ pub struct OAuthProviderConfig {
 
     /// Client secret (from environment variable)
     pub client_secret: String,
+
+    /// Device authorization endpoint URL, if the provider supports the device flow
+    pub device_auth_url: Option<String>,
+
+    /// How the token endpoint expects the client ID and secret
+    pub client_auth: ClientAuth,
 }
 
 impl OAuthProviderConfig {
     /// Build authorization URL with state and redirect_uri
+    ///
+    /// Parameters already in `auth_url` (Notion's `owner=user`) are kept.
     pub fn build_auth_url(&self, state: &str, redirect_uri: &str) -> String {
         let scopes = self.scopes.join(" ");
+        let separator = if self.auth_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
         format!(
-            "{}?client_id={}&redirect_uri={}&scope={}&state={}&response_type=code",
+            "{}{}client_id={}&redirect_uri={}&scope={}&state={}&response_type=code",
             self.auth_url,
+            separator,
             urlencoding::encode(&self.client_id),
             urlencoding::encode(redirect_uri),
             urlencoding::encode(&scopes),
@@ -38,49 +61,311 @@ impl OAuthProviderConfig {
     }
 }
 
-/// Get OAuth provider configuration by connector name
-pub fn get_provider_config(connector_name: &str) -> Option<OAuthProviderConfig> {
-    // Load client ID and secret from environment
-    let env_prefix = connector_name.to_uppercase();
-    let client_id = std::env::var(format!("FLUX_OAUTH_{}_CLIENT_ID", env_prefix)).ok()?;
-    let client_secret = std::env::var(format!("FLUX_OAUTH_{}_CLIENT_SECRET", env_prefix)).ok()?;
-
-    let (auth_url, token_url, scopes) = match connector_name {
-        "github" => (
-            "https://github.com/login/oauth/authorize",
-            "https://github.com/login/oauth/access_token",
-            vec!["repo", "read:user"],
-        ),
-        "gmail" => (
-            "https://accounts.google.com/o/oauth2/v2/auth",
-            "https://oauth2.googleapis.com/token",
-            vec!["https://www.googleapis.com/auth/gmail.readonly"],
-        ),
-        "linkedin" => (
-            "https://www.linkedin.com/oauth/v2/authorization",
-            "https://www.linkedin.com/oauth/v2/accessToken",
-            vec!["r_liteprofile", "r_emailaddress"],
-        ),
-        "calendar" => (
-            "https://accounts.google.com/o/oauth2/v2/auth",
-            "https://oauth2.googleapis.com/token",
-            vec!["https://www.googleapis.com/auth/calendar.readonly"],
-        ),
-        _ => return None,
+/// Provider endpoints, scopes and where its client credentials come from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "auth_url": "https://api.notion.com/v1/oauth/authorize?owner=user",
+    "token_url": "https://api.notion.com/v1/oauth/token",
+    "scopes": [],
+    "client_id_env": "NOTION_CLIENT_ID",
+    "client_secret_env": "NOTION_CLIENT_SECRET",
+    "client_auth": "basic"
+}))]
+pub struct ProviderDefinition {
+    /// Authorization endpoint (https)
+    pub auth_url: String,
+    /// Token exchange endpoint (https)
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Env var holding the client ID (default `FLUX_OAUTH_<NAME>_CLIENT_ID`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id_env: Option<String>,
+    /// Env var holding the client secret (default `FLUX_OAUTH_<NAME>_CLIENT_SECRET`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret_env: Option<String>,
+    /// Device authorization endpoint (https); unset if the provider has no device flow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_auth_url: Option<String>,
+    /// How the token endpoint expects the client ID and secret
+    #[serde(default, skip_serializing_if = "ClientAuth::is_body")]
+    pub client_auth: ClientAuth,
+}
+
+impl ProviderDefinition {
+    fn builtin(auth_url: &str, token_url: &str, scopes: &[&str]) -> Self {
+        Self {
+            auth_url: auth_url.to_string(),
+            token_url: token_url.to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            client_id_env: None,
+            client_secret_env: None,
+            device_auth_url: None,
+            client_auth: ClientAuth::Body,
+        }
+    }
+
+    fn with_device_auth_url(mut self, device_auth_url: &str) -> Self {
+        self.device_auth_url = Some(device_auth_url.to_string());
+        self
+    }
+
+    fn with_client_auth(mut self, client_auth: ClientAuth) -> Self {
+        self.client_auth = client_auth;
+        self
+    }
+
+    /// Names of the env vars holding the client ID and secret for provider `name`
+    pub fn credential_vars(&self, name: &str) -> (String, String) {
+        let prefix = format!("FLUX_OAUTH_{}", name.to_uppercase().replace('-', "_"));
+        (
+            self.client_id_env
+                .clone()
+                .unwrap_or_else(|| format!("{}_CLIENT_ID", prefix)),
+            self.client_secret_env
+                .clone()
+                .unwrap_or_else(|| format!("{}_CLIENT_SECRET", prefix)),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ProviderError> {
+        let endpoints = [
+            ("auth_url", Some(&self.auth_url)),
+            ("token_url", Some(&self.token_url)),
+            ("device_auth_url", self.device_auth_url.as_ref()),
+        ];
+        for (field, url) in endpoints {
+            let Some(url) = url else {
+                continue;
+            };
+            let invalid = |reason: &str| ProviderError::InvalidUrl {
+                field,
+                url: url.clone(),
+                reason: reason.to_string(),
+            };
+            let parsed = reqwest::Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+            if parsed.scheme() != "https" {
+                return Err(invalid("must use https"));
+            }
+            if parsed.host_str().is_none() {
+                return Err(invalid("missing host"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a provider definition was rejected
+#[derive(Debug, PartialEq)]
+pub enum ProviderError {
+    /// Name is not lowercase letters, digits, `-` or `_`
+    InvalidName(String),
+    /// Endpoint URL does not parse or is not https
+    InvalidUrl {
+        field: &'static str,
+        url: String,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProviderError::InvalidName(name) => {
+                write!(f, "Invalid provider name '{}' (must be [a-z0-9-_])", name)
+            }
+            ProviderError::InvalidUrl { field, url, reason } => {
+                write!(f, "Invalid {} '{}': {}", field, url, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+fn validate_name(name: &str) -> Result<(), ProviderError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ProviderError::InvalidName(name.to_string()))
+    }
+}
+
+/// Parse and validate a providers file: a table of provider name to definition
+pub fn parse_providers(
+    contents: &str,
+    json: bool,
+) -> anyhow::Result<BTreeMap<String, ProviderDefinition>> {
+    let providers: BTreeMap<String, ProviderDefinition> = if json {
+        serde_json::from_str(contents).context("Invalid providers JSON")?
+    } else {
+        toml::from_str(contents).context("Invalid providers TOML")?
     };
+    for (name, definition) in &providers {
+        validate_name(name)?;
+        definition
+            .validate()
+            .with_context(|| format!("Provider '{}'", name))?;
+    }
+    Ok(providers)
+}
 
-    Some(OAuthProviderConfig {
-        auth_url: auth_url.to_string(),
-        token_url: token_url.to_string(),
-        scopes: scopes.into_iter().map(|s| s.to_string()).collect(),
-        client_id,
-        client_secret,
-    })
+/// Known OAuth providers: the built-ins, merged with any loaded or added at runtime
+pub struct ProviderRegistry {
+    providers: RwLock<HashMap<String, ProviderDefinition>>,
 }
 
-/// Check if a connector name is valid
-pub fn is_valid_connector(name: &str) -> bool {
-    matches!(name, "github" | "gmail" | "linkedin" | "calendar")
+impl ProviderRegistry {
+    /// Registry with only the built-in providers
+    pub fn builtin() -> Self {
+        let google_auth = "https://accounts.google.com/o/oauth2/v2/auth";
+        let google_token = "https://oauth2.googleapis.com/token";
+        let providers = HashMap::from([
+            (
+                "github".to_string(),
+                ProviderDefinition::builtin(
+                    "https://github.com/login/oauth/authorize",
+                    "https://github.com/login/oauth/access_token",
+                    &["repo", "read:user"],
+                )
+                .with_device_auth_url("https://github.com/login/device/code"),
+            ),
+            (
+                "gmail".to_string(),
+                ProviderDefinition::builtin(
+                    google_auth,
+                    google_token,
+                    &["https://www.googleapis.com/auth/gmail.readonly"],
+                ),
+            ),
+            (
+                "linkedin".to_string(),
+                ProviderDefinition::builtin(
+                    "https://www.linkedin.com/oauth/v2/authorization",
+                    "https://www.linkedin.com/oauth/v2/accessToken",
+                    &["r_liteprofile", "r_emailaddress"],
+                ),
+            ),
+            (
+                "calendar".to_string(),
+                ProviderDefinition::builtin(
+                    google_auth,
+                    google_token,
+                    &["https://www.googleapis.com/auth/calendar.readonly"],
+                ),
+            ),
+            (
+                "notion".to_string(),
+                ProviderDefinition::builtin(
+                    "https://api.notion.com/v1/oauth/authorize?owner=user",
+                    "https://api.notion.com/v1/oauth/token",
+                    &[],
+                )
+                .with_client_auth(ClientAuth::Basic),
+            ),
+            (
+                "jira".to_string(),
+                ProviderDefinition::builtin(
+                    "https://auth.atlassian.com/authorize?audience=api.atlassian.com&prompt=consent",
+                    "https://auth.atlassian.com/oauth/token",
+                    &[
+                        "read:jira-work",
+                        "read:jira-user",
+                        "read:board-scope:jira-software",
+                        "read:sprint:jira-software",
+                        "offline_access",
+                    ],
+                ),
+            ),
+        ]);
+        Self {
+            providers: RwLock::new(providers),
+        }
+    }
+
+    /// Built-in providers overridden and extended by the providers file at `path`
+    ///
+    /// `.json` files are read as JSON, anything else as TOML.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read OAuth providers file {}", path.display()))?;
+        let json = path.extension().and_then(|ext| ext.to_str()) == Some("json");
+        let registry = Self::builtin();
+        for (name, definition) in parse_providers(&contents, json)
+            .with_context(|| format!("Invalid OAuth providers file {}", path.display()))?
+        {
+            registry.providers.write().unwrap().insert(name, definition);
+        }
+        Ok(registry)
+    }
+
+    /// Add or replace provider `name`
+    pub fn set(&self, name: &str, definition: ProviderDefinition) -> Result<(), ProviderError> {
+        validate_name(name)?;
+        definition.validate()?;
+        self.providers
+            .write()
+            .unwrap()
+            .insert(name.to_string(), definition);
+        Ok(())
+    }
+
+    /// All providers, ordered by name
+    pub fn list(&self) -> BTreeMap<String, ProviderDefinition> {
+        self.providers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, definition)| (name.clone(), definition.clone()))
+            .collect()
+    }
+
+    /// Add provider `name` without validating it, e.g. pointing at a local mock server
+    #[cfg(test)]
+    pub(crate) fn insert_unchecked(&self, name: &str, definition: ProviderDefinition) {
+        self.providers
+            .write()
+            .unwrap()
+            .insert(name.to_string(), definition);
+    }
+
+    pub fn get(&self, name: &str) -> Option<ProviderDefinition> {
+        self.providers.read().unwrap().get(name).cloned()
+    }
+
+    /// Check if a connector name is valid
+    pub fn is_valid_connector(&self, name: &str) -> bool {
+        self.providers.read().unwrap().contains_key(name)
+    }
+
+    /// Get OAuth provider configuration by connector name
+    ///
+    /// None if the provider is unknown or its client ID or secret env var is unset.
+    pub fn get_provider_config(&self, connector_name: &str) -> Option<OAuthProviderConfig> {
+        self.resolve(connector_name, |var| std::env::var(var).ok())
+    }
+
+    fn resolve(
+        &self,
+        name: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<OAuthProviderConfig> {
+        let definition = self.get(name)?;
+        let (id_var, secret_var) = definition.credential_vars(name);
+        Some(OAuthProviderConfig {
+            client_id: env(&id_var)?,
+            client_secret: env(&secret_var)?,
+            auth_url: definition.auth_url,
+            token_url: definition.token_url,
+            scopes: definition.scopes,
+            device_auth_url: definition.device_auth_url,
+            client_auth: definition.client_auth,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -89,12 +374,15 @@ mod tests {
 
     #[test]
     fn test_valid_connector_names() {
-        assert!(is_valid_connector("github"));
-        assert!(is_valid_connector("gmail"));
-        assert!(is_valid_connector("linkedin"));
-        assert!(is_valid_connector("calendar"));
-        assert!(!is_valid_connector("invalid"));
-        assert!(!is_valid_connector(""));
+        let registry = ProviderRegistry::builtin();
+        assert!(registry.is_valid_connector("github"));
+        assert!(registry.is_valid_connector("gmail"));
+        assert!(registry.is_valid_connector("linkedin"));
+        assert!(registry.is_valid_connector("calendar"));
+        assert!(registry.is_valid_connector("notion"));
+        assert!(registry.is_valid_connector("jira"));
+        assert!(!registry.is_valid_connector("invalid"));
+        assert!(!registry.is_valid_connector(""));
     }
 
     #[test]
@@ -105,6 +393,8 @@ mod tests {
             scopes: vec!["read".to_string(), "write".to_string()],
             client_id: "test_client_id".to_string(),
             client_secret: "test_secret".to_string(),
+            device_auth_url: None,
+            client_auth: ClientAuth::Body,
         };
 
         let url = config.build_auth_url("random_state", "http://localhost:3000/callback");
@@ -116,4 +406,173 @@ mod tests {
         assert!(url.contains("state=random_state"));
         assert!(url.contains("response_type=code"));
     }
+
+    #[test]
+    fn test_notion_provider() {
+        let registry = ProviderRegistry::builtin();
+        let env = |var: &str| Some(format!("{}-value", var));
+        let config = registry.resolve("notion", env).unwrap();
+        assert_eq!(config.client_auth, ClientAuth::Basic);
+        assert_eq!(config.client_id, "FLUX_OAUTH_NOTION_CLIENT_ID-value");
+
+        let url = config.build_auth_url("state", "http://localhost:3000/callback");
+        assert!(url.starts_with(
+            "https://api.notion.com/v1/oauth/authorize?owner=user&client_id=FLUX_OAUTH_NOTION"
+        ));
+        assert_eq!(url.matches('?').count(), 1);
+    }
+
+    #[test]
+    fn test_parse_providers_file() {
+        let toml = r#"
+            [notion]
+            auth_url = "https://api.notion.com/v1/oauth/authorize"
+            token_url = "https://api.notion.com/v1/oauth/token"
+            client_id_env = "NOTION_CLIENT_ID"
+            client_secret_env = "NOTION_CLIENT_SECRET"
+
+            [github]
+            auth_url = "https://github.example.com/login/oauth/authorize"
+            token_url = "https://github.example.com/login/oauth/access_token"
+            scopes = ["repo"]
+        "#;
+        let providers = parse_providers(toml, false).unwrap();
+        assert_eq!(providers.len(), 2);
+        assert!(providers["notion"].scopes.is_empty());
+        assert_eq!(providers["github"].scopes, vec!["repo".to_string()]);
+        assert_eq!(providers["notion"].client_auth, ClientAuth::Body);
+
+        let json = r#"{"notion": {
+            "auth_url": "https://api.notion.com/v1/oauth/authorize",
+            "token_url": "https://api.notion.com/v1/oauth/token",
+            "client_auth": "basic"
+        }}"#;
+        let providers = parse_providers(json, true).unwrap();
+        assert_eq!(providers["notion"].client_id_env, None);
+        assert_eq!(providers["notion"].client_auth, ClientAuth::Basic);
+
+        assert!(parse_providers("[notion]\nauth_url = 1", false).is_err());
+    }
+
+    #[test]
+    fn test_file_overrides_builtins() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("providers.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [github]
+            auth_url = "https://github.example.com/login/oauth/authorize"
+            token_url = "https://github.example.com/login/oauth/access_token"
+
+            [jira]
+            auth_url = "https://auth.atlassian.com/authorize"
+            token_url = "https://auth.atlassian.com/oauth/token"
+            scopes = ["read:jira-work"]
+        "#,
+        )
+        .unwrap();
+
+        let registry = ProviderRegistry::from_file(&path).unwrap();
+        assert!(registry.is_valid_connector("jira"));
+        assert!(registry.is_valid_connector("gmail"));
+        assert_eq!(
+            registry.get("github").unwrap().auth_url,
+            "https://github.example.com/login/oauth/authorize"
+        );
+    }
+
+    #[test]
+    fn test_secrets_resolved_from_env_vars() {
+        let registry = ProviderRegistry::builtin();
+        registry
+            .set(
+                "my-crm",
+                ProviderDefinition {
+                    auth_url: "https://crm.example.com/authorize".to_string(),
+                    token_url: "https://crm.example.com/token".to_string(),
+                    scopes: vec!["contacts".to_string()],
+                    client_id_env: Some("CRM_ID".to_string()),
+                    client_secret_env: None,
+                    device_auth_url: None,
+                    client_auth: ClientAuth::Body,
+                },
+            )
+            .unwrap();
+
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("CRM_ID", "crm-client"),
+            ("FLUX_OAUTH_MY_CRM_CLIENT_SECRET", "crm-secret"),
+            ("FLUX_OAUTH_GITHUB_CLIENT_ID", "gh-client"),
+        ]);
+        let lookup = |var: &str| env.get(var).map(|v| v.to_string());
+
+        let config = registry.resolve("my-crm", lookup).unwrap();
+        assert_eq!(config.client_id, "crm-client");
+        assert_eq!(config.client_secret, "crm-secret");
+        assert_eq!(config.scopes, vec!["contacts".to_string()]);
+
+        // Secret unset
+        assert!(registry.resolve("github", lookup).is_none());
+        assert!(registry.resolve("unknown", lookup).is_none());
+    }
+
+    #[test]
+    fn test_non_https_urls_rejected() {
+        let registry = ProviderRegistry::builtin();
+        let definition = |auth_url: &str| ProviderDefinition {
+            auth_url: auth_url.to_string(),
+            token_url: "https://example.com/token".to_string(),
+            scopes: Vec::new(),
+            client_id_env: None,
+            client_secret_env: None,
+            device_auth_url: None,
+            client_auth: ClientAuth::Body,
+        };
+
+        for url in [
+            "http://example.com/authorize",
+            "javascript:alert(1)",
+            "example.com/authorize",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    registry.set("example", definition(url)),
+                    Err(ProviderError::InvalidUrl {
+                        field: "auth_url",
+                        ..
+                    })
+                ),
+                "{} should be rejected",
+                url
+            );
+        }
+        assert!(!registry.is_valid_connector("example"));
+        assert!(matches!(
+            registry.set(
+                "example",
+                ProviderDefinition {
+                    device_auth_url: Some("http://example.com/device/code".to_string()),
+                    ..definition("https://example.com/authorize")
+                }
+            ),
+            Err(ProviderError::InvalidUrl {
+                field: "device_auth_url",
+                ..
+            })
+        ));
+        assert_eq!(
+            registry.set("Bad Name", definition("https://example.com/authorize")),
+            Err(ProviderError::InvalidName("Bad Name".to_string()))
+        );
+
+        let file = r#"
+            [example]
+            auth_url = "http://example.com/authorize"
+            token_url = "https://example.com/token"
+        "#;
+        let err = parse_providers(file, false).unwrap_err();
+        assert!(format!("{:#}", err).contains("must use https"));
+    }
 }
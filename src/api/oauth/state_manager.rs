@@ -1,6 +1,7 @@
 //! OAuth state management for CSRF protection.
 //!
-//! Manages temporary state tokens used to prevent CSRF attacks during OAuth flow.
+//! Manages temporary state tokens used to prevent CSRF attacks during OAuth flow,
+//! and pending device authorizations, which expire when the provider says.
 
 use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
@@ -12,13 +13,36 @@ use uuid::Uuid;
 pub struct StateEntry {
     pub connector: String,
     pub namespace: String,
+    /// Namespace token to record with the credentials (auth mode only)
+    pub flux_token: Option<String>,
+    /// Validated post-callback redirect target (from `return_to` on start)
+    pub return_to: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+/// Device authorization waiting for the user to approve it
+#[derive(Clone, Debug)]
+pub struct DeviceFlowEntry {
+    pub connector: String,
+    pub namespace: String,
+    /// Namespace token to record with the credentials (auth mode only)
+    pub flux_token: Option<String>,
+    /// Provider's device code, exchanged for tokens once approved
+    pub device_code: String,
+    /// Minimum seconds between token polls
+    pub interval: i64,
+    /// When the provider stops accepting `device_code`
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Seconds a `slow_down` adds to the poll interval (RFC 8628 §3.5)
+const SLOW_DOWN_SECONDS: i64 = 5;
+
 /// OAuth state manager with automatic expiration
 #[derive(Clone)]
 pub struct StateManager {
     states: Arc<Mutex<HashMap<String, StateEntry>>>,
+    device_flows: Arc<Mutex<HashMap<String, DeviceFlowEntry>>>,
     expiry_duration: Duration,
 }
 
@@ -30,6 +54,7 @@ impl StateManager {
     pub fn new(expiry_seconds: i64) -> Self {
         Self {
             states: Arc::new(Mutex::new(HashMap::new())),
+            device_flows: Arc::new(Mutex::new(HashMap::new())),
             expiry_duration: Duration::seconds(expiry_seconds),
         }
     }
@@ -38,10 +63,26 @@ impl StateManager {
     ///
     /// Returns the state token (UUID v4)
     pub fn create_state(&self, connector: &str, namespace: &str) -> String {
+        self.create_state_with_return(connector, namespace, None, None)
+    }
+
+    /// Generate a new state token that carries the caller's namespace token
+    /// and a redirect target
+    ///
+    /// `return_to` must already be validated against the origin allowlist.
+    pub fn create_state_with_return(
+        &self,
+        connector: &str,
+        namespace: &str,
+        flux_token: Option<String>,
+        return_to: Option<String>,
+    ) -> String {
         let state = Uuid::new_v4().to_string();
         let entry = StateEntry {
             connector: connector.to_string(),
             namespace: namespace.to_string(),
+            flux_token,
+            return_to,
             created_at: Utc::now(),
         };
 
@@ -70,20 +111,86 @@ impl StateManager {
         Some(entry)
     }
 
+    /// Track a device authorization, expiring after the provider's `expires_in`
+    ///
+    /// Returns the flow ID (UUID v4) clients poll with; the device code itself
+    /// never leaves the server.
+    pub fn create_device_flow(
+        &self,
+        connector: &str,
+        namespace: &str,
+        flux_token: Option<String>,
+        device_code: String,
+        interval: i64,
+        expires_in: i64,
+    ) -> String {
+        let flow_id = Uuid::new_v4().to_string();
+        let entry = DeviceFlowEntry {
+            connector: connector.to_string(),
+            namespace: namespace.to_string(),
+            flux_token,
+            device_code,
+            interval,
+            expires_at: Utc::now() + Duration::seconds(expires_in),
+        };
+
+        self.device_flows
+            .lock()
+            .unwrap()
+            .insert(flow_id.clone(), entry);
+
+        flow_id
+    }
+
+    /// Look up a device flow, None if unknown or expired
+    pub fn device_flow(&self, flow_id: &str) -> Option<DeviceFlowEntry> {
+        let mut flows = self.device_flows.lock().unwrap();
+        let entry = flows.get(flow_id)?;
+        if Utc::now() >= entry.expires_at {
+            flows.remove(flow_id);
+            return None;
+        }
+        Some(entry.clone())
+    }
+
+    /// Lengthen a device flow's poll interval after a `slow_down`
+    ///
+    /// Returns the new interval, None if the flow is gone.
+    pub fn slow_down_device_flow(&self, flow_id: &str) -> Option<i64> {
+        let mut flows = self.device_flows.lock().unwrap();
+        let entry = flows.get_mut(flow_id)?;
+        entry.interval += SLOW_DOWN_SECONDS;
+        Some(entry.interval)
+    }
+
+    /// Forget a device flow once it succeeded or failed for good
+    pub fn remove_device_flow(&self, flow_id: &str) -> Option<DeviceFlowEntry> {
+        self.device_flows.lock().unwrap().remove(flow_id)
+    }
+
     /// Clean up expired states (should be called periodically)
     pub fn cleanup_expired(&self) {
         let mut states = self.states.lock().unwrap();
         let now = Utc::now();
 
-        states.retain(|_, entry| {
-            now - entry.created_at <= self.expiry_duration
-        });
+        states.retain(|_, entry| now - entry.created_at <= self.expiry_duration);
+        drop(states);
+
+        self.device_flows
+            .lock()
+            .unwrap()
+            .retain(|_, entry| now < entry.expires_at);
     }
 
     /// Get count of active states (for debugging/monitoring)
     pub fn count(&self) -> usize {
         self.states.lock().unwrap().len()
     }
+
+    /// Get count of pending device flows (for debugging/monitoring)
+    pub fn device_flow_count(&self) -> usize {
+        self.device_flows.lock().unwrap().len()
+    }
 }
 
 /// Background task to periodically clean up expired states
@@ -93,7 +200,11 @@ pub async fn run_state_cleanup(manager: StateManager, interval_seconds: u64) {
     loop {
         interval.tick().await;
         manager.cleanup_expired();
-        tracing::debug!("OAuth state cleanup complete, {} states remaining", manager.count());
+        tracing::debug!(
+            "OAuth state cleanup complete, {} states and {} device flows remaining",
+            manager.count(),
+            manager.device_flow_count()
+        );
     }
 }
 
@@ -114,6 +225,26 @@ mod tests {
         let entry = entry.unwrap();
         assert_eq!(entry.connector, "github");
         assert_eq!(entry.namespace, "user123");
+        assert_eq!(entry.return_to, None);
+    }
+
+    #[test]
+    fn test_state_carries_return_to() {
+        let manager = StateManager::new(600);
+
+        let state = manager.create_state_with_return(
+            "github",
+            "user123",
+            Some("tok-1".to_string()),
+            Some("https://app.example.com/settings".to_string()),
+        );
+
+        let entry = manager.validate_and_consume(&state).unwrap();
+        assert_eq!(entry.flux_token.as_deref(), Some("tok-1"));
+        assert_eq!(
+            entry.return_to.as_deref(),
+            Some("https://app.example.com/settings")
+        );
     }
 
     #[test]
@@ -165,4 +296,46 @@ mod tests {
         manager.cleanup_expired();
         assert_eq!(manager.count(), 0);
     }
+
+    #[test]
+    fn test_device_flow_expires_with_provider_expiry() {
+        let manager = StateManager::new(600);
+
+        let flow_id = manager.create_device_flow(
+            "github",
+            "alice",
+            Some("tok-1".to_string()),
+            "device-123".to_string(),
+            5,
+            1,
+        );
+        let entry = manager.device_flow(&flow_id).unwrap();
+        assert_eq!(entry.device_code, "device-123");
+        assert_eq!(entry.interval, 5);
+        // Polling doesn't consume the flow
+        assert!(manager.device_flow(&flow_id).is_some());
+
+        // Expires with the device code, not the 10-minute state expiry
+        std::thread::sleep(std::time::Duration::from_secs(2));
+        assert!(manager.device_flow(&flow_id).is_none());
+        assert_eq!(manager.device_flow_count(), 0);
+    }
+
+    #[test]
+    fn test_device_flow_slow_down_and_cleanup() {
+        let manager = StateManager::new(600);
+
+        let flow_id =
+            manager.create_device_flow("github", "alice", None, "device-1".to_string(), 5, 900);
+        assert_eq!(manager.slow_down_device_flow(&flow_id), Some(10));
+        assert_eq!(manager.device_flow(&flow_id).unwrap().interval, 10);
+        assert_eq!(manager.slow_down_device_flow("unknown"), None);
+
+        manager.create_device_flow("github", "bob", None, "device-2".to_string(), 5, 0);
+        manager.cleanup_expired();
+        assert_eq!(manager.device_flow_count(), 1);
+
+        assert!(manager.remove_device_flow(&flow_id).is_some());
+        assert!(manager.device_flow(&flow_id).is_none());
+    }
 }
@@ -0,0 +1,416 @@
+//! OpenAPI document for the Flux HTTP API.
+//!
+//! Each API module describes its own paths and schemas; this module merges
+//! them into one spec served at `GET /api/openapi.json`, with an optional
+//! Swagger UI at `/api/docs`.
+
+use crate::api::admin::AdminApi;
+use crate::api::compaction::CompactionApi;
+use crate::api::connectors::ConnectorApi;
+use crate::api::deletion::DeletionApi;
+use crate::api::federation::FederationApi;
+use crate::api::health::HealthApi;
+use crate::api::history::HistoryApi;
+use crate::api::ingestion::IngestionApi;
+use crate::api::introspect::IntrospectApi;
+use crate::api::messages::MessagesApi;
+use crate::api::namespace::NamespaceApi;
+#[cfg(feature = "oauth")]
+use crate::api::oauth::OAuthApi;
+use crate::api::query::QueryApi;
+use crate::api::rename::RenameApi;
+use crate::api::replay::ReplayApi;
+use crate::api::standby::StandbyApi;
+use crate::api::stream_mappings::StreamMappingApi;
+use crate::api::watches::WatchApi;
+use axum::{routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{Components, OpenApi as OpenApiDoc};
+use utoipa::{Modify, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Error body for handlers that build `{"error": ...}` inline
+#[derive(Serialize, Deserialize, ToSchema)]
+pub(crate) struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Flux API",
+        description = "Event ingestion, entity state queries and connector management"
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "ingestion", description = "Publish events"),
+        (name = "query", description = "Read current entity state"),
+        (name = "deletion", description = "Delete entities via tombstone events"),
+        (name = "history", description = "Raw stored events"),
+        (name = "messages", description = "Messages between agents"),
+        (name = "watches", description = "One-shot notifications when a property crosses a predicate"),
+        (name = "namespaces", description = "Namespace registration (auth mode)"),
+        (name = "auth", description = "Token introspection for other services"),
+        (name = "connectors", description = "Connector status and credentials"),
+        (name = "oauth", description = "Connector OAuth flow"),
+        (name = "admin", description = "Runtime configuration, entity maintenance, stream mappings, replays, standby promotion, federation and compaction"),
+        (name = "health", description = "Readiness and NATS connection state")
+    )
+)]
+struct RootApi;
+
+/// Registers the bearer schemes referenced by `security(...)` on handlers
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Components::new);
+        components.add_security_scheme(
+            "bearer_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Namespace token (required when auth is enabled)"))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("FLUX_ADMIN_TOKEN (required when configured)"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Build the merged OpenAPI document for every Flux router
+pub fn openapi_spec() -> OpenApiDoc {
+    let mut doc = RootApi::openapi();
+    for part in [
+        IngestionApi::openapi(),
+        QueryApi::openapi(),
+        DeletionApi::openapi(),
+        HistoryApi::openapi(),
+        MessagesApi::openapi(),
+        WatchApi::openapi(),
+        NamespaceApi::openapi(),
+        IntrospectApi::openapi(),
+        ConnectorApi::openapi(),
+        AdminApi::openapi(),
+        RenameApi::openapi(),
+        ReplayApi::openapi(),
+        StreamMappingApi::openapi(),
+        StandbyApi::openapi(),
+        FederationApi::openapi(),
+        CompactionApi::openapi(),
+        HealthApi::openapi(),
+    ] {
+        merge_into(&mut doc, part);
+    }
+    #[cfg(feature = "oauth")]
+    merge_into(&mut doc, OAuthApi::openapi());
+    doc
+}
+
+/// Merge `part` into `doc`, combining operations that share a path
+/// (`/api/events` is POST in ingestion and GET in history).
+fn merge_into(doc: &mut OpenApiDoc, part: OpenApiDoc) {
+    for (path, item) in part.paths.paths {
+        match doc.paths.paths.get_mut(&path) {
+            Some(existing) => existing.operations.extend(item.operations),
+            None => {
+                doc.paths.paths.insert(path, item);
+            }
+        }
+    }
+
+    if let Some(components) = part.components {
+        let target = doc.components.get_or_insert_with(Components::new);
+        for (name, schema) in components.schemas {
+            target.schemas.entry(name).or_insert(schema);
+        }
+    }
+}
+
+/// Create router serving the OpenAPI spec, plus Swagger UI if `docs_enabled`
+pub fn create_openapi_router(docs_enabled: bool) -> Router {
+    let spec = openapi_spec();
+    if docs_enabled {
+        Router::new().merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", spec))
+    } else {
+        Router::new().route(
+            "/api/openapi.json",
+            get(move || {
+                let spec = spec.clone();
+                async move { Json(spec) }
+            }),
+        )
+    }
+}
+
+// The spec tests cover every router, OAuth included
+#[cfg(all(test, feature = "oauth"))]
+mod tests {
+    use super::*;
+    use crate::api::admin::ConfigResponse;
+    use crate::api::connectors::{ConnectorDetail, ListConnectorsResponse};
+    use crate::api::deletion::{
+        BatchDeleteRequest, BatchDeleteResponse, DeleteFilter, DeleteResponse, FilterDeleteRequest,
+        FilterDeleteResponse,
+    };
+    use crate::api::health::{ReadinessResponse, ReplayingResponse};
+    use crate::api::ingestion::{BatchRequest, BatchResponse, EventResponse};
+    use crate::api::messages::{SendMessageRequest, SendMessageResponse};
+    use crate::api::namespace::{NamespaceInfo, RegisterRequest, RegisterResponse};
+    use crate::api::oauth::{OAuthSuccessResponse, ProviderDefinition, ProviderEntry};
+    use crate::api::query::EntityResponse;
+    use crate::api::rename::{RenameRequest, RenameResponse};
+    use crate::api::replay::ReplayRequest;
+    use crate::api::stream_mappings::{PutStreamMappingRequest, StreamMappingPreview};
+    use crate::config::RuntimeConfigUpdate;
+    use crate::event::FluxEvent;
+    use crate::journal::JournalEntry;
+    use crate::replay::{ReplayProgress, ReplayStatus};
+    use crate::state::{AgentMessage, RenamePreference};
+    use crate::supervisor::{TaskInfo, TaskStatus};
+    use crate::watch::{NewWatch, Predicate, Watch, WatchState};
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+
+    fn spec_json() -> Value {
+        serde_json::to_value(openapi_spec()).unwrap()
+    }
+
+    /// Collect property names of a schema, following `$ref`, `allOf` and `oneOf`
+    fn property_names(spec: &Value, schema: &Value, out: &mut BTreeSet<String>) {
+        if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            property_names(spec, &spec["components"]["schemas"][name], out);
+            return;
+        }
+        for key in ["allOf", "oneOf", "anyOf"] {
+            if let Some(parts) = schema.get(key).and_then(|p| p.as_array()) {
+                for part in parts {
+                    property_names(spec, part, out);
+                }
+            }
+        }
+        if let Some(props) = schema.get("properties").and_then(|p| p.as_object()) {
+            out.extend(props.keys().cloned());
+        }
+    }
+
+    /// Deserialize the example of component `name` into `T`, checking that
+    /// every example key is a documented property of the schema.
+    fn example_of<T: DeserializeOwned>(spec: &Value, name: &str) -> T {
+        let schema = &spec["components"]["schemas"][name];
+        let example = schema
+            .get("example")
+            .unwrap_or_else(|| panic!("{} has no example", name))
+            .clone();
+
+        let mut props = BTreeSet::new();
+        property_names(spec, schema, &mut props);
+        for key in example.as_object().expect("example is an object").keys() {
+            assert!(
+                props.contains(key),
+                "{}: example key `{}` not in schema properties {:?}",
+                name,
+                key,
+                props
+            );
+        }
+
+        serde_json::from_value(example)
+            .unwrap_or_else(|e| panic!("{} example does not deserialize: {}", name, e))
+    }
+
+    #[test]
+    fn test_spec_covers_all_routes() {
+        let spec = spec_json();
+        let paths = spec["paths"].as_object().unwrap();
+
+        for (path, method) in [
+            ("/api/events", "post"),
+            ("/api/events", "get"),
+            ("/api/events/batch", "post"),
+            ("/api/messages", "post"),
+            ("/api/messages", "get"),
+            ("/api/watches", "post"),
+            ("/api/watches/{id}", "get"),
+            ("/api/state/entities", "get"),
+            ("/api/state/entities/{id}", "get"),
+            ("/api/state/entities/{id}/journal", "get"),
+            ("/api/state/changes", "get"),
+            ("/api/state/recent", "get"),
+            ("/api/state/entities/{id}", "delete"),
+            ("/api/state/entities/delete", "post"),
+            ("/api/state/entities/delete-by-filter", "post"),
+            ("/api/namespaces", "post"),
+            ("/api/namespaces/{name}", "get"),
+            ("/api/namespaces/{name}", "delete"),
+            ("/api/admin/namespaces/{name}/quota", "put"),
+            ("/api/namespaces/{name}/grants", "post"),
+            ("/api/namespaces/{name}/grants", "get"),
+            ("/api/namespaces/{name}/grants/{grant_id}", "delete"),
+            ("/api/auth/introspect", "post"),
+            ("/api/connectors", "get"),
+            ("/api/connectors/{name}", "get"),
+            ("/api/connectors/{name}/token", "post"),
+            ("/api/connectors/{name}/token", "delete"),
+            ("/api/connectors/{name}/oauth/start", "get"),
+            ("/api/connectors/{name}/oauth/callback", "get"),
+            ("/api/admin/oauth-providers", "get"),
+            ("/api/admin/oauth-providers/{name}", "put"),
+            ("/api/admin/config", "get"),
+            ("/api/admin/config", "put"),
+            ("/api/admin/tasks", "get"),
+            ("/api/admin/tasks/{name}/restart", "post"),
+            ("/api/admin/entities/rename", "post"),
+            ("/api/admin/replay", "post"),
+            ("/api/admin/replay/{job_id}", "get"),
+            ("/api/admin/stream-mappings", "get"),
+            ("/api/admin/stream-mappings/{stream}", "get"),
+            ("/api/admin/stream-mappings/{stream}", "put"),
+            ("/api/admin/stream-mappings/{stream}", "delete"),
+            ("/api/admin/promote", "post"),
+            ("/api/admin/snapshots/latest/download", "get"),
+            ("/api/admin/federation", "get"),
+            ("/api/admin/compact", "post"),
+            ("/api/ready", "get"),
+        ] {
+            assert!(
+                paths.get(path).and_then(|p| p.get(method)).is_some(),
+                "missing {} {}",
+                method.to_uppercase(),
+                path
+            );
+        }
+
+        let schemes = &spec["components"]["securitySchemes"];
+        assert_eq!(schemes["bearer_token"]["scheme"], "bearer");
+        assert_eq!(schemes["admin_token"]["scheme"], "bearer");
+    }
+
+    #[test]
+    fn test_camel_case_renames_in_schema() {
+        let spec = spec_json();
+        let schemas = &spec["components"]["schemas"];
+
+        let info = schemas["NamespaceInfo"]["properties"].as_object().unwrap();
+        assert!(info.contains_key("namespaceId"));
+        assert!(info.contains_key("createdAt"));
+        assert!(info.contains_key("entityCount"));
+        assert!(!info.contains_key("namespace_id"));
+
+        assert!(schemas["FluxEvent"]["properties"].get("eventId").is_some());
+        assert!(schemas["EntityResponse"]["properties"]
+            .get("lastUpdated")
+            .is_some());
+        assert!(schemas["DeleteResponse"]["properties"]
+            .get("eventId")
+            .is_some());
+    }
+
+    #[test]
+    fn test_untagged_delete_filter_is_one_of() {
+        let spec = spec_json();
+        let variants = spec["components"]["schemas"]["DeleteFilter"]["oneOf"]
+            .as_array()
+            .expect("untagged enum renders as oneOf");
+        assert_eq!(variants.len(), 3);
+
+        let mut props = BTreeSet::new();
+        property_names(
+            &spec,
+            &spec["components"]["schemas"]["BatchDeleteRequest"],
+            &mut props,
+        );
+        for key in ["namespace", "prefix", "entity_ids"] {
+            assert!(props.contains(key), "BatchDeleteRequest missing {}", key);
+        }
+    }
+
+    #[test]
+    fn test_schema_examples_deserialize() {
+        let spec = spec_json();
+
+        let event: FluxEvent = example_of(&spec, "FluxEvent");
+        assert_eq!(event.payload["entity_id"], "temp-sensor-01");
+        let batch: BatchRequest = example_of(&spec, "BatchRequest");
+        assert_eq!(batch.events.len(), 1);
+        let _: EventResponse = example_of(&spec, "EventResponse");
+        let batch: BatchResponse = example_of(&spec, "BatchResponse");
+        assert_eq!(batch.results.len(), 2);
+        let sent: SendMessageRequest = example_of(&spec, "SendMessageRequest");
+        let _: SendMessageResponse = example_of(&spec, "SendMessageResponse");
+        let message: AgentMessage = example_of(&spec, "AgentMessage");
+        assert_eq!((sent.from, sent.to), (message.from, message.to));
+        let new_watch: NewWatch = example_of(&spec, "NewWatch");
+        let watch: Watch = example_of(&spec, "Watch");
+        assert_eq!(new_watch.predicate, watch.predicate);
+        assert!(matches!(watch.predicate, Predicate::Equals { .. }));
+        assert!(matches!(watch.state, WatchState::Fired { .. }));
+
+        let entity: EntityResponse = example_of(&spec, "EntityResponse");
+        assert_eq!(entity.id, "temp-sensor-01");
+        let change: JournalEntry = example_of(&spec, "JournalEntry");
+        assert_eq!(change.sequence, Some(1052));
+
+        let _: DeleteResponse = example_of(&spec, "DeleteResponse");
+        let request: BatchDeleteRequest = example_of(&spec, "BatchDeleteRequest");
+        assert!(matches!(request.filter, DeleteFilter::Namespace { .. }));
+        let _: BatchDeleteResponse = example_of(&spec, "BatchDeleteResponse");
+        let filter: FilterDeleteRequest = example_of(&spec, "FilterDeleteRequest");
+        assert!(filter.dry_run);
+        let _: FilterDeleteResponse = example_of(&spec, "FilterDeleteResponse");
+
+        let _: RegisterRequest = example_of(&spec, "RegisterRequest");
+        let registered: RegisterResponse = example_of(&spec, "RegisterResponse");
+        assert_eq!(registered.namespace_id, "ns_7x9f2a");
+        let info: NamespaceInfo = example_of(&spec, "NamespaceInfo");
+        assert_eq!(info.entity_count, 42);
+        assert_eq!(info.quota.max_entities, Some(10000));
+        assert_eq!(info.usage.entities, info.entity_count);
+
+        let _: ConnectorDetail = example_of(&spec, "ConnectorDetail");
+        let _: ListConnectorsResponse = example_of(&spec, "ListConnectorsResponse");
+        let _: OAuthSuccessResponse = example_of(&spec, "OAuthSuccessResponse");
+        let provider: ProviderDefinition = example_of(&spec, "ProviderDefinition");
+        assert!(provider.auth_url.starts_with("https://"));
+        let _: ProviderEntry = example_of(&spec, "ProviderEntry");
+
+        let config: ConfigResponse = example_of(&spec, "ConfigResponse");
+        assert!(config.config.rate_limit_enabled);
+        let update: RuntimeConfigUpdate = example_of(&spec, "RuntimeConfigUpdate");
+        assert_eq!(update.entity_ttl_seconds, Some(86400));
+        let task: TaskInfo = example_of(&spec, "TaskInfo");
+        assert_eq!(task.status, TaskStatus::Running);
+        let rename: RenameRequest = example_of(&spec, "RenameRequest");
+        assert!(rename.merge);
+        assert_eq!(rename.prefer, RenamePreference::To);
+        let renamed: RenameResponse = example_of(&spec, "RenameResponse");
+        assert_eq!(renamed.event_ids.len(), 2);
+        let replay: ReplayRequest = example_of(&spec, "ReplayRequest");
+        assert_ne!(replay.namespace, replay.target_namespace);
+        let progress: ReplayProgress = example_of(&spec, "ReplayProgress");
+        assert_eq!(progress.status, ReplayStatus::Completed);
+        let mapping: PutStreamMappingRequest = example_of(&spec, "PutStreamMappingRequest");
+        let compiled = crate::mapping::CompiledMapping::compile(&mapping.mapping).unwrap();
+        let preview: StreamMappingPreview = example_of(&spec, "StreamMappingPreview");
+        assert_eq!(
+            compiled.extract(&mapping.example).unwrap().0,
+            preview.entity_id
+        );
+
+        let ready: ReadinessResponse = example_of(&spec, "ReadinessResponse");
+        assert!(ready.nats.tls);
+        let replaying: ReplayingResponse = example_of(&spec, "ReplayingResponse");
+        assert_eq!(replaying.replay.percent_complete, Some(25.0));
+    }
+}
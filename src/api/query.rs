@@ -1,48 +1,330 @@
-use crate::state::StateEngine;
+use crate::api::auth_middleware::{AuthScope, ReadAuthState};
+use crate::api::query_cache::QueryCache;
+use crate::journal::{EntityJournal, JournalEntry};
+use crate::namespace::NamespaceRegistry;
+use crate::state::{
+    AgentMessage, ChangesError, ChangesSince, Entity, PublisherInfo, RecentChange, StateEngine,
+};
+use crate::subscription::truncate_large_values;
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
     routing::get,
     Router,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
+use tracing::error;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 /// Shared state for query API (uses same WsAppState from websocket module)
 pub struct QueryAppState {
     pub state_engine: Arc<StateEngine>,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    /// When true, requests need a bearer token and only see its namespace
+    pub auth_enabled: bool,
+    /// Token that sees every namespace
+    pub admin_token: Option<String>,
+    /// Recently served entity listings
+    pub response_cache: QueryCache,
+    /// Last changes of each entity, if `[journal]` is enabled
+    pub journal: Option<Arc<EntityJournal>>,
+}
+
+impl ReadAuthState for QueryAppState {
+    fn auth_enabled(&self) -> bool {
+        self.auth_enabled
+    }
+    fn namespace_registry(&self) -> &NamespaceRegistry {
+        &self.namespace_registry
+    }
+    fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
 }
 
 /// Query parameters for entity listing
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct EntityQueryParams {
     /// Filter by namespace (exact match on namespace prefix)
     pub namespace: Option<String>,
     /// Filter by entity ID prefix (string matching)
     pub prefix: Option<String>,
+    /// Replace property values larger than this many bytes with a truncation marker
+    pub truncate: Option<usize>,
+    /// Only entities with a property not written for this many seconds
+    pub stale_properties_older_than: Option<u64>,
+    /// Include when each property was last written (`propertyTimes`)
+    #[serde(default)]
+    pub include_property_times: bool,
+    /// Only entities whose property of this name equals `equals`
+    pub property: Option<String>,
+    /// Value `property` must have: JSON (`42`, `true`, `"42"`), else a plain string
+    pub equals: Option<String>,
+}
+
+/// Query parameters for a single entity
+#[derive(Deserialize, IntoParams)]
+pub struct EntityParams {
+    /// Replace property values larger than this many bytes with a truncation marker
+    pub truncate: Option<usize>,
+    /// Include when each property was last written (`propertyTimes`)
+    #[serde(default)]
+    pub include_property_times: bool,
+}
+
+/// Query parameters for the trash listing
+#[derive(Deserialize, IntoParams)]
+pub struct TrashQueryParams {
+    /// Filter by entity ID prefix (string matching)
+    pub prefix: Option<String>,
+}
+
+/// Seconds without events after which a publisher is reported stale
+const DEFAULT_PUBLISHER_STALE_SECONDS: u64 = 300;
+
+/// Query parameters for publisher queries
+#[derive(Deserialize, IntoParams)]
+pub struct PublisherQueryParams {
+    /// Report publishers idle for longer than this many seconds as stale (default 300)
+    pub stale_after_seconds: Option<u64>,
+}
+
+/// Default and maximum page size of `GET /api/state/changes`
+const DEFAULT_CHANGES_LIMIT: usize = 1_000;
+const MAX_CHANGES_LIMIT: usize = 10_000;
+
+/// Query parameters for changes since a cursor (one of `since_seq` / `since_ts`)
+#[derive(Deserialize, IntoParams)]
+pub struct ChangesQueryParams {
+    /// Changes after this NATS stream sequence (a previous `next_since_seq`)
+    pub since_seq: Option<u64>,
+    /// Changes after this time (RFC 3339), to start syncing without a sequence
+    pub since_ts: Option<DateTime<Utc>>,
+    /// Page size (default 1000, max 10000)
+    pub limit: Option<usize>,
+    /// Replace property values larger than this many bytes with a truncation marker
+    pub truncate: Option<usize>,
+}
+
+/// Default number of entries returned by `GET /api/state/entities/:id/journal`
+const DEFAULT_JOURNAL_LIMIT: usize = 20;
+
+/// Most entries returned by one journal page
+const MAX_JOURNAL_LIMIT: usize = 1000;
+
+/// Query parameters for an entity's journal
+#[derive(Deserialize, IntoParams)]
+pub struct JournalQueryParams {
+    /// Page size (default 20, max 1000)
+    pub limit: Option<usize>,
+    /// Only changes older than this cursor (a previous `next_cursor`)
+    pub before: Option<i64>,
+}
+
+/// Default number of entries returned by `GET /api/state/recent`
+const DEFAULT_RECENT_LIMIT: usize = 100;
+
+/// Query parameters for recent activity
+#[derive(Deserialize, IntoParams)]
+pub struct RecentQueryParams {
+    /// `messages` (agent messages) or `events` (property changes)
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// Return at most this many of the newest entries (default 100); no more
+    /// than `[state] recent_activity_capacity` are kept
+    pub limit: Option<usize>,
+}
+
+/// Entities changed since a cursor
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "entities": [{
+        "id": "temp-sensor-01",
+        "properties": {"temperature": 22.5},
+        "lastUpdated": "2026-01-01T00:00:00+00:00"
+    }],
+    "deleted": ["temp-sensor-02"],
+    "next_since_seq": 12400,
+    "has_more": false,
+    "last_processed_sequence": 12400
+}))]
+pub struct ChangesResponse {
+    /// Entities created or modified, with their current state
+    pub entities: Vec<EntityResponse>,
+    /// IDs of entities deleted
+    pub deleted: Vec<String>,
+    /// Pass as `since_seq` on the next request
+    pub next_since_seq: u64,
+    /// True if more changes are waiting; request the next page right away
+    pub has_more: bool,
+    /// Last NATS sequence the engine has processed
+    pub last_processed_sequence: u64,
+}
+
+/// One page of an entity's journal
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct JournalResponse {
+    pub entity_id: String,
+    /// Newest first
+    pub entries: Vec<JournalEntry>,
+    /// Pass as `before` for the next page; absent on the last one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<i64>,
 }
 
 /// Entity response (matches StateEngine Entity model)
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "id": "temp-sensor-01",
+    "properties": {"temperature": 22.5, "unit": "celsius"},
+    "lastUpdated": "2026-01-01T00:00:00+00:00"
+}))]
 pub struct EntityResponse {
     pub id: String,
+    #[schema(value_type = Object)]
+    pub properties: serde_json::Value,
+    #[serde(rename = "lastUpdated")]
+    pub last_updated: String,
+    /// When each property was last written, if requested
+    #[serde(
+        rename = "propertyTimes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub property_times: Option<BTreeMap<String, String>>,
+}
+
+/// Deleted entity that can still be undeleted
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "id": "temp-sensor-01",
+    "properties": {"temperature": 22.5},
+    "lastUpdated": "2026-01-01T00:00:00+00:00",
+    "deletedAt": "2026-01-02T00:00:00+00:00",
+    "expiresAt": "2026-01-03T00:00:00+00:00"
+}))]
+pub struct DeletedEntityResponse {
+    pub id: String,
+    #[schema(value_type = Object)]
     pub properties: serde_json::Value,
     #[serde(rename = "lastUpdated")]
     pub last_updated: String,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: String,
+    /// When the entity leaves the trash unless undeleted first
+    #[serde(rename = "expiresAt")]
+    pub expires_at: String,
+}
+
+/// An event source and its last activity
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "source": "modbus-gateway",
+    "first_seen": "2026-01-01T00:00:00+00:00",
+    "last_seen": "2026-01-02T08:15:00+00:00",
+    "events_total": 48211,
+    "namespaces": ["plant"],
+    "idle_seconds": 12,
+    "stale": false
+}))]
+pub struct PublisherResponse {
+    pub source: String,
+    pub first_seen: String,
+    /// Time of its newest event
+    pub last_seen: String,
+    /// Events published since the engine started or loaded its snapshot
+    pub events_total: u64,
+    /// Namespaces it wrote entities in (with auth enabled, only the token's)
+    pub namespaces: Vec<String>,
+    /// Seconds since its newest event
+    pub idle_seconds: i64,
+    /// True if idle for longer than `stale_after_seconds`
+    pub stale: bool,
+}
+
+impl PublisherResponse {
+    /// `publisher` as seen in `scope`, or `None` if it didn't write there
+    fn visible(
+        publisher: PublisherInfo,
+        scope: &AuthScope,
+        stale_after_seconds: u64,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let namespaces = match scope {
+            AuthScope::All => publisher.namespaces.into_iter().collect(),
+            AuthScope::Namespace(namespace) => {
+                if !publisher.namespaces.contains(namespace) {
+                    return None;
+                }
+                vec![namespace.clone()]
+            }
+            // Publishers write whole namespaces, not a grant's slice
+            AuthScope::Grant { .. } => return None,
+        };
+        let idle_seconds = (now - publisher.last_seen).num_seconds().max(0);
+        Some(PublisherResponse {
+            source: publisher.source,
+            first_seen: publisher.first_seen.to_rfc3339(),
+            last_seen: publisher.last_seen.to_rfc3339(),
+            events_total: publisher.events_total,
+            namespaces,
+            idle_seconds,
+            stale: idle_seconds as u64 > stale_after_seconds,
+        })
+    }
 }
 
 /// Error response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
+/// OpenAPI description of the query endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_entities,
+        get_entity,
+        get_entity_journal,
+        list_changes,
+        list_trash,
+        list_publishers,
+        get_publisher,
+        list_recent
+    ),
+    components(schemas(
+        EntityResponse,
+        JournalResponse,
+        JournalEntry,
+        RecentChange,
+        AgentMessage,
+        ChangesResponse,
+        DeletedEntityResponse,
+        PublisherResponse,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct QueryApi;
+
 /// Create query API router
 pub fn create_query_router(state: Arc<QueryAppState>) -> Router {
     Router::new()
         .route("/api/state/entities", get(list_entities))
         .route("/api/state/entities/:id", get(get_entity))
+        .route("/api/state/entities/:id/journal", get(get_entity_journal))
+        .route("/api/state/changes", get(list_changes))
+        .route("/api/state/trash", get(list_trash))
+        .route("/api/state/publishers", get(list_publishers))
+        .route("/api/state/publishers/:source", get(get_publisher))
+        .route("/api/state/recent", get(list_recent))
+        .layer(CompressionLayer::new())
         .with_state(state)
 }
 
@@ -51,18 +333,121 @@ pub fn create_query_router(state: Arc<QueryAppState>) -> Router {
 /// Query parameters:
 /// - `namespace`: Filter by namespace (exact match, e.g., ?namespace=matt)
 /// - `prefix`: Filter by entity ID prefix (string matching, e.g., ?prefix=matt/sensor)
+/// - `truncate`: Replace property values over this many bytes with a marker
+/// - `stale_properties_older_than`: Only entities with a property not written
+///   for this many seconds (e.g. ?stale_properties_older_than=86400)
+/// - `include_property_times`: Add `propertyTimes` to each entity
+/// - `property` and `equals`: Only entities whose property has this value
+///   (e.g. ?property=status&equals=error); fast for `indexed_properties`
 ///
-/// Both filters can be combined (AND logic):
+/// Filters can be combined (AND logic):
 /// - ?namespace=matt&prefix=matt/sensor
+///
+/// With auth enabled, results are limited to the token's namespace.
+///
+/// The weak `ETag` changes whenever an entity in the listed namespace (or
+/// anywhere, if the listing isn't limited to one) changes; a matching
+/// `If-None-Match` gets 304.
+///
+/// Listings are cached for `query_cache_ttl_ms` and until the version in
+/// their `ETag` moves on; staleness listings are never cached.
+#[utoipa::path(
+    get,
+    path = "/api/state/entities",
+    tag = "query",
+    params(EntityQueryParams),
+    responses(
+        (status = 200, description = "Matching entities", body = [EntityResponse]),
+        (status = 304, description = "Nothing changed since the ETag in If-None-Match"),
+        (status = 400, description = "Only one of property and equals given", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn list_entities(
     State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    headers: HeaderMap,
     Query(params): Query<EntityQueryParams>,
-) -> Result<Json<Vec<EntityResponse>>, QueryError> {
-    let entities = state.state_engine.get_all_entities();
+) -> Result<Response, QueryError> {
+    // Read before the entities: a change racing this listing moves the version
+    // past the returned tag, so the next request refetches. Staleness changes
+    // with the clock alone, so stale listings are never tagged.
+    let engine = &state.state_engine;
+    let version = match scope.namespace().or(params.namespace.as_deref()) {
+        Some(namespace) => engine.namespace_version(namespace),
+        None => engine.world_version(),
+    };
+    let etag = params
+        .stale_properties_older_than
+        .is_none()
+        .then(|| format!("W/\"{:x}-{}\"", engine.version_epoch(), version));
+    if let Some(etag) = etag.as_ref().filter(|etag| not_modified(&headers, etag)) {
+        return Ok(not_modified_response(etag.clone()));
+    }
+    if params.property.is_some() != params.equals.is_some() {
+        return Err(QueryError::PropertyWithoutValue);
+    }
+
+    let body = match &etag {
+        Some(_) if state.response_cache.enabled() => {
+            let lookup = state
+                .response_cache
+                .get_or_compute(listing_cache_key(&scope, &params), version, || {
+                    entities_json(engine, &scope, &params)
+                })
+                .await;
+            if lookup.hit {
+                engine.metrics.record_query_cache_hit();
+            } else {
+                engine.metrics.record_query_cache_miss();
+            }
+            lookup.body
+        }
+        _ => entities_json(engine, &scope, &params),
+    };
+
+    let content_type = [(header::CONTENT_TYPE, "application/json")];
+    Ok(match etag {
+        Some(etag) => with_etag(etag, (content_type, body)),
+        None => (content_type, body).into_response(),
+    })
+}
+
+/// Cache key of a listing: everything that decides its contents
+fn listing_cache_key(scope: &AuthScope, params: &EntityQueryParams) -> String {
+    format!(
+        "{:?}|{:?}",
+        scope,
+        (
+            &params.namespace,
+            &params.prefix,
+            params.truncate,
+            params.include_property_times,
+            &params.property,
+            &params.equals,
+        )
+    )
+}
+
+/// Entities matching `params` in `scope`, serialized
+fn entities_json(engine: &StateEngine, scope: &AuthScope, params: &EntityQueryParams) -> Bytes {
+    let stale_cutoff = params.stale_properties_older_than.map(stale_cutoff);
+
+    // Shared refs: property maps are only copied for entities that pass the filters
+    let entities = match (&params.property, &params.equals) {
+        (Some(property), Some(equals)) => engine.query_entities(property, &equals_value(equals)),
+        _ => engine.entities_snapshot_refs(),
+    };
 
     let response: Vec<EntityResponse> = entities
-        .into_iter()
+        .iter()
         .filter(|entity| {
+            // Entities outside the caller's namespace are never visible
+            if !scope.allows(&entity.id) {
+                return false;
+            }
+
             // Apply namespace filter if specified
             if let Some(ref namespace) = params.namespace {
                 // Extract namespace from entity_id (format: "namespace/entity")
@@ -83,47 +468,465 @@ async fn list_entities(
                 }
             }
 
+            // Apply staleness filter if specified
+            if let Some(cutoff) = stale_cutoff {
+                if !cutoff.is_some_and(|cutoff| entity.has_property_older_than(cutoff)) {
+                    return false;
+                }
+            }
+
             true
         })
         .map(|entity| EntityResponse {
-            id: entity.id,
-            properties: serde_json::to_value(entity.properties)
-                .unwrap_or(serde_json::Value::Object(Default::default())),
+            id: entity.id.clone(),
+            properties: properties_json(&entity.properties, params.truncate),
             last_updated: entity.last_updated.to_rfc3339(),
+            property_times: params
+                .include_property_times
+                .then(|| property_times_json(entity)),
         })
         .collect();
 
-    Ok(Json(response))
+    Bytes::from(serde_json::to_vec(&response).expect("entity listing serializes"))
 }
 
 /// GET /api/state/entities/:id - Get specific entity
+///
+/// The weak `ETag` is derived from the entity's last change; a matching
+/// `If-None-Match` gets 304.
+#[utoipa::path(
+    get,
+    path = "/api/state/entities/{id}",
+    tag = "query",
+    params(
+        ("id" = String, Path, description = "Entity ID (percent-encode `/`)"),
+        EntityParams
+    ),
+    responses(
+        (status = 200, description = "Entity state", body = EntityResponse),
+        (status = 304, description = "Entity unchanged since the ETag in If-None-Match"),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Entity outside the token's namespace", body = ErrorResponse),
+        (status = 404, description = "Entity not found", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
 async fn get_entity(
     State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<EntityResponse>, QueryError> {
+    Query(params): Query<EntityParams>,
+) -> Result<Response, QueryError> {
+    let id = state.state_engine.normalize_entity_id(&id);
+    // Decided from the ID alone, so it doesn't reveal whether the entity exists
+    if !scope.allows(&id) {
+        return Err(QueryError::Forbidden);
+    }
+
     let entity = state
         .state_engine
         .get_entity(&id)
         .ok_or(QueryError::NotFound)?;
 
-    Ok(Json(EntityResponse {
-        id: entity.id,
-        properties: serde_json::to_value(entity.properties)
-            .unwrap_or(serde_json::Value::Object(Default::default())),
+    let etag = entity_etag(&entity);
+    if not_modified(&headers, &etag) {
+        return Ok(not_modified_response(etag));
+    }
+
+    let body = Json(EntityResponse {
+        properties: properties_json(&entity.properties, params.truncate),
         last_updated: entity.last_updated.to_rfc3339(),
+        property_times: params
+            .include_property_times
+            .then(|| property_times_json(&entity)),
+        id: entity.id,
+    });
+    Ok(with_etag(etag, body))
+}
+
+/// GET /api/state/entities/:id/journal - Last changes of an entity
+///
+/// Served from the persistent journal, newest first; pass `next_cursor` back
+/// as `before` for older changes. Only the last `[journal] entries_per_entity`
+/// changes are kept. A deleted entity's changes stay readable.
+#[utoipa::path(
+    get,
+    path = "/api/state/entities/{id}/journal",
+    tag = "query",
+    params(
+        ("id" = String, Path, description = "Entity ID (percent-encode `/`)"),
+        JournalQueryParams
+    ),
+    responses(
+        (status = 200, description = "One page of changes", body = JournalResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Entity outside the token's namespace", body = ErrorResponse),
+        (status = 404, description = "Journal not enabled", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn get_entity_journal(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Path(id): Path<String>,
+    Query(params): Query<JournalQueryParams>,
+) -> Result<Json<JournalResponse>, QueryError> {
+    let journal = state.journal.clone().ok_or(QueryError::JournalDisabled)?;
+    let id = state.state_engine.normalize_entity_id(&id).into_owned();
+    if !scope.allows(&id) {
+        return Err(QueryError::Forbidden);
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_JOURNAL_LIMIT)
+        .clamp(1, MAX_JOURNAL_LIMIT);
+    let entity_id = id.clone();
+    let page = tokio::task::spawn_blocking(move || journal.page(&entity_id, limit, params.before))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|page| page)
+        .map_err(|e| {
+            error!(error = %e, entity_id = %id, "Failed to read entity journal");
+            QueryError::JournalUnavailable
+        })?;
+
+    Ok(Json(JournalResponse {
+        entity_id: id,
+        entries: page.entries,
+        next_cursor: page.next_cursor,
+    }))
+}
+
+/// GET /api/state/changes - Entities changed since a cursor
+///
+/// For clients that poll instead of holding a WebSocket open. Start with
+/// `since_ts` (or `since_seq=0`), then pass `next_since_seq` back as
+/// `since_seq`. With auth enabled, only the token's namespace is reported.
+///
+/// A 410 means the cursor is older than the engine can answer (it started from
+/// a later snapshot, or too many deletions happened since): fetch
+/// `/api/state/entities` again and continue from `last_processed_sequence`.
+#[utoipa::path(
+    get,
+    path = "/api/state/changes",
+    tag = "query",
+    params(ChangesQueryParams),
+    responses(
+        (status = 200, description = "One page of changes", body = ChangesResponse),
+        (status = 400, description = "Neither or both of since_seq and since_ts", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 410, description = "Cursor too old, full resync required", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn list_changes(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Query(params): Query<ChangesQueryParams>,
+) -> Result<Json<ChangesResponse>, QueryError> {
+    let since = match (params.since_seq, params.since_ts) {
+        (Some(sequence), None) => ChangesSince::Sequence(sequence),
+        (None, Some(time)) => ChangesSince::Time(time),
+        _ => return Err(QueryError::InvalidCursor),
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+
+    let changes = state
+        .state_engine
+        .changes_since(since, limit, |id| scope.allows(id))?;
+
+    Ok(Json(ChangesResponse {
+        entities: changes
+            .entities
+            .iter()
+            .map(|entity| EntityResponse {
+                id: entity.id.clone(),
+                properties: properties_json(&entity.properties, params.truncate),
+                last_updated: entity.last_updated.to_rfc3339(),
+                property_times: None,
+            })
+            .collect(),
+        deleted: changes.deleted,
+        next_since_seq: changes.next_since_seq,
+        has_more: changes.has_more,
+        last_processed_sequence: state.state_engine.get_last_processed_sequence(),
     }))
 }
 
+/// GET /api/state/trash - Deleted entities that can still be undeleted
+///
+/// Most recently deleted first. With auth enabled, only the token's namespace
+/// is listed. Restore one with `POST /api/state/entities/:id/undelete`.
+#[utoipa::path(
+    get,
+    path = "/api/state/trash",
+    tag = "query",
+    params(TrashQueryParams),
+    responses(
+        (status = 200, description = "Recoverable entities", body = [DeletedEntityResponse]),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn list_trash(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Query(params): Query<TrashQueryParams>,
+) -> Result<Json<Vec<DeletedEntityResponse>>, QueryError> {
+    let retention = state.state_engine.trash_retention();
+    let prefix = params.prefix.as_deref().unwrap_or("");
+
+    let response = state
+        .state_engine
+        .deleted_entities()
+        .into_iter()
+        .filter(|deleted| scope.allows(&deleted.entity.id) && deleted.entity.id.starts_with(prefix))
+        .map(|deleted| DeletedEntityResponse {
+            properties: properties_json(&deleted.entity.properties, None),
+            last_updated: deleted.entity.last_updated.to_rfc3339(),
+            deleted_at: deleted.deleted_at.to_rfc3339(),
+            expires_at: (deleted.deleted_at + retention).to_rfc3339(),
+            id: deleted.entity.id,
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// GET /api/state/publishers - Event sources and their last activity
+///
+/// Sorted by source. Only the most recently active sources are tracked
+/// (`max_tracked_publishers`). With auth enabled, only sources that wrote to
+/// the token's namespace are listed.
+#[utoipa::path(
+    get,
+    path = "/api/state/publishers",
+    tag = "query",
+    params(PublisherQueryParams),
+    responses(
+        (status = 200, description = "Tracked publishers", body = [PublisherResponse]),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn list_publishers(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Query(params): Query<PublisherQueryParams>,
+) -> Json<Vec<PublisherResponse>> {
+    let stale_after = params
+        .stale_after_seconds
+        .unwrap_or(DEFAULT_PUBLISHER_STALE_SECONDS);
+    let now = Utc::now();
+    let response = state
+        .state_engine
+        .publishers()
+        .into_iter()
+        .filter_map(|publisher| PublisherResponse::visible(publisher, &scope, stale_after, now))
+        .collect();
+    Json(response)
+}
+
+/// GET /api/state/publishers/:source - One event source's last activity
+#[utoipa::path(
+    get,
+    path = "/api/state/publishers/{source}",
+    tag = "query",
+    params(
+        ("source" = String, Path, description = "Event source"),
+        PublisherQueryParams
+    ),
+    responses(
+        (status = 200, description = "Publisher found", body = PublisherResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 404, description = "Source not tracked (or not in the token's namespace)", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn get_publisher(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Path(source): Path<String>,
+    Query(params): Query<PublisherQueryParams>,
+) -> Result<Json<PublisherResponse>, QueryError> {
+    let stale_after = params
+        .stale_after_seconds
+        .unwrap_or(DEFAULT_PUBLISHER_STALE_SECONDS);
+    state
+        .state_engine
+        .publisher(&source)
+        .and_then(|publisher| {
+            PublisherResponse::visible(publisher, &scope, stale_after, Utc::now())
+        })
+        .map(Json)
+        .ok_or(QueryError::PublisherNotFound)
+}
+
+/// GET /api/state/recent - Recent live agent messages or property changes
+///
+/// Oldest first, so a client can fill its feeds after a reload. Only traffic
+/// seen since the server went live is kept, the last
+/// `[state] recent_activity_capacity` of each type. With auth enabled, only
+/// the token's namespace is listed (messages to or from it).
+#[utoipa::path(
+    get,
+    path = "/api/state/recent",
+    tag = "query",
+    params(RecentQueryParams),
+    responses(
+        (status = 200, description = "Property changes; for `type=messages`, agent messages (`AgentMessage`)", body = [RecentChange]),
+        (status = 400, description = "Missing or unknown type", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn list_recent(
+    State(state): State<Arc<QueryAppState>>,
+    scope: AuthScope,
+    Query(params): Query<RecentQueryParams>,
+) -> Result<Response, QueryError> {
+    let limit = params.limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+    let engine = &state.state_engine;
+    match params.kind.as_deref() {
+        Some("messages") => {
+            let messages = engine.recent_messages(limit, |message| {
+                scope.allows(&message.to) || scope.allows(&message.from)
+            });
+            Ok(Json(messages).into_response())
+        }
+        Some("events") => {
+            let changes = engine.recent_changes(limit, |change| scope.allows(&change.entity_id));
+            Ok(Json(changes).into_response())
+        }
+        _ => Err(QueryError::InvalidRecentType),
+    }
+}
+
+/// Weak ETag of one entity: its last change's stream sequence and time
+fn entity_etag(entity: &Entity) -> String {
+    format!(
+        "W/\"{}-{}\"",
+        entity.last_modified_sequence,
+        entity.last_updated.timestamp_micros()
+    )
+}
+
+/// True if `If-None-Match` lists `etag` (weak comparison) or is `*`
+fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    fn opaque(tag: &str) -> &str {
+        let tag = tag.trim();
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+fn not_modified_response(etag: String) -> Response {
+    (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+}
+
+fn with_etag(etag: String, body: impl IntoResponse) -> Response {
+    ([(header::ETAG, etag)], body).into_response()
+}
+
+/// When each of the entity's properties was last written (RFC 3339)
+fn property_times_json(entity: &Entity) -> BTreeMap<String, String> {
+    entity
+        .properties
+        .keys()
+        .map(|property| {
+            (
+                property.clone(),
+                entity.property_time(property).to_rfc3339(),
+            )
+        })
+        .collect()
+}
+
+/// Time before which a property is stale, `None` if no time is that old
+fn stale_cutoff(max_age_seconds: u64) -> Option<DateTime<Utc>> {
+    let max_age = chrono::Duration::seconds(max_age_seconds.min(i64::MAX as u64 / 1_000) as i64);
+    Utc::now().checked_sub_signed(max_age)
+}
+
+/// `equals` as JSON if it parses, else as a string, so `?equals=error`
+/// needs no quotes
+fn equals_value(equals: &str) -> serde_json::Value {
+    serde_json::from_str(equals).unwrap_or_else(|_| serde_json::Value::from(equals))
+}
+
+/// Entity properties as JSON; values over `truncate` bytes become markers
+fn properties_json(properties: &impl Serialize, truncate: Option<usize>) -> serde_json::Value {
+    let mut properties =
+        serde_json::to_value(properties).unwrap_or(serde_json::Value::Object(Default::default()));
+    if let (Some(max_bytes), Some(map)) = (truncate, properties.as_object_mut()) {
+        map.values_mut()
+            .for_each(|value| truncate_large_values(value, max_bytes));
+    }
+    properties
+}
+
 /// Query error types
 #[derive(Debug)]
 enum QueryError {
     NotFound,
+    PublisherNotFound,
+    Forbidden,
+    InvalidCursor,
+    InvalidRecentType,
+    PropertyWithoutValue,
+    JournalDisabled,
+    JournalUnavailable,
+    Changes(ChangesError),
+}
+
+impl From<ChangesError> for QueryError {
+    fn from(e: ChangesError) -> Self {
+        QueryError::Changes(e)
+    }
 }
 
 impl IntoResponse for QueryError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             QueryError::NotFound => (StatusCode::NOT_FOUND, "Entity not found"),
+            QueryError::PublisherNotFound => (StatusCode::NOT_FOUND, "Publisher not found"),
+            QueryError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "Entity is outside the token's namespace",
+            ),
+            QueryError::InvalidCursor => (
+                StatusCode::BAD_REQUEST,
+                "Pass exactly one of since_seq and since_ts",
+            ),
+            QueryError::InvalidRecentType => {
+                (StatusCode::BAD_REQUEST, "type must be messages or events")
+            }
+            QueryError::PropertyWithoutValue => {
+                (StatusCode::BAD_REQUEST, "Pass property and equals together")
+            }
+            QueryError::JournalDisabled => (StatusCode::NOT_FOUND, "Entity journal is not enabled"),
+            QueryError::JournalUnavailable => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read entity journal",
+            ),
+            QueryError::Changes(e @ ChangesError::ResyncRequired { oldest_sequence }) => {
+                let body = serde_json::json!({
+                    "error": e.to_string(),
+                    "resync_required": true,
+                    "oldest_sequence": oldest_sequence,
+                });
+                return (StatusCode::GONE, Json(body)).into_response();
+            }
         };
 
         let body = Json(ErrorResponse {
@@ -143,12 +946,21 @@ mod tests {
         Arc::new(StateEngine::new())
     }
 
+    fn create_app_state(engine: &Arc<StateEngine>) -> Arc<QueryAppState> {
+        Arc::new(QueryAppState {
+            state_engine: Arc::clone(engine),
+            namespace_registry: Arc::new(NamespaceRegistry::new()),
+            auth_enabled: false,
+            admin_token: None,
+            response_cache: QueryCache::new(),
+            journal: None,
+        })
+    }
+
     #[tokio::test]
     async fn test_list_entities_no_filters() {
         let engine = create_test_state();
-        let app_state = Arc::new(QueryAppState {
-            state_engine: engine.clone(),
-        });
+        let app_state = create_app_state(&engine);
 
         // Create test entities with different namespaces
         engine.update_property("matt/sensor-01", "value", serde_json::json!(42));
@@ -159,21 +971,22 @@ mod tests {
         let params = EntityQueryParams {
             namespace: None,
             prefix: None,
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
         };
 
-        let result = list_entities(State(app_state), Query(params))
-            .await
-            .unwrap();
+        let result = list(app_state, AuthScope::All, params).await;
 
-        assert_eq!(result.0.len(), 3);
+        assert_eq!(result.len(), 3);
     }
 
     #[tokio::test]
     async fn test_list_entities_namespace_filter() {
         let engine = create_test_state();
-        let app_state = Arc::new(QueryAppState {
-            state_engine: engine.clone(),
-        });
+        let app_state = create_app_state(&engine);
 
         // Create test entities
         engine.update_property("matt/sensor-01", "value", serde_json::json!(42));
@@ -184,22 +997,23 @@ mod tests {
         let params = EntityQueryParams {
             namespace: Some("matt".to_string()),
             prefix: None,
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
         };
 
-        let result = list_entities(State(app_state), Query(params))
-            .await
-            .unwrap();
+        let result = list(app_state, AuthScope::All, params).await;
 
-        assert_eq!(result.0.len(), 2);
-        assert!(result.0.iter().all(|e| e.id.starts_with("matt/")));
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|e| e.id.starts_with("matt/")));
     }
 
     #[tokio::test]
     async fn test_list_entities_prefix_filter() {
         let engine = create_test_state();
-        let app_state = Arc::new(QueryAppState {
-            state_engine: engine.clone(),
-        });
+        let app_state = create_app_state(&engine);
 
         // Create test entities
         engine.update_property("matt/sensor-01", "value", serde_json::json!(42));
@@ -210,22 +1024,23 @@ mod tests {
         let params = EntityQueryParams {
             namespace: None,
             prefix: Some("matt/sensor".to_string()),
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
         };
 
-        let result = list_entities(State(app_state), Query(params))
-            .await
-            .unwrap();
+        let result = list(app_state, AuthScope::All, params).await;
 
-        assert_eq!(result.0.len(), 2);
-        assert!(result.0.iter().all(|e| e.id.starts_with("matt/sensor")));
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|e| e.id.starts_with("matt/sensor")));
     }
 
     #[tokio::test]
     async fn test_list_entities_combined_filters() {
         let engine = create_test_state();
-        let app_state = Arc::new(QueryAppState {
-            state_engine: engine.clone(),
-        });
+        let app_state = create_app_state(&engine);
 
         // Create test entities
         engine.update_property("matt/sensor-01", "value", serde_json::json!(42));
@@ -237,15 +1052,17 @@ mod tests {
         let params = EntityQueryParams {
             namespace: Some("matt".to_string()),
             prefix: Some("matt/sensor".to_string()),
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
         };
 
-        let result = list_entities(State(app_state), Query(params))
-            .await
-            .unwrap();
+        let result = list(app_state, AuthScope::All, params).await;
 
-        assert_eq!(result.0.len(), 2);
+        assert_eq!(result.len(), 2);
         assert!(result
-            .0
             .iter()
             .all(|e| e.id.starts_with("matt/") && e.id.starts_with("matt/sensor")));
     }
@@ -253,9 +1070,7 @@ mod tests {
     #[tokio::test]
     async fn test_list_entities_namespace_excludes_non_namespaced() {
         let engine = create_test_state();
-        let app_state = Arc::new(QueryAppState {
-            state_engine: engine.clone(),
-        });
+        let app_state = create_app_state(&engine);
 
         // Create entities with and without namespaces
         engine.update_property("matt/sensor-01", "value", serde_json::json!(42));
@@ -265,13 +1080,781 @@ mod tests {
         let params = EntityQueryParams {
             namespace: Some("matt".to_string()),
             prefix: None,
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
         };
 
-        let result = list_entities(State(app_state), Query(params))
+        let result = list(app_state, AuthScope::All, params).await;
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id, "matt/sensor-01");
+    }
+
+    /// Entities listed by `list_entities` for a request without `If-None-Match`
+    async fn list(
+        app_state: Arc<QueryAppState>,
+        scope: AuthScope,
+        params: EntityQueryParams,
+    ) -> Vec<EntityResponse> {
+        let response = list_entities(State(app_state), scope, HeaderMap::new(), Query(params))
             .await
             .unwrap();
+        json_body(response).await
+    }
 
+    async fn json_body<T: serde::de::DeserializeOwned>(response: Response) -> T {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn no_filters() -> EntityQueryParams {
+        EntityQueryParams {
+            namespace: None,
+            prefix: None,
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_entities_scoped_to_namespace() {
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(1));
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(2));
+        engine.update_property("simple-entity", "value", serde_json::json!(3));
+
+        let alice = AuthScope::Namespace("alice".to_string());
+        let result = list(Arc::clone(&app_state), alice.clone(), no_filters()).await;
+        let ids: Vec<&str> = result.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["alice/sensor-01"]);
+
+        // Asking for bob's namespace explicitly still returns nothing
+        let params = EntityQueryParams {
+            namespace: Some("bob".to_string()),
+            prefix: None,
+            truncate: None,
+            stale_properties_older_than: None,
+            include_property_times: false,
+            property: None,
+            equals: None,
+        };
+        let result = list(app_state, alice, params).await;
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_entities_property_filter() {
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+        engine.update_property("matt/pump-01", "status", serde_json::json!("error"));
+        engine.update_property("matt/pump-02", "status", serde_json::json!("ok"));
+        engine.update_property("arc/pump-01", "status", serde_json::json!("error"));
+        engine.update_property("matt/pump-03", "status", serde_json::json!(42));
+
+        let query = |equals: &str| {
+            let params = EntityQueryParams {
+                namespace: Some("matt".to_string()),
+                property: Some("status".to_string()),
+                equals: Some(equals.to_string()),
+                ..no_filters()
+            };
+            list(Arc::clone(&app_state), AuthScope::All, params)
+        };
+        let result = query("error").await;
+        let ids: Vec<&str> = result.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["matt/pump-01"]);
+
+        // JSON values match by type
+        assert_eq!(query("42").await.len(), 1);
+        assert!(query("\"42\"").await.is_empty());
+
+        let params = EntityQueryParams {
+            property: Some("status".to_string()),
+            ..no_filters()
+        };
+        let result = list_entities(
+            State(app_state),
+            AuthScope::All,
+            HeaderMap::new(),
+            Query(params),
+        )
+        .await;
+        assert!(matches!(result, Err(QueryError::PropertyWithoutValue)));
+    }
+
+    #[tokio::test]
+    async fn test_listing_cached_until_state_changes() {
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(1));
+        let alice = || EntityQueryParams {
+            namespace: Some("alice".to_string()),
+            ..no_filters()
+        };
+
+        let first = list(Arc::clone(&app_state), AuthScope::All, alice()).await;
+        let second = list(Arc::clone(&app_state), AuthScope::All, alice()).await;
+        assert_eq!(first[0].properties, second[0].properties);
+        assert_eq!(engine.metrics.get_query_cache_misses(), 1);
+        assert_eq!(engine.metrics.get_query_cache_hits(), 1);
+
+        // Another scope or filter is another listing
+        let scoped = AuthScope::Namespace("alice".to_string());
+        list(Arc::clone(&app_state), scoped, alice()).await;
+        assert_eq!(engine.metrics.get_query_cache_misses(), 2);
+
+        // A write to the namespace moves its version past the cached listing
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(2));
+        let result = list(Arc::clone(&app_state), AuthScope::All, alice()).await;
+        assert_eq!(result[0].properties["value"], serde_json::json!(2));
+        assert_eq!(engine.metrics.get_query_cache_misses(), 3);
+
+        // Staleness listings depend on the clock and skip the cache
+        let stale = EntityQueryParams {
+            stale_properties_older_than: Some(0),
+            ..alice()
+        };
+        list(Arc::clone(&app_state), AuthScope::All, stale).await;
+        assert_eq!(engine.metrics.get_query_cache_misses(), 3);
+        assert_eq!(engine.metrics.get_query_cache_hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_entity_outside_namespace_forbidden() {
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(2));
+
+        let alice = AuthScope::Namespace("alice".to_string());
+        let result = get_entity(
+            State(Arc::clone(&app_state)),
+            alice,
+            HeaderMap::new(),
+            Path("bob/sensor-01".to_string()),
+            Query(EntityParams {
+                truncate: None,
+                include_property_times: false,
+            }),
+        )
+        .await;
+        assert!(matches!(result, Err(QueryError::Forbidden)));
+
+        let result = get_entity(
+            State(app_state),
+            AuthScope::All,
+            HeaderMap::new(),
+            Path("bob/sensor-01".to_string()),
+            Query(EntityParams {
+                truncate: None,
+                include_property_times: false,
+            }),
+        )
+        .await
+        .unwrap();
+        let result: EntityResponse = json_body(result).await;
+        assert_eq!(result.id, "bob/sensor-01");
+    }
+
+    #[tokio::test]
+    async fn test_router_requires_token_when_auth_enabled() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let engine = create_test_state();
+        let registry = Arc::new(NamespaceRegistry::new());
+        let alice = registry.register("alice").unwrap();
+        registry.register("bob").unwrap();
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(1));
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(2));
+
+        let app = create_query_router(Arc::new(QueryAppState {
+            state_engine: engine,
+            namespace_registry: registry,
+            auth_enabled: true,
+            admin_token: Some("admin-secret".to_string()),
+            response_cache: QueryCache::new(),
+            journal: None,
+        }));
+        let get = |uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = get("/api/state/entities", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = get("/api/state/entities", Some("bogus")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = get("/api/state/entities", Some(&alice.token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let entities: Vec<EntityResponse> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].id, "alice/sensor-01");
+
+        let response = get("/api/state/entities/bob%2Fsensor-01", Some(&alice.token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = get("/api/state/entities/bob%2Fsensor-01", Some("admin-secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get_returns_304_until_state_changes() {
+        use axum::body::Body;
+        use axum::http::{HeaderValue, Request};
+        use tower::ServiceExt;
+
+        let engine = create_test_state();
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(1));
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(2));
+        let app = create_query_router(create_app_state(&engine));
+        let get = |uri: &str, etag: Option<&HeaderValue>| {
+            let mut request = Request::get(uri);
+            if let Some(etag) = etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        for uri in [
+            "/api/state/entities",
+            "/api/state/entities?namespace=alice",
+            "/api/state/entities/alice%2Fsensor-01",
+        ] {
+            let response = get(uri, None).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let etag = response.headers()[header::ETAG].clone();
+            assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+            let response = get(uri, Some(&etag)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{}", uri);
+            assert_eq!(response.headers()[header::ETAG], etag);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty());
+        }
+
+        // A change elsewhere leaves alice's listing and entity alone
+        let etag = |uri: &'static str| {
+            let response = get(uri, None);
+            async move { response.await.unwrap().headers()[header::ETAG].clone() }
+        };
+        let all = etag("/api/state/entities").await;
+        let alice = etag("/api/state/entities?namespace=alice").await;
+        let sensor = etag("/api/state/entities/alice%2Fsensor-01").await;
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(3));
+        let status = |uri: &'static str, etag: &HeaderValue| {
+            let response = get(uri, Some(etag));
+            async move { response.await.unwrap().status() }
+        };
+        assert_eq!(status("/api/state/entities", &all).await, StatusCode::OK);
+        assert_eq!(
+            status("/api/state/entities?namespace=alice", &alice).await,
+            StatusCode::NOT_MODIFIED
+        );
+
+        // Updates and deletions in the queried scope invalidate the tag
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(4));
+        assert_eq!(
+            status("/api/state/entities?namespace=alice", &alice).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("/api/state/entities/alice%2Fsensor-01", &sensor).await,
+            StatusCode::OK
+        );
+        let alice = etag("/api/state/entities?namespace=alice").await;
+        engine.delete_entity("alice/sensor-01");
+        assert_eq!(
+            status("/api/state/entities?namespace=alice", &alice).await,
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    fn test_if_none_match_parsing() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, value.parse().unwrap());
+            headers
+        };
+        let etag = "W/\"1-2\"";
+        assert!(not_modified(&headers("W/\"1-2\""), etag));
+        assert!(not_modified(&headers("\"1-2\""), etag));
+        assert!(not_modified(&headers("\"0-1\", W/\"1-2\""), etag));
+        assert!(not_modified(&headers("*"), etag));
+        assert!(!not_modified(&headers("W/\"1-3\""), etag));
+        assert!(!not_modified(&HeaderMap::new(), etag));
+    }
+
+    #[tokio::test]
+    async fn test_listing_is_gzipped_when_accepted() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use std::io::Read;
+        use tower::ServiceExt;
+
+        let engine = create_test_state();
+        engine.update_property("matt/sensor-01", "status", serde_json::json!("ok"));
+        let app = create_query_router(create_app_state(&engine));
+
+        let request = Request::get("/api/state/entities")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        let entities: Vec<EntityResponse> = serde_json::from_str(&json).unwrap();
+        assert_eq!(entities[0].id, "matt/sensor-01");
+    }
+
+    #[tokio::test]
+    async fn test_stale_properties_filter_and_property_times() {
+        use crate::state::Entity;
+        use std::collections::HashMap;
+
+        let engine = create_test_state();
+        let week_ago = Utc::now() - chrono::Duration::days(7);
+        let old = |id: &str| Entity {
+            id: id.to_string(),
+            properties: HashMap::from([
+                ("temp".to_string(), serde_json::json!(20)),
+                ("battery".to_string(), serde_json::json!(90)),
+            ]),
+            last_updated: week_ago,
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
+        };
+        engine.load_from_snapshot(
+            HashMap::from([
+                ("matt/sensor-01".to_string(), old("matt/sensor-01")),
+                ("matt/sensor-02".to_string(), old("matt/sensor-02")),
+            ]),
+            1,
+        );
+        // sensor-01 reports temperature again but its battery level went quiet
+        engine.update_property("matt/sensor-01", "temp", serde_json::json!(21));
+        engine.update_properties(
+            "matt/sensor-02",
+            [
+                ("temp".to_string(), serde_json::json!(21)),
+                ("battery".to_string(), serde_json::json!(89)),
+            ],
+        );
+        engine.update_property("matt/sensor-03", "temp", serde_json::json!(22));
+
+        let params = EntityQueryParams {
+            stale_properties_older_than: Some(86_400),
+            include_property_times: true,
+            ..no_filters()
+        };
+        let result = list(create_app_state(&engine), AuthScope::All, params).await;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].id, "matt/sensor-01");
+        let times = result[0].property_times.as_ref().unwrap();
+        assert_eq!(times["battery"], week_ago.to_rfc3339());
+        assert_eq!(times["temp"], result[0].last_updated);
+
+        // Property times are opt-in on the entity endpoint too
+        let get = |include_property_times| {
+            get_entity(
+                State(create_app_state(&engine)),
+                AuthScope::All,
+                HeaderMap::new(),
+                Path("matt/sensor-01".to_string()),
+                Query(EntityParams {
+                    truncate: None,
+                    include_property_times,
+                }),
+            )
+        };
+        let entity: EntityResponse = json_body(get(false).await.unwrap()).await;
+        assert!(entity.property_times.is_none());
+        let entity: EntityResponse = json_body(get(true).await.unwrap()).await;
+        assert_eq!(entity.property_times.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_truncate_is_opt_in() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let engine = create_test_state();
+        let blob = "x".repeat(100_000);
+        engine.update_property("matt/cam", "raw_payload", serde_json::json!(blob));
+        engine.update_property("matt/cam", "status", serde_json::json!("ok"));
+        let app = create_query_router(create_app_state(&engine));
+        let get = |uri: &str| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+        let entity = |body: &[u8]| serde_json::from_slice::<EntityResponse>(body).unwrap();
+
+        // Full values by default
+        let response = get("/api/state/entities/matt%2Fcam").await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            entity(&body).properties["raw_payload"],
+            serde_json::json!(blob)
+        );
+
+        let response = get("/api/state/entities/matt%2Fcam?truncate=32768")
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let properties = entity(&body).properties;
+        assert_eq!(properties["raw_payload"]["__truncated__"], true);
+        assert_eq!(properties["raw_payload"]["bytes"], 100_000);
+        assert_eq!(properties["status"], "ok");
+
+        let response = get("/api/state/entities?truncate=32768").await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let entities: Vec<EntityResponse> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(entities[0].properties["raw_payload"]["__truncated__"], true);
+    }
+
+    #[tokio::test]
+    async fn test_list_changes() {
+        use crate::state::Entity;
+        use std::collections::HashMap;
+
+        let engine = create_test_state();
+        engine.load_from_snapshot(
+            HashMap::from([(
+                "alice/old".to_string(),
+                Entity {
+                    id: "alice/old".to_string(),
+                    properties: HashMap::new(),
+                    last_updated: chrono::Utc::now(),
+                    last_applied: None,
+                    last_modified_sequence: 0,
+                    property_times: HashMap::new(),
+                },
+            )]),
+            10,
+        );
+        let loaded = chrono::Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        engine.update_property("alice/sensor-01", "value", serde_json::json!(1));
+        engine.update_property("bob/sensor-01", "value", serde_json::json!(2));
+        let app_state = create_app_state(&engine);
+        let params = |since_seq, since_ts| ChangesQueryParams {
+            since_seq,
+            since_ts,
+            limit: None,
+            truncate: None,
+        };
+
+        let alice = AuthScope::Namespace("alice".to_string());
+        let result = list_changes(
+            State(Arc::clone(&app_state)),
+            alice.clone(),
+            Query(params(Some(9), None)),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(QueryError::Changes(ChangesError::ResyncRequired {
+                oldest_sequence: 10
+            }))
+        ));
+
+        let result = list_changes(
+            State(Arc::clone(&app_state)),
+            alice.clone(),
+            Query(params(None, None)),
+        )
+        .await;
+        assert!(matches!(result, Err(QueryError::InvalidCursor)));
+
+        let response = list_changes(
+            State(Arc::clone(&app_state)),
+            alice.clone(),
+            Query(params(None, Some(loaded))),
+        )
+        .await
+        .unwrap();
+        let ids: Vec<&str> = response.0.entities.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["alice/sensor-01"]);
+        assert_eq!(response.0.next_since_seq, 10);
+        assert!(!response.0.has_more);
+
+        // Changes outside the stream share the last processed sequence
+        let response = list_changes(State(app_state), alice, Query(params(Some(10), None)))
+            .await
+            .unwrap();
+        assert!(response.0.entities.is_empty());
+        assert_eq!(response.0.last_processed_sequence, 10);
+    }
+
+    #[tokio::test]
+    async fn test_list_trash_scoped_to_namespace() {
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+
+        for id in ["alice/sensor-01", "alice/valve-01", "bob/sensor-01"] {
+            engine.update_property(id, "value", serde_json::json!(1));
+            engine.delete_entity(id);
+        }
+        engine.update_property("alice/live", "value", serde_json::json!(1));
+
+        let alice = AuthScope::Namespace("alice".to_string());
+        let params = |prefix: Option<&str>| TrashQueryParams {
+            prefix: prefix.map(str::to_string),
+        };
+
+        let result = list_trash(
+            State(Arc::clone(&app_state)),
+            alice.clone(),
+            Query(params(None)),
+        )
+        .await
+        .unwrap();
+        let mut ids: Vec<&str> = result.0.iter().map(|e| e.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["alice/sensor-01", "alice/valve-01"]);
+
+        let result = list_trash(State(app_state), alice, Query(params(Some("alice/sensor"))))
+            .await
+            .unwrap();
         assert_eq!(result.0.len(), 1);
-        assert_eq!(result.0[0].id, "matt/sensor-01");
+        assert_eq!(result.0[0].properties["value"], serde_json::json!(1));
+        let deleted_at = DateTime::parse_from_rfc3339(&result.0[0].deleted_at).unwrap();
+        let expires_at = DateTime::parse_from_rfc3339(&result.0[0].expires_at).unwrap();
+        assert_eq!(expires_at - deleted_at, engine.trash_retention());
+    }
+
+    #[tokio::test]
+    async fn test_publishers_scoped_and_marked_stale() {
+        use crate::event::FluxEvent;
+
+        let engine = create_test_state();
+        let app_state = create_app_state(&engine);
+        let event = |source: &str, entity_id: &str, timestamp: DateTime<Utc>| FluxEvent {
+            event_id: Some(uuid::Uuid::now_v7().to_string()),
+            stream: "flux.events".to_string(),
+            source: source.to_string(),
+            timestamp: timestamp.timestamp_millis(),
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: serde_json::json!({"entity_id": entity_id, "properties": {"v": 1}}),
+        };
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        engine.process_event(&event("gw", "alice/a", Utc::now()), None);
+        engine.process_event(&event("gw", "bob/b", Utc::now()), None);
+        engine.process_event(&event("old", "bob/c", hour_ago), None);
+
+        let params = || {
+            Query(PublisherQueryParams {
+                stale_after_seconds: None,
+            })
+        };
+        let all = list_publishers(State(Arc::clone(&app_state)), AuthScope::All, params()).await;
+        let summary: Vec<(&str, bool)> = all.iter().map(|p| (p.source.as_str(), p.stale)).collect();
+        assert_eq!(summary, [("gw", false), ("old", true)]);
+        assert_eq!(all[0].namespaces, ["alice", "bob"]);
+
+        // A namespace token only sees sources that wrote to it
+        let alice = AuthScope::Namespace("alice".to_string());
+        let listed = list_publishers(State(Arc::clone(&app_state)), alice.clone(), params()).await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].namespaces, ["alice"]);
+        let result = get_publisher(
+            State(Arc::clone(&app_state)),
+            alice,
+            Path("old".to_string()),
+            params(),
+        )
+        .await;
+        assert!(matches!(result, Err(QueryError::PublisherNotFound)));
+
+        let old = get_publisher(
+            State(app_state),
+            AuthScope::All,
+            Path("old".to_string()),
+            Query(PublisherQueryParams {
+                stale_after_seconds: Some(7200),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(!old.stale);
+        assert!(old.idle_seconds >= 3600);
+    }
+
+    #[tokio::test]
+    async fn test_list_recent_limit_type_and_scope() {
+        use crate::event::FluxEvent;
+
+        let engine = Arc::new(StateEngine::new().with_recent_activity(150));
+        let app_state = create_app_state(&engine);
+        engine.set_live();
+        for i in 0..200 {
+            engine.update_property("alice/counter", "n", serde_json::json!(i));
+        }
+        engine.update_property("bob/sensor", "n", serde_json::json!(0));
+        for (from, to) in [("alice/a", "bob/b"), ("bob/b", "bob/c")] {
+            let mut event = FluxEvent::agent_message(from, to, serde_json::json!("hi"), "test");
+            event.validate_and_prepare().unwrap();
+            engine.process_event(&event, None);
+        }
+
+        let recent = |scope: AuthScope, kind: Option<&str>, limit: Option<usize>| {
+            list_recent(
+                State(Arc::clone(&app_state)),
+                scope,
+                Query(RecentQueryParams {
+                    kind: kind.map(str::to_string),
+                    limit,
+                }),
+            )
+        };
+
+        // Default limit, newest last
+        let response = recent(AuthScope::All, Some("events"), None).await.unwrap();
+        let changes: Vec<RecentChange> = json_body(response).await;
+        assert_eq!(changes.len(), 100);
+        assert_eq!(changes[99].entity_id, "bob/sensor");
+        assert_eq!(changes[98].value, serde_json::json!(199));
+
+        // Past the capacity only what's kept comes back
+        let response = recent(AuthScope::All, Some("events"), Some(1_000))
+            .await
+            .unwrap();
+        let changes: Vec<RecentChange> = json_body(response).await;
+        assert_eq!(changes.len(), 150);
+        assert_eq!(changes[0].value, serde_json::json!(51));
+
+        let response = recent(AuthScope::All, Some("events"), Some(0))
+            .await
+            .unwrap();
+        assert!(json_body::<Vec<RecentChange>>(response).await.is_empty());
+
+        // A namespace token sees its own changes and messages to or from it
+        let alice = AuthScope::Namespace("alice".to_string());
+        let response = recent(alice.clone(), Some("events"), Some(1))
+            .await
+            .unwrap();
+        let changes: Vec<RecentChange> = json_body(response).await;
+        assert_eq!(changes[0].entity_id, "alice/counter");
+        let response = recent(alice, Some("messages"), None).await.unwrap();
+        let messages: Vec<AgentMessage> = json_body(response).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].from, "alice/a");
+        let response = recent(AuthScope::All, Some("messages"), None)
+            .await
+            .unwrap();
+        assert_eq!(json_body::<Vec<AgentMessage>>(response).await.len(), 2);
+
+        for kind in [None, Some("entities")] {
+            let result = recent(AuthScope::All, kind, None).await;
+            assert!(matches!(result, Err(QueryError::InvalidRecentType)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_journal_pages() {
+        use crate::state::JournalRecord;
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let engine = create_test_state();
+        let journal = Arc::new(EntityJournal::new(":memory:").unwrap());
+        let records: Vec<JournalRecord> = (1..=3)
+            .map(|i| JournalRecord {
+                sequence: Some(i),
+                update: engine.update_properties(
+                    "matt/sensor-01",
+                    [("value".to_string(), serde_json::json!(i))],
+                ),
+            })
+            .collect();
+        journal.append(&records).unwrap();
+
+        let app = create_query_router(Arc::new(QueryAppState {
+            state_engine: Arc::clone(&engine),
+            namespace_registry: Arc::new(NamespaceRegistry::new()),
+            auth_enabled: false,
+            admin_token: None,
+            response_cache: QueryCache::new(),
+            journal: Some(journal),
+        }));
+        let get = |uri: String| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("/api/state/entities/matt%2Fsensor-01/journal?limit=2".into())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let first: JournalResponse = json_body(response).await;
+        assert_eq!(first.entity_id, "matt/sensor-01");
+        let values: Vec<_> = first.entries.iter().map(|e| e.new_value.clone()).collect();
+        assert_eq!(values, [serde_json::json!(3), serde_json::json!(2)]);
+
+        let response = get(format!(
+            "/api/state/entities/matt%2Fsensor-01/journal?limit=2&before={}",
+            first.next_cursor.unwrap()
+        ))
+        .await
+        .unwrap();
+        let second: JournalResponse = json_body(response).await;
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.entries[0].sequence, Some(1));
+        assert_eq!(second.next_cursor, None);
+
+        // Without a journal the endpoint doesn't exist
+        let response = create_query_router(create_app_state(&engine))
+            .oneshot(
+                Request::get("/api/state/entities/matt%2Fsensor-01/journal")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 }
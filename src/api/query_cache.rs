@@ -0,0 +1,300 @@
+//! Short-lived cache of serialized entity listings.
+//!
+//! Dashboards poll the same listing every second or so; a hit returns the
+//! stored JSON without touching the entity map. An entry answers only while
+//! the state version it was computed at is still current and it is younger
+//! than `query_cache_ttl_ms`. At most `query_cache_max_entries` listings are
+//! kept, dropping the least recently used. Concurrent misses on one key
+//! compute once: later callers wait for the first and take its result.
+
+use crate::config::SharedRuntimeConfig;
+use axum::body::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Used when no runtime config is attached
+const DEFAULT_TTL: Duration = Duration::from_millis(1_000);
+const DEFAULT_MAX_ENTRIES: usize = 1_000;
+
+struct Slot {
+    body: Bytes,
+    /// State version the body was computed at
+    version: u64,
+    stored_at: Instant,
+    /// Position in `Entries::recency`
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    slots: HashMap<String, Slot>,
+    /// Keys by last use, oldest first
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl Entries {
+    /// Body stored for `key` if still valid at `version`; drops it otherwise
+    fn get(&mut self, key: &str, version: u64, ttl: Duration) -> Option<Bytes> {
+        let slot = self.slots.get(key)?;
+        if slot.version != version || slot.stored_at.elapsed() >= ttl {
+            self.remove(key);
+            return None;
+        }
+        self.clock += 1;
+        let slot = self.slots.get_mut(key)?;
+        self.recency.remove(&slot.last_used);
+        slot.last_used = self.clock;
+        self.recency.insert(self.clock, key.to_string());
+        Some(slot.body.clone())
+    }
+
+    fn insert(&mut self, key: String, version: u64, body: Bytes, max_entries: usize) {
+        self.remove(&key);
+        while self.slots.len() >= max_entries {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.slots.remove(&oldest);
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.slots.insert(
+            key,
+            Slot {
+                body,
+                version,
+                stored_at: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(slot) = self.slots.remove(key) {
+            self.recency.remove(&slot.last_used);
+        }
+    }
+}
+
+/// Result of [`QueryCache::get_or_compute`]
+pub(crate) struct Lookup {
+    pub body: Bytes,
+    /// False if the body was computed for this call
+    pub hit: bool,
+}
+
+pub struct QueryCache {
+    entries: Mutex<Entries>,
+    /// Held while a key's listing is computed
+    in_flight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    runtime_config: Option<SharedRuntimeConfig>,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QueryCache {
+    /// Cache with a 1s TTL holding up to 1000 listings
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::default(),
+            in_flight: Mutex::default(),
+            runtime_config: None,
+        }
+    }
+
+    /// Take the TTL and entry cap from runtime config and follow its changes
+    pub fn with_runtime_config(mut self, runtime_config: SharedRuntimeConfig) -> Self {
+        self.runtime_config = Some(runtime_config);
+        self
+    }
+
+    /// TTL and entry cap; a zero TTL turns the cache off
+    fn limits(&self) -> (Duration, usize) {
+        match &self.runtime_config {
+            Some(rc) => {
+                let config = rc.read().expect("RuntimeConfig lock poisoned");
+                (
+                    Duration::from_millis(config.query_cache_ttl_ms),
+                    config.query_cache_max_entries.max(1),
+                )
+            }
+            None => (DEFAULT_TTL, DEFAULT_MAX_ENTRIES),
+        }
+    }
+
+    /// True unless the TTL is zero
+    pub(crate) fn enabled(&self) -> bool {
+        !self.limits().0.is_zero()
+    }
+
+    /// Body cached for `key` at `version`, else the result of `compute`,
+    /// which is stored for later calls
+    ///
+    /// `version` must be read before `compute` looks at any state, so a
+    /// write racing the computation leaves an entry that is already stale.
+    pub(crate) async fn get_or_compute(
+        &self,
+        key: String,
+        version: u64,
+        compute: impl FnOnce() -> Bytes,
+    ) -> Lookup {
+        let (ttl, max_entries) = self.limits();
+        if ttl.is_zero() {
+            return Lookup {
+                body: compute(),
+                hit: false,
+            };
+        }
+        if let Some(body) = self.entries.lock().unwrap().get(&key, version, ttl) {
+            return Lookup { body, hit: true };
+        }
+
+        let guard = Arc::clone(
+            self.in_flight
+                .lock()
+                .unwrap()
+                .entry(key.clone())
+                .or_default(),
+        );
+        let lookup = {
+            let _computing = guard.lock().await;
+            // Whoever held the guard before us may have stored the listing
+            let cached = self.entries.lock().unwrap().get(&key, version, ttl);
+            match cached {
+                Some(body) => Lookup { body, hit: true },
+                None => {
+                    let body = compute();
+                    self.entries.lock().unwrap().insert(
+                        key.clone(),
+                        version,
+                        body.clone(),
+                        max_entries,
+                    );
+                    Lookup { body, hit: false }
+                }
+            }
+        };
+        let mut in_flight = self.in_flight.lock().unwrap();
+        // Only the map and this call hold the guard: nobody is waiting on it
+        if Arc::strong_count(&guard) == 2 {
+            in_flight.remove(&key);
+        }
+        lookup
+    }
+
+    /// Listings currently cached
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::new_runtime_config;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn body(text: &'static str) -> Bytes {
+        Bytes::from_static(text.as_bytes())
+    }
+
+    fn cache(ttl_ms: u64, max_entries: usize) -> QueryCache {
+        let runtime_config = new_runtime_config();
+        {
+            let mut config = runtime_config.write().unwrap();
+            config.query_cache_ttl_ms = ttl_ms;
+            config.query_cache_max_entries = max_entries;
+        }
+        QueryCache::new().with_runtime_config(runtime_config)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_misses_compute_once() {
+        let cache = Arc::new(QueryCache::new());
+        let computed = Arc::new(AtomicUsize::new(0));
+
+        let lookups: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let computed = Arc::clone(&computed);
+                tokio::spawn(async move {
+                    cache
+                        .get_or_compute("*".to_string(), 1, || {
+                            computed.fetch_add(1, Ordering::SeqCst);
+                            std::thread::sleep(Duration::from_millis(50));
+                            body("[]")
+                        })
+                        .await
+                })
+            })
+            .collect();
+        let mut hits = 0;
+        for lookup in lookups {
+            let lookup = lookup.await.unwrap();
+            assert_eq!(lookup.body, body("[]"));
+            hits += lookup.hit as usize;
+        }
+
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+        assert_eq!(hits, 7);
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_version_change_invalidates() {
+        let cache = QueryCache::new();
+        let first = cache.get_or_compute("*".into(), 1, || body("[1]")).await;
+        assert!(!first.hit);
+        let again = cache.get_or_compute("*".into(), 1, || body("[2]")).await;
+        assert!(again.hit);
+        assert_eq!(again.body, body("[1]"));
+
+        let advanced = cache.get_or_compute("*".into(), 2, || body("[2]")).await;
+        assert!(!advanced.hit);
+        assert_eq!(advanced.body, body("[2]"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_after_ttl() {
+        let cache = cache(20, 10);
+        cache.get_or_compute("*".into(), 1, || body("[1]")).await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let lookup = cache.get_or_compute("*".into(), 1, || body("[2]")).await;
+        assert!(!lookup.hit);
+        assert_eq!(lookup.body, body("[2]"));
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_cache() {
+        let cache = cache(0, 10);
+        assert!(!cache.enabled());
+        cache.get_or_compute("*".into(), 1, || body("[1]")).await;
+        let lookup = cache.get_or_compute("*".into(), 1, || body("[2]")).await;
+        assert!(!lookup.hit);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_evicted() {
+        let cache = cache(60_000, 2);
+        cache.get_or_compute("a".into(), 1, || body("a")).await;
+        cache.get_or_compute("b".into(), 1, || body("b")).await;
+        // Using "a" leaves "b" the oldest
+        assert!(cache.get_or_compute("a".into(), 1, || body("a")).await.hit);
+        cache.get_or_compute("c".into(), 1, || body("c")).await;
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_or_compute("a".into(), 1, || body("a")).await.hit);
+        assert!(cache.get_or_compute("c".into(), 1, || body("c")).await.hit);
+        assert!(!cache.get_or_compute("b".into(), 1, || body("b")).await.hit);
+    }
+}
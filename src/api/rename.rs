@@ -0,0 +1,303 @@
+use crate::api::auth_middleware::{AuthScope, ReadAuthState};
+use crate::api::openapi::ErrorResponse;
+use crate::event::FluxEvent;
+use crate::namespace::NamespaceRegistry;
+use crate::nats::EventPublisher;
+use crate::state::{Entity, RenameError, RenamePreference, StateEngine};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the entity rename API
+pub struct RenameAppState {
+    pub state_engine: Arc<StateEngine>,
+    pub event_publisher: EventPublisher,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    /// When true, both IDs must be in the token's namespace
+    pub auth_enabled: bool,
+    /// Token that may rename across namespaces
+    pub admin_token: Option<String>,
+}
+
+impl ReadAuthState for RenameAppState {
+    fn auth_enabled(&self) -> bool {
+        self.auth_enabled
+    }
+    fn namespace_registry(&self) -> &NamespaceRegistry {
+        &self.namespace_registry
+    }
+    fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+}
+
+/// Rename (or merge) request
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "from": "github/repo/alice/my-repo",
+    "to": "github/repo/12345",
+    "merge": true,
+    "prefer": "to"
+}))]
+pub struct RenameRequest {
+    pub from: String,
+    pub to: String,
+    /// Merge into `to` if it already exists (otherwise that is a 409)
+    #[serde(default)]
+    pub merge: bool,
+    /// Which side wins for properties both entities have
+    #[serde(default)]
+    pub prefer: RenamePreference,
+}
+
+/// Outcome of a rename, with the events published for it
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "from": "github/repo/alice/my-repo",
+    "to": "github/repo/12345",
+    "merged": true,
+    "conflicts": ["stars"],
+    "event_ids": [
+        "01936f8e-7c2a-7000-8000-000000000000",
+        "01936f8e-7c2a-7000-8000-000000000001"
+    ]
+}))]
+pub struct RenameResponse {
+    pub from: String,
+    pub to: String,
+    /// True if `to` already existed
+    pub merged: bool,
+    /// Properties both entities had with different values
+    pub conflicts: Vec<String>,
+    /// Full-state event for `to`, then the tombstone for `from`
+    pub event_ids: Vec<String>,
+}
+
+/// OpenAPI description of the rename endpoint
+#[derive(OpenApi)]
+#[openapi(
+    paths(rename_entity),
+    components(schemas(RenameRequest, RenameResponse, RenamePreference, ErrorResponse))
+)]
+pub(crate) struct RenameApi;
+
+/// Create entity rename router
+pub fn create_rename_router(state: Arc<RenameAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/entities/rename", post(rename_entity))
+        .with_state(state)
+}
+
+/// POST /api/admin/entities/rename
+///
+/// Moves an entity to a new ID, or merges it into an existing one. The state
+/// engine applies the change at once; the published events let replay,
+/// other replicas and downstream consumers converge on the same result.
+#[utoipa::path(
+    post,
+    path = "/api/admin/entities/rename",
+    tag = "admin",
+    request_body = RenameRequest,
+    responses(
+        (status = 200, description = "Entity renamed; events published", body = RenameResponse),
+        (status = 400, description = "Same old and new ID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "An ID is outside the token's namespace", body = ErrorResponse),
+        (status = 404, description = "No entity with the old ID", body = ErrorResponse),
+        (status = 409, description = "New ID exists and merge is false", body = ErrorResponse),
+        (status = 422, description = "Merged entity exceeds the property cap", body = ErrorResponse),
+        (status = 500, description = "Applied locally but publishing failed", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []), ("admin_token" = []))
+)]
+async fn rename_entity(
+    State(state): State<Arc<RenameAppState>>,
+    scope: AuthScope,
+    Json(request): Json<RenameRequest>,
+) -> Result<Json<RenameResponse>, RenameApiError> {
+    for id in [&request.from, &request.to] {
+        if !scope.allows_write(id) {
+            return Err(RenameApiError::Forbidden(format!(
+                "Entity '{}' is outside the token's namespace",
+                id
+            )));
+        }
+    }
+
+    let outcome = state.state_engine.rename_entity(
+        &request.from,
+        &request.to,
+        request.merge,
+        request.prefer,
+    )?;
+
+    let mut event_ids = Vec::new();
+    for mut event in rename_events(&request.from, &outcome.entity) {
+        event
+            .validate_and_prepare()
+            .map_err(|e| RenameApiError::PublishError(e.to_string()))?;
+        // One at a time: the tombstone must not land before the new state
+        if let Err(e) = state.event_publisher.publish(&event).await {
+            error!(
+                from = %request.from,
+                to = %request.to,
+                error = %e,
+                "Failed to publish rename event"
+            );
+            return Err(RenameApiError::PublishError(e.to_string()));
+        }
+        event_ids.push(event.event_id.unwrap_or_default());
+    }
+
+    Ok(Json(RenameResponse {
+        from: request.from,
+        to: request.to,
+        merged: outcome.merged,
+        conflicts: outcome.conflicts,
+        event_ids,
+    }))
+}
+
+/// Events that reproduce a rename: the full state of the renamed entity
+/// (forced past the stale-event check), then a tombstone for the old ID
+fn rename_events(from: &str, entity: &Entity) -> [FluxEvent; 2] {
+    let state = FluxEvent {
+        event_id: None,
+        stream: "flux.events.renames".to_string(),
+        source: "api".to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        received_at: None,
+        key: Some(entity.id.clone()),
+        schema: None,
+        payload: serde_json::json!({
+            "entity_id": entity.id,
+            "properties": entity.properties,
+            "force": true,
+            "renamed_from": from
+        }),
+    };
+    [state, FluxEvent::tombstone(from, "api")]
+}
+
+/// Rename API errors
+#[derive(Debug)]
+pub enum RenameApiError {
+    Forbidden(String),
+    Rename(RenameError),
+    PublishError(String),
+}
+
+impl From<RenameError> for RenameApiError {
+    fn from(e: RenameError) -> Self {
+        RenameApiError::Rename(e)
+    }
+}
+
+impl IntoResponse for RenameApiError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            RenameApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            RenameApiError::Rename(RenameError::SameId) => StatusCode::BAD_REQUEST,
+            RenameApiError::Rename(RenameError::NotFound(_)) => StatusCode::NOT_FOUND,
+            RenameApiError::Rename(RenameError::TargetExists(_)) => StatusCode::CONFLICT,
+            RenameApiError::Rename(RenameError::TooManyProperties { .. }) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            RenameApiError::PublishError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match self {
+            RenameApiError::Forbidden(msg) | RenameApiError::PublishError(msg) => msg,
+            RenameApiError::Rename(e) => e.to_string(),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn seeded_engine() -> StateEngine {
+        let engine = StateEngine::new();
+        engine.update_properties(
+            "github/repo/alice/my-repo",
+            [
+                ("stars".to_string(), json!(12)),
+                ("language".to_string(), json!("rust")),
+            ],
+        );
+        engine.update_properties(
+            "github/repo/12345",
+            [
+                ("stars".to_string(), json!(10)),
+                ("open_issues".to_string(), json!(3)),
+            ],
+        );
+        engine
+    }
+
+    #[test]
+    fn test_rename_events_replay_to_the_renamed_state() {
+        let live = seeded_engine();
+        let replica = seeded_engine();
+
+        let outcome = live
+            .rename_entity(
+                "github/repo/alice/my-repo",
+                "github/repo/12345",
+                true,
+                RenamePreference::From,
+            )
+            .unwrap();
+        let events = rename_events("github/repo/alice/my-repo", &outcome.entity);
+
+        // New state first, then the tombstone for the old ID
+        assert_eq!(events[0].stream, "flux.events.renames");
+        assert_eq!(events[0].payload["entity_id"], "github/repo/12345");
+        assert_eq!(events[0].payload["force"], true);
+        assert_eq!(
+            events[0].payload["renamed_from"],
+            "github/repo/alice/my-repo"
+        );
+        assert_eq!(events[1].stream, "flux.events.deletions");
+        assert_eq!(events[1].payload["entity_id"], "github/repo/alice/my-repo");
+        assert_eq!(events[1].payload["properties"]["__deleted__"], true);
+
+        for mut event in events {
+            event.validate_and_prepare().unwrap();
+            replica.process_event(&event, None);
+        }
+        assert!(replica.get_entity("github/repo/alice/my-repo").is_none());
+        assert_eq!(
+            replica.get_entity("github/repo/12345").unwrap().properties,
+            live.get_entity("github/repo/12345").unwrap().properties
+        );
+    }
+
+    #[test]
+    fn test_error_statuses() {
+        let status = |e: RenameApiError| e.into_response().status();
+        assert_eq!(status(RenameError::SameId.into()), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            status(RenameError::NotFound("a/b".to_string()).into()),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(RenameError::TargetExists("a/b".to_string()).into()),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(RenameApiError::Forbidden("no".to_string())),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
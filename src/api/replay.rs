@@ -0,0 +1,158 @@
+use crate::api::admin::validate_admin_token;
+use crate::api::openapi::ErrorResponse;
+use crate::replay::{
+    ReplayError, ReplayJobs, ReplayProgress, ReplayStatus, DEFAULT_REPLAY_TTL_SECONDS,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the sandbox replay API
+pub struct ReplayAppState {
+    pub jobs: Arc<ReplayJobs>,
+    /// Required bearer token. None = unrestricted (dev mode)
+    pub admin_token: Option<String>,
+}
+
+/// Sandbox replay request
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "namespace": "prod",
+    "until": "2026-02-12T14:00:00Z",
+    "target_namespace": "prod-replay-1",
+    "ttl_seconds": 3600
+}))]
+pub struct ReplayRequest {
+    /// Namespace to rebuild
+    pub namespace: String,
+    /// Replay events up to and including this time (RFC 3339)
+    pub until: DateTime<Utc>,
+    /// Namespace the rebuilt entities are created under
+    pub target_namespace: String,
+    /// How long the sandbox entities live after the replay finishes
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+fn default_ttl_seconds() -> u64 {
+    DEFAULT_REPLAY_TTL_SECONDS
+}
+
+/// OpenAPI description of the sandbox replay endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(start_replay, get_replay),
+    components(schemas(ReplayRequest, ReplayProgress, ReplayStatus, ErrorResponse))
+)]
+pub(crate) struct ReplayApi;
+
+/// Create sandbox replay router
+pub fn create_replay_router(state: Arc<ReplayAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/replay", post(start_replay))
+        .route("/api/admin/replay/:job_id", get(get_replay))
+        .with_state(state)
+}
+
+/// POST /api/admin/replay
+///
+/// Rebuilds `namespace` as it was at `until` under `target_namespace`, in the
+/// background. Poll the returned job for progress.
+#[utoipa::path(
+    post,
+    path = "/api/admin/replay",
+    tag = "admin",
+    request_body = ReplayRequest,
+    responses(
+        (status = 202, description = "Replay started", body = ReplayProgress),
+        (status = 400, description = "Invalid or identical namespaces", body = ErrorResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 409, description = "Target namespace already has entities", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn start_replay(
+    State(state): State<Arc<ReplayAppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ReplayRequest>,
+) -> Result<(StatusCode, Json<ReplayProgress>), ReplayApiError> {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return Err(ReplayApiError::Unauthorized);
+    }
+    let job = state.jobs.start(
+        &request.namespace,
+        &request.target_namespace,
+        request.until,
+        Duration::from_secs(request.ttl_seconds),
+    )?;
+    Ok((StatusCode::ACCEPTED, Json(job.progress())))
+}
+
+/// GET /api/admin/replay/:job_id
+#[utoipa::path(
+    get,
+    path = "/api/admin/replay/{job_id}",
+    tag = "admin",
+    params(("job_id" = String, Path, description = "Replay job ID")),
+    responses(
+        (status = 200, description = "Replay progress", body = ReplayProgress),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Unknown job", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn get_replay(
+    State(state): State<Arc<ReplayAppState>>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+) -> Result<Json<ReplayProgress>, ReplayApiError> {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return Err(ReplayApiError::Unauthorized);
+    }
+    state
+        .jobs
+        .get(&job_id)
+        .map(|job| Json(job.progress()))
+        .ok_or(ReplayApiError::NotFound(job_id))
+}
+
+/// Sandbox replay API errors
+#[derive(Debug)]
+pub enum ReplayApiError {
+    Unauthorized,
+    NotFound(String),
+    Replay(ReplayError),
+}
+
+impl From<ReplayError> for ReplayApiError {
+    fn from(e: ReplayError) -> Self {
+        ReplayApiError::Replay(e)
+    }
+}
+
+impl IntoResponse for ReplayApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ReplayApiError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "Admin token required".to_string())
+            }
+            ReplayApiError::NotFound(job_id) => {
+                (StatusCode::NOT_FOUND, format!("No replay job '{}'", job_id))
+            }
+            ReplayApiError::Replay(e @ ReplayError::TargetInUse(_)) => {
+                (StatusCode::CONFLICT, e.to_string())
+            }
+            ReplayApiError::Replay(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
@@ -0,0 +1,112 @@
+//! Request correlation IDs for HTTP access logs and ingestion.
+//!
+//! [`with_request_tracing`] gives every request an `X-Request-Id` (keeping
+//! one sent by the client), logs it in a span around the request and echoes
+//! it in the response. Ingestion forwards the same ID to NATS, so the state
+//! engine's logs and the resulting WebSocket updates can be joined with the
+//! access log.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request},
+    Router,
+};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::{Level, Span};
+
+/// HTTP header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request ID that is passed on
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Wraps `router` with request ID assignment, propagation and access logging
+pub fn with_request_tracing(router: Router) -> Router {
+    router
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+/// Span for one request; logs the path only, since query strings can carry tokens
+fn request_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id,
+    )
+}
+
+/// The request's ID, or a new one if it has none (or an unusable one)
+pub(crate) fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        with_request_tracing(Router::new().route(
+            "/echo",
+            get(|headers: HeaderMap| async move { request_id(&headers) }),
+        ))
+    }
+
+    async fn call(request: Request<Body>) -> (String, String) {
+        let response = app().oneshot(request).await.unwrap();
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_is_kept_and_echoed() {
+        let request = Request::get("/echo")
+            .header("X-Request-Id", "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            call(request).await,
+            ("abc-123".to_string(), "abc-123".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_request_id_is_assigned() {
+        let request = Request::get("/echo").body(Body::empty()).unwrap();
+        let (header, seen) = call(request).await;
+        assert_eq!(header, seen);
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+    }
+
+    #[test]
+    fn test_oversized_request_id_is_replaced() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, "x".repeat(200).parse().unwrap());
+        assert_eq!(request_id(&headers).len(), 36);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::api::admin::validate_admin_token;
+use crate::api::openapi::ErrorResponse;
+use crate::snapshot::recovery::latest_snapshot_path;
+use crate::standby::{InstanceMode, ModeHandle};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tracing::error;
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for promotion and snapshot download
+pub struct StandbyAppState {
+    pub mode: ModeHandle,
+    /// Directory the snapshot manager (or shipper, on a standby) writes to
+    pub snapshot_dir: PathBuf,
+    pub admin_token: Option<String>,
+}
+
+/// Result of a promotion request
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"mode": "primary", "promoted": true}))]
+pub struct PromoteResponse {
+    pub mode: InstanceMode,
+    /// False if the instance was already primary
+    pub promoted: bool,
+}
+
+/// OpenAPI description of the standby endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(promote, download_latest_snapshot),
+    components(schemas(PromoteResponse, InstanceMode))
+)]
+pub(crate) struct StandbyApi;
+
+/// Create promotion and snapshot download router
+///
+/// Served in both modes: a standby must be reachable to be promoted.
+pub fn create_standby_router(state: Arc<StandbyAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/promote", post(promote))
+        .route(
+            "/api/admin/snapshots/latest/download",
+            get(download_latest_snapshot),
+        )
+        .with_state(state)
+}
+
+/// Answer 503 on every route of `router` while `mode` is standby
+pub fn primary_only(router: Router, mode: ModeHandle) -> Router {
+    router.layer(middleware::from_fn_with_state(mode, require_primary))
+}
+
+async fn require_primary(State(mode): State<ModeHandle>, request: Request, next: Next) -> Response {
+    if mode.is_standby() {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Instance is in standby mode; promote it with POST /api/admin/promote",
+        );
+    }
+    next.run(request).await
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/promote - Turn a standby into the primary
+///
+/// Enables the write APIs and leader-only tasks and stops snapshot shipping.
+/// Promoting a primary changes nothing.
+#[utoipa::path(
+    post,
+    path = "/api/admin/promote",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Instance is primary", body = PromoteResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn promote(State(state): State<Arc<StandbyAppState>>, headers: HeaderMap) -> Response {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+
+    let promoted = state.mode.promote();
+    Json(PromoteResponse {
+        mode: state.mode.mode(),
+        promoted,
+    })
+    .into_response()
+}
+
+/// GET /api/admin/snapshots/latest/download - Newest snapshot file
+///
+/// Streams the gzip-compressed snapshot with its filename in
+/// `Content-Disposition` and as the `ETag`; a matching `If-None-Match` gets 304.
+#[utoipa::path(
+    get,
+    path = "/api/admin/snapshots/latest/download",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Snapshot file (application/gzip)", content_type = "application/gzip", body = Vec<u8>),
+        (status = 304, description = "Newest snapshot matches If-None-Match"),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "No snapshot written yet", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn download_latest_snapshot(
+    State(state): State<Arc<StandbyAppState>>,
+    headers: HeaderMap,
+) -> Response {
+    if !validate_admin_token(&headers, &state.admin_token) {
+        return error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+
+    let path = match latest_snapshot_path(&state.snapshot_dir) {
+        Ok(Some(path)) => path,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "No snapshot available"),
+        Err(e) => {
+            error!(error = %e, "Failed to list snapshots");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to list snapshots",
+            );
+        }
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let etag = format!("\"{}\"", name);
+
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == etag);
+    if unchanged {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    // Opened before streaming, so snapshot cleanup can't pull it away mid-download
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!(error = %e, path = %path.display(), "Failed to open snapshot");
+            return error_response(StatusCode::NOT_FOUND, "No snapshot available");
+        }
+    };
+    let length = file.metadata().await.map(|m| m.len()).ok();
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", name),
+            ),
+            (header::ETAG, etag),
+        ],
+        Body::from_stream(file_chunks(file)),
+    )
+        .into_response();
+    if let Some(length) = length {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, length.into());
+    }
+    response
+}
+
+/// Read `file` in 64 KB chunks, ending after the first error
+fn file_chunks(
+    file: tokio::fs::File,
+) -> impl futures::Stream<Item = std::io::Result<Vec<u8>>> + Send {
+    futures::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut chunk = vec![0u8; 64 * 1024];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(n) => {
+                chunk.truncate(n);
+                Some((Ok(chunk), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn app(mode: &ModeHandle, snapshot_dir: PathBuf, admin_token: Option<&str>) -> Router {
+        let writes = Router::new().route("/write", post(|| async { "written" }));
+        let reads = Router::new().route("/read", get(|| async { "read" }));
+        let standby = create_standby_router(Arc::new(StandbyAppState {
+            mode: mode.clone(),
+            snapshot_dir,
+            admin_token: admin_token.map(str::to_string),
+        }));
+        primary_only(writes, mode.clone())
+            .merge(reads)
+            .merge(standby)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_primary_serves_writes() {
+        let dir = TempDir::new().unwrap();
+        let mode = ModeHandle::new(InstanceMode::Primary);
+        let app = app(&mode, dir.path().to_path_buf(), None);
+
+        assert_eq!(
+            send(&app, "POST", "/write", None).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(&app, "GET", "/read", None).await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_standby_gates_writes_until_promoted() {
+        let dir = TempDir::new().unwrap();
+        let mode = ModeHandle::new(InstanceMode::Standby);
+        let app = app(&mode, dir.path().to_path_buf(), Some("admin"));
+
+        let response = send(&app, "POST", "/write", None).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            send(&app, "GET", "/read", None).await.status(),
+            StatusCode::OK
+        );
+
+        // Promotion needs the admin token
+        let response = send(&app, "POST", "/api/admin/promote", None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(mode.is_standby());
+
+        let response = send(&app, "POST", "/api/admin/promote", Some("admin")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let promoted: PromoteResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(promoted.mode, InstanceMode::Primary);
+        assert!(promoted.promoted);
+
+        // Same router, no rebuild: writes are served now
+        assert_eq!(
+            send(&app, "POST", "/write", None).await.status(),
+            StatusCode::OK
+        );
+
+        // Promoting again is a no-op
+        let response = send(&app, "POST", "/api/admin/promote", Some("admin")).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let promoted: PromoteResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!promoted.promoted);
+    }
+
+    #[tokio::test]
+    async fn test_download_latest_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let mode = ModeHandle::new(InstanceMode::Primary);
+        let app = app(&mode, dir.path().to_path_buf(), Some("admin"));
+        let uri = "/api/admin/snapshots/latest/download";
+
+        let response = send(&app, "GET", uri, Some("admin")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let name = "snapshot-20260212T100000.000Z-seq100.json.gz";
+        std::fs::write(
+            dir.path()
+                .join("snapshot-20260211T100000.000Z-seq50.json.gz"),
+            b"old",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join(name), b"gz bytes").unwrap();
+
+        assert_eq!(
+            send(&app, "GET", uri, None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = send(&app, "GET", uri, Some("admin")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers().clone();
+        assert_eq!(headers[header::CONTENT_TYPE], "application/gzip");
+        assert_eq!(headers[header::CONTENT_LENGTH], "8");
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"{}\"", name).as_str()
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"gz bytes");
+
+        let request = Request::get(uri)
+            .header("Authorization", "Bearer admin")
+            .header(header::IF_NONE_MATCH, headers[header::ETAG].clone())
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+}
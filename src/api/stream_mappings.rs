@@ -0,0 +1,417 @@
+use crate::api::admin::validate_admin_token;
+use crate::api::openapi::ErrorResponse;
+use crate::event::is_valid_stream_name;
+use crate::mapping::{CompiledMapping, MappingError, StreamMapping, StreamMappingStore};
+use crate::state::StateEngine;
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use tracing::{error, info};
+use utoipa::{OpenApi, ToSchema};
+
+/// Shared state for the stream mapping API
+pub struct StreamMappingAppState {
+    pub state_engine: Arc<StateEngine>,
+    pub store: Arc<StreamMappingStore>,
+    /// Required bearer token for changes. None = unrestricted (dev mode)
+    pub admin_token: Option<String>,
+}
+
+/// Mapping to install, with a payload to check it against
+#[derive(Deserialize, ToSchema)]
+#[schema(example = json!({
+    "entity_id": "$.device.serial",
+    "properties": "$.readings",
+    "namespace": "acme",
+    "example": {"device": {"serial": "A-100"}, "readings": {"temp": 21.5}}
+}))]
+pub struct PutStreamMappingRequest {
+    #[serde(flatten)]
+    pub mapping: StreamMapping,
+    /// Sample payload the mapping must extract an entity from
+    #[schema(value_type = Object)]
+    pub example: Value,
+}
+
+/// Mapping installed for a stream
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "stream": "vendor.telemetry",
+    "entity_id": "$.device.serial",
+    "properties": "$.readings",
+    "namespace": "acme"
+}))]
+pub struct StreamMappingResponse {
+    pub stream: String,
+    #[serde(flatten)]
+    pub mapping: StreamMapping,
+}
+
+/// What the mapping produced from the example payload
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "stream": "vendor.telemetry",
+    "entity_id": "acme/A-100",
+    "properties": {"temp": 21.5}
+}))]
+pub struct StreamMappingPreview {
+    pub stream: String,
+    pub entity_id: String,
+    #[schema(value_type = Object)]
+    pub properties: Map<String, Value>,
+}
+
+/// OpenAPI description of the stream mapping endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_stream_mappings,
+        get_stream_mapping,
+        put_stream_mapping,
+        delete_stream_mapping
+    ),
+    components(schemas(
+        StreamMapping,
+        PutStreamMappingRequest,
+        StreamMappingResponse,
+        StreamMappingPreview,
+        ErrorResponse
+    ))
+)]
+pub(crate) struct StreamMappingApi;
+
+/// Create stream mapping router
+pub fn create_stream_mapping_router(state: Arc<StreamMappingAppState>) -> Router {
+    Router::new()
+        .route("/api/admin/stream-mappings", get(list_stream_mappings))
+        .route(
+            "/api/admin/stream-mappings/:stream",
+            get(get_stream_mapping)
+                .put(put_stream_mapping)
+                .delete(delete_stream_mapping),
+        )
+        .with_state(state)
+}
+
+/// GET /api/admin/stream-mappings
+#[utoipa::path(
+    get,
+    path = "/api/admin/stream-mappings",
+    tag = "admin",
+    responses(
+        (status = 200, description = "All mappings, ordered by stream", body = Vec<StreamMappingResponse>),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 500, description = "Mapping store error", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn list_stream_mappings(
+    State(state): State<Arc<StreamMappingAppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<StreamMappingResponse>>, StreamMappingError> {
+    authorize(&state, &headers)?;
+    let mappings = state.store.load_all().map_err(StreamMappingError::store)?;
+    Ok(Json(
+        mappings
+            .into_iter()
+            .map(|(stream, mapping)| StreamMappingResponse { stream, mapping })
+            .collect(),
+    ))
+}
+
+/// GET /api/admin/stream-mappings/:stream
+#[utoipa::path(
+    get,
+    path = "/api/admin/stream-mappings/{stream}",
+    tag = "admin",
+    params(("stream" = String, Path, description = "Stream name")),
+    responses(
+        (status = 200, description = "Mapping for the stream", body = StreamMappingResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Stream has no mapping", body = ErrorResponse),
+        (status = 500, description = "Mapping store error", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn get_stream_mapping(
+    State(state): State<Arc<StreamMappingAppState>>,
+    headers: HeaderMap,
+    Path(stream): Path<String>,
+) -> Result<Json<StreamMappingResponse>, StreamMappingError> {
+    authorize(&state, &headers)?;
+    state
+        .store
+        .load_all()
+        .map_err(StreamMappingError::store)?
+        .into_iter()
+        .find(|(s, _)| *s == stream)
+        .map(|(stream, mapping)| Json(StreamMappingResponse { stream, mapping }))
+        .ok_or(StreamMappingError::NotFound(stream))
+}
+
+/// PUT /api/admin/stream-mappings/:stream
+///
+/// Installs or replaces the mapping for a stream. It takes effect for the
+/// next event processed on that stream, including events seen during replay.
+#[utoipa::path(
+    put,
+    path = "/api/admin/stream-mappings/{stream}",
+    tag = "admin",
+    params(("stream" = String, Path, description = "Stream name")),
+    request_body = PutStreamMappingRequest,
+    responses(
+        (status = 200, description = "Mapping installed; preview of the example", body = StreamMappingPreview),
+        (status = 400, description = "Invalid stream name", body = ErrorResponse),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 422, description = "Mapping is invalid or does not fit the example", body = ErrorResponse),
+        (status = 500, description = "Mapping store error", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn put_stream_mapping(
+    State(state): State<Arc<StreamMappingAppState>>,
+    headers: HeaderMap,
+    Path(stream): Path<String>,
+    Json(request): Json<PutStreamMappingRequest>,
+) -> Result<Json<StreamMappingPreview>, StreamMappingError> {
+    authorize(&state, &headers)?;
+    if !is_valid_stream_name(&stream) {
+        return Err(StreamMappingError::InvalidStream(stream));
+    }
+
+    let invalid = |e: MappingError| StreamMappingError::InvalidMapping(e.to_string());
+    let compiled = CompiledMapping::compile(&request.mapping).map_err(invalid)?;
+    let (entity_id, properties) = compiled.extract(&request.example).map_err(invalid)?;
+    let preview = StreamMappingPreview {
+        stream: stream.clone(),
+        entity_id,
+        properties: properties.clone(),
+    };
+
+    state
+        .store
+        .upsert(&stream, &request.mapping)
+        .map_err(StreamMappingError::store)?;
+    state.state_engine.set_stream_mapping(&stream, compiled);
+
+    info!(
+        stream = %stream,
+        entity_id = %request.mapping.entity_id,
+        properties = %request.mapping.properties,
+        "Stream mapping installed"
+    );
+    Ok(Json(preview))
+}
+
+/// DELETE /api/admin/stream-mappings/:stream
+#[utoipa::path(
+    delete,
+    path = "/api/admin/stream-mappings/{stream}",
+    tag = "admin",
+    params(("stream" = String, Path, description = "Stream name")),
+    responses(
+        (status = 204, description = "Mapping removed; the stream uses the default payload shape"),
+        (status = 401, description = "Admin token required", body = ErrorResponse),
+        (status = 404, description = "Stream has no mapping", body = ErrorResponse),
+        (status = 500, description = "Mapping store error", body = ErrorResponse),
+    ),
+    security((), ("admin_token" = []))
+)]
+async fn delete_stream_mapping(
+    State(state): State<Arc<StreamMappingAppState>>,
+    headers: HeaderMap,
+    Path(stream): Path<String>,
+) -> Result<StatusCode, StreamMappingError> {
+    authorize(&state, &headers)?;
+    let deleted = state
+        .store
+        .delete(&stream)
+        .map_err(StreamMappingError::store)?;
+    state.state_engine.remove_stream_mapping(&stream);
+    if !deleted {
+        return Err(StreamMappingError::NotFound(stream));
+    }
+
+    info!(stream = %stream, "Stream mapping removed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn authorize(state: &StreamMappingAppState, headers: &HeaderMap) -> Result<(), StreamMappingError> {
+    if validate_admin_token(headers, &state.admin_token) {
+        Ok(())
+    } else {
+        Err(StreamMappingError::Unauthorized)
+    }
+}
+
+/// Stream mapping API errors
+#[derive(Debug)]
+pub enum StreamMappingError {
+    Unauthorized,
+    InvalidStream(String),
+    InvalidMapping(String),
+    NotFound(String),
+    Store(String),
+}
+
+impl StreamMappingError {
+    fn store(e: anyhow::Error) -> Self {
+        error!(error = %e, "Stream mapping store error");
+        StreamMappingError::Store(e.to_string())
+    }
+}
+
+impl IntoResponse for StreamMappingError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            StreamMappingError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "Admin token required".to_string())
+            }
+            StreamMappingError::InvalidStream(stream) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid stream name '{}'", stream),
+            ),
+            StreamMappingError::InvalidMapping(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            StreamMappingError::NotFound(stream) => (
+                StatusCode::NOT_FOUND,
+                format!("No mapping for stream '{}'", stream),
+            ),
+            StreamMappingError::Store(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::json;
+    use tower::util::ServiceExt;
+
+    fn app(admin_token: Option<&str>) -> (Router, Arc<StateEngine>) {
+        let state_engine = Arc::new(StateEngine::new());
+        let state = Arc::new(StreamMappingAppState {
+            state_engine: Arc::clone(&state_engine),
+            store: Arc::new(StreamMappingStore::new(":memory:").unwrap()),
+            admin_token: admin_token.map(str::to_string),
+        });
+        (create_stream_mapping_router(state), state_engine)
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_put_previews_and_installs_mapping() {
+        let (app, engine) = app(None);
+        let (status, preview) = send(
+            &app,
+            "PUT",
+            "/api/admin/stream-mappings/vendor.telemetry",
+            Some(json!({
+                "entity_id": "$.device.serial",
+                "properties": "$.readings",
+                "namespace": "acme",
+                "example": {"device": {"serial": "A-100"}, "readings": {"temp": 21.5}}
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preview["entity_id"], "acme/A-100");
+        assert_eq!(preview["properties"]["temp"], 21.5);
+
+        let (status, listed) = send(&app, "GET", "/api/admin/stream-mappings", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["stream"], "vendor.telemetry");
+        assert_eq!(listed[0]["namespace"], "acme");
+
+        let (status, _) = send(
+            &app,
+            "DELETE",
+            "/api/admin/stream-mappings/vendor.telemetry",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!engine.remove_stream_mapping("vendor.telemetry"));
+
+        let (status, _) = send(
+            &app,
+            "GET",
+            "/api/admin/stream-mappings/vendor.telemetry",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_put_rejects_bad_input() {
+        let (app, _) = app(None);
+        let body = |entity_id: &str| {
+            json!({
+                "entity_id": entity_id,
+                "properties": "$.readings",
+                "example": {"serial": "A-100", "readings": {}}
+            })
+        };
+
+        let (status, _) = send(
+            &app,
+            "PUT",
+            "/api/admin/stream-mappings/Bad..Stream",
+            Some(body("$.serial")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Parses, but the example has no value there
+        let (status, error) = send(
+            &app,
+            "PUT",
+            "/api/admin/stream-mappings/vendor.telemetry",
+            Some(body("$.device.serial")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"], "No entity ID at '$.device.serial'");
+
+        let (status, listed) = send(&app, "GET", "/api/admin/stream-mappings", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_required() {
+        let (app, _) = app(Some("secret"));
+        let (status, _) = send(&app, "GET", "/api/admin/stream-mappings", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::api::auth_middleware::{AuthScope, ReadAuthState};
+use crate::api::openapi::ErrorResponse;
+use crate::namespace::NamespaceRegistry;
+use crate::watch::{NewWatch, Predicate, Watch, WatchError, WatchManager, WatchState};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{IntoParams, OpenApi};
+
+/// Longest a `GET /api/watches/:id` is held open
+const MAX_HOLD_SECONDS: u64 = 30;
+
+/// Shared state for the watch API
+pub struct WatchAppState {
+    pub watch_manager: Arc<WatchManager>,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    pub auth_enabled: bool,
+    /// Token that may watch every entity
+    pub admin_token: Option<String>,
+}
+
+impl ReadAuthState for WatchAppState {
+    fn auth_enabled(&self) -> bool {
+        self.auth_enabled
+    }
+    fn namespace_registry(&self) -> &NamespaceRegistry {
+        &self.namespace_registry
+    }
+    fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
+}
+
+/// Query parameters for polling a watch
+#[derive(Deserialize, IntoParams)]
+pub struct WatchPollParams {
+    /// Seconds to wait for the watch to fire or expire (default and max 30;
+    /// 0 returns at once)
+    pub wait_seconds: Option<u64>,
+    /// `poll_token` from an earlier response: wait for a state newer than it
+    pub poll_token: Option<String>,
+}
+
+/// OpenAPI description of the watch endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(create_watch, poll_watch),
+    components(schemas(NewWatch, Predicate, Watch, WatchState, ErrorResponse))
+)]
+pub(crate) struct WatchApi;
+
+/// Create watch API router
+pub fn create_watch_router(state: Arc<WatchAppState>) -> Router {
+    Router::new()
+        .route("/api/watches", post(create_watch))
+        .route("/api/watches/:id", get(poll_watch))
+        .with_state(state)
+}
+
+/// POST /api/watches - Watch one property of an entity
+///
+/// The watch fires the first time the property crosses the predicate after
+/// it is created, then is dropped 5 minutes later. Unfired watches expire.
+#[utoipa::path(
+    post,
+    path = "/api/watches",
+    tag = "watches",
+    request_body = NewWatch,
+    responses(
+        (status = 201, description = "Watch created", body = Watch),
+        (status = 400, description = "Missing entity or property, or invalid webhook URL", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 403, description = "Entity outside the token's namespace", body = ErrorResponse),
+        (status = 429, description = "Too many watches", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn create_watch(
+    State(state): State<Arc<WatchAppState>>,
+    scope: AuthScope,
+    Json(request): Json<NewWatch>,
+) -> Result<(StatusCode, Json<Watch>), WatchApiError> {
+    if !scope.allows(&request.entity_id) {
+        return Err(WatchApiError::Forbidden(format!(
+            "Entity '{}' is outside the token's namespace",
+            request.entity_id
+        )));
+    }
+    let watch = state.watch_manager.create(request, Utc::now()).await?;
+    Ok((StatusCode::CREATED, Json(watch)))
+}
+
+/// GET /api/watches/:id - Long-poll a watch
+///
+/// Returns once the watch fires or expires, or after `wait_seconds` with it
+/// still pending. Poll again with the returned `poll_token` to resume.
+#[utoipa::path(
+    get,
+    path = "/api/watches/{id}",
+    tag = "watches",
+    params(("id" = String, Path, description = "Watch ID"), WatchPollParams),
+    responses(
+        (status = 200, description = "Watch and its state", body = Watch),
+        (status = 401, description = "Missing or invalid token (auth mode)", body = ErrorResponse),
+        (status = 404, description = "Unknown, dropped or out-of-namespace watch", body = ErrorResponse),
+    ),
+    security((), ("bearer_token" = []))
+)]
+async fn poll_watch(
+    State(state): State<Arc<WatchAppState>>,
+    scope: AuthScope,
+    Path(id): Path<String>,
+    Query(params): Query<WatchPollParams>,
+) -> Result<Json<Watch>, WatchApiError> {
+    let not_found = || WatchApiError::NotFound(format!("Watch '{}' not found", id));
+    // Checked before waiting, so other namespaces can't hold requests open
+    let watch = state.watch_manager.get(&id).ok_or_else(not_found)?;
+    if !scope.allows(&watch.entity_id) {
+        return Err(not_found());
+    }
+
+    let hold = params
+        .wait_seconds
+        .unwrap_or(MAX_HOLD_SECONDS)
+        .min(MAX_HOLD_SECONDS);
+    state
+        .watch_manager
+        .wait(&id, params.poll_token.as_deref(), Duration::from_secs(hold))
+        .await
+        .map(Json)
+        .ok_or_else(not_found)
+}
+
+/// Watch API errors
+#[derive(Debug)]
+pub enum WatchApiError {
+    BadRequest(String),
+    Forbidden(String),
+    NotFound(String),
+    TooManyWatches,
+}
+
+impl From<WatchError> for WatchApiError {
+    fn from(e: WatchError) -> Self {
+        match e {
+            WatchError::Invalid(reason) => WatchApiError::BadRequest(reason),
+            WatchError::TooManyWatches => WatchApiError::TooManyWatches,
+        }
+    }
+}
+
+impl IntoResponse for WatchApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            WatchApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            WatchApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            WatchApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            WatchApiError::TooManyWatches => (
+                StatusCode::TOO_MANY_REQUESTS,
+                WatchError::TooManyWatches.to_string(),
+            ),
+        };
+        (status, Json(ErrorResponse { error: message })).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StateEngine;
+    use crate::watch::run_watches;
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    struct Harness {
+        app: Router,
+        engine: Arc<StateEngine>,
+        token: String,
+    }
+
+    fn harness(auth_enabled: bool) -> Harness {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let token = registry.register("alice").unwrap().token;
+        let engine = Arc::new(StateEngine::new());
+        engine.set_live();
+        let manager = Arc::new(WatchManager::new());
+        tokio::spawn(run_watches(Arc::clone(&manager), Arc::clone(&engine)));
+        let state = WatchAppState {
+            watch_manager: manager,
+            namespace_registry: registry,
+            auth_enabled,
+            admin_token: None,
+        };
+        Harness {
+            app: create_watch_router(Arc::new(state)),
+            engine,
+            token,
+        }
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn post_watch(body: Value, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/api/watches").header("Content-Type", "application/json");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    fn get_watch(path: &str, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::get(path);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_long_poll_returns_when_update_fires_watch() {
+        let h = harness(false);
+        h.engine
+            .update_property("deploy/backend", "status", json!("running"));
+
+        let (status, created) = send(
+            &h.app,
+            post_watch(
+                json!({
+                    "entity_id": "deploy/backend",
+                    "property": "status",
+                    "predicate": {"op": "equals", "value": "done"}
+                }),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["state"], "pending");
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let poll = tokio::spawn({
+            let app = h.app.clone();
+            let path = format!("/api/watches/{}", id);
+            async move { send(&app, get_watch(&path, None)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!poll.is_finished());
+
+        h.engine
+            .update_property("deploy/backend", "progress", json!(100));
+        h.engine
+            .update_property("deploy/backend", "status", json!("done"));
+        let (status, fired) = tokio::time::timeout(Duration::from_secs(5), poll)
+            .await
+            .expect("long poll returns once the watch fires")
+            .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fired["state"], "fired");
+        assert_eq!(fired["value"], "done");
+        assert_eq!(fired["previous_value"], "running");
+
+        // Still readable after firing; the fired token holds the poll open
+        let path = format!(
+            "/api/watches/{}?wait_seconds=0&poll_token={}",
+            id,
+            fired["poll_token"].as_str().unwrap()
+        );
+        let (status, again) = send(&h.app, get_watch(&path, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(again, fired);
+    }
+
+    #[tokio::test]
+    async fn test_pending_watch_returns_after_wait() {
+        let h = harness(false);
+        let (_, created) = send(
+            &h.app,
+            post_watch(
+                json!({
+                    "entity_id": "deploy/backend",
+                    "property": "cpu",
+                    "predicate": {"op": "gt", "value": 90},
+                    "expires_in_seconds": 60
+                }),
+                None,
+            ),
+        )
+        .await;
+        let path = format!(
+            "/api/watches/{}?wait_seconds=0",
+            created["id"].as_str().unwrap()
+        );
+        let (status, polled) = send(&h.app, get_watch(&path, None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(polled, created);
+
+        let (status, _) = send(&h.app, get_watch("/api/watches/nope", None)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_watches_are_namespace_scoped() {
+        let h = harness(true);
+        let body = |entity_id: &str| json!({"entity_id": entity_id, "property": "status", "predicate": {"op": "changed"}});
+
+        let (status, _) = send(&h.app, post_watch(body("alice/job"), None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&h.app, post_watch(body("bob/job"), Some(&h.token))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, created) = send(&h.app, post_watch(body("alice/job"), Some(&h.token))).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let path = format!(
+            "/api/watches/{}?wait_seconds=0",
+            created["id"].as_str().unwrap()
+        );
+        let (status, _) = send(&h.app, get_watch(&path, Some(&h.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&h.app, get_watch(&path, None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_watches_rejected() {
+        let h = harness(false);
+        let (status, body) = send(
+            &h.app,
+            post_watch(
+                json!({
+                    "entity_id": "deploy/backend",
+                    "property": "status",
+                    "predicate": {"op": "changed"},
+                    "webhook_url": "file:///etc/passwd"
+                }),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("webhook URL"));
+    }
+
+    #[tokio::test]
+    async fn test_internal_webhook_targets_rejected() {
+        let h = harness(false);
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://localhost:8080/admin",
+            "http://192.168.1.10/hook",
+        ] {
+            let (status, body) = send(
+                &h.app,
+                post_watch(
+                    json!({
+                        "entity_id": "deploy/backend",
+                        "property": "status",
+                        "predicate": {"op": "changed"},
+                        "webhook_url": url
+                    }),
+                    None,
+                ),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", url);
+            assert!(body["error"].as_str().unwrap().contains("internal address"));
+        }
+    }
+}
@@ -1,30 +1,51 @@
+use crate::config::SharedRuntimeConfig;
+use crate::namespace::NamespaceRegistry;
 use crate::state::StateEngine;
-use crate::subscription::ConnectionManager;
+use crate::subscription::manager::WsAuth;
+use crate::subscription::{ConnectionManager, ConnectionTracker};
 use axum::{
     extract::{
         ws::{WebSocket, WebSocketUpgrade},
-        State,
+        ConnectInfo, State,
     },
     response::Response,
     routing::get,
     Router,
 };
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Shared application state for WebSocket handler
 #[derive(Clone)]
 pub struct WsAppState {
     pub state_engine: Arc<StateEngine>,
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    /// When true, the first client message must carry a token and
+    /// subscriptions are limited to that token's namespace
+    pub auth_enabled: bool,
+    /// Token allowed to subscribe across all namespaces
+    pub admin_token: Option<String>,
+    /// Cap on property values in outbound messages (0 = no limit)
+    pub max_value_bytes: usize,
+    /// Connection, message rate and subscription limits
+    pub runtime_config: SharedRuntimeConfig,
+    /// Open connections, checked against the connection caps
+    pub connections: Arc<ConnectionTracker>,
 }
 
 /// GET /api/ws - WebSocket upgrade handler
+///
+/// The client IP comes from the peer address when the server was started
+/// with connect info; without it only the global connection cap applies.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<WsAppState>>,
+    peer: Option<ConnectInfo<SocketAddr>>,
 ) -> Response {
     info!("WebSocket upgrade request received");
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    let ip = peer.map(|ConnectInfo(addr)| addr.ip());
+    ws.on_upgrade(move |socket| handle_socket(socket, state, ip))
 }
 
 /// Create WebSocket router
@@ -35,7 +56,28 @@ pub fn create_ws_router(state: Arc<WsAppState>) -> Router {
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: Arc<WsAppState>) {
+async fn handle_socket(mut socket: WebSocket, state: Arc<WsAppState>, ip: Option<IpAddr>) {
+    let (max_connections, max_per_ip) = {
+        let config = state
+            .runtime_config
+            .read()
+            .expect("RuntimeConfig lock poisoned");
+        (config.ws_max_connections, config.ws_max_connections_per_ip)
+    };
+    let acquired = state
+        .connections
+        .try_acquire(ip, max_connections, max_per_ip);
+    // Held until the connection ends
+    let _slot = match acquired {
+        Ok(slot) => slot,
+        Err(rejected) => {
+            warn!(ip = ?ip, reason = %rejected, "WebSocket connection refused");
+            state.state_engine.metrics.record_ws_limit_close();
+            ConnectionManager::close_policy_violation(&mut socket, &rejected.to_string()).await;
+            return;
+        }
+    };
+
     // Subscribe to state updates
     let state_rx = state.state_engine.subscribe();
 
@@ -45,8 +87,20 @@ async fn handle_socket(socket: WebSocket, state: Arc<WsAppState>) {
     // Subscribe to deletion events
     let deletion_rx = state.state_engine.subscribe_deletions();
 
+    // Subscribe to agent messages
+    let message_rx = state.state_engine.subscribe_messages();
+
     // Create connection manager
-    let manager = ConnectionManager::new();
+    let manager = if state.auth_enabled {
+        ConnectionManager::with_auth(WsAuth {
+            namespace_registry: Arc::clone(&state.namespace_registry),
+            admin_token: state.admin_token.clone(),
+        })
+    } else {
+        ConnectionManager::new()
+    }
+    .with_max_value_bytes(state.max_value_bytes)
+    .with_runtime_config(Arc::clone(&state.runtime_config));
 
     // Handle connection lifecycle
     manager
@@ -55,6 +109,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<WsAppState>) {
             state_rx,
             metrics_rx,
             deletion_rx,
+            message_rx,
             Arc::clone(&state.state_engine),
         )
         .await;
@@ -0,0 +1,642 @@
+use super::config::ArchiveConfig;
+use super::manifest::{Manifest, MANIFEST_KEY};
+use super::segment::{self, StoredEvent};
+use super::store::ArchiveStore;
+use crate::nats::is_ingest_subject;
+use crate::snapshot::recovery;
+use crate::snapshot::SnapshotStore;
+use anyhow::{bail, Context, Result};
+use async_nats::jetstream;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Sequence bounds of the event stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamBounds {
+    /// First sequence still stored (last + 1 when the stream is empty)
+    pub first_sequence: u64,
+    /// Last sequence ever stored
+    pub last_sequence: u64,
+}
+
+/// The stream being archived. Implemented for JetStream; tests substitute an
+/// in-memory stream.
+pub trait ArchiveSource: Send + Sync + 'static {
+    fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>>;
+
+    /// Up to `max` events in sequence order, starting at `start`
+    fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>>;
+
+    /// Remove every event with a sequence below `sequence`
+    fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>>;
+}
+
+/// JetStream stream as an [`ArchiveSource`]
+pub struct JetStreamSource {
+    jetstream: jetstream::Context,
+    stream_name: String,
+}
+
+impl JetStreamSource {
+    pub fn new(jetstream: jetstream::Context, stream_name: impl Into<String>) -> Self {
+        Self {
+            jetstream,
+            stream_name: stream_name.into(),
+        }
+    }
+
+    pub(crate) fn jetstream(&self) -> &jetstream::Context {
+        &self.jetstream
+    }
+
+    async fn stream(&self) -> Result<jetstream::stream::Stream> {
+        self.jetstream
+            .get_stream(&self.stream_name)
+            .await
+            .context(format!("Failed to get stream '{}'", self.stream_name))
+    }
+}
+
+impl ArchiveSource for JetStreamSource {
+    fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+        Box::pin(async move {
+            let mut stream = self.stream().await?;
+            let state = &stream
+                .info()
+                .await
+                .context("Failed to get stream info")?
+                .state;
+            Ok(StreamBounds {
+                first_sequence: state.first_sequence,
+                last_sequence: state.last_sequence,
+            })
+        })
+    }
+
+    fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+        Box::pin(async move {
+            let stream = self.stream().await?;
+            // Ephemeral ordered consumer, removed by the server once we're done
+            let consumer = stream
+                .create_consumer(jetstream::consumer::pull::OrderedConfig {
+                    deliver_policy: jetstream::consumer::DeliverPolicy::ByStartSequence {
+                        start_sequence: start,
+                    },
+                    ..Default::default()
+                })
+                .await
+                .context("Failed to create archive consumer")?;
+            let mut messages = consumer
+                .messages()
+                .await
+                .context("Failed to read events for archiving")?;
+
+            let mut events = Vec::new();
+            while events.len() < max {
+                let message =
+                    match tokio::time::timeout(std::time::Duration::from_secs(1), messages.next())
+                        .await
+                    {
+                        Ok(Some(message)) => message.context("Failed to receive event")?,
+                        // Caught up with the stream, or idle
+                        Ok(None) | Err(_) => break,
+                    };
+                let info = message
+                    .info()
+                    .map_err(|e| anyhow::anyhow!("Invalid message metadata: {}", e))?;
+                let published = DateTime::from_timestamp(
+                    info.published.unix_timestamp(),
+                    info.published.nanosecond(),
+                )
+                .context("Invalid publish time")?;
+                // Not events yet (or ever); purged with the range around them
+                if is_ingest_subject(message.subject.as_str()) {
+                    continue;
+                }
+                events.push(StoredEvent {
+                    sequence: info.stream_sequence,
+                    published,
+                    payload: message.payload.to_vec(),
+                });
+            }
+            Ok(events)
+        })
+    }
+
+    fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let stream = self.stream().await?;
+            let response = stream
+                .purge()
+                .sequence(sequence)
+                .await
+                .context("Failed to purge stream")?;
+            if !response.success {
+                bail!(
+                    "Stream purge up to sequence {} was not successful",
+                    sequence
+                );
+            }
+            info!(
+                sequence,
+                purged = response.purged,
+                "Purged archived events from stream"
+            );
+            Ok(())
+        })
+    }
+}
+
+/// Result of one archive run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveRun {
+    /// Segments written
+    pub segments: usize,
+    /// Events archived
+    pub events: usize,
+    /// Stream purged up to (exclusive), if it was purged
+    pub purged_before: Option<u64>,
+}
+
+/// Moves old events from the stream into the archive store, then prunes them
+///
+/// Order of operations per segment: write segment → save manifest → purge.
+/// The stream is only ever purged up to the high-water mark of a manifest
+/// that has been saved, so a failure at any step leaves unarchived events in
+/// the stream (at worst a segment is written twice).
+pub struct Archiver {
+    source: Arc<dyn ArchiveSource>,
+    store: Arc<dyn ArchiveStore>,
+    config: ArchiveConfig,
+    /// Snapshots used for recovery; events they don't cover are never archived
+    snapshots: Arc<dyn SnapshotStore>,
+}
+
+impl Archiver {
+    pub fn new(
+        source: Arc<dyn ArchiveSource>,
+        store: Arc<dyn ArchiveStore>,
+        config: ArchiveConfig,
+        snapshots: Arc<dyn SnapshotStore>,
+    ) -> Self {
+        Self {
+            source,
+            store,
+            config,
+            snapshots,
+        }
+    }
+
+    /// Run archive passes every `interval_minutes` until the task is cancelled
+    pub async fn run_archive_loop(&self) -> Result<()> {
+        if !self.config.enabled {
+            info!("Archiver disabled, exiting loop");
+            return Ok(());
+        }
+
+        info!(
+            interval_minutes = self.config.interval_minutes,
+            min_age_hours = self.config.min_age_hours,
+            "Starting event archiver"
+        );
+
+        let period = std::time::Duration::from_secs(self.config.interval_minutes.max(1) * 60);
+        let mut timer = tokio::time::interval(period);
+        loop {
+            timer.tick().await;
+            match self.run_once(Utc::now()).await {
+                Ok(run) if run.events > 0 => info!(
+                    segments = run.segments,
+                    events = run.events,
+                    purged_before = ?run.purged_before,
+                    "Archive run complete"
+                ),
+                Ok(_) => {}
+                Err(e) => error!(error = %e, "Archive run failed"),
+            }
+        }
+    }
+
+    /// Archive every event older than `min_age_hours` (relative to `now`) that
+    /// the oldest retained snapshot covers, then purge it from the stream
+    pub async fn run_once(&self, now: DateTime<Utc>) -> Result<ArchiveRun> {
+        let mut manifest = self.load_manifest().await?;
+        let mut run = ArchiveRun::default();
+
+        // Never archive past what every retained snapshot covers: recovery
+        // from any of them must still find the events that follow it.
+        let Some(floor) = self
+            .snapshots
+            .list()
+            .await?
+            .iter()
+            .filter_map(|name| recovery::sequence_from_name(name))
+            .min()
+        else {
+            warn!("No snapshots yet, skipping archive run");
+            return Ok(run);
+        };
+        let cutoff = now - Duration::hours(self.config.min_age_hours as i64);
+
+        let bounds = self.source.bounds().await?;
+        // Finish a purge interrupted after the manifest was saved
+        self.purge(&manifest, bounds.first_sequence, &mut run)
+            .await?;
+
+        loop {
+            let start = manifest.next_sequence(bounds.first_sequence);
+            let last_eligible = floor.min(bounds.last_sequence);
+            if start > last_eligible {
+                break;
+            }
+            if start > manifest.high_water_sequence + 1 && manifest.high_water_sequence > 0 {
+                warn!(
+                    from = manifest.high_water_sequence + 1,
+                    to = start - 1,
+                    "Events left the stream before they were archived"
+                );
+            }
+
+            let max = self.config.max_segment_events.max(1);
+            let events: Vec<StoredEvent> = self
+                .source
+                .read(start, max)
+                .await?
+                .into_iter()
+                .take_while(|event| event.sequence <= last_eligible && event.published < cutoff)
+                .collect();
+            if events.is_empty() {
+                break;
+            }
+            let full = events.len() == max;
+
+            let (info, data) = segment::encode(&events)?;
+            self.store
+                .put(&info.key, data)
+                .await
+                .context(format!("Failed to write segment {}", info.key))?;
+
+            let mut updated = manifest.clone();
+            updated.record(info)?;
+            self.store
+                .put(MANIFEST_KEY, updated.to_bytes())
+                .await
+                .context("Failed to save archive manifest")?;
+            manifest = updated;
+
+            run.segments += 1;
+            run.events += events.len();
+            self.purge(&manifest, bounds.first_sequence, &mut run)
+                .await?;
+
+            if !full {
+                break;
+            }
+        }
+
+        Ok(run)
+    }
+
+    async fn load_manifest(&self) -> Result<Manifest> {
+        match self.store.get(MANIFEST_KEY).await? {
+            Some(bytes) => Manifest::from_bytes(&bytes).context("Invalid archive manifest"),
+            None => Ok(Manifest::new()),
+        }
+    }
+
+    /// Purge what `manifest` (already saved) says is archived
+    async fn purge(
+        &self,
+        manifest: &Manifest,
+        stream_first: u64,
+        run: &mut ArchiveRun,
+    ) -> Result<()> {
+        if let Some(before) = manifest.purge_before(stream_first) {
+            if run.purged_before.is_none_or(|purged| before > purged) {
+                self.source.purge_before(before).await?;
+                run.purged_before = Some(before);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::LocalSnapshotStore;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    /// Stream of `count` events, one per hour starting at `start`
+    struct MockStream {
+        events: Mutex<Vec<StoredEvent>>,
+        last_sequence: u64,
+        purges: Mutex<Vec<u64>>,
+    }
+
+    impl MockStream {
+        fn new(count: u64, start: DateTime<Utc>) -> Arc<Self> {
+            let events = (1..=count)
+                .map(|seq| StoredEvent {
+                    sequence: seq,
+                    published: start + Duration::hours(seq as i64),
+                    payload: format!(r#"{{"seq":{}}}"#, seq).into_bytes(),
+                })
+                .collect();
+            Arc::new(Self {
+                events: Mutex::new(events),
+                last_sequence: count,
+                purges: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn first_sequence(&self) -> u64 {
+            let events = self.events.lock().unwrap();
+            events
+                .first()
+                .map_or(self.last_sequence + 1, |e| e.sequence)
+        }
+    }
+
+    impl ArchiveSource for MockStream {
+        fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+            Box::pin(async move {
+                Ok(StreamBounds {
+                    first_sequence: self.first_sequence(),
+                    last_sequence: self.last_sequence,
+                })
+            })
+        }
+
+        fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+            Box::pin(async move {
+                let events = self.events.lock().unwrap();
+                Ok(events
+                    .iter()
+                    .filter(|e| e.sequence >= start)
+                    .take(max)
+                    .cloned()
+                    .collect())
+            })
+        }
+
+        fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .retain(|e| e.sequence >= sequence);
+                self.purges.lock().unwrap().push(sequence);
+                Ok(())
+            })
+        }
+    }
+
+    /// In-memory store that can be told to fail writes to one key
+    #[derive(Default)]
+    struct MockStore {
+        objects: Mutex<std::collections::HashMap<String, Vec<u8>>>,
+        fail_puts_to: Mutex<Option<String>>,
+    }
+
+    impl MockStore {
+        fn fail_puts_to(&self, key: Option<&str>) {
+            *self.fail_puts_to.lock().unwrap() = key.map(String::from);
+        }
+
+        fn manifest(&self) -> Option<Manifest> {
+            let objects = self.objects.lock().unwrap();
+            objects
+                .get(MANIFEST_KEY)
+                .map(|bytes| Manifest::from_bytes(bytes).unwrap())
+        }
+    }
+
+    impl ArchiveStore for MockStore {
+        fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                let fail = self.fail_puts_to.lock().unwrap().clone();
+                if fail.is_some_and(|fail| key.starts_with(&fail)) {
+                    bail!("disk full");
+                }
+                self.objects.lock().unwrap().insert(key.to_string(), data);
+                Ok(())
+            })
+        }
+
+        fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+            Box::pin(async move { Ok(self.objects.lock().unwrap().get(key).cloned()) })
+        }
+    }
+
+    struct Fixture {
+        stream: Arc<MockStream>,
+        store: Arc<MockStore>,
+        archiver: Archiver,
+        start: DateTime<Utc>,
+        _snapshots: tempfile::TempDir,
+    }
+
+    /// 100 hourly events; snapshot at `snapshot_seq`; segments of 30 events;
+    /// events older than 24h are archivable
+    fn fixture(snapshot_seq: Option<u64>) -> Fixture {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let stream = MockStream::new(100, start);
+        let store = Arc::new(MockStore::default());
+        let snapshots = tempfile::tempdir().unwrap();
+        if let Some(seq) = snapshot_seq {
+            let name = format!("snapshot-20260105T000000.000Z-seq{}.json.gz", seq);
+            std::fs::write(snapshots.path().join(name), b"").unwrap();
+        }
+        let config = ArchiveConfig {
+            enabled: true,
+            min_age_hours: 24,
+            max_segment_events: 30,
+            ..ArchiveConfig::default()
+        };
+        let archiver = Archiver::new(
+            Arc::clone(&stream) as Arc<dyn ArchiveSource>,
+            Arc::clone(&store) as Arc<dyn ArchiveStore>,
+            config,
+            Arc::new(LocalSnapshotStore::new(snapshots.path())),
+        );
+        Fixture {
+            stream,
+            store,
+            archiver,
+            start,
+            _snapshots: snapshots,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archives_old_events_and_purges() {
+        let f = fixture(Some(1_000));
+        // Events 1..=75 are more than 24h old at hour 100
+        let now = f.start + Duration::hours(100);
+
+        let run = f.archiver.run_once(now).await.unwrap();
+        assert_eq!(run.events, 75);
+        assert_eq!(run.segments, 3);
+        assert_eq!(run.purged_before, Some(76));
+
+        let manifest = f.store.manifest().unwrap();
+        assert_eq!(manifest.high_water_sequence, 75);
+        let ranges: Vec<_> = manifest
+            .segments
+            .iter()
+            .map(|s| (s.first_sequence, s.last_sequence))
+            .collect();
+        assert_eq!(ranges, vec![(1, 30), (31, 60), (61, 75)]);
+        assert_eq!(f.stream.first_sequence(), 76);
+
+        // Every archived event is readable from its segment
+        let objects = f.store.objects.lock().unwrap();
+        let archived: usize = manifest
+            .segments
+            .iter()
+            .map(|s| segment::decode(&objects[&s.key]).unwrap().len())
+            .sum();
+        assert_eq!(archived, 75);
+    }
+
+    #[tokio::test]
+    async fn test_second_run_continues_from_high_water() {
+        let f = fixture(Some(1_000));
+        f.archiver
+            .run_once(f.start + Duration::hours(50))
+            .await
+            .unwrap();
+        assert_eq!(f.store.manifest().unwrap().high_water_sequence, 25);
+
+        let run = f
+            .archiver
+            .run_once(f.start + Duration::hours(60))
+            .await
+            .unwrap();
+        assert_eq!(run.events, 10);
+        assert_eq!(f.store.manifest().unwrap().high_water_sequence, 35);
+        assert_eq!(*f.stream.purges.lock().unwrap(), vec![26, 36]);
+    }
+
+    #[tokio::test]
+    async fn test_never_archives_past_oldest_snapshot() {
+        let f = fixture(Some(40));
+        let run = f
+            .archiver
+            .run_once(f.start + Duration::hours(500))
+            .await
+            .unwrap();
+        assert_eq!(run.events, 40);
+        assert_eq!(f.stream.first_sequence(), 41);
+
+        // Without snapshots nothing is archived
+        let f = fixture(None);
+        let run = f
+            .archiver
+            .run_once(f.start + Duration::hours(500))
+            .await
+            .unwrap();
+        assert_eq!(run, ArchiveRun::default());
+        assert!(f.store.manifest().is_none());
+        assert!(f.stream.purges.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_segment_write_failure_purges_nothing() {
+        let f = fixture(Some(1_000));
+        f.store.fail_puts_to(Some("segments/"));
+
+        assert!(f
+            .archiver
+            .run_once(f.start + Duration::hours(100))
+            .await
+            .is_err());
+        assert!(f.store.manifest().is_none());
+        assert!(f.stream.purges.lock().unwrap().is_empty());
+        assert_eq!(f.stream.first_sequence(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_write_failure_purges_nothing() {
+        let f = fixture(Some(1_000));
+        f.store.fail_puts_to(Some(MANIFEST_KEY));
+
+        assert!(f
+            .archiver
+            .run_once(f.start + Duration::hours(100))
+            .await
+            .is_err());
+        // The segment was written, but without a manifest it doesn't count
+        assert!(f.stream.purges.lock().unwrap().is_empty());
+        assert_eq!(f.stream.first_sequence(), 1);
+
+        // Once the store recovers the same events are archived again
+        f.store.fail_puts_to(None);
+        let run = f
+            .archiver
+            .run_once(f.start + Duration::hours(100))
+            .await
+            .unwrap();
+        assert_eq!(run.events, 75);
+        assert_eq!(f.store.manifest().unwrap().segments[0].first_sequence, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failure_mid_run_keeps_earlier_segments() {
+        let f = fixture(Some(1_000));
+        // Second segment fails to write
+        f.store.fail_puts_to(Some(&segment_key(31, 60)));
+
+        assert!(f
+            .archiver
+            .run_once(f.start + Duration::hours(100))
+            .await
+            .is_err());
+        assert_eq!(f.store.manifest().unwrap().high_water_sequence, 30);
+        assert_eq!(*f.stream.purges.lock().unwrap(), vec![31]);
+        assert_eq!(f.stream.first_sequence(), 31);
+    }
+
+    #[tokio::test]
+    async fn test_resumes_interrupted_purge() {
+        let f = fixture(Some(1_000));
+        // A previous run saved the manifest but died before purging
+        let mut manifest = Manifest::new();
+        let events = f.stream.read(1, 10).await.unwrap();
+        let (info, data) = segment::encode(&events).unwrap();
+        f.store.put(&info.key, data).await.unwrap();
+        manifest.record(info).unwrap();
+        f.store
+            .put(MANIFEST_KEY, manifest.to_bytes())
+            .await
+            .unwrap();
+
+        // Nothing is old enough to archive now, but the purge completes
+        let run = f.archiver.run_once(f.start).await.unwrap();
+        assert_eq!(run.events, 0);
+        assert_eq!(run.purged_before, Some(11));
+        assert_eq!(f.stream.first_sequence(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_manifest_aborts_without_purge() {
+        let f = fixture(Some(1_000));
+        f.store.put(MANIFEST_KEY, b"{oops".to_vec()).await.unwrap();
+
+        assert!(f
+            .archiver
+            .run_once(f.start + Duration::hours(100))
+            .await
+            .is_err());
+        assert!(f.stream.purges.lock().unwrap().is_empty());
+    }
+
+    fn segment_key(first: u64, last: u64) -> String {
+        super::super::manifest::SegmentInfo::key_for(first, last)
+    }
+}
@@ -0,0 +1,40 @@
+pub use crate::s3::S3Config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Configuration for the event archiver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Enable archiving (and pruning) of old events
+    pub enabled: bool,
+
+    /// Events stored in the stream for longer than this are archived (hours)
+    pub min_age_hours: u64,
+
+    /// Interval between archive runs (minutes)
+    pub interval_minutes: u64,
+
+    /// Max events written to a single segment
+    pub max_segment_events: usize,
+
+    /// Local directory for segments and the manifest (used unless `s3` is set)
+    pub directory: PathBuf,
+
+    /// S3-compatible object store; credentials come from
+    /// FLUX_ARCHIVE_S3_ACCESS_KEY_ID / FLUX_ARCHIVE_S3_SECRET_ACCESS_KEY
+    pub s3: Option<S3Config>,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_age_hours: 168,
+            interval_minutes: 60,
+            max_segment_events: 10_000,
+            directory: PathBuf::from("/var/lib/flux/archive"),
+            s3: None,
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Object key of the manifest in the archive store
+pub const MANIFEST_KEY: &str = "manifest.json";
+
+/// Index of archived segments and the archive high-water mark
+///
+/// The manifest is the only source of truth for what may be purged from the
+/// stream: a sequence is purgeable once a segment covering it has been
+/// written *and* a manifest recording that segment has been saved.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Manifest format version
+    pub version: u32,
+
+    /// Highest stream sequence stored in an archived segment (0 = none)
+    pub high_water_sequence: u64,
+
+    /// Archived segments, in sequence order
+    pub segments: Vec<SegmentInfo>,
+}
+
+/// One gzipped NDJSON segment of archived events
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    /// Object key in the archive store
+    pub key: String,
+    /// First stream sequence in the segment
+    pub first_sequence: u64,
+    /// Last stream sequence in the segment
+    pub last_sequence: u64,
+    /// Stream publish time of the first event
+    pub first_published: DateTime<Utc>,
+    /// Stream publish time of the last event
+    pub last_published: DateTime<Utc>,
+    /// Number of events in the segment
+    pub event_count: usize,
+}
+
+impl SegmentInfo {
+    /// Object key for a segment covering `first..=last`
+    pub fn key_for(first: u64, last: u64) -> String {
+        format!("segments/{:020}-{:020}.ndjson.gz", first, last)
+    }
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            version: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a stored manifest
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let manifest: Self = serde_json::from_slice(bytes)?;
+        manifest.check()?;
+        Ok(manifest)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).expect("manifest serializes")
+    }
+
+    /// First sequence still to archive, given the stream's first sequence
+    ///
+    /// Sequences between the high-water mark and `stream_first` are gone from
+    /// the stream (dropped by limits or purged externally) and are skipped.
+    pub fn next_sequence(&self, stream_first: u64) -> u64 {
+        (self.high_water_sequence + 1).max(stream_first)
+    }
+
+    /// Record a segment that has been written to the store
+    ///
+    /// Segments must be non-empty and strictly above the high-water mark, so
+    /// the mark only ever moves forward over archived sequences.
+    pub fn record(&mut self, segment: SegmentInfo) -> Result<()> {
+        if segment.event_count == 0 || segment.last_sequence < segment.first_sequence {
+            bail!(
+                "invalid segment {}: sequences {}..={}, {} events",
+                segment.key,
+                segment.first_sequence,
+                segment.last_sequence,
+                segment.event_count
+            );
+        }
+        if segment.first_sequence <= self.high_water_sequence {
+            bail!(
+                "segment {} starts at sequence {}, at or below high-water mark {}",
+                segment.key,
+                segment.first_sequence,
+                self.high_water_sequence
+            );
+        }
+        self.high_water_sequence = segment.last_sequence;
+        self.segments.push(segment);
+        Ok(())
+    }
+
+    /// Sequence to purge the stream up to (exclusive), if anything archived
+    /// is still in the stream
+    ///
+    /// Never exceeds the high-water mark + 1, so only archived events go.
+    pub fn purge_before(&self, stream_first: u64) -> Option<u64> {
+        let archived = self.high_water_sequence > 0 && self.high_water_sequence >= stream_first;
+        archived.then_some(self.high_water_sequence + 1)
+    }
+
+    /// Segments that may hold events published at or after `since`
+    pub fn segments_since(&self, since: DateTime<Utc>) -> impl Iterator<Item = &SegmentInfo> {
+        self.segments
+            .iter()
+            .filter(move |segment| segment.last_published >= since)
+    }
+
+    /// Reject manifests whose segments overlap, are out of order, or end
+    /// somewhere other than the high-water mark
+    fn check(&self) -> Result<()> {
+        let mut last = 0;
+        for segment in &self.segments {
+            if segment.first_sequence <= last || segment.last_sequence < segment.first_sequence {
+                bail!("manifest segment {} is out of order", segment.key);
+            }
+            last = segment.last_sequence;
+        }
+        if last != self.high_water_sequence {
+            bail!(
+                "manifest high-water mark {} does not match last segment ({})",
+                self.high_water_sequence,
+                last
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn segment(first: u64, last: u64) -> SegmentInfo {
+        SegmentInfo {
+            key: SegmentInfo::key_for(first, last),
+            first_sequence: first,
+            last_sequence: last,
+            first_published: Utc.timestamp_opt(first as i64, 0).unwrap(),
+            last_published: Utc.timestamp_opt(last as i64, 0).unwrap(),
+            event_count: (last - first + 1) as usize,
+        }
+    }
+
+    #[test]
+    fn test_empty_manifest_purges_nothing() {
+        let manifest = Manifest::new();
+        assert_eq!(manifest.high_water_sequence, 0);
+        assert_eq!(manifest.next_sequence(1), 1);
+        assert_eq!(manifest.purge_before(1), None);
+        assert_eq!(manifest.purge_before(0), None);
+    }
+
+    #[test]
+    fn test_record_advances_high_water() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 100)).unwrap();
+        manifest.record(segment(101, 150)).unwrap();
+
+        assert_eq!(manifest.high_water_sequence, 150);
+        assert_eq!(manifest.next_sequence(1), 151);
+        // Purge is exclusive: everything up to and including 150 goes
+        assert_eq!(manifest.purge_before(1), Some(151));
+    }
+
+    #[test]
+    fn test_record_rejects_overlap_and_empty_segments() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 100)).unwrap();
+
+        assert!(manifest.record(segment(100, 120)).is_err());
+        assert!(manifest.record(segment(50, 60)).is_err());
+        let mut empty = segment(101, 101);
+        empty.event_count = 0;
+        assert!(manifest.record(empty).is_err());
+        let mut backwards = segment(101, 110);
+        backwards.last_sequence = 99;
+        assert!(manifest.record(backwards).is_err());
+
+        // Failed records leave the manifest untouched
+        assert_eq!(manifest.high_water_sequence, 100);
+        assert_eq!(manifest.segments.len(), 1);
+    }
+
+    #[test]
+    fn test_gaps_in_stream_are_skipped() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 100)).unwrap();
+
+        // Stream limits dropped 101..=199 before they were archived
+        assert_eq!(manifest.next_sequence(200), 200);
+        manifest.record(segment(200, 210)).unwrap();
+        assert_eq!(manifest.high_water_sequence, 210);
+    }
+
+    #[test]
+    fn test_purge_only_when_archived_events_remain() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 100)).unwrap();
+
+        // Archived but not yet purged (e.g. crash after saving the manifest)
+        assert_eq!(manifest.purge_before(1), Some(101));
+        assert_eq!(manifest.purge_before(100), Some(101));
+        // Already purged
+        assert_eq!(manifest.purge_before(101), None);
+        assert_eq!(manifest.purge_before(500), None);
+    }
+
+    #[test]
+    fn test_round_trip_and_validation() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 10)).unwrap();
+        manifest.record(segment(11, 20)).unwrap();
+
+        let loaded = Manifest::from_bytes(&manifest.to_bytes()).unwrap();
+        assert_eq!(loaded, manifest);
+
+        let mut wrong_mark = manifest.clone();
+        wrong_mark.high_water_sequence = 25;
+        assert!(Manifest::from_bytes(&wrong_mark.to_bytes()).is_err());
+
+        let mut overlapping = manifest;
+        overlapping.segments[1].first_sequence = 5;
+        assert!(Manifest::from_bytes(&overlapping.to_bytes()).is_err());
+
+        assert!(Manifest::from_bytes(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_segments_since() {
+        let mut manifest = Manifest::new();
+        manifest.record(segment(1, 10)).unwrap();
+        manifest.record(segment(11, 20)).unwrap();
+
+        let since = Utc.timestamp_opt(15, 0).unwrap();
+        let keys: Vec<_> = manifest
+            .segments_since(since)
+            .map(|s| s.first_sequence)
+            .collect();
+        assert_eq!(keys, vec![11]);
+    }
+}
@@ -0,0 +1,147 @@
+use super::manifest::SegmentInfo;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+
+/// An event as stored in the stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredEvent {
+    /// Stream sequence number
+    pub sequence: u64,
+    /// When the stream stored the event
+    pub published: DateTime<Utc>,
+    /// Raw message payload (normally a JSON FluxEvent)
+    pub payload: Vec<u8>,
+}
+
+/// One NDJSON line of a segment
+///
+/// JSON payloads are kept as `event`; anything else is kept verbatim as
+/// base64 in `raw` so archiving never drops a message.
+#[derive(Debug, Serialize, Deserialize)]
+struct Line {
+    sequence: u64,
+    published: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+}
+
+/// Encode `events` (in sequence order) as a gzipped NDJSON segment
+pub fn encode(events: &[StoredEvent]) -> Result<(SegmentInfo, Vec<u8>)> {
+    let (first, last) = match (events.first(), events.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => anyhow::bail!("cannot encode an empty segment"),
+    };
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for stored in events {
+        let line = match serde_json::from_slice::<Value>(&stored.payload) {
+            Ok(event) => Line {
+                sequence: stored.sequence,
+                published: stored.published,
+                event: Some(event),
+                raw: None,
+            },
+            Err(_) => Line {
+                sequence: stored.sequence,
+                published: stored.published,
+                event: None,
+                raw: Some(BASE64.encode(&stored.payload)),
+            },
+        };
+        serde_json::to_writer(&mut encoder, &line).context("Failed to serialize archived event")?;
+        encoder.write_all(b"\n")?;
+    }
+    let data = encoder.finish().context("Failed to compress segment")?;
+
+    let info = SegmentInfo {
+        key: SegmentInfo::key_for(first.sequence, last.sequence),
+        first_sequence: first.sequence,
+        last_sequence: last.sequence,
+        first_published: first.published,
+        last_published: last.published,
+        event_count: events.len(),
+    };
+    Ok((info, data))
+}
+
+/// Decode a segment back into stored events
+pub fn decode(data: &[u8]) -> Result<Vec<StoredEvent>> {
+    let reader = BufReader::new(GzDecoder::new(data));
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line.context("Failed to decompress segment")?;
+        if line.is_empty() {
+            continue;
+        }
+        let line: Line = serde_json::from_str(&line).context("Invalid segment line")?;
+        let payload = match (line.event, line.raw) {
+            (Some(event), _) => serde_json::to_vec(&event)?,
+            (None, Some(raw)) => BASE64
+                .decode(raw)
+                .context("Invalid base64 in segment line")?,
+            (None, None) => Vec::new(),
+        };
+        events.push(StoredEvent {
+            sequence: line.sequence,
+            published: line.published,
+            payload,
+        });
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn stored(sequence: u64, payload: &[u8]) -> StoredEvent {
+        StoredEvent {
+            sequence,
+            published: Utc
+                .timestamp_opt(1_700_000_000 + sequence as i64, 0)
+                .unwrap(),
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let events = vec![
+            stored(7, br#"{"stream":"s","payload":{"entity_id":"a"}}"#),
+            stored(8, b"not json\nwith a newline"),
+            stored(10, br#"{"stream":"s","payload":{"text":"multi\nline"}}"#),
+        ];
+
+        let (info, data) = encode(&events).unwrap();
+        assert_eq!(info.first_sequence, 7);
+        assert_eq!(info.last_sequence, 10);
+        assert_eq!(info.event_count, 3);
+        assert_eq!(info.last_published, events[2].published);
+        assert_eq!(info.key, SegmentInfo::key_for(7, 10));
+
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1], events[1]);
+        for (decoded, original) in decoded.iter().zip(&events) {
+            assert_eq!(decoded.sequence, original.sequence);
+            assert_eq!(decoded.published, original.published);
+        }
+        let event: Value = serde_json::from_slice(&decoded[2].payload).unwrap();
+        assert_eq!(event["payload"]["text"], "multi\nline");
+    }
+
+    #[test]
+    fn test_empty_segment_rejected() {
+        assert!(encode(&[]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use super::config::S3Config;
+use crate::s3::S3Client;
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Where archive segments and the manifest are kept
+///
+/// Keys are relative paths such as "manifest.json" or "segments/…".
+/// `put` must be durable when it returns.
+pub trait ArchiveStore: Send + Sync + 'static {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>>;
+
+    /// Object contents, or `None` if the key does not exist
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>>;
+}
+
+/// Archive in a local directory
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Write via tmp file + fsync + rename so readers never see partial objects
+    fn put_sync(&self, key: &str, data: &[u8]) -> Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create archive directory")?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut file = File::create(&tmp_path).context("Failed to create archive file")?;
+            file.write_all(data)
+                .context("Failed to write archive file")?;
+            file.sync_all()
+                .context("Failed to sync archive file to disk")?;
+        }
+        fs::rename(&tmp_path, &path).context("Failed to rename archive file")?;
+        Ok(())
+    }
+}
+
+impl ArchiveStore for LocalStore {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.put_sync(key, &data) })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+        Box::pin(async move {
+            match fs::read(self.root.join(key)) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).context(format!("Failed to read archive object '{}'", key)),
+            }
+        })
+    }
+}
+
+/// Archive in an S3-compatible bucket (AWS S3, MinIO, R2, ...)
+pub struct S3Store {
+    client: S3Client,
+}
+
+impl S3Store {
+    pub fn new(config: S3Config, access_key_id: String, secret_access_key: String) -> Self {
+        Self {
+            client: S3Client::new(config, access_key_id, secret_access_key),
+        }
+    }
+
+    /// Credentials from FLUX_ARCHIVE_S3_ACCESS_KEY_ID / FLUX_ARCHIVE_S3_SECRET_ACCESS_KEY
+    pub fn from_env(config: S3Config) -> Result<Self> {
+        Ok(Self {
+            client: S3Client::from_env(config, "FLUX_ARCHIVE_S3")?,
+        })
+    }
+
+    /// Talks to the bucket with `http_client` (see [`crate::http_client`])
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(http_client);
+        self
+    }
+}
+
+impl ArchiveStore for S3Store {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.client.put_object(key, data, &[]))
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+        Box::pin(async move { Ok(self.client.get_object(key).await?.map(|object| object.data)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_store_put_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(dir.path());
+
+        assert_eq!(store.get("manifest.json").await.unwrap(), None);
+
+        store
+            .put("segments/a.ndjson.gz", b"one".to_vec())
+            .await
+            .unwrap();
+        store
+            .put("segments/a.ndjson.gz", b"two".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get("segments/a.ndjson.gz").await.unwrap(),
+            Some(b"two".to_vec())
+        );
+        // No temp files left behind
+        let names: Vec<_> = fs::read_dir(dir.path().join("segments"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["a.ndjson.gz"]);
+    }
+}
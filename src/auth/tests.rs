@@ -82,10 +82,7 @@ mod extract_bearer_token_tests {
     #[test]
     fn wrong_auth_scheme() {
         let mut headers = HeaderMap::new();
-        headers.insert(
-            "authorization",
-            "Basic dXNlcjpwYXNz".parse().unwrap(),
-        );
+        headers.insert("authorization", "Basic dXNlcjpwYXNz".parse().unwrap());
 
         let result = extract_bearer_token(&headers);
         assert_eq!(result, Err(TokenError::InvalidFormat));
@@ -206,10 +203,7 @@ mod extract_token_from_message_tests {
         // Should accept token as-is (with whitespace)
         let result = extract_token_from_message(&message);
         assert!(result.is_ok());
-        assert_eq!(
-            result.unwrap(),
-            "  550e8400-e29b-41d4-a716-446655440000  "
-        );
+        assert_eq!(result.unwrap(), "  550e8400-e29b-41d4-a716-446655440000  ");
     }
 }
 
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Configuration for event compaction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactionConfig {
+    /// Compact on a schedule (`POST /api/admin/compact` works either way)
+    pub enabled: bool,
+
+    /// Interval between scheduled runs (hours)
+    pub interval_hours: u64,
+
+    /// Events read from the stream at a time
+    pub read_batch: usize,
+
+    /// Times a run reads to the end of the stream, catching up with events
+    /// published meanwhile, before it gives up
+    pub max_catch_up_passes: u32,
+
+    /// How long to wait for the state engine to reach the end of the stream (seconds)
+    pub engine_wait_seconds: u64,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            read_batch: 10_000,
+            max_catch_up_passes: 5,
+            engine_wait_seconds: 30,
+        }
+    }
+}
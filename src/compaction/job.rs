@@ -0,0 +1,856 @@
+//! Compaction runs: read the stream, publish its compacted set, purge the rest.
+//!
+//! A run reads the stream to its end, folding every event (see
+//! [`EventFold`]), and waits for the state engine to reach the same sequence
+//! so it can check the compacted set rebuilds what the engine holds. Events
+//! published meanwhile are read in further passes. The compacted events are
+//! then published behind a fence: each expects the stream's last sequence to
+//! be the one before it, so nothing can slip in between them unnoticed.
+//! Once the engine has processed them too, a snapshot is taken and the
+//! stream is purged up to the first compacted event.
+//!
+//! A run that stops early purges nothing. Compacted events it already
+//! published reproduce state the engine had reached anyway, so replaying
+//! them after their originals changes nothing.
+
+use super::config::CompactionConfig;
+use super::plan::{CompactionRules, EventFold};
+use super::COMPACTED_THROUGH_HEADER;
+use crate::archive::archiver::ArchiveSource;
+use crate::archive::JetStreamSource;
+use crate::event::FluxEvent;
+use crate::nats::{event_subject, SubjectScheme};
+use crate::replay::ReplayJobs;
+use crate::snapshot::manager::SnapshotManager;
+use crate::state::{StateEngine, RAW_ID_PROPERTY};
+use anyhow::{Context, Result};
+use async_nats::jetstream;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// Diverging entity IDs listed in a report
+const DIVERGING_SAMPLE: usize = 20;
+
+/// How often the state engine's position is checked while waiting for it
+const ENGINE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The event stream being compacted: an [`ArchiveSource`] that can also
+/// publish. Implemented for JetStream; tests substitute an in-memory stream.
+pub trait CompactionStream: ArchiveSource {
+    /// Publish `payload` on `subject`, with [`COMPACTED_THROUGH_HEADER`] set
+    /// to `through`, if the stream's last sequence is still `expected_last`
+    ///
+    /// Returns the new message's sequence, or None if another message was
+    /// stored first.
+    fn publish_after(
+        &self,
+        subject: String,
+        through: u64,
+        payload: Vec<u8>,
+        expected_last: u64,
+    ) -> BoxFuture<'_, Result<Option<u64>>>;
+}
+
+impl CompactionStream for JetStreamSource {
+    fn publish_after(
+        &self,
+        subject: String,
+        through: u64,
+        payload: Vec<u8>,
+        expected_last: u64,
+    ) -> BoxFuture<'_, Result<Option<u64>>> {
+        Box::pin(async move {
+            let publish = jetstream::context::Publish::build()
+                .payload(payload.into())
+                .header(COMPACTED_THROUGH_HEADER, through.to_string().as_str())
+                .expected_last_sequence(expected_last);
+            let published = match self.jetstream().send_publish(subject, publish).await {
+                Ok(ack) => ack.await,
+                Err(e) => Err(e),
+            };
+            match published {
+                Ok(ack) => Ok(Some(ack.sequence)),
+                Err(e) if e.kind() == jetstream::context::PublishErrorKind::WrongLastSequence => {
+                    Ok(None)
+                }
+                Err(e) => Err(e).context("Failed to publish compacted event"),
+            }
+        })
+    }
+}
+
+/// How to run a compaction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CompactOptions {
+    /// Only report what compaction would do
+    pub dry_run: bool,
+    /// Compact even if the result would not rebuild the state of some
+    /// entities exactly (see `diverging_entities` in the report)
+    pub force: bool,
+}
+
+/// What a compaction did, or would do on a dry run
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "dry_run": false,
+    "compacted_through": 1482210,
+    "events_read": 1482210,
+    "events_after": 3120,
+    "reduction_percent": 99.79,
+    "entities": 3012,
+    "deleted_entities": 8,
+    "kept_as_published": 92,
+    "diverging_entities": 0,
+    "passes": 2,
+    "purged_before": 1482211,
+    "snapshot_sequence": 1485330
+}))]
+pub struct CompactionReport {
+    pub dry_run: bool,
+    /// Last stream sequence the compacted events stand for
+    pub compacted_through: u64,
+    /// Events read from the stream
+    pub events_read: u64,
+    /// Events left in their place
+    pub events_after: u64,
+    /// Share of the events read that compaction removes
+    pub reduction_percent: f64,
+    /// Entities written by one event each
+    pub entities: usize,
+    /// Deleted entities kept, with their tombstones, for undelete
+    pub deleted_entities: usize,
+    /// Messages, quotas and mapped-stream events, kept as published
+    pub kept_as_published: usize,
+    /// Entities whose state in the engine the compacted events would not
+    /// rebuild, e.g. because the engine dropped updates within a deadband
+    /// or over a quota
+    pub diverging_entities: usize,
+    /// Some of their IDs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diverging_sample: Vec<String>,
+    /// Times the stream was read to its end
+    pub passes: u32,
+    /// The stream was purged below this sequence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purged_before: Option<u64>,
+    /// Sequence of the snapshot taken after publishing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_sequence: Option<u64>,
+}
+
+/// Why a compaction did not run or stopped
+#[derive(Debug)]
+pub enum CompactionError {
+    /// Another compaction is running
+    AlreadyRunning,
+    /// The state engine is still replaying the stream
+    Replaying,
+    /// Sandbox replays are reading the stream
+    ReplayJobsRunning(usize),
+    /// The compacted events would not rebuild every entity; `force` overrides
+    Diverging {
+        count: usize,
+        sample: Vec<String>,
+    },
+    /// Events were published faster than the run could read them
+    StreamGrowing {
+        passes: u32,
+    },
+    /// Another event was stored between the compacted events
+    Interrupted {
+        published: usize,
+    },
+    /// The state engine did not reach `sequence` in time
+    EngineBehind {
+        sequence: u64,
+    },
+    Failed(anyhow::Error),
+}
+
+impl std::fmt::Display for CompactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompactionError::AlreadyRunning => write!(f, "A compaction is already running"),
+            CompactionError::Replaying => {
+                write!(f, "The state engine is still replaying the stream")
+            }
+            CompactionError::ReplayJobsRunning(count) => {
+                write!(f, "{} sandbox replay(s) are reading the stream", count)
+            }
+            CompactionError::Diverging { count, sample } => write!(
+                f,
+                "Compacted events would not rebuild {} entities (e.g. {}); set force to compact anyway",
+                count,
+                sample.join(", ")
+            ),
+            CompactionError::StreamGrowing { passes } => write!(
+                f,
+                "Events arrive faster than compaction reads them (gave up after {} passes)",
+                passes
+            ),
+            CompactionError::Interrupted { published } => write!(
+                f,
+                "Another event was stored after {} compacted events; nothing was purged",
+                published
+            ),
+            CompactionError::EngineBehind { sequence } => write!(
+                f,
+                "State engine did not reach sequence {} in time; nothing was purged",
+                sequence
+            ),
+            CompactionError::Failed(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for CompactionError {
+    fn from(e: anyhow::Error) -> Self {
+        CompactionError::Failed(e)
+    }
+}
+
+/// Where the state engine is relative to a stream sequence
+enum EnginePosition {
+    At,
+    Beyond,
+}
+
+/// Compacts the event stream, on demand or on a schedule
+pub struct Compactor {
+    source: Arc<dyn CompactionStream>,
+    state_engine: Arc<StateEngine>,
+    config: CompactionConfig,
+    subject_scheme: SubjectScheme,
+    /// Takes the snapshot at the new head of the stream
+    snapshots: Option<Arc<SnapshotManager>>,
+    /// Sandbox replays, which read the history compaction removes
+    replay_jobs: Option<Arc<ReplayJobs>>,
+    /// Held for the length of a run
+    running: Mutex<()>,
+}
+
+impl Compactor {
+    pub fn new(
+        source: Arc<dyn CompactionStream>,
+        state_engine: Arc<StateEngine>,
+        config: CompactionConfig,
+    ) -> Self {
+        Self {
+            source,
+            state_engine,
+            config,
+            subject_scheme: SubjectScheme::default(),
+            snapshots: None,
+            replay_jobs: None,
+            running: Mutex::new(()),
+        }
+    }
+
+    /// Publish compacted events on subjects laid out per `scheme`
+    pub fn with_subject_scheme(mut self, scheme: SubjectScheme) -> Self {
+        self.subject_scheme = scheme;
+        self
+    }
+
+    /// Snapshot through `snapshots` once the compacted events are processed
+    pub fn with_snapshots(mut self, snapshots: Arc<SnapshotManager>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Refuse to run while any of `jobs` is reading the stream
+    pub fn with_replay_jobs(mut self, jobs: Arc<ReplayJobs>) -> Self {
+        self.replay_jobs = Some(jobs);
+        self
+    }
+
+    /// Compact every `interval_hours` until the task is cancelled
+    ///
+    /// Scheduled runs are never forced: one that would change some entity's
+    /// state is skipped with a warning.
+    pub async fn run_schedule_loop(&self) -> Result<()> {
+        if !self.config.enabled {
+            info!("Scheduled compaction disabled, exiting loop");
+            return Ok(());
+        }
+
+        info!(
+            interval_hours = self.config.interval_hours,
+            "Starting scheduled compaction"
+        );
+        let period = Duration::from_secs(self.config.interval_hours.max(1) * 3600);
+        // Not at startup: the engine is replaying then anyway
+        let mut timer = tokio::time::interval_at(Instant::now() + period, period);
+        loop {
+            timer.tick().await;
+            match self.run(CompactOptions::default()).await {
+                Ok(report) => info!(
+                    events_read = report.events_read,
+                    events_after = report.events_after,
+                    purged_before = ?report.purged_before,
+                    "Scheduled compaction complete"
+                ),
+                Err(CompactionError::Failed(e)) => {
+                    error!(error = %e, "Scheduled compaction failed")
+                }
+                Err(e) => warn!(reason = %e, "Scheduled compaction skipped"),
+            }
+        }
+    }
+
+    /// Compact the stream (or, with `dry_run`, report what compacting would do)
+    pub async fn run(&self, options: CompactOptions) -> Result<CompactionReport, CompactionError> {
+        let _running = self
+            .running
+            .try_lock()
+            .map_err(|_| CompactionError::AlreadyRunning)?;
+        if !self.state_engine.is_live() {
+            return Err(CompactionError::Replaying);
+        }
+        if let Some(jobs) = &self.replay_jobs {
+            let running = jobs.running();
+            if running > 0 {
+                return Err(CompactionError::ReplayJobsRunning(running));
+            }
+        }
+
+        let rules = CompactionRules {
+            mapped_streams: self.state_engine.mapped_streams().into_iter().collect(),
+            keep_deleted_since: Some(Utc::now() - self.state_engine.trash_retention()),
+        };
+        let mut fold = EventFold::new(rules);
+        let mut next = self.source.bounds().await?.first_sequence;
+        let mut passes = 0;
+        let mut last_pass_read = None;
+
+        loop {
+            let (through, read) = self.read_to_end(&mut fold, &mut next).await?;
+            passes += 1;
+            // Each catch-up pass should have less to read than the one before
+            let growing = passes > 2 && last_pass_read.is_some_and(|last| read > last);
+            if growing || passes > self.config.max_catch_up_passes.max(1) {
+                return Err(CompactionError::StreamGrowing { passes });
+            }
+            if passes > 1 {
+                last_pass_read = Some(read);
+            }
+            if let EnginePosition::Beyond = self.wait_for_engine(through).await? {
+                continue;
+            }
+
+            let compaction = fold.finish();
+            let diverging = self.diverging(&fold);
+            let mut report = CompactionReport {
+                dry_run: options.dry_run,
+                compacted_through: through,
+                events_read: compaction.events_read,
+                events_after: compaction.events.len() as u64,
+                reduction_percent: reduction_percent(
+                    compaction.events_read,
+                    compaction.events.len() as u64,
+                ),
+                entities: compaction.entities,
+                deleted_entities: compaction.deleted,
+                kept_as_published: compaction.kept_as_published,
+                diverging_entities: diverging.len(),
+                diverging_sample: diverging.iter().take(DIVERGING_SAMPLE).cloned().collect(),
+                passes,
+                purged_before: None,
+                snapshot_sequence: None,
+            };
+            if options.dry_run {
+                return Ok(report);
+            }
+            if !diverging.is_empty() && !options.force {
+                return Err(CompactionError::Diverging {
+                    count: report.diverging_entities,
+                    sample: report.diverging_sample,
+                });
+            }
+
+            let Some(last) = self.publish(&compaction.events, through).await? else {
+                // Something was stored before the first compacted event: read it too
+                continue;
+            };
+            if let Err(e) = self.wait_for_engine(last).await {
+                warn!(through, error = %e, "Compacted events published, but not purging");
+                return Err(e);
+            }
+            if let Some(snapshots) = &self.snapshots {
+                report.snapshot_sequence = Some(snapshots.snapshot_now().await?);
+            }
+            self.source.purge_before(through + 1).await?;
+            report.purged_before = Some(through + 1);
+            info!(
+                through,
+                events_read = report.events_read,
+                events_after = report.events_after,
+                "Event stream compacted"
+            );
+            return Ok(report);
+        }
+    }
+
+    /// Fold events from `next` to the stream's current end; returns that
+    /// end's sequence and the number of events read
+    async fn read_to_end(&self, fold: &mut EventFold, next: &mut u64) -> Result<(u64, u64)> {
+        let through = self.source.bounds().await?.last_sequence;
+        let mut read = 0;
+        'read: while *next <= through {
+            let batch = self
+                .source
+                .read(*next, self.config.read_batch.max(1))
+                .await?;
+            if batch.is_empty() {
+                break;
+            }
+            for stored in batch {
+                if stored.sequence > through {
+                    break 'read;
+                }
+                *next = stored.sequence + 1;
+                read += 1;
+                match serde_json::from_slice::<FluxEvent>(&stored.payload) {
+                    Ok(event) => fold.apply(stored.sequence, event),
+                    Err(e) => warn!(
+                        sequence = stored.sequence,
+                        error = %e,
+                        "Skipping undecodable event"
+                    ),
+                }
+            }
+        }
+        // Ingest traffic and purged gaps at the end are read past too
+        *next = (*next).max(through + 1);
+        Ok((through, read))
+    }
+
+    /// Wait until the state engine has processed the stream through `sequence`
+    async fn wait_for_engine(&self, sequence: u64) -> Result<EnginePosition, CompactionError> {
+        let deadline = Instant::now() + Duration::from_secs(self.config.engine_wait_seconds);
+        loop {
+            let processed = self.state_engine.get_last_processed_sequence();
+            if processed > sequence {
+                return Ok(EnginePosition::Beyond);
+            }
+            if processed == sequence {
+                return Ok(EnginePosition::At);
+            }
+            if Instant::now() >= deadline {
+                return Err(CompactionError::EngineBehind { sequence });
+            }
+            tokio::time::sleep(ENGINE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// IDs of folded entities whose properties differ from the engine's
+    fn diverging(&self, fold: &EventFold) -> Vec<String> {
+        let mut diverging: Vec<String> = fold
+            .entity_states()
+            .filter(|(entity_id, expected)| {
+                let normalized = self.state_engine.normalize_entity_id(entity_id);
+                let mut actual = self
+                    .state_engine
+                    .get_entity(&normalized)
+                    .map(|entity| entity.properties)
+                    .unwrap_or_default();
+                actual.remove(RAW_ID_PROPERTY);
+                let actual: BTreeMap<String, Value> = actual.into_iter().collect();
+                match expected {
+                    Some(expected) => **expected != actual,
+                    None => !actual.is_empty(),
+                }
+            })
+            .map(|(entity_id, _)| entity_id.to_string())
+            .collect();
+        diverging.sort();
+        diverging
+    }
+
+    /// Publish `events`, each right after the one before and the first right
+    /// after `through`; returns the last one's sequence (or `through` if there
+    /// are none), or None if something else came first
+    async fn publish(
+        &self,
+        events: &[FluxEvent],
+        through: u64,
+    ) -> Result<Option<u64>, CompactionError> {
+        let mut last = through;
+        for (published, event) in events.iter().enumerate() {
+            let subject = event_subject(event, self.subject_scheme);
+            let payload =
+                serde_json::to_vec(event).context("Failed to serialize compacted event")?;
+            match self
+                .source
+                .publish_after(subject, through, payload, last)
+                .await?
+            {
+                Some(sequence) => last = sequence,
+                None if published == 0 => return Ok(None),
+                None => return Err(CompactionError::Interrupted { published }),
+            }
+        }
+        Ok(Some(last))
+    }
+}
+
+fn reduction_percent(before: u64, after: u64) -> f64 {
+    if before == 0 {
+        return 0.0;
+    }
+    let removed = before.saturating_sub(after) as f64;
+    (removed * 10_000.0 / before as f64).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::archiver::StreamBounds;
+    use crate::archive::StoredEvent;
+    use serde_json::json;
+    use std::sync::Mutex as StdMutex;
+
+    const T0: i64 = 1_760_000_000_000;
+
+    /// In-memory stream the state engine follows as messages are stored
+    struct MockStream {
+        engine: Arc<StateEngine>,
+        inner: StdMutex<Inner>,
+    }
+
+    #[derive(Default)]
+    struct Inner {
+        /// Sequence, event, compacted-through header
+        messages: Vec<(u64, FluxEvent, Option<u64>)>,
+        last_sequence: u64,
+        /// Events stored by other publishers after each call to `bounds`
+        arrivals: Vec<Vec<FluxEvent>>,
+        /// Stored just before the next publish
+        interloper: Option<FluxEvent>,
+    }
+
+    impl MockStream {
+        fn new(engine: Arc<StateEngine>) -> Arc<Self> {
+            Arc::new(Self {
+                engine,
+                inner: StdMutex::new(Inner::default()),
+            })
+        }
+
+        fn store(&self, inner: &mut Inner, event: FluxEvent, through: Option<u64>) -> u64 {
+            inner.last_sequence += 1;
+            let sequence = inner.last_sequence;
+            // Live engine: compacted copies of what it has seen are skipped
+            if through.is_none() {
+                self.engine.process_event(&event, Some(sequence));
+            }
+            self.engine.set_last_processed_sequence(sequence);
+            inner.messages.push((sequence, event, through));
+            sequence
+        }
+
+        fn append(&self, event: FluxEvent) {
+            let mut inner = self.inner.lock().unwrap();
+            self.store(&mut inner, event, None);
+        }
+
+        fn messages(&self) -> Vec<(u64, FluxEvent, Option<u64>)> {
+            self.inner.lock().unwrap().messages.clone()
+        }
+    }
+
+    impl ArchiveSource for MockStream {
+        fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+            Box::pin(async move {
+                let mut inner = self.inner.lock().unwrap();
+                let bounds = StreamBounds {
+                    first_sequence: inner
+                        .messages
+                        .first()
+                        .map_or(inner.last_sequence + 1, |m| m.0),
+                    last_sequence: inner.last_sequence,
+                };
+                // Stored right after, so the engine is past the bounds
+                if !inner.arrivals.is_empty() {
+                    for event in inner.arrivals.remove(0) {
+                        self.store(&mut inner, event, None);
+                    }
+                }
+                Ok(bounds)
+            })
+        }
+
+        fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+            Box::pin(async move {
+                let inner = self.inner.lock().unwrap();
+                Ok(inner
+                    .messages
+                    .iter()
+                    .filter(|m| m.0 >= start)
+                    .take(max)
+                    .map(|(sequence, event, _)| StoredEvent {
+                        sequence: *sequence,
+                        published: Utc::now(),
+                        payload: serde_json::to_vec(event).unwrap(),
+                    })
+                    .collect())
+            })
+        }
+
+        fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                self.inner
+                    .lock()
+                    .unwrap()
+                    .messages
+                    .retain(|m| m.0 >= sequence);
+                Ok(())
+            })
+        }
+    }
+
+    impl CompactionStream for MockStream {
+        fn publish_after(
+            &self,
+            _subject: String,
+            through: u64,
+            payload: Vec<u8>,
+            expected_last: u64,
+        ) -> BoxFuture<'_, Result<Option<u64>>> {
+            Box::pin(async move {
+                let mut inner = self.inner.lock().unwrap();
+                if let Some(event) = inner.interloper.take() {
+                    self.store(&mut inner, event, None);
+                }
+                if inner.last_sequence != expected_last {
+                    return Ok(None);
+                }
+                let event = serde_json::from_slice(&payload).unwrap();
+                Ok(Some(self.store(&mut inner, event, Some(through))))
+            })
+        }
+    }
+
+    fn event(entity_id: &str, at: i64, properties: Value) -> FluxEvent {
+        FluxEvent {
+            event_id: Some(format!("{:020}-{}", at, entity_id)),
+            stream: "sensors".to_string(),
+            source: "test".to_string(),
+            timestamp: T0 + at,
+            received_at: Some(T0 + at),
+            key: None,
+            schema: None,
+            payload: json!({"entity_id": entity_id, "properties": properties}),
+        }
+    }
+
+    fn config() -> CompactionConfig {
+        CompactionConfig {
+            read_batch: 3,
+            engine_wait_seconds: 1,
+            ..CompactionConfig::default()
+        }
+    }
+
+    /// Live engine following a stream of 10 updates to 2 sensors
+    fn fixture() -> (Arc<StateEngine>, Arc<MockStream>, Compactor) {
+        let engine = Arc::new(StateEngine::new());
+        engine.set_live();
+        let stream = MockStream::new(Arc::clone(&engine));
+        for i in 0..10 {
+            stream.append(event(&format!("plant/s{}", i % 2), i, json!({"temp": i})));
+        }
+        let compactor = Compactor::new(
+            Arc::clone(&stream) as Arc<dyn CompactionStream>,
+            Arc::clone(&engine),
+            config(),
+        );
+        (engine, stream, compactor)
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_reduction_only() {
+        let (_, stream, compactor) = fixture();
+        let report = compactor
+            .run(CompactOptions {
+                dry_run: true,
+                force: false,
+            })
+            .await
+            .unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.compacted_through, 10);
+        assert_eq!(report.events_read, 10);
+        assert_eq!(report.events_after, 2);
+        assert_eq!(report.reduction_percent, 80.0);
+        assert_eq!(report.entities, 2);
+        assert_eq!(report.diverging_entities, 0);
+        assert_eq!(report.purged_before, None);
+        assert_eq!(stream.messages().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_compacts_and_purges() {
+        let (engine, stream, compactor) = fixture();
+        let report = compactor.run(CompactOptions::default()).await.unwrap();
+        assert_eq!(report.purged_before, Some(11));
+
+        let messages = stream.messages();
+        let sequences: Vec<u64> = messages.iter().map(|m| m.0).collect();
+        assert_eq!(sequences, [11, 12]);
+        assert!(messages.iter().all(|m| m.2 == Some(10)));
+        assert_eq!(engine.get_last_processed_sequence(), 12);
+
+        // A new engine replaying what's left rebuilds the same state
+        let replayed = StateEngine::new();
+        for (sequence, event, _) in &messages {
+            replayed.process_event(event, Some(*sequence));
+        }
+        for id in ["plant/s0", "plant/s1"] {
+            let (a, b) = (
+                engine.get_entity(id).unwrap(),
+                replayed.get_entity(id).unwrap(),
+            );
+            assert_eq!(a.properties, b.properties);
+            assert_eq!(a.last_applied, b.last_applied);
+        }
+        // Live state was left alone
+        assert_eq!(engine.get_entity("plant/s1").unwrap().properties["temp"], 9);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_while_replaying() {
+        let engine = Arc::new(StateEngine::new());
+        let stream = MockStream::new(Arc::clone(&engine));
+        let compactor = Compactor::new(stream, engine, config());
+        let err = compactor.run(CompactOptions::default()).await.unwrap_err();
+        assert!(matches!(err, CompactionError::Replaying));
+    }
+
+    #[tokio::test]
+    async fn test_diverging_state_needs_force() {
+        let (engine, stream, compactor) = fixture();
+        // Changed outside the stream: compaction would not rebuild it
+        engine.update_property("plant/s0", "temp", json!(-1));
+
+        let err = compactor.run(CompactOptions::default()).await.unwrap_err();
+        match err {
+            CompactionError::Diverging { count, sample } => {
+                assert_eq!(count, 1);
+                assert_eq!(sample, ["plant/s0"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(stream.messages().len(), 10);
+
+        let report = compactor
+            .run(CompactOptions {
+                dry_run: false,
+                force: true,
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.diverging_entities, 1);
+        assert_eq!(stream.messages().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reads_events_published_during_run() {
+        let (_, stream, compactor) = fixture();
+        {
+            let mut inner = stream.inner.lock().unwrap();
+            // One arrives while reading, one just before the first publish
+            inner.arrivals = vec![vec![], vec![event("plant/s2", 20, json!({"temp": 1}))]];
+            inner.interloper = Some(event("plant/s3", 21, json!({"temp": 2})));
+        }
+        let report = compactor.run(CompactOptions::default()).await.unwrap();
+        assert_eq!(report.compacted_through, 12);
+        assert_eq!(report.entities, 4);
+        assert_eq!(report.passes, 3);
+        assert_eq!(report.purged_before, Some(13));
+        assert_eq!(stream.messages().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_on_growing_stream() {
+        let (_, stream, compactor) = fixture();
+        let burst = |n: i64| -> Vec<FluxEvent> {
+            (0..n)
+                .map(|i| event("plant/busy", 100 + n * 10 + i, json!({"n": i})))
+                .collect()
+        };
+        stream.inner.lock().unwrap().arrivals =
+            vec![vec![], burst(1), burst(2), burst(4), burst(8)];
+
+        let err = compactor.run(CompactOptions::default()).await.unwrap_err();
+        assert!(
+            matches!(err, CompactionError::StreamGrowing { passes: 3 }),
+            "{:?}",
+            err
+        );
+        assert!(stream.messages().iter().all(|m| m.2.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_publish_purges_nothing() {
+        let (_, stream, compactor) = fixture();
+        let result = {
+            // Store an interloper after the first compacted event
+            struct Interrupting(Arc<MockStream>, StdMutex<usize>);
+            impl ArchiveSource for Interrupting {
+                fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+                    self.0.bounds()
+                }
+                fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+                    self.0.read(start, max)
+                }
+                fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>> {
+                    self.0.purge_before(sequence)
+                }
+            }
+            impl CompactionStream for Interrupting {
+                fn publish_after(
+                    &self,
+                    subject: String,
+                    through: u64,
+                    payload: Vec<u8>,
+                    expected_last: u64,
+                ) -> BoxFuture<'_, Result<Option<u64>>> {
+                    let mut published = self.1.lock().unwrap();
+                    *published += 1;
+                    if *published == 2 {
+                        self.0.append(event("plant/s9", 50, json!({"temp": 0})));
+                    }
+                    self.0
+                        .publish_after(subject, through, payload, expected_last)
+                }
+            }
+            let source = Arc::new(Interrupting(Arc::clone(&stream), StdMutex::new(0)));
+            let compactor = Compactor::new(source, Arc::clone(&compactor.state_engine), config());
+            compactor.run(CompactOptions::default()).await
+        };
+        assert!(matches!(
+            result,
+            Err(CompactionError::Interrupted { published: 1 })
+        ));
+        // Originals, one compacted copy, the interloper: nothing purged
+        assert_eq!(stream.messages().len(), 12);
+    }
+
+    #[test]
+    fn test_reduction_percent() {
+        assert_eq!(reduction_percent(0, 0), 0.0);
+        assert_eq!(reduction_percent(3, 1), 66.67);
+        assert_eq!(reduction_percent(10, 10), 0.0);
+    }
+}
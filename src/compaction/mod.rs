@@ -0,0 +1,14 @@
+// Event compaction: the stream's history is replaced by the fewest events
+// that rebuild the current state, so startup replay and stream storage stop
+// growing with every update. Compacted events are published to the same
+// stream, after the events they stand for, and everything before them is
+// then purged; the state engine's durable consumer never has to move.
+
+pub mod config;
+pub mod job;
+pub mod plan;
+
+pub use crate::nats::COMPACTED_THROUGH_HEADER;
+pub use config::CompactionConfig;
+pub use job::{CompactOptions, CompactionError, CompactionReport, CompactionStream, Compactor};
+pub use plan::{compact, Compaction, CompactionRules, EventFold};
@@ -0,0 +1,743 @@
+//! The minimal event set: what remains of the stream after compaction.
+//!
+//! [`EventFold`] reads events in stream order with the state engine's rules
+//! (event ordering, `force`, unsets, tombstones) and keeps, per entity, only
+//! the latest value of each property. [`EventFold::finish`] turns that into
+//! one event per entity, which replayed from an empty state rebuilds the same
+//! entities. Nothing here touches NATS or the engine, so the job can fold
+//! while it reads and tests can fold plain vectors.
+
+use crate::event::FluxEvent;
+use crate::state::{event_time, is_unset, AppliedEvent, MESSAGES_STREAM, QUOTAS_STREAM};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// What the fold needs to know about the engine's configuration
+#[derive(Clone, Debug, Default)]
+pub struct CompactionRules {
+    /// Streams read through a payload mapping; their events are kept as
+    /// published, since their entity and properties depend on the mapping
+    pub mapped_streams: HashSet<String>,
+    /// Deleted entities are kept (their last state, then the tombstone) so
+    /// they can still be undeleted, unless deleted before this time
+    pub keep_deleted_since: Option<DateTime<Utc>>,
+}
+
+/// The compacted stream
+#[derive(Clone, Debug, Default)]
+pub struct Compaction {
+    /// Events to publish, in order
+    pub events: Vec<FluxEvent>,
+    /// Events folded
+    pub events_read: u64,
+    /// Entities written by one event each
+    pub entities: usize,
+    /// Deleted entities kept for undelete, by an event and its tombstone
+    pub deleted: usize,
+    /// Events kept as published (messages, quotas, mapped streams)
+    pub kept_as_published: usize,
+}
+
+/// Fields the compacted event takes from the latest event applied to an
+/// entity
+#[derive(Clone, Debug)]
+struct Origin {
+    /// Stream sequence of that event; compacted events keep stream order
+    sequence: u64,
+    stream: String,
+    source: String,
+    key: Option<String>,
+    schema: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct LiveEntity {
+    properties: BTreeMap<String, Value>,
+    last_applied: Option<AppliedEvent>,
+    origin: Origin,
+}
+
+#[derive(Clone, Debug)]
+struct DeletedEntity {
+    /// The entity as it was deleted
+    entity: LiveEntity,
+    tombstone: FluxEvent,
+    tombstone_sequence: u64,
+    deleted_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+enum EntityFold {
+    Live(LiveEntity),
+    Deleted(DeletedEntity),
+}
+
+/// Folds events into the latest state of each entity, keyed by the entity
+/// ID as published
+#[derive(Debug, Default)]
+pub struct EventFold {
+    rules: CompactionRules,
+    entities: HashMap<String, EntityFold>,
+    /// Events kept as published, with their stream sequence
+    kept: Vec<(u64, FluxEvent)>,
+    events_read: u64,
+}
+
+impl EventFold {
+    pub fn new(rules: CompactionRules) -> Self {
+        Self {
+            rules,
+            ..Self::default()
+        }
+    }
+
+    /// Events folded so far
+    pub fn events_read(&self) -> u64 {
+        self.events_read
+    }
+
+    /// Fold the event stored at `sequence`; events must come in stream order
+    pub fn apply(&mut self, sequence: u64, event: FluxEvent) {
+        self.events_read += 1;
+        if event.stream == MESSAGES_STREAM
+            || event.stream == QUOTAS_STREAM
+            || self.rules.mapped_streams.contains(&event.stream)
+        {
+            self.kept.push((sequence, event));
+            return;
+        }
+
+        // The engine skips events without the envelope; so does compaction
+        let Some(entity_id) = event.payload.get("entity_id").and_then(Value::as_str) else {
+            return;
+        };
+        let Some(properties) = event.payload.get("properties").and_then(Value::as_object) else {
+            return;
+        };
+
+        if let Some(Value::Bool(true)) = properties.get("__deleted__") {
+            self.delete(entity_id.to_string(), sequence, &event);
+            return;
+        }
+
+        let applied = AppliedEvent {
+            event_id: event.event_id.clone().unwrap_or_default(),
+            timestamp: event_time(&event),
+            sequence: event.payload.get("sequence").and_then(Value::as_u64),
+        };
+        let live = match self.entities.get(entity_id) {
+            Some(EntityFold::Live(live)) => Some(live),
+            _ => None,
+        };
+        let force = matches!(event.payload.get("force"), Some(Value::Bool(true)));
+        let stale = live
+            .and_then(|live| live.last_applied.as_ref())
+            .is_some_and(|last| applied.is_older_than(last));
+        if stale && !force {
+            return;
+        }
+
+        let null_unsets = matches!(event.payload.get("null_unsets"), Some(Value::Bool(true)));
+        let sets = properties.values().any(|v| !is_unset(v, null_unsets));
+        if live.is_none() && !sets {
+            // Only unsets: nothing to create
+            return;
+        }
+
+        let origin = Origin {
+            sequence,
+            stream: event.stream.clone(),
+            source: event.source.clone(),
+            key: event.key.clone(),
+            schema: event.schema.clone(),
+        };
+        // Writing a deleted entity brings it back without its old state
+        let mut live = match self.entities.remove(entity_id) {
+            Some(EntityFold::Live(live)) => live,
+            _ => LiveEntity {
+                properties: BTreeMap::new(),
+                last_applied: None,
+                origin: origin.clone(),
+            },
+        };
+        apply_properties(&mut live.properties, properties, null_unsets);
+        live.origin = origin;
+        let newest = live
+            .last_applied
+            .as_ref()
+            .is_none_or(|last| !applied.is_older_than(last));
+        if newest {
+            live.last_applied = Some(applied);
+        }
+        self.entities
+            .insert(entity_id.to_string(), EntityFold::Live(live));
+    }
+
+    fn delete(&mut self, entity_id: String, sequence: u64, tombstone: &FluxEvent) {
+        // Only a live entity goes to the trash; a tombstone for one that's
+        // gone changes nothing the compacted stream needs
+        let entity = match self.entities.remove(&entity_id) {
+            Some(EntityFold::Live(entity)) => entity,
+            // Already in the trash, as the first tombstone left it
+            Some(deleted) => {
+                self.entities.insert(entity_id, deleted);
+                return;
+            }
+            None => return,
+        };
+        let deleted_at = tombstone
+            .payload
+            .get("properties")
+            .and_then(|properties| properties.get("__deleted_at__"))
+            .and_then(Value::as_i64)
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(|| event_time(tombstone));
+        self.entities.insert(
+            entity_id,
+            EntityFold::Deleted(DeletedEntity {
+                entity,
+                tombstone: tombstone.clone(),
+                tombstone_sequence: sequence,
+                deleted_at,
+            }),
+        );
+    }
+
+    /// Each entity the fold has seen with the properties it ends up with
+    /// (None if deleted, or left without properties)
+    pub fn entity_states(&self) -> impl Iterator<Item = (&str, Option<&BTreeMap<String, Value>>)> {
+        self.entities.iter().map(|(id, fold)| {
+            let properties = match fold {
+                EntityFold::Live(live) if !live.properties.is_empty() => Some(&live.properties),
+                _ => None,
+            };
+            (id.as_str(), properties)
+        })
+    }
+
+    /// The events that rebuild what was folded so far
+    ///
+    /// Each event keeps the stream position of the latest event it stands
+    /// for, so compacted events come out in the order their originals had.
+    pub fn finish(&self) -> Compaction {
+        let mut compaction = Compaction {
+            events_read: self.events_read,
+            kept_as_published: self.kept.len(),
+            ..Compaction::default()
+        };
+        let mut ordered: Vec<(u64, FluxEvent)> = self.kept.clone();
+        for (entity_id, fold) in &self.entities {
+            match fold {
+                // An entity without properties can't be recreated by an event
+                EntityFold::Live(live) if live.properties.is_empty() => {}
+                EntityFold::Live(live) => {
+                    ordered.push((live.origin.sequence, entity_event(entity_id, live)));
+                    compaction.entities += 1;
+                }
+                EntityFold::Deleted(deleted) => {
+                    let keep = self
+                        .rules
+                        .keep_deleted_since
+                        .is_none_or(|since| deleted.deleted_at >= since);
+                    if !keep {
+                        continue;
+                    }
+                    if !deleted.entity.properties.is_empty() {
+                        ordered.push((
+                            deleted.tombstone_sequence,
+                            entity_event(entity_id, &deleted.entity),
+                        ));
+                    }
+                    let mut tombstone = deleted.tombstone.clone();
+                    tombstone.payload["properties"]["__deleted_at__"] =
+                        Value::from(deleted.deleted_at.timestamp_millis());
+                    ordered.push((deleted.tombstone_sequence, tombstone));
+                    compaction.deleted += 1;
+                }
+            }
+        }
+        // Stable: an entity's last state stays ahead of its tombstone
+        ordered.sort_by_key(|(sequence, _)| *sequence);
+        compaction.events = ordered.into_iter().map(|(_, event)| event).collect();
+        compaction
+    }
+}
+
+/// Fold `events` (sequence, event), in stream order, into their compacted set
+pub fn compact<I>(events: I, rules: CompactionRules) -> Compaction
+where
+    I: IntoIterator<Item = (u64, FluxEvent)>,
+{
+    let mut fold = EventFold::new(rules);
+    for (sequence, event) in events {
+        fold.apply(sequence, event);
+    }
+    fold.finish()
+}
+
+fn apply_properties(
+    current: &mut BTreeMap<String, Value>,
+    properties: &Map<String, Value>,
+    null_unsets: bool,
+) {
+    for (property, value) in properties {
+        if is_unset(value, null_unsets) {
+            current.remove(property);
+        } else {
+            current.insert(property.clone(), value.clone());
+        }
+    }
+}
+
+/// One event setting every property of `entity`, ordered like the last
+/// event the engine applied to it
+fn entity_event(entity_id: &str, entity: &LiveEntity) -> FluxEvent {
+    let mut payload = serde_json::json!({
+        "entity_id": entity_id,
+        "properties": entity.properties,
+    });
+    let (event_id, timestamp) = match &entity.last_applied {
+        Some(applied) => {
+            if let Some(sequence) = applied.sequence {
+                payload["sequence"] = Value::from(sequence);
+            }
+            let event_id = (!applied.event_id.is_empty()).then(|| applied.event_id.clone());
+            (event_id, applied.timestamp.timestamp_millis())
+        }
+        None => (None, Utc::now().timestamp_millis()),
+    };
+    FluxEvent {
+        event_id,
+        stream: entity.origin.stream.clone(),
+        source: entity.origin.source.clone(),
+        timestamp,
+        // The same instant, so the engine takes the timestamp as it is
+        received_at: Some(timestamp),
+        key: entity.origin.key.clone(),
+        schema: entity.origin.schema.clone(),
+        payload,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{StateEngine, RAW_ID_PROPERTY};
+    use serde_json::json;
+
+    const T0: i64 = 1_760_000_000_000;
+
+    fn event(entity_id: &str, at: i64, properties: Value) -> FluxEvent {
+        FluxEvent {
+            event_id: Some(format!("{:020}-{}", at, entity_id)),
+            stream: "sensors".to_string(),
+            source: "test".to_string(),
+            timestamp: T0 + at,
+            received_at: Some(T0 + at),
+            key: None,
+            schema: None,
+            payload: json!({"entity_id": entity_id, "properties": properties}),
+        }
+    }
+
+    fn with(mut event: FluxEvent, field: &str, value: Value) -> FluxEvent {
+        event.payload[field] = value;
+        event
+    }
+
+    fn tombstone(entity_id: &str, at: i64) -> FluxEvent {
+        let mut event = event(entity_id, at, json!({"__deleted__": true}));
+        event.stream = "flux.events.deletions".to_string();
+        event
+    }
+
+    fn sequenced(events: Vec<FluxEvent>) -> Vec<(u64, FluxEvent)> {
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| (i as u64 + 1, event))
+            .collect()
+    }
+
+    /// An entity's id, properties and last applied event
+    type ReplayedEntity = (String, Value, Option<AppliedEvent>);
+
+    /// Entities, last applied events and trash an engine ends up with after
+    /// replaying `events`
+    fn replay(events: &[FluxEvent]) -> (Vec<ReplayedEntity>, Vec<String>) {
+        let engine = StateEngine::new();
+        for (i, event) in events.iter().enumerate() {
+            engine.process_event(event, Some(i as u64 + 1));
+        }
+        let mut entities: Vec<_> = engine
+            .get_all_entities()
+            .into_iter()
+            .filter(|entity| !entity.properties.is_empty())
+            .map(|entity| {
+                let mut properties = entity.properties;
+                properties.remove(RAW_ID_PROPERTY);
+                (entity.id, json!(properties), entity.last_applied)
+            })
+            .collect();
+        entities.sort_by(|a, b| a.0.cmp(&b.0));
+        // An entity deleted without properties has no event to rebuild it
+        let mut trash: Vec<_> = engine
+            .deleted_entities()
+            .into_iter()
+            .filter(|deleted| !deleted.entity.properties.is_empty())
+            .map(|deleted| format!("{} {}", deleted.entity.id, json!(deleted.entity.properties)))
+            .collect();
+        trash.sort();
+        (entities, trash)
+    }
+
+    /// Compacting `events` and replaying the result rebuilds what replaying
+    /// `events` does
+    fn assert_equivalent(events: Vec<FluxEvent>) -> Compaction {
+        let compaction = compact(sequenced(events.clone()), CompactionRules::default());
+        assert_eq!(replay(&compaction.events), replay(&events));
+        // Compacting again changes nothing
+        let again = compact(
+            sequenced(compaction.events.clone()),
+            CompactionRules::default(),
+        );
+        assert_eq!(replay(&again.events), replay(&events));
+        assert_eq!(again.events.len(), compaction.events.len());
+        compaction
+    }
+
+    #[test]
+    fn test_keeps_latest_value_per_property() {
+        let compaction = assert_equivalent(vec![
+            event("plant/s1", 1, json!({"temp": 20, "unit": "C"})),
+            event("plant/s1", 2, json!({"temp": 21})),
+            event("plant/s2", 3, json!({"temp": 5})),
+            event("plant/s1", 4, json!({"temp": 22, "humidity": 40})),
+        ]);
+        assert_eq!(compaction.events_read, 4);
+        assert_eq!(compaction.entities, 2);
+        assert_eq!(compaction.events.len(), 2);
+
+        // In the order of each entity's latest event
+        let s1 = &compaction.events[1];
+        assert_eq!(s1.payload["entity_id"], "plant/s1");
+        assert_eq!(
+            s1.payload["properties"],
+            json!({"temp": 22, "unit": "C", "humidity": 40})
+        );
+        assert_eq!(s1.timestamp, T0 + 4);
+        assert_eq!(
+            s1.event_id.as_deref(),
+            Some("00000000000000000004-plant/s1")
+        );
+        assert_eq!(compaction.events[0].payload["entity_id"], "plant/s2");
+    }
+
+    #[test]
+    fn test_event_fields_come_from_latest_event() {
+        let mut first = event("s1", 1, json!({"a": 1}));
+        first.stream = "old".to_string();
+        let mut latest = event("s1", 2, json!({"b": 2}));
+        latest.source = "gateway-7".to_string();
+        latest.key = Some("s1".to_string());
+        latest.schema = Some("v2".to_string());
+
+        let compaction = assert_equivalent(vec![first, latest]);
+        let event = &compaction.events[0];
+        assert_eq!(event.stream, "sensors");
+        assert_eq!(event.source, "gateway-7");
+        assert_eq!(event.key.as_deref(), Some("s1"));
+        assert_eq!(event.schema.as_deref(), Some("v2"));
+        assert_eq!(event.received_at, Some(event.timestamp));
+    }
+
+    #[test]
+    fn test_stale_events_are_discarded() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 10, json!({"temp": 20})),
+            // Arrives late: the engine discards it
+            event("s1", 5, json!({"temp": 99, "extra": true})),
+        ]);
+        assert_eq!(
+            compaction.events[0].payload["properties"],
+            json!({"temp": 20})
+        );
+        assert_eq!(compaction.events[0].timestamp, T0 + 10);
+    }
+
+    #[test]
+    fn test_forced_stale_event_applies_but_keeps_ordering() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 10, json!({"temp": 20})),
+            with(event("s1", 5, json!({"temp": 15})), "force", json!(true)),
+            // Still older than the newest applied event
+            event("s1", 7, json!({"temp": 99})),
+        ]);
+        let event = &compaction.events[0];
+        assert_eq!(event.payload["properties"], json!({"temp": 15}));
+        assert_eq!(event.timestamp, T0 + 10);
+    }
+
+    #[test]
+    fn test_producer_sequence_orders_events() {
+        let compaction = assert_equivalent(vec![
+            with(event("s1", 10, json!({"v": "b"})), "sequence", json!(2)),
+            // Later timestamp, earlier producer sequence: stale
+            with(event("s1", 20, json!({"v": "a"})), "sequence", json!(1)),
+            with(event("s1", 5, json!({"v": "c"})), "sequence", json!(3)),
+        ]);
+        let event = &compaction.events[0];
+        assert_eq!(event.payload["properties"], json!({"v": "c"}));
+        assert_eq!(event.payload["sequence"], 3);
+    }
+
+    #[test]
+    fn test_unsets_remove_properties() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 1, json!({"a": 1, "b": 2, "c": 3})),
+            event("s1", 2, json!({"a": {"__unset__": true}})),
+            with(
+                event("s1", 3, json!({"b": null})),
+                "null_unsets",
+                json!(true),
+            ),
+            // Without the opt-in, null is a value
+            event("s1", 4, json!({"d": null})),
+        ]);
+        assert_eq!(
+            compaction.events[0].payload["properties"],
+            json!({"c": 3, "d": null})
+        );
+    }
+
+    #[test]
+    fn test_unsets_alone_create_nothing() {
+        let compaction = assert_equivalent(vec![
+            event("ghost", 1, json!({"a": {"__unset__": true}})),
+            event("s1", 2, json!({"a": 1})),
+            event("s1", 3, json!({"a": {"__unset__": true}})),
+        ]);
+        // s1 is left without properties: nothing to write
+        assert!(compaction.events.is_empty());
+        assert_eq!(compaction.entities, 0);
+    }
+
+    #[test]
+    fn test_deleted_entity_keeps_state_for_undelete() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 1, json!({"a": 1})),
+            event("s2", 2, json!({"b": 2})),
+            event("s1", 3, json!({"a": 2, "c": 3})),
+            tombstone("s1", 4),
+        ]);
+        assert_eq!(compaction.entities, 1);
+        assert_eq!(compaction.deleted, 1);
+        assert_eq!(compaction.events.len(), 3);
+        assert_eq!(
+            compaction.events[1].payload["properties"],
+            json!({"a": 2, "c": 3})
+        );
+        let tombstone = &compaction.events[2];
+        assert_eq!(tombstone.payload["properties"]["__deleted__"], true);
+        assert_eq!(tombstone.payload["properties"]["__deleted_at__"], T0 + 4);
+    }
+
+    #[test]
+    fn test_recreated_entity_drops_earlier_state() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 1, json!({"old": 1})),
+            tombstone("s1", 2),
+            // Older than the event before the tombstone, but the entity is new
+            event("s1", 0, json!({"new": 1})),
+        ]);
+        assert_eq!(compaction.events.len(), 1);
+        assert_eq!(compaction.deleted, 0);
+        assert_eq!(
+            compaction.events[0].payload["properties"],
+            json!({"new": 1})
+        );
+    }
+
+    #[test]
+    fn test_tombstone_for_missing_entity_is_dropped() {
+        let compaction = assert_equivalent(vec![
+            tombstone("nobody", 1),
+            event("s1", 2, json!({"a": 1})),
+        ]);
+        assert_eq!(compaction.events.len(), 1);
+        assert_eq!(compaction.deleted, 0);
+    }
+
+    #[test]
+    fn test_repeated_tombstone_keeps_deleted_state() {
+        let compaction = assert_equivalent(vec![
+            event("s1", 1, json!({"a": 1})),
+            tombstone("s1", 2),
+            tombstone("s1", 3),
+        ]);
+        assert_eq!(compaction.deleted, 1);
+        assert_eq!(compaction.events.len(), 2);
+        assert_eq!(
+            compaction.events[1].payload["properties"]["__deleted_at__"],
+            T0 + 2
+        );
+    }
+
+    #[test]
+    fn test_old_deletions_are_dropped() {
+        let events = sequenced(vec![
+            event("s1", 1, json!({"a": 1})),
+            tombstone("s1", 2),
+            event("s2", 3, json!({"a": 1})),
+            tombstone("s2", 1_000),
+        ]);
+        let rules = CompactionRules {
+            keep_deleted_since: DateTime::from_timestamp_millis(T0 + 500),
+            ..CompactionRules::default()
+        };
+        let compaction = compact(events, rules);
+        assert_eq!(compaction.deleted, 1);
+        let ids: Vec<_> = compaction
+            .events
+            .iter()
+            .map(|event| event.payload["entity_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["s2", "s2"]);
+    }
+
+    #[test]
+    fn test_undelete_after_tombstone() {
+        let mut restore = event("s1", 3, json!({"a": 1}));
+        restore.payload["force"] = json!(true);
+        let compaction = assert_equivalent(vec![
+            event("s1", 1, json!({"a": 1})),
+            tombstone("s1", 2),
+            restore,
+        ]);
+        assert_eq!(compaction.deleted, 0);
+        assert_eq!(compaction.entities, 1);
+    }
+
+    #[test]
+    fn test_messages_quotas_and_mapped_streams_kept_as_published() {
+        let mut message = event("x", 1, json!({}));
+        message.stream = MESSAGES_STREAM.to_string();
+        message.payload = json!({"from": "a", "to": "b", "body": "hi"});
+        let mut quota = event("x", 2, json!({}));
+        quota.stream = QUOTAS_STREAM.to_string();
+        quota.payload = json!({"namespace": "acme", "max_entities": 10});
+        let mut mapped = event("x", 3, json!({}));
+        mapped.stream = "legacy".to_string();
+        mapped.payload = json!({"device": "d1", "reading": 4});
+
+        let rules = CompactionRules {
+            mapped_streams: HashSet::from(["legacy".to_string()]),
+            ..CompactionRules::default()
+        };
+        let compaction = compact(
+            sequenced(vec![
+                message.clone(),
+                event("s1", 4, json!({"a": 1})),
+                quota.clone(),
+                mapped.clone(),
+                message,
+                event("s1", 5, json!({"a": 2})),
+            ]),
+            rules,
+        );
+        assert_eq!(compaction.kept_as_published, 4);
+        let streams: Vec<_> = compaction
+            .events
+            .iter()
+            .map(|e| e.stream.as_str())
+            .collect();
+        assert_eq!(
+            streams,
+            [
+                MESSAGES_STREAM,
+                QUOTAS_STREAM,
+                "legacy",
+                MESSAGES_STREAM,
+                "sensors"
+            ]
+        );
+        assert_eq!(compaction.events[2].payload, mapped.payload);
+    }
+
+    #[test]
+    fn test_events_without_envelope_are_dropped() {
+        let mut no_properties = event("s1", 1, json!({}));
+        no_properties.payload = json!({"entity_id": "s1"});
+        let mut no_entity = event("s1", 2, json!({}));
+        no_entity.payload = json!({"properties": {"a": 1}});
+        let compaction = compact(
+            sequenced(vec![no_properties, no_entity]),
+            CompactionRules::default(),
+        );
+        assert_eq!(compaction.events_read, 2);
+        assert!(compaction.events.is_empty());
+    }
+
+    #[test]
+    fn test_entity_states() {
+        let mut fold = EventFold::new(CompactionRules::default());
+        for (sequence, event) in sequenced(vec![
+            event("s1", 1, json!({"a": 1})),
+            event("s2", 2, json!({"b": 1})),
+            tombstone("s2", 3),
+        ]) {
+            fold.apply(sequence, event);
+        }
+        let states: HashMap<_, _> = fold
+            .entity_states()
+            .map(|(id, properties)| (id.to_string(), properties.cloned()))
+            .collect();
+        assert_eq!(
+            states["s1"],
+            Some(BTreeMap::from([("a".to_string(), json!(1))]))
+        );
+        assert_eq!(states["s2"], None);
+        assert_eq!(fold.events_read(), 3);
+    }
+
+    /// Deterministic pseudo-random event sequences over a few entities, with
+    /// out-of-order timestamps, forced events, unsets and deletions
+    #[test]
+    fn test_random_sequences_replay_the_same() {
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        for _ in 0..200 {
+            let mut events = Vec::new();
+            for i in 0..40 {
+                let entity = format!("e{}", next(4));
+                let at = i * 10 - next(30) as i64;
+                let event = match next(10) {
+                    0 => tombstone(&entity, at),
+                    1 => event(
+                        &entity,
+                        at,
+                        json!({format!("p{}", next(3)): {"__unset__": true}}),
+                    ),
+                    2 => with(
+                        event(&entity, at, json!({"p0": next(5)})),
+                        "force",
+                        json!(true),
+                    ),
+                    _ => event(
+                        &entity,
+                        at,
+                        json!({format!("p{}", next(3)): next(5), format!("p{}", next(3)): next(5)}),
+                    ),
+                };
+                events.push(event);
+            }
+            assert_equivalent(events);
+        }
+    }
+}
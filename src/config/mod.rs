@@ -1,14 +1,27 @@
 pub mod runtime;
-pub use runtime::{new_runtime_config, RuntimeConfig, SharedRuntimeConfig};
+#[cfg(feature = "http-api")]
+pub use runtime::new_runtime_config_from_file;
+pub use runtime::{
+    new_runtime_config, ConfigChanged, ConfigSource, ConfigValidationError, RuntimeConfig,
+    RuntimeConfigHandle, RuntimeConfigUpdate, SharedRuntimeConfig,
+};
 
 use serde::Deserialize;
 
 // Re-export existing config types
+pub use crate::leader::config::LeaderConfig;
 pub use crate::nats::NatsConfig;
-pub use crate::snapshot::config::SnapshotConfig;
+// The server's sections (FluxConfig) come with the HTTP API build
+#[cfg(feature = "http-api")]
+pub use crate::{
+    archive::config::ArchiveConfig, compaction::CompactionConfig, federation::FederationConfig,
+    http_client::HttpClientConfig, journal::JournalConfig, snapshot::config::SnapshotConfig,
+    snapshot::recovery::OnMismatch, standby::config::StandbyConfig,
+};
 
 /// Complete Flux configuration
-#[derive(Debug, Clone, Deserialize)]
+#[cfg(feature = "http-api")]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct FluxConfig {
     #[serde(default)]
     pub snapshot: SnapshotConfig,
@@ -20,23 +33,46 @@ pub struct FluxConfig {
     pub metrics: MetricsConfig,
     #[serde(default)]
     pub api: ApiConfig,
+    #[serde(default)]
+    pub state: StateConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+    #[serde(default)]
+    pub leader: LeaderConfig,
+    #[serde(default)]
+    pub standby: StandbyConfig,
+    #[serde(default)]
+    pub http: HttpClientConfig,
+    #[serde(default)]
+    pub federation: FederationConfig,
+    #[serde(default)]
+    pub journal: JournalConfig,
 }
 
 /// Recovery configuration
+#[cfg(feature = "http-api")]
 #[derive(Debug, Clone, Deserialize)]
 pub struct RecoveryConfig {
     #[serde(default = "default_auto_recover")]
     pub auto_recover: bool,
+    /// What to do when the latest snapshot was taken from a different stream
+    #[serde(default)]
+    pub on_mismatch: OnMismatch,
 }
 
+#[cfg(feature = "http-api")]
 fn default_auto_recover() -> bool {
     true
 }
 
+#[cfg(feature = "http-api")]
 impl Default for RecoveryConfig {
     fn default() -> Self {
         Self {
             auto_recover: default_auto_recover(),
+            on_mismatch: OnMismatch::default(),
         }
     }
 }
@@ -50,6 +86,15 @@ pub struct MetricsConfig {
     /// Time window for "active publisher" tracking (seconds)
     #[serde(default = "default_active_publisher_window")]
     pub active_publisher_window_seconds: i64,
+    /// Distinct event sources tracked; beyond it new sources share one bucket
+    #[serde(default = "default_max_tracked_sources")]
+    pub max_tracked_sources: usize,
+    /// Publishers listed by the publishers API; the least recently seen is dropped
+    #[serde(default = "default_max_tracked_publishers")]
+    pub max_tracked_publishers: usize,
+    /// Also keep each publisher as a `_system/publisher/{source}` entity
+    #[serde(default)]
+    pub publisher_entities: bool,
 }
 
 fn default_broadcast_interval() -> u64 {
@@ -60,64 +105,158 @@ fn default_active_publisher_window() -> i64 {
     10
 }
 
+fn default_max_tracked_sources() -> usize {
+    crate::state::DEFAULT_MAX_TRACKED_SOURCES
+}
+
+fn default_max_tracked_publishers() -> usize {
+    crate::state::DEFAULT_MAX_PUBLISHERS
+}
+
 impl Default for MetricsConfig {
     fn default() -> Self {
         Self {
             broadcast_interval_seconds: default_broadcast_interval(),
             active_publisher_window_seconds: default_active_publisher_window(),
+            max_tracked_sources: default_max_tracked_sources(),
+            max_tracked_publishers: default_max_tracked_publishers(),
+            publisher_entities: false,
         }
     }
 }
 
 /// API configuration (Phase 4A)
+#[cfg(feature = "http-api")]
 #[derive(Debug, Clone, Deserialize)]
 pub struct ApiConfig {
     /// Maximum entities allowed in batch delete operation
     #[serde(default = "default_max_batch_delete")]
     pub max_batch_delete: usize,
+    /// Serve Swagger UI at /api/docs (the OpenAPI JSON is always served)
+    #[serde(default)]
+    pub docs_enabled: bool,
+    /// Property values larger than this (serialized) are replaced by a marker
+    /// in WebSocket messages (0 = no limit)
+    #[serde(default = "default_ws_max_value_bytes")]
+    pub ws_max_value_bytes: usize,
+    /// POST /api/auth/introspect requests allowed per client IP per minute
+    /// (0 = no limit)
+    #[serde(default = "default_introspect_per_ip_per_minute")]
+    pub introspect_per_ip_per_minute: u64,
+    /// Hosts watch webhooks may reach even though they resolve to loopback,
+    /// link-local or private addresses
+    #[serde(default)]
+    pub watch_webhook_allowed_hosts: Vec<String>,
 }
 
+#[cfg(feature = "http-api")]
 fn default_max_batch_delete() -> usize {
     10000
 }
 
+#[cfg(feature = "http-api")]
+fn default_ws_max_value_bytes() -> usize {
+    crate::subscription::DEFAULT_MAX_VALUE_BYTES
+}
+
+#[cfg(feature = "http-api")]
+fn default_introspect_per_ip_per_minute() -> u64 {
+    crate::api::introspect::DEFAULT_INTROSPECT_PER_IP_PER_MINUTE
+}
+
+#[cfg(feature = "http-api")]
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
             max_batch_delete: default_max_batch_delete(),
+            docs_enabled: false,
+            ws_max_value_bytes: default_ws_max_value_bytes(),
+            introspect_per_ip_per_minute: default_introspect_per_ip_per_minute(),
+            watch_webhook_allowed_hosts: Vec::new(),
         }
     }
 }
 
-impl Default for FluxConfig {
+/// State engine configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateConfig {
+    /// Number of state broadcast shards (updates are routed by hash of entity_id)
+    #[serde(default = "default_broadcast_shards")]
+    pub broadcast_shards: usize,
+    /// Max properties a single entity may hold; updates beyond it are rejected
+    #[serde(default = "default_max_properties_per_entity")]
+    pub max_properties_per_entity: usize,
+    /// How long deleted entities can be undeleted (0 disables the trash)
+    #[serde(default = "default_trash_retention_seconds")]
+    pub trash_retention_seconds: u64,
+    /// Most deleted entities kept; the oldest are dropped first
+    #[serde(default = "default_max_trash_entries")]
+    pub max_trash_entries: usize,
+    /// Agent messages kept per recipient for `GET /api/messages` (0 = none)
+    #[serde(default = "default_max_messages_per_recipient")]
+    pub max_messages_per_recipient: usize,
+    /// Live agent messages, and property changes, kept for
+    /// `GET /api/state/recent` and WebSocket `include_recent` (0 = none)
+    #[serde(default = "default_recent_activity_capacity")]
+    pub recent_activity_capacity: usize,
+}
+
+fn default_broadcast_shards() -> usize {
+    crate::state::DEFAULT_BROADCAST_SHARDS
+}
+
+fn default_max_properties_per_entity() -> usize {
+    crate::state::DEFAULT_MAX_PROPERTIES_PER_ENTITY
+}
+
+fn default_trash_retention_seconds() -> u64 {
+    crate::state::DEFAULT_TRASH_RETENTION_SECONDS
+}
+
+fn default_max_trash_entries() -> usize {
+    crate::state::DEFAULT_TRASH_CAPACITY
+}
+
+fn default_max_messages_per_recipient() -> usize {
+    crate::state::DEFAULT_MESSAGES_PER_RECIPIENT
+}
+
+fn default_recent_activity_capacity() -> usize {
+    crate::state::DEFAULT_RECENT_ACTIVITY_CAPACITY
+}
+
+impl Default for StateConfig {
     fn default() -> Self {
         Self {
-            snapshot: SnapshotConfig::default(),
-            nats: NatsConfig::default(),
-            recovery: RecoveryConfig::default(),
-            metrics: MetricsConfig::default(),
-            api: ApiConfig::default(),
+            broadcast_shards: default_broadcast_shards(),
+            max_properties_per_entity: default_max_properties_per_entity(),
+            trash_retention_seconds: default_trash_retention_seconds(),
+            max_trash_entries: default_max_trash_entries(),
+            max_messages_per_recipient: default_max_messages_per_recipient(),
+            recent_activity_capacity: default_recent_activity_capacity(),
         }
     }
 }
 
 /// Load configuration from TOML file
+#[cfg(feature = "http-api")]
 pub fn load_config(path: &str) -> Result<FluxConfig, Box<dyn std::error::Error>> {
     let contents = std::fs::read_to_string(path)?;
     let config: FluxConfig = toml::from_str(&contents)?;
     Ok(config)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "http-api"))]
 mod tests {
     use super::*;
 
     #[test]
     fn test_default_config() {
         let config = FluxConfig::default();
-        assert_eq!(config.snapshot.enabled, true);
+        assert!(config.snapshot.enabled);
         assert_eq!(config.snapshot.interval_minutes, 5);
         assert_eq!(config.nats.stream_name, "FLUX_EVENTS");
+        assert_eq!(config.nats.max_in_flight, 512);
         assert_eq!(config.metrics.broadcast_interval_seconds, 2);
         assert_eq!(config.api.max_batch_delete, 10000);
     }
@@ -134,9 +273,11 @@ mod tests {
             [nats]
             url = "nats://example.com:4222"
             stream_name = "TEST_STREAM"
+            max_in_flight = 64
 
             [recovery]
             auto_recover = false
+            on_mismatch = "replay_all"
 
             [metrics]
             broadcast_interval_seconds = 5
@@ -144,14 +285,18 @@ mod tests {
 
             [api]
             max_batch_delete = 5000
+            watch_webhook_allowed_hosts = ["hooks.internal"]
         "#;
 
         let config: FluxConfig = toml::from_str(toml).unwrap();
         assert_eq!(config.snapshot.interval_minutes, 10);
         assert_eq!(config.nats.url, "nats://example.com:4222");
-        assert_eq!(config.recovery.auto_recover, false);
+        assert_eq!(config.nats.max_in_flight, 64);
+        assert!(!config.recovery.auto_recover);
+        assert_eq!(config.recovery.on_mismatch, OnMismatch::ReplayAll);
         assert_eq!(config.metrics.broadcast_interval_seconds, 5);
         assert_eq!(config.api.max_batch_delete, 5000);
+        assert_eq!(config.api.watch_webhook_allowed_hosts, ["hooks.internal"]);
     }
 
     #[test]
@@ -164,7 +309,63 @@ mod tests {
 
         let config: FluxConfig = toml::from_str(toml).unwrap();
         assert_eq!(config.metrics.broadcast_interval_seconds, 3);
-        assert_eq!(config.snapshot.enabled, true); // Default
+        assert!(config.snapshot.enabled); // Default
         assert_eq!(config.api.max_batch_delete, 10000); // Default
+        assert_eq!(config.state.broadcast_shards, 8); // Default
+        assert_eq!(config.recovery.on_mismatch, OnMismatch::Fail); // Default
+    }
+
+    #[test]
+    fn test_state_config_deserialization() {
+        let toml = r#"
+            [state]
+            broadcast_shards = 16
+        "#;
+
+        let config: FluxConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.state.broadcast_shards, 16);
+    }
+
+    #[test]
+    fn test_archive_config_deserialization() {
+        assert!(!FluxConfig::default().archive.enabled);
+
+        let toml = r#"
+            [archive]
+            enabled = true
+            min_age_hours = 72
+
+            [archive.s3]
+            endpoint = "http://minio:9000"
+            bucket = "flux"
+        "#;
+
+        let config: FluxConfig = toml::from_str(toml).unwrap();
+        assert!(config.archive.enabled);
+        assert_eq!(config.archive.min_age_hours, 72);
+        assert_eq!(config.archive.interval_minutes, 60); // Default
+        let s3 = config.archive.s3.unwrap();
+        assert_eq!(s3.bucket, "flux");
+        assert_eq!(s3.region, "us-east-1"); // Default
+    }
+
+    #[test]
+    fn test_leader_config_deserialization() {
+        let defaults = FluxConfig::default().leader;
+        assert!(!defaults.enabled);
+        assert_eq!(defaults.ttl().as_secs(), 15);
+
+        let toml = r#"
+            [leader]
+            enabled = true
+            instance_id = "flux-1"
+            ttl_seconds = 1
+        "#;
+
+        let config: FluxConfig = toml::from_str(toml).unwrap();
+        assert!(config.leader.enabled);
+        assert_eq!(config.leader.instance_id(), "flux-1");
+        assert_eq!(config.leader.ttl().as_secs(), 3); // Clamped
+        assert_eq!(config.leader.bucket, "flux_leader"); // Default
     }
 }
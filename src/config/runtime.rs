@@ -1,14 +1,74 @@
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, RwLock};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+#[cfg(feature = "http-api")]
+use super::FluxConfig;
+use crate::entity::IdNormalization;
+use crate::event::{is_valid_stream_name, EventLimits, SecondsTimestampPolicy, TimestampRules};
+use crate::namespace::NamespaceRegistry;
+use crate::state::DeadbandRule;
 
 /// Runtime-configurable limits. Changes via PUT /api/admin/config take effect immediately
 /// without restart.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
 pub struct RuntimeConfig {
     pub rate_limit_enabled: bool,
     pub rate_limit_per_namespace_per_minute: u64,
     pub body_size_limit_single_bytes: usize,
     pub body_size_limit_batch_bytes: usize,
+    /// How often the metrics broadcaster publishes to WebSocket clients
+    pub metrics_broadcast_interval_seconds: u64,
+    /// Time window for "active publisher" tracking
+    pub active_publisher_window_seconds: i64,
+    /// Interval between automatic snapshots
+    pub snapshot_interval_minutes: u64,
+    /// Entities not updated within this many seconds are deleted (0 = disabled)
+    pub entity_ttl_seconds: u64,
+    /// Max serialized size of an event payload
+    pub max_payload_bytes: usize,
+    /// Max properties in a single event
+    pub max_properties_per_event: usize,
+    /// Max property name length, in bytes
+    pub max_property_name_length: usize,
+    /// Max length of any string value in an event payload, in bytes
+    pub max_string_value_length: usize,
+    /// How far an event timestamp may be ahead of server time, in seconds
+    pub max_timestamp_skew_seconds: u64,
+    /// Reject or convert timestamps that look like seconds instead of milliseconds
+    pub seconds_timestamp_policy: SecondsTimestampPolicy,
+    /// Entity ID normalization by namespace; `*` covers IDs without a namespace
+    /// and namespaces not listed
+    #[schema(example = json!({"*": "encode", "legacy": "off"}))]
+    pub entity_id_normalization: BTreeMap<String, IdNormalization>,
+    /// Max concurrent WebSocket connections
+    pub ws_max_connections: usize,
+    /// Max concurrent WebSocket connections from one client IP
+    pub ws_max_connections_per_ip: usize,
+    /// Subscribe/unsubscribe messages one WebSocket connection may send per minute
+    pub ws_control_messages_per_minute: u64,
+    /// Max distinct subscriptions one WebSocket connection may hold
+    pub ws_max_subscriptions_per_connection: usize,
+    /// Deadband for numeric properties by entity namespace
+    #[schema(example = json!({"sensors": {"min_change": 0.1, "keepalive_seconds": 300}}))]
+    pub deadband_by_namespace: BTreeMap<String, DeadbandRule>,
+    /// Deadband for numeric properties by event stream; wins over the namespace's
+    #[schema(example = json!({"plant.telemetry": {"min_change_percent": 0.5}}))]
+    pub deadband_by_stream: BTreeMap<String, DeadbandRule>,
+    /// Properties whose values are indexed for equality queries
+    #[schema(example = json!(["status", "type"]))]
+    pub indexed_properties: Vec<String>,
+    /// How long `GET /api/state/entities` responses are cached, in milliseconds (0 = off)
+    pub query_cache_ttl_ms: u64,
+    /// Most responses the query cache holds; the least recently used go first
+    pub query_cache_max_entries: usize,
+    /// CloudEvents attribute the Flux stream is taken from: `type` or an extension
+    #[schema(example = "type")]
+    pub cloudevents_stream_attribute: String,
 }
 
 impl Default for RuntimeConfig {
@@ -16,44 +76,962 @@ impl Default for RuntimeConfig {
         Self {
             rate_limit_enabled: true,
             rate_limit_per_namespace_per_minute: 10_000,
-            body_size_limit_single_bytes: 1_048_576,   // 1 MB
-            body_size_limit_batch_bytes: 10_485_760,   // 10 MB
+            body_size_limit_single_bytes: 1_048_576, // 1 MB
+            body_size_limit_batch_bytes: 10_485_760, // 10 MB
+            metrics_broadcast_interval_seconds: 2,
+            active_publisher_window_seconds: 10,
+            snapshot_interval_minutes: 5,
+            entity_ttl_seconds: 0,
+            max_payload_bytes: 262_144, // 256 KB
+            max_properties_per_event: 256,
+            max_property_name_length: 256,
+            max_string_value_length: 65_536, // 64 KB
+            max_timestamp_skew_seconds: 300,
+            seconds_timestamp_policy: SecondsTimestampPolicy::Convert,
+            entity_id_normalization: BTreeMap::new(),
+            ws_max_connections: 10_000,
+            ws_max_connections_per_ip: 100,
+            ws_control_messages_per_minute: 600,
+            ws_max_subscriptions_per_connection: 1_000,
+            deadband_by_namespace: BTreeMap::new(),
+            deadband_by_stream: BTreeMap::new(),
+            indexed_properties: Vec::new(),
+            query_cache_ttl_ms: 1_000,
+            query_cache_max_entries: 1_000,
+            cloudevents_stream_attribute: "type".to_string(),
         }
     }
 }
 
+/// Most properties `indexed_properties` may list
+const MAX_INDEXED_PROPERTIES: usize = 64;
+
+/// Where a runtime config field's effective value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfigSource {
+    Default,
+    Env,
+    File,
+    AdminApi,
+}
+
+/// Names of every runtime config field, in declaration order
+pub const RUNTIME_CONFIG_FIELDS: &[&str] = &[
+    "rate_limit_enabled",
+    "rate_limit_per_namespace_per_minute",
+    "body_size_limit_single_bytes",
+    "body_size_limit_batch_bytes",
+    "metrics_broadcast_interval_seconds",
+    "active_publisher_window_seconds",
+    "snapshot_interval_minutes",
+    "entity_ttl_seconds",
+    "max_payload_bytes",
+    "max_properties_per_event",
+    "max_property_name_length",
+    "max_string_value_length",
+    "max_timestamp_skew_seconds",
+    "seconds_timestamp_policy",
+    "entity_id_normalization",
+    "ws_max_connections",
+    "ws_max_connections_per_ip",
+    "ws_control_messages_per_minute",
+    "ws_max_subscriptions_per_connection",
+    "deadband_by_namespace",
+    "deadband_by_stream",
+    "indexed_properties",
+    "query_cache_ttl_ms",
+    "query_cache_max_entries",
+    "cloudevents_stream_attribute",
+];
+
 impl RuntimeConfig {
     /// Build from env vars, falling back to defaults.
     pub fn from_env() -> Self {
         let mut cfg = Self::default();
+        cfg.apply_env(&mut BTreeMap::new());
+        cfg
+    }
+
+    /// Override fields from FLUX_* env vars, recording each one set as `Env`.
+    fn apply_env(&mut self, sources: &mut BTreeMap<&'static str, ConfigSource>) {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse::<T>().ok())
+        }
+
+        if let Some(b) = var("FLUX_RATE_LIMIT_ENABLED") {
+            self.rate_limit_enabled = b;
+            sources.insert("rate_limit_enabled", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_RATE_LIMIT_PER_NAMESPACE_PER_MINUTE") {
+            self.rate_limit_per_namespace_per_minute = n;
+            sources.insert("rate_limit_per_namespace_per_minute", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_BODY_SIZE_LIMIT_SINGLE_BYTES") {
+            self.body_size_limit_single_bytes = n;
+            sources.insert("body_size_limit_single_bytes", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_BODY_SIZE_LIMIT_BATCH_BYTES") {
+            self.body_size_limit_batch_bytes = n;
+            sources.insert("body_size_limit_batch_bytes", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_METRICS_BROADCAST_INTERVAL_SECONDS") {
+            self.metrics_broadcast_interval_seconds = n;
+            sources.insert("metrics_broadcast_interval_seconds", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_ACTIVE_PUBLISHER_WINDOW_SECONDS") {
+            self.active_publisher_window_seconds = n;
+            sources.insert("active_publisher_window_seconds", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_SNAPSHOT_INTERVAL_MINUTES") {
+            self.snapshot_interval_minutes = n;
+            sources.insert("snapshot_interval_minutes", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_ENTITY_TTL_SECONDS") {
+            self.entity_ttl_seconds = n;
+            sources.insert("entity_ttl_seconds", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_MAX_PAYLOAD_BYTES") {
+            self.max_payload_bytes = n;
+            sources.insert("max_payload_bytes", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_MAX_PROPERTIES_PER_EVENT") {
+            self.max_properties_per_event = n;
+            sources.insert("max_properties_per_event", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_MAX_PROPERTY_NAME_LENGTH") {
+            self.max_property_name_length = n;
+            sources.insert("max_property_name_length", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_MAX_STRING_VALUE_LENGTH") {
+            self.max_string_value_length = n;
+            sources.insert("max_string_value_length", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_MAX_TIMESTAMP_SKEW_SECONDS") {
+            self.max_timestamp_skew_seconds = n;
+            sources.insert("max_timestamp_skew_seconds", ConfigSource::Env);
+        }
+        if let Some(p) = var("FLUX_SECONDS_TIMESTAMP_POLICY") {
+            self.seconds_timestamp_policy = p;
+            sources.insert("seconds_timestamp_policy", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_WS_MAX_CONNECTIONS") {
+            self.ws_max_connections = n;
+            sources.insert("ws_max_connections", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_WS_MAX_CONNECTIONS_PER_IP") {
+            self.ws_max_connections_per_ip = n;
+            sources.insert("ws_max_connections_per_ip", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_WS_CONTROL_MESSAGES_PER_MINUTE") {
+            self.ws_control_messages_per_minute = n;
+            sources.insert("ws_control_messages_per_minute", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_WS_MAX_SUBSCRIPTIONS_PER_CONNECTION") {
+            self.ws_max_subscriptions_per_connection = n;
+            sources.insert("ws_max_subscriptions_per_connection", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_QUERY_CACHE_TTL_MS") {
+            self.query_cache_ttl_ms = n;
+            sources.insert("query_cache_ttl_ms", ConfigSource::Env);
+        }
+        if let Some(n) = var("FLUX_QUERY_CACHE_MAX_ENTRIES") {
+            self.query_cache_max_entries = n;
+            sources.insert("query_cache_max_entries", ConfigSource::Env);
+        }
+        if let Some(attribute) = var("FLUX_CLOUDEVENTS_STREAM_ATTRIBUTE") {
+            self.cloudevents_stream_attribute = attribute;
+            sources.insert("cloudevents_stream_attribute", ConfigSource::Env);
+        }
+    }
+
+    /// Seed fields that also exist in the config file, recording non-default ones as `File`.
+    #[cfg(feature = "http-api")]
+    fn apply_file(
+        &mut self,
+        file: &FluxConfig,
+        sources: &mut BTreeMap<&'static str, ConfigSource>,
+    ) {
+        let defaults = Self::default();
+
+        self.metrics_broadcast_interval_seconds = file.metrics.broadcast_interval_seconds;
+        if self.metrics_broadcast_interval_seconds != defaults.metrics_broadcast_interval_seconds {
+            sources.insert("metrics_broadcast_interval_seconds", ConfigSource::File);
+        }
+        self.active_publisher_window_seconds = file.metrics.active_publisher_window_seconds;
+        if self.active_publisher_window_seconds != defaults.active_publisher_window_seconds {
+            sources.insert("active_publisher_window_seconds", ConfigSource::File);
+        }
+        self.snapshot_interval_minutes = file.snapshot.interval_minutes;
+        if self.snapshot_interval_minutes != defaults.snapshot_interval_minutes {
+            sources.insert("snapshot_interval_minutes", ConfigSource::File);
+        }
+    }
 
-        if let Ok(v) = std::env::var("FLUX_RATE_LIMIT_ENABLED") {
-            if let Ok(b) = v.parse::<bool>() {
-                cfg.rate_limit_enabled = b;
+    /// Check every field against its allowed range.
+    pub fn validate(&self) -> Result<(), ConfigValidationError> {
+        check_range(
+            "rate_limit_per_namespace_per_minute",
+            self.rate_limit_per_namespace_per_minute,
+            1,
+            10_000_000,
+        )?;
+        check_range(
+            "body_size_limit_single_bytes",
+            self.body_size_limit_single_bytes as u64,
+            1_024,
+            104_857_600,
+        )?;
+        check_range(
+            "body_size_limit_batch_bytes",
+            self.body_size_limit_batch_bytes as u64,
+            1_024,
+            1_073_741_824,
+        )?;
+        check_range(
+            "metrics_broadcast_interval_seconds",
+            self.metrics_broadcast_interval_seconds,
+            1,
+            300,
+        )?;
+        check_range(
+            "active_publisher_window_seconds",
+            self.active_publisher_window_seconds.max(0) as u64,
+            1,
+            3_600,
+        )?;
+        check_range(
+            "snapshot_interval_minutes",
+            self.snapshot_interval_minutes,
+            1,
+            1_440,
+        )?;
+        check_range("entity_ttl_seconds", self.entity_ttl_seconds, 0, 31_536_000)?;
+        check_range(
+            "max_payload_bytes",
+            self.max_payload_bytes as u64,
+            1_024,
+            104_857_600,
+        )?;
+        check_range(
+            "max_properties_per_event",
+            self.max_properties_per_event as u64,
+            1,
+            100_000,
+        )?;
+        check_range(
+            "max_property_name_length",
+            self.max_property_name_length as u64,
+            1,
+            65_536,
+        )?;
+        check_range(
+            "max_string_value_length",
+            self.max_string_value_length as u64,
+            1,
+            104_857_600,
+        )?;
+        check_range(
+            "max_timestamp_skew_seconds",
+            self.max_timestamp_skew_seconds,
+            0,
+            31_536_000,
+        )?;
+        check_range(
+            "ws_max_connections",
+            self.ws_max_connections as u64,
+            1,
+            1_000_000,
+        )?;
+        check_range(
+            "ws_max_connections_per_ip",
+            self.ws_max_connections_per_ip as u64,
+            1,
+            1_000_000,
+        )?;
+        check_range(
+            "ws_control_messages_per_minute",
+            self.ws_control_messages_per_minute,
+            1,
+            1_000_000,
+        )?;
+        check_range(
+            "ws_max_subscriptions_per_connection",
+            self.ws_max_subscriptions_per_connection as u64,
+            1,
+            1_000_000,
+        )?;
+        for namespace in self.entity_id_normalization.keys() {
+            if namespace != "*" && NamespaceRegistry::validate_name(namespace).is_err() {
+                return Err(ConfigValidationError {
+                    field: "entity_id_normalization",
+                    message: format!(
+                        "entity_id_normalization keys must be namespace names or \"*\" (got \"{}\")",
+                        namespace
+                    ),
+                });
             }
         }
-        if let Ok(v) = std::env::var("FLUX_RATE_LIMIT_PER_NAMESPACE_PER_MINUTE") {
-            if let Ok(n) = v.parse::<u64>() {
-                cfg.rate_limit_per_namespace_per_minute = n;
+        for (namespace, rule) in &self.deadband_by_namespace {
+            if NamespaceRegistry::validate_name(namespace).is_err() {
+                return Err(ConfigValidationError {
+                    field: "deadband_by_namespace",
+                    message: format!(
+                        "deadband_by_namespace keys must be namespace names (got \"{}\")",
+                        namespace
+                    ),
+                });
             }
+            check_deadband("deadband_by_namespace", namespace, rule)?;
         }
-        if let Ok(v) = std::env::var("FLUX_BODY_SIZE_LIMIT_SINGLE_BYTES") {
-            if let Ok(n) = v.parse::<usize>() {
-                cfg.body_size_limit_single_bytes = n;
+        for (stream, rule) in &self.deadband_by_stream {
+            if !is_valid_stream_name(stream) {
+                return Err(ConfigValidationError {
+                    field: "deadband_by_stream",
+                    message: format!(
+                        "deadband_by_stream keys must be stream names (got \"{}\")",
+                        stream
+                    ),
+                });
             }
+            check_deadband("deadband_by_stream", stream, rule)?;
         }
-        if let Ok(v) = std::env::var("FLUX_BODY_SIZE_LIMIT_BATCH_BYTES") {
-            if let Ok(n) = v.parse::<usize>() {
-                cfg.body_size_limit_batch_bytes = n;
+        check_range(
+            "indexed_properties",
+            self.indexed_properties.len() as u64,
+            0,
+            MAX_INDEXED_PROPERTIES as u64,
+        )?;
+        for property in &self.indexed_properties {
+            if property.is_empty() || property.len() > self.max_property_name_length {
+                return Err(ConfigValidationError {
+                    field: "indexed_properties",
+                    message: format!(
+                        "indexed_properties must be property names of 1 to {} bytes (got \"{}\")",
+                        self.max_property_name_length, property
+                    ),
+                });
             }
         }
+        check_range("query_cache_ttl_ms", self.query_cache_ttl_ms, 0, 60_000)?;
+        check_range(
+            "query_cache_max_entries",
+            self.query_cache_max_entries as u64,
+            1,
+            1_000_000,
+        )?;
+        // CloudEvents attribute names: lowercase letters and digits, at most 20
+        let attribute = &self.cloudevents_stream_attribute;
+        if attribute.is_empty()
+            || attribute.len() > 20
+            || !attribute
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        {
+            return Err(ConfigValidationError {
+                field: "cloudevents_stream_attribute",
+                message: format!(
+                    "cloudevents_stream_attribute must be a CloudEvents attribute name (got \"{}\")",
+                    attribute
+                ),
+            });
+        }
+        Ok(())
+    }
 
-        cfg
+    /// Per-event limits enforced at ingestion
+    pub fn event_limits(&self) -> EventLimits {
+        EventLimits {
+            max_payload_bytes: self.max_payload_bytes,
+            max_properties_per_event: self.max_properties_per_event,
+            max_property_name_length: self.max_property_name_length,
+            max_string_value_length: self.max_string_value_length,
+        }
+    }
+
+    /// Producer timestamp checks enforced at ingestion
+    pub fn timestamp_rules(&self) -> TimestampRules {
+        TimestampRules {
+            max_skew_seconds: self.max_timestamp_skew_seconds,
+            seconds_policy: self.seconds_timestamp_policy,
+        }
+    }
+
+    /// Normalization applied to `entity_id`, chosen by its namespace prefix
+    pub fn id_normalization_for(&self, entity_id: &str) -> IdNormalization {
+        let namespace = entity_id.split_once('/').map(|(namespace, _)| namespace);
+        namespace
+            .and_then(|namespace| self.entity_id_normalization.get(namespace))
+            .or_else(|| self.entity_id_normalization.get("*"))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn check_range(
+    field: &'static str,
+    value: u64,
+    min: u64,
+    max: u64,
+) -> Result<(), ConfigValidationError> {
+    if value < min || value > max {
+        return Err(ConfigValidationError {
+            field,
+            message: format!(
+                "{} must be between {} and {} (got {})",
+                field, min, max, value
+            ),
+        });
     }
+    Ok(())
 }
 
-pub type SharedRuntimeConfig = Arc<RwLock<RuntimeConfig>>;
+fn check_deadband(
+    field: &'static str,
+    key: &str,
+    rule: &DeadbandRule,
+) -> Result<(), ConfigValidationError> {
+    rule.validate().map_err(|detail| ConfigValidationError {
+        field,
+        message: format!("{}.{}: {}", field, key, detail),
+    })
+}
+
+/// Field-level validation failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
 
+/// Partial update body — only fields present in the request are changed.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[schema(example = json!({"rate_limit_per_namespace_per_minute": 5000, "entity_ttl_seconds": 86400}))]
+pub struct RuntimeConfigUpdate {
+    pub rate_limit_enabled: Option<bool>,
+    pub rate_limit_per_namespace_per_minute: Option<u64>,
+    pub body_size_limit_single_bytes: Option<usize>,
+    pub body_size_limit_batch_bytes: Option<usize>,
+    pub metrics_broadcast_interval_seconds: Option<u64>,
+    pub active_publisher_window_seconds: Option<i64>,
+    pub snapshot_interval_minutes: Option<u64>,
+    pub entity_ttl_seconds: Option<u64>,
+    pub max_payload_bytes: Option<usize>,
+    pub max_properties_per_event: Option<usize>,
+    pub max_property_name_length: Option<usize>,
+    pub max_string_value_length: Option<usize>,
+    pub max_timestamp_skew_seconds: Option<u64>,
+    pub seconds_timestamp_policy: Option<SecondsTimestampPolicy>,
+    /// Replaces the whole map
+    pub entity_id_normalization: Option<BTreeMap<String, IdNormalization>>,
+    pub ws_max_connections: Option<usize>,
+    pub ws_max_connections_per_ip: Option<usize>,
+    pub ws_control_messages_per_minute: Option<u64>,
+    pub ws_max_subscriptions_per_connection: Option<usize>,
+    /// Replaces the whole map
+    pub deadband_by_namespace: Option<BTreeMap<String, DeadbandRule>>,
+    /// Replaces the whole map
+    pub deadband_by_stream: Option<BTreeMap<String, DeadbandRule>>,
+    /// Replaces the whole list
+    pub indexed_properties: Option<Vec<String>>,
+    pub query_cache_ttl_ms: Option<u64>,
+    pub query_cache_max_entries: Option<usize>,
+    pub cloudevents_stream_attribute: Option<String>,
+}
+
+impl RuntimeConfigUpdate {
+    /// Apply present fields to `cfg`, returning the names of fields that were set.
+    fn apply_to(&self, cfg: &mut RuntimeConfig) -> Vec<&'static str> {
+        let mut set = Vec::new();
+        macro_rules! apply {
+            ($field:ident) => {
+                if let Some(v) = self.$field {
+                    cfg.$field = v;
+                    set.push(stringify!($field));
+                }
+            };
+        }
+        apply!(rate_limit_enabled);
+        apply!(rate_limit_per_namespace_per_minute);
+        apply!(body_size_limit_single_bytes);
+        apply!(body_size_limit_batch_bytes);
+        apply!(metrics_broadcast_interval_seconds);
+        apply!(active_publisher_window_seconds);
+        apply!(snapshot_interval_minutes);
+        apply!(entity_ttl_seconds);
+        apply!(max_payload_bytes);
+        apply!(max_properties_per_event);
+        apply!(max_property_name_length);
+        apply!(max_string_value_length);
+        apply!(max_timestamp_skew_seconds);
+        apply!(seconds_timestamp_policy);
+        apply!(ws_max_connections);
+        apply!(ws_max_connections_per_ip);
+        apply!(ws_control_messages_per_minute);
+        apply!(ws_max_subscriptions_per_connection);
+        apply!(query_cache_ttl_ms);
+        apply!(query_cache_max_entries);
+        if let Some(modes) = &self.entity_id_normalization {
+            cfg.entity_id_normalization = modes.clone();
+            set.push("entity_id_normalization");
+        }
+        if let Some(rules) = &self.deadband_by_namespace {
+            cfg.deadband_by_namespace = rules.clone();
+            set.push("deadband_by_namespace");
+        }
+        if let Some(rules) = &self.deadband_by_stream {
+            cfg.deadband_by_stream = rules.clone();
+            set.push("deadband_by_stream");
+        }
+        if let Some(properties) = &self.indexed_properties {
+            cfg.indexed_properties = properties.clone();
+            set.push("indexed_properties");
+        }
+        if let Some(attribute) = &self.cloudevents_stream_attribute {
+            cfg.cloudevents_stream_attribute = attribute.clone();
+            set.push("cloudevents_stream_attribute");
+        }
+        set
+    }
+}
+
+/// Notification sent on the config change channel after a successful update
+#[derive(Debug, Clone)]
+pub struct ConfigChanged {
+    /// Fields included in the update
+    pub fields: Vec<String>,
+    /// Effective config after the update
+    pub config: RuntimeConfig,
+}
+
+/// Shared runtime config with per-field source tracking and change notifications
+///
+/// `read()`/`write()` mirror `RwLock` so hot paths read limits directly. Direct
+/// writes skip validation and notification; the admin API goes through
+/// [`apply_update`](Self::apply_update).
+pub struct RuntimeConfigHandle {
+    config: RwLock<RuntimeConfig>,
+    sources: RwLock<BTreeMap<&'static str, ConfigSource>>,
+    changes: broadcast::Sender<ConfigChanged>,
+    /// Bumped by every update that changes a field
+    generation: AtomicU64,
+}
+
+impl RuntimeConfigHandle {
+    fn new(config: RuntimeConfig, sources: BTreeMap<&'static str, ConfigSource>) -> Self {
+        let (changes, _) = broadcast::channel(16);
+        Self {
+            config: RwLock::new(config),
+            sources: RwLock::new(sources),
+            changes,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, RuntimeConfig>> {
+        self.config.read()
+    }
+
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, RuntimeConfig>> {
+        self.config.write()
+    }
+
+    /// Counter bumped by every [`apply_update`](Self::apply_update) that sets
+    /// a field, so caches of derived values know when to refresh
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Subscribe to `config_changed` notifications
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChanged> {
+        self.changes.subscribe()
+    }
+
+    /// Source of every field's effective value
+    pub fn sources(&self) -> BTreeMap<String, ConfigSource> {
+        let sources = self
+            .sources
+            .read()
+            .expect("RuntimeConfig sources lock poisoned");
+        RUNTIME_CONFIG_FIELDS
+            .iter()
+            .map(|field| {
+                let source = sources.get(field).copied().unwrap_or(ConfigSource::Default);
+                (field.to_string(), source)
+            })
+            .collect()
+    }
+
+    /// Validate and apply a partial update, then notify subscribers.
+    ///
+    /// The update is all-or-nothing: if any resulting field is out of range,
+    /// nothing changes and the offending field is reported.
+    pub fn apply_update(
+        &self,
+        update: &RuntimeConfigUpdate,
+    ) -> Result<RuntimeConfig, ConfigValidationError> {
+        let (fields, updated) = {
+            let mut cfg = self.config.write().expect("RuntimeConfig lock poisoned");
+            let mut candidate = cfg.clone();
+            let fields = update.apply_to(&mut candidate);
+            candidate.validate()?;
+            *cfg = candidate.clone();
+            if !fields.is_empty() {
+                // Still under the lock, so a new generation always reads the new config
+                self.generation.fetch_add(1, Ordering::AcqRel);
+            }
+            (fields, candidate)
+        };
+
+        {
+            let mut sources = self
+                .sources
+                .write()
+                .expect("RuntimeConfig sources lock poisoned");
+            for field in &fields {
+                sources.insert(*field, ConfigSource::AdminApi);
+            }
+        }
+
+        if !fields.is_empty() {
+            let _ = self.changes.send(ConfigChanged {
+                fields: fields.iter().map(|f| f.to_string()).collect(),
+                config: updated.clone(),
+            });
+        }
+
+        Ok(updated)
+    }
+}
+
+pub type SharedRuntimeConfig = Arc<RuntimeConfigHandle>;
+
+/// Runtime config from env vars and defaults.
 pub fn new_runtime_config() -> SharedRuntimeConfig {
-    Arc::new(RwLock::new(RuntimeConfig::from_env()))
+    let mut sources = BTreeMap::new();
+    let mut cfg = RuntimeConfig::default();
+    cfg.apply_env(&mut sources);
+    Arc::new(RuntimeConfigHandle::new(cfg, sources))
+}
+
+/// Runtime config seeded from the config file, then env vars (env wins).
+#[cfg(feature = "http-api")]
+pub fn new_runtime_config_from_file(file: &FluxConfig) -> SharedRuntimeConfig {
+    let mut sources = BTreeMap::new();
+    let mut cfg = RuntimeConfig::default();
+    cfg.apply_file(file, &mut sources);
+    cfg.apply_env(&mut sources);
+    Arc::new(RuntimeConfigHandle::new(cfg, sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(RuntimeConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_apply_update_partial() {
+        let shared = new_runtime_config();
+        let before = shared.read().unwrap().clone();
+
+        let update = RuntimeConfigUpdate {
+            metrics_broadcast_interval_seconds: Some(30),
+            ..Default::default()
+        };
+        let after = shared.apply_update(&update).unwrap();
+
+        assert_eq!(after.metrics_broadcast_interval_seconds, 30);
+        assert_eq!(
+            after.snapshot_interval_minutes,
+            before.snapshot_interval_minutes
+        );
+        assert_eq!(
+            shared.sources()["metrics_broadcast_interval_seconds"],
+            ConfigSource::AdminApi
+        );
+    }
+
+    #[test]
+    fn test_apply_update_rejects_out_of_range_atomically() {
+        let shared = new_runtime_config();
+        let before = shared.read().unwrap().clone();
+
+        let update = RuntimeConfigUpdate {
+            snapshot_interval_minutes: Some(10),
+            metrics_broadcast_interval_seconds: Some(301),
+            ..Default::default()
+        };
+        let err = shared.apply_update(&update).unwrap_err();
+
+        assert_eq!(err.field, "metrics_broadcast_interval_seconds");
+        // Nothing applied, including the valid field
+        assert_eq!(*shared.read().unwrap(), before);
+    }
+
+    #[test]
+    fn test_apply_update_notifies_subscribers() {
+        let shared = new_runtime_config();
+        let mut rx = shared.subscribe();
+
+        let update = RuntimeConfigUpdate {
+            entity_ttl_seconds: Some(3600),
+            ..Default::default()
+        };
+        shared.apply_update(&update).unwrap();
+
+        let changed = rx.try_recv().unwrap();
+        assert_eq!(changed.fields, vec!["entity_ttl_seconds".to_string()]);
+        assert_eq!(changed.config.entity_ttl_seconds, 3600);
+    }
+
+    #[test]
+    fn test_empty_update_does_not_notify() {
+        let shared = new_runtime_config();
+        let mut rx = shared.subscribe();
+
+        shared
+            .apply_update(&RuntimeConfigUpdate::default())
+            .unwrap();
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[cfg(feature = "http-api")]
+    #[test]
+    fn test_file_values_marked_as_file_source() {
+        let mut file = FluxConfig::default();
+        file.metrics.broadcast_interval_seconds = 7;
+
+        let shared = new_runtime_config_from_file(&file);
+        assert_eq!(shared.read().unwrap().metrics_broadcast_interval_seconds, 7);
+
+        let sources = shared.sources();
+        assert_eq!(
+            sources["metrics_broadcast_interval_seconds"],
+            ConfigSource::File
+        );
+        assert_eq!(sources["snapshot_interval_minutes"], ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_event_limits_follow_admin_update() {
+        let shared = new_runtime_config();
+        assert_eq!(
+            shared.read().unwrap().event_limits().max_payload_bytes,
+            262_144
+        );
+
+        let update = RuntimeConfigUpdate {
+            max_payload_bytes: Some(4_096),
+            max_properties_per_event: Some(8),
+            ..Default::default()
+        };
+        shared.apply_update(&update).unwrap();
+
+        let limits = shared.read().unwrap().event_limits();
+        assert_eq!(limits.max_payload_bytes, 4_096);
+        assert_eq!(limits.max_properties_per_event, 8);
+        assert_eq!(limits.max_property_name_length, 256);
+        assert_eq!(
+            shared.sources()["max_payload_bytes"],
+            ConfigSource::AdminApi
+        );
+    }
+
+    #[test]
+    fn test_event_limits_reject_zero() {
+        let shared = new_runtime_config();
+        let update = RuntimeConfigUpdate {
+            max_properties_per_event: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            shared.apply_update(&update).unwrap_err().field,
+            "max_properties_per_event"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_rules_follow_admin_update() {
+        let shared = new_runtime_config();
+        let rules = shared.read().unwrap().timestamp_rules();
+        assert_eq!(rules.max_skew_seconds, 300);
+        assert_eq!(rules.seconds_policy, SecondsTimestampPolicy::Convert);
+
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "max_timestamp_skew_seconds": 60,
+            "seconds_timestamp_policy": "reject"
+        }))
+        .unwrap();
+        shared.apply_update(&update).unwrap();
+
+        let rules = shared.read().unwrap().timestamp_rules();
+        assert_eq!(rules.max_skew_seconds, 60);
+        assert_eq!(rules.seconds_policy, SecondsTimestampPolicy::Reject);
+        assert_eq!(
+            shared.sources()["seconds_timestamp_policy"],
+            ConfigSource::AdminApi
+        );
+    }
+
+    #[test]
+    fn test_ws_limits_reject_zero() {
+        let shared = new_runtime_config();
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "ws_max_connections_per_ip": 10,
+            "ws_control_messages_per_minute": 0
+        }))
+        .unwrap();
+        assert_eq!(
+            shared.apply_update(&update).unwrap_err().field,
+            "ws_control_messages_per_minute"
+        );
+        assert_eq!(shared.read().unwrap().ws_max_connections_per_ip, 100);
+    }
+
+    #[test]
+    fn test_query_cache_limits_validated() {
+        let shared = new_runtime_config();
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "query_cache_ttl_ms": 0,
+            "query_cache_max_entries": 0
+        }))
+        .unwrap();
+        assert_eq!(
+            shared.apply_update(&update).unwrap_err().field,
+            "query_cache_max_entries"
+        );
+
+        let update: RuntimeConfigUpdate =
+            serde_json::from_value(serde_json::json!({"query_cache_ttl_ms": 0})).unwrap();
+        shared.apply_update(&update).unwrap();
+        assert_eq!(shared.read().unwrap().query_cache_ttl_ms, 0);
+    }
+
+    #[test]
+    fn test_cloudevents_stream_attribute_validated() {
+        let shared = new_runtime_config();
+        for bad in ["", "Type", "flux-stream", "abcdefghijklmnopqrstu"] {
+            let update = RuntimeConfigUpdate {
+                cloudevents_stream_attribute: Some(bad.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(
+                shared.apply_update(&update).unwrap_err().field,
+                "cloudevents_stream_attribute"
+            );
+        }
+
+        let update = RuntimeConfigUpdate {
+            cloudevents_stream_attribute: Some("fluxstream".to_string()),
+            ..Default::default()
+        };
+        shared.apply_update(&update).unwrap();
+        assert_eq!(
+            shared.read().unwrap().cloudevents_stream_attribute,
+            "fluxstream"
+        );
+    }
+
+    #[test]
+    fn test_id_normalization_by_namespace() {
+        let shared = new_runtime_config();
+        assert_eq!(
+            shared.read().unwrap().id_normalization_for("acme/x"),
+            IdNormalization::Off
+        );
+
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "entity_id_normalization": {"*": "encode", "legacy": "off"}
+        }))
+        .unwrap();
+        shared.apply_update(&update).unwrap();
+
+        let config = shared.read().unwrap();
+        let mode = |entity_id: &str| config.id_normalization_for(entity_id);
+        assert_eq!(mode("acme/x"), IdNormalization::Encode);
+        assert_eq!(mode("sensor-01"), IdNormalization::Encode);
+        assert_eq!(mode("legacy/x"), IdNormalization::Off);
+    }
+
+    #[test]
+    fn test_deadband_rules_validated_and_bump_generation() {
+        let shared = new_runtime_config();
+        let generation = shared.generation();
+
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "deadband_by_stream": {"sensors": {"min_change": 0.1, "min_change_percent": 1.0}}
+        }))
+        .unwrap();
+        let err = shared.apply_update(&update).unwrap_err();
+        assert_eq!(err.field, "deadband_by_stream");
+        assert_eq!(shared.generation(), generation);
+
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "deadband_by_namespace": {"Not A Namespace": {"min_change": 0.1}}
+        }))
+        .unwrap();
+        assert_eq!(
+            shared.apply_update(&update).unwrap_err().field,
+            "deadband_by_namespace"
+        );
+
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "deadband_by_namespace": {"plant": {"min_change_percent": 2.5}}
+        }))
+        .unwrap();
+        shared.apply_update(&update).unwrap();
+        assert_eq!(shared.generation(), generation + 1);
+        let rule = shared.read().unwrap().deadband_by_namespace["plant"];
+        assert_eq!(rule.min_change_percent, Some(2.5));
+        assert_eq!(rule.keepalive_seconds, 300);
+    }
+
+    #[test]
+    fn test_id_normalization_rejects_bad_namespace() {
+        let shared = new_runtime_config();
+        let update: RuntimeConfigUpdate = serde_json::from_value(serde_json::json!({
+            "entity_id_normalization": {"Not A Namespace": "strip"}
+        }))
+        .unwrap();
+        assert_eq!(
+            shared.apply_update(&update).unwrap_err().field,
+            "entity_id_normalization"
+        );
+    }
+
+    #[test]
+    fn test_indexed_properties_validated() {
+        let shared = new_runtime_config();
+        for properties in [
+            serde_json::json!([""]),
+            serde_json::json!(["x".repeat(257)]),
+            serde_json::json!((0..65).map(|i| format!("p{}", i)).collect::<Vec<_>>()),
+        ] {
+            let update: RuntimeConfigUpdate =
+                serde_json::from_value(serde_json::json!({ "indexed_properties": properties }))
+                    .unwrap();
+            assert_eq!(
+                shared.apply_update(&update).unwrap_err().field,
+                "indexed_properties"
+            );
+        }
+
+        let update: RuntimeConfigUpdate =
+            serde_json::from_value(serde_json::json!({"indexed_properties": ["status"]})).unwrap();
+        shared.apply_update(&update).unwrap();
+        assert_eq!(shared.read().unwrap().indexed_properties, ["status"]);
+    }
 }
@@ -60,8 +60,8 @@ pub fn encrypt(plaintext: &str, key: &[u8]) -> Result<(String, String)> {
     }
 
     // Create cipher instance
-    let cipher = Aes256Gcm::new_from_slice(key)
-        .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+    let cipher =
+        Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
 
     // Generate random nonce (never reuse!)
     let nonce_bytes = Aes256Gcm::generate_nonce(&mut OsRng);
@@ -73,7 +73,7 @@ pub fn encrypt(plaintext: &str, key: &[u8]) -> Result<(String, String)> {
 
     // Encode to base64 for storage
     let ciphertext = BASE64.encode(&ciphertext_bytes);
-    let nonce = BASE64.encode(&nonce_bytes);
+    let nonce = BASE64.encode(nonce_bytes);
 
     Ok((ciphertext, nonce))
 }
@@ -100,12 +100,16 @@ pub fn decrypt(ciphertext: &str, nonce: &str, key: &[u8]) -> Result<String> {
     let nonce_bytes = BASE64.decode(nonce).context("Failed to decode nonce")?;
 
     if nonce_bytes.len() != NONCE_SIZE {
-        return Err(anyhow!("Invalid nonce size: expected {}, got {}", NONCE_SIZE, nonce_bytes.len()));
+        return Err(anyhow!(
+            "Invalid nonce size: expected {}, got {}",
+            NONCE_SIZE,
+            nonce_bytes.len()
+        ));
     }
 
     // Create cipher instance
-    let cipher = Aes256Gcm::new_from_slice(key)
-        .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+    let cipher =
+        Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
 
     let nonce = Nonce::from_slice(&nonce_bytes);
 
@@ -125,15 +129,15 @@ mod tests {
     #[test]
     fn test_key_validation() {
         // Valid 32-byte key (base64-encoded)
-        let valid_key = BASE64.encode(&[0u8; 32]);
+        let valid_key = BASE64.encode([0u8; 32]);
         assert!(validate_key(&valid_key).is_ok());
 
         // Too short
-        let short_key = BASE64.encode(&[0u8; 16]);
+        let short_key = BASE64.encode([0u8; 16]);
         assert!(validate_key(&short_key).is_err());
 
         // Too long
-        let long_key = BASE64.encode(&[0u8; 64]);
+        let long_key = BASE64.encode([0u8; 64]);
         assert!(validate_key(&long_key).is_err());
 
         // Invalid base64
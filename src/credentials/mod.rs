@@ -72,6 +72,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 mod encryption;
 mod storage;
@@ -101,3 +102,98 @@ pub struct Credentials {
     /// When the access token expires (UTC)
     pub expires_at: Option<DateTime<Utc>>,
 }
+
+/// OAuth scopes an authorization asked for and the ones the provider granted.
+///
+/// Users can untick scopes on the consent screen, leaving a token that works
+/// for some calls and gets 403s on others.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScopeGrant {
+    /// Scopes in the authorization request
+    pub requested: Vec<String>,
+    /// Scopes in the token response; `None` if it had no `scope` field,
+    /// which means the requested ones were granted (RFC 6749 §5.1)
+    pub granted: Option<Vec<String>>,
+}
+
+impl ScopeGrant {
+    /// Requested scopes the provider did not grant
+    pub fn missing(&self) -> Vec<String> {
+        match &self.granted {
+            Some(granted) => missing_scopes(&self.requested, granted),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Splits a `scope` value into scopes.
+///
+/// The spec separates scopes with spaces, but some providers (GitHub) use
+/// commas; both are accepted. Duplicates are dropped, order is kept.
+pub fn parse_scopes(scope: &str) -> Vec<String> {
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in scope.split(|c: char| c == ',' || c.is_whitespace()) {
+        if !scope.is_empty() && !scopes.iter().any(|s| s == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+    scopes
+}
+
+/// Scopes in `required` that are not in `granted`
+pub fn missing_scopes(required: &[String], granted: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|scope| !granted.contains(scope))
+        .cloned()
+        .collect()
+}
+
+/// ETags from a connector's last published poll, keyed by request URL.
+///
+/// Connectors send them back as `If-None-Match`, so resources that haven't
+/// changed come back as `304 Not Modified` and produce no events.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ETagCache {
+    entries: HashMap<String, CachedETag>,
+}
+
+/// ETag of one response, plus the item IDs it listed
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CachedETag {
+    pub etag: String,
+    /// IDs of the items in the response, for connectors that need to walk
+    /// them again when the listing itself is unchanged
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+impl ETagCache {
+    pub fn get(&self, url: &str) -> Option<&CachedETag> {
+        self.entries.get(url)
+    }
+
+    pub fn etag(&self, url: &str) -> Option<&str> {
+        self.entries.get(url).map(|e| e.etag.as_str())
+    }
+
+    pub fn insert(&mut self, url: impl Into<String>, entry: CachedETag) {
+        self.entries.insert(url.into(), entry);
+    }
+
+    pub fn remove(&mut self, url: &str) -> Option<CachedETag> {
+        self.entries.remove(url)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &CachedETag)> {
+        self.entries.iter()
+    }
+}
@@ -3,13 +3,63 @@
 //! Stores OAuth credentials (access tokens, refresh tokens) for users and connectors.
 //! All tokens are encrypted at rest using AES-256-GCM.
 
-use super::{encryption, Credentials};
+use super::{encryption, CachedETag, Credentials, ETagCache, ScopeGrant};
+use crate::migrations::{self, Migration};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
 use std::sync::Mutex;
 
+/// Schema history of the credential store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        r#"
+        CREATE TABLE IF NOT EXISTS credentials (
+            id INTEGER PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            connector TEXT NOT NULL,
+            access_token TEXT NOT NULL,
+            access_token_nonce TEXT NOT NULL,
+            refresh_token TEXT,
+            refresh_token_nonce TEXT,
+            expires_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(user_id, connector)
+        );
+        CREATE INDEX IF NOT EXISTS idx_user_connector ON credentials(user_id, connector);
+        "#,
+    ),
+    Migration::Sql(
+        r#"
+        CREATE TABLE IF NOT EXISTS etags (
+            user_id TEXT NOT NULL,
+            connector TEXT NOT NULL,
+            url TEXT NOT NULL,
+            etag TEXT NOT NULL,
+            items TEXT NOT NULL,
+            PRIMARY KEY (user_id, connector, url)
+        );
+        "#,
+    ),
+    Migration::AddColumn {
+        table: "credentials",
+        column: "flux_token",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "credentials",
+        column: "flux_token_nonce",
+        definition: "TEXT",
+    },
+    Migration::AddColumn {
+        table: "credentials",
+        column: "scope_grant",
+        definition: "TEXT",
+    },
+];
+
 /// Encrypted credential storage backed by SQLite.
 ///
 /// # Schema
@@ -25,10 +75,24 @@ use std::sync::Mutex;
 ///     expires_at TEXT,                  -- ISO 8601 timestamp (optional)
 ///     created_at TEXT NOT NULL,         -- ISO 8601 timestamp
 ///     updated_at TEXT NOT NULL,         -- ISO 8601 timestamp
+///     flux_token TEXT,                  -- Encrypted namespace token (optional)
+///     flux_token_nonce TEXT,            -- Nonce for flux_token (optional)
+///     scope_grant TEXT,                 -- JSON requested/granted OAuth scopes (optional)
 ///     UNIQUE(user_id, connector)
 /// );
+///
+/// CREATE TABLE etags (
+///     user_id TEXT NOT NULL,
+///     connector TEXT NOT NULL,
+///     url TEXT NOT NULL,
+///     etag TEXT NOT NULL,
+///     items TEXT NOT NULL,              -- JSON array of item IDs
+///     PRIMARY KEY (user_id, connector, url)
+/// );
 /// ```
 ///
+/// Versioned by [`crate::migrations`]; a database from a newer build is refused.
+///
 /// # Security
 /// - Access and refresh tokens are encrypted separately with unique nonces
 /// - Master key is stored in memory only (from env var)
@@ -52,42 +116,17 @@ impl CredentialStore {
     ///
     /// # Returns
     /// * `Ok(CredentialStore)` - Initialized store
-    /// * `Err` - If database creation fails or key is invalid
+    /// * `Err` - If database creation fails, the schema is newer than this
+    ///   build, or key is invalid
     pub fn new<P: AsRef<Path>>(db_path: P, encryption_key: &str) -> Result<Self> {
         // Validate encryption key
-        let key_bytes = encryption::validate_key(encryption_key)
-            .context("Invalid encryption key")?;
+        let key_bytes =
+            encryption::validate_key(encryption_key).context("Invalid encryption key")?;
 
         // Open/create database
-        let conn = Connection::open(db_path).context("Failed to open database")?;
-
-        // Create schema if not exists
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS credentials (
-                id INTEGER PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                connector TEXT NOT NULL,
-                access_token TEXT NOT NULL,
-                access_token_nonce TEXT NOT NULL,
-                refresh_token TEXT,
-                refresh_token_nonce TEXT,
-                expires_at TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                UNIQUE(user_id, connector)
-            )
-            "#,
-            [],
-        )
-        .context("Failed to create credentials table")?;
-
-        // Create index for faster lookups
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_user_connector ON credentials(user_id, connector)",
-            [],
-        )
-        .context("Failed to create index")?;
+        let mut conn = Connection::open(db_path).context("Failed to open database")?;
+        migrations::migrate(&mut conn, "credentials", MIGRATIONS)
+            .context("Failed to migrate credential store")?;
 
         Ok(Self {
             conn: Mutex::new(conn),
@@ -209,10 +248,10 @@ impl CredentialStore {
             let refresh_token: Option<String> = row.get(2)?;
             let refresh_token_nonce: Option<String> = row.get(3)?;
             let refresh_token = match (refresh_token, refresh_token_nonce) {
-                (Some(encrypted), Some(nonce)) => {
-                    Some(encryption::decrypt(&encrypted, &nonce, &self.encryption_key)
-                        .context("Failed to decrypt refresh token")?)
-                }
+                (Some(encrypted), Some(nonce)) => Some(
+                    encryption::decrypt(&encrypted, &nonce, &self.encryption_key)
+                        .context("Failed to decrypt refresh token")?,
+                ),
                 _ => None,
             };
 
@@ -233,6 +272,135 @@ impl CredentialStore {
         }
     }
 
+    /// Records the Flux bearer token the connector-manager publishes with.
+    ///
+    /// `user_id` is the namespace the token belongs to. The token is kept
+    /// when the credentials are replaced or refreshed.
+    pub fn set_flux_token(&self, user_id: &str, connector: &str, token: &str) -> Result<()> {
+        let (encrypted, nonce) = encryption::encrypt(token, &self.encryption_key)
+            .context("Failed to encrypt flux token")?;
+        let rows = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE credentials SET flux_token = ?3, flux_token_nonce = ?4
+                 WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector, encrypted, nonce],
+            )
+            .context("Failed to store flux token")?;
+        if rows == 0 {
+            anyhow::bail!(
+                "No credentials for user '{}' connector '{}'",
+                user_id,
+                connector
+            );
+        }
+        Ok(())
+    }
+
+    /// Flux bearer token recorded for a user and connector, if any.
+    pub fn get_flux_token(&self, user_id: &str, connector: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(Option<String>, Option<String>)> = conn
+            .query_row(
+                "SELECT flux_token, flux_token_nonce FROM credentials
+                 WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .context("Failed to read flux token")?;
+
+        match row {
+            Some((Some(encrypted), Some(nonce))) => Ok(Some(
+                encryption::decrypt(&encrypted, &nonce, &self.encryption_key)
+                    .context("Failed to decrypt flux token")?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Records the OAuth scopes requested and granted for a user and
+    /// connector; `None` clears them (e.g. when a PAT replaces the grant).
+    ///
+    /// Like the Flux token, they are kept when the credentials are refreshed.
+    pub fn set_scope_grant(
+        &self,
+        user_id: &str,
+        connector: &str,
+        grant: Option<&ScopeGrant>,
+    ) -> Result<()> {
+        let grant = grant.map(serde_json::to_string).transpose()?;
+        let rows = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE credentials SET scope_grant = ?3 WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector, grant],
+            )
+            .context("Failed to store scope grant")?;
+        if rows == 0 {
+            anyhow::bail!(
+                "No credentials for user '{}' connector '{}'",
+                user_id,
+                connector
+            );
+        }
+        Ok(())
+    }
+
+    /// OAuth scopes recorded for a user and connector, if any.
+    pub fn get_scope_grant(&self, user_id: &str, connector: &str) -> Result<Option<ScopeGrant>> {
+        let conn = self.conn.lock().unwrap();
+        let grant: Option<Option<String>> = conn
+            .query_row(
+                "SELECT scope_grant FROM credentials WHERE user_id = ?1 AND connector = ?2",
+                params![user_id, connector],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read scope grant")?;
+
+        grant
+            .flatten()
+            .map(|grant| serde_json::from_str(&grant).context("Invalid scope grant"))
+            .transpose()
+    }
+
+    /// Moves credentials stored under a namespace's bearer token to the
+    /// namespace name, recording the token as their Flux token.
+    ///
+    /// Credentials used to be keyed by the raw bearer token. Returns the
+    /// number of connectors moved; rows that already exist under the
+    /// namespace name win.
+    pub fn rekey_token_user(&self, token: &str, namespace: &str) -> Result<usize> {
+        let (encrypted, nonce) = encryption::encrypt(token, &self.encryption_key)
+            .context("Failed to encrypt flux token")?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        let moved = tx
+            .execute(
+                "UPDATE OR IGNORE credentials
+                 SET user_id = ?2, flux_token = ?3, flux_token_nonce = ?4
+                 WHERE user_id = ?1",
+                params![token, namespace, encrypted, nonce],
+            )
+            .context("Failed to rekey credentials")?;
+        tx.execute(
+            "UPDATE OR IGNORE etags SET user_id = ?2 WHERE user_id = ?1",
+            params![token, namespace],
+        )
+        .context("Failed to rekey etags")?;
+        tx.execute("DELETE FROM credentials WHERE user_id = ?1", params![token])
+            .context("Failed to drop superseded credentials")?;
+        tx.execute("DELETE FROM etags WHERE user_id = ?1", params![token])
+            .context("Failed to drop superseded etags")?;
+        tx.commit().context("Failed to commit rekey")?;
+        Ok(moved)
+    }
+
     /// Updates credentials for a user and connector.
     ///
     /// This is an alias for `store()` since the storage uses upsert semantics.
@@ -245,7 +413,7 @@ impl CredentialStore {
         self.store(user_id, connector, credentials)
     }
 
-    /// Deletes credentials for a user and connector.
+    /// Deletes credentials for a user and connector, along with their ETags.
     ///
     /// # Arguments
     /// * `user_id` - User identifier
@@ -256,19 +424,72 @@ impl CredentialStore {
     /// * `Ok(false)` - No credentials found
     /// * `Err` - If database operation fails
     pub fn delete(&self, user_id: &str, connector: &str) -> Result<bool> {
-        let rows_affected = self
-            .conn
-            .lock()
-            .unwrap()
+        let conn = self.conn.lock().unwrap();
+        let rows_affected = conn
             .execute(
                 "DELETE FROM credentials WHERE user_id = ?1 AND connector = ?2",
                 params![user_id, connector],
             )
             .context("Failed to delete credentials")?;
+        conn.execute(
+            "DELETE FROM etags WHERE user_id = ?1 AND connector = ?2",
+            params![user_id, connector],
+        )
+        .context("Failed to delete etags")?;
 
         Ok(rows_affected > 0)
     }
 
+    /// Loads the ETags saved for a user and connector (empty if none).
+    pub fn load_etags(&self, user_id: &str, connector: &str) -> Result<ETagCache> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT url, etag, items FROM etags WHERE user_id = ?1 AND connector = ?2")
+            .context("Failed to prepare query")?;
+
+        let rows = stmt
+            .query_map(params![user_id, connector], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .context("Failed to execute query")?;
+
+        let mut etags = ETagCache::default();
+        for row in rows {
+            let (url, etag, items): (String, String, String) =
+                row.context("Failed to read results")?;
+            let items = serde_json::from_str(&items)
+                .with_context(|| format!("Invalid items for ETag of {}", url))?;
+            etags.insert(url, CachedETag { etag, items });
+        }
+        Ok(etags)
+    }
+
+    /// Replaces the ETags saved for a user and connector.
+    pub fn save_etags(&self, user_id: &str, connector: &str, etags: &ETagCache) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().context("Failed to begin transaction")?;
+        tx.execute(
+            "DELETE FROM etags WHERE user_id = ?1 AND connector = ?2",
+            params![user_id, connector],
+        )
+        .context("Failed to clear etags")?;
+        for (url, entry) in etags.iter() {
+            tx.execute(
+                "INSERT INTO etags (user_id, connector, url, etag, items) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    user_id,
+                    connector,
+                    url,
+                    entry.etag,
+                    serde_json::to_string(&entry.items)?,
+                ],
+            )
+            .context("Failed to store etag")?;
+        }
+        tx.commit().context("Failed to commit etags")?;
+        Ok(())
+    }
+
     /// Lists all (user_id, connector) pairs across all users.
     ///
     /// Used by the connector manager on startup to resume polling
@@ -320,7 +541,7 @@ mod tests {
 
     fn create_test_store() -> CredentialStore {
         // Generate random 32-byte key for testing
-        let key = BASE64.encode(&[0u8; 32]);
+        let key = BASE64.encode([0u8; 32]);
         CredentialStore::new(":memory:", &key).expect("Failed to create test store")
     }
 
@@ -404,6 +625,129 @@ mod tests {
         assert!(!deleted_again);
     }
 
+    #[test]
+    fn test_flux_token_survives_refresh() {
+        let store = create_test_store();
+        assert!(store.set_flux_token("alice", "github", "tok-1").is_err());
+
+        store
+            .store("alice", "github", &create_test_credentials())
+            .unwrap();
+        assert_eq!(store.get_flux_token("alice", "github").unwrap(), None);
+
+        store.set_flux_token("alice", "github", "tok-1").unwrap();
+        assert_eq!(
+            store.get_flux_token("alice", "github").unwrap().as_deref(),
+            Some("tok-1")
+        );
+
+        // A token refresh replaces the credentials but not the flux token
+        let mut refreshed = create_test_credentials();
+        refreshed.access_token = "new-access-token".to_string();
+        store.store("alice", "github", &refreshed).unwrap();
+        assert_eq!(
+            store.get_flux_token("alice", "github").unwrap().as_deref(),
+            Some("tok-1")
+        );
+        assert_eq!(store.get_flux_token("bob", "github").unwrap(), None);
+    }
+
+    #[test]
+    fn test_scope_grant_survives_refresh() {
+        let store = create_test_store();
+        let grant = ScopeGrant {
+            requested: vec!["repo".to_string(), "notifications".to_string()],
+            granted: Some(vec!["repo".to_string()]),
+        };
+        assert!(store
+            .set_scope_grant("alice", "github", Some(&grant))
+            .is_err());
+
+        store
+            .store("alice", "github", &create_test_credentials())
+            .unwrap();
+        assert_eq!(store.get_scope_grant("alice", "github").unwrap(), None);
+
+        store
+            .set_scope_grant("alice", "github", Some(&grant))
+            .unwrap();
+        let mut refreshed = create_test_credentials();
+        refreshed.access_token = "new-access-token".to_string();
+        store.store("alice", "github", &refreshed).unwrap();
+        let stored = store.get_scope_grant("alice", "github").unwrap().unwrap();
+        assert_eq!(stored, grant);
+        assert_eq!(stored.missing(), vec!["notifications".to_string()]);
+
+        store.set_scope_grant("alice", "github", None).unwrap();
+        assert_eq!(store.get_scope_grant("alice", "github").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rekey_token_user() {
+        let store = create_test_store();
+        let creds = create_test_credentials();
+        store.store("tok-1", "github", &creds).unwrap();
+        store.store("tok-1", "gmail", &creds).unwrap();
+        // Already moved by hand; the existing row wins
+        let mut newer = create_test_credentials();
+        newer.access_token = "newer".to_string();
+        store.store("alice", "gmail", &newer).unwrap();
+
+        assert_eq!(store.rekey_token_user("tok-1", "alice").unwrap(), 1);
+        assert!(store.list_by_user("tok-1").unwrap().is_empty());
+        assert_eq!(
+            store.list_by_user("alice").unwrap(),
+            vec!["github", "gmail"]
+        );
+        assert_eq!(
+            store.get_flux_token("alice", "github").unwrap().as_deref(),
+            Some("tok-1")
+        );
+        assert_eq!(
+            store.get("alice", "gmail").unwrap().unwrap().access_token,
+            "newer"
+        );
+
+        assert_eq!(store.rekey_token_user("tok-1", "alice").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_etags_roundtrip() {
+        let store = create_test_store();
+        assert!(store.load_etags("user1", "github").unwrap().is_empty());
+
+        let mut etags = ETagCache::default();
+        etags.insert(
+            "https://api.github.com/user/repos",
+            CachedETag {
+                etag: "\"abc\"".to_string(),
+                items: vec!["alice/repo".to_string()],
+            },
+        );
+        etags.insert(
+            "https://api.github.com/notifications",
+            CachedETag {
+                etag: "W/\"def\"".to_string(),
+                items: vec![],
+            },
+        );
+        store.save_etags("user1", "github", &etags).unwrap();
+        assert_eq!(store.load_etags("user1", "github").unwrap(), etags);
+        assert!(store.load_etags("user2", "github").unwrap().is_empty());
+
+        // Saving replaces the previous set
+        etags.remove("https://api.github.com/notifications");
+        store.save_etags("user1", "github", &etags).unwrap();
+        assert_eq!(store.load_etags("user1", "github").unwrap(), etags);
+
+        // Deleting the credentials forgets the ETags
+        store
+            .store("user1", "github", &create_test_credentials())
+            .unwrap();
+        store.delete("user1", "github").unwrap();
+        assert!(store.load_etags("user1", "github").unwrap().is_empty());
+    }
+
     #[test]
     fn test_list_by_user() {
         let store = create_test_store();
@@ -475,4 +819,64 @@ mod tests {
         let result = CredentialStore::new(":memory:", "not-valid-base64!@#$");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_migrates_unversioned_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("credentials.db");
+        let key = BASE64.encode([0u8; 32]);
+
+        // Schema as created before versioning, without etags or flux_token
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE credentials (
+                id INTEGER PRIMARY KEY, user_id TEXT NOT NULL, connector TEXT NOT NULL,
+                access_token TEXT NOT NULL, access_token_nonce TEXT NOT NULL,
+                refresh_token TEXT, refresh_token_nonce TEXT, expires_at TEXT,
+                created_at TEXT NOT NULL, updated_at TEXT NOT NULL,
+                UNIQUE(user_id, connector)
+            );",
+        )
+        .unwrap();
+        let (token, nonce) = encryption::encrypt("ghp_old", &[0u8; 32]).unwrap();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO credentials
+                (user_id, connector, access_token, access_token_nonce, created_at, updated_at)
+             VALUES ('alice', 'github', ?1, ?2, ?3, ?3)",
+            params![token, nonce, now],
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = CredentialStore::new(&db_path, &key).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        assert_eq!(
+            migrations::schema_version(&conn, "credentials").unwrap(),
+            MIGRATIONS.len() as u32
+        );
+        let stored = store.get("alice", "github").unwrap().unwrap();
+        assert_eq!(stored.access_token, "ghp_old");
+        store.set_flux_token("alice", "github", "tok-1").unwrap();
+        assert!(store.load_etags("alice", "github").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("credentials.db");
+        let key = BASE64.encode([0u8; 32]);
+        drop(CredentialStore::new(&db_path, &key).unwrap());
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute(
+            "UPDATE schema_version SET version = 99 WHERE store = 'credentials'",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let err = CredentialStore::new(&db_path, &key).err().unwrap();
+        assert!(format!("{:#}", err).contains("newer Flux"), "{:#}", err);
+    }
 }
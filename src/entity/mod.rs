@@ -1,8 +1,11 @@
 use crate::namespace::NamespaceRegistry;
 
+mod normalize;
 #[cfg(test)]
 mod tests;
 
+pub use normalize::{normalize_entity_id, IdNormalization, MAX_NORMALIZED_ID_LENGTH};
+
 /// Parsed entity ID with optional namespace prefix
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsedEntityId {
@@ -29,6 +32,9 @@ pub enum ParseError {
 /// - "namespace/entity" → Some("namespace"), "entity"
 /// - "entity" → None, "entity"
 ///
+/// The namespace is everything before the first '/', so the entity part may
+/// itself be a path ("alice/github/repo/x" → "alice", "github/repo/x").
+///
 /// Validates namespace part if present using namespace rules:
 /// - 3-32 characters
 /// - Lowercase alphanumeric + dash/underscore: [a-z0-9-_]
@@ -43,6 +49,11 @@ pub enum ParseError {
 /// assert_eq!(parsed.namespace, Some("matt".to_string()));
 /// assert_eq!(parsed.entity, "sensor-01".to_string());
 ///
+/// // Deeper paths stay in the entity part
+/// let parsed = parse_entity_id("matt/sensors/sensor-01").unwrap();
+/// assert_eq!(parsed.namespace, Some("matt".to_string()));
+/// assert_eq!(parsed.entity, "sensors/sensor-01".to_string());
+///
 /// // Without namespace (internal mode)
 /// let parsed = parse_entity_id("sensor-01").unwrap();
 /// assert_eq!(parsed.namespace, None);
@@ -54,61 +65,38 @@ pub fn parse_entity_id(entity_id: &str) -> Result<ParsedEntityId, ParseError> {
         return Err(ParseError::Empty);
     }
 
-    // Split on "/" delimiter
-    let parts: Vec<&str> = entity_id.split('/').collect();
-
-    match parts.len() {
-        1 => {
-            // Format: "entity" (no namespace)
-            let entity = parts[0];
-            if entity.is_empty() {
-                return Err(ParseError::InvalidFormat(
-                    "Entity part cannot be empty".to_string(),
-                ));
-            }
-            Ok(ParsedEntityId {
-                namespace: None,
-                entity: entity.to_string(),
-            })
-        }
-        2 => {
-            // Format: "namespace/entity"
-            let namespace = parts[0];
-            let entity = parts[1];
+    // Only the first "/" separates the namespace; the rest is the entity's path
+    let Some((namespace, entity)) = entity_id.split_once('/') else {
+        return Ok(ParsedEntityId {
+            namespace: None,
+            entity: entity_id.to_string(),
+        });
+    };
 
-            // Validate parts are not empty
-            if namespace.is_empty() {
-                return Err(ParseError::InvalidFormat(
-                    "Namespace part cannot be empty".to_string(),
-                ));
-            }
-            if entity.is_empty() {
-                return Err(ParseError::InvalidFormat(
-                    "Entity part cannot be empty".to_string(),
-                ));
-            }
-
-            // Validate namespace format
-            if let Err(e) = NamespaceRegistry::validate_name(namespace) {
-                return Err(ParseError::InvalidNamespace(format!(
-                    "Invalid namespace '{}': {:?}",
-                    namespace, e
-                )));
-            }
+    // Validate parts are not empty
+    if namespace.is_empty() {
+        return Err(ParseError::InvalidFormat(
+            "Namespace part cannot be empty".to_string(),
+        ));
+    }
+    if entity.is_empty() {
+        return Err(ParseError::InvalidFormat(
+            "Entity part cannot be empty".to_string(),
+        ));
+    }
 
-            Ok(ParsedEntityId {
-                namespace: Some(namespace.to_string()),
-                entity: entity.to_string(),
-            })
-        }
-        _ => {
-            // Too many slashes
-            Err(ParseError::InvalidFormat(format!(
-                "Entity ID '{}' contains multiple '/' separators (expected at most one)",
-                entity_id
-            )))
-        }
+    // Validate namespace format
+    if let Err(e) = NamespaceRegistry::validate_name(namespace) {
+        return Err(ParseError::InvalidNamespace(format!(
+            "Invalid namespace '{}': {:?}",
+            namespace, e
+        )));
     }
+
+    Ok(ParsedEntityId {
+        namespace: Some(namespace.to_string()),
+        entity: entity.to_string(),
+    })
 }
 
 /// Extract namespace from entity ID
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+/// Longest entity ID normalization produces, in bytes
+pub const MAX_NORMALIZED_ID_LENGTH: usize = 256;
+
+/// Hex characters of the hash suffix on shortened IDs
+const HASH_SUFFIX_LENGTH: usize = 16;
+
+/// How entity IDs are normalized before they reach state
+///
+/// Both active modes lowercase the ID, turn whitespace into `-` and shorten
+/// IDs longer than [`MAX_NORMALIZED_ID_LENGTH`] to a prefix plus a hash of the
+/// whole ID. They differ in what happens to characters outside
+/// `[a-z0-9/_.-]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IdNormalization {
+    /// IDs are used as published
+    #[default]
+    Off,
+    /// Percent-encode other characters (UTF-8 bytes, lowercase hex)
+    Encode,
+    /// Drop other characters
+    Strip,
+}
+
+fn is_allowed(c: char) -> bool {
+    matches!(c, 'a'..='z' | '0'..='9' | '/' | '_' | '.' | '-')
+}
+
+fn is_lower_hex(c: Option<char>) -> bool {
+    matches!(c, Some('0'..='9' | 'a'..='f'))
+}
+
+/// Normalize `entity_id` under `mode`
+///
+/// Pure and idempotent: normalizing an already normalized ID returns it
+/// unchanged, so it is safe to apply on every path that takes an entity ID.
+/// In `Encode` mode an existing `%xx` escape is kept as is. An ID with nothing
+/// left after stripping becomes the hash of the original.
+///
+/// # Examples
+///
+/// ```
+/// use flux::entity::{normalize_entity_id, IdNormalization};
+///
+/// assert_eq!(normalize_entity_id("acme/Room 12", IdNormalization::Encode), "acme/room-12");
+/// assert_eq!(normalize_entity_id("acme/Café", IdNormalization::Encode), "acme/caf%c3%a9");
+/// assert_eq!(normalize_entity_id("acme/Café", IdNormalization::Strip), "acme/caf");
+/// assert_eq!(normalize_entity_id("acme/Café", IdNormalization::Off), "acme/Café");
+/// ```
+pub fn normalize_entity_id(entity_id: &str, mode: IdNormalization) -> Cow<'_, str> {
+    if mode == IdNormalization::Off {
+        return Cow::Borrowed(entity_id);
+    }
+
+    let lowered = entity_id.to_lowercase();
+    let mut normalized = String::with_capacity(lowered.len());
+    let mut chars = lowered.chars();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            normalized.push('-');
+        } else if is_allowed(c) {
+            normalized.push(c);
+        } else if mode == IdNormalization::Encode {
+            if c == '%' {
+                let mut ahead = chars.clone();
+                if is_lower_hex(ahead.next()) && is_lower_hex(ahead.next()) {
+                    normalized.push('%');
+                    continue;
+                }
+            }
+            let mut utf8 = [0u8; 4];
+            for byte in c.encode_utf8(&mut utf8).bytes() {
+                let _ = write!(normalized, "%{:02x}", byte);
+            }
+        }
+    }
+
+    if normalized.is_empty() {
+        return Cow::Owned(hash_hex(entity_id));
+    }
+    if normalized.len() > MAX_NORMALIZED_ID_LENGTH {
+        // Output is ASCII, so any byte index is a char boundary; back off
+        // rather than cut a `%xx` escape in half
+        let suffix = hash_hex(&normalized);
+        let mut cut = MAX_NORMALIZED_ID_LENGTH - HASH_SUFFIX_LENGTH - 1;
+        if let Some(escape) = normalized[..cut].rfind('%') {
+            if escape + 3 > cut {
+                cut = escape;
+            }
+        }
+        normalized.truncate(cut);
+        normalized.push('-');
+        normalized.push_str(&suffix);
+    }
+
+    if normalized == entity_id {
+        Cow::Borrowed(entity_id)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+/// First [`HASH_SUFFIX_LENGTH`] hex characters of the SHA-256 of `value`
+fn hash_hex(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let mut hex = String::with_capacity(HASH_SUFFIX_LENGTH);
+    for byte in &digest[..HASH_SUFFIX_LENGTH / 2] {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use IdNormalization::{Encode, Off, Strip};
+
+    fn both(entity_id: &str) -> (String, String) {
+        (
+            normalize_entity_id(entity_id, Encode).into_owned(),
+            normalize_entity_id(entity_id, Strip).into_owned(),
+        )
+    }
+
+    #[test]
+    fn test_off_leaves_ids_alone() {
+        for id in ["Acme/Room 12", "", "ünïcode", &"x".repeat(1_000)] {
+            assert!(matches!(normalize_entity_id(id, Off), Cow::Borrowed(s) if s == id));
+        }
+    }
+
+    #[test]
+    fn test_normalized_ids_are_borrowed() {
+        for id in ["acme/sensor-01", "a.b_c-d/e", "0"] {
+            assert!(matches!(normalize_entity_id(id, Encode), Cow::Borrowed(_)));
+            assert!(matches!(normalize_entity_id(id, Strip), Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn test_lowercases() {
+        assert_eq!(
+            both("Acme/Sensor-01"),
+            ("acme/sensor-01".into(), "acme/sensor-01".into())
+        );
+        // Non-ASCII lowercasing happens before encoding
+        assert_eq!(both("É"), ("%c3%a9".into(), hash_hex("É")));
+    }
+
+    #[test]
+    fn test_whitespace_becomes_dash() {
+        assert_eq!(both("room 1"), ("room-1".into(), "room-1".into()));
+        assert_eq!(both("a\tb\nc"), ("a-b-c".into(), "a-b-c".into()));
+        // Each whitespace character is replaced, not runs
+        assert_eq!(both("a  b"), ("a--b".into(), "a--b".into()));
+        // Unicode whitespace too
+        assert_eq!(both("a\u{00a0}b"), ("a-b".into(), "a-b".into()));
+    }
+
+    #[test]
+    fn test_allowed_characters_kept() {
+        let allowed = "abcdefghijklmnopqrstuvwxyz0123456789/_.-";
+        assert_eq!(both(allowed), (allowed.into(), allowed.into()));
+    }
+
+    #[test]
+    fn test_every_other_ascii_character() {
+        for byte in 0x21u8..0x7f {
+            let c = byte as char;
+            if c.is_ascii_alphanumeric() || "/_.-%".contains(c) {
+                continue;
+            }
+            let id = format!("a{}b", c);
+            let (encoded, stripped) = both(&id);
+            assert_eq!(encoded, format!("a%{:02x}b", byte), "{:?}", c);
+            assert_eq!(stripped, "ab", "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        assert_eq!(both("caf\u{e9}"), ("caf%c3%a9".into(), "caf".into()));
+        assert_eq!(
+            both("温度"),
+            ("%e6%b8%a9%e5%ba%a6".into(), hash_hex("温度"))
+        );
+        assert_eq!(both("a😀"), ("a%f0%9f%98%80".into(), "a".into()));
+    }
+
+    #[test]
+    fn test_percent_signs() {
+        // A valid escape is kept (after lowercasing), anything else is encoded
+        assert_eq!(both("a%2Fb"), ("a%2fb".into(), "a2fb".into()));
+        assert_eq!(both("100%"), ("100%25".into(), "100".into()));
+        assert_eq!(both("a%zzb"), ("a%25zzb".into(), "azzb".into()));
+        assert_eq!(both("a%2"), ("a%252".into(), "a2".into()));
+    }
+
+    #[test]
+    fn test_empty_after_stripping_becomes_hash() {
+        assert_eq!(normalize_entity_id("!!!", Strip), hash_hex("!!!"));
+        assert_ne!(hash_hex("!!!"), hash_hex("???"));
+        assert_eq!(normalize_entity_id("!!!", Encode), "%21%21%21");
+        assert_eq!(normalize_entity_id("", Encode), hash_hex(""));
+    }
+
+    #[test]
+    fn test_length_cap() {
+        let exact = "a".repeat(MAX_NORMALIZED_ID_LENGTH);
+        assert_eq!(both(&exact), (exact.clone(), exact.clone()));
+
+        let long = "a".repeat(MAX_NORMALIZED_ID_LENGTH + 1);
+        let capped = normalize_entity_id(&long, Encode);
+        assert_eq!(capped.len(), MAX_NORMALIZED_ID_LENGTH);
+        assert!(capped.starts_with(&"a".repeat(MAX_NORMALIZED_ID_LENGTH - HASH_SUFFIX_LENGTH - 1)));
+        assert!(capped.ends_with(&format!("-{}", hash_hex(&long))));
+    }
+
+    #[test]
+    fn test_length_cap_applies_after_encoding() {
+        // 100 chars that each encode to 6 bytes; the cut lands mid-escape
+        let id = "é".repeat(100);
+        let capped = normalize_entity_id(&id, Encode);
+        assert!(capped.len() <= MAX_NORMALIZED_ID_LENGTH);
+        assert!(capped.starts_with("%c3%a9"));
+        assert!(capped.ends_with(&format!("%c3-{}", hash_hex(&"%c3%a9".repeat(100)))));
+    }
+
+    #[test]
+    fn test_long_ids_sharing_a_prefix_stay_distinct() {
+        let prefix = "x".repeat(MAX_NORMALIZED_ID_LENGTH);
+        let a = normalize_entity_id(&format!("{}a", prefix), Strip).into_owned();
+        let b = normalize_entity_id(&format!("{}b", prefix), Strip).into_owned();
+        assert_ne!(a, b);
+        assert_eq!(a.len(), b.len());
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // Changing this breaks lookups of every shortened ID already in state
+        assert_eq!(hash_hex("abc"), "ba7816bf8f01cfea");
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let ids = [
+            "Acme/Room 12",
+            "caf\u{e9} %41 100%",
+            "!!!",
+            "温度",
+            &"Long Id ".repeat(60),
+            &"é".repeat(300),
+        ];
+        for id in ids {
+            for mode in [Encode, Strip] {
+                let once = normalize_entity_id(id, mode).into_owned();
+                let twice = normalize_entity_id(&once, mode);
+                assert_eq!(twice, once, "{:?} under {:?}", id, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distinct_ids_can_collide() {
+        assert_eq!(both("Room 1").0, both("room-1").0);
+        assert_eq!(both("ROOM-1").1, both("room!-1").1);
+    }
+}
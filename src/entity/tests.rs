@@ -42,11 +42,13 @@ fn test_parse_entity_id_empty_entity() {
 
 #[test]
 fn test_parse_entity_id_multiple_slashes() {
-    let result = parse_entity_id("matt/sensors/sensor-01");
-    assert!(matches!(result, Err(ParseError::InvalidFormat(_))));
+    let parsed = parse_entity_id("matt/sensors/sensor-01").unwrap();
+    assert_eq!(parsed.namespace, Some("matt".to_string()));
+    assert_eq!(parsed.entity, "sensors/sensor-01".to_string());
 
+    // The namespace is still validated
     let result = parse_entity_id("a/b/c/d");
-    assert!(matches!(result, Err(ParseError::InvalidFormat(_))));
+    assert!(matches!(result, Err(ParseError::InvalidNamespace(_))));
 }
 
 #[test]
@@ -0,0 +1,539 @@
+use super::validation::{is_reserved_stream_name, is_valid_stream_name, validate_and_prepare};
+use super::{FluxEvent, ValidationError, MIN_MILLIS_TIMESTAMP};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Errors returned by [`FluxEventBuilder::build`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// Stream name the state engine would reject; `reason` says why
+    InvalidStream { stream: String, reason: String },
+    /// No entity ID was set, or it was empty
+    MissingEntity,
+    /// `properties()` was given a JSON value other than an object
+    PropertiesNotObject { found: &'static str },
+    /// A property value could not be serialized to JSON
+    InvalidProperty { name: String, error: String },
+    /// The assembled event failed [`validate_and_prepare`]
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidStream { stream, reason } => write!(
+                f,
+                "invalid stream '{}': {} (streams are lowercase letters, digits and dots, e.g. \"github.repos\")",
+                stream, reason
+            ),
+            BuildError::MissingEntity => {
+                write!(f, "entity ID is required: call .entity(\"...\") before .build()")
+            }
+            BuildError::PropertiesNotObject { found } => write!(
+                f,
+                "properties must be a JSON object of name → value, got {}",
+                found
+            ),
+            BuildError::InvalidProperty { name, error } => {
+                write!(f, "property '{}' cannot be serialized: {}", name, error)
+            }
+            BuildError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<ValidationError> for BuildError {
+    fn from(e: ValidationError) -> Self {
+        BuildError::Invalid(e)
+    }
+}
+
+/// Builds state events: an entity ID and properties, wrapped in the payload
+/// envelope the state engine reads (`{"entity_id", "properties"}`).
+///
+/// ```
+/// use flux::event::FluxEventBuilder;
+///
+/// let event = FluxEventBuilder::new("connectors", "connector-manager")
+///     .entity("github/repo/alice/x")
+///     .property("stars", 10)
+///     .key_from_entity()
+///     .schema("github.repository")
+///     .build()
+///     .unwrap();
+/// assert_eq!(event.payload["properties"]["stars"], 10);
+/// ```
+///
+/// Mistakes are recorded as they happen and the first one is returned by
+/// [`build`](Self::build), so calls can be chained unconditionally.
+#[derive(Debug, Clone)]
+pub struct FluxEventBuilder {
+    stream: String,
+    source: String,
+    entity_id: Option<String>,
+    properties: Map<String, Value>,
+    key: Option<String>,
+    key_from_entity: bool,
+    schema: Option<String>,
+    timestamp: Option<i64>,
+    error: Option<BuildError>,
+}
+
+impl FluxEventBuilder {
+    pub fn new(stream: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            stream: stream.into(),
+            source: source.into(),
+            entity_id: None,
+            properties: Map::new(),
+            key: None,
+            key_from_entity: false,
+            schema: None,
+            timestamp: None,
+            error: None,
+        }
+    }
+
+    /// Entity the event updates, e.g. `"github/repo/alice/x"`.
+    pub fn entity(mut self, entity_id: impl Into<String>) -> Self {
+        self.entity_id = Some(entity_id.into());
+        self
+    }
+
+    /// Sets one property, replacing an earlier value of the same name.
+    pub fn property(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let name = name.into();
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.properties.insert(name, value);
+            }
+            Err(e) => self.fail(BuildError::InvalidProperty {
+                name,
+                error: e.to_string(),
+            }),
+        }
+        self
+    }
+
+    /// Sets every field of `properties`, which must serialize to a JSON
+    /// object (a map or a struct).
+    pub fn properties(mut self, properties: impl Serialize) -> Self {
+        match serde_json::to_value(properties) {
+            Ok(Value::Object(map)) => self.properties.extend(map),
+            Ok(other) => self.fail(BuildError::PropertiesNotObject {
+                found: json_type(&other),
+            }),
+            Err(e) => self.fail(BuildError::InvalidProperty {
+                name: "properties".to_string(),
+                error: e.to_string(),
+            }),
+        }
+        self
+    }
+
+    /// Ordering/grouping key.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self.key_from_entity = false;
+        self
+    }
+
+    /// Uses the entity ID as the key, so updates to one entity stay ordered.
+    pub fn key_from_entity(mut self) -> Self {
+        self.key = None;
+        self.key_from_entity = true;
+        self
+    }
+
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Producer time in Unix epoch milliseconds (default: now).
+    pub fn at(mut self, timestamp_ms: i64) -> Self {
+        self.timestamp = Some(timestamp_ms);
+        self
+    }
+
+    /// Assembles the event and validates it like ingestion would, assigning
+    /// an event ID.
+    ///
+    /// Timestamps that look like seconds are rejected rather than converted.
+    pub fn build(self) -> Result<FluxEvent, BuildError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if let Some(reason) = stream_problem(&self.stream) {
+            return Err(BuildError::InvalidStream {
+                stream: self.stream,
+                reason,
+            });
+        }
+        let entity_id = match self.entity_id {
+            Some(id) if !id.is_empty() => id,
+            _ => return Err(BuildError::MissingEntity),
+        };
+        let timestamp = self
+            .timestamp
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        if timestamp > 0 && timestamp < MIN_MILLIS_TIMESTAMP {
+            return Err(ValidationError::TimestampInSeconds(timestamp).into());
+        }
+
+        let key = if self.key_from_entity {
+            Some(entity_id.clone())
+        } else {
+            self.key
+        };
+        let mut event = FluxEvent {
+            event_id: None,
+            stream: self.stream,
+            source: self.source,
+            timestamp,
+            received_at: None,
+            key,
+            schema: self.schema,
+            payload: serde_json::json!({
+                "entity_id": entity_id,
+                "properties": self.properties,
+            }),
+        };
+        validate_and_prepare(&mut event)?;
+        Ok(event)
+    }
+
+    /// Validated tombstone that deletes `entity_id` (see [`FluxEvent::tombstone`]).
+    pub fn tombstone(entity_id: &str, source: &str) -> Result<FluxEvent, BuildError> {
+        if entity_id.is_empty() {
+            return Err(BuildError::MissingEntity);
+        }
+        let mut event = FluxEvent::tombstone(entity_id, source);
+        validate_and_prepare(&mut event)?;
+        Ok(event)
+    }
+
+    /// Keeps the first error; later ones are usually consequences of it.
+    fn fail(&mut self, error: BuildError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// Explains why `stream` fails [`is_valid_stream_name`] or is reserved.
+fn stream_problem(stream: &str) -> Option<String> {
+    if stream.is_empty() {
+        return Some("stream is empty".to_string());
+    }
+    if is_reserved_stream_name(stream) {
+        return Some("reserved for NATS ingestion".to_string());
+    }
+    if is_valid_stream_name(stream) {
+        return None;
+    }
+    if let Some((i, c)) = stream
+        .char_indices()
+        .find(|&(_, c)| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.'))
+    {
+        let hint = if c.is_ascii_uppercase() {
+            format!("; use '{}'", c.to_ascii_lowercase())
+        } else {
+            String::new()
+        };
+        return Some(format!(
+            "character '{}' at byte {} is not allowed{}",
+            c, i, hint
+        ));
+    }
+    if stream.starts_with('.') || stream.ends_with('.') {
+        return Some("starts or ends with a dot".to_string());
+    }
+    Some("contains an empty segment ('..')".to_string())
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::{BTreeMap, HashMap};
+
+    const TS: i64 = 1_707_668_400_000;
+
+    fn builder() -> FluxEventBuilder {
+        FluxEventBuilder::new("connectors", "connector-manager").entity("github/repo/alice/x")
+    }
+
+    #[test]
+    fn test_builds_state_event_envelope() {
+        let event = builder()
+            .property("stars", 10)
+            .property("language", "Rust")
+            .property("description", None::<String>)
+            .key_from_entity()
+            .schema("github.repository")
+            .at(TS)
+            .build()
+            .unwrap();
+
+        assert_eq!(event.stream, "connectors");
+        assert_eq!(event.source, "connector-manager");
+        assert_eq!(event.timestamp, TS);
+        assert_eq!(event.key.as_deref(), Some("github/repo/alice/x"));
+        assert_eq!(event.schema.as_deref(), Some("github.repository"));
+        assert_eq!(event.event_id.as_ref().map(|id| id.len()), Some(36));
+        assert_eq!(event.received_at, None);
+        assert_eq!(
+            event.payload,
+            json!({
+                "entity_id": "github/repo/alice/x",
+                "properties": {"stars": 10, "language": "Rust", "description": null}
+            })
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let before = chrono::Utc::now().timestamp_millis();
+        let event = builder().build().unwrap();
+
+        assert!(event.timestamp >= before);
+        assert_eq!(event.key, None);
+        assert_eq!(event.schema, None);
+        assert_eq!(event.payload["properties"], json!({}));
+    }
+
+    #[test]
+    fn test_properties_merge_in_call_order() {
+        let mut map = HashMap::new();
+        map.insert("stars", json!(1));
+        map.insert("forks", json!(2));
+
+        let event = builder()
+            .property("stars", 0)
+            .properties(map)
+            .properties(json!({"forks": 3, "open": true}))
+            .property("open", false)
+            .at(TS)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            event.payload["properties"],
+            json!({"stars": 1, "forks": 3, "open": false})
+        );
+    }
+
+    #[test]
+    fn test_properties_from_struct() {
+        #[derive(Serialize)]
+        struct Repo {
+            name: &'static str,
+            tags: Vec<&'static str>,
+        }
+
+        let event = builder()
+            .properties(Repo {
+                name: "x",
+                tags: vec!["cli"],
+            })
+            .at(TS)
+            .build()
+            .unwrap();
+        assert_eq!(event.payload["properties"]["name"], "x");
+        assert_eq!(event.payload["properties"]["tags"], json!(["cli"]));
+    }
+
+    #[test]
+    fn test_explicit_key_and_key_from_entity_replace_each_other() {
+        let event = builder().key_from_entity().key("k").build().unwrap();
+        assert_eq!(event.key.as_deref(), Some("k"));
+
+        let event = builder().key("k").key_from_entity().build().unwrap();
+        assert_eq!(event.key.as_deref(), Some("github/repo/alice/x"));
+    }
+
+    #[test]
+    fn test_non_object_properties_are_rejected() {
+        for (value, found) in [
+            (json!([1, 2]), "an array"),
+            (json!("stars"), "a string"),
+            (json!(10), "a number"),
+            (json!(true), "a boolean"),
+            (Value::Null, "null"),
+        ] {
+            let err = builder().properties(value).build().unwrap_err();
+            assert_eq!(err, BuildError::PropertiesNotObject { found });
+            assert!(err.to_string().contains(found));
+        }
+    }
+
+    #[test]
+    fn test_unserializable_property_is_rejected() {
+        // JSON object keys must be strings
+        let mut map = BTreeMap::new();
+        map.insert((1, 2), "pair");
+
+        let err = builder().property("pairs", map).build().unwrap_err();
+        assert!(matches!(&err, BuildError::InvalidProperty { name, .. } if name == "pairs"));
+        assert!(err
+            .to_string()
+            .starts_with("property 'pairs' cannot be serialized"));
+    }
+
+    #[test]
+    fn test_first_error_wins() {
+        let err = builder()
+            .properties(json!([]))
+            .properties(json!("x"))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::PropertiesNotObject { found: "an array" });
+    }
+
+    #[test]
+    fn test_invalid_stream_names() {
+        let cases = [
+            ("", "stream is empty"),
+            ("GitHub", "character 'G' at byte 0 is not allowed; use 'g'"),
+            ("github-repos", "character '-' at byte 6 is not allowed"),
+            ("github repos", "character ' ' at byte 6 is not allowed"),
+            (".github", "starts or ends with a dot"),
+            ("github.", "starts or ends with a dot"),
+            ("github..repos", "contains an empty segment ('..')"),
+            ("ingest", "reserved for NATS ingestion"),
+            ("ingest.github", "reserved for NATS ingestion"),
+            ("rejected", "reserved for NATS ingestion"),
+        ];
+        for (stream, reason) in cases {
+            let err = FluxEventBuilder::new(stream, "src")
+                .entity("e")
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                err,
+                BuildError::InvalidStream {
+                    stream: stream.to_string(),
+                    reason: reason.to_string(),
+                },
+                "stream {:?}",
+                stream
+            );
+        }
+
+        let err = FluxEventBuilder::new("GitHub.Repos", "src")
+            .entity("e")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid stream 'GitHub.Repos': character 'G' at byte 0 is not allowed; use 'g' \
+             (streams are lowercase letters, digits and dots, e.g. \"github.repos\")"
+        );
+    }
+
+    #[test]
+    fn test_valid_stream_names() {
+        for stream in [
+            "sensors",
+            "github.repos",
+            "a.b.c",
+            "zone1.temp",
+            "ingestion",
+        ] {
+            assert!(FluxEventBuilder::new(stream, "src")
+                .entity("e")
+                .build()
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_missing_entity() {
+        let err = FluxEventBuilder::new("connectors", "src")
+            .property("stars", 1)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::MissingEntity);
+        assert!(err.to_string().contains(".entity("));
+
+        let err = FluxEventBuilder::new("connectors", "src")
+            .entity("")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::MissingEntity);
+    }
+
+    #[test]
+    fn test_missing_source() {
+        let err = FluxEventBuilder::new("connectors", "")
+            .entity("e")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::Invalid(ValidationError::MissingSource));
+        assert_eq!(err.to_string(), "source is required");
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let err = builder().at(0).build().unwrap_err();
+        assert_eq!(
+            err,
+            BuildError::Invalid(ValidationError::InvalidTimestamp(0))
+        );
+
+        let err = builder().at(-5).build().unwrap_err();
+        assert_eq!(
+            err,
+            BuildError::Invalid(ValidationError::InvalidTimestamp(-5))
+        );
+
+        let err = builder().at(1_707_668_400).build().unwrap_err();
+        assert_eq!(
+            err,
+            BuildError::Invalid(ValidationError::TimestampInSeconds(1_707_668_400))
+        );
+        assert!(err.to_string().contains("milliseconds"));
+
+        assert_eq!(
+            builder()
+                .at(MIN_MILLIS_TIMESTAMP)
+                .build()
+                .unwrap()
+                .timestamp,
+            MIN_MILLIS_TIMESTAMP
+        );
+    }
+
+    #[test]
+    fn test_tombstone() {
+        let event =
+            FluxEventBuilder::tombstone("github/repo/alice/x", "connector-manager").unwrap();
+        assert_eq!(event.stream, "flux.events.deletions");
+        assert_eq!(event.key.as_deref(), Some("github/repo/alice/x"));
+        assert_eq!(event.payload["entity_id"], "github/repo/alice/x");
+        assert_eq!(event.payload["properties"]["__deleted__"], true);
+        assert!(event.event_id.is_some());
+
+        assert_eq!(
+            FluxEventBuilder::tombstone("", "connector-manager").unwrap_err(),
+            BuildError::MissingEntity
+        );
+        assert_eq!(
+            FluxEventBuilder::tombstone("e", "").unwrap_err(),
+            BuildError::Invalid(ValidationError::MissingSource)
+        );
+    }
+}
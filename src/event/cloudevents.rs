@@ -0,0 +1,541 @@
+//! CloudEvents 1.0 (JSON) mapped onto [`FluxEvent`]s.
+//!
+//! Producers that already emit CloudEvents can post them as they are, in
+//! structured mode (`Content-Type: application/cloudevents+json`, the whole
+//! event in the body), binary mode (attributes in `ce-*` headers, `data` as
+//! the body) or as a batch (`application/cloudevents-batch+json`). The
+//! attributes map as follows:
+//!
+//! | CloudEvents | Flux |
+//! |-------------|------|
+//! | `id` | `eventId` |
+//! | `source` | `source` |
+//! | `type` | `schema` |
+//! | `time` | `timestamp` (ms; the receive time if absent) |
+//! | `subject` | `key` |
+//! | `data` | `payload`, which must be a JSON object |
+//!
+//! The stream comes from `type` or an extension attribute, chosen by the
+//! `cloudevents_stream_attribute` runtime config. The mapped event is then
+//! validated like any other.
+
+use super::FluxEvent;
+use axum::http::HeaderMap;
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Structured-mode content type
+pub const CLOUDEVENTS_CONTENT_TYPE: &str = "application/cloudevents+json";
+/// Batch content type: a JSON array of structured-mode events
+pub const CLOUDEVENTS_BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+/// Header whose presence marks a binary-mode request
+pub const SPECVERSION_HEADER: &str = "ce-specversion";
+
+/// The only spec version accepted
+const SPEC_VERSION: &str = "1.0";
+
+/// Attributes of the spec; any other top-level member is an extension
+const SPEC_ATTRIBUTES: &[&str] = &[
+    "id",
+    "source",
+    "specversion",
+    "type",
+    "datacontenttype",
+    "dataschema",
+    "subject",
+    "time",
+    "data",
+    "data_base64",
+];
+
+/// Why a CloudEvent couldn't be mapped to a Flux event
+#[derive(Debug, Clone, PartialEq)]
+pub enum CloudEventError {
+    /// Structured-mode body isn't a JSON object
+    NotAnObject,
+    /// A required attribute, or the one the stream comes from, is absent
+    MissingAttribute(String),
+    /// An attribute isn't a string, or isn't valid in a header
+    InvalidAttribute(String),
+    UnsupportedSpecVersion(String),
+    /// `time` isn't an RFC 3339 timestamp
+    InvalidTime(String),
+    /// `data` is absent, not JSON or not a JSON object
+    DataNotObject,
+}
+
+impl CloudEventError {
+    /// True if the event is well-formed but its data can't be a Flux payload
+    pub fn is_unprocessable(&self) -> bool {
+        matches!(self, CloudEventError::DataNotObject)
+    }
+}
+
+impl fmt::Display for CloudEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudEventError::NotAnObject => write!(f, "CloudEvent must be a JSON object"),
+            CloudEventError::MissingAttribute(name) => {
+                write!(f, "CloudEvent attribute '{}' is required", name)
+            }
+            CloudEventError::InvalidAttribute(name) => {
+                write!(f, "CloudEvent attribute '{}' must be a string", name)
+            }
+            CloudEventError::UnsupportedSpecVersion(version) => write!(
+                f,
+                "unsupported CloudEvents specversion '{}' (expected {})",
+                version, SPEC_VERSION
+            ),
+            CloudEventError::InvalidTime(time) => {
+                write!(f, "CloudEvent time '{}' is not an RFC 3339 timestamp", time)
+            }
+            CloudEventError::DataNotObject => {
+                write!(f, "CloudEvent data must be a JSON object")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CloudEventError {}
+
+/// A parsed CloudEvent, before mapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudEvent {
+    pub id: String,
+    pub source: String,
+    pub event_type: String,
+    pub time: Option<String>,
+    pub subject: Option<String>,
+    /// `None` if the event has no `data`
+    pub data: Option<Value>,
+    /// Extension attributes (binary mode: always strings)
+    pub extensions: BTreeMap<String, Value>,
+}
+
+impl CloudEvent {
+    /// Parse a structured-mode event (one element of a batch, or the body)
+    pub fn from_structured(event: Value) -> Result<Self, CloudEventError> {
+        let Value::Object(mut attributes) = event else {
+            return Err(CloudEventError::NotAnObject);
+        };
+        let mut take = |name: &str| match attributes.remove(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(CloudEventError::InvalidAttribute(name.to_string())),
+        };
+        check_specversion(take("specversion")?)?;
+        let id = required(take("id")?, "id")?;
+        let source = required(take("source")?, "source")?;
+        let event_type = required(take("type")?, "type")?;
+        let time = take("time")?;
+        let subject = take("subject")?;
+        let data = match attributes.remove("data") {
+            Some(Value::Null) | None => None,
+            Some(data) => Some(data),
+        };
+        // Binary data can't be a JSON object
+        if data.is_none() && attributes.contains_key("data_base64") {
+            return Err(CloudEventError::DataNotObject);
+        }
+        let extensions = attributes
+            .into_iter()
+            .filter(|(name, _)| !SPEC_ATTRIBUTES.contains(&name.as_str()))
+            .collect();
+        Ok(Self {
+            id,
+            source,
+            event_type,
+            time,
+            subject,
+            data,
+            extensions,
+        })
+    }
+
+    /// Parse a binary-mode event: attributes from `ce-*` headers, `body` as data
+    pub fn from_binary(headers: &HeaderMap, body: &[u8]) -> Result<Self, CloudEventError> {
+        let mut attributes = BTreeMap::new();
+        for (name, value) in headers {
+            let Some(attribute) = name.as_str().strip_prefix("ce-") else {
+                continue;
+            };
+            let value = value
+                .to_str()
+                .ok()
+                .and_then(percent_decode)
+                .ok_or_else(|| CloudEventError::InvalidAttribute(attribute.to_string()))?;
+            attributes.insert(attribute.to_string(), value);
+        }
+        let mut take = |name: &str| attributes.remove(name);
+        check_specversion(take("specversion"))?;
+        let id = required(take("id"), "id")?;
+        let source = required(take("source"), "source")?;
+        let event_type = required(take("type"), "type")?;
+        let time = take("time");
+        let subject = take("subject");
+        let data = if body.iter().all(u8::is_ascii_whitespace) {
+            None
+        } else {
+            Some(serde_json::from_slice(body).map_err(|_| CloudEventError::DataNotObject)?)
+        };
+        let extensions = attributes
+            .into_iter()
+            .filter(|(name, _)| !SPEC_ATTRIBUTES.contains(&name.as_str()))
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        Ok(Self {
+            id,
+            source,
+            event_type,
+            time,
+            subject,
+            data,
+            extensions,
+        })
+    }
+
+    /// The equivalent Flux event, its stream taken from `stream_attribute`
+    /// (`type` or an extension) and its timestamp `now_ms` if it has no `time`
+    pub fn into_flux_event(
+        self,
+        stream_attribute: &str,
+        now_ms: i64,
+    ) -> Result<FluxEvent, CloudEventError> {
+        let stream = if stream_attribute == "type" {
+            self.event_type.clone()
+        } else {
+            match self.extensions.get(stream_attribute) {
+                Some(Value::String(stream)) => stream.clone(),
+                Some(_) => {
+                    return Err(CloudEventError::InvalidAttribute(
+                        stream_attribute.to_string(),
+                    ))
+                }
+                None => {
+                    return Err(CloudEventError::MissingAttribute(
+                        stream_attribute.to_string(),
+                    ))
+                }
+            }
+        };
+        let timestamp = match &self.time {
+            Some(time) => DateTime::parse_from_rfc3339(time)
+                .map_err(|_| CloudEventError::InvalidTime(time.clone()))?
+                .timestamp_millis(),
+            None => now_ms,
+        };
+        let payload: Map<String, Value> = match self.data {
+            Some(Value::Object(data)) => data,
+            _ => return Err(CloudEventError::DataNotObject),
+        };
+        Ok(FluxEvent {
+            event_id: Some(self.id),
+            stream,
+            source: self.source,
+            timestamp,
+            received_at: None,
+            key: self.subject,
+            schema: Some(self.event_type),
+            payload: Value::Object(payload),
+        })
+    }
+}
+
+/// `value` of the required attribute `name`
+fn required(value: Option<String>, name: &str) -> Result<String, CloudEventError> {
+    value
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| CloudEventError::MissingAttribute(name.to_string()))
+}
+
+fn check_specversion(specversion: Option<String>) -> Result<(), CloudEventError> {
+    match required(specversion, "specversion")? {
+        version if version == SPEC_VERSION => Ok(()),
+        version => Err(CloudEventError::UnsupportedSpecVersion(version)),
+    }
+}
+
+/// Decode `%XX` escapes in a binary-mode header value; None if malformed
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    const NOW_MS: i64 = 1_700_000_000_000;
+
+    fn structured() -> Value {
+        json!({
+            "specversion": "1.0",
+            "id": "a1b2c3",
+            "source": "/plant/line-4",
+            "type": "plant.telemetry",
+            "time": "2026-01-01T00:00:00.250Z",
+            "subject": "pump-7",
+            "datacontenttype": "application/json",
+            "data": {"entity_id": "plant/pump-7", "properties": {"rpm": 1450}}
+        })
+    }
+
+    fn binary_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("ce-specversion", "1.0"),
+            ("ce-id", "a1b2c3"),
+            ("ce-source", "/plant/line-4"),
+            ("ce-type", "plant.telemetry"),
+            ("ce-time", "2026-01-01T00:00:00.250Z"),
+            ("ce-subject", "pump-7"),
+            ("content-type", "application/json"),
+        ] {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    const BINARY_BODY: &[u8] = br#"{"entity_id": "plant/pump-7", "properties": {"rpm": 1450}}"#;
+
+    fn map(event: Value) -> Result<FluxEvent, CloudEventError> {
+        CloudEvent::from_structured(event)?.into_flux_event("type", NOW_MS)
+    }
+
+    fn assert_mapped(event: &FluxEvent) {
+        assert_eq!(event.event_id.as_deref(), Some("a1b2c3"));
+        assert_eq!(event.stream, "plant.telemetry");
+        assert_eq!(event.source, "/plant/line-4");
+        assert_eq!(event.schema.as_deref(), Some("plant.telemetry"));
+        assert_eq!(event.key.as_deref(), Some("pump-7"));
+        assert_eq!(event.timestamp, 1_767_225_600_250);
+        assert_eq!(event.payload["properties"]["rpm"], 1450);
+    }
+
+    #[test]
+    fn test_structured_event_maps_attributes() {
+        let event = map(structured()).unwrap();
+        assert_mapped(&event);
+        assert_eq!(event.received_at, None);
+    }
+
+    #[test]
+    fn test_binary_event_maps_attributes() {
+        let event = CloudEvent::from_binary(&binary_headers(), BINARY_BODY)
+            .unwrap()
+            .into_flux_event("type", NOW_MS)
+            .unwrap();
+        assert_mapped(&event);
+    }
+
+    #[test]
+    fn test_binary_and_structured_parse_alike() {
+        let binary = CloudEvent::from_binary(&binary_headers(), BINARY_BODY).unwrap();
+        assert_eq!(binary, CloudEvent::from_structured(structured()).unwrap());
+    }
+
+    #[test]
+    fn test_mapped_event_passes_validation() {
+        let mut event = map(structured()).unwrap();
+        assert!(event.validate_and_prepare().is_ok());
+        // The CloudEvent id is kept rather than replaced by a UUIDv7
+        assert_eq!(event.event_id.as_deref(), Some("a1b2c3"));
+    }
+
+    #[test]
+    fn test_optional_attributes_may_be_missing() {
+        let mut event = structured();
+        let attributes = event.as_object_mut().unwrap();
+        attributes.remove("time");
+        attributes.remove("subject");
+        attributes.remove("datacontenttype");
+        attributes.insert("dataschema".to_string(), Value::Null);
+
+        let event = map(event).unwrap();
+        assert_eq!(event.timestamp, NOW_MS);
+        assert_eq!(event.key, None);
+
+        let mut headers = binary_headers();
+        headers.remove("ce-time");
+        headers.remove("ce-subject");
+        let event = CloudEvent::from_binary(&headers, BINARY_BODY)
+            .unwrap()
+            .into_flux_event("type", NOW_MS)
+            .unwrap();
+        assert_eq!(event.timestamp, NOW_MS);
+        assert_eq!(event.key, None);
+    }
+
+    #[test]
+    fn test_required_attributes() {
+        for name in ["specversion", "id", "source", "type"] {
+            let mut event = structured();
+            event.as_object_mut().unwrap().remove(name);
+            assert_eq!(
+                map(event).unwrap_err(),
+                CloudEventError::MissingAttribute(name.to_string())
+            );
+
+            let mut event = structured();
+            event[name] = json!("");
+            assert_eq!(
+                map(event).unwrap_err(),
+                CloudEventError::MissingAttribute(name.to_string())
+            );
+
+            let mut headers = binary_headers();
+            headers.remove(format!("ce-{}", name).as_str());
+            assert_eq!(
+                CloudEvent::from_binary(&headers, BINARY_BODY),
+                Err(CloudEventError::MissingAttribute(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        let mut event = structured();
+        event["id"] = json!(42);
+        assert_eq!(
+            map(event).unwrap_err(),
+            CloudEventError::InvalidAttribute("id".to_string())
+        );
+
+        let mut event = structured();
+        event["specversion"] = json!("0.3");
+        assert_eq!(
+            map(event).unwrap_err(),
+            CloudEventError::UnsupportedSpecVersion("0.3".to_string())
+        );
+
+        let mut event = structured();
+        event["time"] = json!("yesterday");
+        let error = map(event).unwrap_err();
+        assert_eq!(error, CloudEventError::InvalidTime("yesterday".to_string()));
+        assert!(!error.is_unprocessable());
+
+        assert_eq!(
+            CloudEvent::from_structured(json!([structured()])),
+            Err(CloudEventError::NotAnObject)
+        );
+
+        let mut headers = binary_headers();
+        headers.insert("ce-id", HeaderValue::from_static("a%2"));
+        assert_eq!(
+            CloudEvent::from_binary(&headers, BINARY_BODY),
+            Err(CloudEventError::InvalidAttribute("id".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_non_object_data_rejected() {
+        for data in [json!([1, 2]), json!("text"), json!(42), Value::Null] {
+            let mut event = structured();
+            event["data"] = data;
+            let error = map(event).unwrap_err();
+            assert_eq!(error, CloudEventError::DataNotObject);
+            assert!(error.is_unprocessable());
+        }
+
+        let mut event = structured();
+        event.as_object_mut().unwrap().remove("data");
+        assert_eq!(map(event).unwrap_err(), CloudEventError::DataNotObject);
+
+        let mut event = structured();
+        let attributes = event.as_object_mut().unwrap();
+        attributes.remove("data");
+        attributes.insert("data_base64".to_string(), json!("AAEC"));
+        assert_eq!(map(event).unwrap_err(), CloudEventError::DataNotObject);
+
+        for body in [&b"[1, 2]"[..], b"plain text", b"", b"  "] {
+            let error = CloudEvent::from_binary(&binary_headers(), body)
+                .and_then(|event| event.into_flux_event("type", NOW_MS))
+                .unwrap_err();
+            assert_eq!(error, CloudEventError::DataNotObject);
+        }
+    }
+
+    #[test]
+    fn test_stream_from_extension_attribute() {
+        let mut event = structured();
+        event["fluxstream"] = json!("plant.pumps");
+        let mapped = CloudEvent::from_structured(event)
+            .unwrap()
+            .into_flux_event("fluxstream", NOW_MS)
+            .unwrap();
+        assert_eq!(mapped.stream, "plant.pumps");
+        assert_eq!(mapped.schema.as_deref(), Some("plant.telemetry"));
+
+        let mut headers = binary_headers();
+        headers.insert("ce-fluxstream", HeaderValue::from_static("plant.pumps"));
+        let mapped = CloudEvent::from_binary(&headers, BINARY_BODY)
+            .unwrap()
+            .into_flux_event("fluxstream", NOW_MS)
+            .unwrap();
+        assert_eq!(mapped.stream, "plant.pumps");
+
+        let missing = CloudEvent::from_structured(structured())
+            .unwrap()
+            .into_flux_event("fluxstream", NOW_MS);
+        assert_eq!(
+            missing.unwrap_err(),
+            CloudEventError::MissingAttribute("fluxstream".to_string())
+        );
+
+        let mut event = structured();
+        event["fluxstream"] = json!(7);
+        let not_string = CloudEvent::from_structured(event)
+            .unwrap()
+            .into_flux_event("fluxstream", NOW_MS);
+        assert_eq!(
+            not_string.unwrap_err(),
+            CloudEventError::InvalidAttribute("fluxstream".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extensions_exclude_spec_attributes() {
+        let mut event = structured();
+        event["traceparent"] = json!("00-abc-def-01");
+        let parsed = CloudEvent::from_structured(event).unwrap();
+        let names: Vec<&str> = parsed.extensions.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["traceparent"]);
+    }
+
+    #[test]
+    fn test_binary_header_values_are_percent_decoded() {
+        let mut headers = binary_headers();
+        headers.insert(
+            "ce-subject",
+            HeaderValue::from_static("pump%207%20%E2%9C%93"),
+        );
+        let event = CloudEvent::from_binary(&headers, BINARY_BODY).unwrap();
+        assert_eq!(event.subject.as_deref(), Some("pump 7 ✓"));
+    }
+
+    #[test]
+    fn test_stream_from_type_still_validated() {
+        let mut event = structured();
+        event["type"] = json!("com.example.Order-Created");
+        let mut mapped = map(event).unwrap();
+        assert!(matches!(
+            mapped.validate_and_prepare(),
+            Err(crate::event::ValidationError::InvalidStreamFormat(_))
+        ));
+    }
+}
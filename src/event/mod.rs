@@ -1,17 +1,41 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use utoipa::ToSchema;
 
-mod validation;
+mod builder;
+mod cloudevents;
+mod raw;
 #[cfg(test)]
 mod tests;
+mod validation;
 
-pub use validation::{validate_and_prepare, ValidationError};
+pub use builder::{BuildError, FluxEventBuilder};
+pub use cloudevents::{
+    CloudEvent, CloudEventError, CLOUDEVENTS_BATCH_CONTENT_TYPE, CLOUDEVENTS_CONTENT_TYPE,
+    SPECVERSION_HEADER,
+};
+pub use raw::RawFluxEvent;
+pub use validation::{
+    check_limits, check_timestamp, timestamp_is_plausible, validate_and_prepare, EventLimits,
+    SecondsTimestampPolicy, TimestampRules, ValidationError, DEFAULT_MAX_TIMESTAMP_SKEW_MS,
+    MIN_MILLIS_TIMESTAMP,
+};
+pub(crate) use validation::{is_valid_stream_name, serialized_len};
 
 /// FluxEvent represents an immutable event in the Flux system.
 ///
 /// Events have a fixed envelope structure with domain-agnostic payload.
 /// All events are time-ordered via UUIDv7 identifiers.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "stream": "sensors",
+    "source": "sensor-01",
+    "timestamp": 1700000000000i64,
+    "payload": {
+        "entity_id": "temp-sensor-01",
+        "properties": {"temperature": 22.5, "unit": "celsius"}
+    }
+}))]
 pub struct FluxEvent {
     /// UUIDv7 identifier (time-ordered, globally unique)
     /// Auto-generated if not provided
@@ -27,9 +51,14 @@ pub struct FluxEvent {
     pub source: String,
 
     /// Unix epoch milliseconds (producer time)
-    /// Must be positive
+    /// Must be positive and not too far in the future
     pub timestamp: i64,
 
+    /// Unix epoch milliseconds when Flux accepted the event (server time)
+    /// Set by ingestion; absent on events published before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<i64>,
+
     /// Optional ordering/grouping key
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key: Option<String>,
@@ -40,6 +69,7 @@ pub struct FluxEvent {
 
     /// Domain-specific event data (opaque to Flux)
     /// Must be a valid JSON object
+    #[schema(value_type = Object)]
     pub payload: Value,
 }
 
@@ -57,4 +87,115 @@ impl FluxEvent {
     pub fn validate_and_prepare(&mut self) -> Result<(), ValidationError> {
         validation::validate_and_prepare(self)
     }
+
+    /// Checks payload size, property count, property name and string value
+    /// lengths against `limits`.
+    pub fn check_limits(&self, limits: &EventLimits) -> Result<(), ValidationError> {
+        validation::check_limits(self, limits)
+    }
+
+    /// Checks the producer timestamp against server time `now_ms` (epoch
+    /// milliseconds), converting seconds-scale timestamps if `rules` allow.
+    pub fn check_timestamp(
+        &mut self,
+        rules: &TimestampRules,
+        now_ms: i64,
+    ) -> Result<(), ValidationError> {
+        validation::check_timestamp(self, rules, now_ms)
+    }
+
+    /// Build a tombstone event that deletes `entity_id` when processed.
+    ///
+    /// Published on the "flux.events.deletions" stream; call
+    /// `validate_and_prepare` before publishing to assign an event ID.
+    pub fn tombstone(entity_id: &str, source: &str) -> Self {
+        let now = chrono::Utc::now().timestamp_millis();
+        Self {
+            event_id: None,
+            stream: "flux.events.deletions".to_string(),
+            source: source.to_string(),
+            timestamp: now,
+            received_at: None,
+            key: Some(entity_id.to_string()),
+            schema: None,
+            payload: serde_json::json!({
+                "entity_id": entity_id,
+                "properties": {
+                    "__deleted__": true,
+                    "__deleted_at__": now
+                }
+            }),
+        }
+    }
+
+    /// Build an event that restores a deleted entity's `properties`.
+    ///
+    /// Published on the deletions stream, after the tombstone it reverses, and
+    /// forced so it applies regardless of event ordering.
+    pub fn undelete(
+        entity_id: &str,
+        properties: &std::collections::HashMap<String, Value>,
+        source: &str,
+    ) -> Self {
+        Self {
+            event_id: None,
+            stream: "flux.events.deletions".to_string(),
+            source: source.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            received_at: None,
+            key: Some(entity_id.to_string()),
+            schema: None,
+            payload: serde_json::json!({
+                "entity_id": entity_id,
+                "properties": properties,
+                "force": true
+            }),
+        }
+    }
+
+    /// Build an event that sets `namespace`'s quota.
+    ///
+    /// Published on the "flux.quotas" stream, so the state engine applies
+    /// the quota in stream order.
+    pub fn namespace_quota(
+        namespace: &str,
+        quota: &crate::state::NamespaceQuota,
+        source: &str,
+    ) -> Self {
+        Self {
+            event_id: None,
+            stream: crate::state::QUOTAS_STREAM.to_string(),
+            source: source.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            received_at: None,
+            key: Some(namespace.to_string()),
+            schema: None,
+            payload: serde_json::json!({
+                "namespace": namespace,
+                "max_entities": quota.max_entities,
+                "max_bytes": quota.max_bytes
+            }),
+        }
+    }
+
+    /// Build an event carrying a message from entity `from` to entity `to`.
+    ///
+    /// Published on the "flux.messages" stream with `from` as the payload's
+    /// `entity_id`, so it is authorized like a write to the sender.
+    pub fn agent_message(from: &str, to: &str, body: Value, source: &str) -> Self {
+        Self {
+            event_id: None,
+            stream: crate::state::MESSAGES_STREAM.to_string(),
+            source: source.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            received_at: None,
+            key: Some(to.to_string()),
+            schema: None,
+            payload: serde_json::json!({
+                "entity_id": from,
+                "to": to,
+                "body": body
+            }),
+        }
+    }
 }
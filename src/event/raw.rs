@@ -0,0 +1,344 @@
+//! Events whose payload stays the JSON text it arrived in.
+//!
+//! `POST /api/events` only needs the envelope and a few facts about the
+//! payload (is it an object, its `entity_id`, property names and string
+//! lengths), so it parses the envelope and keeps the payload as a
+//! [`RawValue`] slice of the request body. The payload is scanned for those
+//! facts without building a [`Value`](serde_json::Value), and published
+//! as-is. The state engine still parses events in full.
+
+use super::validation::{
+    assign_event_id, check_payload_size, check_property_names, check_string_lengths,
+    check_timestamp_ms, validate_envelope, Envelope, PayloadKind,
+};
+use super::{EventLimits, FluxEvent, TimestampRules, ValidationError};
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A [`FluxEvent`] with its payload borrowed as raw JSON.
+///
+/// Serializes to the same JSON as the equivalent `FluxEvent`, except that
+/// the payload keeps the formatting it arrived with.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RawFluxEvent<'a> {
+    #[serde(rename = "eventId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    pub stream: String,
+    pub source: String,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(borrow)]
+    pub payload: &'a RawValue,
+    /// Top-level payload fields, when the payload is an object
+    #[serde(skip)]
+    fields: Option<BTreeMap<String, &'a RawValue>>,
+}
+
+impl<'a> RawFluxEvent<'a> {
+    /// Parse an event from `json`, borrowing its payload
+    pub fn from_slice(json: &'a [u8]) -> serde_json::Result<Self> {
+        let mut event: Self = serde_json::from_slice(json)?;
+        // Keys are copied (escapes may need decoding); values stay borrowed
+        let payload = event.payload;
+        event.fields = serde_json::from_str(payload.get()).ok();
+        Ok(event)
+    }
+
+    /// [`FluxEvent::validate_and_prepare`] without parsing the payload
+    pub fn validate_and_prepare(&mut self) -> Result<(), ValidationError> {
+        let payload = if self.fields.is_some() {
+            PayloadKind::Object
+        } else if self.payload.get() == "null" {
+            PayloadKind::Null
+        } else {
+            PayloadKind::Other
+        };
+        validate_envelope(&Envelope {
+            stream: &self.stream,
+            source: &self.source,
+            timestamp: self.timestamp,
+            payload,
+        })?;
+        assign_event_id(&mut self.event_id);
+        Ok(())
+    }
+
+    /// [`FluxEvent::check_limits`], scanning the payload text.
+    ///
+    /// Size is that of the payload with insignificant whitespace removed.
+    pub fn check_limits(&self, limits: &EventLimits) -> Result<(), ValidationError> {
+        check_payload_size(compact_len(self.payload.get()), limits)?;
+
+        let Some(fields) = &self.fields else {
+            return Ok(());
+        };
+        let properties = fields.get("properties").and_then(|p| parse_object(p));
+        let names = properties.as_ref().unwrap_or(fields);
+        check_property_names(names.keys().map(String::as_str), limits)?;
+
+        for (name, value) in fields {
+            match &properties {
+                Some(props) if name == "properties" => check_string_lengths(
+                    props
+                        .iter()
+                        .map(|(property, value)| (property.as_str(), longest_string(value))),
+                    limits,
+                )?,
+                _ => check_string_lengths(
+                    std::iter::once((name.as_str(), longest_string(value))),
+                    limits,
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// [`FluxEvent::check_timestamp`]
+    pub fn check_timestamp(
+        &mut self,
+        rules: &TimestampRules,
+        now_ms: i64,
+    ) -> Result<(), ValidationError> {
+        check_timestamp_ms(&mut self.timestamp, rules, now_ms)
+    }
+
+    /// The payload's `entity_id`, if it is a string
+    pub fn entity_id(&self) -> Option<String> {
+        let raw = self.fields.as_ref()?.get("entity_id")?;
+        serde_json::from_str::<String>(raw.get()).ok()
+    }
+
+    /// Parse the payload, giving the equivalent [`FluxEvent`]
+    pub fn to_event(&self) -> serde_json::Result<FluxEvent> {
+        Ok(FluxEvent {
+            event_id: self.event_id.clone(),
+            stream: self.stream.clone(),
+            source: self.source.clone(),
+            timestamp: self.timestamp,
+            received_at: self.received_at,
+            key: self.key.clone(),
+            schema: self.schema.clone(),
+            payload: serde_json::from_str(self.payload.get())?,
+        })
+    }
+}
+
+fn parse_object(raw: &RawValue) -> Option<BTreeMap<String, &RawValue>> {
+    serde_json::from_str(raw.get()).ok()
+}
+
+/// Byte length of the longest string anywhere in `raw`
+fn longest_string(raw: &RawValue) -> Option<usize> {
+    serde_json::from_str::<LongestString>(raw.get())
+        .ok()
+        .and_then(|longest| longest.0)
+}
+
+/// Byte length of the longest string in a JSON value, read without
+/// building the value
+struct LongestString(Option<usize>);
+
+impl<'de> Deserialize<'de> for LongestString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LongestStringVisitor)
+    }
+}
+
+struct LongestStringVisitor;
+
+impl<'de> Visitor<'de> for LongestStringVisitor {
+    type Value = LongestString;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(LongestString(None))
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(LongestString(None))
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(LongestString(None))
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(LongestString(None))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(LongestString(None))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(LongestString(Some(s.len())))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut longest = None;
+        while let Some(LongestString(item)) = seq.next_element()? {
+            longest = longest.max(item);
+        }
+        Ok(LongestString(longest))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut longest = None;
+        while let Some((IgnoredAny, LongestString(value))) = map.next_entry()? {
+            longest = longest.max(value);
+        }
+        Ok(LongestString(longest))
+    }
+}
+
+/// Length of `json` without whitespace outside strings
+fn compact_len(json: &str) -> usize {
+    let mut len = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for byte in json.bytes() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+        } else if byte == b'"' {
+            in_string = true;
+        } else if matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            continue;
+        }
+        len += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{serialized_len, SecondsTimestampPolicy};
+    use serde_json::{json, Value};
+
+    const LIMITS: EventLimits = EventLimits {
+        max_payload_bytes: 200,
+        max_properties_per_event: 3,
+        max_property_name_length: 8,
+        max_string_value_length: 10,
+    };
+
+    fn event(payload: Value) -> Value {
+        json!({
+            "stream": "sensors",
+            "source": "sensor-001",
+            "timestamp": 1707668400000i64,
+            "payload": payload
+        })
+    }
+
+    /// Raw and parsed checks agree on `body`
+    fn assert_same_verdict(body: &str) {
+        let mut parsed: FluxEvent = serde_json::from_str(body).unwrap();
+        let mut raw = RawFluxEvent::from_slice(body.as_bytes()).unwrap();
+        let rules = TimestampRules {
+            max_skew_seconds: 300,
+            seconds_policy: SecondsTimestampPolicy::Reject,
+        };
+        let now = 1707668400000;
+
+        let expected = parsed
+            .validate_and_prepare()
+            .and_then(|_| parsed.check_limits(&LIMITS))
+            .and_then(|_| parsed.check_timestamp(&rules, now));
+        let actual = raw
+            .validate_and_prepare()
+            .and_then(|_| raw.check_limits(&LIMITS))
+            .and_then(|_| raw.check_timestamp(&rules, now));
+        assert_eq!(actual, expected, "{}", body);
+    }
+
+    #[test]
+    fn test_raw_checks_match_parsed_checks() {
+        let payloads = [
+            json!({"entity_id": "e1", "properties": {"a": 1, "b": "ok"}}),
+            json!({"entity_id": "e1", "properties": {"a": 1, "b": 2, "c": 3, "d": 4}}),
+            json!({"a": 1, "b": 2, "c": 3, "d": 4}),
+            json!({"entity_id": "e1", "properties": {"abcdefghi": 1}}),
+            json!({"entity_id": "e1", "properties": {"zz": "0123456789x", "cfg": {"t": ["0123456789xy"]}}}),
+            json!({"entity_id": "0123456789x", "properties": {}}),
+            json!({"entity_id": "e1", "properties": "0123456789x"}),
+            json!({"blob": "x".repeat(300)}),
+            json!({"entity_id": "e\u{e9}", "properties": {"caf\u{e9}": "\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}"}}),
+            json!([1, 2]),
+            json!("text"),
+            Value::Null,
+        ];
+        for payload in payloads {
+            assert_same_verdict(&event(payload).to_string());
+        }
+
+        let mut bad_stream = event(json!({"entity_id": "e1"}));
+        bad_stream["stream"] = json!("Bad Stream");
+        assert_same_verdict(&bad_stream.to_string());
+        let mut seconds = event(json!({"entity_id": "e1"}));
+        seconds["timestamp"] = json!(1707668400);
+        assert_same_verdict(&seconds.to_string());
+    }
+
+    #[test]
+    fn test_escaped_strings_and_keys_are_decoded() {
+        let body = r#"{"stream":"sensors","source":"s","timestamp":1707668400000,
+            "payload":{"entity_id":"ns\/e1","properties":{"name":"\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9"}}}"#;
+        let raw = RawFluxEvent::from_slice(body.as_bytes()).unwrap();
+        assert_eq!(raw.entity_id().as_deref(), Some("ns/e1"));
+        // Six two-byte characters, not 36 bytes of escapes
+        assert_eq!(
+            raw.check_limits(&EventLimits {
+                max_string_value_length: 12,
+                ..LIMITS
+            }),
+            Ok(())
+        );
+        assert_same_verdict(body);
+    }
+
+    #[test]
+    fn test_size_ignores_insignificant_whitespace() {
+        let payload = json!({"entity_id": "e1", "properties": {"note": "a  b\t\"c\" \\"}});
+        let pretty = serde_json::to_string_pretty(&payload).unwrap();
+        assert_eq!(compact_len(&pretty), serialized_len(&payload));
+    }
+
+    #[test]
+    fn test_serializes_like_the_parsed_event() {
+        let body =
+            event(json!({"entity_id": "e1", "properties": {"a": [1, {"b": null}]}})).to_string();
+        let mut raw = RawFluxEvent::from_slice(body.as_bytes()).unwrap();
+        raw.validate_and_prepare().unwrap();
+        raw.received_at = Some(1707668400001);
+
+        let published: FluxEvent =
+            serde_json::from_slice(&serde_json::to_vec(&raw).unwrap()).unwrap();
+        let expected = raw.to_event().unwrap();
+        assert_eq!(
+            serde_json::to_value(&published).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+        assert_eq!(published.event_id, raw.event_id);
+        assert_eq!(published.received_at, Some(1707668400001));
+    }
+}
@@ -8,6 +8,7 @@ fn test_valid_event_passes_validation() {
         stream: "sensors.temperature".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000, // 2024-02-11 13:00:00 UTC
+        received_at: None,
         key: Some("zone1".to_string()),
         schema: Some("temp-v1".to_string()),
         payload: json!({"value": 23.5, "unit": "celsius"}),
@@ -26,6 +27,7 @@ fn test_missing_stream_fails() {
         stream: "".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -43,6 +45,7 @@ fn test_missing_source_fails() {
         stream: "sensors".to_string(),
         source: "".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -60,6 +63,7 @@ fn test_invalid_stream_format_fails() {
         stream: "Sensors.Temp".to_string(), // Uppercase not allowed
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -73,6 +77,42 @@ fn test_invalid_stream_format_fails() {
     }
 }
 
+#[test]
+fn test_reserved_streams_fail() {
+    for stream in ["ingest", "ingest.acme", "rejected"] {
+        let mut event = FluxEvent {
+            event_id: None,
+            stream: stream.to_string(),
+            source: "sensor-001".to_string(),
+            timestamp: 1707668400000,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: json!({"value": 23.5}),
+        };
+
+        assert_eq!(
+            event.validate_and_prepare(),
+            Err(ValidationError::ReservedStream(stream.to_string()))
+        );
+    }
+
+    // Only the exact names and the ingest prefix are reserved
+    for stream in ["ingestion", "sensors.ingest", "rejected.today"] {
+        let mut event = FluxEvent {
+            event_id: None,
+            stream: stream.to_string(),
+            source: "sensor-001".to_string(),
+            timestamp: 1707668400000,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: json!({"value": 23.5}),
+        };
+        assert!(event.validate_and_prepare().is_ok(), "{}", stream);
+    }
+}
+
 #[test]
 fn test_invalid_timestamp_fails() {
     let mut event = FluxEvent {
@@ -80,6 +120,7 @@ fn test_invalid_timestamp_fails() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: -1, // Negative timestamp
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -97,6 +138,7 @@ fn test_zero_timestamp_fails() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 0,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -114,6 +156,7 @@ fn test_payload_not_object_fails() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!("not an object"), // String instead of object
@@ -131,6 +174,7 @@ fn test_payload_array_fails() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!([1, 2, 3]), // Array instead of object
@@ -148,6 +192,7 @@ fn test_null_payload_fails() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!(null),
@@ -165,6 +210,7 @@ fn test_uuidv7_generation() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -175,6 +221,7 @@ fn test_uuidv7_generation() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 24.0}),
@@ -203,6 +250,7 @@ fn test_existing_event_id_preserved() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -221,7 +269,8 @@ fn test_optional_fields() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
-        key: None, // Optional
+        received_at: None,
+        key: None,    // Optional
         schema: None, // Optional
         payload: json!({"value": 23.5}),
     };
@@ -237,6 +286,7 @@ fn test_serde_serialization() {
         stream: "sensors.temperature".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: Some("zone1".to_string()),
         schema: Some("temp-v1".to_string()),
         payload: json!({"value": 23.5, "unit": "celsius"}),
@@ -258,6 +308,7 @@ fn test_serde_skip_none_fields() {
         stream: "sensors".to_string(),
         source: "sensor-001".to_string(),
         timestamp: 1707668400000,
+        received_at: None,
         key: None,
         schema: None,
         payload: json!({"value": 23.5}),
@@ -267,4 +318,37 @@ fn test_serde_skip_none_fields() {
     // Optional None fields should not be serialized
     assert!(!json_str.contains("\"key\""));
     assert!(!json_str.contains("\"schema\""));
+    assert!(!json_str.contains("\"received_at\""));
+}
+
+#[test]
+fn test_serde_event_without_received_at() {
+    // Events stored before received_at existed still deserialize
+    let event: FluxEvent = serde_json::from_value(json!({
+        "eventId": "01933e4b-8e6f-7890-abcd-ef1234567890",
+        "stream": "sensors",
+        "source": "sensor-001",
+        "timestamp": 1707668400000i64,
+        "payload": {"value": 23.5}
+    }))
+    .unwrap();
+    assert_eq!(event.received_at, None);
+}
+
+#[test]
+fn test_serde_received_at_round_trip() {
+    let mut event: FluxEvent = serde_json::from_value(json!({
+        "stream": "sensors",
+        "source": "sensor-001",
+        "timestamp": 1707668400000i64,
+        "payload": {"value": 23.5}
+    }))
+    .unwrap();
+    event.received_at = Some(1707668400123);
+
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(value["received_at"], json!(1707668400123i64));
+
+    let deserialized: FluxEvent = serde_json::from_value(value).unwrap();
+    assert_eq!(deserialized.received_at, Some(1707668400123));
 }
@@ -1,7 +1,19 @@
 use super::FluxEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fmt;
+use std::io;
+use std::str::FromStr;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+/// Smallest plausible millisecond timestamp (2001-09-09). Anything below it
+/// is almost certainly seconds since the epoch.
+pub const MIN_MILLIS_TIMESTAMP: i64 = 1_000_000_000_000;
+
+/// Default allowance for producer clocks running ahead of the server
+pub const DEFAULT_MAX_TIMESTAMP_SKEW_MS: i64 = 5 * 60 * 1000;
+
 /// Validation errors for FluxEvent
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValidationError {
@@ -9,8 +21,39 @@ pub enum ValidationError {
     MissingSource,
     MissingPayload,
     InvalidStreamFormat(String),
+    /// Stream name whose subject carries ingestion traffic (`ingest.*`, `rejected`)
+    ReservedStream(String),
     InvalidTimestamp(i64),
     PayloadNotObject,
+    /// Serialized payload exceeds `max_payload_bytes`
+    PayloadTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// Event carries more than `max_properties_per_event` properties
+    TooManyProperties {
+        count: usize,
+        max: usize,
+    },
+    /// A property name exceeds `max_property_name_length` bytes
+    PropertyNameTooLong {
+        length: usize,
+        max: usize,
+    },
+    /// A string value (at any depth) exceeds `max_string_value_length` bytes
+    StringValueTooLong {
+        property: String,
+        length: usize,
+        max: usize,
+    },
+    /// Timestamp is further ahead of server time than `max_timestamp_skew_seconds`
+    TimestampInFuture {
+        timestamp: i64,
+        ahead_ms: i64,
+        max_skew_seconds: u64,
+    },
+    /// Timestamp looks like seconds, and the policy is to reject those
+    TimestampInSeconds(i64),
 }
 
 impl fmt::Display for ValidationError {
@@ -22,18 +65,153 @@ impl fmt::Display for ValidationError {
             ValidationError::InvalidStreamFormat(s) => {
                 write!(f, "invalid stream format '{}': must be lowercase with optional dots", s)
             }
+            ValidationError::ReservedStream(s) => {
+                write!(f, "stream '{}' is reserved for NATS ingestion", s)
+            }
             ValidationError::InvalidTimestamp(ts) => {
                 write!(f, "timestamp must be positive, got {}", ts)
             }
             ValidationError::PayloadNotObject => {
                 write!(f, "payload must be a JSON object")
             }
+            ValidationError::PayloadTooLarge { size, max } => write!(
+                f,
+                "payload is {} bytes, exceeding max_payload_bytes ({})",
+                size, max
+            ),
+            ValidationError::TooManyProperties { count, max } => write!(
+                f,
+                "event has {} properties, exceeding max_properties_per_event ({})",
+                count, max
+            ),
+            ValidationError::PropertyNameTooLong { length, max } => write!(
+                f,
+                "property name is {} bytes, exceeding max_property_name_length ({})",
+                length, max
+            ),
+            ValidationError::StringValueTooLong {
+                property,
+                length,
+                max,
+            } => write!(
+                f,
+                "string value in property '{}' is {} bytes, exceeding max_string_value_length ({})",
+                property, length, max
+            ),
+            ValidationError::TimestampInFuture {
+                timestamp,
+                ahead_ms,
+                max_skew_seconds,
+            } => write!(
+                f,
+                "timestamp {} is {}ms ahead of server time, exceeding max_timestamp_skew_seconds ({})",
+                timestamp, ahead_ms, max_skew_seconds
+            ),
+            ValidationError::TimestampInSeconds(ts) => write!(
+                f,
+                "timestamp {} looks like seconds; timestamps must be Unix epoch milliseconds",
+                ts
+            ),
         }
     }
 }
 
 impl std::error::Error for ValidationError {}
 
+impl ValidationError {
+    /// True for violations of [`EventLimits`] (as opposed to malformed events)
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            ValidationError::PayloadTooLarge { .. }
+                | ValidationError::TooManyProperties { .. }
+                | ValidationError::PropertyNameTooLong { .. }
+                | ValidationError::StringValueTooLong { .. }
+        )
+    }
+
+    /// True for producer clock problems caught by [`check_timestamp`]
+    pub fn is_timestamp_rejected(&self) -> bool {
+        matches!(
+            self,
+            ValidationError::TimestampInFuture { .. } | ValidationError::TimestampInSeconds(_)
+        )
+    }
+}
+
+/// Size limits for a single event, usually taken from the runtime config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventLimits {
+    /// Serialized payload size, in bytes
+    pub max_payload_bytes: usize,
+    /// Number of properties in the event
+    pub max_properties_per_event: usize,
+    /// Property name length, in bytes
+    pub max_property_name_length: usize,
+    /// Length of any string value in the payload, in bytes
+    pub max_string_value_length: usize,
+}
+
+/// What ingestion does with a timestamp below [`MIN_MILLIS_TIMESTAMP`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SecondsTimestampPolicy {
+    /// Reject the event with 422
+    Reject,
+    /// Multiply by 1000 and accept
+    #[default]
+    Convert,
+}
+
+impl FromStr for SecondsTimestampPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(SecondsTimestampPolicy::Reject),
+            "convert" => Ok(SecondsTimestampPolicy::Convert),
+            other => Err(format!("unknown seconds timestamp policy '{}'", other)),
+        }
+    }
+}
+
+/// Producer clock checks, usually taken from the runtime config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampRules {
+    /// How far ahead of server time a timestamp may be, in seconds
+    pub max_skew_seconds: u64,
+    /// Handling of seconds-scale timestamps
+    pub seconds_policy: SecondsTimestampPolicy,
+}
+
+/// Shape of an event's payload, as far as envelope validation cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PayloadKind {
+    Null,
+    Object,
+    /// Any other JSON value
+    Other,
+}
+
+impl PayloadKind {
+    pub(crate) fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => PayloadKind::Null,
+            Value::Object(_) => PayloadKind::Object,
+            _ => PayloadKind::Other,
+        }
+    }
+}
+
+/// The fields envelope validation reads, borrowed from a parsed
+/// [`FluxEvent`] or a [`RawFluxEvent`](super::RawFluxEvent)
+pub(crate) struct Envelope<'a> {
+    pub stream: &'a str,
+    pub source: &'a str,
+    pub timestamp: i64,
+    pub payload: PayloadKind,
+}
+
 /// Validates and prepares a FluxEvent for ingestion.
 ///
 /// Validation rules:
@@ -43,40 +221,228 @@ impl std::error::Error for ValidationError {}
 /// - Payload: must be a JSON object (not array, string, etc.)
 /// - EventId: auto-generated UUIDv7 if missing or empty
 pub fn validate_and_prepare(event: &mut FluxEvent) -> Result<(), ValidationError> {
+    validate_envelope(&Envelope {
+        stream: &event.stream,
+        source: &event.source,
+        timestamp: event.timestamp,
+        payload: PayloadKind::of(&event.payload),
+    })?;
+    assign_event_id(&mut event.event_id);
+    Ok(())
+}
+
+/// The checks of [`validate_and_prepare`], on the envelope alone
+pub(crate) fn validate_envelope(envelope: &Envelope) -> Result<(), ValidationError> {
     // Validate required fields
-    if event.stream.is_empty() {
+    if envelope.stream.is_empty() {
         return Err(ValidationError::MissingStream);
     }
-    if event.source.is_empty() {
+    if envelope.source.is_empty() {
         return Err(ValidationError::MissingSource);
     }
-    if event.payload.is_null() {
+    if envelope.payload == PayloadKind::Null {
         return Err(ValidationError::MissingPayload);
     }
 
     // Validate stream format (lowercase, numbers, dots)
-    if !is_valid_stream_name(&event.stream) {
-        return Err(ValidationError::InvalidStreamFormat(event.stream.clone()));
+    if !is_valid_stream_name(envelope.stream) {
+        return Err(ValidationError::InvalidStreamFormat(
+            envelope.stream.to_string(),
+        ));
+    }
+    if is_reserved_stream_name(envelope.stream) {
+        return Err(ValidationError::ReservedStream(envelope.stream.to_string()));
     }
 
     // Validate timestamp is positive
-    if event.timestamp <= 0 {
-        return Err(ValidationError::InvalidTimestamp(event.timestamp));
+    if envelope.timestamp <= 0 {
+        return Err(ValidationError::InvalidTimestamp(envelope.timestamp));
     }
 
     // Validate payload is an object
-    if !event.payload.is_object() {
+    if envelope.payload != PayloadKind::Object {
         return Err(ValidationError::PayloadNotObject);
     }
 
-    // Generate UUIDv7 if missing or empty
-    if event.event_id.is_none() || event.event_id.as_ref().map_or(false, |id| id.is_empty()) {
-        event.event_id = Some(Uuid::now_v7().to_string());
+    Ok(())
+}
+
+/// Generate a UUIDv7 if `event_id` is missing or empty
+pub(crate) fn assign_event_id(event_id: &mut Option<String>) {
+    if event_id.as_deref().unwrap_or_default().is_empty() {
+        *event_id = Some(Uuid::now_v7().to_string());
+    }
+}
+
+/// Checks an event's payload against `limits`.
+///
+/// Properties are the keys of `payload.properties` for state events, or of
+/// the payload itself otherwise. String values are checked at any depth and
+/// reported against their top-level property.
+pub fn check_limits(event: &FluxEvent, limits: &EventLimits) -> Result<(), ValidationError> {
+    check_payload_size(serialized_len(&event.payload), limits)?;
+
+    let Some(payload) = event.payload.as_object() else {
+        return Ok(());
+    };
+    let properties: &Map<String, Value> = payload
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .unwrap_or(payload);
+    check_property_names(properties.keys().map(String::as_str), limits)?;
+
+    for (name, value) in payload {
+        match value {
+            Value::Object(props) if name == "properties" => check_string_lengths(
+                props
+                    .iter()
+                    .map(|(property, value)| (property.as_str(), longest_string(value))),
+                limits,
+            )?,
+            _ => check_string_lengths(
+                std::iter::once((name.as_str(), longest_string(value))),
+                limits,
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_payload_size(size: usize, limits: &EventLimits) -> Result<(), ValidationError> {
+    if size > limits.max_payload_bytes {
+        return Err(ValidationError::PayloadTooLarge {
+            size,
+            max: limits.max_payload_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Property count, then each of `names` in order
+pub(crate) fn check_property_names<'a>(
+    names: impl ExactSizeIterator<Item = &'a str>,
+    limits: &EventLimits,
+) -> Result<(), ValidationError> {
+    if names.len() > limits.max_properties_per_event {
+        return Err(ValidationError::TooManyProperties {
+            count: names.len(),
+            max: limits.max_properties_per_event,
+        });
+    }
+
+    for name in names {
+        if name.len() > limits.max_property_name_length {
+            return Err(ValidationError::PropertyNameTooLong {
+                length: name.len(),
+                max: limits.max_property_name_length,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Each property's longest string, in order; the first over the limit is
+/// reported
+pub(crate) fn check_string_lengths<'a>(
+    strings: impl Iterator<Item = (&'a str, Option<usize>)>,
+    limits: &EventLimits,
+) -> Result<(), ValidationError> {
+    for (property, longest) in strings {
+        match longest {
+            Some(length) if length > limits.max_string_value_length => {
+                return Err(ValidationError::StringValueTooLong {
+                    property: property.to_string(),
+                    length,
+                    max: limits.max_string_value_length,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks an event's timestamp against server time `now_ms`.
+///
+/// Seconds-scale timestamps are converted to milliseconds or rejected per
+/// `rules.seconds_policy`; the (converted) timestamp may then be at most
+/// `rules.max_skew_seconds` ahead of `now_ms`.
+pub fn check_timestamp(
+    event: &mut FluxEvent,
+    rules: &TimestampRules,
+    now_ms: i64,
+) -> Result<(), ValidationError> {
+    check_timestamp_ms(&mut event.timestamp, rules, now_ms)
+}
+
+/// [`check_timestamp`] on the timestamp alone
+pub(crate) fn check_timestamp_ms(
+    timestamp: &mut i64,
+    rules: &TimestampRules,
+    now_ms: i64,
+) -> Result<(), ValidationError> {
+    if *timestamp < MIN_MILLIS_TIMESTAMP {
+        match rules.seconds_policy {
+            SecondsTimestampPolicy::Reject => {
+                return Err(ValidationError::TimestampInSeconds(*timestamp));
+            }
+            SecondsTimestampPolicy::Convert => *timestamp *= 1000,
+        }
+    }
+
+    let ahead_ms = timestamp.saturating_sub(now_ms);
+    if ahead_ms > skew_ms(rules.max_skew_seconds) {
+        return Err(ValidationError::TimestampInFuture {
+            timestamp: *timestamp,
+            ahead_ms,
+            max_skew_seconds: rules.max_skew_seconds,
+        });
     }
 
     Ok(())
 }
 
+/// True if `timestamp` is millisecond-scale and at most `max_skew_ms` ahead
+/// of `reference_ms`
+pub fn timestamp_is_plausible(timestamp: i64, reference_ms: i64, max_skew_ms: i64) -> bool {
+    timestamp >= MIN_MILLIS_TIMESTAMP && timestamp.saturating_sub(reference_ms) <= max_skew_ms
+}
+
+fn skew_ms(seconds: u64) -> i64 {
+    i64::try_from(seconds.saturating_mul(1000)).unwrap_or(i64::MAX)
+}
+
+/// Length of `value` as compact JSON, without allocating it
+pub(crate) fn serialized_len(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value).expect("serializing a Value cannot fail");
+    counter.0
+}
+
+/// Byte length of the longest string anywhere in `value`
+fn longest_string(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(s) => Some(s.len()),
+        Value::Array(items) => items.iter().filter_map(longest_string).max(),
+        Value::Object(map) => map.values().filter_map(longest_string).max(),
+        _ => None,
+    }
+}
+
 /// Validates stream name format.
 ///
 /// Valid stream names:
@@ -85,7 +451,7 @@ pub fn validate_and_prepare(event: &mut FluxEvent) -> Result<(), ValidationError
 /// - Dots (.) for hierarchy
 /// - No leading/trailing dots
 /// - No consecutive dots
-fn is_valid_stream_name(stream: &str) -> bool {
+pub(crate) fn is_valid_stream_name(stream: &str) -> bool {
     if stream.is_empty() {
         return false;
     }
@@ -101,7 +467,14 @@ fn is_valid_stream_name(stream: &str) -> bool {
     }
 
     // Check all characters are valid (lowercase letters, numbers, dots)
-    stream.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.')
+    stream
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.')
+}
+
+/// Streams whose `flux.events.<stream>` subject the NATS ingester reads or writes
+pub(crate) fn is_reserved_stream_name(stream: &str) -> bool {
+    stream == "ingest" || stream.starts_with("ingest.") || stream == "rejected"
 }
 
 #[cfg(test)]
@@ -129,4 +502,206 @@ mod validation_tests {
         assert!(!is_valid_stream_name("sensors_temp"));
         assert!(!is_valid_stream_name("sensors/temp"));
     }
+
+    const LIMITS: EventLimits = EventLimits {
+        max_payload_bytes: 200,
+        max_properties_per_event: 3,
+        max_property_name_length: 8,
+        max_string_value_length: 10,
+    };
+
+    fn state_event(properties: Value) -> FluxEvent {
+        FluxEvent {
+            event_id: None,
+            stream: "sensors".to_string(),
+            source: "sensor-001".to_string(),
+            timestamp: 1707668400000,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: serde_json::json!({"entity_id": "e1", "properties": properties}),
+        }
+    }
+
+    #[test]
+    fn test_payload_size_boundary() {
+        let mut event = state_event(serde_json::json!({}));
+        event.payload = serde_json::json!({"blob": "x".repeat(10)});
+        let base = serialized_len(&event.payload);
+        assert_eq!(base, serde_json::to_vec(&event.payload).unwrap().len());
+
+        let at_limit = EventLimits {
+            max_payload_bytes: base,
+            ..LIMITS
+        };
+        assert!(check_limits(&event, &at_limit).is_ok());
+
+        let below = EventLimits {
+            max_payload_bytes: base - 1,
+            ..LIMITS
+        };
+        assert_eq!(
+            check_limits(&event, &below),
+            Err(ValidationError::PayloadTooLarge {
+                size: base,
+                max: base - 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_property_count_boundary() {
+        let event = state_event(serde_json::json!({"a": 1, "b": 2, "c": 3}));
+        assert!(check_limits(&event, &LIMITS).is_ok());
+
+        let event = state_event(serde_json::json!({"a": 1, "b": 2, "c": 3, "d": 4}));
+        assert_eq!(
+            check_limits(&event, &LIMITS),
+            Err(ValidationError::TooManyProperties { count: 4, max: 3 })
+        );
+    }
+
+    #[test]
+    fn test_property_count_uses_payload_without_properties() {
+        let mut event = state_event(serde_json::json!({}));
+        event.payload = serde_json::json!({"a": 1, "b": 2, "c": 3, "d": 4});
+        assert!(matches!(
+            check_limits(&event, &LIMITS),
+            Err(ValidationError::TooManyProperties { count: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_property_name_length_boundary() {
+        let event = state_event(serde_json::json!({"abcdefgh": 1}));
+        assert!(check_limits(&event, &LIMITS).is_ok());
+
+        let event = state_event(serde_json::json!({"abcdefghi": 1}));
+        assert_eq!(
+            check_limits(&event, &LIMITS),
+            Err(ValidationError::PropertyNameTooLong { length: 9, max: 8 })
+        );
+    }
+
+    #[test]
+    fn test_string_value_length_boundary() {
+        let event = state_event(serde_json::json!({"name": "0123456789"}));
+        assert!(check_limits(&event, &LIMITS).is_ok());
+
+        let event = state_event(serde_json::json!({"name": "0123456789x"}));
+        assert_eq!(
+            check_limits(&event, &LIMITS),
+            Err(ValidationError::StringValueTooLong {
+                property: "name".to_string(),
+                length: 11,
+                max: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_string_reported_against_top_level_property() {
+        let event = state_event(serde_json::json!({"cfg": {"tags": ["ok", "0123456789x"]}}));
+        let err = check_limits(&event, &LIMITS).unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::StringValueTooLong { ref property, length: 11, .. } if property == "cfg"
+        ));
+        assert!(err.is_limit_exceeded());
+        assert!(err.to_string().contains("max_string_value_length"));
+    }
+
+    #[test]
+    fn test_entity_id_counts_as_string_value() {
+        let mut event = state_event(serde_json::json!({}));
+        event.payload["entity_id"] = Value::String("x".repeat(11));
+        assert!(matches!(
+            check_limits(&event, &LIMITS),
+            Err(ValidationError::StringValueTooLong { ref property, .. }) if property == "entity_id"
+        ));
+    }
+
+    const NOW_MS: i64 = 1_707_668_400_000;
+
+    fn rules(seconds_policy: SecondsTimestampPolicy) -> TimestampRules {
+        TimestampRules {
+            max_skew_seconds: 300,
+            seconds_policy,
+        }
+    }
+
+    #[test]
+    fn test_timestamp_within_skew_accepted() {
+        let mut event = state_event(serde_json::json!({}));
+        event.timestamp = NOW_MS + 300_000;
+        let result = check_timestamp(&mut event, &rules(SecondsTimestampPolicy::Reject), NOW_MS);
+        assert!(result.is_ok());
+        assert_eq!(event.timestamp, NOW_MS + 300_000);
+    }
+
+    #[test]
+    fn test_timestamp_beyond_skew_rejected() {
+        let mut event = state_event(serde_json::json!({}));
+        event.timestamp = NOW_MS + 300_001;
+        let err = check_timestamp(&mut event, &rules(SecondsTimestampPolicy::Convert), NOW_MS)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::TimestampInFuture {
+                timestamp: NOW_MS + 300_001,
+                ahead_ms: 300_001,
+                max_skew_seconds: 300,
+            }
+        );
+        assert!(err.is_timestamp_rejected());
+        assert!(err.to_string().contains("max_timestamp_skew_seconds (300)"));
+    }
+
+    #[test]
+    fn test_seconds_timestamp_rejected_by_policy() {
+        let mut event = state_event(serde_json::json!({}));
+        event.timestamp = NOW_MS / 1000;
+        let err = check_timestamp(&mut event, &rules(SecondsTimestampPolicy::Reject), NOW_MS)
+            .unwrap_err();
+        assert_eq!(err, ValidationError::TimestampInSeconds(NOW_MS / 1000));
+        assert!(err.is_timestamp_rejected());
+        assert!(!err.is_limit_exceeded());
+    }
+
+    #[test]
+    fn test_seconds_timestamp_converted_by_policy() {
+        let mut event = state_event(serde_json::json!({}));
+        event.timestamp = NOW_MS / 1000;
+        check_timestamp(&mut event, &rules(SecondsTimestampPolicy::Convert), NOW_MS).unwrap();
+        assert_eq!(event.timestamp, NOW_MS);
+    }
+
+    #[test]
+    fn test_converted_timestamp_still_checked_for_skew() {
+        let mut event = state_event(serde_json::json!({}));
+        event.timestamp = NOW_MS / 1000 + 3_600;
+        assert!(matches!(
+            check_timestamp(&mut event, &rules(SecondsTimestampPolicy::Convert), NOW_MS),
+            Err(ValidationError::TimestampInFuture {
+                ahead_ms: 3_600_000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_plausibility() {
+        let skew = DEFAULT_MAX_TIMESTAMP_SKEW_MS;
+        assert!(timestamp_is_plausible(NOW_MS, NOW_MS, skew));
+        assert!(timestamp_is_plausible(NOW_MS - 86_400_000, NOW_MS, skew));
+        assert!(!timestamp_is_plausible(NOW_MS / 1000, NOW_MS, skew));
+        assert!(!timestamp_is_plausible(NOW_MS + skew + 1, NOW_MS, skew));
+    }
+
+    #[test]
+    fn test_seconds_policy_parse() {
+        assert_eq!("reject".parse(), Ok(SecondsTimestampPolicy::Reject));
+        assert_eq!("convert".parse(), Ok(SecondsTimestampPolicy::Convert));
+        assert!("drop".parse::<SecondsTimestampPolicy>().is_err());
+    }
 }
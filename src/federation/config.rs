@@ -0,0 +1,245 @@
+use crate::entity::parse_entity_id;
+use crate::event::FluxEvent;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Configuration for forwarding events to other Flux sites (`[federation]`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FederationConfig {
+    /// Name of this site, recorded on forwarded events; defaults to the
+    /// leader instance ID
+    pub site: Option<String>,
+
+    /// Forwarding rules; no forwarder runs when empty
+    pub rules: Vec<FederationRule>,
+
+    /// Most stream messages read per forwarding pass (and per remote batch)
+    pub batch_size: usize,
+
+    /// Interval between passes once a rule has caught up (seconds)
+    pub interval_seconds: u64,
+
+    /// Longest wait between retries after a failed pass (seconds)
+    pub max_backoff_seconds: u64,
+
+    /// File holding the last forwarded stream sequence of each rule. Put it
+    /// on a volume every replica shares so a new leader resumes from it.
+    pub state_path: String,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            site: None,
+            rules: Vec::new(),
+            batch_size: 500,
+            interval_seconds: 5,
+            max_backoff_seconds: 300,
+            state_path: "/var/lib/flux/federation.json".to_string(),
+        }
+    }
+}
+
+impl FederationConfig {
+    /// Every rule has a selector and a unique name
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for rule in &self.rules {
+            if rule.namespace.is_none() && rule.stream_prefix.is_none() {
+                bail!(
+                    "Federation rule '{}' needs a namespace or stream_prefix",
+                    rule.name
+                );
+            }
+            if !names.insert(rule.name.as_str()) {
+                bail!("Duplicate federation rule '{}'", rule.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass interval, at least one second
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_seconds.max(1))
+    }
+
+    /// Longest retry backoff, never shorter than the pass interval
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_secs(self.max_backoff_seconds).max(self.interval())
+    }
+}
+
+/// Events to forward and where to send them
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FederationRule {
+    /// Identifies the rule's progress; renaming it starts over
+    pub name: String,
+
+    /// Forward events whose entity ID is in this namespace
+    #[serde(default)]
+    pub namespace: Option<String>,
+
+    /// Forward events on this stream or its sub-streams (`sensors` matches
+    /// `sensors` and `sensors.temp`, not `sensorsx`)
+    #[serde(default)]
+    pub stream_prefix: Option<String>,
+
+    /// Base URL of the remote Flux (`https://flux.eu.example.com`)
+    pub remote_url: String,
+
+    /// Environment variable holding the bearer token for the remote
+    #[serde(default)]
+    pub token_env: Option<String>,
+
+    /// Environment variable holding the secret batches are signed with
+    /// (`X-Flux-Signature`, see [`crate::webhook`])
+    #[serde(default)]
+    pub secret_env: Option<String>,
+}
+
+impl FederationRule {
+    /// Whether `event` is selected by every selector the rule sets
+    pub fn matches(&self, event: &FluxEvent) -> bool {
+        if let Some(prefix) = &self.stream_prefix {
+            let on_stream = event.stream == *prefix
+                || event
+                    .stream
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'));
+            if !on_stream {
+                return false;
+            }
+        }
+        if let Some(namespace) = &self.namespace {
+            let event_namespace = event
+                .payload
+                .get("entity_id")
+                .and_then(|id| id.as_str())
+                .and_then(|id| parse_entity_id(id).ok())
+                .and_then(|parsed| parsed.namespace);
+            if event_namespace.as_deref() != Some(namespace.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Bearer token from `token_env`, if the rule names one
+    pub fn token(&self) -> Result<Option<String>> {
+        self.env(self.token_env.as_deref())
+    }
+
+    /// Signing secret from `secret_env`, if the rule names one
+    pub fn secret(&self) -> Result<Option<String>> {
+        self.env(self.secret_env.as_deref())
+    }
+
+    fn env(&self, var: Option<&str>) -> Result<Option<String>> {
+        match var {
+            Some(var) => match std::env::var(var) {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => bail!("Federation rule '{}': {} is not set", self.name, var),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(namespace: Option<&str>, stream_prefix: Option<&str>) -> FederationRule {
+        FederationRule {
+            name: "eu".to_string(),
+            namespace: namespace.map(String::from),
+            stream_prefix: stream_prefix.map(String::from),
+            remote_url: "http://flux-eu:3000".to_string(),
+            token_env: None,
+            secret_env: None,
+        }
+    }
+
+    fn event(stream: &str, entity_id: &str) -> FluxEvent {
+        serde_json::from_value(json!({
+            "stream": stream,
+            "source": "test",
+            "timestamp": 1_000,
+            "payload": {"entity_id": entity_id, "properties": {}}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stream_prefix_matches_sub_streams() {
+        let rule = rule(None, Some("sensors"));
+        assert!(rule.matches(&event("sensors", "a")));
+        assert!(rule.matches(&event("sensors.temp", "a")));
+        assert!(!rule.matches(&event("sensorsx", "a")));
+        assert!(!rule.matches(&event("other.sensors", "a")));
+    }
+
+    #[test]
+    fn test_namespace_matches_entity_namespace() {
+        let rule = rule(Some("acme"), None);
+        assert!(rule.matches(&event("sensors", "acme/pump-1")));
+        assert!(!rule.matches(&event("sensors", "other/pump-1")));
+        assert!(!rule.matches(&event("sensors", "pump-1")));
+        assert!(rule.matches(&event("sensors", "acme/a/b")));
+    }
+
+    #[test]
+    fn test_selectors_are_combined() {
+        let rule = rule(Some("acme"), Some("sensors"));
+        assert!(rule.matches(&event("sensors.temp", "acme/pump-1")));
+        assert!(!rule.matches(&event("sensors.temp", "other/pump-1")));
+        assert!(!rule.matches(&event("alerts", "acme/pump-1")));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = FederationConfig {
+            rules: vec![rule(Some("acme"), None)],
+            ..FederationConfig::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.rules.push(rule(None, Some("sensors")));
+        assert!(config.validate().is_err(), "duplicate name");
+
+        config.rules[1].name = "us".to_string();
+        assert!(config.validate().is_ok());
+
+        config.rules[1].stream_prefix = None;
+        assert!(config.validate().is_err(), "no selector");
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let config: FederationConfig = toml::from_str(
+            r#"
+            site = "us-east"
+
+            [[rules]]
+            name = "eu"
+            namespace = "acme"
+            remote_url = "https://flux-eu.example.com"
+            token_env = "FLUX_EU_TOKEN"
+            secret_env = "FLUX_EU_SECRET"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.site.as_deref(), Some("us-east"));
+        assert_eq!(config.rules[0].namespace.as_deref(), Some("acme"));
+        assert_eq!(config.rules[0].stream_prefix, None);
+        assert_eq!(
+            config.rules[0].secret_env.as_deref(),
+            Some("FLUX_EU_SECRET")
+        );
+        assert_eq!(config.batch_size, 500);
+    }
+}
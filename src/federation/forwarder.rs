@@ -0,0 +1,587 @@
+use super::config::{FederationConfig, FederationRule};
+use super::marks::HighWaterMarks;
+use super::{is_forwarded, mark_forwarded};
+use crate::archive::archiver::ArchiveSource;
+use crate::archive::StoredEvent;
+use crate::event::FluxEvent;
+use crate::webhook::signed_post;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+/// Where a rule's events are sent. Implemented over HTTP; tests substitute
+/// an in-memory sink.
+pub trait FederationSink: Send + Sync + 'static {
+    /// Deliver `events` to `rule`'s remote; an error means nothing should be
+    /// considered delivered
+    fn send<'a>(
+        &'a self,
+        rule: &'a FederationRule,
+        events: &'a [FluxEvent],
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Posts events to the remote's `POST /api/events/batch`
+pub struct HttpSink {
+    client: reqwest::Client,
+}
+
+impl HttpSink {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    events: &'a [FluxEvent],
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<BatchResult>,
+}
+
+#[derive(Deserialize)]
+struct BatchResult {
+    #[serde(rename = "eventId")]
+    event_id: Option<String>,
+    error: Option<String>,
+}
+
+impl FederationSink for HttpSink {
+    fn send<'a>(
+        &'a self,
+        rule: &'a FederationRule,
+        events: &'a [FluxEvent],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let url = format!("{}/api/events/batch", rule.remote_url.trim_end_matches('/'));
+            let body = serde_json::to_vec(&BatchRequest { events })?;
+            let secret = rule.secret()?;
+            let mut request = signed_post(&self.client, &url, body, secret.as_deref());
+            if let Some(token) = rule.token()? {
+                request = request.bearer_auth(token);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach {}", url))?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                bail!("{} answered {}: {}", url, status, body);
+            }
+
+            // The remote refusing an event won't change on retry: log and move on
+            let batch: BatchResponse = response
+                .json()
+                .await
+                .with_context(|| format!("Invalid batch response from {}", url))?;
+            for result in batch.results {
+                if let Some(error) = result.error {
+                    warn!(
+                        rule = %rule.name,
+                        event_id = ?result.event_id,
+                        error = %error,
+                        "Remote rejected forwarded event"
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The event in `stored`, or None for anything else on the stream
+fn parse_event(stored: &StoredEvent) -> Option<FluxEvent> {
+    match serde_json::from_slice(&stored.payload) {
+        Ok(event) => Some(event),
+        Err(e) => {
+            debug!(sequence = stored.sequence, error = %e, "Skipping non-event message");
+            None
+        }
+    }
+}
+
+/// Result of one forwarding pass for a rule
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForwardPass {
+    /// Stream events read
+    pub read: usize,
+    /// Events sent to the remote
+    pub forwarded: usize,
+}
+
+/// Progress of a rule, as reported by the admin API
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleStatus {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_prefix: Option<String>,
+    pub remote_url: String,
+    /// Last stream sequence forwarded (or skipped as not matching)
+    pub forwarded_sequence: u64,
+    /// Stream sequences not yet looked at
+    pub lag: u64,
+    /// When events were last sent to the remote
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_forwarded_at: Option<DateTime<Utc>>,
+    /// Why the latest pass failed; cleared by the next successful one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct Progress {
+    last_forwarded_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Forwards events matching each rule to its remote site
+///
+/// A pass reads a batch from the stream after the rule's high-water
+/// sequence, sends the matching events that weren't themselves forwarded,
+/// and only then saves the batch's last sequence. A failed send leaves the
+/// mark where it was, so the batch is retried (with backoff) rather than
+/// skipped; a pass interrupted after the remote accepted it sends the batch
+/// again.
+pub struct Forwarder {
+    source: Arc<dyn ArchiveSource>,
+    sink: Arc<dyn FederationSink>,
+    config: FederationConfig,
+    /// Recorded in the marker of every forwarded event
+    site: String,
+    marks: Mutex<HighWaterMarks>,
+    progress: Mutex<HashMap<String, Progress>>,
+}
+
+impl Forwarder {
+    pub fn new(
+        source: Arc<dyn ArchiveSource>,
+        sink: Arc<dyn FederationSink>,
+        config: FederationConfig,
+        site: impl Into<String>,
+    ) -> Result<Self> {
+        config.validate()?;
+        let marks = HighWaterMarks::open(&config.state_path)?;
+        Ok(Self {
+            source,
+            sink,
+            config,
+            site: site.into(),
+            marks: Mutex::new(marks),
+            progress: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    /// Run every rule until the task is cancelled
+    pub async fn run(&self) {
+        // Pick up marks saved by a previous leader
+        {
+            let mut marks = self.marks.lock().unwrap();
+            match HighWaterMarks::open(marks.path()) {
+                Ok(reloaded) => *marks = reloaded,
+                Err(e) => warn!(error = %e, "Failed to reload federation state"),
+            }
+        }
+
+        info!(
+            site = %self.site,
+            rules = self.config.rules.len(),
+            "Starting federation forwarder"
+        );
+        futures::future::join_all(self.config.rules.iter().map(|rule| self.run_rule(rule))).await;
+    }
+
+    async fn run_rule(&self, rule: &FederationRule) {
+        let interval = self.config.interval();
+        let mut backoff = interval;
+        loop {
+            match self.forward_once(rule).await {
+                Ok(pass) => {
+                    backoff = interval;
+                    if pass.forwarded > 0 {
+                        debug!(rule = %rule.name, forwarded = pass.forwarded, "Forwarded events");
+                    }
+                    // More waiting in the stream: don't pause
+                    if pass.read >= self.config.batch_size.max(1) {
+                        continue;
+                    }
+                    tokio::time::sleep(interval).await;
+                }
+                Err(e) => {
+                    warn!(
+                        rule = %rule.name,
+                        error = %e,
+                        retry_in_seconds = backoff.as_secs(),
+                        "Federation pass failed"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.config.max_backoff());
+                }
+            }
+        }
+    }
+
+    /// Forward the next batch of `rule`'s events
+    pub async fn forward_once(&self, rule: &FederationRule) -> Result<ForwardPass> {
+        let result = self.pass(rule).await;
+        let mut all = self.progress.lock().unwrap();
+        let progress = all.entry(rule.name.clone()).or_default();
+        match &result {
+            Ok(pass) => {
+                progress.last_error = None;
+                if pass.forwarded > 0 {
+                    progress.last_forwarded_at = Some(Utc::now());
+                }
+            }
+            Err(e) => progress.last_error = Some(format!("{:#}", e)),
+        }
+        result
+    }
+
+    async fn pass(&self, rule: &FederationRule) -> Result<ForwardPass> {
+        let bounds = self.source.bounds().await?;
+        let mark = self.forwarded_sequence(&rule.name);
+        let mut start = mark + 1;
+        if start < bounds.first_sequence {
+            if mark > 0 {
+                warn!(
+                    rule = %rule.name,
+                    from = start,
+                    to = bounds.first_sequence - 1,
+                    "Events left the stream before they were forwarded"
+                );
+            }
+            start = bounds.first_sequence;
+        }
+        if start > bounds.last_sequence {
+            return Ok(ForwardPass::default());
+        }
+
+        let stored = self
+            .source
+            .read(start, self.config.batch_size.max(1))
+            .await?;
+        let Some(last) = stored.last().map(|event| event.sequence) else {
+            return Ok(ForwardPass::default());
+        };
+
+        let events: Vec<FluxEvent> = stored
+            .iter()
+            .filter_map(parse_event)
+            .filter(|event| !is_forwarded(event) && rule.matches(event))
+            .map(|mut event| {
+                mark_forwarded(&mut event, &self.site);
+                event
+            })
+            .collect();
+
+        if !events.is_empty() {
+            self.sink.send(rule, &events).await?;
+        }
+        self.marks.lock().unwrap().set(&rule.name, last)?;
+
+        Ok(ForwardPass {
+            read: stored.len(),
+            forwarded: events.len(),
+        })
+    }
+
+    /// Last stream sequence handled for the rule named `rule`
+    pub fn forwarded_sequence(&self, rule: &str) -> u64 {
+        self.marks.lock().unwrap().get(rule)
+    }
+
+    /// Every rule with its progress and lag behind the stream
+    pub async fn status(&self) -> Result<Vec<RuleStatus>> {
+        let last_sequence = self.source.bounds().await?.last_sequence;
+        let progress = self.progress.lock().unwrap();
+        Ok(self
+            .config
+            .rules
+            .iter()
+            .map(|rule| {
+                let forwarded_sequence = self.forwarded_sequence(&rule.name);
+                let progress = progress.get(&rule.name);
+                RuleStatus {
+                    name: rule.name.clone(),
+                    namespace: rule.namespace.clone(),
+                    stream_prefix: rule.stream_prefix.clone(),
+                    remote_url: rule.remote_url.clone(),
+                    forwarded_sequence,
+                    lag: last_sequence.saturating_sub(forwarded_sequence),
+                    last_forwarded_at: progress.and_then(|p| p.last_forwarded_at),
+                    last_error: progress.and_then(|p| p.last_error.clone()),
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::archiver::StreamBounds;
+    use crate::federation::FORWARDED_FROM_FIELD;
+    use serde_json::json;
+
+    /// Stream holding the given payloads at sequences 1..
+    struct MockStream {
+        events: Mutex<Vec<StoredEvent>>,
+    }
+
+    impl MockStream {
+        fn new(payloads: Vec<serde_json::Value>) -> Arc<Self> {
+            let events = payloads
+                .into_iter()
+                .enumerate()
+                .map(|(i, payload)| StoredEvent {
+                    sequence: i as u64 + 1,
+                    published: Utc::now(),
+                    payload: serde_json::to_vec(&payload).unwrap(),
+                })
+                .collect();
+            Arc::new(Self {
+                events: Mutex::new(events),
+            })
+        }
+
+        fn drop_before(&self, sequence: u64) {
+            self.events
+                .lock()
+                .unwrap()
+                .retain(|e| e.sequence >= sequence);
+        }
+    }
+
+    impl ArchiveSource for MockStream {
+        fn bounds(&self) -> BoxFuture<'_, Result<StreamBounds>> {
+            Box::pin(async move {
+                let events = self.events.lock().unwrap();
+                let last_sequence = events.last().map_or(0, |e| e.sequence);
+                Ok(StreamBounds {
+                    first_sequence: events.first().map_or(last_sequence + 1, |e| e.sequence),
+                    last_sequence,
+                })
+            })
+        }
+
+        fn read(&self, start: u64, max: usize) -> BoxFuture<'_, Result<Vec<StoredEvent>>> {
+            Box::pin(async move {
+                let events = self.events.lock().unwrap();
+                Ok(events
+                    .iter()
+                    .filter(|e| e.sequence >= start)
+                    .take(max)
+                    .cloned()
+                    .collect())
+            })
+        }
+
+        fn purge_before(&self, sequence: u64) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async move {
+                self.drop_before(sequence);
+                Ok(())
+            })
+        }
+    }
+
+    /// Records what it is sent; can be told to fail
+    #[derive(Default)]
+    struct MockSink {
+        sent: Mutex<Vec<FluxEvent>>,
+        fail: Mutex<bool>,
+    }
+
+    impl MockSink {
+        fn entity_ids(&self) -> Vec<String> {
+            self.sent
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|e| e.payload["entity_id"].as_str().unwrap().to_string())
+                .collect()
+        }
+    }
+
+    impl FederationSink for MockSink {
+        fn send<'a>(
+            &'a self,
+            _rule: &'a FederationRule,
+            events: &'a [FluxEvent],
+        ) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                if *self.fail.lock().unwrap() {
+                    bail!("connection refused");
+                }
+                self.sent.lock().unwrap().extend_from_slice(events);
+                Ok(())
+            })
+        }
+    }
+
+    fn event(stream: &str, entity_id: &str) -> serde_json::Value {
+        json!({
+            "stream": stream,
+            "source": "test",
+            "timestamp": 1_000,
+            "payload": {"entity_id": entity_id, "properties": {}}
+        })
+    }
+
+    fn forwarded(entity_id: &str) -> serde_json::Value {
+        let mut event = event("sensors", entity_id);
+        event["payload"][FORWARDED_FROM_FIELD] = json!("eu-west");
+        event
+    }
+
+    struct Fixture {
+        stream: Arc<MockStream>,
+        sink: Arc<MockSink>,
+        forwarder: Forwarder,
+        rule: FederationRule,
+        dir: tempfile::TempDir,
+    }
+
+    /// Forwarder for namespace `acme`, batches of 3
+    fn fixture(payloads: Vec<serde_json::Value>) -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let stream = MockStream::new(payloads);
+        let sink = Arc::new(MockSink::default());
+        let rule = FederationRule {
+            name: "eu".to_string(),
+            namespace: Some("acme".to_string()),
+            stream_prefix: None,
+            remote_url: "http://flux-eu:3000".to_string(),
+            token_env: None,
+            secret_env: None,
+        };
+        let config = FederationConfig {
+            rules: vec![rule.clone()],
+            batch_size: 3,
+            state_path: dir.path().join("federation.json").display().to_string(),
+            ..FederationConfig::default()
+        };
+        let forwarder = Forwarder::new(
+            Arc::clone(&stream) as Arc<dyn ArchiveSource>,
+            Arc::clone(&sink) as Arc<dyn FederationSink>,
+            config,
+            "us-east",
+        )
+        .unwrap();
+        Fixture {
+            stream,
+            sink,
+            forwarder,
+            rule,
+            dir,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwards_matching_events_once() {
+        let f = fixture(vec![
+            event("sensors", "acme/a"),
+            event("sensors", "other/b"),
+            forwarded("acme/c"),
+            json!({"not": "an event"}),
+            event("alerts", "acme/d"),
+        ]);
+
+        let pass = f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!((pass.read, pass.forwarded), (3, 1));
+        let pass = f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!((pass.read, pass.forwarded), (2, 1));
+        // Caught up
+        let pass = f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!(pass, ForwardPass::default());
+
+        // Events from another site are never sent back
+        assert_eq!(f.sink.entity_ids(), vec!["acme/a", "acme/d"]);
+        for event in f.sink.sent.lock().unwrap().iter() {
+            assert_eq!(event.payload[FORWARDED_FROM_FIELD], "us-east");
+        }
+        assert_eq!(f.forwarder.forwarded_sequence("eu"), 5);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_does_not_advance() {
+        let f = fixture(vec![event("sensors", "acme/a"), event("sensors", "acme/b")]);
+        *f.sink.fail.lock().unwrap() = true;
+
+        assert!(f.forwarder.forward_once(&f.rule).await.is_err());
+        assert_eq!(f.forwarder.forwarded_sequence("eu"), 0);
+        let status = f.forwarder.status().await.unwrap();
+        assert_eq!(status[0].lag, 2);
+        assert!(status[0]
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("connection refused"));
+
+        *f.sink.fail.lock().unwrap() = false;
+        f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!(f.sink.entity_ids(), vec!["acme/a", "acme/b"]);
+        let status = f.forwarder.status().await.unwrap();
+        assert_eq!(status[0].lag, 0);
+        assert_eq!(status[0].last_error, None);
+        assert!(status[0].last_forwarded_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_resumes_from_saved_mark() {
+        let f = fixture(vec![
+            event("sensors", "acme/a"),
+            event("sensors", "acme/b"),
+            event("sensors", "acme/c"),
+            event("sensors", "acme/d"),
+        ]);
+        f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!(f.sink.entity_ids(), vec!["acme/a", "acme/b", "acme/c"]);
+
+        // A new forwarder (restart, or another leader) picks up at 4
+        let sink = Arc::new(MockSink::default());
+        let forwarder = Forwarder::new(
+            Arc::clone(&f.stream) as Arc<dyn ArchiveSource>,
+            Arc::clone(&sink) as Arc<dyn FederationSink>,
+            FederationConfig {
+                rules: vec![f.rule.clone()],
+                state_path: f.dir.path().join("federation.json").display().to_string(),
+                ..FederationConfig::default()
+            },
+            "us-east",
+        )
+        .unwrap();
+        assert_eq!(forwarder.forwarded_sequence("eu"), 3);
+        forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!(sink.entity_ids(), vec!["acme/d"]);
+    }
+
+    #[tokio::test]
+    async fn test_skips_events_purged_before_forwarding() {
+        let f = fixture(vec![
+            event("sensors", "acme/a"),
+            event("sensors", "acme/b"),
+            event("sensors", "acme/c"),
+            event("sensors", "acme/d"),
+        ]);
+        f.stream.drop_before(3);
+
+        f.forwarder.forward_once(&f.rule).await.unwrap();
+        assert_eq!(f.sink.entity_ids(), vec!["acme/c", "acme/d"]);
+        assert_eq!(f.forwarder.forwarded_sequence("eu"), 4);
+    }
+}
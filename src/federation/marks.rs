@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Last forwarded stream sequence of each rule, kept in a JSON file
+///
+/// Saved through a temporary file and a rename, so a crash leaves either the
+/// old marks or the new ones.
+#[derive(Debug)]
+pub struct HighWaterMarks {
+    path: PathBuf,
+    marks: BTreeMap<String, u64>,
+}
+
+impl HighWaterMarks {
+    /// Load the marks at `path`; all rules start at zero if it doesn't exist
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let marks = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("Invalid federation state {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read federation state {}", path.display()))
+            }
+        };
+        Ok(Self { path, marks })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Last sequence forwarded for `rule`, 0 if none
+    pub fn get(&self, rule: &str) -> u64 {
+        self.marks.get(rule).copied().unwrap_or(0)
+    }
+
+    /// Record `sequence` for `rule` and save every mark
+    pub fn set(&mut self, rule: &str, sequence: u64) -> Result<()> {
+        let previous = self.marks.insert(rule.to_string(), sequence);
+        if let Err(e) = self.save() {
+            // Keep memory in step with the file
+            match previous {
+                Some(previous) => self.marks.insert(rule.to_string(), previous),
+                None => self.marks.remove(rule),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.marks)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to save federation state {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("federation.json");
+
+        let mut marks = HighWaterMarks::open(&path).unwrap();
+        assert_eq!(marks.get("eu"), 0);
+        marks.set("eu", 42).unwrap();
+        marks.set("us", 7).unwrap();
+        marks.set("eu", 50).unwrap();
+
+        let reopened = HighWaterMarks::open(&path).unwrap();
+        assert_eq!(reopened.get("eu"), 50);
+        assert_eq!(reopened.get("us"), 7);
+        assert_eq!(reopened.get("apac"), 0);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("federation.json");
+        std::fs::write(&path, b"{not json").unwrap();
+        assert!(HighWaterMarks::open(&path).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! Idempotency keys for create endpoints.
+//!
+//! Automation that retries a create after a timeout sends the same
+//! `Idempotency-Key` header again. The first request claims the key along
+//! with a hash of its body, and its response is stored once it succeeds.
+//! Repeats with the same body get that response back instead of creating a
+//! second resource; the same key with another body is refused. Keys expire
+//! after a TTL (24 hours by default) and are removed by
+//! [`IdempotencyStore::purge_expired`] (see [`run_idempotency_sweeper`]).
+
+use anyhow::{Context, Result};
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::migrations::{self, Migration};
+
+/// Request header carrying the client's key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Response header set on a stored response returned for a repeated key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// How long a key is remembered by default
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest key accepted
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// A claim not completed or released within this long belongs to a request
+/// whose process went away; the next request with the key takes it over
+const ABANDONED_CLAIM_SECS: i64 = 5 * 60;
+
+/// Schema history of the idempotency store. Append only.
+const MIGRATIONS: &[Migration] = &[Migration::Sql(
+    "CREATE TABLE IF NOT EXISTS idempotency_keys (
+        scope      TEXT NOT NULL,
+        key        TEXT NOT NULL,
+        body_hash  TEXT NOT NULL,
+        response   TEXT,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (scope, key)
+    );
+    CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys(created_at);",
+)];
+
+/// What a request may do with its key
+#[derive(Debug, PartialEq)]
+pub enum Claim {
+    /// First use of the key: create the resource, then
+    /// [`complete`](IdempotencyStore::complete) or
+    /// [`release`](IdempotencyStore::release) the claim
+    New,
+    /// The key already created something from the same body; this is the
+    /// stored response
+    Replay(String),
+    /// The key was used with a different body
+    Mismatch,
+    /// A request with the key and the same body hasn't finished yet
+    InProgress,
+}
+
+/// Hex SHA-256 of a request body
+pub fn body_hash(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Keys are 1 to 255 visible ASCII characters
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_IDEMPOTENCY_KEY_LEN
+        && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// The `Idempotency-Key` header isn't a valid key
+#[derive(Debug, PartialEq)]
+pub struct InvalidIdempotencyKey;
+
+impl fmt::Display for InvalidIdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} must be 1 to {} visible ASCII characters",
+            IDEMPOTENCY_KEY_HEADER, MAX_IDEMPOTENCY_KEY_LEN
+        )
+    }
+}
+
+impl std::error::Error for InvalidIdempotencyKey {}
+
+/// The request's `Idempotency-Key`, or None if it didn't send one
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<&str>, InvalidIdempotencyKey> {
+    match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        None => Ok(None),
+        Some(value) => match value.to_str() {
+            Ok(key) if is_valid_key(key) => Ok(Some(key)),
+            _ => Err(InvalidIdempotencyKey),
+        },
+    }
+}
+
+/// Persists idempotency keys in SQLite.
+///
+/// Keys are namespaced by a caller-chosen scope (endpoint and caller), so
+/// two clients picking the same key don't see each other's responses.
+pub struct IdempotencyStore {
+    conn: Mutex<Connection>,
+    ttl_secs: i64,
+}
+
+impl IdempotencyStore {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open idempotency DB at {}", db_path))?;
+        migrations::migrate(&mut conn, "idempotency_keys", MIGRATIONS)
+            .context("Failed to migrate idempotency DB")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            ttl_secs: DEFAULT_IDEMPOTENCY_TTL.as_secs() as i64,
+        })
+    }
+
+    /// Remembers keys for `ttl` instead of 24 hours.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl_secs = ttl.as_secs() as i64;
+        self
+    }
+
+    /// Claims `key` in `scope` for a request whose body hashes to `body_hash`.
+    pub fn claim(&self, scope: &str, key: &str, body_hash: &str) -> Result<Claim> {
+        self.claim_at(scope, key, body_hash, Utc::now())
+    }
+
+    /// [`claim`](Self::claim) at time `now`.
+    pub fn claim_at(
+        &self,
+        scope: &str,
+        key: &str,
+        body_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Claim> {
+        let now = now.timestamp();
+        let conn = self.conn.lock().unwrap();
+        let existing: Option<(String, Option<String>, i64)> = conn
+            .query_row(
+                "SELECT body_hash, response, created_at FROM idempotency_keys
+                 WHERE scope = ?1 AND key = ?2",
+                params![scope, key],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .context("Failed to look up idempotency key")?;
+
+        let claim = match existing {
+            Some((_, _, created_at)) if created_at <= now - self.ttl_secs => None,
+            Some((_, None, created_at)) if created_at <= now - ABANDONED_CLAIM_SECS => None,
+            Some((hash, _, _)) if hash != body_hash => Some(Claim::Mismatch),
+            Some((_, Some(response), _)) => Some(Claim::Replay(response)),
+            Some((_, None, _)) => Some(Claim::InProgress),
+            None => None,
+        };
+        if let Some(claim) = claim {
+            return Ok(claim);
+        }
+
+        // Unused, expired or abandoned: the key is this request's now
+        conn.execute(
+            "INSERT OR REPLACE INTO idempotency_keys (scope, key, body_hash, response, created_at)
+             VALUES (?1, ?2, ?3, NULL, ?4)",
+            params![scope, key, body_hash, now],
+        )
+        .context("Failed to claim idempotency key")?;
+        Ok(Claim::New)
+    }
+
+    /// Stores the response of the request that claimed `key`; repeats get it
+    /// back until the key expires.
+    pub fn complete(&self, scope: &str, key: &str, response: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE idempotency_keys SET response = ?3 WHERE scope = ?1 AND key = ?2",
+            params![scope, key, response],
+        )
+        .context("Failed to store idempotent response")?;
+        Ok(())
+    }
+
+    /// Gives up a claim whose request failed, so a retry can try again.
+    pub fn release(&self, scope: &str, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM idempotency_keys WHERE scope = ?1 AND key = ?2 AND response IS NULL",
+            params![scope, key],
+        )
+        .context("Failed to release idempotency key")?;
+        Ok(())
+    }
+
+    /// Deletes expired keys. Returns how many were removed.
+    pub fn purge_expired(&self) -> Result<usize> {
+        self.purge_expired_at(Utc::now())
+    }
+
+    /// [`purge_expired`](Self::purge_expired) at time `now`.
+    pub fn purge_expired_at(&self, now: DateTime<Utc>) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM idempotency_keys WHERE created_at <= ?1",
+            params![now.timestamp() - self.ttl_secs],
+        )
+        .context("Failed to purge expired idempotency keys")
+    }
+}
+
+/// Purges expired keys from `store` every `every`. Runs until aborted.
+pub async fn run_idempotency_sweeper(store: Arc<IdempotencyStore>, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+    loop {
+        interval.tick().await;
+        match store.purge_expired() {
+            Ok(0) => {}
+            Ok(purged) => info!(purged, "Purged expired idempotency keys"),
+            Err(e) => warn!(error = %e, "Failed to purge expired idempotency keys"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn store() -> IdempotencyStore {
+        IdempotencyStore::new(":memory:").unwrap()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_body_hash_and_key_validation() {
+        assert_eq!(
+            body_hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(body_hash(b"{}"), body_hash(b"{}"));
+        assert_ne!(body_hash(b"{}"), body_hash(b"{ }"));
+
+        assert!(is_valid_key("3f1c9a7e-provision-42"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("has space"));
+        assert!(!is_valid_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1)));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(key_from_headers(&headers), Ok(None));
+        headers.insert(IDEMPOTENCY_KEY_HEADER, "abc".parse().unwrap());
+        assert_eq!(key_from_headers(&headers), Ok(Some("abc")));
+        headers.insert(IDEMPOTENCY_KEY_HEADER, "a b".parse().unwrap());
+        assert_eq!(key_from_headers(&headers), Err(InvalidIdempotencyKey));
+    }
+
+    #[test]
+    fn test_claim_complete_replay_and_mismatch() {
+        let store = store();
+        let hash = body_hash(b"{\"a\":1}");
+        assert_eq!(store.claim_at("s", "k", &hash, at(0)).unwrap(), Claim::New);
+        assert_eq!(
+            store.claim_at("s", "k", &hash, at(1)).unwrap(),
+            Claim::InProgress
+        );
+
+        store.complete("s", "k", "source-1").unwrap();
+        assert_eq!(
+            store.claim_at("s", "k", &hash, at(2)).unwrap(),
+            Claim::Replay("source-1".to_string())
+        );
+        assert_eq!(
+            store
+                .claim_at("s", "k", &body_hash(b"{\"a\":2}"), at(3))
+                .unwrap(),
+            Claim::Mismatch
+        );
+
+        // Scopes don't share keys
+        assert_eq!(store.claim_at("t", "k", &hash, at(4)).unwrap(), Claim::New);
+    }
+
+    #[test]
+    fn test_release_lets_a_retry_claim_again() {
+        let store = store();
+        assert_eq!(store.claim_at("s", "k", "h1", at(0)).unwrap(), Claim::New);
+        store.release("s", "k").unwrap();
+        // A failed request leaves nothing behind, even for another body
+        assert_eq!(store.claim_at("s", "k", "h2", at(1)).unwrap(), Claim::New);
+
+        // Completed keys can't be released
+        store.complete("s", "k", "r").unwrap();
+        store.release("s", "k").unwrap();
+        assert_eq!(
+            store.claim_at("s", "k", "h2", at(2)).unwrap(),
+            Claim::Replay("r".to_string())
+        );
+    }
+
+    #[test]
+    fn test_abandoned_claim_is_taken_over() {
+        let store = store();
+        assert_eq!(store.claim_at("s", "k", "h", at(0)).unwrap(), Claim::New);
+        assert_eq!(
+            store
+                .claim_at("s", "k", "h", at(ABANDONED_CLAIM_SECS - 1))
+                .unwrap(),
+            Claim::InProgress
+        );
+        assert_eq!(
+            store
+                .claim_at("s", "k", "h", at(ABANDONED_CLAIM_SECS))
+                .unwrap(),
+            Claim::New
+        );
+    }
+
+    #[test]
+    fn test_ttl_expiry_and_purge() {
+        let store = store().with_ttl(Duration::from_secs(3_600));
+        store.claim_at("s", "old", "h", at(0)).unwrap();
+        store.complete("s", "old", "r1").unwrap();
+        store.claim_at("s", "new", "h", at(1_800)).unwrap();
+        store.complete("s", "new", "r2").unwrap();
+
+        // Expired keys are free again even before a purge
+        assert_eq!(
+            store.claim_at("s", "old", "other", at(3_600)).unwrap(),
+            Claim::New
+        );
+        store.release("s", "old").unwrap();
+
+        assert_eq!(store.purge_expired_at(at(3_599)).unwrap(), 0);
+        assert_eq!(store.purge_expired_at(at(5_400)).unwrap(), 1);
+        assert_eq!(
+            store.claim_at("s", "new", "other", at(5_401)).unwrap(),
+            Claim::New
+        );
+    }
+}
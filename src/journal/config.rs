@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Configuration for the persistent entity journal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Keep recent property changes per entity for
+    /// `GET /api/state/entities/:id/journal`
+    pub enabled: bool,
+
+    /// SQLite database file; overridden by FLUX_JOURNAL_DB
+    pub path: String,
+
+    /// Changes kept per entity; older ones are trimmed
+    pub entries_per_entity: usize,
+
+    /// Commit (and fsync) once this many changes are waiting
+    pub flush_every_writes: usize,
+
+    /// Commit waiting changes at least this often (milliseconds)
+    pub flush_interval_ms: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "journal.db".to_string(),
+            entries_per_entity: super::DEFAULT_ENTRIES_PER_ENTITY,
+            flush_every_writes: 500,
+            flush_interval_ms: 200,
+        }
+    }
+}
+
+impl JournalConfig {
+    pub fn path(&self) -> String {
+        std::env::var("FLUX_JOURNAL_DB").unwrap_or_else(|_| self.path.clone())
+    }
+
+    /// Flush interval, at least one millisecond
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms.max(1))
+    }
+}
@@ -0,0 +1,17 @@
+// Entity journal: the last changes of each entity, kept in SQLite so the
+// "recent changes" of an entity survive restarts without scanning the event
+// stream. The state engine hands each applied update to a channel; a writer
+// task commits them in batches. Updates replayed from the stream on startup
+// that the journal already holds (at or below its head sequence) are not
+// sent again.
+
+pub mod config;
+pub mod store;
+pub mod writer;
+
+pub use config::JournalConfig;
+pub use store::{EntityJournal, JournalEntry, JournalPage};
+pub use writer::run_journal_writer;
+
+/// Changes kept per entity by default
+pub const DEFAULT_ENTRIES_PER_ENTITY: usize = 100;
@@ -0,0 +1,346 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::migrations::{self, Migration};
+use crate::state::JournalRecord;
+
+/// Schema history of the entity journal. Append only.
+const MIGRATIONS: &[Migration] = &[Migration::Sql(
+    "CREATE TABLE IF NOT EXISTS entity_journal (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        entity_id  TEXT NOT NULL,
+        sequence   INTEGER,
+        property   TEXT NOT NULL,
+        old_value  TEXT,
+        new_value  TEXT NOT NULL,
+        removed    INTEGER NOT NULL,
+        timestamp  INTEGER NOT NULL,
+        source     TEXT,
+        request_id TEXT
+    );
+    CREATE INDEX IF NOT EXISTS idx_entity_journal_entity ON entity_journal(entity_id, id);
+    CREATE TABLE IF NOT EXISTS entity_journal_head (
+        id       INTEGER PRIMARY KEY CHECK (id = 1),
+        sequence INTEGER NOT NULL
+    );",
+)];
+
+/// One journaled property change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "cursor": 48213,
+    "sequence": 1052,
+    "property": "temperature",
+    "old_value": 22.1,
+    "new_value": 22.5,
+    "timestamp": "2026-02-14T14:30:45.123Z",
+    "source": "sensor-gateway"
+}))]
+pub struct JournalEntry {
+    /// Pass as `before` to continue after this entry
+    pub cursor: i64,
+    /// Stream sequence of the event that made the change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    pub property: String,
+    #[schema(value_type = Option<Object>)]
+    pub old_value: Option<Value>,
+    #[schema(value_type = Object)]
+    pub new_value: Value,
+    /// The property was removed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Newest-first page of an entity's journal
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalPage {
+    pub entries: Vec<JournalEntry>,
+    /// Cursor of the last entry, if older ones remain
+    pub next_cursor: Option<i64>,
+}
+
+/// Recent property changes of every entity, in SQLite.
+///
+/// Each entity keeps its last `entries_per_entity` changes; appending trims
+/// the older ones. Cursors are row IDs, which only grow, so a page boundary
+/// stays valid while newer changes arrive.
+pub struct EntityJournal {
+    conn: Mutex<Connection>,
+    entries_per_entity: usize,
+}
+
+impl EntityJournal {
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open entity journal at {}", db_path))?;
+        // Every commit is synced to disk; with WAL that's one append and sync
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .context("Failed to enable WAL for entity journal")?;
+        conn.pragma_update(None, "synchronous", "FULL")
+            .context("Failed to set entity journal synchronous mode")?;
+        migrations::migrate(&mut conn, "entity_journal", MIGRATIONS)
+            .context("Failed to migrate entity journal")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            entries_per_entity: super::DEFAULT_ENTRIES_PER_ENTITY,
+        })
+    }
+
+    /// Keeps `entries` changes per entity instead of 100 (min 1).
+    pub fn with_entries_per_entity(mut self, entries: usize) -> Self {
+        self.entries_per_entity = entries.max(1);
+        self
+    }
+
+    /// Highest stream sequence journaled so far (0 if none).
+    pub fn head(&self) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let head: Option<i64> = conn
+            .query_row(
+                "SELECT sequence FROM entity_journal_head WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read entity journal head")?;
+        Ok(head.unwrap_or(0) as u64)
+    }
+
+    /// Writes the changes of `records` in one transaction, trims the entities
+    /// they touched and advances the head. Returns the number of changes written.
+    pub fn append(&self, records: &[JournalRecord]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .context("Failed to start entity journal transaction")?;
+        let mut written = 0;
+        let mut touched = BTreeSet::new();
+        let mut head = None;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO entity_journal (entity_id, sequence, property, old_value,
+                     new_value, removed, timestamp, source, request_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for record in records {
+                let update = &record.update;
+                for change in &update.changes {
+                    insert
+                        .execute(params![
+                            update.entity_id,
+                            record.sequence.map(|sequence| sequence as i64),
+                            change.property,
+                            change.old_value.as_ref().map(Value::to_string),
+                            change.new_value.to_string(),
+                            change.removed,
+                            update.timestamp.timestamp_millis(),
+                            update.source,
+                            update.request_id,
+                        ])
+                        .context("Failed to append to entity journal")?;
+                    written += 1;
+                }
+                touched.insert(update.entity_id.as_str());
+                head = head.max(record.sequence);
+            }
+
+            let mut trim = tx.prepare_cached(
+                "DELETE FROM entity_journal WHERE entity_id = ?1 AND id <= (
+                     SELECT id FROM entity_journal WHERE entity_id = ?1
+                     ORDER BY id DESC LIMIT 1 OFFSET ?2
+                 )",
+            )?;
+            for entity_id in touched {
+                trim.execute(params![entity_id, self.entries_per_entity as i64])
+                    .context("Failed to trim entity journal")?;
+            }
+        }
+        if let Some(head) = head {
+            tx.execute(
+                "INSERT INTO entity_journal_head (id, sequence) VALUES (1, ?1)
+                 ON CONFLICT(id) DO UPDATE SET sequence = MAX(sequence, excluded.sequence)",
+                params![head as i64],
+            )
+            .context("Failed to advance entity journal head")?;
+        }
+        tx.commit()
+            .context("Failed to commit entity journal transaction")?;
+        Ok(written)
+    }
+
+    /// Up to `limit` of `entity_id`'s changes older than the `before` cursor
+    /// (newest if none), newest first.
+    pub fn page(&self, entity_id: &str, limit: usize, before: Option<i64>) -> Result<JournalPage> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, sequence, property, old_value, new_value, removed, timestamp,
+                 source, request_id
+             FROM entity_journal WHERE entity_id = ?1 AND id < ?2
+             ORDER BY id DESC LIMIT ?3",
+        )?;
+        // One more than asked for tells whether older entries remain
+        let mut entries = stmt
+            .query_map(
+                params![entity_id, before.unwrap_or(i64::MAX), limit as i64 + 1],
+                |row| {
+                    Ok(JournalEntry {
+                        cursor: row.get(0)?,
+                        sequence: row
+                            .get::<_, Option<i64>>(1)?
+                            .map(|sequence| sequence as u64),
+                        property: row.get(2)?,
+                        old_value: row
+                            .get::<_, Option<String>>(3)?
+                            .map(|value| json_column(3, &value))
+                            .transpose()?,
+                        new_value: json_column(4, &row.get::<_, String>(4)?)?,
+                        removed: row.get(5)?,
+                        timestamp: DateTime::from_timestamp_millis(row.get(6)?).unwrap_or_default(),
+                        source: row.get(7)?,
+                        request_id: row.get(8)?,
+                    })
+                },
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .context("Failed to read entity journal")?;
+
+        let next_cursor = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|entry| entry.cursor)
+        } else {
+            None
+        };
+        Ok(JournalPage {
+            entries,
+            next_cursor,
+        })
+    }
+}
+
+/// Parse the JSON stored in column `index`
+fn json_column(index: usize, value: &str) -> rusqlite::Result<Value> {
+    serde_json::from_str(value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{EntityUpdate, PropertyChange};
+    use serde_json::json;
+
+    fn record(entity_id: &str, sequence: u64, value: i64) -> JournalRecord {
+        JournalRecord {
+            sequence: Some(sequence),
+            update: EntityUpdate {
+                entity_id: entity_id.to_string(),
+                changes: vec![PropertyChange {
+                    property: "value".to_string(),
+                    old_value: Some(json!(value - 1)),
+                    new_value: json!(value),
+                    removed: false,
+                }],
+                timestamp: DateTime::from_timestamp_millis(1_700_000_000_000 + value).unwrap(),
+                source: Some("sensor-gateway".to_string()),
+                entity_last_updated: None,
+                request_id: None,
+            },
+        }
+    }
+
+    fn values(page: &JournalPage) -> Vec<i64> {
+        page.entries
+            .iter()
+            .map(|entry| entry.new_value.as_i64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_each_entity_keeps_its_last_entries() {
+        let journal = EntityJournal::new(":memory:")
+            .unwrap()
+            .with_entries_per_entity(3);
+        let records: Vec<JournalRecord> = (1..=5)
+            .map(|i| record("sensor-01", i as u64, i))
+            .chain([record("sensor-02", 6, 100)])
+            .collect();
+        assert_eq!(journal.append(&records).unwrap(), 6);
+        journal.append(&[record("sensor-01", 7, 6)]).unwrap();
+
+        let page = journal.page("sensor-01", 10, None).unwrap();
+        assert_eq!(values(&page), vec![6, 5, 4]);
+        assert_eq!(page.next_cursor, None);
+        // Trimming one entity leaves the others alone
+        assert_eq!(
+            values(&journal.page("sensor-02", 10, None).unwrap()),
+            vec![100]
+        );
+
+        let newest = &page.entries[0];
+        assert_eq!(newest.sequence, Some(7));
+        assert_eq!(newest.old_value, Some(json!(5)));
+        assert_eq!(newest.source.as_deref(), Some("sensor-gateway"));
+        assert_eq!(newest.timestamp.timestamp_millis(), 1_700_000_000_006);
+    }
+
+    #[test]
+    fn test_cursor_pagination() {
+        let journal = EntityJournal::new(":memory:").unwrap();
+        let records: Vec<JournalRecord> =
+            (1..=5).map(|i| record("sensor-01", i as u64, i)).collect();
+        journal.append(&records).unwrap();
+
+        let first = journal.page("sensor-01", 2, None).unwrap();
+        assert_eq!(values(&first), vec![5, 4]);
+        let second = journal.page("sensor-01", 2, first.next_cursor).unwrap();
+        assert_eq!(values(&second), vec![3, 2]);
+
+        // Newer changes don't shift pages already handed out
+        journal.append(&[record("sensor-01", 6, 6)]).unwrap();
+        let last = journal.page("sensor-01", 2, second.next_cursor).unwrap();
+        assert_eq!(values(&last), vec![1]);
+        assert_eq!(last.next_cursor, None);
+
+        assert!(journal
+            .page("sensor-99", 2, None)
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+
+    #[test]
+    fn test_head_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.db");
+        let path = path.to_str().unwrap();
+
+        let journal = EntityJournal::new(path).unwrap();
+        assert_eq!(journal.head().unwrap(), 0);
+        journal
+            .append(&[record("a", 9, 1), record("b", 4, 2)])
+            .unwrap();
+        // Changes that didn't come from the stream leave the head alone
+        let mut direct = record("a", 0, 3);
+        direct.sequence = None;
+        journal.append(&[direct]).unwrap();
+        drop(journal);
+
+        let journal = EntityJournal::new(path).unwrap();
+        assert_eq!(journal.head().unwrap(), 9);
+        assert_eq!(journal.page("a", 10, None).unwrap().entries.len(), 2);
+    }
+}
@@ -0,0 +1,102 @@
+use super::EntityJournal;
+use crate::state::JournalRecord;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+/// Writes the updates the state engine sends through `rx` to `journal`
+///
+/// Updates are committed together once `flush_every` changes are waiting or
+/// `flush_interval` has passed, so each commit's fsync covers many of them.
+/// Returns after writing what's left once every sender is dropped.
+pub async fn run_journal_writer(
+    journal: Arc<EntityJournal>,
+    mut rx: mpsc::UnboundedReceiver<JournalRecord>,
+    flush_every: usize,
+    flush_interval: Duration,
+) {
+    let mut pending = Vec::new();
+    let mut pending_changes = 0;
+    let mut interval = tokio::time::interval(flush_interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            record = rx.recv() => match record {
+                Some(record) => {
+                    pending_changes += record.update.changes.len();
+                    pending.push(record);
+                    if pending_changes < flush_every.max(1) {
+                        continue;
+                    }
+                }
+                None => {
+                    flush(&journal, pending).await;
+                    return;
+                }
+            },
+            _ = interval.tick() => {
+                if pending.is_empty() {
+                    continue;
+                }
+            }
+        }
+        flush(&journal, std::mem::take(&mut pending)).await;
+        pending_changes = 0;
+    }
+}
+
+/// Append `records` off the runtime; a failed batch is logged and dropped
+async fn flush(journal: &Arc<EntityJournal>, records: Vec<JournalRecord>) {
+    if records.is_empty() {
+        return;
+    }
+    let count = records.len();
+    let journal = Arc::clone(journal);
+    match tokio::task::spawn_blocking(move || journal.append(&records)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => warn!(error = %e, updates = count, "Failed to write entity journal"),
+        Err(e) => warn!(error = %e, updates = count, "Entity journal write panicked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StateEngine;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_writer_batches_and_drains_on_close() {
+        let journal = Arc::new(EntityJournal::new(":memory:").unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+        let engine = StateEngine::new().with_journal(tx, 0);
+        engine.set_live();
+        let writer = tokio::spawn(run_journal_writer(
+            Arc::clone(&journal),
+            rx,
+            2,
+            Duration::from_secs(3600),
+        ));
+
+        engine.update_property("sensor-01", "value", json!(1));
+        engine.update_property("sensor-01", "value", json!(2));
+        // Two changes fill a batch, long before the interval
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while journal.page("sensor-01", 10, None).unwrap().entries.len() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        engine.update_property("sensor-01", "value", json!(3));
+        drop(engine);
+        writer.await.unwrap();
+        let page = journal.page("sensor-01", 10, None).unwrap();
+        assert_eq!(page.entries.len(), 3);
+        assert_eq!(page.entries[0].new_value, json!(3));
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Configuration for leader election between Flux replicas
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderConfig {
+    /// Elect a leader through NATS KV; when off this instance always leads
+    pub enabled: bool,
+
+    /// Replica ID; defaults to FLUX_INSTANCE_ID, then HOSTNAME, then a random UUID
+    pub instance_id: Option<String>,
+
+    /// Lease lifetime (seconds); the leader renews every third of it
+    pub ttl_seconds: u64,
+
+    /// KV bucket holding the lease
+    pub bucket: String,
+}
+
+impl Default for LeaderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            instance_id: None,
+            ttl_seconds: 15,
+            bucket: "flux_leader".to_string(),
+        }
+    }
+}
+
+impl LeaderConfig {
+    pub fn instance_id(&self) -> String {
+        self.instance_id
+            .clone()
+            .or_else(|| std::env::var("FLUX_INSTANCE_ID").ok())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Lease TTL, at least 3 seconds so renewals happen at least once a second
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_seconds.max(3))
+    }
+}
@@ -0,0 +1,86 @@
+use super::{Lease, LeaseStore};
+use anyhow::{Context, Result};
+use async_nats::jetstream::{self, kv};
+use chrono::{DateTime, Utc};
+
+/// Lease kept under one key of a NATS KV bucket
+///
+/// Writes use the KV revision as a compare-and-set, so two replicas racing
+/// for an expired lease cannot both win.
+pub struct KvLeaseStore {
+    kv: kv::Store,
+    key: String,
+}
+
+impl KvLeaseStore {
+    /// Open the bucket (created on first use) holding the lease under `key`
+    pub async fn open(jetstream: &jetstream::Context, bucket: &str, key: &str) -> Result<Self> {
+        let kv = match jetstream.get_key_value(bucket).await {
+            Ok(kv) => kv,
+            Err(_) => jetstream
+                .create_key_value(kv::Config {
+                    bucket: bucket.to_string(),
+                    history: 1,
+                    ..Default::default()
+                })
+                .await
+                .with_context(|| format!("Failed to create KV bucket '{}'", bucket))?,
+        };
+        Ok(Self {
+            kv,
+            key: key.to_string(),
+        })
+    }
+
+    async fn read(&self) -> Result<Option<(Lease, u64)>> {
+        let entry = self
+            .kv
+            .entry(&self.key)
+            .await
+            .context("Failed to read leader lease")?;
+        match entry {
+            Some(entry) if matches!(entry.operation, kv::Operation::Put) => {
+                let lease =
+                    serde_json::from_slice(&entry.value).context("Malformed leader lease")?;
+                Ok(Some((lease, entry.revision)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl LeaseStore for KvLeaseStore {
+    async fn acquire(
+        &self,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Lease> {
+        let lease = Lease {
+            holder: holder.to_string(),
+            expires_at,
+        };
+        let value = serde_json::to_vec(&lease)?;
+
+        let written = match self.read().await? {
+            None => self.kv.create(&self.key, value.into()).await.is_ok(),
+            Some((current, _)) if current.holder != holder && current.expires_at > now => {
+                return Ok(current);
+            }
+            Some((_, revision)) => self
+                .kv
+                .update(&self.key, value.into(), revision)
+                .await
+                .is_ok(),
+        };
+        if written {
+            return Ok(lease);
+        }
+
+        // Lost a race for the key: report whoever won
+        self.read()
+            .await?
+            .map(|(current, _)| current)
+            .context("Leader lease missing after a conflicting write")
+    }
+}
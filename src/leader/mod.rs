@@ -0,0 +1,271 @@
+// Leader election for multi-replica deployments: replicas race for one lease
+// with a TTL, the holder renews it every third of the TTL, and leader-only
+// loops (snapshots, archiving) run only on the holder. A leader whose renewals
+// keep failing steps down before the lease can expire, so another replica
+// never takes over while the old one is still running those loops.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+pub mod config;
+pub mod kv;
+
+pub use config::LeaderConfig;
+pub use kv::KvLeaseStore;
+
+#[cfg(test)]
+mod tests;
+
+/// The lease as stored: who holds it and until when
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lease {
+    pub holder: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Shared storage for the lease
+pub trait LeaseStore: Send + Sync + 'static {
+    /// Take or extend the lease for `holder` until `expires_at`, unless another
+    /// holder's lease is still valid at `now`
+    ///
+    /// Returns the lease as stored afterwards; the caller leads if it names them.
+    fn acquire(
+        &self,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Lease>> + Send;
+}
+
+/// This replica's view of the election
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "instance_id": "flux-0",
+    "is_leader": true,
+    "leader": "flux-0",
+    "changed_at": "2026-01-01T00:00:00Z"
+}))]
+pub struct LeaderStatus {
+    pub instance_id: String,
+    pub is_leader: bool,
+    /// Current lease holder, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+    /// When this replica last gained or lost leadership
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+/// Cloneable handle for reading and awaiting leadership changes
+#[derive(Clone)]
+pub struct Leadership {
+    rx: watch::Receiver<LeaderStatus>,
+}
+
+impl Leadership {
+    /// Leadership of a single-instance deployment: always the leader
+    pub fn standalone(instance_id: impl Into<String>) -> Self {
+        let instance_id = instance_id.into();
+        let (_tx, rx) = watch::channel(LeaderStatus {
+            leader: Some(instance_id.clone()),
+            instance_id,
+            is_leader: true,
+            changed_at: None,
+        });
+        Self { rx }
+    }
+
+    /// Leadership of a single instance that starts leading once `start`
+    /// completes (a warm standby waiting to be promoted)
+    pub fn standalone_after<F>(instance_id: impl Into<String>, start: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let instance_id = instance_id.into();
+        let (tx, rx) = watch::channel(LeaderStatus {
+            instance_id: instance_id.clone(),
+            is_leader: false,
+            leader: None,
+            changed_at: None,
+        });
+        tokio::spawn(async move {
+            start.await;
+            info!(instance_id = %instance_id, "Acquired leadership");
+            tx.send_modify(|status| {
+                status.is_leader = true;
+                status.leader = Some(instance_id);
+                status.changed_at = Some(Utc::now());
+            });
+        });
+        Self { rx }
+    }
+
+    pub fn status(&self) -> LeaderStatus {
+        self.rx.borrow().clone()
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.rx.borrow().is_leader
+    }
+
+    /// Wait until this replica's leadership equals `is_leader`
+    ///
+    /// Returns false if it never will (the elector has stopped).
+    pub async fn wait_for(&mut self, is_leader: bool) -> bool {
+        self.rx.wait_for(|s| s.is_leader == is_leader).await.is_ok()
+    }
+}
+
+/// Run `task` only while this replica leads
+///
+/// The task starts when leadership is gained and is aborted when it is lost;
+/// a fresh one starts if leadership comes back.
+pub fn spawn_while_leader<F, Fut>(
+    leadership: Leadership,
+    name: &'static str,
+    task: F,
+) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(run_while_leader(leadership, name, task))
+}
+
+/// [`spawn_while_leader`] without the spawn, for a caller that supervises it
+///
+/// The task runs inside this future, so a panic in it unwinds the caller and
+/// dropping the future stops it.
+pub async fn run_while_leader<F, Fut>(mut leadership: Leadership, name: &'static str, mut task: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    loop {
+        if !leadership.wait_for(true).await {
+            return;
+        }
+        info!(task = name, "Leader: starting task");
+        let run = task();
+        tokio::pin!(run);
+        tokio::select! {
+            lost = leadership.wait_for(false) => {
+                if !lost {
+                    // Elector gone while leading (standalone): run to completion
+                    run.await;
+                    return;
+                }
+                info!(task = name, "No longer leader: task stopped");
+            }
+            () = &mut run => {
+                if !leadership.wait_for(false).await {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Takes and renews the lease, publishing the outcome to [`Leadership`]
+pub struct LeaderElector<S> {
+    store: Arc<S>,
+    instance_id: String,
+    ttl: Duration,
+    /// Last successful renewal while leading
+    renewed_at: Option<DateTime<Utc>>,
+    tx: watch::Sender<LeaderStatus>,
+}
+
+impl<S: LeaseStore> LeaderElector<S> {
+    pub fn new(store: Arc<S>, instance_id: String, ttl: std::time::Duration) -> (Self, Leadership) {
+        let (tx, rx) = watch::channel(LeaderStatus {
+            instance_id: instance_id.clone(),
+            is_leader: false,
+            leader: None,
+            changed_at: None,
+        });
+        let elector = Self {
+            store,
+            instance_id,
+            ttl: Duration::from_std(ttl).expect("leader TTL out of range"),
+            renewed_at: None,
+            tx,
+        };
+        (elector, Leadership { rx })
+    }
+
+    fn renew_interval(&self) -> Duration {
+        self.ttl / 3
+    }
+
+    /// One election round at `now`; returns whether this replica leads
+    pub async fn tick(&mut self, now: DateTime<Utc>) -> bool {
+        match self
+            .store
+            .acquire(&self.instance_id, now, now + self.ttl)
+            .await
+        {
+            Ok(lease) => {
+                let is_leader = lease.holder == self.instance_id && lease.expires_at > now;
+                self.renewed_at = is_leader.then_some(now);
+                self.publish(is_leader, Some(lease.holder), now);
+                is_leader
+            }
+            Err(e) => {
+                // Keep leading only while the lease is sure to outlive the next round
+                let deadline = self.ttl - self.renew_interval();
+                let is_leader = self.renewed_at.is_some_and(|at| now < at + deadline);
+                warn!(error = %e, is_leader, "Leader lease renewal failed");
+                if !is_leader {
+                    self.renewed_at = None;
+                }
+                let leader = self.tx.borrow().leader.clone();
+                self.publish(is_leader, leader, now);
+                is_leader
+            }
+        }
+    }
+
+    fn publish(&self, is_leader: bool, leader: Option<String>, now: DateTime<Utc>) {
+        let instance_id = &self.instance_id;
+        self.tx.send_if_modified(|status| {
+            let flipped = status.is_leader != is_leader;
+            if flipped {
+                if is_leader {
+                    info!(instance_id = %instance_id, "Acquired leadership");
+                } else {
+                    warn!(instance_id = %instance_id, leader = ?leader, "Lost leadership");
+                }
+                status.is_leader = is_leader;
+                status.changed_at = Some(now);
+            } else if !is_leader && leader.is_some() && status.leader != leader {
+                info!(instance_id = %instance_id, leader = ?leader, "Following leader");
+            }
+            let holder_changed = status.leader != leader;
+            status.leader = leader;
+            flipped || holder_changed
+        });
+    }
+
+    /// Run election rounds every third of the TTL, forever
+    pub async fn run(mut self) {
+        let period = self
+            .renew_interval()
+            .to_std()
+            .expect("leader TTL out of range");
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.tick(Utc::now()).await;
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// In-memory lease with the same semantics as the KV store; can be made to fail
+#[derive(Default)]
+struct MemoryLeaseStore {
+    lease: Mutex<Option<Lease>>,
+    failing: AtomicBool,
+}
+
+impl LeaseStore for MemoryLeaseStore {
+    async fn acquire(
+        &self,
+        holder: &str,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Lease> {
+        if self.failing.load(Ordering::SeqCst) {
+            anyhow::bail!("kv unavailable");
+        }
+        let mut lease = self.lease.lock().unwrap();
+        match lease.as_ref() {
+            Some(current) if current.holder != holder && current.expires_at > now => {
+                Ok(current.clone())
+            }
+            _ => {
+                let taken = Lease {
+                    holder: holder.to_string(),
+                    expires_at,
+                };
+                *lease = Some(taken.clone());
+                Ok(taken)
+            }
+        }
+    }
+}
+
+const TTL: std::time::Duration = std::time::Duration::from_secs(15);
+
+fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+}
+
+fn elector(
+    store: &Arc<MemoryLeaseStore>,
+    id: &str,
+) -> (LeaderElector<MemoryLeaseStore>, Leadership) {
+    LeaderElector::new(Arc::clone(store), id.to_string(), TTL)
+}
+
+#[tokio::test]
+async fn test_first_replica_leads_and_second_follows() {
+    let store = Arc::new(MemoryLeaseStore::default());
+    let (mut a, lead_a) = elector(&store, "a");
+    let (mut b, lead_b) = elector(&store, "b");
+
+    assert!(a.tick(at(0)).await);
+    assert!(!b.tick(at(1)).await);
+    assert!(a.tick(at(5)).await);
+
+    assert!(lead_a.is_leader());
+    assert_eq!(lead_a.status().changed_at, Some(at(0)));
+    let follower = lead_b.status();
+    assert!(!follower.is_leader);
+    assert_eq!(follower.leader.as_deref(), Some("a"));
+    assert_eq!(follower.changed_at, None);
+}
+
+#[tokio::test]
+async fn test_follower_takes_over_after_lease_expires() {
+    let store = Arc::new(MemoryLeaseStore::default());
+    let (mut a, lead_a) = elector(&store, "a");
+    let (mut b, lead_b) = elector(&store, "b");
+
+    assert!(a.tick(at(0)).await);
+    // `a` stops renewing; its lease runs until at(15)
+    assert!(!b.tick(at(14)).await);
+    assert!(b.tick(at(16)).await);
+    assert_eq!(lead_b.status().changed_at, Some(at(16)));
+
+    // `a` comes back and finds `b` in charge
+    assert!(!a.tick(at(17)).await);
+    let status = lead_a.status();
+    assert!(!status.is_leader);
+    assert_eq!(status.leader.as_deref(), Some("b"));
+    assert_eq!(status.changed_at, Some(at(17)));
+}
+
+#[tokio::test]
+async fn test_leader_steps_down_before_lease_expires_when_renewals_fail() {
+    let store = Arc::new(MemoryLeaseStore::default());
+    let (mut a, lead_a) = elector(&store, "a");
+
+    assert!(a.tick(at(0)).await);
+    store.failing.store(true, Ordering::SeqCst);
+    // One missed renewal is tolerated ...
+    assert!(a.tick(at(5)).await);
+    // ... but not one that leaves the lease (expiring at 15) without a next round
+    assert!(!a.tick(at(10)).await);
+    assert!(!lead_a.is_leader());
+    assert_eq!(lead_a.status().leader.as_deref(), Some("a"));
+
+    store.failing.store(false, Ordering::SeqCst);
+    assert!(a.tick(at(11)).await);
+}
+
+#[tokio::test]
+async fn test_follower_errors_keep_it_following() {
+    let store = Arc::new(MemoryLeaseStore::default());
+    store.failing.store(true, Ordering::SeqCst);
+    let (mut a, lead_a) = elector(&store, "a");
+    assert!(!a.tick(at(0)).await);
+    assert_eq!(lead_a.status().leader, None);
+}
+
+/// Counts running copies of a task; decremented when a copy is aborted
+struct Running(Arc<AtomicUsize>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+async fn settle() {
+    for _ in 0..10 {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn test_spawn_while_leader_follows_leadership() {
+    let store = Arc::new(MemoryLeaseStore::default());
+    let (mut a, leadership) = elector(&store, "a");
+    let running = Arc::new(AtomicUsize::new(0));
+    let starts = Arc::new(AtomicUsize::new(0));
+
+    let (r, s) = (Arc::clone(&running), Arc::clone(&starts));
+    spawn_while_leader(leadership, "test", move || {
+        let running = Arc::clone(&r);
+        s.fetch_add(1, Ordering::SeqCst);
+        async move {
+            running.fetch_add(1, Ordering::SeqCst);
+            let _guard = Running(running);
+            std::future::pending::<()>().await;
+        }
+    });
+
+    settle().await;
+    assert_eq!(starts.load(Ordering::SeqCst), 0);
+
+    a.tick(at(0)).await;
+    settle().await;
+    assert_eq!(running.load(Ordering::SeqCst), 1);
+
+    // Another replica holds the lease: the task must stop
+    *store.lease.lock().unwrap() = Some(Lease {
+        holder: "b".to_string(),
+        expires_at: at(30),
+    });
+    a.tick(at(5)).await;
+    settle().await;
+    assert_eq!(running.load(Ordering::SeqCst), 0);
+
+    a.tick(at(31)).await;
+    settle().await;
+    assert_eq!(running.load(Ordering::SeqCst), 1);
+    assert_eq!(starts.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_standalone_always_leads() {
+    let mut leadership = Leadership::standalone("solo");
+    assert!(leadership.is_leader());
+    assert!(leadership.wait_for(true).await);
+    assert!(!leadership.wait_for(false).await);
+    assert_eq!(leadership.status().leader.as_deref(), Some("solo"));
+}
+
+#[tokio::test]
+async fn test_standalone_after_leads_once_started() {
+    let (start, started) = tokio::sync::oneshot::channel::<()>();
+    let mut leadership = Leadership::standalone_after("standby", async move {
+        let _ = started.await;
+    });
+    settle().await;
+    assert!(!leadership.is_leader());
+    assert_eq!(leadership.status().leader, None);
+
+    start.send(()).unwrap();
+    assert!(leadership.wait_for(true).await);
+    assert_eq!(leadership.status().leader.as_deref(), Some("standby"));
+    assert!(leadership.status().changed_at.is_some());
+    // Like standalone, it never steps down
+    assert!(!leadership.wait_for(false).await);
+}
@@ -1,3 +1,7 @@
+// Cargo features (see Cargo.toml): the event model, validation, entity
+// parsing and the state engine are always built; `snapshot`, `credentials`,
+// `http-api` and `oauth` add the rest of the server.
+
 // Configuration
 pub mod config;
 
@@ -10,7 +14,11 @@ pub use event::FluxEvent;
 // State engine and entity management
 pub mod state;
 
+// Payload mappings for streams without the entity_id/properties envelope
+pub mod mapping;
+
 // HTTP and WebSocket APIs
+#[cfg(feature = "http-api")]
 pub mod api;
 
 // NATS client integration
@@ -19,9 +27,48 @@ pub mod nats;
 // Subscription management
 pub mod subscription;
 
+// One-shot entity watches
+#[cfg(feature = "http-api")]
+pub mod watch;
+
 // Snapshot and persistence
+#[cfg(feature = "snapshot")]
 pub mod snapshot;
 
+// Event archive and stream pruning
+#[cfg(feature = "snapshot")]
+pub mod archive;
+
+// Event stream compaction
+#[cfg(feature = "snapshot")]
+pub mod compaction;
+
+// S3-compatible object storage client (archive and snapshots)
+#[cfg(feature = "snapshot")]
+pub mod s3;
+
+// Outbound HTTP clients (proxy, timeouts, User-Agent)
+#[cfg(feature = "snapshot")]
+pub mod http_client;
+
+// Leader election between replicas
+pub mod leader;
+
+// Restarting background tasks that panic
+pub mod supervisor;
+
+// Warm standby and promotion
+#[cfg(feature = "snapshot")]
+pub mod standby;
+
+// Event forwarding to other sites
+#[cfg(feature = "snapshot")]
+pub mod federation;
+
+// Point-in-time replays into sandbox namespaces
+#[cfg(feature = "snapshot")]
+pub mod replay;
+
 // Namespace and multi-tenancy
 pub mod namespace;
 
@@ -32,7 +79,28 @@ pub mod auth;
 pub mod entity;
 
 // Connector credential storage
+#[cfg(feature = "credentials")]
 pub mod credentials;
 
+// Versioned SQLite schemas for the stores
+#[cfg(feature = "credentials")]
+pub mod migrations;
+
+// Idempotency keys for create endpoints
+#[cfg(feature = "credentials")]
+pub mod idempotency;
+
+// Recent changes per entity, persisted
+#[cfg(feature = "credentials")]
+pub mod journal;
+
 // Rate limiting (ADR-006)
 pub mod rate_limit;
+
+// Signatures on webhook and federation deliveries
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+// Synthetic load generation (flux simulate)
+#[cfg(feature = "http-api")]
+pub mod simulate;
@@ -1,34 +1,349 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use axum::Router;
-use tower_http::cors::{Any, CorsLayer};
+use clap::{Parser, Subcommand, ValueEnum};
 use flux::api::{
-    create_admin_router, create_connector_router, create_deletion_router, create_history_router,
-    create_namespace_router, create_oauth_router, create_query_router, create_router,
-    create_ws_router, run_state_cleanup, AdminAppState, AppState, ConnectorAppState,
-    DeletionAppState, HistoryAppState, OAuthAppState, QueryAppState, StateManager, WsAppState,
+    block_during_replay, create_admin_router, create_compaction_router, create_connector_router,
+    create_deletion_router, create_federation_router, create_health_router, create_history_router,
+    create_introspect_router, create_messages_router, create_namespace_router,
+    create_openapi_router, create_query_router, create_rename_router, create_replay_router,
+    create_router, create_standby_router, create_stream_mapping_router, create_watch_router,
+    create_ws_router, primary_only, with_request_tracing, AdminAppState, AppState,
+    CompactionAppState, ConnectorAppState, DeletionAppState, FederationAppState, HealthAppState,
+    HistoryAppState, IntrospectAppState, MessagesAppState, NamespaceAppState, QueryAppState,
+    QueryCache, RenameAppState, ReplayAppState, StandbyAppState, StreamMappingAppState,
+    WatchAppState, WsAppState,
 };
-use flux::rate_limit::RateLimiter;
+#[cfg(feature = "oauth")]
+use flux::api::{
+    create_oauth_router, parse_allowed_origins, run_state_cleanup, OAuthAppState, ProviderRegistry,
+    StateManager,
+};
+use flux::archive::{ArchiveStore, Archiver, JetStreamSource, LocalStore, S3Store};
+use flux::compaction::Compactor;
 use flux::config;
-use flux::config::new_runtime_config;
+use flux::config::new_runtime_config_from_file;
 use flux::credentials::CredentialStore;
+use flux::federation::{Forwarder, HttpSink};
+use flux::http_client::HttpClientConfig;
+use flux::idempotency::{run_idempotency_sweeper, IdempotencyStore, DEFAULT_IDEMPOTENCY_TTL};
+use flux::journal::{run_journal_writer, EntityJournal};
+use flux::leader::{run_while_leader, spawn_while_leader, KvLeaseStore, LeaderElector, Leadership};
+use flux::mapping::{CompiledMapping, StreamMappingStore};
 use flux::namespace::{NamespaceRegistry, NamespaceStore};
-use flux::nats::{EventPublisher, NatsClient};
-use flux::snapshot::{manager::SnapshotManager, recovery};
-use flux::state::StateEngine;
+use flux::nats::{EventPublisher, NatsClient, NatsIngester};
+use flux::rate_limit::RateLimiter;
+use flux::replay::ReplayJobs;
+use flux::simulate::{self, Scenario, Target};
+use flux::snapshot::{
+    manager::SnapshotManager, recovery, verify::verify_recovery, LocalSnapshotStore,
+    S3SnapshotStore, SnapshotStore, StreamIdentity,
+};
+use flux::standby::{InstanceMode, ModeHandle, SnapshotShipper, SnapshotSource};
+use flux::state::{
+    repair_consumer, DiffOptions, JetStreamConsumerStore, StateEngine,
+    DEFAULT_MAX_REPORTED_DIVERGENCES, EVENTS_STREAM,
+};
+use flux::subscription::ConnectionTracker;
+use flux::supervisor::{RestartPolicy, TaskSupervisor};
+use flux::watch::{run_watches, WatchManager};
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
 
+#[derive(Parser)]
+#[command(name = "flux", version, about = "Flux state engine")]
+struct Cli {
+    /// Runs the server when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Publish synthetic entity updates to a running Flux
+    Simulate(Box<SimulateArgs>),
+    /// Line the state engine's NATS consumer up with the latest snapshot
+    /// after the event stream was purged or re-created (run with Flux stopped)
+    RepairConsumer(RepairConsumerArgs),
+    /// Check that recovering from the latest snapshot gives the same state
+    /// as replaying the whole event stream (exits non-zero if not)
+    VerifyRecovery(VerifyRecoveryArgs),
+}
+
+#[derive(clap::Args)]
+struct RepairConsumerArgs {
+    /// Report what would be done without changing the consumer
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(clap::Args)]
+struct VerifyRecoveryArgs {
+    /// Entities' last_updated may differ by this much
+    #[arg(long, default_value_t = 1000)]
+    tolerance_ms: i64,
+    /// Divergent entities to list
+    #[arg(long, default_value_t = DEFAULT_MAX_REPORTED_DIVERGENCES)]
+    max_reported: usize,
+}
+
+#[derive(clap::Args)]
+struct SimulateArgs {
+    /// TOML scenario file; flags override its values
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+    /// Number of entities
+    #[arg(long)]
+    entities: Option<usize>,
+    /// Number of namespaces the entities are spread over
+    #[arg(long)]
+    namespaces: Option<usize>,
+    /// Target events per second
+    #[arg(long)]
+    rate: Option<u32>,
+    /// Run for this many seconds
+    #[arg(long)]
+    duration: Option<u64>,
+    /// Stop after this many events (runs until then unless --duration is set)
+    #[arg(long)]
+    events: Option<u64>,
+    /// Chance that an event deletes its entity
+    #[arg(long)]
+    delete_probability: Option<f64>,
+    /// Publishes awaiting a response at once
+    #[arg(long)]
+    concurrency: Option<usize>,
+    /// RNG seed for a reproducible run
+    #[arg(long)]
+    seed: Option<u64>,
+    #[arg(long, value_enum, default_value_t = SimulateTarget::Http)]
+    target: SimulateTarget,
+    /// Flux base URL (http target)
+    #[arg(long, default_value = "http://localhost:3000")]
+    url: String,
+    /// Bearer token for namespaces without one in the scenario (http target)
+    #[arg(long, env = "FLUX_TOKEN")]
+    token: Option<String>,
+    /// NATS URL (nats target; defaults to the config file's)
+    #[arg(long)]
+    nats_url: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SimulateTarget {
+    /// POST /api/events
+    Http,
+    /// Publish to JetStream directly
+    Nats,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize tracing subscriber
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "flux=info".into()),
+                .unwrap_or_else(|_| "flux=info,tower_http=info".into()),
         )
         .init();
 
+    match cli.command {
+        None => serve().await,
+        Some(Command::Simulate(args)) => run_simulation(*args).await,
+        Some(Command::RepairConsumer(args)) => repair_state_consumer(args).await,
+        Some(Command::VerifyRecovery(args)) => verify_state_recovery(args).await,
+    }
+}
+
+async fn repair_state_consumer(args: RepairConsumerArgs) -> Result<()> {
+    let config_path = std::env::var("FLUX_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+    let flux_config = config::load_config(&config_path).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Failed to load config, using defaults");
+        config::FluxConfig::default()
+    });
+
+    let transfer_client = flux_config
+        .http
+        .clone()
+        .with_env_proxy()
+        .with_timeout_seconds(0)
+        .build()?;
+    let snapshot_store: Arc<dyn SnapshotStore> = match &flux_config.snapshot.s3 {
+        Some(s3) => {
+            Arc::new(S3SnapshotStore::from_env(s3.clone())?.with_http_client(transfer_client))
+        }
+        None => Arc::new(LocalSnapshotStore::new(&flux_config.snapshot.directory)),
+    };
+    let snapshot_sequence = recovery::load_latest_snapshot(snapshot_store.as_ref())
+        .await?
+        .map(|(_, sequence)| sequence);
+
+    let nats_client = NatsClient::connect(flux_config.nats.clone()).await?;
+    let store = JetStreamConsumerStore::new(nats_client.jetstream().clone());
+    let repair = repair_consumer(&store, snapshot_sequence, args.dry_run).await?;
+    println!("{}", repair);
+    Ok(())
+}
+
+async fn verify_state_recovery(args: VerifyRecoveryArgs) -> Result<()> {
+    let config_path = std::env::var("FLUX_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+    let flux_config = config::load_config(&config_path).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Failed to load config, using defaults");
+        config::FluxConfig::default()
+    });
+
+    let transfer_client = flux_config
+        .http
+        .clone()
+        .with_env_proxy()
+        .with_timeout_seconds(0)
+        .build()?;
+    let snapshot_store: Arc<dyn SnapshotStore> = match &flux_config.snapshot.s3 {
+        Some(s3) => {
+            Arc::new(S3SnapshotStore::from_env(s3.clone())?.with_http_client(transfer_client))
+        }
+        None => Arc::new(LocalSnapshotStore::new(&flux_config.snapshot.directory)),
+    };
+    let snapshot = match recovery::load_latest_snapshot(snapshot_store.as_ref()).await? {
+        Some((snapshot, _)) => snapshot,
+        None => anyhow::bail!("No snapshot to verify"),
+    };
+
+    // Both worlds are built the way the server builds its engine
+    let runtime_config = new_runtime_config_from_file(&flux_config);
+    let mappings_db_path = std::env::var("FLUX_STREAM_MAPPINGS_DB")
+        .unwrap_or_else(|_| "stream_mappings.db".to_string());
+    let stream_mappings = compile_stream_mappings(&StreamMappingStore::new(&mappings_db_path)?)?;
+    let new_engine = || {
+        let engine = new_state_engine(&flux_config, &runtime_config);
+        for (stream, compiled) in &stream_mappings {
+            engine.set_stream_mapping(stream, compiled.clone());
+        }
+        engine
+    };
+
+    let nats_client = NatsClient::connect(flux_config.nats.clone()).await?;
+    let source = JetStreamSource::new(nats_client.jetstream().clone(), EVENTS_STREAM);
+    let options = DiffOptions {
+        last_updated_tolerance: chrono::Duration::milliseconds(args.tolerance_ms),
+        max_reported: args.max_reported,
+    };
+    let report = verify_recovery(&source, snapshot, new_engine, &options).await?;
+    println!("{}", report);
+    if !report.is_consistent() {
+        anyhow::bail!("{} entities diverge", report.diff.divergent);
+    }
+    Ok(())
+}
+
+async fn run_simulation(args: SimulateArgs) -> Result<()> {
+    let mut scenario = match &args.scenario {
+        Some(path) => Scenario::load(path)?,
+        None => Scenario::default(),
+    };
+    if let Some(entities) = args.entities {
+        scenario.entities = entities;
+    }
+    if let Some(namespaces) = args.namespaces {
+        scenario.namespaces = namespaces;
+    }
+    if let Some(rate) = args.rate {
+        scenario.events_per_second = rate;
+    }
+    if let Some(events) = args.events {
+        scenario.max_events = Some(events);
+        if args.duration.is_none() {
+            scenario.duration_seconds = None;
+        }
+    }
+    if let Some(duration) = args.duration {
+        scenario.duration_seconds = Some(duration);
+    }
+    if let Some(probability) = args.delete_probability {
+        scenario.delete_probability = probability;
+    }
+    if let Some(concurrency) = args.concurrency {
+        scenario.concurrency = concurrency;
+    }
+    if let Some(seed) = args.seed {
+        scenario.seed = Some(seed);
+    }
+
+    let target = match args.target {
+        SimulateTarget::Http => Target::http(
+            HttpClientConfig::default().with_env_proxy().build()?,
+            &args.url,
+            std::mem::take(&mut scenario.tokens),
+            args.token,
+        ),
+        SimulateTarget::Nats => {
+            let config_path =
+                std::env::var("FLUX_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+            let mut nats_config = config::load_config(&config_path)
+                .unwrap_or_else(|e| {
+                    tracing::warn!(error = %e, "Failed to load config, using defaults");
+                    config::FluxConfig::default()
+                })
+                .nats;
+            if let Some(url) = args.nats_url {
+                nats_config.url = url;
+            }
+            let max_in_flight = nats_config.max_in_flight;
+            let subject_scheme = nats_config.subject_scheme;
+            let nats_client = NatsClient::connect(nats_config).await?;
+            Target::Nats(
+                EventPublisher::new(nats_client.jetstream().clone())
+                    .with_max_in_flight(max_in_flight)
+                    .with_subject_scheme(subject_scheme)
+                    .with_connection(nats_client.connection()),
+            )
+        }
+    };
+
+    let report = simulate::run(scenario, target).await?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// State engine configured from the config file, without a NATS connection
+fn new_state_engine(
+    flux_config: &config::FluxConfig,
+    runtime_config: &config::SharedRuntimeConfig,
+) -> StateEngine {
+    StateEngine::with_broadcast_shards(flux_config.state.broadcast_shards)
+        .with_max_properties_per_entity(flux_config.state.max_properties_per_entity)
+        .with_trash(
+            flux_config.state.max_trash_entries,
+            flux_config.state.trash_retention_seconds,
+        )
+        .with_message_log(flux_config.state.max_messages_per_recipient)
+        .with_recent_activity(flux_config.state.recent_activity_capacity)
+        .with_max_tracked_sources(flux_config.metrics.max_tracked_sources)
+        .with_max_publishers(flux_config.metrics.max_tracked_publishers)
+        .with_publisher_entities(flux_config.metrics.publisher_entities)
+        .with_runtime_config(Arc::clone(runtime_config))
+}
+
+/// Stored stream mappings, skipping any that no longer compile
+fn compile_stream_mappings(store: &StreamMappingStore) -> Result<Vec<(String, CompiledMapping)>> {
+    let mut compiled = Vec::new();
+    for (stream, mapping) in store.load_all()? {
+        match CompiledMapping::compile(&mapping) {
+            Ok(mapping) => compiled.push((stream, mapping)),
+            Err(e) => {
+                tracing::warn!(stream = %stream, error = %e, "Skipping invalid stream mapping")
+            }
+        }
+    }
+    Ok(compiled)
+}
+
+async fn serve() -> Result<()> {
     info!("Flux starting...");
 
     // Load configuration
@@ -38,21 +353,187 @@ async fn main() -> Result<()> {
         config::FluxConfig::default()
     });
 
+    // Outbound HTTP clients (OAuth, webhooks, S3, snapshot shipping)
+    let http_config = flux_config.http.clone().with_env_proxy();
+    if let Some(proxy) = &http_config.proxy {
+        info!(proxy = %proxy.redacted_url(), "Outbound HTTP goes through a proxy");
+    }
+    let http_client = http_config.build()?;
+    // Snapshots and archive segments can be large: no whole-request timeout
+    let transfer_client = http_config.clone().with_timeout_seconds(0).build()?;
+
+    // Instance mode: a standby serves no writes until promoted
+    let instance_mode = match std::env::var("FLUX_MODE") {
+        Ok(mode) => mode.parse::<InstanceMode>().map_err(anyhow::Error::msg)?,
+        Err(_) => InstanceMode::Primary,
+    };
+    let mode = ModeHandle::new(instance_mode);
+    info!(mode = ?instance_mode, "Instance mode");
+
     // Initialize NATS client
     let nats_config = flux_config.nats.clone();
     let nats_client = NatsClient::connect(nats_config).await?;
     info!("NATS client connected");
 
-    // Create event publisher
-    let event_publisher = EventPublisher::new(nats_client.jetstream().clone());
+    // Leader election: only the leader writes snapshots and archives events.
+    // A standby doesn't campaign (or lead) until it is promoted.
+    let instance_id = flux_config.leader.instance_id();
+    let leadership = if flux_config.leader.enabled {
+        let lease_store =
+            KvLeaseStore::open(nats_client.jetstream(), &flux_config.leader.bucket, "flux").await?;
+        let (elector, leadership) = LeaderElector::new(
+            Arc::new(lease_store),
+            instance_id.clone(),
+            flux_config.leader.ttl(),
+        );
+        let promoted = mode.clone();
+        tokio::spawn(async move {
+            promoted.wait_for_primary().await;
+            elector.run().await
+        });
+        info!(instance_id = %instance_id, "Leader election started");
+        leadership
+    } else if mode.is_standby() {
+        let promoted = mode.clone();
+        Leadership::standalone_after(
+            instance_id,
+            async move { promoted.wait_for_primary().await },
+        )
+    } else {
+        Leadership::standalone(instance_id)
+    };
+
+    // Initialize runtime config (config file, then env vars, defaults otherwise)
+    let runtime_config = new_runtime_config_from_file(&flux_config);
+    info!("Runtime config initialized");
 
-    // Create state engine
-    let state_engine = Arc::new(StateEngine::new());
+    // Create state engine (entity ID normalization follows runtime config)
+    let mut state_engine =
+        new_state_engine(&flux_config, &runtime_config).with_connection(nats_client.connection());
+
+    // Entity journal: the engine hands applied updates to a batching writer.
+    // Its head tells the engine which replayed updates it already holds
+    let journal = if flux_config.journal.enabled {
+        let journal_config = &flux_config.journal;
+        let journal_path = journal_config.path();
+        let opened = EntityJournal::new(&journal_path).and_then(|journal| {
+            let journal = journal.with_entries_per_entity(journal_config.entries_per_entity);
+            let head = journal.head()?;
+            Ok((Arc::new(journal), head))
+        });
+        match opened {
+            Ok((journal, head)) => {
+                let (journal_tx, journal_rx) = tokio::sync::mpsc::unbounded_channel();
+                state_engine = state_engine.with_journal(journal_tx, head);
+                tokio::spawn(run_journal_writer(
+                    Arc::clone(&journal),
+                    journal_rx,
+                    journal_config.flush_every_writes,
+                    journal_config.flush_interval(),
+                ));
+                info!(head, "Entity journal initialized at {}", journal_path);
+                Some(journal)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to open entity journal, continuing without it");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let state_engine = Arc::new(state_engine);
     info!("State engine initialized");
 
-    // Recovery: Try to load latest snapshot
+    // Load stream mappings before replay so mapped streams rebuild the same state
+    let mappings_db_path = std::env::var("FLUX_STREAM_MAPPINGS_DB")
+        .unwrap_or_else(|_| "stream_mappings.db".to_string());
+    let stream_mapping_store = Arc::new(match StreamMappingStore::new(&mappings_db_path) {
+        Ok(store) => {
+            info!("Stream mapping store initialized at {}", mappings_db_path);
+            store
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to initialize stream mapping store, using in-memory only");
+            StreamMappingStore::new(":memory:")?
+        }
+    });
+    let stream_mappings = compile_stream_mappings(&stream_mapping_store)?;
+    for (stream, compiled) in &stream_mappings {
+        state_engine.set_stream_mapping(stream, compiled.clone());
+    }
+
+    // Create event publisher (acks awaited concurrently, bounded window)
+    let event_publisher = EventPublisher::new(nats_client.jetstream().clone())
+        .with_max_in_flight(flux_config.nats.max_in_flight)
+        .with_subject_scheme(flux_config.nats.subject_scheme)
+        .with_metrics(state_engine.metrics.clone())
+        .with_connection(nats_client.connection());
+
+    // Admin token (admin APIs, and pulling snapshots from the primary)
+    let admin_token = std::env::var("FLUX_ADMIN_TOKEN").ok();
+
+    // Standby: pull the primary's newest snapshot before recovery, then keep pulling
     let snapshot_dir = PathBuf::from(&flux_config.snapshot.directory);
-    let start_sequence = match recovery::load_latest_snapshot(&snapshot_dir)? {
+    if let (true, Some(source)) = (mode.is_standby(), flux_config.standby.snapshot_source()) {
+        let shipper = SnapshotShipper::new(
+            SnapshotSource::parse(&source, admin_token.clone()),
+            snapshot_dir.clone(),
+            flux_config.snapshot.keep_count,
+        )
+        .with_http_client(transfer_client.clone());
+        match shipper.ship_latest().await {
+            Ok(Some(path)) => info!(path = %path.display(), "Shipped snapshot from primary"),
+            Ok(None) => info!("No newer snapshot on primary"),
+            Err(e) => tracing::warn!(error = %e, "Initial snapshot shipping failed"),
+        }
+        let mode = mode.clone();
+        let interval = flux_config.standby.poll_interval();
+        tokio::spawn(async move { shipper.run(mode, interval).await });
+        info!(source = %source, "Snapshot shipping started");
+    }
+
+    // Snapshot storage: the local directory, or an S3-compatible bucket
+    let snapshot_store: Arc<dyn SnapshotStore> = match &flux_config.snapshot.s3 {
+        Some(s3) => Arc::new(
+            S3SnapshotStore::from_env(s3.clone())?.with_http_client(transfer_client.clone()),
+        ),
+        None => Arc::new(LocalSnapshotStore::new(&snapshot_dir)),
+    };
+
+    // The stream snapshot sequences refer to; a re-created one restarts them
+    let live_stream = match nats_client.jetstream().get_stream(EVENTS_STREAM).await {
+        Ok(stream) => Some(StreamIdentity::from_info(stream.cached_info())),
+        Err(e) => {
+            tracing::warn!(
+                error = %e,
+                "Failed to get stream info, snapshots can't be checked against it"
+            );
+            None
+        }
+    };
+
+    // Recovery: Try to load latest snapshot, unless it belongs to another stream
+    let latest = recovery::load_latest_snapshot(snapshot_store.as_ref()).await?;
+    let latest = match (latest, &live_stream) {
+        (Some((snapshot, seq)), Some(live)) => {
+            let check = recovery::StreamCheck::new(snapshot.stream.as_ref(), live);
+            if check == recovery::StreamCheck::Unknown {
+                tracing::warn!(
+                    sequence = seq,
+                    "Snapshot doesn't record its stream, assuming it matches"
+                );
+            }
+            if check.resume_from_snapshot(flux_config.recovery.on_mismatch)? {
+                Some((snapshot, seq))
+            } else {
+                tracing::warn!(%check, "Ignoring snapshot, replaying the whole stream");
+                None
+            }
+        }
+        (latest, _) => latest,
+    };
+    let start_sequence = match latest {
         Some((snapshot, seq)) => {
             info!(
                 sequence = seq,
@@ -61,7 +542,7 @@ async fn main() -> Result<()> {
                 seq,
                 snapshot.entity_count()
             );
-            state_engine.load_from_snapshot(snapshot.to_hashmap(), seq);
+            snapshot.restore_into(&state_engine);
             Some(seq)
         }
         None => {
@@ -70,49 +551,149 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Background tasks below are restarted if they panic (GET /api/admin/tasks)
+    let tasks = Arc::new(TaskSupervisor::new());
+
     // Start state engine subscriber (background task)
     let engine_clone = Arc::clone(&state_engine);
     let jetstream_clone = nats_client.jetstream().clone();
-    tokio::spawn(async move {
-        if let Err(e) = engine_clone.run_subscriber(jetstream_clone, start_sequence).await {
-            tracing::error!(error = %e, "State engine subscriber failed");
-        }
+    tasks.supervise("state_subscriber", RestartPolicy::default(), move || {
+        // A restart resumes after the last applied event, not the snapshot
+        let start = match engine_clone.get_last_processed_sequence() {
+            0 => start_sequence,
+            last => Some(last),
+        };
+        Arc::clone(&engine_clone).run_subscriber(jetstream_clone.clone(), start)
     });
     info!("State engine subscriber started");
 
-    // Start metrics broadcaster (background task)
+    // Start metrics broadcaster (background task, follows runtime config)
     let engine_clone = Arc::clone(&state_engine);
-    let metrics_config = flux_config.metrics.clone();
-    tokio::spawn(async move {
-        flux::state::run_metrics_broadcaster(
-            engine_clone,
-            metrics_config.broadcast_interval_seconds,
-            metrics_config.active_publisher_window_seconds,
-        )
-        .await;
+    let config_clone = Arc::clone(&runtime_config);
+    tasks.supervise("metrics_broadcaster", RestartPolicy::default(), move || {
+        flux::state::run_metrics_broadcaster(Arc::clone(&engine_clone), Arc::clone(&config_clone))
     });
     info!("Metrics broadcaster started");
 
-    // Start snapshot manager (background task)
-    let snapshot_manager = SnapshotManager::new(Arc::clone(&state_engine), flux_config.snapshot.clone());
-    tokio::spawn(async move {
-        if let Err(e) = snapshot_manager.run_snapshot_loop().await {
-            tracing::error!(error = %e, "Snapshot manager failed");
+    // Start entity TTL sweeper (background task, idle while entity_ttl_seconds = 0);
+    // it publishes tombstones, so a standby starts it once promoted
+    let engine_clone = Arc::clone(&state_engine);
+    let publisher_clone = event_publisher.clone();
+    let config_clone = Arc::clone(&runtime_config);
+    let promoted = mode.clone();
+    tasks.supervise("ttl_sweeper", RestartPolicy::default(), move || {
+        let ttl_sweeper = flux::state::run_ttl_sweeper(
+            Arc::clone(&engine_clone),
+            publisher_clone.clone(),
+            Arc::clone(&config_clone),
+        );
+        let promoted = promoted.clone();
+        async move {
+            promoted.wait_for_primary().await;
+            ttl_sweeper.await
+        }
+    });
+    info!("TTL sweeper started");
+
+    // Purge deleted entities once their trash retention runs out
+    let engine_clone = Arc::clone(&state_engine);
+    tasks.supervise("trash_sweeper", RestartPolicy::default(), move || {
+        flux::state::run_trash_sweeper(Arc::clone(&engine_clone))
+    });
+    info!("Trash sweeper started");
+
+    // Start snapshot manager (background task, leader only)
+    let mut snapshot_manager =
+        SnapshotManager::new(Arc::clone(&state_engine), flux_config.snapshot.clone())
+            .with_store(Arc::clone(&snapshot_store))
+            .with_runtime_config(Arc::clone(&runtime_config));
+    if let Some(stream) = live_stream {
+        snapshot_manager = snapshot_manager.with_stream_identity(stream);
+    }
+    let snapshot_manager = Arc::new(snapshot_manager);
+    let compaction_snapshots = Arc::clone(&snapshot_manager);
+    let snapshot_loop = move || {
+        let snapshot_manager = Arc::clone(&snapshot_manager);
+        async move {
+            if let Err(e) = snapshot_manager.run_snapshot_loop().await {
+                tracing::error!(error = %e, "Snapshot manager failed");
+            }
         }
+    };
+    let snapshot_leadership = leadership.clone();
+    tasks.supervise("snapshots", RestartPolicy::default(), move || {
+        let leadership = snapshot_leadership.clone();
+        run_while_leader(leadership, "snapshots", snapshot_loop.clone())
     });
     info!("Snapshot manager started");
 
+    // Start event archiver (background task); history reads fall back to the archive
+    let archive_store: Option<Arc<dyn ArchiveStore>> = if flux_config.archive.enabled {
+        let store: Arc<dyn ArchiveStore> = match &flux_config.archive.s3 {
+            Some(s3) => {
+                Arc::new(S3Store::from_env(s3.clone())?.with_http_client(transfer_client.clone()))
+            }
+            None => Arc::new(LocalStore::new(&flux_config.archive.directory)),
+        };
+        let archiver = Arc::new(Archiver::new(
+            Arc::new(JetStreamSource::new(
+                nats_client.jetstream().clone(),
+                flux_config.nats.stream_name.clone(),
+            )),
+            Arc::clone(&store),
+            flux_config.archive.clone(),
+            Arc::clone(&snapshot_store),
+        ));
+        spawn_while_leader(leadership.clone(), "archiver", move || {
+            let archiver = Arc::clone(&archiver);
+            async move {
+                if let Err(e) = archiver.run_archive_loop().await {
+                    tracing::error!(error = %e, "Event archiver failed");
+                }
+            }
+        });
+        info!("Event archiver started");
+        Some(store)
+    } else {
+        None
+    };
+
+    // Start federation forwarder (background task, leader only)
+    let forwarder = if flux_config.federation.rules.is_empty() {
+        None
+    } else {
+        let site = flux_config
+            .federation
+            .site
+            .clone()
+            .unwrap_or_else(|| leadership.status().instance_id);
+        let forwarder = Arc::new(Forwarder::new(
+            Arc::new(JetStreamSource::new(
+                nats_client.jetstream().clone(),
+                flux_config.nats.stream_name.clone(),
+            )),
+            Arc::new(HttpSink::new(http_client.clone())),
+            flux_config.federation.clone(),
+            site.clone(),
+        )?);
+        let task = Arc::clone(&forwarder);
+        spawn_while_leader(leadership.clone(), "federation", move || {
+            let forwarder = Arc::clone(&task);
+            async move { forwarder.run().await }
+        });
+        info!(
+            site = %site,
+            rules = flux_config.federation.rules.len(),
+            "Federation forwarder started"
+        );
+        Some(forwarder)
+    };
+
     // Initialize HTTP server
     let port = std::env::var("PORT")
         .unwrap_or_else(|_| "3000".to_string())
         .parse::<u16>()?;
 
-    // Initialize runtime config (loaded from env vars, defaults otherwise)
-    let runtime_config = new_runtime_config();
-    info!("Runtime config initialized");
-
-    // Admin token (for PUT /api/admin/config)
-    let admin_token = std::env::var("FLUX_ADMIN_TOKEN").ok();
     if admin_token.is_none() {
         tracing::warn!("FLUX_ADMIN_TOKEN not set - admin config PUT is unrestricted");
     }
@@ -126,8 +707,8 @@ async fn main() -> Result<()> {
     info!("Auth enabled: {}", auth_enabled);
 
     // Initialize namespace store (persists registrations across restarts)
-    let ns_db_path = std::env::var("FLUX_NAMESPACE_DB")
-        .unwrap_or_else(|_| "namespaces.db".to_string());
+    let ns_db_path =
+        std::env::var("FLUX_NAMESPACE_DB").unwrap_or_else(|_| "namespaces.db".to_string());
     let namespace_registry = Arc::new(match NamespaceStore::new(&ns_db_path) {
         Ok(store) => {
             info!("Namespace store initialized at {}", ns_db_path);
@@ -139,32 +720,65 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Idempotency-Keys of namespace registrations, kept next to the namespaces
+    let idempotency = match IdempotencyStore::new(&ns_db_path) {
+        Ok(store) => {
+            let store = Arc::new(store);
+            // Hourly; expired keys are ignored until then
+            tokio::spawn(run_idempotency_sweeper(
+                Arc::clone(&store),
+                DEFAULT_IDEMPOTENCY_TTL / 24,
+            ));
+            Some(store)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to initialize idempotency store, ignoring Idempotency-Key");
+            None
+        }
+    };
+
     // Initialize credential store (for connector framework)
-    let credential_store = std::env::var("FLUX_ENCRYPTION_KEY")
-        .ok()
-        .and_then(|key| {
-            let db_path = std::env::var("FLUX_CREDENTIALS_DB")
-                .unwrap_or_else(|_| "credentials.db".to_string());
-
-            match CredentialStore::new(&db_path, &key) {
-                Ok(store) => {
-                    info!("Credential store initialized at {}", db_path);
-                    Some(Arc::new(store))
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        error = %e,
-                        "Failed to initialize credential store (connectors disabled)"
-                    );
-                    None
-                }
+    let credential_store = std::env::var("FLUX_ENCRYPTION_KEY").ok().and_then(|key| {
+        let db_path =
+            std::env::var("FLUX_CREDENTIALS_DB").unwrap_or_else(|_| "credentials.db".to_string());
+
+        match CredentialStore::new(&db_path, &key) {
+            Ok(store) => {
+                info!("Credential store initialized at {}", db_path);
+                Some(Arc::new(store))
             }
-        });
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "Failed to initialize credential store (connectors disabled)"
+                );
+                None
+            }
+        }
+    });
 
     if credential_store.is_none() {
         tracing::warn!("FLUX_ENCRYPTION_KEY not set - connector framework disabled");
     }
 
+    // Credentials used to be keyed by the raw namespace token
+    if let (Some(store), true) = (&credential_store, auth_enabled) {
+        let users: BTreeSet<String> = store
+            .list_all()
+            .context("Failed to list stored credentials")?
+            .into_iter()
+            .map(|(user_id, _)| user_id)
+            .collect();
+        for user_id in users {
+            if let Some(ns) = namespace_registry.lookup_by_token(&user_id) {
+                let moved = store
+                    .rekey_token_user(&user_id, &ns.name)
+                    .context("Failed to move token-keyed credentials")?;
+                info!(namespace = %ns.name, connectors = moved, "Moved credentials to namespace");
+            }
+        }
+    }
+
     // Initialize rate limiter (per-namespace token buckets, auth-gated)
     let rate_limiter = Arc::new(RateLimiter::new());
     info!("Rate limiter initialized");
@@ -180,8 +794,43 @@ async fn main() -> Result<()> {
     };
     let ingestion_router = create_router(ingestion_state.clone());
 
-    // Create namespace API router (reuses ingestion_state)
-    let namespace_router = create_namespace_router(ingestion_state);
+    // Validate events published to flux.events.ingest.<namespace> (a write path)
+    let ingester = NatsIngester::new(ingestion_state.admission(), event_publisher.clone());
+    let jetstream_clone = nats_client.jetstream().clone();
+    let promoted = mode.clone();
+    tokio::spawn(async move {
+        promoted.wait_for_primary().await;
+        if let Err(e) = ingester.run(jetstream_clone).await {
+            tracing::error!(error = %e, "NATS ingester failed");
+        }
+    });
+    info!("NATS ingester started");
+
+    // Create agent messaging router (messages are admitted like events)
+    let messages_router = create_messages_router(Arc::new(MessagesAppState {
+        event_publisher: event_publisher.clone(),
+        admission: ingestion_state.admission(),
+        state_engine: Arc::clone(&state_engine),
+        admin_token: admin_token.clone(),
+    }));
+
+    // Create namespace API router (quota changes are published as events)
+    let namespace_router = create_namespace_router(NamespaceAppState {
+        event_publisher: event_publisher.clone(),
+        namespace_registry: Arc::clone(&namespace_registry),
+        state_engine: Arc::clone(&state_engine),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+        idempotency,
+    });
+
+    // Create token introspection router (rate limited per client IP)
+    let introspect_router = create_introspect_router(Arc::new(IntrospectAppState {
+        namespace_registry: Arc::clone(&namespace_registry),
+        admin_token: admin_token.clone(),
+        per_ip_per_minute: flux_config.api.introspect_per_ip_per_minute,
+        limiter: RateLimiter::new(),
+    }));
 
     // Create deletion API router
     let deletion_state = DeletionAppState {
@@ -189,23 +838,118 @@ async fn main() -> Result<()> {
         namespace_registry: Arc::clone(&namespace_registry),
         state_engine: Arc::clone(&state_engine),
         auth_enabled,
+        admin_token: admin_token.clone(),
         max_batch_delete: flux_config.api.max_batch_delete,
     };
     let deletion_router = create_deletion_router(deletion_state);
 
-    // Create WebSocket API router (no auth — WS is read-only)
+    // Create entity rename/merge router (admin token or owner of both IDs)
+    let rename_router = create_rename_router(Arc::new(RenameAppState {
+        state_engine: Arc::clone(&state_engine),
+        event_publisher: event_publisher.clone(),
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+    }));
+
+    // Create stream mapping router (admin token)
+    let stream_mapping_router = create_stream_mapping_router(Arc::new(StreamMappingAppState {
+        state_engine: Arc::clone(&state_engine),
+        store: stream_mapping_store,
+        admin_token: admin_token.clone(),
+    }));
+
+    // Create sandbox replay router (admin token)
+    let replay_jobs = Arc::new(ReplayJobs::new(
+        nats_client.jetstream().clone(),
+        flux_config.nats.stream_name.clone(),
+        Arc::clone(&state_engine),
+        snapshot_dir.clone(),
+    ));
+    let replay_router = create_replay_router(Arc::new(ReplayAppState {
+        jobs: Arc::clone(&replay_jobs),
+        admin_token: admin_token.clone(),
+    }));
+
+    // Start scheduled compaction (background task, leader only)
+    let mut compactor = Compactor::new(
+        Arc::new(JetStreamSource::new(
+            nats_client.jetstream().clone(),
+            flux_config.nats.stream_name.clone(),
+        )),
+        Arc::clone(&state_engine),
+        flux_config.compaction.clone(),
+    )
+    .with_subject_scheme(flux_config.nats.subject_scheme)
+    .with_replay_jobs(replay_jobs);
+    if flux_config.snapshot.enabled {
+        compactor = compactor.with_snapshots(compaction_snapshots);
+    }
+    let compactor = Arc::new(compactor);
+    let task = Arc::clone(&compactor);
+    spawn_while_leader(leadership.clone(), "compaction", move || {
+        let compactor = Arc::clone(&task);
+        async move {
+            if let Err(e) = compactor.run_schedule_loop().await {
+                tracing::error!(error = %e, "Compaction scheduler failed");
+            }
+        }
+    });
+
+    // Create compaction router (admin token, leader only)
+    let compaction_router = create_compaction_router(Arc::new(CompactionAppState {
+        compactor,
+        leadership: leadership.clone(),
+        admin_token: admin_token.clone(),
+    }));
+
+    // Create WebSocket API router (token in first message when auth is enabled)
     let ws_state = Arc::new(WsAppState {
         state_engine: Arc::clone(&state_engine),
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+        max_value_bytes: flux_config.api.ws_max_value_bytes,
+        runtime_config: Arc::clone(&runtime_config),
+        connections: Arc::new(ConnectionTracker::new()),
     });
     let ws_router = create_ws_router(ws_state);
 
-    // Create Query API router
-    let query_state = Arc::new(QueryAppState { state_engine });
+    // Create Query API router (results scoped to the token's namespace in auth mode)
+    let query_state = Arc::new(QueryAppState {
+        state_engine: Arc::clone(&state_engine),
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+        response_cache: QueryCache::new().with_runtime_config(Arc::clone(&runtime_config)),
+        journal,
+    });
     let query_router = create_query_router(query_state);
 
+    // Create watch API router (one-shot watches, held in memory on this instance)
+    let watch_manager = Arc::new(
+        WatchManager::new()
+            .with_http_client(http_client.clone())
+            .with_allowed_webhook_hosts(flux_config.api.watch_webhook_allowed_hosts.clone()),
+    );
+    tokio::spawn(run_watches(
+        Arc::clone(&watch_manager),
+        Arc::clone(&state_engine),
+    ));
+    let watch_router = create_watch_router(Arc::new(WatchAppState {
+        watch_manager,
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+    }));
+
     // Create History API router
     let history_state = Arc::new(HistoryAppState {
         jetstream: nats_client.jetstream().clone(),
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled,
+        admin_token: admin_token.clone(),
+        archive: archive_store,
     });
     let history_router = create_history_router(history_state);
 
@@ -218,6 +962,7 @@ async fn main() -> Result<()> {
     let connector_router = create_connector_router(connector_state);
 
     // Create OAuth API router (requires credential store)
+    #[cfg(feature = "oauth")]
     let oauth_router = if let Some(ref store) = credential_store {
         // Create OAuth state manager
         let state_manager = StateManager::new(600); // 10 minutes expiry
@@ -235,12 +980,34 @@ async fn main() -> Result<()> {
 
         info!("OAuth callback base URL: {}", callback_base_url);
 
+        // Origins the UI may ask to be redirected back to after the callback
+        let allowed_return_origins = std::env::var("FLUX_OAUTH_ALLOWED_RETURN_ORIGINS")
+            .map(|raw| parse_allowed_origins(&raw))
+            .unwrap_or_default();
+        if !allowed_return_origins.is_empty() {
+            info!("OAuth return_to origins: {:?}", allowed_return_origins);
+        }
+
+        // Built-in providers, overridden and extended by an optional file
+        let providers = match std::env::var("FLUX_OAUTH_PROVIDERS_FILE") {
+            Ok(path) => {
+                let registry = ProviderRegistry::from_file(std::path::Path::new(&path))?;
+                info!("OAuth providers loaded from {}", path);
+                registry
+            }
+            Err(_) => ProviderRegistry::builtin(),
+        };
+
         let oauth_state = OAuthAppState {
             credential_store: Arc::clone(store),
             namespace_registry: Arc::clone(&namespace_registry),
             state_manager,
             auth_enabled,
             callback_base_url,
+            allowed_return_origins,
+            providers: Arc::new(providers),
+            admin_token: admin_token.clone(),
+            http_client: http_client.clone(),
         };
 
         create_oauth_router(oauth_state)
@@ -248,14 +1015,41 @@ async fn main() -> Result<()> {
         // OAuth disabled without credential store
         Router::new()
     };
+    #[cfg(not(feature = "oauth"))]
+    let oauth_router = Router::new();
 
     // Create Admin API router
     let admin_state = AdminAppState {
         runtime_config,
-        admin_token,
+        admin_token: admin_token.clone(),
+        tasks,
     };
     let admin_router = create_admin_router(admin_state);
 
+    // Create federation status router (rules and their lag)
+    let federation_router = create_federation_router(Arc::new(FederationAppState {
+        forwarder,
+        admin_token: admin_token.clone(),
+    }));
+
+    // Create promotion and snapshot download router (admin token, both modes)
+    let standby_router = create_standby_router(Arc::new(StandbyAppState {
+        mode: mode.clone(),
+        snapshot_dir,
+        admin_token,
+    }));
+
+    // Create readiness router (reports NATS TLS, server version, leadership and mode)
+    let health_router = create_health_router(Arc::new(HealthAppState {
+        nats: nats_client.status_handle(),
+        leadership,
+        mode: mode.clone(),
+        state_engine: Arc::clone(&state_engine),
+    }));
+
+    // OpenAPI spec (+ Swagger UI when enabled)
+    let openapi_router = create_openapi_router(flux_config.api.docs_enabled);
+
     // CORS — allow browsers (flux-universe.com explorer) to fetch from Flux
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -268,25 +1062,58 @@ async fn main() -> Result<()> {
         .allow_headers([
             axum::http::header::AUTHORIZATION,
             axum::http::header::CONTENT_TYPE,
-        ]);
+            axum::http::HeaderName::from_static("idempotency-key"),
+        ])
+        .expose_headers([axum::http::HeaderName::from_static("idempotent-replayed")]);
 
-    // Combine routers
-    let app = ingestion_router
+    // Combine routers; writes answer 503 while in standby, reads too unless served
+    let writes = ingestion_router
         .merge(namespace_router)
+        .merge(messages_router)
         .merge(deletion_router)
-        .merge(ws_router)
-        .merge(query_router)
-        .merge(history_router)
+        .merge(rename_router)
+        .merge(stream_mapping_router)
+        .merge(replay_router)
         .merge(connector_router)
         .merge(oauth_router)
         .merge(admin_router)
+        .merge(federation_router)
+        .merge(compaction_router);
+    // State reads can answer 503 until the startup replay has caught up
+    let block_reads_during_replay = std::env::var("FLUX_BLOCK_READS_DURING_REPLAY")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or(false);
+    let state_reads = ws_router.merge(query_router).merge(watch_router);
+    let state_reads = if block_reads_during_replay {
+        info!("Reads blocked until startup replay completes");
+        block_during_replay(state_reads, state_engine)
+    } else {
+        state_reads
+    };
+    let reads = state_reads.merge(history_router).merge(introspect_router);
+    let reads = if flux_config.standby.serve_reads {
+        reads
+    } else {
+        primary_only(reads, mode.clone())
+    };
+    let app = primary_only(writes, mode)
+        .merge(reads)
+        .merge(standby_router)
+        .merge(health_router)
+        .merge(openapi_router)
         .layer(cors);
+    let app = with_request_tracing(app);
 
     let addr = format!("0.0.0.0:{}", port);
     info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
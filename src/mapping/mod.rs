@@ -0,0 +1,215 @@
+//! Stream mappings: per-stream rules for reading the entity ID and properties
+//! out of payloads that don't use the `{"entity_id", "properties"}` envelope.
+//!
+//! Paths use a small JSONPath subset: `$`, `.field`, `['field']` and `[index]`.
+
+use crate::namespace::NamespaceRegistry;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+#[cfg(feature = "credentials")]
+mod store;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "credentials")]
+pub use store::StreamMappingStore;
+
+/// Stored mapping for one stream
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "entity_id": "$.device.serial",
+    "properties": "$.readings",
+    "namespace": "acme"
+}))]
+pub struct StreamMapping {
+    /// Path to the entity ID (a string or number)
+    pub entity_id: String,
+    /// Path to the object whose fields become properties
+    pub properties: String,
+    /// Prefix the entity ID with `<namespace>/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// Why a mapping could not be compiled or applied to a payload
+#[derive(Debug, PartialEq)]
+pub enum MappingError {
+    /// Path expression does not parse
+    InvalidPath { path: String, reason: String },
+    /// Namespace prefix is not a valid namespace name
+    InvalidNamespace { namespace: String, reason: String },
+    /// Entity ID path matched nothing
+    MissingEntityId(String),
+    /// Entity ID path matched something other than a non-empty string or a number
+    InvalidEntityId(String),
+    /// Properties path matched nothing
+    MissingProperties(String),
+    /// Properties path matched something other than an object
+    PropertiesNotObject(String),
+}
+
+impl std::fmt::Display for MappingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MappingError::InvalidPath { path, reason } => {
+                write!(f, "Invalid path '{}': {}", path, reason)
+            }
+            MappingError::InvalidNamespace { namespace, reason } => {
+                write!(f, "Invalid namespace '{}': {}", namespace, reason)
+            }
+            MappingError::MissingEntityId(path) => write!(f, "No entity ID at '{}'", path),
+            MappingError::InvalidEntityId(path) => write!(
+                f,
+                "Entity ID at '{}' must be a non-empty string or a number",
+                path
+            ),
+            MappingError::MissingProperties(path) => write!(f, "No properties at '{}'", path),
+            MappingError::PropertiesNotObject(path) => {
+                write!(f, "Properties at '{}' must be an object", path)
+            }
+        }
+    }
+}
+
+/// One step of a path
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parsed path expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    expr: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(expr: &str) -> Result<Self, MappingError> {
+        let invalid = |reason: &str| MappingError::InvalidPath {
+            path: expr.to_string(),
+            reason: reason.to_string(),
+        };
+        let mut rest = expr
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with '$'"))?;
+
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid("empty field name"));
+                }
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                let inner = &after[..end];
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                let segment = match quoted {
+                    Some(key) => Segment::Key(key.to_string()),
+                    None => Segment::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| invalid("brackets need an index or a quoted field"))?,
+                    ),
+                };
+                segments.push(segment);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(Self {
+            expr: expr.to_string(),
+            segments,
+        })
+    }
+
+    /// The value at this path, if any
+    pub fn eval<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match segment {
+                Segment::Key(key) => current.get(key.as_str()),
+                Segment::Index(index) => current.get(*index),
+            })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+}
+
+/// A [`StreamMapping`] with its paths parsed, ready to apply to payloads
+#[derive(Clone, Debug)]
+pub struct CompiledMapping {
+    entity_id: JsonPath,
+    properties: JsonPath,
+    namespace: Option<String>,
+}
+
+impl CompiledMapping {
+    pub fn compile(mapping: &StreamMapping) -> Result<Self, MappingError> {
+        if let Some(namespace) = &mapping.namespace {
+            NamespaceRegistry::validate_name(namespace).map_err(|e| {
+                MappingError::InvalidNamespace {
+                    namespace: namespace.clone(),
+                    reason: e.to_string(),
+                }
+            })?;
+        }
+        Ok(Self {
+            entity_id: JsonPath::parse(&mapping.entity_id)?,
+            properties: JsonPath::parse(&mapping.properties)?,
+            namespace: mapping.namespace.clone(),
+        })
+    }
+
+    /// Compile `mapping` and check that it extracts an entity from `example`
+    pub fn compile_with_example(
+        mapping: &StreamMapping,
+        example: &Value,
+    ) -> Result<Self, MappingError> {
+        let compiled = Self::compile(mapping)?;
+        compiled.extract(example)?;
+        Ok(compiled)
+    }
+
+    /// Entity ID (namespace-prefixed if configured) and properties of `payload`
+    pub fn extract<'a>(
+        &self,
+        payload: &'a Value,
+    ) -> Result<(String, &'a Map<String, Value>), MappingError> {
+        let id_path = self.entity_id.as_str();
+        let id = match self.entity_id.eval(payload) {
+            None | Some(Value::Null) => {
+                return Err(MappingError::MissingEntityId(id_path.to_string()))
+            }
+            Some(Value::String(s)) if !s.is_empty() => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            Some(_) => return Err(MappingError::InvalidEntityId(id_path.to_string())),
+        };
+        let entity_id = match &self.namespace {
+            Some(namespace) => format!("{}/{}", namespace, id),
+            None => id,
+        };
+
+        let props_path = self.properties.as_str();
+        let properties = match self.properties.eval(payload) {
+            None => return Err(MappingError::MissingProperties(props_path.to_string())),
+            Some(Value::Object(map)) => map,
+            Some(_) => return Err(MappingError::PropertiesNotObject(props_path.to_string())),
+        };
+
+        Ok((entity_id, properties))
+    }
+}
@@ -0,0 +1,79 @@
+//! Stream mapping persistence using SQLite.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use std::sync::Mutex;
+
+use super::StreamMapping;
+
+/// Persists stream mappings in SQLite, keyed by stream name.
+pub struct StreamMappingStore {
+    conn: Mutex<Connection>,
+}
+
+impl StreamMappingStore {
+    /// Opens (or creates) the SQLite database and ensures the table exists.
+    pub fn new(db_path: &str) -> Result<Self> {
+        let conn = Connection::open(db_path)
+            .with_context(|| format!("Failed to open stream mapping DB at {}", db_path))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS stream_mappings (
+                stream     TEXT PRIMARY KEY,
+                mapping    TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+        )
+        .context("Failed to create stream_mappings table")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Inserts or replaces the mapping for `stream`.
+    pub fn upsert(&self, stream: &str, mapping: &StreamMapping) -> Result<()> {
+        let json = serde_json::to_string(mapping)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO stream_mappings (stream, mapping, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(stream) DO UPDATE SET mapping = ?2, updated_at = ?3",
+            params![stream, json, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to save stream mapping")?;
+        Ok(())
+    }
+
+    /// Deletes the mapping for `stream`. Returns false if there was none.
+    pub fn delete(&self, stream: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn
+            .execute(
+                "DELETE FROM stream_mappings WHERE stream = ?1",
+                params![stream],
+            )
+            .context("Failed to delete stream mapping")?;
+        Ok(deleted > 0)
+    }
+
+    /// Returns all mappings ordered by stream name.
+    pub fn load_all(&self) -> Result<Vec<(String, StreamMapping)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT stream, mapping FROM stream_mappings ORDER BY stream ASC")
+            .context("Failed to prepare load_all query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("Failed to query stream mappings")?;
+
+        let mut mappings = Vec::new();
+        for row in rows {
+            let (stream, json) = row.context("Failed to read stream mapping row")?;
+            let mapping = serde_json::from_str(&json)
+                .with_context(|| format!("Malformed mapping for stream {}", stream))?;
+            mappings.push((stream, mapping));
+        }
+        Ok(mappings)
+    }
+}
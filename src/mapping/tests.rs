@@ -0,0 +1,118 @@
+use super::*;
+use serde_json::json;
+
+fn mapping(entity_id: &str, properties: &str, namespace: Option<&str>) -> StreamMapping {
+    StreamMapping {
+        entity_id: entity_id.to_string(),
+        properties: properties.to_string(),
+        namespace: namespace.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_path_parse_and_eval() {
+    let payload = json!({
+        "device": {"serial": "abc", "tags": ["x", "y"]},
+        "odd.key": {"v": 1}
+    });
+
+    let eval = |expr: &str| JsonPath::parse(expr).unwrap().eval(&payload).cloned();
+    assert_eq!(eval("$"), Some(payload.clone()));
+    assert_eq!(eval("$.device.serial"), Some(json!("abc")));
+    assert_eq!(eval("$.device.tags[1]"), Some(json!("y")));
+    assert_eq!(eval("$['odd.key'].v"), Some(json!(1)));
+    assert_eq!(eval("$[\"device\"]['serial']"), Some(json!("abc")));
+    assert_eq!(eval("$.device.missing"), None);
+    assert_eq!(eval("$.device.tags[5]"), None);
+    assert_eq!(eval("$.device.serial.deeper"), None);
+}
+
+#[test]
+fn test_path_parse_rejects_malformed() {
+    for expr in ["", "device.id", "$.", "$..a", "$[", "$[x]", "$.a[0", "$a"] {
+        assert!(
+            matches!(JsonPath::parse(expr), Err(MappingError::InvalidPath { .. })),
+            "{} should not parse",
+            expr
+        );
+    }
+}
+
+#[test]
+fn test_extract_with_namespace_and_numeric_id() {
+    let compiled = CompiledMapping::compile(&mapping("$.meta.id", "$.data", Some("acme"))).unwrap();
+    let payload = json!({"meta": {"id": 42}, "data": {"temp": 21.5}});
+
+    let (entity_id, properties) = compiled.extract(&payload).unwrap();
+    assert_eq!(entity_id, "acme/42");
+    assert_eq!(properties.get("temp"), Some(&json!(21.5)));
+}
+
+#[test]
+fn test_extract_errors() {
+    let compiled = CompiledMapping::compile(&mapping("$.id", "$.data", None)).unwrap();
+
+    assert_eq!(
+        compiled.extract(&json!({"data": {}})),
+        Err(MappingError::MissingEntityId("$.id".to_string()))
+    );
+    assert_eq!(
+        compiled.extract(&json!({"id": "", "data": {}})),
+        Err(MappingError::InvalidEntityId("$.id".to_string()))
+    );
+    assert_eq!(
+        compiled.extract(&json!({"id": {"nested": 1}, "data": {}})),
+        Err(MappingError::InvalidEntityId("$.id".to_string()))
+    );
+    assert_eq!(
+        compiled.extract(&json!({"id": "a"})),
+        Err(MappingError::MissingProperties("$.data".to_string()))
+    );
+    assert_eq!(
+        compiled.extract(&json!({"id": "a", "data": [1, 2]})),
+        Err(MappingError::PropertiesNotObject("$.data".to_string()))
+    );
+}
+
+#[test]
+fn test_compile_with_example() {
+    let good = mapping("$.serial", "$.readings", None);
+    assert!(CompiledMapping::compile_with_example(
+        &good,
+        &json!({"serial": "s1", "readings": {"v": 1}})
+    )
+    .is_ok());
+    // Paths parse but miss the example
+    assert_eq!(
+        CompiledMapping::compile_with_example(&good, &json!({"readings": {}})).unwrap_err(),
+        MappingError::MissingEntityId("$.serial".to_string())
+    );
+    assert!(matches!(
+        CompiledMapping::compile(&mapping("$.serial", "$.readings", Some("Bad NS"))),
+        Err(MappingError::InvalidNamespace { .. })
+    ));
+}
+
+#[test]
+#[cfg(feature = "credentials")]
+fn test_store_round_trip() {
+    let store = StreamMappingStore::new(":memory:").unwrap();
+    let first = mapping("$.id", "$.data", None);
+    store.upsert("vendor.telemetry", &first).unwrap();
+    let updated = mapping("$.serial", "$", Some("acme"));
+    store.upsert("vendor.telemetry", &updated).unwrap();
+    store.upsert("other", &first).unwrap();
+
+    let all = store.load_all().unwrap();
+    assert_eq!(
+        all,
+        vec![
+            ("other".to_string(), first),
+            ("vendor.telemetry".to_string(), updated)
+        ]
+    );
+
+    assert!(store.delete("other").unwrap());
+    assert!(!store.delete("other").unwrap());
+    assert_eq!(store.load_all().unwrap().len(), 1);
+}
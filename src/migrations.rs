@@ -0,0 +1,216 @@
+//! Versioned SQLite schema migrations.
+//!
+//! Each store lists its schema as an ordered slice of [`Migration`]s. The
+//! number applied is recorded per store in a `schema_version` table, so stores
+//! sharing a database file don't interfere. Opening a store applies the
+//! pending steps, forward only, inside one transaction.
+//!
+//! Databases created before versioning have no `schema_version` row and start
+//! at version 0. Their tables were created with `IF NOT EXISTS` and may already
+//! have columns added by hand, so steps that run against them must tolerate
+//! that ([`Migration::AddColumn`] does).
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+
+/// One step of a store's schema history. Never edit or reorder a released step;
+/// append a new one instead.
+pub enum Migration {
+    /// Statements run as one batch.
+    Sql(&'static str),
+    /// `ALTER TABLE .. ADD COLUMN`, skipped if the column already exists.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+impl Migration {
+    fn apply(&self, conn: &Connection) -> Result<()> {
+        match self {
+            Migration::Sql(sql) => conn.execute_batch(sql)?,
+            Migration::AddColumn {
+                table,
+                column,
+                definition,
+            } => {
+                if !has_column(conn, table, column)? {
+                    conn.execute_batch(&format!(
+                        "ALTER TABLE {} ADD COLUMN {} {};",
+                        table, column, definition
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Brings `store`'s schema up to date and returns its version.
+///
+/// Fails without touching the database if it was written by a newer build
+/// (its version is past the end of `migrations`). A failing step rolls back
+/// every step applied by this call.
+pub fn migrate(conn: &mut Connection, store: &str, migrations: &[Migration]) -> Result<u32> {
+    let latest = migrations.len() as u32;
+
+    // IMMEDIATE: a second process opening the same file waits instead of
+    // applying the same steps concurrently
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Failed to start migration transaction")?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            store   TEXT PRIMARY KEY,
+            version INTEGER NOT NULL
+        );",
+    )
+    .context("Failed to create schema_version table")?;
+
+    let current = read_version(&tx, store)?;
+    if current > latest {
+        anyhow::bail!(
+            "{} schema is at version {}, but this build only knows up to version {}; \
+             refusing to open a database written by a newer Flux",
+            store,
+            current,
+            latest
+        );
+    }
+    if current == latest {
+        return Ok(current);
+    }
+
+    for (index, migration) in migrations.iter().enumerate().skip(current as usize) {
+        migration
+            .apply(&tx)
+            .with_context(|| format!("{} migration to version {} failed", store, index + 1))?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO schema_version (store, version) VALUES (?1, ?2)",
+        params![store, latest],
+    )
+    .context("Failed to record schema version")?;
+    tx.commit().context("Failed to commit migrations")?;
+
+    tracing::info!(
+        store,
+        from = current,
+        to = latest,
+        "Applied schema migrations"
+    );
+    Ok(latest)
+}
+
+/// Returns the recorded schema version of `store` (0 if never migrated).
+pub fn schema_version(conn: &Connection, store: &str) -> Result<u32> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to look up schema_version table")?;
+    if !exists {
+        return Ok(0);
+    }
+    read_version(conn, store)
+}
+
+fn read_version(conn: &Connection, store: &str) -> Result<u32> {
+    let version: Option<u32> = conn
+        .query_row(
+            "SELECT version FROM schema_version WHERE store = ?1",
+            params![store],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to read schema version")?;
+    Ok(version.unwrap_or(0))
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[Migration] = &[
+        Migration::Sql("CREATE TABLE IF NOT EXISTS items (id TEXT PRIMARY KEY);"),
+        Migration::AddColumn {
+            table: "items",
+            column: "label",
+            definition: "TEXT NOT NULL DEFAULT ''",
+        },
+    ];
+
+    #[test]
+    fn test_fresh_database_reaches_latest() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn, "items").unwrap(), 0);
+
+        assert_eq!(migrate(&mut conn, "items", STEPS).unwrap(), 2);
+        assert_eq!(schema_version(&conn, "items").unwrap(), 2);
+        assert!(has_column(&conn, "items", "label").unwrap());
+
+        // Reopening is a no-op
+        assert_eq!(migrate(&mut conn, "items", STEPS).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_unversioned_column_added_by_hand_is_skipped() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id TEXT PRIMARY KEY, label TEXT);
+             INSERT INTO items VALUES ('a', 'kept');",
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&mut conn, "items", STEPS).unwrap(), 2);
+        let label: String = conn
+            .query_row("SELECT label FROM items WHERE id = 'a'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(label, "kept");
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, "items", STEPS).unwrap();
+
+        let err = migrate(&mut conn, "items", &STEPS[..1]).unwrap_err();
+        assert!(err.to_string().contains("newer Flux"), "{}", err);
+        assert_eq!(schema_version(&conn, "items").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_failed_step_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let steps = [
+            Migration::Sql("CREATE TABLE items (id TEXT PRIMARY KEY);"),
+            Migration::Sql("ALTER TABLE missing ADD COLUMN x TEXT;"),
+        ];
+
+        assert!(migrate(&mut conn, "items", &steps).is_err());
+        assert_eq!(schema_version(&conn, "items").unwrap(), 0);
+        assert!(conn.prepare("SELECT id FROM items").is_err());
+    }
+
+    #[test]
+    fn test_stores_sharing_a_file_are_versioned_separately() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, "items", STEPS).unwrap();
+        migrate(&mut conn, "other", &STEPS[..1]).unwrap();
+
+        assert_eq!(schema_version(&conn, "items").unwrap(), 2);
+        assert_eq!(schema_version(&conn, "other").unwrap(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+//! Access grants: tokens limited to the entities under one path prefix of a
+//! namespace, so an owner can share a slice of it without handing over the
+//! namespace token.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a grant's token may do under its prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantScope {
+    /// Query and subscribe
+    Read,
+    /// Read, plus publish and delete
+    Write,
+}
+
+impl GrantScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GrantScope::Read => "read",
+            GrantScope::Write => "write",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(GrantScope::Read),
+            "write" => Some(GrantScope::Write),
+            _ => None,
+        }
+    }
+}
+
+/// A token bound to a namespace, a prefix inside it and a scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessGrant {
+    /// System-generated ID (grant_{random_8chars})
+    pub id: String,
+    /// Name of the namespace the grant is in
+    pub namespace: String,
+    /// Normalized path inside the namespace, ending in `/` (`public/`)
+    pub prefix: String,
+    pub scope: GrantScope,
+    /// Bearer token (UUID v4)
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    /// None = never expires
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl AccessGrant {
+    /// Entity ID prefix the grant covers (`alice/public/`)
+    pub fn entity_prefix(&self) -> String {
+        format!("{}/{}", self.namespace, self.prefix)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// True if `entity_id` is under the grant's prefix
+    pub fn covers(&self, entity_id: &str) -> bool {
+        prefix_covers(&self.entity_prefix(), entity_id)
+    }
+
+    /// True if the grant's token may publish to or delete `entity_id`
+    pub fn allows_write(&self, entity_id: &str) -> bool {
+        self.scope == GrantScope::Write && self.covers(entity_id)
+    }
+}
+
+/// `path` with empty and `.` segments dropped and `..` applied, or None if
+/// `..` climbs above its start
+///
+/// `alice/public/../secret` is `alice/secret`.
+pub fn normalize_path(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// A grant prefix as stored: normalized, ending in `/`
+///
+/// `public`, `/public/` and `public/*` all become `public/`.
+pub fn normalize_prefix(prefix: &str) -> Result<String, GrantError> {
+    let prefix = prefix.strip_suffix('*').unwrap_or(prefix);
+    match normalize_path(prefix) {
+        None => Err(GrantError::InvalidPrefix(format!(
+            "Prefix '{}' points outside the namespace",
+            prefix
+        ))),
+        Some(path) if path.is_empty() => Err(GrantError::InvalidPrefix(
+            "Prefix must name a path inside the namespace".to_string(),
+        )),
+        Some(path) if path.contains('*') => Err(GrantError::InvalidPrefix(format!(
+            "Prefix '{}' may only end in '*'",
+            prefix
+        ))),
+        Some(path) => Ok(format!("{}/", path)),
+    }
+}
+
+/// True if `entity_id` is under `prefix` (normalized, ending in `/`) both as
+/// written and once normalized, so `..` segments can't step out of it
+pub fn prefix_covers(prefix: &str, entity_id: &str) -> bool {
+    entity_id.starts_with(prefix)
+        && normalize_path(entity_id).is_some_and(|normalized| normalized.starts_with(prefix))
+}
+
+/// Grant creation errors
+#[derive(Debug, PartialEq)]
+pub enum GrantError {
+    NamespaceNotFound,
+    InvalidPrefix(String),
+    StoreFailed,
+}
+
+impl std::fmt::Display for GrantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrantError::NamespaceNotFound => write!(f, "Namespace not found"),
+            GrantError::InvalidPrefix(detail) => write!(f, "{}", detail),
+            GrantError::StoreFailed => write!(f, "Failed to persist grant"),
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use chrono::{DateTime, Utc};
+use crate::state::NamespaceQuota;
+use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
 use rand::Rng;
 use std::sync::Arc;
 use uuid::Uuid;
 
+pub mod grants;
+#[cfg(feature = "credentials")]
 pub mod store;
+pub use grants::{AccessGrant, GrantError, GrantScope};
+#[cfg(feature = "credentials")]
 pub use store::NamespaceStore;
 
 #[cfg(test)]
@@ -23,6 +28,8 @@ pub struct Namespace {
     pub created_at: DateTime<Utc>,
     /// Number of entities in this namespace (stats)
     pub entity_count: u64,
+    /// Limits requested by an admin; the state engine enforces them
+    pub quota: NamespaceQuota,
 }
 
 /// Namespace registry manages registration and lookups
@@ -33,7 +40,10 @@ pub struct NamespaceRegistry {
     names: Arc<DashMap<String, String>>,
     /// Secondary index: token -> namespace_id (for auth)
     tokens: Arc<DashMap<String, String>>,
+    /// Access grants by token
+    grants: Arc<DashMap<String, AccessGrant>>,
     /// Optional SQLite-backed persistence
+    #[cfg(feature = "credentials")]
     store: Option<NamespaceStore>,
 }
 
@@ -44,16 +54,20 @@ impl NamespaceRegistry {
             namespaces: Arc::new(DashMap::new()),
             names: Arc::new(DashMap::new()),
             tokens: Arc::new(DashMap::new()),
+            grants: Arc::new(DashMap::new()),
+            #[cfg(feature = "credentials")]
             store: None,
         }
     }
 
     /// Create registry backed by a persistent store, loading existing namespaces.
+    #[cfg(feature = "credentials")]
     pub fn new_persistent(store: NamespaceStore) -> Self {
         let registry = Self {
             namespaces: Arc::new(DashMap::new()),
             names: Arc::new(DashMap::new()),
             tokens: Arc::new(DashMap::new()),
+            grants: Arc::new(DashMap::new()),
             store: Some(store),
         };
         if let Some(ref s) = registry.store {
@@ -69,6 +83,16 @@ impl NamespaceRegistry {
                     tracing::warn!(error = %e, "Failed to load namespaces from store");
                 }
             }
+            match s.load_grants() {
+                Ok(grants) => {
+                    for grant in grants {
+                        registry.grants.insert(grant.token.clone(), grant);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to load access grants from store");
+                }
+            }
         }
         registry
     }
@@ -87,7 +111,7 @@ impl NamespaceRegistry {
         }
 
         // Generate namespace ID and token
-        let namespace_id = generate_namespace_id();
+        let namespace_id = generate_id("ns");
         let token = Uuid::new_v4().to_string();
         let now = Utc::now();
 
@@ -98,11 +122,15 @@ impl NamespaceRegistry {
             token: token.clone(),
             created_at: now,
             entity_count: 0,
+            quota: NamespaceQuota::default(),
         };
 
         // Persist first (fail fast if DB write fails)
+        #[cfg(feature = "credentials")]
         if let Some(ref store) = self.store {
-            store.insert(&namespace).map_err(|_| RegistrationError::StoreFailed)?;
+            store
+                .insert(&namespace)
+                .map_err(|_| RegistrationError::StoreFailed)?;
         }
 
         // Insert into all indices
@@ -190,8 +218,10 @@ impl NamespaceRegistry {
         if let Some((_, ns)) = self.namespaces.remove(&namespace_id) {
             self.tokens.remove(&ns.token);
         }
+        self.grants.retain(|_, grant| grant.namespace != name);
 
         // Persist deletion (best-effort)
+        #[cfg(feature = "credentials")]
         if let Some(ref store) = self.store {
             if let Err(e) = store.delete(name) {
                 tracing::warn!(error = %e, name = %name, "Failed to delete namespace from store");
@@ -201,6 +231,103 @@ impl NamespaceRegistry {
         true
     }
 
+    /// Record a namespace's quota.
+    ///
+    /// Returns the updated namespace, or None if it doesn't exist.
+    pub fn set_quota(&self, name: &str, quota: NamespaceQuota) -> Option<Namespace> {
+        let namespace_id = self.names.get(name)?.value().clone();
+        let mut namespace = self.namespaces.get_mut(&namespace_id)?;
+        namespace.quota = quota;
+
+        // Persist (best-effort; the quota event is what takes effect)
+        #[cfg(feature = "credentials")]
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.set_quota(name, &quota) {
+                tracing::warn!(error = %e, name = %name, "Failed to persist namespace quota");
+            }
+        }
+
+        Some(namespace.clone())
+    }
+
+    /// Issue a token for `scope` access to the entities under `prefix` in
+    /// `namespace`, valid for `expires_in` (None = until revoked)
+    pub fn create_grant(
+        &self,
+        namespace: &str,
+        prefix: &str,
+        scope: GrantScope,
+        expires_in: Option<Duration>,
+    ) -> Result<AccessGrant, GrantError> {
+        if !self.names.contains_key(namespace) {
+            return Err(GrantError::NamespaceNotFound);
+        }
+        let prefix = grants::normalize_prefix(prefix)?;
+        let now = Utc::now();
+        let grant = AccessGrant {
+            id: generate_id("grant"),
+            namespace: namespace.to_string(),
+            prefix,
+            scope,
+            token: Uuid::new_v4().to_string(),
+            created_at: now,
+            expires_at: expires_in.map(|expires_in| now + expires_in),
+        };
+
+        #[cfg(feature = "credentials")]
+        if let Some(ref store) = self.store {
+            store
+                .insert_grant(&grant)
+                .map_err(|_| GrantError::StoreFailed)?;
+        }
+        self.grants.insert(grant.token.clone(), grant.clone());
+        Ok(grant)
+    }
+
+    /// Look up an unexpired grant by token
+    pub fn lookup_grant(&self, token: &str) -> Option<AccessGrant> {
+        self.grants
+            .get(token)
+            .filter(|grant| !grant.is_expired(Utc::now()))
+            .map(|grant| grant.clone())
+    }
+
+    /// Grants of `namespace`, expired ones included, oldest first
+    pub fn list_grants(&self, namespace: &str) -> Vec<AccessGrant> {
+        let mut grants: Vec<AccessGrant> = self
+            .grants
+            .iter()
+            .filter(|grant| grant.namespace == namespace)
+            .map(|grant| grant.clone())
+            .collect();
+        grants.sort_by_key(|grant| grant.created_at);
+        grants
+    }
+
+    /// Revoke grant `grant_id` of `namespace`.
+    ///
+    /// Returns true if it existed, false if not found.
+    pub fn revoke_grant(&self, namespace: &str, grant_id: &str) -> bool {
+        let token = self
+            .grants
+            .iter()
+            .find(|grant| grant.namespace == namespace && grant.id == grant_id)
+            .map(|grant| grant.token.clone());
+        let Some(token) = token else {
+            return false;
+        };
+        self.grants.remove(&token);
+
+        // Persist revocation (best-effort)
+        #[cfg(feature = "credentials")]
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.delete_grant(grant_id) {
+                tracing::warn!(error = %e, grant_id = %grant_id, "Failed to delete grant from store");
+            }
+        }
+        true
+    }
+
     /// Get count of registered namespaces
     pub fn count(&self) -> usize {
         self.namespaces.len()
@@ -213,8 +340,8 @@ impl Default for NamespaceRegistry {
     }
 }
 
-/// Generate namespace ID: ns_{random_8chars}
-fn generate_namespace_id() -> String {
+/// Generate an ID: {kind}_{random_8chars}
+fn generate_id(kind: &str) -> String {
     let mut rng = rand::thread_rng();
     let random: String = (0..8)
         .map(|_| {
@@ -226,7 +353,7 @@ fn generate_namespace_id() -> String {
             }
         })
         .collect();
-    format!("ns_{}", random)
+    format!("{}_{}", kind, random)
 }
 
 /// Registration errors
@@ -251,6 +378,16 @@ pub enum ValidationError {
     InvalidCharacters(String),
 }
 
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::TooShort => write!(f, "minimum 3 characters"),
+            ValidationError::TooLong => write!(f, "maximum 32 characters"),
+            ValidationError::InvalidCharacters(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
 /// Authorization errors
 #[derive(Debug, PartialEq)]
 pub enum AuthError {
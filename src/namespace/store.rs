@@ -1,13 +1,56 @@
 //! Namespace persistence using SQLite.
 //!
-//! Stores registered namespaces so they survive Flux restarts.
+//! Stores registered namespaces and their access grants so they survive
+//! Flux restarts.
 //! `entity_count` is runtime-derived and not persisted.
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::sync::Mutex;
 
-use super::Namespace;
+use super::{AccessGrant, GrantScope, Namespace};
+use crate::migrations::{self, Migration};
+use crate::state::NamespaceQuota;
+
+/// Schema history of the namespace store. Append only.
+const MIGRATIONS: &[Migration] = &[
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS namespaces (
+            id         TEXT PRIMARY KEY,
+            name       TEXT UNIQUE NOT NULL,
+            token      TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );",
+    ),
+    // load_all() returns namespaces in registration order
+    Migration::Sql(
+        "CREATE INDEX IF NOT EXISTS idx_namespaces_created_at ON namespaces(created_at);",
+    ),
+    // Quota limits; NULL = unlimited
+    Migration::AddColumn {
+        table: "namespaces",
+        column: "max_entities",
+        definition: "INTEGER",
+    },
+    Migration::AddColumn {
+        table: "namespaces",
+        column: "max_bytes",
+        definition: "INTEGER",
+    },
+    // Access grants; expires_at NULL = never
+    Migration::Sql(
+        "CREATE TABLE IF NOT EXISTS namespace_grants (
+            id         TEXT PRIMARY KEY,
+            namespace  TEXT NOT NULL,
+            prefix     TEXT NOT NULL,
+            scope      TEXT NOT NULL,
+            token      TEXT UNIQUE NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_namespace_grants_namespace ON namespace_grants(namespace);",
+    ),
+];
 
 /// Persists namespace records in SQLite.
 pub struct NamespaceStore {
@@ -15,29 +58,15 @@ pub struct NamespaceStore {
 }
 
 impl NamespaceStore {
-    /// Opens (or creates) the SQLite database and ensures the table exists.
+    /// Opens (or creates) the SQLite database and migrates it to the current schema.
     pub fn new(db_path: &str) -> Result<Self> {
-        let conn = Connection::open(db_path)
+        let mut conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open namespace DB at {}", db_path))?;
-        let store = Self {
+        migrations::migrate(&mut conn, "namespaces", MIGRATIONS)
+            .context("Failed to migrate namespace DB")?;
+        Ok(Self {
             conn: Mutex::new(conn),
-        };
-        store.create_table()?;
-        Ok(store)
-    }
-
-    fn create_table(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS namespaces (
-                id         TEXT PRIMARY KEY,
-                name       TEXT UNIQUE NOT NULL,
-                token      TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            );",
-        )
-        .context("Failed to create namespaces table")?;
-        Ok(())
+        })
     }
 
     /// Inserts a new namespace. Fails if id or name already exists.
@@ -51,20 +80,131 @@ impl NamespaceStore {
         Ok(())
     }
 
-    /// Deletes a namespace by name. Returns Ok(()) whether or not the row exists.
+    /// Deletes a namespace and its grants by name. Returns Ok(()) whether or
+    /// not the row exists.
     pub fn delete(&self, name: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM namespaces WHERE name = ?1", params![name])
             .context("Failed to delete namespace")?;
+        conn.execute(
+            "DELETE FROM namespace_grants WHERE namespace = ?1",
+            params![name],
+        )
+        .context("Failed to delete namespace grants")?;
+        Ok(())
+    }
+
+    /// Inserts a new access grant.
+    pub fn insert_grant(&self, grant: &AccessGrant) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO namespace_grants (id, namespace, prefix, scope, token, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                grant.id,
+                grant.namespace,
+                grant.prefix,
+                grant.scope.as_str(),
+                grant.token,
+                grant.created_at.to_rfc3339(),
+                grant.expires_at.map(|t| t.to_rfc3339())
+            ],
+        )
+        .context("Failed to insert grant")?;
+        Ok(())
+    }
+
+    /// Deletes an access grant by ID. Returns Ok(()) whether or not the row exists.
+    pub fn delete_grant(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM namespace_grants WHERE id = ?1", params![id])
+            .context("Failed to delete grant")?;
+        Ok(())
+    }
+
+    /// Returns all persisted access grants ordered by creation time.
+    pub fn load_grants(&self) -> Result<Vec<AccessGrant>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, namespace, prefix, scope, token, created_at, expires_at
+                 FROM namespace_grants ORDER BY created_at ASC",
+            )
+            .context("Failed to prepare load_grants query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })
+            .context("Failed to query grants")?;
+
+        let mut grants = Vec::new();
+        for row in rows {
+            let (id, namespace, prefix, scope, token, created_at, expires_at) =
+                row.context("Failed to read grant row")?;
+            let scope = GrantScope::parse(&scope)
+                .with_context(|| format!("Unknown scope '{}' for grant {}", scope, id))?;
+            let created_at = created_at
+                .parse()
+                .with_context(|| format!("Failed to parse created_at for grant {}", id))?;
+            let expires_at = expires_at
+                .map(|t| t.parse())
+                .transpose()
+                .with_context(|| format!("Failed to parse expires_at for grant {}", id))?;
+            grants.push(AccessGrant {
+                id,
+                namespace,
+                prefix,
+                scope,
+                token,
+                created_at,
+                expires_at,
+            });
+        }
+        Ok(grants)
+    }
+
+    /// Updates a namespace's quota. Returns Ok(()) whether or not the row exists.
+    pub fn set_quota(&self, name: &str, quota: &NamespaceQuota) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE namespaces SET max_entities = ?1, max_bytes = ?2 WHERE name = ?3",
+            params![
+                quota.max_entities.map(|n| n as i64),
+                quota.max_bytes.map(|n| n as i64),
+                name
+            ],
+        )
+        .context("Failed to update namespace quota")?;
         Ok(())
     }
 
+    /// Returns the name of the namespace `token` belongs to, if any.
+    pub fn name_for_token(&self, token: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT name FROM namespaces WHERE token = ?1",
+            params![token],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to look up namespace token")
+    }
+
     /// Returns all persisted namespaces ordered by creation time.
     pub fn load_all(&self) -> Result<Vec<Namespace>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, token, created_at FROM namespaces ORDER BY created_at ASC",
+                "SELECT id, name, token, created_at, max_entities, max_bytes
+                 FROM namespaces ORDER BY created_at ASC",
             )
             .context("Failed to prepare load_all query")?;
         let rows = stmt
@@ -73,13 +213,18 @@ impl NamespaceStore {
                 let name: String = row.get(1)?;
                 let token: String = row.get(2)?;
                 let created_at_str: String = row.get(3)?;
-                Ok((id, name, token, created_at_str))
+                let quota = NamespaceQuota {
+                    max_entities: row.get::<_, Option<i64>>(4)?.map(|n| n as u64),
+                    max_bytes: row.get::<_, Option<i64>>(5)?.map(|n| n as u64),
+                };
+                Ok((id, name, token, created_at_str, quota))
             })
             .context("Failed to query namespaces")?;
 
         let mut namespaces = Vec::new();
         for row in rows {
-            let (id, name, token, created_at_str) = row.context("Failed to read namespace row")?;
+            let (id, name, token, created_at_str, quota) =
+                row.context("Failed to read namespace row")?;
             let created_at = created_at_str
                 .parse()
                 .with_context(|| format!("Failed to parse created_at for namespace {}", id))?;
@@ -89,6 +234,7 @@ impl NamespaceStore {
                 token,
                 created_at,
                 entity_count: 0,
+                quota,
             });
         }
         Ok(namespaces)
@@ -111,6 +257,7 @@ mod tests {
             token: "tok-abc123".to_string(),
             created_at: Utc::now(),
             entity_count: 0,
+            quota: NamespaceQuota::default(),
         }
     }
 
@@ -157,6 +304,20 @@ mod tests {
         assert!(names.contains(&"gamma"));
     }
 
+    #[test]
+    fn test_name_for_token() {
+        let store = in_memory_store();
+        store
+            .insert(&sample_namespace("ns_aaaaaaaa", "myspace"))
+            .unwrap();
+
+        assert_eq!(
+            store.name_for_token("tok-abc123").unwrap().as_deref(),
+            Some("myspace")
+        );
+        assert!(store.name_for_token("tok-other").unwrap().is_none());
+    }
+
     #[test]
     fn test_duplicate_name_fails() {
         let store = in_memory_store();
@@ -196,4 +357,99 @@ mod tests {
         let result = store.insert(&sample_namespace("ns_aaaaaaaa", "beta"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_set_quota_round_trip() {
+        let store = in_memory_store();
+        store
+            .insert(&sample_namespace("ns_aaaaaaaa", "myspace"))
+            .unwrap();
+        let quota = NamespaceQuota {
+            max_entities: Some(100),
+            max_bytes: None,
+        };
+
+        store.set_quota("myspace", &quota).unwrap();
+        assert_eq!(store.load_all().unwrap()[0].quota, quota);
+
+        store
+            .set_quota("myspace", &NamespaceQuota::default())
+            .unwrap();
+        assert!(store.load_all().unwrap()[0].quota.is_unlimited());
+    }
+
+    #[test]
+    fn test_grants_round_trip() {
+        let store = in_memory_store();
+        store
+            .insert(&sample_namespace("ns_aaaaaaaa", "myspace"))
+            .unwrap();
+        let grant = AccessGrant {
+            id: "grant_aaaaaaaa".to_string(),
+            namespace: "myspace".to_string(),
+            prefix: "public/".to_string(),
+            scope: GrantScope::Write,
+            token: "tok-grant".to_string(),
+            created_at: Utc::now(),
+            expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
+        };
+        store.insert_grant(&grant).unwrap();
+        store
+            .insert_grant(&AccessGrant {
+                id: "grant_bbbbbbbb".to_string(),
+                token: "tok-grant-2".to_string(),
+                scope: GrantScope::Read,
+                expires_at: None,
+                ..grant.clone()
+            })
+            .unwrap();
+
+        let loaded = store.load_grants().unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0], grant);
+        assert_eq!(loaded[1].scope, GrantScope::Read);
+        assert_eq!(loaded[1].expires_at, None);
+
+        store.delete_grant("grant_aaaaaaaa").unwrap();
+        assert_eq!(store.load_grants().unwrap().len(), 1);
+
+        // Deleting the namespace drops its grants
+        store.delete("myspace").unwrap();
+        assert!(store.load_grants().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrates_unversioned_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("namespaces.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Table as created before versioning
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE namespaces (
+                id TEXT PRIMARY KEY, name TEXT UNIQUE NOT NULL,
+                token TEXT NOT NULL, created_at TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO namespaces VALUES ('ns_aaaaaaaa', 'alpha', 'tok-1', ?1)",
+            params![Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = NamespaceStore::new(db_path).expect("migrate failed");
+        let loaded = store.load_all().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].token, "tok-1");
+        assert!(loaded[0].quota.is_unlimited());
+
+        let conn = store.conn.lock().unwrap();
+        assert_eq!(
+            migrations::schema_version(&conn, "namespaces").unwrap(),
+            MIGRATIONS.len() as u32
+        );
+    }
 }
@@ -7,7 +7,8 @@ fn test_validate_name_valid() {
     assert!(NamespaceRegistry::validate_name("arc").is_ok());
     assert!(NamespaceRegistry::validate_name("sensor-team").is_ok());
     assert!(NamespaceRegistry::validate_name("test_123").is_ok());
-    assert!(NamespaceRegistry::validate_name("a1b2c3d4e5f6g7h8i9j0k1l2m3n4o5p6").is_ok()); // 32 chars
+    assert!(NamespaceRegistry::validate_name("a1b2c3d4e5f6g7h8i9j0k1l2m3n4o5p6").is_ok());
+    // 32 chars
 }
 
 #[test]
@@ -53,7 +54,9 @@ fn test_register_success() {
     let registry = NamespaceRegistry::new();
 
     // Register namespace
-    let ns = registry.register("matt").expect("Registration should succeed");
+    let ns = registry
+        .register("matt")
+        .expect("Registration should succeed");
 
     // Verify fields
     assert_eq!(ns.name, "matt");
@@ -71,7 +74,9 @@ fn test_register_duplicate_name() {
     let registry = NamespaceRegistry::new();
 
     // First registration succeeds
-    registry.register("matt").expect("First registration should succeed");
+    registry
+        .register("matt")
+        .expect("First registration should succeed");
 
     // Second registration with same name fails
     let result = registry.register("matt");
@@ -93,7 +98,9 @@ fn test_register_invalid_name() {
     let result = registry.register("Matt");
     assert!(matches!(
         result,
-        Err(RegistrationError::InvalidName(ValidationError::InvalidCharacters(_)))
+        Err(RegistrationError::InvalidName(
+            ValidationError::InvalidCharacters(_)
+        ))
     ));
 }
 
@@ -102,7 +109,9 @@ fn test_lookup_by_name() {
     let registry = NamespaceRegistry::new();
 
     // Register namespace
-    let ns = registry.register("matt").expect("Registration should succeed");
+    let ns = registry
+        .register("matt")
+        .expect("Registration should succeed");
 
     // Look up by name
     let found = registry
@@ -121,7 +130,9 @@ fn test_lookup_by_token() {
     let registry = NamespaceRegistry::new();
 
     // Register namespace
-    let ns = registry.register("matt").expect("Registration should succeed");
+    let ns = registry
+        .register("matt")
+        .expect("Registration should succeed");
 
     // Look up by token
     let found = registry
@@ -139,7 +150,9 @@ fn test_validate_token_success() {
     let registry = NamespaceRegistry::new();
 
     // Register namespace
-    let ns = registry.register("matt").expect("Registration should succeed");
+    let ns = registry
+        .register("matt")
+        .expect("Registration should succeed");
 
     // Validate correct token
     let result = registry.validate_token(&ns.token, "matt");
@@ -151,7 +164,9 @@ fn test_validate_token_wrong_token() {
     let registry = NamespaceRegistry::new();
 
     // Register namespace
-    registry.register("matt").expect("Registration should succeed");
+    registry
+        .register("matt")
+        .expect("Registration should succeed");
 
     // Try with wrong token
     let result = registry.validate_token("wrong-token", "matt");
@@ -172,8 +187,12 @@ fn test_validate_token_cross_namespace() {
     let registry = NamespaceRegistry::new();
 
     // Register two namespaces
-    let ns1 = registry.register("matt").expect("Registration should succeed");
-    registry.register("arc").expect("Registration should succeed");
+    let ns1 = registry
+        .register("matt")
+        .expect("Registration should succeed");
+    registry
+        .register("arc")
+        .expect("Registration should succeed");
 
     // Try to use matt's token for arc namespace (should fail)
     let result = registry.validate_token(&ns1.token, "arc");
@@ -186,10 +205,14 @@ fn test_count() {
 
     assert_eq!(registry.count(), 0);
 
-    registry.register("matt").expect("Registration should succeed");
+    registry
+        .register("matt")
+        .expect("Registration should succeed");
     assert_eq!(registry.count(), 1);
 
-    registry.register("arc").expect("Registration should succeed");
+    registry
+        .register("arc")
+        .expect("Registration should succeed");
     assert_eq!(registry.count(), 2);
 }
 
@@ -199,7 +222,9 @@ fn test_namespace_id_format() {
 
     // Register multiple namespaces and check ID format
     for name in &["matt", "arc", "test"] {
-        let ns = registry.register(name).expect("Registration should succeed");
+        let ns = registry
+            .register(name)
+            .expect("Registration should succeed");
         assert!(ns.id.starts_with("ns_"));
         assert_eq!(ns.id.len(), 11);
         // Verify it's alphanumeric
@@ -234,9 +259,15 @@ fn test_delete_unknown_returns_false() {
 fn test_multiple_namespaces_unique_ids() {
     let registry = NamespaceRegistry::new();
 
-    let ns1 = registry.register("matt").expect("Registration should succeed");
-    let ns2 = registry.register("arc").expect("Registration should succeed");
-    let ns3 = registry.register("test").expect("Registration should succeed");
+    let ns1 = registry
+        .register("matt")
+        .expect("Registration should succeed");
+    let ns2 = registry
+        .register("arc")
+        .expect("Registration should succeed");
+    let ns3 = registry
+        .register("test")
+        .expect("Registration should succeed");
 
     // All IDs should be unique
     assert_ne!(ns1.id, ns2.id);
@@ -248,3 +279,147 @@ fn test_multiple_namespaces_unique_ids() {
     assert_ne!(ns2.token, ns3.token);
     assert_ne!(ns1.token, ns3.token);
 }
+
+#[test]
+fn test_set_quota() {
+    let registry = NamespaceRegistry::new();
+    let ns = registry.register("matt").unwrap();
+    assert!(ns.quota.is_unlimited());
+
+    let quota = NamespaceQuota {
+        max_entities: Some(10),
+        max_bytes: Some(4096),
+    };
+    let updated = registry.set_quota("matt", quota).expect("namespace exists");
+    assert_eq!(updated.quota, quota);
+    assert_eq!(registry.lookup_by_token(&ns.token).unwrap().quota, quota);
+
+    assert!(registry.set_quota("nonexistent", quota).is_none());
+}
+
+#[test]
+#[cfg(feature = "credentials")]
+fn test_quota_survives_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("namespaces.db");
+    let db_path = db_path.to_str().unwrap();
+    let quota = NamespaceQuota {
+        max_entities: Some(10),
+        max_bytes: None,
+    };
+
+    let registry = NamespaceRegistry::new_persistent(NamespaceStore::new(db_path).unwrap());
+    registry.register("matt").unwrap();
+    registry.set_quota("matt", quota).unwrap();
+    drop(registry);
+
+    let registry = NamespaceRegistry::new_persistent(NamespaceStore::new(db_path).unwrap());
+    assert_eq!(registry.lookup_by_name("matt").unwrap().quota, quota);
+}
+
+#[test]
+fn test_create_grant_normalizes_prefix() {
+    let registry = NamespaceRegistry::new();
+    registry.register("alice").unwrap();
+
+    for prefix in ["public", "/public/", "public/*", "public/./"] {
+        let grant = registry
+            .create_grant("alice", prefix, GrantScope::Read, None)
+            .unwrap();
+        assert_eq!(grant.prefix, "public/", "{}", prefix);
+        assert_eq!(grant.entity_prefix(), "alice/public/");
+        assert!(grant.id.starts_with("grant_"));
+        assert!(grant.expires_at.is_none());
+    }
+
+    for prefix in ["", "/", "*", "..", "public/../../bob", "pub*lic/"] {
+        assert!(
+            matches!(
+                registry.create_grant("alice", prefix, GrantScope::Read, None),
+                Err(GrantError::InvalidPrefix(_))
+            ),
+            "{} should be rejected",
+            prefix
+        );
+    }
+    assert_eq!(
+        registry.create_grant("nobody", "public/", GrantScope::Read, None),
+        Err(GrantError::NamespaceNotFound)
+    );
+}
+
+#[test]
+fn test_list_and_revoke_grants() {
+    let registry = NamespaceRegistry::new();
+    registry.register("alice").unwrap();
+    registry.register("bob").unwrap();
+    let first = registry
+        .create_grant("alice", "public/", GrantScope::Read, None)
+        .unwrap();
+    let second = registry
+        .create_grant(
+            "alice",
+            "shared/",
+            GrantScope::Write,
+            Some(Duration::hours(1)),
+        )
+        .unwrap();
+    registry
+        .create_grant("bob", "public/", GrantScope::Read, None)
+        .unwrap();
+
+    let ids: Vec<String> = registry
+        .list_grants("alice")
+        .into_iter()
+        .map(|grant| grant.id)
+        .collect();
+    assert_eq!(ids, [first.id.clone(), second.id.clone()]);
+    assert_eq!(registry.lookup_grant(&second.token), Some(second.clone()));
+    // Grant tokens never identify the namespace itself
+    assert!(registry.lookup_by_token(&first.token).is_none());
+
+    // Only the owning namespace can revoke
+    assert!(!registry.revoke_grant("bob", &first.id));
+    assert!(registry.revoke_grant("alice", &first.id));
+    assert!(!registry.revoke_grant("alice", &first.id));
+    assert!(registry.lookup_grant(&first.token).is_none());
+    assert_eq!(registry.list_grants("alice").len(), 1);
+
+    registry.delete("alice");
+    assert!(registry.lookup_grant(&second.token).is_none());
+    assert_eq!(registry.list_grants("bob").len(), 1);
+}
+
+#[test]
+#[cfg(feature = "credentials")]
+fn test_grants_survive_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("namespaces.db");
+    let db_path = db_path.to_str().unwrap();
+
+    let registry = NamespaceRegistry::new_persistent(NamespaceStore::new(db_path).unwrap());
+    registry.register("alice").unwrap();
+    let kept = registry
+        .create_grant(
+            "alice",
+            "public/",
+            GrantScope::Write,
+            Some(Duration::days(1)),
+        )
+        .unwrap();
+    let revoked = registry
+        .create_grant("alice", "public/", GrantScope::Read, None)
+        .unwrap();
+    registry.revoke_grant("alice", &revoked.id);
+    drop(registry);
+
+    let registry = NamespaceRegistry::new_persistent(NamespaceStore::new(db_path).unwrap());
+    let loaded = registry.lookup_grant(&kept.token).unwrap();
+    assert_eq!(loaded.prefix, "public/");
+    assert_eq!(loaded.scope, GrantScope::Write);
+    assert_eq!(
+        loaded.expires_at.map(|t| t.timestamp()),
+        kept.expires_at.map(|t| t.timestamp())
+    );
+    assert!(registry.lookup_grant(&revoked.token).is_none());
+}
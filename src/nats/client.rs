@@ -1,7 +1,13 @@
+use crate::nats::connection::{ConnectionMonitor, ConnectionStatus};
+use crate::nats::SubjectScheme;
 use anyhow::{Context, Result};
+use async_nats::connection::State;
 use async_nats::jetstream::{self, stream};
-use serde::Deserialize;
-use tracing::info;
+use async_nats::{ConnectOptions, Event};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use utoipa::ToSchema;
 
 /// NATS configuration
 #[derive(Clone, Debug, Deserialize)]
@@ -14,6 +20,36 @@ pub struct NatsConfig {
     pub max_age_days: i64,
     #[serde(default = "default_max_bytes")]
     pub max_bytes: i64,
+    /// Published events allowed to await their JetStream ack at once
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Subject layout for published events; `flat` keeps the pre-namespace one
+    #[serde(default)]
+    pub subject_scheme: SubjectScheme,
+    /// User/password auth; `password` requires `username`
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Token auth; cannot be combined with `username`
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub tls: NatsTlsConfig,
+}
+
+/// TLS settings (`[nats.tls]`)
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct NatsTlsConfig {
+    /// PEM CA bundle used to verify the server instead of the system roots
+    pub ca_file: Option<PathBuf>,
+    /// PEM client certificate; requires `key_file`
+    pub cert_file: Option<PathBuf>,
+    /// PEM private key for `cert_file`
+    pub key_file: Option<PathBuf>,
+    /// Refuse plaintext even if the server does not require TLS
+    #[serde(default)]
+    pub require_tls: bool,
 }
 
 fn default_stream_subjects() -> Vec<String> {
@@ -28,6 +64,10 @@ fn default_max_bytes() -> i64 {
     10 * 1024 * 1024 * 1024 // 10GB
 }
 
+fn default_max_in_flight() -> usize {
+    super::publisher::DEFAULT_MAX_IN_FLIGHT
+}
+
 impl Default for NatsConfig {
     fn default() -> Self {
         Self {
@@ -36,6 +76,127 @@ impl Default for NatsConfig {
             stream_subjects: vec!["flux.events.>".to_string()],
             max_age_days: 7,
             max_bytes: 10 * 1024 * 1024 * 1024, // 10GB
+            max_in_flight: default_max_in_flight(),
+            subject_scheme: SubjectScheme::default(),
+            username: None,
+            password: None,
+            token: None,
+            tls: NatsTlsConfig::default(),
+        }
+    }
+}
+
+impl NatsConfig {
+    /// Build connect options from the auth and TLS settings.
+    ///
+    /// Certificate and key files are read up front so a bad path or a file
+    /// without PEM data fails here, naming the setting, instead of surfacing
+    /// as a handshake error.
+    pub fn connect_options(&self) -> Result<ConnectOptions> {
+        let mut options = ConnectOptions::new();
+
+        match (&self.username, &self.password, &self.token) {
+            (Some(_), _, Some(_)) => {
+                anyhow::bail!("nats: set either username/password or token, not both")
+            }
+            (None, Some(_), _) => anyhow::bail!("nats: password is set without username"),
+            (Some(user), password, None) => {
+                options =
+                    options.user_and_password(user.clone(), password.clone().unwrap_or_default());
+            }
+            (None, None, Some(token)) => options = options.token(token.clone()),
+            (None, None, None) => {}
+        }
+
+        let tls = &self.tls;
+        if let Some(ca_file) = &tls.ca_file {
+            check_pem(ca_file, "ca_file", "CERTIFICATE")?;
+            options = options.add_root_certificates(ca_file.clone());
+        }
+        match (&tls.cert_file, &tls.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                check_pem(cert_file, "cert_file", "CERTIFICATE")?;
+                check_pem(key_file, "key_file", "PRIVATE KEY")?;
+                options = options.add_client_certificate(cert_file.clone(), key_file.clone());
+            }
+            (Some(_), None) => anyhow::bail!("nats.tls: cert_file is set without key_file"),
+            (None, Some(_)) => anyhow::bail!("nats.tls: key_file is set without cert_file"),
+            (None, None) => {}
+        }
+        if tls.require_tls {
+            options = options.require_tls(true);
+        }
+
+        Ok(options)
+    }
+
+    /// Whether the client insists on TLS regardless of what the server offers
+    fn client_requires_tls(&self) -> bool {
+        self.tls.require_tls || self.url.starts_with("tls://")
+    }
+}
+
+/// Fail unless `path` is readable and holds a PEM block whose label ends with
+/// `label` (`PRIVATE KEY` matches PKCS#8, RSA and EC keys).
+fn check_pem(path: &Path, setting: &str, label: &str) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("nats.tls: cannot read {} {}", setting, path.display()))?;
+    let found = contents.lines().any(|line| {
+        line.trim()
+            .strip_prefix("-----BEGIN ")
+            .and_then(|rest| rest.strip_suffix("-----"))
+            .is_some_and(|found| found.ends_with(label))
+    });
+    if !found {
+        anyhow::bail!(
+            "nats.tls: {} {} contains no PEM {}",
+            setting,
+            path.display(),
+            label
+        );
+    }
+    Ok(())
+}
+
+/// Negotiated NATS connection state, reported by `GET /api/ready`
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "connected": true,
+    "tls": true,
+    "server_version": "2.10.22",
+    "server_name": "nats-1",
+    "state": "connected",
+    "last_disconnect_at": "2026-01-01T00:00:00Z",
+    "reconnects": 1,
+    "messages_since_reconnect": 5200
+}))]
+pub struct NatsConnectionStatus {
+    pub connected: bool,
+    /// True when either side required TLS, so the connection is encrypted
+    pub tls: bool,
+    pub server_version: String,
+    pub server_name: String,
+    #[serde(flatten)]
+    pub connection: ConnectionStatus,
+}
+
+/// Cloneable handle for reading the connection state from API handlers
+#[derive(Clone)]
+pub struct NatsStatusHandle {
+    client: async_nats::Client,
+    client_requires_tls: bool,
+    monitor: ConnectionMonitor,
+}
+
+impl NatsStatusHandle {
+    pub fn status(&self) -> NatsConnectionStatus {
+        let info = self.client.server_info();
+        NatsConnectionStatus {
+            connected: matches!(self.client.connection_state(), State::Connected),
+            tls: self.client_requires_tls || info.tls_required,
+            server_version: info.version,
+            server_name: info.server_name,
+            connection: self.monitor.status(),
         }
     }
 }
@@ -45,6 +206,7 @@ pub struct NatsClient {
     client: async_nats::Client,
     jetstream: jetstream::Context,
     config: NatsConfig,
+    monitor: ConnectionMonitor,
 }
 
 impl NatsClient {
@@ -52,9 +214,33 @@ impl NatsClient {
     pub async fn connect(config: NatsConfig) -> Result<Self> {
         info!("Connecting to NATS at {}", config.url);
 
-        let client = async_nats::connect(&config.url)
+        let monitor = ConnectionMonitor::new();
+        let events = monitor.clone();
+        let options = config
+            .connect_options()
+            .context("Invalid NATS connection settings")?
+            .event_callback(move |event| {
+                let events = events.clone();
+                async move {
+                    match event {
+                        Event::Connected => {
+                            if !events.is_connected() {
+                                info!("Reconnected to NATS");
+                            }
+                            events.record_connected();
+                        }
+                        Event::Disconnected => {
+                            warn!("Lost connection to NATS, reconnecting");
+                            events.record_disconnected();
+                        }
+                        _ => {}
+                    }
+                }
+            });
+        let client = options
+            .connect(&config.url)
             .await
-            .context("Failed to connect to NATS")?;
+            .with_context(|| format!("Failed to connect to NATS at {}", config.url))?;
 
         let jetstream = jetstream::new(client.clone());
 
@@ -62,8 +248,17 @@ impl NatsClient {
             client,
             jetstream,
             config,
+            monitor,
         };
 
+        let status = nats_client.status_handle().status();
+        info!(
+            tls = status.tls,
+            server_version = %status.server_version,
+            "Connected to NATS server '{}'",
+            status.server_name
+        );
+
         nats_client.ensure_stream().await?;
 
         Ok(nats_client)
@@ -71,7 +266,10 @@ impl NatsClient {
 
     /// Ensure JetStream stream exists with proper configuration
     async fn ensure_stream(&mut self) -> Result<()> {
-        info!("Ensuring JetStream stream '{}' exists", self.config.stream_name);
+        info!(
+            "Ensuring JetStream stream '{}' exists",
+            self.config.stream_name
+        );
 
         // Check if stream exists
         match self.jetstream.get_stream(&self.config.stream_name).await {
@@ -80,7 +278,10 @@ impl NatsClient {
                 return Ok(());
             }
             Err(_) => {
-                info!("Stream '{}' does not exist, creating...", self.config.stream_name);
+                info!(
+                    "Stream '{}' does not exist, creating...",
+                    self.config.stream_name
+                );
             }
         }
 
@@ -113,4 +314,149 @@ impl NatsClient {
     pub fn client(&self) -> &async_nats::Client {
         &self.client
     }
+
+    /// Handle for reporting connection state (TLS, server version) over the API
+    pub fn status_handle(&self) -> NatsStatusHandle {
+        NatsStatusHandle {
+            client: self.client.clone(),
+            client_requires_tls: self.config.client_requires_tls(),
+            monitor: self.monitor.clone(),
+        }
+    }
+
+    /// Connection losses and recoveries, shared with the publisher and state engine
+    pub fn connection(&self) -> ConnectionMonitor {
+        self.monitor.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn pem_file(label: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "-----BEGIN {}-----\nMIIB\n-----END {}-----",
+            label, label
+        )
+        .unwrap();
+        file
+    }
+
+    fn tls_config(tls: NatsTlsConfig) -> NatsConfig {
+        NatsConfig {
+            url: "tls://nats:4222".to_string(),
+            tls,
+            ..NatsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_tls_and_auth_from_toml() {
+        let config: NatsConfig = toml::from_str(
+            r#"
+            url = "tls://nats:4222"
+            stream_name = "FLUX_EVENTS"
+            username = "flux"
+            password = "secret"
+
+            [tls]
+            ca_file = "/etc/nats/ca.pem"
+            cert_file = "/etc/nats/client.pem"
+            key_file = "/etc/nats/client-key.pem"
+            require_tls = true
+        "#,
+        )
+        .unwrap();
+        assert_eq!(config.username.as_deref(), Some("flux"));
+        assert_eq!(config.password.as_deref(), Some("secret"));
+        assert!(config.token.is_none());
+        assert_eq!(config.tls.ca_file, Some(PathBuf::from("/etc/nats/ca.pem")));
+        assert_eq!(
+            config.tls.key_file,
+            Some(PathBuf::from("/etc/nats/client-key.pem"))
+        );
+        assert!(config.tls.require_tls);
+
+        let plain: NatsConfig =
+            toml::from_str("url = \"nats://localhost:4222\"\nstream_name = \"S\"").unwrap();
+        assert!(plain.tls.ca_file.is_none());
+        assert!(!plain.tls.require_tls);
+        assert!(!plain.client_requires_tls());
+        assert!(config.client_requires_tls());
+    }
+
+    #[test]
+    fn test_connect_options_with_certificates() {
+        let ca = pem_file("CERTIFICATE");
+        let cert = pem_file("CERTIFICATE");
+        let key = pem_file("EC PRIVATE KEY");
+        let config = tls_config(NatsTlsConfig {
+            ca_file: Some(ca.path().to_path_buf()),
+            cert_file: Some(cert.path().to_path_buf()),
+            key_file: Some(key.path().to_path_buf()),
+            require_tls: true,
+        });
+        config.connect_options().unwrap();
+
+        let token = NatsConfig {
+            token: Some("s3cr3t".to_string()),
+            ..NatsConfig::default()
+        };
+        token.connect_options().unwrap();
+    }
+
+    #[test]
+    fn test_connect_options_reject_misconfiguration() {
+        let err = |config: NatsConfig| format!("{:#}", config.connect_options().unwrap_err());
+
+        let missing = err(tls_config(NatsTlsConfig {
+            ca_file: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..NatsTlsConfig::default()
+        }));
+        assert!(
+            missing.contains("cannot read ca_file /nonexistent/ca.pem"),
+            "{}",
+            missing
+        );
+
+        let key = pem_file("PRIVATE KEY");
+        let not_a_cert = err(tls_config(NatsTlsConfig {
+            cert_file: Some(key.path().to_path_buf()),
+            key_file: Some(key.path().to_path_buf()),
+            ..NatsTlsConfig::default()
+        }));
+        assert!(
+            not_a_cert.contains("contains no PEM CERTIFICATE"),
+            "{}",
+            not_a_cert
+        );
+
+        let cert = pem_file("CERTIFICATE");
+        let no_key = err(tls_config(NatsTlsConfig {
+            cert_file: Some(cert.path().to_path_buf()),
+            ..NatsTlsConfig::default()
+        }));
+        assert_eq!(no_key, "nats.tls: cert_file is set without key_file");
+
+        let both = err(NatsConfig {
+            username: Some("flux".to_string()),
+            token: Some("t".to_string()),
+            ..NatsConfig::default()
+        });
+        assert_eq!(
+            both,
+            "nats: set either username/password or token, not both"
+        );
+
+        let orphan = err(NatsConfig {
+            password: Some("p".to_string()),
+            ..NatsConfig::default()
+        });
+        assert_eq!(orphan, "nats: password is set without username");
+    }
 }
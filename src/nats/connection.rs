@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Whether the NATS connection is up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    /// Lost; the client keeps reconnecting in the background
+    Reconnecting,
+}
+
+/// Outage history of the NATS connection, reported by `GET /api/ready` and
+/// the metrics broadcast
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionStatus {
+    pub state: ConnectionState,
+    /// When the connection was last lost
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_disconnect_at: Option<DateTime<Utc>>,
+    /// Times the connection came back after being lost
+    pub reconnects: u64,
+    /// Events the state engine received since the connection (re)opened
+    pub messages_since_reconnect: u64,
+}
+
+/// Tracks connection losses and recoveries
+///
+/// Fed by the NATS client's connection events; the publisher checks it to
+/// fail fast during an outage and the state engine counts received messages
+/// in it. Clones share the same state.
+#[derive(Clone)]
+pub struct ConnectionMonitor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    connected: AtomicBool,
+    last_disconnect_at: Mutex<Option<DateTime<Utc>>>,
+    reconnects: AtomicU64,
+    messages_since_reconnect: AtomicU64,
+}
+
+impl ConnectionMonitor {
+    /// A monitor for a connection that is up
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                connected: AtomicBool::new(true),
+                last_disconnect_at: Mutex::new(None),
+                reconnects: AtomicU64::new(0),
+                messages_since_reconnect: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.inner.connected.load(Ordering::SeqCst)
+    }
+
+    /// The connection was lost
+    pub fn record_disconnected(&self) {
+        if self.inner.connected.swap(false, Ordering::SeqCst) {
+            *self.inner.last_disconnect_at.lock().unwrap() = Some(Utc::now());
+        }
+    }
+
+    /// The connection is up; counts as a reconnect if it was lost
+    pub fn record_connected(&self) {
+        if !self.inner.connected.swap(true, Ordering::SeqCst) {
+            self.inner.reconnects.fetch_add(1, Ordering::Relaxed);
+            self.inner
+                .messages_since_reconnect
+                .store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// The state engine received a message
+    pub fn record_message(&self) {
+        self.inner
+            .messages_since_reconnect
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        ConnectionStatus {
+            state: if self.is_connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Reconnecting
+            },
+            last_disconnect_at: *self.inner.last_disconnect_at.lock().unwrap(),
+            reconnects: self.inner.reconnects.load(Ordering::Relaxed),
+            messages_since_reconnect: self.inner.messages_since_reconnect.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for ConnectionMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outage_and_recovery() {
+        let monitor = ConnectionMonitor::new();
+        monitor.record_connected();
+        monitor.record_message();
+        let status = monitor.status();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.reconnects, 0, "initial connection is no reconnect");
+        assert_eq!(status.messages_since_reconnect, 1);
+        assert_eq!(status.last_disconnect_at, None);
+
+        monitor.record_disconnected();
+        let lost_at = monitor.status().last_disconnect_at.unwrap();
+        // Repeated events during one outage keep the first time
+        monitor.record_disconnected();
+        let status = monitor.status();
+        assert_eq!(status.state, ConnectionState::Reconnecting);
+        assert_eq!(status.last_disconnect_at, Some(lost_at));
+        assert!(!monitor.is_connected());
+
+        monitor.record_connected();
+        monitor.record_message();
+        monitor.record_message();
+        let status = monitor.status();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.reconnects, 1);
+        assert_eq!(status.messages_since_reconnect, 2);
+        assert_eq!(status.last_disconnect_at, Some(lost_at));
+    }
+}
@@ -0,0 +1,396 @@
+use crate::api::auth_middleware::AuthError;
+use crate::api::{EventAdmission, Rejection};
+use crate::entity::parse_entity_id;
+use crate::event::FluxEvent;
+use crate::nats::{EventPublisher, INGEST_SUBJECT_PREFIX, REJECTED_SUBJECT, REQUEST_ID_HEADER};
+use anyhow::{Context, Result};
+use async_nats::jetstream::{self, consumer::DeliverPolicy, AckKind};
+use axum::http::{header, HeaderMap, HeaderValue};
+use chrono::Utc;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+/// Durable consumer shared by every instance, so each message is ingested once
+const INGEST_CONSUMER: &str = "flux-ingest";
+
+/// Published to [`REJECTED_SUBJECT`] for each event the ingester refuses
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedEvent {
+    /// Subject the event was published to
+    pub subject: String,
+    /// Same message the HTTP API returns in its `error` field
+    pub reason: String,
+    /// Status the HTTP API would have answered with
+    pub status: u16,
+    /// Event ID, if the payload carried one
+    pub event_id: Option<String>,
+    /// Unix epoch milliseconds
+    pub rejected_at: i64,
+}
+
+/// Validates events published directly to NATS and republishes them
+///
+/// Runs the same [`EventAdmission`] checks as `POST /api/events`; the
+/// namespace token travels in an `Authorization: Bearer <token>` header.
+/// An `X-Request-Id` header is passed on with the republished event, and one
+/// is assigned if the producer sent none.
+pub struct NatsIngester {
+    admission: EventAdmission,
+    publisher: EventPublisher,
+}
+
+impl NatsIngester {
+    pub fn new(admission: EventAdmission, publisher: EventPublisher) -> Self {
+        Self {
+            admission,
+            publisher,
+        }
+    }
+
+    /// Admit one ingest message, exactly as the HTTP API would admit its body
+    ///
+    /// The entity's namespace must also match the subject's, so a subject
+    /// can't be used to write into another namespace.
+    pub fn admit_message(
+        &self,
+        subject: &str,
+        headers: Option<&async_nats::HeaderMap>,
+        payload: &[u8],
+    ) -> Result<FluxEvent, Rejection> {
+        let mut http_headers = HeaderMap::new();
+        let authorization = headers
+            .and_then(|h| h.get("Authorization"))
+            .and_then(|v| HeaderValue::from_str(v.as_str()).ok());
+        if let Some(value) = authorization {
+            http_headers.insert(header::AUTHORIZATION, value);
+        }
+
+        let event = self.admission.admit_body(payload, &http_headers)?;
+
+        let subject_namespace = subject.strip_prefix(INGEST_SUBJECT_PREFIX).unwrap_or("");
+        let entity_namespace = event
+            .payload
+            .get("entity_id")
+            .and_then(|v| v.as_str())
+            .and_then(|eid| parse_entity_id(eid).ok())
+            .and_then(|parsed| parsed.namespace);
+        if let Some(namespace) = entity_namespace {
+            if namespace != subject_namespace {
+                return Err(Rejection::Unauthorized(AuthError::Forbidden(format!(
+                    "Entity namespace '{}' does not match subject '{}'",
+                    namespace, subject
+                ))));
+            }
+        }
+
+        Ok(event)
+    }
+
+    /// Consume `flux.events.ingest.*` until the stream ends
+    ///
+    /// Accepted events are acked once republished; a failed publish is
+    /// redelivered.
+    pub async fn run(self, jetstream: jetstream::Context) -> Result<()> {
+        let stream = jetstream
+            .get_stream("FLUX_EVENTS")
+            .await
+            .context("Failed to get FLUX_EVENTS stream")?;
+        let consumer = stream
+            .get_or_create_consumer(
+                INGEST_CONSUMER,
+                jetstream::consumer::pull::Config {
+                    durable_name: Some(INGEST_CONSUMER.to_string()),
+                    filter_subject: format!("{}*", INGEST_SUBJECT_PREFIX),
+                    // Applies only when the consumer is first created
+                    deliver_policy: DeliverPolicy::New,
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Failed to get or create ingest consumer")?;
+
+        info!("NATS ingester started on {}*", INGEST_SUBJECT_PREFIX);
+        let mut messages = consumer.messages().await?;
+
+        while let Some(msg) = messages.next().await {
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(e) => {
+                    error!(error = %e, "Error receiving ingest message");
+                    continue;
+                }
+            };
+            let subject = msg.subject.to_string();
+            let request_id = msg
+                .headers
+                .as_ref()
+                .and_then(|h| h.get(REQUEST_ID_HEADER))
+                .map(|v| v.as_str().to_string())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+            let ack = match self.admit_message(&subject, msg.headers.as_ref(), &msg.payload) {
+                Ok(event) => match self
+                    .publisher
+                    .publish_with_request_id(&event, Some(&request_id))
+                    .await
+                {
+                    Ok(()) => {
+                        debug!(subject = %subject, stream = %event.stream, request_id = %request_id, "Ingested event from NATS");
+                        AckKind::Ack
+                    }
+                    Err(e) => {
+                        error!(error = %e, subject = %subject, "Failed to republish ingested event");
+                        AckKind::Nak(None)
+                    }
+                },
+                Err(rejection) => {
+                    let rejected = RejectedEvent {
+                        subject,
+                        reason: rejection.to_string(),
+                        status: rejection.status().as_u16(),
+                        event_id: serde_json::from_slice::<serde_json::Value>(&msg.payload)
+                            .ok()
+                            .and_then(|v| v.get("eventId")?.as_str().map(str::to_string)),
+                        rejected_at: Utc::now().timestamp_millis(),
+                    };
+                    warn!(subject = %rejected.subject, reason = %rejected.reason, "Rejected event from NATS");
+                    publish_rejection(&jetstream, &rejected).await;
+                    AckKind::Ack
+                }
+            };
+
+            if let Err(e) = msg.ack_with(ack).await {
+                error!(error = %e, "Failed to acknowledge ingest message");
+            }
+        }
+
+        warn!("NATS ingester stream ended");
+        Ok(())
+    }
+}
+
+async fn publish_rejection(jetstream: &jetstream::Context, rejected: &RejectedEvent) {
+    let payload = match serde_json::to_vec(rejected) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!(error = %e, "Failed to serialize rejection");
+            return;
+        }
+    };
+    let published = match jetstream.publish(REJECTED_SUBJECT, payload.into()).await {
+        Ok(ack) => ack.await.map(|_| ()).map_err(anyhow::Error::from),
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = published {
+        error!(error = %e, "Failed to publish rejection");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_router, AppState};
+    use crate::config::new_runtime_config;
+    use crate::namespace::NamespaceRegistry;
+    use crate::nats::publisher::{AckFuture, PublishSink};
+    use crate::nats::subject::is_ingest_subject;
+    use crate::rate_limit::RateLimiter;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use futures::future::BoxFuture;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// Acks everything without sending it anywhere
+    struct NullSink;
+
+    impl PublishSink for NullSink {
+        fn send(
+            &self,
+            _subject: String,
+            _request_id: Option<String>,
+            _payload: Vec<u8>,
+        ) -> BoxFuture<'_, Result<AckFuture>> {
+            Box::pin(async {
+                let ack: AckFuture = Box::pin(async { Ok(()) });
+                Ok(ack)
+            })
+        }
+    }
+
+    struct Paths {
+        http: axum::Router,
+        ingester: NatsIngester,
+        token: String,
+    }
+
+    fn paths(auth_enabled: bool) -> Paths {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let token = registry.register("matt").unwrap().token;
+        let state = AppState {
+            event_publisher: EventPublisher::with_sink(Arc::new(NullSink)),
+            namespace_registry: registry,
+            auth_enabled,
+            admin_token: None,
+            runtime_config: new_runtime_config(),
+            rate_limiter: Arc::new(RateLimiter::new()),
+        };
+        Paths {
+            ingester: NatsIngester::new(state.admission(), state.event_publisher.clone()),
+            http: create_router(state),
+            token,
+        }
+    }
+
+    /// Status and error message the HTTP API answers `body` with
+    async fn via_http(paths: &Paths, body: &str, token: Option<&str>) -> (u16, String) {
+        let mut request = Request::post("/api/events").header("Content-Type", "application/json");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        let response = paths
+            .http
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let error = body["error"].as_str().unwrap_or_default().to_string();
+        (status, error)
+    }
+
+    fn via_nats(paths: &Paths, subject: &str, body: &str, token: Option<&str>) -> (u16, String) {
+        let mut headers = async_nats::HeaderMap::new();
+        if let Some(token) = token {
+            headers.insert("Authorization", format!("Bearer {}", token).as_str());
+        }
+        match paths
+            .ingester
+            .admit_message(subject, Some(&headers), body.as_bytes())
+        {
+            Ok(_) => (StatusCode::OK.as_u16(), String::new()),
+            Err(rejection) => (rejection.status().as_u16(), rejection.to_string()),
+        }
+    }
+
+    fn event_json(stream: &str, entity_id: &str) -> String {
+        serde_json::json!({
+            "stream": stream,
+            "source": "producer",
+            "timestamp": Utc::now().timestamp_millis(),
+            "payload": {"entity_id": entity_id, "properties": {"temperature": 22.5}}
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_malformed_events_rejected_identically() {
+        let cases = [
+            "not json".to_string(),
+            r#"{"stream": "sensors"}"#.to_string(),
+            r#"{"stream": "sensors", "source": "p", "timestamp": -5, "payload": {}}"#.to_string(),
+            r#"{"stream": "sensors", "source": "p", "timestamp": 1, "payload": "text"}"#
+                .to_string(),
+            event_json("Bad Stream", "sensor-1"),
+            event_json("ingest.matt", "sensor-1"),
+        ];
+
+        let paths = paths(false);
+        for body in &cases {
+            let http = via_http(&paths, body, None).await;
+            let nats = via_nats(&paths, "flux.events.ingest.matt", body, None);
+            assert_ne!(http.0, 200, "{} should be rejected", body);
+            assert_eq!(http, nats, "paths disagree on {}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authorization_rejected_identically() {
+        let paths = paths(true);
+        let token = paths.token.clone();
+        let cases = [
+            (event_json("sensors", "matt/sensor-1"), None),
+            (event_json("sensors", "matt/sensor-1"), Some("wrong-token")),
+            (event_json("sensors", "sensor-1"), Some(token.as_str())),
+            (event_json("sensors", "arc/sensor-1"), Some(token.as_str())),
+        ];
+
+        for (body, token) in &cases {
+            let http = via_http(&paths, body, *token).await;
+            let nats = via_nats(&paths, "flux.events.ingest.matt", body, *token);
+            assert!(http.0 == 401 || http.0 == 403, "{} should be refused", body);
+            assert_eq!(http, nats, "paths disagree on {}", body);
+        }
+
+        let valid = event_json("sensors", "matt/sensor-1");
+        assert_eq!(via_http(&paths, &valid, Some(&token)).await.0, 200);
+        assert_eq!(
+            via_nats(&paths, "flux.events.ingest.matt", &valid, Some(&token)).0,
+            200
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quota_stream_refused_on_both_paths() {
+        let paths = paths(false);
+        let body = serde_json::json!({
+            "stream": "flux.quotas",
+            "source": "producer",
+            "timestamp": Utc::now().timestamp_millis(),
+            "payload": {"namespace": "matt", "max_entities": null, "max_bytes": null}
+        })
+        .to_string();
+
+        let http = via_http(&paths, &body, None).await;
+        assert_eq!(http.0, 403);
+        assert_eq!(
+            http,
+            via_nats(&paths, "flux.events.ingest.matt", &body, None)
+        );
+    }
+
+    #[test]
+    fn test_subject_namespace_must_match_entity() {
+        let paths = paths(false);
+        let body = event_json("sensors", "matt/sensor-1");
+
+        let (status, reason) = via_nats(&paths, "flux.events.ingest.arc", &body, None);
+        assert_eq!(status, 403);
+        assert!(reason.contains("does not match subject"), "{}", reason);
+
+        // Entities without a namespace prefix are only possible with auth disabled
+        let unprefixed = event_json("sensors", "sensor-1");
+        assert_eq!(
+            via_nats(&paths, "flux.events.ingest.arc", &unprefixed, None).0,
+            200
+        );
+    }
+
+    #[test]
+    fn test_admitted_event_is_prepared() {
+        let paths = paths(false);
+        let event = paths
+            .ingester
+            .admit_message(
+                "flux.events.ingest.matt",
+                None,
+                event_json("sensors", "matt/sensor-1").as_bytes(),
+            )
+            .unwrap();
+        assert!(event.event_id.is_some());
+        assert!(event.received_at.is_some());
+    }
+
+    #[test]
+    fn test_is_ingest_subject() {
+        assert!(is_ingest_subject("flux.events.ingest.matt"));
+        assert!(is_ingest_subject("flux.events.rejected"));
+        assert!(!is_ingest_subject("flux.events.sensors"));
+        assert!(!is_ingest_subject("flux.events.ingestion"));
+    }
+}
@@ -1,7 +1,21 @@
 // NATS client integration (Task 4)
 
 mod client;
+mod connection;
+#[cfg(feature = "http-api")]
+mod ingester;
 mod publisher;
+mod subject;
 
-pub use client::{NatsClient, NatsConfig};
-pub use publisher::EventPublisher;
+pub use client::{NatsClient, NatsConfig, NatsConnectionStatus, NatsStatusHandle, NatsTlsConfig};
+pub use connection::{ConnectionMonitor, ConnectionState, ConnectionStatus};
+#[cfg(feature = "http-api")]
+pub use ingester::{NatsIngester, RejectedEvent};
+pub use publisher::{
+    is_unavailable, AckFuture, EventPublisher, NatsUnavailable, PublishSink,
+    COMPACTED_THROUGH_HEADER, REQUEST_ID_HEADER,
+};
+pub use subject::{
+    encode_subject_token, event_subject, is_ingest_subject, namespace_token, stream_subject,
+    SubjectScheme, DEFAULT_SUBJECT_NAMESPACE, INGEST_SUBJECT_PREFIX, REJECTED_SUBJECT,
+};
@@ -1,55 +1,616 @@
-use crate::event::FluxEvent;
+use crate::event::{FluxEvent, RawFluxEvent};
+use crate::nats::{event_subject, stream_subject, ConnectionMonitor, SubjectScheme};
+use crate::state::MetricsTracker;
 use anyhow::{Context, Result};
 use async_nats::jetstream;
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
 use tracing::debug;
 
+/// Default number of published events that may await their ack at once
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 512;
+
+/// NATS header carrying the correlation ID of the request that published an
+/// event (the HTTP `X-Request-Id`); the event payload itself is not touched
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Header on compacted events: the last stream sequence they stand for
+///
+/// An engine that has processed that sequence while live already holds
+/// their state and skips them.
+pub const COMPACTED_THROUGH_HEADER: &str = "Flux-Compacted-Through";
+
+/// A publish failed because the NATS connection is down; retrying once it
+/// is back may succeed
+#[derive(Debug)]
+pub struct NatsUnavailable;
+
+impl fmt::Display for NatsUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NATS connection is down")
+    }
+}
+
+impl std::error::Error for NatsUnavailable {}
+
+/// True if `error` is (or was caused by) the NATS connection being down
+pub fn is_unavailable(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<NatsUnavailable>().is_some()
+}
+
+/// Resolves once JetStream has acked (or rejected) a published message
+pub type AckFuture = BoxFuture<'static, Result<()>>;
+
+/// Destination for published events.
+///
+/// `send` returns once the message has been handed to the connection, so
+/// messages sent one after another keep their order; the returned future
+/// resolves on the ack. `request_id` goes out as the [`REQUEST_ID_HEADER`]
+/// header. Implemented for the JetStream context; tests can substitute an
+/// in-memory sink.
+pub trait PublishSink: Send + Sync + 'static {
+    fn send(
+        &self,
+        subject: String,
+        request_id: Option<String>,
+        payload: Vec<u8>,
+    ) -> BoxFuture<'_, Result<AckFuture>>;
+}
+
+impl PublishSink for jetstream::Context {
+    fn send(
+        &self,
+        subject: String,
+        request_id: Option<String>,
+        payload: Vec<u8>,
+    ) -> BoxFuture<'_, Result<AckFuture>> {
+        Box::pin(async move {
+            let published = match request_id {
+                Some(request_id) => {
+                    let mut headers = async_nats::HeaderMap::new();
+                    headers.insert(REQUEST_ID_HEADER, request_id.as_str());
+                    self.publish_with_headers(subject.clone(), headers, payload.into())
+                        .await
+                }
+                None => self.publish(subject.clone(), payload.into()).await,
+            };
+            let ack =
+                published.context(format!("Failed to publish event to subject '{}'", subject))?;
+            let ack: AckFuture = Box::pin(async move {
+                ack.await.context("Failed to await publish ack")?;
+                Ok(())
+            });
+            Ok(ack)
+        })
+    }
+}
+
 /// Event publisher for NATS JetStream
+///
+/// Acks are awaited concurrently, with at most `max_in_flight` unacked
+/// events across all callers sharing this publisher (clones share the window).
 #[derive(Clone)]
 pub struct EventPublisher {
-    jetstream: jetstream::Context,
+    sink: Arc<dyn PublishSink>,
+    in_flight: Arc<Semaphore>,
+    metrics: Option<MetricsTracker>,
+    subject_scheme: SubjectScheme,
+    connection: Option<ConnectionMonitor>,
 }
 
 impl EventPublisher {
     /// Create a new event publisher
     pub fn new(jetstream: jetstream::Context) -> Self {
-        Self { jetstream }
+        Self::with_sink(Arc::new(jetstream))
+    }
+
+    /// Create a publisher over any [`PublishSink`]
+    pub fn with_sink(sink: Arc<dyn PublishSink>) -> Self {
+        Self {
+            sink,
+            in_flight: Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT)),
+            metrics: None,
+            subject_scheme: SubjectScheme::default(),
+            connection: None,
+        }
+    }
+
+    /// Limit the number of events awaiting their ack at once (minimum 1)
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.in_flight = Arc::new(Semaphore::new(max_in_flight.max(1)));
+        self
+    }
+
+    /// Publish on subjects laid out per `scheme`
+    pub fn with_subject_scheme(mut self, scheme: SubjectScheme) -> Self {
+        self.subject_scheme = scheme;
+        self
+    }
+
+    /// Record in-flight depth and ack latency in `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsTracker) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Fail with [`NatsUnavailable`] while `connection` is down, without
+    /// waiting for the ack to time out
+    pub fn with_connection(mut self, connection: ConnectionMonitor) -> Self {
+        self.connection = Some(connection);
+        self
     }
 
     /// Publish a single event to NATS
     ///
-    /// Subject format: flux.events.{stream}
+    /// Subject format: flux.events.{namespace}.{stream}, or flux.events.{stream}
+    /// with the flat scheme (see [`event_subject`])
     /// Payload: JSON-serialized FluxEvent
     pub async fn publish(&self, event: &FluxEvent) -> Result<()> {
-        let subject = format!("flux.events.{}", event.stream);
-        let payload = serde_json::to_vec(event)
-            .context("Failed to serialize event to JSON")?;
+        self.publish_with_request_id(event, None).await
+    }
 
+    /// [`publish`](Self::publish), tagging the message with `request_id`
+    pub async fn publish_with_request_id(
+        &self,
+        event: &FluxEvent,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let permit = Arc::clone(&self.in_flight)
+            .acquire_owned()
+            .await
+            .expect("publish semaphore is never closed");
+        let ack = self.send(event, request_id).await?;
+        let result = ack.await;
+        drop(permit);
+        result
+    }
+
+    /// Publish multiple events, awaiting their acks concurrently
+    ///
+    /// Events are sent in order, so ordering per subject is preserved.
+    /// Returns one result per event, in input order.
+    pub async fn publish_batch(&self, events: &[FluxEvent]) -> Vec<Result<()>> {
+        self.publish_batch_with_request_id(events, None).await
+    }
+
+    /// [`publish_batch`](Self::publish_batch), tagging every message with
+    /// `request_id`
+    pub async fn publish_batch_with_request_id(
+        &self,
+        events: &[FluxEvent],
+        request_id: Option<&str>,
+    ) -> Vec<Result<()>> {
+        let mut results: Vec<Option<Result<()>>> = events.iter().map(|_| None).collect();
+        let mut pending: FuturesUnordered<BoxFuture<'static, (usize, Result<()>)>> =
+            FuturesUnordered::new();
+
+        for (i, event) in events.iter().enumerate() {
+            // Keep collecting our own acks while waiting for window space,
+            // otherwise a batch larger than the window could never progress
+            let permit = loop {
+                tokio::select! {
+                    biased;
+                    Some((j, result)) = pending.next(), if !pending.is_empty() => {
+                        results[j] = Some(result);
+                    }
+                    permit = Arc::clone(&self.in_flight).acquire_owned() => {
+                        break permit.expect("publish semaphore is never closed");
+                    }
+                }
+            };
+
+            match self.send(event, request_id).await {
+                Ok(ack) => pending.push(Box::pin(async move {
+                    let result = ack.await;
+                    drop(permit);
+                    (i, result)
+                })),
+                Err(e) => results[i] = Some(Err(e)),
+            }
+        }
+
+        while let Some((j, result)) = pending.next().await {
+            results[j] = Some(result);
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every event has a result"))
+            .collect()
+    }
+
+    /// [`publish_with_request_id`](Self::publish_with_request_id) for an
+    /// event whose payload is still raw JSON; the payload is copied into the
+    /// message as-is
+    pub async fn publish_raw_with_request_id(
+        &self,
+        event: &RawFluxEvent<'_>,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let subject = stream_subject(
+            &event.stream,
+            event.entity_id().as_deref(),
+            self.subject_scheme,
+        );
+        let payload = serde_json::to_vec(event).context("Failed to serialize event to JSON")?;
+        let permit = Arc::clone(&self.in_flight)
+            .acquire_owned()
+            .await
+            .expect("publish semaphore is never closed");
+        let ack = self
+            .send_bytes(
+                subject,
+                payload,
+                event.event_id.as_deref(),
+                &event.stream,
+                request_id,
+            )
+            .await?;
+        let result = ack.await;
+        drop(permit);
+        result
+    }
+
+    /// Hand one event to the sink; the returned future resolves on its ack
+    async fn send(&self, event: &FluxEvent, request_id: Option<&str>) -> Result<AckFuture> {
+        let subject = event_subject(event, self.subject_scheme);
+        let payload = serde_json::to_vec(event).context("Failed to serialize event to JSON")?;
+        self.send_bytes(
+            subject,
+            payload,
+            event.event_id.as_deref(),
+            &event.stream,
+            request_id,
+        )
+        .await
+    }
+
+    /// Hand a serialized event to the sink
+    async fn send_bytes(
+        &self,
+        subject: String,
+        payload: Vec<u8>,
+        event_id: Option<&str>,
+        stream: &str,
+        request_id: Option<&str>,
+    ) -> Result<AckFuture> {
         debug!(
-            event_id = %event.event_id.as_ref().unwrap(),
-            stream = %event.stream,
+            event_id = %event_id.unwrap_or_default(),
+            stream = %stream,
             subject = %subject,
+            request_id = request_id,
             "Publishing event to NATS"
         );
 
-        self.jetstream
-            .publish(subject.clone(), payload.into())
+        if self.connection.as_ref().is_some_and(|c| !c.is_connected()) {
+            if let Some(metrics) = &self.metrics {
+                metrics.record_publish_unavailable();
+            }
+            return Err(NatsUnavailable.into());
+        }
+
+        let started = Instant::now();
+        if let Some(metrics) = &self.metrics {
+            metrics.record_publish_started();
+        }
+
+        let request_id = request_id.map(str::to_string);
+        let ack = match self.sink.send(subject, request_id, payload).await {
+            Ok(ack) => ack,
+            Err(e) => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_publish_finished(started.elapsed());
+                }
+                return Err(classify(e, self.connection.as_ref(), self.metrics.as_ref()));
+            }
+        };
+
+        let metrics = self.metrics.clone();
+        let connection = self.connection.clone();
+        Ok(Box::pin(async move {
+            let result = ack.await;
+            if let Some(metrics) = &metrics {
+                metrics.record_publish_finished(started.elapsed());
+            }
+            result.map_err(|e| classify(e, connection.as_ref(), metrics.as_ref()))
+        }))
+    }
+}
+
+/// Mark a publish `error` as [`NatsUnavailable`] if `connection` is down
+fn classify(
+    error: anyhow::Error,
+    connection: Option<&ConnectionMonitor>,
+    metrics: Option<&MetricsTracker>,
+) -> anyhow::Error {
+    match connection {
+        Some(connection) if !connection.is_connected() => {
+            if let Some(metrics) = metrics {
+                metrics.record_publish_unavailable();
+            }
+            error.context(NatsUnavailable)
+        }
+        _ => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Acks every message after `ack_delay`; rejects payloads containing "reject"
+    #[derive(Default)]
+    struct MockSink {
+        ack_delay: Duration,
+        sent: Mutex<Vec<String>>,
+        unacked: Arc<AtomicUsize>,
+        max_unacked: Arc<AtomicUsize>,
+    }
+
+    impl PublishSink for MockSink {
+        fn send(
+            &self,
+            subject: String,
+            request_id: Option<String>,
+            payload: Vec<u8>,
+        ) -> BoxFuture<'_, Result<AckFuture>> {
+            Box::pin(async move {
+                let body = String::from_utf8(payload).unwrap();
+                if body.contains("unsendable") {
+                    anyhow::bail!("connection closed");
+                }
+                let request_id = request_id.map(|id| format!(" [{}]", id));
+                self.sent.lock().unwrap().push(format!(
+                    "{}{} {}",
+                    subject,
+                    request_id.unwrap_or_default(),
+                    body
+                ));
+
+                let now = self.unacked.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_unacked.fetch_max(now, Ordering::SeqCst);
+
+                let delay = self.ack_delay;
+                let unacked = Arc::clone(&self.unacked);
+                let ack: AckFuture = Box::pin(async move {
+                    tokio::time::sleep(delay).await;
+                    unacked.fetch_sub(1, Ordering::SeqCst);
+                    if body.contains("reject") {
+                        anyhow::bail!("stream full");
+                    }
+                    Ok(())
+                });
+                Ok(ack)
+            })
+        }
+    }
+
+    fn event(stream: &str, entity_id: &str) -> FluxEvent {
+        FluxEvent {
+            event_id: Some(format!("evt-{}", entity_id)),
+            stream: stream.to_string(),
+            source: "test".to_string(),
+            timestamp: 1,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: json!({"entity_id": entity_id, "properties": {}}),
+        }
+    }
+
+    fn publisher(sink: &Arc<MockSink>, max_in_flight: usize) -> EventPublisher {
+        EventPublisher::with_sink(Arc::clone(sink) as Arc<dyn PublishSink>)
+            .with_max_in_flight(max_in_flight)
+    }
+
+    #[tokio::test]
+    async fn test_batch_results_follow_input_order() {
+        let sink = Arc::new(MockSink::default());
+        let events = vec![
+            event("a", "one"),
+            event("a", "reject-me"),
+            event("b", "unsendable"),
+            event("b", "four"),
+        ];
+
+        let results = publisher(&sink, 8).publish_batch(&events).await;
+
+        assert!(results[0].is_ok());
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("stream full"));
+        assert!(results[2]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("connection closed"));
+        assert!(results[3].is_ok());
+
+        // Sent in request order
+        let sent = sink.sent.lock().unwrap();
+        assert_eq!(sent.len(), 3);
+        assert!(sent[0].starts_with("flux.events._default.a ") && sent[0].contains("one"));
+        assert!(sent[1].starts_with("flux.events._default.a ") && sent[1].contains("reject-me"));
+        assert!(sent[2].starts_with("flux.events._default.b ") && sent[2].contains("four"));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_sent_alongside_unchanged_payload() {
+        let sink = Arc::new(MockSink::default());
+        let publisher = publisher(&sink, 8);
+
+        publisher
+            .publish_with_request_id(&event("a", "one"), Some("req-1"))
             .await
-            .context(format!("Failed to publish event to subject '{}'", subject))?
+            .unwrap();
+        publisher.publish(&event("a", "two")).await.unwrap();
+
+        let expected = serde_json::to_string(&event("a", "one")).unwrap();
+        let sent = sink.sent.lock().unwrap();
+        assert_eq!(
+            sent[0],
+            format!("flux.events._default.a [req-1] {}", expected)
+        );
+        assert!(sent[1].starts_with("flux.events._default.a {"));
+    }
+
+    #[tokio::test]
+    async fn test_subject_follows_scheme() {
+        let sink = Arc::new(MockSink::default());
+        let event = event("sensors", "acme/one");
+
+        publisher(&sink, 8).publish(&event).await.unwrap();
+        publisher(&sink, 8)
+            .with_subject_scheme(SubjectScheme::Flat)
+            .publish(&event)
             .await
-            .context("Failed to await publish ack")?;
+            .unwrap();
+
+        let sent = sink.sent.lock().unwrap();
+        assert!(sent[0].starts_with("flux.events.acme.sensors {"));
+        assert!(sent[1].starts_with("flux.events.sensors {"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_respects_in_flight_window() {
+        let sink = Arc::new(MockSink {
+            ack_delay: Duration::from_millis(2),
+            ..Default::default()
+        });
+        let events: Vec<_> = (0..50).map(|i| event("a", &i.to_string())).collect();
+
+        let results = publisher(&sink, 4).publish_batch(&events).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(sink.max_unacked.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_throughput_scales_with_window() {
+        let events: Vec<_> = (0..64).map(|i| event("a", &i.to_string())).collect();
+
+        let mut elapsed = Vec::new();
+        for window in [1, 64] {
+            let sink = Arc::new(MockSink {
+                ack_delay: Duration::from_millis(10),
+                ..Default::default()
+            });
+            let started = Instant::now();
+            let results = publisher(&sink, window).publish_batch(&events).await;
+            elapsed.push(started.elapsed());
+            assert!(results.iter().all(|r| r.is_ok()));
+        }
 
-        Ok(())
+        // Sequential acks take >= 64 * 10ms; a full window overlaps them
+        assert!(elapsed[0] >= Duration::from_millis(640));
+        assert!(elapsed[1] * 4 < elapsed[0], "{:?}", elapsed);
     }
 
-    /// Publish multiple events in batch
-    pub async fn publish_batch(&self, events: &[FluxEvent]) -> Result<Vec<Result<()>>> {
-        let mut results = Vec::with_capacity(events.len());
+    #[tokio::test]
+    async fn test_window_is_shared_between_callers() {
+        let sink = Arc::new(MockSink {
+            ack_delay: Duration::from_millis(5),
+            ..Default::default()
+        });
+        let publisher = publisher(&sink, 3);
 
-        for event in events {
-            let result = self.publish(event).await;
-            results.push(result);
+        let tasks: Vec<_> = (0..12)
+            .map(|i| {
+                let publisher = publisher.clone();
+                tokio::spawn(async move { publisher.publish(&event("a", &i.to_string())).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
         }
 
-        Ok(results)
+        assert_eq!(sink.max_unacked.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_track_in_flight_and_latency() {
+        let sink = Arc::new(MockSink {
+            ack_delay: Duration::from_millis(5),
+            ..Default::default()
+        });
+        let metrics = MetricsTracker::new();
+        let publisher = publisher(&sink, 8).with_metrics(metrics.clone());
+
+        let events: Vec<_> = (0..4).map(|i| event("a", &i.to_string())).collect();
+        let batch = publisher.publish_batch(&events);
+        tokio::pin!(batch);
+
+        // Mid-flight: all four sent, none acked yet
+        tokio::select! {
+            _ = &mut batch => panic!("acks resolved too early"),
+            _ = tokio::time::sleep(Duration::from_millis(2)) => {}
+        }
+        assert_eq!(metrics.get_publish_in_flight(), 4);
+
+        batch.await;
+        assert_eq!(metrics.get_publish_in_flight(), 0);
+        assert!(metrics.get_publish_latency().p50_ms >= 5.0);
+    }
+
+    #[tokio::test]
+    async fn test_outage_fails_fast_as_unavailable() {
+        let sink = Arc::new(MockSink::default());
+        let metrics = MetricsTracker::new();
+        let connection = ConnectionMonitor::new();
+        let publisher = publisher(&sink, 8)
+            .with_metrics(metrics.clone())
+            .with_connection(connection.clone());
+
+        // Other failures while connected are not outages
+        let error = publisher
+            .publish(&event("a", "unsendable"))
+            .await
+            .unwrap_err();
+        assert!(!is_unavailable(&error));
+
+        connection.record_disconnected();
+        let error = publisher.publish(&event("a", "one")).await.unwrap_err();
+        assert!(is_unavailable(&error));
+        let results = publisher.publish_batch(&[event("a", "two")]).await;
+        assert!(is_unavailable(results[0].as_ref().unwrap_err()));
+        // Nothing handed to the connection
+        assert!(sink.sent.lock().unwrap().is_empty());
+        assert_eq!(metrics.get_publish_unavailable(), 2);
+
+        connection.record_connected();
+        publisher.publish(&event("a", "three")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ack_lost_to_outage_is_unavailable() {
+        let sink = Arc::new(MockSink {
+            ack_delay: Duration::from_millis(5),
+            ..Default::default()
+        });
+        let connection = ConnectionMonitor::new();
+        let publisher = publisher(&sink, 8).with_connection(connection.clone());
+
+        // Sent while connected; the connection drops before the ack fails
+        let rejected = event("a", "reject");
+        let publish = publisher.publish(&rejected);
+        tokio::pin!(publish);
+        tokio::select! {
+            _ = &mut publish => panic!("ack resolved too early"),
+            _ = tokio::time::sleep(Duration::from_millis(1)) => {}
+        }
+        connection.record_disconnected();
+        let error = publish.await.unwrap_err();
+        assert!(is_unavailable(&error));
+        assert!(format!("{:#}", error).contains("stream full"));
     }
 }
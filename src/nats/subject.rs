@@ -0,0 +1,304 @@
+//! Subjects events are published on.
+//!
+//! With the namespaced scheme an event goes to `flux.events.{namespace}.{stream}`,
+//! so NATS permissions and retention can be set per tenant. Every consumer
+//! reads `flux.events.>` and takes the namespace and stream from the payload,
+//! so events published under either scheme are read the same way; instances
+//! on the old scheme and the new one can share a stream during a rolling
+//! upgrade.
+
+use crate::event::FluxEvent;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Namespace token for events whose entity ID has no namespace
+pub const DEFAULT_SUBJECT_NAMESPACE: &str = "_default";
+
+/// Producers publish raw events to `flux.events.ingest.<namespace>`
+pub const INGEST_SUBJECT_PREFIX: &str = "flux.events.ingest.";
+
+/// Events the ingester turned away, as `RejectedEvent` JSON
+pub const REJECTED_SUBJECT: &str = "flux.events.rejected";
+
+/// True for subjects that carry unvalidated or rejected events
+///
+/// These are captured by the `flux.events.>` stream but are not canonical
+/// events; the state engine, replay and history skip them.
+pub fn is_ingest_subject(subject: &str) -> bool {
+    subject.starts_with(INGEST_SUBJECT_PREFIX) || subject == REJECTED_SUBJECT
+}
+
+/// Namespace tokens that would read as another kind of subject: the NATS
+/// ingester's subjects, or events without a namespace
+const RESERVED_NAMESPACE_TOKENS: [&str; 3] = ["ingest", "rejected", DEFAULT_SUBJECT_NAMESPACE];
+
+/// How event subjects are laid out (`[nats] subject_scheme`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubjectScheme {
+    /// `flux.events.{namespace}.{stream}`
+    #[default]
+    Namespaced,
+    /// `flux.events.{stream}`, as published before namespaced subjects
+    Flat,
+}
+
+/// `token` as a single subject token
+///
+/// Letters, digits, `-` and `_` are kept; every other byte (including `.`,
+/// `*`, `>`, whitespace and `~` itself) becomes `~` and two hex digits. An
+/// empty token becomes `~`.
+pub fn encode_subject_token(token: &str) -> String {
+    if token.is_empty() {
+        return "~".to_string();
+    }
+    let mut encoded = String::with_capacity(token.len());
+    for byte in token.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "~{:02x}", byte);
+        }
+    }
+    encoded
+}
+
+/// Subject token for `namespace` (`None` = no namespace)
+///
+/// Reserved names get their first character escaped, so a namespace called
+/// `ingest` can't be mistaken for ingester traffic.
+pub fn namespace_token(namespace: Option<&str>) -> String {
+    let Some(namespace) = namespace.filter(|namespace| !namespace.is_empty()) else {
+        return DEFAULT_SUBJECT_NAMESPACE.to_string();
+    };
+    if RESERVED_NAMESPACE_TOKENS.contains(&namespace) {
+        let mut chars = namespace.chars();
+        let first = chars.next().expect("reserved tokens are not empty");
+        return format!("~{:02x}{}", first as u32, chars.as_str());
+    }
+    encode_subject_token(namespace)
+}
+
+/// Subject `event` is published on under `scheme`
+///
+/// The namespace is the part of the payload's `entity_id` before the first
+/// `/`. Each `.`-separated part of the stream name is encoded on its own, so
+/// stream names keep their hierarchy.
+pub fn event_subject(event: &FluxEvent, scheme: SubjectScheme) -> String {
+    let entity_id = event.payload.get("entity_id").and_then(Value::as_str);
+    stream_subject(&event.stream, entity_id, scheme)
+}
+
+/// [`event_subject`] from the event's stream and `entity_id`
+pub fn stream_subject(stream: &str, entity_id: Option<&str>, scheme: SubjectScheme) -> String {
+    match scheme {
+        SubjectScheme::Flat => format!("flux.events.{}", stream),
+        SubjectScheme::Namespaced => {
+            let namespace = entity_id
+                .and_then(|entity_id| entity_id.split_once('/'))
+                .map(|(namespace, _)| namespace);
+            let stream: Vec<String> = stream.split('.').map(encode_subject_token).collect();
+            format!(
+                "flux.events.{}.{}",
+                namespace_token(namespace),
+                stream.join(".")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nats::is_ingest_subject;
+    use serde_json::json;
+
+    fn event(stream: &str, payload: Value) -> FluxEvent {
+        FluxEvent {
+            event_id: None,
+            stream: stream.to_string(),
+            source: "test".to_string(),
+            timestamp: 1,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload,
+        }
+    }
+
+    /// True if `subject` is a valid NATS subject of plain tokens
+    fn is_plain_subject(subject: &str) -> bool {
+        subject.split('.').all(|token| {
+            !token.is_empty()
+                && token
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"-_~".contains(&b))
+        })
+    }
+
+    #[test]
+    fn test_encode_keeps_safe_characters() {
+        assert_eq!(encode_subject_token("acme"), "acme");
+        assert_eq!(encode_subject_token("acme-corp_2"), "acme-corp_2");
+        assert_eq!(encode_subject_token("Acme"), "Acme");
+    }
+
+    #[test]
+    fn test_encode_escapes_everything_else() {
+        let cases = [
+            ("a.b", "a~2eb"),
+            ("a/b", "a~2fb"),
+            ("a b", "a~20b"),
+            ("a\tb", "a~09b"),
+            ("*", "~2a"),
+            (">", "~3e"),
+            ("a~b", "a~7eb"),
+            ("~2e", "~7e2e"),
+            ("%", "~25"),
+            ("", "~"),
+            ("é", "~c3~a9"),
+            ("日本", "~e6~97~a5~e6~9c~ac"),
+            ("🦀", "~f0~9f~a6~80"),
+            ("\0", "~00"),
+        ];
+        for (token, encoded) in cases {
+            assert_eq!(encode_subject_token(token), encoded, "{:?}", token);
+            assert!(
+                is_plain_subject(&encode_subject_token(token)),
+                "{:?}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_is_injective() {
+        let tokens = [
+            "", "~", "~7e", "a.b", "a~2eb", "a/b", "a~2fb", ".", "..", "~2e", "ingest", "é",
+            "~c3~a9", "e\u{301}",
+        ];
+        let mut encoded: Vec<String> = tokens.iter().map(|t| encode_subject_token(t)).collect();
+        encoded.sort();
+        encoded.dedup();
+        assert_eq!(encoded.len(), tokens.len());
+    }
+
+    #[test]
+    fn test_encode_never_panics_on_any_char() {
+        for c in (0..=0x10ffff).step_by(97).filter_map(char::from_u32) {
+            let token = format!("x{}y.{}", c, c);
+            assert!(is_plain_subject(&encode_subject_token(&token)), "{:?}", c);
+            let _ = namespace_token(Some(&token));
+        }
+    }
+
+    #[test]
+    fn test_reserved_namespaces_are_escaped() {
+        assert_eq!(namespace_token(None), "_default");
+        assert_eq!(namespace_token(Some("")), "_default");
+        assert_eq!(namespace_token(Some("_default")), "~5fdefault");
+        assert_eq!(namespace_token(Some("ingest")), "~69ngest");
+        assert_eq!(namespace_token(Some("rejected")), "~72ejected");
+        assert_eq!(namespace_token(Some("ingestion")), "ingestion");
+        assert_eq!(namespace_token(Some("acme")), "acme");
+    }
+
+    #[test]
+    fn test_namespaced_subjects() {
+        let cases = [
+            (
+                json!({"entity_id": "acme/sensor-1"}),
+                "sensors",
+                "flux.events.acme.sensors",
+            ),
+            (
+                json!({"entity_id": "acme/site/sensor-1"}),
+                "sensors.zone1",
+                "flux.events.acme.sensors.zone1",
+            ),
+            (
+                json!({"entity_id": "sensor-1"}),
+                "sensors",
+                "flux.events._default.sensors",
+            ),
+            (
+                json!({"entity_id": "/sensor-1"}),
+                "sensors",
+                "flux.events._default.sensors",
+            ),
+            (
+                json!({"entity_id": 42}),
+                "sensors",
+                "flux.events._default.sensors",
+            ),
+            (
+                json!({"from": "a", "to": "b"}),
+                "flux.messages",
+                "flux.events._default.flux.messages",
+            ),
+            (
+                json!({"entity_id": "my.ns/x"}),
+                "sensors",
+                "flux.events.my~2ens.sensors",
+            ),
+            (
+                json!({"entity_id": "a*b >c/x"}),
+                "s",
+                "flux.events.a~2ab~20~3ec.s",
+            ),
+            (
+                json!({"entity_id": "ünï/x"}),
+                "s",
+                "flux.events.~c3~bcn~c3~af.s",
+            ),
+            (
+                json!({"entity_id": "ingest/x"}),
+                "sensors",
+                "flux.events.~69ngest.sensors",
+            ),
+            (
+                json!({"entity_id": "rejected/x"}),
+                "s",
+                "flux.events.~72ejected.s",
+            ),
+            (
+                json!({"entity_id": "acme/x"}),
+                "a..b",
+                "flux.events.acme.a.~.b",
+            ),
+        ];
+        for (payload, stream, subject) in cases {
+            let event = event(stream, payload);
+            let published = event_subject(&event, SubjectScheme::Namespaced);
+            assert_eq!(published, subject);
+            assert!(is_plain_subject(&published), "{}", published);
+            assert!(!is_ingest_subject(&published), "{}", published);
+        }
+    }
+
+    #[test]
+    fn test_flat_subjects_are_unchanged() {
+        let event = event("sensors.zone1", json!({"entity_id": "acme/sensor-1"}));
+        assert_eq!(
+            event_subject(&event, SubjectScheme::Flat),
+            "flux.events.sensors.zone1"
+        );
+    }
+
+    #[test]
+    fn test_scheme_from_config() {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(default)]
+            subject_scheme: SubjectScheme,
+        }
+        let parse = |toml: &str| toml::from_str::<Config>(toml).map(|c| c.subject_scheme);
+        assert_eq!(parse("").unwrap(), SubjectScheme::Namespaced);
+        assert_eq!(
+            parse("subject_scheme = \"flat\"").unwrap(),
+            SubjectScheme::Flat
+        );
+        assert!(parse("subject_scheme = \"nested\"").is_err());
+    }
+}
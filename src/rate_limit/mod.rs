@@ -45,6 +45,12 @@ pub struct RateLimiter {
     buckets: DashMap<String, TokenBucket>,
 }
 
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RateLimiter {
     pub fn new() -> Self {
         Self {
@@ -109,7 +115,7 @@ mod tests {
         // With capacity=3600 tokens/minute (60/sec): 0.07 sec * 60 = 4.2 tokens refilled
         let limiter2 = RateLimiter::new();
         assert!(limiter2.check_and_consume("ns1", 3600)); // fresh bucket, allowed
-        // drain it
+                                                          // drain it
         for _ in 0..3599 {
             limiter2.check_and_consume("ns1", 3600);
         }
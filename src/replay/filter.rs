@@ -0,0 +1,68 @@
+//! Event selection and ID rewriting for sandbox replays.
+
+use crate::event::FluxEvent;
+use crate::state::Entity;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// Picks the events of one namespace up to a point in time and moves their
+/// entities into a target namespace
+#[derive(Clone, Debug)]
+pub struct ReplayFilter {
+    namespace: String,
+    target_namespace: String,
+    until: DateTime<Utc>,
+}
+
+impl ReplayFilter {
+    pub fn new(namespace: &str, target_namespace: &str, until: DateTime<Utc>) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            target_namespace: target_namespace.to_string(),
+            until,
+        }
+    }
+
+    /// True once the stream has moved past `until`; later messages are not read
+    pub fn is_past(&self, published: DateTime<Utc>) -> bool {
+        published > self.until
+    }
+
+    /// `entity_id` moved into the target namespace, if it is in the source namespace
+    pub fn rewrite_id(&self, entity_id: &str) -> Option<String> {
+        let local = entity_id
+            .strip_prefix(self.namespace.as_str())?
+            .strip_prefix('/')?;
+        Some(format!("{}/{}", self.target_namespace, local))
+    }
+
+    /// Copy of `event` for the target namespace, or None if it is not replayed
+    ///
+    /// Only events with `entity_id` in the payload and a timestamp at or
+    /// before `until` are replayed.
+    pub fn rewrite_event(&self, event: &FluxEvent) -> Option<FluxEvent> {
+        if event.timestamp > self.until.timestamp_millis() {
+            return None;
+        }
+        let entity_id = event.payload.get("entity_id")?.as_str()?;
+        let rewritten = self.rewrite_id(entity_id)?;
+
+        let mut event = event.clone();
+        event.payload["entity_id"] = Value::String(rewritten);
+        Some(event)
+    }
+
+    /// Copy of a snapshot entity for the target namespace
+    pub fn rewrite_entity(&self, entity: &Entity) -> Option<Entity> {
+        let id = self.rewrite_id(&entity.id)?;
+        Some(Entity {
+            id,
+            ..entity.clone()
+        })
+    }
+
+    /// Prefix shared by every entity this replay materializes
+    pub fn target_prefix(&self) -> String {
+        format!("{}/", self.target_namespace)
+    }
+}
@@ -0,0 +1,127 @@
+use super::*;
+use crate::state::Entity;
+use serde_json::json;
+
+fn until() -> DateTime<Utc> {
+    "2026-02-12T14:00:00Z".parse().unwrap()
+}
+
+fn event_at(entity_id: &str, properties: serde_json::Value, timestamp: DateTime<Utc>) -> FluxEvent {
+    let mut event = FluxEvent {
+        event_id: None,
+        stream: "sensors".to_string(),
+        source: "test".to_string(),
+        timestamp: timestamp.timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({"entity_id": entity_id, "properties": properties}),
+    };
+    event.validate_and_prepare().unwrap();
+    event
+}
+
+#[test]
+fn test_rewrite_id() {
+    let filter = ReplayFilter::new("prod", "prod-replay-1", until());
+    assert_eq!(
+        filter.rewrite_id("prod/sensor-01"),
+        Some("prod-replay-1/sensor-01".to_string())
+    );
+    assert_eq!(
+        filter.rewrite_id("prod/github/repo/1"),
+        Some("prod-replay-1/github/repo/1".to_string())
+    );
+    assert_eq!(filter.rewrite_id("production/sensor-01"), None);
+    assert_eq!(filter.rewrite_id("staging/sensor-01"), None);
+    assert_eq!(filter.rewrite_id("prod"), None);
+    assert_eq!(filter.target_prefix(), "prod-replay-1/");
+}
+
+#[test]
+fn test_rewrite_event_filters_namespace_and_time() {
+    let filter = ReplayFilter::new("prod", "prod-replay-1", until());
+    let before = until() - chrono::Duration::minutes(5);
+    let after = until() + chrono::Duration::seconds(1);
+
+    let event = event_at("prod/sensor-01", json!({"temp": 20}), before);
+    let rewritten = filter.rewrite_event(&event).unwrap();
+    assert_eq!(rewritten.payload["entity_id"], "prod-replay-1/sensor-01");
+    assert_eq!(rewritten.payload["properties"], event.payload["properties"]);
+    assert_eq!(rewritten.event_id, event.event_id);
+
+    assert!(filter
+        .rewrite_event(&event_at("prod/sensor-01", json!({}), until()))
+        .is_some());
+    assert!(filter
+        .rewrite_event(&event_at("prod/sensor-01", json!({}), after))
+        .is_none());
+    assert!(filter
+        .rewrite_event(&event_at("staging/sensor-01", json!({}), before))
+        .is_none());
+
+    let mut no_id = event.clone();
+    no_id.payload = json!({"properties": {"temp": 1}});
+    assert!(filter.rewrite_event(&no_id).is_none());
+
+    assert!(!filter.is_past(until()));
+    assert!(filter.is_past(after));
+}
+
+#[test]
+fn test_rewrite_entity_keeps_state() {
+    let filter = ReplayFilter::new("prod", "prod-replay-1", until());
+    let entity = Entity {
+        id: "prod/sensor-01".to_string(),
+        properties: [("temp".to_string(), json!(20))].into_iter().collect(),
+        last_updated: until(),
+        last_applied: None,
+        last_modified_sequence: 0,
+        property_times: Default::default(),
+    };
+
+    let rewritten = filter.rewrite_entity(&entity).unwrap();
+    assert_eq!(rewritten.id, "prod-replay-1/sensor-01");
+    assert_eq!(rewritten.properties, entity.properties);
+    assert_eq!(rewritten.last_updated, entity.last_updated);
+    assert!(filter
+        .rewrite_entity(&Entity {
+            id: "staging/sensor-01".to_string(),
+            ..entity
+        })
+        .is_none());
+}
+
+#[test]
+fn test_rewritten_events_materialize_sandbox() {
+    let filter = ReplayFilter::new("prod", "prod-replay-1", until());
+    let engine = StateEngine::new();
+    let t = |minutes: i64| until() - chrono::Duration::minutes(minutes);
+
+    let events = [
+        event_at("prod/sensor-01", json!({"temp": 20}), t(30)),
+        event_at("prod/sensor-02", json!({"temp": 5}), t(20)),
+        event_at("staging/sensor-01", json!({"temp": 99}), t(20)),
+        event_at("prod/sensor-01", json!({"temp": 21}), t(10)),
+        FluxEvent {
+            timestamp: t(5).timestamp_millis(),
+            ..FluxEvent::tombstone("prod/sensor-02", "test")
+        },
+        // After the cutoff
+        event_at(
+            "prod/sensor-01",
+            json!({"temp": 30}),
+            until() + chrono::Duration::minutes(1),
+        ),
+    ];
+    for event in &events {
+        if let Some(event) = filter.rewrite_event(event) {
+            engine.process_event(&event, None);
+        }
+    }
+
+    let sandbox = engine.get_all_entities();
+    assert_eq!(sandbox.len(), 1);
+    assert_eq!(sandbox[0].id, "prod-replay-1/sensor-01");
+    assert_eq!(sandbox[0].properties["temp"], json!(21));
+}
@@ -0,0 +1,141 @@
+use super::scenario::{PropertySpec, Scenario};
+use crate::event::FluxEvent;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Source the generated events carry
+pub const SIMULATOR_SOURCE: &str = "flux-simulate";
+
+/// Produces the events of a [`Scenario`], one at a time
+///
+/// Each entity remembers its property values, so numbers walk rather than
+/// jump. With a seed the sequence of entities and values is reproducible.
+pub struct Generator {
+    scenario: Scenario,
+    rng: StdRng,
+    /// Property values by entity index; absent until first written, and
+    /// again after a deletion
+    values: HashMap<usize, Vec<Value>>,
+}
+
+impl Generator {
+    pub fn new(scenario: Scenario) -> Self {
+        let rng = match scenario.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            scenario,
+            rng,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Entity ID of entity `index`: `{namespace}/entity-{n}`, with entities
+    /// dealt round-robin over the namespaces
+    pub fn entity_id(&self, index: usize) -> String {
+        format!(
+            "{}/entity-{}",
+            self.scenario.namespace(index % self.scenario.namespaces),
+            index
+        )
+    }
+
+    /// Index of the next entity to change, following the scenario's skew
+    pub fn pick_entity(&mut self) -> usize {
+        let u: f64 = self.rng.gen();
+        let index = (u.powf(self.scenario.skew) * self.scenario.entities as f64) as usize;
+        index.min(self.scenario.entities - 1)
+    }
+
+    /// Next event, stamped `timestamp_ms`: an update of every property of a
+    /// picked entity, or sometimes a tombstone deleting it
+    pub fn next_event(&mut self, timestamp_ms: i64) -> FluxEvent {
+        let index = self.pick_entity();
+        let entity_id = self.entity_id(index);
+
+        if self.values.contains_key(&index) && self.rng.gen_bool(self.scenario.delete_probability) {
+            self.values.remove(&index);
+            let mut event = FluxEvent::tombstone(&entity_id, SIMULATOR_SOURCE);
+            event.timestamp = timestamp_ms;
+            return event;
+        }
+
+        let specs = &self.scenario.properties;
+        let values = match self.values.remove(&index) {
+            Some(previous) => step(specs, previous, &mut self.rng),
+            None => initial_values(specs, &mut self.rng),
+        };
+        let properties: Map<String, Value> = self
+            .scenario
+            .properties
+            .iter()
+            .map(|spec| spec.name().to_string())
+            .zip(values.iter().cloned())
+            .collect();
+        self.values.insert(index, values);
+
+        FluxEvent {
+            event_id: None,
+            stream: self.scenario.stream.clone(),
+            source: SIMULATOR_SOURCE.to_string(),
+            timestamp: timestamp_ms,
+            received_at: None,
+            key: Some(entity_id.clone()),
+            schema: None,
+            payload: serde_json::json!({
+                "entity_id": entity_id,
+                "properties": properties,
+            }),
+        }
+    }
+}
+
+fn initial_values(specs: &[PropertySpec], rng: &mut StdRng) -> Vec<Value> {
+    specs
+        .iter()
+        .map(|spec| match spec {
+            PropertySpec::Walk { start, .. } => Value::from(*start),
+            PropertySpec::Status { values, .. } => {
+                Value::from(values[rng.gen_range(0..values.len())].clone())
+            }
+        })
+        .collect()
+}
+
+/// Moves each value one step: walks by a random amount, statuses sometimes
+fn step(specs: &[PropertySpec], previous: Vec<Value>, rng: &mut StdRng) -> Vec<Value> {
+    specs
+        .iter()
+        .zip(previous)
+        .map(|(spec, value)| match spec {
+            PropertySpec::Walk { step, min, max, .. } => {
+                let current = value.as_f64().unwrap_or(*min);
+                let delta = if *step > 0.0 {
+                    rng.gen_range(-*step..=*step)
+                } else {
+                    0.0
+                };
+                Value::from(round(current + delta).clamp(*min, *max))
+            }
+            PropertySpec::Status {
+                values,
+                change_probability,
+                ..
+            } => {
+                if rng.gen_bool(*change_probability) {
+                    Value::from(values[rng.gen_range(0..values.len())].clone())
+                } else {
+                    value
+                }
+            }
+        })
+        .collect()
+}
+
+/// Rounds to three decimals, so walked values stay readable
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
@@ -0,0 +1,223 @@
+//! Synthetic load for demos and capacity tests (`flux simulate`).
+//!
+//! A [`Scenario`] describes a world of entities spread over namespaces;
+//! the [`Generator`] turns it into a stream of updates (random-walk numbers,
+//! status values, occasional deletions), and [`run`] publishes them to a
+//! [`Target`] at the scenario's rate, reporting what it achieved.
+
+use crate::event::FluxEvent;
+use crate::nats::EventPublisher;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+mod generator;
+mod scenario;
+#[cfg(test)]
+mod tests;
+
+pub use generator::{Generator, SIMULATOR_SOURCE};
+pub use scenario::{PropertySpec, Scenario};
+
+/// Where generated events are published
+pub enum Target {
+    /// `POST /api/events` on a running instance
+    Http {
+        client: reqwest::Client,
+        /// Base URL, e.g. `http://localhost:3000`
+        url: String,
+        /// Bearer token per namespace
+        tokens: HashMap<String, String>,
+        /// Token for namespaces not in `tokens`
+        default_token: Option<String>,
+    },
+    /// Straight onto the event stream, bypassing the HTTP API
+    Nats(EventPublisher),
+}
+
+impl Target {
+    /// `client` from [`crate::http_client::HttpClientConfig`]
+    pub fn http(
+        client: reqwest::Client,
+        url: &str,
+        tokens: HashMap<String, String>,
+        default_token: Option<String>,
+    ) -> Self {
+        Target::Http {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            tokens,
+            default_token,
+        }
+    }
+
+    async fn publish(&self, event: &FluxEvent) -> Result<()> {
+        match self {
+            Target::Http {
+                client,
+                url,
+                tokens,
+                default_token,
+            } => {
+                let namespace = event
+                    .payload
+                    .get("entity_id")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| id.split_once('/'))
+                    .map(|(namespace, _)| namespace);
+                let token = namespace
+                    .and_then(|namespace| tokens.get(namespace))
+                    .or(default_token.as_ref());
+
+                let mut request = client.post(format!("{}/api/events", url)).json(event);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                let response = request.send().await.context("request failed")?;
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    bail!("HTTP {}: {}", status, body);
+                }
+                Ok(())
+            }
+            Target::Nats(publisher) => publisher.publish(event).await,
+        }
+    }
+}
+
+/// Outcome of a [`run`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// Events published successfully
+    pub published: u64,
+    pub failed: u64,
+    pub elapsed: Duration,
+    /// Successful publishes per second over the whole run
+    pub events_per_second: f64,
+    pub p50_latency: Duration,
+    pub p99_latency: Duration,
+}
+
+impl Report {
+    fn new(failed: u64, elapsed: Duration, mut latencies: Vec<Duration>) -> Self {
+        latencies.sort_unstable();
+        let published = latencies.len() as u64;
+        let seconds = elapsed.as_secs_f64();
+        Self {
+            published,
+            failed,
+            elapsed,
+            events_per_second: if seconds > 0.0 {
+                published as f64 / seconds
+            } else {
+                0.0
+            },
+            p50_latency: percentile(&latencies, 50.0),
+            p99_latency: percentile(&latencies, 99.0),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "published {} events ({} failed) in {:.1}s",
+            self.published,
+            self.failed,
+            self.elapsed.as_secs_f64()
+        )?;
+        writeln!(f, "throughput: {:.1} events/s", self.events_per_second)?;
+        write!(
+            f,
+            "publish latency: p50 {:.2}ms, p99 {:.2}ms",
+            self.p50_latency.as_secs_f64() * 1000.0,
+            self.p99_latency.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Nearest-rank percentile `p` (0–100) of `sorted`; zero when empty
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Publish `scenario`'s events to `target` until its duration or event
+/// count runs out
+///
+/// Event `n` is sent at `n / events_per_second` seconds after the start, with
+/// up to `concurrency` publishes in flight; a slow target lowers the
+/// achieved rate rather than queueing without bound.
+pub async fn run(scenario: Scenario, target: Target) -> Result<Report> {
+    scenario.validate()?;
+    let rate = f64::from(scenario.events_per_second);
+    let max_events = scenario.max_events.unwrap_or(u64::MAX);
+    let duration = scenario.duration_seconds.map(Duration::from_secs);
+    let concurrency = scenario.concurrency;
+    info!(
+        entities = scenario.entities,
+        namespaces = scenario.namespaces,
+        events_per_second = scenario.events_per_second,
+        "Simulation started"
+    );
+
+    let target = Arc::new(target);
+    let in_flight = Arc::new(Semaphore::new(concurrency));
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let failed = Arc::new(AtomicU64::new(0));
+    let mut generator = Generator::new(scenario);
+
+    let start = Instant::now();
+    let mut sent = 0u64;
+    while sent < max_events {
+        let offset = Duration::from_secs_f64(sent as f64 / rate);
+        if duration.is_some_and(|duration| offset >= duration) {
+            break;
+        }
+        tokio::time::sleep_until((start + offset).into()).await;
+
+        let permit = Arc::clone(&in_flight).acquire_owned().await?;
+        let mut event = generator.next_event(Utc::now().timestamp_millis());
+        event
+            .validate_and_prepare()
+            .context("generated an invalid event")?;
+        let target = Arc::clone(&target);
+        let latencies = Arc::clone(&latencies);
+        let failed = Arc::clone(&failed);
+        tokio::spawn(async move {
+            let sent_at = Instant::now();
+            match target.publish(&event).await {
+                Ok(()) => latencies.lock().unwrap().push(sent_at.elapsed()),
+                Err(e) => {
+                    // Every failure is counted; only the first few are logged
+                    if failed.fetch_add(1, Ordering::Relaxed) < 10 {
+                        warn!(error = %e, "Publish failed");
+                    }
+                }
+            }
+            drop(permit);
+        });
+        sent += 1;
+    }
+
+    // Wait for the last publishes to finish
+    let _ = in_flight.acquire_many(concurrency as u32).await?;
+    let elapsed = start.elapsed();
+    let latencies = std::mem::take(&mut *latencies.lock().unwrap());
+    Ok(Report::new(
+        failed.load(Ordering::Relaxed),
+        elapsed,
+        latencies,
+    ))
+}
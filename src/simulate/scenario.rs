@@ -0,0 +1,184 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A synthetic world: its entities, how they change and how fast
+///
+/// Loaded from a TOML scenario file; every field has a default, so an empty
+/// file (or none) gives a small world of sensors.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// Total entities, spread evenly over the namespaces
+    pub entities: usize,
+    /// Namespaces, named `{namespace_prefix}-{n}`
+    pub namespaces: usize,
+    pub namespace_prefix: String,
+    /// Stream the events are published on
+    pub stream: String,
+    /// Target publish rate
+    pub events_per_second: u32,
+    /// Stop after this long...
+    pub duration_seconds: Option<u64>,
+    /// ...or after this many events, whichever comes first
+    pub max_events: Option<u64>,
+    /// Chance that an event deletes its entity instead of updating it; a
+    /// deleted entity is recreated by its next update
+    pub delete_probability: f64,
+    /// How entity picks are spread: 1.0 is uniform, higher values favour
+    /// low-numbered entities (a few hot entities, a long cold tail)
+    pub skew: f64,
+    /// Publishes allowed to wait for their ack at once
+    pub concurrency: usize,
+    /// Fixed RNG seed for a reproducible event sequence
+    pub seed: Option<u64>,
+    /// Bearer token per namespace, for HTTP targets with auth enabled
+    pub tokens: HashMap<String, String>,
+    pub properties: Vec<PropertySpec>,
+}
+
+/// One property every entity carries
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PropertySpec {
+    /// Number that moves by up to `step` either way per event, kept
+    /// within `min..=max`
+    Walk {
+        name: String,
+        start: f64,
+        step: f64,
+        min: f64,
+        max: f64,
+    },
+    /// One of `values`, switching to a random one with `change_probability`
+    /// per event
+    Status {
+        name: String,
+        values: Vec<String>,
+        #[serde(default = "default_change_probability")]
+        change_probability: f64,
+    },
+}
+
+fn default_change_probability() -> f64 {
+    0.05
+}
+
+impl PropertySpec {
+    pub fn name(&self) -> &str {
+        match self {
+            PropertySpec::Walk { name, .. } | PropertySpec::Status { name, .. } => name,
+        }
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            entities: 100,
+            namespaces: 1,
+            namespace_prefix: "sim".to_string(),
+            stream: "simulation".to_string(),
+            events_per_second: 100,
+            duration_seconds: Some(10),
+            max_events: None,
+            delete_probability: 0.0,
+            skew: 1.0,
+            concurrency: 64,
+            seed: None,
+            tokens: HashMap::new(),
+            properties: vec![
+                PropertySpec::Walk {
+                    name: "temperature".to_string(),
+                    start: 20.0,
+                    step: 0.5,
+                    min: -10.0,
+                    max: 50.0,
+                },
+                PropertySpec::Status {
+                    name: "status".to_string(),
+                    values: vec!["ok".to_string(), "warn".to_string(), "error".to_string()],
+                    change_probability: default_change_probability(),
+                },
+            ],
+        }
+    }
+}
+
+impl Scenario {
+    /// Reads a scenario file; fields it leaves out keep their defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse scenario {}", path.display()))
+    }
+
+    /// Name of namespace `index`
+    pub fn namespace(&self, index: usize) -> String {
+        format!("{}-{}", self.namespace_prefix, index)
+    }
+
+    /// Checks the scenario can run, naming the first bad field
+    pub fn validate(&self) -> Result<()> {
+        if self.entities == 0 {
+            bail!("entities must be at least 1");
+        }
+        if self.namespaces == 0 || self.namespaces > self.entities {
+            bail!("namespaces must be between 1 and the number of entities");
+        }
+        if self.events_per_second == 0 {
+            bail!("events_per_second must be at least 1");
+        }
+        if self.duration_seconds.is_none() && self.max_events.is_none() {
+            bail!("set duration_seconds or max_events so the run ends");
+        }
+        if !(0.0..=1.0).contains(&self.delete_probability) {
+            bail!("delete_probability must be between 0 and 1");
+        }
+        if self.skew.is_nan() || self.skew < 1.0 {
+            bail!("skew must be 1.0 (uniform) or more");
+        }
+        if self.concurrency == 0 {
+            bail!("concurrency must be at least 1");
+        }
+        if self.properties.is_empty() {
+            bail!("at least one property is required");
+        }
+        for property in &self.properties {
+            match property {
+                PropertySpec::Walk {
+                    name,
+                    start,
+                    step,
+                    min,
+                    max,
+                } => {
+                    if !(*min..=*max).contains(start) || *step < 0.0 {
+                        bail!(
+                            "property '{}': start must lie within min..=max and step must not be negative",
+                            name
+                        );
+                    }
+                }
+                PropertySpec::Status {
+                    name,
+                    values,
+                    change_probability,
+                } => {
+                    if values.is_empty() {
+                        bail!("property '{}': values must not be empty", name);
+                    }
+                    if !(0.0..=1.0).contains(change_probability) {
+                        bail!(
+                            "property '{}': change_probability must be between 0 and 1",
+                            name
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+use super::*;
+use serde_json::json;
+use std::collections::HashSet;
+
+const TS: i64 = 1_707_668_400_000;
+
+fn seeded(scenario: Scenario) -> Generator {
+    Generator::new(Scenario {
+        seed: Some(7),
+        ..scenario
+    })
+}
+
+fn is_tombstone(event: &FluxEvent) -> bool {
+    event.payload["properties"]["__deleted__"] == json!(true)
+}
+
+#[test]
+fn test_entity_ids_spread_over_namespaces() {
+    let generator = seeded(Scenario {
+        entities: 6,
+        namespaces: 3,
+        ..Scenario::default()
+    });
+    let ids: Vec<String> = (0..6).map(|i| generator.entity_id(i)).collect();
+    assert_eq!(
+        ids,
+        [
+            "sim-0/entity-0",
+            "sim-1/entity-1",
+            "sim-2/entity-2",
+            "sim-0/entity-3",
+            "sim-1/entity-4",
+            "sim-2/entity-5",
+        ]
+    );
+}
+
+#[test]
+fn test_uniform_picks_cover_every_entity() {
+    let mut generator = seeded(Scenario {
+        entities: 20,
+        ..Scenario::default()
+    });
+    let picked: HashSet<usize> = (0..2_000).map(|_| generator.pick_entity()).collect();
+    assert_eq!(picked.len(), 20);
+    assert!(picked.iter().all(|&i| i < 20));
+}
+
+#[test]
+fn test_skew_favours_low_numbered_entities() {
+    let mut generator = seeded(Scenario {
+        entities: 100,
+        skew: 3.0,
+        ..Scenario::default()
+    });
+    let picks: Vec<usize> = (0..10_000).map(|_| generator.pick_entity()).collect();
+    let hot = picks.iter().filter(|&&i| i < 10).count();
+    // u^3 < 0.1 for u < 0.464: close to half the picks hit the first 10%
+    assert!(hot > 4_000, "hot picks: {}", hot);
+    assert!(picks.iter().all(|&i| i < 100));
+}
+
+#[test]
+fn test_same_seed_same_events() {
+    let scenario = Scenario {
+        delete_probability: 0.1,
+        ..Scenario::default()
+    };
+    let mut a = seeded(scenario.clone());
+    let mut b = seeded(scenario);
+    for _ in 0..200 {
+        let (a, b) = (a.next_event(TS), b.next_event(TS));
+        assert_eq!(a.payload, b.payload);
+        assert_eq!(a.stream, b.stream);
+    }
+}
+
+#[test]
+fn test_events_are_valid_state_updates() {
+    let mut generator = seeded(Scenario::default());
+    for _ in 0..100 {
+        let mut event = generator.next_event(TS);
+        assert_eq!(event.source, SIMULATOR_SOURCE);
+        assert_eq!(event.stream, "simulation");
+        assert_eq!(event.timestamp, TS);
+        assert_eq!(event.key.as_deref(), event.payload["entity_id"].as_str());
+        let properties = event.payload["properties"].as_object().unwrap();
+        assert!(properties["temperature"].is_f64());
+        assert!(["ok", "warn", "error"].contains(&properties["status"].as_str().unwrap()));
+        event.validate_and_prepare().unwrap();
+    }
+}
+
+#[test]
+fn test_walk_moves_by_step_within_bounds() {
+    let mut generator = seeded(Scenario {
+        entities: 1,
+        properties: vec![PropertySpec::Walk {
+            name: "level".to_string(),
+            start: 1.0,
+            step: 0.5,
+            min: 0.0,
+            max: 2.0,
+        }],
+        ..Scenario::default()
+    });
+
+    let mut previous = 1.0;
+    let mut seen = HashSet::new();
+    for i in 0..1_000 {
+        let event = generator.next_event(TS);
+        let level = event.payload["properties"]["level"].as_f64().unwrap();
+        if i == 0 {
+            assert_eq!(level, 1.0);
+        }
+        assert!((0.0..=2.0).contains(&level), "{}", level);
+        assert!((level - previous).abs() <= 0.5 + 1e-9);
+        seen.insert((level * 10.0).round() as i64);
+        previous = level;
+    }
+    // It actually walks, reaching both ends of the range
+    assert!(seen.contains(&0) || seen.contains(&1));
+    assert!(seen.contains(&20) || seen.contains(&19));
+}
+
+#[test]
+fn test_status_changes_at_its_probability() {
+    let spec = |change_probability| PropertySpec::Status {
+        name: "mode".to_string(),
+        values: vec!["a".to_string(), "b".to_string()],
+        change_probability,
+    };
+
+    let mut steady = seeded(Scenario {
+        entities: 1,
+        properties: vec![spec(0.0)],
+        ..Scenario::default()
+    });
+    let first = steady.next_event(TS).payload["properties"]["mode"].clone();
+    for _ in 0..100 {
+        assert_eq!(steady.next_event(TS).payload["properties"]["mode"], first);
+    }
+
+    let mut flapping = seeded(Scenario {
+        entities: 1,
+        properties: vec![spec(1.0)],
+        ..Scenario::default()
+    });
+    let modes: HashSet<String> = (0..100)
+        .map(|_| flapping.next_event(TS).payload["properties"]["mode"].to_string())
+        .collect();
+    assert_eq!(modes.len(), 2);
+}
+
+#[test]
+fn test_deleted_entities_are_recreated_from_start() {
+    let mut generator = seeded(Scenario {
+        entities: 1,
+        delete_probability: 0.5,
+        properties: vec![PropertySpec::Walk {
+            name: "level".to_string(),
+            start: 5.0,
+            step: 1.0,
+            min: 0.0,
+            max: 10.0,
+        }],
+        ..Scenario::default()
+    });
+
+    let events: Vec<FluxEvent> = (0..200).map(|_| generator.next_event(TS)).collect();
+    assert!(!is_tombstone(&events[0]), "a new entity is created first");
+    let mut tombstones = 0;
+    for pair in events.windows(2) {
+        if is_tombstone(&pair[1]) {
+            tombstones += 1;
+            // Never two deletions in a row: there is nothing left to delete
+            assert!(!is_tombstone(&pair[0]));
+            assert_eq!(pair[1].payload["entity_id"], "sim-0/entity-0");
+            assert_eq!(pair[1].timestamp, TS);
+        } else if is_tombstone(&pair[0]) {
+            assert_eq!(pair[1].payload["properties"]["level"], json!(5.0));
+        }
+    }
+    assert!(tombstones > 20, "tombstones: {}", tombstones);
+}
+
+#[test]
+fn test_scenario_file_overrides_defaults() {
+    let scenario: Scenario = toml::from_str(
+        r#"
+        entities = 1000
+        namespaces = 4
+        events_per_second = 2000
+        max_events = 50000
+        delete_probability = 0.001
+
+        [tokens]
+        sim-0 = "token-0"
+
+        [[properties]]
+        kind = "walk"
+        name = "load"
+        start = 0.5
+        step = 0.05
+        min = 0.0
+        max = 1.0
+
+        [[properties]]
+        kind = "status"
+        name = "state"
+        values = ["running", "stopped"]
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(scenario.entities, 1000);
+    assert_eq!(scenario.namespaces, 4);
+    assert_eq!(scenario.events_per_second, 2000);
+    assert_eq!(scenario.max_events, Some(50_000));
+    assert_eq!(scenario.duration_seconds, Some(10));
+    assert_eq!(scenario.stream, "simulation");
+    assert_eq!(scenario.tokens["sim-0"], "token-0");
+    assert_eq!(scenario.properties.len(), 2);
+    assert_eq!(
+        scenario.properties[1],
+        PropertySpec::Status {
+            name: "state".to_string(),
+            values: vec!["running".to_string(), "stopped".to_string()],
+            change_probability: 0.05,
+        }
+    );
+    scenario.validate().unwrap();
+
+    assert!(toml::from_str::<Scenario>("entites = 10").is_err());
+}
+
+#[test]
+fn test_scenario_validation() {
+    let cases: Vec<(Scenario, &str)> = vec![
+        (
+            Scenario {
+                entities: 0,
+                ..Scenario::default()
+            },
+            "entities",
+        ),
+        (
+            Scenario {
+                namespaces: 200,
+                ..Scenario::default()
+            },
+            "namespaces",
+        ),
+        (
+            Scenario {
+                events_per_second: 0,
+                ..Scenario::default()
+            },
+            "events_per_second",
+        ),
+        (
+            Scenario {
+                duration_seconds: None,
+                max_events: None,
+                ..Scenario::default()
+            },
+            "duration_seconds",
+        ),
+        (
+            Scenario {
+                delete_probability: 1.5,
+                ..Scenario::default()
+            },
+            "delete_probability",
+        ),
+        (
+            Scenario {
+                skew: 0.5,
+                ..Scenario::default()
+            },
+            "skew",
+        ),
+        (
+            Scenario {
+                properties: vec![PropertySpec::Walk {
+                    name: "level".to_string(),
+                    start: 20.0,
+                    step: 1.0,
+                    min: 0.0,
+                    max: 10.0,
+                }],
+                ..Scenario::default()
+            },
+            "property 'level'",
+        ),
+        (
+            Scenario {
+                properties: vec![PropertySpec::Status {
+                    name: "mode".to_string(),
+                    values: vec![],
+                    change_probability: 0.1,
+                }],
+                ..Scenario::default()
+            },
+            "property 'mode'",
+        ),
+    ];
+    for (scenario, field) in cases {
+        let err = scenario.validate().unwrap_err().to_string();
+        assert!(err.contains(field), "{:?} should mention {}", err, field);
+    }
+    Scenario::default().validate().unwrap();
+}
+
+#[test]
+fn test_percentile() {
+    let ms = |n: u64| Duration::from_millis(n);
+    let sorted: Vec<Duration> = (1..=100).map(ms).collect();
+    assert_eq!(percentile(&sorted, 50.0), ms(50));
+    assert_eq!(percentile(&sorted, 99.0), ms(99));
+    assert_eq!(percentile(&sorted, 100.0), ms(100));
+    assert_eq!(percentile(&sorted, 0.0), ms(1));
+    assert_eq!(percentile(&[ms(7)], 99.0), ms(7));
+    assert_eq!(percentile(&[], 99.0), Duration::ZERO);
+}
+
+#[test]
+fn test_report() {
+    let report = Report::new(
+        2,
+        Duration::from_secs(2),
+        vec![Duration::from_millis(3), Duration::from_millis(1)],
+    );
+    assert_eq!(report.published, 2);
+    assert_eq!(report.failed, 2);
+    assert_eq!(report.events_per_second, 1.0);
+    assert_eq!(report.p50_latency, Duration::from_millis(1));
+    assert_eq!(report.p99_latency, Duration::from_millis(3));
+    assert_eq!(
+        report.to_string(),
+        "published 2 events (2 failed) in 2.0s\n\
+         throughput: 1.0 events/s\n\
+         publish latency: p50 1.00ms, p99 3.00ms"
+    );
+}
@@ -1,3 +1,4 @@
+use crate::s3::S3Config;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -10,11 +11,16 @@ pub struct SnapshotConfig {
     /// Interval between snapshots (minutes)
     pub interval_minutes: u64,
 
-    /// Directory to store snapshots
+    /// Directory to store snapshots (used unless `s3` is set)
     pub directory: PathBuf,
 
     /// Number of snapshots to keep (delete oldest)
     pub keep_count: usize,
+
+    /// S3-compatible bucket to keep snapshots in; credentials come from
+    /// FLUX_SNAPSHOT_S3_ACCESS_KEY_ID / FLUX_SNAPSHOT_S3_SECRET_ACCESS_KEY
+    #[serde(default)]
+    pub s3: Option<S3Config>,
 }
 
 impl Default for SnapshotConfig {
@@ -24,6 +30,7 @@ impl Default for SnapshotConfig {
             interval_minutes: 5,
             directory: PathBuf::from("/var/lib/flux/snapshots"),
             keep_count: 10,
+            s3: None,
         }
     }
 }
@@ -1,12 +1,13 @@
-use crate::snapshot::{config::SnapshotConfig, Snapshot};
+use crate::config::SharedRuntimeConfig;
+use crate::snapshot::{
+    config::SnapshotConfig, LocalSnapshotStore, Snapshot, SnapshotStore, StreamIdentity,
+};
 use crate::state::StateEngine;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use chrono::Utc;
-use std::fs;
-use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::interval;
+use tokio::time::{interval, interval_at, Instant};
 use tracing::{error, info};
 
 #[cfg(test)]
@@ -16,17 +17,57 @@ mod tests;
 pub struct SnapshotManager {
     state_engine: Arc<StateEngine>,
     config: SnapshotConfig,
+    store: Arc<dyn SnapshotStore>,
+    /// When set, the snapshot interval follows runtime config changes
+    runtime_config: Option<SharedRuntimeConfig>,
+    /// Recorded in each snapshot so recovery can tell a re-created stream
+    stream: Option<StreamIdentity>,
 }
 
 impl SnapshotManager {
-    /// Create new snapshot manager
+    /// Create new snapshot manager, saving to `config.directory`
     pub fn new(state_engine: Arc<StateEngine>, config: SnapshotConfig) -> Self {
+        let store = Arc::new(LocalSnapshotStore::new(&config.directory));
         Self {
             state_engine,
             config,
+            store,
+            runtime_config: None,
+            stream: None,
         }
     }
 
+    /// Save snapshots to `store` instead of the configured directory
+    pub fn with_store(mut self, store: Arc<dyn SnapshotStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Take the snapshot interval from runtime config and follow its changes
+    pub fn with_runtime_config(mut self, runtime_config: SharedRuntimeConfig) -> Self {
+        self.runtime_config = Some(runtime_config);
+        self
+    }
+
+    /// Record `stream` as the stream snapshot sequences refer to
+    pub fn with_stream_identity(mut self, stream: StreamIdentity) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    /// Current snapshot interval (runtime config wins over static config)
+    fn interval_minutes(&self) -> u64 {
+        match &self.runtime_config {
+            Some(rc) => {
+                rc.read()
+                    .expect("RuntimeConfig lock poisoned")
+                    .snapshot_interval_minutes
+            }
+            None => self.config.interval_minutes,
+        }
+        .max(1)
+    }
+
     /// Run background snapshot loop
     ///
     /// Periodically creates snapshots and cleans up old ones.
@@ -38,108 +79,69 @@ impl SnapshotManager {
         }
 
         info!(
-            interval_minutes = self.config.interval_minutes,
-            directory = %self.config.directory.display(),
+            interval_minutes = self.interval_minutes(),
+            location = %self.store.location(),
             keep_count = self.config.keep_count,
             "Starting snapshot manager"
         );
 
-        // Create directory if it doesn't exist
-        fs::create_dir_all(&self.config.directory)
-            .context("Failed to create snapshot directory")?;
-
-        let mut timer = interval(Duration::from_secs(self.config.interval_minutes * 60));
+        let mut interval_minutes = self.interval_minutes();
+        let mut timer = interval(Duration::from_secs(interval_minutes * 60));
+        let mut changes = self.runtime_config.as_ref().map(|rc| rc.subscribe());
 
         loop {
-            timer.tick().await;
-
-            if let Err(e) = self.create_and_save_snapshot().await {
-                error!(error = %e, "Failed to create snapshot");
+            tokio::select! {
+                _ = timer.tick() => {
+                    if let Err(e) = self.create_and_save_snapshot().await {
+                        error!(error = %e, "Failed to create snapshot");
+                    }
+                }
+                _ = async { changes.as_mut().unwrap().recv().await }, if changes.is_some() => {
+                    let new_minutes = self.interval_minutes();
+                    if new_minutes != interval_minutes {
+                        info!(old = interval_minutes, new = new_minutes, "Snapshot interval changed");
+                        interval_minutes = new_minutes;
+                        let period = Duration::from_secs(interval_minutes * 60);
+                        timer = interval_at(Instant::now() + period, period);
+                    }
+                }
             }
         }
     }
 
-    /// Create snapshot and save to filesystem
-    async fn create_and_save_snapshot(&self) -> Result<()> {
-        let seq = self.state_engine.get_last_processed_sequence();
-        let snapshot = Snapshot::from_state_engine(&self.state_engine, seq);
-        let entity_count = snapshot.entity_count();
+    /// Take a snapshot now, outside the schedule; returns its sequence
+    pub async fn snapshot_now(&self) -> Result<u64> {
+        self.create_and_save_snapshot().await
+    }
 
-        let path = self.snapshot_path(seq);
-        snapshot.save_to_file(&path)?;
+    /// Create snapshot and save it to the store, returning its sequence
+    async fn create_and_save_snapshot(&self) -> Result<u64> {
+        let seq = self.state_engine.get_last_processed_sequence();
+        let name = snapshot_name(seq);
+        let (data, entity_count) =
+            Snapshot::encode_state_engine(&self.state_engine, seq, self.stream.as_ref())?;
+        self.store.save(&name, data).await?;
 
         info!(
             sequence = seq,
             entities = entity_count,
-            path = %path.display(),
+            name = %name,
+            location = %self.store.location(),
             "Snapshot saved"
         );
 
-        self.cleanup_old_snapshots()?;
+        // Delete old snapshots, keeping only the most recent N
+        self.store.delete_old(self.config.keep_count).await?;
 
-        Ok(())
-    }
-
-    /// Generate snapshot file path with timestamp and sequence
-    ///
-    /// Format: snapshot-{timestamp}-seq{sequence}.json.gz
-    /// Example: snapshot-20260212T153045.123Z-seq12345.json.gz
-    fn snapshot_path(&self, sequence: u64) -> PathBuf {
-        let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
-        let filename = format!("snapshot-{}-seq{}.json.gz", timestamp, sequence);
-        self.config.directory.join(filename)
+        Ok(seq)
     }
+}
 
-    /// Delete old snapshots, keeping only the most recent N
-    fn cleanup_old_snapshots(&self) -> Result<()> {
-        let mut snapshots = self.list_snapshots()?;
-
-        // Keep only files to delete
-        if snapshots.len() <= self.config.keep_count {
-            return Ok(());
-        }
-
-        // Sort by filename (timestamp is lexicographically sortable)
-        snapshots.sort();
-
-        // Calculate how many to delete
-        let delete_count = snapshots.len() - self.config.keep_count;
-        let to_delete = &snapshots[..delete_count];
-
-        for path in to_delete {
-            if let Err(e) = fs::remove_file(path) {
-                error!(error = %e, path = %path.display(), "Failed to delete old snapshot");
-            } else {
-                info!(path = %path.display(), "Deleted old snapshot");
-            }
-        }
-
-        Ok(())
-    }
-
-    /// List all snapshot files in directory
-    fn list_snapshots(&self) -> Result<Vec<PathBuf>> {
-        let entries = fs::read_dir(&self.config.directory)
-            .context("Failed to read snapshot directory")?;
-
-        let mut snapshots = Vec::new();
-
-        for entry in entries {
-            let entry = entry.context("Failed to read directory entry")?;
-            let path = entry.path();
-
-            // Include both .json.gz (current) and .json (legacy) files
-            if path.is_file() {
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    if filename.starts_with("snapshot-")
-                        && (filename.ends_with(".json.gz") || filename.ends_with(".json"))
-                    {
-                        snapshots.push(path);
-                    }
-                }
-            }
-        }
-
-        Ok(snapshots)
-    }
+/// Snapshot file name with timestamp and sequence
+///
+/// Format: snapshot-{timestamp}-seq{sequence}.json.gz
+/// Example: snapshot-20260212T153045.123Z-seq12345.json.gz
+fn snapshot_name(sequence: u64) -> String {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    format!("snapshot-{}-seq{}.json.gz", timestamp, sequence)
 }
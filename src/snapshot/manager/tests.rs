@@ -1,25 +1,15 @@
 use super::*;
+use crate::snapshot::recovery::list_snapshots;
 use crate::state::StateEngine;
 use serde_json::json;
+use std::fs;
 use std::sync::Arc;
 use tempfile::TempDir;
 use tokio::time::{sleep, Duration};
 
 #[tokio::test]
 async fn test_snapshot_path_format() {
-    let temp_dir = TempDir::new().unwrap();
-    let config = SnapshotConfig {
-        enabled: true,
-        interval_minutes: 1,
-        directory: temp_dir.path().to_path_buf(),
-        keep_count: 5,
-    };
-
-    let engine = Arc::new(StateEngine::new());
-    let manager = SnapshotManager::new(engine.clone(), config);
-
-    let path = manager.snapshot_path(12345);
-    let filename = path.file_name().unwrap().to_str().unwrap();
+    let filename = snapshot_name(12345);
 
     // Verify format: snapshot-{timestamp}-seq{sequence}.json.gz
     assert!(filename.starts_with("snapshot-"));
@@ -35,6 +25,7 @@ async fn test_create_and_save_snapshot() {
         interval_minutes: 1,
         directory: temp_dir.path().to_path_buf(),
         keep_count: 5,
+        s3: None,
     };
 
     let engine = Arc::new(StateEngine::new());
@@ -49,7 +40,7 @@ async fn test_create_and_save_snapshot() {
     manager.create_and_save_snapshot().await.unwrap();
 
     // Verify snapshot file exists
-    let snapshots = manager.list_snapshots().unwrap();
+    let snapshots = list_snapshots(temp_dir.path()).unwrap();
     assert_eq!(snapshots.len(), 1);
 
     // Verify snapshot content
@@ -67,6 +58,7 @@ async fn test_cleanup_old_snapshots() {
         interval_minutes: 1,
         directory: temp_dir.path().to_path_buf(),
         keep_count: 3,
+        s3: None,
     };
 
     let engine = Arc::new(StateEngine::new());
@@ -80,7 +72,7 @@ async fn test_cleanup_old_snapshots() {
     }
 
     // Should have kept only 3 most recent
-    let snapshots = manager.list_snapshots().unwrap();
+    let snapshots = list_snapshots(temp_dir.path()).unwrap();
     assert_eq!(snapshots.len(), 3);
 }
 
@@ -92,6 +84,7 @@ async fn test_list_snapshots_filters_correctly() {
         interval_minutes: 1,
         directory: temp_dir.path().to_path_buf(),
         keep_count: 10,
+        s3: None,
     };
 
     let engine = Arc::new(StateEngine::new());
@@ -106,7 +99,7 @@ async fn test_list_snapshots_filters_correctly() {
     fs::write(temp_dir.path().join("data.json"), "{}").unwrap();
 
     // Should only list the valid snapshot
-    let snapshots = manager.list_snapshots().unwrap();
+    let snapshots = list_snapshots(temp_dir.path()).unwrap();
     assert_eq!(snapshots.len(), 1);
 
     let filename = snapshots[0].file_name().unwrap().to_str().unwrap();
@@ -122,6 +115,7 @@ async fn test_disabled_manager_exits_immediately() {
         interval_minutes: 1,
         directory: temp_dir.path().to_path_buf(),
         keep_count: 5,
+        s3: None,
     };
 
     let engine = Arc::new(StateEngine::new());
@@ -132,7 +126,7 @@ async fn test_disabled_manager_exits_immediately() {
     assert!(result.is_ok());
 
     // No snapshots should be created
-    let snapshots = manager.list_snapshots().unwrap();
+    let snapshots = list_snapshots(temp_dir.path()).unwrap();
     assert_eq!(snapshots.len(), 0);
 }
 
@@ -144,6 +138,7 @@ async fn test_snapshot_preserves_sequence_number() {
         interval_minutes: 1,
         directory: temp_dir.path().to_path_buf(),
         keep_count: 5,
+        s3: None,
     };
 
     let engine = Arc::new(StateEngine::new());
@@ -158,7 +153,7 @@ async fn test_snapshot_preserves_sequence_number() {
     manager.create_and_save_snapshot().await.unwrap();
 
     // Load snapshot and verify sequence
-    let snapshots = manager.list_snapshots().unwrap();
+    let snapshots = list_snapshots(temp_dir.path()).unwrap();
     let snapshot = Snapshot::load_from_file(&snapshots[0]).unwrap();
     assert_eq!(snapshot.sequence_number, 0);
 }
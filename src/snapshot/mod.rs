@@ -1,18 +1,23 @@
-use crate::state::{Entity, StateEngine};
+use crate::state::{AgentMessage, DeletedEntity, Entity, QuotaRecord, StateEngine};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
+use std::sync::Arc;
 
 pub mod config;
 pub mod manager;
 pub mod recovery;
+pub mod store;
+pub mod verify;
+
+pub use store::{LocalSnapshotStore, S3SnapshotStore, SnapshotStore};
 
 #[cfg(test)]
 mod tests;
@@ -31,6 +36,47 @@ pub struct Snapshot {
 
     /// All entities at snapshot time (entity_id -> Entity)
     pub entities: HashMap<String, Entity>,
+
+    /// Deleted entities that could still be undeleted at snapshot time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<DeletedEntity>,
+
+    /// Logged agent messages at snapshot time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<AgentMessage>,
+
+    /// Namespace quotas and their rejection counts at snapshot time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotas: Vec<QuotaRecord>,
+
+    /// Stream `sequence_number` refers to (unknown in older snapshots)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamIdentity>,
+}
+
+/// Identifies one incarnation of a NATS stream
+///
+/// A stream deleted and created again under the same name restarts its
+/// sequences, so the name alone can't tell whether a snapshot's sequence
+/// still means the same events.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamIdentity {
+    pub name: String,
+    /// When the stream was created
+    pub created: DateTime<Utc>,
+}
+
+impl StreamIdentity {
+    pub fn from_info(info: &async_nats::jetstream::stream::Info) -> Self {
+        Self {
+            name: info.config.name.clone(),
+            created: DateTime::from_timestamp(
+                info.created.unix_timestamp(),
+                info.created.nanosecond(),
+            )
+            .unwrap_or_default(),
+        }
+    }
 }
 
 impl Snapshot {
@@ -40,17 +86,20 @@ impl Snapshot {
     /// * `engine` - StateEngine to snapshot
     /// * `sequence_number` - Current NATS sequence number
     pub fn from_state_engine(engine: &StateEngine, sequence_number: u64) -> Self {
-        let entities: HashMap<String, Entity> = engine
-            .get_all_entities()
-            .into_iter()
-            .map(|entity| (entity.id.clone(), entity))
-            .collect();
+        let mut entities: HashMap<String, Entity> = HashMap::with_capacity(engine.entities.len());
+        engine.for_each_entity(|entity| {
+            entities.insert(entity.id.clone(), entity.clone());
+        });
 
         Self {
             snapshot_version: "1".to_string(),
             created_at: Utc::now(),
             sequence_number,
             entities,
+            trash: engine.deleted_entities(),
+            messages: engine.messages(),
+            quotas: engine.quotas(),
+            stream: None,
         }
     }
 
@@ -59,43 +108,53 @@ impl Snapshot {
         self.entities
     }
 
+    /// Load everything the snapshot holds into `engine`, as recovery does
+    pub fn restore_into(self, engine: &StateEngine) {
+        let sequence = self.sequence_number;
+        engine.load_from_snapshot(self.entities, sequence);
+        engine.load_trash(self.trash);
+        engine.load_messages(self.messages);
+        engine.load_quotas(self.quotas);
+    }
+
     /// Save snapshot to filesystem as compressed JSON (gzip)
     ///
     /// Uses atomic write: writes to .tmp file, fsyncs, then renames.
     /// This prevents partial/corrupt snapshots from being read.
     pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        // Serialize to JSON
-        let json = serde_json::to_string_pretty(self)
-            .context("Failed to serialize snapshot to JSON")?;
-
-        // Create temporary file path
-        let tmp_path = path.with_extension("tmp");
-
-        // Write compressed JSON to temporary file
-        {
-            let tmp_file = File::create(&tmp_path)
-                .context("Failed to create temporary snapshot file")?;
-
-            let mut encoder = GzEncoder::new(tmp_file, Compression::default());
-            encoder
-                .write_all(json.as_bytes())
-                .context("Failed to write compressed snapshot data")?;
-
-            // Finish compression and get underlying file
-            let file = encoder
-                .finish()
-                .context("Failed to finish compression")?;
-
-            // Fsync to ensure data is written to disk
-            file.sync_all()
-                .context("Failed to sync snapshot file to disk")?;
-        }
+        write_compressed_atomic(path, self)
+    }
 
-        // Atomically rename temp file to final path
-        fs::rename(&tmp_path, path)
-            .context("Failed to rename temporary snapshot file")?;
+    /// Stream current StateEngine state straight to a snapshot file
+    ///
+    /// Serializes from shared entity references instead of building an owned
+    /// `Snapshot`, so property maps are never cloned. Produces the same on-disk
+    /// format as [`save_to_file`](Self::save_to_file). Returns the entity count.
+    pub fn save_state_engine(
+        engine: &StateEngine,
+        sequence_number: u64,
+        stream: Option<&StreamIdentity>,
+        path: &Path,
+    ) -> Result<usize> {
+        let ((), entity_count) = with_state_view(engine, sequence_number, stream, |view| {
+            write_compressed_atomic(path, view)
+        })?;
+        Ok(entity_count)
+    }
 
-        Ok(())
+    /// Serialize current StateEngine state to snapshot file contents
+    ///
+    /// Same format as [`save_state_engine`](Self::save_state_engine), for
+    /// stores that aren't a local directory. Returns the gzipped bytes and
+    /// the entity count.
+    pub fn encode_state_engine(
+        engine: &StateEngine,
+        sequence_number: u64,
+        stream: Option<&StreamIdentity>,
+    ) -> Result<(Vec<u8>, usize)> {
+        with_state_view(engine, sequence_number, stream, |view| {
+            write_compressed(Vec::new(), view)
+        })
     }
 
     /// Load snapshot from compressed JSON file (.json.gz)
@@ -103,9 +162,7 @@ impl Snapshot {
     /// Supports backward compatibility: if .json.gz doesn't exist,
     /// tries loading uncompressed .json file.
     pub fn load_from_file(path: &Path) -> Result<Self> {
-        // Open file for reading
-        let file = File::open(path)
-            .context("Failed to open snapshot file")?;
+        let data = fs::read(path).context("Failed to open snapshot file")?;
 
         // Check if file is gzipped by extension
         let is_compressed = path
@@ -114,25 +171,23 @@ impl Snapshot {
             .map(|ext| ext == "gz")
             .unwrap_or(false);
 
+        Self::decode(&data, is_compressed)
+    }
+
+    /// Parse snapshot file contents, gzipped or (legacy) plain JSON
+    pub fn decode(data: &[u8], is_compressed: bool) -> Result<Self> {
         let snapshot = if is_compressed {
             // Decompress and deserialize
-            let mut decoder = GzDecoder::new(file);
+            let mut decoder = GzDecoder::new(data);
             let mut json = String::new();
             decoder
                 .read_to_string(&mut json)
                 .context("Failed to decompress snapshot file")?;
 
-            serde_json::from_str(&json)
-                .context("Failed to deserialize snapshot JSON")?
+            serde_json::from_str(&json).context("Failed to deserialize snapshot JSON")?
         } else {
             // Read uncompressed (backward compatibility)
-            let mut json = String::new();
-            let mut file = file;
-            file.read_to_string(&mut json)
-                .context("Failed to read snapshot file")?;
-
-            serde_json::from_str(&json)
-                .context("Failed to deserialize snapshot JSON")?
+            serde_json::from_slice(data).context("Failed to deserialize snapshot JSON")?
         };
 
         Ok(snapshot)
@@ -143,3 +198,97 @@ impl Snapshot {
         self.entities.len()
     }
 }
+
+/// Borrowed snapshot view with the same serialized layout as [`Snapshot`]
+#[derive(Serialize)]
+struct SnapshotView<'a> {
+    snapshot_version: &'a str,
+    created_at: DateTime<Utc>,
+    sequence_number: u64,
+    #[serde(serialize_with = "serialize_entity_refs")]
+    entities: &'a [Arc<Entity>],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    trash: &'a [DeletedEntity],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    messages: &'a [AgentMessage],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    quotas: &'a [QuotaRecord],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<&'a StreamIdentity>,
+}
+
+/// Call `write` with a view of the engine's current state; returns its
+/// result and the entity count
+fn with_state_view<R>(
+    engine: &StateEngine,
+    sequence_number: u64,
+    stream: Option<&StreamIdentity>,
+    write: impl FnOnce(&SnapshotView<'_>) -> Result<R>,
+) -> Result<(R, usize)> {
+    let entities = engine.entities_snapshot_refs();
+    let trash = engine.deleted_entities();
+    let messages = engine.messages();
+    let quotas = engine.quotas();
+    let view = SnapshotView {
+        snapshot_version: "1",
+        created_at: Utc::now(),
+        sequence_number,
+        entities: &entities,
+        trash: &trash,
+        messages: &messages,
+        quotas: &quotas,
+        stream,
+    };
+    Ok((write(&view)?, entities.len()))
+}
+
+fn serialize_entity_refs<S>(entities: &&[Arc<Entity>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(entities.iter().map(|entity| (&entity.id, entity.as_ref())))
+}
+
+/// Write value as gzip-compressed JSON using an atomic tmp + rename
+///
+/// Writes to .tmp file, fsyncs, then renames.
+/// This prevents partial/corrupt snapshots from being read.
+fn write_compressed_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    // Create temporary file path
+    let tmp_path = path.with_extension("tmp");
+
+    // Stream compressed JSON to temporary file
+    {
+        let tmp_file =
+            File::create(&tmp_path).context("Failed to create temporary snapshot file")?;
+
+        let file = write_compressed(tmp_file, value)?;
+
+        // Fsync to ensure data is written to disk
+        file.sync_all()
+            .context("Failed to sync snapshot file to disk")?;
+    }
+
+    // Atomically rename temp file to final path
+    fs::rename(&tmp_path, path).context("Failed to rename temporary snapshot file")?;
+
+    Ok(())
+}
+
+/// Stream value as gzip-compressed JSON into writer, returning the writer
+fn write_compressed<W: Write, T: Serialize>(writer: W, value: &T) -> Result<W> {
+    let mut encoder = BufWriter::new(GzEncoder::new(writer, Compression::default()));
+    serde_json::to_writer_pretty(&mut encoder, value)
+        .context("Failed to serialize snapshot to JSON")?;
+    encoder
+        .flush()
+        .context("Failed to write compressed snapshot data")?;
+
+    // Finish compression and get underlying writer
+    encoder
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to flush snapshot writer")?
+        .finish()
+        .context("Failed to finish compression")
+}
@@ -1,75 +1,165 @@
-use crate::snapshot::Snapshot;
-use anyhow::{Context, Result};
+use crate::snapshot::store::{is_snapshot_name, SnapshotStore};
+use crate::snapshot::{Snapshot, StreamIdentity};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
-use tracing::{error, info, warn};
+use tracing::warn;
 
-/// Load the most recent valid snapshot from directory
+/// Load the most recent valid snapshot from the store
 ///
 /// Returns None if no snapshots exist or all are corrupt.
 /// Tries snapshots from newest to oldest until one loads successfully.
-pub fn load_latest_snapshot(snapshot_dir: &Path) -> Result<Option<(Snapshot, u64)>> {
-    // Create directory if it doesn't exist
-    if !snapshot_dir.exists() {
-        info!(
-            directory = %snapshot_dir.display(),
-            "Snapshot directory does not exist, starting without snapshot"
-        );
-        return Ok(None);
+pub async fn load_latest_snapshot(store: &dyn SnapshotStore) -> Result<Option<(Snapshot, u64)>> {
+    Ok(store.load_latest().await?.map(|snapshot| {
+        let seq = snapshot.sequence_number;
+        (snapshot, seq)
+    }))
+}
+
+/// What startup does with a snapshot taken from a different stream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnMismatch {
+    /// Refuse to start
+    #[default]
+    Fail,
+    /// Ignore the snapshot and rebuild state from everything in the stream
+    ReplayAll,
+}
+
+/// How the stream a snapshot recorded compares with the live one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamCheck {
+    Matches,
+    /// The snapshot predates stream recording
+    Unknown,
+    /// The snapshot's sequence belongs to another stream
+    Mismatch {
+        snapshot: StreamIdentity,
+        live: StreamIdentity,
+    },
+}
+
+impl fmt::Display for StreamCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamCheck::Matches => write!(f, "snapshot matches the stream"),
+            StreamCheck::Unknown => write!(f, "snapshot doesn't record its stream"),
+            StreamCheck::Mismatch { snapshot, live } => write!(
+                f,
+                "snapshot was taken from stream '{}' created {}, but the stream is now '{}' created {}",
+                snapshot.name,
+                snapshot.created.to_rfc3339(),
+                live.name,
+                live.created.to_rfc3339()
+            ),
+        }
+    }
+}
+
+impl StreamCheck {
+    /// Compare the stream a snapshot recorded against the live one
+    pub fn new(snapshot: Option<&StreamIdentity>, live: &StreamIdentity) -> Self {
+        match snapshot {
+            None => StreamCheck::Unknown,
+            Some(snapshot) if snapshot == live => StreamCheck::Matches,
+            Some(snapshot) => StreamCheck::Mismatch {
+                snapshot: snapshot.clone(),
+                live: live.clone(),
+            },
+        }
     }
 
-    // List all snapshot files
-    let mut snapshots = list_snapshots(snapshot_dir)?;
+    /// Whether recovery may resume from the snapshot
+    ///
+    /// Errors on a mismatch under [`OnMismatch::Fail`]; false means replay
+    /// the whole stream without the snapshot.
+    pub fn resume_from_snapshot(&self, on_mismatch: OnMismatch) -> Result<bool> {
+        match (self, on_mismatch) {
+            (StreamCheck::Matches | StreamCheck::Unknown, _) => Ok(true),
+            (StreamCheck::Mismatch { .. }, OnMismatch::ReplayAll) => Ok(false),
+            (StreamCheck::Mismatch { .. }, OnMismatch::Fail) => bail!(
+                "{}; refusing to resume from it. Set `on_mismatch = \"replay_all\"` under \
+                 [recovery] to rebuild state from the stream instead",
+                self
+            ),
+        }
+    }
+}
 
-    if snapshots.is_empty() {
-        info!("No snapshots found, starting from beginning");
+/// Load the newest valid snapshot created at or before `at`
+///
+/// Returns None if there is no such snapshot. Corrupt files are skipped.
+pub fn load_snapshot_at(snapshot_dir: &Path, at: DateTime<Utc>) -> Result<Option<(Snapshot, u64)>> {
+    if !snapshot_dir.exists() {
         return Ok(None);
     }
 
-    // Sort by filename descending (newest first, timestamp is lexicographically sortable)
+    let mut snapshots = list_snapshots(snapshot_dir)?;
     snapshots.sort_by(|a, b| b.cmp(a));
 
-    info!(
-        count = snapshots.len(),
-        directory = %snapshot_dir.display(),
-        "Found {} snapshot(s), attempting to load newest",
-        snapshots.len()
-    );
-
-    // Try loading snapshots from newest to oldest
     for path in snapshots {
         match Snapshot::load_from_file(&path) {
-            Ok(snapshot) => {
+            Ok(snapshot) if snapshot.created_at <= at => {
                 let seq = snapshot.sequence_number;
-                let entity_count = snapshot.entity_count();
-
-                info!(
-                    path = %path.display(),
-                    sequence = seq,
-                    entities = entity_count,
-                    "Loaded snapshot successfully"
-                );
-
                 return Ok(Some((snapshot, seq)));
             }
+            Ok(_) => continue,
             Err(e) => {
                 warn!(
                     path = %path.display(),
                     error = %e,
                     "Corrupt snapshot, trying next oldest"
                 );
-                continue;
             }
         }
     }
 
-    // All snapshots failed to load
-    error!("All snapshots are corrupt, starting from beginning");
     Ok(None)
 }
 
+/// Sequence numbers of the snapshots in directory, parsed from filenames
+///
+/// Files without a `-seq{N}` suffix (legacy names) are ignored.
+pub fn snapshot_sequences(snapshot_dir: &Path) -> Result<Vec<u64>> {
+    if !snapshot_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(list_snapshots(snapshot_dir)?
+        .iter()
+        .filter_map(|path| path.file_name()?.to_str())
+        .filter_map(sequence_from_name)
+        .collect())
+}
+
+/// Sequence number in a snapshot file name (`...-seq{N}.json.gz`)
+pub fn sequence_from_name(filename: &str) -> Option<u64> {
+    let stem = filename
+        .strip_suffix(".json.gz")
+        .or_else(|| filename.strip_suffix(".json"))?;
+    stem.rsplit_once("-seq")?.1.parse().ok()
+}
+
+/// Newest compressed snapshot file in directory, by filename
+///
+/// Doesn't check that it loads. None if there is none.
+pub fn latest_snapshot_path(snapshot_dir: &Path) -> Result<Option<PathBuf>> {
+    if !snapshot_dir.exists() {
+        return Ok(None);
+    }
+
+    Ok(list_snapshots(snapshot_dir)?
+        .into_iter()
+        .filter(|path| path.to_string_lossy().ends_with(".json.gz"))
+        .max())
+}
+
 /// List all snapshot files in directory
-fn list_snapshots(snapshot_dir: &Path) -> Result<Vec<PathBuf>> {
+pub(crate) fn list_snapshots(snapshot_dir: &Path) -> Result<Vec<PathBuf>> {
     let entries = fs::read_dir(snapshot_dir).context("Failed to read snapshot directory")?;
 
     let mut snapshots = Vec::new();
@@ -81,9 +171,7 @@ fn list_snapshots(snapshot_dir: &Path) -> Result<Vec<PathBuf>> {
         // Include both .json.gz (current) and .json (legacy) files
         if path.is_file() {
             if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                if filename.starts_with("snapshot-")
-                    && (filename.ends_with(".json.gz") || filename.ends_with(".json"))
-                {
+                if is_snapshot_name(filename) {
                     snapshots.push(path);
                 }
             }
@@ -96,29 +184,34 @@ fn list_snapshots(snapshot_dir: &Path) -> Result<Vec<PathBuf>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snapshot::LocalSnapshotStore;
     use crate::state::StateEngine;
     use tempfile::TempDir;
 
-    #[test]
-    fn test_load_latest_snapshot_no_directory() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_no_directory() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path().join("nonexistent");
 
-        let result = load_latest_snapshot(&snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_none());
     }
 
-    #[test]
-    fn test_load_latest_snapshot_empty_directory() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path();
 
-        let result = load_latest_snapshot(snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_none());
     }
 
-    #[test]
-    fn test_load_latest_snapshot_success() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_success() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path();
 
@@ -131,7 +224,9 @@ mod tests {
         snapshot.save_to_file(&path).unwrap();
 
         // Load snapshot
-        let result = load_latest_snapshot(snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_some());
 
         let (loaded_snapshot, seq) = result.unwrap();
@@ -139,8 +234,8 @@ mod tests {
         assert_eq!(loaded_snapshot.entity_count(), 1);
     }
 
-    #[test]
-    fn test_load_latest_snapshot_picks_newest() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_picks_newest() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path();
 
@@ -159,7 +254,9 @@ mod tests {
         snapshot2.save_to_file(&path2).unwrap();
 
         // Should load newest (seq 100)
-        let result = load_latest_snapshot(snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_some());
 
         let (loaded_snapshot, seq) = result.unwrap();
@@ -167,8 +264,8 @@ mod tests {
         assert!(loaded_snapshot.entities.contains_key("test2"));
     }
 
-    #[test]
-    fn test_load_latest_snapshot_fallback_on_corrupt() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_fallback_on_corrupt() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path();
 
@@ -184,7 +281,9 @@ mod tests {
         fs::write(&path2, b"not a gzip file").unwrap();
 
         // Should fall back to older valid snapshot
-        let result = load_latest_snapshot(snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_some());
 
         let (loaded_snapshot, seq) = result.unwrap();
@@ -192,8 +291,8 @@ mod tests {
         assert_eq!(loaded_snapshot.entity_count(), 1);
     }
 
-    #[test]
-    fn test_load_latest_snapshot_all_corrupt() {
+    #[tokio::test]
+    async fn test_load_latest_snapshot_all_corrupt() {
         let temp_dir = TempDir::new().unwrap();
         let snapshot_dir = temp_dir.path();
 
@@ -205,7 +304,144 @@ mod tests {
         fs::write(&path2, b"not gzip at all").unwrap();
 
         // Should return None (cold start)
-        let result = load_latest_snapshot(snapshot_dir).unwrap();
+        let result = load_latest_snapshot(&LocalSnapshotStore::new(snapshot_dir))
+            .await
+            .unwrap();
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_load_snapshot_at_skips_newer() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path();
+
+        let engine = StateEngine::new();
+        let mut older = Snapshot::from_state_engine(&engine, 50);
+        older.created_at = "2026-02-12T10:00:00Z".parse().unwrap();
+        older
+            .save_to_file(&snapshot_dir.join("snapshot-20260212T100000.000Z-seq50.json.gz"))
+            .unwrap();
+        let mut newer = Snapshot::from_state_engine(&engine, 100);
+        newer.created_at = "2026-02-12T11:00:00Z".parse().unwrap();
+        newer
+            .save_to_file(&snapshot_dir.join("snapshot-20260212T110000.000Z-seq100.json.gz"))
+            .unwrap();
+
+        let at = |ts: &str| {
+            load_snapshot_at(snapshot_dir, ts.parse().unwrap())
+                .unwrap()
+                .map(|(_, seq)| seq)
+        };
+        assert_eq!(at("2026-02-12T12:00:00Z"), Some(100));
+        assert_eq!(at("2026-02-12T10:30:00Z"), Some(50));
+        assert_eq!(at("2026-02-12T09:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_snapshot_sequences() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path();
+
+        for name in [
+            "snapshot-20260212T153045.123Z-seq12345.json.gz",
+            "snapshot-20260213T153045.123Z-seq20000.json.gz",
+            "snapshot-legacy.json",
+            "other-seq5.json.gz",
+        ] {
+            fs::write(snapshot_dir.join(name), b"").unwrap();
+        }
+
+        let mut sequences = snapshot_sequences(snapshot_dir).unwrap();
+        sequences.sort();
+        assert_eq!(sequences, vec![12345, 20000]);
+
+        assert!(snapshot_sequences(&snapshot_dir.join("nonexistent"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_latest_snapshot_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path();
+        assert_eq!(latest_snapshot_path(snapshot_dir).unwrap(), None);
+
+        for name in [
+            "snapshot-20260212T153045.123Z-seq12345.json.gz",
+            "snapshot-20260213T153045.123Z-seq20000.json.gz",
+            "snapshot-20260214T153045.123Z-seq30000.json",
+            "snapshot-20260215T153045.123Z-seq40000.json.tmp",
+        ] {
+            fs::write(snapshot_dir.join(name), b"").unwrap();
+        }
+
+        assert_eq!(
+            latest_snapshot_path(snapshot_dir).unwrap(),
+            Some(snapshot_dir.join("snapshot-20260213T153045.123Z-seq20000.json.gz"))
+        );
+        let missing = snapshot_dir.join("nonexistent");
+        assert_eq!(latest_snapshot_path(&missing).unwrap(), None);
+    }
+
+    fn stream(name: &str, created: i64) -> StreamIdentity {
+        StreamIdentity {
+            name: name.to_string(),
+            created: DateTime::from_timestamp(created, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_stream_check() {
+        let live = stream("FLUX_EVENTS", 1000);
+        assert_eq!(StreamCheck::new(Some(&live), &live), StreamCheck::Matches);
+        assert_eq!(StreamCheck::new(None, &live), StreamCheck::Unknown);
+
+        // Re-created under the same name, or a different stream
+        for recorded in [stream("FLUX_EVENTS", 2000), stream("OTHER", 1000)] {
+            assert_eq!(
+                StreamCheck::new(Some(&recorded), &live),
+                StreamCheck::Mismatch {
+                    snapshot: recorded.clone(),
+                    live: live.clone(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_from_snapshot_follows_on_mismatch() {
+        let live = stream("FLUX_EVENTS", 1000);
+        let mismatch = StreamCheck::new(Some(&stream("FLUX_EVENTS", 2000)), &live);
+        for on_mismatch in [OnMismatch::Fail, OnMismatch::ReplayAll] {
+            assert!(StreamCheck::Matches
+                .resume_from_snapshot(on_mismatch)
+                .unwrap());
+            assert!(StreamCheck::Unknown
+                .resume_from_snapshot(on_mismatch)
+                .unwrap());
+        }
+        assert!(!mismatch
+            .resume_from_snapshot(OnMismatch::ReplayAll)
+            .unwrap());
+
+        let error = mismatch
+            .resume_from_snapshot(OnMismatch::Fail)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("created 1970-01-01T00:33:20+00:00"));
+        assert!(error.contains("replay_all"));
+    }
+
+    #[test]
+    fn test_stream_identity_round_trips_and_defaults() {
+        let engine = StateEngine::new();
+        let live = stream("FLUX_EVENTS", 1000);
+        let (data, _) = Snapshot::encode_state_engine(&engine, 5, Some(&live)).unwrap();
+        let snapshot = Snapshot::decode(&data, true).unwrap();
+        assert_eq!(snapshot.stream, Some(live));
+
+        // Older snapshots don't record a stream
+        let (data, _) = Snapshot::encode_state_engine(&engine, 5, None).unwrap();
+        assert_eq!(Snapshot::decode(&data, true).unwrap().stream, None);
+    }
 }
@@ -0,0 +1,307 @@
+use crate::s3::{hex, S3Client, S3Config};
+use crate::snapshot::{recovery, Snapshot};
+use anyhow::{bail, Context, Result};
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{error, info, warn};
+
+#[cfg(test)]
+mod tests;
+
+/// Where snapshot files are kept
+///
+/// Snapshots are addressed by file name (`snapshot-{timestamp}-seq{N}.json.gz`,
+/// or legacy `.json`), so sorting names sorts them oldest first.
+pub trait SnapshotStore: Send + Sync + 'static {
+    /// Store `data` under `name`; durable when it returns
+    fn save<'a>(&'a self, name: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>>;
+
+    /// Names of all stored snapshots, in no particular order
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>>>;
+
+    /// Contents of snapshot `name`; fails if it is missing or damaged
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>>;
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    /// Where the snapshots are, for logs
+    fn location(&self) -> String;
+
+    /// Newest snapshot that loads, trying older ones while they don't
+    ///
+    /// None if there are no snapshots or all are corrupt.
+    fn load_latest(&self) -> BoxFuture<'_, Result<Option<Snapshot>>> {
+        Box::pin(async move {
+            let mut names = self.list().await?;
+            if names.is_empty() {
+                info!(location = %self.location(), "No snapshots found, starting from beginning");
+                return Ok(None);
+            }
+            names.sort_by(|a, b| b.cmp(a));
+
+            info!(
+                count = names.len(),
+                location = %self.location(),
+                "Found {} snapshot(s), attempting to load newest",
+                names.len()
+            );
+
+            for name in names {
+                let loaded = self
+                    .load(&name)
+                    .await
+                    .and_then(|data| Snapshot::decode(&data, name.ends_with(".gz")));
+                match loaded {
+                    Ok(snapshot) => {
+                        info!(
+                            name = %name,
+                            sequence = snapshot.sequence_number,
+                            entities = snapshot.entity_count(),
+                            "Loaded snapshot successfully"
+                        );
+                        return Ok(Some(snapshot));
+                    }
+                    Err(e) => {
+                        warn!(name = %name, error = %e, "Corrupt snapshot, trying next oldest");
+                    }
+                }
+            }
+
+            error!("All snapshots are corrupt, starting from beginning");
+            Ok(None)
+        })
+    }
+
+    /// Delete all but the newest `keep_count` snapshots, returning how many
+    /// were deleted. A failed delete is logged and left for the next sweep.
+    fn delete_old(&self, keep_count: usize) -> BoxFuture<'_, Result<usize>> {
+        Box::pin(async move {
+            let mut names = self.list().await?;
+            if names.len() <= keep_count {
+                return Ok(0);
+            }
+            names.sort();
+
+            let mut deleted = 0;
+            for name in &names[..names.len() - keep_count] {
+                match self.delete(name).await {
+                    Ok(()) => {
+                        info!(name = %name, "Deleted old snapshot");
+                        deleted += 1;
+                    }
+                    Err(e) => error!(error = %e, name = %name, "Failed to delete old snapshot"),
+                }
+            }
+            Ok(deleted)
+        })
+    }
+}
+
+/// True for snapshot file names: `snapshot-*.json.gz`, or legacy `.json`
+pub(crate) fn is_snapshot_name(name: &str) -> bool {
+    name.starts_with("snapshot-")
+        && (name.ends_with(".json.gz") || name.ends_with(".json"))
+        && !name.contains(['/', '\\'])
+}
+
+/// Snapshots in a local directory
+pub struct LocalSnapshotStore {
+    directory: PathBuf,
+}
+
+impl LocalSnapshotStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Write via tmp file + fsync + rename so readers never see partial files
+    fn save_sync(&self, name: &str, data: &[u8]) -> Result<()> {
+        fs::create_dir_all(&self.directory).context("Failed to create snapshot directory")?;
+        let path = self.directory.join(name);
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut file =
+                File::create(&tmp_path).context("Failed to create temporary snapshot file")?;
+            file.write_all(data)
+                .context("Failed to write snapshot file")?;
+            file.sync_all()
+                .context("Failed to sync snapshot file to disk")?;
+        }
+        fs::rename(&tmp_path, &path).context("Failed to rename temporary snapshot file")?;
+        Ok(())
+    }
+}
+
+impl SnapshotStore for LocalSnapshotStore {
+    fn save<'a>(&'a self, name: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.save_sync(name, &data) })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move {
+            if !self.directory.exists() {
+                return Ok(Vec::new());
+            }
+            Ok(recovery::list_snapshots(&self.directory)?
+                .iter()
+                .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+                .collect())
+        })
+    }
+
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            fs::read(self.directory.join(name))
+                .with_context(|| format!("Failed to read snapshot {}", name))
+        })
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            fs::remove_file(self.directory.join(name))
+                .with_context(|| format!("Failed to delete snapshot {}", name))
+        })
+    }
+
+    fn location(&self) -> String {
+        self.directory.display().to_string()
+    }
+}
+
+/// Upload part size for snapshots larger than one part
+pub const DEFAULT_PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Object metadata key holding the hex SHA-256 of the snapshot contents
+const SHA256_METADATA: &str = "sha256";
+
+/// Snapshots in an S3-compatible bucket, one object per snapshot under the
+/// configured prefix
+///
+/// Each object carries the SHA-256 of its contents in its metadata; a
+/// snapshot whose contents don't match is treated as corrupt.
+pub struct S3SnapshotStore {
+    client: S3Client,
+    part_size: usize,
+}
+
+impl S3SnapshotStore {
+    pub fn new(client: S3Client) -> Self {
+        Self {
+            client,
+            part_size: DEFAULT_PART_SIZE,
+        }
+    }
+
+    /// Credentials from FLUX_SNAPSHOT_S3_ACCESS_KEY_ID / FLUX_SNAPSHOT_S3_SECRET_ACCESS_KEY
+    pub fn from_env(config: S3Config) -> Result<Self> {
+        Ok(Self::new(S3Client::from_env(config, "FLUX_SNAPSHOT_S3")?))
+    }
+
+    /// Talks to the bucket with `http_client` (see [`crate::http_client`])
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(http_client);
+        self
+    }
+
+    /// Snapshots larger than `part_size` bytes go up as a multipart upload in
+    /// parts of that size. S3 rejects parts under 5 MiB other than the last.
+    pub fn with_part_size(mut self, part_size: usize) -> Self {
+        self.part_size = part_size.max(1);
+        self
+    }
+
+    async fn upload_multipart(
+        &self,
+        name: &str,
+        data: &[u8],
+        metadata: &[(&str, &str)],
+    ) -> Result<()> {
+        let upload_id = self.client.create_multipart_upload(name, metadata).await?;
+        let uploaded = self.upload_parts(name, &upload_id, data).await;
+        if uploaded.is_err() {
+            // Don't leave the uploaded parts behind (and billed)
+            if let Err(e) = self.client.abort_multipart_upload(name, &upload_id).await {
+                warn!(name = %name, error = %e, "Failed to abort snapshot upload");
+            }
+        }
+        uploaded
+    }
+
+    async fn upload_parts(&self, name: &str, upload_id: &str, data: &[u8]) -> Result<()> {
+        let mut parts = Vec::new();
+        for (index, chunk) in data.chunks(self.part_size).enumerate() {
+            let part_number = index as u32 + 1;
+            let etag = self
+                .client
+                .upload_part(name, upload_id, part_number, chunk.to_vec())
+                .await?;
+            parts.push((part_number, etag));
+        }
+        self.client
+            .complete_multipart_upload(name, upload_id, &parts)
+            .await
+    }
+}
+
+impl SnapshotStore for S3SnapshotStore {
+    fn save<'a>(&'a self, name: &'a str, data: Vec<u8>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let digest = hex(&Sha256::digest(&data));
+            let metadata = [(SHA256_METADATA, digest.as_str())];
+            if data.len() > self.part_size {
+                self.upload_multipart(name, &data, &metadata).await
+            } else {
+                self.client.put_object(name, data, &metadata).await
+            }
+        })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move {
+            Ok(self
+                .client
+                .list_objects("snapshot-")
+                .await?
+                .into_iter()
+                .filter(|name| is_snapshot_name(name))
+                .collect())
+        })
+    }
+
+    fn load<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            let object = self
+                .client
+                .get_object(name)
+                .await?
+                .with_context(|| format!("Snapshot {} not found", name))?;
+            let Some(expected) = object.metadata.get(SHA256_METADATA) else {
+                bail!("Snapshot {} has no {} metadata", name, SHA256_METADATA);
+            };
+            let actual = hex(&Sha256::digest(&object.data));
+            if !actual.eq_ignore_ascii_case(expected) {
+                bail!(
+                    "Snapshot {} failed its integrity check (SHA-256 {}, expected {})",
+                    name,
+                    actual,
+                    expected
+                );
+            }
+            Ok(object.data)
+        })
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.client.delete_object(name))
+    }
+
+    fn location(&self) -> String {
+        let config = self.client.config();
+        format!("s3://{}/{}", config.bucket, config.prefix)
+    }
+}
@@ -0,0 +1,363 @@
+use super::*;
+use crate::snapshot::recovery::load_latest_snapshot;
+use crate::state::StateEngine;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get};
+use axum::Router;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+/// An object's body and metadata
+type StoredObject = (Vec<u8>, HashMap<String, String>);
+
+/// Objects and multipart uploads of an in-process S3 stand-in
+#[derive(Default)]
+struct MockBucket {
+    objects: Mutex<BTreeMap<String, StoredObject>>,
+    uploads: Mutex<HashMap<String, Upload>>,
+    parts_uploaded: AtomicUsize,
+    aborted: AtomicUsize,
+    /// Part number that fails with a 500
+    fail_part: Mutex<Option<u32>>,
+}
+
+struct Upload {
+    key: String,
+    metadata: HashMap<String, String>,
+    parts: BTreeMap<u32, Vec<u8>>,
+}
+
+/// Keys per listing page, small enough that tests follow continuation tokens
+const PAGE_SIZE: usize = 2;
+
+async fn list(
+    State(bucket): State<Arc<MockBucket>>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    if !signed(&headers) || query.get("list-type").map(String::as_str) != Some("2") {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    let after = query.get("continuation-token").cloned().unwrap_or_default();
+    let objects = bucket.objects.lock().unwrap();
+    let keys: Vec<&String> = objects
+        .keys()
+        .filter(|key| key.starts_with(&prefix) && key.as_str() > after.as_str())
+        .collect();
+
+    let page = &keys[..keys.len().min(PAGE_SIZE)];
+    let truncated = keys.len() > PAGE_SIZE;
+    let mut xml = format!(
+        "<ListBucketResult><KeyCount>{}</KeyCount><IsTruncated>{}</IsTruncated>",
+        page.len(),
+        truncated
+    );
+    for key in page {
+        xml.push_str(&format!("<Contents><Key>{}</Key></Contents>", key));
+    }
+    if truncated {
+        xml.push_str(&format!(
+            "<NextContinuationToken>{}</NextContinuationToken>",
+            page[PAGE_SIZE - 1]
+        ));
+    }
+    xml.push_str("</ListBucketResult>");
+    xml.into_response()
+}
+
+async fn object(
+    State(bucket): State<Arc<MockBucket>>,
+    method: Method,
+    Path((_bucket, key)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !signed(&headers) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let upload_id = query.get("uploadId").cloned();
+
+    match (method, upload_id) {
+        (Method::POST, None) if query.contains_key("uploads") => {
+            let upload_id = format!("upload-{}", bucket.uploads.lock().unwrap().len() + 1);
+            bucket.uploads.lock().unwrap().insert(
+                upload_id.clone(),
+                Upload {
+                    key,
+                    metadata: metadata(&headers),
+                    parts: BTreeMap::new(),
+                },
+            );
+            format!(
+                "<InitiateMultipartUploadResult><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                upload_id
+            )
+            .into_response()
+        }
+        (Method::PUT, Some(upload_id)) => {
+            let part_number: u32 = query["partNumber"].parse().unwrap();
+            if *bucket.fail_part.lock().unwrap() == Some(part_number) {
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            let mut uploads = bucket.uploads.lock().unwrap();
+            let Some(upload) = uploads.get_mut(&upload_id) else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            upload.parts.insert(part_number, body.to_vec());
+            bucket.parts_uploaded.fetch_add(1, Ordering::SeqCst);
+            ([("etag", format!("\"etag-{}\"", part_number))], "").into_response()
+        }
+        (Method::POST, Some(upload_id)) => {
+            let Some(upload) = bucket.uploads.lock().unwrap().remove(&upload_id) else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            let request = String::from_utf8(body.to_vec()).unwrap();
+            let mut data = Vec::new();
+            for part in request.split("<PartNumber>").skip(1) {
+                let number: u32 = part.split('<').next().unwrap().parse().unwrap();
+                data.extend_from_slice(&upload.parts[&number]);
+            }
+            assert_eq!(upload.key, key);
+            bucket
+                .objects
+                .lock()
+                .unwrap()
+                .insert(key, (data, upload.metadata));
+            "<CompleteMultipartUploadResult></CompleteMultipartUploadResult>".into_response()
+        }
+        (Method::DELETE, Some(upload_id)) => {
+            bucket.uploads.lock().unwrap().remove(&upload_id);
+            bucket.aborted.fetch_add(1, Ordering::SeqCst);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        (Method::PUT, None) => {
+            bucket
+                .objects
+                .lock()
+                .unwrap()
+                .insert(key, (body.to_vec(), metadata(&headers)));
+            StatusCode::OK.into_response()
+        }
+        (Method::GET, None) => match bucket.objects.lock().unwrap().get(&key) {
+            Some((data, metadata)) => {
+                let mut response = data.clone().into_response();
+                for (name, value) in metadata {
+                    response.headers_mut().insert(
+                        HeaderName::try_from(format!("x-amz-meta-{}", name)).unwrap(),
+                        HeaderValue::from_str(value).unwrap(),
+                    );
+                }
+                response
+            }
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+        (Method::DELETE, None) => {
+            bucket.objects.lock().unwrap().remove(&key);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+fn signed(headers: &HeaderMap) -> bool {
+    headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("AWS4-HMAC-SHA256 Credential=key/"))
+}
+
+fn metadata(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = name.as_str().strip_prefix("x-amz-meta-")?;
+            Some((name.to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect()
+}
+
+/// Mock bucket served on a local port, and a store using it under "snapshots/"
+async fn mock_s3() -> (Arc<MockBucket>, S3SnapshotStore) {
+    let bucket = Arc::new(MockBucket::default());
+    let router = Router::new()
+        .route("/:bucket", get(list))
+        .route("/:bucket/*key", any(object))
+        .with_state(Arc::clone(&bucket));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let client = S3Client::new(
+        S3Config {
+            endpoint,
+            bucket: "flux".to_string(),
+            region: "us-east-1".to_string(),
+            prefix: "snapshots/".to_string(),
+        },
+        "key".to_string(),
+        "secret".to_string(),
+    );
+    (bucket, S3SnapshotStore::new(client))
+}
+
+fn name(seq: u64) -> String {
+    format!("snapshot-20260212T{:06}.000Z-seq{}.json.gz", seq, seq)
+}
+
+fn snapshot_bytes(seq: u64) -> Vec<u8> {
+    let engine = StateEngine::new();
+    engine.update_property("sensor-1", "temp", json!(seq));
+    Snapshot::encode_state_engine(&engine, seq, None).unwrap().0
+}
+
+#[tokio::test]
+async fn test_local_store_save_list_delete_old() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = LocalSnapshotStore::new(temp_dir.path().join("snapshots"));
+    assert!(store.list().await.unwrap().is_empty());
+    assert!(store.load_latest().await.unwrap().is_none());
+
+    // Encoding stamps the time, so compare against the bytes saved
+    let mut saved = Vec::new();
+    for seq in 1..=4 {
+        let bytes = snapshot_bytes(seq);
+        store.save(&name(seq), bytes.clone()).await.unwrap();
+        saved.push(bytes);
+    }
+    fs::write(temp_dir.path().join("snapshots/notes.txt"), b"").unwrap();
+
+    let mut names = store.list().await.unwrap();
+    names.sort();
+    assert_eq!(names, vec![name(1), name(2), name(3), name(4)]);
+    assert_eq!(store.load(&name(2)).await.unwrap(), saved[1]);
+
+    assert_eq!(store.delete_old(2).await.unwrap(), 2);
+    let mut names = store.list().await.unwrap();
+    names.sort();
+    assert_eq!(names, vec![name(3), name(4)]);
+    assert_eq!(store.delete_old(2).await.unwrap(), 0);
+
+    let snapshot = store.load_latest().await.unwrap().unwrap();
+    assert_eq!(snapshot.sequence_number, 4);
+}
+
+#[tokio::test]
+async fn test_s3_store_round_trip() {
+    let (bucket, store) = mock_s3().await;
+    assert!(store.list().await.unwrap().is_empty());
+    assert!(load_latest_snapshot(&store).await.unwrap().is_none());
+
+    let data = snapshot_bytes(100);
+    store.save(&name(100), data.clone()).await.unwrap();
+
+    {
+        let objects = bucket.objects.lock().unwrap();
+        let (stored, metadata) = &objects[&format!("snapshots/{}", name(100))];
+        assert_eq!(stored, &data);
+        assert_eq!(metadata["sha256"], hex(&Sha256::digest(&data)));
+    }
+    assert_eq!(bucket.parts_uploaded.load(Ordering::SeqCst), 0);
+    assert_eq!(store.list().await.unwrap(), vec![name(100)]);
+    assert_eq!(store.load(&name(100)).await.unwrap(), data);
+
+    let (snapshot, seq) = load_latest_snapshot(&store).await.unwrap().unwrap();
+    assert_eq!(seq, 100);
+    assert_eq!(snapshot.entities["sensor-1"].properties["temp"], json!(100));
+    assert_eq!(store.location(), "s3://flux/snapshots/");
+}
+
+#[tokio::test]
+async fn test_s3_store_multipart_upload() {
+    let (bucket, store) = mock_s3().await;
+    let store = store.with_part_size(1_000);
+
+    let data: Vec<u8> = (0..4_500u32).map(|i| (i % 251) as u8).collect();
+    store.save(&name(7), data.clone()).await.unwrap();
+
+    assert_eq!(bucket.parts_uploaded.load(Ordering::SeqCst), 5);
+    assert!(bucket.uploads.lock().unwrap().is_empty());
+    assert_eq!(store.load(&name(7)).await.unwrap(), data);
+}
+
+#[tokio::test]
+async fn test_s3_store_aborts_failed_multipart_upload() {
+    let (bucket, store) = mock_s3().await;
+    let store = store.with_part_size(1_000);
+    *bucket.fail_part.lock().unwrap() = Some(2);
+
+    assert!(store.save(&name(7), vec![1; 4_500]).await.is_err());
+
+    assert_eq!(bucket.aborted.load(Ordering::SeqCst), 1);
+    assert!(bucket.uploads.lock().unwrap().is_empty());
+    assert!(bucket.objects.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_s3_store_rejects_damaged_snapshot() {
+    let (bucket, store) = mock_s3().await;
+    store.save(&name(50), snapshot_bytes(50)).await.unwrap();
+    store.save(&name(100), snapshot_bytes(100)).await.unwrap();
+
+    // Flip a byte of the newest; its digest no longer matches
+    bucket
+        .objects
+        .lock()
+        .unwrap()
+        .get_mut(&format!("snapshots/{}", name(100)))
+        .unwrap()
+        .0[20] ^= 0xff;
+
+    let err = store.load(&name(100)).await.unwrap_err();
+    assert!(err.to_string().contains("integrity check"), "{}", err);
+
+    // Recovery falls back to the older snapshot
+    let (_, seq) = load_latest_snapshot(&store).await.unwrap().unwrap();
+    assert_eq!(seq, 50);
+
+    // A snapshot without a recorded digest isn't trusted either
+    bucket
+        .objects
+        .lock()
+        .unwrap()
+        .get_mut(&format!("snapshots/{}", name(50)))
+        .unwrap()
+        .1
+        .clear();
+    assert!(store.load(&name(50)).await.is_err());
+    assert!(load_latest_snapshot(&store).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_s3_store_retention_across_listing_pages() {
+    let (bucket, store) = mock_s3().await;
+    for seq in 1..=5 {
+        store.save(&name(seq), snapshot_bytes(seq)).await.unwrap();
+    }
+    // Other objects under the prefix are listed past but never deleted
+    bucket.objects.lock().unwrap().insert(
+        "snapshots/snapshot-notes.txt".to_string(),
+        (Vec::new(), HashMap::new()),
+    );
+
+    let mut names = store.list().await.unwrap();
+    names.sort();
+    assert_eq!(names, (1..=5).map(name).collect::<Vec<_>>());
+
+    assert_eq!(store.delete_old(3).await.unwrap(), 2);
+    let mut names = store.list().await.unwrap();
+    names.sort();
+    assert_eq!(names, vec![name(3), name(4), name(5)]);
+    assert!(bucket
+        .objects
+        .lock()
+        .unwrap()
+        .contains_key("snapshots/snapshot-notes.txt"));
+}
@@ -19,6 +19,9 @@ fn test_snapshot_serialize_deserialize_roundtrip() {
                 props
             },
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
     entities.insert(
@@ -31,6 +34,9 @@ fn test_snapshot_serialize_deserialize_roundtrip() {
                 props
             },
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
 
@@ -39,14 +45,17 @@ fn test_snapshot_serialize_deserialize_roundtrip() {
         created_at: Utc::now(),
         sequence_number: 12345,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     // Serialize to JSON
     let json = serde_json::to_string(&original).expect("Serialization failed");
 
     // Deserialize back
-    let deserialized: Snapshot =
-        serde_json::from_str(&json).expect("Deserialization failed");
+    let deserialized: Snapshot = serde_json::from_str(&json).expect("Deserialization failed");
 
     // Verify fields match
     assert_eq!(deserialized.snapshot_version, "1");
@@ -77,6 +86,9 @@ fn test_snapshot_save_and_load() {
                 props
             },
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
 
@@ -85,6 +97,10 @@ fn test_snapshot_save_and_load() {
         created_at: Utc::now(),
         sequence_number: 999,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     // Create temp directory for test
@@ -195,6 +211,9 @@ fn test_snapshot_to_hashmap() {
                 props
             },
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
 
@@ -203,6 +222,10 @@ fn test_snapshot_to_hashmap() {
         created_at: Utc::now(),
         sequence_number: 100,
         entities: entities.clone(),
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     // Convert to hashmap
@@ -224,6 +247,9 @@ fn test_snapshot_entity_count() {
                 id: format!("entity_{}", i),
                 properties: HashMap::new(),
                 last_updated: Utc::now(),
+                last_applied: None,
+                last_modified_sequence: 0,
+                property_times: HashMap::new(),
             },
         );
     }
@@ -233,6 +259,10 @@ fn test_snapshot_entity_count() {
         created_at: Utc::now(),
         sequence_number: 1000,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     assert_eq!(snapshot.entity_count(), 10);
@@ -246,7 +276,10 @@ fn test_compression_reduces_size() {
         let mut props = HashMap::new();
         props.insert("status".to_string(), json!("active"));
         props.insert("value".to_string(), json!(i));
-        props.insert("description".to_string(), json!("This is a test entity with repeating data"));
+        props.insert(
+            "description".to_string(),
+            json!("This is a test entity with repeating data"),
+        );
 
         entities.insert(
             format!("entity_{}", i),
@@ -254,6 +287,9 @@ fn test_compression_reduces_size() {
                 id: format!("entity_{}", i),
                 properties: props,
                 last_updated: Utc::now(),
+                last_applied: None,
+                last_modified_sequence: 0,
+                property_times: HashMap::new(),
             },
         );
     }
@@ -263,6 +299,10 @@ fn test_compression_reduces_size() {
         created_at: Utc::now(),
         sequence_number: 5000,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     let temp_dir = std::env::temp_dir();
@@ -312,6 +352,9 @@ fn test_atomic_write_no_tmp_file() {
             id: "test".to_string(),
             properties: HashMap::new(),
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
 
@@ -320,6 +363,10 @@ fn test_atomic_write_no_tmp_file() {
         created_at: Utc::now(),
         sequence_number: 100,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     let temp_dir = std::env::temp_dir();
@@ -362,6 +409,9 @@ fn test_backward_compatibility_load_uncompressed() {
                 props
             },
             last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
         },
     );
 
@@ -370,6 +420,10 @@ fn test_backward_compatibility_load_uncompressed() {
         created_at: Utc::now(),
         sequence_number: 777,
         entities,
+        trash: Vec::new(),
+        messages: Vec::new(),
+        quotas: Vec::new(),
+        stream: None,
     };
 
     let temp_dir = std::env::temp_dir();
@@ -395,3 +449,116 @@ fn test_backward_compatibility_load_uncompressed() {
     // Clean up
     std::fs::remove_file(&legacy_path).expect("Failed to clean up test file");
 }
+
+#[test]
+fn test_save_state_engine_matches_owned_snapshot() {
+    let engine = StateEngine::new();
+    engine.update_property("agent_1", "status", json!("active"));
+    engine.update_property("agent_1", "payload", json!({"nested": [1, 2, 3]}));
+    engine.update_property("agent_2", "status", json!("idle"));
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = temp_dir.path().join("streamed.json.gz");
+
+    let count = Snapshot::save_state_engine(&engine, 42, None, &path).expect("Failed to save");
+    assert_eq!(count, 2);
+
+    // Streamed file loads back identically to the owned (cloning) path
+    let streamed = Snapshot::load_from_file(&path).expect("Failed to load");
+    let owned = Snapshot::from_state_engine(&engine, 42);
+
+    assert_eq!(streamed.snapshot_version, owned.snapshot_version);
+    assert_eq!(streamed.sequence_number, 42);
+    assert_eq!(streamed.entities.len(), owned.entities.len());
+    for (id, entity) in &owned.entities {
+        let loaded = &streamed.entities[id];
+        assert_eq!(loaded.properties, entity.properties);
+        assert_eq!(loaded.last_updated, entity.last_updated);
+    }
+}
+
+#[test]
+fn test_trash_survives_snapshot() {
+    let engine = StateEngine::new();
+    engine.update_property("ns/kept", "status", json!("active"));
+    engine.update_property("ns/gone", "status", json!("offline"));
+    engine.delete_entity("ns/gone");
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = temp_dir.path().join("trash.json.gz");
+    Snapshot::save_state_engine(&engine, 7, None, &path).expect("Failed to save");
+
+    let mut loaded = Snapshot::load_from_file(&path).expect("Failed to load");
+    assert_eq!(loaded.trash.len(), 1);
+
+    let restored = StateEngine::new();
+    let trash = std::mem::take(&mut loaded.trash);
+    restored.load_from_snapshot(loaded.to_hashmap(), 7);
+    restored.load_trash(trash);
+
+    assert!(restored.get_entity("ns/gone").is_none());
+    let deleted = restored.get_deleted_entity("ns/gone").unwrap();
+    let original = engine.get_deleted_entity("ns/gone").unwrap();
+    assert_eq!(deleted.deleted_at, original.deleted_at);
+    assert_eq!(deleted.entity.properties["status"], json!("offline"));
+}
+
+#[test]
+fn test_snapshot_without_trash_loads() {
+    // Snapshots written before the trash existed have no `trash` field
+    let json = r#"{
+        "snapshot_version": "1",
+        "created_at": "2026-01-01T00:00:00Z",
+        "sequence_number": 5,
+        "entities": {}
+    }"#;
+    let snapshot: Snapshot = serde_json::from_str(json).unwrap();
+    assert!(snapshot.trash.is_empty());
+}
+
+#[test]
+fn test_snapshot_without_property_times_defaults_to_last_updated() {
+    let json = r#"{
+        "snapshot_version": "1",
+        "created_at": "2026-01-01T00:00:00Z",
+        "sequence_number": 5,
+        "entities": {
+            "ns/sensor": {
+                "id": "ns/sensor",
+                "properties": {"temp": 21.5, "unit": "C"},
+                "last_updated": "2026-01-01T00:00:00Z"
+            }
+        }
+    }"#;
+    let snapshot: Snapshot = serde_json::from_str(json).unwrap();
+    let engine = StateEngine::new();
+    engine.load_from_snapshot(snapshot.to_hashmap(), 5);
+
+    let entity = engine.get_entity("ns/sensor").unwrap();
+    assert_eq!(entity.property_times.len(), 2);
+    assert!(entity
+        .property_times
+        .values()
+        .all(|time| *time == entity.last_updated));
+}
+
+#[test]
+fn test_property_times_overhead() {
+    let engine = StateEngine::new();
+    engine.update_properties(
+        "ns/wide",
+        (0..100).map(|i| (format!("property_{:03}", i), json!(i))),
+    );
+    let snapshot = Snapshot::from_state_engine(&engine, 1);
+    let with_times = serde_json::to_vec(&snapshot).unwrap().len();
+
+    let mut without = snapshot.clone();
+    for entity in without.entities.values_mut() {
+        entity.property_times.clear();
+    }
+    let without_times = serde_json::to_vec(&without).unwrap().len();
+
+    // Property name plus an RFC 3339 timestamp, per property
+    let per_property = (with_times - without_times) / 100;
+    assert!(per_property < 64, "{} bytes per property", per_property);
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Configuration for running as a warm standby (`FLUX_MODE=standby`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StandbyConfig {
+    /// Where the primary's snapshots are pulled from: its base URL
+    /// (`http://` or `https://`) or a shared directory. Overridden by
+    /// FLUX_STANDBY_SNAPSHOT_SOURCE; no shipping when unset
+    pub snapshot_source: Option<String>,
+
+    /// Interval between snapshot pulls (seconds)
+    pub poll_interval_seconds: u64,
+
+    /// Keep serving query, history and WebSocket reads while in standby
+    pub serve_reads: bool,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_source: None,
+            poll_interval_seconds: 60,
+            serve_reads: true,
+        }
+    }
+}
+
+impl StandbyConfig {
+    pub fn snapshot_source(&self) -> Option<String> {
+        std::env::var("FLUX_STANDBY_SNAPSHOT_SOURCE")
+            .ok()
+            .or_else(|| self.snapshot_source.clone())
+    }
+
+    /// Poll interval, at least one second
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_seconds.max(1))
+    }
+}
@@ -0,0 +1,86 @@
+// Warm standby: a second instance runs the NATS subscriber and state engine
+// like the primary but serves no writes and runs no leader-only loops. It
+// keeps a copy of the primary's latest snapshot so a restart is fast, and
+// `POST /api/admin/promote` turns it into a primary without a restart.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::info;
+use utoipa::ToSchema;
+
+pub mod config;
+pub mod shipper;
+
+pub use config::StandbyConfig;
+pub use shipper::{SnapshotShipper, SnapshotSource};
+
+#[cfg(all(test, feature = "http-api"))]
+mod tests;
+
+/// Whether this instance serves writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceMode {
+    Primary,
+    Standby,
+}
+
+impl FromStr for InstanceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "primary" => Ok(InstanceMode::Primary),
+            "standby" => Ok(InstanceMode::Standby),
+            other => Err(format!(
+                "unknown mode '{}' (expected primary or standby)",
+                other
+            )),
+        }
+    }
+}
+
+/// Cloneable handle on the instance mode
+///
+/// A standby can be promoted once; a primary stays primary.
+#[derive(Clone)]
+pub struct ModeHandle {
+    tx: Arc<watch::Sender<InstanceMode>>,
+}
+
+impl ModeHandle {
+    pub fn new(mode: InstanceMode) -> Self {
+        let (tx, _) = watch::channel(mode);
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn mode(&self) -> InstanceMode {
+        *self.tx.borrow()
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.mode() == InstanceMode::Standby
+    }
+
+    /// Switch a standby to primary; false if it already was one
+    pub fn promote(&self) -> bool {
+        let promoted = self.tx.send_if_modified(|mode| {
+            let standby = *mode == InstanceMode::Standby;
+            *mode = InstanceMode::Primary;
+            standby
+        });
+        if promoted {
+            info!("Promoted from standby to primary");
+        }
+        promoted
+    }
+
+    /// Wait until this instance is primary (immediately if it already is)
+    pub async fn wait_for_primary(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives as long as `self`, so this can't fail
+        let _ = rx.wait_for(|mode| *mode == InstanceMode::Primary).await;
+    }
+}
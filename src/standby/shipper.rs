@@ -0,0 +1,243 @@
+use crate::http_client::HttpClientConfig;
+use crate::snapshot::recovery::{latest_snapshot_path, list_snapshots};
+use crate::standby::ModeHandle;
+use anyhow::{Context, Result};
+use reqwest::header::{CONTENT_DISPOSITION, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Path of the primary's snapshot download endpoint
+pub const SNAPSHOT_DOWNLOAD_PATH: &str = "/api/admin/snapshots/latest/download";
+
+/// Where a standby pulls the primary's snapshots from
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotSource {
+    /// Snapshot directory shared with the primary
+    Directory(PathBuf),
+    /// The primary's base URL, downloaded with the admin token
+    Http {
+        base_url: String,
+        admin_token: Option<String>,
+    },
+}
+
+impl SnapshotSource {
+    /// `http://` and `https://` sources name the primary, anything else a directory
+    pub fn parse(source: &str, admin_token: Option<String>) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            SnapshotSource::Http {
+                base_url: source.trim_end_matches('/').to_string(),
+                admin_token,
+            }
+        } else {
+            SnapshotSource::Directory(PathBuf::from(source))
+        }
+    }
+}
+
+/// True for names the snapshot manager writes; anything else from the
+/// primary is refused rather than written into the snapshot directory
+pub(crate) fn is_snapshot_filename(name: &str) -> bool {
+    name.starts_with("snapshot-")
+        && name.ends_with(".json.gz")
+        && !name.contains(['/', '\\'])
+        && !name.contains("..")
+}
+
+/// `filename` of an `attachment; filename="..."` header value
+pub(crate) fn attachment_filename(disposition: &str) -> Option<&str> {
+    disposition.split(';').find_map(|part| {
+        let value = part.trim().strip_prefix("filename=")?;
+        Some(value.trim_matches('"'))
+    })
+}
+
+/// Copies the primary's newest snapshot into the local snapshot directory
+pub struct SnapshotShipper {
+    source: SnapshotSource,
+    snapshot_dir: PathBuf,
+    keep_count: usize,
+    client: reqwest::Client,
+}
+
+impl SnapshotShipper {
+    /// Ship into `snapshot_dir`, keeping the newest `keep_count` snapshots there (min 1)
+    pub fn new(source: SnapshotSource, snapshot_dir: PathBuf, keep_count: usize) -> Self {
+        Self {
+            source,
+            snapshot_dir,
+            keep_count: keep_count.max(1),
+            // Snapshots can be large; no whole-request timeout
+            client: HttpClientConfig::default()
+                .with_timeout_seconds(0)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Downloads with `client` (see [`crate::http_client`])
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Fetch the primary's newest snapshot unless it is already here
+    ///
+    /// Returns the local path of a newly shipped snapshot. Files are written
+    /// under a temporary name and renamed, so recovery never sees a partial one.
+    pub async fn ship_latest(&self) -> Result<Option<PathBuf>> {
+        fs::create_dir_all(&self.snapshot_dir).context("Failed to create snapshot directory")?;
+
+        let shipped = match &self.source {
+            SnapshotSource::Directory(dir) => self.copy_from_directory(dir).await?,
+            SnapshotSource::Http {
+                base_url,
+                admin_token,
+            } => self.download(base_url, admin_token.as_deref()).await?,
+        };
+
+        if shipped.is_some() {
+            self.prune()?;
+        }
+        Ok(shipped)
+    }
+
+    async fn copy_from_directory(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let Some(latest) = latest_snapshot_path(dir)? else {
+            return Ok(None);
+        };
+        let Some(name) = latest.file_name().and_then(|n| n.to_str()) else {
+            return Ok(None);
+        };
+        let target = self.snapshot_dir.join(name);
+        if target == latest || target.exists() {
+            return Ok(None);
+        }
+
+        let part = part_path(&target);
+        let copied = tokio::fs::copy(&latest, &part)
+            .await
+            .with_context(|| format!("Failed to copy {}", latest.display()));
+        finish(copied.map(|_| ()), &part, &target).await?;
+        Ok(Some(target))
+    }
+
+    async fn download(&self, base_url: &str, admin_token: Option<&str>) -> Result<Option<PathBuf>> {
+        let mut request = self
+            .client
+            .get(format!("{}{}", base_url, SNAPSHOT_DOWNLOAD_PATH));
+        if let Some(token) = admin_token {
+            request = request.bearer_auth(token);
+        }
+        // The primary answers 304 if its newest snapshot is the one we have
+        let newest_local = latest_snapshot_path(&self.snapshot_dir)?;
+        if let Some(name) = newest_local
+            .as_deref()
+            .and_then(|p| p.file_name()?.to_str())
+        {
+            request = request.header(IF_NONE_MATCH, format!("\"{}\"", name));
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .context("Failed to reach primary for snapshot")?;
+        match response.status() {
+            // 404: the primary hasn't written a snapshot yet
+            StatusCode::NOT_MODIFIED | StatusCode::NOT_FOUND => return Ok(None),
+            status if !status.is_success() => {
+                anyhow::bail!("Primary returned {} for the latest snapshot", status)
+            }
+            _ => {}
+        }
+
+        let name = response
+            .headers()
+            .get(CONTENT_DISPOSITION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(attachment_filename)
+            .filter(|name| is_snapshot_filename(name))
+            .map(str::to_string)
+            .context("Primary sent no valid snapshot filename")?;
+        let target = self.snapshot_dir.join(&name);
+        if target.exists() {
+            return Ok(None);
+        }
+
+        let part = part_path(&target);
+        let written = async {
+            let mut file = tokio::fs::File::create(&part).await?;
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk).await?;
+            }
+            file.sync_all().await?;
+            anyhow::Ok(())
+        }
+        .await
+        .context("Failed to download snapshot");
+        finish(written, &part, &target).await?;
+        Ok(Some(target))
+    }
+
+    /// Delete the oldest local snapshots beyond `keep_count`
+    fn prune(&self) -> Result<()> {
+        let mut snapshots = list_snapshots(&self.snapshot_dir)?;
+        if snapshots.len() <= self.keep_count {
+            return Ok(());
+        }
+        snapshots.sort();
+        let excess = snapshots.len() - self.keep_count;
+        for path in &snapshots[..excess] {
+            if let Err(e) = fs::remove_file(path) {
+                warn!(error = %e, path = %path.display(), "Failed to delete old snapshot");
+            }
+        }
+        Ok(())
+    }
+
+    /// Ship every `interval` until this instance is promoted
+    pub async fn run(&self, mode: ModeHandle, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = mode.wait_for_primary() => {
+                    info!("Promoted to primary, snapshot shipping stopped");
+                    return;
+                }
+            }
+            match self.ship_latest().await {
+                Ok(Some(path)) => info!(path = %path.display(), "Shipped snapshot from primary"),
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, "Snapshot shipping failed"),
+            }
+        }
+    }
+}
+
+/// Temporary name while shipping; recovery ignores it
+fn part_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+/// Rename `part` into place, or remove it if writing it failed
+async fn finish(written: Result<()>, part: &Path, target: &Path) -> Result<()> {
+    let result = match written {
+        Ok(()) => tokio::fs::rename(part, target)
+            .await
+            .context("Failed to move shipped snapshot into place"),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(part).await;
+    }
+    result
+}
@@ -0,0 +1,194 @@
+use super::shipper::{attachment_filename, is_snapshot_filename};
+use super::*;
+use crate::api::standby::{create_standby_router, StandbyAppState};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn write_snapshot(dir: &Path, name: &str, contents: &[u8]) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join(name), contents).unwrap();
+}
+
+fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_instance_mode_from_str() {
+    assert_eq!("primary".parse(), Ok(InstanceMode::Primary));
+    assert_eq!("standby".parse(), Ok(InstanceMode::Standby));
+    assert!("Standby".parse::<InstanceMode>().is_err());
+    assert!("".parse::<InstanceMode>().is_err());
+}
+
+#[test]
+fn test_promote_once() {
+    let mode = ModeHandle::new(InstanceMode::Standby);
+    let shared = mode.clone();
+    assert!(shared.is_standby());
+
+    assert!(mode.promote());
+    assert!(!shared.is_standby());
+    assert_eq!(shared.mode(), InstanceMode::Primary);
+    assert!(!mode.promote());
+
+    assert!(!ModeHandle::new(InstanceMode::Primary).promote());
+}
+
+#[tokio::test]
+async fn test_wait_for_primary() {
+    ModeHandle::new(InstanceMode::Primary)
+        .wait_for_primary()
+        .await;
+
+    let mode = ModeHandle::new(InstanceMode::Standby);
+    let waiter = tokio::spawn({
+        let mode = mode.clone();
+        async move { mode.wait_for_primary().await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!waiter.is_finished());
+
+    mode.promote();
+    tokio::time::timeout(Duration::from_secs(1), waiter)
+        .await
+        .expect("promotion should wake the waiter")
+        .unwrap();
+}
+
+#[test]
+fn test_snapshot_source_parse() {
+    assert_eq!(
+        SnapshotSource::parse("/mnt/primary/snapshots", None),
+        SnapshotSource::Directory("/mnt/primary/snapshots".into())
+    );
+    assert_eq!(
+        SnapshotSource::parse("https://flux-0:3000/", Some("secret".into())),
+        SnapshotSource::Http {
+            base_url: "https://flux-0:3000".into(),
+            admin_token: Some("secret".into()),
+        }
+    );
+}
+
+#[test]
+fn test_snapshot_filenames() {
+    assert!(is_snapshot_filename(
+        "snapshot-20260212T100000.000Z-seq100.json.gz"
+    ));
+    assert!(!is_snapshot_filename("snapshot-1.json"));
+    assert!(!is_snapshot_filename("../snapshot-1.json.gz"));
+    assert!(!is_snapshot_filename("snapshot-/../../etc.json.gz"));
+    assert!(!is_snapshot_filename("other.json.gz"));
+
+    assert_eq!(
+        attachment_filename("attachment; filename=\"snapshot-1.json.gz\""),
+        Some("snapshot-1.json.gz")
+    );
+    assert_eq!(attachment_filename("attachment"), None);
+}
+
+#[tokio::test]
+async fn test_ship_from_directory() {
+    let temp = TempDir::new().unwrap();
+    let primary = temp.path().join("primary");
+    let local = temp.path().join("local");
+    let shipper =
+        SnapshotShipper::new(SnapshotSource::Directory(primary.clone()), local.clone(), 2);
+
+    // Nothing written on the primary yet
+    assert_eq!(shipper.ship_latest().await.unwrap(), None);
+
+    write_snapshot(
+        &primary,
+        "snapshot-20260210T100000.000Z-seq10.json.gz",
+        b"a",
+    );
+    write_snapshot(
+        &primary,
+        "snapshot-20260211T100000.000Z-seq20.json.gz",
+        b"b",
+    );
+    let shipped = shipper.ship_latest().await.unwrap().unwrap();
+    assert_eq!(
+        shipped,
+        local.join("snapshot-20260211T100000.000Z-seq20.json.gz")
+    );
+    assert_eq!(fs::read(&shipped).unwrap(), b"b");
+
+    // Already shipped
+    assert_eq!(shipper.ship_latest().await.unwrap(), None);
+
+    // Newer snapshots replace the oldest beyond keep_count
+    write_snapshot(
+        &primary,
+        "snapshot-20260212T100000.000Z-seq30.json.gz",
+        b"c",
+    );
+    shipper.ship_latest().await.unwrap().unwrap();
+    write_snapshot(
+        &primary,
+        "snapshot-20260213T100000.000Z-seq40.json.gz",
+        b"d",
+    );
+    shipper.ship_latest().await.unwrap().unwrap();
+    assert_eq!(
+        names(&local),
+        vec![
+            "snapshot-20260212T100000.000Z-seq30.json.gz",
+            "snapshot-20260213T100000.000Z-seq40.json.gz",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_ship_over_http() {
+    let temp = TempDir::new().unwrap();
+    let primary = temp.path().join("primary");
+    let local = temp.path().join("local");
+    fs::create_dir_all(&primary).unwrap();
+
+    let router = create_standby_router(Arc::new(StandbyAppState {
+        mode: ModeHandle::new(InstanceMode::Primary),
+        snapshot_dir: primary.clone(),
+        admin_token: Some("admin".into()),
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let unauthorized = SnapshotShipper::new(
+        SnapshotSource::parse(&base_url, Some("wrong".into())),
+        local.clone(),
+        3,
+    );
+    let shipper = SnapshotShipper::new(
+        SnapshotSource::parse(&base_url, Some("admin".into())),
+        local.clone(),
+        3,
+    );
+
+    // 404 until the primary writes one
+    assert_eq!(shipper.ship_latest().await.unwrap(), None);
+
+    let name = "snapshot-20260212T100000.000Z-seq100.json.gz";
+    let contents = vec![7u8; 200_000];
+    write_snapshot(&primary, name, &contents);
+
+    assert!(unauthorized.ship_latest().await.is_err());
+
+    let shipped = shipper.ship_latest().await.unwrap().unwrap();
+    assert_eq!(shipped, local.join(name));
+    assert_eq!(fs::read(&shipped).unwrap(), contents);
+    assert_eq!(names(&local), vec![name]);
+
+    // Answered with 304 now
+    assert_eq!(shipper.ship_latest().await.unwrap(), None);
+}
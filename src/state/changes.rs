@@ -0,0 +1,190 @@
+use crate::state::entity::Entity;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Default number of recent deletions kept for "changed since" queries
+pub const DEFAULT_DELETION_LOG_CAPACITY: usize = 10_000;
+
+/// Where a "changed since" query starts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangesSince {
+    /// Changes after this NATS stream sequence
+    Sequence(u64),
+    /// Changes after this time
+    Time(DateTime<Utc>),
+}
+
+/// One page of changes, see [`StateEngine::changes_since`](crate::state::StateEngine::changes_since)
+#[derive(Clone, Debug)]
+pub struct Changes {
+    /// Entities created or modified, in sequence order
+    pub entities: Vec<Arc<Entity>>,
+    /// IDs of entities deleted and not recreated since
+    pub deleted: Vec<String>,
+    /// `since_seq` for the next request
+    pub next_since_seq: u64,
+    /// True if changes remain after this page
+    pub has_more: bool,
+}
+
+/// Why a "changed since" query can't be answered
+#[derive(Debug, PartialEq)]
+pub enum ChangesError {
+    /// The cursor is older than the changes the engine remembers (snapshot
+    /// load, or deletions pushed out of the log); the client must resync
+    ResyncRequired { oldest_sequence: u64 },
+}
+
+impl std::fmt::Display for ChangesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangesError::ResyncRequired { oldest_sequence } => write!(
+                f,
+                "Changes before sequence {} are no longer known; fetch the full state and resync",
+                oldest_sequence
+            ),
+        }
+    }
+}
+
+/// An entity deletion, as remembered for "changed since" queries
+#[derive(Clone, Debug)]
+pub(crate) struct RecordedDeletion {
+    pub entity_id: String,
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Bounded log of recent deletions
+///
+/// Remembers how far back it can answer: the sequence and time the engine
+/// started tracking from, moved forward as old deletions are dropped.
+#[derive(Debug)]
+pub(crate) struct DeletionLog {
+    entries: VecDeque<RecordedDeletion>,
+    capacity: usize,
+    oldest_sequence: u64,
+    oldest_time: Option<DateTime<Utc>>,
+}
+
+impl DeletionLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            oldest_sequence: 0,
+            oldest_time: None,
+        }
+    }
+
+    pub fn record(&mut self, deletion: RecordedDeletion) {
+        if self.entries.len() == self.capacity {
+            if let Some(dropped) = self.entries.pop_front() {
+                self.forget_before(dropped.sequence, dropped.timestamp);
+            }
+        }
+        self.entries.push_back(deletion);
+    }
+
+    /// Drop everything and only answer from `sequence` / `time` on
+    pub fn reset(&mut self, sequence: u64, time: DateTime<Utc>) {
+        self.entries.clear();
+        self.oldest_sequence = sequence;
+        self.oldest_time = Some(time);
+    }
+
+    fn forget_before(&mut self, sequence: u64, time: DateTime<Utc>) {
+        self.oldest_sequence = self.oldest_sequence.max(sequence);
+        self.oldest_time = Some(self.oldest_time.map_or(time, |t| t.max(time)));
+    }
+
+    /// Fails if changes after `since` may have been forgotten
+    pub fn check(&self, since: ChangesSince) -> Result<(), ChangesError> {
+        let answerable = match since {
+            ChangesSince::Sequence(sequence) => sequence >= self.oldest_sequence,
+            ChangesSince::Time(time) => self.oldest_time.is_none_or(|oldest| time >= oldest),
+        };
+        if answerable {
+            Ok(())
+        } else {
+            Err(ChangesError::ResyncRequired {
+                oldest_sequence: self.oldest_sequence,
+            })
+        }
+    }
+
+    /// Deletions after `since`
+    pub fn since(&self, since: ChangesSince) -> impl Iterator<Item = &RecordedDeletion> {
+        self.entries.iter().filter(move |deletion| match since {
+            ChangesSince::Sequence(sequence) => deletion.sequence > sequence,
+            ChangesSince::Time(time) => deletion.timestamp > time,
+        })
+    }
+}
+
+/// Entity or deletion, ordered by sequence for paging
+pub(crate) enum Change {
+    Modified(Arc<Entity>),
+    Deleted(String, u64),
+}
+
+impl Change {
+    fn sequence(&self) -> u64 {
+        match self {
+            Change::Modified(entity) => entity.last_modified_sequence,
+            Change::Deleted(_, sequence) => *sequence,
+        }
+    }
+}
+
+/// Sort `changes` and cut the first page of about `limit`
+///
+/// A page never ends inside a group of changes sharing one sequence, so
+/// resuming after its last sequence skips nothing. `head` is the cursor when
+/// the page holds everything.
+pub(crate) fn paginate(mut changes: Vec<Change>, limit: usize, head: u64) -> Changes {
+    changes.sort_by_key(|change| change.sequence());
+
+    let mut end = changes.len().min(limit.max(1));
+    if end < changes.len() {
+        let boundary = changes[end - 1].sequence();
+        if changes[end].sequence() == boundary {
+            // Stop before the group, or take all of it if it fills the page
+            let group_start = changes[..end]
+                .iter()
+                .rposition(|c| c.sequence() != boundary)
+                .map_or(0, |i| i + 1);
+            end = if group_start > 0 {
+                group_start
+            } else {
+                changes[end..]
+                    .iter()
+                    .position(|c| c.sequence() != boundary)
+                    .map_or(changes.len(), |i| end + i)
+            };
+        }
+    }
+    let has_more = end < changes.len();
+    let next_since_seq = if has_more {
+        changes[end - 1].sequence()
+    } else {
+        changes.last().map_or(head, |c| c.sequence().max(head))
+    };
+
+    changes.truncate(end);
+    let mut entities = Vec::new();
+    let mut deleted = Vec::new();
+    for change in changes {
+        match change {
+            Change::Modified(entity) => entities.push(entity),
+            Change::Deleted(entity_id, _) => deleted.push(entity_id),
+        }
+    }
+    Changes {
+        entities,
+        deleted,
+        next_since_seq,
+        has_more,
+    }
+}
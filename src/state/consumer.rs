@@ -0,0 +1,551 @@
+//! Where the state engine's durable consumer starts reading FLUX_EVENTS.
+//!
+//! A snapshot records the last stream sequence it includes, and the consumer
+//! normally resumes right after it. When the stream has been purged or
+//! re-created since (retention changes, moving between NATS clusters), that
+//! sequence no longer lines up with the stream, so [`plan_consumer`] checks it
+//! against the stream's current range first. Startup follows the plan, and
+//! `flux repair-consumer` applies (or just reports) it on demand.
+
+use anyhow::{Context, Result};
+use async_nats::jetstream::{self, consumer::pull, consumer::DeliverPolicy};
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+
+/// Stream the state engine reads events from
+pub const EVENTS_STREAM: &str = "FLUX_EVENTS";
+
+/// Durable name of the state engine's consumer
+pub const STATE_CONSUMER: &str = "flux-state-engine";
+
+/// Sequences currently held by the stream
+///
+/// After a purge the stream is empty with `first_sequence = last_sequence + 1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct StreamRange {
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+}
+
+/// Where the consumer starts delivering
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "from", rename_all = "snake_case")]
+pub enum ConsumerStart {
+    /// Every event still in the stream
+    Beginning,
+    /// Events from `start_sequence` on
+    Sequence { start_sequence: u64 },
+}
+
+impl ConsumerStart {
+    pub fn deliver_policy(self) -> DeliverPolicy {
+        match self {
+            ConsumerStart::Beginning => DeliverPolicy::All,
+            ConsumerStart::Sequence { start_sequence } => {
+                DeliverPolicy::ByStartSequence { start_sequence }
+            }
+        }
+    }
+}
+
+/// Why the consumer can't resume right after the snapshot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SequenceGap {
+    /// Events after the snapshot were purged before they were read
+    Purged {
+        snapshot_sequence: u64,
+        first_sequence: u64,
+    },
+    /// The stream ends before the snapshot does: it was re-created
+    StreamReset {
+        snapshot_sequence: u64,
+        last_sequence: u64,
+    },
+}
+
+impl fmt::Display for SequenceGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceGap::Purged {
+                snapshot_sequence,
+                first_sequence,
+            } => write!(
+                f,
+                "snapshot ends at sequence {} but the stream starts at {}; events {}..{} are gone",
+                snapshot_sequence,
+                first_sequence,
+                snapshot_sequence + 1,
+                first_sequence - 1
+            ),
+            SequenceGap::StreamReset {
+                snapshot_sequence,
+                last_sequence,
+            } => write!(
+                f,
+                "snapshot ends at sequence {} but the stream only reaches {}; it was re-created \
+                 and events published to the old stream after the snapshot are gone",
+                snapshot_sequence, last_sequence
+            ),
+        }
+    }
+}
+
+/// How to set up the consumer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ConsumerPlan {
+    pub start: ConsumerStart,
+    /// Delete the existing consumer first; otherwise an existing one keeps
+    /// delivering from its own offset whatever `start` says
+    pub recreate: bool,
+    /// Events between the snapshot and the stream that can't be replayed
+    pub gap: Option<SequenceGap>,
+}
+
+/// Decide where the consumer starts
+///
+/// - `snapshot_sequence`: last sequence in the loaded snapshot (`None` = no snapshot)
+/// - `stream`: the stream's current range, if it could be read
+/// - `delivered`: last sequence the existing consumer delivered (`None` = no consumer)
+///
+/// Without a snapshot, or when the snapshot doesn't line up with the stream,
+/// everything still in the stream is replayed. Otherwise replay resumes after
+/// the snapshot, recreating a consumer that is anywhere else.
+pub fn plan_consumer(
+    snapshot_sequence: Option<u64>,
+    stream: Option<StreamRange>,
+    delivered: Option<u64>,
+) -> ConsumerPlan {
+    let from_beginning = |gap| ConsumerPlan {
+        start: ConsumerStart::Beginning,
+        recreate: true,
+        gap,
+    };
+    let Some(snapshot_sequence) = snapshot_sequence else {
+        return from_beginning(None);
+    };
+
+    if let Some(stream) = stream {
+        if snapshot_sequence > stream.last_sequence {
+            return from_beginning(Some(SequenceGap::StreamReset {
+                snapshot_sequence,
+                last_sequence: stream.last_sequence,
+            }));
+        }
+        if snapshot_sequence + 1 < stream.first_sequence {
+            return from_beginning(Some(SequenceGap::Purged {
+                snapshot_sequence,
+                first_sequence: stream.first_sequence,
+            }));
+        }
+    }
+
+    ConsumerPlan {
+        start: ConsumerStart::Sequence {
+            start_sequence: snapshot_sequence + 1,
+        },
+        recreate: delivered.is_some_and(|delivered| delivered != snapshot_sequence),
+        gap: None,
+    }
+}
+
+/// Pull consumer config for the state engine's consumer starting at `start`
+pub(crate) fn consumer_config(start: ConsumerStart) -> pull::Config {
+    pull::Config {
+        durable_name: Some(STATE_CONSUMER.to_string()),
+        filter_subject: "flux.events.>".to_string(),
+        deliver_policy: start.deliver_policy(),
+        ..Default::default()
+    }
+}
+
+/// The events stream and the state engine's consumer on it
+pub trait ConsumerStore: Send + Sync {
+    /// The stream's current range
+    fn range(&self) -> impl Future<Output = Result<StreamRange>> + Send;
+
+    /// Last sequence the consumer delivered, `None` if there is no consumer
+    fn delivered(&self) -> impl Future<Output = Result<Option<u64>>> + Send;
+
+    /// Delete the consumer if it exists
+    fn delete(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Create the consumer starting at `start`, unless it exists
+    fn create(&self, start: ConsumerStart) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// [`ConsumerStore`] backed by JetStream
+pub struct JetStreamConsumerStore {
+    jetstream: jetstream::Context,
+}
+
+impl JetStreamConsumerStore {
+    pub fn new(jetstream: jetstream::Context) -> Self {
+        Self { jetstream }
+    }
+
+    async fn stream(&self) -> Result<jetstream::stream::Stream> {
+        self.jetstream
+            .get_stream(EVENTS_STREAM)
+            .await
+            .with_context(|| format!("Failed to get {} stream", EVENTS_STREAM))
+    }
+}
+
+impl ConsumerStore for JetStreamConsumerStore {
+    async fn range(&self) -> Result<StreamRange> {
+        let stream = self.stream().await?;
+        let state = &stream.cached_info().state;
+        Ok(StreamRange {
+            first_sequence: state.first_sequence,
+            last_sequence: state.last_sequence,
+        })
+    }
+
+    async fn delivered(&self) -> Result<Option<u64>> {
+        // A missing consumer is the only expected failure once the stream exists
+        Ok(self
+            .stream()
+            .await?
+            .consumer_info(STATE_CONSUMER)
+            .await
+            .ok()
+            .map(|info| info.delivered.stream_sequence))
+    }
+
+    async fn delete(&self) -> Result<()> {
+        let stream = self.stream().await?;
+        if stream.consumer_info(STATE_CONSUMER).await.is_ok() {
+            stream
+                .delete_consumer(STATE_CONSUMER)
+                .await
+                .with_context(|| format!("Failed to delete consumer '{}'", STATE_CONSUMER))?;
+        }
+        Ok(())
+    }
+
+    async fn create(&self, start: ConsumerStart) -> Result<()> {
+        self.stream()
+            .await?
+            .get_or_create_consumer(STATE_CONSUMER, consumer_config(start))
+            .await
+            .with_context(|| format!("Failed to create consumer '{}'", STATE_CONSUMER))?;
+        Ok(())
+    }
+}
+
+/// What `flux repair-consumer` found and did
+#[derive(Debug, Serialize)]
+pub struct ConsumerRepair {
+    pub stream: StreamRange,
+    pub snapshot_sequence: Option<u64>,
+    /// Last sequence the consumer had delivered, if it existed
+    pub consumer_delivered: Option<u64>,
+    pub plan: ConsumerPlan,
+    /// False for a dry run
+    pub applied: bool,
+}
+
+impl fmt::Display for ConsumerRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "stream {}: sequences {}..={}",
+            EVENTS_STREAM, self.stream.first_sequence, self.stream.last_sequence
+        )?;
+        match self.snapshot_sequence {
+            Some(sequence) => writeln!(f, "snapshot: sequence {}", sequence)?,
+            None => writeln!(f, "snapshot: none")?,
+        }
+        match self.consumer_delivered {
+            Some(sequence) => writeln!(
+                f,
+                "consumer {}: delivered up to {}",
+                STATE_CONSUMER, sequence
+            )?,
+            None => writeln!(f, "consumer {}: missing", STATE_CONSUMER)?,
+        }
+        if let Some(gap) = &self.plan.gap {
+            writeln!(f, "WARNING: {}", gap)?;
+        }
+        let start = match self.plan.start {
+            ConsumerStart::Beginning => "the beginning of the stream".to_string(),
+            ConsumerStart::Sequence { start_sequence } => format!("sequence {}", start_sequence),
+        };
+        let action = match (self.plan.recreate, self.consumer_delivered) {
+            (true, Some(_)) => "recreate the consumer",
+            (false, Some(_)) => "keep the consumer",
+            (_, None) => "create the consumer",
+        };
+        let done = if self.applied { "" } else { "would " };
+        write!(f, "{}{} delivering from {}", done, action, start)
+    }
+}
+
+/// Bring the consumer in line with the snapshot at `snapshot_sequence`
+///
+/// With `dry_run`, only reports what would be done.
+pub async fn repair_consumer(
+    store: &impl ConsumerStore,
+    snapshot_sequence: Option<u64>,
+    dry_run: bool,
+) -> Result<ConsumerRepair> {
+    let stream = store.range().await?;
+    let consumer_delivered = store.delivered().await?;
+    let plan = plan_consumer(snapshot_sequence, Some(stream), consumer_delivered);
+
+    if !dry_run {
+        if plan.recreate {
+            store.delete().await?;
+        }
+        store.create(plan.start).await?;
+    }
+
+    Ok(ConsumerRepair {
+        stream,
+        snapshot_sequence,
+        consumer_delivered,
+        plan,
+        applied: !dry_run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn range(first_sequence: u64, last_sequence: u64) -> Option<StreamRange> {
+        Some(StreamRange {
+            first_sequence,
+            last_sequence,
+        })
+    }
+
+    fn from(start_sequence: u64) -> ConsumerStart {
+        ConsumerStart::Sequence { start_sequence }
+    }
+
+    #[test]
+    fn test_plan_decision_table() {
+        use ConsumerStart::Beginning;
+
+        let purged = |snapshot_sequence, first_sequence| {
+            Some(SequenceGap::Purged {
+                snapshot_sequence,
+                first_sequence,
+            })
+        };
+        let reset = |snapshot_sequence, last_sequence| {
+            Some(SequenceGap::StreamReset {
+                snapshot_sequence,
+                last_sequence,
+            })
+        };
+
+        // (snapshot, stream, delivered) => (start, recreate, gap)
+        let cases = [
+            // No snapshot: replay everything, dropping any consumer's offset
+            (None, range(1, 100), None, Beginning, true, None),
+            (None, range(1, 100), Some(100), Beginning, true, None),
+            (None, range(50, 100), Some(100), Beginning, true, None),
+            (None, None, None, Beginning, true, None),
+            // Snapshot inside the stream: resume after it
+            (Some(99), range(1, 100), None, from(100), false, None),
+            (Some(99), range(1, 100), Some(99), from(100), false, None),
+            (Some(100), range(1, 100), Some(100), from(101), false, None),
+            // ...recreating a consumer that is ahead of or behind the snapshot
+            (Some(99), range(1, 100), Some(100), from(100), true, None),
+            (Some(50), range(1, 100), Some(10), from(51), true, None),
+            // Stream starts right after the snapshot: nothing lost
+            (Some(49), range(50, 100), Some(49), from(50), false, None),
+            // Purged up to the snapshot, nothing new yet
+            (
+                Some(100),
+                range(101, 100),
+                Some(100),
+                from(101),
+                false,
+                None,
+            ),
+            // Purged past the snapshot: replay what is left
+            (
+                Some(40),
+                range(50, 100),
+                Some(40),
+                Beginning,
+                true,
+                purged(40, 50),
+            ),
+            (
+                Some(40),
+                range(151, 150),
+                None,
+                Beginning,
+                true,
+                purged(40, 151),
+            ),
+            // Re-created stream, empty or already refilled
+            (
+                Some(100),
+                range(0, 0),
+                Some(100),
+                Beginning,
+                true,
+                reset(100, 0),
+            ),
+            (
+                Some(100),
+                range(1, 30),
+                Some(30),
+                Beginning,
+                true,
+                reset(100, 30),
+            ),
+            (
+                Some(100),
+                range(1, 30),
+                None,
+                Beginning,
+                true,
+                reset(100, 30),
+            ),
+            // Empty stream, snapshot from before any events
+            (Some(0), range(0, 0), None, from(1), false, None),
+            // Stream range unknown: trust the snapshot
+            (Some(99), None, None, from(100), false, None),
+            (Some(99), None, Some(120), from(100), true, None),
+        ];
+
+        for (snapshot, stream, delivered, start, recreate, gap) in cases {
+            assert_eq!(
+                plan_consumer(snapshot, stream, delivered),
+                ConsumerPlan {
+                    start,
+                    recreate,
+                    gap
+                },
+                "snapshot {:?}, stream {:?}, delivered {:?}",
+                snapshot,
+                stream,
+                delivered
+            );
+        }
+    }
+
+    #[test]
+    fn test_start_maps_to_deliver_policy() {
+        assert!(matches!(
+            ConsumerStart::Beginning.deliver_policy(),
+            DeliverPolicy::All
+        ));
+        assert!(matches!(
+            from(100).deliver_policy(),
+            DeliverPolicy::ByStartSequence {
+                start_sequence: 100
+            }
+        ));
+    }
+
+    /// In-memory stream and consumer, recording the calls made
+    struct FakeStore {
+        range: StreamRange,
+        delivered: Mutex<Option<u64>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FakeStore {
+        fn new(first_sequence: u64, last_sequence: u64, delivered: Option<u64>) -> Self {
+            Self {
+                range: StreamRange {
+                    first_sequence,
+                    last_sequence,
+                },
+                delivered: Mutex::new(delivered),
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl ConsumerStore for FakeStore {
+        async fn range(&self) -> Result<StreamRange> {
+            Ok(self.range)
+        }
+
+        async fn delivered(&self) -> Result<Option<u64>> {
+            Ok(*self.delivered.lock().unwrap())
+        }
+
+        async fn delete(&self) -> Result<()> {
+            self.calls.lock().unwrap().push("delete".to_string());
+            *self.delivered.lock().unwrap() = None;
+            Ok(())
+        }
+
+        async fn create(&self, start: ConsumerStart) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("create {:?}", start));
+            let mut delivered = self.delivered.lock().unwrap();
+            if delivered.is_none() {
+                *delivered = Some(match start {
+                    ConsumerStart::Beginning => self.range.first_sequence.saturating_sub(1),
+                    ConsumerStart::Sequence { start_sequence } => start_sequence - 1,
+                });
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_changes() {
+        let store = FakeStore::new(50, 100, Some(100));
+        let report = repair_consumer(&store, Some(40), true).await.unwrap();
+
+        assert!(store.calls().is_empty());
+        assert!(!report.applied);
+        assert_eq!(report.consumer_delivered, Some(100));
+        assert_eq!(report.plan.start, ConsumerStart::Beginning);
+        let text = report.to_string();
+        assert!(
+            text.contains("WARNING: snapshot ends at sequence 40"),
+            "{}",
+            text
+        );
+        assert!(text
+            .ends_with("would recreate the consumer delivering from the beginning of the stream"));
+    }
+
+    #[tokio::test]
+    async fn test_repair_recreates_after_purge() {
+        let store = FakeStore::new(50, 100, Some(100));
+        let report = repair_consumer(&store, Some(40), false).await.unwrap();
+
+        assert!(report.applied);
+        assert_eq!(store.calls(), ["delete", "create Beginning"]);
+    }
+
+    #[tokio::test]
+    async fn test_repair_resumes_after_snapshot_and_is_idempotent() {
+        let store = FakeStore::new(1, 100, Some(100));
+        repair_consumer(&store, Some(80), false).await.unwrap();
+        assert_eq!(
+            store.calls(),
+            ["delete", "create Sequence { start_sequence: 81 }"]
+        );
+
+        // The consumer now lines up with the snapshot and is kept
+        let report = repair_consumer(&store, Some(80), false).await.unwrap();
+        assert!(!report.plan.recreate);
+        assert_eq!(store.calls().len(), 3);
+        assert!(report
+            .to_string()
+            .ends_with("keep the consumer delivering from sequence 81"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Property deadbands: numeric updates too small to matter are dropped before
+//! they reach state, so a noisy sensor doesn't flood subscribers, history and
+//! snapshots.
+//!
+//! Rules come from the `deadband_by_stream` and `deadband_by_namespace`
+//! runtime config. A dropped update writes nothing and broadcasts nothing, but
+//! every `keepalive_seconds` one goes through anyway so `last_updated` keeps
+//! moving.
+
+use crate::config::RuntimeConfigHandle;
+use chrono::Duration;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Default time after which an unchanged property is written anyway
+pub const DEFAULT_DEADBAND_KEEPALIVE_SECONDS: u64 = 300;
+
+/// Smallest change of a numeric property that is applied
+///
+/// Exactly one of `min_change` and `min_change_percent` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"min_change": 0.1, "keepalive_seconds": 300}))]
+pub struct DeadbandRule {
+    /// Absolute change below which an update is dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_change: Option<f64>,
+    /// Change below which an update is dropped, in percent of the previous value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_change_percent: Option<f64>,
+    /// Seconds after the last write when an update goes through regardless (0 = never)
+    #[serde(default = "default_keepalive_seconds")]
+    pub keepalive_seconds: u64,
+}
+
+fn default_keepalive_seconds() -> u64 {
+    DEFAULT_DEADBAND_KEEPALIVE_SECONDS
+}
+
+impl DeadbandRule {
+    pub fn validate(&self) -> Result<(), String> {
+        let threshold = match (self.min_change, self.min_change_percent) {
+            (Some(threshold), None) | (None, Some(threshold)) => threshold,
+            _ => return Err("set exactly one of min_change and min_change_percent".to_string()),
+        };
+        if !threshold.is_finite() || threshold <= 0.0 {
+            return Err(format!(
+                "threshold must be a positive number (got {})",
+                threshold
+            ));
+        }
+        Ok(())
+    }
+
+    /// True if changing a property from `previous` to `new` is within the
+    /// deadband, so the update should be dropped
+    ///
+    /// Only numbers are dampened: a missing or non-numeric previous value, or a
+    /// non-numeric new one, always goes through. So does any update once
+    /// `since_write` (time since the property was last written, None if
+    /// unknown) reaches the keepalive.
+    pub fn dampens(
+        &self,
+        previous: Option<&Value>,
+        new: &Value,
+        since_write: Option<Duration>,
+    ) -> bool {
+        let (Some(Value::Number(previous)), Value::Number(new)) = (previous, new) else {
+            return false;
+        };
+        let Some(since_write) = since_write else {
+            return false;
+        };
+        let keepalive = self.keepalive_seconds.min(i64::MAX as u64 / 1_000) as i64;
+        if keepalive > 0 && since_write >= Duration::seconds(keepalive) {
+            return false;
+        }
+        let Some((change, magnitude)) = change(previous, new) else {
+            return false;
+        };
+        if change == 0.0 {
+            return true;
+        }
+        let threshold = match (self.min_change, self.min_change_percent) {
+            (Some(min_change), _) => min_change,
+            (None, Some(percent)) => magnitude * percent / 100.0,
+            (None, None) => return false,
+        };
+        change < threshold
+    }
+}
+
+/// Absolute change from `previous` to `new` and the magnitude of `previous`,
+/// exact for integers
+fn change(previous: &Number, new: &Number) -> Option<(f64, f64)> {
+    if let (Some(previous), Some(new)) = (previous.as_i64(), new.as_i64()) {
+        let change = (new as i128 - previous as i128).unsigned_abs();
+        return Some((change as f64, previous.unsigned_abs() as f64));
+    }
+    let (previous, new) = (previous.as_f64()?, new.as_f64()?);
+    Some(((new - previous).abs(), previous.abs()))
+}
+
+/// Deadband rules resolved per stream, refreshed when the runtime config
+/// generation changes
+#[derive(Default)]
+pub(crate) struct DeadbandCache {
+    streams: DashMap<String, StreamRules>,
+}
+
+#[derive(Clone)]
+struct StreamRules {
+    /// Runtime config generation the rules were read at
+    generation: u64,
+    /// The stream's own rule
+    stream: Option<DeadbandRule>,
+    /// Namespace rules, for streams without one
+    namespaces: Arc<BTreeMap<String, DeadbandRule>>,
+}
+
+impl DeadbandCache {
+    /// Rule for an update on `stream` to an entity in `namespace`
+    pub(crate) fn rule_for(
+        &self,
+        config: &RuntimeConfigHandle,
+        stream: &str,
+        namespace: Option<&str>,
+    ) -> Option<DeadbandRule> {
+        // Read before the config, so rules read from a newer config are at
+        // worst refreshed once more
+        let generation = config.generation();
+        let cached = self
+            .streams
+            .get(stream)
+            .filter(|rules| rules.generation == generation)
+            .map(|rules| rules.clone());
+        let rules = cached.unwrap_or_else(|| {
+            let config = config.read().unwrap();
+            let rules = StreamRules {
+                generation,
+                stream: config.deadband_by_stream.get(stream).copied(),
+                namespaces: Arc::new(config.deadband_by_namespace.clone()),
+            };
+            drop(config);
+            self.streams.insert(stream.to_string(), rules.clone());
+            rules
+        });
+        rules
+            .stream
+            .or_else(|| rules.namespaces.get(namespace?).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn absolute(min_change: f64) -> DeadbandRule {
+        DeadbandRule {
+            min_change: Some(min_change),
+            min_change_percent: None,
+            keepalive_seconds: 60,
+        }
+    }
+
+    fn percent(min_change_percent: f64) -> DeadbandRule {
+        DeadbandRule {
+            min_change: None,
+            min_change_percent: Some(min_change_percent),
+            keepalive_seconds: 60,
+        }
+    }
+
+    fn recent() -> Option<Duration> {
+        Some(Duration::seconds(1))
+    }
+
+    #[test]
+    fn test_floats() {
+        let rule = absolute(0.1);
+        assert!(rule.dampens(Some(&json!(21.001)), &json!(21.002), recent()));
+        assert!(rule.dampens(Some(&json!(21.0)), &json!(20.95), recent()));
+        assert!(!rule.dampens(Some(&json!(21.0)), &json!(21.2), recent()));
+        assert!(!rule.dampens(Some(&json!(21.0)), &json!(20.8), recent()));
+
+        let rule = percent(1.0);
+        assert!(rule.dampens(Some(&json!(200.0)), &json!(201.5), recent()));
+        assert!(!rule.dampens(Some(&json!(200.0)), &json!(202.5), recent()));
+        assert!(!rule.dampens(Some(&json!(-200.0)), &json!(-197.0), recent()));
+    }
+
+    #[test]
+    fn test_integers() {
+        let rule = absolute(5.0);
+        assert!(rule.dampens(Some(&json!(100)), &json!(104), recent()));
+        // The threshold itself is a change worth applying
+        assert!(!rule.dampens(Some(&json!(100)), &json!(105), recent()));
+        assert!(!rule.dampens(Some(&json!(i64::MIN)), &json!(i64::MAX), recent()));
+        assert!(rule.dampens(Some(&json!(u64::MAX)), &json!(u64::MAX), recent()));
+        // Integer against float
+        assert!(rule.dampens(Some(&json!(100)), &json!(102.5), recent()));
+
+        // A previous 0 has no relative band, but an unchanged value is dropped
+        let rule = percent(10.0);
+        assert!(!rule.dampens(Some(&json!(0)), &json!(1), recent()));
+        assert!(rule.dampens(Some(&json!(0)), &json!(0), recent()));
+        assert!(rule.dampens(Some(&json!(50)), &json!(54), recent()));
+    }
+
+    #[test]
+    fn test_missing_and_non_numeric_values_go_through() {
+        let rule = absolute(1.0);
+        assert!(!rule.dampens(None, &json!(1), recent()));
+        assert!(!rule.dampens(Some(&json!("1")), &json!(1), recent()));
+        assert!(!rule.dampens(Some(&json!(1)), &json!("1"), recent()));
+        assert!(!rule.dampens(Some(&json!(true)), &json!(true), recent()));
+        assert!(!rule.dampens(Some(&json!(1)), &json!(null), recent()));
+        // No write time recorded: let it through so one gets recorded
+        assert!(!rule.dampens(Some(&json!(1)), &json!(1), None));
+    }
+
+    #[test]
+    fn test_keepalive_forces_update_through() {
+        let rule = absolute(1.0);
+        assert!(rule.dampens(Some(&json!(1)), &json!(1), Some(Duration::seconds(59))));
+        assert!(!rule.dampens(Some(&json!(1)), &json!(1), Some(Duration::seconds(60))));
+        assert!(!rule.dampens(Some(&json!(1)), &json!(1.5), Some(Duration::hours(1))));
+
+        let never = DeadbandRule {
+            keepalive_seconds: 0,
+            ..rule
+        };
+        assert!(never.dampens(Some(&json!(1)), &json!(1), Some(Duration::days(365))));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(absolute(0.1).validate().is_ok());
+        assert!(percent(5.0).validate().is_ok());
+        assert!(absolute(0.0).validate().is_err());
+        assert!(absolute(f64::NAN).validate().is_err());
+        assert!(percent(-1.0).validate().is_err());
+        let both = DeadbandRule {
+            min_change_percent: Some(1.0),
+            ..absolute(0.1)
+        };
+        assert!(both.validate().is_err());
+        let neither = DeadbandRule {
+            min_change: None,
+            ..absolute(0.1)
+        };
+        assert!(neither.validate().is_err());
+    }
+
+    #[test]
+    fn test_cache_refreshes_on_config_update() {
+        let config = crate::config::new_runtime_config();
+        let cache = DeadbandCache::default();
+        assert_eq!(cache.rule_for(&config, "sensors", Some("plant")), None);
+
+        let update = serde_json::from_value(json!({
+            "deadband_by_namespace": {"plant": {"min_change": 0.5}},
+            "deadband_by_stream": {"sensors": {"min_change_percent": 1.0}}
+        }))
+        .unwrap();
+        config.apply_update(&update).unwrap();
+
+        // The stream's rule wins over the namespace's
+        let rule = cache.rule_for(&config, "sensors", Some("plant")).unwrap();
+        assert_eq!(rule.min_change_percent, Some(1.0));
+        let rule = cache.rule_for(&config, "other", Some("plant")).unwrap();
+        assert_eq!(rule.min_change, Some(0.5));
+        assert_eq!(cache.rule_for(&config, "other", Some("office")), None);
+        assert_eq!(cache.rule_for(&config, "other", None), None);
+
+        let update = serde_json::from_value(json!({"deadband_by_stream": {}})).unwrap();
+        config.apply_update(&update).unwrap();
+        let rule = cache.rule_for(&config, "sensors", Some("plant")).unwrap();
+        assert_eq!(rule.min_change, Some(0.5));
+    }
+}
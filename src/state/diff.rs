@@ -0,0 +1,332 @@
+//! Comparing two engines' worlds entity by entity, for checking that
+//! different ways of building state (snapshot plus tail, full replay,
+//! compaction, another site) agree.
+//!
+//! Only entity IDs are collected up front; entities are then compared one ID
+//! at a time by reference, so the comparison itself never copies a world.
+
+use crate::state::{Entity, StateEngine};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// Default number of divergent entities listed in a [`StateDiff`]
+pub const DEFAULT_MAX_REPORTED_DIVERGENCES: usize = 100;
+
+/// How closely [`diff_engines`] compares
+#[derive(Clone, Debug)]
+pub struct DiffOptions {
+    /// Entities whose `last_updated` differ by no more than this match
+    pub last_updated_tolerance: Duration,
+    /// Divergent entities listed in the report; all are counted
+    pub max_reported: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            last_updated_tolerance: Duration::seconds(1),
+            max_reported: DEFAULT_MAX_REPORTED_DIVERGENCES,
+        }
+    }
+}
+
+/// First difference found in one entity
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Difference {
+    /// Only the right world has the entity
+    MissingLeft,
+    /// Only the left world has the entity
+    MissingRight,
+    /// The first property, in name order, whose value differs (None = absent)
+    Property {
+        property: String,
+        left: Option<Value>,
+        right: Option<Value>,
+    },
+    /// Same properties, `last_updated` further apart than the tolerance
+    LastUpdated {
+        left: DateTime<Utc>,
+        right: DateTime<Utc>,
+    },
+}
+
+/// One entity the two worlds disagree on
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EntityDivergence {
+    pub entity_id: String,
+    #[serde(flatten)]
+    pub difference: Difference,
+}
+
+/// Result of comparing two worlds
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StateDiff {
+    pub left_entities: usize,
+    pub right_entities: usize,
+    pub matching: usize,
+    /// Every divergent entity is counted, even past `max_reported`
+    pub divergent: usize,
+    /// The first divergent entities in ID order
+    pub divergences: Vec<EntityDivergence>,
+}
+
+impl StateDiff {
+    pub fn is_match(&self) -> bool {
+        self.divergent == 0
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::MissingLeft => write!(f, "missing on the left"),
+            Difference::MissingRight => write!(f, "missing on the right"),
+            Difference::Property {
+                property,
+                left,
+                right,
+            } => write!(
+                f,
+                "property {}: {} vs {}",
+                property,
+                display_value(left),
+                display_value(right)
+            ),
+            Difference::LastUpdated { left, right } => write!(
+                f,
+                "last_updated: {} vs {}",
+                left.to_rfc3339(),
+                right.to_rfc3339()
+            ),
+        }
+    }
+}
+
+fn display_value(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(absent)".to_string(),
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "entities: {} left, {} right, {} matching, {} divergent",
+            self.left_entities, self.right_entities, self.matching, self.divergent
+        )?;
+        for divergence in &self.divergences {
+            writeln!(f, "  {}: {}", divergence.entity_id, divergence.difference)?;
+        }
+        if self.divergent > self.divergences.len() {
+            writeln!(
+                f,
+                "  ... and {} more",
+                self.divergent - self.divergences.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare every entity of `left` with the same entity of `right`
+///
+/// Neither engine is changed; entities updated while comparing are compared
+/// as they are when reached.
+pub fn diff_engines(left: &StateEngine, right: &StateEngine, options: &DiffOptions) -> StateDiff {
+    let ids: BTreeSet<String> = left
+        .entities
+        .iter()
+        .chain(right.entities.iter())
+        .map(|entry| entry.key().clone())
+        .collect();
+
+    let mut diff = StateDiff {
+        left_entities: left.entities.len(),
+        right_entities: right.entities.len(),
+        ..StateDiff::default()
+    };
+    for entity_id in ids {
+        let l = left.entities.get(&entity_id).map(|e| Arc::clone(e.value()));
+        let r = right
+            .entities
+            .get(&entity_id)
+            .map(|e| Arc::clone(e.value()));
+        match diff_entity(l.as_deref(), r.as_deref(), options) {
+            None => diff.matching += 1,
+            Some(difference) => {
+                diff.divergent += 1;
+                if diff.divergences.len() < options.max_reported {
+                    diff.divergences.push(EntityDivergence {
+                        entity_id,
+                        difference,
+                    });
+                }
+            }
+        }
+    }
+    diff
+}
+
+/// First difference between two versions of an entity, if any
+pub fn diff_entity(
+    left: Option<&Entity>,
+    right: Option<&Entity>,
+    options: &DiffOptions,
+) -> Option<Difference> {
+    let (left, right) = match (left, right) {
+        (None, None) => return None,
+        (None, Some(_)) => return Some(Difference::MissingLeft),
+        (Some(_), None) => return Some(Difference::MissingRight),
+        (Some(left), Some(right)) => (left, right),
+    };
+
+    let properties: BTreeSet<&String> = left
+        .properties
+        .keys()
+        .chain(right.properties.keys())
+        .collect();
+    for property in properties {
+        let l = left.properties.get(property);
+        let r = right.properties.get(property);
+        if l != r {
+            return Some(Difference::Property {
+                property: property.clone(),
+                left: l.cloned(),
+                right: r.cloned(),
+            });
+        }
+    }
+
+    let apart = (left.last_updated - right.last_updated).abs();
+    if apart > options.last_updated_tolerance {
+        return Some(Difference::LastUpdated {
+            left: left.last_updated,
+            right: right.last_updated,
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::FluxEvent;
+    use serde_json::json;
+
+    fn event(entity_id: &str, properties: Value, timestamp: i64) -> FluxEvent {
+        FluxEvent {
+            event_id: None,
+            stream: "test".to_string(),
+            source: "test".to_string(),
+            timestamp,
+            received_at: None,
+            key: None,
+            schema: None,
+            payload: json!({ "entity_id": entity_id, "properties": properties }),
+        }
+    }
+
+    /// Two engines holding the same three entities
+    fn twins() -> (StateEngine, StateEngine) {
+        let events = [
+            event("ns/a", json!({"x": 1, "y": "on"}), 1_700_000_000_000),
+            event("ns/b", json!({"x": 2}), 1_700_000_000_000),
+            event("ns/c", json!({"x": 3}), 1_700_000_000_000),
+        ];
+        let (left, right) = (StateEngine::new(), StateEngine::new());
+        for event in &events {
+            left.process_event(event, None);
+            right.process_event(event, None);
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn test_identical_worlds_match() {
+        let (left, right) = twins();
+        let diff = diff_engines(&left, &right, &DiffOptions::default());
+        assert!(diff.is_match());
+        assert_eq!(diff.matching, 3);
+        assert_eq!((diff.left_entities, diff.right_entities), (3, 3));
+    }
+
+    #[test]
+    fn test_reports_first_difference_per_entity() {
+        let (left, right) = twins();
+        // Two differing properties: only the first by name is reported
+        right.process_event(
+            &event("ns/a", json!({"y": "off", "z": true}), 1_700_000_000_000),
+            None,
+        );
+        left.process_event(
+            &event("ns/only-left", json!({"x": 0}), 1_700_000_000_000),
+            None,
+        );
+        right.process_event(
+            &event("ns/only-right", json!({"x": 0}), 1_700_000_000_000),
+            None,
+        );
+        // Same value, written a minute later
+        right.process_event(&event("ns/c", json!({"x": 3}), 1_700_000_060_000), None);
+
+        let diff = diff_engines(&left, &right, &DiffOptions::default());
+        assert_eq!(diff.matching, 1);
+        assert_eq!(diff.divergent, 4);
+        let found: Vec<_> = diff
+            .divergences
+            .iter()
+            .map(|d| (d.entity_id.as_str(), d.difference.clone()))
+            .collect();
+        assert_eq!(
+            found[0],
+            (
+                "ns/a",
+                Difference::Property {
+                    property: "y".to_string(),
+                    left: Some(json!("on")),
+                    right: Some(json!("off")),
+                }
+            )
+        );
+        assert!(matches!(found[1], ("ns/c", Difference::LastUpdated { .. })));
+        assert_eq!(found[2], ("ns/only-left", Difference::MissingRight));
+        assert_eq!(found[3], ("ns/only-right", Difference::MissingLeft));
+
+        // A wide enough tolerance accepts the later write
+        let lenient = DiffOptions {
+            last_updated_tolerance: Duration::minutes(5),
+            ..DiffOptions::default()
+        };
+        assert_eq!(diff_engines(&left, &right, &lenient).divergent, 3);
+    }
+
+    #[test]
+    fn test_counts_past_max_reported() {
+        let (left, right) = twins();
+        for i in 0..5 {
+            left.process_event(
+                &event(
+                    &format!("ns/extra-{}", i),
+                    json!({"x": i}),
+                    1_700_000_000_000,
+                ),
+                None,
+            );
+        }
+        let options = DiffOptions {
+            max_reported: 2,
+            ..DiffOptions::default()
+        };
+        let diff = diff_engines(&left, &right, &options);
+        assert_eq!(diff.divergent, 5);
+        assert_eq!(diff.divergences.len(), 2);
+        assert!(diff.to_string().contains("... and 3 more"));
+    }
+}
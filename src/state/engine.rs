@@ -1,35 +1,154 @@
-use crate::event::FluxEvent;
-use crate::state::entity::{Entity, EntityDeleted, StateUpdate};
+use crate::config::SharedRuntimeConfig;
+use crate::entity::IdNormalization;
+use crate::event::{timestamp_is_plausible, FluxEvent, DEFAULT_MAX_TIMESTAMP_SKEW_MS};
+use crate::mapping::CompiledMapping;
+use crate::nats::{
+    is_ingest_subject, ConnectionMonitor, ConnectionStatus, COMPACTED_THROUGH_HEADER,
+    REQUEST_ID_HEADER,
+};
+use crate::state::changes::{
+    paginate, Change, Changes, ChangesError, ChangesSince, DeletionLog, RecordedDeletion,
+    DEFAULT_DELETION_LOG_CAPACITY,
+};
+use crate::state::consumer::{
+    plan_consumer, ConsumerStart, ConsumerStore, JetStreamConsumerStore, STATE_CONSUMER,
+};
+use crate::state::deadband::DeadbandCache;
+use crate::state::entity::{
+    AppliedEvent, DeletedEntity, Entity, EntityDeleted, EntityUpdate, PropertyChange,
+    RenameOutcome, RenamePreference, StateUpdate,
+};
+use crate::state::journal::{JournalFeed, JournalRecord};
+use crate::state::messages::{
+    AgentMessage, MessageLog, DEFAULT_MESSAGES_PER_RECIPIENT, MESSAGES_STREAM,
+};
 use crate::state::metrics::MetricsTracker;
-use anyhow::{Context, Result};
+use crate::state::property_index::PropertyIndex;
+use crate::state::publishers::{
+    PublisherInfo, PublisherRegistry, DEFAULT_MAX_PUBLISHERS, PUBLISHER_ENTITY_PREFIX,
+};
+use crate::state::quotas::{
+    entity_bytes, namespace_of, property_bytes, NamespaceQuota, NamespaceUsage, QuotaExceeded,
+    QuotaRecord, QuotaState, QUOTAS_STREAM,
+};
+use crate::state::recent::{RecentActivity, RecentChange, DEFAULT_RECENT_ACTIVITY_CAPACITY};
+use crate::state::startup_replay::{StartupReplay, StartupReplayProgress};
+use crate::state::subscriber::{
+    EventMessage, EventSubscription, RESUBSCRIBE_MAX_BACKOFF, RESUBSCRIBE_MIN_BACKOFF,
+};
+use anyhow::Result;
 use async_nats::jetstream;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
-use futures::StreamExt;
-use serde_json::Value;
-use std::collections::HashMap;
+use futures::{Stream, StreamExt};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+#[cfg(test)]
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{error, info, warn};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info, info_span, warn};
 
 /// State engine maintains in-memory world state
 pub struct StateEngine {
     /// Lock-free concurrent map for fast reads
-    pub(crate) entities: Arc<DashMap<String, Entity>>,
+    ///
+    /// Entities are stored behind `Arc` and mutated copy-on-write, so readers
+    /// (snapshots, queries) can hold references without cloning property maps.
+    pub(crate) entities: Arc<DashMap<String, Arc<Entity>>>,
+
+    /// Broadcast channel for all state change events (wildcard consumers)
+    ///
+    /// Only fed while it has receivers, so narrow subscribers that use the
+    /// shard channels don't pay for every update.
+    state_tx: broadcast::Sender<EntityUpdate>,
 
-    /// Broadcast channel for state change events
-    state_tx: broadcast::Sender<StateUpdate>,
+    /// Per-shard broadcast channels, keyed by hash of entity_id
+    state_shards: Vec<broadcast::Sender<EntityUpdate>>,
 
     /// Broadcast channel for entity deletion events
     deletion_tx: broadcast::Sender<EntityDeleted>,
 
+    /// Broadcast channel for agent messages
+    message_tx: broadcast::Sender<AgentMessage>,
+
+    /// Recent agent messages per recipient
+    message_log: Mutex<MessageLog>,
+
+    /// Last live messages and property changes, for clients catching up
+    recent: Mutex<RecentActivity>,
+
+    /// Event sources seen, with their last activity
+    publishers: Mutex<PublisherRegistry>,
+
+    /// Keep each publisher as a `_system/publisher/{source}` entity
+    publisher_entities: bool,
+
     /// Last processed NATS sequence number
     last_processed_sequence: AtomicU64,
 
+    /// Bumped on every entity change or deletion, for conditional requests
+    world_version: AtomicU64,
+
+    /// `world_version` as of the last change in each namespace
+    namespace_versions: DashMap<String, u64>,
+
+    /// Random per-engine value, so versions from before a restart aren't reused
+    version_epoch: u64,
+
+    /// Recent deletions, for "changed since" queries
+    deletion_log: Mutex<DeletionLog>,
+
+    /// Recently deleted entities, recoverable until purged
+    trash: DashMap<String, DeletedEntity>,
+
+    /// Most deleted entities kept; the oldest is dropped to make room
+    trash_capacity: usize,
+
+    /// How long deleted entities stay recoverable
+    trash_retention: chrono::Duration,
+
     /// True during NATS replay on startup; broadcasts are suppressed
     replaying: AtomicBool,
 
+    /// Sequence range and start time of the startup replay, for progress reports
+    startup_replay: StartupReplay,
+
+    /// Times `set_live` was called
+    #[cfg(test)]
+    set_live_calls: AtomicUsize,
+
+    /// Upper bound on properties per entity; updates that would exceed it are rejected
+    max_properties_per_entity: usize,
+
+    /// Payload mappings for streams that don't use the entity_id/properties shape
+    stream_mappings: DashMap<String, Arc<CompiledMapping>>,
+
+    /// Quota and usage of each namespace with a quota, set by quota events
+    quotas: DashMap<String, QuotaState>,
+
+    /// Source of the per-namespace entity ID normalization and deadbands
+    /// (none = IDs used as published, nothing dampened)
+    runtime_config: Option<SharedRuntimeConfig>,
+
+    /// Deadband rules resolved per stream from `runtime_config`
+    deadbands: DeadbandCache,
+
+    /// Entities by value of the `indexed_properties` in `runtime_config`
+    property_index: PropertyIndex,
+
+    /// NATS connection state; counts the messages received since it reopened
+    connection: ConnectionMonitor,
+
+    /// Writer of the persistent entity journal, if enabled
+    journal: Option<JournalFeed>,
+
     /// Metrics tracker for monitoring
     pub metrics: MetricsTracker,
 
@@ -37,84 +156,601 @@ pub struct StateEngine {
     pub(crate) metrics_tx: broadcast::Sender<crate::state::metrics_broadcaster::MetricsUpdate>,
 }
 
+/// Default number of state broadcast shards
+pub const DEFAULT_BROADCAST_SHARDS: usize = 8;
+
+/// Property value that removes the property instead of setting it
+pub const UNSET_MARKER: &str = "__unset__";
+
+/// Property holding the published ID of an entity whose ID was normalized
+pub const RAW_ID_PROPERTY: &str = "__raw_id";
+
+/// Where an update came from, passed through to subscribers
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct UpdateOrigin<'a> {
+    /// Source of the originating event
+    pub source: Option<&'a str>,
+    /// Correlation ID the event was published with
+    pub request_id: Option<&'a str>,
+}
+
+/// When an event's changes took effect: the producer timestamp if it passes
+/// the ingestion sanity checks, else the server receive time, else now
+pub(crate) fn event_time(event: &FluxEvent) -> DateTime<Utc> {
+    let now = Utc::now();
+    let reference = event.received_at.unwrap_or_else(|| now.timestamp_millis());
+    let plausible =
+        timestamp_is_plausible(event.timestamp, reference, DEFAULT_MAX_TIMESTAMP_SKEW_MS);
+    let millis = if plausible {
+        event.timestamp
+    } else {
+        reference
+    };
+    DateTime::from_timestamp_millis(millis).unwrap_or(now)
+}
+
+fn retention_duration(seconds: u64) -> chrono::Duration {
+    chrono::Duration::seconds(seconds.min(i64::MAX as u64 / 1_000) as i64)
+}
+
+/// True if `value` is `{"__unset__": true}`, or null when `null_unsets` is set
+pub(crate) fn is_unset(value: &Value, null_unsets: bool) -> bool {
+    match value {
+        Value::Object(map) => map.len() == 1 && map.get(UNSET_MARKER) == Some(&Value::Bool(true)),
+        Value::Null => null_unsets,
+        _ => false,
+    }
+}
+
+/// Default cap on properties per entity
+pub const DEFAULT_MAX_PROPERTIES_PER_ENTITY: usize = 1_024;
+
+/// Default number of deleted entities kept for undelete
+pub const DEFAULT_TRASH_CAPACITY: usize = 10_000;
+
+/// Default time deleted entities stay recoverable (24h)
+pub const DEFAULT_TRASH_RETENTION_SECONDS: u64 = 86_400;
+
+/// Why an entity rename was refused
+#[derive(Debug, PartialEq)]
+pub enum RenameError {
+    /// Old and new IDs are the same
+    SameId,
+    /// No entity with the old ID
+    NotFound(String),
+    /// The new ID is taken and merging was not requested
+    TargetExists(String),
+    /// The merged entity would exceed the per-entity property cap
+    TooManyProperties { count: usize, max: usize },
+}
+
+impl std::fmt::Display for RenameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenameError::SameId => write!(f, "Old and new entity IDs are the same"),
+            RenameError::NotFound(id) => write!(f, "Entity '{}' not found", id),
+            RenameError::TargetExists(id) => {
+                write!(f, "Entity '{}' already exists; set merge to join them", id)
+            }
+            RenameError::TooManyProperties { count, max } => write!(
+                f,
+                "Merged entity would have {} properties, max is {}",
+                count, max
+            ),
+        }
+    }
+}
+
 impl StateEngine {
     /// Create new state engine with broadcast channel
     pub fn new() -> Self {
+        Self::with_broadcast_shards(DEFAULT_BROADCAST_SHARDS)
+    }
+
+    /// Create new state engine with `shards` state broadcast channels (min 1)
+    pub fn with_broadcast_shards(shards: usize) -> Self {
         let (state_tx, _) = broadcast::channel(1000);
         let (deletion_tx, _) = broadcast::channel(100);
+        let (message_tx, _) = broadcast::channel(1000);
         let (metrics_tx, _) = broadcast::channel(10);
+        let state_shards = (0..shards.max(1))
+            .map(|_| broadcast::channel(1000).0)
+            .collect();
 
         Self {
             entities: Arc::new(DashMap::new()),
             state_tx,
+            state_shards,
             deletion_tx,
+            message_tx,
+            message_log: Mutex::new(MessageLog::new(DEFAULT_MESSAGES_PER_RECIPIENT)),
+            recent: Mutex::new(RecentActivity::new(DEFAULT_RECENT_ACTIVITY_CAPACITY)),
+            publishers: Mutex::new(PublisherRegistry::new(DEFAULT_MAX_PUBLISHERS)),
+            publisher_entities: false,
             last_processed_sequence: AtomicU64::new(0),
+            world_version: AtomicU64::new(0),
+            namespace_versions: DashMap::new(),
+            version_epoch: uuid::Uuid::new_v4().as_u64_pair().0,
+            deletion_log: Mutex::new(DeletionLog::new(DEFAULT_DELETION_LOG_CAPACITY)),
+            trash: DashMap::new(),
+            trash_capacity: DEFAULT_TRASH_CAPACITY,
+            trash_retention: retention_duration(DEFAULT_TRASH_RETENTION_SECONDS),
             replaying: AtomicBool::new(true),
+            startup_replay: StartupReplay::default(),
+            #[cfg(test)]
+            set_live_calls: AtomicUsize::new(0),
+            max_properties_per_entity: DEFAULT_MAX_PROPERTIES_PER_ENTITY,
+            stream_mappings: DashMap::new(),
+            quotas: DashMap::new(),
+            runtime_config: None,
+            deadbands: DeadbandCache::default(),
+            property_index: PropertyIndex::default(),
+            connection: ConnectionMonitor::new(),
+            journal: None,
             metrics: MetricsTracker::new(),
             metrics_tx,
         }
     }
 
+    /// Set the per-entity property cap (min 1)
+    pub fn with_max_properties_per_entity(mut self, max: usize) -> Self {
+        self.max_properties_per_entity = max.max(1);
+        self
+    }
+
+    /// Set how many distinct event sources metrics track (min 1)
+    pub fn with_max_tracked_sources(mut self, max: usize) -> Self {
+        self.metrics = self.metrics.with_max_sources(max);
+        self
+    }
+
+    /// Set how many publishers are tracked (min 1)
+    pub fn with_max_publishers(self, max: usize) -> Self {
+        *self.publishers.lock().unwrap() = PublisherRegistry::new(max);
+        self
+    }
+
+    /// Keep each publisher as a `_system/publisher/{source}` entity
+    ///
+    /// The entities are written at most once a second per publisher, and only
+    /// once live; [`set_live`](Self::set_live) writes them all after replay.
+    pub fn with_publisher_entities(mut self, enabled: bool) -> Self {
+        self.publisher_entities = enabled;
+        self
+    }
+
+    /// Set how many recent deletions "changed since" queries can report (min 1)
+    pub fn with_deletion_log_capacity(self, capacity: usize) -> Self {
+        *self.deletion_log.lock().unwrap() = DeletionLog::new(capacity);
+        self
+    }
+
+    /// Keep up to `capacity` deleted entities for `retention_seconds` (0 keeps none)
+    pub fn with_trash(mut self, capacity: usize, retention_seconds: u64) -> Self {
+        self.trash_capacity = capacity;
+        self.trash_retention = retention_duration(retention_seconds);
+        self
+    }
+
+    /// Keep the last `per_recipient` agent messages to each entity (0 = none)
+    pub fn with_message_log(self, per_recipient: usize) -> Self {
+        *self.message_log.lock().unwrap() = MessageLog::new(per_recipient);
+        self
+    }
+
+    /// Keep the last `capacity` live agent messages and property changes (0 = none)
+    pub fn with_recent_activity(self, capacity: usize) -> Self {
+        *self.recent.lock().unwrap() = RecentActivity::new(capacity);
+        self
+    }
+
+    /// Normalize entity IDs and dampen numeric updates as configured in
+    /// `runtime_config`
+    pub fn with_runtime_config(mut self, runtime_config: SharedRuntimeConfig) -> Self {
+        self.runtime_config = Some(runtime_config);
+        self
+    }
+
+    /// Report the state of this NATS connection
+    pub fn with_connection(mut self, connection: ConnectionMonitor) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    /// Send every applied update to the entity journal writer through `tx`
+    ///
+    /// `head` is the highest stream sequence the journal already holds;
+    /// updates replayed from the stream up to it aren't sent again.
+    pub fn with_journal(mut self, tx: mpsc::UnboundedSender<JournalRecord>, head: u64) -> Self {
+        self.journal = Some(JournalFeed::new(tx, head));
+        self
+    }
+
+    pub fn connection_status(&self) -> ConnectionStatus {
+        self.connection.status()
+    }
+
+    /// Read events on `stream` through `mapping`, replacing any previous one
+    pub fn set_stream_mapping(&self, stream: &str, mapping: CompiledMapping) {
+        self.stream_mappings
+            .insert(stream.to_string(), Arc::new(mapping));
+    }
+
+    /// Go back to the default payload shape for `stream`; false if it had no mapping
+    pub fn remove_stream_mapping(&self, stream: &str) -> bool {
+        self.stream_mappings.remove(stream).is_some()
+    }
+
+    /// True if events on `stream` are read through a mapping
+    pub fn has_stream_mapping(&self, stream: &str) -> bool {
+        self.stream_mappings.contains_key(stream)
+    }
+
+    /// Streams whose events are read through a mapping
+    pub fn mapped_streams(&self) -> Vec<String> {
+        self.stream_mappings
+            .iter()
+            .map(|mapping| mapping.key().clone())
+            .collect()
+    }
+
+    /// Normalization configured for `entity_id`'s namespace
+    fn id_normalization(&self, entity_id: &str) -> IdNormalization {
+        match &self.runtime_config {
+            Some(config) => config.read().unwrap().id_normalization_for(entity_id),
+            None => IdNormalization::Off,
+        }
+    }
+
+    /// `entity_id` as events with that ID are stored in state
+    ///
+    /// Callers that look entities up by a client-supplied ID go through this so
+    /// the raw ID keeps working once normalization is on.
+    pub fn normalize_entity_id<'a>(&self, entity_id: &'a str) -> Cow<'a, str> {
+        crate::entity::normalize_entity_id(entity_id, self.id_normalization(entity_id))
+    }
+
     /// Update entity property (core state mutation)
-    pub fn update_property(
-        &self,
-        entity_id: &str,
-        property: &str,
-        value: Value,
-    ) -> StateUpdate {
-        let now = Utc::now();
+    pub fn update_property(&self, entity_id: &str, property: &str, value: Value) -> StateUpdate {
+        self.update_properties(entity_id, [(property.to_string(), value)])
+            .into_state_updates()
+            .pop()
+            .expect("single-property update yields one change")
+    }
 
-        // Get or create entity
-        let mut entity = self
-            .entities
-            .entry(entity_id.to_string())
-            .or_insert_with(|| Entity {
-                id: entity_id.to_string(),
-                properties: HashMap::new(),
-                last_updated: now,
-            });
+    /// Update several properties of one entity atomically
+    ///
+    /// All properties are applied under a single entity guard, last_updated is
+    /// bumped once, and a single EntityUpdate is broadcast for the whole set.
+    pub fn update_properties<I>(&self, entity_id: &str, properties: I) -> EntityUpdate
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        self.apply_changes(
+            entity_id,
+            properties
+                .into_iter()
+                .map(|(property, value)| (property, Some(value))),
+            Utc::now(),
+            None,
+            None,
+            UpdateOrigin::default(),
+        )
+    }
 
-        // Get old value for delta tracking
-        let old_value = entity.properties.get(property).cloned();
+    /// Set (`Some`) or remove (`None`) properties of one entity atomically,
+    /// stamping the entity and the update with `now`
+    ///
+    /// Removing a property that isn't set produces no change. An update that
+    /// only removes properties never creates the entity, and setting one takes
+    /// the entity out of the trash. `applied` becomes the
+    /// entity's last applied event unless it orders before the current one.
+    /// A change is stamped with its stream `sequence`, or the last processed
+    /// one if it didn't come from the stream. `origin` is passed through to
+    /// subscribers.
+    pub(crate) fn apply_changes<I>(
+        &self,
+        entity_id: &str,
+        properties: I,
+        now: DateTime<Utc>,
+        applied: Option<AppliedEvent>,
+        sequence: Option<u64>,
+        origin: UpdateOrigin<'_>,
+    ) -> EntityUpdate
+    where
+        I: IntoIterator<Item = (String, Option<Value>)>,
+    {
+        let properties: Vec<(String, Option<Value>)> = properties.into_iter().collect();
+
+        let mut created = false;
+        let existing = if properties.iter().all(|(_, value)| value.is_none()) {
+            self.entities.get_mut(entity_id)
+        } else {
+            // A written entity is live again; an undelete lands here too
+            self.trash.remove(entity_id);
+
+            // Get or create entity
+            Some(
+                self.entities
+                    .entry(entity_id.to_string())
+                    .or_insert_with(|| {
+                        created = true;
+                        Arc::new(Entity {
+                            id: entity_id.to_string(),
+                            properties: HashMap::new(),
+                            last_updated: now,
+                            last_applied: None,
+                            last_modified_sequence: 0,
+                            property_times: HashMap::new(),
+                        })
+                    }),
+            )
+        };
+        let Some(mut entry) = existing else {
+            return EntityUpdate {
+                entity_id: entity_id.to_string(),
+                changes: Vec::new(),
+                timestamp: now,
+                source: origin.source.map(str::to_string),
+                entity_last_updated: None,
+                request_id: origin.request_id.map(str::to_string),
+            };
+        };
 
-        // Update property
-        entity.properties.insert(property.to_string(), value.clone());
+        // Copy-on-write: only clones if a reader still holds this Arc
+        let entity = Arc::make_mut(entry.value_mut());
+
+        let mut changes = Vec::new();
+        for (property, value) in properties {
+            match value {
+                Some(value) => {
+                    entity.property_times.insert(property.clone(), now);
+                    // Get old value for delta tracking
+                    let old_value = entity.properties.insert(property.clone(), value.clone());
+                    changes.push(PropertyChange {
+                        property,
+                        old_value,
+                        new_value: value,
+                        removed: false,
+                    });
+                }
+                None => {
+                    entity.property_times.remove(&property);
+                    if let Some(old_value) = entity.properties.remove(&property) {
+                        changes.push(PropertyChange {
+                            property,
+                            old_value: Some(old_value),
+                            new_value: Value::Null,
+                            removed: true,
+                        });
+                    }
+                }
+            }
+        }
         entity.last_updated = now;
+        if !changes.is_empty() {
+            entity.last_modified_sequence =
+                sequence.unwrap_or_else(|| self.get_last_processed_sequence());
+            self.property_index.apply(entity_id, &changes);
+        }
+        if let Some(applied) = applied {
+            let newest = entity
+                .last_applied
+                .as_ref()
+                .is_none_or(|last| !applied.is_older_than(last));
+            if newest {
+                entity.last_applied = Some(applied);
+            }
+        }
+
+        // Release the entry guard before broadcasting
+        drop(entry);
+        self.bump_version(entity_id);
+
+        if !changes.is_empty() {
+            self.track_usage(entity_id, created as i64, || {
+                changes
+                    .iter()
+                    .map(|change| {
+                        let old = change
+                            .old_value
+                            .as_ref()
+                            .map_or(0, |old| property_bytes(&change.property, old));
+                        let new = if change.removed {
+                            0
+                        } else {
+                            property_bytes(&change.property, &change.new_value)
+                        };
+                        new - old
+                    })
+                    .sum()
+            });
+        }
 
-        // Create state update
-        let update = StateUpdate {
+        let update = EntityUpdate {
             entity_id: entity_id.to_string(),
-            property: property.to_string(),
-            old_value,
-            new_value: value,
+            changes,
             timestamp: now,
+            source: origin.source.map(str::to_string),
+            entity_last_updated: Some(now),
+            request_id: origin.request_id.map(str::to_string),
         };
 
+        // Journaled even during replay, unless the journal has it already
+        if !update.changes.is_empty() {
+            if let Some(journal) = &self.journal {
+                journal.record(&update, sequence, self.replaying.load(Ordering::Relaxed));
+            }
+        }
+
         // Broadcast to subscribers (suppressed during NATS replay)
-        if !self.replaying.load(Ordering::Relaxed) {
-            let _ = self.state_tx.send(update.clone());
+        if !update.changes.is_empty() && !self.replaying.load(Ordering::Relaxed) {
+            self.broadcast_update(&update);
         }
 
         update
     }
 
+    /// Record a change to `entity_id` in the world and namespace versions
+    fn bump_version(&self, entity_id: &str) {
+        let version = self.world_version.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(namespace) = namespace_of(entity_id) {
+            self.set_namespace_version(namespace, version);
+        }
+    }
+
+    fn set_namespace_version(&self, namespace: &str, version: u64) {
+        match self.namespace_versions.get_mut(namespace) {
+            Some(mut current) => *current = (*current).max(version),
+            None => {
+                self.namespace_versions
+                    .entry(namespace.to_string())
+                    .and_modify(|current| *current = (*current).max(version))
+                    .or_insert(version);
+            }
+        }
+    }
+
+    /// Counter that changes whenever any entity is written, deleted or renamed
+    pub fn world_version(&self) -> u64 {
+        self.world_version.load(Ordering::SeqCst)
+    }
+
+    /// Counter that changes whenever an entity in `namespace` is written,
+    /// deleted or renamed (0 if none has been since startup)
+    pub fn namespace_version(&self, namespace: &str) -> u64 {
+        self.namespace_versions
+            .get(namespace)
+            .map_or(0, |version| *version)
+    }
+
+    /// Random value fixed for this engine's lifetime; versions are only
+    /// comparable under the same epoch
+    pub fn version_epoch(&self) -> u64 {
+        self.version_epoch
+    }
+
+    /// Send update to its shard and, if anyone is listening, the wildcard channel
+    ///
+    /// Only called once live, so it also feeds the recent activity.
+    fn broadcast_update(&self, update: &EntityUpdate) {
+        self.recent.lock().unwrap().record_update(update);
+        let shard = &self.state_shards[self.shard_for(&update.entity_id)];
+        if shard.receiver_count() > 0 {
+            let _ = shard.send(update.clone());
+        }
+        if self.state_tx.receiver_count() > 0 {
+            let _ = self.state_tx.send(update.clone());
+        }
+    }
+
+    /// Number of state broadcast shards
+    pub fn shard_count(&self) -> usize {
+        self.state_shards.len()
+    }
+
+    /// Shard index that carries updates for `entity_id`
+    pub fn shard_for(&self, entity_id: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        entity_id.hash(&mut hasher);
+        (hasher.finish() % self.state_shards.len() as u64) as usize
+    }
+
     /// Get entity by ID
     pub fn get_entity(&self, entity_id: &str) -> Option<Entity> {
-        self.entities.get(entity_id).map(|e| e.clone())
+        self.entities
+            .get(entity_id)
+            .map(|e| Entity::clone(e.value()))
     }
 
     /// Get all entities
+    ///
+    /// Deep-clones every entity including property maps. Prefer
+    /// [`entities_snapshot_refs`](Self::entities_snapshot_refs) or
+    /// [`for_each_entity`](Self::for_each_entity) on hot paths.
     pub fn get_all_entities(&self) -> Vec<Entity> {
-        self.entities.iter().map(|e| e.value().clone()).collect()
+        self.entities
+            .iter()
+            .map(|e| Entity::clone(e.value()))
+            .collect()
+    }
+
+    /// Get shared references to all entities (no property map clones)
+    ///
+    /// The returned entities are a consistent per-entity view: later mutations
+    /// copy-on-write and do not affect references already handed out.
+    pub fn entities_snapshot_refs(&self) -> Vec<Arc<Entity>> {
+        self.entities
+            .iter()
+            .map(|e| Arc::clone(e.value()))
+            .collect()
     }
 
-    /// Subscribe to state updates
-    pub fn subscribe(&self) -> broadcast::Receiver<StateUpdate> {
+    /// Visit every entity without cloning
+    ///
+    /// Holds a DashMap shard read lock while `f` runs; keep `f` cheap and
+    /// never call back into the engine's write paths from it.
+    pub fn for_each_entity<F>(&self, mut f: F)
+    where
+        F: FnMut(&Entity),
+    {
+        for entry in self.entities.iter() {
+            f(entry.value());
+        }
+    }
+
+    /// Entities whose `property` equals `value`
+    ///
+    /// Answered from the property index if `property` is in the
+    /// `indexed_properties` runtime config and `value` is a string, number or
+    /// boolean, else by scanning every entity.
+    pub fn query_entities(&self, property: &str, value: &Value) -> Vec<Arc<Entity>> {
+        self.sync_property_index();
+        let matches = |entity: &Entity| entity.properties.get(property) == Some(value);
+        match self.property_index.candidates(property, value) {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| self.entities.get(id).map(|e| Arc::clone(e.value())))
+                .filter(|entity| matches(entity))
+                .collect(),
+            None => self
+                .entities
+                .iter()
+                .filter(|e| matches(e.value()))
+                .map(|e| Arc::clone(e.value()))
+                .collect(),
+        }
+    }
+
+    /// Entities in the property index, per indexed property
+    pub fn property_index_entries(&self) -> BTreeMap<String, usize> {
+        self.sync_property_index();
+        self.property_index.entry_counts()
+    }
+
+    /// Pick up changes to `indexed_properties`
+    fn sync_property_index(&self) {
+        if let Some(config) = &self.runtime_config {
+            self.property_index.sync(config, &self.entities);
+        }
+    }
+
+    /// Subscribe to all state updates (wildcard)
+    pub fn subscribe(&self) -> broadcast::Receiver<EntityUpdate> {
         self.state_tx.subscribe()
     }
 
+    /// Subscribe to state updates for a single shard
+    ///
+    /// Receives only updates for entities where `shard_for(id) == shard`.
+    pub fn subscribe_shard(&self, shard: usize) -> broadcast::Receiver<EntityUpdate> {
+        self.state_shards[shard % self.state_shards.len()].subscribe()
+    }
+
     /// Subscribe to metrics updates
-    pub fn subscribe_metrics(&self) -> broadcast::Receiver<crate::state::metrics_broadcaster::MetricsUpdate> {
+    pub fn subscribe_metrics(
+        &self,
+    ) -> broadcast::Receiver<crate::state::metrics_broadcaster::MetricsUpdate> {
         self.metrics_tx.subscribe()
     }
 
@@ -123,10 +759,143 @@ impl StateEngine {
         self.deletion_tx.subscribe()
     }
 
-    /// Delete entity from state
+    /// Subscribe to agent messages
+    pub fn subscribe_messages(&self) -> broadcast::Receiver<AgentMessage> {
+        self.message_tx.subscribe()
+    }
+
+    /// Up to `limit` of the newest logged messages to `to` sent after `since`,
+    /// oldest first
+    pub fn messages_to(
+        &self,
+        to: &str,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Vec<AgentMessage> {
+        self.message_log
+            .lock()
+            .unwrap()
+            .messages_to(to, since, limit)
+    }
+
+    /// Every logged agent message, oldest first
+    pub fn messages(&self) -> Vec<AgentMessage> {
+        self.message_log.lock().unwrap().all()
+    }
+
+    /// Replace the message log with messages from a snapshot
+    pub fn load_messages(&self, messages: Vec<AgentMessage>) {
+        let mut log = self.message_log.lock().unwrap();
+        log.clear();
+        for message in messages {
+            log.record(message);
+        }
+    }
+
+    /// Up to `limit` of the newest live agent messages that pass `filter`,
+    /// oldest first
+    pub fn recent_messages(
+        &self,
+        limit: usize,
+        filter: impl Fn(&AgentMessage) -> bool,
+    ) -> Vec<AgentMessage> {
+        self.recent.lock().unwrap().messages(limit, filter)
+    }
+
+    /// Up to `limit` of the newest live property changes that pass `filter`,
+    /// oldest first
+    pub fn recent_changes(
+        &self,
+        limit: usize,
+        filter: impl Fn(&RecentChange) -> bool,
+    ) -> Vec<RecentChange> {
+        self.recent.lock().unwrap().changes(limit, filter)
+    }
+
+    /// Most messages, and most property changes, the recent activity keeps
+    pub fn recent_activity_capacity(&self) -> usize {
+        self.recent.lock().unwrap().capacity()
+    }
+
+    /// Log `message` and, once live, broadcast it
+    fn record_message(&self, message: AgentMessage) {
+        self.message_log.lock().unwrap().record(message.clone());
+        if !self.replaying.load(Ordering::Relaxed) {
+            self.recent.lock().unwrap().record_message(&message);
+            let _ = self.message_tx.send(message);
+        }
+    }
+
+    /// Every tracked publisher, by source
+    pub fn publishers(&self) -> Vec<PublisherInfo> {
+        self.publishers.lock().unwrap().all()
+    }
+
+    /// The publisher with event source `source`, if tracked
+    pub fn publisher(&self, source: &str) -> Option<PublisherInfo> {
+        self.publishers.lock().unwrap().get(source)
+    }
+
+    /// Note `event`'s source as a publisher that wrote to `namespace`
+    fn record_publisher(&self, event: &FluxEvent, namespace: Option<&str>) {
+        let mut publishers = self.publishers.lock().unwrap();
+        let evicted = publishers.record(&event.source, event_time(event), namespace);
+        if !self.publisher_entities || !self.is_live() {
+            return;
+        }
+        let due = publishers.entity_due(&event.source, Utc::now());
+        drop(publishers);
+
+        if let Some(source) = evicted {
+            self.delete_entity(&format!("{}{}", PUBLISHER_ENTITY_PREFIX, source));
+        }
+        if let Some(info) = due {
+            self.write_publisher_entity(&info);
+        }
+    }
+
+    fn write_publisher_entity(&self, info: &PublisherInfo) {
+        let properties = [
+            ("source", Value::from(info.source.as_str())),
+            ("first_seen", Value::from(info.first_seen.to_rfc3339())),
+            ("last_seen", Value::from(info.last_seen.to_rfc3339())),
+            ("events_total", Value::from(info.events_total)),
+            ("namespaces", Value::from_iter(info.namespaces.clone())),
+        ];
+        self.update_properties(
+            &info.entity_id(),
+            properties.map(|(name, value)| (name.to_string(), value)),
+        );
+    }
+
+    /// Delete entity from state, keeping it in the trash
     pub fn delete_entity(&self, entity_id: &str) -> Option<Entity> {
+        self.remove_entity(entity_id, None, Utc::now())
+    }
+
+    /// Delete entity from state, remembering the deletion at `sequence` (or
+    /// the last processed one)
+    ///
+    /// Tombstones from the stream are remembered even if the entity is already
+    /// gone: a rename removes it locally before its tombstone comes back.
+    fn remove_entity(
+        &self,
+        entity_id: &str,
+        sequence: Option<u64>,
+        deleted_at: DateTime<Utc>,
+    ) -> Option<Entity> {
         // Remove entity from state
-        let removed = self.entities.remove(entity_id).map(|(_, entity)| entity);
+        let removed = self
+            .entities
+            .remove_if(entity_id, |_, entity| {
+                self.property_index.remove_entity(entity);
+                true
+            })
+            .map(|(_, entity)| Arc::unwrap_or_clone(entity));
+
+        if removed.is_some() || sequence.is_some() {
+            self.record_deletion(entity_id, sequence);
+        }
 
         if removed.is_some() {
             // Broadcast deletion event (suppressed during NATS replay)
@@ -141,31 +910,355 @@ impl StateEngine {
             info!(entity_id = %entity_id, "Entity deleted");
         }
 
+        if let Some(entity) = &removed {
+            self.bump_version(entity_id);
+            self.track_usage(entity_id, -1, || -entity_bytes(&entity.properties));
+            self.move_to_trash(entity.clone(), deleted_at);
+        }
+
         removed
     }
 
+    fn move_to_trash(&self, entity: Entity, deleted_at: DateTime<Utc>) {
+        if self.trash_capacity == 0 || self.trash_retention <= chrono::Duration::zero() {
+            return;
+        }
+        if self.trash.len() >= self.trash_capacity && !self.trash.contains_key(&entity.id) {
+            let oldest = self
+                .trash
+                .iter()
+                .min_by_key(|deleted| deleted.deleted_at)
+                .map(|deleted| deleted.key().clone());
+            if let Some(oldest) = oldest {
+                self.trash.remove(&oldest);
+            }
+        }
+        self.trash
+            .insert(entity.id.clone(), DeletedEntity { entity, deleted_at });
+    }
+
+    /// Deleted entity that can still be undeleted
+    pub fn get_deleted_entity(&self, entity_id: &str) -> Option<DeletedEntity> {
+        self.trash.get(entity_id).map(|deleted| deleted.clone())
+    }
+
+    /// All deleted entities in the trash, most recently deleted first
+    pub fn deleted_entities(&self) -> Vec<DeletedEntity> {
+        let mut deleted: Vec<DeletedEntity> =
+            self.trash.iter().map(|deleted| deleted.clone()).collect();
+        deleted.sort_by(|a, b| (b.deleted_at, &a.entity.id).cmp(&(a.deleted_at, &b.entity.id)));
+        deleted
+    }
+
+    /// How long deleted entities stay in the trash
+    pub fn trash_retention(&self) -> chrono::Duration {
+        self.trash_retention
+    }
+
+    /// Drop deleted entities whose retention ran out before `now`; returns how many
+    pub fn purge_trash(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - self.trash_retention;
+        let before = self.trash.len();
+        self.trash.retain(|_, deleted| deleted.deleted_at > cutoff);
+        before.saturating_sub(self.trash.len())
+    }
+
+    /// Replace the trash with deleted entities from a snapshot
+    pub fn load_trash(&self, deleted: Vec<DeletedEntity>) {
+        self.trash.clear();
+        for deleted in deleted {
+            self.move_to_trash(deleted.entity, deleted.deleted_at);
+        }
+    }
+
+    fn record_deletion(&self, entity_id: &str, sequence: Option<u64>) {
+        self.deletion_log.lock().unwrap().record(RecordedDeletion {
+            entity_id: entity_id.to_string(),
+            sequence: sequence.unwrap_or_else(|| self.get_last_processed_sequence()),
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Move entity `from` to the ID `to`
+    ///
+    /// If `to` already exists the call fails unless `merge` is set; merging
+    /// keeps `to`'s value for properties both entities have unless `prefer` is
+    /// [`RenamePreference::From`]. The move is atomic per entity: `from` is
+    /// taken out, then merged into `to` under its entry lock. Subscribers get
+    /// the deletion of `from` and one update with the changes to `to`.
+    pub fn rename_entity(
+        &self,
+        from: &str,
+        to: &str,
+        merge: bool,
+        prefer: RenamePreference,
+    ) -> Result<RenameOutcome, RenameError> {
+        if from == to {
+            return Err(RenameError::SameId);
+        }
+        if !merge && self.entities.contains_key(to) {
+            return Err(RenameError::TargetExists(to.to_string()));
+        }
+        let removed = self.entities.remove_if(from, |_, source| {
+            self.property_index.remove_entity(source);
+            true
+        });
+        let Some((_, source)) = removed else {
+            return Err(RenameError::NotFound(from.to_string()));
+        };
+
+        let now = Utc::now();
+        let (outcome, changes) = match self.move_into(&source, to, merge, prefer, now) {
+            Ok(moved) => moved,
+            Err(e) => {
+                // Put the source back unless it was recreated meanwhile
+                if let Entry::Vacant(slot) = self.entities.entry(from.to_string()) {
+                    self.property_index.insert_entity(&source);
+                    slot.insert(source);
+                }
+                return Err(e);
+            }
+        };
+        self.record_deletion(from, None);
+        self.bump_version(from);
+        self.bump_version(to);
+        for namespace in [namespace_of(from), namespace_of(to)].into_iter().flatten() {
+            self.recount_usage(namespace);
+        }
+
+        if !self.replaying.load(Ordering::Relaxed) {
+            let _ = self.deletion_tx.send(EntityDeleted {
+                entity_id: from.to_string(),
+                timestamp: now,
+            });
+            if !changes.is_empty() {
+                self.broadcast_update(&EntityUpdate {
+                    entity_id: to.to_string(),
+                    changes,
+                    timestamp: now,
+                    source: None,
+                    entity_last_updated: Some(now),
+                    request_id: None,
+                });
+            }
+        }
+
+        info!(
+            from = %from,
+            to = %to,
+            merged = outcome.merged,
+            conflicts = outcome.conflicts.len(),
+            "Entity renamed"
+        );
+        Ok(outcome)
+    }
+
+    /// Store `source`'s properties under `to`, returning the result and the
+    /// changes made to `to`
+    fn move_into(
+        &self,
+        source: &Entity,
+        to: &str,
+        merge: bool,
+        prefer: RenamePreference,
+        now: DateTime<Utc>,
+    ) -> Result<(RenameOutcome, Vec<PropertyChange>), RenameError> {
+        match self.entities.entry(to.to_string()) {
+            Entry::Vacant(slot) => {
+                let entity = Entity {
+                    id: to.to_string(),
+                    properties: source.properties.clone(),
+                    last_updated: now,
+                    last_applied: source.last_applied.clone(),
+                    last_modified_sequence: self.get_last_processed_sequence(),
+                    property_times: source.property_times.clone(),
+                };
+                let changes = entity
+                    .properties
+                    .iter()
+                    .map(|(property, value)| PropertyChange {
+                        property: property.clone(),
+                        old_value: None,
+                        new_value: value.clone(),
+                        removed: false,
+                    })
+                    .collect();
+                self.property_index.insert_entity(&entity);
+                slot.insert(Arc::new(entity.clone()));
+                let outcome = RenameOutcome {
+                    entity,
+                    merged: false,
+                    conflicts: Vec::new(),
+                };
+                Ok((outcome, changes))
+            }
+            Entry::Occupied(mut slot) => {
+                if !merge {
+                    return Err(RenameError::TargetExists(to.to_string()));
+                }
+
+                let mut properties = slot.get().properties.clone();
+                let mut property_times = slot.get().property_times.clone();
+                let mut changes = Vec::new();
+                let mut conflicts = Vec::new();
+                for (property, value) in &source.properties {
+                    let old_value = match properties.get(property) {
+                        Some(existing) if existing == value => continue,
+                        Some(existing) => {
+                            conflicts.push(property.clone());
+                            if prefer == RenamePreference::To {
+                                continue;
+                            }
+                            Some(existing.clone())
+                        }
+                        None => None,
+                    };
+                    properties.insert(property.clone(), value.clone());
+                    property_times.insert(property.clone(), source.property_time(property));
+                    changes.push(PropertyChange {
+                        property: property.clone(),
+                        old_value,
+                        new_value: value.clone(),
+                        removed: false,
+                    });
+                }
+                if properties.len() > self.max_properties_per_entity {
+                    return Err(RenameError::TooManyProperties {
+                        count: properties.len(),
+                        max: self.max_properties_per_entity,
+                    });
+                }
+                conflicts.sort();
+
+                self.property_index.apply(to, &changes);
+                let entity = Arc::make_mut(slot.get_mut());
+                entity.properties = properties;
+                entity.property_times = property_times;
+                entity.last_updated = now;
+                entity.last_modified_sequence = self.get_last_processed_sequence();
+                if let Some(applied) = &source.last_applied {
+                    let newer = entity
+                        .last_applied
+                        .as_ref()
+                        .is_none_or(|last| last.is_older_than(applied));
+                    if newer {
+                        entity.last_applied = Some(applied.clone());
+                    }
+                }
+                let outcome = RenameOutcome {
+                    entity: entity.clone(),
+                    merged: true,
+                    conflicts,
+                };
+                Ok((outcome, changes))
+            }
+        }
+    }
+
     /// Get last processed NATS sequence number
     pub fn get_last_processed_sequence(&self) -> u64 {
         self.last_processed_sequence.load(Ordering::SeqCst)
     }
 
+    /// Pretend the consumer has processed the stream through `sequence`
+    #[cfg(test)]
+    pub(crate) fn set_last_processed_sequence(&self, sequence: u64) {
+        self.last_processed_sequence
+            .store(sequence, Ordering::SeqCst);
+    }
+
+    /// True once NATS replay has completed
+    pub fn is_live(&self) -> bool {
+        !self.replaying.load(Ordering::Relaxed)
+    }
+
     /// Signal that NATS replay is complete; enable state broadcasting
     pub fn set_live(&self) {
+        #[cfg(test)]
+        self.set_live_calls.fetch_add(1, Ordering::SeqCst);
         self.replaying.store(false, Ordering::SeqCst);
+        if self.publisher_entities {
+            // Publishers seen during replay weren't written yet
+            let now = Utc::now();
+            let due: Vec<PublisherInfo> = {
+                let mut publishers = self.publishers.lock().unwrap();
+                publishers
+                    .all()
+                    .iter()
+                    .filter_map(|info| publishers.entity_due(&info.source, now))
+                    .collect()
+            };
+            for info in &due {
+                self.write_publisher_entity(info);
+            }
+        }
         info!("State engine live — broadcasting enabled");
     }
 
+    #[cfg(test)]
+    pub(crate) fn set_live_calls(&self) -> usize {
+        self.set_live_calls.load(Ordering::SeqCst)
+    }
+
+    /// Record that replay resumes after `start_sequence` and catches up at
+    /// `target_sequence` (0 if unknown)
+    pub fn begin_replay(&self, start_sequence: u64, target_sequence: u64) {
+        self.startup_replay.begin(start_sequence, target_sequence);
+    }
+
+    /// How far the startup replay has got
+    pub fn replay_progress(&self) -> StartupReplayProgress {
+        self.startup_replay
+            .progress(!self.is_live(), self.get_last_processed_sequence())
+    }
+
     /// Load state from snapshot
     ///
     /// Clears existing state and loads entities from snapshot.
     /// Sets last_processed_sequence to the snapshot's sequence number.
+    /// Deletions before the snapshot are unknown, so "changed since" queries
+    /// can't reach further back than it.
     pub fn load_from_snapshot(&self, entities: HashMap<String, Entity>, sequence: u64) {
         // Clear existing state
         self.entities.clear();
-
-        // Load entities from snapshot
-        for (id, entity) in entities {
-            self.entities.insert(id, entity);
+        self.sync_property_index();
+        self.trash.clear();
+        self.message_log.lock().unwrap().clear();
+        self.publishers.lock().unwrap().clear();
+        self.quotas.clear();
+
+        // Load entities from snapshot (older snapshots don't record sequences
+        // or property times)
+        for (id, mut entity) in entities {
+            if entity.last_modified_sequence == 0 {
+                entity.last_modified_sequence = sequence;
+            }
+            if entity.property_times.len() < entity.properties.len() {
+                for property in entity.properties.keys() {
+                    if !entity.property_times.contains_key(property) {
+                        entity
+                            .property_times
+                            .insert(property.clone(), entity.last_updated);
+                    }
+                }
+            }
+            self.entities.insert(id, Arc::new(entity));
+        }
+        self.property_index.rebuild(&self.entities);
+        self.deletion_log
+            .lock()
+            .unwrap()
+            .reset(sequence, Utc::now());
+
+        // Everything may have changed, in every namespace
+        let version = self.world_version.fetch_add(1, Ordering::SeqCst) + 1;
+        self.namespace_versions
+            .iter_mut()
+            .for_each(|mut current| *current = version);
+        for entry in self.entities.iter() {
+            if let Some(namespace) = namespace_of(entry.key()) {
+                self.set_namespace_version(namespace, version);
+            }
         }
 
         // Set sequence number
@@ -189,19 +1282,232 @@ impl StateEngine {
     ///     "prop2": value2
     ///   }
     /// }
-    pub fn process_event(&self, event: &FluxEvent) {
+    ///
+    /// Events on a stream with a mapping (see [`set_stream_mapping`](Self::set_stream_mapping))
+    /// are read through it instead.
+    ///
+    /// A property value of `{"__unset__": true}` removes the property. With
+    /// `"null_unsets": true` in the payload, null values remove it too.
+    ///
+    /// Events older than the last event applied to the entity are discarded
+    /// (see [`AppliedEvent::is_older_than`]); an optional `"sequence"` number
+    /// in the payload orders events from one producer, and `"force": true`
+    /// applies an event regardless.
+    ///
+    /// `sequence` is the event's NATS stream sequence, if it came from the
+    /// stream; changes are stamped with it for [`changes_since`](Self::changes_since).
+    ///
+    /// Events on the quotas stream set a namespace's quota; updates that would
+    /// take a namespace past it are rejected (see [`crate::state::NamespaceQuota`]).
+    pub fn process_event(&self, event: &FluxEvent, sequence: Option<u64>) {
+        self.process_event_with_request_id(event, sequence, None);
+    }
+
+    /// [`process_event`](Self::process_event) for an event published with the
+    /// correlation ID `request_id`
+    ///
+    /// Runs in a `process_event` span carrying the event and request IDs, and
+    /// the resulting update carries `request_id` to subscribers.
+    pub fn process_event_with_request_id(
+        &self,
+        event: &FluxEvent,
+        sequence: Option<u64>,
+        request_id: Option<&str>,
+    ) {
+        let span = info_span!(
+            "process_event",
+            event_id = event.event_id.as_deref(),
+            stream = %event.stream,
+            request_id,
+        );
+        let _entered = span.enter();
+        self.apply_event(event, sequence, request_id);
+    }
+
+    fn apply_event(&self, event: &FluxEvent, sequence: Option<u64>, request_id: Option<&str>) {
         // Record metrics
         self.metrics.record_event(&event.source);
 
+        if event.stream == MESSAGES_STREAM {
+            self.record_publisher(event, None);
+            match AgentMessage::from_event(event, event_time(event)) {
+                Some(message) => self.record_message(message),
+                None => warn!(
+                    event_id = %event.event_id.as_deref().unwrap_or_default(),
+                    "Message event has no sender or recipient, skipping"
+                ),
+            }
+            return;
+        }
+
+        if event.stream == QUOTAS_STREAM {
+            self.record_publisher(event, None);
+            match NamespaceQuota::from_event(event) {
+                Some((namespace, quota)) => self.set_quota(&namespace, quota),
+                None => warn!(
+                    event_id = %event.event_id.as_deref().unwrap_or_default(),
+                    "Quota event has no namespace, skipping"
+                ),
+            }
+            return;
+        }
+
+        let Some((raw_id, properties)) = self.extract_entity(event) else {
+            self.record_publisher(event, None);
+            return;
+        };
+        let normalization = self.id_normalization(&raw_id);
+        let entity_id = crate::entity::normalize_entity_id(&raw_id, normalization);
+        let entity_id = entity_id.as_ref();
+        self.record_publisher(event, namespace_of(entity_id));
+
+        // Check for tombstone marker (deletion event)
+        if let Some(Value::Bool(true)) = properties.get("__deleted__") {
+            // The tombstone's own time, so replay rebuilds the same trash
+            let deleted_at = properties
+                .get("__deleted_at__")
+                .and_then(Value::as_i64)
+                .and_then(DateTime::from_timestamp_millis)
+                .unwrap_or_else(|| event_time(event));
+            self.remove_entity(entity_id, sequence, deleted_at);
+            return;
+        }
+
+        // Discard events that arrive after a newer event for the same entity
+        let applied = AppliedEvent {
+            event_id: event.event_id.clone().unwrap_or_default(),
+            timestamp: event_time(event),
+            sequence: event.payload.get("sequence").and_then(Value::as_u64),
+        };
+        let force = matches!(event.payload.get("force"), Some(Value::Bool(true)));
+        if !force {
+            let newer = self.entities.get(entity_id).and_then(|entity| {
+                let last = entity.last_applied.as_ref()?;
+                applied.is_older_than(last).then(|| last.event_id.clone())
+            });
+            if let Some(applied_event_id) = newer {
+                warn!(
+                    event_id = %applied.event_id,
+                    applied_event_id = %applied_event_id,
+                    entity_id = %entity_id,
+                    "Event is older than the entity's last applied event, discarding"
+                );
+                self.metrics.record_stale_update();
+                return;
+            }
+        }
+
+        // `{"__unset__": true}` (or null, if the event opts in) removes a property
+        let null_unsets = matches!(event.payload.get("null_unsets"), Some(Value::Bool(true)));
+        let mut changes: Vec<(String, Option<Value>)> = properties
+            .iter()
+            .map(|(k, v)| {
+                let value = (!is_unset(v, null_unsets)).then(|| v.clone());
+                (k.clone(), value)
+            })
+            .collect();
+
+        if normalization != IdNormalization::Off {
+            self.track_raw_id(&raw_id, entity_id, &mut changes);
+        }
+
+        // Numeric changes within the deadband are dropped; if nothing is left,
+        // neither is the event
+        if self.dampen(&event.stream, entity_id, &mut changes, applied.timestamp) {
+            return;
+        }
+
+        // Defensive cap: never let a single entity grow without bound
+        if self.would_exceed_property_cap(entity_id, &changes) {
+            warn!(
+                event_id = %event.event_id.as_ref().unwrap(),
+                entity_id = %entity_id,
+                max = self.max_properties_per_entity,
+                "Update would exceed per-entity property cap, skipping"
+            );
+            self.metrics.record_rejected_update();
+            return;
+        }
+
+        if let Err(exceeded) = self.check_quota(entity_id, &changes) {
+            warn!(
+                event_id = %applied.event_id,
+                entity_id = %entity_id,
+                reason = %exceeded,
+                "Update exceeds namespace quota, skipping"
+            );
+            self.metrics.record_quota_rejection();
+            return;
+        }
+
+        // Older agents message by setting `message` and `message_to` together
+        let legacy_message = match (properties.get("message"), properties.get("message_to")) {
+            (Some(Value::String(body)), Some(Value::String(to))) if !body.is_empty() => {
+                Some(AgentMessage {
+                    id: applied.event_id.clone(),
+                    from: entity_id.to_string(),
+                    to: to.clone(),
+                    body: Value::String(body.clone()),
+                    timestamp: applied.timestamp,
+                })
+            }
+            _ => None,
+        };
+
+        // Apply all properties as one atomic update (single broadcast)
+        self.apply_changes(
+            entity_id,
+            changes,
+            applied.timestamp,
+            Some(applied),
+            sequence,
+            UpdateOrigin {
+                source: Some(&event.source),
+                request_id,
+            },
+        );
+
+        if let Some(message) = legacy_message {
+            self.record_message(message);
+        }
+    }
+
+    /// Entity ID and properties of `event`, read through its stream's mapping
+    /// if there is one, else from the `entity_id` / `properties` envelope
+    fn extract_entity<'a>(
+        &self,
+        event: &'a FluxEvent,
+    ) -> Option<(Cow<'a, str>, &'a Map<String, Value>)> {
+        let event_id = event.event_id.as_deref().unwrap_or_default();
+
+        let mapping = self
+            .stream_mappings
+            .get(&event.stream)
+            .map(|m| Arc::clone(m.value()));
+        if let Some(mapping) = mapping {
+            return match mapping.extract(&event.payload) {
+                Ok((entity_id, properties)) => Some((Cow::Owned(entity_id), properties)),
+                Err(e) => {
+                    warn!(
+                        event_id = %event_id,
+                        stream = %event.stream,
+                        error = %e,
+                        "Event does not match its stream mapping, skipping"
+                    );
+                    None
+                }
+            };
+        }
+
         // Extract entity_id from payload
         let entity_id = match event.payload.get("entity_id").and_then(|v| v.as_str()) {
             Some(id) => id,
             None => {
                 warn!(
-                    event_id = %event.event_id.as_ref().unwrap(),
+                    event_id = %event_id,
                     "Event payload missing 'entity_id' field, skipping"
                 );
-                return;
+                return None;
             }
         };
 
@@ -210,48 +1516,302 @@ impl StateEngine {
             Some(props) => props,
             None => {
                 warn!(
-                    event_id = %event.event_id.as_ref().unwrap(),
+                    event_id = %event_id,
                     entity_id = %entity_id,
                     "Event payload missing 'properties' object, skipping"
                 );
-                return;
+                return None;
             }
         };
 
-        // Check for tombstone marker (deletion event)
-        if let Some(Value::Bool(true)) = properties.get("__deleted__") {
-            self.delete_entity(entity_id);
+        Some((Cow::Borrowed(entity_id), properties))
+    }
+
+    /// Record the raw ID of an entity created under a normalized ID, and count
+    /// events whose raw ID differs from the one the entity was created with
+    fn track_raw_id(
+        &self,
+        raw_id: &str,
+        entity_id: &str,
+        changes: &mut Vec<(String, Option<Value>)>,
+    ) {
+        let first_raw_id = self.entities.get(entity_id).map(|entity| {
+            let first = entity.properties.get(RAW_ID_PROPERTY);
+            match first.and_then(Value::as_str) {
+                Some(first) => first.to_string(),
+                None => entity.id.clone(),
+            }
+        });
+        match first_raw_id {
+            // Only if the event creates the entity, not if it only unsets
+            None if raw_id != entity_id && changes.iter().any(|(_, v)| v.is_some()) => {
+                changes.push((RAW_ID_PROPERTY.to_string(), Some(Value::from(raw_id))));
+            }
+            Some(first_raw_id) if first_raw_id != raw_id => {
+                warn!(
+                    entity_id = %entity_id,
+                    raw_id = %raw_id,
+                    first_raw_id = %first_raw_id,
+                    "Distinct raw entity IDs normalize to the same ID"
+                );
+                self.metrics.record_id_collision();
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop the changes within the deadband configured for `stream` or the
+    /// entity's namespace, counting them; true if that dropped every change
+    fn dampen(
+        &self,
+        stream: &str,
+        entity_id: &str,
+        changes: &mut Vec<(String, Option<Value>)>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let Some(config) = &self.runtime_config else {
+            return false;
+        };
+        let Some(rule) = self
+            .deadbands
+            .rule_for(config, stream, namespace_of(entity_id))
+        else {
+            return false;
+        };
+        let Some(entity) = self.entities.get(entity_id) else {
+            return false;
+        };
+        let before = changes.len();
+        changes.retain(|(property, value)| {
+            let Some(value) = value else {
+                return true;
+            };
+            let since_write = entity.property_times.get(property).map(|at| now - *at);
+            !rule.dampens(entity.properties.get(property), value, since_write)
+        });
+        drop(entity);
+
+        let dampened = before - changes.len();
+        if dampened > 0 {
+            self.metrics.record_dampened_updates(dampened as u64);
+        }
+        dampened > 0 && changes.is_empty()
+    }
+
+    /// True if applying `changes` would leave `entity_id` with more than
+    /// `max_properties_per_entity` properties
+    fn would_exceed_property_cap(
+        &self,
+        entity_id: &str,
+        changes: &[(String, Option<Value>)],
+    ) -> bool {
+        let entity = self.entities.get(entity_id);
+        let mut total = entity.as_ref().map_or(0, |e| e.properties.len());
+        for (property, value) in changes {
+            let exists = entity
+                .as_ref()
+                .is_some_and(|e| e.properties.contains_key(property));
+            match value {
+                Some(_) if !exists => total += 1,
+                None if exists => total -= 1,
+                _ => {}
+            }
+        }
+        total > self.max_properties_per_entity
+    }
+
+    /// Checks `changes` to `entity_id` against its namespace's quota,
+    /// counting a rejection against the namespace if they exceed it
+    fn check_quota(
+        &self,
+        entity_id: &str,
+        changes: &[(String, Option<Value>)],
+    ) -> Result<(), QuotaExceeded> {
+        let Some(mut state) = namespace_of(entity_id).and_then(|ns| self.quotas.get_mut(ns)) else {
+            return Ok(());
+        };
+        let entity = self.entities.get(entity_id);
+        let creates = entity.is_none() && changes.iter().any(|(_, value)| value.is_some());
+        let mut bytes_delta = 0;
+        for (property, value) in changes {
+            if let Some(old) = entity.as_ref().and_then(|e| e.properties.get(property)) {
+                bytes_delta -= property_bytes(property, old);
+            }
+            if let Some(new) = value {
+                bytes_delta += property_bytes(property, new);
+            }
+        }
+        drop(entity);
+
+        let result = state.check(creates, bytes_delta);
+        if result.is_err() {
+            state.usage.quota_rejections += 1;
+        }
+        result
+    }
+
+    /// Add to the usage of `entity_id`'s namespace, if it has a quota;
+    /// `bytes` is only computed then
+    fn track_usage<F>(&self, entity_id: &str, entities: i64, bytes: F)
+    where
+        F: FnOnce() -> i64,
+    {
+        if self.quotas.is_empty() {
             return;
         }
+        if let Some(mut state) = namespace_of(entity_id).and_then(|ns| self.quotas.get_mut(ns)) {
+            state.adjust(entities, bytes());
+        }
+    }
 
-        // Update each property
-        for (property_name, property_value) in properties {
-            self.update_property(entity_id, property_name, property_value.clone());
+    /// Entities and bytes currently in `namespace`
+    fn count_usage(&self, namespace: &str) -> NamespaceUsage {
+        let prefix = format!("{}/", namespace);
+        let mut usage = NamespaceUsage::default();
+        for entity in self
+            .entities
+            .iter()
+            .filter(|e| e.key().starts_with(&prefix))
+        {
+            usage.entities += 1;
+            usage.bytes += entity_bytes(&entity.properties) as u64;
         }
+        usage
     }
 
-    /// Determine consumer configuration for NATS event replay.
-    ///
-    /// Returns `(should_reset, deliver_policy)`:
-    /// - `should_reset`: when `true`, the existing durable consumer must be deleted before
-    ///   creation. Required when replaying from the beginning (no snapshot), because
-    ///   `get_or_create_consumer` silently returns the existing consumer at its current ack
-    ///   offset, ignoring the requested `DeliverPolicy`.
-    /// - `deliver_policy`: configured delivery start point.
-    pub(crate) fn consumer_delivery(
-        start_sequence: Option<u64>,
-    ) -> (bool, async_nats::jetstream::consumer::DeliverPolicy) {
-        match start_sequence {
-            None => (true, async_nats::jetstream::consumer::DeliverPolicy::All),
-            Some(seq) => (
-                false,
-                async_nats::jetstream::consumer::DeliverPolicy::ByStartSequence {
-                    start_sequence: seq + 1,
+    /// Recount the usage of `namespace` from state, if it has a quota
+    fn recount_usage(&self, namespace: &str) {
+        if !self.quotas.contains_key(namespace) {
+            return;
+        }
+        let usage = self.count_usage(namespace);
+        if let Some(mut state) = self.quotas.get_mut(namespace) {
+            state.usage = NamespaceUsage {
+                quota_rejections: state.usage.quota_rejections,
+                ..usage
+            };
+        }
+    }
+
+    /// Set `namespace`'s quota; an unlimited quota removes it
+    fn set_quota(&self, namespace: &str, quota: NamespaceQuota) {
+        if quota.is_unlimited() {
+            self.quotas.remove(namespace);
+        } else if let Some(mut state) = self.quotas.get_mut(namespace) {
+            state.quota = quota;
+        } else {
+            let usage = self.count_usage(namespace);
+            self.quotas
+                .insert(namespace.to_string(), QuotaState { quota, usage });
+        }
+        info!(
+            namespace = %namespace,
+            max_entities = ?quota.max_entities,
+            max_bytes = ?quota.max_bytes,
+            "Namespace quota set"
+        );
+    }
+
+    /// Quota in force for `namespace`, as of the last processed event
+    pub fn namespace_quota(&self, namespace: &str) -> Option<NamespaceQuota> {
+        self.quotas.get(namespace).map(|state| state.quota)
+    }
+
+    /// What `namespace` holds; counted from state if it has no quota
+    pub fn namespace_usage(&self, namespace: &str) -> NamespaceUsage {
+        match self.quotas.get(namespace) {
+            Some(state) => state.usage,
+            None => self.count_usage(namespace),
+        }
+    }
+
+    /// Every namespace quota with its rejection count, for snapshots
+    pub fn quotas(&self) -> Vec<QuotaRecord> {
+        let mut records: Vec<QuotaRecord> = self
+            .quotas
+            .iter()
+            .map(|state| QuotaRecord {
+                namespace: state.key().clone(),
+                quota: state.quota,
+                quota_rejections: state.usage.quota_rejections,
+            })
+            .collect();
+        records.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+        records
+    }
+
+    /// Replace the namespace quotas with those from a snapshot, counting
+    /// usage from the loaded entities
+    pub fn load_quotas(&self, records: Vec<QuotaRecord>) {
+        self.quotas.clear();
+        for record in records {
+            let mut usage = self.count_usage(&record.namespace);
+            usage.quota_rejections = record.quota_rejections;
+            self.quotas.insert(
+                record.namespace,
+                QuotaState {
+                    quota: record.quota,
+                    usage,
                 },
-            ),
+            );
         }
     }
 
+    /// Entities modified and deleted after `since`, for clients that poll
+    /// instead of holding a WebSocket open
+    ///
+    /// Only entities for which `visible` returns true are considered. Returns
+    /// one page of up to `limit` changes (more if many share a sequence) in
+    /// sequence order; continue with `since_seq = next_since_seq` while
+    /// `has_more` is set. Fails if `since` is older than the snapshot the
+    /// engine started from or than the oldest remembered deletion.
+    pub fn changes_since<F>(
+        &self,
+        since: ChangesSince,
+        limit: usize,
+        visible: F,
+    ) -> Result<Changes, ChangesError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        // Read the cursor first: anything processed during the scan is
+        // reported again next time rather than skipped
+        let head = self.get_last_processed_sequence();
+
+        let mut changes: Vec<Change> = Vec::new();
+        for entry in self.entities.iter() {
+            let entity = entry.value();
+            if !visible(&entity.id) {
+                continue;
+            }
+            let changed = match since {
+                ChangesSince::Sequence(sequence) => entity.last_modified_sequence > sequence,
+                ChangesSince::Time(time) => entity.last_updated > time,
+            };
+            if changed {
+                changes.push(Change::Modified(Arc::clone(entity)));
+            }
+        }
+
+        let log = self.deletion_log.lock().unwrap();
+        log.check(since)?;
+        let mut deleted = HashMap::new();
+        for deletion in log.since(since) {
+            // Recreated entities are reported as modified only
+            if visible(&deletion.entity_id) && !self.entities.contains_key(&deletion.entity_id) {
+                deleted.insert(deletion.entity_id.clone(), deletion.sequence);
+            }
+        }
+        drop(log);
+        changes.extend(
+            deleted
+                .into_iter()
+                .map(|(entity_id, sequence)| Change::Deleted(entity_id, sequence)),
+        );
+
+        Ok(paginate(changes, limit, head))
+    }
+
     /// Run NATS subscriber to process events and update state
     ///
     /// This method subscribes to "flux.events.>" and processes all events,
@@ -259,75 +1819,119 @@ impl StateEngine {
     ///
     /// # Arguments
     /// * `start_sequence` - Optional NATS sequence to start from (for recovery).
-    ///                      If None, replays all events from the beginning.
-    ///                      If Some(n), resumes from n+1 (after snapshot).
+    ///   If None, replays all events from the beginning.
+    ///   If Some(n), resumes from n+1 (after snapshot).
+    ///
+    /// Never returns: when the consumer's messages stop, e.g. during a NATS
+    /// outage, it resubscribes (see [`Self::consume_with_resubscribe`]).
     pub async fn run_subscriber(
         self: Arc<Self>,
         jetstream: jetstream::Context,
         start_sequence: Option<u64>,
-    ) -> Result<()> {
+    ) {
         info!("Starting state engine NATS subscriber");
 
-        let stream = jetstream
-            .get_stream("FLUX_EVENTS")
-            .await
-            .context("Failed to get FLUX_EVENTS stream")?;
-
-        let (should_reset, deliver_policy) = Self::consumer_delivery(start_sequence);
-
-        let consumer = if should_reset {
-            // No snapshot: must replay from the beginning.
-            // Delete any existing durable consumer — get_or_create_consumer would silently
-            // return it at its current ack offset, ignoring DeliverPolicy::All.
-            info!("No snapshot, resetting consumer for full replay from beginning");
-            match stream.delete_consumer("flux-state-engine").await {
-                Ok(_) => info!("Deleted existing 'flux-state-engine' consumer"),
-                Err(e) => info!(error = %e, "No existing consumer to delete (normal on first start)"),
-            }
-            stream
-                .create_consumer(async_nats::jetstream::consumer::pull::Config {
-                    durable_name: Some("flux-state-engine".to_string()),
-                    filter_subject: "flux.events.>".to_string(),
-                    deliver_policy,
-                    ..Default::default()
-                })
-                .await
-                .context("Failed to create consumer")?
-        } else {
-            let seq = start_sequence.unwrap();
-            info!(
-                start_sequence = seq + 1,
-                "Recovering from snapshot, replaying events from sequence {}",
-                seq + 1
-            );
-            stream
-                .get_or_create_consumer(
-                    "flux-state-engine",
-                    async_nats::jetstream::consumer::pull::Config {
-                        durable_name: Some("flux-state-engine".to_string()),
-                        filter_subject: "flux.events.>".to_string(),
-                        deliver_policy,
-                        ..Default::default()
-                    },
-                )
-                .await
-                .context("Failed to get or create consumer")?
+        let store = JetStreamConsumerStore::new(jetstream.clone());
+
+        // Replay is done around the stream's current end
+        let range = match store.range().await {
+            Ok(range) => Some(range),
+            Err(e) => {
+                warn!(error = %e, "Failed to get stream info, replay progress unknown");
+                None
+            }
         };
+        self.begin_replay(
+            start_sequence.unwrap_or(0),
+            range.map_or(0, |range| range.last_sequence),
+        );
 
-        info!("State engine consumer created, processing events...");
+        let delivered = store.delivered().await.unwrap_or_default();
+        let plan = plan_consumer(start_sequence, range, delivered);
+        if let Some(gap) = &plan.gap {
+            warn!(%gap, "Snapshot doesn't line up with the stream, replaying all of it");
+        }
+        match plan.start {
+            ConsumerStart::Beginning => info!("Replaying events from the beginning of the stream"),
+            ConsumerStart::Sequence { start_sequence } => info!(
+                start_sequence,
+                "Recovering from snapshot, replaying events from sequence {}", start_sequence
+            ),
+        }
+        // An existing durable consumer resumes at its own offset whatever
+        // DeliverPolicy says, so one in the wrong place is dropped first
+        if plan.recreate {
+            info!(delivered = ?delivered, "Recreating '{}' consumer", STATE_CONSUMER);
+            if let Err(e) = store.delete().await {
+                warn!(error = %e, "Failed to delete consumer");
+            }
+        }
 
-        // Process messages.
-        // During replay, use a 500 ms idle timeout: if no message arrives within
-        // that window we assume the backlog is drained and we're at the live tail.
-        let mut messages = consumer.messages().await?;
+        self.consume_with_resubscribe(&jetstream, plan.start).await
+    }
 
+    /// Consume events, resubscribing whenever the messages stop
+    ///
+    /// The first subscription delivers from `start`; later ones resume after
+    /// the last processed sequence. Attempts back off exponentially from
+    /// [`RESUBSCRIBE_MIN_BACKOFF`] to [`RESUBSCRIBE_MAX_BACKOFF`] while
+    /// subscribing fails.
+    pub(crate) async fn consume_with_resubscribe<T: EventSubscription>(
+        &self,
+        subscription: &T,
+        start: ConsumerStart,
+    ) {
+        let mut backoff = RESUBSCRIBE_MIN_BACKOFF;
+        let mut attempt: u32 = 0;
+        loop {
+            let from = match self.get_last_processed_sequence() {
+                0 => start,
+                last => ConsumerStart::Sequence {
+                    start_sequence: last + 1,
+                },
+            };
+            match subscription.subscribe(from).await {
+                Ok(messages) => {
+                    if attempt == 0 {
+                        info!("State engine consumer created, processing events...");
+                    } else {
+                        info!(attempt, start = ?from, "State engine consumer resubscribed");
+                    }
+                    attempt = 0;
+                    backoff = RESUBSCRIBE_MIN_BACKOFF;
+                    self.consume(messages).await;
+                    warn!(
+                        last_processed_sequence = self.get_last_processed_sequence(),
+                        "State engine subscriber stream ended, resubscribing"
+                    );
+                }
+                Err(e) => warn!(
+                    attempt,
+                    error = %e,
+                    retry_in_ms = backoff.as_millis() as u64,
+                    "Failed to subscribe state engine consumer"
+                ),
+            }
+            attempt += 1;
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RESUBSCRIBE_MAX_BACKOFF);
+        }
+    }
+
+    /// Apply the consumer's messages until the stream ends
+    ///
+    /// During replay, use a 500 ms idle timeout: if no message arrives within
+    /// that window we assume the backlog is drained and we're at the live tail.
+    pub(crate) async fn consume<S, M, E>(&self, mut messages: S)
+    where
+        S: Stream<Item = Result<M, E>> + Unpin,
+        M: EventMessage,
+        E: fmt::Display,
+    {
         loop {
             let next = if self.replaying.load(Ordering::Relaxed) {
-                match tokio::time::timeout(
-                    std::time::Duration::from_millis(500),
-                    messages.next(),
-                )
-                .await
+                match tokio::time::timeout(std::time::Duration::from_millis(500), messages.next())
+                    .await
                 {
                     Ok(opt) => opt,
                     Err(_) => {
@@ -347,9 +1951,11 @@ impl StateEngine {
 
             match msg {
                 Ok(msg) => {
+                    self.connection.record_message();
+
                     // Extract NATS sequence number
-                    let sequence = match msg.info() {
-                        Ok(info) => info.stream_sequence,
+                    let sequence = match msg.stream_sequence() {
+                        Ok(sequence) => sequence,
                         Err(e) => {
                             error!(error = %e, "Failed to get message info");
                             let _ = msg.ack().await;
@@ -357,15 +1963,43 @@ impl StateEngine {
                         }
                     };
 
+                    // Raw and rejected NATS ingest traffic; the ingester republishes
+                    // accepted events on their canonical subjects
+                    if is_ingest_subject(msg.subject()) {
+                        self.last_processed_sequence
+                            .store(sequence, Ordering::SeqCst);
+                        let _ = msg.ack().await;
+                        continue;
+                    }
+
+                    // A compacted copy of events this engine already applied
+                    // while live: applying it again would only re-broadcast
+                    let compacted_through = msg
+                        .header(COMPACTED_THROUGH_HEADER)
+                        .and_then(|through| through.parse::<u64>().ok());
+                    if compacted_through.is_some_and(|through| {
+                        !self.replaying.load(Ordering::Relaxed)
+                            && self.get_last_processed_sequence() >= through
+                    }) {
+                        self.last_processed_sequence
+                            .store(sequence, Ordering::SeqCst);
+                        let _ = msg.ack().await;
+                        continue;
+                    }
+
+                    let request_id = msg.header(REQUEST_ID_HEADER);
+
                     // Deserialize event
-                    match serde_json::from_slice::<FluxEvent>(&msg.payload) {
+                    match serde_json::from_slice::<FluxEvent>(msg.payload()) {
                         Ok(event) => {
-                            self.process_event(&event);
-                            // Store sequence after successful processing
-                            self.last_processed_sequence.store(sequence, Ordering::SeqCst);
-                            // Acknowledge message
-                            if let Err(e) = msg.ack().await {
-                                error!(error = %e, "Failed to acknowledge message");
+                            self.process_event_with_request_id(&event, Some(sequence), request_id);
+                            // Store sequence once acknowledged; an unacknowledged
+                            // event is redelivered and counted then
+                            match msg.ack().await {
+                                Ok(()) => self
+                                    .last_processed_sequence
+                                    .store(sequence, Ordering::SeqCst),
+                                Err(e) => error!(error = %e, "Failed to acknowledge message"),
                             }
                         }
                         Err(e) => {
@@ -380,9 +2014,6 @@ impl StateEngine {
                 }
             }
         }
-
-        warn!("State engine subscriber stream ended");
-        Ok(())
     }
 }
 
@@ -403,6 +2034,7 @@ mod tests {
             stream: "test".to_string(),
             source: "test-source".to_string(),
             timestamp: 1_000_000,
+            received_at: None,
             key: None,
             schema: None,
             payload: json!({
@@ -419,7 +2051,7 @@ mod tests {
 
         // replaying=true by default — broadcast should be suppressed
         let event = make_event("ent/a", "foo", json!(42));
-        engine.process_event(&event);
+        engine.process_event(&event, None);
 
         // Entity state was updated
         assert_eq!(
@@ -441,7 +2073,7 @@ mod tests {
         engine.set_live();
 
         let event = make_event("ent/b", "bar", json!("hello"));
-        engine.process_event(&event);
+        engine.process_event(&event, None);
 
         // Broadcast should now be delivered
         assert!(rx.try_recv().is_ok());
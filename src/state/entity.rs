@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
 /// Entity represents a domain-agnostic object in the world state
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -13,7 +14,66 @@ pub struct Entity {
     pub properties: HashMap<String, Value>,
 
     /// Last update timestamp
+    #[serde(alias = "lastUpdated")]
     pub last_updated: DateTime<Utc>,
+
+    /// Last event applied, for discarding events that arrive out of order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_applied: Option<AppliedEvent>,
+
+    /// NATS stream sequence of the last change, for "changed since" queries
+    #[serde(default)]
+    pub last_modified_sequence: u64,
+
+    /// When each property was last written
+    ///
+    /// Absent in snapshots from before per-property times were kept; loading
+    /// one fills in `last_updated` for every property.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub property_times: HashMap<String, DateTime<Utc>>,
+}
+
+impl Entity {
+    /// When `property` was last written (`last_updated` if not recorded)
+    pub fn property_time(&self, property: &str) -> DateTime<Utc> {
+        self.property_times
+            .get(property)
+            .copied()
+            .unwrap_or(self.last_updated)
+    }
+
+    /// True if some property was last written before `cutoff`
+    pub fn has_property_older_than(&self, cutoff: DateTime<Utc>) -> bool {
+        self.properties
+            .keys()
+            .any(|property| self.property_time(property) < cutoff)
+    }
+}
+
+/// Ordering position of an event applied to an entity
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedEvent {
+    pub event_id: String,
+    /// When the event's changes took effect
+    pub timestamp: DateTime<Utc>,
+    /// Producer ordering hint (`payload.sequence`), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+}
+
+impl AppliedEvent {
+    /// True if this event orders before `applied`
+    ///
+    /// Events are ordered by their sequence hints when both have one, by
+    /// timestamp otherwise; ties are broken by event ID (UUIDv7, time-ordered).
+    pub fn is_older_than(&self, applied: &AppliedEvent) -> bool {
+        match (self.sequence, applied.sequence) {
+            (Some(sequence), Some(applied_sequence)) => {
+                (sequence, &self.event_id) < (applied_sequence, &applied.event_id)
+            }
+            _ => (self.timestamp, &self.event_id) < (applied.timestamp, &applied.event_id),
+        }
+    }
 }
 
 /// State update message broadcast to subscribers
@@ -23,7 +83,19 @@ pub struct StateUpdate {
     pub property: String,
     pub old_value: Option<Value>,
     pub new_value: Value,
+    /// True if the property was removed (`new_value` is then null)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
     pub timestamp: DateTime<Utc>,
+    /// Source of the event that made the change, if it came from one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The entity's `last_updated` after the change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_last_updated: Option<DateTime<Utc>>,
+    /// Correlation ID of the request that published the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 /// Entity deleted message broadcast to subscribers
@@ -32,3 +104,90 @@ pub struct EntityDeleted {
     pub entity_id: String,
     pub timestamp: DateTime<Utc>,
 }
+
+/// Recently deleted entity, kept so it can be undeleted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeletedEntity {
+    /// The entity as it was when deleted
+    pub entity: Entity,
+    /// When the tombstone was published
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Single property change within an entity update
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PropertyChange {
+    pub property: String,
+    pub old_value: Option<Value>,
+    pub new_value: Value,
+    /// True if the property was removed (`new_value` is then null)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+}
+
+/// Atomic multi-property update broadcast to subscribers
+///
+/// All changes were applied under a single entity lock and share one timestamp.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntityUpdate {
+    pub entity_id: String,
+    pub changes: Vec<PropertyChange>,
+    pub timestamp: DateTime<Utc>,
+    /// Source of the event that made the changes, if it came from one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The entity's `last_updated` after the changes (None if nothing changed
+    /// and the entity doesn't exist)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_last_updated: Option<DateTime<Utc>>,
+    /// Correlation ID of the request that published the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl EntityUpdate {
+    /// Split into one StateUpdate per changed property
+    pub fn into_state_updates(self) -> Vec<StateUpdate> {
+        let entity_id = self.entity_id;
+        let timestamp = self.timestamp;
+        let source = self.source;
+        let entity_last_updated = self.entity_last_updated;
+        let request_id = self.request_id;
+        self.changes
+            .into_iter()
+            .map(|change| StateUpdate {
+                entity_id: entity_id.clone(),
+                property: change.property,
+                old_value: change.old_value,
+                new_value: change.new_value,
+                removed: change.removed,
+                timestamp,
+                source: source.clone(),
+                entity_last_updated,
+                request_id: request_id.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Which entity's value a merging rename keeps for a property both entities have
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenamePreference {
+    /// Keep the existing value on the target entity
+    #[default]
+    To,
+    /// Overwrite it with the renamed entity's value
+    From,
+}
+
+/// Result of [`StateEngine::rename_entity`](crate::state::StateEngine::rename_entity)
+#[derive(Clone, Debug)]
+pub struct RenameOutcome {
+    /// The entity now stored under the new ID
+    pub entity: Entity,
+    /// True if the new ID already existed and the entities were merged
+    pub merged: bool,
+    /// Properties both entities had with different values, sorted
+    pub conflicts: Vec<String>,
+}
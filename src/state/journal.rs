@@ -0,0 +1,36 @@
+use super::entity::EntityUpdate;
+use tokio::sync::mpsc;
+
+/// An applied update on its way to the entity journal
+#[derive(Clone, Debug)]
+pub struct JournalRecord {
+    /// Stream sequence of the event that made the update, if it came from one
+    pub sequence: Option<u64>,
+    pub update: EntityUpdate,
+}
+
+/// Where the engine sends applied updates when a journal is attached
+pub(crate) struct JournalFeed {
+    tx: mpsc::UnboundedSender<JournalRecord>,
+    /// Highest stream sequence already in the journal
+    head: u64,
+}
+
+impl JournalFeed {
+    pub fn new(tx: mpsc::UnboundedSender<JournalRecord>, head: u64) -> Self {
+        Self { tx, head }
+    }
+
+    /// Send `update` to the journal writer, unless it's replayed from the
+    /// stream and was journaled before the restart
+    pub fn record(&self, update: &EntityUpdate, sequence: Option<u64>, replaying: bool) {
+        if replaying && sequence.is_some_and(|sequence| sequence <= self.head) {
+            return;
+        }
+        // The writer going away only loses journal entries, never state
+        let _ = self.tx.send(JournalRecord {
+            sequence,
+            update: update.clone(),
+        });
+    }
+}
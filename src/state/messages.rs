@@ -0,0 +1,182 @@
+use crate::event::FluxEvent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use utoipa::ToSchema;
+
+/// Stream agent messages are published on (`flux.events.flux.messages`)
+pub const MESSAGES_STREAM: &str = "flux.messages";
+
+/// Default number of messages kept per recipient
+pub const DEFAULT_MESSAGES_PER_RECIPIENT: usize = 100;
+
+/// Most recipients with a message log; the one messaged least recently is
+/// dropped to make room
+const MAX_MESSAGE_RECIPIENTS: usize = 10_000;
+
+/// Message from one entity (agent) to another
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "id": "01936f8e-7c2a-7000-8000-000000000000",
+    "from": "agent-a",
+    "to": "agent-b",
+    "body": "Found the anomaly in zone 3",
+    "timestamp": "2026-02-14T14:30:45.123Z"
+}))]
+pub struct AgentMessage {
+    /// ID of the event that carried the message
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    #[schema(value_type = Object)]
+    pub body: Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AgentMessage {
+    /// Message carried by an event on [`MESSAGES_STREAM`]
+    ///
+    /// The payload is `{"entity_id": <from>, "to": <to>, "body": <any>}`;
+    /// the sender is the entity ID so writes to it are what gets authorized.
+    pub fn from_event(event: &FluxEvent, timestamp: DateTime<Utc>) -> Option<Self> {
+        let payload = event.payload.as_object()?;
+        let from = payload.get("entity_id")?.as_str()?;
+        let to = payload.get("to")?.as_str()?;
+        if from.is_empty() || to.is_empty() {
+            return None;
+        }
+        Some(Self {
+            id: event.event_id.clone().unwrap_or_default(),
+            from: from.to_string(),
+            to: to.to_string(),
+            body: payload.get("body").cloned().unwrap_or(Value::Null),
+            timestamp,
+        })
+    }
+}
+
+/// Last messages per recipient, oldest first
+#[derive(Debug)]
+pub(crate) struct MessageLog {
+    by_recipient: HashMap<String, VecDeque<AgentMessage>>,
+    per_recipient: usize,
+}
+
+impl MessageLog {
+    pub fn new(per_recipient: usize) -> Self {
+        Self {
+            by_recipient: HashMap::new(),
+            per_recipient,
+        }
+    }
+
+    pub fn record(&mut self, message: AgentMessage) {
+        if self.per_recipient == 0 {
+            return;
+        }
+        if !self.by_recipient.contains_key(&message.to)
+            && self.by_recipient.len() >= MAX_MESSAGE_RECIPIENTS
+        {
+            let stalest = self
+                .by_recipient
+                .iter()
+                .min_by_key(|(_, log)| log.back().map(|m| m.timestamp))
+                .map(|(to, _)| to.clone());
+            if let Some(to) = stalest {
+                self.by_recipient.remove(&to);
+            }
+        }
+
+        let log = self.by_recipient.entry(message.to.clone()).or_default();
+        log.push_back(message);
+        while log.len() > self.per_recipient {
+            log.pop_front();
+        }
+    }
+
+    /// Up to `limit` of the newest messages to `to` sent after `since`, oldest first
+    pub fn messages_to(
+        &self,
+        to: &str,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Vec<AgentMessage> {
+        let Some(log) = self.by_recipient.get(to) else {
+            return Vec::new();
+        };
+        let newer: Vec<&AgentMessage> = log
+            .iter()
+            .filter(|m| since.is_none_or(|since| m.timestamp > since))
+            .collect();
+        newer[newer.len().saturating_sub(limit)..]
+            .iter()
+            .map(|m| (*m).clone())
+            .collect()
+    }
+
+    /// Every logged message, oldest first per recipient
+    pub fn all(&self) -> Vec<AgentMessage> {
+        let mut messages: Vec<AgentMessage> =
+            self.by_recipient.values().flatten().cloned().collect();
+        messages.sort_by(|a, b| (a.timestamp, &a.id).cmp(&(b.timestamp, &b.id)));
+        messages
+    }
+
+    pub fn clear(&mut self) {
+        self.by_recipient.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(id: &str, to: &str, second: i64) -> AgentMessage {
+        AgentMessage {
+            id: id.to_string(),
+            from: "agent-a".to_string(),
+            to: to.to_string(),
+            body: json!(id),
+            timestamp: DateTime::from_timestamp(second, 0).unwrap(),
+        }
+    }
+
+    fn ids(messages: &[AgentMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_keeps_last_messages_per_recipient() {
+        let mut log = MessageLog::new(2);
+        for (i, id) in ["m1", "m2", "m3"].into_iter().enumerate() {
+            log.record(message(id, "agent-b", i as i64));
+        }
+        log.record(message("m4", "agent-c", 10));
+
+        assert_eq!(ids(&log.messages_to("agent-b", None, 10)), ["m2", "m3"]);
+        assert_eq!(ids(&log.messages_to("agent-c", None, 10)), ["m4"]);
+        assert!(log.messages_to("agent-d", None, 10).is_empty());
+        assert_eq!(ids(&log.all()), ["m2", "m3", "m4"]);
+    }
+
+    #[test]
+    fn test_messages_since_and_limit() {
+        let mut log = MessageLog::new(10);
+        for (i, id) in ["m1", "m2", "m3", "m4"].into_iter().enumerate() {
+            log.record(message(id, "agent-b", i as i64));
+        }
+
+        let since = DateTime::from_timestamp(1, 0);
+        assert_eq!(ids(&log.messages_to("agent-b", since, 10)), ["m3", "m4"]);
+        assert_eq!(ids(&log.messages_to("agent-b", None, 1)), ["m4"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let mut log = MessageLog::new(0);
+        log.record(message("m1", "agent-b", 0));
+        assert!(log.all().is_empty());
+    }
+}
@@ -1,8 +1,18 @@
+use chrono::Utc;
+use serde::Serialize;
 use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
-use chrono::Utc;
-use serde::Serialize;
+use std::time::Duration;
+
+/// Number of recent publish acks kept for latency percentiles
+const PUBLISH_LATENCY_SAMPLES: usize = 1024;
+
+/// Default cap on distinct event sources tracked for active publisher counts
+pub const DEFAULT_MAX_TRACKED_SOURCES: usize = 1000;
+
+/// Bucket for sources seen while the tracker is at its cap
+pub const OTHER_SOURCES: &str = "__other__";
 
 /// Tracks metrics for the Flux state engine
 #[derive(Clone)]
@@ -13,11 +23,52 @@ pub struct MetricsTracker {
     /// Event timestamps for rate calculation (sliding 5-second window)
     event_timestamps: Arc<RwLock<VecDeque<i64>>>,
 
-    /// Active publishers (source -> last_seen_timestamp_ms)
+    /// Active publishers (source -> last_seen_timestamp_ms), at most
+    /// `max_sources` of them plus [`OTHER_SOURCES`]
     active_publishers: Arc<RwLock<HashMap<String, i64>>>,
 
+    /// Distinct sources tracked before new ones are folded into [`OTHER_SOURCES`]
+    max_sources: usize,
+
     /// WebSocket connection count
     websocket_connections: Arc<AtomicU64>,
+
+    /// WebSocket connections closed for exceeding a connection or message limit
+    ws_limit_closes: Arc<AtomicU64>,
+
+    /// WebSocket control messages dropped by the rate limit or subscription cap
+    ws_throttled_messages: Arc<AtomicU64>,
+
+    /// Updates rejected for exceeding the per-entity property cap
+    rejected_updates: Arc<AtomicU64>,
+
+    /// Events discarded for arriving after a newer event for the same entity
+    stale_updates: Arc<AtomicU64>,
+
+    /// Updates rejected for exceeding their namespace's quota
+    quota_rejections: Arc<AtomicU64>,
+
+    /// Property updates dropped for falling within a deadband
+    dampened_updates: Arc<AtomicU64>,
+
+    /// Events whose raw entity ID differs from the one that created the
+    /// (normalized) entity they were applied to
+    id_collisions: Arc<AtomicU64>,
+
+    /// Events published to NATS whose ack is still pending
+    publish_in_flight: Arc<AtomicU64>,
+
+    /// Recent publish-to-ack latencies in microseconds (newest last)
+    publish_latencies: Arc<RwLock<VecDeque<u64>>>,
+
+    /// Publishes that failed because the NATS connection was down
+    publish_unavailable: Arc<AtomicU64>,
+
+    /// Entity listings answered from the query cache
+    query_cache_hits: Arc<AtomicU64>,
+
+    /// Entity listings the query cache had to compute
+    query_cache_misses: Arc<AtomicU64>,
 }
 
 impl MetricsTracker {
@@ -27,17 +78,38 @@ impl MetricsTracker {
             total_events: Arc::new(AtomicU64::new(0)),
             event_timestamps: Arc::new(RwLock::new(VecDeque::new())),
             active_publishers: Arc::new(RwLock::new(HashMap::new())),
+            max_sources: DEFAULT_MAX_TRACKED_SOURCES,
             websocket_connections: Arc::new(AtomicU64::new(0)),
+            ws_limit_closes: Arc::new(AtomicU64::new(0)),
+            ws_throttled_messages: Arc::new(AtomicU64::new(0)),
+            rejected_updates: Arc::new(AtomicU64::new(0)),
+            stale_updates: Arc::new(AtomicU64::new(0)),
+            quota_rejections: Arc::new(AtomicU64::new(0)),
+            dampened_updates: Arc::new(AtomicU64::new(0)),
+            id_collisions: Arc::new(AtomicU64::new(0)),
+            publish_in_flight: Arc::new(AtomicU64::new(0)),
+            publish_unavailable: Arc::new(AtomicU64::new(0)),
+            publish_latencies: Arc::new(RwLock::new(VecDeque::new())),
+            query_cache_hits: Arc::new(AtomicU64::new(0)),
+            query_cache_misses: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Set how many distinct sources are tracked (min 1)
+    pub fn with_max_sources(mut self, max: usize) -> Self {
+        self.max_sources = max.max(1);
+        self
+    }
+
     /// Record an event (call from StateEngine.process_event)
     pub fn record_event(&self, source: &str) {
+        self.record_event_at(source, Utc::now().timestamp_millis());
+    }
+
+    fn record_event_at(&self, source: &str, now: i64) {
         // Increment total counter
         self.total_events.fetch_add(1, Ordering::Relaxed);
 
-        let now = Utc::now().timestamp_millis();
-
         // Update sliding window for rate calculation
         {
             let mut timestamps = self.event_timestamps.write().unwrap();
@@ -53,13 +125,46 @@ impl MetricsTracker {
             }
         }
 
-        // Update active publishers
+        // Update active publishers; past the cap, new sources share one bucket
         {
             let mut publishers = self.active_publishers.write().unwrap();
-            publishers.insert(source.to_string(), now);
+            if let Some(last_seen) = publishers.get_mut(source) {
+                *last_seen = now;
+            } else {
+                let tracked = publishers.len() - publishers.contains_key(OTHER_SOURCES) as usize;
+                let key = if tracked < self.max_sources {
+                    source
+                } else {
+                    OTHER_SOURCES
+                };
+                publishers.insert(key.to_string(), now);
+            }
         }
     }
 
+    /// Forget sources idle for longer than `window_seconds`
+    ///
+    /// They no longer count as active publishers anyway, so counts over the
+    /// same window are unchanged; this just frees their slots. Returns how
+    /// many were dropped.
+    pub fn evict_idle_sources(&self, window_seconds: i64) -> usize {
+        let threshold = Utc::now().timestamp_millis() - (window_seconds * 1000);
+        let mut publishers = self.active_publishers.write().unwrap();
+        let before = publishers.len();
+        publishers.retain(|_, &mut last_seen| last_seen > threshold);
+        before - publishers.len()
+    }
+
+    /// True while sources are being folded into [`OTHER_SOURCES`]
+    ///
+    /// Clears once the bucket has been idle long enough to be evicted.
+    pub fn get_sources_truncated(&self) -> bool {
+        self.active_publishers
+            .read()
+            .unwrap()
+            .contains_key(OTHER_SOURCES)
+    }
+
     /// Get current event rate (events per second over last 5 seconds)
     pub fn get_event_rate(&self) -> f64 {
         let timestamps = self.event_timestamps.read().unwrap();
@@ -67,6 +172,9 @@ impl MetricsTracker {
     }
 
     /// Get count of active publishers (published within window)
+    ///
+    /// Sources folded into [`OTHER_SOURCES`] count as one, so while sources
+    /// are truncated this is a lower bound.
     pub fn get_active_publisher_count(&self, window_seconds: i64) -> usize {
         let now = Utc::now().timestamp_millis();
         let threshold = now - (window_seconds * 1000);
@@ -93,11 +201,158 @@ impl MetricsTracker {
         self.websocket_connections.load(Ordering::Relaxed)
     }
 
+    /// Record a WebSocket connection closed for exceeding a limit
+    pub fn record_ws_limit_close(&self) {
+        self.ws_limit_closes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total WebSocket connections closed for exceeding a limit
+    pub fn get_ws_limit_closes(&self) -> u64 {
+        self.ws_limit_closes.load(Ordering::Relaxed)
+    }
+
+    /// Record a WebSocket control message dropped by a limit
+    pub fn record_ws_throttled_message(&self) {
+        self.ws_throttled_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total WebSocket control messages dropped by a limit
+    pub fn get_ws_throttled_messages(&self) -> u64 {
+        self.ws_throttled_messages.load(Ordering::Relaxed)
+    }
+
     /// Get total events processed
     pub fn get_total_events(&self) -> u64 {
         self.total_events.load(Ordering::Relaxed)
     }
 
+    /// Record an update rejected by the per-entity property cap
+    pub fn record_rejected_update(&self) {
+        self.rejected_updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total updates rejected by the per-entity property cap
+    pub fn get_rejected_updates(&self) -> u64 {
+        self.rejected_updates.load(Ordering::Relaxed)
+    }
+
+    /// Record an event discarded as older than the entity's last applied event
+    pub fn record_stale_update(&self) {
+        self.stale_updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total events discarded as stale
+    pub fn get_stale_updates(&self) -> u64 {
+        self.stale_updates.load(Ordering::Relaxed)
+    }
+
+    /// Record an update rejected by a namespace quota
+    pub fn record_quota_rejection(&self) {
+        self.quota_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total updates rejected by namespace quotas
+    pub fn get_quota_rejections(&self) -> u64 {
+        self.quota_rejections.load(Ordering::Relaxed)
+    }
+
+    /// Record `count` property updates dropped by a deadband
+    pub fn record_dampened_updates(&self, count: u64) {
+        self.dampened_updates.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Get total property updates dropped by deadbands
+    pub fn get_dampened_updates(&self) -> u64 {
+        self.dampened_updates.load(Ordering::Relaxed)
+    }
+
+    /// Record an event whose raw entity ID collided with another after normalization
+    pub fn record_id_collision(&self) {
+        self.id_collisions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total events with colliding raw entity IDs
+    pub fn get_id_collisions(&self) -> u64 {
+        self.id_collisions.load(Ordering::Relaxed)
+    }
+
+    /// Record an event sent to NATS, awaiting its ack
+    pub fn record_publish_started(&self) {
+        self.publish_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a publish ack (or failure) after `latency`
+    pub fn record_publish_finished(&self, latency: Duration) {
+        self.publish_in_flight.fetch_sub(1, Ordering::Relaxed);
+
+        let mut latencies = self.publish_latencies.write().unwrap();
+        if latencies.len() == PUBLISH_LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency.as_micros() as u64);
+    }
+
+    /// Record a publish that failed because the NATS connection was down
+    pub fn record_publish_unavailable(&self) {
+        self.publish_unavailable.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total publishes that failed because the NATS connection was down
+    pub fn get_publish_unavailable(&self) -> u64 {
+        self.publish_unavailable.load(Ordering::Relaxed)
+    }
+
+    /// Record an entity listing answered from the query cache
+    pub fn record_query_cache_hit(&self) {
+        self.query_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total entity listings answered from the query cache
+    pub fn get_query_cache_hits(&self) -> u64 {
+        self.query_cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Record an entity listing the query cache had to compute
+    pub fn record_query_cache_miss(&self) {
+        self.query_cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get total entity listings the query cache had to compute
+    pub fn get_query_cache_misses(&self) -> u64 {
+        self.query_cache_misses.load(Ordering::Relaxed)
+    }
+
+    /// Get number of published events awaiting their ack
+    pub fn get_publish_in_flight(&self) -> u64 {
+        self.publish_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Get publish latency percentiles over the recent samples
+    pub fn get_publish_latency(&self) -> PublishLatency {
+        let mut samples: Vec<u64> = self
+            .publish_latencies
+            .read()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect();
+        samples.sort_unstable();
+
+        let percentile = |p: f64| -> f64 {
+            if samples.is_empty() {
+                return 0.0;
+            }
+            let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1] as f64 / 1000.0
+        };
+
+        PublishLatency {
+            p50_ms: percentile(50.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+        }
+    }
+
     /// Get snapshot of all metrics
     pub fn get_snapshot(&self, publisher_window_seconds: i64) -> MetricsSnapshot {
         MetricsSnapshot {
@@ -105,6 +360,19 @@ impl MetricsTracker {
             event_rate: self.get_event_rate(),
             active_publishers: self.get_active_publisher_count(publisher_window_seconds),
             websocket_connections: self.get_ws_connection_count(),
+            ws_limit_closes: self.get_ws_limit_closes(),
+            ws_throttled_messages: self.get_ws_throttled_messages(),
+            rejected_updates: self.get_rejected_updates(),
+            stale_updates: self.get_stale_updates(),
+            quota_rejections: self.get_quota_rejections(),
+            dampened_updates: self.get_dampened_updates(),
+            id_collisions: self.get_id_collisions(),
+            sources_truncated: self.get_sources_truncated(),
+            publish_in_flight: self.get_publish_in_flight(),
+            publish_unavailable: self.get_publish_unavailable(),
+            publish_latency: self.get_publish_latency(),
+            query_cache_hits: self.get_query_cache_hits(),
+            query_cache_misses: self.get_query_cache_misses(),
         }
     }
 }
@@ -122,6 +390,27 @@ pub struct MetricsSnapshot {
     pub event_rate: f64,
     pub active_publishers: usize,
     pub websocket_connections: u64,
+    pub ws_limit_closes: u64,
+    pub ws_throttled_messages: u64,
+    pub rejected_updates: u64,
+    pub stale_updates: u64,
+    pub quota_rejections: u64,
+    pub dampened_updates: u64,
+    pub id_collisions: u64,
+    pub sources_truncated: bool,
+    pub publish_in_flight: u64,
+    pub publish_unavailable: u64,
+    pub publish_latency: PublishLatency,
+    pub query_cache_hits: u64,
+    pub query_cache_misses: u64,
+}
+
+/// Publish-to-ack latency percentiles (0 when nothing was published yet)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PublishLatency {
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
 }
 
 #[cfg(test)]
@@ -234,6 +523,123 @@ mod tests {
         assert!(snapshot.event_rate > 0.0);
     }
 
+    #[test]
+    fn test_publish_in_flight_and_latency() {
+        let tracker = MetricsTracker::new();
+        assert_eq!(tracker.get_publish_latency(), PublishLatency::default());
+
+        for ms in 1..=100 {
+            tracker.record_publish_started();
+            tracker.record_publish_finished(Duration::from_millis(ms));
+        }
+        tracker.record_publish_started();
+        tracker.record_publish_started();
+
+        let snapshot = tracker.get_snapshot(10);
+        assert_eq!(snapshot.publish_in_flight, 2);
+        assert_eq!(snapshot.publish_latency.p50_ms, 50.0);
+        assert_eq!(snapshot.publish_latency.p95_ms, 95.0);
+        assert_eq!(snapshot.publish_latency.p99_ms, 99.0);
+    }
+
+    #[test]
+    fn test_publish_latency_keeps_recent_samples() {
+        let tracker = MetricsTracker::new();
+
+        for _ in 0..PUBLISH_LATENCY_SAMPLES {
+            tracker.record_publish_started();
+            tracker.record_publish_finished(Duration::from_secs(1));
+        }
+        for _ in 0..PUBLISH_LATENCY_SAMPLES {
+            tracker.record_publish_started();
+            tracker.record_publish_finished(Duration::from_millis(2));
+        }
+
+        assert_eq!(tracker.get_publish_latency().p99_ms, 2.0);
+    }
+
+    #[test]
+    fn test_source_cap_folds_new_sources() {
+        let tracker = MetricsTracker::new().with_max_sources(3);
+
+        for i in 0..3 {
+            tracker.record_event(&format!("source{}", i));
+        }
+        assert!(!tracker.get_sources_truncated());
+
+        for i in 3..100 {
+            tracker.record_event(&format!("source{}", i));
+        }
+        assert!(tracker.get_sources_truncated());
+        assert_eq!(tracker.active_publishers.read().unwrap().len(), 4);
+        // Three tracked sources plus the overflow bucket
+        assert_eq!(tracker.get_active_publisher_count(10), 4);
+        assert_eq!(tracker.get_total_events(), 100);
+
+        // Known sources keep their own entry
+        tracker.record_event("source1");
+        assert!(!tracker
+            .active_publishers
+            .read()
+            .unwrap()
+            .contains_key("source3"));
+        assert!(tracker.get_snapshot(10).sources_truncated);
+    }
+
+    #[test]
+    fn test_evict_idle_sources() {
+        let tracker = MetricsTracker::new();
+        let now = Utc::now().timestamp_millis();
+
+        tracker.record_event_at("old", now - 30_000);
+        tracker.record_event_at("recent", now - 5_000);
+        tracker.record_event("live");
+
+        assert_eq!(tracker.evict_idle_sources(10), 1);
+        let publishers = tracker.active_publishers.read().unwrap();
+        assert!(!publishers.contains_key("old"));
+        assert!(publishers.contains_key("recent"));
+        assert!(publishers.contains_key("live"));
+    }
+
+    #[test]
+    fn test_eviction_keeps_active_count() {
+        let tracker = MetricsTracker::new();
+        let now = Utc::now().timestamp_millis();
+
+        for i in 0..50 {
+            tracker.record_event_at(&format!("dead{}", i), now - 60_000);
+        }
+        tracker.record_event_at("recent", now - 5_000);
+        tracker.record_event("live");
+
+        assert_eq!(tracker.get_active_publisher_count(10), 2);
+        assert_eq!(tracker.evict_idle_sources(10), 50);
+        assert_eq!(tracker.get_active_publisher_count(10), 2);
+        // Narrower windows still count from what is left
+        assert_eq!(tracker.get_active_publisher_count(1), 1);
+    }
+
+    #[test]
+    fn test_eviction_frees_capped_slots() {
+        let tracker = MetricsTracker::new().with_max_sources(2);
+        let now = Utc::now().timestamp_millis();
+
+        tracker.record_event_at("a", now - 60_000);
+        tracker.record_event_at("b", now - 60_000);
+        tracker.record_event_at("c", now - 60_000);
+        assert!(tracker.get_sources_truncated());
+
+        // Idle sources and the idle overflow bucket are dropped together
+        assert_eq!(tracker.evict_idle_sources(10), 3);
+        assert!(!tracker.get_sources_truncated());
+
+        tracker.record_event("d");
+        tracker.record_event("e");
+        assert!(!tracker.get_sources_truncated());
+        assert_eq!(tracker.get_active_publisher_count(10), 2);
+    }
+
     #[test]
     fn test_concurrent_access() {
         let tracker = Arc::new(MetricsTracker::new());
@@ -1,26 +1,64 @@
-use crate::state::StateEngine;
+use crate::config::SharedRuntimeConfig;
+use crate::nats::ConnectionStatus;
+use crate::state::{StartupReplayProgress, StateEngine};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::{interval, MissedTickBehavior};
-use tracing::warn;
+use tokio::time::{interval, interval_at, Instant, Interval, MissedTickBehavior};
+use tracing::{info, warn};
 
 /// Periodically broadcast metrics to all subscribers
 ///
 /// This task runs in the background and broadcasts a metrics snapshot
-/// every `interval_seconds`. The broadcast is non-blocking and won't
-/// affect state engine performance.
+/// every `metrics_broadcast_interval_seconds`. The broadcast is non-blocking
+/// and won't affect state engine performance.
+///
+/// Interval and publisher window are read from runtime config; an interval
+/// change restarts the ticker immediately instead of waiting out the old one.
 pub async fn run_metrics_broadcaster(
     state_engine: Arc<StateEngine>,
-    interval_seconds: u64,
-    publisher_window_seconds: i64,
+    runtime_config: SharedRuntimeConfig,
 ) {
-    let mut ticker = interval(Duration::from_secs(interval_seconds));
-
-    // Skip missed ticks to prevent backlog under load
-    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut changes = runtime_config.subscribe();
+    let mut interval_seconds = runtime_config
+        .read()
+        .expect("RuntimeConfig lock poisoned")
+        .metrics_broadcast_interval_seconds;
+    let mut ticker = new_ticker(interval(Duration::from_secs(interval_seconds.max(1))));
 
     loop {
-        ticker.tick().await;
+        tokio::select! {
+            _ = ticker.tick() => {}
+            // The sender lives in runtime_config (held here), so the channel
+            // never closes; on lag, re-reading the config covers missed changes.
+            _ = changes.recv() => {
+                let new_interval = runtime_config
+                    .read()
+                    .expect("RuntimeConfig lock poisoned")
+                    .metrics_broadcast_interval_seconds;
+                if new_interval != interval_seconds {
+                    info!(
+                        old = interval_seconds,
+                        new = new_interval,
+                        "Metrics broadcast interval changed"
+                    );
+                    interval_seconds = new_interval;
+                    let period = Duration::from_secs(interval_seconds.max(1));
+                    ticker = new_ticker(interval_at(Instant::now() + period, period));
+                }
+                continue;
+            }
+        }
+
+        let publisher_window_seconds = runtime_config
+            .read()
+            .expect("RuntimeConfig lock poisoned")
+            .active_publisher_window_seconds;
+
+        // Drop sources idle past the window so dead publishers don't pile up
+        state_engine
+            .metrics
+            .evict_idle_sources(publisher_window_seconds);
 
         // Get current entity count (lock-free DashMap operation)
         let entity_count = state_engine.entities.len();
@@ -34,7 +72,16 @@ pub async fn run_metrics_broadcaster(
             total_events: metrics_snapshot.total_events,
             event_rate: metrics_snapshot.event_rate,
             active_publishers: metrics_snapshot.active_publishers,
+            sources_truncated: metrics_snapshot.sources_truncated,
             websocket_connections: metrics_snapshot.websocket_connections,
+            ws_limit_closes: metrics_snapshot.ws_limit_closes,
+            ws_throttled_messages: metrics_snapshot.ws_throttled_messages,
+            replay: Some(state_engine.replay_progress()).filter(|p| p.replaying),
+            nats: state_engine.connection_status(),
+            publish_unavailable: metrics_snapshot.publish_unavailable,
+            property_index: state_engine.property_index_entries(),
+            query_cache_hits: metrics_snapshot.query_cache_hits,
+            query_cache_misses: metrics_snapshot.query_cache_misses,
         };
 
         // Broadcast to all subscribers (ignore send errors - no subscribers is fine)
@@ -47,6 +94,12 @@ pub async fn run_metrics_broadcaster(
     }
 }
 
+/// Skip missed ticks to prevent backlog under load
+fn new_ticker(mut ticker: Interval) -> Interval {
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ticker
+}
+
 /// Metrics update message broadcast to WebSocket clients
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct MetricsUpdate {
@@ -54,5 +107,67 @@ pub struct MetricsUpdate {
     pub total_events: u64,
     pub event_rate: f64,
     pub active_publishers: usize,
+    /// Some sources are counted together; `active_publishers` is a lower bound
+    pub sources_truncated: bool,
     pub websocket_connections: u64,
+    /// WebSocket connections closed for exceeding a limit
+    pub ws_limit_closes: u64,
+    /// WebSocket control messages dropped by a limit
+    pub ws_throttled_messages: u64,
+    /// Startup replay progress, only while replaying
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay: Option<StartupReplayProgress>,
+    /// NATS connection state
+    pub nats: ConnectionStatus,
+    /// Publishes refused because NATS was unreachable
+    pub publish_unavailable: u64,
+    /// Entities in the property index, per indexed property
+    pub property_index: BTreeMap<String, usize>,
+    /// Entity listings answered from the query cache
+    pub query_cache_hits: u64,
+    /// Entity listings the query cache had to compute
+    pub query_cache_misses: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{new_runtime_config, RuntimeConfigUpdate};
+
+    #[tokio::test]
+    async fn interval_change_picked_up_within_one_old_interval() {
+        let engine = Arc::new(StateEngine::new());
+        let runtime_config = new_runtime_config();
+        runtime_config
+            .write()
+            .unwrap()
+            .metrics_broadcast_interval_seconds = 3;
+        let mut rx = engine.subscribe_metrics();
+
+        tokio::spawn(run_metrics_broadcaster(
+            Arc::clone(&engine),
+            Arc::clone(&runtime_config),
+        ));
+
+        // First tick fires immediately
+        tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("initial metrics broadcast")
+            .unwrap();
+
+        // Shorten interval; next broadcast must arrive well before the old 3s
+        let changed_at = std::time::Instant::now();
+        runtime_config
+            .apply_update(&RuntimeConfigUpdate {
+                metrics_broadcast_interval_seconds: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(3), rx.recv())
+            .await
+            .expect("broadcast within one old interval")
+            .unwrap();
+        assert!(changed_at.elapsed() < Duration::from_millis(2500));
+    }
 }
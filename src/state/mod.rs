@@ -1,14 +1,56 @@
 // State engine and entity management (Task 3)
 
+mod changes;
+mod consumer;
+mod deadband;
+mod diff;
 mod engine;
 mod entity;
+mod journal;
+mod messages;
 mod metrics;
 mod metrics_broadcaster;
+mod property_index;
+mod publishers;
+mod quotas;
+mod recent;
+mod startup_replay;
+mod subscriber;
+mod trash_sweeper;
+mod ttl_sweeper;
 
-pub use engine::StateEngine;
-pub use entity::{Entity, EntityDeleted, StateUpdate};
-pub use metrics::{MetricsTracker, MetricsSnapshot};
+pub use changes::{Changes, ChangesError, ChangesSince, DEFAULT_DELETION_LOG_CAPACITY};
+pub use consumer::{
+    plan_consumer, repair_consumer, ConsumerPlan, ConsumerRepair, ConsumerStart, ConsumerStore,
+    JetStreamConsumerStore, SequenceGap, StreamRange, EVENTS_STREAM, STATE_CONSUMER,
+};
+pub use deadband::{DeadbandRule, DEFAULT_DEADBAND_KEEPALIVE_SECONDS};
+pub use diff::{
+    diff_engines, diff_entity, DiffOptions, Difference, EntityDivergence, StateDiff,
+    DEFAULT_MAX_REPORTED_DIVERGENCES,
+};
+#[cfg(feature = "snapshot")]
+pub(crate) use engine::{event_time, is_unset, UpdateOrigin};
+pub use engine::{
+    RenameError, StateEngine, DEFAULT_BROADCAST_SHARDS, DEFAULT_MAX_PROPERTIES_PER_ENTITY,
+    DEFAULT_TRASH_CAPACITY, DEFAULT_TRASH_RETENTION_SECONDS, RAW_ID_PROPERTY, UNSET_MARKER,
+};
+pub use entity::{
+    AppliedEvent, DeletedEntity, Entity, EntityDeleted, EntityUpdate, PropertyChange,
+    RenameOutcome, RenamePreference, StateUpdate,
+};
+pub use journal::JournalRecord;
+pub use messages::{AgentMessage, DEFAULT_MESSAGES_PER_RECIPIENT, MESSAGES_STREAM};
+pub use metrics::{
+    MetricsSnapshot, MetricsTracker, PublishLatency, DEFAULT_MAX_TRACKED_SOURCES, OTHER_SOURCES,
+};
 pub use metrics_broadcaster::{run_metrics_broadcaster, MetricsUpdate};
+pub use publishers::{PublisherInfo, DEFAULT_MAX_PUBLISHERS, PUBLISHER_ENTITY_PREFIX};
+pub use quotas::{NamespaceQuota, NamespaceUsage, QuotaRecord, QUOTAS_STREAM};
+pub use recent::{RecentChange, DEFAULT_RECENT_ACTIVITY_CAPACITY};
+pub use startup_replay::StartupReplayProgress;
+pub use trash_sweeper::run_trash_sweeper;
+pub use ttl_sweeper::{expired_entity_ids, run_ttl_sweeper};
 
 #[cfg(test)]
 mod tests;
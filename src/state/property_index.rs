@@ -0,0 +1,235 @@
+//! Inverted index of property values, so equality queries on properties
+//! like `status` don't scan every entity.
+//!
+//! Properties are chosen with the `indexed_properties` runtime config. Only
+//! strings, numbers and booleans are indexed; other values of an indexed
+//! property, and unindexed properties, are found by a scan. Writers keep the
+//! index current under the entity's map guard, so an entity is never missing
+//! from the index while its value matches. Entries are keyed by a hash of the
+//! value: callers check each candidate's actual value.
+
+use crate::config::RuntimeConfigHandle;
+use crate::state::entity::{Entity, PropertyChange};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::info;
+
+/// Generation no runtime config has, so the first sync reads the config
+const UNSYNCED: u64 = u64::MAX;
+
+#[derive(Default)]
+struct Properties {
+    /// Properties writers add to the index
+    maintained: Arc<HashSet<String>>,
+    /// Properties whose entries are complete, so queries may use them
+    ready: Arc<HashSet<String>>,
+}
+
+pub(crate) struct PropertyIndex {
+    properties: RwLock<Properties>,
+    /// Entity IDs by property and value hash
+    entries: DashMap<(String, u64), HashSet<String>>,
+    /// Entity IDs indexed per property
+    counts: DashMap<String, usize>,
+    /// Runtime config generation `properties` was read at
+    generation: AtomicU64,
+    /// Held while the indexed properties change
+    syncing: Mutex<()>,
+}
+
+impl Default for PropertyIndex {
+    fn default() -> Self {
+        Self {
+            properties: RwLock::default(),
+            entries: DashMap::new(),
+            counts: DashMap::new(),
+            generation: AtomicU64::new(UNSYNCED),
+            syncing: Mutex::new(()),
+        }
+    }
+}
+
+/// Hash of an indexable value; None for null, arrays and objects
+fn value_hash(value: &Value) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    match value {
+        Value::String(s) => (0u8, s).hash(&mut hasher),
+        // Same representation as `Number`'s equality: 1 and 1.0 differ
+        Value::Number(n) => (1u8, n.to_string()).hash(&mut hasher),
+        Value::Bool(b) => (2u8, b).hash(&mut hasher),
+        _ => return None,
+    }
+    Some(hasher.finish())
+}
+
+impl PropertyIndex {
+    fn maintained(&self) -> Arc<HashSet<String>> {
+        Arc::clone(&self.properties.read().unwrap().maintained)
+    }
+
+    fn insert(&self, entity_id: &str, property: &str, value: &Value) {
+        let Some(hash) = value_hash(value) else {
+            return;
+        };
+        let added = self
+            .entries
+            .entry((property.to_string(), hash))
+            .or_default()
+            .insert(entity_id.to_string());
+        if added {
+            *self.counts.entry(property.to_string()).or_default() += 1;
+        }
+    }
+
+    fn remove(&self, entity_id: &str, property: &str, value: &Value) {
+        let Some(hash) = value_hash(value) else {
+            return;
+        };
+        if let Entry::Occupied(mut entry) = self.entries.entry((property.to_string(), hash)) {
+            if entry.get_mut().remove(entity_id) {
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+                if let Some(mut count) = self.counts.get_mut(property) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// Record `changes` to `entity_id`; call under the entity's guard
+    pub(crate) fn apply(&self, entity_id: &str, changes: &[PropertyChange]) {
+        let maintained = self.maintained();
+        if maintained.is_empty() {
+            return;
+        }
+        for change in changes {
+            if !maintained.contains(&change.property) {
+                continue;
+            }
+            if let Some(old_value) = &change.old_value {
+                self.remove(entity_id, &change.property, old_value);
+            }
+            if !change.removed {
+                self.insert(entity_id, &change.property, &change.new_value);
+            }
+        }
+    }
+
+    /// Index a stored entity; call under its guard
+    pub(crate) fn insert_entity(&self, entity: &Entity) {
+        self.insert_properties(entity, &self.maintained());
+    }
+
+    /// Drop an entity leaving the map; call under its guard
+    pub(crate) fn remove_entity(&self, entity: &Entity) {
+        for property in self.maintained().iter() {
+            if let Some(value) = entity.properties.get(property) {
+                self.remove(&entity.id, property, value);
+            }
+        }
+    }
+
+    fn insert_properties(&self, entity: &Entity, properties: &HashSet<String>) {
+        for property in properties {
+            if let Some(value) = entity.properties.get(property) {
+                self.insert(&entity.id, property, value);
+            }
+        }
+    }
+
+    /// Follow the `indexed_properties` of `config`, indexing `entities` for
+    /// properties added since the last call
+    pub(crate) fn sync(
+        &self,
+        config: &RuntimeConfigHandle,
+        entities: &DashMap<String, Arc<Entity>>,
+    ) {
+        let generation = config.generation();
+        if self.generation.load(Ordering::Acquire) == generation {
+            return;
+        }
+        let _syncing = self.syncing.lock().unwrap();
+        if self.generation.load(Ordering::Acquire) == generation {
+            return;
+        }
+        let wanted: HashSet<String> = config
+            .read()
+            .unwrap()
+            .indexed_properties
+            .iter()
+            .cloned()
+            .collect();
+        let ready = Arc::clone(&self.properties.read().unwrap().ready);
+        if *ready != wanted {
+            let added: HashSet<String> = wanted.difference(&ready).cloned().collect();
+            {
+                // Stop answering from dropped properties before their entries go
+                let mut properties = self.properties.write().unwrap();
+                properties.ready = Arc::new(ready.intersection(&wanted).cloned().collect());
+                properties.maintained = Arc::new(wanted.clone());
+            }
+            self.entries
+                .retain(|(property, _), _| wanted.contains(property));
+            self.counts.retain(|property, _| wanted.contains(property));
+            // Writers index the added properties from here on; an entity is
+            // indexed under its map shard's read lock, so no write slips by
+            for entity in entities.iter() {
+                self.insert_properties(entity.value(), &added);
+            }
+            self.properties.write().unwrap().ready = Arc::new(wanted);
+            info!(
+                properties = ?self.entry_counts().keys().collect::<Vec<_>>(),
+                "Property index updated"
+            );
+        }
+        self.generation.store(generation, Ordering::Release);
+    }
+
+    /// Index `entities` from scratch, after they were replaced wholesale
+    pub(crate) fn rebuild(&self, entities: &DashMap<String, Arc<Entity>>) {
+        let _syncing = self.syncing.lock().unwrap();
+        let maintained = self.maintained();
+        self.entries.clear();
+        self.counts.clear();
+        if maintained.is_empty() {
+            return;
+        }
+        for entity in entities.iter() {
+            self.insert_properties(entity.value(), &maintained);
+        }
+    }
+
+    /// IDs of entities that may have `property` equal to `value`, or None if
+    /// the index can't tell and the caller has to scan
+    pub(crate) fn candidates(&self, property: &str, value: &Value) -> Option<Vec<String>> {
+        if !self.properties.read().unwrap().ready.contains(property) {
+            return None;
+        }
+        let hash = value_hash(value)?;
+        Some(
+            self.entries
+                .get(&(property.to_string(), hash))
+                .map(|ids| ids.iter().cloned().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Entity IDs indexed per indexed property
+    pub(crate) fn entry_counts(&self) -> BTreeMap<String, usize> {
+        let ready = Arc::clone(&self.properties.read().unwrap().ready);
+        ready
+            .iter()
+            .map(|property| {
+                let count = self.counts.get(property).map_or(0, |count| *count);
+                (property.clone(), count)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// Default number of publishers tracked
+pub const DEFAULT_MAX_PUBLISHERS: usize = 1000;
+
+/// Prefix of the entities publishers are materialized as
+pub const PUBLISHER_ENTITY_PREFIX: &str = "_system/publisher/";
+
+/// Namespaces recorded per publisher; further ones are not listed
+const MAX_NAMESPACES_PER_PUBLISHER: usize = 64;
+
+/// Least time between two writes of one publisher's entity
+const ENTITY_WRITE_INTERVAL_MS: i64 = 1000;
+
+/// What the engine knows about one event `source`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PublisherInfo {
+    pub source: String,
+    /// Time of the first event seen from this source
+    pub first_seen: DateTime<Utc>,
+    /// Time of the newest event seen from this source
+    pub last_seen: DateTime<Utc>,
+    /// Events seen, including ones that were rejected or discarded
+    pub events_total: u64,
+    /// Namespaces of the entities it wrote to (at most 64)
+    pub namespaces: BTreeSet<String>,
+}
+
+impl PublisherInfo {
+    /// ID of the entity this publisher is materialized as
+    pub fn entity_id(&self) -> String {
+        format!("{}{}", PUBLISHER_ENTITY_PREFIX, self.source)
+    }
+}
+
+#[derive(Debug)]
+struct Publisher {
+    info: PublisherInfo,
+    /// When its entity was last written
+    entity_written_at: Option<DateTime<Utc>>,
+}
+
+/// Publishers by source, at most `capacity` of them; the one seen least
+/// recently is dropped to make room
+#[derive(Debug)]
+pub(crate) struct PublisherRegistry {
+    publishers: HashMap<String, Publisher>,
+    capacity: usize,
+}
+
+impl PublisherRegistry {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            publishers: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an event from `source` at `seen_at` that wrote to `namespace`
+    ///
+    /// Returns the source evicted to make room, if any.
+    pub fn record(
+        &mut self,
+        source: &str,
+        seen_at: DateTime<Utc>,
+        namespace: Option<&str>,
+    ) -> Option<String> {
+        let mut evicted = None;
+        if !self.publishers.contains_key(source) && self.publishers.len() >= self.capacity {
+            evicted = self
+                .publishers
+                .values()
+                .min_by_key(|p| p.info.last_seen)
+                .map(|p| p.info.source.clone());
+            if let Some(source) = &evicted {
+                self.publishers.remove(source);
+            }
+        }
+
+        let publisher = self
+            .publishers
+            .entry(source.to_string())
+            .or_insert_with(|| Publisher {
+                info: PublisherInfo {
+                    source: source.to_string(),
+                    first_seen: seen_at,
+                    last_seen: seen_at,
+                    events_total: 0,
+                    namespaces: BTreeSet::new(),
+                },
+                entity_written_at: None,
+            });
+        let info = &mut publisher.info;
+        info.events_total += 1;
+        // Events can arrive out of order
+        info.first_seen = info.first_seen.min(seen_at);
+        info.last_seen = info.last_seen.max(seen_at);
+        if let Some(namespace) = namespace {
+            if info.namespaces.len() < MAX_NAMESPACES_PER_PUBLISHER
+                && !info.namespaces.contains(namespace)
+            {
+                info.namespaces.insert(namespace.to_string());
+            }
+        }
+        evicted
+    }
+
+    /// `source`'s info if its entity wasn't written within the last second
+    /// as of `now`, marking it written
+    pub fn entity_due(&mut self, source: &str, now: DateTime<Utc>) -> Option<PublisherInfo> {
+        let publisher = self.publishers.get_mut(source)?;
+        if publisher
+            .entity_written_at
+            .is_some_and(|written| (now - written).num_milliseconds() < ENTITY_WRITE_INTERVAL_MS)
+        {
+            return None;
+        }
+        publisher.entity_written_at = Some(now);
+        Some(publisher.info.clone())
+    }
+
+    pub fn get(&self, source: &str) -> Option<PublisherInfo> {
+        self.publishers.get(source).map(|p| p.info.clone())
+    }
+
+    /// Every publisher, by source
+    pub fn all(&self) -> Vec<PublisherInfo> {
+        let mut all: Vec<PublisherInfo> =
+            self.publishers.values().map(|p| p.info.clone()).collect();
+        all.sort_by(|a, b| a.source.cmp(&b.source));
+        all
+    }
+
+    pub fn clear(&mut self) {
+        self.publishers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(second: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(second, 0).unwrap()
+    }
+
+    #[test]
+    fn test_records_counts_times_and_namespaces() {
+        let mut registry = PublisherRegistry::new(10);
+        registry.record("gw", at(10), Some("plant"));
+        registry.record("gw", at(5), Some("lab"));
+        registry.record("gw", at(20), Some("plant"));
+        registry.record("gw", at(15), None);
+
+        let gw = registry.get("gw").unwrap();
+        assert_eq!(gw.events_total, 4);
+        assert_eq!(gw.first_seen, at(5));
+        assert_eq!(gw.last_seen, at(20));
+        assert_eq!(gw.namespaces.iter().collect::<Vec<_>>(), ["lab", "plant"]);
+        assert_eq!(gw.entity_id(), "_system/publisher/gw");
+    }
+
+    #[test]
+    fn test_evicts_least_recently_seen() {
+        let mut registry = PublisherRegistry::new(2);
+        assert_eq!(registry.record("a", at(1), None), None);
+        assert_eq!(registry.record("b", at(2), None), None);
+        // `a` is seen again, so `b` is now the stalest
+        assert_eq!(registry.record("a", at(3), None), None);
+        assert_eq!(registry.record("c", at(4), None), Some("b".to_string()));
+
+        let sources: Vec<String> = registry.all().into_iter().map(|p| p.source).collect();
+        assert_eq!(sources, ["a", "c"]);
+    }
+
+    #[test]
+    fn test_namespaces_are_capped() {
+        let mut registry = PublisherRegistry::new(1);
+        for i in 0..100 {
+            registry.record("gw", at(i), Some(&format!("ns{}", i)));
+        }
+        assert_eq!(
+            registry.get("gw").unwrap().namespaces.len(),
+            MAX_NAMESPACES_PER_PUBLISHER
+        );
+    }
+
+    #[test]
+    fn test_entity_writes_are_spaced_out() {
+        let mut registry = PublisherRegistry::new(1);
+        registry.record("gw", at(1), None);
+        assert!(registry.entity_due("gw", at(100)).is_some());
+        assert!(registry.entity_due("gw", at(100)).is_none());
+        assert!(registry.entity_due("gw", at(101)).is_some());
+        assert!(registry.entity_due("other", at(200)).is_none());
+    }
+}
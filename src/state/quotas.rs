@@ -0,0 +1,244 @@
+//! Per-namespace entity and storage quotas.
+//!
+//! Quotas reach the state engine as events on [`QUOTAS_STREAM`], so each
+//! event is checked against the quota in force at its position in the stream,
+//! and usage is counted from state alone. Replaying the stream therefore
+//! rejects exactly the updates that were rejected the first time.
+
+use crate::event::{serialized_len, FluxEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Stream quota changes are published on (`flux.events.flux.quotas`)
+pub const QUOTAS_STREAM: &str = "flux.quotas";
+
+/// Limits on what one namespace may hold (null = unlimited)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"max_entities": 10000, "max_bytes": 104857600}))]
+pub struct NamespaceQuota {
+    /// Most entities in the namespace
+    #[serde(default)]
+    pub max_entities: Option<u64>,
+    /// Most bytes of property names plus their values as JSON, summed over
+    /// the namespace's entities
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+impl NamespaceQuota {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_entities.is_none() && self.max_bytes.is_none()
+    }
+
+    /// Namespace and quota carried by an event on [`QUOTAS_STREAM`]
+    ///
+    /// The payload is `{"namespace": <name>, "max_entities": <n|null>,
+    /// "max_bytes": <n|null>}`.
+    pub fn from_event(event: &FluxEvent) -> Option<(String, Self)> {
+        let namespace = event.payload.get("namespace")?.as_str()?;
+        if namespace.is_empty() {
+            return None;
+        }
+        let quota = serde_json::from_value(event.payload.clone()).ok()?;
+        Some((namespace.to_string(), quota))
+    }
+}
+
+/// What a namespace holds, as counted by the state engine
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"entities": 1204, "bytes": 3811290, "quota_rejections": 3}))]
+pub struct NamespaceUsage {
+    pub entities: u64,
+    /// Property names plus their values as JSON, in bytes
+    pub bytes: u64,
+    /// Updates rejected for exceeding the namespace's quota
+    pub quota_rejections: u64,
+}
+
+/// A namespace's quota and rejection count, as kept in snapshots
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuotaRecord {
+    pub namespace: String,
+    #[serde(flatten)]
+    pub quota: NamespaceQuota,
+    #[serde(default)]
+    pub quota_rejections: u64,
+}
+
+/// Why an update was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum QuotaExceeded {
+    /// The update would create an entity past `max_entities`
+    Entities { max: u64 },
+    /// The update would grow the namespace to `bytes`, past `max_bytes`
+    Bytes { bytes: u64, max: u64 },
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaExceeded::Entities { max } => {
+                write!(f, "namespace already holds its quota of {} entities", max)
+            }
+            QuotaExceeded::Bytes { bytes, max } => write!(
+                f,
+                "update would grow the namespace to {} bytes, exceeding its quota of {}",
+                bytes, max
+            ),
+        }
+    }
+}
+
+/// Quota and usage of a namespace that has a quota
+#[derive(Debug, Clone, Default)]
+pub(crate) struct QuotaState {
+    pub quota: NamespaceQuota,
+    pub usage: NamespaceUsage,
+}
+
+impl QuotaState {
+    /// Checks an update that creates an entity (`creates`) and changes the
+    /// namespace's size by `bytes_delta`. Updates that don't grow the
+    /// namespace always pass, so one already over a lowered quota can shrink.
+    pub fn check(&self, creates: bool, bytes_delta: i64) -> Result<(), QuotaExceeded> {
+        if let Some(max) = self.quota.max_entities {
+            if creates && self.usage.entities >= max {
+                return Err(QuotaExceeded::Entities { max });
+            }
+        }
+        if let Some(max) = self.quota.max_bytes {
+            let bytes = self.usage.bytes.saturating_add_signed(bytes_delta);
+            if bytes_delta > 0 && bytes > max {
+                return Err(QuotaExceeded::Bytes { bytes, max });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn adjust(&mut self, entities: i64, bytes: i64) {
+        self.usage.entities = self.usage.entities.saturating_add_signed(entities);
+        self.usage.bytes = self.usage.bytes.saturating_add_signed(bytes);
+    }
+}
+
+/// Bytes a property counts against the byte quota
+pub(crate) fn property_bytes(name: &str, value: &Value) -> i64 {
+    (name.len() + serialized_len(value)) as i64
+}
+
+/// Bytes an entity's properties count against the byte quota
+pub(crate) fn entity_bytes(properties: &HashMap<String, Value>) -> i64 {
+    properties
+        .iter()
+        .map(|(name, value)| property_bytes(name, value))
+        .sum()
+}
+
+/// Namespace of a `namespace/entity` ID
+pub(crate) fn namespace_of(entity_id: &str) -> Option<&str> {
+    entity_id.split_once('/').map(|(namespace, _)| namespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn quota_state(
+        max_entities: Option<u64>,
+        max_bytes: Option<u64>,
+        entities: u64,
+        bytes: u64,
+    ) -> QuotaState {
+        QuotaState {
+            quota: NamespaceQuota {
+                max_entities,
+                max_bytes,
+            },
+            usage: NamespaceUsage {
+                entities,
+                bytes,
+                quota_rejections: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_entity_quota_boundary() {
+        let state = quota_state(Some(2), None, 1, 0);
+        assert_eq!(state.check(true, 10), Ok(()));
+
+        let state = QuotaState {
+            usage: NamespaceUsage {
+                entities: 2,
+                ..state.usage
+            },
+            ..state
+        };
+        assert_eq!(
+            state.check(true, 10),
+            Err(QuotaExceeded::Entities { max: 2 })
+        );
+        // Updating an existing entity is still allowed
+        assert_eq!(state.check(false, 10), Ok(()));
+    }
+
+    #[test]
+    fn test_byte_quota_boundary() {
+        let state = quota_state(None, Some(100), 1, 90);
+        assert_eq!(state.check(false, 10), Ok(()));
+        assert_eq!(
+            state.check(false, 11),
+            Err(QuotaExceeded::Bytes {
+                bytes: 101,
+                max: 100
+            })
+        );
+
+        // Over a lowered quota, shrinking (or staying level) still works
+        let state = quota_state(None, Some(50), 1, 90);
+        assert_eq!(state.check(false, -5), Ok(()));
+        assert_eq!(state.check(false, 0), Ok(()));
+        assert!(state.check(false, 1).is_err());
+    }
+
+    #[test]
+    fn test_property_bytes() {
+        assert_eq!(property_bytes("stars", &json!(10)), 7);
+        assert_eq!(property_bytes("name", &json!("x")), 7);
+        assert_eq!(property_bytes("tags", &json!(["a", "b"])), 13);
+    }
+
+    #[test]
+    fn test_quota_from_event() {
+        let event = FluxEvent::namespace_quota(
+            "matt",
+            &NamespaceQuota {
+                max_entities: Some(5),
+                max_bytes: None,
+            },
+            "flux-admin",
+        );
+        assert_eq!(event.stream, QUOTAS_STREAM);
+        let (namespace, quota) = NamespaceQuota::from_event(&event).unwrap();
+        assert_eq!(namespace, "matt");
+        assert_eq!(quota.max_entities, Some(5));
+        assert_eq!(quota.max_bytes, None);
+
+        let event = FluxEvent {
+            payload: json!({"max_entities": 5}),
+            ..event
+        };
+        assert!(NamespaceQuota::from_event(&event).is_none());
+    }
+
+    #[test]
+    fn test_namespace_of() {
+        assert_eq!(namespace_of("matt/sensor-1"), Some("matt"));
+        assert_eq!(namespace_of("github/repo/alice/x"), Some("github"));
+        assert_eq!(namespace_of("sensor-1"), None);
+    }
+}
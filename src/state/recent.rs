@@ -0,0 +1,231 @@
+//! Recent live activity: the last agent messages and property changes, so a
+//! client that reconnects can fill its feeds instead of starting empty.
+//!
+//! Only live traffic is recorded; the engine skips it while replaying the
+//! event stream, so a restart doesn't refill it with history. Values are
+//! stored truncated, keeping the buffers' memory bounded by their capacity.
+
+use crate::state::{AgentMessage, EntityUpdate};
+use crate::subscription::truncate_large_values;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+/// Default number of agent messages, and of property changes, kept
+pub const DEFAULT_RECENT_ACTIVITY_CAPACITY: usize = 500;
+
+/// Values larger than this (serialized) are kept as truncation markers
+const MAX_VALUE_BYTES: usize = 4_096;
+
+/// One property change, as it was broadcast
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "entity_id": "temp-sensor-01",
+    "property": "temperature",
+    "value": 22.5,
+    "timestamp": "2026-02-14T14:30:45.123Z",
+    "source": "sensor-gateway"
+}))]
+pub struct RecentChange {
+    pub entity_id: String,
+    pub property: String,
+    /// New value; large values are replaced by a truncation marker
+    #[schema(value_type = Object)]
+    pub value: Value,
+    /// The property was removed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+    pub timestamp: DateTime<Utc>,
+    /// Source of the event that made the change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Correlation ID the event was published with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Last `capacity` agent messages and last `capacity` property changes,
+/// oldest first
+#[derive(Debug)]
+pub(crate) struct RecentActivity {
+    messages: VecDeque<AgentMessage>,
+    changes: VecDeque<RecentChange>,
+    capacity: usize,
+}
+
+impl RecentActivity {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            changes: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn record_message(&mut self, message: &AgentMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut message = message.clone();
+        truncate_large_values(&mut message.body, MAX_VALUE_BYTES);
+        push_bounded(&mut self.messages, message, self.capacity);
+    }
+
+    /// Record each change in `update` separately
+    pub fn record_update(&mut self, update: &EntityUpdate) {
+        if self.capacity == 0 {
+            return;
+        }
+        for change in &update.changes {
+            let mut value = change.new_value.clone();
+            truncate_large_values(&mut value, MAX_VALUE_BYTES);
+            let change = RecentChange {
+                entity_id: update.entity_id.clone(),
+                property: change.property.clone(),
+                value,
+                removed: change.removed,
+                timestamp: update.timestamp,
+                source: update.source.clone(),
+                request_id: update.request_id.clone(),
+            };
+            push_bounded(&mut self.changes, change, self.capacity);
+        }
+    }
+
+    /// Up to `limit` of the newest messages that pass `filter`, oldest first
+    pub fn messages(
+        &self,
+        limit: usize,
+        filter: impl Fn(&AgentMessage) -> bool,
+    ) -> Vec<AgentMessage> {
+        newest(&self.messages, limit, filter)
+    }
+
+    /// Up to `limit` of the newest changes that pass `filter`, oldest first
+    pub fn changes(
+        &self,
+        limit: usize,
+        filter: impl Fn(&RecentChange) -> bool,
+    ) -> Vec<RecentChange> {
+        newest(&self.changes, limit, filter)
+    }
+}
+
+fn push_bounded<T>(items: &mut VecDeque<T>, item: T, capacity: usize) {
+    if items.len() == capacity {
+        items.pop_front();
+    }
+    items.push_back(item);
+}
+
+fn newest<T: Clone>(items: &VecDeque<T>, limit: usize, filter: impl Fn(&T) -> bool) -> Vec<T> {
+    let mut newest: Vec<T> = items
+        .iter()
+        .rev()
+        .filter(|item| filter(item))
+        .take(limit)
+        .cloned()
+        .collect();
+    newest.reverse();
+    newest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PropertyChange;
+    use serde_json::json;
+
+    fn message(id: &str, to: &str) -> AgentMessage {
+        AgentMessage {
+            id: id.to_string(),
+            from: "agent-a".to_string(),
+            to: to.to_string(),
+            body: json!(id),
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn update(entity_id: &str, properties: &[(&str, Value)]) -> EntityUpdate {
+        EntityUpdate {
+            entity_id: entity_id.to_string(),
+            changes: properties
+                .iter()
+                .map(|(property, value)| PropertyChange {
+                    property: property.to_string(),
+                    old_value: None,
+                    new_value: value.clone(),
+                    removed: false,
+                })
+                .collect(),
+            timestamp: Utc::now(),
+            source: Some("test".to_string()),
+            entity_last_updated: None,
+            request_id: None,
+        }
+    }
+
+    fn ids(messages: &[AgentMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_keeps_only_the_newest() {
+        let mut recent = RecentActivity::new(3);
+        for id in ["m1", "m2", "m3", "m4", "m5"] {
+            recent.record_message(&message(id, "agent-b"));
+        }
+        assert_eq!(ids(&recent.messages(10, |_| true)), ["m3", "m4", "m5"]);
+
+        // Each property change counts against the capacity
+        recent.record_update(&update("ns/a", &[("x", json!(1)), ("y", json!(2))]));
+        recent.record_update(&update("ns/b", &[("x", json!(3)), ("y", json!(4))]));
+        let changes = recent.changes(10, |_| true);
+        let kept: Vec<_> = changes
+            .iter()
+            .map(|c| (c.entity_id.as_str(), c.property.as_str()))
+            .collect();
+        assert_eq!(kept, [("ns/a", "y"), ("ns/b", "x"), ("ns/b", "y")]);
+        assert_eq!(changes[0].source.as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn test_limit_and_filter_take_newest_matches() {
+        let mut recent = RecentActivity::new(10);
+        for (id, to) in [("m1", "b"), ("m2", "c"), ("m3", "b"), ("m4", "b")] {
+            recent.record_message(&message(id, to));
+        }
+        assert_eq!(ids(&recent.messages(2, |_| true)), ["m3", "m4"]);
+        assert_eq!(ids(&recent.messages(10, |m| m.to == "c")), ["m2"]);
+        assert_eq!(ids(&recent.messages(2, |m| m.to == "b")), ["m3", "m4"]);
+        assert!(recent.messages(0, |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_large_values_are_truncated() {
+        let mut recent = RecentActivity::new(10);
+        let large = json!("x".repeat(MAX_VALUE_BYTES * 2));
+        recent.record_update(&update("ns/a", &[("blob", large.clone())]));
+        let mut big_message = message("m1", "b");
+        big_message.body = large;
+        recent.record_message(&big_message);
+
+        assert_eq!(recent.changes(1, |_| true)[0].value["__truncated__"], true);
+        assert_eq!(recent.messages(1, |_| true)[0].body["__truncated__"], true);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let mut recent = RecentActivity::new(0);
+        recent.record_message(&message("m1", "b"));
+        recent.record_update(&update("ns/a", &[("x", json!(1))]));
+        assert!(recent.messages(10, |_| true).is_empty());
+        assert!(recent.changes(10, |_| true).is_empty());
+    }
+}
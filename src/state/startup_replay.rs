@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Where the startup NATS replay is, reported by `GET /api/ready`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "replaying": true,
+    "processed_sequence": 1200000,
+    "target_sequence": 4800000,
+    "percent_complete": 25.0,
+    "events_per_second": 85000.0
+}))]
+pub struct StartupReplayProgress {
+    /// False once state is caught up with the stream
+    pub replaying: bool,
+    pub processed_sequence: u64,
+    /// Stream's last sequence when the replay started (0 if unknown)
+    pub target_sequence: u64,
+    /// Estimated completion, 0–100; null until the stream's size is known
+    pub percent_complete: Option<f64>,
+    /// Average rate since the replay started
+    pub events_per_second: f64,
+}
+
+/// Sequence range and start time of the startup replay
+#[derive(Default)]
+pub(crate) struct StartupReplay {
+    start_sequence: AtomicU64,
+    target_sequence: AtomicU64,
+    started_at: Mutex<Option<Instant>>,
+}
+
+impl StartupReplay {
+    /// Replay resumes after `start_sequence` and is done at `target_sequence`
+    pub fn begin(&self, start_sequence: u64, target_sequence: u64) {
+        self.start_sequence.store(start_sequence, Ordering::SeqCst);
+        self.target_sequence
+            .store(target_sequence, Ordering::SeqCst);
+        *self.started_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn progress(&self, replaying: bool, processed_sequence: u64) -> StartupReplayProgress {
+        let elapsed = self
+            .started_at
+            .lock()
+            .unwrap()
+            .map(|at| at.elapsed())
+            .unwrap_or_default();
+        replay_progress(
+            replaying,
+            self.start_sequence.load(Ordering::SeqCst),
+            self.target_sequence.load(Ordering::SeqCst),
+            processed_sequence,
+            elapsed,
+        )
+    }
+}
+
+/// Progress of a replay from `start` to `target` that has reached `processed`
+fn replay_progress(
+    replaying: bool,
+    start: u64,
+    target: u64,
+    processed: u64,
+    elapsed: Duration,
+) -> StartupReplayProgress {
+    let done = processed.saturating_sub(start);
+    let percent_complete = if !replaying || (target != 0 && target <= start) {
+        Some(100.0)
+    } else if target == 0 {
+        None
+    } else {
+        let total = (target - start) as f64;
+        Some((done as f64 / total * 100.0).min(100.0))
+    };
+    let events_per_second = if replaying && elapsed.as_secs_f64() > 0.0 {
+        done as f64 / elapsed.as_secs_f64()
+    } else {
+        0.0
+    };
+    StartupReplayProgress {
+        replaying,
+        processed_sequence: processed,
+        target_sequence: target,
+        percent_complete,
+        events_per_second,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_progress_math() {
+        let secs = Duration::from_secs;
+
+        // Resumed after a snapshot at 1000, stream ends at 3000
+        let progress = replay_progress(true, 1000, 3000, 1500, secs(10));
+        assert_eq!(progress.percent_complete, Some(25.0));
+        assert_eq!(progress.events_per_second, 50.0);
+
+        // Events published during replay can push past the target
+        let progress = replay_progress(true, 0, 100, 150, secs(1));
+        assert_eq!(progress.percent_complete, Some(100.0));
+
+        // Stream size unknown, or nothing to replay
+        assert_eq!(
+            replay_progress(true, 0, 0, 10, secs(1)).percent_complete,
+            None
+        );
+        assert_eq!(
+            replay_progress(true, 500, 500, 500, secs(0)).percent_complete,
+            Some(100.0)
+        );
+
+        let live = replay_progress(false, 0, 100, 100, secs(5));
+        assert_eq!(live.percent_complete, Some(100.0));
+        assert_eq!(live.events_per_second, 0.0);
+    }
+}
@@ -0,0 +1,515 @@
+//! What the state engine's subscriber loop needs from its consumer and
+//! the messages it delivers.
+//!
+//! [`StateEngine::consume`](super::StateEngine) reads messages through
+//! [`EventMessage`], and the resubscribing loop around it opens them through
+//! [`EventSubscription`], rather than JetStream types, so tests can drive
+//! both with the scripted fakes in [`testing`]: delays between messages,
+//! failed acks, malformed payloads, sequence gaps and failed subscriptions.
+
+use crate::state::consumer::{consumer_config, ConsumerStart, EVENTS_STREAM, STATE_CONSUMER};
+use anyhow::{anyhow, Context, Result};
+use async_nats::jetstream;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::future::Future;
+use std::time::Duration;
+
+/// First wait before resubscribing after the message stream ended
+pub(crate) const RESUBSCRIBE_MIN_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest wait between resubscribe attempts
+pub(crate) const RESUBSCRIBE_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Messages delivered by the state engine's consumer
+pub(crate) type EventMessages<M> = BoxStream<'static, Result<M, String>>;
+
+/// Opens the state engine's consumer
+pub(crate) trait EventSubscription: Send + Sync {
+    type Message: EventMessage + 'static;
+
+    /// Messages from the consumer, which is created delivering from `start`
+    /// if it doesn't exist; an existing one resumes after its last ack
+    fn subscribe(
+        &self,
+        start: ConsumerStart,
+    ) -> impl Future<Output = Result<EventMessages<Self::Message>>> + Send;
+}
+
+impl EventSubscription for jetstream::Context {
+    type Message = jetstream::Message;
+
+    async fn subscribe(&self, start: ConsumerStart) -> Result<EventMessages<jetstream::Message>> {
+        let consumer = self
+            .get_stream(EVENTS_STREAM)
+            .await
+            .with_context(|| format!("Failed to get {} stream", EVENTS_STREAM))?
+            .get_or_create_consumer(STATE_CONSUMER, consumer_config(start))
+            .await
+            .context("Failed to get or create consumer")?;
+        let messages = consumer
+            .messages()
+            .await
+            .context("Failed to read from consumer")?;
+        Ok(messages.map(|m| m.map_err(|e| e.to_string())).boxed())
+    }
+}
+
+/// A message delivered by the state engine's consumer
+pub(crate) trait EventMessage: Send + Sync {
+    fn subject(&self) -> &str;
+
+    /// Sequence of the message in the events stream
+    fn stream_sequence(&self) -> Result<u64>;
+
+    /// Value of header `name`, if set
+    fn header(&self, name: &str) -> Option<&str>;
+
+    fn payload(&self) -> &[u8];
+
+    /// Acknowledge the message so it isn't redelivered
+    fn ack(&self) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl EventMessage for jetstream::Message {
+    fn subject(&self) -> &str {
+        self.subject.as_str()
+    }
+
+    fn stream_sequence(&self) -> Result<u64> {
+        self.info()
+            .map(|info| info.stream_sequence)
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .as_ref()
+            .and_then(|h| h.get(name))
+            .map(|v| v.as_str())
+    }
+
+    fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    async fn ack(&self) -> Result<()> {
+        jetstream::Message::ack(self)
+            .await
+            .map_err(|e| anyhow!("{}", e))
+    }
+}
+
+/// Scripted in-memory stand-in for the consumer's message stream
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use futures::Stream;
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use tokio::time::{Instant, Sleep};
+
+    /// A message the fake stream delivers
+    pub(crate) struct FakeMessage {
+        subject: String,
+        /// `None` behaves like a message without JetStream metadata
+        sequence: Option<u64>,
+        payload: Vec<u8>,
+        headers: Vec<(String, String)>,
+        fail_ack: bool,
+        /// Successful acks, shared by every message of a stream
+        acks: Arc<AtomicUsize>,
+    }
+
+    impl EventMessage for FakeMessage {
+        fn subject(&self) -> &str {
+            &self.subject
+        }
+
+        fn stream_sequence(&self) -> Result<u64> {
+            self.sequence
+                .ok_or_else(|| anyhow!("not a JetStream message"))
+        }
+
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| value.as_str())
+        }
+
+        fn payload(&self) -> &[u8] {
+            &self.payload
+        }
+
+        async fn ack(&self) -> Result<()> {
+            if self.fail_ack {
+                return Err(anyhow!("ack timed out"));
+            }
+            self.acks.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    enum Step {
+        Message(FakeMessage),
+        Error(String),
+        Delay(Duration),
+    }
+
+    /// Messages, receive errors and pauses, delivered in the order they
+    /// were added; the stream ends after the last one
+    ///
+    /// Like JetStream's, the stream is cancel-safe: a `next()` dropped
+    /// during a pause resumes the same pause.
+    #[derive(Default)]
+    pub(crate) struct FakeMessages {
+        steps: VecDeque<Step>,
+        sleep: Option<Pin<Box<Sleep>>>,
+        acks: Arc<AtomicUsize>,
+    }
+
+    impl FakeMessages {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Message at `sequence` with `payload` on `flux.events.test`
+        pub fn message(self, sequence: u64, payload: impl Into<Vec<u8>>) -> Self {
+            self.push(Some(sequence), payload.into(), false)
+        }
+
+        /// Message at `sequence` whose ack fails
+        pub fn unackable(self, sequence: u64, payload: impl Into<Vec<u8>>) -> Self {
+            self.push(Some(sequence), payload.into(), true)
+        }
+
+        /// Message at `sequence` with header `name` set to `value`
+        pub fn with_header(
+            mut self,
+            sequence: u64,
+            payload: impl Into<Vec<u8>>,
+            name: &str,
+            value: &str,
+        ) -> Self {
+            self = self.push(Some(sequence), payload.into(), false);
+            if let Some(Step::Message(message)) = self.steps.back_mut() {
+                message.headers.push((name.to_string(), value.to_string()));
+            }
+            self
+        }
+
+        /// Message without JetStream metadata, so without a sequence
+        pub fn unsequenced(self, payload: impl Into<Vec<u8>>) -> Self {
+            self.push(None, payload.into(), false)
+        }
+
+        /// Receive error, like a missed heartbeat
+        pub fn error(mut self, error: &str) -> Self {
+            self.steps.push_back(Step::Error(error.to_string()));
+            self
+        }
+
+        /// Nothing delivered for `millis` ms
+        pub fn pause(mut self, millis: u64) -> Self {
+            self.steps
+                .push_back(Step::Delay(Duration::from_millis(millis)));
+            self
+        }
+
+        /// Successful acks so far
+        pub fn acks(&self) -> Arc<AtomicUsize> {
+            Arc::clone(&self.acks)
+        }
+
+        fn push(mut self, sequence: Option<u64>, payload: Vec<u8>, fail_ack: bool) -> Self {
+            self.steps.push_back(Step::Message(FakeMessage {
+                subject: "flux.events.test".to_string(),
+                sequence,
+                payload,
+                headers: Vec::new(),
+                fail_ack,
+                acks: Arc::clone(&self.acks),
+            }));
+            self
+        }
+    }
+
+    impl Stream for FakeMessages {
+        type Item = Result<FakeMessage, String>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            loop {
+                if let Some(sleep) = self.sleep.as_mut() {
+                    if sleep.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    self.sleep = None;
+                }
+                match self.steps.pop_front() {
+                    None => return Poll::Ready(None),
+                    Some(Step::Message(message)) => return Poll::Ready(Some(Ok(message))),
+                    Some(Step::Error(error)) => return Poll::Ready(Some(Err(error))),
+                    Some(Step::Delay(delay)) => {
+                        self.sleep = Some(Box::pin(tokio::time::sleep(delay)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Subscriptions handed out in the order they were added: a stream of
+    /// messages, or a failure to subscribe. Once they run out every attempt
+    /// fails.
+    #[derive(Default)]
+    pub(crate) struct FakeSubscription {
+        outcomes: Mutex<VecDeque<Result<FakeMessages, String>>>,
+        attempts: Arc<Mutex<Vec<(ConsumerStart, Instant)>>>,
+    }
+
+    impl FakeSubscription {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Subscription delivering `messages`, then ending
+        pub fn messages(self, messages: FakeMessages) -> Self {
+            self.outcomes.lock().unwrap().push_back(Ok(messages));
+            self
+        }
+
+        /// Failed subscription, like while NATS is down
+        pub fn error(self, error: &str) -> Self {
+            self.outcomes
+                .lock()
+                .unwrap()
+                .push_back(Err(error.to_string()));
+            self
+        }
+
+        /// Start requested by each subscribe attempt, and when it was made
+        pub fn attempts(&self) -> Arc<Mutex<Vec<(ConsumerStart, Instant)>>> {
+            Arc::clone(&self.attempts)
+        }
+    }
+
+    impl EventSubscription for FakeSubscription {
+        type Message = FakeMessage;
+
+        async fn subscribe(&self, start: ConsumerStart) -> Result<EventMessages<FakeMessage>> {
+            self.attempts.lock().unwrap().push((start, Instant::now()));
+            match self.outcomes.lock().unwrap().pop_front() {
+                Some(Ok(messages)) => Ok(messages.boxed()),
+                Some(Err(error)) => Err(anyhow!(error)),
+                None => Err(anyhow!("no more subscriptions")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{FakeMessages, FakeSubscription};
+    use crate::state::consumer::ConsumerStart;
+    use crate::state::StateEngine;
+    use serde_json::json;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    fn event(entity_id: &str, value: i64) -> Vec<u8> {
+        json!({
+            "stream": "test",
+            "source": "chaos",
+            "timestamp": 1_000_000,
+            "payload": {"entity_id": entity_id, "properties": {"value": value}}
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn value(engine: &StateEngine, entity_id: &str) -> Option<serde_json::Value> {
+        engine
+            .get_entity(entity_id)
+            .map(|e| e.properties["value"].clone())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_ack_does_not_advance_sequence() {
+        let engine = StateEngine::new();
+        let messages = FakeMessages::new()
+            .message(1, event("a", 1))
+            .unackable(2, event("a", 2));
+        let acks = messages.acks();
+        engine.consume(messages).await;
+
+        // Applied, but redelivered and applied again rather than counted
+        assert_eq!(value(&engine, "a"), Some(json!(2)));
+        assert_eq!(engine.get_last_processed_sequence(), 1);
+        assert_eq!(acks.load(Ordering::SeqCst), 1);
+
+        // The redelivery is acknowledged
+        engine
+            .consume(FakeMessages::new().message(2, event("a", 2)))
+            .await;
+        assert_eq!(engine.get_last_processed_sequence(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sequence_gaps_are_tolerated() {
+        let engine = StateEngine::new();
+        let messages = FakeMessages::new()
+            .message(1, event("a", 1))
+            .message(2, event("b", 2))
+            // 3..=9 purged, or filtered out by the consumer's subject
+            .message(10, event("c", 10))
+            .message(11, event("a", 11));
+        engine.consume(messages).await;
+
+        assert_eq!(value(&engine, "a"), Some(json!(11)));
+        assert_eq!(value(&engine, "c"), Some(json!(10)));
+        assert_eq!(engine.get_last_processed_sequence(), 11);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_goes_live_once_on_idle() {
+        let engine = StateEngine::new();
+        // A slow replay: every gap is under the 500 ms idle window
+        let mut messages = FakeMessages::new();
+        for sequence in 1..=5 {
+            messages = messages
+                .pause(400)
+                .message(sequence, event("a", sequence as i64));
+        }
+        let messages = messages
+            // Caught up; live events then arrive with long gaps
+            .pause(600)
+            .message(6, event("a", 6))
+            .pause(2_000)
+            .message(7, event("a", 7))
+            .pause(2_000);
+        let mut rx = engine.subscribe();
+        engine.consume(messages).await;
+
+        assert!(engine.is_live());
+        assert_eq!(engine.set_live_calls(), 1);
+        // Only the live events were broadcast
+        let mut broadcast = Vec::new();
+        while let Ok(update) = rx.try_recv() {
+            broadcast.push(update.changes[0].new_value.clone());
+        }
+        assert_eq!(broadcast, vec![json!(6), json!(7)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_compacted_copies_skipped_only_once_applied_live() {
+        let header = crate::nats::COMPACTED_THROUGH_HEADER;
+        let engine = StateEngine::new();
+        // Replaying: a compacted copy is applied like any event
+        let messages = FakeMessages::new().message(1, event("a", 1)).with_header(
+            2,
+            event("b", 2),
+            header,
+            "1",
+        );
+        engine.consume(messages).await;
+        assert_eq!(value(&engine, "b"), Some(json!(2)));
+
+        engine.set_live();
+        let messages = FakeMessages::new()
+            .message(3, event("a", 3))
+            // Copies of what this engine applied through 3: skipped
+            .with_header(4, event("a", 1), header, "3")
+            // Copies of events it never saw (purged while it was away): applied
+            .with_header(5, event("c", 5), header, "9");
+        let acks = messages.acks();
+        engine.consume(messages).await;
+
+        assert_eq!(value(&engine, "a"), Some(json!(3)));
+        assert_eq!(value(&engine, "c"), Some(json!(5)));
+        assert_eq!(engine.get_last_processed_sequence(), 5);
+        assert_eq!(acks.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_malformed_messages_do_not_stall_replay() {
+        let engine = StateEngine::new();
+        let messages = FakeMessages::new()
+            .message(1, event("a", 1))
+            .message(2, b"{not json".to_vec())
+            .unsequenced(event("a", 99))
+            .error("missed idle heartbeat")
+            .message(3, json!({"stream": "test"}).to_string())
+            .message(4, event("a", 4))
+            .message(5, event("b", 5));
+        let acks = messages.acks();
+        engine.consume(messages).await;
+
+        assert_eq!(value(&engine, "a"), Some(json!(4)));
+        assert_eq!(value(&engine, "b"), Some(json!(5)));
+        assert_eq!(engine.get_last_processed_sequence(), 5);
+        // Malformed messages are acknowledged so they aren't redelivered
+        assert_eq!(acks.load(Ordering::SeqCst), 6);
+        // Still replaying: nothing paused long enough to look caught up
+        assert!(!engine.is_live());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resubscribes_after_last_processed_sequence() {
+        let engine = StateEngine::new();
+        let subscription = FakeSubscription::new()
+            .messages(
+                FakeMessages::new()
+                    .message(1, event("a", 1))
+                    .message(2, event("a", 2)),
+            )
+            .error("connection refused")
+            .error("connection refused")
+            .messages(FakeMessages::new().message(3, event("a", 3)));
+        let attempts = subscription.attempts();
+
+        // Never returns; resubscribes until cancelled
+        let run = engine.consume_with_resubscribe(&subscription, ConsumerStart::Beginning);
+        assert!(tokio::time::timeout(Duration::from_secs(60), run)
+            .await
+            .is_err());
+
+        assert_eq!(value(&engine, "a"), Some(json!(3)));
+        assert_eq!(engine.get_last_processed_sequence(), 3);
+        assert_eq!(engine.connection_status().messages_since_reconnect, 3);
+
+        let starts: Vec<_> = attempts.lock().unwrap().iter().map(|(s, _)| *s).collect();
+        let resume = |start_sequence| ConsumerStart::Sequence { start_sequence };
+        assert_eq!(
+            starts[..4],
+            [ConsumerStart::Beginning, resume(3), resume(3), resume(3)]
+        );
+        assert!(starts[4..].iter().all(|s| *s == resume(4)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resubscribe_backs_off_exponentially() {
+        let engine = StateEngine::new();
+        let subscription = FakeSubscription::new()
+            .error("connection refused")
+            .error("connection refused")
+            .messages(FakeMessages::new().message(1, event("a", 1)));
+        let attempts = subscription.attempts();
+
+        let run = engine.consume_with_resubscribe(&subscription, ConsumerStart::Beginning);
+        let _ = tokio::time::timeout(Duration::from_secs(120), run).await;
+
+        let attempts = attempts.lock().unwrap();
+        let waits: Vec<u64> = attempts
+            .windows(2)
+            .map(|pair| (pair[1].1 - pair[0].1).as_millis() as u64)
+            .collect();
+        // Reset once subscribed, capped at 30 s
+        assert_eq!(
+            waits,
+            [500, 1_000, 500, 1_000, 2_000, 4_000, 8_000, 16_000, 30_000, 30_000]
+        );
+    }
+}
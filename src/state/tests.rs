@@ -1,6 +1,8 @@
 use super::*;
 use crate::event::FluxEvent;
-use chrono::Utc;
+#[cfg(feature = "snapshot")]
+use crate::snapshot::Snapshot;
+use chrono::{DateTime, Utc};
 use serde_json::json;
 use std::sync::Arc;
 use std::thread;
@@ -68,8 +70,9 @@ fn test_state_updates_broadcast_correctly() {
     // Receive broadcast
     let update = rx.try_recv().unwrap();
     assert_eq!(update.entity_id, "agent_001");
-    assert_eq!(update.property, "name");
-    assert_eq!(update.new_value, json!("Bob"));
+    assert_eq!(update.changes.len(), 1);
+    assert_eq!(update.changes[0].property, "name");
+    assert_eq!(update.changes[0].new_value, json!("Bob"));
 }
 
 #[test]
@@ -192,6 +195,9 @@ fn test_load_from_snapshot() {
         id: "sensor_42".to_string(),
         properties,
         last_updated: Utc::now(),
+        last_applied: None,
+        last_modified_sequence: 0,
+        property_times: HashMap::new(),
     };
     entities.insert("sensor_42".to_string(), entity);
 
@@ -229,6 +235,9 @@ fn test_load_from_snapshot_clears_existing_state() {
         id: "new_entity".to_string(),
         properties,
         last_updated: Utc::now(),
+        last_applied: None,
+        last_modified_sequence: 0,
+        property_times: HashMap::new(),
     };
     entities.insert("new_entity".to_string(), entity);
 
@@ -302,6 +311,7 @@ fn test_tombstone_event_deletes_entity() {
         stream: "test".to_string(),
         source: "test".to_string(),
         timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
         key: Some("test_entity".to_string()),
         schema: None,
         payload: json!({
@@ -313,7 +323,7 @@ fn test_tombstone_event_deletes_entity() {
         }),
     };
 
-    engine.process_event(&tombstone);
+    engine.process_event(&tombstone, None);
 
     // Verify entity is deleted
     assert!(engine.get_entity("test_entity").is_none());
@@ -321,14 +331,14 @@ fn test_tombstone_event_deletes_entity() {
 
 #[test]
 fn test_consumer_delivery_no_snapshot_resets_and_delivers_all() {
-    let (should_reset, policy) = StateEngine::consumer_delivery(None);
+    let plan = plan_consumer(None, None, None);
     assert!(
-        should_reset,
+        plan.recreate,
         "no snapshot: must reset consumer to avoid inheriting stale ack offset"
     );
     assert!(
         matches!(
-            policy,
+            plan.start.deliver_policy(),
             async_nats::jetstream::consumer::DeliverPolicy::All
         ),
         "no snapshot: must deliver all events from beginning"
@@ -337,14 +347,14 @@ fn test_consumer_delivery_no_snapshot_resets_and_delivers_all() {
 
 #[test]
 fn test_consumer_delivery_with_snapshot_resumes_from_next_sequence() {
-    let (should_reset, policy) = StateEngine::consumer_delivery(Some(99));
+    let plan = plan_consumer(Some(99), None, Some(99));
     assert!(
-        !should_reset,
+        !plan.recreate,
         "snapshot present: reuse existing durable consumer"
     );
     assert!(
         matches!(
-            policy,
+            plan.start.deliver_policy(),
             async_nats::jetstream::consumer::DeliverPolicy::ByStartSequence {
                 start_sequence: 100
             }
@@ -369,3 +379,1946 @@ fn test_deletion_broadcast() {
     let deleted = deletion_rx.try_recv().unwrap();
     assert_eq!(deleted.entity_id, "test_entity");
 }
+
+#[test]
+fn test_update_properties_applies_all_atomically() {
+    let engine = StateEngine::new();
+    engine.update_property("sensor_1", "temp", json!(20.0));
+
+    let update = engine.update_properties(
+        "sensor_1",
+        vec![
+            ("temp".to_string(), json!(21.5)),
+            ("humidity".to_string(), json!(55)),
+        ],
+    );
+
+    assert_eq!(update.entity_id, "sensor_1");
+    assert_eq!(update.changes.len(), 2);
+    let temp = update
+        .changes
+        .iter()
+        .find(|c| c.property == "temp")
+        .unwrap();
+    assert_eq!(temp.old_value, Some(json!(20.0)));
+    assert_eq!(temp.new_value, json!(21.5));
+    let humidity = update
+        .changes
+        .iter()
+        .find(|c| c.property == "humidity")
+        .unwrap();
+    assert_eq!(humidity.old_value, None);
+
+    let entity = engine.get_entity("sensor_1").unwrap();
+    assert_eq!(entity.properties.len(), 2);
+    assert_eq!(entity.last_updated, update.timestamp);
+}
+
+#[test]
+fn test_process_event_broadcasts_once_per_event() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe();
+
+    let mut properties = serde_json::Map::new();
+    for i in 0..8 {
+        properties.insert(format!("prop_{}", i), json!(i));
+    }
+    let event = FluxEvent {
+        event_id: Some("multi".to_string()),
+        stream: "test".to_string(),
+        source: "test".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({
+            "entity_id": "device/1",
+            "properties": properties,
+        }),
+    };
+
+    engine.process_event(&event, None);
+
+    // Exactly one broadcast carrying all 8 properties
+    let update = rx.try_recv().unwrap();
+    assert_eq!(update.entity_id, "device/1");
+    assert_eq!(update.changes.len(), 8);
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_process_single_property_event_broadcasts_once() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe();
+
+    let event = FluxEvent {
+        event_id: Some("single".to_string()),
+        stream: "test".to_string(),
+        source: "test".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({
+            "entity_id": "device/2",
+            "properties": { "status": "on" },
+        }),
+    };
+
+    engine.process_event(&event, None);
+
+    let update = rx.try_recv().unwrap();
+    assert_eq!(update.changes.len(), 1);
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_process_event_update_carries_source_and_last_updated() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe();
+
+    let event = FluxEvent {
+        event_id: Some("sourced".to_string()),
+        stream: "test".to_string(),
+        source: "modbus-gw".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({
+            "entity_id": "device/3",
+            "properties": { "status": "on" },
+        }),
+    };
+
+    engine.process_event(&event, None);
+
+    let update = rx.try_recv().unwrap();
+    assert_eq!(update.source.as_deref(), Some("modbus-gw"));
+    let entity = engine.get_entity("device/3").unwrap();
+    assert_eq!(update.entity_last_updated, Some(entity.last_updated));
+
+    // Direct updates have no originating event
+    let direct = engine.update_property("device/3", "status", json!("off"));
+    assert_eq!(direct.source, None);
+    assert!(direct.entity_last_updated.is_some());
+}
+
+/// Records the fields of every span created while it is the default subscriber
+#[derive(Clone, Default)]
+struct SpanFields(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanFields {
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        _id: &tracing::span::Id,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        use tracing::field::{Field, Visit};
+
+        struct Visitor<'a>(&'a str, &'a mut Vec<(String, String)>);
+        impl Visitor<'_> {
+            fn push(&mut self, field: &Field, value: String) {
+                self.1.push((format!("{}.{}", self.0, field.name()), value));
+            }
+        }
+        impl Visit for Visitor<'_> {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.push(field, value.to_string());
+            }
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.push(field, format!("{:?}", value));
+            }
+        }
+        let mut fields = self.0.lock().unwrap();
+        attrs.record(&mut Visitor(attrs.metadata().name(), &mut fields));
+    }
+}
+
+#[test]
+fn test_request_id_reaches_span_and_subscribers() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe();
+    let event = FluxEvent {
+        event_id: Some("traced".to_string()),
+        stream: "test".to_string(),
+        source: "modbus-gw".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({
+            "entity_id": "device/4",
+            "properties": { "status": "on" },
+        }),
+    };
+
+    let spans = SpanFields::default();
+    let subscriber = tracing_subscriber::registry().with(spans.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        engine.process_event_with_request_id(&event, Some(7), Some("req-abc"));
+    });
+
+    let update = rx.try_recv().unwrap();
+    assert_eq!(update.request_id.as_deref(), Some("req-abc"));
+    assert_eq!(
+        update.into_state_updates()[0].request_id.as_deref(),
+        Some("req-abc")
+    );
+    let fields = spans.0.lock().unwrap();
+    let recorded = |name: &str, value: &str| fields.iter().any(|(n, v)| n == name && v == value);
+    assert!(recorded("process_event.request_id", "req-abc"));
+    assert!(recorded("process_event.event_id", "traced"));
+
+    // Events without a request ID still apply, with nothing to pass on
+    engine.process_event(&event, None);
+    assert_eq!(rx.try_recv().ok().and_then(|u| u.request_id), None);
+}
+
+#[test]
+fn test_entity_update_into_state_updates() {
+    let engine = StateEngine::new();
+    let update = engine.update_properties(
+        "agent_001",
+        vec![("a".to_string(), json!(1)), ("b".to_string(), json!(2))],
+    );
+    let timestamp = update.timestamp;
+
+    let singles = update.into_state_updates();
+    assert_eq!(singles.len(), 2);
+    assert!(singles.iter().all(|u| u.entity_id == "agent_001"));
+    assert!(singles.iter().all(|u| u.timestamp == timestamp));
+}
+
+#[test]
+fn test_entity_refs_are_copy_on_write() {
+    let engine = StateEngine::new();
+    engine.update_property("sensor_1", "temp", json!(20));
+
+    let refs = engine.entities_snapshot_refs();
+    assert_eq!(refs.len(), 1);
+
+    // Mutation after taking refs must not change the held view
+    engine.update_property("sensor_1", "temp", json!(25));
+    assert_eq!(refs[0].properties["temp"], json!(20));
+    assert_eq!(
+        engine.get_entity("sensor_1").unwrap().properties["temp"],
+        json!(25)
+    );
+}
+
+#[test]
+fn test_for_each_entity_matches_get_all_entities() {
+    let engine = StateEngine::new();
+    for i in 0..50 {
+        engine.update_property(&format!("ns/entity_{}", i), "value", json!(i));
+    }
+
+    let mut visited = Vec::new();
+    engine.for_each_entity(|e| visited.push((e.id.clone(), e.properties["value"].clone())));
+    visited.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut cloned: Vec<_> = engine
+        .get_all_entities()
+        .into_iter()
+        .map(|e| (e.id, e.properties["value"].clone()))
+        .collect();
+    cloned.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(visited, cloned);
+}
+
+#[test]
+fn test_sharded_subscribers_receive_only_their_shards() {
+    let engine = StateEngine::with_broadcast_shards(8);
+    engine.set_live();
+
+    // Many narrow subscribers, each interested in one entity
+    let targets: Vec<String> = (0..64).map(|i| format!("load/entity_{}", i)).collect();
+    let mut subscribers: Vec<_> = targets
+        .iter()
+        .map(|id| {
+            (
+                engine.shard_for(id),
+                engine.subscribe_shard(engine.shard_for(id)),
+            )
+        })
+        .collect();
+    let mut wildcard = engine.subscribe();
+
+    for round in 0..5 {
+        for id in &targets {
+            engine.update_property(id, "round", json!(round));
+        }
+    }
+
+    // Each shard receiver only ever sees updates hashed to its shard
+    for (shard, rx) in subscribers.iter_mut() {
+        let mut received = 0;
+        while let Ok(update) = rx.try_recv() {
+            assert_eq!(engine.shard_for(&update.entity_id), *shard);
+            received += 1;
+        }
+        let expected = targets
+            .iter()
+            .filter(|id| engine.shard_for(id) == *shard)
+            .count()
+            * 5;
+        assert_eq!(received, expected);
+    }
+
+    // Wildcard fan-in still sees everything
+    let mut wildcard_count = 0;
+    while wildcard.try_recv().is_ok() {
+        wildcard_count += 1;
+    }
+    assert_eq!(wildcard_count, targets.len() * 5);
+}
+
+#[test]
+fn test_shard_for_is_stable_and_in_range() {
+    let engine = StateEngine::with_broadcast_shards(4);
+    assert_eq!(engine.shard_count(), 4);
+    for i in 0..100 {
+        let id = format!("ns/e{}", i);
+        let shard = engine.shard_for(&id);
+        assert!(shard < 4);
+        assert_eq!(shard, engine.shard_for(&id));
+    }
+
+    // Zero shards is clamped to one
+    assert_eq!(StateEngine::with_broadcast_shards(0).shard_count(), 1);
+}
+
+#[test]
+fn test_property_cap_rejects_updates_that_would_exceed_it() {
+    let engine = StateEngine::new().with_max_properties_per_entity(3);
+    let event = |properties: serde_json::Value| FluxEvent {
+        event_id: Some("cap".to_string()),
+        stream: "test".to_string(),
+        source: "test".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({"entity_id": "device/3", "properties": properties}),
+    };
+
+    // Up to the cap is accepted
+    engine.process_event(&event(json!({"a": 1, "b": 2})), None);
+    engine.process_event(&event(json!({"c": 3})), None);
+    assert_eq!(engine.get_entity("device/3").unwrap().properties.len(), 3);
+
+    // Overwriting existing properties at the cap is fine
+    engine.process_event(&event(json!({"a": 10})), None);
+    assert_eq!(
+        engine.get_entity("device/3").unwrap().properties["a"],
+        json!(10)
+    );
+    assert_eq!(engine.metrics.get_rejected_updates(), 0);
+
+    // A new property would make four: the whole update is rejected
+    engine.process_event(&event(json!({"b": 20, "d": 4})), None);
+    let entity = engine.get_entity("device/3").unwrap();
+    assert_eq!(entity.properties.len(), 3);
+    assert_eq!(entity.properties["b"], json!(2));
+    assert_eq!(engine.metrics.get_rejected_updates(), 1);
+
+    // New entities are checked too
+    engine.process_event(
+        &FluxEvent {
+            payload: json!({"entity_id": "device/4", "properties": {"a": 1, "b": 2, "c": 3, "d": 4}}),
+            ..event(json!({}))
+        },
+        None,
+    );
+    assert!(engine.get_entity("device/4").is_none());
+    assert_eq!(engine.metrics.get_rejected_updates(), 2);
+}
+
+fn state_event(
+    entity_id: &str,
+    payload_extra: serde_json::Value,
+    properties: serde_json::Value,
+) -> FluxEvent {
+    let mut payload = json!({"entity_id": entity_id, "properties": properties});
+    if let Some(extra) = payload_extra.as_object() {
+        for (k, v) in extra {
+            payload[k] = v.clone();
+        }
+    }
+    FluxEvent {
+        event_id: Some("evt".to_string()),
+        stream: "test".to_string(),
+        source: "test".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload,
+    }
+}
+
+#[test]
+fn test_unset_marker_removes_property() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    engine.process_event(
+        &state_event(
+            "svc/api",
+            json!({}),
+            json!({"status": "error", "error_message": "timeout"}),
+        ),
+        None,
+    );
+    let before = engine.get_entity("svc/api").unwrap().last_updated;
+    let mut rx = engine.subscribe();
+
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    engine.process_event(
+        &state_event(
+            "svc/api",
+            json!({}),
+            json!({"status": "ok", "error_message": {"__unset__": true}}),
+        ),
+        None,
+    );
+
+    let entity = engine.get_entity("svc/api").unwrap();
+    assert_eq!(entity.properties.len(), 1);
+    assert_eq!(entity.properties["status"], json!("ok"));
+    assert!(entity.last_updated > before);
+
+    let update = rx.try_recv().unwrap();
+    let removed = update
+        .changes
+        .iter()
+        .find(|c| c.property == "error_message")
+        .unwrap();
+    assert!(removed.removed);
+    assert_eq!(removed.new_value, serde_json::Value::Null);
+    assert_eq!(removed.old_value, Some(json!("timeout")));
+    let status = update
+        .changes
+        .iter()
+        .find(|c| c.property == "status")
+        .unwrap();
+    assert!(!status.removed);
+}
+
+#[test]
+fn test_null_unsets_is_opt_in() {
+    let engine = StateEngine::new();
+    engine.process_event(
+        &state_event("svc/a", json!({}), json!({"a": 1, "b": 2})),
+        None,
+    );
+
+    // Without the flag null is an ordinary value
+    engine.process_event(&state_event("svc/a", json!({}), json!({"a": null})), None);
+    let entity = engine.get_entity("svc/a").unwrap();
+    assert_eq!(entity.properties.get("a"), Some(&serde_json::Value::Null));
+
+    engine.process_event(
+        &state_event(
+            "svc/a",
+            json!({"null_unsets": true}),
+            json!({"a": null, "b": null}),
+        ),
+        None,
+    );
+    assert!(engine.get_entity("svc/a").unwrap().properties.is_empty());
+}
+
+#[test]
+fn test_unset_of_missing_property_is_a_no_op() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe();
+
+    // Removal-only updates never create the entity
+    engine.process_event(
+        &state_event("svc/ghost", json!({}), json!({"x": {"__unset__": true}})),
+        None,
+    );
+    assert!(engine.get_entity("svc/ghost").is_none());
+    assert!(rx.try_recv().is_err());
+
+    engine.process_event(&state_event("svc/b", json!({}), json!({"a": 1})), None);
+    rx.try_recv().unwrap();
+    engine.process_event(
+        &state_event("svc/b", json!({}), json!({"x": {"__unset__": true}})),
+        None,
+    );
+    assert!(rx.try_recv().is_err());
+    assert_eq!(engine.get_entity("svc/b").unwrap().properties.len(), 1);
+}
+
+#[test]
+fn test_unset_marker_with_extra_keys_is_a_value() {
+    let engine = StateEngine::new();
+    let value = json!({"__unset__": true, "other": 1});
+    engine.process_event(
+        &state_event("svc/c", json!({}), json!({"cfg": value.clone()})),
+        None,
+    );
+    assert_eq!(engine.get_entity("svc/c").unwrap().properties["cfg"], value);
+}
+
+#[test]
+fn test_unset_during_replay_applies_without_broadcast() {
+    let engine = StateEngine::new();
+    let mut rx = engine.subscribe();
+
+    // Still replaying: same state as live processing, no broadcasts
+    engine.process_event(
+        &state_event("svc/d", json!({}), json!({"a": 1, "b": 2})),
+        None,
+    );
+    engine.process_event(
+        &state_event("svc/d", json!({}), json!({"a": {"__unset__": true}})),
+        None,
+    );
+    assert!(rx.try_recv().is_err());
+
+    let entity = engine.get_entity("svc/d").unwrap();
+    assert!(!entity.properties.contains_key("a"));
+    assert_eq!(entity.properties["b"], json!(2));
+}
+
+#[test]
+fn test_unset_frees_room_under_property_cap() {
+    let engine = StateEngine::new().with_max_properties_per_entity(2);
+    engine.process_event(
+        &state_event("svc/e", json!({}), json!({"a": 1, "b": 2})),
+        None,
+    );
+
+    // Swap one property for another in a single event
+    engine.process_event(
+        &state_event(
+            "svc/e",
+            json!({}),
+            json!({"a": {"__unset__": true}, "c": 3}),
+        ),
+        None,
+    );
+
+    let entity = engine.get_entity("svc/e").unwrap();
+    assert_eq!(entity.properties.len(), 2);
+    assert!(entity.properties.contains_key("c"));
+    assert_eq!(engine.metrics.get_rejected_updates(), 0);
+}
+
+#[test]
+fn test_last_updated_uses_plausible_producer_timestamp() {
+    let engine = StateEngine::new();
+    let mut event = state_event("svc/clock", json!({}), json!({"status": "ok"}));
+    event.timestamp = Utc::now().timestamp_millis() - 60_000;
+    event.received_at = Some(event.timestamp + 1_000);
+    engine.process_event(&event, None);
+
+    let entity = engine.get_entity("svc/clock").unwrap();
+    assert_eq!(entity.last_updated.timestamp_millis(), event.timestamp);
+}
+
+#[test]
+fn test_last_updated_prefers_received_at_for_bad_timestamps() {
+    let engine = StateEngine::new();
+    let received_at = Utc::now().timestamp_millis() - 60_000;
+
+    // Seconds instead of milliseconds, and years in the future
+    for (entity_id, timestamp) in [
+        ("svc/seconds", received_at / 1000),
+        ("svc/future", received_at + 365 * 86_400_000),
+    ] {
+        let mut event = state_event(entity_id, json!({}), json!({"status": "ok"}));
+        event.timestamp = timestamp;
+        event.received_at = Some(received_at);
+        engine.process_event(&event, None);
+
+        let entity = engine.get_entity(entity_id).unwrap();
+        assert_eq!(entity.last_updated.timestamp_millis(), received_at);
+    }
+}
+
+#[test]
+fn test_last_updated_falls_back_to_now_without_received_at() {
+    let engine = StateEngine::new();
+    let mut event = state_event("svc/legacy", json!({}), json!({"status": "ok"}));
+    event.timestamp = 1_700_000_000;
+
+    // Event times are kept to the millisecond
+    let before = Utc::now().timestamp_millis();
+    engine.process_event(&event, None);
+
+    let entity = engine.get_entity("svc/legacy").unwrap();
+    assert!(entity.last_updated.timestamp_millis() >= before);
+}
+
+/// Event `id` for svc/order with producer time `timestamp` (plausible, since
+/// received_at is close) and extra payload fields
+fn ordered_event(
+    id: &str,
+    timestamp: i64,
+    extra: serde_json::Value,
+    properties: serde_json::Value,
+) -> FluxEvent {
+    let mut event = state_event("svc/order", extra, properties);
+    event.event_id = Some(id.to_string());
+    event.timestamp = timestamp;
+    event.received_at = Some(timestamp);
+    event
+}
+
+#[test]
+fn test_out_of_order_event_is_discarded() {
+    let engine = StateEngine::new();
+    let t = Utc::now().timestamp_millis() - 60_000;
+
+    // Batch event (t+2) is processed before an earlier single POST (t+1)
+    engine.process_event(
+        &ordered_event("evt-2", t + 2, json!({}), json!({"status": "new", "a": 1})),
+        None,
+    );
+    engine.process_event(
+        &ordered_event("evt-1", t + 1, json!({}), json!({"status": "old", "b": 2})),
+        None,
+    );
+
+    let entity = engine.get_entity("svc/order").unwrap();
+    assert_eq!(entity.properties["status"], json!("new"));
+    assert!(!entity.properties.contains_key("b"));
+    assert_eq!(entity.last_updated.timestamp_millis(), t + 2);
+    assert_eq!(entity.last_applied.unwrap().event_id, "evt-2");
+    assert_eq!(engine.metrics.get_stale_updates(), 1);
+
+    // A later event still applies
+    engine.process_event(
+        &ordered_event("evt-3", t + 3, json!({}), json!({"status": "newest"})),
+        None,
+    );
+    assert_eq!(
+        engine.get_entity("svc/order").unwrap().properties["status"],
+        json!("newest")
+    );
+    assert_eq!(engine.metrics.get_stale_updates(), 1);
+}
+
+#[test]
+fn test_equal_timestamps_are_ordered_by_event_id() {
+    let engine = StateEngine::new();
+    let t = Utc::now().timestamp_millis() - 60_000;
+
+    engine.process_event(
+        &ordered_event("0190-b", t, json!({}), json!({"v": "b"})),
+        None,
+    );
+    engine.process_event(
+        &ordered_event("0190-a", t, json!({}), json!({"v": "a"})),
+        None,
+    );
+    assert_eq!(
+        engine.get_entity("svc/order").unwrap().properties["v"],
+        json!("b")
+    );
+
+    // Redelivery of the applied event is not stale
+    engine.process_event(
+        &ordered_event("0190-b", t, json!({}), json!({"v": "b"})),
+        None,
+    );
+    assert_eq!(engine.metrics.get_stale_updates(), 1);
+}
+
+#[test]
+fn test_sequence_hint_orders_events_from_one_producer() {
+    let engine = StateEngine::new();
+    let t = Utc::now().timestamp_millis() - 60_000;
+
+    // Producer clock went backwards between sequence 1 and 2
+    engine.process_event(
+        &ordered_event("evt-x", t + 5, json!({"sequence": 1}), json!({"v": 1})),
+        None,
+    );
+    engine.process_event(
+        &ordered_event("evt-y", t, json!({"sequence": 2}), json!({"v": 2})),
+        None,
+    );
+    assert_eq!(
+        engine.get_entity("svc/order").unwrap().properties["v"],
+        json!(2)
+    );
+
+    // Sequence 1 redelivered late is stale despite its later timestamp
+    engine.process_event(
+        &ordered_event("evt-x", t + 5, json!({"sequence": 1}), json!({"v": 1})),
+        None,
+    );
+    assert_eq!(
+        engine.get_entity("svc/order").unwrap().properties["v"],
+        json!(2)
+    );
+    assert_eq!(engine.metrics.get_stale_updates(), 1);
+
+    // Without a hint on both sides, timestamps decide
+    engine.process_event(
+        &ordered_event("evt-z", t - 1, json!({}), json!({"v": 0})),
+        None,
+    );
+    assert_eq!(
+        engine.get_entity("svc/order").unwrap().properties["v"],
+        json!(2)
+    );
+    assert_eq!(engine.metrics.get_stale_updates(), 2);
+}
+
+#[test]
+fn test_force_applies_stale_event() {
+    let engine = StateEngine::new();
+    let t = Utc::now().timestamp_millis() - 60_000;
+
+    engine.process_event(
+        &ordered_event("evt-2", t + 2, json!({}), json!({"v": 2})),
+        None,
+    );
+    engine.process_event(
+        &ordered_event("evt-1", t + 1, json!({"force": true}), json!({"v": 1})),
+        None,
+    );
+
+    let entity = engine.get_entity("svc/order").unwrap();
+    assert_eq!(entity.properties["v"], json!(1));
+    assert_eq!(engine.metrics.get_stale_updates(), 0);
+    // The ordering position stays at the newest event
+    assert_eq!(entity.last_applied.unwrap().event_id, "evt-2");
+    engine.process_event(&ordered_event("evt-0", t, json!({}), json!({"v": 0})), None);
+    assert_eq!(engine.metrics.get_stale_updates(), 1);
+}
+
+#[test]
+fn test_replay_matches_live_out_of_order_delivery() {
+    use std::collections::HashMap;
+
+    let t = Utc::now().timestamp_millis() - 60_000;
+    // Stream order as stored in NATS: delivery raced, so evt-1 landed last
+    let stream = [
+        ordered_event("evt-2", t + 2, json!({}), json!({"v": 2, "a": true})),
+        ordered_event("evt-3", t + 3, json!({}), json!({"v": 3})),
+        ordered_event("evt-1", t + 1, json!({}), json!({"v": 1, "b": true})),
+    ];
+
+    let live = StateEngine::new();
+    live.set_live();
+    for event in &stream {
+        live.process_event(event, None);
+    }
+
+    // Full replay from the stream
+    let replayed = StateEngine::new();
+    for event in &stream {
+        replayed.process_event(event, None);
+    }
+
+    // Replay from a snapshot taken after evt-3: the ordering position must
+    // survive serialization for the tail to be discarded the same way
+    let from_snapshot = StateEngine::new();
+    let partial = StateEngine::new();
+    partial.process_event(&stream[0], None);
+    partial.process_event(&stream[1], None);
+    let entities: HashMap<String, Entity> = partial
+        .get_all_entities()
+        .into_iter()
+        .map(|e| {
+            let json = serde_json::to_string(&e).unwrap();
+            (e.id.clone(), serde_json::from_str(&json).unwrap())
+        })
+        .collect();
+    from_snapshot.load_from_snapshot(entities, 2);
+    from_snapshot.process_event(&stream[2], None);
+
+    for engine in [&live, &replayed, &from_snapshot] {
+        let entity = engine.get_entity("svc/order").unwrap();
+        assert_eq!(entity.properties["v"], json!(3));
+        assert!(!entity.properties.contains_key("b"));
+        assert_eq!(entity.last_applied.unwrap().event_id, "evt-3");
+        assert_eq!(engine.metrics.get_stale_updates(), 1);
+    }
+}
+
+fn seed_rename_pair(engine: &StateEngine) {
+    engine.update_properties(
+        "gh/old",
+        [
+            ("stars".to_string(), json!(12)),
+            ("language".to_string(), json!("rust")),
+            ("owner".to_string(), json!("alice")),
+        ],
+    );
+    engine.update_properties(
+        "gh/new",
+        [
+            ("stars".to_string(), json!(10)),
+            ("owner".to_string(), json!("alice")),
+            ("open_issues".to_string(), json!(3)),
+        ],
+    );
+}
+
+#[test]
+fn test_rename_moves_entity() {
+    let engine = StateEngine::new();
+    engine.update_property("gh/old", "stars", json!(12));
+
+    let outcome = engine
+        .rename_entity("gh/old", "gh/moved", false, RenamePreference::To)
+        .unwrap();
+    assert!(!outcome.merged);
+    assert!(outcome.conflicts.is_empty());
+    assert_eq!(outcome.entity.id, "gh/moved");
+    assert!(engine.get_entity("gh/old").is_none());
+    assert_eq!(
+        engine.get_entity("gh/moved").unwrap().properties["stars"],
+        json!(12)
+    );
+}
+
+#[test]
+fn test_property_times_follow_each_property() {
+    let engine = StateEngine::new();
+    engine.update_properties(
+        "gh/repo",
+        [
+            ("stars".to_string(), json!(1)),
+            ("issues".to_string(), json!(2)),
+        ],
+    );
+    let created = engine.get_entity("gh/repo").unwrap();
+    assert_eq!(created.property_time("stars"), created.last_updated);
+
+    thread::sleep(std::time::Duration::from_millis(2));
+    engine.update_property("gh/repo", "stars", json!(3));
+    let entity = engine.get_entity("gh/repo").unwrap();
+    assert_eq!(entity.property_time("stars"), entity.last_updated);
+    assert_eq!(entity.property_time("issues"), created.last_updated);
+    assert!(entity.has_property_older_than(entity.last_updated));
+    assert!(!entity.has_property_older_than(created.last_updated));
+
+    let unset = state_event("gh/repo", json!({}), json!({"issues": {"__unset__": true}}));
+    engine.process_event(&unset, None);
+    let entity = engine.get_entity("gh/repo").unwrap();
+    assert!(!entity.property_times.contains_key("issues"));
+
+    // Renames carry the times along
+    engine
+        .rename_entity("gh/repo", "gh/moved", false, RenamePreference::To)
+        .unwrap();
+    let moved = engine.get_entity("gh/moved").unwrap();
+    assert_eq!(moved.property_time("stars"), entity.property_time("stars"));
+}
+
+#[test]
+fn test_rename_merge_keeps_target_values_by_default() {
+    let engine = StateEngine::new();
+    seed_rename_pair(&engine);
+
+    let outcome = engine
+        .rename_entity("gh/old", "gh/new", true, RenamePreference::To)
+        .unwrap();
+    assert!(outcome.merged);
+    // Equal values are not conflicts
+    assert_eq!(outcome.conflicts, vec!["stars"]);
+
+    let merged = engine.get_entity("gh/new").unwrap();
+    assert_eq!(merged.properties["stars"], json!(10));
+    assert_eq!(merged.properties["language"], json!("rust"));
+    assert_eq!(merged.properties["open_issues"], json!(3));
+    assert_eq!(merged.properties.len(), 4);
+    assert!(engine.get_entity("gh/old").is_none());
+}
+
+#[test]
+fn test_rename_merge_prefer_from_overwrites_conflicts() {
+    let engine = StateEngine::new();
+    seed_rename_pair(&engine);
+
+    let outcome = engine
+        .rename_entity("gh/old", "gh/new", true, RenamePreference::From)
+        .unwrap();
+    assert_eq!(outcome.conflicts, vec!["stars"]);
+    let merged = engine.get_entity("gh/new").unwrap();
+    assert_eq!(merged.properties["stars"], json!(12));
+    assert_eq!(merged.properties["open_issues"], json!(3));
+}
+
+#[test]
+fn test_rename_refusals_leave_state_untouched() {
+    let engine = StateEngine::new().with_max_properties_per_entity(4);
+    seed_rename_pair(&engine);
+    engine.update_property("gh/old", "forks", json!(1));
+
+    assert_eq!(
+        engine
+            .rename_entity("gh/old", "gh/new", false, RenamePreference::To)
+            .unwrap_err(),
+        RenameError::TargetExists("gh/new".to_string())
+    );
+    assert_eq!(
+        engine
+            .rename_entity("gh/old", "gh/new", true, RenamePreference::To)
+            .unwrap_err(),
+        RenameError::TooManyProperties { count: 5, max: 4 }
+    );
+    assert_eq!(
+        engine
+            .rename_entity("gh/missing", "gh/new", true, RenamePreference::To)
+            .unwrap_err(),
+        RenameError::NotFound("gh/missing".to_string())
+    );
+    assert_eq!(
+        engine
+            .rename_entity("gh/old", "gh/old", true, RenamePreference::To)
+            .unwrap_err(),
+        RenameError::SameId
+    );
+
+    assert_eq!(engine.get_entity("gh/old").unwrap().properties.len(), 4);
+    assert_eq!(engine.get_entity("gh/new").unwrap().properties.len(), 3);
+}
+
+#[test]
+fn test_rename_broadcasts_deletion_and_merged_changes() {
+    let engine = StateEngine::new();
+    seed_rename_pair(&engine);
+    engine.set_live();
+    let mut updates = engine.subscribe();
+    let mut deletions = engine.subscribe_deletions();
+
+    engine
+        .rename_entity("gh/old", "gh/new", true, RenamePreference::From)
+        .unwrap();
+
+    assert_eq!(deletions.try_recv().unwrap().entity_id, "gh/old");
+    let update = updates.try_recv().unwrap();
+    assert_eq!(update.entity_id, "gh/new");
+    let mut changes = update.changes;
+    changes.sort_by(|a, b| a.property.cmp(&b.property));
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].property, "language");
+    assert_eq!(changes[0].old_value, None);
+    assert_eq!(changes[1].property, "stars");
+    assert_eq!(changes[1].old_value, Some(json!(10)));
+    assert_eq!(changes[1].new_value, json!(12));
+    assert!(updates.try_recv().is_err());
+}
+
+fn vendor_event(stream: &str, payload: serde_json::Value) -> FluxEvent {
+    FluxEvent {
+        event_id: Some("evt-vendor".to_string()),
+        stream: stream.to_string(),
+        source: "vendor".to_string(),
+        timestamp: Utc::now().timestamp_millis(),
+        received_at: None,
+        key: None,
+        schema: None,
+        payload,
+    }
+}
+
+#[test]
+fn test_stream_mapping_extracts_entity() {
+    use crate::mapping::{CompiledMapping, StreamMapping};
+
+    let engine = StateEngine::new();
+    let payload = json!({"device": {"serial": "t-100"}, "readings": {"temp": 21.5}});
+
+    // Without a mapping the event doesn't fit the envelope and is skipped
+    engine.process_event(&vendor_event("vendor.telemetry", payload.clone()), None);
+    assert!(engine.get_all_entities().is_empty());
+
+    let mapping = CompiledMapping::compile(&StreamMapping {
+        entity_id: "$.device.serial".to_string(),
+        properties: "$.readings".to_string(),
+        namespace: Some("acme".to_string()),
+    })
+    .unwrap();
+    engine.set_stream_mapping("vendor.telemetry", mapping);
+    engine.process_event(&vendor_event("vendor.telemetry", payload.clone()), None);
+    assert_eq!(
+        engine.get_entity("acme/t-100").unwrap().properties["temp"],
+        json!(21.5)
+    );
+
+    // Other streams keep the default shape
+    engine.process_event(&state_event("plain/1", json!({}), json!({"v": 1})), None);
+    assert!(engine.get_entity("plain/1").is_some());
+
+    assert!(engine.remove_stream_mapping("vendor.telemetry"));
+    assert!(!engine.remove_stream_mapping("vendor.telemetry"));
+    engine.process_event(
+        &vendor_event(
+            "vendor.telemetry",
+            json!({"device": {"serial": "t-200"}, "readings": {"temp": 1}}),
+        ),
+        None,
+    );
+    assert!(engine.get_entity("acme/t-200").is_none());
+}
+
+fn ids(changes: &crate::state::Changes) -> Vec<&str> {
+    changes.entities.iter().map(|e| e.id.as_str()).collect()
+}
+
+#[test]
+fn test_changes_since_sequence() {
+    use crate::state::ChangesSince;
+
+    let engine = StateEngine::new();
+    let tombstone = json!({"__deleted__": true});
+    engine.process_event(&state_event("a", json!({}), json!({"v": 1})), Some(1));
+    engine.process_event(&state_event("b", json!({}), json!({"v": 1})), Some(2));
+    engine.process_event(&state_event("a", json!({}), json!({"v": 2})), Some(3));
+    engine.process_event(&state_event("b", json!({}), tombstone.clone()), Some(4));
+    engine.process_event(&state_event("c", json!({}), json!({"v": 1})), Some(5));
+    engine.set_last_processed_sequence(5);
+
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(2), 100, |_| true)
+        .unwrap();
+    assert_eq!(ids(&changes), vec!["a", "c"]);
+    assert_eq!(changes.deleted, vec!["b"]);
+    assert_eq!(changes.next_since_seq, 5);
+    assert!(!changes.has_more);
+
+    // Nothing new after the cursor
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(5), 100, |_| true)
+        .unwrap();
+    assert!(changes.entities.is_empty() && changes.deleted.is_empty());
+    assert_eq!(changes.next_since_seq, 5);
+
+    // Recreated entities are reported as modified, not deleted
+    engine.process_event(&state_event("b", json!({}), json!({"v": 3})), Some(6));
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(3), 100, |_| true)
+        .unwrap();
+    assert_eq!(ids(&changes), vec!["c", "b"]);
+    assert!(changes.deleted.is_empty());
+
+    // Invisible entities are left out
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(0), 100, |id| id != "a")
+        .unwrap();
+    assert_eq!(ids(&changes), vec!["c", "b"]);
+}
+
+#[test]
+fn test_changes_since_pages_in_sequence_order() {
+    use crate::state::ChangesSince;
+
+    let engine = StateEngine::new();
+    for (seq, id) in ["e1", "e2", "e3", "e4", "e5"].iter().enumerate() {
+        let event = state_event(id, json!({}), json!({"v": 1}));
+        engine.process_event(&event, Some(seq as u64 + 1));
+    }
+
+    let mut since = 0;
+    let mut pages = Vec::new();
+    loop {
+        let changes = engine
+            .changes_since(ChangesSince::Sequence(since), 2, |_| true)
+            .unwrap();
+        pages.push(ids(&changes).join(","));
+        since = changes.next_since_seq;
+        if !changes.has_more {
+            break;
+        }
+    }
+    assert_eq!(pages, vec!["e1,e2", "e3,e4", "e5"]);
+    assert_eq!(since, 5);
+}
+
+#[test]
+fn test_changes_page_never_splits_a_sequence() {
+    use crate::state::ChangesSince;
+
+    let engine = StateEngine::new();
+    for (seq, id) in [(1, "a"), (2, "b"), (2, "c"), (3, "d")] {
+        engine.process_event(&state_event(id, json!({}), json!({"v": 1})), Some(seq));
+    }
+
+    // Stops before a group that would straddle the page boundary...
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(0), 2, |_| true)
+        .unwrap();
+    assert_eq!(ids(&changes), vec!["a"]);
+    assert!(changes.has_more);
+    assert_eq!(changes.next_since_seq, 1);
+
+    // ...or takes all of it when it fills the page
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(1), 1, |_| true)
+        .unwrap();
+    let mut page = ids(&changes);
+    page.sort();
+    assert_eq!(page, vec!["b", "c"]);
+    assert!(changes.has_more);
+    assert_eq!(changes.next_since_seq, 2);
+}
+
+#[test]
+fn test_changes_before_snapshot_require_resync() {
+    use crate::state::{ChangesError, ChangesSince};
+    use std::collections::HashMap;
+
+    let engine = StateEngine::new();
+    let mut entities = HashMap::new();
+    entities.insert(
+        "old".to_string(),
+        Entity {
+            id: "old".to_string(),
+            properties: HashMap::new(),
+            last_updated: Utc::now(),
+            last_applied: None,
+            last_modified_sequence: 0,
+            property_times: HashMap::new(),
+        },
+    );
+    engine.load_from_snapshot(entities, 100);
+
+    assert_eq!(
+        engine
+            .changes_since(ChangesSince::Sequence(50), 100, |_| true)
+            .unwrap_err(),
+        ChangesError::ResyncRequired {
+            oldest_sequence: 100
+        }
+    );
+    let long_ago = Utc::now() - chrono::Duration::days(1);
+    assert!(engine
+        .changes_since(ChangesSince::Time(long_ago), 100, |_| true)
+        .is_err());
+
+    // Snapshot entities count as changed at the snapshot
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(100), 100, |_| true)
+        .unwrap();
+    assert!(changes.entities.is_empty());
+    assert_eq!(changes.next_since_seq, 100);
+    engine.process_event(&state_event("new", json!({}), json!({"v": 1})), Some(101));
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(100), 100, |_| true)
+        .unwrap();
+    assert_eq!(ids(&changes), vec!["new"]);
+}
+
+#[test]
+fn test_changes_after_forgotten_deletions_require_resync() {
+    use crate::state::{ChangesError, ChangesSince};
+
+    let engine = StateEngine::new().with_deletion_log_capacity(2);
+    let tombstone = json!({"__deleted__": true});
+    for (seq, id) in ["a", "b", "c"].iter().enumerate() {
+        engine.process_event(
+            &state_event(id, json!({}), tombstone.clone()),
+            Some(seq as u64 + 1),
+        );
+    }
+
+    assert_eq!(
+        engine
+            .changes_since(ChangesSince::Sequence(0), 100, |_| true)
+            .unwrap_err(),
+        ChangesError::ResyncRequired { oldest_sequence: 1 }
+    );
+    let changes = engine
+        .changes_since(ChangesSince::Sequence(1), 100, |_| true)
+        .unwrap();
+    assert_eq!(changes.deleted, vec!["b", "c"]);
+}
+
+fn normalizing_engine(modes: serde_json::Value) -> StateEngine {
+    let config = crate::config::new_runtime_config();
+    let update = serde_json::from_value(json!({ "entity_id_normalization": modes })).unwrap();
+    config.apply_update(&update).unwrap();
+    StateEngine::new().with_runtime_config(config)
+}
+
+#[test]
+fn test_entity_ids_normalized_per_namespace() {
+    let engine = normalizing_engine(json!({"acme": "encode"}));
+
+    engine.process_event(
+        &state_event("acme/Room 1", json!({}), json!({"v": 1})),
+        None,
+    );
+    engine.process_event(
+        &state_event("other/Room 1", json!({}), json!({"v": 1})),
+        None,
+    );
+
+    let entity = engine.get_entity("acme/room-1").unwrap();
+    assert_eq!(entity.properties[RAW_ID_PROPERTY], json!("acme/Room 1"));
+    assert_eq!(engine.normalize_entity_id("acme/Room 1"), "acme/room-1");
+
+    // Namespaces without a mode keep their IDs, and get no __raw_id
+    let other = engine.get_entity("other/Room 1").unwrap();
+    assert!(!other.properties.contains_key(RAW_ID_PROPERTY));
+    assert_eq!(engine.normalize_entity_id("other/Room 1"), "other/Room 1");
+
+    // Tombstones go through the same normalization
+    engine.process_event(
+        &state_event("acme/Room 1", json!({}), json!({"__deleted__": true})),
+        None,
+    );
+    assert!(engine.get_entity("acme/room-1").is_none());
+}
+
+#[test]
+fn test_normalized_id_collisions_are_counted() {
+    let engine = normalizing_engine(json!({"*": "strip"}));
+
+    engine.process_event(&state_event("Room 1", json!({}), json!({"a": 1})), None);
+    assert_eq!(engine.metrics.get_id_collisions(), 0);
+
+    // Same raw ID again is not a collision
+    engine.process_event(&state_event("Room 1", json!({}), json!({"a": 2})), None);
+    assert_eq!(engine.metrics.get_id_collisions(), 0);
+
+    // A different raw ID landing on the same entity is, and still applies
+    engine.process_event(&state_event("ROOM 1!", json!({}), json!({"b": 1})), None);
+    engine.process_event(&state_event("room-1", json!({}), json!({"c": 1})), None);
+    assert_eq!(engine.metrics.get_id_collisions(), 2);
+
+    let entity = engine.get_entity("room-1").unwrap();
+    assert_eq!(entity.properties[RAW_ID_PROPERTY], json!("Room 1"));
+    assert_eq!(entity.properties["b"], json!(1));
+    assert_eq!(entity.properties["c"], json!(1));
+
+    // An entity created under its normalized ID records no __raw_id, but
+    // raw IDs that differ from it still collide
+    engine.process_event(&state_event("room-2", json!({}), json!({"a": 1})), None);
+    engine.process_event(&state_event("Room 2", json!({}), json!({"a": 2})), None);
+    let entity = engine.get_entity("room-2").unwrap();
+    assert!(!entity.properties.contains_key(RAW_ID_PROPERTY));
+    assert_eq!(engine.metrics.get_id_collisions(), 3);
+}
+
+#[test]
+fn test_deadband_drops_small_numeric_changes() {
+    let config = crate::config::new_runtime_config();
+    let update = serde_json::from_value(json!({
+        "deadband_by_namespace": {"plant": {"min_change": 0.1}}
+    }))
+    .unwrap();
+    config.apply_update(&update).unwrap();
+    let engine = StateEngine::new().with_runtime_config(config);
+    engine.set_live();
+    let mut rx = engine.subscribe();
+
+    let start = Utc::now().timestamp_millis() - 3_600_000;
+    let reading = |offset_secs: i64, properties: serde_json::Value| {
+        let mut event = state_event("plant/sensor-1", json!({}), properties);
+        event.timestamp = start + offset_secs * 1000;
+        event
+    };
+
+    engine.process_event(
+        &reading(0, json!({"temperature": 21.001, "status": "ok"})),
+        None,
+    );
+    assert!(rx.try_recv().is_ok());
+    let first_updated = engine.get_entity("plant/sensor-1").unwrap().last_updated;
+
+    // Within the band: nothing written, nothing broadcast
+    engine.process_event(&reading(1, json!({"temperature": 21.002})), None);
+    assert!(rx.try_recv().is_err());
+    let entity = engine.get_entity("plant/sensor-1").unwrap();
+    assert_eq!(entity.properties["temperature"], json!(21.001));
+    assert_eq!(entity.last_updated, first_updated);
+    assert_eq!(engine.metrics.get_dampened_updates(), 1);
+
+    // Only the dampened property is dropped from a mixed update
+    engine.process_event(
+        &reading(2, json!({"temperature": 21.05, "status": "hot"})),
+        None,
+    );
+    let update = rx.try_recv().unwrap();
+    assert_eq!(update.changes.len(), 1);
+    assert_eq!(update.changes[0].property, "status");
+    assert_eq!(engine.metrics.get_dampened_updates(), 2);
+
+    // The keepalive (300s by default) lets an unchanged value through
+    engine.process_event(&reading(301, json!({"temperature": 21.001})), None);
+    assert!(rx.try_recv().is_ok());
+    assert_eq!(engine.metrics.get_dampened_updates(), 2);
+
+    // A large enough change always goes through; other namespaces aren't dampened
+    engine.process_event(&reading(302, json!({"temperature": 21.2})), None);
+    assert_eq!(
+        engine.get_entity("plant/sensor-1").unwrap().properties["temperature"],
+        json!(21.2)
+    );
+    engine.process_event(
+        &state_event("office/sensor-1", json!({}), json!({"t": 1.0})),
+        None,
+    );
+    engine.process_event(
+        &state_event("office/sensor-1", json!({}), json!({"t": 1.0})),
+        None,
+    );
+    assert_eq!(engine.metrics.get_dampened_updates(), 2);
+}
+
+fn tombstone_at(entity_id: &str, deleted_at_ms: i64) -> FluxEvent {
+    let mut event = FluxEvent::tombstone(entity_id, "test");
+    event.payload["properties"]["__deleted_at__"] = json!(deleted_at_ms);
+    event.validate_and_prepare().unwrap();
+    event
+}
+
+fn trash_of(engine: &StateEngine) -> Vec<(String, DateTime<Utc>)> {
+    engine
+        .deleted_entities()
+        .into_iter()
+        .map(|deleted| (deleted.entity.id, deleted.deleted_at))
+        .collect()
+}
+
+#[test]
+fn test_delete_then_undelete_restores_entity() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut deletions = engine.subscribe_deletions();
+
+    engine.process_event(
+        &state_event("ns/pump", json!({}), json!({"rpm": 1200, "status": "on"})),
+        None,
+    );
+    engine.process_event(&tombstone_at("ns/pump", 1_700_000_000_000), None);
+
+    // Deletion is broadcast as before, and the entity is recoverable
+    assert_eq!(deletions.try_recv().unwrap().entity_id, "ns/pump");
+    assert!(engine.get_entity("ns/pump").is_none());
+    let deleted = engine.get_deleted_entity("ns/pump").unwrap();
+    assert_eq!(deleted.deleted_at.timestamp_millis(), 1_700_000_000_000);
+
+    let mut undelete = FluxEvent::undelete("ns/pump", &deleted.entity.properties, "api");
+    undelete.validate_and_prepare().unwrap();
+    engine.process_event(&undelete, None);
+
+    let entity = engine.get_entity("ns/pump").unwrap();
+    assert_eq!(entity.properties["rpm"], json!(1200));
+    assert_eq!(entity.properties["status"], json!("on"));
+    assert!(engine.get_deleted_entity("ns/pump").is_none());
+}
+
+#[test]
+fn test_trash_expiry_and_capacity() {
+    let engine = StateEngine::new().with_trash(2, 3600);
+    let t = Utc::now().timestamp_millis();
+    for (i, id) in ["ns/a", "ns/b", "ns/c"].iter().enumerate() {
+        engine.update_property(id, "v", json!(i));
+        engine.process_event(&tombstone_at(id, t + i as i64), None);
+    }
+
+    // The oldest deletion made room for the newest
+    let ids: Vec<String> = trash_of(&engine).into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, vec!["ns/c".to_string(), "ns/b".to_string()]);
+
+    assert_eq!(engine.purge_trash(Utc::now()), 0);
+    assert_eq!(
+        engine.purge_trash(Utc::now() + chrono::Duration::seconds(7200)),
+        2
+    );
+    assert!(engine.deleted_entities().is_empty());
+
+    // No retention, no trash
+    let engine = StateEngine::new().with_trash(10, 0);
+    engine.update_property("ns/a", "v", json!(1));
+    engine.delete_entity("ns/a");
+    assert!(engine.get_deleted_entity("ns/a").is_none());
+}
+
+#[test]
+#[cfg(feature = "snapshot")]
+fn test_trash_consistent_across_replay_and_snapshot() {
+    let t = 1_700_000_000_000;
+    let stream = vec![
+        state_event("ns/a", json!({}), json!({"v": 1})),
+        state_event("ns/b", json!({}), json!({"v": 2})),
+        tombstone_at("ns/a", t),
+        tombstone_at("ns/b", t + 1),
+        // Writing b again takes it out of the trash
+        state_event("ns/b", json!({}), json!({"v": 3})),
+    ];
+
+    let live = StateEngine::new();
+    live.set_live();
+    for event in &stream {
+        live.process_event(event, None);
+    }
+
+    let replayed = StateEngine::new();
+    for event in &stream {
+        replayed.process_event(event, None);
+    }
+
+    // Snapshot after both deletions, then the tail
+    let partial = StateEngine::new();
+    for event in &stream[..4] {
+        partial.process_event(event, None);
+    }
+    let json = serde_json::to_string(&Snapshot::from_state_engine(&partial, 4)).unwrap();
+    let mut snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+    let from_snapshot = StateEngine::new();
+    let trash = std::mem::take(&mut snapshot.trash);
+    from_snapshot.load_from_snapshot(snapshot.to_hashmap(), 4);
+    from_snapshot.load_trash(trash);
+    from_snapshot.process_event(&stream[4], None);
+
+    let expected = vec![(
+        "ns/a".to_string(),
+        DateTime::from_timestamp_millis(t).unwrap(),
+    )];
+    for engine in [&live, &replayed, &from_snapshot] {
+        assert_eq!(trash_of(engine), expected);
+        assert_eq!(engine.get_entity("ns/b").unwrap().properties["v"], json!(3));
+    }
+}
+
+fn message_event(from: &str, to: &str, body: serde_json::Value) -> FluxEvent {
+    let mut event = FluxEvent::agent_message(from, to, body, "test");
+    event.validate_and_prepare().unwrap();
+    event
+}
+
+#[test]
+fn test_message_stream_logs_and_broadcasts() {
+    let engine = StateEngine::new().with_message_log(2);
+    let mut rx = engine.subscribe_messages();
+
+    // Logged but not broadcast while replaying
+    engine.process_event(&message_event("agent-a", "agent-b", json!("m1")), Some(1));
+    assert!(rx.try_recv().is_err());
+
+    engine.set_live();
+    let event = message_event("agent-a", "agent-b", json!({"text": "m2"}));
+    engine.process_event(&event, Some(2));
+    let message = rx.try_recv().unwrap();
+    assert_eq!(message.id, event.event_id.unwrap());
+    assert_eq!(
+        (message.from.as_str(), message.to.as_str()),
+        ("agent-a", "agent-b")
+    );
+    assert_eq!(message.body, json!({"text": "m2"}));
+
+    // Messages are not entities
+    assert!(engine.get_entity("agent-a").is_none());
+
+    engine.process_event(&message_event("agent-c", "agent-b", json!("m3")), Some(3));
+    let bodies: Vec<_> = engine
+        .messages_to("agent-b", None, usize::MAX)
+        .into_iter()
+        .map(|m| m.body)
+        .collect();
+    assert_eq!(bodies, vec![json!({"text": "m2"}), json!("m3")]);
+}
+
+#[test]
+fn test_recent_activity_only_records_live_traffic() {
+    let engine = StateEngine::new().with_recent_activity(2);
+
+    // Replayed history stays out of the recent activity
+    engine.process_event(&state_event("ns/a", json!({}), json!({"v": 1})), Some(1));
+    engine.process_event(&message_event("agent-a", "agent-b", json!("m1")), Some(2));
+    assert!(engine.recent_changes(10, |_| true).is_empty());
+    assert!(engine.recent_messages(10, |_| true).is_empty());
+    assert_eq!(engine.messages_to("agent-b", None, 10).len(), 1);
+
+    engine.set_live();
+    for v in 2..=4 {
+        engine.process_event(&state_event("ns/a", json!({}), json!({"v": v})), None);
+    }
+    engine.process_event(&message_event("agent-a", "agent-b", json!("m2")), Some(3));
+    let values: Vec<_> = engine
+        .recent_changes(10, |_| true)
+        .into_iter()
+        .map(|change| change.value)
+        .collect();
+    assert_eq!(values, vec![json!(3), json!(4)]);
+    let bodies: Vec<_> = engine
+        .recent_messages(10, |_| true)
+        .into_iter()
+        .map(|message| message.body)
+        .collect();
+    assert_eq!(bodies, vec![json!("m2")]);
+}
+
+#[test]
+fn test_legacy_message_properties_synthesize_message() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    let mut rx = engine.subscribe_messages();
+
+    engine.process_event(
+        &state_event(
+            "agent-a",
+            json!({}),
+            json!({"message": "hello", "message_to": "agent-b"}),
+        ),
+        None,
+    );
+    let message = rx.try_recv().unwrap();
+    assert_eq!(message.id, "evt");
+    assert_eq!(
+        (message.from.as_str(), message.to.as_str()),
+        ("agent-a", "agent-b")
+    );
+    assert_eq!(message.body, json!("hello"));
+    assert_eq!(engine.messages_to("agent-b", None, 10), vec![message]);
+
+    // The properties are still applied as state
+    let entity = engine.get_entity("agent-a").unwrap();
+    assert_eq!(entity.properties["message_to"], json!("agent-b"));
+
+    // Either property alone, or an empty message, is not a message
+    engine.process_event(
+        &state_event("agent-a", json!({}), json!({"message": "hi"})),
+        None,
+    );
+    engine.process_event(
+        &state_event(
+            "agent-a",
+            json!({}),
+            json!({"message": "", "message_to": "agent-b"}),
+        ),
+        None,
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+#[cfg(feature = "snapshot")]
+fn test_message_log_survives_snapshot() {
+    let engine = StateEngine::new();
+    engine.process_event(&message_event("agent-a", "agent-b", json!("m1")), Some(1));
+
+    let json = serde_json::to_string(&Snapshot::from_state_engine(&engine, 1)).unwrap();
+    let mut snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+    let restored = StateEngine::new();
+    let messages = std::mem::take(&mut snapshot.messages);
+    restored.load_from_snapshot(snapshot.to_hashmap(), 1);
+    restored.load_messages(messages);
+
+    assert_eq!(restored.messages(), engine.messages());
+    assert_eq!(restored.messages().len(), 1);
+}
+
+fn quota_event(namespace: &str, max_entities: Option<u64>, max_bytes: Option<u64>) -> FluxEvent {
+    let quota = NamespaceQuota {
+        max_entities,
+        max_bytes,
+    };
+    let mut event = FluxEvent::namespace_quota(namespace, &quota, "test");
+    event.validate_and_prepare().unwrap();
+    event
+}
+
+fn put(engine: &StateEngine, entity_id: &str, properties: serde_json::Value, sequence: u64) {
+    engine.process_event(
+        &state_event(entity_id, json!({}), properties),
+        Some(sequence),
+    );
+}
+
+fn entity_ids(engine: &StateEngine) -> Vec<String> {
+    let mut ids: Vec<String> = engine
+        .get_all_entities()
+        .into_iter()
+        .map(|e| e.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_entity_quota_rejects_new_entities_at_the_limit() {
+    let engine = StateEngine::new();
+    engine.process_event(&quota_event("acme", Some(2), None), Some(1));
+    assert_eq!(
+        engine.namespace_quota("acme").unwrap().max_entities,
+        Some(2)
+    );
+
+    put(&engine, "acme/a", json!({"temp": 20}), 2);
+    put(&engine, "acme/b", json!({"temp": 20}), 3);
+    put(&engine, "acme/c", json!({"temp": 20}), 4);
+    assert_eq!(entity_ids(&engine), ["acme/a", "acme/b"]);
+    assert_eq!(engine.metrics.get_quota_rejections(), 1);
+
+    // Existing entities can still be updated; other namespaces are unaffected
+    put(&engine, "acme/a", json!({"humidity": 60}), 5);
+    put(&engine, "other/x", json!({"temp": 20}), 6);
+    assert_eq!(engine.get_entity("acme/a").unwrap().properties.len(), 2);
+    assert!(engine.get_entity("other/x").is_some());
+
+    // Deleting frees a slot
+    engine.process_event(&tombstone_at("acme/a", 1_700_000_000_000), Some(7));
+    put(&engine, "acme/c", json!({"temp": 20}), 8);
+    assert!(engine.get_entity("acme/c").is_some());
+
+    let usage = engine.namespace_usage("acme");
+    assert_eq!(usage.entities, 2);
+    assert_eq!(usage.quota_rejections, 1);
+    assert_eq!(engine.metrics.get_quota_rejections(), 1);
+}
+
+#[test]
+fn test_byte_quota_boundary() {
+    let engine = StateEngine::new();
+    // "temp" + "20" counts 6 bytes
+    engine.process_event(&quota_event("acme", None, Some(12)), Some(1));
+
+    put(&engine, "acme/a", json!({"temp": 20}), 2);
+    put(&engine, "acme/b", json!({"temp": 21}), 3);
+    assert_eq!(engine.namespace_usage("acme").bytes, 12);
+
+    // One byte over is rejected as a whole
+    put(&engine, "acme/a", json!({"temp": 200}), 4);
+    assert_eq!(
+        engine.get_entity("acme/a").unwrap().properties["temp"],
+        json!(20)
+    );
+    assert_eq!(engine.namespace_usage("acme").quota_rejections, 1);
+
+    // Same-size and shrinking updates pass at the limit
+    put(&engine, "acme/a", json!({"temp": 30}), 5);
+    engine.process_event(
+        &state_event("acme/b", json!({}), json!({"temp": {"__unset__": true}})),
+        Some(6),
+    );
+    assert_eq!(
+        engine.get_entity("acme/a").unwrap().properties["temp"],
+        json!(30)
+    );
+    assert_eq!(engine.namespace_usage("acme").bytes, 6);
+    assert_eq!(engine.metrics.get_quota_rejections(), 1);
+}
+
+#[test]
+fn test_quota_counts_existing_state_and_can_be_lifted() {
+    let engine = StateEngine::new();
+    put(&engine, "acme/a", json!({"temp": 20}), 1);
+    put(&engine, "acme/b", json!({"temp": 20}), 2);
+
+    // A quota below current usage blocks growth only
+    engine.process_event(&quota_event("acme", Some(1), None), Some(3));
+    assert_eq!(engine.namespace_usage("acme").entities, 2);
+    put(&engine, "acme/c", json!({"temp": 20}), 4);
+    put(&engine, "acme/a", json!({"temp": 21}), 5);
+    assert!(engine.get_entity("acme/c").is_none());
+    assert_eq!(
+        engine.get_entity("acme/a").unwrap().properties["temp"],
+        json!(21)
+    );
+
+    // Null limits remove the quota
+    engine.process_event(&quota_event("acme", None, None), Some(6));
+    assert!(engine.namespace_quota("acme").is_none());
+    put(&engine, "acme/c", json!({"temp": 20}), 7);
+    assert!(engine.get_entity("acme/c").is_some());
+    assert!(engine.quotas().is_empty());
+}
+
+#[test]
+#[cfg(feature = "snapshot")]
+fn test_quota_rejections_are_deterministic_on_replay() {
+    let events: Vec<FluxEvent> = vec![
+        state_event("acme/a", json!({}), json!({"temp": 20})),
+        quota_event("acme", Some(2), Some(30)),
+        state_event("acme/b", json!({}), json!({"temp": 20})),
+        state_event("acme/c", json!({}), json!({"temp": 20})),
+        state_event("acme/a", json!({}), json!({"note": "a longer note!"})),
+        tombstone_at("acme/b", 1_700_000_000_000),
+        state_event("acme/c", json!({}), json!({"temp": 20})),
+        quota_event("acme", Some(1), None),
+        state_event("acme/d", json!({}), json!({"temp": 20})),
+    ];
+    let run = |engine: &StateEngine, from: usize| {
+        for (i, event) in events.iter().enumerate().skip(from) {
+            engine.process_event(event, Some(i as u64 + 1));
+        }
+    };
+
+    let first = StateEngine::new();
+    run(&first, 0);
+    assert_eq!(entity_ids(&first), ["acme/a", "acme/c"]);
+    assert_eq!(first.namespace_usage("acme").quota_rejections, 3);
+
+    // Full replay in a fresh engine
+    let replayed = StateEngine::new();
+    run(&replayed, 0);
+    assert_eq!(entity_ids(&replayed), entity_ids(&first));
+    assert_eq!(
+        replayed.namespace_usage("acme"),
+        first.namespace_usage("acme")
+    );
+    assert_eq!(replayed.quotas(), first.quotas());
+
+    // Snapshot part way through, then replay the rest
+    let midway = StateEngine::new();
+    for (i, event) in events.iter().enumerate().take(4) {
+        midway.process_event(event, Some(i as u64 + 1));
+    }
+    let json = serde_json::to_string(&Snapshot::from_state_engine(&midway, 4)).unwrap();
+    let mut snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+    let quotas = std::mem::take(&mut snapshot.quotas);
+    assert_eq!(quotas[0].quota_rejections, 1);
+
+    let restored = StateEngine::new();
+    restored.load_from_snapshot(snapshot.to_hashmap(), 4);
+    restored.load_quotas(quotas);
+    run(&restored, 4);
+    assert_eq!(entity_ids(&restored), entity_ids(&first));
+    assert_eq!(
+        restored.namespace_usage("acme"),
+        first.namespace_usage("acme")
+    );
+    assert_eq!(restored.quotas(), first.quotas());
+}
+
+fn published_by(source: &str, entity_id: &str) -> FluxEvent {
+    let mut event = state_event(entity_id, json!({}), json!({"temp": 20}));
+    event.source = source.to_string();
+    event
+}
+
+#[test]
+fn test_publishers_track_sources_and_namespaces() {
+    let engine = StateEngine::new();
+    engine.set_live();
+    engine.process_event(&published_by("gw", "plant/a"), None);
+    engine.process_event(&published_by("gw", "lab/b"), None);
+    engine.process_event(&published_by("agent", "plant/c"), None);
+
+    let publishers = engine.publishers();
+    assert_eq!(publishers.len(), 2);
+    let gw = engine.publisher("gw").unwrap();
+    assert_eq!(gw.events_total, 2);
+    assert_eq!(gw.namespaces.iter().collect::<Vec<_>>(), ["lab", "plant"]);
+    assert!(engine.publisher("unknown").is_none());
+
+    // Off by default
+    assert!(engine.get_entity("_system/publisher/gw").is_none());
+}
+
+#[test]
+fn test_publisher_entities_are_written_once_live() {
+    let engine = StateEngine::new().with_publisher_entities(true);
+    let mut updates = engine.subscribe();
+
+    // Replay: tracked, but not written
+    for _ in 0..100 {
+        engine.process_event(&published_by("gw", "plant/a"), None);
+    }
+    assert_eq!(engine.publisher("gw").unwrap().events_total, 100);
+    assert!(engine.get_entity("_system/publisher/gw").is_none());
+
+    engine.set_live();
+    let entity = engine.get_entity("_system/publisher/gw").unwrap();
+    assert_eq!(entity.properties["events_total"], json!(100));
+    assert_eq!(entity.properties["namespaces"], json!(["plant"]));
+    let published: Vec<String> = std::iter::from_fn(|| updates.try_recv().ok())
+        .map(|update| update.entity_id)
+        .filter(|id| id.starts_with(PUBLISHER_ENTITY_PREFIX))
+        .collect();
+    assert_eq!(published, ["_system/publisher/gw"]);
+
+    // Live writes are spaced out
+    engine.process_event(&published_by("gw", "plant/a"), None);
+    let entity = engine.get_entity("_system/publisher/gw").unwrap();
+    assert_eq!(entity.properties["events_total"], json!(100));
+    assert_eq!(engine.publisher("gw").unwrap().events_total, 101);
+}
+
+#[test]
+fn test_evicted_publisher_entity_is_removed() {
+    let engine = StateEngine::new()
+        .with_max_publishers(1)
+        .with_publisher_entities(true);
+    engine.set_live();
+
+    engine.process_event(&published_by("old", "plant/a"), None);
+    assert!(engine.get_entity("_system/publisher/old").is_some());
+    engine.process_event(&published_by("new", "plant/a"), None);
+
+    assert!(engine.publisher("old").is_none());
+    assert!(engine.get_entity("_system/publisher/old").is_none());
+    assert!(engine.get_entity("_system/publisher/new").is_some());
+}
+
+fn indexing_engine(
+    properties: serde_json::Value,
+) -> (StateEngine, crate::config::SharedRuntimeConfig) {
+    let config = crate::config::new_runtime_config();
+    let update = serde_json::from_value(json!({ "indexed_properties": properties })).unwrap();
+    config.apply_update(&update).unwrap();
+    let engine = StateEngine::new().with_runtime_config(Arc::clone(&config));
+    (engine, config)
+}
+
+fn queried_ids(engine: &StateEngine, property: &str, value: serde_json::Value) -> Vec<String> {
+    let mut ids: Vec<String> = engine
+        .query_entities(property, &value)
+        .iter()
+        .map(|entity| entity.id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_property_index_follows_writes() {
+    let (engine, _config) = indexing_engine(json!(["status"]));
+
+    engine.update_property("plant/a", "status", json!("error"));
+    engine.update_property("plant/b", "status", json!("error"));
+    engine.update_property("plant/c", "status", json!("ok"));
+    assert_eq!(
+        queried_ids(&engine, "status", json!("error")),
+        ["plant/a", "plant/b"]
+    );
+    assert_eq!(engine.property_index_entries()["status"], 3);
+
+    // Change
+    engine.update_property("plant/a", "status", json!("ok"));
+    assert_eq!(queried_ids(&engine, "status", json!("error")), ["plant/b"]);
+    assert_eq!(
+        queried_ids(&engine, "status", json!("ok")),
+        ["plant/a", "plant/c"]
+    );
+
+    // Remove the property
+    engine.process_event(
+        &state_event("plant/b", json!({}), json!({"status": {"__unset__": true}})),
+        None,
+    );
+    assert!(queried_ids(&engine, "status", json!("error")).is_empty());
+    assert_eq!(engine.property_index_entries()["status"], 2);
+
+    // Delete the entity
+    engine.delete_entity("plant/c");
+    assert_eq!(queried_ids(&engine, "status", json!("ok")), ["plant/a"]);
+    assert_eq!(engine.property_index_entries()["status"], 1);
+
+    // Rename moves the entry
+    engine
+        .rename_entity("plant/a", "plant/z", false, RenamePreference::To)
+        .unwrap();
+    assert_eq!(queried_ids(&engine, "status", json!("ok")), ["plant/z"]);
+    assert_eq!(engine.property_index_entries()["status"], 1);
+
+    // Numbers and strings don't match each other; unindexed values scan
+    engine.update_property("plant/n", "status", json!(1));
+    assert_eq!(queried_ids(&engine, "status", json!(1)), ["plant/n"]);
+    assert!(queried_ids(&engine, "status", json!("1")).is_empty());
+    engine.update_property("plant/o", "status", json!({"code": 1}));
+    assert_eq!(
+        queried_ids(&engine, "status", json!({"code": 1})),
+        ["plant/o"]
+    );
+    assert_eq!(engine.property_index_entries()["status"], 2);
+}
+
+#[test]
+fn test_property_index_rebuilt_on_snapshot_load_and_config_change() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let (engine, config) = indexing_engine(json!(["status"]));
+    engine.update_property("plant/stale", "status", json!("error"));
+
+    let entity = |id: &str, properties: serde_json::Value| Entity {
+        id: id.to_string(),
+        properties: serde_json::from_value(properties).unwrap(),
+        last_updated: Utc::now(),
+        last_applied: None,
+        last_modified_sequence: 0,
+        property_times: HashMap::new(),
+    };
+    let entities = HashMap::from([
+        (
+            "plant/a".to_string(),
+            entity("plant/a", json!({"status": "error", "type": "pump"})),
+        ),
+        (
+            "plant/b".to_string(),
+            entity("plant/b", json!({"status": "ok", "type": "pump"})),
+        ),
+    ]);
+    engine.load_from_snapshot(entities, 10);
+    assert_eq!(queried_ids(&engine, "status", json!("error")), ["plant/a"]);
+    assert_eq!(
+        engine.property_index_entries(),
+        BTreeMap::from([("status".to_string(), 2)])
+    );
+
+    // Adding a property indexes existing entities; dropping one forgets it
+    let update = serde_json::from_value(json!({"indexed_properties": ["type"]})).unwrap();
+    config.apply_update(&update).unwrap();
+    assert_eq!(
+        queried_ids(&engine, "type", json!("pump")),
+        ["plant/a", "plant/b"]
+    );
+    assert_eq!(
+        engine.property_index_entries(),
+        BTreeMap::from([("type".to_string(), 2)])
+    );
+    engine.update_property("plant/b", "type", json!("valve"));
+    assert_eq!(queried_ids(&engine, "type", json!("pump")), ["plant/a"]);
+    assert_eq!(queried_ids(&engine, "status", json!("ok")), ["plant/b"]);
+}
+
+#[test]
+fn test_journal_skips_replayed_updates_it_already_holds() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    // The journal holds everything through sequence 2
+    let engine = StateEngine::new().with_journal(tx, 2);
+    let now = Utc::now().timestamp_millis();
+    let event = |value: u64| FluxEvent {
+        event_id: Some(format!("e{}", value)),
+        stream: "test".to_string(),
+        source: "test".to_string(),
+        timestamp: now + value as i64,
+        received_at: None,
+        key: None,
+        schema: None,
+        payload: json!({"entity_id": "sensor-01", "properties": {"value": value}}),
+    };
+
+    // Replay from a snapshot taken at sequence 1: 2 is journaled, 3 isn't
+    engine.load_from_snapshot(std::collections::HashMap::new(), 1);
+    engine.process_event(&event(2), Some(2));
+    engine.process_event(&event(3), Some(3));
+    engine.set_live();
+    engine.process_event(&event(4), Some(4));
+    // Direct writes carry no sequence and are journaled once live
+    engine.update_property("sensor-01", "note", json!("checked"));
+
+    let mut journaled = Vec::new();
+    while let Ok(record) = rx.try_recv() {
+        journaled.push(record.sequence);
+    }
+    assert_eq!(journaled, vec![Some(3), Some(4), None]);
+    assert_eq!(
+        engine.get_entity("sensor-01").unwrap().properties["value"],
+        json!(4)
+    );
+}
@@ -0,0 +1,29 @@
+use crate::state::StateEngine;
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::info;
+
+/// Longest pause between sweeps, regardless of retention
+const MAX_SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Periodically purge deleted entities whose trash retention has run out
+///
+/// Purging is local: every instance drops the same entries, since deletion
+/// times come from the tombstones.
+pub async fn run_trash_sweeper(state_engine: Arc<StateEngine>) {
+    let retention_secs = state_engine.trash_retention().num_seconds().max(1) as u64;
+    let mut ticker = tokio::time::interval(Duration::from_secs(
+        retention_secs.min(MAX_SWEEP_INTERVAL_SECS),
+    ));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        let purged = state_engine.purge_trash(Utc::now());
+        if purged > 0 {
+            info!(count = purged, "Purged expired entities from trash");
+        }
+    }
+}
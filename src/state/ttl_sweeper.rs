@@ -0,0 +1,107 @@
+use crate::config::SharedRuntimeConfig;
+use crate::event::FluxEvent;
+use crate::nats::EventPublisher;
+use crate::state::StateEngine;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Longest pause between sweeps, regardless of TTL
+const MAX_SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Periodically delete entities not updated within `entity_ttl_seconds`
+///
+/// Expired entities are removed by publishing tombstone events, so deletion
+/// goes through NATS like any other and survives replay. A TTL of 0 disables
+/// sweeping; TTL changes via runtime config apply on the next wake-up.
+pub async fn run_ttl_sweeper(
+    state_engine: Arc<StateEngine>,
+    publisher: EventPublisher,
+    runtime_config: SharedRuntimeConfig,
+) {
+    let mut changes = runtime_config.subscribe();
+
+    loop {
+        let ttl_seconds = runtime_config
+            .read()
+            .expect("RuntimeConfig lock poisoned")
+            .entity_ttl_seconds;
+
+        if ttl_seconds == 0 {
+            // Disabled: sleep until the config changes
+            let _ = changes.recv().await;
+            continue;
+        }
+
+        let pause = Duration::from_secs(ttl_seconds.clamp(1, MAX_SWEEP_INTERVAL_SECS));
+        tokio::select! {
+            _ = tokio::time::sleep(pause) => {}
+            _ = changes.recv() => continue,
+        }
+
+        // Replayed state has stale timestamps until the backlog drains
+        if !state_engine.is_live() {
+            continue;
+        }
+
+        let expired = expired_entity_ids(&state_engine, ttl_seconds, Utc::now());
+        if expired.is_empty() {
+            continue;
+        }
+
+        info!(
+            count = expired.len(),
+            ttl_seconds = ttl_seconds,
+            "Deleting expired entities"
+        );
+        for entity_id in expired {
+            let mut event = FluxEvent::tombstone(&entity_id, "ttl-sweeper");
+            if let Err(e) = event.validate_and_prepare() {
+                error!(entity_id = %entity_id, error = %e, "Invalid TTL tombstone");
+                continue;
+            }
+            if let Err(e) = publisher.publish(&event).await {
+                error!(entity_id = %entity_id, error = %e, "Failed to publish TTL tombstone");
+            }
+        }
+    }
+}
+
+/// IDs of entities whose last update is older than `ttl_seconds` before `now`
+pub fn expired_entity_ids(
+    state_engine: &StateEngine,
+    ttl_seconds: u64,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let cutoff = now - ChronoDuration::seconds(ttl_seconds.min(i64::MAX as u64) as i64);
+    let mut expired = Vec::new();
+    state_engine.for_each_entity(|entity| {
+        if entity.last_updated < cutoff {
+            expired.push(entity.id.clone());
+        }
+    });
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn expired_ids_respect_ttl() {
+        let engine = StateEngine::new();
+        engine.update_property("ns/a", "x", json!(1));
+        engine.update_property("ns/b", "x", json!(1));
+
+        // Seen from an hour in the future, both entities are past a 30 minute TTL
+        let now = Utc::now() + ChronoDuration::seconds(3600);
+        let mut expired = expired_entity_ids(&engine, 1800, now);
+        expired.sort();
+        assert_eq!(expired, vec!["ns/a".to_string(), "ns/b".to_string()]);
+
+        // Nothing expired relative to the present
+        assert!(expired_entity_ids(&engine, 1800, Utc::now()).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Connection and control-message limits for WebSocket clients.
+//!
+//! [`ConnectionTracker`] caps concurrent connections globally and per client
+//! IP; each admitted connection holds a [`ConnectionSlot`] until it closes.
+//! [`ControlMessageBucket`] throttles the subscribe/unsubscribe messages of a
+//! single connection. All limits come from runtime config and are read when
+//! they are checked, so admin API changes apply to the next check (existing
+//! connections are not closed when a cap is lowered).
+
+use dashmap::DashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Why a connection was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionRejected {
+    /// `ws_max_connections` connections are already open
+    TooManyConnections,
+    /// `ws_max_connections_per_ip` connections are already open from this IP
+    TooManyConnectionsFromIp,
+}
+
+impl fmt::Display for ConnectionRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionRejected::TooManyConnections => write!(f, "Too many connections"),
+            ConnectionRejected::TooManyConnectionsFromIp => {
+                write!(f, "Too many connections from this address")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConnectionRejected {}
+
+/// Open WebSocket connections, in total and per client IP
+#[derive(Default)]
+pub struct ConnectionTracker {
+    total: AtomicUsize,
+    per_ip: DashMap<IpAddr, usize>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admit a connection from `ip` if both caps allow it
+    ///
+    /// Connections without a known peer address only count against the
+    /// global cap.
+    pub fn try_acquire(
+        self: &Arc<Self>,
+        ip: Option<IpAddr>,
+        max_connections: usize,
+        max_per_ip: usize,
+    ) -> Result<ConnectionSlot, ConnectionRejected> {
+        self.total
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < max_connections).then_some(open + 1)
+            })
+            .map_err(|_| ConnectionRejected::TooManyConnections)?;
+
+        if let Some(ip) = ip {
+            let mut open = self.per_ip.entry(ip).or_insert(0);
+            if *open >= max_per_ip {
+                drop(open);
+                self.release(Some(ip), false);
+                return Err(ConnectionRejected::TooManyConnectionsFromIp);
+            }
+            *open += 1;
+        }
+
+        Ok(ConnectionSlot {
+            tracker: Arc::clone(self),
+            ip,
+        })
+    }
+
+    /// Number of open connections
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::SeqCst)
+    }
+
+    /// Number of open connections from `ip`
+    pub fn from_ip(&self, ip: IpAddr) -> usize {
+        self.per_ip.get(&ip).map_or(0, |open| *open)
+    }
+
+    fn release(&self, ip: Option<IpAddr>, counted_for_ip: bool) {
+        self.total.fetch_sub(1, Ordering::SeqCst);
+        if let Some(ip) = ip {
+            if counted_for_ip {
+                if let Some(mut open) = self.per_ip.get_mut(&ip) {
+                    *open -= 1;
+                }
+            }
+            self.per_ip.remove_if(&ip, |_, open| *open == 0);
+        }
+    }
+}
+
+/// An admitted connection; releases its place in the counts when dropped
+pub struct ConnectionSlot {
+    tracker: Arc<ConnectionTracker>,
+    ip: Option<IpAddr>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.tracker.release(self.ip, true);
+    }
+}
+
+/// What to do with a control message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// Within the rate limit
+    Accept,
+    /// Over the rate limit: drop it and tell the client
+    Throttle,
+    /// Over the rate limit for a further full minute's worth of messages:
+    /// close the connection
+    Close,
+}
+
+/// Token bucket for one connection's control messages
+///
+/// Holds up to a minute's worth of messages and refills continuously. A
+/// client that keeps sending while throttled is cut off once it has had as
+/// many messages dropped in a row as the per-minute limit.
+pub struct ControlMessageBucket {
+    tokens: f64,
+    last_refill: Instant,
+    dropped_in_row: u64,
+}
+
+impl ControlMessageBucket {
+    pub fn new(now: Instant, per_minute: u64) -> Self {
+        Self {
+            tokens: per_minute as f64,
+            last_refill: now,
+            dropped_in_row: 0,
+        }
+    }
+
+    /// Admit one message arriving at `now` under a limit of `per_minute`
+    pub fn admit(&mut self, now: Instant, per_minute: u64) -> Admission {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        let capacity = per_minute as f64;
+        self.tokens = (self.tokens + elapsed * capacity / 60.0).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.dropped_in_row = 0;
+            return Admission::Accept;
+        }
+        self.dropped_in_row += 1;
+        if self.dropped_in_row > per_minute {
+            Admission::Close
+        } else {
+            Admission::Throttle
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn connection_caps_are_global_and_per_ip() {
+        let tracker = Arc::new(ConnectionTracker::new());
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let first = tracker.try_acquire(Some(a), 3, 2).unwrap();
+        let _second = tracker.try_acquire(Some(a), 3, 2).unwrap();
+        assert_eq!(
+            tracker.try_acquire(Some(a), 3, 2).err(),
+            Some(ConnectionRejected::TooManyConnectionsFromIp)
+        );
+        // The refused connection doesn't count
+        assert_eq!(tracker.total(), 2);
+
+        let _third = tracker.try_acquire(Some(b), 3, 2).unwrap();
+        assert_eq!(
+            tracker.try_acquire(None, 3, 2).err(),
+            Some(ConnectionRejected::TooManyConnections)
+        );
+
+        drop(first);
+        assert_eq!(tracker.total(), 2);
+        assert_eq!(tracker.from_ip(a), 1);
+        assert!(tracker.try_acquire(Some(a), 3, 2).is_ok());
+    }
+
+    #[test]
+    fn idle_addresses_are_forgotten() {
+        let tracker = Arc::new(ConnectionTracker::new());
+        let ip: IpAddr = "::1".parse().unwrap();
+
+        drop(tracker.try_acquire(Some(ip), 10, 10).unwrap());
+        assert!(tracker.try_acquire(Some(ip), 10, 0).is_err());
+        assert_eq!(tracker.total(), 0);
+        assert!(tracker.per_ip.is_empty());
+    }
+
+    #[test]
+    fn control_messages_are_throttled_then_closed() {
+        let start = Instant::now();
+        let mut bucket = ControlMessageBucket::new(start, 3);
+
+        for _ in 0..3 {
+            assert_eq!(bucket.admit(start, 3), Admission::Accept);
+        }
+        for _ in 0..3 {
+            assert_eq!(bucket.admit(start, 3), Admission::Throttle);
+        }
+        assert_eq!(bucket.admit(start, 3), Admission::Close);
+    }
+
+    #[test]
+    fn bucket_refills_over_the_minute() {
+        let start = Instant::now();
+        let mut bucket = ControlMessageBucket::new(start, 60);
+        for _ in 0..60 {
+            bucket.admit(start, 60);
+        }
+        assert_eq!(bucket.admit(start, 60), Admission::Throttle);
+
+        // One message per second comes back, and accepting one resets the
+        // dropped count
+        let later = start + Duration::from_secs(1);
+        assert_eq!(bucket.admit(later, 60), Admission::Accept);
+        assert_eq!(bucket.dropped_in_row, 0);
+        assert_eq!(bucket.admit(later, 60), Admission::Throttle);
+
+        // A raised limit applies to the next refill
+        let later = later + Duration::from_secs(60);
+        for _ in 0..120 {
+            assert_eq!(bucket.admit(later, 120), Admission::Accept);
+        }
+    }
+}
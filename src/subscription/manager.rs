@@ -1,48 +1,213 @@
-use crate::state::{EntityDeleted, MetricsUpdate, StateEngine, StateUpdate};
+use crate::api::auth_middleware::{resolve_scope, AuthError, AuthScope};
+use crate::auth::extract_token_from_message;
+use crate::config::{RuntimeConfig, SharedRuntimeConfig};
+use crate::namespace::NamespaceRegistry;
+use crate::state::{AgentMessage, EntityDeleted, EntityUpdate, MetricsUpdate, StateEngine};
+use crate::subscription::limits::{Admission, ControlMessageBucket};
 use crate::subscription::protocol::{
-    ClientMessage, EntityDeletedMessage, MetricsUpdateMessage, StateUpdateMessage,
+    AgentMessageNotification, ClientMessage, EntityDeletedMessage, ErrorMessage,
+    MetricsUpdateMessage, StateUpdateBatchMessage, StateUpdateMessage,
 };
-use axum::extract::ws::{Message, WebSocket};
-use std::collections::HashSet;
+use crate::subscription::truncate::truncate_large_values;
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use futures::future::select_all;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
+/// How long an auth-required connection may stay unauthenticated
+const AUTH_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Token resolution for connections that must authenticate
+pub struct WsAuth {
+    pub namespace_registry: Arc<NamespaceRegistry>,
+    pub admin_token: Option<String>,
+}
+
 /// Manages a single WebSocket connection with entity subscriptions
 pub struct ConnectionManager {
     /// Set of entity IDs this connection is subscribed to
     subscriptions: HashSet<String>,
+
+    /// Receiver for all updates, used while subscriptions match everything
+    wildcard_rx: Option<broadcast::Receiver<EntityUpdate>>,
+
+    /// Per-shard receivers, used when subscriptions name specific entities
+    shard_rxs: BTreeMap<usize, broadcast::Receiver<EntityUpdate>>,
+
+    /// Entities this connection may see; `None` until authenticated
+    scope: Option<AuthScope>,
+
+    /// Set when the first client message must carry a token
+    auth: Option<WsAuth>,
+
+    /// Token of the access grant the connection authenticated with, checked
+    /// again for every update so revoking or expiring it takes effect
+    grant_token: Option<String>,
+
+    /// Property values larger than this are sent as a truncation marker (0 = no limit)
+    max_value_bytes: usize,
+
+    /// Source of the control message and subscription limits; defaults if unset
+    runtime_config: Option<SharedRuntimeConfig>,
+
+    /// Rate limit on client messages
+    control_bucket: ControlMessageBucket,
+}
+
+/// A client message the connection refused, reported back in an error frame
+#[derive(Debug, PartialEq)]
+enum Refusal {
+    /// Entity outside the connection's scope
+    OutOfScope(String),
+    /// Subscribing would exceed this many subscriptions
+    SubscriptionLimit(usize),
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::OutOfScope(entity_id) => {
+                write!(f, "Not authorized to subscribe to '{}'", entity_id)
+            }
+            Refusal::SubscriptionLimit(max) => write!(
+                f,
+                "Subscription limit of {} reached; unsubscribe from something first",
+                max
+            ),
+        }
+    }
 }
 
 impl ConnectionManager {
+    /// Connection without auth: sees every entity
     pub fn new() -> Self {
         Self {
             subscriptions: HashSet::new(),
+            wildcard_rx: None,
+            shard_rxs: BTreeMap::new(),
+            scope: Some(AuthScope::All),
+            auth: None,
+            grant_token: None,
+            max_value_bytes: 0,
+            runtime_config: None,
+            control_bucket: ControlMessageBucket::new(
+                Instant::now(),
+                RuntimeConfig::default().ws_control_messages_per_minute,
+            ),
         }
     }
 
+    /// Connection that must authenticate with its first message
+    ///
+    /// Nothing is sent until a token resolves to a scope; subscriptions are
+    /// then limited to that namespace (admin token: everything).
+    pub fn with_auth(auth: WsAuth) -> Self {
+        Self {
+            scope: None,
+            auth: Some(auth),
+            ..Self::new()
+        }
+    }
+
+    /// Truncate property values larger than `max_bytes` in state updates (0 = no limit)
+    pub fn with_max_value_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_value_bytes = max_bytes;
+        self
+    }
+
+    /// Take the control message and subscription limits from runtime config
+    ///
+    /// They are read for every client message, so admin API changes apply
+    /// to open connections too.
+    pub fn with_runtime_config(mut self, runtime_config: SharedRuntimeConfig) -> Self {
+        self.runtime_config = Some(runtime_config);
+        self.control_bucket = ControlMessageBucket::new(
+            Instant::now(),
+            self.config(|c| c.ws_control_messages_per_minute),
+        );
+        self
+    }
+
     /// Handle WebSocket connection lifecycle
+    ///
+    /// `state_rx` is the wildcard receiver; once the client subscribes to
+    /// specific entities it is swapped for receivers on just their shards.
     pub async fn handle(
         mut self,
         mut socket: WebSocket,
-        mut state_rx: broadcast::Receiver<StateUpdate>,
+        state_rx: broadcast::Receiver<EntityUpdate>,
         mut metrics_rx: broadcast::Receiver<MetricsUpdate>,
         mut deletion_rx: broadcast::Receiver<EntityDeleted>,
+        mut message_rx: broadcast::Receiver<AgentMessage>,
         state_engine: Arc<StateEngine>,
     ) {
         // Increment WebSocket connection count
         state_engine.metrics.increment_ws_connection();
         info!("WebSocket connection established");
 
+        self.wildcard_rx = Some(state_rx);
+
+        let auth_deadline = tokio::time::sleep(AUTH_GRACE_PERIOD);
+        tokio::pin!(auth_deadline);
+
         loop {
             tokio::select! {
+                // Unauthenticated for too long
+                _ = &mut auth_deadline, if self.scope.is_none() => {
+                    warn!("WebSocket not authenticated within grace period");
+                    Self::close_policy_violation(&mut socket, "Authentication required").await;
+                    break;
+                }
+
                 // Handle incoming client messages
                 Some(msg) = socket.recv() => {
                     match msg {
                         Ok(Message::Text(text)) => {
-                            if let Err(e) = self.handle_client_message(&mut socket, &text).await {
+                            match self.admit_control_message(Instant::now()) {
+                                Admission::Accept => {}
+                                Admission::Throttle => {
+                                    state_engine.metrics.record_ws_throttled_message();
+                                    let error = "Too many messages; slow down".to_string();
+                                    if let Err(e) = Self::send_error(&mut socket, error).await {
+                                        error!(error = %e, "Failed to send error");
+                                        break;
+                                    }
+                                    continue;
+                                }
+                                Admission::Close => {
+                                    warn!("WebSocket client kept sending while throttled");
+                                    state_engine.metrics.record_ws_limit_close();
+                                    Self::close_policy_violation(&mut socket, "Too many messages")
+                                        .await;
+                                    break;
+                                }
+                            }
+                            if self.scope.is_none() {
+                                match self.authenticate(&text) {
+                                    Ok(scope) => {
+                                        info!(scope = ?scope, "WebSocket authenticated");
+                                        self.scope = Some(scope);
+                                    }
+                                    Err(e) => {
+                                        warn!(error = %e, "WebSocket authentication failed");
+                                        Self::close_policy_violation(&mut socket, &e.to_string())
+                                            .await;
+                                        break;
+                                    }
+                                }
+                            }
+                            if let Err(e) = self
+                                .handle_client_message(&mut socket, &text, &state_engine)
+                                .await
+                            {
                                 error!(error = %e, "Error handling client message");
                             }
+                            self.refresh_receivers(&state_engine);
                         }
                         Ok(Message::Close(_)) => {
                             info!("WebSocket client disconnected");
@@ -64,8 +229,8 @@ impl ConnectionManager {
                     }
                 }
 
-                // Handle state updates from broadcast channel
-                result = state_rx.recv() => {
+                // Handle state updates from wildcard or shard channels
+                result = Self::recv_update(&mut self.wildcard_rx, &mut self.shard_rxs) => {
                     match result {
                         Ok(update) => {
                             if self.should_forward_update(&update) {
@@ -89,12 +254,15 @@ impl ConnectionManager {
                 // Handle metrics updates from broadcast channel
                 result = metrics_rx.recv() => {
                     match result {
-                        Ok(metrics) => {
+                        Ok(metrics) if self.scope.is_some() => {
                             if let Err(e) = self.send_metrics_update(&mut socket, metrics).await {
                                 error!(error = %e, "Failed to send metrics update");
                                 break;
                             }
                         }
+                        Ok(_) => {
+                            // Not authenticated yet
+                        }
                         Err(broadcast::error::RecvError::Lagged(skipped)) => {
                             warn!(skipped = skipped, "WebSocket lagged, skipped metrics updates");
                             // Continue processing
@@ -110,6 +278,9 @@ impl ConnectionManager {
                 result = deletion_rx.recv() => {
                     match result {
                         Ok(deleted) => {
+                            if !self.in_scope(&deleted.entity_id) {
+                                continue;
+                            }
                             if let Err(e) = self.send_entity_deleted(&mut socket, deleted).await {
                                 error!(error = %e, "Failed to send entity deleted");
                                 break;
@@ -126,6 +297,29 @@ impl ConnectionManager {
                     }
                 }
 
+                // Handle agent messages from broadcast channel
+                result = message_rx.recv() => {
+                    match result {
+                        Ok(message) => {
+                            if !self.in_scope(&message.to) && !self.in_scope(&message.from) {
+                                continue;
+                            }
+                            if let Err(e) = self.send_agent_message(&mut socket, message).await {
+                                error!(error = %e, "Failed to send agent message");
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!(skipped = skipped, "WebSocket lagged, skipped agent messages");
+                            // Continue processing
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            error!("Message broadcast channel closed");
+                            break;
+                        }
+                    }
+                }
+
                 else => {
                     break;
                 }
@@ -137,16 +331,136 @@ impl ConnectionManager {
         info!("WebSocket connection closed");
     }
 
+    /// Resolve the token in a client message to a read scope
+    fn authenticate(&mut self, text: &str) -> Result<AuthScope, AuthError> {
+        let Some(auth) = &self.auth else {
+            return Ok(AuthScope::All);
+        };
+        let message: Value = serde_json::from_str(text)
+            .map_err(|_| AuthError::InvalidToken("First message must be JSON".to_string()))?;
+        let token = extract_token_from_message(&message)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+        let scope = resolve_scope(
+            &token,
+            &auth.namespace_registry,
+            auth.admin_token.as_deref(),
+        )?;
+        if matches!(scope, AuthScope::Grant { .. }) {
+            self.grant_token = Some(token);
+        }
+        Ok(scope)
+    }
+
+    /// Close the connection with a policy-violation frame
+    pub(crate) async fn close_policy_violation(socket: &mut WebSocket, reason: &str) {
+        let frame = CloseFrame {
+            code: close_code::POLICY,
+            reason: reason.to_string().into(),
+        };
+        if let Err(e) = socket.send(Message::Close(Some(frame))).await {
+            warn!(error = %e, "Failed to send close frame");
+        }
+    }
+
     /// Handle client message (subscribe/unsubscribe)
     async fn handle_client_message(
         &mut self,
-        _socket: &mut WebSocket,
+        socket: &mut WebSocket,
         text: &str,
+        state_engine: &StateEngine,
     ) -> anyhow::Result<()> {
         let msg: ClientMessage = serde_json::from_str(text)?;
+        let recent = match &msg {
+            ClientMessage::Subscribe {
+                entity_id,
+                include_recent: Some(limit),
+            } => Some((entity_id.clone(), *limit)),
+            _ => None,
+        };
+
+        if let Err(refusal) = self.apply_client_message(msg) {
+            if let Refusal::SubscriptionLimit(_) = refusal {
+                state_engine.metrics.record_ws_throttled_message();
+            }
+            Self::send_error(socket, refusal.to_string()).await?;
+            return Ok(());
+        }
+
+        if let Some((entity_id, limit)) = recent {
+            for json in self.recent_json(&entity_id, limit, state_engine)? {
+                socket.send(Message::Text(json)).await?;
+            }
+        }
+
+        Ok(())
+    }
 
+    /// Recent changes, then recent agent messages, for a subscription to
+    /// `entity_id` ("*" = everything in scope): up to `limit` of each,
+    /// oldest first, sent as `state_update` and `agent_message`
+    fn recent_json(
+        &self,
+        entity_id: &str,
+        limit: usize,
+        state_engine: &StateEngine,
+    ) -> serde_json::Result<Vec<String>> {
+        let matches = |id: &str| self.in_scope(id) && (entity_id == "*" || id == entity_id);
+        let changes = state_engine.recent_changes(limit, |change| matches(&change.entity_id));
+        let messages = state_engine.recent_messages(limit, |message| {
+            matches(&message.to) || matches(&message.from)
+        });
+
+        let mut json = Vec::with_capacity(changes.len() + messages.len());
+        for change in changes {
+            let mut msg = StateUpdateMessage::from(change);
+            if self.max_value_bytes > 0 {
+                truncate_large_values(&mut msg.value, self.max_value_bytes);
+            }
+            json.push(serde_json::to_string(&msg)?);
+        }
+        for message in messages {
+            let msg = AgentMessageNotification::from(message);
+            json.push(serde_json::to_string(&msg)?);
+        }
+        Ok(json)
+    }
+
+    /// Send an error frame
+    async fn send_error(socket: &mut WebSocket, error: String) -> anyhow::Result<()> {
+        let json = serde_json::to_string(&ErrorMessage::new(error))?;
+        socket.send(Message::Text(json)).await?;
+        Ok(())
+    }
+
+    /// Read a limit from runtime config (defaults without one)
+    fn config<T>(&self, read: impl FnOnce(&RuntimeConfig) -> T) -> T {
+        match &self.runtime_config {
+            Some(config) => read(&config.read().expect("RuntimeConfig lock poisoned")),
+            None => read(&RuntimeConfig::default()),
+        }
+    }
+
+    /// Charge a client message arriving at `now` against the rate limit
+    fn admit_control_message(&mut self, now: Instant) -> Admission {
+        let per_minute = self.config(|c| c.ws_control_messages_per_minute);
+        self.control_bucket.admit(now, per_minute)
+    }
+
+    /// Update subscriptions; refuses entities outside the scope and
+    /// subscriptions past the per-connection cap
+    fn apply_client_message(&mut self, msg: ClientMessage) -> Result<(), Refusal> {
         match msg {
-            ClientMessage::Subscribe { entity_id } => {
+            ClientMessage::Subscribe { entity_id, .. } => {
+                // "*" is always allowed; updates are filtered to the scope
+                if entity_id != "*" && !self.in_scope(&entity_id) {
+                    warn!(entity_id = %entity_id, "Subscription outside namespace denied");
+                    return Err(Refusal::OutOfScope(entity_id));
+                }
+                let max = self.config(|c| c.ws_max_subscriptions_per_connection);
+                if self.subscriptions.len() >= max && !self.subscriptions.contains(&entity_id) {
+                    warn!(entity_id = %entity_id, max, "Subscription limit reached");
+                    return Err(Refusal::SubscriptionLimit(max));
+                }
                 info!(entity_id = %entity_id, "Client subscribed to entity");
                 self.subscriptions.insert(entity_id);
             }
@@ -159,15 +473,78 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// True if the connection is authenticated and may see `entity_id`
+    fn in_scope(&self, entity_id: &str) -> bool {
+        self.scope
+            .as_ref()
+            .is_some_and(|scope| scope.allows(entity_id))
+            && self.grant_is_live()
+    }
+
+    /// False once the connection's access grant is revoked or expired
+    fn grant_is_live(&self) -> bool {
+        match (&self.grant_token, &self.auth) {
+            (Some(token), Some(auth)) => auth.namespace_registry.lookup_grant(token).is_some(),
+            _ => true,
+        }
+    }
+
+    /// True when subscriptions match every entity (none, or "*")
+    fn matches_all(&self) -> bool {
+        self.subscriptions.is_empty() || self.subscriptions.contains("*")
+    }
+
+    /// Swap state receivers to match current subscriptions
+    ///
+    /// Wildcard subscriptions use the engine's all-updates channel; narrow
+    /// subscriptions hold one receiver per shard their entity IDs hash to.
+    fn refresh_receivers(&mut self, engine: &StateEngine) {
+        if self.matches_all() {
+            self.shard_rxs.clear();
+            if self.wildcard_rx.is_none() {
+                self.wildcard_rx = Some(engine.subscribe());
+            }
+            return;
+        }
+
+        self.wildcard_rx = None;
+        let wanted: BTreeSet<usize> = self
+            .subscriptions
+            .iter()
+            .map(|id| engine.shard_for(id))
+            .collect();
+        self.shard_rxs.retain(|shard, _| wanted.contains(shard));
+        for shard in wanted {
+            self.shard_rxs
+                .entry(shard)
+                .or_insert_with(|| engine.subscribe_shard(shard));
+        }
+    }
+
+    /// Receive the next update from whichever state receiver is ready
+    async fn recv_update(
+        wildcard_rx: &mut Option<broadcast::Receiver<EntityUpdate>>,
+        shard_rxs: &mut BTreeMap<usize, broadcast::Receiver<EntityUpdate>>,
+    ) -> Result<EntityUpdate, broadcast::error::RecvError> {
+        if let Some(rx) = wildcard_rx {
+            return rx.recv().await;
+        }
+        if shard_rxs.is_empty() {
+            return std::future::pending().await;
+        }
+        let futures = shard_rxs.values_mut().map(|rx| Box::pin(rx.recv()));
+        let (result, _, _) = select_all(futures).await;
+        result
+    }
+
     /// Check if update should be forwarded to this connection
-    fn should_forward_update(&self, update: &StateUpdate) -> bool {
-        // If no subscriptions, forward all updates
-        if self.subscriptions.is_empty() {
-            return true;
+    fn should_forward_update(&self, update: &EntityUpdate) -> bool {
+        if !self.in_scope(&update.entity_id) {
+            return false;
         }
 
-        // Check for wildcard subscription
-        if self.subscriptions.contains("*") {
+        // No subscriptions or wildcard: forward all updates in scope
+        if self.matches_all() {
             return true;
         }
 
@@ -176,17 +553,43 @@ impl ConnectionManager {
     }
 
     /// Send state update to client
+    ///
+    /// Single-property updates use `state_update`; multi-property updates are
+    /// sent as one `state_update_batch` message.
     async fn send_state_update(
         &self,
         socket: &mut WebSocket,
-        update: StateUpdate,
+        update: EntityUpdate,
     ) -> anyhow::Result<()> {
-        let msg = StateUpdateMessage::from(update);
-        let json = serde_json::to_string(&msg)?;
-        socket.send(Message::Text(json)).await?;
+        socket
+            .send(Message::Text(self.state_update_json(update)?))
+            .await?;
         Ok(())
     }
 
+    /// Serialize a state update, truncating oversized property values
+    fn state_update_json(&self, update: EntityUpdate) -> serde_json::Result<String> {
+        let max = self.max_value_bytes;
+        if update.changes.len() == 1 {
+            let mut msg = StateUpdateMessage::from(update.into_state_updates().remove(0));
+            if max > 0 {
+                truncate_large_values(&mut msg.value, max);
+            }
+            serde_json::to_string(&msg)
+        } else {
+            let mut msg = StateUpdateBatchMessage::from(update);
+            if max > 0 {
+                for change in &mut msg.changes {
+                    truncate_large_values(&mut change.value, max);
+                    if let Some(old_value) = &mut change.old_value {
+                        truncate_large_values(old_value, max);
+                    }
+                }
+            }
+            serde_json::to_string(&msg)
+        }
+    }
+
     /// Send metrics update to client
     async fn send_metrics_update(
         &self,
@@ -210,6 +613,18 @@ impl ConnectionManager {
         socket.send(Message::Text(json)).await?;
         Ok(())
     }
+
+    /// Send agent message to client
+    async fn send_agent_message(
+        &self,
+        socket: &mut WebSocket,
+        message: AgentMessage,
+    ) -> anyhow::Result<()> {
+        let msg = AgentMessageNotification::from(message);
+        let json = serde_json::to_string(&msg)?;
+        socket.send(Message::Text(json)).await?;
+        Ok(())
+    }
 }
 
 impl Default for ConnectionManager {
@@ -217,3 +632,344 @@ impl Default for ConnectionManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::namespace::GrantScope;
+
+    #[test]
+    fn no_subscriptions_uses_wildcard_receiver() {
+        let engine = StateEngine::with_broadcast_shards(8);
+        let mut manager = ConnectionManager::new();
+
+        manager.refresh_receivers(&engine);
+
+        assert!(manager.wildcard_rx.is_some());
+        assert!(manager.shard_rxs.is_empty());
+    }
+
+    #[test]
+    fn narrow_subscriptions_use_only_matching_shards() {
+        let engine = StateEngine::with_broadcast_shards(8);
+        let mut manager = ConnectionManager::new();
+        manager.wildcard_rx = Some(engine.subscribe());
+
+        manager.subscriptions.insert("ns/a".to_string());
+        manager.subscriptions.insert("ns/b".to_string());
+        manager.refresh_receivers(&engine);
+
+        let expected: BTreeSet<usize> = [engine.shard_for("ns/a"), engine.shard_for("ns/b")]
+            .into_iter()
+            .collect();
+        assert!(manager.wildcard_rx.is_none());
+        assert_eq!(
+            manager.shard_rxs.keys().copied().collect::<BTreeSet<_>>(),
+            expected
+        );
+
+        // Back to wildcard drops shard receivers
+        manager.subscriptions.insert("*".to_string());
+        manager.refresh_receivers(&engine);
+        assert!(manager.wildcard_rx.is_some());
+        assert!(manager.shard_rxs.is_empty());
+    }
+
+    fn update_for(entity_id: &str) -> EntityUpdate {
+        EntityUpdate {
+            entity_id: entity_id.to_string(),
+            changes: vec![],
+            timestamp: chrono::Utc::now(),
+            source: None,
+            entity_last_updated: None,
+            request_id: None,
+        }
+    }
+
+    fn auth_manager(registry: &Arc<NamespaceRegistry>) -> ConnectionManager {
+        ConnectionManager::with_auth(WsAuth {
+            namespace_registry: Arc::clone(registry),
+            admin_token: Some("admin-secret".to_string()),
+        })
+    }
+
+    fn subscribe(entity_id: &str) -> ClientMessage {
+        ClientMessage::Subscribe {
+            entity_id: entity_id.to_string(),
+            include_recent: None,
+        }
+    }
+
+    #[test]
+    fn unauthenticated_connection_sees_nothing() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let manager = auth_manager(&registry);
+
+        assert!(!manager.should_forward_update(&update_for("alice/sensor")));
+        assert!(!manager.should_forward_update(&update_for("sensor")));
+    }
+
+    #[test]
+    fn authenticate_resolves_token_from_first_message() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let alice = registry.register("alice").unwrap();
+        let mut manager = auth_manager(&registry);
+
+        let first = format!(
+            r#"{{"type":"subscribe","entity_id":"*","token":"{}"}}"#,
+            alice.token
+        );
+        assert_eq!(
+            manager.authenticate(&first).unwrap(),
+            AuthScope::Namespace("alice".to_string())
+        );
+        assert_eq!(
+            manager
+                .authenticate(r#"{"type":"subscribe","entity_id":"*","token":"admin-secret"}"#)
+                .unwrap(),
+            AuthScope::All
+        );
+
+        // Missing, unknown and malformed tokens are all rejected
+        assert!(manager
+            .authenticate(r#"{"type":"subscribe","entity_id":"*"}"#)
+            .is_err());
+        assert!(manager
+            .authenticate(r#"{"type":"subscribe","entity_id":"*","token":"bogus"}"#)
+            .is_err());
+        assert!(manager.authenticate("not json").is_err());
+    }
+
+    #[test]
+    fn namespace_scope_denies_foreign_subscriptions() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let mut manager = auth_manager(&registry);
+        manager.scope = Some(AuthScope::Namespace("alice".to_string()));
+
+        assert!(manager
+            .apply_client_message(subscribe("alice/sensor"))
+            .is_ok());
+        assert!(manager
+            .apply_client_message(subscribe("bob/sensor"))
+            .is_err());
+        assert!(manager.apply_client_message(subscribe("sensor")).is_err());
+        assert!(manager.subscriptions.contains("alice/sensor"));
+        assert!(!manager.subscriptions.contains("bob/sensor"));
+
+        assert!(manager.should_forward_update(&update_for("alice/sensor")));
+        assert!(!manager.should_forward_update(&update_for("bob/sensor")));
+    }
+
+    #[test]
+    fn namespace_wildcard_is_limited_to_namespace() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let mut manager = auth_manager(&registry);
+        manager.scope = Some(AuthScope::Namespace("alice".to_string()));
+
+        assert!(manager.apply_client_message(subscribe("*")).is_ok());
+        assert!(manager.should_forward_update(&update_for("alice/a")));
+        assert!(manager.should_forward_update(&update_for("alice/b")));
+        assert!(!manager.should_forward_update(&update_for("bob/a")));
+        assert!(!manager.should_forward_update(&update_for("unprefixed")));
+    }
+
+    #[test]
+    fn grant_token_sees_only_its_prefix() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        registry.register("alice").unwrap();
+        let grant = registry
+            .create_grant("alice", "public/", GrantScope::Read, None)
+            .unwrap();
+        let mut manager = auth_manager(&registry);
+
+        let first = format!(
+            r#"{{"type":"subscribe","entity_id":"*","token":"{}"}}"#,
+            grant.token
+        );
+        manager.scope = Some(manager.authenticate(&first).unwrap());
+
+        assert!(manager
+            .apply_client_message(subscribe("alice/public/sensor"))
+            .is_ok());
+        assert!(manager
+            .apply_client_message(subscribe("alice/secret"))
+            .is_err());
+        assert!(manager
+            .apply_client_message(subscribe("alice/public/../secret"))
+            .is_err());
+
+        assert!(manager.apply_client_message(subscribe("*")).is_ok());
+        assert!(manager.should_forward_update(&update_for("alice/public/a")));
+        assert!(!manager.should_forward_update(&update_for("alice/publicity")));
+        assert!(!manager.should_forward_update(&update_for("alice/sensor")));
+        assert!(!manager.should_forward_update(&update_for("bob/public/a")));
+
+        // Revoking cuts off the open connection and new ones
+        assert!(registry.revoke_grant("alice", &grant.id));
+        assert!(!manager.should_forward_update(&update_for("alice/public/a")));
+        assert!(manager.authenticate(&first).is_err());
+    }
+
+    #[test]
+    fn admin_scope_subscribes_globally() {
+        let registry = Arc::new(NamespaceRegistry::new());
+        let mut manager = auth_manager(&registry);
+        manager.scope = Some(AuthScope::All);
+
+        assert!(manager
+            .apply_client_message(subscribe("bob/sensor"))
+            .is_ok());
+        assert!(manager.should_forward_update(&update_for("bob/sensor")));
+        assert!(!manager.should_forward_update(&update_for("alice/sensor")));
+    }
+
+    #[test]
+    fn state_updates_truncate_large_values() {
+        let change = |property: &str, value: Value| crate::state::PropertyChange {
+            property: property.to_string(),
+            old_value: Some(Value::from("x".repeat(300))),
+            new_value: value,
+            removed: false,
+        };
+        let update = |changes| EntityUpdate {
+            entity_id: "svc/api".to_string(),
+            changes,
+            timestamp: chrono::Utc::now(),
+            source: None,
+            entity_last_updated: None,
+            request_id: None,
+        };
+        // Room for a marker next to a small value
+        let manager = ConnectionManager::new().with_max_value_bytes(250);
+
+        let single = update(vec![change("raw", Value::from("y".repeat(500)))]);
+        let json: Value =
+            serde_json::from_str(&manager.state_update_json(single).unwrap()).unwrap();
+        assert_eq!(json["type"], "state_update");
+        assert_eq!(json["value"]["__truncated__"], Value::Bool(true));
+        assert_eq!(json["value"]["bytes"], 500);
+
+        let batch = update(vec![
+            change("raw", serde_json::json!(["y".repeat(500), "ok"])),
+            change("status", Value::from("ok")),
+        ]);
+        let json: Value =
+            serde_json::from_str(&manager.state_update_json(batch.clone()).unwrap()).unwrap();
+        assert_eq!(json["type"], "state_update_batch");
+        assert_eq!(
+            json["changes"][0]["value"][0]["__truncated__"],
+            Value::Bool(true)
+        );
+        assert_eq!(json["changes"][0]["value"][1], "ok");
+        assert_eq!(json["changes"][0]["old_value"]["bytes"], 300);
+        assert_eq!(json["changes"][1]["value"], "ok");
+
+        // No limit: sent as is
+        let json: Value =
+            serde_json::from_str(&ConnectionManager::new().state_update_json(batch).unwrap())
+                .unwrap();
+        assert_eq!(json["changes"][0]["value"][0].as_str().unwrap().len(), 500);
+    }
+
+    #[test]
+    fn subscriptions_are_capped_per_connection() {
+        let runtime_config = crate::config::new_runtime_config();
+        runtime_config
+            .write()
+            .unwrap()
+            .ws_max_subscriptions_per_connection = 2;
+        let mut manager = ConnectionManager::new().with_runtime_config(Arc::clone(&runtime_config));
+
+        assert!(manager.apply_client_message(subscribe("ns/a")).is_ok());
+        assert!(manager.apply_client_message(subscribe("ns/b")).is_ok());
+        assert_eq!(
+            manager.apply_client_message(subscribe("ns/c")),
+            Err(Refusal::SubscriptionLimit(2))
+        );
+        // Repeating a subscription doesn't need a new slot
+        assert!(manager.apply_client_message(subscribe("ns/a")).is_ok());
+
+        let unsubscribe = ClientMessage::Unsubscribe {
+            entity_id: "ns/a".to_string(),
+        };
+        assert!(manager.apply_client_message(unsubscribe).is_ok());
+        assert!(manager.apply_client_message(subscribe("ns/c")).is_ok());
+
+        // Raising the cap applies to the open connection
+        runtime_config
+            .write()
+            .unwrap()
+            .ws_max_subscriptions_per_connection = 3;
+        assert!(manager.apply_client_message(subscribe("ns/d")).is_ok());
+        assert_eq!(manager.subscriptions.len(), 3);
+    }
+
+    #[test]
+    fn control_messages_follow_runtime_rate_limit() {
+        let runtime_config = crate::config::new_runtime_config();
+        runtime_config
+            .write()
+            .unwrap()
+            .ws_control_messages_per_minute = 2;
+        let mut manager = ConnectionManager::new().with_runtime_config(Arc::clone(&runtime_config));
+        let now = Instant::now();
+
+        assert_eq!(manager.admit_control_message(now), Admission::Accept);
+        assert_eq!(manager.admit_control_message(now), Admission::Accept);
+        assert_eq!(manager.admit_control_message(now), Admission::Throttle);
+        assert_eq!(manager.admit_control_message(now), Admission::Throttle);
+        assert_eq!(manager.admit_control_message(now), Admission::Close);
+
+        // A higher limit refills faster
+        runtime_config
+            .write()
+            .unwrap()
+            .ws_control_messages_per_minute = 600;
+        let later = now + Duration::from_millis(200);
+        assert_eq!(manager.admit_control_message(later), Admission::Accept);
+    }
+
+    #[test]
+    fn auth_disabled_forwards_everything() {
+        let manager = ConnectionManager::new();
+        assert!(manager.should_forward_update(&update_for("alice/sensor")));
+        assert!(manager.should_forward_update(&update_for("sensor")));
+    }
+
+    #[test]
+    fn include_recent_is_scoped_and_limited() {
+        let engine = StateEngine::new();
+        engine.set_live();
+        for i in 0..3 {
+            engine.update_property("alice/a", "v", serde_json::json!(i));
+        }
+        engine.update_property("alice/b", "v", serde_json::json!("x".repeat(200)));
+        engine.update_property("bob/a", "v", serde_json::json!(0));
+
+        let registry = Arc::new(NamespaceRegistry::new());
+        let mut manager = auth_manager(&registry).with_max_value_bytes(100);
+        manager.scope = Some(AuthScope::Namespace("alice".to_string()));
+        let sent = |entity_id: &str, limit: usize| -> Vec<Value> {
+            manager
+                .recent_json(entity_id, limit, &engine)
+                .unwrap()
+                .iter()
+                .map(|json| serde_json::from_str(json).unwrap())
+                .collect()
+        };
+
+        let all = sent("*", 10);
+        let entities: Vec<&str> = all
+            .iter()
+            .map(|m| m["entity_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(entities, ["alice/a", "alice/a", "alice/a", "alice/b"]);
+        assert_eq!(all[0]["type"], "state_update");
+        assert_eq!(all[3]["value"]["__truncated__"], true);
+
+        let newest = sent("alice/a", 2);
+        let values: Vec<&Value> = newest.iter().map(|m| &m["value"]).collect();
+        assert_eq!(values, [&serde_json::json!(1), &serde_json::json!(2)]);
+        assert!(sent("bob/a", 10).is_empty());
+    }
+}
@@ -1,7 +1,13 @@
 // WebSocket subscription management (Task 5)
 
+pub mod limits;
+#[cfg(feature = "http-api")]
 pub mod manager;
 pub mod protocol;
+pub mod truncate;
 
+pub use limits::ConnectionTracker;
+#[cfg(feature = "http-api")]
 pub use manager::ConnectionManager;
-pub use protocol::{ClientMessage, StateUpdateMessage};
+pub use protocol::{ClientMessage, StateUpdateBatchMessage, StateUpdateMessage};
+pub use truncate::{truncate_large_values, DEFAULT_MAX_VALUE_BYTES};
@@ -1,13 +1,16 @@
-use crate::state::StateUpdate;
+use crate::state::{EntityUpdate, RecentChange, StateUpdate};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Client → Server: Subscribe to entity updates
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type", rename = "subscribe")]
 pub struct SubscribeMessage {
     pub entity_id: String,
+    /// Send up to this many recent changes and agent messages first
+    pub include_recent: Option<usize>,
 }
 
 /// Client → Server: Unsubscribe from entity updates
@@ -22,7 +25,11 @@ pub struct UnsubscribeMessage {
 #[serde(tag = "type")]
 pub enum ClientMessage {
     #[serde(rename = "subscribe")]
-    Subscribe { entity_id: String },
+    Subscribe {
+        entity_id: String,
+        /// Send up to this many recent changes and agent messages first
+        include_recent: Option<usize>,
+    },
     #[serde(rename = "unsubscribe")]
     Unsubscribe { entity_id: String },
 }
@@ -35,7 +42,19 @@ pub struct StateUpdateMessage {
     pub entity_id: String,
     pub property: String,
     pub value: Value,
+    /// Property was removed; clients should drop the key rather than store null
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
     pub timestamp: DateTime<Utc>,
+    /// Source of the event that made the change; omitted if unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The entity's `last_updated` after the change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_last_updated: Option<DateTime<Utc>>,
+    /// `X-Request-Id` of the request that published the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl From<StateUpdate> for StateUpdateMessage {
@@ -45,7 +64,83 @@ impl From<StateUpdate> for StateUpdateMessage {
             entity_id: update.entity_id,
             property: update.property,
             value: update.new_value,
+            removed: update.removed,
             timestamp: update.timestamp,
+            source: update.source,
+            entity_last_updated: update.entity_last_updated,
+            request_id: update.request_id,
+        }
+    }
+}
+
+impl From<RecentChange> for StateUpdateMessage {
+    fn from(change: RecentChange) -> Self {
+        Self {
+            msg_type: "state_update".to_string(),
+            entity_id: change.entity_id,
+            property: change.property,
+            value: change.value,
+            removed: change.removed,
+            timestamp: change.timestamp,
+            source: change.source,
+            entity_last_updated: None,
+            request_id: change.request_id,
+        }
+    }
+}
+
+/// Server → Client: Atomic multi-property update notification
+///
+/// Sent when one event changes several properties of an entity. Single-property
+/// updates still use `state_update` for backward compatibility.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateUpdateBatchMessage {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub entity_id: String,
+    pub changes: Vec<PropertyChangeMessage>,
+    pub timestamp: DateTime<Utc>,
+    /// Source of the event that made the changes; omitted if unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The entity's `last_updated` after the changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_last_updated: Option<DateTime<Utc>>,
+    /// `X-Request-Id` of the request that published the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Single property change within a batch update
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyChangeMessage {
+    pub property: String,
+    pub old_value: Option<Value>,
+    pub value: Value,
+    /// Property was removed; clients should drop the key rather than store null
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+}
+
+impl From<EntityUpdate> for StateUpdateBatchMessage {
+    fn from(update: EntityUpdate) -> Self {
+        Self {
+            msg_type: "state_update_batch".to_string(),
+            entity_id: update.entity_id,
+            changes: update
+                .changes
+                .into_iter()
+                .map(|change| PropertyChangeMessage {
+                    property: change.property,
+                    old_value: change.old_value,
+                    value: change.new_value,
+                    removed: change.removed,
+                })
+                .collect(),
+            timestamp: update.timestamp,
+            source: update.source,
+            entity_last_updated: update.entity_last_updated,
+            request_id: update.request_id,
         }
     }
 }
@@ -60,6 +155,14 @@ pub struct MetricsUpdateMessage {
     pub events: MetricsEvents,
     pub websocket: MetricsWebSocket,
     pub publishers: MetricsPublishers,
+    /// Present while the startup replay is still running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay: Option<crate::state::StartupReplayProgress>,
+    pub nats: MetricsNats,
+    /// Entities in the property index, per indexed property
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub property_index: BTreeMap<String, usize>,
+    pub query_cache: MetricsQueryCache,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -76,11 +179,32 @@ pub struct MetricsEvents {
 #[derive(Debug, Clone, Serialize)]
 pub struct MetricsWebSocket {
     pub connections: u64,
+    /// Connections closed for exceeding a connection or message limit
+    pub limit_closes: u64,
+    /// Control messages dropped by the rate limit or subscription cap
+    pub throttled_messages: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct MetricsPublishers {
     pub active: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsQueryCache {
+    /// Entity listings answered from the cache
+    pub hits: u64,
+    /// Entity listings computed because the cache had no fresh response
+    pub misses: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsNats {
+    #[serde(flatten)]
+    pub connection: crate::nats::ConnectionStatus,
+    /// Publishes refused because NATS was unreachable
+    pub publish_unavailable: u64,
 }
 
 impl From<crate::state::MetricsUpdate> for MetricsUpdateMessage {
@@ -97,9 +221,22 @@ impl From<crate::state::MetricsUpdate> for MetricsUpdateMessage {
             },
             websocket: MetricsWebSocket {
                 connections: update.websocket_connections,
+                limit_closes: update.ws_limit_closes,
+                throttled_messages: update.ws_throttled_messages,
             },
             publishers: MetricsPublishers {
                 active: update.active_publishers,
+                truncated: update.sources_truncated,
+            },
+            replay: update.replay,
+            nats: MetricsNats {
+                connection: update.nats,
+                publish_unavailable: update.publish_unavailable,
+            },
+            property_index: update.property_index,
+            query_cache: MetricsQueryCache {
+                hits: update.query_cache_hits,
+                misses: update.query_cache_misses,
             },
         }
     }
@@ -124,6 +261,31 @@ impl From<crate::state::EntityDeleted> for EntityDeletedMessage {
     }
 }
 
+/// Server → Client: Message from one agent to another
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentMessageNotification {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub body: Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<crate::state::AgentMessage> for AgentMessageNotification {
+    fn from(message: crate::state::AgentMessage) -> Self {
+        Self {
+            msg_type: "agent_message".to_string(),
+            id: message.id,
+            from: message.from,
+            to: message.to,
+            body: message.body,
+            timestamp: message.timestamp,
+        }
+    }
+}
+
 /// Server → Client: Error message
 #[derive(Debug, Clone, Serialize)]
 pub struct ErrorMessage {
@@ -140,3 +302,134 @@ impl ErrorMessage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PropertyChange;
+
+    #[test]
+    fn test_removed_flag_only_serialized_when_set() {
+        let update = EntityUpdate {
+            entity_id: "svc/api".to_string(),
+            changes: vec![
+                PropertyChange {
+                    property: "status".to_string(),
+                    old_value: Some(Value::from("error")),
+                    new_value: Value::from("ok"),
+                    removed: false,
+                },
+                PropertyChange {
+                    property: "error_message".to_string(),
+                    old_value: Some(Value::from("timeout")),
+                    new_value: Value::Null,
+                    removed: true,
+                },
+            ],
+            timestamp: Utc::now(),
+            source: None,
+            entity_last_updated: None,
+            request_id: None,
+        };
+
+        let json = serde_json::to_value(StateUpdateBatchMessage::from(update.clone())).unwrap();
+        assert!(json["changes"][0].get("removed").is_none());
+        assert_eq!(json["changes"][1]["removed"], Value::Bool(true));
+        assert!(json["changes"][1]["value"].is_null());
+
+        let single = update.into_state_updates().remove(1);
+        let json = serde_json::to_value(StateUpdateMessage::from(single)).unwrap();
+        assert_eq!(json["removed"], Value::Bool(true));
+    }
+    fn update_from(source: Option<&str>, changes: Vec<PropertyChange>) -> EntityUpdate {
+        let now = Utc::now();
+        EntityUpdate {
+            entity_id: "sensor-1".to_string(),
+            changes,
+            timestamp: now,
+            source: source.map(str::to_string),
+            entity_last_updated: Some(now),
+            request_id: None,
+        }
+    }
+
+    fn set(property: &str, value: Value) -> PropertyChange {
+        PropertyChange {
+            property: property.to_string(),
+            old_value: None,
+            new_value: value,
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_state_update_carries_source_and_entity_last_updated() {
+        let update = update_from(Some("modbus-gw"), vec![set("temp", Value::from(21))]);
+        let last_updated = update.entity_last_updated.unwrap();
+
+        let single = update.into_state_updates().remove(0);
+        let json = serde_json::to_value(StateUpdateMessage::from(single)).unwrap();
+        assert_eq!(json["type"], "state_update");
+        assert_eq!(json["source"], "modbus-gw");
+        let parsed: DateTime<Utc> =
+            serde_json::from_value(json["entity_last_updated"].clone()).unwrap();
+        assert_eq!(parsed, last_updated);
+    }
+
+    #[test]
+    fn test_batch_update_carries_source() {
+        let update = update_from(
+            Some("modbus-gw"),
+            vec![
+                set("temp", Value::from(21)),
+                set("humidity", Value::from(40)),
+            ],
+        );
+
+        let json = serde_json::to_value(StateUpdateBatchMessage::from(update)).unwrap();
+        assert_eq!(json["type"], "state_update_batch");
+        assert_eq!(json["source"], "modbus-gw");
+        assert!(json["entity_last_updated"].is_string());
+        assert!(json["changes"][0].get("source").is_none());
+    }
+
+    #[test]
+    fn test_unknown_source_omitted() {
+        let mut update = update_from(None, vec![set("temp", Value::from(21))]);
+        update.entity_last_updated = None;
+
+        let single = update.into_state_updates().remove(0);
+        let json = serde_json::to_value(StateUpdateMessage::from(single)).unwrap();
+        assert!(json.get("source").is_none());
+        assert!(json.get("entity_last_updated").is_none());
+        assert!(json.get("request_id").is_none());
+    }
+
+    #[test]
+    fn test_request_id_passed_through() {
+        let mut update = update_from(Some("modbus-gw"), vec![set("temp", Value::from(21))]);
+        update.request_id = Some("req-42".to_string());
+
+        let json = serde_json::to_value(StateUpdateBatchMessage::from(update.clone())).unwrap();
+        assert_eq!(json["request_id"], "req-42");
+
+        let single = update.into_state_updates().remove(0);
+        let json = serde_json::to_value(StateUpdateMessage::from(single)).unwrap();
+        assert_eq!(json["request_id"], "req-42");
+    }
+
+    #[test]
+    fn test_state_update_without_new_fields_deserializes() {
+        // Shape produced before source / entity_last_updated existed
+        let update: StateUpdate = serde_json::from_value(serde_json::json!({
+            "entity_id": "sensor-1",
+            "property": "temp",
+            "old_value": null,
+            "new_value": 21,
+            "timestamp": "2026-02-12T10:30:00Z"
+        }))
+        .unwrap();
+        assert_eq!(update.source, None);
+        assert_eq!(update.entity_last_updated, None);
+    }
+}
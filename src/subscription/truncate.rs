@@ -0,0 +1,135 @@
+use serde_json::{json, Value};
+
+/// Default cap on a property value in outbound WebSocket messages
+pub const DEFAULT_MAX_VALUE_BYTES: usize = 32_768;
+
+/// Characters of the original value kept in a truncation marker
+const PREVIEW_CHARS: usize = 128;
+
+/// Replace values larger than `max_bytes` (serialized) with a marker
+///
+/// Objects and arrays are truncated member by member first and only replaced
+/// whole if they are still too large. Markers look like
+/// `{"__truncated__": true, "bytes": N, "preview": "..."}`, where `bytes` is
+/// the string length or the serialized size of the object or array.
+/// Numbers, booleans and null are never replaced.
+pub fn truncate_large_values(value: &mut Value, max_bytes: usize) {
+    let size = serialized_len(value);
+    if size <= max_bytes {
+        return;
+    }
+
+    let serialized = match value {
+        Value::String(s) => {
+            *value = marker(s.len(), preview(s));
+            return;
+        }
+        Value::Object(map) => {
+            map.values_mut()
+                .for_each(|v| truncate_large_values(v, max_bytes));
+            if serialized_len(value) <= max_bytes {
+                return;
+            }
+            value.to_string()
+        }
+        Value::Array(items) => {
+            items
+                .iter_mut()
+                .for_each(|v| truncate_large_values(v, max_bytes));
+            if serialized_len(value) <= max_bytes {
+                return;
+            }
+            value.to_string()
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => return,
+    };
+    *value = marker(size, preview(&serialized));
+}
+
+fn marker(bytes: usize, preview: &str) -> Value {
+    json!({"__truncated__": true, "bytes": bytes, "preview": preview})
+}
+
+/// Leading `PREVIEW_CHARS` characters of `s`
+fn preview(s: &str) -> &str {
+    match s.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+fn serialized_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|v| v.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_marker(value: &Value) -> bool {
+        value["__truncated__"] == json!(true)
+    }
+
+    #[test]
+    fn test_small_values_untouched() {
+        let mut value = json!({"temperature": 22.5, "tags": ["a", "b"], "name": "sensor"});
+        let before = value.clone();
+        truncate_large_values(&mut value, 1024);
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn test_large_string_replaced() {
+        let mut value = Value::String("é".repeat(1000));
+        truncate_large_values(&mut value, 100);
+
+        assert!(is_marker(&value));
+        assert_eq!(value["bytes"], json!(2000));
+        assert_eq!(value["preview"].as_str().unwrap().chars().count(), 128);
+    }
+
+    #[test]
+    fn test_nested_object_keeps_small_members() {
+        let mut value = json!({
+            "status": "ok",
+            "raw": {"payload": "x".repeat(5000), "code": 200}
+        });
+        truncate_large_values(&mut value, 1000);
+
+        assert_eq!(value["status"], json!("ok"));
+        assert_eq!(value["raw"]["code"], json!(200));
+        assert!(is_marker(&value["raw"]["payload"]));
+        assert_eq!(value["raw"]["payload"]["bytes"], json!(5000));
+    }
+
+    #[test]
+    fn test_array_of_large_strings() {
+        // Each string is over the limit; the markers together are not
+        let mut value = json!(["x".repeat(500), "short", "y".repeat(500)]);
+        truncate_large_values(&mut value, 400);
+
+        let items = value.as_array().unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(is_marker(&items[0]));
+        assert_eq!(items[1], json!("short"));
+        assert!(is_marker(&items[2]));
+        assert_eq!(items[2]["bytes"], json!(500));
+    }
+
+    #[test]
+    fn test_container_still_too_large_replaced_whole() {
+        // No single element is over the limit, but together they are
+        let items: Vec<String> = (0..100).map(|i| format!("item-{:04}", i)).collect();
+        let mut value = json!({"list": items});
+        let size = serialized_len(&value["list"]);
+        truncate_large_values(&mut value, 500);
+
+        assert!(is_marker(&value["list"]));
+        assert_eq!(value["list"]["bytes"], json!(size));
+        assert!(value["list"]["preview"]
+            .as_str()
+            .unwrap()
+            .starts_with("[\"item-0000\""));
+        assert!(serialized_len(&value) <= 500);
+    }
+}
@@ -0,0 +1,388 @@
+// Supervision of long-running background tasks (subscriber, sweepers,
+// snapshots). Each task is registered by name with a factory that builds a
+// fresh run of it; a run that panics is replaced after a backoff, and the
+// admin API can restart a task on demand.
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// How long to wait before replacing a task that panicked
+///
+/// The wait starts at `initial_backoff` and doubles with each panic, up to
+/// `max_backoff`. A run that lasted `max_backoff` or longer starts over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn new(initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            initial_backoff,
+            max_backoff: max_backoff.max(initial_backoff),
+        }
+    }
+}
+
+/// What a supervised task is doing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    /// Panicked; waiting out the backoff before the next run
+    Restarting,
+    /// Returned; stays stopped until restarted through the admin API
+    Finished,
+}
+
+/// A supervised task as reported by `GET /api/admin/tasks`
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "metrics_broadcaster",
+    "status": "running",
+    "restarts": 1,
+    "started_at": "2026-01-01T00:00:05Z",
+    "last_panic": "called `Option::unwrap()` on a `None` value",
+    "last_panic_at": "2026-01-01T00:00:04Z"
+}))]
+pub struct TaskInfo {
+    pub name: String,
+    pub status: TaskStatus,
+    /// Runs started after the first, for panics and manual restarts
+    pub restarts: u64,
+    /// When the current (or last) run started
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_panic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_panic_at: Option<DateTime<Utc>>,
+}
+
+type TaskFactory = Box<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+struct SupervisedTask {
+    policy: RestartPolicy,
+    factory: TaskFactory,
+    info: Mutex<TaskInfo>,
+    /// Signalled by [`TaskSupervisor::restart`]
+    restart: Notify,
+}
+
+/// Registry of supervised background tasks
+#[derive(Default)]
+pub struct TaskSupervisor {
+    tasks: Mutex<BTreeMap<String, Arc<SupervisedTask>>>,
+}
+
+/// How a run ended
+enum Exit {
+    Panicked,
+    Finished,
+    Restarted,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the task `factory` builds and keep it running under `name`
+    ///
+    /// `factory` is called again for every restart, so it captures the task's
+    /// dependencies rather than a single future.
+    ///
+    /// # Panics
+    ///
+    /// If a task named `name` is already supervised.
+    pub fn supervise<F, Fut>(&self, name: impl Into<String>, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        let task = Arc::new(SupervisedTask {
+            policy,
+            factory: Box::new(move || Box::pin(factory())),
+            info: Mutex::new(TaskInfo {
+                name: name.clone(),
+                status: TaskStatus::Running,
+                restarts: 0,
+                started_at: Utc::now(),
+                last_panic: None,
+                last_panic_at: None,
+            }),
+            restart: Notify::new(),
+        });
+        let previous = self
+            .tasks
+            .lock()
+            .unwrap()
+            .insert(name.clone(), Arc::clone(&task));
+        assert!(previous.is_none(), "task {name} is already supervised");
+        tokio::spawn(monitor(name, task));
+    }
+
+    /// Every supervised task, by name
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let tasks = self.tasks.lock().unwrap();
+        tasks
+            .values()
+            .map(|task| task.info.lock().unwrap().clone())
+            .collect()
+    }
+
+    pub fn task(&self, name: &str) -> Option<TaskInfo> {
+        let task = self.tasks.lock().unwrap().get(name).cloned()?;
+        let info = task.info.lock().unwrap().clone();
+        Some(info)
+    }
+
+    /// Stop the task's current run, if any, and start a fresh one now
+    ///
+    /// Returns false if no task is named `name`.
+    pub fn restart(&self, name: &str) -> bool {
+        let Some(task) = self.tasks.lock().unwrap().get(name).cloned() else {
+            return false;
+        };
+        task.restart.notify_one();
+        true
+    }
+}
+
+/// Run `task` until the process exits, replacing runs that panic
+async fn monitor(name: String, task: Arc<SupervisedTask>) {
+    let mut backoff = task.policy.initial_backoff;
+    loop {
+        let started = Instant::now();
+        let mut handle = tokio::spawn((task.factory)());
+        let exit = tokio::select! {
+            result = &mut handle => match result {
+                Err(e) if e.is_panic() => {
+                    let message = panic_message(e.into_panic());
+                    if started.elapsed() >= task.policy.max_backoff {
+                        backoff = task.policy.initial_backoff;
+                    }
+                    error!(
+                        task = %name,
+                        panic = %message,
+                        retry_in_ms = backoff.as_millis() as u64,
+                        "Background task panicked"
+                    );
+                    let mut info = task.info.lock().unwrap();
+                    info.status = TaskStatus::Restarting;
+                    info.last_panic = Some(message);
+                    info.last_panic_at = Some(Utc::now());
+                    Exit::Panicked
+                }
+                _ => {
+                    info!(task = %name, "Background task finished");
+                    task.info.lock().unwrap().status = TaskStatus::Finished;
+                    Exit::Finished
+                }
+            },
+            () = task.restart.notified() => {
+                handle.abort();
+                // Never two runs at once
+                let _ = handle.await;
+                info!(task = %name, "Background task restarted");
+                Exit::Restarted
+            }
+        };
+
+        match exit {
+            Exit::Panicked => {
+                tokio::select! {
+                    () = tokio::time::sleep(backoff) => {}
+                    () = task.restart.notified() => {}
+                }
+                backoff = (backoff * 2).min(task.policy.max_backoff);
+            }
+            Exit::Finished => task.restart.notified().await,
+            Exit::Restarted => backoff = task.policy.initial_backoff,
+        }
+        let mut info = task.info.lock().unwrap();
+        info.status = TaskStatus::Running;
+        info.restarts += 1;
+        info.started_at = Utc::now();
+    }
+}
+
+/// The message a panic was raised with
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "panic with a non-string payload".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Decrements the count of running tasks when a run is dropped
+    struct Running(Arc<AtomicUsize>);
+
+    impl Drop for Running {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Let spawned tasks run
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// A factory whose first `panics` runs panic and later ones run forever,
+    /// recording when each run started
+    fn flaky(
+        panics: usize,
+    ) -> (
+        Arc<Mutex<Vec<Instant>>>,
+        impl Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    ) {
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let runs = Arc::clone(&starts);
+        let factory = move || -> BoxFuture<'static, ()> {
+            let runs = Arc::clone(&runs);
+            Box::pin(async move {
+                let run = {
+                    let mut runs = runs.lock().unwrap();
+                    runs.push(Instant::now());
+                    runs.len()
+                };
+                if run <= panics {
+                    panic!("run {run} failed");
+                }
+                std::future::pending::<()>().await
+            })
+        };
+        (starts, factory)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_panicking_task_restarted_with_backoff() {
+        let supervisor = TaskSupervisor::new();
+        let (starts, factory) = flaky(4);
+        let policy = RestartPolicy::new(Duration::from_secs(1), Duration::from_secs(4));
+        supervisor.supervise("flaky", policy, factory);
+
+        settle().await;
+        let info = supervisor.task("flaky").unwrap();
+        assert_eq!(info.status, TaskStatus::Restarting);
+        assert_eq!(info.last_panic.as_deref(), Some("run 1 failed"));
+
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        let info = supervisor.task("flaky").unwrap();
+        assert_eq!(info.status, TaskStatus::Running);
+        assert_eq!(info.restarts, 4);
+        assert_eq!(info.last_panic.as_deref(), Some("run 4 failed"));
+        assert!(info.last_panic_at.is_some());
+
+        // Waits double from 1s and stop at the 4s cap
+        let starts = starts.lock().unwrap();
+        let gaps: Vec<u64> = starts
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).as_secs())
+            .collect();
+        assert_eq!(gaps, vec![1, 2, 4, 4]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restart_replaces_running_task() {
+        let supervisor = TaskSupervisor::new();
+        let running = Arc::new(AtomicUsize::new(0));
+        let starts = Arc::new(AtomicUsize::new(0));
+        let (r, s) = (Arc::clone(&running), Arc::clone(&starts));
+        supervisor.supervise("steady", RestartPolicy::default(), move || {
+            let running = Arc::clone(&r);
+            s.fetch_add(1, Ordering::SeqCst);
+            async move {
+                running.fetch_add(1, Ordering::SeqCst);
+                let _guard = Running(running);
+                std::future::pending::<()>().await
+            }
+        });
+        settle().await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+
+        assert!(supervisor.restart("steady"));
+        assert!(!supervisor.restart("missing"));
+        settle().await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        assert_eq!(running.load(Ordering::SeqCst), 1);
+        let info = supervisor.task("steady").unwrap();
+        assert_eq!(info.status, TaskStatus::Running);
+        assert_eq!(info.restarts, 1);
+        assert_eq!(info.last_panic, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_finished_task_waits_for_restart() {
+        let supervisor = TaskSupervisor::new();
+        let starts = Arc::new(AtomicUsize::new(0));
+        let s = Arc::clone(&starts);
+        supervisor.supervise("once", RestartPolicy::default(), move || {
+            s.fetch_add(1, Ordering::SeqCst);
+            async {}
+        });
+
+        tokio::time::sleep(Duration::from_secs(120)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            supervisor.task("once").unwrap().status,
+            TaskStatus::Finished
+        );
+
+        supervisor.restart("once");
+        settle().await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        assert_eq!(supervisor.tasks()[0].restarts, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restart_skips_backoff() {
+        let supervisor = TaskSupervisor::new();
+        let (starts, factory) = flaky(1);
+        supervisor.supervise("flaky", RestartPolicy::default(), factory);
+        settle().await;
+        assert_eq!(
+            supervisor.task("flaky").unwrap().status,
+            TaskStatus::Restarting
+        );
+
+        supervisor.restart("flaky");
+        settle().await;
+        assert_eq!(starts.lock().unwrap().len(), 2);
+        assert_eq!(
+            supervisor.task("flaky").unwrap().status,
+            TaskStatus::Running
+        );
+    }
+}
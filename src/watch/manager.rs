@@ -0,0 +1,552 @@
+use crate::http_client::HttpClientConfig;
+use crate::state::{EntityUpdate, StateEngine};
+use crate::watch::target::check_webhook_url;
+use crate::watch::{NewWatch, Watch};
+use crate::webhook::{signed_post, SealedSecret, SecretBox};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tracing::{debug, warn};
+
+/// Default number of watches held at once
+pub const DEFAULT_MAX_WATCHES: usize = 10_000;
+
+/// How often expired watches are finished and old ones dropped
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Timeout of a webhook POST
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a watch could not be created
+#[derive(Debug, PartialEq)]
+pub enum WatchError {
+    /// Missing field or unusable webhook URL
+    Invalid(String),
+    /// The manager already holds its maximum number of watches
+    TooManyWatches,
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::Invalid(reason) => write!(f, "{}", reason),
+            WatchError::TooManyWatches => write!(f, "Too many watches"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {}
+
+#[derive(Default)]
+struct Watches {
+    /// Each watch is kept in the channel long-pollers wait on
+    by_id: HashMap<String, watch::Sender<Watch>>,
+    /// IDs of pending watches by entity
+    by_entity: HashMap<String, Vec<String>>,
+    /// Webhook signing secrets by watch ID
+    secrets: HashMap<String, SealedSecret>,
+}
+
+impl Watches {
+    fn unindex(&mut self, entity_id: &str, id: &str) {
+        if let Some(ids) = self.by_entity.get_mut(entity_id) {
+            ids.retain(|other| other != id);
+            if ids.is_empty() {
+                self.by_entity.remove(entity_id);
+            }
+        }
+    }
+}
+
+/// Holds the watches of this instance and moves them through their states
+pub struct WatchManager {
+    watches: Mutex<Watches>,
+    max_watches: usize,
+    http_client: reqwest::Client,
+    /// Webhook hosts exempt from the internal address check
+    allowed_webhook_hosts: Arc<Vec<String>>,
+    /// Encrypts webhook secrets while they are held
+    secret_box: SecretBox,
+}
+
+impl Default for WatchManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WatchManager {
+    pub fn new() -> Self {
+        Self {
+            watches: Mutex::new(Watches::default()),
+            max_watches: DEFAULT_MAX_WATCHES,
+            http_client: HttpClientConfig::default().build().unwrap_or_default(),
+            allowed_webhook_hosts: Arc::new(Vec::new()),
+            secret_box: SecretBox::ephemeral(),
+        }
+    }
+
+    /// Sends webhooks with `http_client` (see [`crate::http_client`])
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Lets webhooks reach `hosts` even if they resolve to loopback,
+    /// link-local or private addresses (see [`crate::watch::target`])
+    pub fn with_allowed_webhook_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.allowed_webhook_hosts = Arc::new(hosts);
+        self
+    }
+
+    /// Caps the number of watches held, fired and expired ones included
+    pub fn with_max_watches(mut self, max_watches: usize) -> Self {
+        self.max_watches = max_watches;
+        self
+    }
+
+    /// Creates a pending watch
+    ///
+    /// A webhook URL must be http(s) and resolve to public addresses only
+    /// (see [`crate::watch::target`]).
+    pub async fn create(&self, request: NewWatch, now: DateTime<Utc>) -> Result<Watch, WatchError> {
+        if request.entity_id.is_empty() || request.property.is_empty() {
+            return Err(WatchError::Invalid(
+                "`entity_id` and `property` are required".to_string(),
+            ));
+        }
+        if let Some(url) = &request.webhook_url {
+            check_webhook_url(url, &self.allowed_webhook_hosts)
+                .await
+                .map_err(WatchError::Invalid)?;
+        }
+        let secret = match request.webhook_secret.as_deref() {
+            Some(_) if request.webhook_url.is_none() => {
+                return Err(WatchError::Invalid(
+                    "`webhook_secret` needs a `webhook_url`".to_string(),
+                ))
+            }
+            Some("") => {
+                return Err(WatchError::Invalid(
+                    "`webhook_secret` must not be empty".to_string(),
+                ))
+            }
+            Some(secret) => Some(
+                self.secret_box
+                    .seal(secret)
+                    .map_err(|e| WatchError::Invalid(e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let mut watches = self.watches.lock().unwrap();
+        if watches.by_id.len() >= self.max_watches {
+            return Err(WatchError::TooManyWatches);
+        }
+        let created = Watch::new(request, uuid::Uuid::now_v7().to_string(), now);
+        watches
+            .by_entity
+            .entry(created.entity_id.clone())
+            .or_default()
+            .push(created.id.clone());
+        if let Some(secret) = secret {
+            watches.secrets.insert(created.id.clone(), secret);
+        }
+        watches
+            .by_id
+            .insert(created.id.clone(), watch::Sender::new(created.clone()));
+        Ok(created)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Watch> {
+        let watches = self.watches.lock().unwrap();
+        watches.by_id.get(id).map(|sender| sender.borrow().clone())
+    }
+
+    /// Waits up to `hold` for watch `id` to change from the state
+    /// `poll_token` was handed out with (without one, to stop being pending)
+    pub async fn wait(&self, id: &str, poll_token: Option<&str>, hold: Duration) -> Option<Watch> {
+        let mut receiver = {
+            let watches = self.watches.lock().unwrap();
+            watches.by_id.get(id)?.subscribe()
+        };
+        let changed = |w: &Watch| match poll_token {
+            Some(token) => w.poll_token != token,
+            None => !w.is_pending(),
+        };
+        // A dropped watch ends the wait with its last state
+        let _ = tokio::time::timeout(hold, receiver.wait_for(changed)).await;
+        let current = receiver.borrow().clone();
+        Some(current)
+    }
+
+    /// Fires the watches `update` crosses; returns them
+    pub fn apply(&self, update: &EntityUpdate, now: DateTime<Utc>) -> Vec<Watch> {
+        let mut watches = self.watches.lock().unwrap();
+        let Some(ids) = watches.by_entity.get(&update.entity_id).cloned() else {
+            return Vec::new();
+        };
+        let mut fired = Vec::new();
+        for id in ids {
+            let Some(sender) = watches.by_id.get(&id) else {
+                continue;
+            };
+            let mut fired_watch = None;
+            sender.send_if_modified(|w| {
+                let change = update.changes.iter().find(|c| c.property == w.property);
+                let crossed = change.is_some_and(|c| {
+                    let new = (!c.removed).then_some(&c.new_value);
+                    w.observe(c.old_value.as_ref(), new, now)
+                });
+                if crossed {
+                    fired_watch = Some(w.clone());
+                }
+                crossed
+            });
+            if let Some(w) = fired_watch {
+                watches.unindex(&update.entity_id, &id);
+                fired.push(w);
+            }
+        }
+        fired
+    }
+
+    /// Expires pending watches past their expiry and drops finished ones
+    /// past retention
+    pub fn sweep(&self, now: DateTime<Utc>) {
+        let mut watches = self.watches.lock().unwrap();
+        let watches = &mut *watches;
+        let mut expired = Vec::new();
+        watches.by_id.retain(|id, sender| {
+            if sender.send_if_modified(|w| w.expire(now)) {
+                expired.push((sender.borrow().entity_id.clone(), id.clone()));
+            }
+            let keep = !sender.borrow().collectable(now);
+            if !keep {
+                watches.secrets.remove(id);
+            }
+            keep
+        });
+        for (entity_id, id) in expired {
+            debug!(watch = %id, entity_id = %entity_id, "Watch expired");
+            watches.unindex(&entity_id, &id);
+        }
+    }
+
+    /// Number of watches held
+    pub fn len(&self) -> usize {
+        self.watches.lock().unwrap().by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// POSTs a fired watch to its webhook in the background (one attempt),
+    /// signed if the watch has a secret. The URL is checked again first, in
+    /// case its host now resolves to an internal address.
+    fn deliver(&self, watch: Watch) {
+        let Some(url) = watch.webhook_url.clone() else {
+            return;
+        };
+        let secret = match self.watches.lock().unwrap().secrets.get(&watch.id) {
+            Some(sealed) => match self.secret_box.open(sealed) {
+                Ok(secret) => Some(secret),
+                Err(e) => {
+                    warn!(watch = %watch.id, error = %e, "Watch webhook secret unreadable, not delivering");
+                    return;
+                }
+            },
+            None => None,
+        };
+        let body = match serde_json::to_vec(&watch) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(watch = %watch.id, error = %e, "Failed to serialize watch");
+                return;
+            }
+        };
+        let request = signed_post(&self.http_client, &url, body, secret.as_deref());
+        let allowed_hosts = Arc::clone(&self.allowed_webhook_hosts);
+        tokio::spawn(async move {
+            if let Err(reason) = check_webhook_url(&url, &allowed_hosts).await {
+                warn!(watch = %watch.id, url = %url, reason = %reason, "Watch webhook target refused, not delivering");
+                return;
+            }
+            let result = request
+                .timeout(WEBHOOK_TIMEOUT)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                warn!(watch = %watch.id, url = %url, error = %e, "Watch webhook failed");
+            }
+        });
+    }
+}
+
+/// Runs `manager` against the engine's state updates: fires watches,
+/// delivers webhooks, expires and drops old watches. Runs until the
+/// engine's broadcast closes.
+pub async fn run_watches(manager: Arc<WatchManager>, state_engine: Arc<StateEngine>) {
+    let mut updates = state_engine.subscribe();
+    let mut sweep = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    for watch in manager.apply(&update, Utc::now()) {
+                        debug!(watch = %watch.id, entity_id = %watch.entity_id, "Watch fired");
+                        manager.deliver(watch);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Watch manager lagged, crossings in skipped updates are missed");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            _ = sweep.tick() => manager.sweep(Utc::now()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PropertyChange;
+    use crate::watch::{Predicate, WatchState, FINISHED_RETENTION_SECONDS};
+    use serde_json::{json, Value};
+
+    fn at(second: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(second, 0).unwrap()
+    }
+
+    fn new_watch(entity_id: &str, predicate: Predicate) -> NewWatch {
+        NewWatch {
+            entity_id: entity_id.to_string(),
+            property: "status".to_string(),
+            predicate,
+            expires_in_seconds: Some(100),
+            webhook_url: None,
+            webhook_secret: None,
+        }
+    }
+
+    fn update(entity_id: &str, property: &str, old: Option<Value>, new: Value) -> EntityUpdate {
+        EntityUpdate {
+            entity_id: entity_id.to_string(),
+            changes: vec![PropertyChange {
+                property: property.to_string(),
+                old_value: old,
+                new_value: new,
+                removed: false,
+            }],
+            timestamp: Utc::now(),
+            source: None,
+            entity_last_updated: None,
+            request_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_fires_matching_watches_once() {
+        let manager = WatchManager::new();
+        let done = manager
+            .create(
+                new_watch(
+                    "deploy/a",
+                    Predicate::Equals {
+                        value: json!("done"),
+                    },
+                ),
+                at(0),
+            )
+            .await
+            .unwrap();
+        let other = manager
+            .create(new_watch("deploy/b", Predicate::Changed), at(0))
+            .await
+            .unwrap();
+
+        // Other properties and entities leave it pending
+        assert!(manager
+            .apply(&update("deploy/a", "progress", None, json!("done")), at(1))
+            .is_empty());
+        assert!(manager
+            .apply(&update("deploy/c", "status", None, json!("done")), at(1))
+            .is_empty());
+
+        let fired = manager.apply(
+            &update("deploy/a", "status", Some(json!("running")), json!("done")),
+            at(2),
+        );
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id, done.id);
+        assert_eq!(manager.get(&done.id).unwrap(), fired[0]);
+        assert!(manager
+            .apply(
+                &update("deploy/a", "status", Some(json!("x")), json!("done")),
+                at(3)
+            )
+            .is_empty());
+        assert!(manager.get(&other.id).unwrap().is_pending());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_secret_is_sealed_and_dropped_with_watch() {
+        let manager = WatchManager::new();
+        let mut request = new_watch("deploy/a", Predicate::Changed);
+        request.webhook_url = Some("https://93.184.216.34/hook".to_string());
+        request.webhook_secret = Some("whsec".to_string());
+        let w = manager.create(request, at(0)).await.unwrap();
+        assert!(!serde_json::to_string(&w).unwrap().contains("whsec"));
+        {
+            let watches = manager.watches.lock().unwrap();
+            let sealed = &watches.secrets[&w.id];
+            assert_eq!(manager.secret_box.open(sealed).unwrap(), "whsec");
+        }
+
+        manager.sweep(at(100 + FINISHED_RETENTION_SECONDS));
+        assert!(manager.watches.lock().unwrap().secrets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_expires_then_drops() {
+        let manager = WatchManager::new();
+        let w = manager
+            .create(new_watch("deploy/a", Predicate::Changed), at(0))
+            .await
+            .unwrap();
+        manager.sweep(at(99));
+        assert!(manager.get(&w.id).unwrap().is_pending());
+
+        manager.sweep(at(100));
+        assert!(matches!(
+            manager.get(&w.id).unwrap().state,
+            WatchState::Expired { .. }
+        ));
+        assert!(manager
+            .apply(&update("deploy/a", "status", None, json!(1)), at(101))
+            .is_empty());
+
+        manager.sweep(at(100 + FINISHED_RETENTION_SECONDS));
+        assert!(manager.get(&w.id).is_none());
+        assert!(manager.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_validates_and_caps() {
+        let manager = WatchManager::new().with_max_watches(1);
+        let mut invalid = new_watch("deploy/a", Predicate::Changed);
+        invalid.webhook_url = Some("ftp://example.com/hook".to_string());
+        assert!(matches!(
+            manager.create(invalid, at(0)).await,
+            Err(WatchError::Invalid(_))
+        ));
+        assert!(matches!(
+            manager
+                .create(new_watch("", Predicate::Changed), at(0))
+                .await,
+            Err(WatchError::Invalid(_))
+        ));
+        let mut unsent_secret = new_watch("deploy/a", Predicate::Changed);
+        unsent_secret.webhook_secret = Some("whsec".to_string());
+        assert!(matches!(
+            manager.create(unsent_secret, at(0)).await,
+            Err(WatchError::Invalid(_))
+        ));
+
+        manager
+            .create(new_watch("deploy/a", Predicate::Changed), at(0))
+            .await
+            .unwrap();
+        assert_eq!(
+            manager
+                .create(new_watch("deploy/b", Predicate::Changed), at(0))
+                .await,
+            Err(WatchError::TooManyWatches)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_refuses_internal_webhooks() {
+        let manager = WatchManager::new();
+        for url in [
+            "http://127.0.0.1:9000/hook",
+            "http://169.254.169.254/latest",
+        ] {
+            let mut request = new_watch("deploy/a", Predicate::Changed);
+            request.webhook_url = Some(url.to_string());
+            let error = manager.create(request, at(0)).await.unwrap_err();
+            assert!(
+                matches!(&error, WatchError::Invalid(reason) if reason.contains("internal address")),
+                "{}: {:?}",
+                url,
+                error
+            );
+        }
+
+        let manager = WatchManager::new().with_allowed_webhook_hosts(vec!["127.0.0.1".to_string()]);
+        let mut request = new_watch("deploy/a", Predicate::Changed);
+        request.webhook_url = Some("http://127.0.0.1:9000/hook".to_string());
+        assert!(manager.create(request, at(0)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_rechecks_webhook_target() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut request = new_watch("deploy/a", Predicate::Changed);
+        request.webhook_url = Some(format!("http://{}/hook", listener.local_addr().unwrap()));
+        let watch = Watch::new(request, "w1".to_string(), Utc::now());
+        let accepted = || tokio::time::timeout(Duration::from_millis(300), listener.accept());
+
+        // Accepted when created (say before DNS changed), refused at delivery
+        WatchManager::new().deliver(watch.clone());
+        assert!(accepted().await.is_err(), "internal target was contacted");
+
+        WatchManager::new()
+            .with_allowed_webhook_hosts(vec!["127.0.0.1".to_string()])
+            .deliver(watch);
+        assert!(
+            accepted().await.is_ok(),
+            "allowlisted target was not contacted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_returns_on_change_or_hold() {
+        let manager = WatchManager::new();
+        let w = manager
+            .create(new_watch("deploy/a", Predicate::Changed), Utc::now())
+            .await
+            .unwrap();
+
+        let waited = manager.wait(&w.id, None, Duration::from_millis(10)).await;
+        assert!(waited.unwrap().is_pending());
+        assert!(manager
+            .wait("missing", None, Duration::from_millis(10))
+            .await
+            .is_none());
+
+        manager.apply(&update("deploy/a", "status", None, json!(1)), Utc::now());
+        // Fired: returns at once without a token, waits with the fired one
+        let fired = manager
+            .wait(&w.id, None, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert!(!fired.is_pending());
+        let again = manager
+            .wait(&w.id, Some(&fired.poll_token), Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(again, fired);
+        let resumed = manager
+            .wait(&w.id, Some(&w.poll_token), Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(resumed, fired);
+    }
+}
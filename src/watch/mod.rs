@@ -0,0 +1,368 @@
+//! One-shot entity watches.
+//!
+//! A watch waits for one property of one entity to satisfy a predicate,
+//! fires once and is then forgotten. Unlike WebSocket subscriptions it needs
+//! no open connection: a script creates one with `POST /api/watches` and
+//! long-polls `GET /api/watches/:id`, or has the firing POSTed to a webhook.
+//! Watches live in memory on the instance that created them.
+
+mod manager;
+pub mod target;
+
+pub use manager::{run_watches, WatchError, WatchManager, DEFAULT_MAX_WATCHES};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Expiry of a watch created without one
+pub const DEFAULT_WATCH_EXPIRY_SECONDS: u64 = 3600;
+
+/// Longest expiry a watch can be created with
+pub const MAX_WATCH_EXPIRY_SECONDS: u64 = 86_400;
+
+/// How long a fired or expired watch can still be read before it is dropped
+pub const FINISHED_RETENTION_SECONDS: i64 = 300;
+
+/// When a watch fires, given a property's value before and after a change
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Predicate {
+    /// The value becomes equal to `value`
+    Equals {
+        #[schema(value_type = Object)]
+        value: Value,
+    },
+    /// The value changes at all (including being set or removed)
+    Changed,
+    /// The value becomes a number greater than `value`
+    Gt { value: f64 },
+    /// The value becomes a number less than `value`
+    Lt { value: f64 },
+}
+
+impl Predicate {
+    /// True if a property holding `value` (`None` = unset) satisfies the
+    /// predicate; `Changed` is about transitions and never holds
+    fn holds(&self, value: Option<&Value>) -> bool {
+        match (self, value) {
+            (Predicate::Equals { value: expected }, Some(value)) => value == expected,
+            (Predicate::Gt { value: bound }, Some(value)) => {
+                value.as_f64().is_some_and(|v| v > *bound)
+            }
+            (Predicate::Lt { value: bound }, Some(value)) => {
+                value.as_f64().is_some_and(|v| v < *bound)
+            }
+            _ => false,
+        }
+    }
+
+    /// True if going from `old` to `new` crosses the predicate.
+    ///
+    /// Edge-triggered: a value that already satisfied it and still does
+    /// (e.g. 80 → 90 for `gt 50`) doesn't fire again.
+    pub fn fires(&self, old: Option<&Value>, new: Option<&Value>) -> bool {
+        match self {
+            Predicate::Changed => old != new,
+            _ => !self.holds(old) && self.holds(new),
+        }
+    }
+}
+
+/// Where a watch is in its life. Pending is the only state it leaves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum WatchState {
+    Pending,
+    Fired {
+        fired_at: DateTime<Utc>,
+        /// Property value that fired the watch (null if it was removed)
+        #[schema(value_type = Object)]
+        value: Value,
+        #[schema(value_type = Object)]
+        previous_value: Option<Value>,
+    },
+    Expired {
+        expired_at: DateTime<Utc>,
+    },
+}
+
+/// A watch and its state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "id": "0193a3f2-6b1c-7000-8000-000000000000",
+    "entity_id": "deploy/backend",
+    "property": "status",
+    "predicate": {"op": "equals", "value": "done"},
+    "created_at": "2026-03-01T12:00:00Z",
+    "expires_at": "2026-03-01T13:00:00Z",
+    "state": "fired",
+    "fired_at": "2026-03-01T12:04:31Z",
+    "value": "done",
+    "previous_value": "running",
+    "poll_token": "1"
+}))]
+pub struct Watch {
+    pub id: String,
+    pub entity_id: String,
+    pub property: String,
+    pub predicate: Predicate,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// URL the firing is POSTed to, if not long-polled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    #[serde(flatten)]
+    pub state: WatchState,
+    /// Pass back to `GET /api/watches/:id` to wait for the next change of state
+    pub poll_token: String,
+}
+
+impl Watch {
+    fn new(request: NewWatch, id: String, now: DateTime<Utc>) -> Self {
+        let expires_in = request
+            .expires_in_seconds
+            .unwrap_or(DEFAULT_WATCH_EXPIRY_SECONDS)
+            .min(MAX_WATCH_EXPIRY_SECONDS);
+        Self {
+            id,
+            entity_id: request.entity_id,
+            property: request.property,
+            predicate: request.predicate,
+            created_at: now,
+            expires_at: now + Duration::seconds(expires_in as i64),
+            webhook_url: request.webhook_url,
+            state: WatchState::Pending,
+            poll_token: "0".to_string(),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.state == WatchState::Pending
+    }
+
+    /// Fires the watch if `old` → `new` crosses its predicate at `at`.
+    /// Returns true if it fired.
+    fn observe(&mut self, old: Option<&Value>, new: Option<&Value>, at: DateTime<Utc>) -> bool {
+        if !self.is_pending() || at >= self.expires_at || !self.predicate.fires(old, new) {
+            return false;
+        }
+        self.finish(WatchState::Fired {
+            fired_at: at,
+            value: new.cloned().unwrap_or(Value::Null),
+            previous_value: old.cloned(),
+        });
+        true
+    }
+
+    /// Expires the watch if it is still pending at `now`.
+    /// Returns true if it expired.
+    fn expire(&mut self, now: DateTime<Utc>) -> bool {
+        if !self.is_pending() || now < self.expires_at {
+            return false;
+        }
+        self.finish(WatchState::Expired {
+            expired_at: self.expires_at,
+        });
+        true
+    }
+
+    /// True once the watch has been finished for the retention period
+    fn collectable(&self, now: DateTime<Utc>) -> bool {
+        let finished_at = match &self.state {
+            WatchState::Pending => return false,
+            WatchState::Fired { fired_at, .. } => *fired_at,
+            WatchState::Expired { expired_at } => *expired_at,
+        };
+        now - finished_at >= Duration::seconds(FINISHED_RETENTION_SECONDS)
+    }
+
+    fn finish(&mut self, state: WatchState) {
+        self.state = state;
+        self.poll_token = "1".to_string();
+    }
+}
+
+/// Watch to create
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "entity_id": "deploy/backend",
+    "property": "status",
+    "predicate": {"op": "equals", "value": "done"},
+    "expires_in_seconds": 3600
+}))]
+pub struct NewWatch {
+    pub entity_id: String,
+    pub property: String,
+    pub predicate: Predicate,
+    /// Seconds until the watch expires unfired (default 3600, at most 86400)
+    #[serde(default)]
+    pub expires_in_seconds: Option<u64>,
+    /// http(s) URL to POST the watch to when it fires, instead of long-polling
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Secret the webhook POST is signed with (`X-Flux-Signature`); never
+    /// returned
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn at(second: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(second, 0).unwrap()
+    }
+
+    fn watch(predicate: Predicate) -> Watch {
+        Watch::new(
+            NewWatch {
+                entity_id: "deploy/backend".to_string(),
+                property: "status".to_string(),
+                predicate,
+                expires_in_seconds: Some(100),
+                webhook_url: None,
+                webhook_secret: None,
+            },
+            "w1".to_string(),
+            at(0),
+        )
+    }
+
+    #[test]
+    fn test_equals_fires_on_becoming_equal() {
+        let done = json!("done");
+        let p = Predicate::Equals {
+            value: done.clone(),
+        };
+        assert!(p.fires(Some(&json!("running")), Some(&done)));
+        assert!(p.fires(None, Some(&done)));
+        assert!(!p.fires(Some(&done), Some(&done)));
+        assert!(!p.fires(Some(&done), Some(&json!("running"))));
+        assert!(!p.fires(Some(&json!("running")), None));
+        // No coercion between types
+        assert!(!Predicate::Equals { value: json!(1) }.fires(None, Some(&json!("1"))));
+    }
+
+    #[test]
+    fn test_changed_fires_on_any_difference() {
+        let p = Predicate::Changed;
+        assert!(p.fires(Some(&json!(1)), Some(&json!(2))));
+        assert!(p.fires(None, Some(&json!(1))));
+        assert!(p.fires(Some(&json!(1)), None));
+        assert!(!p.fires(Some(&json!(1)), Some(&json!(1))));
+        assert!(!p.fires(None, None));
+    }
+
+    #[test]
+    fn test_thresholds_are_edge_triggered() {
+        let gt = Predicate::Gt { value: 50.0 };
+        assert!(gt.fires(Some(&json!(40)), Some(&json!(60))));
+        assert!(gt.fires(None, Some(&json!(50.5))));
+        assert!(!gt.fires(Some(&json!(80)), Some(&json!(90))));
+        assert!(!gt.fires(Some(&json!(40)), Some(&json!(50))));
+        assert!(gt.fires(Some(&json!("n/a")), Some(&json!(51))));
+        assert!(!gt.fires(Some(&json!(40)), Some(&json!("60"))));
+
+        let lt = Predicate::Lt { value: 0.0 };
+        assert!(lt.fires(Some(&json!(1)), Some(&json!(-1))));
+        assert!(!lt.fires(Some(&json!(-2)), Some(&json!(-1))));
+        assert!(!lt.fires(Some(&json!(1)), None));
+    }
+
+    #[test]
+    fn test_predicate_json() {
+        let parse = |v: Value| serde_json::from_value::<Predicate>(v);
+        assert_eq!(
+            parse(json!({"op": "equals", "value": "done"})).unwrap(),
+            Predicate::Equals {
+                value: json!("done")
+            }
+        );
+        assert_eq!(parse(json!({"op": "changed"})).unwrap(), Predicate::Changed);
+        assert_eq!(
+            parse(json!({"op": "gt", "value": 5})).unwrap(),
+            Predicate::Gt { value: 5.0 }
+        );
+        assert!(parse(json!({"op": "gt", "value": "5"})).is_err());
+        assert!(parse(json!({"op": "between"})).is_err());
+    }
+
+    #[test]
+    fn test_watch_fires_once() {
+        let mut w = watch(Predicate::Equals {
+            value: json!("done"),
+        });
+        assert!(w.is_pending());
+        assert_eq!(w.expires_at, at(100));
+        assert!(!w.observe(None, Some(&json!("running")), at(1)));
+        assert!(w.observe(Some(&json!("running")), Some(&json!("done")), at(2)));
+        assert_eq!(
+            w.state,
+            WatchState::Fired {
+                fired_at: at(2),
+                value: json!("done"),
+                previous_value: Some(json!("running")),
+            }
+        );
+        assert_eq!(w.poll_token, "1");
+
+        // Later crossings and the expiry don't change a fired watch
+        let fired = w.clone();
+        assert!(!w.observe(Some(&json!("failed")), Some(&json!("done")), at(3)));
+        assert!(!w.expire(at(200)));
+        assert_eq!(w, fired);
+    }
+
+    #[test]
+    fn test_watch_expires_unfired() {
+        let mut w = watch(Predicate::Changed);
+        assert!(!w.expire(at(99)));
+        assert!(w.is_pending());
+        // Changes at or after the expiry don't fire it
+        assert!(!w.observe(None, Some(&json!(1)), at(100)));
+        assert!(w.expire(at(150)));
+        assert_eq!(
+            w.state,
+            WatchState::Expired {
+                expired_at: at(100)
+            }
+        );
+        assert!(!w.expire(at(151)));
+        assert!(!w.observe(None, Some(&json!(2)), at(151)));
+    }
+
+    #[test]
+    fn test_finished_watches_are_kept_for_retention() {
+        let mut w = watch(Predicate::Changed);
+        assert!(!w.collectable(at(10_000)));
+
+        w.observe(None, Some(&json!(1)), at(10));
+        assert!(!w.collectable(at(10 + FINISHED_RETENTION_SECONDS - 1)));
+        assert!(w.collectable(at(10 + FINISHED_RETENTION_SECONDS)));
+
+        let mut w = watch(Predicate::Changed);
+        w.expire(at(100));
+        assert!(!w.collectable(at(100 + FINISHED_RETENTION_SECONDS - 1)));
+        assert!(w.collectable(at(100 + FINISHED_RETENTION_SECONDS)));
+    }
+
+    #[test]
+    fn test_watch_json() {
+        let mut w = watch(Predicate::Gt { value: 1.5 });
+        let json = serde_json::to_value(&w).unwrap();
+        assert_eq!(json["state"], "pending");
+        assert_eq!(json["predicate"], json!({"op": "gt", "value": 1.5}));
+        assert!(json.get("webhook_url").is_none());
+
+        w.observe(None, Some(&json!(2)), at(5));
+        let json = serde_json::to_value(&w).unwrap();
+        assert_eq!(json["state"], "fired");
+        assert_eq!(json["value"], 2);
+        assert_eq!(json["previous_value"], Value::Null);
+        assert_eq!(json["poll_token"], "1");
+    }
+}
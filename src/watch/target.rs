@@ -0,0 +1,153 @@
+//! Where watch webhooks may be delivered.
+//!
+//! Anyone who can create a watch picks its webhook URL, so without a check
+//! Flux could be made to POST to its own loopback interface, the cloud
+//! metadata endpoint (169.254.169.254) or hosts on the private network. The
+//! URL's host is resolved and every address it resolves to must be public,
+//! unless the host is on the operator's allowlist. The check runs when the
+//! watch is created and again right before delivery, as DNS may have changed
+//! in between.
+
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// True for addresses a webhook must not reach: unspecified, loopback,
+/// link-local, private (RFC 1918 and IPv6 unique local) and carrier-grade NAT
+pub fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_internal_v4(mapped),
+            None => is_internal_v6(ip),
+        },
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_private()
+        || ip.is_broadcast()
+        // 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_internal_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_unspecified()
+        || ip.is_loopback()
+        // fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // fc00::/7
+        || (first & 0xfe00) == 0xfc00
+}
+
+/// Checks that `url` is http(s) and its host resolves only to public
+/// addresses, or is in `allowed_hosts` (compared case-insensitively, without
+/// the port)
+pub async fn check_webhook_url(url: &str, allowed_hosts: &[String]) -> Result<(), String> {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => {
+            return Err(format!(
+                "Invalid webhook URL '{}' (must be http or https)",
+                url
+            ))
+        }
+    };
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Webhook URL '{}' has no host", url))?;
+    if allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+        return Ok(());
+    }
+
+    // IPv6 literals are bracketed in URLs
+    let bare = host.trim_start_matches('[').trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addresses: Vec<IpAddr> = match bare.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::net::lookup_host((bare, port))
+            .await
+            .map_err(|e| format!("Webhook host '{}' could not be resolved: {}", host, e))?
+            .map(|addr| addr.ip())
+            .collect(),
+    };
+    if addresses.is_empty() {
+        return Err(format!("Webhook host '{}' could not be resolved", host));
+    }
+    if let Some(ip) = addresses.into_iter().find(|ip| is_internal(*ip)) {
+        return Err(format!(
+            "Webhook host '{}' resolves to internal address {}",
+            host, ip
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "0.0.0.0",
+            "10.1.2.3",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(is_internal(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "93.184.216.34",
+            "172.32.0.1",
+            "100.128.0.1",
+            "2606:4700::1111",
+        ] {
+            assert!(!is_internal(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_webhook_url() {
+        assert!(check_webhook_url("https://93.184.216.34/hook", &[])
+            .await
+            .is_ok());
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5/hook",
+            "http://[::1]/hook",
+            "http://localhost:3000/hook",
+        ] {
+            let error = check_webhook_url(url, &[]).await.unwrap_err();
+            assert!(error.contains("internal address"), "{}: {}", url, error);
+        }
+        let error = check_webhook_url("file:///etc/passwd", &[])
+            .await
+            .unwrap_err();
+        assert!(error.contains("must be http or https"));
+
+        // Allowlisted hosts skip resolution
+        let allowed = vec!["LocalHost".to_string(), "hooks.internal".to_string()];
+        assert!(check_webhook_url("http://localhost:3000/hook", &allowed)
+            .await
+            .is_ok());
+        assert!(check_webhook_url("http://hooks.internal/x", &allowed)
+            .await
+            .is_ok());
+    }
+}
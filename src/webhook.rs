@@ -0,0 +1,328 @@
+//! Signatures on outbound deliveries (watch webhooks and federation
+//! batches), so receivers can check a request came from Flux and isn't a
+//! replay.
+//!
+//! # Algorithm
+//!
+//! A delivery to an endpoint with a secret carries
+//!
+//! ```text
+//! X-Flux-Signature: t=<unix seconds>,v1=<hex HMAC-SHA256>
+//! X-Flux-Delivery-Id: <UUIDv7, unique per request>
+//! ```
+//!
+//! where the HMAC is keyed with the secret's UTF-8 bytes and computed over
+//! `<t>.<body>`: the decimal timestamp, a period, then the raw request body.
+//! Receivers recompute it, compare in constant time, and reject timestamps
+//! outside a tolerance (see [`verify_signature`]). Several `v1` entries may
+//! be present; any match is accepted. Unknown keys are ignored.
+//!
+//! # Test vectors
+//!
+//! | secret            | t            | body                | v1 |
+//! |-------------------|--------------|---------------------|----|
+//! | `whsec_flux_test` | `1700000000` | `{"hello":"world"}` | `a55a6db4d2a679e15ab06195db8564c13117eae9817516ee8c05d4fdf9dfd6c9` |
+//! | `secret`          | `1`          | (empty)             | `8f0c4009f5a2110efea93e5f4061f011d119fe65e9c3563c251b9ff41825be79` |
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+use std::time::Duration;
+
+/// Header carrying the timestamp and signature
+pub const SIGNATURE_HEADER: &str = "X-Flux-Signature";
+
+/// Header carrying a unique ID per delivery request
+pub const DELIVERY_ID_HEADER: &str = "X-Flux-Delivery-Id";
+
+/// Default clock difference [`verify_signature`] callers should accept
+pub const DEFAULT_SIGNATURE_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Why a signature was rejected
+#[derive(Debug, PartialEq)]
+pub enum SignatureError {
+    /// The header has no `t` or no `v1`, or they don't parse
+    Malformed,
+    /// The timestamp is further from now than the tolerance
+    Expired { timestamp: i64 },
+    /// No `v1` matches the body and secret
+    Mismatch,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Malformed => write!(f, "Malformed {} header", SIGNATURE_HEADER),
+            SignatureError::Expired { timestamp } => {
+                write!(
+                    f,
+                    "Signature timestamp {} is outside the tolerance",
+                    timestamp
+                )
+            }
+            SignatureError::Mismatch => write!(f, "Signature doesn't match"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>` keyed with `secret`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    mac(secret, timestamp, body)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `X-Flux-Signature` value for `body` sent at `timestamp`
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, sign(secret, timestamp, body))
+}
+
+/// Check an `X-Flux-Signature` header against the raw request `body`
+///
+/// The timestamp may be up to `tolerance` before or after the local clock.
+/// Signatures are compared in constant time. Pass the body exactly as
+/// received, before any JSON parsing.
+pub fn verify_signature(
+    secret: &str,
+    header: &str,
+    body: &[u8],
+    tolerance: Duration,
+) -> Result<(), SignatureError> {
+    verify_signature_at(secret, header, body, tolerance, Utc::now().timestamp())
+}
+
+/// [`verify_signature`] with `now` as the current Unix time
+pub fn verify_signature_at(
+    secret: &str,
+    header: &str,
+    body: &[u8],
+    tolerance: Duration,
+    now: i64,
+) -> Result<(), SignatureError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => {
+                timestamp = Some(
+                    value
+                        .parse::<i64>()
+                        .map_err(|_| SignatureError::Malformed)?,
+                )
+            }
+            Some(("v1", value)) => signatures.push(decode_hex(value)),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or(SignatureError::Malformed)?;
+    if signatures.is_empty() {
+        return Err(SignatureError::Malformed);
+    }
+
+    if now.abs_diff(timestamp) > tolerance.as_secs() {
+        return Err(SignatureError::Expired { timestamp });
+    }
+
+    let expected = mac(secret, timestamp, body);
+    let matches = signatures
+        .iter()
+        .flatten()
+        .any(|signature| expected.clone().verify_slice(signature).is_ok());
+    if matches {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+/// Bytes of a hex string, or None if it isn't one
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// POST `body` (JSON) to `url` with a delivery ID, signed if there's a secret
+#[cfg(feature = "snapshot")]
+pub(crate) fn signed_post(
+    client: &reqwest::Client,
+    url: &str,
+    body: Vec<u8>,
+    secret: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let mut request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_ID_HEADER, uuid::Uuid::now_v7().to_string());
+    if let Some(secret) = secret {
+        let header = signature_header(secret, Utc::now().timestamp(), &body);
+        request = request.header(SIGNATURE_HEADER, header);
+    }
+    request.body(body)
+}
+
+/// Endpoint secrets held encrypted in memory, with the credential store's
+/// AES-256-GCM helpers
+#[cfg(feature = "credentials")]
+pub struct SecretBox {
+    key: Vec<u8>,
+}
+
+/// A secret sealed by a [`SecretBox`]
+#[cfg(feature = "credentials")]
+#[derive(Clone, Debug)]
+pub struct SealedSecret {
+    ciphertext: String,
+    nonce: String,
+}
+
+#[cfg(feature = "credentials")]
+impl SecretBox {
+    /// Box keyed with a base64 32-byte key (like `FLUX_ENCRYPTION_KEY`)
+    pub fn new(key_base64: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            key: crate::credentials::validate_key(key_base64)?,
+        })
+    }
+
+    /// Box with a random key, for secrets that don't outlive the process
+    pub fn ephemeral() -> Self {
+        Self {
+            key: rand::random::<[u8; 32]>().to_vec(),
+        }
+    }
+
+    pub fn seal(&self, secret: &str) -> anyhow::Result<SealedSecret> {
+        let (ciphertext, nonce) = crate::credentials::encrypt(secret, &self.key)?;
+        Ok(SealedSecret { ciphertext, nonce })
+    }
+
+    pub fn open(&self, sealed: &SealedSecret) -> anyhow::Result<String> {
+        crate::credentials::decrypt(&sealed.ciphertext, &sealed.nonce, &self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"hello":"world"}"#;
+    const TOLERANCE: Duration = DEFAULT_SIGNATURE_TOLERANCE;
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(
+            sign("whsec_flux_test", 1_700_000_000, BODY),
+            "a55a6db4d2a679e15ab06195db8564c13117eae9817516ee8c05d4fdf9dfd6c9"
+        );
+        assert_eq!(
+            sign("secret", 1, b""),
+            "8f0c4009f5a2110efea93e5f4061f011d119fe65e9c3563c251b9ff41825be79"
+        );
+        assert_eq!(
+            signature_header("secret", 1, b""),
+            "t=1,v1=8f0c4009f5a2110efea93e5f4061f011d119fe65e9c3563c251b9ff41825be79"
+        );
+
+        let header = signature_header("whsec_flux_test", 1_700_000_000, BODY);
+        assert_eq!(
+            verify_signature_at("whsec_flux_test", &header, BODY, TOLERANCE, 1_700_000_000),
+            Ok(())
+        );
+        assert!(verify_signature(
+            "s",
+            &signature_header("s", Utc::now().timestamp(), BODY),
+            BODY,
+            TOLERANCE
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let header = signature_header("whsec_flux_test", 1_000, BODY);
+        let verify = |now| verify_signature_at("whsec_flux_test", &header, BODY, TOLERANCE, now);
+        assert_eq!(verify(1_300), Ok(()));
+        assert_eq!(verify(700), Ok(()));
+        assert_eq!(
+            verify(1_301),
+            Err(SignatureError::Expired { timestamp: 1_000 })
+        );
+        assert_eq!(
+            verify(699),
+            Err(SignatureError::Expired { timestamp: 1_000 })
+        );
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let header = signature_header("whsec_flux_test", 1_000, BODY);
+        let verify = |secret, header: &str, body| {
+            verify_signature_at(secret, header, body, TOLERANCE, 1_000)
+        };
+        assert_eq!(
+            verify("whsec_flux_test", &header, br#"{"hello":"World"}"#),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify("other", &header, BODY),
+            Err(SignatureError::Mismatch)
+        );
+        // Replaying the signature under a new timestamp
+        let moved = header.replace("t=1000", "t=1001");
+        assert_eq!(
+            verify("whsec_flux_test", &moved, BODY),
+            Err(SignatureError::Mismatch)
+        );
+
+        // Any v1 may match (secret rotation); unknown keys are ignored
+        let rotated = format!(
+            "t=1000,v1={},v0=x,{}",
+            sign("old", 1_000, BODY),
+            &header[7..]
+        );
+        assert_eq!(verify("whsec_flux_test", &rotated, BODY), Ok(()));
+
+        for malformed in ["", "t=1000", "v1=00", "t=soon,v1=00"] {
+            assert_eq!(
+                verify("whsec_flux_test", malformed, BODY),
+                Err(SignatureError::Malformed),
+                "{:?}",
+                malformed
+            );
+        }
+        assert_eq!(
+            verify("whsec_flux_test", "t=1000,v1=not-hex", BODY),
+            Err(SignatureError::Mismatch)
+        );
+    }
+
+    #[cfg(feature = "credentials")]
+    #[test]
+    fn test_secret_box_round_trip() {
+        let secrets = SecretBox::ephemeral();
+        let sealed = secrets.seal("whsec_flux_test").unwrap();
+        assert_ne!(sealed.ciphertext, "whsec_flux_test");
+        assert_eq!(secrets.open(&sealed).unwrap(), "whsec_flux_test");
+        assert!(SecretBox::ephemeral().open(&sealed).is_err());
+    }
+}
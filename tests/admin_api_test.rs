@@ -1,5 +1,7 @@
 // Integration tests for GET/PUT /api/admin/config
 
+#![cfg(feature = "http-api")]
+
 use axum::{
     body::Body,
     http::{Request, StatusCode},
@@ -7,20 +9,29 @@ use axum::{
 };
 use flux::api::{create_admin_router, AdminAppState};
 use flux::config::{new_runtime_config, RuntimeConfig};
+use flux::supervisor::{RestartPolicy, TaskSupervisor};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceExt;
 
 fn create_test_app(admin_token: Option<&str>) -> Router {
     let state = AdminAppState {
         runtime_config: new_runtime_config(),
         admin_token: admin_token.map(|t| t.to_string()),
+        tasks: Arc::new(TaskSupervisor::new()),
     };
     create_admin_router(state)
 }
 
-fn create_test_app_with_config(runtime_config: flux::config::SharedRuntimeConfig, admin_token: Option<&str>) -> Router {
+fn create_test_app_with_config(
+    runtime_config: flux::config::SharedRuntimeConfig,
+    admin_token: Option<&str>,
+) -> Router {
     let state = AdminAppState {
         runtime_config,
         admin_token: admin_token.map(|t| t.to_string()),
+        tasks: Arc::new(TaskSupervisor::new()),
     };
     create_admin_router(state)
 }
@@ -199,3 +210,157 @@ async fn test_put_config_partial_update() {
         defaults.body_size_limit_batch_bytes
     );
 }
+
+/// PUT with an out-of-range value returns 422 naming the field and changes nothing.
+#[tokio::test]
+async fn test_put_config_invalid_value_returns_422() {
+    let shared = new_runtime_config();
+    let app = create_test_app_with_config(shared.clone(), Some("secret"));
+
+    let body = serde_json::json!({
+        "rate_limit_enabled": false,
+        "metrics_broadcast_interval_seconds": 0,
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/api/admin/config")
+                .header("Content-Type", "application/json")
+                .header("Authorization", bearer("secret"))
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let resp_body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let err: serde_json::Value = serde_json::from_slice(&resp_body).unwrap();
+    assert_eq!(err["field"], "metrics_broadcast_interval_seconds");
+
+    // Update is all-or-nothing
+    let stored = shared.read().unwrap();
+    assert!(stored.rate_limit_enabled);
+}
+
+/// PATCH behaves like PUT and GET reports the admin-api source for changed fields.
+#[tokio::test]
+async fn test_patch_config_updates_source() {
+    let shared = new_runtime_config();
+    let app = create_test_app_with_config(shared.clone(), None);
+
+    let body = serde_json::json!({ "snapshot_interval_minutes": 15 });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri("/api/admin/config")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/admin/config")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let resp_body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let cfg: serde_json::Value = serde_json::from_slice(&resp_body).unwrap();
+
+    assert_eq!(cfg["snapshot_interval_minutes"], 15);
+    assert_eq!(cfg["sources"]["snapshot_interval_minutes"], "admin-api");
+    assert!(cfg["sources"]["metrics_broadcast_interval_seconds"].is_string());
+}
+
+/// App whose supervisor runs one task, `counter`, that counts its runs
+fn create_test_app_with_task(admin_token: Option<&str>) -> (Router, Arc<AtomicUsize>) {
+    let runs = Arc::new(AtomicUsize::new(0));
+    let tasks = Arc::new(TaskSupervisor::new());
+    let counted = Arc::clone(&runs);
+    tasks.supervise("counter", RestartPolicy::default(), move || {
+        counted.fetch_add(1, Ordering::SeqCst);
+        std::future::pending::<()>()
+    });
+    let state = AdminAppState {
+        runtime_config: new_runtime_config(),
+        admin_token: admin_token.map(|t| t.to_string()),
+        tasks,
+    };
+    (create_admin_router(state), runs)
+}
+
+/// GET /api/admin/tasks lists supervised tasks.
+#[tokio::test]
+async fn test_list_tasks() {
+    let (app, _) = create_test_app_with_task(Some("secret"));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/admin/tasks")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let tasks: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(tasks[0]["name"], "counter");
+    assert_eq!(tasks[0]["status"], "running");
+    assert_eq!(tasks[0]["restarts"], 0);
+    assert!(tasks[0].get("last_panic").is_none());
+}
+
+/// POST /api/admin/tasks/:name/restart needs the admin token and a known task.
+#[tokio::test]
+async fn test_restart_task() {
+    let (app, runs) = create_test_app_with_task(Some("secret"));
+    let restart = |name: &str, token: Option<&str>| {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(format!("/api/admin/tasks/{}/restart", name));
+        if let Some(token) = token {
+            request = request.header("Authorization", bearer(token));
+        }
+        app.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    let response = restart("counter", None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = restart("counter", Some("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = restart("missing", Some("secret")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = restart("counter", Some("secret")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    for _ in 0..100 {
+        if runs.load(Ordering::SeqCst) == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
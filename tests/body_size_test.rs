@@ -23,10 +23,7 @@ struct BodySizeState {
     batch_limit: usize,
 }
 
-async fn test_single_handler(
-    State(s): State<BodySizeState>,
-    body: Bytes,
-) -> impl IntoResponse {
+async fn test_single_handler(State(s): State<BodySizeState>, body: Bytes) -> impl IntoResponse {
     if body.len() > s.single_limit {
         return (
             StatusCode::PAYLOAD_TOO_LARGE,
@@ -37,10 +34,7 @@ async fn test_single_handler(
     StatusCode::OK.into_response()
 }
 
-async fn test_batch_handler(
-    State(s): State<BodySizeState>,
-    body: Bytes,
-) -> impl IntoResponse {
+async fn test_batch_handler(State(s): State<BodySizeState>, body: Bytes) -> impl IntoResponse {
     if body.len() > s.batch_limit {
         return (
             StatusCode::PAYLOAD_TOO_LARGE,
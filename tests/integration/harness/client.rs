@@ -0,0 +1,268 @@
+//! HTTP and WebSocket client used by scenarios.
+
+use super::TIMEOUT;
+use futures::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+/// Thin wrapper over the Flux HTTP API
+#[derive(Clone)]
+pub struct TestClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl TestClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+            token: None,
+        }
+    }
+
+    /// Same client, sending `token` as a bearer token
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorized(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    /// Build a `{entity_id, properties}` event for the test stream
+    pub fn event(entity_id: &str, properties: Value) -> Value {
+        json!({
+            "stream": "e2e",
+            "source": "integration-test",
+            "timestamp": chrono::Utc::now().timestamp_millis(),
+            "payload": {"entity_id": entity_id, "properties": properties}
+        })
+    }
+
+    /// POST /api/events with a raw body
+    pub async fn post_event(&self, event: &Value) -> reqwest::Response {
+        self.authorized(self.http.post(self.url("/api/events")).json(event))
+            .send()
+            .await
+            .expect("POST /api/events")
+    }
+
+    /// POST /api/events with an `X-Request-Id` header
+    pub async fn post_event_with_request_id(
+        &self,
+        event: &Value,
+        request_id: &str,
+    ) -> reqwest::Response {
+        self.authorized(self.http.post(self.url("/api/events")).json(event))
+            .header("X-Request-Id", request_id)
+            .send()
+            .await
+            .expect("POST /api/events")
+    }
+
+    /// Publish property updates for one entity; panics unless accepted
+    pub async fn publish(&self, entity_id: &str, properties: Value) -> String {
+        let resp = self.post_event(&Self::event(entity_id, properties)).await;
+        assert_eq!(
+            resp.status(),
+            StatusCode::OK,
+            "publish {} rejected",
+            entity_id
+        );
+        let body: Value = resp.json().await.expect("publish response");
+        body["eventId"].as_str().expect("eventId").to_string()
+    }
+
+    /// POST /api/events/batch; returns the response body
+    pub async fn publish_batch(&self, events: Vec<Value>) -> Value {
+        let resp = self
+            .authorized(
+                self.http
+                    .post(self.url("/api/events/batch"))
+                    .json(&json!({"events": events})),
+            )
+            .send()
+            .await
+            .expect("POST /api/events/batch");
+        assert_eq!(resp.status(), StatusCode::OK, "batch rejected");
+        resp.json().await.expect("batch response")
+    }
+
+    /// GET /api/state/entities/:id, or `None` on 404
+    pub async fn get_entity(&self, entity_id: &str) -> Option<Value> {
+        let resp = self
+            .authorized(self.http.get(self.url(&format!(
+                "/api/state/entities/{}",
+                urlencoding::encode(entity_id)
+            ))))
+            .send()
+            .await
+            .expect("GET entity");
+        match resp.status() {
+            StatusCode::NOT_FOUND => None,
+            StatusCode::OK => Some(resp.json().await.expect("entity body")),
+            other => panic!("GET {} returned {}", entity_id, other),
+        }
+    }
+
+    /// Properties of an entity, or `None` if it does not exist
+    pub async fn properties(&self, entity_id: &str) -> Option<Map<String, Value>> {
+        self.get_entity(entity_id).await.map(|entity| {
+            entity["properties"]
+                .as_object()
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
+
+    /// GET /api/state/entities with query filters; returns entity IDs, sorted
+    pub async fn list_ids(&self, query: &[(&str, &str)]) -> Vec<String> {
+        let entities: Vec<Value> = self
+            .authorized(self.http.get(self.url("/api/state/entities")).query(query))
+            .send()
+            .await
+            .expect("GET entities")
+            .json()
+            .await
+            .expect("entities body");
+        let mut ids: Vec<String> = entities
+            .iter()
+            .filter_map(|e| e["id"].as_str().map(String::from))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// DELETE /api/state/entities/:id
+    pub async fn delete_entity(&self, entity_id: &str) -> reqwest::Response {
+        self.authorized(self.http.delete(self.url(&format!(
+            "/api/state/entities/{}",
+            urlencoding::encode(entity_id)
+        ))))
+        .send()
+        .await
+        .expect("DELETE entity")
+    }
+
+    /// GET /api/events history for an entity
+    pub async fn history(&self, entity_id: &str) -> Vec<Value> {
+        let resp = self
+            .authorized(
+                self.http
+                    .get(self.url("/api/events"))
+                    .query(&[("entity", entity_id)]),
+            )
+            .send()
+            .await
+            .expect("GET /api/events");
+        assert_eq!(resp.status(), StatusCode::OK);
+        resp.json().await.expect("history body")
+    }
+
+    /// POST /api/namespaces; returns the namespace token
+    pub async fn register_namespace(&self, name: &str) -> String {
+        let resp = self
+            .authorized(
+                self.http
+                    .post(self.url("/api/namespaces"))
+                    .json(&json!({"name": name})),
+            )
+            .send()
+            .await
+            .expect("POST /api/namespaces");
+        assert_eq!(resp.status(), StatusCode::OK, "register {} rejected", name);
+        let body: Value = resp.json().await.expect("register body");
+        body["token"].as_str().expect("token").to_string()
+    }
+
+    /// Open a WebSocket and subscribe to `entity_ids` (`"*"` for all),
+    /// sending the client's token (if any) with each subscription
+    pub async fn subscribe(&self, entity_ids: &[&str]) -> WsSubscription {
+        let ws_url = self.url("/api/ws").replacen("http://", "ws://", 1);
+        let (mut ws, _) = connect_async(ws_url.as_str()).await.expect("WS connect");
+        for entity_id in entity_ids {
+            let mut msg = json!({"type": "subscribe", "entity_id": entity_id});
+            if let Some(token) = &self.token {
+                msg["token"] = json!(token);
+            }
+            ws.send(Message::Text(msg.to_string()))
+                .await
+                .expect("WS subscribe");
+        }
+        // The server subscribes to broadcasts after the upgrade completes;
+        // give it a moment so the first publish is not missed
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        WsSubscription { ws }
+    }
+}
+
+/// A live WebSocket subscription
+pub struct WsSubscription {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl WsSubscription {
+    /// Next JSON message matching `predicate`, skipping others (e.g. metrics)
+    pub async fn next_matching(&mut self, what: &str, predicate: impl Fn(&Value) -> bool) -> Value {
+        let wait = async {
+            while let Some(msg) = self.ws.next().await {
+                if let Message::Text(text) = msg.expect("WS read") {
+                    let value: Value = serde_json::from_str(&text).expect("WS JSON");
+                    if predicate(&value) {
+                        return value;
+                    }
+                }
+            }
+            panic!("WebSocket closed while waiting for {}", what);
+        };
+        tokio::time::timeout(TIMEOUT, wait)
+            .await
+            .unwrap_or_else(|_| panic!("timed out waiting for {}", what))
+    }
+
+    /// Next property changes for `entity_id`, from either update message shape
+    pub async fn next_update(&mut self, entity_id: &str) -> Map<String, Value> {
+        let msg = self
+            .next_matching(&format!("update for {}", entity_id), |m| {
+                m["entity_id"] == entity_id
+                    && (m["type"] == "state_update" || m["type"] == "state_update_batch")
+            })
+            .await;
+
+        let mut changes = Map::new();
+        if msg["type"] == "state_update" {
+            changes.insert(
+                msg["property"].as_str().unwrap().to_string(),
+                msg["value"].clone(),
+            );
+        } else {
+            for change in msg["changes"].as_array().unwrap() {
+                changes.insert(
+                    change["property"].as_str().unwrap().to_string(),
+                    change["value"].clone(),
+                );
+            }
+        }
+        changes
+    }
+
+    /// Wait for the `entity_deleted` notification for `entity_id`
+    pub async fn next_deletion(&mut self, entity_id: &str) {
+        self.next_matching(&format!("deletion of {}", entity_id), |m| {
+            m["type"] == "entity_deleted" && m["entity_id"] == entity_id
+        })
+        .await;
+    }
+}
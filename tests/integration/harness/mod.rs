@@ -0,0 +1,358 @@
+//! Shared fixtures for end-to-end scenarios.
+//!
+//! `spawn_flux()` starts NATS, the state engine subscriber and the HTTP/WS
+//! router stack on a random port with a temp snapshot directory. Scenarios
+//! talk to it through [`TestClient`] and can restart the engine with or
+//! without a snapshot to exercise recovery.
+
+// Not every scenario uses every helper
+#![allow(dead_code)]
+
+mod client;
+mod nats;
+
+pub use client::TestClient;
+pub use nats::NatsFixture;
+
+use axum::Router;
+use chrono::Utc;
+use flux::api::{
+    create_admin_router, create_connector_router, create_deletion_router, create_history_router,
+    create_namespace_router, create_openapi_router, create_query_router, create_router,
+    create_ws_router, with_request_tracing, AdminAppState, AppState, ConnectorAppState,
+    DeletionAppState, HistoryAppState, NamespaceAppState, QueryAppState, QueryCache, WsAppState,
+};
+use flux::config::{new_runtime_config, SharedRuntimeConfig};
+use flux::namespace::NamespaceRegistry;
+use flux::nats::{EventPublisher, NatsClient, NatsConfig};
+use flux::rate_limit::RateLimiter;
+use flux::snapshot::{recovery, LocalSnapshotStore, Snapshot};
+use flux::state::StateEngine;
+use flux::subscription::{ConnectionTracker, DEFAULT_MAX_VALUE_BYTES};
+use flux::supervisor::TaskSupervisor;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+
+/// How long fixtures wait for asynchronous effects before failing
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const CONSUMER_NAME: &str = "flux-state-engine";
+
+/// Knobs for the booted Flux instance
+#[derive(Clone, Debug, Default)]
+pub struct FluxOptions {
+    pub auth_enabled: bool,
+    pub admin_token: Option<String>,
+}
+
+/// A running Flux stack backed by a real NATS server
+pub struct TestFlux {
+    pub nats: NatsFixture,
+    pub base_url: String,
+    pub state_engine: Arc<StateEngine>,
+    pub runtime_config: SharedRuntimeConfig,
+    jetstream: async_nats::jetstream::Context,
+    options: FluxOptions,
+    snapshot_dir: TempDir,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+/// Start NATS and Flux with default options (auth disabled)
+pub async fn spawn_flux() -> TestFlux {
+    spawn_flux_with(FluxOptions::default()).await
+}
+
+/// Start NATS and Flux with `options`
+pub async fn spawn_flux_with(options: FluxOptions) -> TestFlux {
+    let nats = NatsFixture::start().await;
+    let snapshot_dir = tempfile::tempdir().expect("create snapshot dir");
+    let (jetstream, state_engine, runtime_config, base_url, tasks) =
+        boot(&nats.url, &options, snapshot_dir.path()).await;
+
+    TestFlux {
+        nats,
+        base_url,
+        state_engine,
+        runtime_config,
+        jetstream,
+        options,
+        snapshot_dir,
+        tasks,
+    }
+}
+
+impl TestFlux {
+    /// Client without a token
+    pub fn client(&self) -> TestClient {
+        TestClient::new(&self.base_url)
+    }
+
+    /// Wait until every event in the stream has been applied and acked
+    pub async fn wait_processed(&self) {
+        let deadline = tokio::time::Instant::now() + TIMEOUT;
+        loop {
+            let mut stream = self
+                .jetstream
+                .get_stream("FLUX_EVENTS")
+                .await
+                .expect("get FLUX_EVENTS stream");
+            let last = stream
+                .info()
+                .await
+                .expect("stream info")
+                .state
+                .last_sequence;
+            let processed = self.state_engine.get_last_processed_sequence() >= last;
+            let acked = stream
+                .consumer_info(CONSUMER_NAME)
+                .await
+                .map(|info| info.num_ack_pending == 0)
+                .unwrap_or(last == 0);
+
+            if processed && acked {
+                return;
+            }
+            if tokio::time::Instant::now() > deadline {
+                panic!(
+                    "state engine stuck at sequence {} (stream at {})",
+                    self.state_engine.get_last_processed_sequence(),
+                    last
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Write a snapshot of the current state, as the snapshot manager would.
+    ///
+    /// Returns the NATS sequence the snapshot covers.
+    pub async fn take_snapshot(&self) -> u64 {
+        self.wait_processed().await;
+        let seq = self.state_engine.get_last_processed_sequence();
+        let filename = format!(
+            "snapshot-{}-seq{}.json.gz",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            seq
+        );
+        Snapshot::save_state_engine(
+            &self.state_engine,
+            seq,
+            None,
+            &self.snapshot_dir.path().join(filename),
+        )
+        .expect("save snapshot");
+        seq
+    }
+
+    /// Delete all snapshots so the next restart replays the full stream
+    pub fn clear_snapshots(&self) {
+        for entry in std::fs::read_dir(self.snapshot_dir.path()).expect("read snapshot dir") {
+            std::fs::remove_file(entry.expect("snapshot entry").path()).expect("remove snapshot");
+        }
+    }
+
+    /// Stop the engine and server, then boot a fresh engine on the same NATS
+    /// server. Recovers from the newest snapshot if one exists, otherwise
+    /// replays the whole stream (resetting the durable consumer).
+    pub async fn restart(&mut self) {
+        self.wait_processed().await;
+        self.shutdown().await;
+
+        let (jetstream, state_engine, runtime_config, base_url, tasks) =
+            boot(&self.nats.url, &self.options, self.snapshot_dir.path()).await;
+        self.jetstream = jetstream;
+        self.state_engine = state_engine;
+        self.runtime_config = runtime_config;
+        self.base_url = base_url;
+        self.tasks = tasks;
+    }
+
+    async fn shutdown(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for TestFlux {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+type Booted = (
+    async_nats::jetstream::Context,
+    Arc<StateEngine>,
+    SharedRuntimeConfig,
+    String,
+    Vec<JoinHandle<()>>,
+);
+
+/// Start the subscriber and HTTP server; waits until replay has finished
+async fn boot(nats_url: &str, options: &FluxOptions, snapshot_dir: &Path) -> Booted {
+    let nats_client = NatsClient::connect(NatsConfig {
+        url: nats_url.to_string(),
+        ..NatsConfig::default()
+    })
+    .await
+    .expect("connect to NATS");
+    let jetstream = nats_client.jetstream().clone();
+    let state_engine = Arc::new(StateEngine::new());
+
+    let snapshots = LocalSnapshotStore::new(snapshot_dir);
+    let start_sequence = match recovery::load_latest_snapshot(&snapshots)
+        .await
+        .expect("load snapshot")
+    {
+        Some((mut snapshot, seq)) => {
+            let trash = std::mem::take(&mut snapshot.trash);
+            let messages = std::mem::take(&mut snapshot.messages);
+            let quotas = std::mem::take(&mut snapshot.quotas);
+            state_engine.load_from_snapshot(snapshot.to_hashmap(), seq);
+            state_engine.load_trash(trash);
+            state_engine.load_messages(messages);
+            state_engine.load_quotas(quotas);
+            Some(seq)
+        }
+        None => None,
+    };
+
+    let mut tasks = Vec::new();
+    let engine = Arc::clone(&state_engine);
+    let subscriber_jetstream = jetstream.clone();
+    tasks.push(tokio::spawn(async move {
+        engine
+            .run_subscriber(subscriber_jetstream, start_sequence)
+            .await;
+    }));
+
+    let runtime_config = new_runtime_config();
+    let app = build_app(
+        &jetstream,
+        Arc::clone(&state_engine),
+        Arc::clone(&runtime_config),
+        options,
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind test server");
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tasks.push(tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .expect("test server");
+    }));
+
+    eventually("state engine to finish replay", || {
+        let engine = Arc::clone(&state_engine);
+        async move { engine.is_live().then_some(()) }
+    })
+    .await;
+
+    (jetstream, state_engine, runtime_config, base_url, tasks)
+}
+
+/// Router stack as assembled in main.rs (without OAuth, which needs credentials)
+fn build_app(
+    jetstream: &async_nats::jetstream::Context,
+    state_engine: Arc<StateEngine>,
+    runtime_config: SharedRuntimeConfig,
+    options: &FluxOptions,
+) -> Router {
+    let event_publisher =
+        EventPublisher::new(jetstream.clone()).with_metrics(state_engine.metrics.clone());
+    let namespace_registry = Arc::new(NamespaceRegistry::new());
+
+    let ingestion_state = AppState {
+        event_publisher: event_publisher.clone(),
+        namespace_registry: Arc::clone(&namespace_registry),
+        auth_enabled: options.auth_enabled,
+        admin_token: options.admin_token.clone(),
+        runtime_config: Arc::clone(&runtime_config),
+        rate_limiter: Arc::new(RateLimiter::new()),
+    };
+
+    let app = create_router(ingestion_state)
+        .merge(create_namespace_router(NamespaceAppState {
+            event_publisher: event_publisher.clone(),
+            namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::clone(&state_engine),
+            auth_enabled: options.auth_enabled,
+            admin_token: options.admin_token.clone(),
+            idempotency: None,
+        }))
+        .merge(create_deletion_router(DeletionAppState {
+            event_publisher,
+            namespace_registry: Arc::clone(&namespace_registry),
+            state_engine: Arc::clone(&state_engine),
+            auth_enabled: options.auth_enabled,
+            admin_token: options.admin_token.clone(),
+            max_batch_delete: 10_000,
+        }))
+        .merge(create_ws_router(Arc::new(WsAppState {
+            state_engine: Arc::clone(&state_engine),
+            namespace_registry: Arc::clone(&namespace_registry),
+            auth_enabled: options.auth_enabled,
+            admin_token: options.admin_token.clone(),
+            max_value_bytes: DEFAULT_MAX_VALUE_BYTES,
+            runtime_config: Arc::clone(&runtime_config),
+            connections: Arc::new(ConnectionTracker::new()),
+        })))
+        .merge(create_query_router(Arc::new(QueryAppState {
+            state_engine,
+            namespace_registry: Arc::clone(&namespace_registry),
+            auth_enabled: options.auth_enabled,
+            admin_token: options.admin_token.clone(),
+            response_cache: QueryCache::new().with_runtime_config(Arc::clone(&runtime_config)),
+            journal: None,
+        })))
+        .merge(create_history_router(Arc::new(HistoryAppState {
+            jetstream: jetstream.clone(),
+            namespace_registry: Arc::clone(&namespace_registry),
+            auth_enabled: options.auth_enabled,
+            admin_token: options.admin_token.clone(),
+            archive: None,
+        })))
+        .merge(create_connector_router(ConnectorAppState {
+            credential_store: None,
+            namespace_registry,
+            auth_enabled: options.auth_enabled,
+        }))
+        .merge(create_admin_router(AdminAppState {
+            runtime_config,
+            admin_token: options.admin_token.clone(),
+            tasks: Arc::new(TaskSupervisor::new()),
+        }))
+        .merge(create_openapi_router(false));
+    with_request_tracing(app)
+}
+
+/// Poll `check` until it returns `Some`, panicking after [`TIMEOUT`]
+pub async fn eventually<T, F, Fut>(what: &str, mut check: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<T>>,
+{
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    loop {
+        if let Some(value) = check().await {
+            return value;
+        }
+        if tokio::time::Instant::now() > deadline {
+            panic!("timed out waiting for {}", what);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,135 @@
+//! NATS server fixture for end-to-end tests.
+//!
+//! Backends, in order of preference:
+//! 1. `FLUX_TEST_NATS_URL` — an already-running JetStream server. Tests are
+//!    serialized and the FLUX_EVENTS stream is wiped before each one.
+//! 2. A `nats-server` binary on `PATH`, started per test on a free port.
+//! 3. A `nats` Docker container via testcontainers.
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tempfile::TempDir;
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+use tokio::process::{Child, Command};
+use tokio::sync::{Mutex, MutexGuard};
+
+const NATS_IMAGE: &str = "nats";
+const NATS_TAG: &str = "2.10-alpine";
+const NATS_PORT: u16 = 4222;
+
+/// Serializes tests sharing an external server
+static EXTERNAL_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Keeps the server alive for the lifetime of the fixture
+enum Backend {
+    External { _guard: MutexGuard<'static, ()> },
+    Process { _child: Child, _data_dir: TempDir },
+    Container(Box<ContainerAsync<GenericImage>>),
+}
+
+/// A JetStream-enabled NATS server for one test
+pub struct NatsFixture {
+    pub url: String,
+    _backend: Backend,
+}
+
+impl NatsFixture {
+    pub async fn start() -> Self {
+        if let Ok(url) = std::env::var("FLUX_TEST_NATS_URL") {
+            return Self::external(url).await;
+        }
+        if let Some(binary) = find_on_path("nats-server") {
+            return Self::process(binary).await;
+        }
+        Self::container().await
+    }
+
+    async fn external(url: String) -> Self {
+        let guard = EXTERNAL_LOCK.lock().await;
+
+        // Start from an empty stream; deleting it also drops its consumers
+        let client = connect_with_retry(&url).await;
+        let jetstream = async_nats::jetstream::new(client);
+        let _ = jetstream.delete_stream("FLUX_EVENTS").await;
+
+        Self {
+            url,
+            _backend: Backend::External { _guard: guard },
+        }
+    }
+
+    async fn process(binary: PathBuf) -> Self {
+        let port = free_port();
+        let data_dir = tempfile::tempdir().expect("create NATS data dir");
+        let child = Command::new(binary)
+            .args(["-js", "-a", "127.0.0.1", "-p", &port.to_string(), "-sd"])
+            .arg(data_dir.path())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("spawn nats-server");
+
+        let url = format!("nats://127.0.0.1:{}", port);
+        connect_with_retry(&url).await;
+
+        Self {
+            url,
+            _backend: Backend::Process {
+                _child: child,
+                _data_dir: data_dir,
+            },
+        }
+    }
+
+    async fn container() -> Self {
+        let container = GenericImage::new(NATS_IMAGE, NATS_TAG)
+            .with_exposed_port(NATS_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stderr("Server is ready"))
+            .with_cmd(["-js"])
+            .start()
+            .await
+            .expect("start NATS container (is Docker running? or set FLUX_TEST_NATS_URL)");
+
+        let host = container.get_host().await.expect("container host");
+        let port = container
+            .get_host_port_ipv4(NATS_PORT)
+            .await
+            .expect("container port");
+        let url = format!("nats://{}:{}", host, port);
+        connect_with_retry(&url).await;
+
+        Self {
+            url,
+            _backend: Backend::Container(Box::new(container)),
+        }
+    }
+}
+
+async fn connect_with_retry(url: &str) -> async_nats::Client {
+    for _ in 0..50 {
+        if let Ok(client) = async_nats::connect(url).await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("NATS at {} did not become reachable", url);
+}
+
+fn find_on_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .map(|addr| addr.port())
+        .expect("find free port")
+}
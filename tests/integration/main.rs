@@ -0,0 +1,12 @@
+// End-to-end tests: HTTP → NATS → state engine → query API / WebSocket → snapshot
+//
+// Needs a JetStream-enabled NATS server, so these are behind a feature:
+//
+//   cargo test --features integration-tests --test integration
+//
+// NATS comes from FLUX_TEST_NATS_URL, a `nats-server` binary on PATH, or
+// Docker (testcontainers), in that order. See harness/nats.rs.
+
+mod harness;
+mod pipeline;
+mod recovery;
@@ -0,0 +1,321 @@
+// Live pipeline scenarios: publish over HTTP, observe via query API and WS
+
+use crate::harness::{eventually, spawn_flux, spawn_flux_with, FluxOptions, TestClient};
+use flux::config::RuntimeConfigUpdate;
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_publish_reaches_query_api_and_websocket() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+    let mut ws = client.subscribe(&["e2e/sensor-01"]).await;
+
+    client
+        .publish(
+            "e2e/sensor-01",
+            json!({"temperature": 21.5, "unit": "celsius"}),
+        )
+        .await;
+
+    let changes = ws.next_update("e2e/sensor-01").await;
+    assert_eq!(changes["temperature"], json!(21.5));
+    assert_eq!(changes["unit"], json!("celsius"));
+
+    let props = client
+        .properties("e2e/sensor-01")
+        .await
+        .expect("entity exists");
+    assert_eq!(props["temperature"], json!(21.5));
+
+    // Later updates merge into existing state
+    client
+        .publish("e2e/sensor-01", json!({"temperature": 22.0}))
+        .await;
+    let changes = ws.next_update("e2e/sensor-01").await;
+    assert_eq!(changes["temperature"], json!(22.0));
+
+    let props = client.properties("e2e/sensor-01").await.unwrap();
+    assert_eq!(props["temperature"], json!(22.0));
+    assert_eq!(props["unit"], json!("celsius"));
+}
+
+#[tokio::test]
+async fn test_request_id_reaches_websocket_update() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+    let mut ws = client.subscribe(&["e2e/sensor-02"]).await;
+
+    let event = TestClient::event("e2e/sensor-02", json!({"temperature": 19.0}));
+    let resp = client.post_event_with_request_id(&event, "trace-42").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["x-request-id"], "trace-42");
+
+    let msg = ws
+        .next_matching("update for e2e/sensor-02", |m| {
+            m["entity_id"] == "e2e/sensor-02"
+                && (m["type"] == "state_update" || m["type"] == "state_update_batch")
+        })
+        .await;
+    assert_eq!(msg["request_id"], json!("trace-42"));
+}
+
+#[tokio::test]
+async fn test_batch_publish_and_delete() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+    let mut ws = client.subscribe(&["*"]).await;
+
+    let resp = client
+        .publish_batch(vec![
+            TestClient::event("fleet/truck-1", json!({"speed": 50})),
+            TestClient::event("fleet/truck-2", json!({"speed": 60})),
+            TestClient::event("other/thing", json!({"on": true})),
+        ])
+        .await;
+    assert_eq!(resp["successful"], 3);
+
+    flux.wait_processed().await;
+    assert_eq!(
+        client.list_ids(&[("namespace", "fleet")]).await,
+        vec!["fleet/truck-1", "fleet/truck-2"]
+    );
+
+    let resp = client.delete_entity("fleet/truck-1").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    ws.next_deletion("fleet/truck-1").await;
+
+    eventually("fleet/truck-1 to disappear", || async {
+        client
+            .get_entity("fleet/truck-1")
+            .await
+            .is_none()
+            .then_some(())
+    })
+    .await;
+    assert_eq!(
+        client.list_ids(&[("prefix", "fleet/")]).await,
+        vec!["fleet/truck-2"]
+    );
+}
+
+#[tokio::test]
+async fn test_history_reads_events_back_from_jetstream() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+
+    client.publish("e2e/history", json!({"step": 1})).await;
+    client.publish("e2e/history", json!({"step": 2})).await;
+    client.publish("e2e/unrelated", json!({"step": 99})).await;
+    flux.wait_processed().await;
+
+    let events = client.history("e2e/history").await;
+    assert_eq!(events.len(), 2);
+    // Newest first
+    assert_eq!(events[0]["payload"]["properties"]["step"], json!(2));
+    assert_eq!(events[1]["payload"]["properties"]["step"], json!(1));
+}
+
+#[tokio::test]
+async fn test_auth_scopes_writes_to_token_namespace() {
+    let flux = spawn_flux_with(FluxOptions {
+        auth_enabled: true,
+        ..FluxOptions::default()
+    })
+    .await;
+    let token = flux.client().register_namespace("alpha").await;
+    let client = flux.client().with_token(&token);
+
+    client.publish("alpha/sensor", json!({"ok": true})).await;
+
+    let resp = client
+        .post_event(&TestClient::event("bravo/sensor", json!({"ok": false})))
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = flux
+        .client()
+        .post_event(&TestClient::event("alpha/sensor", json!({"ok": false})))
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    flux.wait_processed().await;
+    assert_eq!(
+        client.properties("alpha/sensor").await.unwrap()["ok"],
+        json!(true)
+    );
+    assert!(flux.state_engine.get_entity("bravo/sensor").is_none());
+}
+
+#[tokio::test]
+async fn test_queries_scoped_to_token_namespace() {
+    let flux = spawn_flux_with(FluxOptions {
+        auth_enabled: true,
+        ..FluxOptions::default()
+    })
+    .await;
+    let alpha_token = flux.client().register_namespace("alpha").await;
+    let alpha = flux.client().with_token(&alpha_token);
+    let bravo = flux
+        .client()
+        .with_token(&flux.client().register_namespace("bravo").await);
+
+    alpha.publish("alpha/sensor", json!({"v": 1})).await;
+    bravo.publish("bravo/secret", json!({"v": 2})).await;
+    flux.wait_processed().await;
+
+    // Listings only contain the caller's namespace, even when asking for another
+    assert_eq!(alpha.list_ids(&[]).await, vec!["alpha/sensor"]);
+    assert!(alpha.list_ids(&[("namespace", "bravo")]).await.is_empty());
+    assert_eq!(bravo.list_ids(&[]).await, vec!["bravo/secret"]);
+
+    // Lookups by ID: no token is 401, another namespace's token is 403
+    let url = format!(
+        "{}/api/state/entities/{}",
+        flux.base_url,
+        urlencoding::encode("bravo/secret")
+    );
+    let http = reqwest::Client::new();
+    let resp = http.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let resp = http
+        .get(&url)
+        .bearer_auth(&alpha_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Same for history
+    let resp = http
+        .get(format!("{}/api/events", flux.base_url))
+        .query(&[("entity", "bravo/secret")])
+        .bearer_auth(&alpha_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(bravo.history("bravo/secret").await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_websocket_scoped_to_token_namespace() {
+    let flux = spawn_flux_with(FluxOptions {
+        auth_enabled: true,
+        ..FluxOptions::default()
+    })
+    .await;
+    let alpha = flux
+        .client()
+        .with_token(&flux.client().register_namespace("alpha").await);
+    let bravo = flux
+        .client()
+        .with_token(&flux.client().register_namespace("bravo").await);
+
+    // "*" means everything in alpha's namespace
+    let mut ws = alpha.subscribe(&["*"]).await;
+
+    bravo.publish("bravo/secret", json!({"v": 1})).await;
+    alpha.publish("alpha/sensor", json!({"v": 2})).await;
+
+    // bravo's update was published first; the first one delivered must be alpha's
+    let msg = ws
+        .next_matching("first state update", |m| {
+            m["type"] == "state_update" || m["type"] == "state_update_batch"
+        })
+        .await;
+    assert_eq!(msg["entity_id"], json!("alpha/sensor"));
+
+    // Subscribing to another namespace is refused
+    let mut ws = alpha.subscribe(&["bravo/secret"]).await;
+    let err = ws
+        .next_matching("subscription error", |m| m["type"] == "error")
+        .await;
+    assert!(err["error"].as_str().unwrap().contains("bravo/secret"));
+}
+
+#[tokio::test]
+async fn test_event_limits_enforced_and_overridable() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+
+    let too_many: serde_json::Map<String, serde_json::Value> =
+        (0..257).map(|i| (format!("p{}", i), json!(i))).collect();
+    let resp = client
+        .post_event(&TestClient::event("limits/wide", json!(too_many)))
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("max_properties_per_event"));
+
+    let big = "x".repeat(300 * 1024);
+    let resp = client
+        .post_event(&TestClient::event("limits/big", json!({"blob": big})))
+        .await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("max_payload_bytes"));
+
+    // Batches report the violation per event and publish the rest
+    let resp = client
+        .publish_batch(vec![
+            TestClient::event("limits/ok", json!({"v": 1})),
+            TestClient::event("limits/wide", json!(too_many)),
+        ])
+        .await;
+    assert_eq!(resp["successful"], 1);
+    assert_eq!(resp["failed"], 1);
+
+    // Loosen the limit at runtime; the same event is now accepted
+    flux.runtime_config
+        .apply_update(&RuntimeConfigUpdate {
+            max_properties_per_event: Some(512),
+            ..Default::default()
+        })
+        .unwrap();
+    client.publish("limits/wide", json!(too_many)).await;
+
+    flux.wait_processed().await;
+    assert_eq!(client.properties("limits/wide").await.unwrap().len(), 257);
+    assert!(client.get_entity("limits/big").await.is_none());
+}
+
+#[tokio::test]
+async fn test_timestamp_checks_and_received_at() {
+    let flux = spawn_flux().await;
+    let client = flux.client();
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let mut future = TestClient::event("clock/future", json!({"v": 1}));
+    future["timestamp"] = json!(now + 3_600_000);
+    let resp = client.post_event(&future).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("max_timestamp_skew_seconds"));
+
+    // Seconds are converted by default
+    let mut seconds = TestClient::event("clock/seconds", json!({"v": 1}));
+    seconds["timestamp"] = json!(now / 1000);
+    assert_eq!(client.post_event(&seconds).await.status(), StatusCode::OK);
+    flux.wait_processed().await;
+
+    let events = client.history("clock/seconds").await;
+    assert_eq!(events[0]["timestamp"], json!(now / 1000 * 1000));
+    assert!(events[0]["received_at"].as_i64().unwrap() >= now);
+
+    // ...or rejected when the policy says so
+    let update: RuntimeConfigUpdate =
+        serde_json::from_value(json!({"seconds_timestamp_policy": "reject"})).unwrap();
+    flux.runtime_config.apply_update(&update).unwrap();
+    let resp = client.post_event(&seconds).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
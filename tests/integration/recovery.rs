@@ -0,0 +1,141 @@
+// Restart scenarios: replay from NATS with and without a snapshot
+
+use crate::harness::spawn_flux;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_restart_without_snapshot_resets_consumer_and_replays_all() {
+    let mut flux = spawn_flux().await;
+    let client = flux.client();
+
+    client.publish("e2e/a", json!({"v": 1})).await;
+    client
+        .publish("e2e/a", json!({"v": 2, "label": "two"}))
+        .await;
+    client.publish("e2e/b", json!({"v": 10})).await;
+    client.publish("e2e/gone", json!({"v": 0})).await;
+    client.delete_entity("e2e/gone").await;
+    flux.wait_processed().await;
+
+    // The durable consumer has acked everything; without the reset a fresh
+    // engine would resume at the tail and come up empty.
+    flux.restart().await;
+    let client = flux.client();
+
+    let a = client.properties("e2e/a").await.expect("e2e/a replayed");
+    assert_eq!(a["v"], json!(2));
+    assert_eq!(a["label"], json!("two"));
+    assert_eq!(client.properties("e2e/b").await.unwrap()["v"], json!(10));
+    assert!(client.get_entity("e2e/gone").await.is_none());
+    assert_eq!(
+        client.list_ids(&[("prefix", "e2e/")]).await,
+        vec!["e2e/a", "e2e/b"]
+    );
+}
+
+#[tokio::test]
+async fn test_restart_from_snapshot_replays_only_the_tail() {
+    let mut flux = spawn_flux().await;
+    let client = flux.client();
+
+    client.publish("e2e/a", json!({"v": 1})).await;
+    client.publish("e2e/doomed", json!({"v": 1})).await;
+    let snapshot_seq = flux.take_snapshot().await;
+
+    // Changes after the snapshot must come from NATS replay
+    client.publish("e2e/a", json!({"v": 2})).await;
+    client.publish("e2e/b", json!({"v": 1})).await;
+    client.delete_entity("e2e/doomed").await;
+
+    flux.restart().await;
+    let client = flux.client();
+
+    assert!(flux.state_engine.get_last_processed_sequence() > snapshot_seq);
+    assert_eq!(client.properties("e2e/a").await.unwrap()["v"], json!(2));
+    assert_eq!(client.properties("e2e/b").await.unwrap()["v"], json!(1));
+    assert!(client.get_entity("e2e/doomed").await.is_none());
+}
+
+#[tokio::test]
+async fn test_restart_after_snapshot_removed_falls_back_to_full_replay() {
+    let mut flux = spawn_flux().await;
+    let client = flux.client();
+
+    client.publish("e2e/a", json!({"v": 1})).await;
+    flux.take_snapshot().await;
+    client.publish("e2e/a", json!({"v": 2})).await;
+
+    // Boot once from the snapshot so the durable consumer exists and is caught up
+    flux.restart().await;
+    flux.clear_snapshots();
+    flux.restart().await;
+    let client = flux.client();
+
+    assert_eq!(client.properties("e2e/a").await.unwrap()["v"], json!(2));
+    assert_eq!(client.list_ids(&[("prefix", "e2e/")]).await, vec!["e2e/a"]);
+}
+
+#[tokio::test]
+async fn test_live_updates_resume_after_restart() {
+    let mut flux = spawn_flux().await;
+    flux.client().publish("e2e/a", json!({"v": 1})).await;
+    flux.take_snapshot().await;
+    flux.restart().await;
+
+    let client = flux.client();
+    let mut ws = client.subscribe(&["e2e/a"]).await;
+    client.publish("e2e/a", json!({"v": 2})).await;
+
+    assert_eq!(ws.next_update("e2e/a").await["v"], json!(2));
+}
+
+#[tokio::test]
+async fn test_property_removal_survives_snapshot_and_replay() {
+    let mut flux = spawn_flux().await;
+    let client = flux.client();
+    let mut ws = client.subscribe(&["e2e/svc"]).await;
+
+    client
+        .publish(
+            "e2e/svc",
+            json!({"status": "error", "error_message": "timeout"}),
+        )
+        .await;
+    ws.next_update("e2e/svc").await;
+    client
+        .publish(
+            "e2e/svc",
+            json!({"status": "ok", "error_message": {"__unset__": true}}),
+        )
+        .await;
+
+    let msg = ws
+        .next_matching("removal of error_message", |m| {
+            m["type"] == "state_update_batch" && m["entity_id"] == "e2e/svc"
+        })
+        .await;
+    let removed = msg["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["property"] == "error_message")
+        .expect("error_message change");
+    assert_eq!(removed["removed"], json!(true));
+    assert!(removed["value"].is_null());
+
+    let props = client.properties("e2e/svc").await.unwrap();
+    assert!(!props.contains_key("error_message"));
+
+    // Snapshot reflects the removal
+    flux.take_snapshot().await;
+    flux.restart().await;
+    let props = flux.client().properties("e2e/svc").await.unwrap();
+    assert!(!props.contains_key("error_message"));
+
+    // Full replay reaches the same state
+    flux.clear_snapshots();
+    flux.restart().await;
+    let props = flux.client().properties("e2e/svc").await.unwrap();
+    assert_eq!(props["status"], json!("ok"));
+    assert!(!props.contains_key("error_message"));
+}
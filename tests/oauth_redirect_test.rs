@@ -0,0 +1,135 @@
+// Integration tests for OAuth return_to redirects
+
+#![cfg(feature = "oauth")]
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flux::api::{
+    create_oauth_router, parse_allowed_origins, OAuthAppState, ProviderRegistry, StateManager,
+};
+use flux::credentials::CredentialStore;
+use flux::namespace::NamespaceRegistry;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn create_test_app(state_manager: StateManager) -> Router {
+    let key = BASE64.encode([0u8; 32]);
+    let store = CredentialStore::new(":memory:", &key).unwrap();
+
+    let state = OAuthAppState {
+        credential_store: Arc::new(store),
+        namespace_registry: Arc::new(NamespaceRegistry::new()),
+        state_manager,
+        auth_enabled: false,
+        callback_base_url: "http://localhost:3000".to_string(),
+        allowed_return_origins: parse_allowed_origins("https://app.example.com"),
+        providers: Arc::new(ProviderRegistry::builtin()),
+        admin_token: None,
+        http_client: reqwest::Client::new(),
+    };
+
+    create_oauth_router(state)
+}
+
+async fn get(app: Router, uri: &str) -> axum::response::Response {
+    app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_start_rejects_disallowed_return_to() {
+    let manager = StateManager::new(600);
+    let app = create_test_app(manager.clone());
+
+    let response = get(
+        app,
+        "/api/connectors/github/oauth/start?return_to=https%3A%2F%2Fapp.example.com.evil.com%2F",
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(response.headers().get(header::LOCATION).is_none());
+    // No state is created for a rejected request
+    assert_eq!(manager.count(), 0);
+}
+
+#[tokio::test]
+async fn test_callback_error_redirects_to_return_to() {
+    let manager = StateManager::new(600);
+    let csrf_state = manager.create_state_with_return(
+        "github",
+        "default",
+        None,
+        Some("https://app.example.com/connectors".to_string()),
+    );
+    let app = create_test_app(manager);
+
+    let response = get(
+        app,
+        &format!(
+            "/api/connectors/github/oauth/callback?error=access_denied&state={}",
+            csrf_state
+        ),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        "https://app.example.com/connectors?connector=github&status=error&reason=access_denied"
+    );
+}
+
+#[tokio::test]
+async fn test_callback_connector_mismatch_redirects_with_reason() {
+    let manager = StateManager::new(600);
+    let csrf_state = manager.create_state_with_return(
+        "gmail",
+        "default",
+        None,
+        Some("https://app.example.com/".to_string()),
+    );
+    let app = create_test_app(manager);
+
+    let response = get(
+        app,
+        &format!(
+            "/api/connectors/github/oauth/callback?code=abc&state={}",
+            csrf_state
+        ),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        "https://app.example.com/?connector=github&status=error&reason=connector_mismatch"
+    );
+}
+
+#[tokio::test]
+async fn test_callback_without_return_to_stays_json() {
+    let app = create_test_app(StateManager::new(600));
+
+    let response = get(
+        app,
+        "/api/connectors/github/oauth/callback?code=abc&state=unknown",
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get(header::LOCATION).is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(json["error"]
+        .as_str()
+        .unwrap()
+        .contains("Invalid or expired"));
+}
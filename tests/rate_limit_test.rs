@@ -27,10 +27,7 @@ struct RateLimitState {
     namespace: String,
 }
 
-async fn test_handler(
-    State(s): State<RateLimitState>,
-    _body: Bytes,
-) -> impl IntoResponse {
+async fn test_handler(State(s): State<RateLimitState>, _body: Bytes) -> impl IntoResponse {
     if s.auth_enabled {
         let limit = s
             .runtime_config
@@ -77,7 +74,10 @@ async fn test_auth_disabled_bypasses_rate_limit() {
 
     // Use a tiny limit; should still pass because auth is off
     let runtime_config = new_runtime_config();
-    runtime_config.write().unwrap().rate_limit_per_namespace_per_minute = 1;
+    runtime_config
+        .write()
+        .unwrap()
+        .rate_limit_per_namespace_per_minute = 1;
 
     let state = RateLimitState {
         auth_enabled: false,
@@ -102,7 +102,10 @@ async fn test_auth_disabled_bypasses_rate_limit() {
 async fn test_within_limit_allowed() {
     let rate_limiter = Arc::new(RateLimiter::new());
     let runtime_config = new_runtime_config();
-    runtime_config.write().unwrap().rate_limit_per_namespace_per_minute = 100;
+    runtime_config
+        .write()
+        .unwrap()
+        .rate_limit_per_namespace_per_minute = 100;
 
     let state = RateLimitState {
         auth_enabled: true,
@@ -122,7 +125,10 @@ async fn test_exceeding_limit_returns_429_with_retry_after() {
     let rate_limiter = Arc::new(RateLimiter::new());
     let runtime_config = new_runtime_config();
     // Capacity = 1 token — first request consumes it, second is blocked
-    runtime_config.write().unwrap().rate_limit_per_namespace_per_minute = 1;
+    runtime_config
+        .write()
+        .unwrap()
+        .rate_limit_per_namespace_per_minute = 1;
 
     let state = RateLimitState {
         auth_enabled: true,
@@ -157,7 +163,10 @@ async fn test_exceeding_limit_returns_429_with_retry_after() {
 async fn test_separate_namespaces_are_isolated() {
     let rate_limiter = Arc::new(RateLimiter::new());
     let runtime_config = new_runtime_config();
-    runtime_config.write().unwrap().rate_limit_per_namespace_per_minute = 1;
+    runtime_config
+        .write()
+        .unwrap()
+        .rate_limit_per_namespace_per_minute = 1;
 
     let state_ns1 = RateLimitState {
         auth_enabled: true,
@@ -1,4 +1,9 @@
-use std::{cell::RefCell, collections::BTreeMap, io::Result, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    io::Result,
+    rc::Rc,
+};
 
 use chrono::{DateTime, Utc};
 use gloo_net::http::Request;
@@ -9,7 +14,7 @@ use ratzilla::ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, TableState, Wrap},
     Terminal,
 };
 use ratzilla::{DomBackend, WebRenderer};
@@ -63,7 +68,17 @@ struct WsMessage {
     #[serde(default)]
     value: serde_json::Value,
     #[serde(default)]
+    removed: bool,
+    #[serde(default)]
     timestamp: String,
+    // Event source and resulting entity last_updated (state updates; absent from older servers)
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    entity_last_updated: Option<String>,
+    // state_update_batch fields
+    #[serde(default)]
+    changes: Vec<WsPropertyChange>,
     // metrics fields
     #[serde(default)]
     entities: Option<MetricsEntities>,
@@ -75,6 +90,15 @@ struct WsMessage {
     publishers: Option<MetricsPublishers>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct WsPropertyChange {
+    property: String,
+    #[serde(default)]
+    value: serde_json::Value,
+    #[serde(default)]
+    removed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct MetricsEntities {
     #[serde(default)]
@@ -101,6 +125,38 @@ struct MetricsPublishers {
     active: u64,
 }
 
+/// A source as listed by the connector-manager (`GET /api/connectors`)
+#[derive(Debug, Clone, Deserialize)]
+struct ConnectorInfo {
+    name: String,
+    #[serde(rename = "type")]
+    connector_type: String,
+    status: String,
+    #[serde(default)]
+    source_id: Option<String>,
+    #[serde(default)]
+    last_started: Option<String>,
+    #[serde(default)]
+    last_error: Option<String>,
+    #[serde(default)]
+    events_emitted: Option<u64>,
+}
+
+impl ConnectorInfo {
+    /// Path segment of the source's kind in the connector-manager API, for
+    /// kinds that can be deleted (builtin and external connectors can't)
+    fn api_path(&self) -> Option<&'static str> {
+        match self.connector_type.as_str() {
+            "generic" => Some("generic"),
+            "named" => Some("named"),
+            "file" => Some("files"),
+            "postgres" => Some("postgres"),
+            "weather" => Some("weather"),
+            _ => None,
+        }
+    }
+}
+
 // ─── App State ──────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -108,6 +164,109 @@ enum Panel {
     Entities,
     Detail,
     Messages,
+    Events,
+    Connectors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortMode {
+    Recency,   // most recently updated first
+    Id,        // alphabetical
+    Staleness, // least recently updated first
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::Recency => SortMode::Id,
+            SortMode::Id => SortMode::Staleness,
+            SortMode::Staleness => SortMode::Recency,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Recency => "recency",
+            SortMode::Id => "id",
+            SortMode::Staleness => "staleness",
+        }
+    }
+}
+
+/// A row in the entity table (group headers only appear in grouping mode)
+#[derive(Debug, Clone, PartialEq)]
+enum ListRow {
+    Group { namespace: String, count: usize, collapsed: bool },
+    Entity(String),
+}
+
+/// Namespace prefix of an entity ID (text before the first `/`)
+fn namespace_of(entity_id: &str) -> &str {
+    match entity_id.split_once('/') {
+        Some((ns, _)) => ns,
+        None => "(none)",
+    }
+}
+
+// Sparkline history bounds
+const HISTORY_POINTS: usize = 120; // values kept per property
+const HISTORY_MAX_PROPERTIES: usize = 16; // numeric properties tracked per entity
+const HISTORY_MAX_ENTITIES: usize = 500; // entities with buffers before eviction
+
+/// Recent numeric values for one entity's properties
+#[derive(Debug, Default)]
+struct EntityHistory {
+    properties: BTreeMap<String, VecDeque<(f64, f64)>>, // (timestamp ms, value)
+    last_touched_ms: f64,
+}
+
+impl EntityHistory {
+    fn record(&mut self, property: &str, ts_ms: f64, value: f64) {
+        self.last_touched_ms = ts_ms;
+        if !self.properties.contains_key(property)
+            && self.properties.len() >= HISTORY_MAX_PROPERTIES
+        {
+            return;
+        }
+        let buf = self.properties.entry(property.to_string()).or_default();
+        if buf.len() == HISTORY_POINTS {
+            buf.pop_front();
+        }
+        buf.push_back((ts_ms, value));
+    }
+}
+
+const EVENT_LOG_CAP: usize = 2000;
+const RECENT_ON_LOAD: usize = 200; // recent server activity requested into an empty event log
+const RECENT_CHANGES: usize = 5; // changes listed under the selected entity's properties
+const STATUS_DURATION_MS: f64 = 5_000.0;
+const CONNECTOR_REFRESH_MS: u32 = 5_000; // connector panel refresh while it is shown
+const CONNECTOR_MANAGER_PORT: u16 = 3001; // default when ?connectors= is not given
+
+/// Overlay that captures key input until closed
+#[derive(Debug, Clone, PartialEq)]
+enum Modal {
+    ConfirmDelete { entity_id: String },
+    EditProperty { entity_id: String, property: String, input: String },
+    Token { input: String },
+    ConfirmDeleteSource { path: &'static str, source_id: String, name: String },
+}
+
+/// Transient message shown in place of the help bar
+#[derive(Debug, Clone)]
+struct StatusLine {
+    text: String,
+    color: Color,
+    expires_ms: f64,
+}
+
+#[derive(Debug, Clone)]
+struct LogEntry {
+    entity_id: String,
+    property: String,
+    source: Option<String>,
+    timestamp: String,
+    text: String,
 }
 
 struct AppState {
@@ -118,8 +277,28 @@ struct AppState {
     table_state: TableState,
     active_panel: Panel,
     ws_connected: bool,
-    event_log: Vec<String>, // recent events for the stream
+    event_log: VecDeque<LogEntry>, // recent events for the stream
+    event_total: u64,              // events ever logged (ring buffer drops old ones)
+    paused_at: Option<u64>,        // event_total when the stream was paused
+    event_scroll: usize,           // lines scrolled back from the newest visible event
     now_ms: f64,            // current time for staleness calc
+    search_query: String,
+    search_active: bool, // `/` pressed, keys go to the search box
+    group_by_namespace: bool,
+    collapsed_namespaces: BTreeSet<String>,
+    sort_mode: SortMode,
+    history: HashMap<String, EntityHistory>,
+    selected_property: usize, // property cursor in the detail panel
+    token: Option<String>,    // bearer token for write actions
+    modal: Option<Modal>,
+    status: Option<StatusLine>,
+    connectors_url: String,              // connector-manager base URL
+    connectors: Vec<ConnectorInfo>,
+    connectors_error: Option<String>,    // why the last refresh failed; the list is stale
+    connectors_loaded: bool,
+    connectors_loading: bool,            // a refresh is in flight
+    selected_connector: usize,
+    connector_table: TableState,
 }
 
 impl AppState {
@@ -132,29 +311,148 @@ impl AppState {
             table_state: TableState::default().with_selected(Some(0)),
             active_panel: Panel::Entities,
             ws_connected: false,
-            event_log: Vec::new(),
+            event_log: VecDeque::with_capacity(EVENT_LOG_CAP),
+            event_total: 0,
+            paused_at: None,
+            event_scroll: 0,
             now_ms: js_sys::Date::now(),
+            search_query: String::new(),
+            search_active: false,
+            group_by_namespace: false,
+            collapsed_namespaces: BTreeSet::new(),
+            sort_mode: SortMode::Recency,
+            history: HashMap::new(),
+            selected_property: 0,
+            token: token_from_query(),
+            modal: None,
+            status: None,
+            connectors_url: connectors_url(),
+            connectors: Vec::new(),
+            connectors_error: None,
+            connectors_loaded: false,
+            connectors_loading: false,
+            selected_connector: 0,
+            connector_table: TableState::default().with_selected(Some(0)),
         }
     }
 
+    fn matches_search(&self, entity: &Entity) -> bool {
+        if self.search_query.is_empty() {
+            return true;
+        }
+        let query = self.search_query.to_lowercase();
+        let status = entity
+            .properties
+            .get("status")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        entity.id.to_lowercase().contains(&query) || status.to_lowercase().contains(&query)
+    }
+
+    /// Entity IDs passing the search filter, in the current sort order
     fn sorted_entity_ids(&self) -> Vec<String> {
-        let mut ids: Vec<_> = self.entities.keys().cloned().collect();
-        // Sort by last_updated descending (most recent first)
-        ids.sort_by(|a, b| {
-            let ta = self.entities.get(a).map(|e| e.last_updated.as_str()).unwrap_or("");
-            let tb = self.entities.get(b).map(|e| e.last_updated.as_str()).unwrap_or("");
-            tb.cmp(ta)
-        });
+        let mut ids: Vec<_> = self
+            .entities
+            .values()
+            .filter(|e| self.matches_search(e))
+            .map(|e| e.id.clone())
+            .collect();
+        let updated = |id: &String| {
+            self.entities.get(id).map(|e| e.last_updated.as_str()).unwrap_or("")
+        };
+        match self.sort_mode {
+            SortMode::Recency => ids.sort_by(|a, b| updated(b).cmp(updated(a))),
+            SortMode::Id => ids.sort(),
+            SortMode::Staleness => ids.sort_by(|a, b| updated(a).cmp(updated(b))),
+        }
         ids
     }
 
-    fn selected_entity_data(&self) -> Option<&Entity> {
+    /// Rows shown in the entity table, with group headers in grouping mode
+    fn visible_rows(&self) -> Vec<ListRow> {
         let ids = self.sorted_entity_ids();
-        ids.get(self.selected_entity)
-            .and_then(|id| self.entities.get(id))
+        if !self.group_by_namespace {
+            return ids.into_iter().map(ListRow::Entity).collect();
+        }
+
+        // Groups ordered by namespace, entities keep the sort order within a group
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for id in ids {
+            groups.entry(namespace_of(&id).to_string()).or_default().push(id);
+        }
+
+        let mut rows = Vec::new();
+        for (namespace, ids) in groups {
+            let collapsed = self.collapsed_namespaces.contains(&namespace);
+            rows.push(ListRow::Group {
+                namespace,
+                count: ids.len(),
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(ids.into_iter().map(ListRow::Entity));
+            }
+        }
+        rows
+    }
+
+    /// Keep the selection inside the visible rows after filtering changes
+    fn clamp_selection(&mut self) {
+        let count = self.visible_rows().len();
+        if self.selected_entity >= count {
+            self.selected_entity = count.saturating_sub(1);
+        }
+        self.table_state.select(Some(self.selected_entity));
+    }
+
+    fn selected_row(&self) -> Option<ListRow> {
+        self.visible_rows().into_iter().nth(self.selected_entity)
+    }
+
+    fn selected_entity_data(&self) -> Option<&Entity> {
+        match self.selected_row()? {
+            ListRow::Entity(id) => self.entities.get(&id),
+            ListRow::Group { .. } => None,
+        }
+    }
+
+    fn selected_property_name(&self) -> Option<String> {
+        let entity = self.selected_entity_data()?;
+        let index = self.selected_property.min(entity.properties.len().checked_sub(1)?);
+        entity.properties.keys().nth(index).cloned()
+    }
+
+    fn set_status(&mut self, text: impl Into<String>, color: Color) {
+        self.status = Some(StatusLine {
+            text: text.into(),
+            color,
+            expires_ms: js_sys::Date::now() + STATUS_DURATION_MS,
+        });
+    }
+
+    fn active_status(&self) -> Option<&StatusLine> {
+        self.status.as_ref().filter(|st| st.expires_ms > self.now_ms)
+    }
+
+    fn toggle_selected_group(&mut self) {
+        if let Some(ListRow::Group { namespace, .. }) = self.selected_row() {
+            if !self.collapsed_namespaces.remove(&namespace) {
+                self.collapsed_namespaces.insert(namespace);
+            }
+            self.clamp_selection();
+        }
     }
 
-    fn apply_state_update(&mut self, entity_id: &str, property: &str, value: serde_json::Value, timestamp: &str) {
+    /// Apply one property change; `removed` drops the key instead of storing null
+    fn apply_state_update(&mut self, entity_id: &str, property: &str, value: serde_json::Value, removed: bool, timestamp: &str, source: Option<&str>) {
+        if removed {
+            if let Some(entity) = self.entities.get_mut(entity_id) {
+                entity.properties.remove(property);
+                entity.last_updated = timestamp.to_string();
+            }
+            return;
+        }
+
         let entity = self.entities.entry(entity_id.to_string()).or_insert_with(|| Entity {
             id: entity_id.to_string(),
             properties: BTreeMap::new(),
@@ -163,6 +461,14 @@ impl AppState {
         entity.properties.insert(property.to_string(), value.clone());
         entity.last_updated = timestamp.to_string();
 
+        if let Some(n) = value.as_f64() {
+            let ts_ms = timestamp
+                .parse::<DateTime<Utc>>()
+                .map(|dt| dt.timestamp_millis() as f64)
+                .unwrap_or(self.now_ms);
+            self.record_history(entity_id, property, ts_ms, n);
+        }
+
         // Check for agent messages
         let has_message = entity.properties.contains_key("message");
         let has_message_to = entity.properties.contains_key("message_to");
@@ -198,10 +504,76 @@ impl AppState {
         // Log event
         let short_val = format!("{}", value);
         let short_val = if short_val.len() > 40 { format!("{}…", &short_val[..40]) } else { short_val };
-        self.event_log.push(format!("{}.{} = {}", entity_id, property, short_val));
-        if self.event_log.len() > 200 {
-            self.event_log.remove(0);
+        if self.event_log.len() == EVENT_LOG_CAP {
+            self.event_log.pop_front();
         }
+        self.event_log.push_back(LogEntry {
+            entity_id: entity_id.to_string(),
+            property: property.to_string(),
+            source: source.map(str::to_string),
+            timestamp: timestamp.to_string(),
+            text: format!("{}.{} = {}", entity_id, property, short_val),
+        });
+        self.event_total += 1;
+    }
+
+    /// Use the server's post-update last_updated when it sends one
+    fn set_last_updated(&mut self, entity_id: &str, last_updated: &str) {
+        if let Some(entity) = self.entities.get_mut(entity_id) {
+            entity.last_updated = last_updated.to_string();
+        }
+    }
+
+    /// Newest logged changes to `entity_id`, newest first
+    fn recent_changes(&self, entity_id: &str, limit: usize) -> Vec<&LogEntry> {
+        self.event_log
+            .iter()
+            .rev()
+            .filter(|entry| entry.entity_id == entity_id)
+            .take(limit)
+            .collect()
+    }
+
+    /// Events received since the stream was paused
+    fn buffered_events(&self) -> u64 {
+        self.paused_at.map(|at| self.event_total - at).unwrap_or(0)
+    }
+
+    /// Number of log entries up to the frozen view (all of them when live)
+    fn visible_event_end(&self) -> usize {
+        let hidden = self.buffered_events() as usize;
+        self.event_log.len().saturating_sub(hidden)
+    }
+
+    fn toggle_pause(&mut self) {
+        if self.paused_at.take().is_none() {
+            self.paused_at = Some(self.event_total);
+        } else {
+            self.event_scroll = 0;
+        }
+    }
+
+    fn scroll_events(&mut self, delta: isize) {
+        let max = self.visible_event_end().saturating_sub(1);
+        self.event_scroll = self.event_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    fn record_history(&mut self, entity_id: &str, property: &str, ts_ms: f64, value: f64) {
+        if !self.history.contains_key(entity_id) && self.history.len() >= HISTORY_MAX_ENTITIES {
+            // Evict the least recently updated entity's buffers
+            let oldest = self
+                .history
+                .iter()
+                .min_by(|a, b| a.1.last_touched_ms.total_cmp(&b.1.last_touched_ms))
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                self.history.remove(&oldest);
+            }
+        }
+        self.history
+            .entry(entity_id.to_string())
+            .or_default()
+            .record(property, ts_ms, value);
     }
 
     fn apply_metrics(&mut self, msg: &WsMessage) {
@@ -222,12 +594,18 @@ impl AppState {
 
     fn delete_entity(&mut self, entity_id: &str) {
         self.entities.remove(entity_id);
-        // Clamp selection
-        let count = self.entities.len();
-        if self.selected_entity >= count && count > 0 {
-            self.selected_entity = count - 1;
-        }
-        self.table_state.select(Some(self.selected_entity));
+        self.history.remove(entity_id);
+        self.clamp_selection();
+    }
+
+    fn selected_connector_data(&self) -> Option<&ConnectorInfo> {
+        self.connectors.get(self.selected_connector)
+    }
+
+    fn move_connector_selection(&mut self, delta: isize) {
+        let max = self.connectors.len().saturating_sub(1);
+        self.selected_connector = self.selected_connector.saturating_add_signed(delta).min(max);
+        self.connector_table.select(Some(self.selected_connector));
     }
 }
 
@@ -288,6 +666,322 @@ fn get_ws_url() -> String {
     format!("{}//{}/api/ws", ws_proto, host)
 }
 
+/// Read `?<name>=` from the page URL
+fn query_param(name: &str) -> Option<String> {
+    let search = window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == name)
+        .and_then(|(_, v)| js_sys::decode_uri_component(v).ok())
+        .map(String::from)
+        .filter(|v| !v.is_empty())
+}
+
+/// Read `?token=` from the page URL
+fn token_from_query() -> Option<String> {
+    query_param("token")
+}
+
+/// Connector-manager base URL: `?connectors=`, else this host on its default port
+fn connectors_url() -> String {
+    if let Some(url) = query_param("connectors") {
+        return url.trim_end_matches('/').to_string();
+    }
+    let loc = window().expect("no window").location();
+    let proto = loc.protocol().unwrap_or_else(|_| "http:".to_string());
+    let hostname = loc.hostname().unwrap_or_else(|_| "localhost".to_string());
+    format!("{}//{}:{}", proto, hostname, CONNECTOR_MANAGER_PORT)
+}
+
+/// Turn a failed write response into a status line message
+fn describe_failure(status: u16, body: &str) -> String {
+    match status {
+        401 => "401 unauthorized — press t to set a token".to_string(),
+        403 => "403 forbidden — token cannot write this entity".to_string(),
+        429 => "429 rate limited — try again shortly".to_string(),
+        _ => {
+            let detail = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(String::from))
+                .unwrap_or_else(|| body.to_string());
+            format!("{} — {}", status, detail)
+        }
+    }
+}
+
+async fn send_write(request: std::result::Result<Request, gloo_net::Error>) -> std::result::Result<(), String> {
+    let request = request.map_err(|e| format!("request error: {}", e))?;
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("network error: {}", e))?;
+    if resp.ok() {
+        Ok(())
+    } else {
+        let body = resp.text().await.unwrap_or_default();
+        Err(describe_failure(resp.status(), &body))
+    }
+}
+
+fn with_auth(builder: gloo_net::http::RequestBuilder, token: &Option<String>) -> gloo_net::http::RequestBuilder {
+    match token {
+        Some(t) => builder.header("Authorization", &format!("Bearer {}", t)),
+        None => builder,
+    }
+}
+
+/// DELETE /api/state/entities/:id
+async fn request_delete(token: Option<String>, entity_id: &str) -> std::result::Result<(), String> {
+    let url = format!(
+        "{}/api/state/entities/{}",
+        get_base_url(),
+        String::from(js_sys::encode_uri_component(entity_id))
+    );
+    send_write(with_auth(Request::delete(&url), &token).build()).await
+}
+
+/// POST /api/events with a single-property update
+async fn request_update(
+    token: Option<String>,
+    entity_id: &str,
+    property: &str,
+    value: serde_json::Value,
+) -> std::result::Result<(), String> {
+    let url = format!("{}/api/events", get_base_url());
+    let event = serde_json::json!({
+        "stream": "monitor.edits",
+        "source": "flux-monitor",
+        "timestamp": js_sys::Date::now() as i64,
+        "payload": {
+            "entity_id": entity_id,
+            "properties": { property: value },
+        },
+    });
+    send_write(with_auth(Request::post(&url), &token).json(&event)).await
+}
+
+/// GET /api/connectors on the connector-manager
+async fn request_connectors(
+    base: &str,
+    token: Option<String>,
+) -> std::result::Result<Vec<ConnectorInfo>, String> {
+    let url = format!("{}/api/connectors", base);
+    let resp = with_auth(Request::get(&url), &token)
+        .send()
+        .await
+        .map_err(|e| format!("unreachable: {}", e))?;
+    if !resp.ok() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(describe_failure(resp.status(), &body));
+    }
+    resp.json::<Vec<ConnectorInfo>>()
+        .await
+        .map_err(|e| format!("unexpected response: {}", e))
+}
+
+// ─── Actions ────────────────────────────────────────────────────────────────
+
+/// Delete optimistically; restore the entity if the API refuses
+fn start_delete(state: Rc<RefCell<AppState>>, s: &mut AppState, entity_id: String) {
+    let removed = s.entities.get(&entity_id).cloned();
+    s.delete_entity(&entity_id);
+    let token = s.token.clone();
+
+    spawn_local(async move {
+        let result = request_delete(token, &entity_id).await;
+        let mut s = state.borrow_mut();
+        match result {
+            Ok(()) => s.set_status(format!("Deleted {}", entity_id), Color::Green),
+            Err(msg) => {
+                if let Some(entity) = removed {
+                    s.entities.entry(entity_id.clone()).or_insert(entity);
+                }
+                s.set_status(format!("Delete failed: {}", msg), Color::Red);
+            }
+        }
+    });
+}
+
+/// Apply the edit locally; the WS broadcast reconciles, failures revert
+fn start_edit(
+    state: Rc<RefCell<AppState>>,
+    s: &mut AppState,
+    entity_id: String,
+    property: String,
+    input: &str,
+) {
+    // Accept JSON literals (numbers, bools, objects), otherwise treat as a string
+    let value = serde_json::from_str(input)
+        .unwrap_or_else(|_| serde_json::Value::String(input.to_string()));
+    let previous = s
+        .entities
+        .get_mut(&entity_id)
+        .and_then(|e| e.properties.insert(property.clone(), value.clone()));
+    let token = s.token.clone();
+
+    spawn_local(async move {
+        let result = request_update(token, &entity_id, &property, value.clone()).await;
+        let mut s = state.borrow_mut();
+        match result {
+            Ok(()) => s.set_status(format!("Updated {}.{}", entity_id, property), Color::Green),
+            Err(msg) => {
+                if let Some(entity) = s.entities.get_mut(&entity_id) {
+                    // Only revert if nothing newer arrived meanwhile
+                    if entity.properties.get(&property) == Some(&value) {
+                        match previous {
+                            Some(prev) => entity.properties.insert(property.clone(), prev),
+                            None => entity.properties.remove(&property),
+                        };
+                    }
+                }
+                s.set_status(format!("Update failed: {}", msg), Color::Red);
+            }
+        }
+    });
+}
+
+/// Reload the connector list unless a refresh is already in flight
+fn refresh_connectors(state: Rc<RefCell<AppState>>, s: &mut AppState) {
+    if s.connectors_loading {
+        return;
+    }
+    s.connectors_loading = true;
+    let base = s.connectors_url.clone();
+    let token = s.token.clone();
+
+    spawn_local(async move {
+        let result = request_connectors(&base, token).await;
+        let mut s = state.borrow_mut();
+        s.connectors_loading = false;
+        s.connectors_loaded = true;
+        match result {
+            Ok(connectors) => {
+                s.connectors = connectors;
+                s.connectors_error = None;
+                s.move_connector_selection(0);
+            }
+            Err(msg) => s.connectors_error = Some(msg),
+        }
+    });
+}
+
+/// Send a write to the connector-manager, report the outcome and reload the list
+fn start_connector_action(
+    state: Rc<RefCell<AppState>>,
+    s: &mut AppState,
+    request: std::result::Result<Request, gloo_net::Error>,
+    done: String,
+) {
+    spawn_local(async move {
+        let result = send_write(request).await;
+        let mut s = state.borrow_mut();
+        match result {
+            Ok(()) => s.set_status(done, Color::Green),
+            Err(msg) => s.set_status(format!("Connector action failed: {}", msg), Color::Red),
+        }
+        refresh_connectors(state.clone(), &mut s);
+    });
+    s.set_status("Sending…", Color::Yellow);
+}
+
+/// Named source ID of the selected connector, or a status line saying why not
+fn selected_named_source(s: &mut AppState, action: &str) -> Option<String> {
+    let connector = s.selected_connector_data()?;
+    if connector.connector_type == "named" {
+        return connector.source_id.clone();
+    }
+    s.set_status(format!("Only named sources can {}", action), Color::Yellow);
+    None
+}
+
+/// POST /api/connectors/named/:id/sync
+fn start_sync(state: Rc<RefCell<AppState>>, s: &mut AppState) {
+    let Some(source_id) = selected_named_source(s, "be synced") else { return };
+    let url = format!("{}/api/connectors/named/{}/sync", s.connectors_url, source_id);
+    let request = with_auth(Request::post(&url), &s.token).build();
+    start_connector_action(state, s, request, "Sync started".to_string());
+}
+
+/// POST /api/connectors/named/:id/pause, or /resume when already paused
+fn start_pause_toggle(state: Rc<RefCell<AppState>>, s: &mut AppState) {
+    let Some(source_id) = selected_named_source(s, "be paused") else { return };
+    let paused = s.selected_connector_data().is_some_and(|c| c.status == "paused");
+    let (action, done) = if paused { ("resume", "Resumed") } else { ("pause", "Paused") };
+    let url = format!("{}/api/connectors/named/{}/{}", s.connectors_url, source_id, action);
+    let request = with_auth(Request::post(&url), &s.token).build();
+    start_connector_action(state, s, request, done.to_string());
+}
+
+/// Ask before deleting the selected source
+fn confirm_delete_source(s: &mut AppState) {
+    let Some(connector) = s.selected_connector_data().cloned() else { return };
+    match (connector.api_path(), connector.source_id) {
+        (Some(path), Some(source_id)) => {
+            s.modal = Some(Modal::ConfirmDeleteSource { path, source_id, name: connector.name });
+        }
+        _ => s.set_status(
+            format!("{} connectors can't be deleted here", connector.connector_type),
+            Color::Yellow,
+        ),
+    }
+}
+
+/// Key handling while an overlay is open
+fn handle_modal_key(state: &Rc<RefCell<AppState>>, s: &mut AppState, code: KeyCode) {
+    let Some(modal) = s.modal.take() else { return };
+    match modal {
+        Modal::ConfirmDelete { entity_id } => match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => start_delete(state.clone(), s, entity_id),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {}
+            _ => s.modal = Some(Modal::ConfirmDelete { entity_id }),
+        },
+        Modal::ConfirmDeleteSource { path, source_id, name } => match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let url = format!("{}/api/connectors/{}/{}", s.connectors_url, path, source_id);
+                let request = with_auth(Request::delete(&url), &s.token).build();
+                start_connector_action(state.clone(), s, request, format!("Deleted {}", name));
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {}
+            _ => s.modal = Some(Modal::ConfirmDeleteSource { path, source_id, name }),
+        },
+        Modal::EditProperty { entity_id, property, mut input } => match code {
+            KeyCode::Enter => start_edit(state.clone(), s, entity_id, property, &input),
+            KeyCode::Esc => {}
+            KeyCode::Backspace => {
+                input.pop();
+                s.modal = Some(Modal::EditProperty { entity_id, property, input });
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                s.modal = Some(Modal::EditProperty { entity_id, property, input });
+            }
+            _ => s.modal = Some(Modal::EditProperty { entity_id, property, input }),
+        },
+        Modal::Token { mut input } => match code {
+            KeyCode::Enter => {
+                let token = input.trim().to_string();
+                s.token = if token.is_empty() { None } else { Some(token) };
+                s.set_status(
+                    if s.token.is_some() { "Token set" } else { "Token cleared" },
+                    Color::Green,
+                );
+            }
+            KeyCode::Esc => {}
+            KeyCode::Backspace => {
+                input.pop();
+                s.modal = Some(Modal::Token { input });
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                s.modal = Some(Modal::Token { input });
+            }
+            _ => s.modal = Some(Modal::Token { input }),
+        },
+    }
+}
+
 // ─── UI Rendering ───────────────────────────────────────────────────────────
 
 fn render_header(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState) {
@@ -317,34 +1011,86 @@ fn render_header(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState)
 }
 
 fn render_entity_list(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &mut AppState) {
-    let ids = state.sorted_entity_ids();
+    // WS updates can shrink the filtered set between frames
+    state.clamp_selection();
+
+    let visible = state.visible_rows();
     let now_ms = state.now_ms;
 
-    let rows: Vec<Row> = ids
+    let show_search = state.search_active || !state.search_query.is_empty();
+    let (search_area, table_area) = if show_search {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3)])
+            .split(area);
+        (Some(chunks[0]), chunks[1])
+    } else {
+        (None, area)
+    };
+
+    if let Some(search_area) = search_area {
+        let cursor = if state.search_active { "▏" } else { "" };
+        let search = Paragraph::new(Line::from(vec![
+            Span::styled(" / ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{}{}", state.search_query, cursor),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+        f.render_widget(search, search_area);
+    }
+
+    let mut shown = 0;
+    let rows: Vec<Row> = visible
         .iter()
-        .map(|id| {
-            let entity = &state.entities[id];
-            let status = entity
-                .properties
-                .get("status")
-                .and_then(|v| v.as_str())
-                .unwrap_or("-");
-            let color = staleness_color(&entity.last_updated, now_ms);
-            let age = staleness_label(&entity.last_updated, now_ms);
-
-            let status_style = match status {
-                "active" | "online" | "healthy" => Style::default().fg(Color::Green),
-                "warning" => Style::default().fg(Color::Yellow),
-                "error" | "critical" => Style::default().fg(Color::Red),
-                _ => Style::default().fg(Color::DarkGray),
-            };
-
-            Row::new(vec![
-                Cell::from(Span::styled("●", Style::default().fg(color))),
-                Cell::from(Span::styled(id.clone(), Style::default().fg(Color::Cyan))),
-                Cell::from(Span::styled(status.to_string(), status_style)),
-                Cell::from(Span::styled(age, Style::default().fg(color))),
-            ])
+        .map(|row| match row {
+            ListRow::Group { namespace, count, collapsed } => {
+                let marker = if *collapsed { "▸" } else { "▾" };
+                Row::new(vec![
+                    Cell::from(Span::styled(marker, Style::default().fg(Color::Magenta))),
+                    Cell::from(Span::styled(
+                        namespace.clone(),
+                        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                    )),
+                    Cell::from(Span::styled(
+                        format!("{} entities", count),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                    Cell::from(""),
+                ])
+            }
+            ListRow::Entity(id) => {
+                shown += 1;
+                let entity = &state.entities[id];
+                let status = entity
+                    .properties
+                    .get("status")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("-");
+                let color = staleness_color(&entity.last_updated, now_ms);
+                let age = staleness_label(&entity.last_updated, now_ms);
+
+                let status_style = match status {
+                    "active" | "online" | "healthy" => Style::default().fg(Color::Green),
+                    "warning" => Style::default().fg(Color::Yellow),
+                    "error" | "critical" => Style::default().fg(Color::Red),
+                    _ => Style::default().fg(Color::DarkGray),
+                };
+
+                // Within a group the namespace is already in the header
+                let label = if state.group_by_namespace {
+                    format!("  {}", id.split_once('/').map(|(_, rest)| rest).unwrap_or(id))
+                } else {
+                    id.clone()
+                };
+
+                Row::new(vec![
+                    Cell::from(Span::styled("●", Style::default().fg(color))),
+                    Cell::from(Span::styled(label, Style::default().fg(Color::Cyan))),
+                    Cell::from(Span::styled(status.to_string(), status_style)),
+                    Cell::from(Span::styled(age, Style::default().fg(color))),
+                ])
+            }
         })
         .collect();
 
@@ -362,6 +1108,12 @@ fn render_entity_list(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &mut
         Color::DarkGray
     };
 
+    let count_label = if state.search_query.is_empty() {
+        format!("{}", state.entities.len())
+    } else {
+        format!("{}/{}", shown, state.entities.len())
+    };
+
     let table = Table::new(
         rows,
         [
@@ -374,13 +1126,27 @@ fn render_entity_list(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &mut
     .header(header)
     .block(
         Block::default()
-            .title(format!(" Entities ({}) ", ids.len()))
+            .title(format!(
+                " Entities ({}) · sort: {} ",
+                count_label,
+                state.sort_mode.label()
+            ))
             .borders(Borders::ALL)
             .border_style(Style::default().fg(border_color)),
     )
     .row_highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
 
-    f.render_stateful_widget(table, area, &mut state.table_state);
+    f.render_stateful_widget(table, table_area, &mut state.table_state);
+}
+
+fn format_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => if *b { "✓".to_string() } else { "✗".to_string() },
+        serde_json::Value::Null => "null".to_string(),
+        other => format!("{}", other),
+    }
 }
 
 fn render_detail(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState) {
@@ -390,65 +1156,148 @@ fn render_detail(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState)
         Color::DarkGray
     };
 
-    if let Some(entity) = state.selected_entity_data() {
-        let mut lines = vec![
-            Line::from(vec![
-                Span::styled("ID: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(&entity.id, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-            ]),
-            Line::from(vec![
-                Span::styled("Updated: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    staleness_label(&entity.last_updated, state.now_ms),
-                    Style::default().fg(staleness_color(&entity.last_updated, state.now_ms)),
-                ),
+    let block = Block::default()
+        .title(" Detail ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color));
+
+    let Some(entity) = state.selected_entity_data() else {
+        let empty = Paragraph::new(Span::styled(
+            "No entity selected",
+            Style::default().fg(Color::DarkGray),
+        ))
+        .block(block);
+        f.render_widget(empty, area);
+        return;
+    };
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let staleness = staleness_color(&entity.last_updated, state.now_ms);
+    let header = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("ID: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(&entity.id, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![
+            Span::styled("Updated: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                staleness_label(&entity.last_updated, state.now_ms),
+                Style::default().fg(staleness),
+            ),
+            Span::styled(
+                format!(" ({})", &entity.last_updated),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "─── Properties ───",
+            Style::default().fg(Color::Magenta),
+        )),
+    ]);
+    let header_height = 4.min(inner.height);
+    f.render_widget(header, Rect { height: header_height, ..inner });
+
+    // Recent changes (with the source that made them) go at the bottom when there's room
+    let recent = state.recent_changes(&entity.id, RECENT_CHANGES);
+    let recent_height = if recent.is_empty() { 0 } else { recent.len() as u16 + 1 };
+    let recent_height = if inner.height >= header_height + recent_height + 2 { recent_height } else { 0 };
+    if recent_height > 0 {
+        let mut lines = vec![Line::from(Span::styled(
+            "─── Recent changes ───",
+            Style::default().fg(Color::Magenta),
+        ))];
+        for entry in &recent {
+            let time = entry
+                .timestamp
+                .parse::<DateTime<Utc>>()
+                .map(|dt| dt.format("%H:%M:%S").to_string())
+                .unwrap_or_else(|_| entry.timestamp.clone());
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", time), Style::default().fg(Color::DarkGray)),
+                Span::styled(entry.property.as_str(), Style::default().fg(Color::Yellow)),
+                Span::styled(" ← ", Style::default().fg(Color::DarkGray)),
                 Span::styled(
-                    format!(" ({})", &entity.last_updated),
-                    Style::default().fg(Color::DarkGray),
+                    entry.source.as_deref().unwrap_or("—"),
+                    Style::default().fg(Color::Cyan),
                 ),
-            ]),
-            Line::from(""),
-            Line::from(Span::styled(
-                "─── Properties ───",
-                Style::default().fg(Color::Magenta),
-            )),
-        ];
-
-        for (key, value) in &entity.properties {
-            let val_str = match value {
-                serde_json::Value::String(s) => s.clone(),
-                serde_json::Value::Number(n) => n.to_string(),
-                serde_json::Value::Bool(b) => if *b { "✓".to_string() } else { "✗".to_string() },
-                serde_json::Value::Null => "null".to_string(),
-                other => format!("{}", other),
-            };
-            lines.push(Line::from(vec![
-                Span::styled(format!("  {}: ", key), Style::default().fg(Color::Yellow)),
-                Span::styled(val_str, Style::default().fg(Color::White)),
             ]));
         }
+        f.render_widget(
+            Paragraph::new(lines),
+            Rect { y: inner.y + inner.height - recent_height, height: recent_height, ..inner },
+        );
+    }
 
-        let detail = Paragraph::new(lines)
-            .block(
-                Block::default()
-                    .title(" Detail ")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(border_color)),
-            )
+    // Lay properties out top to bottom, stopping when the panel is full
+    let bottom = inner.y + inner.height - recent_height;
+    let mut y = inner.y + header_height;
+    let history = state.history.get(&entity.id);
+
+    for (index, (key, value)) in entity.properties.iter().enumerate() {
+        if y >= bottom {
+            break;
+        }
+        let key_style = if state.active_panel == Panel::Detail && index == state.selected_property {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        let points = history
+            .and_then(|h| h.properties.get(key))
+            .filter(|buf| value.is_number() && buf.len() >= 2);
+
+        let Some(points) = points else {
+            let text = format!("  {}: {}", key, format_value(value));
+            let width = inner.width.max(1) as usize;
+            let height = (text.chars().count().div_ceil(width) as u16).clamp(1, bottom - y);
+            let line = Paragraph::new(Line::from(vec![
+                Span::styled(format!("  {}: ", key), key_style),
+                Span::styled(format_value(value), Style::default().fg(Color::White)),
+            ]))
             .wrap(Wrap { trim: false });
-        f.render_widget(detail, area);
-    } else {
-        let empty = Paragraph::new(Span::styled(
-            "No entity selected",
-            Style::default().fg(Color::DarkGray),
-        ))
-        .block(
-            Block::default()
-                .title(" Detail ")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(border_color)),
+            f.render_widget(line, Rect { y, height, ..inner });
+            y += height;
+            continue;
+        };
+
+        let (min, max) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &(_, v)| {
+            (lo.min(v), hi.max(v))
+        });
+        let label = Paragraph::new(Line::from(vec![
+            Span::styled(format!("  {}: ", key), key_style),
+            Span::styled(format_value(value), Style::default().fg(staleness)),
+            Span::styled(
+                format!("  min {}  max {}", min, max),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+        f.render_widget(label, Rect { y, height: 1, ..inner });
+        y += 1;
+
+        if y >= bottom {
+            break;
+        }
+        // Scale into 0..=100 and keep only what fits in the panel width
+        let spark_width = inner.width.saturating_sub(4) as usize;
+        let range = max - min;
+        let data: Vec<u64> = points
+            .iter()
+            .skip(points.len().saturating_sub(spark_width))
+            .map(|&(_, v)| if range > 0.0 { ((v - min) / range * 100.0) as u64 } else { 50 })
+            .collect();
+        let height = 2.min(bottom - y);
+        let sparkline = Sparkline::default()
+            .data(&data)
+            .max(100)
+            .style(Style::default().fg(staleness));
+        f.render_widget(
+            sparkline,
+            Rect { x: inner.x + 4, y, width: spark_width as u16, height },
         );
-        f.render_widget(empty, area);
+        y += height;
     }
 }
 
@@ -491,9 +1340,57 @@ fn render_messages(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppStat
     f.render_widget(messages, area);
 }
 
+fn render_events(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState) {
+    let border_color = if state.active_panel == Panel::Events {
+        Color::Magenta
+    } else {
+        Color::DarkGray
+    };
+
+    // Newest at the bottom; the window ends at the frozen/scrolled position
+    let height = area.height.saturating_sub(2) as usize;
+    let end = state.visible_event_end().saturating_sub(state.event_scroll);
+    let start = end.saturating_sub(height);
+
+    let lines: Vec<Line> = state
+        .event_log
+        .range(start..end)
+        .map(|entry| {
+            let color = state
+                .entities
+                .get(&entry.entity_id)
+                .map(|e| staleness_color(&e.last_updated, state.now_ms))
+                .unwrap_or(Color::DarkGray);
+            Line::from(Span::styled(entry.text.as_str(), Style::default().fg(color)))
+        })
+        .collect();
+
+    let mut title = format!(" Event Stream ({}) ", state.event_log.len());
+    if state.paused_at.is_some() {
+        title.push_str("⏸ ");
+    }
+    if state.event_scroll > 0 {
+        title.push_str(&format!("↑{} ", state.event_scroll));
+    }
+
+    let events = Paragraph::new(if lines.is_empty() {
+        Text::from(Span::styled("No events yet", Style::default().fg(Color::DarkGray)))
+    } else {
+        Text::from(lines)
+    })
+    .block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border_color)),
+    );
+
+    f.render_widget(events, area);
+}
+
 fn render_metrics(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState) {
     let m = &state.metrics;
-    let line = Line::from(vec![
+    let mut spans = vec![
         Span::styled(" ⚡ ", Style::default().fg(Color::Yellow)),
         Span::styled(format!("{:.1}", m.events_per_second), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
         Span::styled(" evt/s", Style::default().fg(Color::DarkGray)),
@@ -513,7 +1410,15 @@ fn render_metrics(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState
         Span::styled("⊙ ", Style::default().fg(Color::Green)),
         Span::styled(format!("{}", m.ws_connections), Style::default().fg(Color::Green)),
         Span::styled(" ws", Style::default().fg(Color::DarkGray)),
-    ]);
+    ];
+    if state.paused_at.is_some() {
+        spans.push(Span::styled("  │  ", Style::default().fg(Color::DarkGray)));
+        spans.push(Span::styled(
+            format!("⏸ paused ({} buffered)", state.buffered_events()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let line = Line::from(spans);
 
     let metrics = Paragraph::new(line).block(
         Block::default()
@@ -523,14 +1428,250 @@ fn render_metrics(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState
     f.render_widget(metrics, area);
 }
 
-fn render_help(f: &mut ratzilla::ratatui::Frame, area: Rect) {
-    let help = Paragraph::new(Line::from(vec![
-        Span::styled(" ↑↓", Style::default().fg(Color::Yellow)),
-        Span::styled(" navigate  ", Style::default().fg(Color::DarkGray)),
-        Span::styled("Tab", Style::default().fg(Color::Yellow)),
-        Span::styled(" switch panel  ", Style::default().fg(Color::DarkGray)),
-    ]));
-    f.render_widget(help, area);
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn connector_status_color(status: &str) -> Color {
+    match status {
+        "running" => Color::Green,
+        "partial" | "backoff" | "circuit_open" => Color::Yellow,
+        "error" => Color::Red,
+        _ => Color::DarkGray,
+    }
+}
+
+fn render_connectors(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &mut AppState) {
+    let mut title = vec![Span::styled(
+        format!(" Connectors ({}) · {} ", state.connectors.len(), state.connectors_url),
+        Style::default().fg(Color::White),
+    )];
+    if state.connectors_error.is_some() {
+        title.push(Span::styled("unavailable ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+    }
+    let block = Block::default()
+        .title(Line::from(title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+
+    // Nothing to list yet: say why instead of showing an empty table
+    if state.connectors.is_empty() {
+        let lines = match (&state.connectors_error, state.connectors_loaded) {
+            (Some(error), _) => vec![
+                Line::from(Span::styled(
+                    "Connector manager unavailable",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(error.as_str(), Style::default().fg(Color::DarkGray))),
+                Line::from(Span::styled(
+                    "Set ?connectors=<url> to point the monitor at it",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ],
+            (None, false) => vec![Line::from(Span::styled("Loading…", Style::default().fg(Color::DarkGray)))],
+            (None, true) => vec![Line::from(Span::styled(
+                "No sources configured",
+                Style::default().fg(Color::DarkGray),
+            ))],
+        };
+        f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+        return;
+    }
+
+    let header = Row::new(["Type", "Name", "Status", "Last run", "Events", "Last error"])
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = state
+        .connectors
+        .iter()
+        .map(|c| {
+            let last_run = c
+                .last_started
+                .as_deref()
+                .map(|t| staleness_label(t, state.now_ms))
+                .unwrap_or_else(|| "—".to_string());
+            let events = c.events_emitted.map(|n| n.to_string()).unwrap_or_else(|| "—".to_string());
+            Row::new([
+                Cell::from(c.connector_type.clone()).style(Style::default().fg(Color::DarkGray)),
+                Cell::from(c.name.clone()).style(Style::default().fg(Color::Cyan)),
+                Cell::from(c.status.clone()).style(Style::default().fg(connector_status_color(&c.status))),
+                Cell::from(last_run),
+                Cell::from(events),
+                Cell::from(c.last_error.clone().unwrap_or_default()).style(Style::default().fg(Color::Red)),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Percentage(25),
+            Constraint::Length(13),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .block(block)
+    .row_highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+
+    f.render_stateful_widget(table, area, &mut state.connector_table);
+}
+
+fn render_modal(f: &mut ratzilla::ratatui::Frame, modal: &Modal) {
+    let (title, lines) = match modal {
+        Modal::ConfirmDelete { entity_id } => (
+            " Delete entity ",
+            vec![
+                Line::from(vec![
+                    Span::styled("Delete ", Style::default().fg(Color::White)),
+                    Span::styled(entity_id.as_str(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled("?", Style::default().fg(Color::White)),
+                ]),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("y", Style::default().fg(Color::Yellow)),
+                    Span::styled(" confirm  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("n", Style::default().fg(Color::Yellow)),
+                    Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
+                ]),
+            ],
+        ),
+        Modal::ConfirmDeleteSource { name, .. } => (
+            " Delete source ",
+            vec![
+                Line::from(vec![
+                    Span::styled("Delete source ", Style::default().fg(Color::White)),
+                    Span::styled(name.as_str(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled("?", Style::default().fg(Color::White)),
+                ]),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("y", Style::default().fg(Color::Yellow)),
+                    Span::styled(" confirm  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("n", Style::default().fg(Color::Yellow)),
+                    Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
+                ]),
+            ],
+        ),
+        Modal::EditProperty { entity_id, property, input } => (
+            " Edit property ",
+            vec![
+                Line::from(Span::styled(
+                    format!("{}.{}", entity_id, property),
+                    Style::default().fg(Color::Cyan),
+                )),
+                Line::from(Span::styled(format!("> {}▏", input), Style::default().fg(Color::White))),
+                Line::from(Span::styled(
+                    "JSON literal or plain text · Enter save · Esc cancel",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ],
+        ),
+        Modal::Token { input } => (
+            " API token ",
+            vec![
+                Line::from(Span::styled(
+                    format!("> {}▏", "•".repeat(input.chars().count())),
+                    Style::default().fg(Color::White),
+                )),
+                Line::from(""),
+                Line::from(Span::styled(
+                    "Enter save (empty clears) · Esc cancel",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ],
+        ),
+    };
+
+    let area = centered_rect(60, 5, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Magenta)),
+            )
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn render_help(f: &mut ratzilla::ratatui::Frame, area: Rect, state: &AppState) {
+    let key = |k: &'static str| Span::styled(k, Style::default().fg(Color::Yellow));
+    let desc = |d: &'static str| Span::styled(d, Style::default().fg(Color::DarkGray));
+
+    if let Some(status) = state.active_status() {
+        let line = Line::from(Span::styled(
+            format!(" {}", status.text),
+            Style::default().fg(status.color).add_modifier(Modifier::BOLD),
+        ));
+        f.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    let spans = if state.search_active {
+        vec![
+            desc(" type to filter by id/status  "),
+            key("Enter"),
+            desc(" apply  "),
+            key("Esc"),
+            desc(" clear  "),
+        ]
+    } else if state.active_panel == Panel::Connectors {
+        vec![
+            key(" ↑↓"),
+            desc(" select  "),
+            key("Tab"),
+            desc(" switch panel  "),
+            key("s"),
+            desc(" sync now  "),
+            key("p"),
+            desc(" pause/resume  "),
+            key("d"),
+            desc(" delete source  "),
+            key("r"),
+            desc(" refresh  "),
+            key("t"),
+            desc(" token  "),
+        ]
+    } else {
+        vec![
+            key(" ↑↓"),
+            desc(" navigate  "),
+            key("Tab"),
+            desc(" switch panel  "),
+            key("/"),
+            desc(" search  "),
+            key("g"),
+            desc(" group by namespace  "),
+            key("s"),
+            desc(" sort  "),
+            key("Enter"),
+            desc(" collapse group  "),
+            key("p"),
+            desc(" pause stream  "),
+            key("PgUp/PgDn"),
+            desc(" scroll stream  "),
+            key("d"),
+            desc(" delete  "),
+            key("e"),
+            desc(" edit property  "),
+            key("t"),
+            desc(" token  "),
+        ]
+    };
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 // ─── Main ───────────────────────────────────────────────────────────────────
@@ -544,10 +1685,11 @@ fn main() -> Result<()> {
     // ── Load initial state via HTTP ─────────────────────────────────────
     {
         let state_clone = state.clone();
+        let token = state.borrow().token.clone();
         spawn_local(async move {
             let base = get_base_url();
             let url = format!("{}/api/state/entities", base);
-            match Request::get(&url).send().await {
+            match with_auth(Request::get(&url), &token).send().await {
                 Ok(resp) => {
                     if let Ok(entities) = resp.json::<Vec<EntityData>>().await {
                         let mut s = state_clone.borrow_mut();
@@ -587,33 +1729,148 @@ fn main() -> Result<()> {
         std::mem::forget(_interval);
     }
 
+    // ── Refresh the connector panel while it is shown ───────────────────
+    {
+        let state_clone = state.clone();
+        let _interval = Interval::new(CONNECTOR_REFRESH_MS, move || {
+            let mut s = state_clone.borrow_mut();
+            if s.active_panel == Panel::Connectors {
+                refresh_connectors(state_clone.clone(), &mut s);
+            }
+        });
+        std::mem::forget(_interval);
+    }
+
     // ── Key events ──────────────────────────────────────────────────────
     terminal.on_key_event({
         let state_clone = state.clone();
         move |key_event| {
             let mut s = state_clone.borrow_mut();
-            let entity_count = s.entities.len();
+
+            // Open overlays capture all keys
+            if s.modal.is_some() {
+                handle_modal_key(&state_clone, &mut s, key_event.code);
+                return;
+            }
+
+            // Search box captures keys while active
+            if s.search_active {
+                match key_event.code {
+                    KeyCode::Char(c) => s.search_query.push(c),
+                    KeyCode::Backspace => {
+                        s.search_query.pop();
+                    }
+                    KeyCode::Enter => s.search_active = false,
+                    KeyCode::Esc => {
+                        s.search_active = false;
+                        s.search_query.clear();
+                    }
+                    _ => {}
+                }
+                s.selected_entity = 0;
+                s.clamp_selection();
+                return;
+            }
+
+            let row_count = s.visible_rows().len();
+            let connectors_active = s.active_panel == Panel::Connectors;
             match key_event.code {
+                // Connector panel keys shadow the entity ones while it is shown
+                KeyCode::Up | KeyCode::Char('k') if connectors_active => s.move_connector_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') if connectors_active => s.move_connector_selection(1),
+                KeyCode::Char('s') if connectors_active => start_sync(state_clone.clone(), &mut s),
+                KeyCode::Char('p') if connectors_active => start_pause_toggle(state_clone.clone(), &mut s),
+                KeyCode::Char('d') if connectors_active => confirm_delete_source(&mut s),
+                KeyCode::Char('r') if connectors_active => refresh_connectors(state_clone.clone(), &mut s),
+                KeyCode::Up | KeyCode::Char('k') if s.active_panel == Panel::Detail => {
+                    s.selected_property = s.selected_property.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') if s.active_panel == Panel::Detail => {
+                    let count = s.selected_entity_data().map(|e| e.properties.len()).unwrap_or(0);
+                    if s.selected_property + 1 < count {
+                        s.selected_property += 1;
+                    }
+                }
                 KeyCode::Up | KeyCode::Char('k') => {
                     if s.selected_entity > 0 {
                         s.selected_entity -= 1;
+                        s.selected_property = 0;
                         let selected = s.selected_entity;
                         s.table_state.select(Some(selected));
                     }
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
-                    if entity_count > 0 && s.selected_entity < entity_count - 1 {
+                    if row_count > 0 && s.selected_entity < row_count - 1 {
                         s.selected_entity += 1;
+                        s.selected_property = 0;
                         let selected = s.selected_entity;
                         s.table_state.select(Some(selected));
                     }
                 }
+                KeyCode::Char('/') => {
+                    s.search_active = true;
+                }
+                KeyCode::Char('g') => {
+                    s.group_by_namespace = !s.group_by_namespace;
+                    s.selected_entity = 0;
+                    s.clamp_selection();
+                }
+                KeyCode::Char('s') => {
+                    s.sort_mode = s.sort_mode.next();
+                    s.clamp_selection();
+                }
+                KeyCode::Enter => {
+                    s.toggle_selected_group();
+                }
+                KeyCode::Esc => {
+                    if !s.search_query.is_empty() {
+                        s.search_query.clear();
+                        s.clamp_selection();
+                    }
+                }
                 KeyCode::Tab => {
                     s.active_panel = match s.active_panel {
                         Panel::Entities => Panel::Detail,
                         Panel::Detail => Panel::Messages,
-                        Panel::Messages => Panel::Entities,
+                        Panel::Messages => Panel::Events,
+                        Panel::Events => Panel::Connectors,
+                        Panel::Connectors => Panel::Entities,
                     };
+                    if s.active_panel == Panel::Connectors {
+                        refresh_connectors(state_clone.clone(), &mut s);
+                    }
+                }
+                KeyCode::Char('p') => {
+                    s.toggle_pause();
+                }
+                KeyCode::Char('d') => {
+                    if let Some(entity_id) = s.selected_entity_data().map(|e| e.id.clone()) {
+                        s.modal = Some(Modal::ConfirmDelete { entity_id });
+                    }
+                }
+                KeyCode::Char('e') => {
+                    let entity_id = s.selected_entity_data().map(|e| e.id.clone());
+                    if let (Some(entity_id), Some(property)) = (entity_id, s.selected_property_name()) {
+                        let input = s.entities[&entity_id]
+                            .properties
+                            .get(&property)
+                            .map(|v| match v {
+                                serde_json::Value::String(text) => text.clone(),
+                                other => other.to_string(),
+                            })
+                            .unwrap_or_default();
+                        s.modal = Some(Modal::EditProperty { entity_id, property, input });
+                    }
+                }
+                KeyCode::Char('t') => {
+                    let input = s.token.clone().unwrap_or_default();
+                    s.modal = Some(Modal::Token { input });
+                }
+                KeyCode::PageUp => {
+                    s.scroll_events(10);
+                }
+                KeyCode::PageDown => {
+                    s.scroll_events(-10);
                 }
                 _ => {}
             }
@@ -637,14 +1894,31 @@ fn main() -> Result<()> {
                 .split(f.area());
 
             render_header(f, outer[0], s);
+            render_metrics(f, outer[2], s);
+            render_help(f, outer[3], s);
+
+            if s.active_panel == Panel::Connectors {
+                render_connectors(f, outer[1], s);
+                if let Some(modal) = &s.modal {
+                    render_modal(f, modal);
+                }
+                return;
+            }
 
-            // Main content: left (entity list) | right (detail + messages)
+            // Main content: left (entity list + events) | right (detail + messages)
             let main_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
                 .split(outer[1]);
 
-            render_entity_list(f, main_chunks[0], s);
+            // Left side: entity list on top, event stream on bottom
+            let left_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .split(main_chunks[0]);
+
+            render_entity_list(f, left_chunks[0], s);
+            render_events(f, left_chunks[1], s);
 
             // Right side: detail on top, messages on bottom
             let right_chunks = Layout::default()
@@ -655,8 +1929,9 @@ fn main() -> Result<()> {
             render_detail(f, right_chunks[0], s);
             render_messages(f, right_chunks[1], s);
 
-            render_metrics(f, outer[2], s);
-            render_help(f, outer[3]);
+            if let Some(modal) = &s.modal {
+                render_modal(f, modal);
+            }
         }
     });
 
@@ -675,8 +1950,15 @@ fn connect_websocket(state: Rc<RefCell<AppState>>) {
             let ws_clone = ws.clone();
             let state_clone = state.clone();
             let onopen = wasm_bindgen::closure::Closure::wrap(Box::new(move |_e: web_sys::Event| {
-                // Send subscribe message
-                let sub_msg = serde_json::json!({"type": "subscribe", "entity_id": "*"});
+                // Send subscribe message (with token for auth-enabled servers)
+                let mut sub_msg = serde_json::json!({"type": "subscribe", "entity_id": "*"});
+                if let Some(token) = &state_clone.borrow().token {
+                    sub_msg["token"] = serde_json::Value::from(token.as_str());
+                }
+                // Fill the feeds after a reload; a reconnect already has them
+                if state_clone.borrow().event_log.is_empty() {
+                    sub_msg["include_recent"] = serde_json::Value::from(RECENT_ON_LOAD);
+                }
                 if let Err(e) = ws_clone.send_with_str(&sub_msg.to_string()) {
                     web_sys::console::log_1(&format!("WS send error: {:?}", e).into());
                 } else {
@@ -699,8 +1981,28 @@ fn connect_websocket(state: Rc<RefCell<AppState>>) {
                                     &ws_msg.entity_id,
                                     &ws_msg.property,
                                     ws_msg.value.clone(),
+                                    ws_msg.removed,
                                     &ws_msg.timestamp,
+                                    ws_msg.source.as_deref(),
                                 );
+                                if let Some(last_updated) = &ws_msg.entity_last_updated {
+                                    s.set_last_updated(&ws_msg.entity_id, last_updated);
+                                }
+                            }
+                            "state_update_batch" => {
+                                for change in &ws_msg.changes {
+                                    s.apply_state_update(
+                                        &ws_msg.entity_id,
+                                        &change.property,
+                                        change.value.clone(),
+                                        change.removed,
+                                        &ws_msg.timestamp,
+                                        ws_msg.source.as_deref(),
+                                    );
+                                }
+                                if let Some(last_updated) = &ws_msg.entity_last_updated {
+                                    s.set_last_updated(&ws_msg.entity_id, last_updated);
+                                }
                             }
                             "metrics_update" => {
                                 s.apply_metrics(&ws_msg);